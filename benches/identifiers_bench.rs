@@ -0,0 +1,69 @@
+use darklua_core::{
+    nodes::Block,
+    rules::{ContextBuilder, RenameVariables, Rule},
+    Parser, Resources,
+};
+
+const INPUTS: &[(&str, &str, &str)] = &[
+    (
+        "React - ReactFiberWorkLoop.new.lua",
+        "src/ReactFiberWorkLoop.new.lua",
+        include_str!("../bench_content/core-packages/modules/ReactReconciler-9c8468d8-8a7220fd/src/ReactFiberWorkLoop.new.lua"),
+    ),
+    (
+        "React - ReactFiberCommitWork.new.lua",
+        "src/ReactFiberCommitWork.new.lua",
+        include_str!("../bench_content/core-packages/modules/ReactReconciler-9c8468d8-8a7220fd/src/ReactFiberCommitWork.new.lua"),
+    ),
+    (
+        "React - ReactFiberHooks.new.lua",
+        "src/ReactFiberHooks.new.lua",
+        include_str!("../bench_content/core-packages/modules/ReactReconciler-9c8468d8-8a7220fd/src/ReactFiberHooks.new.lua"),
+    ),
+    (
+        "React - ReactFiberBeginWork.new.lua",
+        "src/ReactFiberBeginWork.new.lua",
+        include_str!("../bench_content/core-packages/modules/ReactReconciler-9c8468d8-8a7220fd/src/ReactFiberBeginWork.new.lua"),
+    ),
+];
+
+fn rename_variables(rule: &RenameVariables, block: &mut Block, path: &str, content: &str) {
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new(path, &resources, content).build();
+
+    rule.process(block, &context).unwrap();
+}
+
+fn identifiers(c: &mut criterion::Criterion) {
+    for (name, path, content) in INPUTS {
+        let parser = Parser::default();
+        let block = parser.parse(content).unwrap();
+        let rule = RenameVariables::default();
+
+        let mut group = c.benchmark_group(format!("{} - identifiers", name));
+        group.throughput(criterion::Throughput::Bytes(content.len() as u64));
+
+        group.bench_function("parse", |b| {
+            b.iter(|| parser.parse(criterion::black_box(content)).unwrap())
+        });
+
+        group.bench_function("clone", |b| b.iter(|| criterion::black_box(&block).clone()));
+
+        group.bench_function("rename_variables", |b| {
+            b.iter_batched_ref(
+                || block.clone(),
+                |block| rename_variables(criterion::black_box(&rule), block, path, content),
+                criterion::BatchSize::SmallInput,
+            )
+        });
+
+        group.finish();
+    }
+}
+
+criterion::criterion_group!(
+    name = identifiers_bench;
+    config = criterion::Criterion::default();
+    targets = identifiers,
+);
+criterion::criterion_main!(identifiers_bench);
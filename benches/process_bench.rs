@@ -1,6 +1,6 @@
 use darklua_core::{
     rules::{self, Rule},
-    Configuration, Options,
+    Configuration, Options, Resources,
 };
 
 mod bench_utils;
@@ -53,9 +53,76 @@ bench_utils::generate_bench!(crosswalk, {
     },
 });
 
+fn generate_data_module(index: usize) -> String {
+    let entries: Vec<_> = (0..100)
+        .map(|i| {
+            format!(
+                "    entry_{i} = {{ id = {}, name = \"item-{index}-{i}\", enabled = {}, values = {{ {}, {}, -{} }} }},",
+                index * 100 + i,
+                i % 2 == 0,
+                i,
+                i * 2,
+                i * 3,
+            )
+        })
+        .collect();
+
+    format!(
+        "-- generated data module\nreturn {{\n{}\n}}\n",
+        entries.join("\n")
+    )
+}
+
+fn data_modules(c: &mut criterion::Criterion) {
+    let resources = Resources::from_memory();
+    let mut bytes = 0_u64;
+
+    for index in 0..50 {
+        let content = generate_data_module(index);
+        bytes += content.len() as u64;
+        resources
+            .write(format!("src/data_{}.lua", index), &content)
+            .unwrap();
+    }
+
+    let mut group = c.benchmark_group("data_modules");
+    group.throughput(criterion::Throughput::Bytes(bytes));
+
+    group.bench_function("default_run", |b| {
+        b.iter(|| {
+            darklua_core::process(
+                criterion::black_box(&resources),
+                criterion::black_box(
+                    Options::new("src").with_configuration(Configuration::default()),
+                ),
+            )
+            .unwrap()
+            .result()
+            .unwrap()
+        })
+    });
+
+    group.bench_function("data_fast_path", |b| {
+        b.iter(|| {
+            darklua_core::process(
+                criterion::black_box(&resources),
+                criterion::black_box(
+                    Options::new("src")
+                        .with_configuration(Configuration::default().with_data_fast_path()),
+                ),
+            )
+            .unwrap()
+            .result()
+            .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
 criterion::criterion_group!(
     name = process;
     config = criterion::Criterion::default();
-    targets = roact, crosswalk
+    targets = roact, crosswalk, data_modules
 );
 criterion::criterion_main!(process);
@@ -0,0 +1,111 @@
+use darklua_core::{
+    generator::{DenseLuaGenerator, LuaGenerator, ReadableLuaGenerator, TokenBasedLuaGenerator},
+    nodes::Block,
+    rules::{get_default_rules, ContextBuilder, Rule},
+    Parser, Resources,
+};
+
+const INPUTS: &[(&str, &str, &str)] = &[
+    (
+        "Roact - Component.lua",
+        "src/Component.lua",
+        include_str!("../bench_content/roact/src/Component.lua"),
+    ),
+    (
+        "React - ReactFiberHooks.new.lua",
+        "src/ReactFiberHooks.new.lua",
+        include_str!("../bench_content/core-packages/modules/ReactReconciler-9c8468d8-8a7220fd/src/ReactFiberHooks.new.lua"),
+    ),
+];
+
+fn apply_rules(rules: &[Box<dyn Rule>], block: &mut Block, path: &str, content: &str) {
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new(path, &resources, content).build();
+
+    for rule in rules {
+        rule.process(block, &context).unwrap();
+    }
+}
+
+fn default_rules(c: &mut criterion::Criterion) {
+    for (name, path, content) in INPUTS {
+        let block = Parser::default().parse(content).unwrap();
+
+        let mut group = c.benchmark_group(format!("{} - rules", name));
+        group.throughput(criterion::Throughput::Bytes(content.len() as u64));
+
+        for rule in get_default_rules() {
+            let rules = [rule];
+            group.bench_function(rules[0].get_name(), |b| {
+                b.iter_batched_ref(
+                    || block.clone(),
+                    |block| apply_rules(criterion::black_box(&rules), block, path, content),
+                    criterion::BatchSize::SmallInput,
+                )
+            });
+        }
+
+        group.bench_function("default_pipeline", |b| {
+            b.iter_batched_ref(
+                || block.clone(),
+                |block| {
+                    apply_rules(
+                        criterion::black_box(&get_default_rules()),
+                        block,
+                        path,
+                        content,
+                    )
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        });
+
+        group.finish();
+    }
+}
+
+fn generate_code(c: &mut criterion::Criterion) {
+    for (name, path, content) in INPUTS {
+        let mut block = Parser::default().parse(content).unwrap();
+        apply_rules(&get_default_rules(), &mut block, path, content);
+
+        let mut block_with_tokens = Parser::default().preserve_tokens().parse(content).unwrap();
+        apply_rules(&get_default_rules(), &mut block_with_tokens, path, content);
+
+        let mut group = c.benchmark_group(format!("{} - generate", name));
+        group.throughput(criterion::Throughput::Bytes(content.len() as u64));
+
+        group.bench_function("dense", |b| {
+            b.iter(|| {
+                let mut generator = DenseLuaGenerator::default();
+                generator.write_block(criterion::black_box(&block));
+                generator.into_string()
+            })
+        });
+
+        group.bench_function("readable", |b| {
+            b.iter(|| {
+                let mut generator = ReadableLuaGenerator::default();
+                generator.write_block(criterion::black_box(&block));
+                generator.into_string()
+            })
+        });
+
+        group.bench_function("retain_lines", |b| {
+            b.iter(|| {
+                let mut generator = TokenBasedLuaGenerator::new(content);
+                generator.write_block(criterion::black_box(&block_with_tokens));
+                generator.into_string()
+            })
+        });
+
+        group.finish();
+    }
+}
+
+criterion::criterion_group!(
+    name = rules;
+    config = criterion::Criterion::default();
+    targets = default_rules, generate_code,
+);
+criterion::criterion_main!(rules);
@@ -0,0 +1,42 @@
+-- Computes the results of the expressions of a reference file with Lua 5.1 and prints the file
+-- with the new results (see tests/test_cases/deterministic_stdlib/lua51.txt)
+--
+-- usage: lua5.1 scripts/generate-stdlib-reference.lua < reference.txt
+
+local function serialize(value)
+    if type(value) == 'string' then
+        return ('%q'):format(value)
+    elseif type(value) == 'number' then
+        return ('%.17g'):format(value)
+    else
+        return tostring(value)
+    end
+end
+
+local function pack(...)
+    return { n = select('#', ...), ... }
+end
+
+for line in io.lines() do
+    local prefix, expression = line:match('^(!?%s*)(.-)%s*=>')
+
+    if expression == nil then
+        print(line)
+    else
+        local chunk = assert(loadstring('return ' .. expression))
+        local results = pack(pcall(chunk))
+
+        local output
+        if results[1] then
+            local values = {}
+            for i = 2, results.n do
+                table.insert(values, serialize(results[i]))
+            end
+            output = table.concat(values, ', ')
+        else
+            output = 'error'
+        end
+
+        print(((prefix .. expression .. ' => ' .. output):gsub('%s+$', '')))
+    end
+end
@@ -1,4 +1,4 @@
-use std::{fmt, str::FromStr};
+use std::{cell::RefCell, collections::HashMap, fmt, str::FromStr};
 
 use full_moon::{
     ast,
@@ -6,7 +6,7 @@ use full_moon::{
     tokenizer::{self, InterpolatedStringKind, Symbol, TokenType},
 };
 
-use crate::nodes::*;
+use crate::{nodes::*, utils::SourceAttribute};
 
 #[derive(Debug, Default)]
 pub(crate) struct AstConverter<'a> {
@@ -25,6 +25,10 @@ pub(crate) struct AstConverter<'a> {
     generic_type_packs: Vec<GenericTypePack>,
     type_parameters: Vec<TypeParameters>,
     type_packs: Vec<TypePack>,
+    function_attributes: HashMap<usize, Vec<SourceAttribute>>,
+    attribute_regions: Vec<(usize, usize)>,
+    identifier_names: HashMap<String, String>,
+    interner: RefCell<StringInterner>,
 }
 
 impl<'a> AstConverter<'a> {
@@ -35,6 +39,34 @@ impl<'a> AstConverter<'a> {
         }
     }
 
+    /// Uses the function attributes that were removed before parsing. The trivia located
+    /// where the attributes were is moved to the attribute tokens.
+    pub(crate) fn with_function_attributes(
+        mut self,
+        function_attributes: HashMap<usize, Vec<SourceAttribute>>,
+    ) -> Self {
+        self.attribute_regions = function_attributes
+            .iter()
+            .filter_map(|(keyword, attributes)| {
+                attributes
+                    .first()
+                    .map(|attribute| (attribute.start, *keyword))
+            })
+            .collect();
+        self.function_attributes = function_attributes;
+        self
+    }
+
+    /// Uses the original names of the identifiers that were replaced with placeholders
+    /// before parsing.
+    pub(crate) fn with_identifier_names(
+        mut self,
+        identifier_names: HashMap<String, String>,
+    ) -> Self {
+        self.identifier_names = identifier_names;
+        self
+    }
+
     #[inline]
     fn push_work(&mut self, work: impl Into<ConvertWork<'a>>) {
         self.work_stack.push(work.into());
@@ -412,8 +444,9 @@ impl<'a> AstConverter<'a> {
                             );
 
                             for trivia_token in literal.trailing_trivia() {
-                                opening_brace
-                                    .push_trailing_trivia(self.convert_trivia(trivia_token)?);
+                                if let Some(trivia) = self.convert_trivia(trivia_token)? {
+                                    opening_brace.push_trailing_trivia(trivia);
+                                }
                             }
 
                             let next_literal = segments_iter
@@ -472,15 +505,15 @@ impl<'a> AstConverter<'a> {
                                     );
 
                                     for trivia_token in first.leading_trivia() {
-                                        start_token.push_leading_trivia(
-                                            self.convert_trivia(trivia_token)?,
-                                        );
+                                        if let Some(trivia) = self.convert_trivia(trivia_token)? {
+                                            start_token.push_leading_trivia(trivia);
+                                        }
                                     }
 
                                     for trivia_token in last.trailing_trivia() {
-                                        end_token.push_trailing_trivia(
-                                            self.convert_trivia(trivia_token)?,
-                                        );
+                                        if let Some(trivia) = self.convert_trivia(trivia_token)? {
+                                            end_token.push_trailing_trivia(trivia);
+                                        }
                                     }
                                     (start_token, end_token)
                                 }
@@ -507,8 +540,11 @@ impl<'a> AstConverter<'a> {
                     self.expressions.push(value.into());
                 }
                 ConvertWork::MakeFunctionExpression { body, token } => {
-                    let builder =
+                    let mut builder =
                         self.convert_function_body_attributes(body, self.convert_token(token)?)?;
+                    for attribute in self.convert_function_attributes(token) {
+                        builder.push_attribute(attribute);
+                    }
 
                     self.expressions
                         .push(builder.into_function_expression().into());
@@ -598,10 +634,13 @@ impl<'a> AstConverter<'a> {
                     self.statements.push(generic_for.into());
                 }
                 ConvertWork::MakeFunctionDeclaration { statement } => {
-                    let builder = self.convert_function_body_attributes(
+                    let mut builder = self.convert_function_body_attributes(
                         statement.body(),
                         self.convert_token(statement.function_token())?,
                     )?;
+                    for attribute in self.convert_function_attributes(statement.function_token()) {
+                        builder.push_attribute(attribute);
+                    }
                     let name = self.convert_function_name(statement.name())?;
 
                     self.statements
@@ -834,11 +873,14 @@ impl<'a> AstConverter<'a> {
                     self.expressions.push(call.into());
                 }
                 ConvertWork::MakeLocalFunctionStatement { statement } => {
-                    let builder = self.convert_function_body_attributes(
+                    let mut builder = self.convert_function_body_attributes(
                         statement.body(),
                         self.convert_token(statement.function_token())?,
                     )?;
-                    let mut name = Identifier::new(statement.name().token().to_string());
+                    for attribute in self.convert_function_attributes(statement.local_token()) {
+                        builder.push_attribute(attribute);
+                    }
+                    let mut name = self.create_identifier(statement.name());
                     let mut local_token = None;
 
                     if self.hold_token_data {
@@ -2353,40 +2395,109 @@ impl<'a> AstConverter<'a> {
         let mut new_token = Token::new_with_line(position.0, position.1, position.2);
 
         for trivia_token in token.leading_trivia() {
-            new_token.push_leading_trivia(self.convert_trivia(trivia_token)?);
+            if let Some(trivia) = self.convert_trivia(trivia_token)? {
+                new_token.push_leading_trivia(trivia);
+            }
         }
 
         for trivia_token in token.trailing_trivia() {
-            new_token.push_trailing_trivia(self.convert_trivia(trivia_token)?);
+            if let Some(trivia) = self.convert_trivia(trivia_token)? {
+                new_token.push_trailing_trivia(trivia);
+            }
         }
 
         Ok(new_token)
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
-    fn convert_trivia(&self, token: &tokenizer::Token) -> Result<Trivia, ConvertError> {
+    fn convert_trivia(&self, token: &tokenizer::Token) -> Result<Option<Trivia>, ConvertError> {
         use tokenizer::TokenKind;
 
-        let trivia = match token.token_kind() {
+        let kind = match token.token_kind() {
             TokenKind::MultiLineComment => TriviaKind::Comment,
             TokenKind::SingleLineComment => TriviaKind::Comment,
             TokenKind::Whitespace => TriviaKind::Whitespace,
             _ => return Err(ConvertError::UnexpectedTrivia(token.token_kind())),
+        };
+        let start = token.start_position().bytes();
+        let mut end = token.end_position().bytes();
+
+        // the trivia located where function attributes were removed belongs to the
+        // attribute tokens
+        if let Some((region_start, _)) = self
+            .attribute_regions
+            .iter()
+            .find(|(region_start, region_end)| start < *region_end && end > *region_start)
+        {
+            end = end.min(*region_start);
         }
-        .at(
-            token.start_position().bytes(),
-            token.end_position().bytes(),
-            token.start_position().line(),
-        );
-        Ok(trivia)
+
+        if start >= end {
+            return Ok(None);
+        }
+
+        Ok(Some(kind.at(start, end, token.start_position().line())))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn convert_function_attributes(
+        &self,
+        keyword: &tokenizer::TokenReference,
+    ) -> Vec<FunctionAttribute> {
+        let Some(attributes) = self
+            .function_attributes
+            .get(&keyword.token().start_position().bytes())
+        else {
+            return Vec::new();
+        };
+
+        attributes
+            .iter()
+            .map(|attribute| {
+                let mut name = Identifier::new(self.interner.borrow_mut().intern(&attribute.name));
+
+                if self.hold_token_data {
+                    let mut name_token =
+                        Token::new_with_line(attribute.start + 1, attribute.end, attribute.line);
+                    for trivia in attribute.trailing_trivia.iter().cloned() {
+                        name_token.push_trailing_trivia(trivia);
+                    }
+                    name.set_token(name_token);
+
+                    FunctionAttribute::new(name).with_token(Token::new_with_line(
+                        attribute.start,
+                        attribute.start + 1,
+                        attribute.line,
+                    ))
+                } else {
+                    FunctionAttribute::new(name)
+                }
+            })
+            .collect()
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    /// Creates an identifier whose name is shared with the other identifiers of the same name
+    /// in the converted block.
+    fn create_identifier(&self, token: &tokenizer::TokenReference) -> Identifier {
+        let mut interner = self.interner.borrow_mut();
+        let name = match token.token_type() {
+            TokenType::Identifier { identifier } => interner.intern(
+                self.identifier_names
+                    .get(identifier.as_str())
+                    .map(String::as_str)
+                    .unwrap_or(identifier.as_str()),
+            ),
+            _ => interner.intern(&token.token().to_string()),
+        };
+        Identifier::new(name)
+    }
+
     fn convert_token_to_identifier(
         &self,
         token: &tokenizer::TokenReference,
     ) -> Result<Identifier, ConvertError> {
-        let mut identifier = Identifier::new(token.token().to_string());
+        let mut identifier = self.create_identifier(token);
         if self.hold_token_data {
             identifier.set_token(self.convert_token(token)?);
         }
@@ -2488,7 +2599,7 @@ impl<'a> AstConverter<'a> {
                             parameters: body.parameters().to_string(),
                         });
                     }
-                    let mut identifier = Identifier::new(name.token().to_string());
+                    let mut identifier = self.create_identifier(name);
                     if self.hold_token_data {
                         identifier.set_token(self.convert_token(name)?);
                     }
@@ -0,0 +1,418 @@
+use crate::cli::error::CliError;
+use crate::cli::utils::maybe_plural;
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::process::{Occurrences, SizeAnalysis, SizeCategory};
+use darklua_core::{Parser, Resources};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Where outputs are written in the in-memory resources.
+const OUTPUT_PATH: &str = "darklua-analyze-size-output";
+const DEFAULT_CONFIG_PATHS: [&str; 2] = [".darklua.json", ".darklua.json5"];
+/// The number of identifiers and strings listed in the report.
+const TOP_OCCURRENCES: usize = 20;
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Path to the lua file or directory to analyze.
+    input_path: PathBuf,
+    /// Path to the configuration file used to process the files. If not provided,
+    /// `.darklua.json` or `.darklua.json5` is used when found in the working directory.
+    #[arg(long, short, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Analyze the files as they are, without processing them.
+    #[arg(long)]
+    raw: bool,
+    /// Also rank the functions of each file by size.
+    #[arg(long)]
+    by_function: bool,
+    /// Choose how the report is printed ('text' or 'json').
+    #[arg(long, default_value = "text")]
+    format: ReportFormat,
+    /// Skip the files and directories matching the given glob pattern (like 'Packages' or
+    /// '**/*.spec.lua') when collecting the files to analyze. The pattern is matched against
+    /// the paths relative to the input directory, and the excluded directories are not read.
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum ReportFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "format '{}' does not exist! (possible options are: 'text' or 'json')",
+                format
+            )),
+        }
+    }
+}
+
+struct FileAnalysis {
+    path: PathBuf,
+    analysis: SizeAnalysis,
+}
+
+fn display_path(path: &Path) -> String {
+    path.display().to_string().replace('\\', "/")
+}
+
+fn category_key(category: SizeCategory) -> String {
+    category.name().replace(' ', "_")
+}
+
+fn percentage(size: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        size as f64 * 100.0 / total as f64
+    }
+}
+
+/// The content of the files read from the file system, with their path.
+type FileContents = Vec<(PathBuf, String)>;
+
+/// Reads the input files and the configuration file, so that they can be processed in
+/// memory.
+fn read_files(options: &Options) -> Result<(Vec<PathBuf>, FileContents), String> {
+    let file_system = Resources::from_file_system();
+
+    let mut sources = file_system
+        .collect_work_without(&options.input_path, &options.exclude)
+        .map_err(|err| format!("unable to collect the files to analyze: {}", err))?;
+    sources.sort();
+
+    let mut paths = sources.clone();
+    if !options.raw {
+        match &options.config {
+            Some(config) => paths.push(config.clone()),
+            None => paths.extend(
+                DEFAULT_CONFIG_PATHS
+                    .iter()
+                    .map(PathBuf::from)
+                    .filter(|path| file_system.exists(path).unwrap_or(false)),
+            ),
+        }
+    }
+
+    let files = paths
+        .into_iter()
+        .map(|path| {
+            file_system
+                .get(&path)
+                .map(|content| (path.clone(), content))
+                .map_err(|err| format!("unable to read `{}`: {:?}", path.display(), err))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((sources, files))
+}
+
+fn output_path(options: &Options, source: &Path) -> PathBuf {
+    let relative = source
+        .strip_prefix(&options.input_path)
+        .ok()
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .or_else(|| source.file_name().map(Path::new))
+        .unwrap_or(source);
+
+    Path::new(OUTPUT_PATH).join(relative)
+}
+
+/// Processes the files in memory and returns the generated code of each source.
+fn process_files(
+    options: &Options,
+    sources: &[PathBuf],
+    files: &[(PathBuf, String)],
+) -> Result<Vec<(PathBuf, Option<String>)>, String> {
+    let resources = Resources::from_memory();
+
+    for (path, content) in files {
+        resources
+            .write(path, content)
+            .map_err(|err| format!("unable to load `{}`: {:?}", path.display(), err))?;
+    }
+
+    let mut process_options =
+        darklua_core::Options::new(&options.input_path).with_output(OUTPUT_PATH);
+    if let Some(config) = &options.config {
+        process_options = process_options.with_configuration_at(config);
+    }
+    for pattern in options.exclude.iter() {
+        process_options = process_options.with_exclude(pattern);
+    }
+
+    let result = darklua_core::process(&resources, process_options)
+        .map_err(|err| format!("unable to process files: {}", err))?;
+
+    for error in result.collect_errors() {
+        log::error!("{}", error);
+    }
+
+    Ok(sources
+        .iter()
+        .map(|source| {
+            let output = resources.get(output_path(options, source)).ok();
+            (source.clone(), output)
+        })
+        .collect())
+}
+
+fn analyze(path: &Path, code: &str) -> Result<SizeAnalysis, String> {
+    let mut block = Parser::default()
+        .preserve_tokens()
+        .parse(code)
+        .map_err(|err| format!("unable to parse `{}`: {}", path.display(), err))?;
+
+    Ok(SizeAnalysis::analyze(&mut block, code))
+}
+
+#[derive(Debug, Serialize)]
+struct JsonSize {
+    size: usize,
+    categories: BTreeMap<String, usize>,
+}
+
+impl JsonSize {
+    fn new(analysis: &SizeAnalysis) -> Self {
+        Self {
+            size: analysis.size(),
+            categories: SizeCategory::ALL
+                .iter()
+                .map(|category| (category_key(*category), analysis.category_size(*category)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct JsonFile {
+    path: String,
+    #[serde(flatten)]
+    size: JsonSize,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonOccurrences {
+    content: String,
+    length: usize,
+    count: usize,
+}
+
+impl JsonOccurrences {
+    fn list(occurrences: &[Occurrences]) -> Vec<Self> {
+        occurrences
+            .iter()
+            .take(TOP_OCCURRENCES)
+            .map(|occurrences| Self {
+                content: occurrences.content().to_owned(),
+                length: occurrences.len(),
+                count: occurrences.count(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct JsonFunction {
+    path: String,
+    name: Option<String>,
+    line: Option<usize>,
+    size: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonReport {
+    files: Vec<JsonFile>,
+    total: JsonSize,
+    identifiers: Vec<JsonOccurrences>,
+    strings: Vec<JsonOccurrences>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    functions: Option<Vec<JsonFunction>>,
+}
+
+/// Returns the functions of every file, from the largest to the smallest.
+fn rank_functions(files: &[FileAnalysis]) -> Vec<JsonFunction> {
+    let mut functions: Vec<_> = files
+        .iter()
+        .flat_map(|file| {
+            file.analysis
+                .functions()
+                .iter()
+                .map(move |function| JsonFunction {
+                    path: display_path(&file.path),
+                    name: function.name().map(ToOwned::to_owned),
+                    line: function.line(),
+                    size: function.size(),
+                })
+        })
+        .collect();
+
+    functions.sort_by_key(|function| Reverse(function.size));
+    functions
+}
+
+fn write_json(options: &Options, files: &[FileAnalysis], total: &SizeAnalysis) -> String {
+    let report = JsonReport {
+        files: files
+            .iter()
+            .map(|file| JsonFile {
+                path: display_path(&file.path),
+                size: JsonSize::new(&file.analysis),
+            })
+            .collect(),
+        total: JsonSize::new(total),
+        identifiers: JsonOccurrences::list(&total.longest_identifiers()),
+        strings: JsonOccurrences::list(&total.longest_strings()),
+        functions: options.by_function.then(|| rank_functions(files)),
+    };
+
+    serde_json::to_string_pretty(&report).expect("unable to serialize size report")
+}
+
+fn write_occurrences(report: &mut String, title: &str, occurrences: &[Occurrences]) {
+    if occurrences.is_empty() {
+        return;
+    }
+
+    report.push_str(&format!("\n{}:\n", title));
+    for occurrences in occurrences.iter().take(TOP_OCCURRENCES) {
+        report.push_str(&format!(
+            "  {} ({} byte{}, {} occurrence{})\n",
+            occurrences.content(),
+            occurrences.len(),
+            maybe_plural(occurrences.len()),
+            occurrences.count(),
+            maybe_plural(occurrences.count()),
+        ));
+    }
+}
+
+fn write_text(options: &Options, files: &[FileAnalysis], total: &SizeAnalysis) -> String {
+    let mut report = String::new();
+
+    for file in files {
+        report.push_str(&format!(
+            "{}: {} bytes\n",
+            display_path(&file.path),
+            file.analysis.size()
+        ));
+    }
+
+    report.push_str(&format!(
+        "\ntotal: {} bytes in {} file{}\n",
+        total.size(),
+        files.len(),
+        maybe_plural(files.len())
+    ));
+
+    for category in SizeCategory::ALL {
+        let size = total.category_size(category);
+        report.push_str(&format!(
+            "  {:<22}{:>10}{:>8.1}%\n",
+            category.name(),
+            size,
+            percentage(size, total.size())
+        ));
+    }
+
+    write_occurrences(
+        &mut report,
+        "longest identifiers",
+        &total.longest_identifiers(),
+    );
+    write_occurrences(&mut report, "longest strings", &total.longest_strings());
+
+    if options.by_function {
+        let functions = rank_functions(files);
+
+        if !functions.is_empty() {
+            report.push_str("\nlargest functions:\n");
+            for function in functions {
+                let location = match function.line {
+                    Some(line) => format!("{}:{}", function.path, line),
+                    None => function.path,
+                };
+                report.push_str(&format!(
+                    "  {:>10} bytes  {} {}\n",
+                    function.size,
+                    location,
+                    function.name.as_deref().unwrap_or("(anonymous)")
+                ));
+            }
+        }
+    }
+
+    report.trim_end().to_owned()
+}
+
+pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `analyze-size`: {:?}", options);
+
+    let (sources, files) = read_files(options).map_err(|err| {
+        log::error!("{}", err);
+        CliError::new(2)
+    })?;
+
+    let codes: Vec<(PathBuf, Option<String>)> = if options.raw {
+        files
+            .into_iter()
+            .map(|(path, content)| (path, Some(content)))
+            .collect()
+    } else {
+        process_files(options, &sources, &files).map_err(|err| {
+            log::error!("{}", err);
+            CliError::new(2)
+        })?
+    };
+
+    let mut success = true;
+    let mut analyses = Vec::new();
+    let mut total = SizeAnalysis::default();
+
+    for (path, code) in codes {
+        let Some(code) = code else {
+            log::error!("no code was generated for `{}`", path.display());
+            success = false;
+            continue;
+        };
+
+        match analyze(&path, &code) {
+            Ok(analysis) => {
+                total.merge(&analysis);
+                analyses.push(FileAnalysis { path, analysis });
+            }
+            Err(err) => {
+                log::error!("{}", err);
+                success = false;
+            }
+        }
+    }
+
+    let report = match options.format {
+        ReportFormat::Text => write_text(options, &analyses, &total),
+        ReportFormat::Json => write_json(options, &analyses, &total),
+    };
+
+    println!("{}", report);
+
+    if success {
+        Ok(())
+    } else {
+        Err(CliError::new(1))
+    }
+}
@@ -0,0 +1,70 @@
+use crate::cli::error::CliError;
+use crate::cli::utils::maybe_plural;
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::process::NodeEdit;
+use darklua_core::{DarkluaError, Resources};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Path to the lua file to edit.
+    input_path: PathBuf,
+    /// Path to the JSON file containing the list of edits.
+    #[arg(long, short)]
+    edits: PathBuf,
+    /// Validate the edits without writing the file.
+    #[arg(long)]
+    check: bool,
+}
+
+fn read_edits(resources: &Resources, path: &Path) -> Result<Vec<NodeEdit>, String> {
+    let content = resources
+        .get(path)
+        .map_err(|err| DarkluaError::from(err).to_string())?;
+
+    serde_json::from_str(&content)
+        .map_err(|err| format!("unable to read edits from `{}`: {}", path.display(), err))
+}
+
+pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `apply-edits`: {:?}", options);
+
+    let resources = Resources::from_file_system();
+
+    let edits = read_edits(&resources, &options.edits).map_err(|err| {
+        log::error!("{}", err);
+        CliError::new(1)
+    })?;
+
+    let code =
+        darklua_core::apply_edits(&resources, &options.input_path, &edits).map_err(|err| {
+            log::error!("{}", err);
+            CliError::new(1)
+        })?;
+
+    if options.check {
+        log::info!(
+            "{} edit{} can be applied to `{}`",
+            edits.len(),
+            maybe_plural(edits.len()),
+            options.input_path.display()
+        );
+        return Ok(());
+    }
+
+    resources.write(&options.input_path, &code).map_err(|err| {
+        log::error!("{}", DarkluaError::from(err));
+        CliError::new(1)
+    })?;
+
+    log::info!(
+        "applied {} edit{} to `{}`",
+        edits.len(),
+        maybe_plural(edits.len()),
+        options.input_path.display()
+    );
+
+    Ok(())
+}
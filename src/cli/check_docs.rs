@@ -0,0 +1,148 @@
+use crate::cli::error::CliError;
+use crate::cli::utils::maybe_plural;
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::nodes::Block;
+use darklua_core::process::{check_docs, fix_docs, DocMismatch};
+use darklua_core::{Parser, Resources};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Path to the lua file or directory to check.
+    input_path: PathBuf,
+    /// Choose how mismatches are printed ('text' or 'json').
+    #[arg(long, default_value = "text")]
+    format: CheckDocsFormat,
+    /// Rewrite the documentation comments to add a stub for each undocumented
+    /// parameter and to remove the tags of parameters that do not exist.
+    #[arg(long)]
+    fix: bool,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum CheckDocsFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for CheckDocsFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "format '{}' does not exist! (possible options are: 'text' or 'json')",
+                format
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct FileMismatch<'a> {
+    path: String,
+    #[serde(flatten)]
+    mismatch: &'a DocMismatch,
+    message: String,
+}
+
+fn parse(code: &str, path: &Path) -> Result<Block, String> {
+    Parser::default()
+        .preserve_tokens()
+        .parse(code)
+        .map_err(|err| format!("unable to parse `{}`: {}", path.display(), err))
+}
+
+fn check_file(resources: &Resources, path: &Path, fix: bool) -> Result<Vec<DocMismatch>, String> {
+    let mut code = resources
+        .get(path)
+        .map_err(|err| format!("unable to read `{}`: {:?}", path.display(), err))?;
+
+    let mut block = parse(&code, path)?;
+
+    if fix {
+        let fixed_code = fix_docs(&mut block, &code);
+
+        if fixed_code != code {
+            resources
+                .write(path, &fixed_code)
+                .map_err(|err| format!("unable to write `{}`: {:?}", path.display(), err))?;
+            log::info!("fixed documentation of `{}`", path.display());
+
+            code = fixed_code;
+            block = parse(&code, path)?;
+        }
+    }
+
+    Ok(check_docs(&mut block, &code))
+}
+
+pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `check-docs`: {:?}", options);
+
+    let resources = Resources::from_file_system();
+
+    let mut files: Vec<_> = resources.collect_work(&options.input_path).collect();
+    files.sort();
+
+    let mut mismatches = Vec::new();
+    let mut has_errors = false;
+
+    for path in files {
+        match check_file(&resources, &path, options.fix) {
+            Ok(found) => {
+                mismatches.extend(found.into_iter().map(|mismatch| (path.clone(), mismatch)));
+            }
+            Err(err) => {
+                log::error!("{}", err);
+                has_errors = true;
+            }
+        }
+    }
+
+    match options.format {
+        CheckDocsFormat::Text => {
+            for (path, mismatch) in mismatches.iter() {
+                println!("{}:{}", path.display(), mismatch);
+            }
+            if !mismatches.is_empty() {
+                eprintln!(
+                    "found {} documentation issue{}",
+                    mismatches.len(),
+                    maybe_plural(mismatches.len())
+                );
+            }
+        }
+        CheckDocsFormat::Json => {
+            let output: Vec<_> = mismatches
+                .iter()
+                .map(|(path, mismatch)| FileMismatch {
+                    path: path.display().to_string(),
+                    mismatch,
+                    message: mismatch.kind().to_string(),
+                })
+                .collect();
+            let json = serde_json::to_string_pretty(&output).map_err(|err| {
+                log::error!("unable to serialize mismatches: {}", err);
+                CliError::new(2)
+            })?;
+            println!("{}", json);
+        }
+    }
+
+    if has_errors {
+        return Err(CliError::new(2));
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(CliError::new(1))
+    }
+}
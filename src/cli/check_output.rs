@@ -0,0 +1,61 @@
+use crate::cli::error::CliError;
+use crate::cli::utils::maybe_plural;
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::Resources;
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Path to the lua file or directory to check.
+    input_path: PathBuf,
+    /// Choose a specific configuration file.
+    #[arg(long, short, alias = "config-path")]
+    config: Option<PathBuf>,
+}
+
+impl Options {
+    fn get_process_options(&self) -> darklua_core::Options {
+        let mut process_options = darklua_core::Options::new(&self.input_path);
+
+        if let Some(config) = self.config.as_ref() {
+            process_options = process_options.with_configuration_at(config);
+        }
+
+        process_options
+    }
+}
+
+pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `check-output`: {:?}", options);
+
+    let resources = Resources::from_file_system();
+
+    let failures = darklua_core::check_output_files(&resources, options.get_process_options())
+        .map_err(|err| {
+            log::error!("{}", err);
+            CliError::new(2)
+        })?;
+
+    let mut violation_count = 0;
+    for (path, violations) in failures.iter() {
+        for violation in violations {
+            println!("{}:{}", path.display(), violation);
+        }
+        violation_count += violations.len();
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        eprintln!(
+            "found {} violation{} in {} file{}",
+            violation_count,
+            maybe_plural(violation_count),
+            failures.len(),
+            maybe_plural(failures.len())
+        );
+        Err(CliError::new(1))
+    }
+}
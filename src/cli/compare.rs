@@ -0,0 +1,249 @@
+use crate::cli::error::CliError;
+use crate::cli::utils::{find_first_difference, maybe_plural, unified_diff};
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::{Parser, Resources};
+use std::path::{Path, PathBuf};
+
+/// Where outputs are written in the in-memory resources of each configuration.
+const OUTPUT_PATH: &str = "darklua-compare-output";
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Path to the lua file or directory to process.
+    input_path: PathBuf,
+    /// The first configuration file.
+    #[arg(long, value_name = "PATH")]
+    config_a: PathBuf,
+    /// The second configuration file.
+    #[arg(long, value_name = "PATH")]
+    config_b: PathBuf,
+    /// Print a unified diff of the outputs of each file that differs.
+    #[arg(long)]
+    show_diff: bool,
+    /// Exit with an error code when the outputs of a file do not have the same
+    /// syntax tree (formatting differences are allowed).
+    #[arg(long)]
+    require_ast_equal: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Comparison {
+    Identical,
+    FormattingOnly,
+    Different(String),
+}
+
+fn compare_outputs(a: &str, b: &str) -> Comparison {
+    if a == b {
+        return Comparison::Identical;
+    }
+
+    let parser = Parser::default();
+
+    match (parser.parse(a), parser.parse(b)) {
+        (Ok(block_a), Ok(block_b)) => match find_first_difference(&block_a, &block_b) {
+            None => Comparison::FormattingOnly,
+            Some(path) => Comparison::Different(path),
+        },
+        (Err(err), _) | (_, Err(err)) => {
+            Comparison::Different(format!("unable to parse output: {}", err))
+        }
+    }
+}
+
+/// Reads the input files and both configuration files, so that they are only read once from
+/// the file system.
+fn read_files(options: &Options) -> Result<Vec<(PathBuf, String)>, String> {
+    let file_system = Resources::from_file_system();
+
+    let mut paths: Vec<_> = file_system.walk(&options.input_path).collect();
+    paths.push(options.config_a.clone());
+    paths.push(options.config_b.clone());
+
+    paths
+        .into_iter()
+        .map(|path| {
+            file_system
+                .get(&path)
+                .map(|content| (path.clone(), content))
+                .map_err(|err| format!("unable to read `{}`: {:?}", path.display(), err))
+        })
+        .collect()
+}
+
+fn memory_resources(files: &[(PathBuf, String)]) -> Result<Resources, String> {
+    let resources = Resources::from_memory();
+
+    for (path, content) in files {
+        resources
+            .write(path, content)
+            .map_err(|err| format!("unable to load `{}`: {:?}", path.display(), err))?;
+    }
+
+    Ok(resources)
+}
+
+fn process_with(resources: &Resources, options: &Options, config: &Path) -> Result<bool, String> {
+    let process_options = darklua_core::Options::new(&options.input_path)
+        .with_configuration_at(config)
+        .with_output(OUTPUT_PATH);
+
+    let result = darklua_core::process(resources, process_options)
+        .map_err(|err| format!("unable to process with `{}`: {}", config.display(), err))?;
+
+    let errors = result.collect_errors();
+
+    for error in errors.iter() {
+        log::error!("[{}] {}", config.display(), error);
+    }
+
+    Ok(errors.is_empty())
+}
+
+fn output_path(options: &Options, source: &Path) -> PathBuf {
+    let relative = source
+        .strip_prefix(&options.input_path)
+        .ok()
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .or_else(|| source.file_name().map(Path::new))
+        .unwrap_or(source);
+
+    Path::new(OUTPUT_PATH).join(relative)
+}
+
+pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `compare`: {:?}", options);
+
+    let files = read_files(options).map_err(|err| {
+        log::error!("{}", err);
+        CliError::new(2)
+    })?;
+
+    let mut success = true;
+    let mut outputs = Vec::new();
+    for config in [&options.config_a, &options.config_b] {
+        let resources = memory_resources(&files).map_err(|err| {
+            log::error!("{}", err);
+            CliError::new(2)
+        })?;
+        success &= process_with(&resources, options, config).map_err(|err| {
+            log::error!("{}", err);
+            CliError::new(2)
+        })?;
+        outputs.push(resources);
+    }
+    let (resources_a, resources_b) = (&outputs[0], &outputs[1]);
+
+    let mut sources: Vec<_> = Resources::from_file_system()
+        .collect_work(&options.input_path)
+        .collect();
+    sources.sort();
+
+    let (mut identical, mut formatting_only, mut different) = (0, 0, 0);
+    let (mut size_a, mut size_b) = (0, 0);
+
+    for source in sources {
+        let (Ok(output_a), Ok(output_b)) = (
+            resources_a.get(output_path(options, &source)),
+            resources_b.get(output_path(options, &source)),
+        ) else {
+            log::warn!("[{}] skip comparison: missing output", source.display());
+            continue;
+        };
+
+        size_a += output_a.len();
+        size_b += output_b.len();
+
+        let comparison = compare_outputs(&output_a, &output_b);
+
+        match &comparison {
+            Comparison::Identical => {
+                identical += 1;
+                println!("identical: {}", source.display());
+            }
+            Comparison::FormattingOnly => {
+                formatting_only += 1;
+                println!("formatting only: {}", source.display());
+            }
+            Comparison::Different(node_path) => {
+                different += 1;
+                println!("different: {} (at {})", source.display(), node_path);
+            }
+        }
+
+        if options.show_diff && comparison != Comparison::Identical {
+            print!(
+                "{}",
+                unified_diff(
+                    &output_a,
+                    &output_b,
+                    &format!("a/{}", source.display()),
+                    &format!("b/{}", source.display()),
+                )
+            );
+        }
+    }
+
+    let total = identical + formatting_only + different;
+    println!(
+        "compared {} file{}: {} identical, {} formatting only, {} different",
+        total,
+        maybe_plural(total),
+        identical,
+        formatting_only,
+        different
+    );
+    println!(
+        "total size: {} byte{} with `{}`, {} byte{} with `{}`",
+        size_a,
+        maybe_plural(size_a),
+        options.config_a.display(),
+        size_b,
+        maybe_plural(size_b),
+        options.config_b.display()
+    );
+
+    if !success {
+        Err(CliError::new(2))
+    } else if options.require_ast_equal && different > 0 {
+        log::error!(
+            "{} file{} with a different syntax tree",
+            different,
+            maybe_plural(different)
+        );
+        Err(CliError::new(1))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compare_equal_outputs() {
+        assert_eq!(
+            compare_outputs("return 1", "return 1"),
+            Comparison::Identical
+        );
+    }
+
+    #[test]
+    fn compare_outputs_with_formatting_difference() {
+        assert_eq!(
+            compare_outputs("return 1 -- one", "return 1"),
+            Comparison::FormattingOnly
+        );
+    }
+
+    #[test]
+    fn compare_outputs_with_different_syntax_tree() {
+        assert_eq!(
+            compare_outputs("return 1 + 1", "return 2"),
+            Comparison::Different("last statement (return)".to_owned())
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::cli::error::CliError;
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::{Args, Subcommand};
+use darklua_core::{DarkluaError, Resources};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_CONFIG_PATHS: [&str; 2] = [".darklua.json", ".darklua.json5"];
+
+#[derive(Debug, Args)]
+pub struct Options {
+    #[command(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// Print a configuration file
+    ///
+    /// With `--resolved`, the configuration files listed in `extends` are merged
+    /// into the configuration and the effective configuration is printed.
+    Show(ShowOptions),
+}
+
+#[derive(Debug, Args)]
+struct ShowOptions {
+    /// Choose a specific configuration file.
+    #[arg(long, short, alias = "config-path")]
+    config: Option<PathBuf>,
+    /// Print the configuration obtained by merging the extended configuration files.
+    #[arg(long)]
+    resolved: bool,
+}
+
+fn find_configuration(resources: &Resources, options: &ShowOptions) -> Result<PathBuf, CliError> {
+    if let Some(config) = options.config.as_ref() {
+        return Ok(config.to_path_buf());
+    }
+
+    let mut configuration_files = Vec::new();
+    for path in DEFAULT_CONFIG_PATHS.iter().map(Path::new) {
+        if resources.exists(path).map_err(|err| {
+            log::error!("{}", DarkluaError::from(err));
+            CliError::new(1)
+        })? {
+            configuration_files.push(path);
+        }
+    }
+
+    match configuration_files.as_slice() {
+        [path] => Ok(path.to_path_buf()),
+        [] => {
+            log::error!(
+                "no configuration file found (expected `{}`)",
+                DEFAULT_CONFIG_PATHS.join("` or `")
+            );
+            Err(CliError::new(1))
+        }
+        _ => {
+            log::error!("multiple configuration files found, use `--config` to choose one of them");
+            Err(CliError::new(1))
+        }
+    }
+}
+
+fn show_configuration(
+    resources: &Resources,
+    path: &Path,
+    resolved: bool,
+) -> Result<String, DarkluaError> {
+    if resolved {
+        let configuration = darklua_core::read_resolved_configuration(resources, path)?;
+        serde_json::to_string_pretty(&configuration).map_err(DarkluaError::from)
+    } else {
+        let content = resources.get(path).map_err(DarkluaError::from)?;
+        let value: serde_json::Value = json5::from_str(&content).map_err(|err| {
+            DarkluaError::custom(format!(
+                "unable to parse configuration file `{}`: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        serde_json::to_string_pretty(&value).map_err(DarkluaError::from)
+    }
+}
+
+pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `config`: {:?}", options);
+
+    match &options.command {
+        ConfigCommand::Show(show_options) => {
+            let resources = Resources::from_file_system();
+            let path = find_configuration(&resources, show_options)?;
+
+            let output =
+                show_configuration(&resources, &path, show_options.resolved).map_err(|err| {
+                    log::error!("{}", err);
+                    CliError::new(1)
+                })?;
+
+            println!("{}", output);
+            Ok(())
+        }
+    }
+}
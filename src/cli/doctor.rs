@@ -0,0 +1,157 @@
+use crate::cli::error::CliError;
+use crate::cli::utils::maybe_plural;
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::rules::CompatibilityKind;
+use darklua_core::{DoctorReport, Resources, RuleCompatibility};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The number of notes printed for each rule in the human summary.
+const MAX_EXAMPLES: usize = 3;
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Path to the lua file or directory to analyze.
+    input_path: PathBuf,
+    /// Choose a specific configuration file.
+    #[arg(long, short, alias = "config-path")]
+    config: Option<PathBuf>,
+    /// Choose how the report is printed ('human' or 'json').
+    #[arg(long, default_value = "human")]
+    format: ReportFormat,
+}
+
+impl Options {
+    fn get_process_options(&self) -> darklua_core::Options {
+        let mut process_options = darklua_core::Options::new(&self.input_path);
+
+        if let Some(config) = self.config.as_ref() {
+            process_options = process_options.with_configuration_at(config);
+        }
+
+        process_options
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum ReportFormat {
+    Human,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "format '{}' does not exist! (possible options are: 'human' or 'json')",
+                format
+            )),
+        }
+    }
+}
+
+fn write_rule_summary(rule: &RuleCompatibility) -> String {
+    let counts: Vec<_> = [
+        CompatibilityKind::Skipped,
+        CompatibilityKind::Risky,
+        CompatibilityKind::Conservative,
+    ]
+    .iter()
+    .filter_map(|kind| {
+        let count = rule.file_count(*kind);
+        (count > 0).then(|| format!("{} in {} file{}", kind, count, maybe_plural(count)))
+    })
+    .collect();
+
+    let mut output = format!("{}: {}\n", rule.rule(), counts.join(", "));
+
+    let mut notes = rule
+        .files()
+        .iter()
+        .flat_map(|file| file.notes().iter().map(move |note| (file.path(), note)));
+
+    for (path, note) in notes.by_ref().take(MAX_EXAMPLES) {
+        output.push_str(&format!("  {}: {}\n", path.display(), note));
+    }
+
+    let remaining = notes.count();
+    if remaining > 0 {
+        output.push_str(&format!(
+            "  ... and {} more note{}\n",
+            remaining,
+            maybe_plural(remaining)
+        ));
+    }
+
+    output
+}
+
+fn write_human(report: &DoctorReport) -> String {
+    let mut output = format!(
+        "checked {} file{}\n",
+        report.checked_files(),
+        maybe_plural(report.checked_files())
+    );
+
+    let issues = report.file_issues();
+    if !issues.is_empty() {
+        output.push_str(&format!(
+            "\n{} file{} could not be analyzed:\n",
+            issues.len(),
+            maybe_plural(issues.len())
+        ));
+        for issue in issues {
+            output.push_str(&format!(
+                "  {}: {}\n",
+                issue.path().display(),
+                issue.message().replace('\n', "\n    ")
+            ));
+        }
+    }
+
+    let (compatible, reported): (Vec<_>, Vec<_>) =
+        report.rules().iter().partition(|rule| rule.is_compatible());
+
+    for rule in reported {
+        output.push('\n');
+        output.push_str(&write_rule_summary(rule));
+    }
+
+    output.push_str(&format!(
+        "\n{} rule{} without compatibility notes",
+        compatible.len(),
+        maybe_plural(compatible.len())
+    ));
+
+    output
+}
+
+pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `doctor`: {:?}", options);
+
+    let resources = Resources::from_file_system();
+
+    let report =
+        darklua_core::doctor(&resources, options.get_process_options()).map_err(|err| {
+            log::error!("{}", err);
+            CliError::new(1)
+        })?;
+
+    let output = match options.format {
+        ReportFormat::Human => write_human(&report),
+        ReportFormat::Json => serde_json::to_string_pretty(&report).map_err(|err| {
+            log::error!("unable to serialize report: {}", err);
+            CliError::new(1)
+        })?,
+    };
+
+    println!("{}", output);
+
+    Ok(())
+}
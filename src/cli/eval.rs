@@ -0,0 +1,76 @@
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::{
+    nodes::{Block, Expression, LastStatement, ReturnStatement},
+    process::Evaluator,
+    rules::{ComputeExpression, ContextBuilder, Rule},
+    GeneratorParameters, Parser, Resources,
+};
+
+use super::error::CliError;
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// The Lua expression to evaluate
+    #[arg(short, long)]
+    expression: String,
+    /// Print the value of the expression, or fail if it cannot be computed
+    #[arg(long)]
+    value: bool,
+}
+
+pub fn run(options: &Options, _: &GlobalOptions) -> CommandResult {
+    match evaluate(options) {
+        Ok(output) => {
+            println!("{}", output);
+            Ok(())
+        }
+        Err(err) => {
+            eprintln!("an error happened: {}", err);
+            Err(CliError::new(1))
+        }
+    }
+}
+
+fn evaluate(options: &Options) -> Result<String, String> {
+    let expression = Parser::default()
+        .parse_expression(&options.expression)
+        .map_err(|err| err.to_string())?;
+
+    let generator = GeneratorParameters::default_dense();
+
+    if options.value {
+        let evaluator = Evaluator::default();
+
+        if evaluator.has_side_effects(&expression) {
+            return Err("unable to compute the value of the expression".to_owned());
+        }
+
+        return evaluator
+            .evaluate(&expression)
+            .to_expression()
+            .map(|value| darklua_core::generate_expression(&value, &generator))
+            .ok_or_else(|| "unable to compute the value of the expression".to_owned());
+    }
+
+    let folded = fold_expression(expression)?;
+
+    Ok(darklua_core::generate_expression(&folded, &generator))
+}
+
+fn fold_expression(expression: Expression) -> Result<Expression, String> {
+    let mut block = Block::default().with_last_statement(ReturnStatement::one(expression));
+
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new(".", &resources, "").build();
+    ComputeExpression::default().process(&mut block, &context)?;
+
+    match block.take_last_statement() {
+        Some(LastStatement::Return(statement)) => statement
+            .into_iter_expressions()
+            .next()
+            .ok_or_else(|| "unable to fold the expression".to_owned()),
+        _ => Err("unable to fold the expression".to_owned()),
+    }
+}
@@ -0,0 +1,127 @@
+use crate::cli::error::CliError;
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::process::{NodeQuery, QueryMatch};
+use darklua_core::{Parser, Resources};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Path to the lua file or directory to search.
+    input_path: PathBuf,
+    /// The pattern of the nodes to find ('call:<path>', 'global:<name>',
+    /// 'env-global:<name>', 'string:<text>', 'assign-global:*' or 'number:><value>').
+    #[arg(long, short)]
+    pattern: NodeQuery,
+    /// Choose how matches are printed ('text' or 'json').
+    #[arg(long, default_value = "text")]
+    format: FindFormat,
+    /// Exit with an error code when a match is found, instead of when no
+    /// match is found.
+    #[arg(long)]
+    fail_if_found: bool,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum FindFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for FindFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "format '{}' does not exist! (possible options are: 'text' or 'json')",
+                format
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct FileMatch<'a> {
+    path: String,
+    #[serde(flatten)]
+    found: &'a QueryMatch,
+}
+
+fn find_in_file(
+    resources: &Resources,
+    path: &Path,
+    query: &NodeQuery,
+) -> Result<Vec<QueryMatch>, String> {
+    let code = resources
+        .get(path)
+        .map_err(|err| format!("unable to read `{}`: {:?}", path.display(), err))?;
+
+    let mut block = Parser::default()
+        .preserve_tokens()
+        .parse(&code)
+        .map_err(|err| format!("unable to parse `{}`: {}", path.display(), err))?;
+
+    Ok(query.find(&mut block, &code))
+}
+
+pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `find`: {:?}", options);
+
+    let resources = Resources::from_file_system();
+
+    let mut files: Vec<_> = resources.collect_work(&options.input_path).collect();
+    files.sort();
+
+    let mut matches = Vec::new();
+    let mut has_errors = false;
+
+    for path in files {
+        match find_in_file(&resources, &path, &options.pattern) {
+            Ok(found) => {
+                matches.extend(found.into_iter().map(|found| (path.clone(), found)));
+            }
+            Err(err) => {
+                log::error!("{}", err);
+                has_errors = true;
+            }
+        }
+    }
+
+    match options.format {
+        FindFormat::Text => {
+            for (path, found) in matches.iter() {
+                println!("{}:{}", path.display(), found);
+            }
+        }
+        FindFormat::Json => {
+            let output: Vec<_> = matches
+                .iter()
+                .map(|(path, found)| FileMatch {
+                    path: path.display().to_string(),
+                    found,
+                })
+                .collect();
+            let json = serde_json::to_string_pretty(&output).map_err(|err| {
+                log::error!("unable to serialize matches: {}", err);
+                CliError::new(2)
+            })?;
+            println!("{}", json);
+        }
+    }
+
+    if has_errors {
+        return Err(CliError::new(2));
+    }
+
+    if matches.is_empty() == options.fail_if_found {
+        Ok(())
+    } else {
+        Err(CliError::new(1))
+    }
+}
@@ -0,0 +1,66 @@
+use crate::cli::error::CliError;
+use crate::cli::utils::report_process;
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::{ProcessingSession, Resources};
+use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Path to the lua file or directory to format.
+    input_path: PathBuf,
+    /// Choose a specific configuration file.
+    #[arg(long, short, alias = "config-path")]
+    config: Option<PathBuf>,
+    /// Skip the files and directories matching the given glob pattern (like 'Packages' or
+    /// '**/*.spec.lua') when collecting the files to format. The pattern is matched against
+    /// the paths relative to the input directory, and the excluded directories are not read.
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+}
+
+impl Options {
+    fn get_process_options(&self) -> darklua_core::Options {
+        let mut process_options =
+            darklua_core::Options::new(&self.input_path).with_formatting_rules_only();
+
+        if let Some(config) = self.config.as_ref() {
+            process_options = process_options.with_configuration_at(config);
+        }
+
+        for pattern in self.exclude.iter() {
+            process_options = process_options.with_exclude(pattern);
+        }
+
+        process_options
+    }
+}
+
+fn format(process_options: darklua_core::Options, command: &'static str) -> CommandResult {
+    let resources = Resources::from_file_system();
+
+    let process_start_time = Instant::now();
+
+    let mut session = ProcessingSession::from_resources(resources, process_options);
+
+    session.run().map_err(|err| {
+        log::error!("{}", err);
+        CliError::new(1)
+    })?;
+
+    report_process(command, &session, process_start_time.elapsed()).map_err(|_| CliError::new(1))
+}
+
+pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `format`: {:?}", options);
+
+    format(options.get_process_options(), "formatted")
+}
+
+pub fn run_check(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `format-check`: {:?}", options);
+
+    format(options.get_process_options().with_check(), "checked")
+}
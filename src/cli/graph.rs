@@ -0,0 +1,221 @@
+use crate::cli::error::CliError;
+use crate::cli::utils::{maybe_plural, RojoProject};
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::{DependencyGraph, DependencyGraphOptions, Resources};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Path to the lua file or directory to analyze.
+    input_path: PathBuf,
+    /// Choose how the graph is printed ('dot', 'json' or 'edges').
+    #[arg(long, default_value = "dot")]
+    format: GraphFormat,
+    /// Path to a Rojo project file (like `default.project.json`) used to resolve
+    /// requires of Roblox instances.
+    #[arg(long, value_name = "PATH", value_parser = parse_rojo_project)]
+    rojo_project: Option<RojoProject>,
+    /// Only print the files required by the given module, directly or indirectly, and
+    /// the files that require it.
+    #[arg(long, value_name = "MODULE")]
+    focus: Option<PathBuf>,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum GraphFormat {
+    Dot,
+    Json,
+    Edges,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "dot" => Ok(Self::Dot),
+            "json" => Ok(Self::Json),
+            "edges" => Ok(Self::Edges),
+            _ => Err(format!(
+                "format '{}' does not exist! (possible options are: 'dot', 'json' or 'edges')",
+                format
+            )),
+        }
+    }
+}
+
+fn parse_rojo_project(path: &str) -> Result<RojoProject, String> {
+    RojoProject::from_file(Path::new(path))
+}
+
+#[derive(Debug, Serialize)]
+struct JsonUnresolvedRequire {
+    source: String,
+    line: Option<usize>,
+    require: String,
+    reason: String,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonGraph {
+    modules: BTreeMap<String, Vec<String>>,
+    cycles: Vec<Vec<String>>,
+    unresolved: Vec<JsonUnresolvedRequire>,
+}
+
+fn display_path(path: &Path) -> String {
+    path.display().to_string().replace('\\', "/")
+}
+
+fn dot_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn write_dot(graph: &DependencyGraph, cycles: &[Vec<PathBuf>]) -> String {
+    let cycle_edges: BTreeSet<(&Path, &Path)> = cycles
+        .iter()
+        .flat_map(|cycle| {
+            cycle
+                .iter()
+                .zip(cycle.iter().cycle().skip(1))
+                .map(|(from, to)| (from.as_path(), to.as_path()))
+        })
+        .collect();
+
+    let mut dot = "digraph dependencies {\n".to_owned();
+
+    for module in graph.modules() {
+        dot.push_str(&format!("    {};\n", dot_string(&display_path(module))));
+    }
+
+    for (from, to) in graph.edges() {
+        dot.push_str(&format!(
+            "    {} -> {}{};\n",
+            dot_string(&display_path(from)),
+            dot_string(&display_path(to)),
+            if cycle_edges.contains(&(from, to)) {
+                " [color=red]"
+            } else {
+                ""
+            }
+        ));
+    }
+
+    dot.push('}');
+    dot
+}
+
+fn write_edges(graph: &DependencyGraph) -> String {
+    graph
+        .edges()
+        .map(|(from, to)| format!("{} -> {}", display_path(from), display_path(to)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn write_json(graph: &DependencyGraph, cycles: &[Vec<PathBuf>]) -> serde_json::Result<String> {
+    let output = JsonGraph {
+        modules: graph
+            .modules()
+            .map(|module| {
+                (
+                    display_path(module),
+                    graph.dependencies(module).map(display_path).collect(),
+                )
+            })
+            .collect(),
+        cycles: cycles
+            .iter()
+            .map(|cycle| cycle.iter().map(|path| display_path(path)).collect())
+            .collect(),
+        unresolved: graph
+            .unresolved()
+            .iter()
+            .map(|unresolved| JsonUnresolvedRequire {
+                source: display_path(unresolved.source()),
+                line: unresolved.line(),
+                require: unresolved.require().to_owned(),
+                reason: unresolved.reason().to_owned(),
+            })
+            .collect(),
+    };
+
+    serde_json::to_string_pretty(&output)
+}
+
+fn report_cycles(cycles: &[Vec<PathBuf>]) {
+    if cycles.is_empty() {
+        return;
+    }
+
+    eprintln!(
+        "found {} dependency cycle{}:",
+        cycles.len(),
+        maybe_plural(cycles.len())
+    );
+
+    for cycle in cycles {
+        let mut modules: Vec<_> = cycle.iter().map(|path| display_path(path)).collect();
+        if let Some(first) = modules.first().cloned() {
+            modules.push(first);
+        }
+        eprintln!("-> {}", modules.join(" -> "));
+    }
+}
+
+pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `graph`: {:?}", options);
+
+    let resources = Resources::from_file_system();
+
+    let mut graph_options = DependencyGraphOptions::new(&options.input_path);
+
+    if let Some(project) = options.rojo_project.as_ref() {
+        graph_options = graph_options.with_roblox_paths(project.collect_instance_paths(&resources));
+    }
+
+    let mut graph = DependencyGraph::build(&resources, graph_options).map_err(|err| {
+        log::error!("{}", err);
+        CliError::new(1)
+    })?;
+
+    if let Some(module) = options.focus.as_ref() {
+        graph = graph.focus(module).ok_or_else(|| {
+            log::error!(
+                "unable to focus on `{}`: the module is not in the graph",
+                module.display()
+            );
+            CliError::new(1)
+        })?;
+    }
+
+    for unresolved in graph.unresolved() {
+        log::warn!("{}", unresolved);
+    }
+
+    let cycles = graph.cycles();
+
+    let output = match options.format {
+        GraphFormat::Dot => write_dot(&graph, &cycles),
+        GraphFormat::Edges => write_edges(&graph),
+        GraphFormat::Json => write_json(&graph, &cycles).map_err(|err| {
+            log::error!("unable to serialize graph: {}", err);
+            CliError::new(1)
+        })?,
+    };
+
+    if !output.is_empty() {
+        println!("{}", output);
+    }
+
+    if !matches!(options.format, GraphFormat::Json) {
+        report_cycles(&cycles);
+    }
+
+    Ok(())
+}
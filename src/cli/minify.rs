@@ -3,7 +3,7 @@ use crate::cli::utils::report_process;
 use crate::cli::{CommandResult, GlobalOptions};
 
 use clap::Args;
-use darklua_core::{Configuration, GeneratorParameters, Resources};
+use darklua_core::{Configuration, GeneratorParameters, ProcessingSession, Resources};
 use std::path::PathBuf;
 use std::time::Instant;
 
@@ -16,13 +16,18 @@ pub struct Options {
     /// The maximum number of characters that should be written on a line.
     #[arg(long)]
     column_span: Option<usize>,
+    /// Skip the files and directories matching the given glob pattern (like 'Packages' or
+    /// '**/*.spec.lua') when collecting the files to minify. The pattern is matched against
+    /// the paths relative to the input directory, and the excluded directories are not read.
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
 }
 
 pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
     log::debug!("running `minify`: {:?}", options);
 
     let resources = Resources::from_file_system();
-    let process_options = darklua_core::Options::new(&options.input_path)
+    let mut process_options = darklua_core::Options::new(&options.input_path)
         .with_output(&options.output_path)
         .with_configuration(
             Configuration::empty().with_generator(
@@ -33,12 +38,18 @@ pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
             ),
         );
 
+    for pattern in options.exclude.iter() {
+        process_options = process_options.with_exclude(pattern);
+    }
+
     let process_start_time = Instant::now();
 
-    let result = darklua_core::process(&resources, process_options).map_err(|err| {
+    let mut session = ProcessingSession::from_resources(resources, process_options);
+
+    session.run().map_err(|err| {
         log::error!("{}", err);
         CliError::new(1)
     })?;
 
-    report_process("minified", &result, process_start_time.elapsed()).map_err(|_| CliError::new(1))
+    report_process("minified", &session, process_start_time.elapsed()).map_err(|_| CliError::new(1))
 }
@@ -1,7 +1,20 @@
+pub mod analyze_size;
+pub mod apply_edits;
+pub mod check_docs;
+pub mod check_output;
+pub mod compare;
+pub mod config;
 pub mod convert;
+pub mod doctor;
 pub mod error;
+pub mod eval;
+pub mod find;
+pub mod format;
+pub mod graph;
 pub mod minify;
 pub mod process;
+pub mod rules;
+pub mod schema;
 pub mod utils;
 
 use clap::{Args, Parser, Subcommand};
@@ -41,6 +54,94 @@ pub enum Command {
     Process(process::Options),
     /// Convert a data file [json, json5, yaml, toml] into a Lua file
     Convert(convert::Options),
+    /// Evaluate a Lua expression and print the result
+    ///
+    /// Constant parts of the expression are computed and the result is printed
+    /// as minified Lua code. Use `--value` to require the expression to be fully
+    /// computed into a value.
+    Eval(eval::Options),
+    /// Apply the formatting rules of the configuration to lua files
+    ///
+    /// Only the rules that change how the code is formatted (like `remove_spaces`
+    /// or `normalize_semicolons`) are applied, and files are overwritten in place.
+    Format(format::Options),
+    /// Check that lua files are already formatted
+    ///
+    /// Runs the same rules as the `format` command, but reports an error for each
+    /// file that would be modified instead of writing it.
+    FormatCheck(format::Options),
+    /// Find nodes matching a pattern in lua files and print their locations
+    ///
+    /// Each match is printed on its own line as `path:line:column: kind snippet`.
+    /// The command fails when no match is found, or when a match is found if
+    /// `--fail-if-found` is used.
+    Find(find::Options),
+    /// Process lua files with two configurations and compare the results
+    ///
+    /// Each file is reported as identical, different only in formatting (the
+    /// syntax trees are equal) or different, with the location of the first
+    /// node that differs.
+    Compare(compare::Options),
+    /// Print a JSON Schema describing the configuration file
+    ///
+    /// Editors can use the schema to validate and complete darklua
+    /// configuration files. The schema includes the properties of every rule.
+    Schema(schema::Options),
+    /// Print the graph of the files required by lua files
+    ///
+    /// Requires of strings and of Roblox instances (like `script.Parent.Util`)
+    /// are resolved to files. Dependency cycles and requires that cannot be
+    /// resolved are reported on stderr.
+    Graph(graph::Options),
+    /// Report what takes space in the code generated for lua files
+    ///
+    /// The bytes of each file are attributed to identifiers (locals, globals
+    /// and fields), strings, numbers, keywords and symbols, comments and
+    /// whitespace. The longest identifiers and strings are listed with their
+    /// number of occurrences. Files are processed with the configuration before
+    /// being analyzed, unless `--raw` is used.
+    AnalyzeSize(analyze_size::Options),
+    /// Check that lua files pass the output checks of the configuration
+    ///
+    /// The files are checked as they are, without processing them. Each
+    /// violation is printed on its own line as `path:line:column: message (check)`
+    /// and the command fails when a violation is found.
+    CheckOutput(check_output::Options),
+    /// Check that documentation comments match the parameters of functions
+    ///
+    /// Functions with `@param` or `@return` tags in their leading comments are
+    /// verified: each parameter (including `...`) must have one `@param` tag, in
+    /// the order of the parameters. Each mismatch is printed on its own line as
+    /// `path:line:column: message` and the command fails when a mismatch is found.
+    /// With `--fix`, stubs are added for undocumented parameters and the tags of
+    /// parameters that do not exist are removed.
+    CheckDocs(check_docs::Options),
+    /// Predict which rules are safe to apply on lua files
+    ///
+    /// Every file is parsed and analyzed without applying any rule. For each
+    /// built-in rule, the report lists the files where applying it would be
+    /// skipped (like renaming in files using `setfenv`), risky (like files
+    /// exceeding the bytecode limits) or conservative (like dynamic requires).
+    /// Files that cannot be read or parsed are reported separately.
+    Doctor(doctor::Options),
+    /// Apply a list of node edits to a lua file
+    ///
+    /// The edits are read from a JSON file. Each edit targets a node from its
+    /// path (like `block.statements[3].values[0]`) and replaces it, deletes it
+    /// or inserts a statement before it. New nodes are written as Lua code. No
+    /// edit is applied if one of them is invalid.
+    ApplyEdits(apply_edits::Options),
+    /// List the built-in rules
+    ///
+    /// With `--format json --full`, each rule is described with its properties,
+    /// its capabilities and its examples. The output of each example is computed
+    /// by applying the rule on it.
+    Rules(rules::Options),
+    /// Inspect configuration files
+    ///
+    /// Use `darklua config show --resolved` to print the effective configuration
+    /// once the configuration files listed in `extends` are merged.
+    Config(config::Options),
 }
 
 impl Command {
@@ -49,6 +150,20 @@ impl Command {
             Command::Minify(options) => minify::run(options, global_options),
             Command::Process(options) => process::run(options, global_options),
             Command::Convert(options) => convert::run(options, global_options),
+            Command::Eval(options) => eval::run(options, global_options),
+            Command::Format(options) => format::run(options, global_options),
+            Command::FormatCheck(options) => format::run_check(options, global_options),
+            Command::Find(options) => find::run(options, global_options),
+            Command::Compare(options) => compare::run(options, global_options),
+            Command::Schema(options) => schema::run(options, global_options),
+            Command::Graph(options) => graph::run(options, global_options),
+            Command::AnalyzeSize(options) => analyze_size::run(options, global_options),
+            Command::CheckOutput(options) => check_output::run(options, global_options),
+            Command::CheckDocs(options) => check_docs::run(options, global_options),
+            Command::Doctor(options) => doctor::run(options, global_options),
+            Command::ApplyEdits(options) => apply_edits::run(options, global_options),
+            Command::Rules(options) => rules::run(options, global_options),
+            Command::Config(options) => config::run(options, global_options),
         }
     }
 }
@@ -1,18 +1,27 @@
 use crate::cli::error::CliError;
-use crate::cli::utils::report_process;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::cli::utils::FileWatcher;
+use crate::cli::utils::{report_process, RojoProject};
 use crate::cli::{CommandResult, GlobalOptions};
 
 use clap::Args;
-use darklua_core::{GeneratorParameters, Resources};
-use std::path::PathBuf;
+use darklua_core::process::EnvironmentTarget;
+use darklua_core::rules::{parse_byte_size, parse_duration, verify_generated_name_prefix};
+use darklua_core::{
+    ArtifactTemplate, AtomicMode, DeprecationPolicy, GeneratorParameters, HashAlgorithm,
+    OutputTemplate, ProcessingSession, RawMarkers, ReprocessPolicy, Resources, StageSelection,
+};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Args, Clone)]
 pub struct Options {
-    /// Path to the lua file to process.
+    /// Path to the lua file or directory to process. It can also be a glob pattern relative to
+    /// the current directory (like 'src/**/*.lua'): the Lua files matching the pattern, or
+    /// inside a directory matching it, are processed.
     pub(crate) input_path: PathBuf,
     /// Where to output the result.
     output_path: PathBuf,
@@ -23,9 +32,145 @@ pub struct Options {
     /// This will override the format given by the configuration file.
     #[arg(long)]
     format: Option<LuaFormat>,
+    /// Choose the environment in which the code runs ('lua51', 'lua53', 'lua54',
+    /// 'luau' or 'roblox'), so that rules know which globals exist.
+    /// This will override the target given by the configuration file.
+    #[arg(long)]
+    target: Option<EnvironmentTarget>,
     /// Watch files and directories for changes and automatically re-run
     #[arg(long, short)]
     watch: bool,
+    /// Skip rules that have no effect on modules that only return a table
+    /// of literal values.
+    #[arg(long)]
+    data_fast_path: bool,
+    /// Prefix the names generated by rules (like helper variables). A `{hash}`
+    /// placeholder is replaced with a short hash of each file content.
+    /// This will override the prefix given by the configuration file.
+    #[arg(long, value_name = "PREFIX", value_parser = parse_symbol_prefix)]
+    symbol_prefix: Option<String>,
+    /// Path to a Rojo project file (like `default.project.json`). Rules can use the
+    /// path of the Roblox instance created from each file instead of the file path.
+    #[arg(long, value_name = "PATH", value_parser = parse_rojo_project)]
+    rojo_project: Option<RojoProject>,
+    /// Copy the regions of code between the given begin and end markers (separated by a
+    /// comma) to the output without parsing them. Without a value, the regions are
+    /// delimited by `--[[@raw]]` and `--[[@endraw]]`.
+    /// This will override the markers given by the configuration file.
+    #[arg(
+        long,
+        value_name = "BEGIN,END",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "--[[@raw]],--[[@endraw]]",
+        value_parser = parse_raw_markers
+    )]
+    raw_markers: Option<RawMarkers>,
+    /// Process the files listed in the given file (one path per line) instead of
+    /// the files found in the input path. Use `-` to read the list from stdin.
+    /// Output paths are computed relative to the input path.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["watch", "files_from0"])]
+    files_from: Option<PathBuf>,
+    /// Same as `--files-from`, but with paths separated by NUL characters.
+    #[arg(long, value_name = "PATH", conflicts_with = "watch")]
+    files_from0: Option<PathBuf>,
+    /// Stop processing files after the first error.
+    #[arg(long)]
+    fail_fast: bool,
+    /// Write the generated files in a staging directory first, and move them to their
+    /// destination only if every file succeeds. With `--atomic=best-effort`, the files that
+    /// succeeded are moved even if other files failed.
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "all"
+    )]
+    atomic: Option<AtomicMode>,
+    /// Keep the staging directory of `--atomic` instead of removing it.
+    #[arg(long, requires = "atomic")]
+    keep_staging: bool,
+    /// Where the generated files of `--atomic` are staged. Staging on the same file system
+    /// as the output avoids copying the files.
+    #[arg(long, value_name = "PATH", requires = "atomic")]
+    staging_dir: Option<PathBuf>,
+    /// Append a comment at the end of each generated file with the version of darklua, a
+    /// hash of the configuration and the applied rules. Enabled by default when the files
+    /// are processed in place.
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    mark_processed: Option<bool>,
+    /// Choose what happens with files that were already processed by darklua ('skip',
+    /// 'force' or 'differential'). With 'differential', only the rules that were not
+    /// applied before are applied.
+    #[arg(long, value_name = "POLICY")]
+    reprocess_policy: Option<ReprocessPolicy>,
+    /// Fail when the generated code of a file is larger than the given size. The size is a
+    /// number of bytes or a number followed by a unit (like '200KB' or '1 MiB').
+    #[arg(long, value_name = "SIZE", value_parser = parse_byte_size)]
+    max_size: Option<u64>,
+    /// Compress each generated file into a self-extracting file that decompresses and loads
+    /// its code when it runs. This is not available for the 'luau' and 'roblox' targets.
+    #[arg(long)]
+    self_extract: bool,
+    /// Skip a rule for a file when it runs for longer than the given duration on that file.
+    /// The duration is a number of seconds or a number followed by a unit (like '30s' or
+    /// '500ms'). This will override the time budget given by the configuration file.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    rule_timeout: Option<Duration>,
+    /// Choose the file name of each generated file. The template can use the `{name}`,
+    /// `{extension}`, `{content_hash}` and `{content_hash8}` placeholders (like
+    /// `{name}.{content_hash8}.lua`).
+    #[arg(long, value_name = "TEMPLATE")]
+    output_template: Option<OutputTemplate>,
+    /// Write the digest of each generated file to the given file, with one
+    /// `relative/path.lua: <digest>` line per file.
+    #[arg(long, value_name = "PATH")]
+    hash_manifest: Option<PathBuf>,
+    /// Choose the algorithm used to compute the digest of the generated files ('sha256' or
+    /// 'xxh3'). Defaults to 'sha256'.
+    #[arg(long, value_name = "ALGORITHM")]
+    hash_algorithm: Option<HashAlgorithm>,
+    /// Write the artifacts with the given name emitted by rules (like the `strings` artifact
+    /// of `extract_strings`) to a path relative to each generated file. The path can use the
+    /// same placeholders as `--output-template` (like `strings=i18n/{name}.json`).
+    /// This will override the path given by the configuration file.
+    #[arg(long = "artifact", value_name = "NAME=PATH", value_parser = parse_artifact)]
+    artifacts: Vec<(String, ArtifactTemplate)>,
+    /// Do not warn when the configuration file uses deprecated rule names.
+    #[arg(long, conflicts_with = "deny_deprecated")]
+    no_deprecation_warnings: bool,
+    /// Fail when the configuration file uses deprecated rule names.
+    #[arg(long)]
+    deny_deprecated: bool,
+    /// Fail when a rule is configured with properties that can change the behavior of the
+    /// code (like `assume_no_metatables`).
+    #[arg(long)]
+    strict: bool,
+    /// Skip the files and directories matching the given glob pattern (like 'Packages' or
+    /// '**/*.spec.lua') when collecting the files to process. The pattern is matched against
+    /// the paths relative to the input directory, and the excluded directories are not read.
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+    /// Follow the symlinks found in the input directory. Each file is processed once, even
+    /// if several symlinks lead to it.
+    #[arg(long)]
+    follow_symlinks: bool,
+    /// Run only the stage with the given name from the `stages` of the configuration, using
+    /// the state written by the previous stage. With 'all' (the default), every stage runs
+    /// in sequence.
+    #[arg(long, value_name = "NAME")]
+    stage: Option<StageSelection>,
+    /// Where the state shared between stages is written. Defaults to '.darklua-cache'.
+    /// This will override the directory given by the configuration file.
+    #[arg(long, value_name = "PATH")]
+    cache_dir: Option<PathBuf>,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -52,15 +197,49 @@ impl FromStr for LuaFormat {
     }
 }
 
+fn parse_symbol_prefix(prefix: &str) -> Result<String, String> {
+    verify_generated_name_prefix(prefix)?;
+    Ok(prefix.to_owned())
+}
+
+fn parse_raw_markers(markers: &str) -> Result<RawMarkers, String> {
+    match markers.split_once(',') {
+        Some((begin, end)) if !begin.is_empty() && !end.is_empty() => {
+            Ok(RawMarkers::new(begin, end))
+        }
+        _ => Err(format!(
+            "invalid raw markers `{}` (expected the begin and end markers separated by a comma)",
+            markers
+        )),
+    }
+}
+
+fn parse_artifact(artifact: &str) -> Result<(String, ArtifactTemplate), String> {
+    match artifact.split_once('=') {
+        Some((name, template)) if !name.is_empty() => Ok((name.to_owned(), template.parse()?)),
+        _ => Err(format!(
+            "invalid artifact `{}` (expected the artifact name and its path separated by `=`)",
+            artifact
+        )),
+    }
+}
+
+fn parse_rojo_project(path: &str) -> Result<RojoProject, String> {
+    RojoProject::from_file(Path::new(path))
+}
+
 fn process(resources: Resources, process_options: darklua_core::Options) -> CommandResult {
     let process_start_time = Instant::now();
 
-    let result = darklua_core::process(&resources, process_options).map_err(|err| {
+    let mut session = ProcessingSession::from_resources(resources, process_options);
+
+    session.run().map_err(|err| {
         log::error!("{}", err);
         CliError::new(1)
     })?;
 
-    report_process("processed", &result, process_start_time.elapsed()).map_err(|_| CliError::new(1))
+    report_process("processed", &session, process_start_time.elapsed())
+        .map_err(|_| CliError::new(1))
 }
 
 impl Options {
@@ -72,6 +251,108 @@ impl Options {
             process_options = process_options.with_configuration_at(config);
         }
 
+        if self.fail_fast {
+            process_options = process_options.fail_fast();
+        }
+
+        if let Some(mode) = self.atomic {
+            process_options = process_options.with_atomic_mode(mode);
+
+            if self.keep_staging {
+                process_options = process_options.keep_staging();
+            }
+
+            if let Some(directory) = self.staging_dir.as_ref() {
+                process_options = process_options.with_staging_directory(directory);
+            }
+        }
+
+        if self
+            .mark_processed
+            .unwrap_or(self.input_path == self.output_path)
+        {
+            process_options = process_options.with_processed_marker();
+        }
+
+        if let Some(policy) = self.reprocess_policy {
+            process_options = process_options.with_reprocess_policy(policy);
+        }
+
+        if let Some(max_size) = self.max_size {
+            process_options = process_options.with_max_output_size(max_size);
+        }
+
+        if self.self_extract {
+            process_options = process_options.with_self_extract();
+        }
+
+        if let Some(timeout) = self.rule_timeout {
+            process_options = process_options.with_rule_timeout(timeout);
+        }
+
+        if let Some(template) = self.output_template.as_ref() {
+            process_options = process_options.with_output_template(template.clone());
+        }
+
+        if let Some(manifest) = self.hash_manifest.as_ref() {
+            process_options = process_options.with_hash_manifest(manifest);
+        }
+
+        if let Some(algorithm) = self.hash_algorithm {
+            process_options = process_options.with_hash_algorithm(algorithm);
+        }
+
+        for (name, template) in self.artifacts.iter() {
+            process_options = process_options.with_artifact(name, template.clone());
+        }
+
+        if self.deny_deprecated {
+            process_options = process_options.with_deprecation_policy(DeprecationPolicy::Deny);
+        } else if self.no_deprecation_warnings {
+            process_options = process_options.with_deprecation_policy(DeprecationPolicy::Allow);
+        }
+
+        if self.data_fast_path {
+            process_options = process_options.with_data_fast_path();
+        }
+
+        if self.strict {
+            process_options = process_options.with_strict();
+        }
+
+        for pattern in self.exclude.iter() {
+            process_options = process_options.with_exclude(pattern);
+        }
+
+        if self.follow_symlinks {
+            process_options = process_options.with_follow_symlinks();
+        }
+
+        if let Some(stage) = self.stage.as_ref() {
+            process_options = process_options.with_stage(stage.clone());
+        }
+
+        if let Some(directory) = self.cache_dir.as_ref() {
+            process_options = process_options.with_cache_directory(directory);
+        }
+
+        if let Some(target) = self.target {
+            process_options = process_options.with_target(target);
+        }
+
+        if let Some(prefix) = self.symbol_prefix.as_ref() {
+            process_options = process_options.with_generated_name_prefix(prefix);
+        }
+
+        if let Some(markers) = self.raw_markers.as_ref() {
+            process_options = process_options.with_raw_markers(markers.clone());
+        }
+
+        if let Some(project) = self.rojo_project.as_ref() {
+            process_options = process_options
+                .with_roblox_paths(project.collect_instance_paths(&Resources::from_file_system()));
+        }
+
         if let Some(format) = self.format {
             process_options = process_options.with_generator_override(match format {
                 LuaFormat::Dense => GeneratorParameters::default_dense(),
@@ -81,6 +362,40 @@ impl Options {
         }
         process_options
     }
+
+    fn read_files_list(&self) -> Result<Option<Vec<PathBuf>>, String> {
+        let (path, separator) = match (&self.files_from, &self.files_from0) {
+            (Some(path), _) => (path, '\n'),
+            (None, Some(path)) => (path, '\0'),
+            (None, None) => return Ok(None),
+        };
+
+        let content = if path == Path::new("-") {
+            let mut content = String::new();
+            io::stdin().read_to_string(&mut content).map(|_| content)
+        } else {
+            fs::read_to_string(path)
+        }
+        .map_err(|err| {
+            format!(
+                "unable to read list of files from `{}`: {}",
+                path.display(),
+                err
+            )
+        })?;
+
+        let files = content
+            .split(separator)
+            .map(|file| match separator {
+                '\n' => file.strip_suffix('\r').unwrap_or(file),
+                _ => file,
+            })
+            .filter(|file| !file.is_empty())
+            .map(PathBuf::from)
+            .collect();
+
+        Ok(Some(files))
+    }
 }
 
 pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
@@ -95,6 +410,15 @@ pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
     } else {
         let resources = Resources::from_file_system();
 
-        process(resources, options.get_process_options())
+        let mut process_options = options.get_process_options();
+
+        if let Some(files) = options.read_files_list().map_err(|err| {
+            log::error!("{}", err);
+            CliError::new(1)
+        })? {
+            process_options = process_options.with_files(files);
+        }
+
+        process(resources, process_options)
     }
 }
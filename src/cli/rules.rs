@@ -0,0 +1,69 @@
+use crate::cli::error::CliError;
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::rules::get_all_rule_names;
+use std::str::FromStr;
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Choose how the rules are printed ('text' or 'json').
+    #[arg(long, default_value = "text")]
+    format: RulesFormat,
+    /// Describe the properties, capabilities and examples of each rule (requires
+    /// `--format json`).
+    #[arg(long)]
+    full: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum RulesFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for RulesFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "format '{}' does not exist! (possible options are: 'text' or 'json')",
+                format
+            )),
+        }
+    }
+}
+
+fn to_json(value: &impl serde::Serialize) -> Result<String, CliError> {
+    serde_json::to_string_pretty(value).map_err(|err| {
+        log::error!("unable to serialize rules: {}", err);
+        CliError::new(1)
+    })
+}
+
+pub fn run(options: &Options, _global: &GlobalOptions) -> CommandResult {
+    log::debug!("running `rules`: {:?}", options);
+
+    let output = match (options.format, options.full) {
+        (RulesFormat::Text, true) => {
+            log::error!("`--full` can only be used with `--format json`");
+            return Err(CliError::new(1));
+        }
+        (RulesFormat::Text, false) => get_all_rule_names().join("\n"),
+        (RulesFormat::Json, false) => to_json(&get_all_rule_names())?,
+        (RulesFormat::Json, true) => {
+            let descriptions = darklua_core::describe_rules().map_err(|err| {
+                log::error!("{}", err);
+                CliError::new(1)
+            })?;
+            to_json(&descriptions)?
+        }
+    };
+
+    println!("{}", output);
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use crate::cli::{CommandResult, GlobalOptions};
+
+use clap::Args;
+use darklua_core::{DarkluaError, Resources};
+use std::path::PathBuf;
+
+use super::error::CliError;
+
+#[derive(Debug, Args)]
+pub struct Options {
+    /// Path where to write the JSON Schema (printed to stdout by default)
+    output: Option<PathBuf>,
+}
+
+pub fn run(options: &Options, _: &GlobalOptions) -> CommandResult {
+    write_schema(options).map_err(|err| {
+        eprintln!("an error happened: {}", err);
+        CliError::new(1)
+    })
+}
+
+fn write_schema(options: &Options) -> Result<(), DarkluaError> {
+    let schema = darklua_core::get_configuration_schema();
+
+    let content = serde_json::to_string_pretty(&schema).map_err(DarkluaError::from)?;
+
+    if let Some(output) = &options.output {
+        Resources::from_file_system()
+            .write(output, &content)
+            .map_err(DarkluaError::from)?;
+        log::info!("configuration schema written to `{}`", output.display());
+    } else {
+        println!("{}", content);
+    }
+
+    Ok(())
+}
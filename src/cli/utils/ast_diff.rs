@@ -0,0 +1,205 @@
+use darklua_core::nodes::{Block, IfStatement, LastStatement, Statement};
+
+/// Returns a description of the path to the first node that differs between two blocks, or
+/// `None` if both blocks are equal.
+pub fn find_first_difference(a: &Block, b: &Block) -> Option<String> {
+    block_difference(a, b).map(|path| path.join(" > "))
+}
+
+fn block_difference(a: &Block, b: &Block) -> Option<Vec<String>> {
+    if a == b {
+        return None;
+    }
+
+    for (index, (left, right)) in a.iter_statements().zip(b.iter_statements()).enumerate() {
+        if left != right {
+            let mut path = vec![format!(
+                "statement {} ({})",
+                index + 1,
+                statement_kind(left)
+            )];
+            if let Some(inner_path) = statement_difference(left, right) {
+                path.extend(inner_path);
+            }
+            return Some(path);
+        }
+    }
+
+    let common_length = a.statements_len().min(b.statements_len());
+
+    if a.statements_len() != b.statements_len() {
+        let statement = a
+            .iter_statements()
+            .chain(b.iter_statements().skip(common_length))
+            .nth(common_length)
+            .map(statement_kind)
+            .unwrap_or("statement");
+        return Some(vec![format!(
+            "statement {} ({}, only in one output)",
+            common_length + 1,
+            statement
+        )]);
+    }
+
+    let kind = a
+        .get_last_statement()
+        .or_else(|| b.get_last_statement())
+        .map(last_statement_kind)
+        .unwrap_or("last statement");
+
+    Some(vec![format!("last statement ({})", kind)])
+}
+
+fn statement_difference(a: &Statement, b: &Statement) -> Option<Vec<String>> {
+    match (a, b) {
+        (Statement::Do(a), Statement::Do(b)) => block_difference(a.get_block(), b.get_block()),
+        (Statement::While(a), Statement::While(b)) if a.get_condition() == b.get_condition() => {
+            block_difference(a.get_block(), b.get_block())
+        }
+        (Statement::Repeat(a), Statement::Repeat(b)) => {
+            block_difference(a.get_block(), b.get_block())
+        }
+        (Statement::NumericFor(a), Statement::NumericFor(b))
+            if a.get_identifier() == b.get_identifier()
+                && a.get_start() == b.get_start()
+                && a.get_end() == b.get_end()
+                && a.get_step() == b.get_step() =>
+        {
+            block_difference(a.get_block(), b.get_block())
+        }
+        (Statement::GenericFor(a), Statement::GenericFor(b))
+            if a.get_identifiers() == b.get_identifiers()
+                && a.get_expressions() == b.get_expressions() =>
+        {
+            block_difference(a.get_block(), b.get_block())
+        }
+        (Statement::LocalFunction(a), Statement::LocalFunction(b))
+            if a.get_identifier() == b.get_identifier()
+                && a.get_parameters() == b.get_parameters() =>
+        {
+            block_difference(a.get_block(), b.get_block())
+        }
+        (Statement::Function(a), Statement::Function(b))
+            if a.get_name() == b.get_name() && a.get_parameters() == b.get_parameters() =>
+        {
+            block_difference(a.get_block(), b.get_block())
+        }
+        (Statement::If(a), Statement::If(b)) => if_difference(a, b),
+        _ => None,
+    }
+}
+
+fn if_difference(a: &IfStatement, b: &IfStatement) -> Option<Vec<String>> {
+    for (index, (left, right)) in a.iter_branches().zip(b.iter_branches()).enumerate() {
+        if left.get_condition() != right.get_condition() {
+            return Some(vec![format!("branch {} (condition)", index + 1)]);
+        }
+        if let Some(inner_path) = block_difference(left.get_block(), right.get_block()) {
+            let mut path = vec![format!("branch {}", index + 1)];
+            path.extend(inner_path);
+            return Some(path);
+        }
+    }
+
+    if a.get_branches().len() != b.get_branches().len() {
+        return None;
+    }
+
+    match (a.get_else_block(), b.get_else_block()) {
+        (Some(left), Some(right)) => block_difference(left, right).map(|inner_path| {
+            let mut path = vec!["else".to_owned()];
+            path.extend(inner_path);
+            path
+        }),
+        _ => None,
+    }
+}
+
+fn statement_kind(statement: &Statement) -> &'static str {
+    match statement {
+        Statement::Assign(_) => "assignment",
+        Statement::Do(_) => "do",
+        Statement::Call(_) => "call",
+        Statement::CompoundAssign(_) => "compound assignment",
+        Statement::Function(_) => "function",
+        Statement::GenericFor(_) => "generic for",
+        Statement::If(_) => "if",
+        Statement::LocalAssign(_) => "local assignment",
+        Statement::LocalFunction(_) => "local function",
+        Statement::NumericFor(_) => "numeric for",
+        Statement::Repeat(_) => "repeat",
+        Statement::While(_) => "while",
+        Statement::TypeDeclaration(_) => "type declaration",
+        Statement::Verbatim(_) => "verbatim",
+    }
+}
+
+fn last_statement_kind(statement: &LastStatement) -> &'static str {
+    match statement {
+        LastStatement::Break(_) => "break",
+        LastStatement::Continue(_) => "continue",
+        LastStatement::Return(_) => "return",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use darklua_core::Parser;
+
+    fn difference(a: &str, b: &str) -> Option<String> {
+        let parser = Parser::default();
+        find_first_difference(
+            &parser.parse(a).expect("code should parse"),
+            &parser.parse(b).expect("code should parse"),
+        )
+    }
+
+    #[test]
+    fn equal_blocks() {
+        assert_eq!(difference("local a = 1", "local  a=1"), None);
+    }
+
+    #[test]
+    fn different_statement() {
+        pretty_assertions::assert_eq!(
+            difference("local a = 1 print(a)", "local a = 1 print(b)"),
+            Some("statement 2 (call)".to_owned())
+        );
+    }
+
+    #[test]
+    fn different_statement_in_nested_blocks() {
+        pretty_assertions::assert_eq!(
+            difference(
+                "if a then do print(1) end end",
+                "if a then do print(2) end end"
+            ),
+            Some("statement 1 (if) > branch 1 > statement 1 (do) > statement 1 (call)".to_owned())
+        );
+    }
+
+    #[test]
+    fn different_if_condition() {
+        pretty_assertions::assert_eq!(
+            difference("if a then end", "if b then end"),
+            Some("statement 1 (if) > branch 1 (condition)".to_owned())
+        );
+    }
+
+    #[test]
+    fn additional_statement() {
+        pretty_assertions::assert_eq!(
+            difference("local a = 1", "local a = 1 print(a)"),
+            Some("statement 2 (call, only in one output)".to_owned())
+        );
+    }
+
+    #[test]
+    fn different_last_statement() {
+        pretty_assertions::assert_eq!(
+            difference("return 1", "return 2"),
+            Some("last statement (return)".to_owned())
+        );
+    }
+}
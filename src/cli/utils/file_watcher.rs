@@ -14,7 +14,7 @@ use notify_debouncer_full::{new_debouncer, DebounceEventResult, DebouncedEvent};
 
 use crate::cli::{error::CliError, process::Options as ProcessOptions, CommandResult};
 
-use super::report_process;
+use super::report_worker_tree;
 
 const FILE_WATCHING_DEBOUNCE_DURATION_MILLIS: u64 = 400;
 const DEFAULT_CONFIG_PATHS: [&str; 2] = [".darklua.json", ".darklua.json5"];
@@ -42,7 +42,7 @@ impl FileWatcher {
         let (sender, receiver) = mpsc::channel();
 
         Self {
-            input_path: process_option.input_path.clone(),
+            input_path: darklua_core::Options::new(&process_option.input_path).input_root(),
             resources: Resources::from_file_system(),
             sender,
             receiver: Some(receiver),
@@ -69,14 +69,16 @@ impl FileWatcher {
         }
 
         if let Some(worker_tree) = self.worker_tree.as_mut() {
-            report_process("processed", worker_tree, process_start_time.elapsed()).ok();
+            report_worker_tree("processed", worker_tree, process_start_time.elapsed()).ok();
         }
 
         self.update_extra_file_watch();
     }
 
     fn build_options(&self) -> Options {
-        self.process_option.get_process_options()
+        self.process_option
+            .get_process_options()
+            .with_incremental_generation()
     }
 
     pub fn start(mut self) -> CommandResult {
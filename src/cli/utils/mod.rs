@@ -1,11 +1,17 @@
+mod ast_diff;
 #[cfg(not(target_arch = "wasm32"))]
 mod file_watcher;
+mod rojo_project;
+mod text_diff;
 
 use std::time::Duration;
 
-use darklua_core::WorkerTree;
+pub use ast_diff::find_first_difference;
+use darklua_core::{DarkluaError, ProcessingSession, WorkerTree};
 #[cfg(not(target_arch = "wasm32"))]
 pub use file_watcher::FileWatcher;
+pub use rojo_project::RojoProject;
+pub use text_diff::unified_diff;
 
 pub fn maybe_plural(count: usize) -> &'static str {
     if count > 1 {
@@ -16,13 +22,96 @@ pub fn maybe_plural(count: usize) -> &'static str {
 }
 
 pub fn report_process(
+    command: &'static str,
+    session: &ProcessingSession,
+    duration: Duration,
+) -> Result<(), ()> {
+    let result = report(
+        command,
+        session.success_count(),
+        session.collect_errors(),
+        duration,
+    );
+
+    report_self_extracting_sizes(session);
+    report_timed_out_rules(session);
+
+    result
+}
+
+fn report_self_extracting_sizes(session: &ProcessingSession) {
+    for (name, outcome) in session.iter_outputs() {
+        if let Some((raw_size, output_size)) =
+            outcome.uncompressed_size().zip(outcome.output_size())
+        {
+            println!(
+                "-> `{}` is {} bytes self-extracting ({} bytes raw{})",
+                name,
+                output_size,
+                raw_size,
+                if output_size >= raw_size {
+                    ", the self-extracting file is not smaller"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+}
+
+fn report_timed_out_rules(session: &ProcessingSession) {
+    let timed_out_rules: Vec<_> = session
+        .iter_outputs()
+        .flat_map(|(name, outcome)| {
+            outcome
+                .timed_out_rules()
+                .iter()
+                .map(move |(rule, elapsed)| (name, rule, elapsed))
+        })
+        .collect();
+
+    if timed_out_rules.is_empty() {
+        return;
+    }
+
+    let count = timed_out_rules.len();
+    eprintln!(
+        "{} rule{} exceeded the time budget and {} skipped:",
+        count,
+        maybe_plural(count),
+        if count > 1 { "were" } else { "was" }
+    );
+
+    for (name, rule, elapsed) in timed_out_rules {
+        eprintln!(
+            "-> `{}` on `{}` (stopped after {})",
+            rule,
+            name,
+            durationfmt::to_string(*elapsed)
+        );
+    }
+}
+
+pub fn report_worker_tree(
     command: &'static str,
     worker_tree: &WorkerTree,
     duration: Duration,
 ) -> Result<(), ()> {
-    let process_duration = durationfmt::to_string(duration);
+    report(
+        command,
+        worker_tree.success_count(),
+        worker_tree.collect_errors(),
+        duration,
+    )
+}
 
-    let success_count = worker_tree.success_count();
+fn report(
+    command: &'static str,
+    success_count: usize,
+    errors: Vec<&DarkluaError>,
+    duration: Duration,
+) -> Result<(), ()> {
+    let process_duration = durationfmt::to_string(duration);
 
     println!(
         "successfully {} {} file{} (in {})",
@@ -32,8 +121,6 @@ pub fn report_process(
         process_duration
     );
 
-    let errors = worker_tree.collect_errors();
-
     if errors.is_empty() {
         Ok(())
     } else {
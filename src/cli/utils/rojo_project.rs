@@ -0,0 +1,289 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use darklua_core::Resources;
+use serde_json::{Map, Value};
+
+/// An instance of a Rojo project tree that is synced from a file or a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProjectMount {
+    instance_path: Vec<String>,
+    path: PathBuf,
+}
+
+impl ProjectMount {
+    fn resolve(&self, relative: &Path) -> Option<String> {
+        let components = relative
+            .components()
+            .map(|component| component.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut names = self.instance_path.clone();
+
+        if let Some((file_name, directories)) = components.split_last() {
+            names.extend(directories.iter().map(|directory| directory.to_string()));
+
+            let name = script_name(file_name)?;
+            // `init` scripts create the instance of their parent directory
+            if name != "init" {
+                names.push(name.to_owned());
+            }
+        } else {
+            script_name(self.path.file_name()?.to_str()?)?;
+        }
+
+        if names.is_empty() {
+            None
+        } else {
+            Some(names.join("."))
+        }
+    }
+}
+
+/// Returns the name of the instance created from a Lua file name, or `None` if the file
+/// is not a Lua script.
+fn script_name(file_name: &str) -> Option<&str> {
+    let stem = file_name
+        .strip_suffix(".luau")
+        .or_else(|| file_name.strip_suffix(".lua"))?;
+
+    Some(
+        stem.strip_suffix(".server")
+            .or_else(|| stem.strip_suffix(".client"))
+            .unwrap_or(stem),
+    )
+}
+
+fn collect_mounts(
+    node: &Map<String, Value>,
+    instance_path: Vec<String>,
+    location: &Path,
+    mounts: &mut Vec<ProjectMount>,
+) -> Result<(), String> {
+    if let Some(path) = node.get("$path") {
+        let path = match path {
+            Value::String(path) => Some(path.as_str()),
+            Value::Object(object) => object.get("optional").and_then(Value::as_str),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            format!(
+                "invalid `$path` value for instance `{}`",
+                display_instance_path(&instance_path)
+            )
+        })?;
+
+        mounts.push(ProjectMount {
+            instance_path: instance_path.clone(),
+            path: location.join(path),
+        });
+    }
+
+    for (name, child) in node.iter() {
+        if name.starts_with('$') {
+            continue;
+        }
+        if let Some(child) = child.as_object() {
+            let mut child_path = instance_path.clone();
+            child_path.push(name.clone());
+            collect_mounts(child, child_path, location, mounts)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn display_instance_path(instance_path: &[String]) -> String {
+    if instance_path.is_empty() {
+        "<root>".to_owned()
+    } else {
+        instance_path.join(".")
+    }
+}
+
+/// The instances of a Rojo project (like `default.project.json`) that are synced from the
+/// file system. It is used to find the path of the Roblox instance created from a Lua file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RojoProject {
+    mounts: Vec<ProjectMount>,
+}
+
+impl RojoProject {
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("unable to read Rojo project `{}`: {}", path.display(), err))?;
+
+        let location = path.parent().unwrap_or_else(|| Path::new(""));
+
+        Self::parse(&content, location)
+            .map_err(|err| format!("unable to parse Rojo project `{}`: {}", path.display(), err))
+    }
+
+    /// Parses the content of a Rojo project. The `$path` values of the project are relative
+    /// to the given location.
+    pub fn parse(content: &str, location: &Path) -> Result<Self, String> {
+        let project: Value = serde_json::from_str(content).map_err(|err| err.to_string())?;
+
+        let tree = project
+            .get("tree")
+            .and_then(Value::as_object)
+            .ok_or("missing `tree` object")?;
+
+        let root_path = if tree.get("$className").and_then(Value::as_str) == Some("DataModel") {
+            Vec::new()
+        } else {
+            let name = project
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or("missing `name` string")?;
+            vec![name.to_owned()]
+        };
+
+        let mut mounts = Vec::new();
+        collect_mounts(tree, root_path, location, &mut mounts)?;
+
+        Ok(Self { mounts })
+    }
+
+    /// Returns the path of the Roblox instance created from the given file (like
+    /// `ReplicatedStorage.Shared.Util`), or `None` if the file is not synced by the project.
+    pub fn instance_path(&self, file: &Path) -> Option<String> {
+        self.mounts
+            .iter()
+            .filter_map(|mount| {
+                file.strip_prefix(&mount.path)
+                    .ok()
+                    .map(|relative| (mount, relative))
+            })
+            .max_by_key(|(mount, _)| mount.path.components().count())
+            .and_then(|(mount, relative)| mount.resolve(relative))
+    }
+
+    /// Returns the instance path of each Lua file synced by the project.
+    pub fn collect_instance_paths(&self, resources: &Resources) -> Vec<(PathBuf, String)> {
+        self.mounts
+            .iter()
+            .flat_map(|mount| resources.collect_work(&mount.path))
+            .filter_map(|file| {
+                self.instance_path(&file)
+                    .map(|instance_path| (file, instance_path))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const GAME_PROJECT: &str = r#"{
+        "name": "game",
+        "tree": {
+            "$className": "DataModel",
+            "ReplicatedStorage": {
+                "$className": "ReplicatedStorage",
+                "Shared": {
+                    "$path": "src/shared"
+                },
+                "Packages": {
+                    "$path": "packages",
+                    "Index": {
+                        "$path": "packages/_Index"
+                    }
+                }
+            },
+            "ServerScriptService": {
+                "Server": {
+                    "$path": "src/server"
+                }
+            },
+            "StarterPlayer": {
+                "StarterPlayerScripts": {
+                    "Client": {
+                        "$path": { "optional": "src/client" }
+                    }
+                }
+            },
+            "Workspace": {
+                "Main": {
+                    "$path": "src/main.server.lua"
+                },
+                "Config": {
+                    "$path": "config.json"
+                }
+            }
+        }
+    }"#;
+
+    fn game_project() -> RojoProject {
+        RojoProject::parse(GAME_PROJECT, Path::new("")).expect("project should parse")
+    }
+
+    macro_rules! test_instance_paths {
+        ($project:expr, $($name:ident ($path:literal) => $expected:expr),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    pretty_assertions::assert_eq!(
+                        $project.instance_path(Path::new($path)).as_deref(),
+                        $expected
+                    );
+                }
+            )*
+        };
+    }
+
+    test_instance_paths!(
+        game_project(),
+        module_in_directory("src/shared/Util.lua") => Some("ReplicatedStorage.Shared.Util"),
+        luau_module_in_directory("src/shared/Util.luau") => Some("ReplicatedStorage.Shared.Util"),
+        init_module_of_mounted_directory("src/shared/init.lua") => Some("ReplicatedStorage.Shared"),
+        init_module_in_nested_directory("src/shared/Signal/init.lua")
+            => Some("ReplicatedStorage.Shared.Signal"),
+        module_in_nested_directory("src/shared/Signal/Connection.luau")
+            => Some("ReplicatedStorage.Shared.Signal.Connection"),
+        init_server_script("src/server/init.server.lua") => Some("ServerScriptService.Server"),
+        client_script("src/client/Input.client.lua")
+            => Some("StarterPlayer.StarterPlayerScripts.Client.Input"),
+        mounted_file("src/main.server.lua") => Some("Workspace.Main"),
+        nested_mount("packages/_Index/Promise.lua") => Some("ReplicatedStorage.Packages.Index.Promise"),
+        parent_mount("packages/Promise.lua") => Some("ReplicatedStorage.Packages.Promise"),
+        unmapped_file("scripts/build.lua") => None,
+        non_lua_file("src/shared/data.json") => None,
+        mounted_non_lua_file("config.json") => None,
+    );
+
+    fn library_project() -> RojoProject {
+        RojoProject::parse(
+            r#"{ "name": "Library", "tree": { "$path": "src" } }"#,
+            Path::new("lib"),
+        )
+        .expect("project should parse")
+    }
+
+    test_instance_paths!(
+        library_project(),
+        library_init_module("lib/src/init.lua") => Some("Library"),
+        library_module("lib/src/Module.lua") => Some("Library.Module"),
+        library_file_outside_location("src/Module.lua") => None,
+    );
+
+    #[test]
+    fn parse_project_without_tree_should_error() {
+        pretty_assertions::assert_eq!(
+            RojoProject::parse(r#"{ "name": "game" }"#, Path::new("")),
+            Err("missing `tree` object".to_owned())
+        );
+    }
+
+    #[test]
+    fn parse_project_with_invalid_path_should_error() {
+        pretty_assertions::assert_eq!(
+            RojoProject::parse(
+                r#"{ "name": "game", "tree": { "$className": "DataModel", "Lighting": { "$path": 1 } } }"#,
+                Path::new("")
+            ),
+            Err("invalid `$path` value for instance `Lighting`".to_owned())
+        );
+    }
+}
@@ -0,0 +1,252 @@
+/// Lines longer than this are not compared line by line (minified code often fits on a
+/// single line).
+const MAX_DIFF_LINE_LENGTH: usize = 200;
+/// Above this number of compared line pairs, the diff is not computed.
+const MAX_DIFF_COMPLEXITY: usize = 4_000_000;
+const CONTEXT_LINES: usize = 3;
+const LONG_LINE_CONTEXT: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+impl DiffLine<'_> {
+    fn is_same(&self) -> bool {
+        matches!(self, Self::Same(_))
+    }
+}
+
+fn diff_lines<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<DiffLine<'a>> {
+    // length of the longest common subsequence of `a[i..]` and `b[j..]`
+    let width = b.len() + 1;
+    let mut lengths = vec![0usize; (a.len() + 1) * width];
+
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i * width + j] = if a[i] == b[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            lines.push(DiffLine::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            lines.push(DiffLine::Removed(a[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(b[j]));
+            j += 1;
+        }
+    }
+
+    lines.extend(a[i..].iter().map(|line| DiffLine::Removed(line)));
+    lines.extend(b[j..].iter().map(|line| DiffLine::Added(line)));
+
+    lines
+}
+
+/// Describes where two texts with very long lines start to differ.
+fn long_line_difference(a: &str, b: &str) -> String {
+    let offset = a
+        .char_indices()
+        .zip(b.chars())
+        .find(|((_, left), right)| left != right)
+        .map(|((index, _), _)| index)
+        .unwrap_or_else(|| a.len().min(b.len()));
+
+    let excerpt = |text: &str| {
+        let start = text[..offset.min(text.len())]
+            .char_indices()
+            .rev()
+            .nth(LONG_LINE_CONTEXT - 1)
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+        text[start..]
+            .chars()
+            .take(LONG_LINE_CONTEXT * 2)
+            .collect::<String>()
+    };
+
+    format!(
+        "outputs differ at byte {} (lines are too long to be compared line by line)\n-{}\n+{}\n",
+        offset,
+        excerpt(a),
+        excerpt(b)
+    )
+}
+
+/// Writes the differences between two texts in the unified diff format. When the texts have
+/// very long lines, only the location of the first difference is shown.
+pub fn unified_diff(a: &str, b: &str, a_label: &str, b_label: &str) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", a_label, b_label);
+
+    if a == b {
+        return output;
+    }
+
+    let a_lines: Vec<_> = a.lines().collect();
+    let b_lines: Vec<_> = b.lines().collect();
+
+    if a_lines
+        .iter()
+        .chain(b_lines.iter())
+        .any(|line| line.len() > MAX_DIFF_LINE_LENGTH)
+    {
+        output.push_str(&long_line_difference(a, b));
+        return output;
+    }
+
+    if a_lines.len().saturating_mul(b_lines.len()) > MAX_DIFF_COMPLEXITY {
+        output.push_str(&format!(
+            "outputs are too large to be compared ({} and {} lines)\n",
+            a_lines.len(),
+            b_lines.len()
+        ));
+        return output;
+    }
+
+    let lines = diff_lines(&a_lines, &b_lines);
+
+    let mut index = 0;
+    while let Some(change) = lines[index..].iter().position(|line| !line.is_same()) {
+        let change = index + change;
+        let start = change.saturating_sub(CONTEXT_LINES);
+
+        // extend the hunk while the next change is close enough
+        let mut end = change;
+        let mut unchanged = 0;
+        for (offset, line) in lines[change..].iter().enumerate() {
+            if line.is_same() {
+                unchanged += 1;
+                if unchanged > CONTEXT_LINES * 2 {
+                    break;
+                }
+            } else {
+                unchanged = 0;
+                end = change + offset;
+            }
+        }
+        let end = (end + CONTEXT_LINES + 1).min(lines.len());
+
+        let a_start = lines[..start]
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Added(_)))
+            .count();
+        let b_start = lines[..start]
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Removed(_)))
+            .count();
+        let hunk = &lines[start..end];
+        let a_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Added(_)))
+            .count();
+        let b_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Removed(_)))
+            .count();
+
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            a_start + 1,
+            a_count,
+            b_start + 1,
+            b_count
+        ));
+
+        for line in hunk {
+            let (prefix, content) = match line {
+                DiffLine::Same(content) => (' ', content),
+                DiffLine::Removed(content) => ('-', content),
+                DiffLine::Added(content) => ('+', content),
+            };
+            output.push(prefix);
+            output.push_str(content);
+            output.push('\n');
+        }
+
+        index = end;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn diff_equal_texts() {
+        pretty_assertions::assert_eq!(
+            unified_diff("a\nb\n", "a\nb\n", "a.lua", "b.lua"),
+            "--- a.lua\n+++ b.lua\n"
+        );
+    }
+
+    #[test]
+    fn diff_changed_line() {
+        pretty_assertions::assert_eq!(
+            unified_diff("a\nb\nc\n", "a\nB\nc\n", "a.lua", "b.lua"),
+            "--- a.lua\n+++ b.lua\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"
+        );
+    }
+
+    #[test]
+    fn diff_added_line_at_end() {
+        pretty_assertions::assert_eq!(
+            unified_diff("a\n", "a\nb\n", "a.lua", "b.lua"),
+            "--- a.lua\n+++ b.lua\n@@ -1,1 +1,2 @@\n a\n+b\n"
+        );
+    }
+
+    #[test]
+    fn diff_separate_hunks() {
+        let a = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        let b = "one\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\ntwelve\n";
+
+        pretty_assertions::assert_eq!(
+            unified_diff(a, b, "a.lua", "b.lua"),
+            "--- a.lua\n+++ b.lua\n\
+            @@ -1,4 +1,4 @@\n-1\n+one\n 2\n 3\n 4\n\
+            @@ -9,4 +9,4 @@\n 9\n 10\n 11\n-12\n+twelve\n"
+        );
+    }
+
+    #[test]
+    fn diff_long_lines() {
+        let a = format!("return {{{}1}}", "0,".repeat(100));
+        let b = format!("return {{{}2}}", "0,".repeat(100));
+
+        let diff = unified_diff(&a, &b, "a.lua", "b.lua");
+
+        assert!(
+            diff.starts_with("--- a.lua\n+++ b.lua\noutputs differ at byte 208"),
+            "unexpected diff: {}",
+            diff
+        );
+        assert!(
+            diff.lines()
+                .any(|line| line.starts_with("-0,") && line.ends_with(",0,1}")),
+            "unexpected diff: {}",
+            diff
+        );
+        assert!(
+            diff.lines()
+                .any(|line| line.starts_with("+0,") && line.ends_with(",0,2}")),
+            "unexpected diff: {}",
+            diff
+        );
+    }
+}
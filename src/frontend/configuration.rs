@@ -1,37 +1,208 @@
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, BTreeSet},
     path::{Path, PathBuf},
     str::FromStr,
+    time::Duration,
 };
 
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+#[cfg(feature = "serde-config")]
+use crate::rules::{parse_duration, verify_generated_name_prefix};
 use crate::{
-    generator::{DenseLuaGenerator, LuaGenerator, ReadableLuaGenerator, TokenBasedLuaGenerator},
-    nodes::Block,
+    generator::{
+        generate_segments, match_segments, regenerate_segments, DenseLuaGenerator, LuaGenerator,
+        ReadableLuaGenerator, TokenBasedLuaGenerator,
+    },
+    nodes::{Block, Expression},
+    process::{Environment, EnvironmentTarget, GlobalEntry},
     rules::{
         bundle::{BundleRequireMode, Bundler},
-        get_default_rules, Rule,
+        get_default_rules, Rule, TextPostprocessor, TextPreprocessor,
     },
-    Parser,
+    Parser, RawMarkers,
 };
 
+use super::{incremental::SegmentedLua, ArtifactTemplate, OutputCheck, StageConfiguration};
+
 const DEFAULT_COLUMN_SPAN: usize = 80;
 
+#[cfg(feature = "serde-config")]
 fn get_default_column_span() -> usize {
     DEFAULT_COLUMN_SPAN
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
+const DEFAULT_MAX_CONSECUTIVE_BLANK_LINES: usize = 1;
+
+#[cfg(feature = "serde-config")]
+fn get_default_max_consecutive_blank_lines() -> usize {
+    DEFAULT_MAX_CONSECUTIVE_BLANK_LINES
+}
+
+#[cfg(feature = "serde-config")]
+fn is_default_max_consecutive_blank_lines(value: &usize) -> bool {
+    *value == DEFAULT_MAX_CONSECUTIVE_BLANK_LINES
+}
+
+#[cfg(feature = "serde-config")]
+fn deserialize_generated_name_prefix<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let prefix = String::deserialize(deserializer)?;
+    verify_generated_name_prefix(&prefix).map_err(serde::de::Error::custom)?;
+    Ok(Some(prefix))
+}
+
+#[cfg(feature = "serde-config")]
+fn deserialize_rule_timeout<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawDuration {
+        Seconds(f64),
+        Text(String),
+    }
+
+    match RawDuration::deserialize(deserializer)? {
+        RawDuration::Seconds(seconds) => Duration::try_from_secs_f64(seconds)
+            .map_err(|_| {
+                serde::de::Error::custom(format!(
+                    "invalid rule timeout `{}` (expected a positive number of seconds)",
+                    seconds
+                ))
+            })
+            .map(Some),
+        RawDuration::Text(value) => parse_duration(&value)
+            .map_err(serde::de::Error::custom)
+            .map(Some),
+    }
+}
+
+#[cfg(feature = "serde-config")]
+fn serialize_rule_timeout<S>(timeout: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match timeout {
+        Some(timeout) if timeout.subsec_nanos() == 0 => {
+            serializer.serialize_str(&format!("{}s", timeout.as_secs()))
+        }
+        Some(timeout) if timeout.subsec_nanos() % 1_000_000 == 0 => {
+            serializer.serialize_str(&format!("{}ms", timeout.as_millis()))
+        }
+        Some(timeout) => serializer.serialize_str(&format!("{}s", timeout.as_secs_f64())),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-config", serde(deny_unknown_fields))]
 pub struct Configuration {
-    #[serde(alias = "process", default = "get_default_rules")]
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    #[cfg_attr(not(feature = "serde-config"), allow(dead_code))]
+    schema_version: Option<u64>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(alias = "process", default = "get_default_rules")
+    )]
     rules: Vec<Box<dyn Rule>>,
-    #[serde(default, deserialize_with = "crate::utils::string_or_struct")]
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, deserialize_with = "crate::utils::string_or_struct")
+    )]
     generator: GeneratorParameters,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     bundle: Option<BundleConfiguration>,
-    #[serde(default, skip)]
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    data_fast_path: bool,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    strict: bool,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    preprocessors: Vec<Box<dyn TextPreprocessor>>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    postprocessors: Vec<Box<dyn TextPostprocessor>>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    target: Option<EnvironmentTarget>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    source_target: Option<EnvironmentTarget>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    globals: Vec<GlobalEntry>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(
+            default,
+            deserialize_with = "deserialize_generated_name_prefix",
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    generated_name_prefix: Option<String>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    raw_markers: Option<RawMarkers>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    artifacts: BTreeMap<String, ArtifactTemplate>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    checks: Vec<OutputCheck>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    stages: Vec<StageConfiguration>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    cache_directory: Option<PathBuf>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(
+            default,
+            deserialize_with = "deserialize_rule_timeout",
+            serialize_with = "serialize_rule_timeout",
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    rule_timeout: Option<Duration>,
+    #[cfg_attr(feature = "serde-config", serde(default, skip))]
     location: Option<PathBuf>,
 }
 
@@ -40,9 +211,24 @@ impl Configuration {
     /// generator
     pub fn empty() -> Self {
         Self {
+            schema_version: None,
             rules: Vec::new(),
             generator: GeneratorParameters::default(),
             bundle: None,
+            data_fast_path: false,
+            strict: false,
+            preprocessors: Vec::new(),
+            postprocessors: Vec::new(),
+            target: None,
+            source_target: None,
+            globals: Vec::new(),
+            generated_name_prefix: None,
+            raw_markers: None,
+            artifacts: BTreeMap::new(),
+            checks: Vec::new(),
+            stages: Vec::new(),
+            cache_directory: None,
+            rule_timeout: None,
             location: None,
         }
     }
@@ -70,6 +256,174 @@ impl Configuration {
         self
     }
 
+    /// Skip the rules that have no effect on data modules (modules that only
+    /// return a table of literal values) when processing these modules.
+    #[inline]
+    pub fn with_data_fast_path(mut self) -> Self {
+        self.data_fast_path = true;
+        self
+    }
+
+    #[inline]
+    pub fn set_data_fast_path(&mut self, enabled: bool) {
+        self.data_fast_path = enabled;
+    }
+
+    /// Rejects the rules configured with properties that can change the behavior of the
+    /// code (see [`RuleConfiguration::unsafe_properties`](crate::rules::RuleConfiguration::unsafe_properties)).
+    #[inline]
+    pub fn with_strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    #[inline]
+    pub fn set_strict(&mut self, enabled: bool) {
+        self.strict = enabled;
+    }
+
+    /// Adds a text transformation that runs on the source code before parsing it.
+    #[inline]
+    pub fn with_preprocessor(mut self, preprocessor: impl Into<Box<dyn TextPreprocessor>>) -> Self {
+        self.push_preprocessor(preprocessor);
+        self
+    }
+
+    #[inline]
+    pub fn push_preprocessor(&mut self, preprocessor: impl Into<Box<dyn TextPreprocessor>>) {
+        self.preprocessors.push(preprocessor.into());
+    }
+
+    /// Adds a text transformation that runs on the generated code.
+    #[inline]
+    pub fn with_postprocessor(
+        mut self,
+        postprocessor: impl Into<Box<dyn TextPostprocessor>>,
+    ) -> Self {
+        self.push_postprocessor(postprocessor);
+        self
+    }
+
+    #[inline]
+    pub fn push_postprocessor(&mut self, postprocessor: impl Into<Box<dyn TextPostprocessor>>) {
+        self.postprocessors.push(postprocessor.into());
+    }
+
+    /// Sets the environment in which the processed code runs, so that rules know which
+    /// globals exist.
+    #[inline]
+    pub fn with_target(mut self, target: EnvironmentTarget) -> Self {
+        self.set_target(target);
+        self
+    }
+
+    #[inline]
+    pub fn set_target(&mut self, target: EnvironmentTarget) {
+        self.target = Some(target);
+    }
+
+    /// Sets the environment for which the source code is written, so that the parser accepts
+    /// its syntax (like identifiers with UTF-8 characters for Luau).
+    #[inline]
+    pub fn with_source_target(mut self, target: EnvironmentTarget) -> Self {
+        self.source_target = Some(target);
+        self
+    }
+
+    /// Sets the prefix of the names generated by rules (like helper variables). A `{hash}`
+    /// placeholder is replaced with a short hash of each processed file.
+    #[inline]
+    pub fn with_generated_name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.set_generated_name_prefix(prefix);
+        self
+    }
+
+    #[inline]
+    pub fn set_generated_name_prefix(&mut self, prefix: impl Into<String>) {
+        self.generated_name_prefix = Some(prefix.into());
+    }
+
+    /// Sets the markers of the regions of code that are copied to the output without being
+    /// parsed or modified by rules.
+    #[inline]
+    pub fn with_raw_markers(mut self, markers: RawMarkers) -> Self {
+        self.set_raw_markers(markers);
+        self
+    }
+
+    #[inline]
+    pub fn set_raw_markers(&mut self, markers: RawMarkers) {
+        self.raw_markers = Some(markers);
+    }
+
+    /// Writes the artifacts with the given name emitted by rules (like the `strings` artifact
+    /// of `extract_strings`) to the path computed from the template, relative to the
+    /// directory of each generated file.
+    #[inline]
+    pub fn with_artifact(mut self, name: impl Into<String>, template: ArtifactTemplate) -> Self {
+        self.set_artifact(name, template);
+        self
+    }
+
+    #[inline]
+    pub fn set_artifact(&mut self, name: impl Into<String>, template: ArtifactTemplate) {
+        self.artifacts.insert(name.into(), template);
+    }
+
+    /// Adds a check that the generated code of each file must pass. Files that do not pass
+    /// a check fail to process.
+    #[inline]
+    pub fn with_check(mut self, check: OutputCheck) -> Self {
+        self.push_check(check);
+        self
+    }
+
+    #[inline]
+    pub fn push_check(&mut self, check: OutputCheck) {
+        self.checks.push(check);
+    }
+
+    /// Adds a stage to the rules pipeline. When stages are defined, the rules of the
+    /// configuration are not used: each stage applies its own rules.
+    #[inline]
+    pub fn with_stage(mut self, stage: StageConfiguration) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Sets the directory where the state shared between stages is written. By default, it
+    /// is `.darklua-cache`.
+    #[inline]
+    pub fn with_cache_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.set_cache_directory(directory);
+        self
+    }
+
+    #[inline]
+    pub fn set_cache_directory(&mut self, directory: impl Into<PathBuf>) {
+        self.cache_directory = Some(directory.into());
+    }
+
+    /// Sets the time budget of each rule applied to a file. When a rule runs for longer, its
+    /// changes to the file are discarded and the next rules are applied.
+    #[inline]
+    pub fn with_rule_timeout(mut self, timeout: Duration) -> Self {
+        self.set_rule_timeout(timeout);
+        self
+    }
+
+    #[inline]
+    pub fn set_rule_timeout(&mut self, timeout: Duration) {
+        self.rule_timeout = Some(timeout);
+    }
+
+    /// Adds a global to the environment, on top of the globals of the target.
+    #[inline]
+    pub fn with_global(mut self, global: GlobalEntry) -> Self {
+        self.globals.push(global);
+        self
+    }
+
     #[inline]
     pub fn with_location(mut self, location: impl Into<PathBuf>) -> Self {
         self.location = Some(location.into());
@@ -81,14 +435,66 @@ impl Configuration {
         self.rules.push(rule.into());
     }
 
+    /// Removes every rule and text processor that is not a formatting rule, and disables
+    /// bundling.
+    /// Returns a text that changes when the configuration changes, used to find out if the
+    /// configuration used to process files is different.
+    #[cfg(feature = "serde-config")]
+    pub(crate) fn fingerprint(&self) -> String {
+        json5::to_string(self).unwrap_or_default()
+    }
+
+    /// Returns a text that changes when the configuration changes, used to find out if the
+    /// configuration used to process files is different.
+    #[cfg(not(feature = "serde-config"))]
+    pub(crate) fn fingerprint(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    pub(crate) fn retain_formatting_rules(&mut self) {
+        self.rules.retain(|rule| rule.is_formatting_rule());
+        for stage in self.stages.iter_mut() {
+            stage.retain_rules(|rule| rule.is_formatting_rule());
+        }
+        self.preprocessors
+            .retain(|preprocessor| preprocessor.is_formatting_rule());
+        self.postprocessors
+            .retain(|postprocessor| postprocessor.is_formatting_rule());
+        self.bundle = None;
+    }
+
+    /// Returns the rules of the stage at the given index, or the rules of the configuration
+    /// when no stage is given.
     #[inline]
-    pub(crate) fn rules<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = &'a dyn Rule> {
-        self.rules.iter().map(AsRef::as_ref)
+    pub(crate) fn rules<'a, 'b: 'a>(
+        &'b self,
+        stage: Option<usize>,
+    ) -> impl Iterator<Item = &'a dyn Rule> {
+        self.rule_list(stage).iter().map(AsRef::as_ref)
+    }
+
+    fn rule_list(&self, stage: Option<usize>) -> &[Box<dyn Rule>] {
+        match stage {
+            Some(index) => self.stages[index].rules(),
+            None => &self.rules,
+        }
     }
 
     #[inline]
     pub(crate) fn build_parser(&self) -> Parser {
-        self.generator.build_parser()
+        let mut parser = self.generator.build_parser();
+
+        if self
+            .source_target
+            .is_some_and(|target| target.allows_utf8_identifiers())
+        {
+            parser = parser.allow_utf8_identifiers();
+        }
+
+        match self.raw_markers.as_ref() {
+            Some(markers) => parser.with_raw_markers(markers.clone()),
+            None => parser,
+        }
     }
 
     #[inline]
@@ -96,14 +502,40 @@ impl Configuration {
         self.generator.generate_lua(block, code)
     }
 
+    pub(crate) fn generate_segmented_lua(&self, block: &Block) -> Option<SegmentedLua> {
+        self.generator.generate_segmented_lua(block)
+    }
+
+    pub(crate) fn regenerate_segmented_lua(
+        &self,
+        previous: &SegmentedLua,
+        previous_block: &Block,
+        block: &Block,
+    ) -> Option<(SegmentedLua, usize)> {
+        self.generator
+            .regenerate_segmented_lua(previous, previous_block, block)
+    }
+
+    /// Returns `true` if files can be processed incrementally: only the top-level statements
+    /// that changed since the last time are processed and generated again.
+    pub(crate) fn supports_incremental_work(&self) -> bool {
+        self.bundle.is_none()
+            && !self.data_fast_path
+            && self.generator.supports_segments()
+            && self.stages.is_empty()
+            && self.rules.iter().all(|rule| rule.is_statement_local())
+    }
+
     pub(crate) fn bundle(&self) -> Option<Bundler> {
         if let Some(bundle_config) = self.bundle.as_ref() {
-            let bundler = Bundler::new(
+            let mut bundler = Bundler::new(
                 self.build_parser(),
                 bundle_config.require_mode().clone(),
                 bundle_config.excludes(),
-            )
-            .with_modules_identifier(bundle_config.modules_identifier());
+            );
+            if let Some(modules_identifier) = bundle_config.modules_identifier() {
+                bundler = bundler.with_modules_identifier(modules_identifier);
+            }
             Some(bundler)
         } else {
             None
@@ -111,8 +543,96 @@ impl Configuration {
     }
 
     #[inline]
-    pub(crate) fn rules_len(&self) -> usize {
-        self.rules.len()
+    pub(crate) fn rules_len(&self, stage: Option<usize>) -> usize {
+        self.rule_list(stage).len()
+    }
+
+    #[inline]
+    pub(crate) fn data_fast_path(&self) -> bool {
+        self.data_fast_path
+    }
+
+    #[inline]
+    pub(crate) fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Returns the name of each rule configured with unsafe properties, with these
+    /// properties.
+    pub(crate) fn unsafe_rules(&self) -> Vec<(&'static str, Vec<&'static str>)> {
+        self.rules
+            .iter()
+            .chain(self.stages.iter().flat_map(|stage| stage.rules()))
+            .filter_map(|rule| {
+                let properties = rule.unsafe_properties();
+                if properties.is_empty() {
+                    None
+                } else {
+                    Some((rule.get_name(), properties))
+                }
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub(crate) fn preprocessors<'a, 'b: 'a>(
+        &'b self,
+    ) -> impl Iterator<Item = &'a dyn TextPreprocessor> {
+        self.preprocessors.iter().map(AsRef::as_ref)
+    }
+
+    #[inline]
+    pub(crate) fn postprocessors<'a, 'b: 'a>(
+        &'b self,
+    ) -> impl Iterator<Item = &'a dyn TextPostprocessor> {
+        self.postprocessors.iter().map(AsRef::as_ref)
+    }
+
+    /// Builds the environment from the target and the additional globals. Without a target
+    /// and globals, the environment is empty.
+    pub(crate) fn build_environment(&self) -> Environment {
+        let mut environment = self.target.map(Environment::new).unwrap_or_default();
+
+        for global in self.globals.iter() {
+            environment.insert_global(global.clone());
+        }
+
+        environment
+    }
+
+    #[inline]
+    pub(crate) fn target(&self) -> Option<EnvironmentTarget> {
+        self.target
+    }
+
+    #[inline]
+    pub(crate) fn generated_name_prefix(&self) -> Option<&str> {
+        self.generated_name_prefix.as_deref()
+    }
+
+    #[inline]
+    pub(crate) fn artifacts(&self) -> &BTreeMap<String, ArtifactTemplate> {
+        &self.artifacts
+    }
+
+    #[inline]
+    pub(crate) fn checks(&self) -> &[OutputCheck] {
+        &self.checks
+    }
+
+    #[inline]
+    pub(crate) fn stages(&self) -> &[StageConfiguration] {
+        &self.stages
+    }
+
+    #[inline]
+    pub(crate) fn cache_directory(&self) -> Option<&Path> {
+        self.cache_directory.as_deref()
+    }
+
+    #[inline]
+    pub(crate) fn rule_timeout(&self) -> Option<Duration> {
+        self.rule_timeout
     }
 
     #[inline]
@@ -124,28 +644,103 @@ impl Configuration {
 impl Default for Configuration {
     fn default() -> Self {
         Self {
+            schema_version: None,
             rules: get_default_rules(),
             generator: Default::default(),
             bundle: None,
+            data_fast_path: false,
+            strict: false,
+            preprocessors: Vec::new(),
+            postprocessors: Vec::new(),
+            target: None,
+            source_target: None,
+            globals: Vec::new(),
+            generated_name_prefix: None,
+            raw_markers: None,
+            artifacts: BTreeMap::new(),
+            checks: Vec::new(),
+            stages: Vec::new(),
+            cache_directory: None,
+            rule_timeout: None,
             location: None,
         }
     }
 }
 
+#[cfg(feature = "serde-config")]
+fn format_rule(rule: &(dyn Rule + 'static)) -> String {
+    json5::to_string(&rule)
+        .ok()
+        .unwrap_or_else(|| rule.get_name().to_owned())
+}
+
+#[cfg(not(feature = "serde-config"))]
+fn format_rule(rule: &(dyn Rule + 'static)) -> String {
+    let properties: BTreeMap<_, _> = rule.serialize_to_properties().into_iter().collect();
+
+    if properties.is_empty() {
+        rule.get_name().to_owned()
+    } else {
+        format!("{} {:?}", rule.get_name(), properties)
+    }
+}
+
 impl std::fmt::Debug for Configuration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Config")
             .field("generator", &self.generator)
+            .field("data_fast_path", &self.data_fast_path)
+            .field("strict", &self.strict)
+            .field("target", &self.target)
+            .field("source_target", &self.source_target)
+            .field("generated_name_prefix", &self.generated_name_prefix)
+            .field("raw_markers", &self.raw_markers)
+            .field("artifacts", &self.artifacts)
+            .field("checks", &self.checks)
+            .field(
+                "stages",
+                &self
+                    .stages
+                    .iter()
+                    .map(StageConfiguration::name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+            .field("cache_directory", &self.cache_directory)
+            .field("rule_timeout", &self.rule_timeout)
+            .field(
+                "globals",
+                &self
+                    .globals
+                    .iter()
+                    .map(GlobalEntry::get_name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+            .field(
+                "preprocessors",
+                &self
+                    .preprocessors
+                    .iter()
+                    .map(|preprocessor| preprocessor.get_name())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+            .field(
+                "postprocessors",
+                &self
+                    .postprocessors
+                    .iter()
+                    .map(|postprocessor| postprocessor.get_name())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
             .field(
                 "rules",
                 &self
                     .rules
                     .iter()
-                    .map(|rule| {
-                        json5::to_string(rule)
-                            .ok()
-                            .unwrap_or_else(|| rule.get_name().to_owned())
-                    })
+                    .map(|rule| format_rule(rule.as_ref()))
                     .collect::<Vec<_>>()
                     .join(", "),
             )
@@ -153,18 +748,36 @@ impl std::fmt::Debug for Configuration {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "snake_case", tag = "name")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde-config",
+    serde(deny_unknown_fields, rename_all = "snake_case", tag = "name")
+)]
 pub enum GeneratorParameters {
-    #[serde(alias = "retain-lines")]
+    #[cfg_attr(feature = "serde-config", serde(alias = "retain-lines"))]
     RetainLines,
     Dense {
-        #[serde(default = "get_default_column_span")]
+        #[cfg_attr(feature = "serde-config", serde(default = "get_default_column_span"))]
         column_span: usize,
     },
     Readable {
-        #[serde(default = "get_default_column_span")]
+        #[cfg_attr(feature = "serde-config", serde(default = "get_default_column_span"))]
         column_span: usize,
+        /// Keeps the blank lines between statements and the comments around them.
+        #[cfg_attr(
+            feature = "serde-config",
+            serde(default, skip_serializing_if = "std::ops::Not::not")
+        )]
+        preserve_layout: bool,
+        #[cfg_attr(
+            feature = "serde-config",
+            serde(
+                default = "get_default_max_consecutive_blank_lines",
+                skip_serializing_if = "is_default_max_consecutive_blank_lines"
+            )
+        )]
+        max_consecutive_blank_lines: usize,
     },
 }
 
@@ -184,6 +797,8 @@ impl GeneratorParameters {
     pub fn default_readable() -> Self {
         Self::Readable {
             column_span: DEFAULT_COLUMN_SPAN,
+            preserve_layout: false,
+            max_consecutive_blank_lines: DEFAULT_MAX_CONSECUTIVE_BLANK_LINES,
         }
     }
 
@@ -199,17 +814,112 @@ impl GeneratorParameters {
                 generator.write_block(block);
                 generator.into_string()
             }
-            Self::Readable { column_span } => {
+            Self::Readable {
+                column_span,
+                preserve_layout,
+                max_consecutive_blank_lines,
+            } => {
                 let mut generator = ReadableLuaGenerator::new(*column_span);
+                if *preserve_layout {
+                    generator = generator
+                        .with_preserved_layout(code)
+                        .with_max_consecutive_blank_lines(*max_consecutive_blank_lines);
+                }
                 generator.write_block(block);
                 generator.into_string()
             }
         }
     }
 
+    /// Returns `true` if the generated code can be split into segments generated
+    /// separately. The generators that read the original code cannot.
+    fn supports_segments(&self) -> bool {
+        match self {
+            Self::RetainLines => false,
+            Self::Dense { .. } => true,
+            Self::Readable {
+                preserve_layout, ..
+            } => !preserve_layout,
+        }
+    }
+
+    fn generate_segmented_lua(&self, block: &Block) -> Option<SegmentedLua> {
+        if !self.supports_segments() {
+            return None;
+        }
+
+        match self {
+            Self::RetainLines => None,
+            Self::Dense { column_span } => Some(SegmentedLua::Dense(generate_segments(
+                DenseLuaGenerator::new(*column_span),
+                block,
+            ))),
+            Self::Readable { column_span, .. } => Some(SegmentedLua::Readable(generate_segments(
+                ReadableLuaGenerator::new(*column_span),
+                block,
+            ))),
+        }
+    }
+
+    fn regenerate_segmented_lua(
+        &self,
+        previous: &SegmentedLua,
+        previous_block: &Block,
+        block: &Block,
+    ) -> Option<(SegmentedLua, usize)> {
+        let reused = match_segments(previous_block, block);
+
+        match (self, previous) {
+            (Self::Dense { column_span }, SegmentedLua::Dense(previous)) => {
+                let (code, regenerated) = regenerate_segments(
+                    || DenseLuaGenerator::new(*column_span),
+                    previous,
+                    &reused,
+                    block,
+                );
+                Some((SegmentedLua::Dense(code), regenerated))
+            }
+            (Self::Readable { column_span, .. }, SegmentedLua::Readable(previous)) => {
+                let (code, regenerated) = regenerate_segments(
+                    || ReadableLuaGenerator::new(*column_span),
+                    previous,
+                    &reused,
+                    block,
+                );
+                Some((SegmentedLua::Readable(code), regenerated))
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn generate_expression(&self, expression: &Expression) -> String {
+        match self {
+            Self::RetainLines => {
+                // the expression tokens are expected to be owned, so no original code is needed
+                let mut generator = TokenBasedLuaGenerator::new("");
+                generator.write_expression(expression);
+                generator.into_string()
+            }
+            Self::Dense { column_span } => {
+                let mut generator = DenseLuaGenerator::new(*column_span);
+                generator.write_expression(expression);
+                generator.into_string()
+            }
+            Self::Readable { column_span, .. } => {
+                let mut generator = ReadableLuaGenerator::new(*column_span);
+                generator.write_expression(expression);
+                generator.into_string()
+            }
+        }
+    }
+
     fn build_parser(&self) -> Parser {
         match self {
             Self::RetainLines => Parser::default().preserve_tokens(),
+            Self::Readable {
+                preserve_layout: true,
+                ..
+            } => Parser::default().preserve_tokens(),
             Self::Dense { .. } | Self::Readable { .. } => Parser::default(),
         }
     }
@@ -225,23 +935,34 @@ impl FromStr for GeneratorParameters {
             "dense" => Self::Dense {
                 column_span: DEFAULT_COLUMN_SPAN,
             },
-            "readable" => Self::Readable {
-                column_span: DEFAULT_COLUMN_SPAN,
-            },
+            "readable" => Self::default_readable(),
             _ => return Err(format!("invalid generator name `{}`", s)),
         })
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "snake_case")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde-config",
+    serde(deny_unknown_fields, rename_all = "snake_case")
+)]
 pub struct BundleConfiguration {
-    #[serde(deserialize_with = "crate::utils::string_or_struct")]
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(deserialize_with = "crate::utils::string_or_struct")
+    )]
     require_mode: BundleRequireMode,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(skip_serializing_if = "Option::is_none")
+    )]
     modules_identifier: Option<String>,
-    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
-    excludes: HashSet<String>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "BTreeSet::is_empty")
+    )]
+    excludes: BTreeSet<String>,
 }
 
 impl BundleConfiguration {
@@ -267,11 +988,8 @@ impl BundleConfiguration {
         &self.require_mode
     }
 
-    pub(crate) fn modules_identifier(&self) -> &str {
-        self.modules_identifier
-            .as_ref()
-            .map(AsRef::as_ref)
-            .unwrap_or("__DARKLUA_BUNDLE_MODULES")
+    pub(crate) fn modules_identifier(&self) -> Option<&str> {
+        self.modules_identifier.as_deref()
     }
 
     pub(crate) fn excludes(&self) -> impl Iterator<Item = &str> {
@@ -332,9 +1050,7 @@ mod test {
 
             pretty_assertions::assert_eq!(
                 config.generator,
-                GeneratorParameters::Readable {
-                    column_span: DEFAULT_COLUMN_SPAN
-                }
+                GeneratorParameters::default_readable()
             );
         }
 
@@ -345,7 +1061,36 @@ mod test {
 
             pretty_assertions::assert_eq!(
                 config.generator,
-                GeneratorParameters::Readable { column_span: 110 }
+                GeneratorParameters::Readable {
+                    column_span: 110,
+                    preserve_layout: false,
+                    max_consecutive_blank_lines: DEFAULT_MAX_CONSECUTIVE_BLANK_LINES,
+                }
+            );
+        }
+
+        #[test]
+        fn deserialize_readable_params_with_preserved_layout() {
+            let config: Configuration = json5::from_str(
+                "{ generator: { name: 'readable', preserve_layout: true, max_consecutive_blank_lines: 2 }}",
+            )
+            .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.generator,
+                GeneratorParameters::Readable {
+                    column_span: DEFAULT_COLUMN_SPAN,
+                    preserve_layout: true,
+                    max_consecutive_blank_lines: 2,
+                }
+            );
+        }
+
+        #[test]
+        fn serialize_default_readable_params_omits_layout() {
+            pretty_assertions::assert_eq!(
+                json5::to_string(&GeneratorParameters::default_readable()).unwrap(),
+                "{\"name\":\"readable\",\"column_span\":80}"
             );
         }
 
@@ -374,9 +1119,7 @@ mod test {
 
             pretty_assertions::assert_eq!(
                 config.generator,
-                GeneratorParameters::Readable {
-                    column_span: DEFAULT_COLUMN_SPAN
-                }
+                GeneratorParameters::default_readable()
             );
         }
 
@@ -391,6 +1134,173 @@ mod test {
         }
     }
 
+    mod data_fast_path {
+        use super::*;
+
+        #[test]
+        fn deserialize_default_is_disabled() {
+            let config: Configuration = json5::from_str("{}").unwrap();
+
+            assert!(!config.data_fast_path());
+        }
+
+        #[test]
+        fn deserialize_enabled() {
+            let config: Configuration = json5::from_str("{ data_fast_path: true }").unwrap();
+
+            assert!(config.data_fast_path());
+        }
+    }
+
+    mod strict {
+        use super::*;
+
+        #[test]
+        fn deserialize_default_is_disabled() {
+            let config: Configuration = json5::from_str("{}").unwrap();
+
+            assert!(!config.is_strict());
+        }
+
+        #[test]
+        fn deserialize_enabled() {
+            let config: Configuration = json5::from_str("{ strict: true }").unwrap();
+
+            assert!(config.is_strict());
+        }
+
+        #[test]
+        fn default_rules_are_not_unsafe() {
+            assert!(Configuration::default().unsafe_rules().is_empty());
+        }
+
+        #[test]
+        fn unsafe_rules_lists_rules_with_unsafe_properties() {
+            let config: Configuration = json5::from_str(
+                "{ rules: ['compute_expression', { rule: 'hoist_loop_invariants', assume_no_metatables: true }] }",
+            )
+            .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config.unsafe_rules(),
+                vec![("hoist_loop_invariants", vec!["assume_no_metatables"])]
+            );
+        }
+    }
+
+    mod generated_name_prefix {
+        use super::*;
+
+        #[test]
+        fn deserialize_default_has_no_prefix() {
+            let config: Configuration = json5::from_str("{}").unwrap();
+
+            pretty_assertions::assert_eq!(config.generated_name_prefix(), None);
+        }
+
+        #[test]
+        fn deserialize_prefix_with_hash() {
+            let config: Configuration =
+                json5::from_str("{ generated_name_prefix: '__dk_{hash}_' }").unwrap();
+
+            pretty_assertions::assert_eq!(config.generated_name_prefix(), Some("__dk_{hash}_"));
+        }
+
+        #[test]
+        fn deserialize_invalid_prefix() {
+            let result: Result<Configuration, _> =
+                json5::from_str("{ generated_name_prefix: 'dk.' }");
+
+            pretty_assertions::assert_eq!(
+                result.expect_err("deserialization should fail").to_string(),
+                "invalid generated name prefix `dk.`: unexpected character `.` (only letters, \
+                digits, underscores and the `{hash}` placeholder are allowed)"
+            );
+        }
+    }
+
+    mod text_processors {
+        use super::*;
+
+        #[test]
+        fn deserialize_default_has_no_processors() {
+            let config: Configuration = json5::from_str("{}").unwrap();
+
+            assert_eq!(config.preprocessors().count(), 0);
+            assert_eq!(config.postprocessors().count(), 0);
+        }
+
+        #[test]
+        fn deserialize_preprocessor_name() {
+            let config: Configuration =
+                json5::from_str("{ preprocessors: ['strip_line_directives'] }").unwrap();
+
+            pretty_assertions::assert_eq!(
+                config
+                    .preprocessors()
+                    .map(|preprocessor| preprocessor.get_name())
+                    .collect::<Vec<_>>(),
+                vec!["strip_line_directives"]
+            );
+        }
+
+        #[test]
+        fn deserialize_preprocessor_object() {
+            let config: Configuration = json5::from_str(
+                "{ preprocessors: [{ name: 'strip_line_directives', directives: ['if', 'end'] }] }",
+            )
+            .unwrap();
+
+            pretty_assertions::assert_eq!(
+                config
+                    .preprocessors()
+                    .map(|preprocessor| preprocessor.get_name())
+                    .collect::<Vec<_>>(),
+                vec!["strip_line_directives"]
+            );
+        }
+
+        #[test]
+        fn deserialize_postprocessor_name() {
+            let config: Configuration =
+                json5::from_str("{ postprocessors: ['checksum_trailer'] }").unwrap();
+
+            pretty_assertions::assert_eq!(
+                config
+                    .postprocessors()
+                    .map(|postprocessor| postprocessor.get_name())
+                    .collect::<Vec<_>>(),
+                vec!["checksum_trailer"]
+            );
+        }
+
+        #[test]
+        fn deserialize_unknown_preprocessor_error() {
+            let result = json5::from_str::<Configuration>("{ preprocessors: ['unknown'] }");
+
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn serialize_processors() {
+            let config = Configuration::empty()
+                .with_preprocessor(Box::<crate::rules::StripLineDirectives>::default()
+                    as Box<dyn TextPreprocessor>)
+                .with_postprocessor(
+                    Box::<crate::rules::ChecksumTrailer>::default() as Box<dyn TextPostprocessor>
+                );
+
+            pretty_assertions::assert_eq!(
+                json5::to_string(&config).unwrap(),
+                concat!(
+                    r#"{"rules":[],"generator":{"name":"retain_lines"},"#,
+                    r#""preprocessors":["strip_line_directives"],"#,
+                    r#""postprocessors":["checksum_trailer"]}"#
+                )
+            );
+        }
+    }
+
     mod bundle_configuration {
         use crate::rules::require::PathRequireMode;
 
@@ -0,0 +1,945 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::frontend::configuration_schema::ConfigurationSchema;
+use crate::frontend::{Configuration, DarkluaError, DarkluaResult, Resources};
+use crate::rules::get_default_rules;
+use crate::utils::log;
+use crate::utils::normalize_path;
+
+/// The maximum number of configuration files that can be extended one after the other.
+pub(crate) const MAX_EXTENDS_DEPTH: usize = 16;
+
+const EXTENDS_FIELD: &str = "extends";
+const RULES_MODE_FIELD: &str = "rules_mode";
+
+/// The checks that can only be used once: a check from an extending file replaces the
+/// check of the same kind instead of being added next to it.
+const UNIQUE_CHECKS: [&str; 3] = ["forbid_comments", "max_line_length", "require_parse"];
+
+/// How the rules of a configuration file are combined with the rules of the configuration
+/// files it extends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum RulesMode {
+    #[default]
+    Replace,
+    Prepend,
+    Append,
+}
+
+impl RulesMode {
+    fn parse(value: &Value) -> Option<Self> {
+        match value.as_str()? {
+            "replace" => Some(Self::Replace),
+            "prepend" => Some(Self::Prepend),
+            "append" => Some(Self::Append),
+            _ => None,
+        }
+    }
+}
+
+fn format_chain<'a>(chain: impl Iterator<Item = &'a PathBuf>) -> String {
+    chain
+        .map(|path| format!("`{}`", path.display()))
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// Reads configuration files and the configuration files they extend.
+struct ConfigurationLoader<'a> {
+    resources: &'a Resources,
+    chain: Vec<PathBuf>,
+}
+
+impl<'a> ConfigurationLoader<'a> {
+    fn new(resources: &'a Resources) -> Self {
+        Self {
+            resources,
+            chain: Vec::new(),
+        }
+    }
+
+    fn load(&mut self, path: &Path) -> DarkluaResult<Value> {
+        let normalized_path = normalize_path(path);
+
+        if self.chain.contains(&normalized_path) {
+            return Err(
+                DarkluaError::invalid_configuration_file(path).context(format!(
+                    "configuration files extend each other in a cycle: {}",
+                    format_chain(self.chain.iter().chain(std::iter::once(&normalized_path)))
+                )),
+            );
+        }
+
+        if self.chain.len() >= MAX_EXTENDS_DEPTH {
+            return Err(
+                DarkluaError::invalid_configuration_file(path).context(format!(
+                    "configuration files cannot be extended more than {} levels deep: {}",
+                    MAX_EXTENDS_DEPTH,
+                    format_chain(self.chain.iter().chain(std::iter::once(&normalized_path)))
+                )),
+            );
+        }
+
+        let content = self.resources.get(path)?;
+        let mut value = parse_configuration_value(path, &content)?;
+
+        let extends = take_extends(path, &mut value)?;
+        let rules_mode = take_rules_mode(path, &mut value)?;
+
+        self.chain.push(normalized_path);
+
+        let parent = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut resolved = Value::Object(Map::new());
+
+        for extended in extends {
+            let extended_path = parent.join(extended);
+
+            if !self.resources.exists(&extended_path)? {
+                return Err(
+                    DarkluaError::resource_not_found(&extended_path).context(format!(
+                        "expected to find configuration file extended by `{}`",
+                        path.display()
+                    )),
+                );
+            }
+
+            let extended_value = self.load(&extended_path)?;
+            resolved = merge_configurations(resolved, extended_value, RulesMode::Replace);
+        }
+
+        self.chain.pop();
+
+        Ok(merge_configurations(resolved, value, rules_mode))
+    }
+}
+
+fn parse_configuration_value(path: &Path, content: &str) -> DarkluaResult<Value> {
+    let mut value: Value = json5::from_str(content)
+        .map_err(|err| DarkluaError::invalid_configuration_file(path).context(err.to_string()))?;
+
+    if let Value::Object(object) = &mut value {
+        if !object.contains_key("rules") {
+            if let Some(rules) = object.remove("process") {
+                object.insert("rules".to_owned(), rules);
+            }
+        }
+    }
+
+    Ok(value)
+}
+
+fn take_extends(path: &Path, value: &mut Value) -> DarkluaResult<Vec<String>> {
+    let extends = match value
+        .as_object_mut()
+        .and_then(|object| object.remove(EXTENDS_FIELD))
+    {
+        Some(extends) => extends,
+        None => return Ok(Vec::new()),
+    };
+
+    let invalid = || {
+        DarkluaError::invalid_configuration_file(path)
+            .context("`extends` must be a path or a list of paths")
+    };
+
+    match extends {
+        Value::String(extended) => Ok(vec![extended]),
+        Value::Array(list) => list
+            .into_iter()
+            .map(|extended| match extended {
+                Value::String(extended) => Ok(extended),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        _ => Err(invalid()),
+    }
+}
+
+fn take_rules_mode(path: &Path, value: &mut Value) -> DarkluaResult<RulesMode> {
+    match value
+        .as_object_mut()
+        .and_then(|object| object.remove(RULES_MODE_FIELD))
+    {
+        Some(mode) => RulesMode::parse(&mode).ok_or_else(|| {
+            DarkluaError::invalid_configuration_file(path).context(format!(
+                "unexpected value for `{}`, expected `replace`, `prepend` or `append`",
+                RULES_MODE_FIELD
+            ))
+        }),
+        None => Ok(RulesMode::default()),
+    }
+}
+
+/// Merges a configuration on top of the configuration it extends. Values from the `overlay`
+/// take precedence over values from the `base`.
+pub(crate) fn merge_configurations(base: Value, overlay: Value, rules_mode: RulesMode) -> Value {
+    let (mut base, overlay) = match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => (base, overlay),
+        (_, overlay) => return overlay,
+    };
+
+    for (key, value) in overlay {
+        let merged = match (key.as_str(), base.remove(&key)) {
+            ("rules", previous) => merge_rules(previous, value, rules_mode),
+            ("checks", Some(Value::Array(previous))) => merge_checks(previous, value),
+            ("stages", Some(Value::Array(previous))) => merge_stages(previous, value),
+            (_, Some(previous)) => merge_values(previous, value),
+            (_, None) => value,
+        };
+        base.insert(key, merged);
+    }
+
+    Value::Object(base)
+}
+
+fn merge_rules(previous: Option<Value>, rules: Value, rules_mode: RulesMode) -> Value {
+    if rules_mode == RulesMode::Replace {
+        return rules;
+    }
+
+    let previous = match previous {
+        Some(Value::Array(previous)) => previous,
+        // the extended configurations use the default rules
+        _ => match serde_json::to_value(get_default_rules()) {
+            Ok(Value::Array(default_rules)) => default_rules,
+            _ => Vec::new(),
+        },
+    };
+
+    let rules = match rules {
+        Value::Array(rules) => rules,
+        rules => return rules,
+    };
+
+    match rules_mode {
+        RulesMode::Prepend => rules.into_iter().chain(previous).collect(),
+        RulesMode::Append | RulesMode::Replace => previous.into_iter().chain(rules).collect(),
+    }
+}
+
+/// Returns the key that identifies a check, so that a check of an extending file replaces
+/// the same check from an extended file.
+fn check_key(check: &Value) -> String {
+    match check {
+        Value::String(name) => name.to_owned(),
+        Value::Object(object) if object.len() == 1 => {
+            let (name, _) = object.iter().next().expect("object should have one entry");
+            if UNIQUE_CHECKS.contains(&name.as_str()) {
+                name.to_owned()
+            } else {
+                check.to_string()
+            }
+        }
+        _ => check.to_string(),
+    }
+}
+
+fn merge_checks(previous: Vec<Value>, checks: Value) -> Value {
+    merge_list_by_key(previous, checks, |check| Some(check_key(check)))
+}
+
+fn merge_stages(previous: Vec<Value>, stages: Value) -> Value {
+    merge_list_by_key(previous, stages, |stage| {
+        stage
+            .get("name")
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned)
+    })
+}
+
+fn merge_list_by_key(
+    mut previous: Vec<Value>,
+    list: Value,
+    get_key: impl Fn(&Value) -> Option<String>,
+) -> Value {
+    let list = match list {
+        Value::Array(list) => list,
+        list => return list,
+    };
+
+    for element in list {
+        let key = get_key(&element);
+        let existing = key.as_ref().and_then(|key| {
+            previous
+                .iter()
+                .position(|previous| get_key(previous).as_ref() == Some(key))
+        });
+
+        match existing {
+            Some(index) => previous[index] = element,
+            None => previous.push(element),
+        }
+    }
+
+    Value::Array(previous)
+}
+
+/// Turns a name (like `"dense"`) into the object form of a named value (like
+/// `{ "name": "dense" }`) when the other value uses the object form.
+fn expand_name(value: Value, other: &Value) -> Value {
+    match value {
+        Value::String(name) if other.get("name").is_some() => {
+            let mut object = Map::new();
+            object.insert("name".to_owned(), Value::String(name));
+            Value::Object(object)
+        }
+        value => value,
+    }
+}
+
+/// Returns true when both values are named (like generators) but their names are different.
+fn is_other_name(previous: &Value, value: &Value) -> bool {
+    let get_name = |value: &Value| match value {
+        Value::String(name) => Some(name.to_owned()),
+        Value::Object(object) => object
+            .get("name")
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned),
+        _ => None,
+    };
+
+    match (previous, value) {
+        (Value::Object(_), _) | (_, Value::Object(_)) => {
+            matches!((get_name(previous), get_name(value)), (Some(a), Some(b)) if a != b)
+        }
+        _ => false,
+    }
+}
+
+fn merge_values(previous: Value, value: Value) -> Value {
+    if is_other_name(&previous, &value) {
+        return value;
+    }
+
+    let previous = expand_name(previous, &value);
+    let value = expand_name(value, &previous);
+
+    match (previous, value) {
+        (Value::Object(mut previous), Value::Object(object)) => {
+            for (key, value) in object {
+                let merged = match previous.remove(&key) {
+                    Some(previous_value) => merge_values(previous_value, value),
+                    None => value,
+                };
+                previous.insert(key, merged);
+            }
+
+            Value::Object(previous)
+        }
+        (_, value) => value,
+    }
+}
+
+/// Reads a configuration file and merges it with the configuration files it extends. The
+/// merged configuration is validated.
+pub(crate) fn resolve_configuration_value(
+    resources: &Resources,
+    path: &Path,
+) -> DarkluaResult<Value> {
+    let value = ConfigurationLoader::new(resources).load(path)?;
+
+    let issues = ConfigurationSchema::new().validate(&value);
+    if !issues.is_empty() {
+        return Err(DarkluaError::invalid_configuration_content(path, issues));
+    }
+
+    if value.get("stages").is_some() && value.get("rules").is_some() {
+        return Err(DarkluaError::invalid_configuration_file(path).context(
+            "the `rules` field cannot be used with `stages` (each stage defines its rules)",
+        ));
+    }
+
+    Ok(value)
+}
+
+pub(crate) fn deserialize_configuration(path: &Path, value: Value) -> DarkluaResult<Configuration> {
+    let configuration: Configuration = serde_json::from_value(value)
+        .map_err(|err| DarkluaError::invalid_configuration_file(path).context(err.to_string()))?;
+
+    Ok(configuration.with_location({
+        path.parent().unwrap_or_else(|| {
+            log::warn!(
+                "unexpected configuration path `{}` (unable to extract parent path)",
+                path.display()
+            );
+            path
+        })
+    }))
+}
+
+/// Reads a configuration file with the configuration files it extends, and returns the
+/// configuration obtained by merging them.
+pub fn read_resolved_configuration(
+    resources: &Resources,
+    path: impl AsRef<Path>,
+) -> DarkluaResult<Configuration> {
+    let path = path.as_ref();
+    let value = resolve_configuration_value(resources, path)?;
+    deserialize_configuration(path, value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use serde_json::json;
+
+    fn merge(base: Value, overlay: Value, rules_mode: RulesMode) -> Value {
+        merge_configurations(base, overlay, rules_mode)
+    }
+
+    fn load(files: &[(&str, &str)], path: &str) -> DarkluaResult<Value> {
+        let resources = Resources::from_memory();
+        for (file, content) in files {
+            resources.write(file, content).unwrap();
+        }
+        ConfigurationLoader::new(&resources).load(Path::new(path))
+    }
+
+    fn default_rules() -> Vec<Value> {
+        match serde_json::to_value(get_default_rules()).unwrap() {
+            Value::Array(rules) => rules,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn rules_are_replaced_by_default() {
+        assert_eq!(
+            merge(
+                json!({ "rules": ["remove_comments", "remove_spaces"] }),
+                json!({ "rules": ["remove_types"] }),
+                RulesMode::default(),
+            ),
+            json!({ "rules": ["remove_types"] })
+        );
+    }
+
+    #[test]
+    fn rules_are_prepended() {
+        assert_eq!(
+            merge(
+                json!({ "rules": ["remove_comments", "remove_spaces"] }),
+                json!({ "rules": ["remove_types"] }),
+                RulesMode::Prepend,
+            ),
+            json!({ "rules": ["remove_types", "remove_comments", "remove_spaces"] })
+        );
+    }
+
+    #[test]
+    fn rules_are_appended() {
+        assert_eq!(
+            merge(
+                json!({ "rules": ["remove_comments", "remove_spaces"] }),
+                json!({ "rules": ["remove_types"] }),
+                RulesMode::Append,
+            ),
+            json!({ "rules": ["remove_comments", "remove_spaces", "remove_types"] })
+        );
+    }
+
+    #[test]
+    fn rules_are_appended_to_default_rules() {
+        let mut expected = default_rules();
+        expected.push(json!("remove_types"));
+
+        assert_eq!(
+            merge(
+                json!({}),
+                json!({ "rules": ["remove_types"] }),
+                RulesMode::Append,
+            ),
+            json!({ "rules": expected })
+        );
+    }
+
+    #[test]
+    fn rules_are_prepended_to_default_rules() {
+        let expected: Vec<_> = std::iter::once(json!("remove_types"))
+            .chain(default_rules())
+            .collect();
+
+        assert_eq!(
+            merge(
+                json!({}),
+                json!({ "rules": ["remove_types"] }),
+                RulesMode::Prepend,
+            ),
+            json!({ "rules": expected })
+        );
+    }
+
+    #[test]
+    fn rules_mode_without_rules_keeps_extended_rules() {
+        assert_eq!(
+            merge(
+                json!({ "rules": ["remove_comments"] }),
+                json!({ "target": "luau" }),
+                RulesMode::Append,
+            ),
+            json!({ "rules": ["remove_comments"], "target": "luau" })
+        );
+    }
+
+    #[test]
+    fn extended_rules_are_kept_when_not_overridden() {
+        assert_eq!(
+            merge(
+                json!({ "rules": ["remove_comments"] }),
+                json!({}),
+                RulesMode::Replace
+            ),
+            json!({ "rules": ["remove_comments"] })
+        );
+    }
+
+    #[test]
+    fn scalar_options_are_overridden() {
+        assert_eq!(
+            merge(
+                json!({ "target": "lua51", "strict": true }),
+                json!({ "target": "roblox" }),
+                RulesMode::Replace,
+            ),
+            json!({ "target": "roblox", "strict": true })
+        );
+    }
+
+    #[test]
+    fn single_generator_option_is_overridden() {
+        assert_eq!(
+            merge(
+                json!({
+                    "generator": {
+                        "name": "readable",
+                        "column_span": 80,
+                        "preserve_layout": true,
+                    },
+                }),
+                json!({ "generator": { "name": "readable", "column_span": 120 } }),
+                RulesMode::Replace,
+            ),
+            json!({
+                "generator": {
+                    "name": "readable",
+                    "column_span": 120,
+                    "preserve_layout": true,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn generator_option_is_added_to_generator_name() {
+        assert_eq!(
+            merge(
+                json!({ "generator": "dense" }),
+                json!({ "generator": { "name": "dense", "column_span": 120 } }),
+                RulesMode::Replace,
+            ),
+            json!({ "generator": { "name": "dense", "column_span": 120 } })
+        );
+    }
+
+    #[test]
+    fn generator_name_keeps_options_of_the_same_generator() {
+        assert_eq!(
+            merge(
+                json!({ "generator": { "name": "dense", "column_span": 120 } }),
+                json!({ "generator": "dense" }),
+                RulesMode::Replace,
+            ),
+            json!({ "generator": { "name": "dense", "column_span": 120 } })
+        );
+    }
+
+    #[test]
+    fn different_generator_replaces_generator() {
+        assert_eq!(
+            merge(
+                json!({ "generator": { "name": "dense", "column_span": 120 } }),
+                json!({ "generator": { "name": "readable", "preserve_layout": true } }),
+                RulesMode::Replace,
+            ),
+            json!({ "generator": { "name": "readable", "preserve_layout": true } })
+        );
+    }
+
+    #[test]
+    fn different_generator_name_replaces_generator() {
+        assert_eq!(
+            merge(
+                json!({ "generator": { "name": "dense", "column_span": 120 } }),
+                json!({ "generator": "retain_lines" }),
+                RulesMode::Replace,
+            ),
+            json!({ "generator": "retain_lines" })
+        );
+    }
+
+    #[test]
+    fn bundle_options_are_merged() {
+        assert_eq!(
+            merge(
+                json!({
+                    "bundle": {
+                        "require_mode": { "name": "path", "sources": { "pkg": "./Packages" } },
+                        "excludes": ["@lune/**"],
+                    },
+                }),
+                json!({
+                    "bundle": {
+                        "require_mode": { "name": "path", "sources": { "shared": "./Shared" } },
+                        "modules_identifier": "__MODULES",
+                    },
+                }),
+                RulesMode::Replace,
+            ),
+            json!({
+                "bundle": {
+                    "require_mode": {
+                        "name": "path",
+                        "sources": { "pkg": "./Packages", "shared": "./Shared" },
+                    },
+                    "excludes": ["@lune/**"],
+                    "modules_identifier": "__MODULES",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn lists_other_than_rules_are_replaced() {
+        assert_eq!(
+            merge(
+                json!({ "globals": [{ "name": "game" }], "preprocessors": ["a"] }),
+                json!({ "globals": [{ "name": "script" }] }),
+                RulesMode::Append,
+            ),
+            json!({ "globals": [{ "name": "script" }], "preprocessors": ["a"] })
+        );
+    }
+
+    #[test]
+    fn artifacts_are_merged_by_name() {
+        assert_eq!(
+            merge(
+                json!({ "artifacts": { "sourcemap": "a.json", "manifest": "manifest.json" } }),
+                json!({ "artifacts": { "sourcemap": "b.json", "report": "report.json" } }),
+                RulesMode::Replace,
+            ),
+            json!({
+                "artifacts": {
+                    "sourcemap": "b.json",
+                    "manifest": "manifest.json",
+                    "report": "report.json",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn checks_are_merged_by_kind() {
+        assert_eq!(
+            merge(
+                json!({
+                    "checks": [
+                        { "max_line_length": 100 },
+                        { "forbid_call": "loadstring" },
+                        "forbid_comments",
+                    ],
+                }),
+                json!({
+                    "checks": [
+                        { "max_line_length": 120 },
+                        { "forbid_call": "getfenv" },
+                        { "forbid_call": "loadstring" },
+                        "forbid_comments",
+                    ],
+                }),
+                RulesMode::Replace,
+            ),
+            json!({
+                "checks": [
+                    { "max_line_length": 120 },
+                    { "forbid_call": "loadstring" },
+                    "forbid_comments",
+                    { "forbid_call": "getfenv" },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn stages_are_merged_by_name() {
+        assert_eq!(
+            merge(
+                json!({
+                    "stages": [
+                        { "name": "build", "rules": ["remove_types"] },
+                        { "name": "minify", "rules": ["remove_spaces"] },
+                    ],
+                }),
+                json!({
+                    "stages": [
+                        { "name": "minify", "rules": ["remove_comments"] },
+                        { "name": "check" },
+                    ],
+                }),
+                RulesMode::Replace,
+            ),
+            json!({
+                "stages": [
+                    { "name": "build", "rules": ["remove_types"] },
+                    { "name": "minify", "rules": ["remove_comments"] },
+                    { "name": "check" },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn load_configuration_without_extends() {
+        assert_eq!(
+            load(&[("config.json", "{ target: 'luau' }")], "config.json").unwrap(),
+            json!({ "target": "luau" })
+        );
+    }
+
+    #[test]
+    fn load_configuration_with_process_alias() {
+        assert_eq!(
+            load(
+                &[
+                    ("base.json", "{ process: ['remove_comments'] }"),
+                    (
+                        "config.json",
+                        "{ extends: 'base.json', rules_mode: 'append', process: ['remove_types'] }"
+                    ),
+                ],
+                "config.json"
+            )
+            .unwrap(),
+            json!({ "rules": ["remove_comments", "remove_types"] })
+        );
+    }
+
+    #[test]
+    fn load_three_level_chain() {
+        let value = load(
+            &[
+                (
+                    "shared/base.json",
+                    r#"{
+                        rules: ["remove_comments"],
+                        target: "lua51",
+                        generator: { name: "readable", column_span: 80, preserve_layout: true },
+                    }"#,
+                ),
+                (
+                    "shared/roblox.json",
+                    r#"{
+                        extends: "base.json",
+                        rules_mode: "append",
+                        rules: ["remove_types"],
+                        target: "roblox",
+                    }"#,
+                ),
+                (
+                    "project/config.json",
+                    r#"{
+                        extends: "../shared/roblox.json",
+                        rules_mode: "prepend",
+                        rules: ["remove_spaces"],
+                        generator: { name: "readable", column_span: 120 },
+                    }"#,
+                ),
+            ],
+            "project/config.json",
+        )
+        .unwrap();
+
+        assert_eq!(
+            value,
+            json!({
+                "rules": ["remove_spaces", "remove_comments", "remove_types"],
+                "target": "roblox",
+                "generator": { "name": "readable", "column_span": 120, "preserve_layout": true },
+            })
+        );
+    }
+
+    #[test]
+    fn load_list_of_extended_files_in_order() {
+        let value = load(
+            &[
+                ("a.json", "{ target: 'lua51', strict: true }"),
+                ("b.json", "{ target: 'luau' }"),
+                ("config.json", "{ extends: ['a.json', 'b.json'] }"),
+            ],
+            "config.json",
+        )
+        .unwrap();
+
+        assert_eq!(value, json!({ "target": "luau", "strict": true }));
+    }
+
+    #[test]
+    fn load_extended_files_depth_first() {
+        let value = load(
+            &[
+                ("a.json", "{ extends: 'a_base.json' }"),
+                (
+                    "a_base.json",
+                    "{ target: 'lua51', generated_name_prefix: 'a' }",
+                ),
+                ("b.json", "{ target: 'luau' }"),
+                ("config.json", "{ extends: ['a.json', 'b.json'] }"),
+            ],
+            "config.json",
+        )
+        .unwrap();
+
+        assert_eq!(
+            value,
+            json!({ "target": "luau", "generated_name_prefix": "a" })
+        );
+    }
+
+    #[test]
+    fn same_file_extended_twice_is_not_a_cycle() {
+        let value = load(
+            &[
+                ("base.json", "{ strict: true }"),
+                ("a.json", "{ extends: 'base.json' }"),
+                ("config.json", "{ extends: ['a.json', 'base.json'] }"),
+            ],
+            "config.json",
+        )
+        .unwrap();
+
+        assert_eq!(value, json!({ "strict": true }));
+    }
+
+    #[test]
+    fn cycle_error_contains_include_chain() {
+        let error = load(
+            &[
+                ("a.json", "{ extends: 'b.json' }"),
+                ("b.json", "{ extends: 'c.json' }"),
+                ("c.json", "{ extends: 'a.json' }"),
+            ],
+            "a.json",
+        )
+        .unwrap_err();
+
+        pretty_assertions::assert_eq!(
+            error.to_string(),
+            "invalid configuration file at `a.json` (configuration files extend each other \
+            in a cycle: `a.json` -> `b.json` -> `c.json` -> `a.json`)"
+        );
+    }
+
+    #[test]
+    fn self_extending_file_is_a_cycle() {
+        let error = load(&[("a.json", "{ extends: './a.json' }")], "a.json").unwrap_err();
+
+        pretty_assertions::assert_eq!(
+            error.to_string(),
+            "invalid configuration file at `./a.json` (configuration files extend each other \
+            in a cycle: `a.json` -> `a.json`)"
+        );
+    }
+
+    #[test]
+    fn missing_extended_file_error_names_including_file() {
+        let error = load(
+            &[("config/project.json", "{ extends: 'missing.json' }")],
+            "config/project.json",
+        )
+        .unwrap_err();
+
+        pretty_assertions::assert_eq!(
+            error.to_string(),
+            "unable to find `config/missing.json` (expected to find configuration file \
+            extended by `config/project.json`)"
+        );
+    }
+
+    #[test]
+    fn depth_limit_error() {
+        let files: Vec<_> = (0..=MAX_EXTENDS_DEPTH)
+            .map(|i| {
+                (
+                    format!("{}.json", i),
+                    format!("{{ extends: '{}.json' }}", i + 1),
+                )
+            })
+            .collect();
+        let files: Vec<_> = files
+            .iter()
+            .map(|(name, content)| (name.as_str(), content.as_str()))
+            .collect();
+
+        let error = load(&files, "0.json").unwrap_err();
+
+        assert!(
+            error
+                .to_string()
+                .contains("configuration files cannot be extended more than 16 levels deep"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn invalid_extends_error() {
+        let error = load(&[("config.json", "{ extends: 1 }")], "config.json").unwrap_err();
+
+        pretty_assertions::assert_eq!(
+            error.to_string(),
+            "invalid configuration file at `config.json` (`extends` must be a path or a list \
+            of paths)"
+        );
+    }
+
+    #[test]
+    fn invalid_rules_mode_error() {
+        let error = load(
+            &[("config.json", "{ rules_mode: 'merge', rules: [] }")],
+            "config.json",
+        )
+        .unwrap_err();
+
+        pretty_assertions::assert_eq!(
+            error.to_string(),
+            "invalid configuration file at `config.json` (unexpected value for `rules_mode`, \
+            expected `replace`, `prepend` or `append`)"
+        );
+    }
+
+    #[test]
+    fn resolved_configuration_is_validated() {
+        let resources = Resources::from_memory();
+        resources
+            .write("base.json", "{ rules: ['remove_comments'] }")
+            .unwrap();
+        resources
+            .write(
+                "config.json",
+                "{ extends: 'base.json', stages: [{ name: 'build' }] }",
+            )
+            .unwrap();
+
+        let error = resolve_configuration_value(&resources, Path::new("config.json")).unwrap_err();
+
+        pretty_assertions::assert_eq!(
+            error.to_string(),
+            "invalid configuration file at `config.json` (the `rules` field cannot be used \
+            with `stages` (each stage defines its rules))"
+        );
+    }
+}
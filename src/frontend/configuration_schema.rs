@@ -0,0 +1,1188 @@
+// the validation of configuration values is only used when reading configuration files
+#![cfg_attr(not(feature = "serde-config"), allow(dead_code))]
+
+use std::fmt;
+
+use serde_json::{json, Map, Value};
+
+use crate::rules::{
+    get_all_postprocessor_names, get_all_preprocessor_names, get_all_rule_names, get_rule_aliases,
+    suggest_property, Rule, RuleConfiguration, RulePropertyKind, TextPostprocessor,
+    TextPreprocessor,
+};
+use crate::utils::log;
+
+/// The version of the configuration file format. It is increased when the format changes in a
+/// way that requires existing configuration files to be updated.
+pub const CONFIGURATION_SCHEMA_VERSION: u64 = 1;
+
+const JSON_SCHEMA_DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+
+const TARGETS: &[&str] = &["lua51", "lua53", "lua54", "luau", "roblox"];
+const LUA_TYPES: &[&str] = &["nil", "boolean", "number", "string", "table", "function"];
+
+/// A problem found in a configuration file, located with a JSON pointer (like
+/// `/rules/3/identifier`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigurationIssue {
+    path: String,
+    message: String,
+}
+
+impl ConfigurationIssue {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ConfigurationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "/: {}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Definition {
+    Rule,
+    GlobalEntry,
+}
+
+impl Definition {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Rule => "rule",
+            Self::GlobalEntry => "global_entry",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ValueSchema {
+    Any,
+    Boolean,
+    Unsigned,
+    Number,
+    String,
+    Choice(Vec<&'static str>),
+    Array(Box<ValueSchema>),
+    Map(Box<ValueSchema>),
+    Object(ObjectSchema),
+    OneOf(Vec<ValueSchema>),
+    /// A name (as a string) or an object where the `tag` field contains the name.
+    Tagged(TaggedSchema),
+    Reference(Definition),
+}
+
+impl ValueSchema {
+    fn array(item: ValueSchema) -> Self {
+        Self::Array(Box::new(item))
+    }
+
+    fn map(value: ValueSchema) -> Self {
+        Self::Map(Box::new(value))
+    }
+}
+
+impl From<RulePropertyKind> for ValueSchema {
+    fn from(kind: RulePropertyKind) -> Self {
+        match kind {
+            RulePropertyKind::Boolean => Self::Boolean,
+            RulePropertyKind::String => Self::String,
+            RulePropertyKind::Choice(values) => Self::Choice(values.to_vec()),
+            RulePropertyKind::Usize => Self::Unsigned,
+            RulePropertyKind::Float => Self::Number,
+            RulePropertyKind::ByteSize => Self::OneOf(vec![Self::Unsigned, Self::String]),
+            RulePropertyKind::Duration => Self::OneOf(vec![Self::Number, Self::String]),
+            RulePropertyKind::StringList => Self::array(Self::String),
+            RulePropertyKind::StringMap => Self::map(Self::String),
+            RulePropertyKind::StringMapList => Self::array(Self::map(Self::String)),
+            RulePropertyKind::ValueMap => Self::map(Self::Any),
+            RulePropertyKind::RequireMode => Self::OneOf(vec![
+                Self::Choice(vec!["path", "roblox"]),
+                Self::Object(ObjectSchema::open()),
+            ]),
+            RulePropertyKind::Any => Self::Any,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Property {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    value: ValueSchema,
+    required: bool,
+    description: Option<&'static str>,
+}
+
+impl Property {
+    fn new(name: &'static str, value: ValueSchema) -> Self {
+        Self {
+            name,
+            aliases: &[],
+            value,
+            required: false,
+            description: None,
+        }
+    }
+
+    fn with_aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    fn with_description(mut self, description: &'static str) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ObjectSchema {
+    properties: Vec<Property>,
+    /// When true, fields that are not described are accepted.
+    open: bool,
+}
+
+impl ObjectSchema {
+    fn open() -> Self {
+        Self {
+            properties: Vec::new(),
+            open: true,
+        }
+    }
+
+    fn with(mut self, property: Property) -> Self {
+        self.properties.push(property);
+        self
+    }
+
+    fn from_configuration<T: RuleConfiguration + ?Sized>(configuration: &T) -> Self {
+        configuration
+            .get_property_names()
+            .iter()
+            .fold(Self::default(), |object, name| {
+                object.with(Property::new(
+                    name,
+                    configuration.get_property_kind(name).into(),
+                ))
+            })
+    }
+
+    fn find(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.matches(name))
+    }
+
+    fn field_names(&self) -> Vec<String> {
+        self.properties
+            .iter()
+            .flat_map(|property| {
+                std::iter::once(property.name).chain(property.aliases.iter().copied())
+            })
+            .map(ToOwned::to_owned)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TaggedSchema {
+    /// What the name refers to, used in error messages (like `rule` or `generator`).
+    kind: &'static str,
+    tag: &'static str,
+    variants: Vec<(&'static str, ObjectSchema)>,
+    /// Names that are still accepted but not suggested nor listed in the JSON Schema.
+    deprecated_variants: Vec<(&'static str, ObjectSchema)>,
+}
+
+impl TaggedSchema {
+    fn find(&self, name: &str) -> Option<&ObjectSchema> {
+        self.variants
+            .iter()
+            .chain(self.deprecated_variants.iter())
+            .find(|(variant, _)| *variant == name)
+            .map(|(_, object)| object)
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.variants
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+}
+
+/// The description of the configuration file format. The same description is used to validate
+/// configuration files and to generate a JSON Schema document.
+#[derive(Debug, Clone)]
+pub(crate) struct ConfigurationSchema {
+    root: ObjectSchema,
+    rule: TaggedSchema,
+    global_entry: ObjectSchema,
+}
+
+impl ConfigurationSchema {
+    pub(crate) fn new() -> Self {
+        Self {
+            root: root_schema(),
+            rule: rule_schema(),
+            global_entry: global_entry_schema(),
+        }
+    }
+
+    /// Returns all the problems found in the given configuration.
+    pub(crate) fn validate(&self, value: &Value) -> Vec<ConfigurationIssue> {
+        let mut validator = Validator {
+            schema: self,
+            issues: Vec::new(),
+        };
+
+        validator.validate_value(&ValueSchema::Object(self.root.clone()), value, "");
+
+        if let Some(version) = value.get("schema_version").and_then(Value::as_u64) {
+            if version > CONFIGURATION_SCHEMA_VERSION {
+                validator.issues.push(ConfigurationIssue::new(
+                    "/schema_version",
+                    format!(
+                        "unsupported schema version {} (this version of darklua reads \
+                         configurations up to version {})",
+                        version, CONFIGURATION_SCHEMA_VERSION
+                    ),
+                ));
+            }
+        }
+
+        validator.issues
+    }
+
+    /// Generates a JSON Schema (draft 7) document describing the configuration file.
+    pub(crate) fn to_json_schema(&self) -> Value {
+        let mut schema = Map::new();
+        schema.insert("$schema".to_owned(), JSON_SCHEMA_DRAFT.into());
+        schema.insert("title".to_owned(), "darklua configuration".into());
+        schema.insert(
+            "description".to_owned(),
+            format!(
+                "Configuration file of darklua (schema version {})",
+                CONFIGURATION_SCHEMA_VERSION
+            )
+            .into(),
+        );
+
+        if let Value::Object(root) = object_to_json(&self.root) {
+            schema.extend(root);
+        }
+
+        let mut definitions = Map::new();
+        definitions.insert(
+            Definition::Rule.name().to_owned(),
+            tagged_to_json(&self.rule),
+        );
+        definitions.insert(
+            Definition::GlobalEntry.name().to_owned(),
+            object_to_json(&self.global_entry),
+        );
+        schema.insert("definitions".to_owned(), Value::Object(definitions));
+
+        Value::Object(schema)
+    }
+
+    fn resolve(&self, definition: Definition) -> ValueSchema {
+        match definition {
+            Definition::Rule => ValueSchema::Tagged(self.rule.clone()),
+            Definition::GlobalEntry => ValueSchema::Object(self.global_entry.clone()),
+        }
+    }
+}
+
+fn root_schema() -> ObjectSchema {
+    ObjectSchema::default()
+        .with(
+            Property::new("schema_version", ValueSchema::Unsigned)
+                .with_description("The version of the configuration format"),
+        )
+        .with(
+            Property::new(
+                "extends",
+                ValueSchema::OneOf(vec![
+                    ValueSchema::String,
+                    ValueSchema::array(ValueSchema::String),
+                ]),
+            )
+            .with_description(
+                "The configuration files (relative to this file) that this configuration builds on",
+            ),
+        )
+        .with(
+            Property::new(
+                "rules_mode",
+                ValueSchema::Choice(vec!["replace", "prepend", "append"]),
+            )
+            .with_description("How the rules are combined with the rules of the extended files"),
+        )
+        .with(
+            Property::new(
+                "rules",
+                ValueSchema::array(ValueSchema::Reference(Definition::Rule)),
+            )
+            .with_aliases(&["process"])
+            .with_description("The rules applied to the code, in order"),
+        )
+        .with(
+            Property::new("generator", ValueSchema::Tagged(generator_schema()))
+                .with_description("How the code is generated"),
+        )
+        .with(
+            Property::new("bundle", ValueSchema::Object(bundle_schema()))
+                .with_description("Bundle the required modules into a single file"),
+        )
+        .with(
+            Property::new("data_fast_path", ValueSchema::Boolean).with_description(
+                "Skip the rules that have no effect on modules that only return data",
+            ),
+        )
+        .with(
+            Property::new("strict", ValueSchema::Boolean).with_description(
+                "Reject the rules configured with properties that can change the behavior of the code",
+            ),
+        )
+        .with(
+            Property::new(
+                "preprocessors",
+                ValueSchema::array(ValueSchema::Tagged(processor_schema(
+                    "preprocessor",
+                    "name",
+                    get_all_preprocessor_names(),
+                    |name| {
+                        name.parse::<Box<dyn TextPreprocessor>>()
+                            .map(|processor| ObjectSchema::from_configuration(processor.as_ref()))
+                    },
+                ))),
+            )
+            .with_description("Transformations of the source text before it is parsed"),
+        )
+        .with(
+            Property::new(
+                "postprocessors",
+                ValueSchema::array(ValueSchema::Tagged(processor_schema(
+                    "postprocessor",
+                    "name",
+                    get_all_postprocessor_names(),
+                    |name| {
+                        name.parse::<Box<dyn TextPostprocessor>>()
+                            .map(|processor| ObjectSchema::from_configuration(processor.as_ref()))
+                    },
+                ))),
+            )
+            .with_description("Transformations of the generated code"),
+        )
+        .with(
+            Property::new("target", ValueSchema::Choice(TARGETS.to_vec()))
+                .with_description("The environment in which the code runs"),
+        )
+        .with(
+            Property::new("source_target", ValueSchema::Choice(TARGETS.to_vec()))
+                .with_description("The environment for which the source code is written"),
+        )
+        .with(
+            Property::new(
+                "globals",
+                ValueSchema::array(ValueSchema::Reference(Definition::GlobalEntry)),
+            )
+            .with_description("Globals that exist on top of the ones of the target"),
+        )
+        .with(
+            Property::new("generated_name_prefix", ValueSchema::String)
+                .with_description("The prefix of the names generated by rules"),
+        )
+        .with(
+            Property::new(
+                "raw_markers",
+                ValueSchema::Object(
+                    ObjectSchema::default()
+                        .with(Property::new("begin", ValueSchema::String))
+                        .with(Property::new("end", ValueSchema::String)),
+                ),
+            )
+            .with_description("The comments that delimit regions of code copied as they are"),
+        )
+        .with(
+            Property::new("artifacts", ValueSchema::map(ValueSchema::String)).with_description(
+                "The paths where the artifacts emitted by rules are written, by artifact name",
+            ),
+        )
+        .with(
+            Property::new("checks", ValueSchema::array(output_check_schema()))
+                .with_description("The checks that the generated code of each file must pass"),
+        )
+        .with(
+            Property::new("stages", ValueSchema::array(ValueSchema::Object(stage_schema())))
+                .with_description(
+                    "Named parts of the rules pipeline that can run in separate invocations",
+                ),
+        )
+        .with(
+            Property::new("cache_directory", ValueSchema::String)
+                .with_description("The directory where the state shared between stages is written"),
+        )
+        .with(
+            Property::new(
+                "rule_timeout",
+                ValueSchema::OneOf(vec![ValueSchema::Number, ValueSchema::String]),
+            )
+            .with_description(
+                "The time budget of each rule applied to a file (like `30s`), after which the rule is skipped for that file",
+            ),
+        )
+}
+
+fn stage_schema() -> ObjectSchema {
+    ObjectSchema::default()
+        .with(Property::new("name", ValueSchema::String).required())
+        .with(
+            Property::new(
+                "rules",
+                ValueSchema::array(ValueSchema::Reference(Definition::Rule)),
+            )
+            .with_description("The rules applied to the code by the stage, in order"),
+        )
+}
+
+fn output_check_schema() -> ValueSchema {
+    let check =
+        |name, value| ValueSchema::Object(ObjectSchema::default().with(Property::new(name, value)));
+
+    ValueSchema::OneOf(vec![
+        ValueSchema::Choice(vec!["forbid_comments"]),
+        check("forbid_call", ValueSchema::String),
+        check("forbid_global", ValueSchema::String),
+        check("forbid_string_matching", ValueSchema::String),
+        check("max_line_length", ValueSchema::Unsigned),
+        check("require_parse", ValueSchema::Boolean),
+    ])
+}
+
+fn generator_schema() -> TaggedSchema {
+    let column_span =
+        || ObjectSchema::default().with(Property::new("column_span", ValueSchema::Unsigned));
+
+    TaggedSchema {
+        kind: "generator",
+        tag: "name",
+        variants: vec![
+            ("retain_lines", ObjectSchema::default()),
+            ("retain-lines", ObjectSchema::default()),
+            ("dense", column_span()),
+            (
+                "readable",
+                column_span()
+                    .with(Property::new("preserve_layout", ValueSchema::Boolean))
+                    .with(Property::new(
+                        "max_consecutive_blank_lines",
+                        ValueSchema::Unsigned,
+                    )),
+            ),
+        ],
+        deprecated_variants: Vec::new(),
+    }
+}
+
+fn bundle_schema() -> ObjectSchema {
+    let path_require_mode = ObjectSchema::default()
+        .with(Property::new("module_folder_name", ValueSchema::String))
+        .with(Property::new(
+            "sources",
+            ValueSchema::map(ValueSchema::String),
+        ));
+
+    ObjectSchema::default()
+        .with(
+            Property::new(
+                "require_mode",
+                ValueSchema::Tagged(TaggedSchema {
+                    kind: "require mode",
+                    tag: "name",
+                    variants: vec![("path", path_require_mode)],
+                    deprecated_variants: Vec::new(),
+                }),
+            )
+            .required(),
+        )
+        .with(Property::new("modules_identifier", ValueSchema::String))
+        .with(Property::new(
+            "excludes",
+            ValueSchema::array(ValueSchema::String),
+        ))
+}
+
+fn rule_schema() -> TaggedSchema {
+    let mut schema = processor_schema("rule", "rule", get_all_rule_names(), |name| {
+        name.parse::<Box<dyn Rule>>()
+            .map(|rule| ObjectSchema::from_configuration(rule.as_ref()))
+    });
+
+    schema.deprecated_variants = get_rule_aliases()
+        .iter()
+        .filter_map(|alias| {
+            if alias.has_properties_translation() {
+                // the properties are only known once translated
+                Some((alias.name(), ObjectSchema::open()))
+            } else {
+                schema
+                    .find(alias.target())
+                    .map(|object| (alias.name(), object.clone()))
+            }
+        })
+        .collect();
+
+    schema
+}
+
+fn processor_schema(
+    kind: &'static str,
+    tag: &'static str,
+    names: Vec<&'static str>,
+    describe: impl Fn(&str) -> Result<ObjectSchema, String>,
+) -> TaggedSchema {
+    TaggedSchema {
+        kind,
+        tag,
+        variants: names
+            .into_iter()
+            .filter_map(|name| match describe(name) {
+                Ok(object) => Some((name, object)),
+                Err(err) => {
+                    log::warn!("unable to describe {} `{}`: {}", kind, name, err);
+                    None
+                }
+            })
+            .collect(),
+        deprecated_variants: Vec::new(),
+    }
+}
+
+fn global_entry_schema() -> ObjectSchema {
+    ObjectSchema::default()
+        .with(Property::new("name", ValueSchema::String).required())
+        .with(
+            Property::new(
+                "type",
+                ValueSchema::Choice(vec!["table", "function", "value"]),
+            )
+            .required(),
+        )
+        .with(Property::new("pure", ValueSchema::Boolean))
+        .with(Property::new("arity", ValueSchema::Unsigned))
+        .with(Property::new(
+            "returns",
+            ValueSchema::Choice(LUA_TYPES.to_vec()),
+        ))
+        .with(Property::new(
+            "value_type",
+            ValueSchema::Choice(LUA_TYPES.to_vec()),
+        ))
+        .with(Property::new(
+            "targets",
+            ValueSchema::array(ValueSchema::Choice(TARGETS.to_vec())),
+        ))
+        .with(Property::new(
+            "children",
+            ValueSchema::array(ValueSchema::Reference(Definition::GlobalEntry)),
+        ))
+}
+
+struct Validator<'a> {
+    schema: &'a ConfigurationSchema,
+    issues: Vec<ConfigurationIssue>,
+}
+
+impl Validator<'_> {
+    fn validate_value(&mut self, schema: &ValueSchema, value: &Value, path: &str) {
+        match schema {
+            ValueSchema::Any => {}
+            ValueSchema::Boolean => {
+                if !value.is_boolean() {
+                    self.push_type_mismatch(schema, value, path);
+                }
+            }
+            ValueSchema::Unsigned => {
+                if !value.is_u64() {
+                    self.push_type_mismatch(schema, value, path);
+                }
+            }
+            ValueSchema::Number => {
+                if !value.is_number() {
+                    self.push_type_mismatch(schema, value, path);
+                }
+            }
+            ValueSchema::String => {
+                if !value.is_string() {
+                    self.push_type_mismatch(schema, value, path);
+                }
+            }
+            ValueSchema::Choice(values) => match value.as_str() {
+                Some(string) if values.contains(&string) => {}
+                Some(string) => {
+                    let known: Vec<String> = values.iter().map(ToString::to_string).collect();
+                    self.issues.push(ConfigurationIssue::new(
+                        path,
+                        with_suggestion(
+                            format!(
+                                "unexpected value `{}`, expected {}",
+                                string,
+                                enumerate(values)
+                            ),
+                            string,
+                            &known,
+                        ),
+                    ));
+                }
+                None => self.push_type_mismatch(schema, value, path),
+            },
+            ValueSchema::Array(item) => match value.as_array() {
+                Some(array) => {
+                    for (index, element) in array.iter().enumerate() {
+                        self.validate_value(item, element, &format!("{}/{}", path, index));
+                    }
+                }
+                None => self.push_type_mismatch(schema, value, path),
+            },
+            ValueSchema::Map(item) => match value.as_object() {
+                Some(map) => {
+                    for (key, element) in map {
+                        self.validate_value(item, element, &join_pointer(path, key));
+                    }
+                }
+                None => self.push_type_mismatch(schema, value, path),
+            },
+            ValueSchema::Object(object) => match value.as_object() {
+                Some(map) => self.validate_object(object, map, path, None),
+                None => self.push_type_mismatch(schema, value, path),
+            },
+            ValueSchema::OneOf(variants) => self.validate_one_of(variants, value, path),
+            ValueSchema::Tagged(tagged) => self.validate_tagged(tagged, value, path),
+            ValueSchema::Reference(definition) => {
+                let resolved = self.schema.resolve(*definition);
+                self.validate_value(&resolved, value, path);
+            }
+        }
+    }
+
+    fn validate_object(
+        &mut self,
+        object: &ObjectSchema,
+        map: &Map<String, Value>,
+        path: &str,
+        tag: Option<&str>,
+    ) {
+        for (key, value) in map {
+            if Some(key.as_str()) == tag {
+                continue;
+            }
+
+            match object.find(key) {
+                Some(property) => {
+                    self.validate_value(&property.value, value, &join_pointer(path, key))
+                }
+                None if object.open => {}
+                None => {
+                    self.issues.push(ConfigurationIssue::new(
+                        join_pointer(path, key),
+                        with_suggestion(
+                            format!("unknown field `{}`", key),
+                            key,
+                            &object.field_names(),
+                        ),
+                    ));
+                }
+            }
+        }
+
+        for property in object
+            .properties
+            .iter()
+            .filter(|property| property.required)
+        {
+            if !map.keys().any(|key| property.matches(key)) {
+                self.issues.push(ConfigurationIssue::new(
+                    path,
+                    format!("missing field `{}`", property.name),
+                ));
+            }
+        }
+    }
+
+    fn validate_one_of(&mut self, variants: &[ValueSchema], value: &Value, path: &str) {
+        let mut first_issues = None;
+
+        for variant in variants {
+            let mut validator = Validator {
+                schema: self.schema,
+                issues: Vec::new(),
+            };
+            validator.validate_value(variant, value, path);
+
+            if validator.issues.is_empty() {
+                return;
+            }
+
+            if first_issues.is_none() && accepts_type(variant, value) {
+                first_issues = Some(validator.issues);
+            }
+        }
+
+        match first_issues {
+            Some(issues) => self.issues.extend(issues),
+            None => self.push_type_mismatch(&ValueSchema::OneOf(variants.to_vec()), value, path),
+        }
+    }
+
+    fn validate_tagged(&mut self, tagged: &TaggedSchema, value: &Value, path: &str) {
+        match value {
+            Value::String(name) => {
+                if tagged.find(name).is_none() {
+                    self.push_unknown_name(tagged, name, path);
+                }
+            }
+            Value::Object(map) => match map.get(tagged.tag) {
+                Some(Value::String(name)) => match tagged.find(name) {
+                    Some(object) => self.validate_object(object, map, path, Some(tagged.tag)),
+                    None => self.push_unknown_name(tagged, name, &join_pointer(path, tagged.tag)),
+                },
+                Some(other) => self.push_type_mismatch(
+                    &ValueSchema::String,
+                    other,
+                    &join_pointer(path, tagged.tag),
+                ),
+                None => self.issues.push(ConfigurationIssue::new(
+                    path,
+                    format!("missing field `{}`", tagged.tag),
+                )),
+            },
+            _ => self.push_type_mismatch(&ValueSchema::Tagged(tagged.clone()), value, path),
+        }
+    }
+
+    fn push_unknown_name(&mut self, tagged: &TaggedSchema, name: &str, path: &str) {
+        self.issues.push(ConfigurationIssue::new(
+            path,
+            with_suggestion(
+                format!("unknown {} `{}`", tagged.kind, name),
+                name,
+                &tagged.names(),
+            ),
+        ));
+    }
+
+    fn push_type_mismatch(&mut self, schema: &ValueSchema, value: &Value, path: &str) {
+        self.issues.push(ConfigurationIssue::new(
+            path,
+            format!(
+                "expected {}, found {}",
+                self.describe(schema),
+                describe_value(value)
+            ),
+        ));
+    }
+
+    fn describe(&self, schema: &ValueSchema) -> String {
+        match schema {
+            ValueSchema::Any => "any value".to_owned(),
+            ValueSchema::Boolean => "boolean".to_owned(),
+            ValueSchema::Unsigned => "unsigned integer".to_owned(),
+            ValueSchema::Number => "number".to_owned(),
+            ValueSchema::String | ValueSchema::Choice(_) => "string".to_owned(),
+            ValueSchema::Array(_) => "array".to_owned(),
+            ValueSchema::Map(_) | ValueSchema::Object(_) => "object".to_owned(),
+            ValueSchema::Tagged(_) => "string or object".to_owned(),
+            ValueSchema::OneOf(variants) => {
+                let mut descriptions: Vec<String> = Vec::new();
+                for variant in variants {
+                    let description = self.describe(variant);
+                    if !descriptions.contains(&description) {
+                        descriptions.push(description);
+                    }
+                }
+                descriptions.join(" or ")
+            }
+            ValueSchema::Reference(definition) => self.describe(&self.schema.resolve(*definition)),
+        }
+    }
+}
+
+fn accepts_type(schema: &ValueSchema, value: &Value) -> bool {
+    match schema {
+        ValueSchema::Any => true,
+        ValueSchema::Boolean => value.is_boolean(),
+        ValueSchema::Unsigned | ValueSchema::Number => value.is_number(),
+        ValueSchema::String | ValueSchema::Choice(_) => value.is_string(),
+        ValueSchema::Array(_) => value.is_array(),
+        ValueSchema::Map(_) | ValueSchema::Object(_) => value.is_object(),
+        ValueSchema::Tagged(_) => value.is_string() || value.is_object(),
+        ValueSchema::OneOf(variants) => variants.iter().any(|variant| accepts_type(variant, value)),
+        ValueSchema::Reference(_) => true,
+    }
+}
+
+fn describe_value(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn with_suggestion(message: String, name: &str, known: &[String]) -> String {
+    match suggest_property(name, known) {
+        Some(suggestion) => format!("{} (did you mean `{}`?)", message, suggestion),
+        None => message,
+    }
+}
+
+fn enumerate(values: &[&str]) -> String {
+    let quoted: Vec<_> = values.iter().map(|value| format!("`{}`", value)).collect();
+
+    match quoted.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => quoted.join(""),
+    }
+}
+
+fn join_pointer(path: &str, key: &str) -> String {
+    format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn value_to_json(schema: &ValueSchema) -> Value {
+    match schema {
+        ValueSchema::Any => json!({}),
+        ValueSchema::Boolean => json!({ "type": "boolean" }),
+        ValueSchema::Unsigned => json!({ "type": "integer", "minimum": 0 }),
+        ValueSchema::Number => json!({ "type": "number" }),
+        ValueSchema::String => json!({ "type": "string" }),
+        ValueSchema::Choice(values) => json!({ "type": "string", "enum": values }),
+        ValueSchema::Array(item) => json!({ "type": "array", "items": value_to_json(item) }),
+        ValueSchema::Map(value) => {
+            json!({ "type": "object", "additionalProperties": value_to_json(value) })
+        }
+        ValueSchema::Object(object) => object_to_json(object),
+        ValueSchema::OneOf(variants) => {
+            json!({ "anyOf": variants.iter().map(value_to_json).collect::<Vec<_>>() })
+        }
+        ValueSchema::Tagged(tagged) => tagged_to_json(tagged),
+        ValueSchema::Reference(definition) => {
+            json!({ "$ref": format!("#/definitions/{}", definition.name()) })
+        }
+    }
+}
+
+fn object_to_json(object: &ObjectSchema) -> Value {
+    object_with_tag_to_json(object, None)
+}
+
+fn object_with_tag_to_json(object: &ObjectSchema, tag: Option<(&str, &str)>) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+
+    if let Some((tag, name)) = tag {
+        properties.insert(tag.to_owned(), json!({ "const": name }));
+        required.push(tag.to_owned());
+    }
+
+    for property in object.properties.iter() {
+        let mut value = value_to_json(&property.value);
+
+        if let (Some(description), Value::Object(map)) = (property.description, &mut value) {
+            map.insert("description".to_owned(), description.into());
+        }
+
+        for alias in property.aliases {
+            properties.insert(alias.to_string(), value.clone());
+        }
+        properties.insert(property.name.to_owned(), value);
+
+        if property.required {
+            required.push(property.name.to_owned());
+        }
+    }
+
+    let mut schema = Map::new();
+    schema.insert("type".to_owned(), "object".into());
+    schema.insert("properties".to_owned(), Value::Object(properties));
+    if !required.is_empty() {
+        schema.insert("required".to_owned(), required.into());
+    }
+    if !object.open {
+        schema.insert("additionalProperties".to_owned(), false.into());
+    }
+
+    Value::Object(schema)
+}
+
+fn tagged_to_json(tagged: &TaggedSchema) -> Value {
+    let names: Vec<_> = tagged.variants.iter().map(|(name, _)| *name).collect();
+
+    let variants = std::iter::once(json!({ "type": "string", "enum": names })).chain(
+        tagged
+            .variants
+            .iter()
+            .map(|(name, object)| object_with_tag_to_json(object, Some((tagged.tag, *name)))),
+    );
+
+    json!({ "anyOf": variants.collect::<Vec<_>>() })
+}
+
+/// Returns the JSON Schema of the values accepted by a rule property.
+pub(crate) fn get_property_schema(kind: RulePropertyKind) -> Value {
+    value_to_json(&kind.into())
+}
+
+/// Returns a JSON Schema document describing the configuration file, which can be used by
+/// editors to validate and complete configuration files.
+pub fn get_configuration_schema() -> Value {
+    ConfigurationSchema::new().to_json_schema()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn validate(config: &str) -> Vec<String> {
+        let value: Value = json5::from_str(config).expect("configuration should be valid json5");
+        let mut issues: Vec<_> = ConfigurationSchema::new()
+            .validate(&value)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        issues.sort();
+        issues
+    }
+
+    #[test]
+    fn empty_configuration_is_valid() {
+        assert_eq!(validate("{}"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn complete_configuration_is_valid() {
+        assert_eq!(
+            validate(
+                r#"{
+                    schema_version: 1,
+                    generator: { name: "dense", column_span: 120 },
+                    target: "roblox",
+                    data_fast_path: true,
+                    preprocessors: ["strip_line_directives"],
+                    postprocessors: [{ name: "checksum_trailer" }],
+                    globals: [{ name: "game", type: "table", children: [{ name: "Players", type: "value" }] }],
+                    raw_markers: { begin: "--[[raw]]" },
+                    bundle: { require_mode: { name: "path", sources: { pkg: "./Packages" } } },
+                    rules: [
+                        "remove_comments",
+                        { rule: "inject_global_value", identifier: "DEBUG", value: false },
+                        { rule: "convert_require", current: "path", target: { name: "roblox", rojo_sourcemap: "sourcemap.json" } },
+                        { rule: "rename_variables", globals: ["$default"], include_functions: true },
+                    ],
+                }"#
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn reports_every_issue() {
+        let issues = validate(
+            r#"{
+                generatr: "dense",
+                data_fast_path: "yes",
+                rules: [
+                    "remove_comments",
+                    "remove_spaces",
+                    "compute_expression",
+                    { rule: "inject_global_value", identifier: 1 },
+                ],
+            }"#,
+        );
+
+        assert_eq!(
+            issues,
+            vec![
+                "/data_fast_path: expected boolean, found string".to_owned(),
+                "/generatr: unknown field `generatr` (did you mean `generator`?)".to_owned(),
+                "/rules/3/identifier: expected string, found number".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn suggests_rule_name() {
+        assert_eq!(
+            validate("{ rules: ['remove_comment'] }"),
+            vec!["/rules/0: unknown rule `remove_comment` (did you mean `remove_comments`?)"]
+        );
+    }
+
+    #[test]
+    fn suggests_rule_property() {
+        assert_eq!(
+            validate("{ rules: [{ rule: 'rename_variables', include_function: true }] }"),
+            vec![
+                "/rules/0/include_function: unknown field `include_function` (did you mean `include_functions`?)"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_choice() {
+        assert_eq!(
+            validate("{ target: 'lua52' }"),
+            vec![
+                "/target: unexpected value `lua52`, expected `lua51`, `lua53`, `lua54`, `luau` or `roblox` (did you mean `lua51`?)"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_missing_fields() {
+        assert_eq!(
+            validate("{ bundle: {}, rules: [{ identifier: 'DEBUG' }] }"),
+            vec![
+                "/bundle: missing field `require_mode`",
+                "/rules/0: missing field `rule`"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_object_instead_of_array() {
+        assert_eq!(
+            validate("{ rules: { rule: 'remove_comments' } }"),
+            vec!["/rules: expected array, found object"]
+        );
+    }
+
+    #[test]
+    fn reports_newer_schema_version() {
+        assert_eq!(
+            validate("{ schema_version: 2 }"),
+            vec![format!(
+                "/schema_version: unsupported schema version 2 (this version of darklua reads \
+                configurations up to version {})",
+                CONFIGURATION_SCHEMA_VERSION
+            )]
+        );
+    }
+
+    #[test]
+    fn escapes_pointer_keys() {
+        assert_eq!(join_pointer("/a", "b/c~d"), "/a/b~1c~0d");
+    }
+
+    #[test]
+    fn deprecated_rule_name_is_valid() {
+        assert_eq!(
+            validate(
+                r#"{ rules: ["remove_compound_assign", { rule: "remove_compound_assign" }] }"#
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn deprecated_rule_name_validates_target_properties() {
+        assert_eq!(
+            validate(r#"{ rules: [{ rule: "remove_compound_assign", unknown: true }] }"#).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn json_schema_describes_every_rule() {
+        let schema = get_configuration_schema();
+        let rules = schema["definitions"]["rule"]["anyOf"][0]["enum"]
+            .as_array()
+            .expect("rule names should be listed");
+
+        assert_eq!(rules.len(), get_all_rule_names().len());
+    }
+
+    #[test]
+    fn json_schema_describes_rule_properties() {
+        let schema = get_configuration_schema();
+        let inject_value = schema["definitions"]["rule"]["anyOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|variant| variant["properties"]["rule"]["const"] == "inject_global_value")
+            .expect("inject_global_value should be described");
+
+        assert_eq!(
+            inject_value["properties"]["identifier"],
+            json!({ "type": "string" })
+        );
+    }
+
+    #[test]
+    fn fixture_configuration_matches_json_schema() {
+        let schema = get_configuration_schema();
+        let validator =
+            jsonschema::validator_for(&schema).expect("json schema should be a valid schema");
+
+        let fixture: Value = json5::from_str(include_str!(
+            "../../tests/test_cases/configuration/complete.json5"
+        ))
+        .expect("fixture should be valid json5");
+
+        let errors: Vec<String> = validator
+            .iter_errors(&fixture)
+            .map(|error| error.to_string())
+            .collect();
+
+        assert_eq!(errors, Vec::<String>::new());
+        assert_eq!(
+            ConfigurationSchema::new().validate(&fixture),
+            Vec::<ConfigurationIssue>::new()
+        );
+    }
+
+    #[test]
+    fn invalid_configuration_does_not_match_json_schema() {
+        let schema = get_configuration_schema();
+        let validator =
+            jsonschema::validator_for(&schema).expect("json schema should be a valid schema");
+
+        let config: Value = json5::from_str(
+            "{ generatr: 'dense', rules: [{ rule: 'inject_global_value', identifier: 1 }] }",
+        )
+        .unwrap();
+
+        assert!(!validator.is_valid(&config));
+    }
+}
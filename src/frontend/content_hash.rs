@@ -0,0 +1,536 @@
+use std::{
+    fmt,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+#[cfg(feature = "serde-config")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use xxhash_rust::xxh3::xxh3_64;
+
+/// The algorithm used to compute the digest of the generated code of each file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// A SHA-256 digest, written as 64 hexadecimal characters.
+    #[default]
+    Sha256,
+    /// A 64-bit XXH3 digest, written as 16 hexadecimal characters. It is faster to compute
+    /// but it is not a cryptographic hash.
+    Xxh3,
+}
+
+impl HashAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Xxh3 => "xxh3",
+        }
+    }
+
+    /// Computes the digest of the content as lowercase hexadecimal characters.
+    pub fn digest(&self, content: &[u8]) -> String {
+        match self {
+            Self::Sha256 => Sha256::digest(content)
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            Self::Xxh3 => format!("{:016x}", xxh3_64(content)),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256" => Ok(Self::Sha256),
+            "xxh3" => Ok(Self::Xxh3),
+            _ => Err(format!(
+                "invalid hash algorithm `{}` (expected `sha256` or `xxh3`)",
+                s
+            )),
+        }
+    }
+}
+
+const NAME_PLACEHOLDER: &str = "name";
+const EXTENSION_PLACEHOLDER: &str = "extension";
+const CONTENT_HASH_PLACEHOLDER: &str = "content_hash";
+const SHORT_CONTENT_HASH_PLACEHOLDER: &str = "content_hash8";
+const SHORT_CONTENT_HASH_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Name,
+    Extension,
+    ContentHash,
+    ShortContentHash,
+}
+
+/// Defines the file name of each generated file from the name of its source and the digest
+/// of its generated code. The directories of the output are not changed by the template.
+///
+/// The template can use these placeholders:
+/// - `{name}`: the name of the source without its extension
+/// - `{extension}`: the extension of the source (without the dot)
+/// - `{content_hash}`: the digest of the generated code
+/// - `{content_hash8}`: the first 8 characters of the digest of the generated code
+///
+/// For example, `{name}.{content_hash8}.lua` writes the generated code of `src/init.lua` to
+/// a file like `init.3f2c9a1b.lua`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate {
+    template: String,
+    parts: Vec<TemplatePart>,
+}
+
+impl OutputTemplate {
+    /// Returns true when the template needs the digest of the generated code.
+    pub fn uses_content_hash(&self) -> bool {
+        uses_content_hash(&self.parts)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Computes the file name of the generated code of the given output path. The content
+    /// hash is only needed when the template uses it.
+    pub fn render(&self, output: &Path, content_hash: Option<&str>) -> PathBuf {
+        output.with_file_name(render_parts(&self.parts, output, content_hash))
+    }
+}
+
+fn render_parts(parts: &[TemplatePart], output: &Path, content_hash: Option<&str>) -> String {
+    let name = output
+        .file_stem()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let extension = output
+        .extension()
+        .map(|extension| extension.to_string_lossy())
+        .unwrap_or_default();
+    let content_hash = content_hash.unwrap_or_default();
+
+    parts
+        .iter()
+        .map(|part| match part {
+            TemplatePart::Text(text) => text.as_str(),
+            TemplatePart::Name => &name,
+            TemplatePart::Extension => &extension,
+            TemplatePart::ContentHash => content_hash,
+            TemplatePart::ShortContentHash => content_hash
+                .get(..SHORT_CONTENT_HASH_LENGTH)
+                .unwrap_or(content_hash),
+        })
+        .collect()
+}
+
+fn uses_content_hash(parts: &[TemplatePart]) -> bool {
+    parts.iter().any(|part| {
+        matches!(
+            part,
+            TemplatePart::ContentHash | TemplatePart::ShortContentHash
+        )
+    })
+}
+
+impl fmt::Display for OutputTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+impl FromStr for OutputTemplate {
+    type Err = String;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        if template.contains(['/', '\\']) {
+            return Err(format!(
+                "invalid output template `{}` (the template defines a file name and cannot contain path separators)",
+                template
+            ));
+        }
+
+        Ok(Self {
+            template: template.to_owned(),
+            parts: parse_template("output template", template)?,
+        })
+    }
+}
+
+fn parse_template(label: &str, template: &str) -> Result<Vec<TemplatePart>, String> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(TemplatePart::Text(rest[..start].to_owned()));
+        }
+
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("invalid {} `{}` (missing `}}` after `{{`)", label, template))?
+            + start;
+
+        let part = match &rest[start + 1..end] {
+            NAME_PLACEHOLDER => TemplatePart::Name,
+            EXTENSION_PLACEHOLDER => TemplatePart::Extension,
+            CONTENT_HASH_PLACEHOLDER => TemplatePart::ContentHash,
+            SHORT_CONTENT_HASH_PLACEHOLDER => TemplatePart::ShortContentHash,
+            placeholder => {
+                return Err(format!(
+                    "invalid {} `{}` (unknown placeholder `{{{}}}`, expected `{{{}}}`, `{{{}}}`, `{{{}}}` or `{{{}}}`)",
+                    label,
+                    template,
+                    placeholder,
+                    NAME_PLACEHOLDER,
+                    EXTENSION_PLACEHOLDER,
+                    CONTENT_HASH_PLACEHOLDER,
+                    SHORT_CONTENT_HASH_PLACEHOLDER,
+                ))
+            }
+        };
+        parts.push(part);
+
+        rest = &rest[end + 1..];
+    }
+
+    if !rest.is_empty() {
+        parts.push(TemplatePart::Text(rest.to_owned()));
+    }
+
+    if parts.is_empty() {
+        return Err(format!("invalid {} (the template is empty)", label));
+    }
+
+    Ok(parts)
+}
+
+/// Defines where an artifact emitted by a rule is written, relative to the directory of the
+/// generated file it comes from. The template accepts the same placeholders as an
+/// [`OutputTemplate`] and it can contain directories.
+///
+/// For example, `maps/{name}.json` writes the artifact of `src/lib/util.lua` (generated in
+/// `out/lib/util.lua`) to `out/lib/maps/util.json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactTemplate {
+    template: String,
+    parts: Vec<TemplatePart>,
+}
+
+impl ArtifactTemplate {
+    /// Returns true when the template needs the digest of the generated code.
+    pub fn uses_content_hash(&self) -> bool {
+        uses_content_hash(&self.parts)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Computes the path of an artifact from the path of the generated code it comes from.
+    pub fn render(&self, output: &Path, content_hash: Option<&str>) -> PathBuf {
+        let path = render_parts(&self.parts, output, content_hash);
+
+        match output.parent() {
+            Some(parent) => parent.join(path),
+            None => PathBuf::from(path),
+        }
+    }
+}
+
+impl fmt::Display for ArtifactTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+impl FromStr for ArtifactTemplate {
+    type Err = String;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        if Path::new(template).has_root() {
+            return Err(format!(
+                "invalid artifact template `{}` (the template must be a relative path)",
+                template
+            ));
+        }
+
+        Ok(Self {
+            template: template.to_owned(),
+            parts: parse_template("artifact template", template)?,
+        })
+    }
+}
+
+#[cfg(feature = "serde-config")]
+impl Serialize for ArtifactTemplate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.template)
+    }
+}
+
+#[cfg(feature = "serde-config")]
+impl<'de> Deserialize<'de> for ArtifactTemplate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let template = String::deserialize(deserializer)?;
+        template.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Formats the content of a hash manifest: one `relative/path.lua: <digest>` line for each
+/// generated file, sorted by path. Paths always use `/` as their separator, so that the
+/// manifest is the same on every platform.
+pub(crate) fn format_hash_manifest<'a>(
+    entries: impl IntoIterator<Item = (&'a Path, &'a str)>,
+) -> String {
+    let mut lines: Vec<_> = entries
+        .into_iter()
+        .map(|(path, digest)| format!("{}: {}", manifest_path(path), digest))
+        .collect();
+    lines.sort();
+
+    lines
+        .into_iter()
+        .map(|line| line + "\n")
+        .collect::<String>()
+}
+
+fn manifest_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            Component::Prefix(_)
+            | Component::RootDir
+            | Component::CurDir
+            | Component::ParentDir => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    mod hash_algorithm {
+        use super::*;
+
+        #[test]
+        fn sha256_digest_of_empty_content() {
+            assert_eq!(
+                HashAlgorithm::Sha256.digest(b""),
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+            );
+        }
+
+        #[test]
+        fn sha256_digest() {
+            assert_eq!(
+                HashAlgorithm::Sha256.digest(b"abc"),
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+            );
+        }
+
+        #[test]
+        fn xxh3_digest_has_16_characters() {
+            let digest = HashAlgorithm::Xxh3.digest(b"return 1");
+
+            assert_eq!(digest.len(), 16);
+            assert_eq!(digest, format!("{:016x}", xxh3_64(b"return 1")));
+        }
+
+        #[test]
+        fn parse_algorithms() {
+            assert_eq!("sha256".parse(), Ok(HashAlgorithm::Sha256));
+            assert_eq!("xxh3".parse(), Ok(HashAlgorithm::Xxh3));
+        }
+
+        #[test]
+        fn parse_unknown_algorithm() {
+            assert_eq!(
+                "md5".parse::<HashAlgorithm>(),
+                Err("invalid hash algorithm `md5` (expected `sha256` or `xxh3`)".to_owned())
+            );
+        }
+    }
+
+    mod output_template {
+        use super::*;
+
+        fn render(template: &str, output: &str, hash: Option<&str>) -> PathBuf {
+            template
+                .parse::<OutputTemplate>()
+                .unwrap()
+                .render(Path::new(output), hash)
+        }
+
+        #[test]
+        fn render_name_and_extension() {
+            assert_eq!(
+                render("{name}.min.{extension}", "out/init.lua", None),
+                Path::new("out/init.min.lua")
+            );
+        }
+
+        #[test]
+        fn render_content_hash() {
+            assert_eq!(
+                render(
+                    "{content_hash}.lua",
+                    "out/init.lua",
+                    Some("0123456789abcdef")
+                ),
+                Path::new("out/0123456789abcdef.lua")
+            );
+        }
+
+        #[test]
+        fn render_short_content_hash() {
+            assert_eq!(
+                render(
+                    "{name}.{content_hash8}.lua",
+                    "out/lib/util.lua",
+                    Some("0123456789abcdef")
+                ),
+                Path::new("out/lib/util.01234567.lua")
+            );
+        }
+
+        #[test]
+        fn render_without_placeholders() {
+            assert_eq!(
+                render("main.lua", "out/init.lua", None),
+                Path::new("out/main.lua")
+            );
+        }
+
+        #[test]
+        fn uses_content_hash() {
+            let template: OutputTemplate = "{name}.{content_hash8}.lua".parse().unwrap();
+
+            assert!(template.uses_content_hash());
+        }
+
+        #[test]
+        fn does_not_use_content_hash() {
+            let template: OutputTemplate = "{name}.min.lua".parse().unwrap();
+
+            assert!(!template.uses_content_hash());
+        }
+
+        #[test]
+        fn unknown_placeholder_error() {
+            assert_eq!(
+                "{hash}.lua".parse::<OutputTemplate>().unwrap_err(),
+                "invalid output template `{hash}.lua` (unknown placeholder `{hash}`, expected `{name}`, `{extension}`, `{content_hash}` or `{content_hash8}`)"
+            );
+        }
+
+        #[test]
+        fn unclosed_placeholder_error() {
+            assert_eq!(
+                "{name.lua".parse::<OutputTemplate>().unwrap_err(),
+                "invalid output template `{name.lua` (missing `}` after `{`)"
+            );
+        }
+
+        #[test]
+        fn path_separator_error() {
+            assert!("lib/{name}.lua".parse::<OutputTemplate>().is_err());
+        }
+
+        #[test]
+        fn empty_template_error() {
+            assert!("".parse::<OutputTemplate>().is_err());
+        }
+    }
+
+    mod artifact_template {
+        use super::*;
+
+        fn render(template: &str, output: &str, hash: Option<&str>) -> PathBuf {
+            template
+                .parse::<ArtifactTemplate>()
+                .unwrap()
+                .render(Path::new(output), hash)
+        }
+
+        #[test]
+        fn render_in_output_directory() {
+            assert_eq!(
+                render("{name}.strings.json", "out/init.lua", None),
+                Path::new("out/init.strings.json")
+            );
+        }
+
+        #[test]
+        fn render_in_sub_directory() {
+            assert_eq!(
+                render("i18n/{name}.json", "out/lib/util.lua", None),
+                Path::new("out/lib/i18n/util.json")
+            );
+        }
+
+        #[test]
+        fn render_short_content_hash() {
+            assert_eq!(
+                render(
+                    "{name}.{content_hash8}.json",
+                    "out/init.lua",
+                    Some("0123456789abcdef")
+                ),
+                Path::new("out/init.01234567.json")
+            );
+        }
+
+        #[test]
+        fn uses_content_hash() {
+            let template: ArtifactTemplate = "{content_hash}.json".parse().unwrap();
+
+            assert!(template.uses_content_hash());
+        }
+
+        #[test]
+        fn absolute_path_error() {
+            assert_eq!(
+                "/tmp/{name}.json".parse::<ArtifactTemplate>().unwrap_err(),
+                "invalid artifact template `/tmp/{name}.json` (the template must be a relative path)"
+            );
+        }
+
+        #[test]
+        fn unknown_placeholder_error() {
+            assert!("{hash}.json".parse::<ArtifactTemplate>().is_err());
+        }
+    }
+
+    mod hash_manifest {
+        use super::*;
+
+        #[test]
+        fn format_sorted_entries() {
+            let manifest = format_hash_manifest([
+                (Path::new("lib/util.lua"), "bbbb"),
+                (Path::new("init.lua"), "aaaa"),
+            ]);
+
+            assert_eq!(manifest, "init.lua: aaaa\nlib/util.lua: bbbb\n");
+        }
+
+        #[test]
+        fn format_empty_manifest() {
+            assert_eq!(format_hash_manifest([]), "");
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::nodes::{Block, Expression, LastStatement, TableEntry, TableExpression, UnaryOperator};
+
+/// Returns `true` when the block only returns a table made of literal values
+/// (like `return { name = "value", 1, 2, 3 }`).
+pub(crate) fn is_data_module(block: &Block) -> bool {
+    if block.statements_len() != 0 {
+        return false;
+    }
+
+    match block.get_last_statement() {
+        Some(LastStatement::Return(statement)) if statement.len() == 1 => statement
+            .iter_expressions()
+            .next()
+            .and_then(|expression| match expression {
+                Expression::Table(table) => Some(is_literal_table(table)),
+                _ => None,
+            })
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
+fn is_literal_table(table: &TableExpression) -> bool {
+    table.iter_entries().all(|entry| match entry {
+        TableEntry::Field(field) => is_literal(field.get_value()),
+        TableEntry::Index(index) => is_literal(index.get_key()) && is_literal(index.get_value()),
+        TableEntry::Value(value) => is_literal(value),
+    })
+}
+
+fn is_literal(expression: &Expression) -> bool {
+    match expression {
+        Expression::False(_)
+        | Expression::Nil(_)
+        | Expression::Number(_)
+        | Expression::String(_)
+        | Expression::True(_) => true,
+        Expression::Table(table) => is_literal_table(table),
+        Expression::Unary(unary) => {
+            unary.operator() == UnaryOperator::Minus
+                && matches!(unary.get_expression(), Expression::Number(_))
+        }
+        Expression::Binary(_)
+        | Expression::Call(_)
+        | Expression::Field(_)
+        | Expression::Function(_)
+        | Expression::Identifier(_)
+        | Expression::If(_)
+        | Expression::Index(_)
+        | Expression::Parenthese(_)
+        | Expression::InterpolatedString(_)
+        | Expression::TypeCast(_)
+        | Expression::VariableArguments(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Parser;
+
+    macro_rules! test_data_module {
+        ($($name:ident ($code:literal) => $expect:literal),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    let block = Parser::default().parse($code).expect("code should parse");
+
+                    assert_eq!(is_data_module(&block), $expect);
+                }
+            )*
+        };
+    }
+
+    test_data_module!(
+        empty_table("return {}") => true,
+        array_of_numbers("return { 1, 2, 3 }") => true,
+        negative_number("return { -1 }") => true,
+        string_fields("return { name = 'darklua', version = \"1.0\" }") => true,
+        index_entries("return { ['key'] = true, [1] = false, [2] = nil }") => true,
+        nested_tables("return { a = { b = { 1, 2 } }, { 'value' } }") => true,
+        empty_block("") => false,
+        return_nothing("return") => false,
+        return_number("return 1") => false,
+        return_two_tables("return {}, {}") => false,
+        statement_before_return("local a = 1 return { a }") => false,
+        identifier_value("return { value = a }") => false,
+        call_value("return { value = call() }") => false,
+        binary_value("return { value = 1 + 2 }") => false,
+        function_value("return { value = function() end }") => false,
+        not_unary_value("return { value = not true }") => false,
+        identifier_key("return { [key] = true }") => false,
+        interpolated_string_value("return { value = `abc` }") => false,
+    );
+}
@@ -0,0 +1,512 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    rules::require::{InstanceTree, RequireResolver},
+    utils::normalize_path,
+    Parser,
+};
+
+use super::{DarkluaError, DarkluaResult, Resources};
+
+/// Options to build a [`DependencyGraph`].
+#[derive(Debug, Clone)]
+pub struct DependencyGraphOptions {
+    location: PathBuf,
+    roblox_paths: Option<Vec<(PathBuf, String)>>,
+}
+
+impl DependencyGraphOptions {
+    /// Creates options to build the graph of the Lua files found at the given location (a
+    /// file or a directory).
+    pub fn new(location: impl Into<PathBuf>) -> Self {
+        Self {
+            location: location.into(),
+            roblox_paths: None,
+        }
+    }
+
+    /// Associates files with the path of the Roblox instance they create (like
+    /// `ReplicatedStorage.Shared.Util`). Without these paths, requires of Roblox instances
+    /// are resolved from the location of the files, and instances from `game` cannot be
+    /// resolved.
+    pub fn with_roblox_paths(
+        mut self,
+        roblox_paths: impl IntoIterator<Item = (impl Into<PathBuf>, impl Into<String>)>,
+    ) -> Self {
+        self.roblox_paths
+            .get_or_insert_with(Vec::new)
+            .extend(roblox_paths.into_iter().map(|(path, roblox_path)| {
+                let path: PathBuf = path.into();
+                (normalize_path(path), roblox_path.into())
+            }));
+        self
+    }
+}
+
+/// A require call that could not be associated with a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedRequire {
+    source: PathBuf,
+    line: Option<usize>,
+    require: String,
+    reason: String,
+}
+
+impl UnresolvedRequire {
+    /// The file that contains the require call.
+    #[inline]
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    #[inline]
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// The code of the require call.
+    #[inline]
+    pub fn require(&self) -> &str {
+        &self.require
+    }
+
+    #[inline]
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl fmt::Display for UnresolvedRequire {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        write!(
+            f,
+            ": unable to resolve `{}` ({})",
+            self.require, self.reason
+        )
+    }
+}
+
+/// The files of a project and the files they require.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    modules: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    unresolved: Vec<UnresolvedRequire>,
+}
+
+impl DependencyGraph {
+    /// Parses each Lua file at the location of the options and resolves its requires.
+    /// String requires are resolved like the `path` require mode, and requires of Roblox
+    /// instances (like `require(script.Parent.Util)`) are resolved with the Roblox paths of
+    /// the options, or from the location of the files.
+    pub fn build(resources: &Resources, options: DependencyGraphOptions) -> DarkluaResult<Self> {
+        let location = normalize_path(&options.location);
+
+        let mut files: Vec<_> = resources
+            .collect_work(&location)
+            .map(normalize_path)
+            .collect();
+        files.sort();
+
+        let instances = match options.roblox_paths {
+            Some(roblox_paths) => InstanceTree::from_roblox_paths(roblox_paths),
+            None => {
+                let root = if resources.is_file(&location)? {
+                    location.parent().map(Path::to_path_buf).unwrap_or_default()
+                } else {
+                    location.clone()
+                };
+                InstanceTree::from_files(&root, files.iter().cloned())
+            }
+        };
+
+        let project_location = if resources.is_file(&location)? {
+            PathBuf::new()
+        } else {
+            location
+        };
+        let resolver = RequireResolver::new(resources, project_location, instances);
+
+        let mut graph = Self::default();
+
+        for file in files {
+            let code = resources.get(&file)?;
+            let mut block = Parser::default()
+                .preserve_tokens()
+                .parse(&code)
+                .map_err(|err| DarkluaError::parser_error(&file, err))?;
+
+            let mut dependencies = BTreeSet::new();
+
+            for found in resolver.find_requires(&mut block, &file) {
+                match found.required {
+                    Ok(required) => {
+                        dependencies.insert(required);
+                    }
+                    Err(reason) => {
+                        graph.unresolved.push(UnresolvedRequire {
+                            source: file.clone(),
+                            line: found.line,
+                            require: found.snippet,
+                            reason,
+                        });
+                    }
+                }
+            }
+
+            for dependency in dependencies.iter() {
+                graph.modules.entry(dependency.clone()).or_default();
+            }
+            graph.modules.entry(file).or_default().extend(dependencies);
+        }
+
+        Ok(graph)
+    }
+
+    /// Returns every file of the graph, in order.
+    pub fn modules(&self) -> impl Iterator<Item = &Path> {
+        self.modules.keys().map(PathBuf::as_path)
+    }
+
+    pub fn contains(&self, module: impl AsRef<Path>) -> bool {
+        self.modules.contains_key(&normalize_path(module))
+    }
+
+    /// Returns the files required by the given file.
+    pub fn dependencies(&self, module: impl AsRef<Path>) -> impl Iterator<Item = &Path> {
+        self.modules
+            .get(&normalize_path(module))
+            .into_iter()
+            .flatten()
+            .map(PathBuf::as_path)
+    }
+
+    /// Returns each pair of files where the first file requires the second one.
+    pub fn edges(&self) -> impl Iterator<Item = (&Path, &Path)> {
+        self.modules.iter().flat_map(|(module, dependencies)| {
+            dependencies
+                .iter()
+                .map(move |dependency| (module.as_path(), dependency.as_path()))
+        })
+    }
+
+    /// Returns the require calls that could not be associated with a file.
+    pub fn unresolved(&self) -> &[UnresolvedRequire] {
+        &self.unresolved
+    }
+
+    /// Returns the cycles of the graph. Each cycle is a list of files where each file
+    /// requires the next one, and the last file requires the first one. One cycle is given
+    /// for each group of files that require each other.
+    pub fn cycles(&self) -> Vec<Vec<PathBuf>> {
+        let mut cycles: Vec<_> = StronglyConnectedComponents::find(&self.modules)
+            .into_iter()
+            .filter_map(|component| self.find_cycle(&component))
+            .collect();
+        cycles.sort();
+        cycles
+    }
+
+    /// Returns the part of the graph around the given file: the files it requires directly
+    /// or indirectly, and the files that require it directly or indirectly. Returns `None`
+    /// if the file is not in the graph.
+    pub fn focus(&self, module: impl AsRef<Path>) -> Option<Self> {
+        let (module, _) = self.modules.get_key_value(&normalize_path(module))?;
+
+        let mut dependents: HashMap<&Path, Vec<&Path>> = HashMap::new();
+        for (from, to) in self.edges() {
+            dependents.entry(to).or_default().push(from);
+        }
+
+        let mut kept = self.reachable(module, |current| {
+            self.modules
+                .get(current)
+                .into_iter()
+                .flatten()
+                .map(PathBuf::as_path)
+                .collect()
+        });
+        kept.extend(self.reachable(module, |current| {
+            dependents.get(current).cloned().unwrap_or_default()
+        }));
+
+        let modules = self
+            .modules
+            .iter()
+            .filter(|(module, _)| kept.contains(module.as_path()))
+            .map(|(module, dependencies)| {
+                (
+                    module.clone(),
+                    dependencies
+                        .iter()
+                        .filter(|dependency| kept.contains(dependency.as_path()))
+                        .cloned()
+                        .collect(),
+                )
+            })
+            .collect();
+
+        let unresolved = self
+            .unresolved
+            .iter()
+            .filter(|unresolved| kept.contains(unresolved.source.as_path()))
+            .cloned()
+            .collect();
+
+        Some(Self {
+            modules,
+            unresolved,
+        })
+    }
+
+    fn reachable<'a>(
+        &'a self,
+        start: &'a Path,
+        next: impl Fn(&'a Path) -> Vec<&'a Path>,
+    ) -> BTreeSet<&'a Path> {
+        let mut visited = BTreeSet::new();
+        let mut queue = vec![start];
+
+        while let Some(current) = queue.pop() {
+            if visited.insert(current) {
+                queue.extend(next(current));
+            }
+        }
+
+        visited
+    }
+
+    /// Finds the shortest cycle that starts from the first file of a strongly connected
+    /// component. Components of a single file only have a cycle if the file requires itself.
+    fn find_cycle(&self, component: &BTreeSet<&Path>) -> Option<Vec<PathBuf>> {
+        let start = *component.iter().next()?;
+
+        let mut previous: HashMap<&Path, &Path> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(current) = queue.pop_front() {
+            for dependency in self.dependencies(current) {
+                if dependency == start {
+                    let mut cycle = vec![current.to_path_buf()];
+                    let mut node = current;
+                    while let Some(parent) = previous.get(node) {
+                        cycle.push(parent.to_path_buf());
+                        node = parent;
+                    }
+                    cycle.reverse();
+                    return Some(cycle);
+                }
+
+                if component.contains(dependency) && !previous.contains_key(dependency) {
+                    previous.insert(dependency, current);
+                    queue.push_back(dependency);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Finds the strongly connected components of a graph with Tarjan's algorithm.
+struct StronglyConnectedComponents<'a> {
+    modules: &'a BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    index: usize,
+    indexes: HashMap<&'a Path, usize>,
+    low_links: HashMap<&'a Path, usize>,
+    stack: Vec<&'a Path>,
+    on_stack: BTreeSet<&'a Path>,
+    components: Vec<BTreeSet<&'a Path>>,
+}
+
+impl<'a> StronglyConnectedComponents<'a> {
+    fn find(modules: &'a BTreeMap<PathBuf, BTreeSet<PathBuf>>) -> Vec<BTreeSet<&'a Path>> {
+        let mut finder = Self {
+            modules,
+            index: 0,
+            indexes: HashMap::new(),
+            low_links: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: BTreeSet::new(),
+            components: Vec::new(),
+        };
+
+        for module in modules.keys() {
+            if !finder.indexes.contains_key(module.as_path()) {
+                finder.visit(module);
+            }
+        }
+
+        finder.components
+    }
+
+    fn visit(&mut self, module: &'a Path) {
+        self.indexes.insert(module, self.index);
+        self.low_links.insert(module, self.index);
+        self.index += 1;
+        self.stack.push(module);
+        self.on_stack.insert(module);
+
+        let modules = self.modules;
+        for dependency in modules.get(module).into_iter().flatten() {
+            let dependency = dependency.as_path();
+
+            if !self.indexes.contains_key(dependency) {
+                self.visit(dependency);
+                let low_link = self.low_links[module].min(self.low_links[dependency]);
+                self.low_links.insert(module, low_link);
+            } else if self.on_stack.contains(dependency) {
+                let low_link = self.low_links[module].min(self.indexes[dependency]);
+                self.low_links.insert(module, low_link);
+            }
+        }
+
+        if self.low_links[module] == self.indexes[module] {
+            let mut component = BTreeSet::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                component.insert(member);
+                if member == module {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build_graph(files: &[(&str, &str)]) -> DependencyGraph {
+        let resources = Resources::from_memory();
+        for (path, content) in files {
+            resources.write(path, content).unwrap();
+        }
+        DependencyGraph::build(&resources, DependencyGraphOptions::new("src")).unwrap()
+    }
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn build_graph_with_relative_requires() {
+        let graph = build_graph(&[
+            (
+                "src/main.lua",
+                "local a = require('./a') local b = require('./b')",
+            ),
+            ("src/a.lua", "return require('./b')"),
+            ("src/b.lua", "return 1"),
+        ]);
+
+        assert_eq!(
+            graph
+                .edges()
+                .map(|(from, to)| (from.to_path_buf(), to.to_path_buf()))
+                .collect::<Vec<_>>(),
+            vec![
+                (PathBuf::from("src/a.lua"), PathBuf::from("src/b.lua")),
+                (PathBuf::from("src/main.lua"), PathBuf::from("src/a.lua")),
+                (PathBuf::from("src/main.lua"), PathBuf::from("src/b.lua")),
+            ]
+        );
+        assert!(graph.cycles().is_empty());
+        assert!(graph.unresolved().is_empty());
+    }
+
+    #[test]
+    fn build_graph_with_instance_requires() {
+        let graph = build_graph(&[
+            ("src/main.lua", "return require(script.Parent.lib)"),
+            ("src/lib/init.lua", "return require(script.util)"),
+            ("src/lib/util.lua", "return 1"),
+        ]);
+
+        assert_eq!(
+            graph.dependencies("src/main.lua").collect::<Vec<_>>(),
+            vec![Path::new("src/lib/init.lua")]
+        );
+        assert_eq!(
+            graph.dependencies("src/lib/init.lua").collect::<Vec<_>>(),
+            vec![Path::new("src/lib/util.lua")]
+        );
+    }
+
+    #[test]
+    fn unresolved_require_has_location() {
+        let graph = build_graph(&[("src/main.lua", "local a = 1\nreturn require('./missing')")]);
+
+        let unresolved = graph.unresolved();
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].source(), Path::new("src/main.lua"));
+        assert_eq!(unresolved[0].line(), Some(2));
+        assert!(unresolved[0].require().contains("./missing"));
+    }
+
+    #[test]
+    fn find_cycle_between_two_files() {
+        let graph = build_graph(&[
+            ("src/a.lua", "return require('./b')"),
+            ("src/b.lua", "return require('./a')"),
+            ("src/c.lua", "return require('./a')"),
+        ]);
+
+        assert_eq!(graph.cycles(), vec![paths(&["src/a.lua", "src/b.lua"])]);
+    }
+
+    #[test]
+    fn find_cycle_of_file_requiring_itself() {
+        let graph = build_graph(&[("src/a.lua", "return require('./a')")]);
+
+        assert_eq!(graph.cycles(), vec![paths(&["src/a.lua"])]);
+    }
+
+    #[test]
+    fn find_shortest_cycle_in_component() {
+        let graph = build_graph(&[
+            ("src/a.lua", "require('./b') require('./c')"),
+            ("src/b.lua", "require('./c')"),
+            ("src/c.lua", "require('./a')"),
+        ]);
+
+        assert_eq!(graph.cycles(), vec![paths(&["src/a.lua", "src/c.lua"])]);
+    }
+
+    #[test]
+    fn focus_keeps_dependencies_and_dependents() {
+        let graph = build_graph(&[
+            ("src/main.lua", "require('./a')"),
+            ("src/a.lua", "require('./b')"),
+            ("src/b.lua", "return 1"),
+            ("src/other.lua", "require('./b')"),
+        ]);
+
+        let focused = graph.focus("src/a.lua").unwrap();
+
+        assert_eq!(
+            focused.modules().collect::<Vec<_>>(),
+            vec![
+                Path::new("src/a.lua"),
+                Path::new("src/b.lua"),
+                Path::new("src/main.lua")
+            ]
+        );
+    }
+
+    #[test]
+    fn focus_on_unknown_module_is_none() {
+        let graph = build_graph(&[("src/main.lua", "return 1")]);
+
+        assert_eq!(graph.focus("src/other.lua"), None);
+    }
+}
@@ -0,0 +1,235 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[cfg(feature = "serde-config")]
+use serde::Serialize;
+
+use crate::frontend::resources::ResourceError;
+use crate::frontend::worker::Worker;
+use crate::frontend::{DarkluaResult, Options, Resources};
+use crate::rules::{
+    get_all_rule_names, CompatibilityKind, CompatibilityNote, DynamicEnvironmentUsage, Rule,
+};
+use crate::utils::log;
+
+/// A problem that prevents the compatibility analysis of a file, like a parsing error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+pub struct FileIssue {
+    path: PathBuf,
+    message: String,
+}
+
+impl FileIssue {
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// The compatibility notes of a rule for a single file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+pub struct FileNotes {
+    path: PathBuf,
+    notes: Vec<CompatibilityNote>,
+}
+
+impl FileNotes {
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[inline]
+    pub fn notes(&self) -> &[CompatibilityNote] {
+        &self.notes
+    }
+}
+
+/// The files where applying a rule would be skipped, risky or conservative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+pub struct RuleCompatibility {
+    rule: &'static str,
+    skipped: usize,
+    risky: usize,
+    conservative: usize,
+    files: Vec<FileNotes>,
+}
+
+impl RuleCompatibility {
+    fn new(rule: &'static str) -> Self {
+        Self {
+            rule,
+            skipped: 0,
+            risky: 0,
+            conservative: 0,
+            files: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, path: &Path, notes: Vec<CompatibilityNote>) {
+        if notes.is_empty() {
+            return;
+        }
+
+        for kind in [
+            CompatibilityKind::Skipped,
+            CompatibilityKind::Risky,
+            CompatibilityKind::Conservative,
+        ]
+        .iter()
+        {
+            if notes.iter().any(|note| note.kind() == *kind) {
+                *self.count_mut(*kind) += 1;
+            }
+        }
+
+        self.files.push(FileNotes {
+            path: path.to_path_buf(),
+            notes,
+        });
+    }
+
+    fn count_mut(&mut self, kind: CompatibilityKind) -> &mut usize {
+        match kind {
+            CompatibilityKind::Skipped => &mut self.skipped,
+            CompatibilityKind::Risky => &mut self.risky,
+            CompatibilityKind::Conservative => &mut self.conservative,
+        }
+    }
+
+    #[inline]
+    pub fn rule(&self) -> &'static str {
+        self.rule
+    }
+
+    /// Returns the number of files with at least one note of the given kind.
+    pub fn file_count(&self, kind: CompatibilityKind) -> usize {
+        match kind {
+            CompatibilityKind::Skipped => self.skipped,
+            CompatibilityKind::Risky => self.risky,
+            CompatibilityKind::Conservative => self.conservative,
+        }
+    }
+
+    #[inline]
+    pub fn files(&self) -> &[FileNotes] {
+        &self.files
+    }
+
+    /// Returns true when the rule has no note for any file.
+    #[inline]
+    pub fn is_compatible(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// The result of the `doctor` analysis: the files that could not be analyzed and the
+/// compatibility of each built-in rule with the other files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+pub struct DoctorReport {
+    checked_files: usize,
+    file_issues: Vec<FileIssue>,
+    rules: Vec<RuleCompatibility>,
+}
+
+impl DoctorReport {
+    /// Returns the number of files found, including the files with issues.
+    #[inline]
+    pub fn checked_files(&self) -> usize {
+        self.checked_files
+    }
+
+    #[inline]
+    pub fn file_issues(&self) -> &[FileIssue] {
+        &self.file_issues
+    }
+
+    #[inline]
+    pub fn rules(&self) -> &[RuleCompatibility] {
+        &self.rules
+    }
+
+    pub fn get_rule(&self, name: &str) -> Option<&RuleCompatibility> {
+        self.rules.iter().find(|rule| rule.rule == name)
+    }
+}
+
+/// Predicts how each built-in rule (with its default configuration) would behave on the
+/// files of the input, without applying any rule. Files that cannot be read or parsed are
+/// reported as issues and do not stop the analysis of the other files.
+pub fn doctor(resources: &Resources, mut options: Options) -> DarkluaResult<DoctorReport> {
+    let mut worker = Worker::new(resources);
+    worker.setup_worker(&mut options)?;
+
+    let rules = get_all_rule_names()
+        .into_iter()
+        .map(|name| name.parse::<Box<dyn Rule>>())
+        .collect::<Result<Vec<_>, _>>()
+        .expect("built-in rules should have a default configuration");
+
+    let mut report = DoctorReport {
+        checked_files: 0,
+        file_issues: Vec::new(),
+        rules: rules
+            .iter()
+            .map(|rule| RuleCompatibility::new(rule.get_name()))
+            .collect(),
+    };
+
+    let parser = worker.configuration().build_parser();
+
+    let mut files: Vec<_> = resources.collect_work(options.input()).collect();
+    files.sort();
+
+    for path in files {
+        report.checked_files += 1;
+
+        let code = match resources.get(&path) {
+            Ok(code) => code,
+            Err(err) => {
+                let message = match err {
+                    ResourceError::InvalidEncoding(_) => "file is not valid UTF-8".to_owned(),
+                    ResourceError::NotFound(_) => "unable to find file".to_owned(),
+                    ResourceError::IO { error, .. } => format!("unable to read file: {}", error),
+                };
+                report.file_issues.push(FileIssue { path, message });
+                continue;
+            }
+        };
+
+        let mut block = match parser.parse(&code) {
+            Ok(block) => block,
+            Err(err) => {
+                report.file_issues.push(FileIssue {
+                    path,
+                    message: format!("unable to parse file: {}", err.to_string().trim_end()),
+                });
+                continue;
+            }
+        };
+
+        log::debug!("check rule compatibility of `{}`", path.display());
+
+        let dynamic_environment = Arc::new(DynamicEnvironmentUsage::find(&mut block));
+
+        for (rule, compatibility) in rules.iter().zip(report.rules.iter_mut()) {
+            let context = worker
+                .create_rule_context(&path, &code)
+                .with_dynamic_environment(dynamic_environment.clone())
+                .build();
+
+            compatibility.push(&path, rule.compatibility_check(&block, &context));
+        }
+    }
+
+    Ok(report)
+}
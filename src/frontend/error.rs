@@ -7,10 +7,13 @@ use std::{
     path::PathBuf,
 };
 
-use crate::{process::LuaSerializerError, rules::Rule, ParserError};
+#[cfg(feature = "serde-config")]
+use crate::process::LuaSerializerError;
+use crate::{rules::Rule, ParserError};
 
 use super::{
     resources::ResourceError,
+    utils::maybe_plural,
     work_item::{WorkData, WorkItem, WorkStatus},
 };
 
@@ -25,6 +28,7 @@ enum ErrorKind {
     },
     InvalidConfiguration {
         path: PathBuf,
+        issues: Vec<String>,
     },
     MultipleConfigurationFound {
         paths: Vec<PathBuf>,
@@ -41,14 +45,17 @@ enum ErrorKind {
         rule_name: String,
         rule_number: Option<usize>,
         error: String,
+        location: Option<RuleErrorLocation>,
     },
     CyclicWork {
         work: Vec<(WorkData, Vec<PathBuf>)>,
     },
+    #[cfg_attr(not(feature = "serde-config"), allow(dead_code))]
     Deserialization {
         message: String,
         data_type: &'static str,
     },
+    #[cfg_attr(not(feature = "serde-config"), allow(dead_code))]
     Serialization {
         message: String,
         data_type: &'static str,
@@ -63,11 +70,23 @@ enum ErrorKind {
     OsStringConversion {
         os_string: OsString,
     },
+    UnsafeRules {
+        rules: Vec<(String, Vec<&'static str>)>,
+    },
     Custom {
         message: Cow<'static, str>,
     },
 }
 
+#[derive(Debug, Clone)]
+struct RuleErrorLocation {
+    node_path: String,
+    code: Option<String>,
+}
+
+/// The maximum number of characters of the statement code shown in rule errors.
+const MAX_RULE_ERROR_CODE_LENGTH: usize = 300;
+
 pub type DarkluaResult<T> = Result<T, DarkluaError>;
 
 #[derive(Debug, Clone)]
@@ -104,6 +123,15 @@ impl DarkluaError {
         })
     }
 
+    pub(crate) fn unsafe_rules(rules: Vec<(&'static str, Vec<&'static str>)>) -> Self {
+        Self::new(ErrorKind::UnsafeRules {
+            rules: rules
+                .into_iter()
+                .map(|(name, properties)| (name.to_owned(), properties))
+                .collect(),
+        })
+    }
+
     pub(crate) fn io_error(path: impl Into<PathBuf>, error: impl Into<String>) -> Self {
         Self::new(ErrorKind::IO {
             path: path.into(),
@@ -116,7 +144,21 @@ impl DarkluaError {
     }
 
     pub(crate) fn invalid_configuration_file(path: impl Into<PathBuf>) -> Self {
-        Self::new(ErrorKind::InvalidConfiguration { path: path.into() })
+        Self::new(ErrorKind::InvalidConfiguration {
+            path: path.into(),
+            issues: Vec::new(),
+        })
+    }
+
+    #[cfg_attr(not(feature = "serde-config"), allow(dead_code))]
+    pub(crate) fn invalid_configuration_content(
+        path: impl Into<PathBuf>,
+        issues: impl IntoIterator<Item = impl ToString>,
+    ) -> Self {
+        Self::new(ErrorKind::InvalidConfiguration {
+            path: path.into(),
+            issues: issues.into_iter().map(|issue| issue.to_string()).collect(),
+        })
     }
 
     pub(crate) fn uncached_work(path: impl Into<PathBuf>) -> Self {
@@ -134,6 +176,7 @@ impl DarkluaError {
             rule_name: rule.get_name().to_owned(),
             rule_number: Some(rule_index),
             error: rule_error.into(),
+            location: None,
         })
     }
 
@@ -147,9 +190,40 @@ impl DarkluaError {
             rule_name: rule.get_name().to_owned(),
             rule_number: None,
             error: rule_error.into(),
+            location: None,
         })
     }
 
+    pub(crate) fn text_processor_error(
+        path: impl Into<PathBuf>,
+        processor_name: &str,
+        error: impl Into<String>,
+    ) -> Self {
+        Self::new(ErrorKind::RuleError {
+            path: path.into(),
+            rule_name: processor_name.to_owned(),
+            rule_number: None,
+            error: error.into(),
+            location: None,
+        })
+    }
+
+    /// Attaches the location of the statement that was processed when a rule failed, with
+    /// the generated code of that statement. Only applies to rule errors.
+    pub(crate) fn with_rule_location(
+        mut self,
+        node_path: impl Into<String>,
+        code: Option<String>,
+    ) -> Self {
+        if let ErrorKind::RuleError { location, .. } = self.kind.as_mut() {
+            *location = Some(RuleErrorLocation {
+                node_path: node_path.into(),
+                code: code.map(|code| truncate_code(code, MAX_RULE_ERROR_CODE_LENGTH)),
+            });
+        }
+        self
+    }
+
     pub(crate) fn cyclic_work(work_left: Vec<&WorkItem>) -> Self {
         let source_left: HashSet<PathBuf> = work_left
             .iter()
@@ -225,11 +299,15 @@ impl From<ResourceError> for DarkluaError {
     fn from(err: ResourceError) -> Self {
         match err {
             ResourceError::NotFound(path) => DarkluaError::resource_not_found(path),
+            ResourceError::InvalidEncoding(path) => {
+                DarkluaError::io_error(path, "file is not valid UTF-8")
+            }
             ResourceError::IO { path, error } => DarkluaError::io_error(path, error),
         }
     }
 }
 
+#[cfg(feature = "serde-config")]
 impl From<json5::Error> for DarkluaError {
     fn from(error: json5::Error) -> Self {
         Self::new(ErrorKind::Deserialization {
@@ -239,6 +317,7 @@ impl From<json5::Error> for DarkluaError {
     }
 }
 
+#[cfg(feature = "serde-config")]
 impl From<serde_json::Error> for DarkluaError {
     fn from(error: serde_json::Error) -> Self {
         Self::new(ErrorKind::Deserialization {
@@ -248,6 +327,7 @@ impl From<serde_json::Error> for DarkluaError {
     }
 }
 
+#[cfg(feature = "data-formats")]
 impl From<serde_yaml::Error> for DarkluaError {
     fn from(error: serde_yaml::Error) -> Self {
         Self::new(ErrorKind::Deserialization {
@@ -257,6 +337,7 @@ impl From<serde_yaml::Error> for DarkluaError {
     }
 }
 
+#[cfg(feature = "data-formats")]
 impl From<toml::de::Error> for DarkluaError {
     fn from(error: toml::de::Error) -> Self {
         Self::new(ErrorKind::Deserialization {
@@ -266,6 +347,7 @@ impl From<toml::de::Error> for DarkluaError {
     }
 }
 
+#[cfg(feature = "data-formats")]
 impl From<toml::ser::Error> for DarkluaError {
     fn from(error: toml::ser::Error) -> Self {
         Self::new(ErrorKind::Serialization {
@@ -275,6 +357,7 @@ impl From<toml::ser::Error> for DarkluaError {
     }
 }
 
+#[cfg(feature = "serde-config")]
 impl From<LuaSerializerError> for DarkluaError {
     fn from(error: LuaSerializerError) -> Self {
         Self::new(ErrorKind::Serialization {
@@ -293,8 +376,20 @@ impl Display for DarkluaError {
             ErrorKind::ResourceNotFound { path } => {
                 write!(f, "unable to find `{}`", path.display())?;
             }
-            ErrorKind::InvalidConfiguration { path } => {
+            ErrorKind::InvalidConfiguration { path, issues } => {
                 write!(f, "invalid configuration file at `{}`", path.display())?;
+
+                if !issues.is_empty() {
+                    write!(
+                        f,
+                        " ({} problem{} found):",
+                        issues.len(),
+                        maybe_plural(issues.len())
+                    )?;
+                    for issue in issues {
+                        write!(f, "\n  {}", issue)?;
+                    }
+                }
             }
             ErrorKind::MultipleConfigurationFound { paths } => {
                 write!(
@@ -318,6 +413,7 @@ impl Display for DarkluaError {
                 rule_name,
                 rule_number,
                 error,
+                location,
             } => {
                 if let Some(rule_number) = rule_number {
                     write!(
@@ -339,6 +435,17 @@ impl Display for DarkluaError {
                         error,
                     )?;
                 }
+
+                if let Some(location) = location {
+                    write!(
+                        f,
+                        "\n  while processing statement at {}",
+                        location.node_path
+                    )?;
+                    if let Some(code) = &location.code {
+                        write!(f, "\n  --> {}", code)?;
+                    }
+                }
             }
             ErrorKind::CyclicWork { work } => {
                 const MAX_PRINTED_WORK: usize = 12;
@@ -410,6 +517,27 @@ impl Display for DarkluaError {
                     os_string.to_string_lossy(),
                 )?;
             }
+            ErrorKind::UnsafeRules { rules } => {
+                write!(
+                    f,
+                    "strict mode does not allow rules configured to change the behavior of the code: {}",
+                    rules
+                        .iter()
+                        .map(|(name, properties)| {
+                            format!(
+                                "`{}` ({})",
+                                name,
+                                properties
+                                    .iter()
+                                    .map(|property| format!("`{}`", property))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?;
+            }
             ErrorKind::Custom { message } => {
                 write!(f, "{}", message)?;
             }
@@ -422,3 +550,10 @@ impl Display for DarkluaError {
         Ok(())
     }
 }
+
+fn truncate_code(code: String, max_length: usize) -> String {
+    match code.char_indices().nth(max_length) {
+        Some((index, _)) => format!("{}...", &code[..index]),
+        None => code,
+    }
+}
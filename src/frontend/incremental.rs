@@ -0,0 +1,285 @@
+use crate::{
+    generator::{DenseGeneratorState, ReadableGeneratorState, SegmentedCode},
+    nodes::Block,
+};
+
+/// Generated code with the segment of each top-level statement, for the generators that
+/// support it.
+#[derive(Debug, Clone)]
+pub(crate) enum SegmentedLua {
+    Dense(SegmentedCode<DenseGeneratorState>),
+    Readable(SegmentedCode<ReadableGeneratorState>),
+}
+
+impl SegmentedLua {
+    pub(crate) fn code(&self) -> &str {
+        match self {
+            Self::Dense(code) => code.code(),
+            Self::Readable(code) => code.code(),
+        }
+    }
+}
+
+/// What is kept from the last time a file was processed, to only process and generate the
+/// top-level statements that changed the next time.
+#[derive(Debug, Clone)]
+pub(crate) struct IncrementalState {
+    original: Block,
+    processed: Block,
+    generated: SegmentedLua,
+}
+
+impl IncrementalState {
+    /// Returns `None` when the top-level statements of the processed block do not match the
+    /// statements of the original block.
+    pub(crate) fn new(original: Block, processed: Block, generated: SegmentedLua) -> Option<Self> {
+        if original.statements_len() == processed.statements_len()
+            && original.get_last_statement().is_some() == processed.get_last_statement().is_some()
+        {
+            Some(Self {
+                original,
+                processed,
+                generated,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn original(&self) -> &Block {
+        &self.original
+    }
+
+    pub(crate) fn processed(&self) -> &Block {
+        &self.processed
+    }
+
+    pub(crate) fn generated(&self) -> &SegmentedLua {
+        &self.generated
+    }
+}
+
+fn items_len(block: &Block) -> usize {
+    block.statements_len() + usize::from(block.get_last_statement().is_some())
+}
+
+/// The range of top-level statements (the last statement included) that changed between two
+/// versions of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BlockChange {
+    prefix: usize,
+    suffix: usize,
+}
+
+impl BlockChange {
+    pub(crate) fn find(previous: &Block, block: &Block) -> Self {
+        let prefix = previous
+            .iter_statements()
+            .zip(block.iter_statements())
+            .take_while(|(previous, statement)| previous == statement)
+            .count();
+
+        let prefix = if prefix == previous.statements_len()
+            && prefix == block.statements_len()
+            && previous.get_last_statement() == block.get_last_statement()
+        {
+            items_len(block)
+        } else {
+            prefix
+        };
+
+        let previous_len = items_len(previous);
+        let len = items_len(block);
+        let max_suffix = previous_len.min(len) - prefix;
+
+        let last_statements = match (previous.get_last_statement(), block.get_last_statement()) {
+            (Some(previous), Some(last)) if previous == last => Some(1),
+            (None, None) => Some(0),
+            _ => None,
+        };
+
+        let suffix = last_statements
+            .map(|last_statements| {
+                last_statements
+                    + previous
+                        .reverse_iter_statements()
+                        .zip(block.reverse_iter_statements())
+                        .take_while(|(previous, statement)| previous == statement)
+                        .count()
+            })
+            .unwrap_or(0)
+            .min(max_suffix);
+
+        Self { prefix, suffix }
+    }
+
+    /// Returns the number of top-level statements that changed in the new block.
+    pub(crate) fn changed_len(&self, block: &Block) -> usize {
+        items_len(block) - self.prefix - self.suffix
+    }
+
+    /// Returns a block with the top-level statements of the new block that changed.
+    pub(crate) fn changed_block(&self, block: &Block) -> Block {
+        let end = items_len(block) - self.suffix;
+        let statements_end = end.min(block.statements_len());
+
+        let statements = block
+            .iter_statements()
+            .skip(self.prefix)
+            .take(statements_end.saturating_sub(self.prefix))
+            .cloned()
+            .collect();
+
+        let last_statement = block
+            .get_last_statement()
+            .filter(|_| (self.prefix..end).contains(&block.statements_len()))
+            .cloned();
+
+        Block::new(statements, last_statement)
+    }
+
+    /// Builds the processed version of the new block from the previous processed block and
+    /// the processed statements that changed. Returns `None` if the processed statements
+    /// that changed do not match the statements of the new block.
+    pub(crate) fn splice(
+        &self,
+        previous_processed: &Block,
+        block: &Block,
+        changed_processed: Block,
+    ) -> Option<Block> {
+        let expected_changed = self.changed_block(block);
+
+        if expected_changed.statements_len() != changed_processed.statements_len()
+            || expected_changed.get_last_statement().is_some()
+                != changed_processed.get_last_statement().is_some()
+        {
+            return None;
+        }
+
+        let previous_len = items_len(previous_processed);
+        let suffix_start = previous_len - self.suffix;
+
+        let mut changed_processed = changed_processed;
+        let changed_last_statement = changed_processed.take_last_statement();
+
+        let statements = previous_processed
+            .iter_statements()
+            .take(self.prefix)
+            .cloned()
+            .chain(changed_processed.take_statements())
+            .chain(
+                previous_processed
+                    .iter_statements()
+                    .skip(suffix_start)
+                    .cloned(),
+            )
+            .collect();
+
+        let last_statement = if block.get_last_statement().is_some() {
+            changed_last_statement.or_else(|| previous_processed.get_last_statement().cloned())
+        } else {
+            None
+        };
+
+        Some(Block::new(statements, last_statement))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Parser;
+
+    fn parse(code: &str) -> Block {
+        Parser::default().parse(code).expect("code should parse")
+    }
+
+    #[test]
+    fn find_change_of_equal_blocks() {
+        let block = parse("local a = 1 return a");
+        let change = BlockChange::find(&block, &block);
+
+        assert_eq!(change.changed_len(&block), 0);
+        assert_eq!(change.changed_block(&block), Block::default());
+    }
+
+    #[test]
+    fn find_changed_statement() {
+        let previous = parse("local a = 1 local b = 2 local c = 3 return c");
+        let block = parse("local a = 1 local b = 4 local c = 3 return c");
+
+        let change = BlockChange::find(&previous, &block);
+
+        assert_eq!(change.changed_len(&block), 1);
+        assert_eq!(change.changed_block(&block), parse("local b = 4"));
+    }
+
+    #[test]
+    fn find_changed_last_statement() {
+        let previous = parse("local a = 1 return a");
+        let block = parse("local a = 1 return a + 1");
+
+        let change = BlockChange::find(&previous, &block);
+
+        assert_eq!(change.changed_block(&block), parse("return a + 1"));
+    }
+
+    #[test]
+    fn find_inserted_statement() {
+        let previous = parse("local a = 1 local c = 3");
+        let block = parse("local a = 1 local b = 2 local c = 3");
+
+        let change = BlockChange::find(&previous, &block);
+
+        assert_eq!(change.changed_block(&block), parse("local b = 2"));
+    }
+
+    #[test]
+    fn find_removed_statement() {
+        let previous = parse("local a = 1 local b = 2 local c = 3");
+        let block = parse("local a = 1 local c = 3");
+
+        let change = BlockChange::find(&previous, &block);
+
+        assert_eq!(change.changed_len(&block), 0);
+        assert_eq!(
+            change.splice(&previous, &block, Block::default()),
+            Some(block)
+        );
+    }
+
+    #[test]
+    fn find_repeated_statements() {
+        let previous = parse("f() f()");
+        let block = parse("f() f() f()");
+
+        let change = BlockChange::find(&previous, &block);
+
+        assert_eq!(change.changed_len(&block), 1);
+        assert_eq!(change.splice(&previous, &block, parse("f()")), Some(block));
+    }
+
+    #[test]
+    fn splice_processed_statements() {
+        let previous = parse("local a = 1 local b = 2 return b");
+        let previous_processed = parse("local a = 10 local b = 20 return b * 10");
+        let block = parse("local a = 1 local b = 3 return b");
+
+        let change = BlockChange::find(&previous, &block);
+
+        assert_eq!(
+            change.splice(&previous_processed, &block, parse("local b = 30")),
+            Some(parse("local a = 10 local b = 30 return b * 10"))
+        );
+    }
+
+    #[test]
+    fn splice_with_unexpected_statements() {
+        let previous = parse("local a = 1 local b = 2");
+        let block = parse("local a = 1 local b = 3");
+
+        let change = BlockChange::find(&previous, &block);
+
+        assert_eq!(change.splice(&previous, &block, Block::default()), None);
+    }
+}
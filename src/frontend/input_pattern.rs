@@ -0,0 +1,438 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use wax::{Glob, Pattern};
+
+use crate::utils::{normalize_path, relative_path};
+
+use super::{DarkluaError, DarkluaResult, Resources};
+
+const GLOB_CHARACTERS: &[char] = &['*', '?', '[', '{', '<'];
+
+const NEGATION_START: &str = "!(";
+
+/// Returns `true` when the input path contains glob syntax (like `src/**/*.lua`).
+pub(crate) fn is_input_pattern(input: &Path) -> bool {
+    input
+        .to_str()
+        .map(|input| input.contains(GLOB_CHARACTERS) || input.contains(NEGATION_START))
+        .unwrap_or_default()
+}
+
+/// Returns the directory where the files of the input are collected: the input itself, or
+/// the literal directory prefix of a glob (like `src` for `src/**/*.lua`).
+pub(crate) fn input_root(input: &Path) -> PathBuf {
+    if is_input_pattern(input) {
+        if let Ok(pattern) = InputPattern::new(input) {
+            return pattern.root;
+        }
+    }
+    input.to_path_buf()
+}
+
+fn parse_glob(expression: &str) -> DarkluaResult<Glob<'static>> {
+    parse_glob_of(expression, expression)
+}
+
+/// Parses a glob derived from the given expression, reporting errors with the expression.
+fn parse_glob_of(glob: &str, expression: &str) -> DarkluaResult<Glob<'static>> {
+    Glob::new(glob).map(Glob::into_owned).map_err(|err| {
+        DarkluaError::custom(format!("invalid glob pattern `{}`: {}", expression, err))
+    })
+}
+
+/// Splits a glob containing an extglob negation (like `!(*.spec)` in `src/**/!(*.spec).lua`),
+/// which wax does not support, into the glob with `*` in place of the negation and the glob
+/// with the negated patterns in place of the negation. A path matches the original glob when
+/// it matches the first glob and not the second one.
+fn split_negation(expression: &str) -> DarkluaResult<Option<(String, String)>> {
+    let Some(start) = expression.find(NEGATION_START) else {
+        return Ok(None);
+    };
+    let content_start = start + NEGATION_START.len();
+
+    let mut depth = 0;
+    let mut alternatives = Vec::new();
+    let mut alternative_start = content_start;
+    let mut end = None;
+
+    for (index, character) in expression[content_start..].char_indices() {
+        let index = content_start + index;
+        match character {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                alternatives.push(&expression[alternative_start..index]);
+                end = Some(index);
+                break;
+            }
+            ')' => depth -= 1,
+            '|' if depth == 0 => {
+                alternatives.push(&expression[alternative_start..index]);
+                alternative_start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    let end = end.ok_or_else(|| {
+        DarkluaError::custom(format!(
+            "invalid glob pattern `{}`: missing `)` to close `!(`",
+            expression
+        ))
+    })?;
+
+    let (before, after) = (&expression[..start], &expression[end + 1..]);
+
+    if alternatives
+        .iter()
+        .chain(std::iter::once(&after))
+        .any(|part| part.contains(NEGATION_START))
+    {
+        return Err(DarkluaError::custom(format!(
+            "invalid glob pattern `{}`: only one `!(...)` negation is supported (use \
+            `--exclude` to skip other files)",
+            expression
+        )));
+    }
+
+    let negated = if alternatives.len() == 1 {
+        alternatives[0].to_owned()
+    } else {
+        format!("{{{}}}", alternatives.join(","))
+    };
+
+    Ok(Some((
+        format!("{}*{}", before, after),
+        format!("{}{}{}", before, negated, after),
+    )))
+}
+
+/// A glob given as the input path. The pattern is relative to the current directory and
+/// only the directory given by its literal prefix is searched.
+#[derive(Debug)]
+pub(crate) struct InputPattern {
+    expression: String,
+    root: PathBuf,
+    glob: Glob<'static>,
+    negation: Option<(PathBuf, Glob<'static>)>,
+}
+
+impl InputPattern {
+    pub(crate) fn new(input: &Path) -> DarkluaResult<Self> {
+        let expression = input.to_str().ok_or_else(|| {
+            DarkluaError::custom(format!(
+                "invalid glob pattern `{}` (it is not valid UTF-8)",
+                input.display()
+            ))
+        })?;
+
+        let (glob, negation) = match split_negation(expression)? {
+            Some((glob, negated_glob)) => {
+                let (negated_root, negated_glob) =
+                    parse_glob_of(&negated_glob, expression)?.partition();
+                (
+                    parse_glob_of(&glob, expression)?,
+                    Some((normalize_path(negated_root), negated_glob)),
+                )
+            }
+            None => (parse_glob(expression)?, None),
+        };
+        let (root, glob) = glob.partition();
+        let root = normalize_path(root);
+
+        Ok(Self {
+            expression: expression.to_owned(),
+            root: if root == Path::new("") {
+                PathBuf::from(".")
+            } else {
+                root
+            },
+            glob,
+            negation,
+        })
+    }
+
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the Lua files matched by the pattern, sorted and without duplicates. A file
+    /// is matched when its path, or the path of one of its parent directories, matches the
+    /// pattern. Fails when no file is matched.
+    pub(crate) fn find(
+        &self,
+        resources: &Resources,
+        follow_symlinks: bool,
+        excludes: &ExcludePatterns,
+    ) -> DarkluaResult<Vec<PathBuf>> {
+        let is_excluded = |path: &Path| excludes.is_excluded(path);
+
+        let files: BTreeSet<_> = resources
+            .collect_work_excluding(&self.root, follow_symlinks, &is_excluded)
+            .map(normalize_path)
+            .filter(|source| self.is_match(source))
+            .collect();
+
+        if files.is_empty() {
+            return Err(DarkluaError::custom(if excludes.is_empty() {
+                format!("no Lua files match the input pattern `{}`", self.expression)
+            } else {
+                format!(
+                    "no Lua files match the input pattern `{}` outside of the excluded paths",
+                    self.expression
+                )
+            }));
+        }
+
+        Ok(files.into_iter().collect())
+    }
+
+    fn is_match(&self, source: &Path) -> bool {
+        let Some(relative) = relative_path(source, &self.root) else {
+            return false;
+        };
+
+        relative
+            .ancestors()
+            .filter(|path| *path != Path::new(""))
+            .any(|path| self.glob.is_match(path) && !self.is_negated(&self.root.join(path)))
+    }
+
+    fn is_negated(&self, path: &Path) -> bool {
+        self.negation.as_ref().is_some_and(|(root, glob)| {
+            relative_path(path, root).is_some_and(|relative| glob.is_match(&*relative))
+        })
+    }
+}
+
+/// The glob patterns of the files and directories to skip when collecting the files of the
+/// input. The patterns are matched against the paths relative to the input root (the input
+/// directory, or the literal prefix of a glob input).
+#[derive(Debug, Default)]
+pub(crate) struct ExcludePatterns {
+    root: PathBuf,
+    globs: Vec<Glob<'static>>,
+}
+
+impl ExcludePatterns {
+    pub(crate) fn new(root: impl Into<PathBuf>, patterns: &[String]) -> DarkluaResult<Self> {
+        Ok(Self {
+            root: root.into(),
+            globs: patterns
+                .iter()
+                .map(|pattern| parse_glob(pattern))
+                .collect::<DarkluaResult<_>>()?,
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.globs.is_empty()
+    }
+
+    pub(crate) fn is_excluded(&self, path: &Path) -> bool {
+        if self.globs.is_empty() {
+            return false;
+        }
+
+        match relative_path(path, &self.root) {
+            Some(relative) if relative != Path::new("") => self
+                .globs
+                .iter()
+                .any(|glob| glob.is_match(relative.as_path())),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resources() -> Resources {
+        let resources = Resources::from_memory();
+        for path in [
+            "src/init.lua",
+            "src/init.spec.lua",
+            "src/client/main.luau",
+            "src/client/main.spec.luau",
+            "src/shared/util.lua",
+            "src/shared/README.md",
+            "lib/other.lua",
+        ] {
+            resources.write(path, "return nil").unwrap();
+        }
+        resources
+    }
+
+    fn find(pattern: &str, excludes: &[&str]) -> DarkluaResult<Vec<PathBuf>> {
+        let excludes: Vec<_> = excludes.iter().map(ToString::to_string).collect();
+        let pattern = InputPattern::new(Path::new(pattern))?;
+        let excludes = ExcludePatterns::new(pattern.root(), &excludes)?;
+        pattern.find(&resources(), false, &excludes)
+    }
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn detect_input_patterns() {
+        assert!(is_input_pattern(Path::new("src/**/*.lua")));
+        assert!(is_input_pattern(Path::new("src/{client,shared}")));
+        assert!(!is_input_pattern(Path::new("src/init.lua")));
+        assert!(!is_input_pattern(Path::new("src")));
+    }
+
+    #[test]
+    fn input_root_of_pattern_is_its_literal_prefix() {
+        pretty_assertions::assert_eq!(
+            input_root(Path::new("src/client/**/*.lua")),
+            PathBuf::from("src/client")
+        );
+    }
+
+    #[test]
+    fn input_root_of_path_is_the_path() {
+        pretty_assertions::assert_eq!(input_root(Path::new("src")), PathBuf::from("src"));
+    }
+
+    #[test]
+    fn find_files_with_extension() {
+        pretty_assertions::assert_eq!(
+            find("src/**/*.lua", &[]).unwrap(),
+            paths(&["src/init.lua", "src/init.spec.lua", "src/shared/util.lua"])
+        );
+    }
+
+    #[test]
+    fn find_lua_files_in_matched_directories() {
+        pretty_assertions::assert_eq!(
+            find("src/*", &[]).unwrap(),
+            paths(&[
+                "src/client/main.luau",
+                "src/client/main.spec.luau",
+                "src/init.lua",
+                "src/init.spec.lua",
+                "src/shared/util.lua",
+            ])
+        );
+    }
+
+    #[test]
+    fn find_overlapping_alternatives_once() {
+        pretty_assertions::assert_eq!(
+            find("src/{shared/*.lua,shared/util.lua,shared}", &[]).unwrap(),
+            paths(&["src/shared/util.lua"])
+        );
+    }
+
+    #[test]
+    fn find_files_without_excluded_files() {
+        pretty_assertions::assert_eq!(
+            find("src/**/*.{lua,luau}", &["**/*.spec.{lua,luau}"]).unwrap(),
+            paths(&[
+                "src/client/main.luau",
+                "src/init.lua",
+                "src/shared/util.lua"
+            ])
+        );
+    }
+
+    #[test]
+    fn find_files_without_excluded_directories() {
+        pretty_assertions::assert_eq!(
+            find("src/**/*.{lua,luau}", &["client", "shared"]).unwrap(),
+            paths(&["src/init.lua", "src/init.spec.lua"])
+        );
+    }
+
+    #[test]
+    fn exclude_patterns_are_relative_to_the_input_root() {
+        pretty_assertions::assert_eq!(
+            find("src/**/*.lua", &["src/**"]).unwrap(),
+            paths(&["src/init.lua", "src/init.spec.lua", "src/shared/util.lua"])
+        );
+    }
+
+    #[test]
+    fn input_root_of_pattern_without_prefix_is_the_current_directory() {
+        pretty_assertions::assert_eq!(input_root(Path::new("*/other.lua")), PathBuf::from("."));
+    }
+
+    #[test]
+    fn find_files_from_current_directory() {
+        pretty_assertions::assert_eq!(find("*/other.lua", &[]).unwrap(), paths(&["lib/other.lua"]));
+    }
+
+    #[test]
+    fn pattern_without_matches_is_an_error() {
+        pretty_assertions::assert_eq!(
+            find("src/**/*.txt", &[]).unwrap_err().to_string(),
+            "no Lua files match the input pattern `src/**/*.txt`"
+        );
+    }
+
+    #[test]
+    fn pattern_with_only_excluded_matches_is_an_error() {
+        pretty_assertions::assert_eq!(
+            find("src/*.lua", &["*"]).unwrap_err().to_string(),
+            "no Lua files match the input pattern `src/*.lua` outside of the excluded paths"
+        );
+    }
+
+    #[test]
+    fn detect_input_pattern_with_negation() {
+        assert!(is_input_pattern(Path::new("src/!(init).lua")));
+    }
+
+    #[test]
+    fn find_files_without_negated_files() {
+        pretty_assertions::assert_eq!(
+            find("src/**/!(*.spec).lua", &[]).unwrap(),
+            paths(&["src/init.lua", "src/shared/util.lua"])
+        );
+    }
+
+    #[test]
+    fn find_files_without_negated_alternatives() {
+        pretty_assertions::assert_eq!(
+            find("src/**/!(*.spec|util).{lua,luau}", &[]).unwrap(),
+            paths(&["src/client/main.luau", "src/init.lua"])
+        );
+    }
+
+    #[test]
+    fn find_files_without_negated_directories() {
+        pretty_assertions::assert_eq!(
+            find("src/!(client)", &[]).unwrap(),
+            paths(&["src/init.lua", "src/init.spec.lua", "src/shared/util.lua"])
+        );
+    }
+
+    #[test]
+    fn pattern_with_multiple_negations_is_an_error() {
+        pretty_assertions::assert_eq!(
+            find("src/!(client)/!(*.spec).lua", &[])
+                .unwrap_err()
+                .to_string(),
+            "invalid glob pattern `src/!(client)/!(*.spec).lua`: only one `!(...)` negation is \
+            supported (use `--exclude` to skip other files)"
+        );
+    }
+
+    #[test]
+    fn pattern_with_unclosed_negation_is_an_error() {
+        pretty_assertions::assert_eq!(
+            find("src/!(*.spec.lua", &[]).unwrap_err().to_string(),
+            "invalid glob pattern `src/!(*.spec.lua`: missing `)` to close `!(`"
+        );
+    }
+
+    #[test]
+    fn invalid_pattern_is_an_error() {
+        pretty_assertions::assert_eq!(
+            find("src/**/{a", &[]).unwrap_err().to_string(),
+            "invalid glob pattern `src/**/{a`: failed to parse glob expression"
+        );
+    }
+}
@@ -1,7 +1,25 @@
 mod configuration;
+#[cfg(feature = "serde-config")]
+mod configuration_extends;
+#[cfg(feature = "serde-config")]
+mod configuration_schema;
+mod content_hash;
+mod data_module;
+mod dependency_graph;
+mod doctor;
 mod error;
+mod incremental;
+mod input_pattern;
 mod options;
+mod output_checks;
+mod processed_marker;
 mod resources;
+mod rules_report;
+mod self_extract;
+mod session;
+mod stages;
+mod staging;
+mod target_lowering;
 mod utils;
 mod work_cache;
 mod work_item;
@@ -9,22 +27,50 @@ mod worker;
 mod worker_tree;
 
 pub use configuration::{BundleConfiguration, Configuration, GeneratorParameters};
+#[cfg(feature = "serde-config")]
+pub use configuration_extends::read_resolved_configuration;
+#[cfg(feature = "serde-config")]
+pub use configuration_schema::{
+    get_configuration_schema, ConfigurationIssue, CONFIGURATION_SCHEMA_VERSION,
+};
+pub use content_hash::{ArtifactTemplate, HashAlgorithm, OutputTemplate};
+pub use dependency_graph::{DependencyGraph, DependencyGraphOptions, UnresolvedRequire};
+pub use doctor::{doctor, DoctorReport, FileIssue, FileNotes, RuleCompatibility};
 pub use error::{DarkluaError, DarkluaResult};
-pub use options::Options;
+pub use options::{DeprecationPolicy, Options};
+pub use output_checks::{check_output, OutputCheck, OutputViolation};
+pub use processed_marker::ReprocessPolicy;
 pub use resources::Resources;
+pub use rules_report::{
+    describe_rules, run_rule_example, PropertyDescription, PropertySafety, RuleDescription,
+    RuleExampleOutput,
+};
+#[cfg(feature = "serde-config")]
 use serde::Serialize;
+pub use session::{ProcessOutcome, ProcessingSession};
+pub use stages::{StageConfiguration, StageSelection};
+pub use staging::AtomicMode;
+use std::path::{Path, PathBuf};
 use work_item::WorkItem;
 use worker::Worker;
 pub use worker_tree::WorkerTree;
 
+#[cfg(feature = "serde-config")]
 use crate::{
-    generator::{DenseLuaGenerator, LuaGenerator},
+    generator::DenseLuaGenerator,
     nodes::{Block, ReturnStatement},
     process::to_expression,
-    utils::normalize_path,
+};
+use crate::{
+    generator::{LuaGenerator, TokenBasedLuaGenerator},
+    nodes::Expression,
+    process::{apply_node_edits, NodeEdit},
+    utils::{log, normalize_path},
+    Parser,
 };
 
 /// Convert serializable data into a Lua module
+#[cfg(feature = "serde-config")]
 pub fn convert_data(value: impl Serialize) -> Result<String, DarkluaError> {
     let expression = to_expression(&value).map_err(DarkluaError::from)?;
 
@@ -36,6 +82,11 @@ pub fn convert_data(value: impl Serialize) -> Result<String, DarkluaError> {
     Ok(generator.into_string())
 }
 
+/// Generate the Lua code of a single expression, without any wrapping statement
+pub fn generate_expression(expression: &Expression, generator: &GeneratorParameters) -> String {
+    generator.generate_expression(expression)
+}
+
 pub fn process(resources: &Resources, options: Options) -> DarkluaResult<WorkerTree> {
     let mut worker_tree = WorkerTree::default();
 
@@ -44,3 +95,89 @@ pub fn process(resources: &Resources, options: Options) -> DarkluaResult<WorkerT
 
     Ok(worker_tree)
 }
+
+/// Processes Lua code with the given configuration and returns the generated code. The code
+/// is processed in memory, without reading or writing any file.
+pub fn process_code(code: &str, configuration: Configuration) -> DarkluaResult<String> {
+    const INPUT: &str = "input.lua";
+    const OUTPUT: &str = "output.lua";
+
+    let resources = Resources::from_memory();
+    resources.write(INPUT, code)?;
+
+    process(
+        &resources,
+        Options::new(INPUT)
+            .with_output(OUTPUT)
+            .with_configuration(configuration),
+    )?
+    .result()
+    .map_err(|errors| {
+        errors
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| DarkluaError::custom("unable to process code"))
+    })?;
+
+    Ok(resources.get(OUTPUT)?)
+}
+
+/// Runs the output checks of the configuration against the Lua files found at the input
+/// of the options, without processing them. Returns each file that does not pass the
+/// checks with its violations.
+pub fn check_output_files(
+    resources: &Resources,
+    mut options: Options,
+) -> DarkluaResult<Vec<(PathBuf, Vec<OutputViolation>)>> {
+    let mut worker = Worker::new(resources);
+    worker.setup_worker(&mut options)?;
+
+    let checks = worker.configuration().checks();
+    if checks.is_empty() {
+        log::warn!("the configuration does not define any output check");
+    }
+
+    let mut files: Vec<_> = resources.collect_work(options.input()).collect();
+    files.sort();
+
+    let mut failures = Vec::new();
+    for path in files {
+        let code = resources.get(&path)?;
+        let violations = check_output(checks, &code);
+
+        if !violations.is_empty() {
+            failures.push((path, violations));
+        }
+    }
+
+    Ok(failures)
+}
+
+/// Applies node edits to a Lua file and returns the edited code. The file is parsed with its
+/// tokens so that the code outside of the edited nodes keeps its formatting. No edit is
+/// applied if one of them is invalid.
+pub fn apply_edits(
+    resources: &Resources,
+    path: impl AsRef<Path>,
+    edits: &[NodeEdit],
+) -> DarkluaResult<String> {
+    let path = path.as_ref();
+    let code = resources.get(path)?;
+
+    let parser = Parser::default().preserve_tokens();
+    let mut block = parser
+        .parse(&code)
+        .map_err(|err| DarkluaError::parser_error(path, err))?;
+
+    apply_node_edits(&mut block, edits, &parser).map_err(|err| {
+        DarkluaError::custom(format!(
+            "unable to apply edits to `{}`: {}",
+            path.display(),
+            err
+        ))
+    })?;
+
+    let mut generator = TokenBasedLuaGenerator::new(&code);
+    generator.write_block(&block);
+    Ok(generator.into_string())
+}
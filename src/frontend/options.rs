@@ -1,29 +1,131 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::process::EnvironmentTarget;
+use crate::rules::{TextPostprocessor, TextPreprocessor};
+use crate::utils::normalize_path;
+use crate::RawMarkers;
 
 use super::configuration::{Configuration, GeneratorParameters};
+use super::content_hash::{ArtifactTemplate, HashAlgorithm, OutputTemplate};
+use super::input_pattern::input_root;
+use super::processed_marker::ReprocessPolicy;
+use super::stages::StageSelection;
+use super::staging::AtomicMode;
+
+/// Defines what happens when a configuration file refers to a rule with a deprecated name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeprecationPolicy {
+    /// Log a warning for each deprecated name.
+    #[default]
+    Warn,
+    /// Accept deprecated names silently.
+    Allow,
+    /// Reject configuration files that use deprecated names.
+    Deny,
+}
 
 #[derive(Debug)]
 pub struct Options {
     input: PathBuf,
+    files: Option<Vec<PathBuf>>,
+    excludes: Vec<String>,
     config_path: Option<PathBuf>,
     config: Option<Configuration>,
     config_generator_override: Option<GeneratorParameters>,
     output: Option<PathBuf>,
     fail_fast: bool,
+    atomic: Option<AtomicMode>,
+    keep_staging: bool,
+    staging_directory: Option<PathBuf>,
+    data_fast_path: bool,
+    strict: bool,
+    follow_symlinks: bool,
+    formatting_rules_only: bool,
+    check: bool,
+    incremental: bool,
+    verify_incremental: bool,
+    target: Option<EnvironmentTarget>,
+    generated_name_prefix: Option<String>,
+    raw_markers: Option<RawMarkers>,
+    mark_processed: bool,
+    reprocess_policy: ReprocessPolicy,
+    deprecation_policy: DeprecationPolicy,
+    max_output_size: Option<u64>,
+    self_extract: bool,
+    rule_timeout: Option<Duration>,
+    hash_algorithm: Option<HashAlgorithm>,
+    hash_manifest: Option<PathBuf>,
+    output_template: Option<OutputTemplate>,
+    artifacts: Vec<(String, ArtifactTemplate)>,
+    stage: Option<StageSelection>,
+    cache_directory: Option<PathBuf>,
+    rule_profiling: bool,
+    roblox_paths: HashMap<PathBuf, String>,
+    preprocessors: Vec<Box<dyn TextPreprocessor>>,
+    postprocessors: Vec<Box<dyn TextPostprocessor>>,
 }
 
 impl Options {
     pub fn new(input: impl Into<PathBuf>) -> Self {
         Self {
             input: input.into(),
+            files: None,
+            excludes: Vec::new(),
             config_path: None,
             config: None,
             output: None,
             fail_fast: false,
+            atomic: None,
+            keep_staging: false,
+            staging_directory: None,
+            data_fast_path: false,
+            strict: false,
+            follow_symlinks: false,
+            formatting_rules_only: false,
+            check: false,
+            incremental: false,
+            verify_incremental: false,
+            target: None,
+            generated_name_prefix: None,
+            raw_markers: None,
+            mark_processed: false,
+            reprocess_policy: ReprocessPolicy::default(),
+            deprecation_policy: DeprecationPolicy::default(),
+            max_output_size: None,
+            self_extract: false,
+            rule_timeout: None,
+            hash_algorithm: None,
+            hash_manifest: None,
+            output_template: None,
+            artifacts: Vec::new(),
+            stage: None,
+            cache_directory: None,
+            rule_profiling: false,
+            roblox_paths: HashMap::new(),
+            preprocessors: Vec::new(),
+            postprocessors: Vec::new(),
             config_generator_override: None,
         }
     }
 
+    /// Processes only the given files instead of collecting the files from the input path.
+    /// When an output is defined, each output path is computed from the path of the file
+    /// relative to the input path.
+    pub fn with_files(mut self, files: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        self.files = Some(files.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Skips the files matching the given glob pattern (like `**/*.spec.lua`) when
+    /// collecting the files from the input path. The pattern is relative to the current
+    /// directory.
+    pub fn with_exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.excludes.push(pattern.into());
+        self
+    }
+
     pub fn with_configuration_at(mut self, config: impl Into<PathBuf>) -> Self {
         self.config_path = Some(config.into());
         self
@@ -44,6 +146,226 @@ impl Options {
         self
     }
 
+    /// Writes the generated files in a staging directory, and moves them to their
+    /// destination at the end of the process. With [`AtomicMode::All`], nothing is written
+    /// to the destination when a file fails.
+    pub fn with_atomic_mode(mut self, mode: AtomicMode) -> Self {
+        self.atomic = Some(mode);
+        self
+    }
+
+    /// Keeps the staging directory of an atomic process instead of removing it.
+    pub fn keep_staging(mut self) -> Self {
+        self.keep_staging = true;
+        self
+    }
+
+    /// Sets where the generated files of an atomic process are staged. By default, a
+    /// `.darklua-staging-<process id>` directory is created in the current directory.
+    pub fn with_staging_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.staging_directory = Some(directory.into());
+        self
+    }
+
+    /// Enables the data module fast path, even if the configuration does not.
+    pub fn with_data_fast_path(mut self) -> Self {
+        self.data_fast_path = true;
+        self
+    }
+
+    /// Fails before processing any file when a rule is configured with properties that can
+    /// change the behavior of the code, even if the configuration does not enable strict
+    /// mode.
+    pub fn with_strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Follows the symlinks found in the input directory. Each file is processed once,
+    /// even if several symlinks lead to it.
+    pub fn with_follow_symlinks(mut self) -> Self {
+        self.follow_symlinks = true;
+        self
+    }
+
+    /// Only applies the rules and text processors of the configuration that change how the
+    /// code is formatted, without bundling.
+    pub fn with_formatting_rules_only(mut self) -> Self {
+        self.formatting_rules_only = true;
+        self
+    }
+
+    /// Compares the generated code with the current content of each output file instead of
+    /// writing it. Files that would be modified are reported as errors.
+    pub fn with_check(mut self) -> Self {
+        self.check = true;
+        self
+    }
+
+    /// Keeps the parsed and generated code of each file, so that when a file is processed
+    /// again by the same [`WorkerTree`](crate::WorkerTree) (like in watch mode), only its
+    /// top-level statements that changed are processed and generated again. This is only
+    /// done when every rule is statement-local and the generator is not `retain_lines`.
+    pub fn with_incremental_generation(mut self) -> Self {
+        self.incremental = true;
+        self
+    }
+
+    /// Same as `with_incremental_generation`, but each incremental result is also compared
+    /// with the result of processing the whole file. When they do not match, a warning is
+    /// logged and the result of the whole file is used. This is always done in debug builds.
+    pub fn with_incremental_verification(mut self) -> Self {
+        self.incremental = true;
+        self.verify_incremental = true;
+        self
+    }
+
+    /// Overrides the environment target of the configuration.
+    pub fn with_target(mut self, target: EnvironmentTarget) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Overrides the prefix of the names generated by rules.
+    pub fn with_generated_name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.generated_name_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Overrides the markers of the regions copied to the output without being parsed.
+    pub fn with_raw_markers(mut self, markers: RawMarkers) -> Self {
+        self.raw_markers = Some(markers);
+        self
+    }
+
+    /// Appends a comment at the end of each generated file to record the version of darklua,
+    /// a hash of the configuration and the rules that were applied. When a file with this
+    /// marker is processed again, the [`ReprocessPolicy`] defines what happens.
+    pub fn with_processed_marker(mut self) -> Self {
+        self.mark_processed = true;
+        self
+    }
+
+    /// Defines what happens with files that were already processed (files that end with a
+    /// processed marker). By default, these files are skipped.
+    pub fn with_reprocess_policy(mut self, policy: ReprocessPolicy) -> Self {
+        self.reprocess_policy = policy;
+        self
+    }
+
+    /// Defines what happens when the configuration file uses deprecated rule names. By
+    /// default, a warning is logged for each deprecated name.
+    pub fn with_deprecation_policy(mut self, policy: DeprecationPolicy) -> Self {
+        self.deprecation_policy = policy;
+        self
+    }
+
+    /// Fails the processing of files when their generated code is larger than the given
+    /// number of bytes.
+    pub fn with_max_output_size(mut self, max_size: u64) -> Self {
+        self.max_output_size = Some(max_size);
+        self
+    }
+
+    /// Writes each generated file as a self-extracting file: the code is compressed into a
+    /// string and a small decompressor loads it when the file runs. This fails for the `luau`
+    /// and `roblox` targets, which can not load code from a string.
+    pub fn with_self_extract(mut self) -> Self {
+        self.self_extract = true;
+        self
+    }
+
+    /// Sets the time budget of each rule applied to a file. When a rule runs for longer, its
+    /// changes to the file are discarded and the next rules are applied. This will override
+    /// the time budget given by the configuration file.
+    pub fn with_rule_timeout(mut self, timeout: Duration) -> Self {
+        self.rule_timeout = Some(timeout);
+        self
+    }
+
+    /// Computes the digest of the generated code of each file with the given algorithm. The
+    /// digests are available from the outcomes of a
+    /// [`ProcessingSession`](crate::ProcessingSession).
+    pub fn with_hash_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = Some(algorithm);
+        self
+    }
+
+    /// Writes a file listing the digest of the generated code of each file (one
+    /// `relative/path.lua: <digest>` line per file, relative to the output path) once all
+    /// the files are processed. Digests use SHA-256 unless another algorithm is given with
+    /// [`with_hash_algorithm`](Self::with_hash_algorithm).
+    pub fn with_hash_manifest(mut self, path: impl Into<PathBuf>) -> Self {
+        self.hash_manifest = Some(path.into());
+        self
+    }
+
+    /// Defines the file name of each generated file. When the template uses the digest of
+    /// the generated code, the code is generated before its destination is known.
+    pub fn with_output_template(mut self, template: OutputTemplate) -> Self {
+        self.output_template = Some(template);
+        self
+    }
+
+    /// Writes the artifacts with the given name emitted by rules to the path computed from
+    /// the template. This overrides the template given by the configuration for the same
+    /// artifact name.
+    pub fn with_artifact(mut self, name: impl Into<String>, template: ArtifactTemplate) -> Self {
+        self.artifacts.push((name.into(), template));
+        self
+    }
+
+    /// Selects which stages of the configuration are run. By default, every stage runs in
+    /// sequence.
+    pub fn with_stage(mut self, stage: StageSelection) -> Self {
+        self.stage = Some(stage);
+        self
+    }
+
+    /// Overrides the directory where the state shared between stages is written.
+    pub fn with_cache_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.cache_directory = Some(directory.into());
+        self
+    }
+
+    /// Measures how long each rule takes to apply on each file. The durations are available
+    /// from the outcomes of a [`ProcessingSession`](crate::ProcessingSession).
+    pub fn with_rule_profiling(mut self) -> Self {
+        self.rule_profiling = true;
+        self
+    }
+
+    /// Associates files with the path of the Roblox instance they create (like
+    /// `ReplicatedStorage.Shared.Util`), which rules can use instead of the file path.
+    pub fn with_roblox_paths(
+        mut self,
+        roblox_paths: impl IntoIterator<Item = (impl Into<PathBuf>, impl Into<String>)>,
+    ) -> Self {
+        self.roblox_paths
+            .extend(roblox_paths.into_iter().map(|(path, roblox_path)| {
+                let path: PathBuf = path.into();
+                (normalize_path(path), roblox_path.into())
+            }));
+        self
+    }
+
+    /// Adds a text transformation that runs on the source code before parsing it, after the
+    /// preprocessors of the configuration.
+    pub fn with_preprocessor(mut self, preprocessor: impl Into<Box<dyn TextPreprocessor>>) -> Self {
+        self.preprocessors.push(preprocessor.into());
+        self
+    }
+
+    /// Adds a text transformation that runs on the generated code, after the postprocessors
+    /// of the configuration.
+    pub fn with_postprocessor(
+        mut self,
+        postprocessor: impl Into<Box<dyn TextPostprocessor>>,
+    ) -> Self {
+        self.postprocessors.push(postprocessor.into());
+        self
+    }
+
     pub fn with_generator_override(mut self, generator: impl Into<GeneratorParameters>) -> Self {
         self.config_generator_override = Some(generator.into());
         self
@@ -53,6 +375,21 @@ impl Options {
         &self.input
     }
 
+    /// Returns the directory where the files of the input are collected. It is the input
+    /// path, or the literal directory prefix when the input is a glob pattern (like `src`
+    /// for `src/**/*.lua`).
+    pub fn input_root(&self) -> PathBuf {
+        input_root(&self.input)
+    }
+
+    pub fn files(&self) -> Option<&[PathBuf]> {
+        self.files.as_deref()
+    }
+
+    pub fn excludes(&self) -> &[String] {
+        &self.excludes
+    }
+
     pub fn output(&self) -> Option<&Path> {
         self.output.as_ref().map(AsRef::as_ref)
     }
@@ -61,6 +398,121 @@ impl Options {
         self.fail_fast
     }
 
+    pub fn atomic_mode(&self) -> Option<AtomicMode> {
+        self.atomic
+    }
+
+    pub fn should_keep_staging(&self) -> bool {
+        self.keep_staging
+    }
+
+    pub fn staging_directory(&self) -> Option<&Path> {
+        self.staging_directory.as_deref()
+    }
+
+    /// The algorithm used to compute the digest of the generated code. When no algorithm is
+    /// given, SHA-256 is used if the hash manifest or the output template needs a digest.
+    pub fn hash_algorithm(&self) -> Option<HashAlgorithm> {
+        self.hash_algorithm.or_else(|| {
+            let needs_digest = self.hash_manifest.is_some()
+                || self
+                    .output_template
+                    .as_ref()
+                    .is_some_and(OutputTemplate::uses_content_hash);
+            needs_digest.then(HashAlgorithm::default)
+        })
+    }
+
+    pub fn hash_manifest(&self) -> Option<&Path> {
+        self.hash_manifest.as_deref()
+    }
+
+    pub fn output_template(&self) -> Option<&OutputTemplate> {
+        self.output_template.as_ref()
+    }
+
+    pub fn artifacts(&self) -> impl Iterator<Item = (&str, &ArtifactTemplate)> {
+        self.artifacts
+            .iter()
+            .map(|(name, template)| (name.as_str(), template))
+    }
+
+    pub fn stage(&self) -> Option<&StageSelection> {
+        self.stage.as_ref()
+    }
+
+    pub fn cache_directory(&self) -> Option<&Path> {
+        self.cache_directory.as_deref()
+    }
+
+    pub fn should_use_data_fast_path(&self) -> bool {
+        self.data_fast_path
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn should_follow_symlinks(&self) -> bool {
+        self.follow_symlinks
+    }
+
+    pub fn should_use_formatting_rules_only(&self) -> bool {
+        self.formatting_rules_only
+    }
+
+    pub fn should_check(&self) -> bool {
+        self.check
+    }
+
+    pub fn should_use_incremental_generation(&self) -> bool {
+        self.incremental
+    }
+
+    pub fn should_verify_incremental_generation(&self) -> bool {
+        self.verify_incremental || cfg!(debug_assertions)
+    }
+
+    pub fn target(&self) -> Option<EnvironmentTarget> {
+        self.target
+    }
+
+    pub fn generated_name_prefix(&self) -> Option<&str> {
+        self.generated_name_prefix.as_deref()
+    }
+
+    pub fn raw_markers(&self) -> Option<&RawMarkers> {
+        self.raw_markers.as_ref()
+    }
+
+    pub fn should_mark_processed(&self) -> bool {
+        self.mark_processed
+    }
+
+    pub fn reprocess_policy(&self) -> ReprocessPolicy {
+        self.reprocess_policy
+    }
+
+    pub fn deprecation_policy(&self) -> DeprecationPolicy {
+        self.deprecation_policy
+    }
+
+    pub fn max_output_size(&self) -> Option<u64> {
+        self.max_output_size
+    }
+
+    pub fn should_self_extract(&self) -> bool {
+        self.self_extract
+    }
+
+    pub fn rule_timeout(&self) -> Option<Duration> {
+        self.rule_timeout
+    }
+
+    pub fn should_profile_rules(&self) -> bool {
+        self.rule_profiling
+    }
+
     pub fn configuration_path(&self) -> Option<&Path> {
         self.config_path.as_ref().map(AsRef::as_ref)
     }
@@ -72,4 +524,16 @@ impl Options {
     pub fn take_configuration(&mut self) -> Option<Configuration> {
         self.config.take()
     }
+
+    pub(crate) fn take_roblox_paths(&mut self) -> HashMap<PathBuf, String> {
+        std::mem::take(&mut self.roblox_paths)
+    }
+
+    pub(crate) fn take_preprocessors(&mut self) -> Vec<Box<dyn TextPreprocessor>> {
+        std::mem::take(&mut self.preprocessors)
+    }
+
+    pub(crate) fn take_postprocessors(&mut self) -> Vec<Box<dyn TextPostprocessor>> {
+        std::mem::take(&mut self.postprocessors)
+    }
 }
@@ -0,0 +1,421 @@
+use std::fmt;
+
+#[cfg(feature = "serde-config")]
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::nodes::{Block, TriviaKind};
+use crate::process::NodeQuery;
+use crate::rules::visit_trivia;
+use crate::utils::log;
+use crate::Parser;
+
+#[cfg(feature = "serde-config")]
+fn deserialize_query_argument<'de, D>(deserializer: D, kind: &str) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let argument = String::deserialize(deserializer)?;
+    format!("{}:{}", kind, argument)
+        .parse::<NodeQuery>()
+        .map_err(de::Error::custom)?;
+    Ok(argument)
+}
+
+#[cfg(feature = "serde-config")]
+fn deserialize_call_pattern<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_query_argument(deserializer, "call")
+}
+
+#[cfg(feature = "serde-config")]
+fn deserialize_global_name<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_query_argument(deserializer, "global")
+}
+
+/// A check run against the generated code of each file, after the rules, the postprocessors
+/// and the processed marker. Checks are configured in the `checks` field of the
+/// configuration file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "snake_case"))]
+pub enum OutputCheck {
+    /// Forbids calls of a function accessed through the given names (like `loadstring` or
+    /// `string.dump`), written like the `call:` patterns of the `find` command.
+    ForbidCall(
+        #[cfg_attr(
+            feature = "serde-config",
+            serde(deserialize_with = "deserialize_call_pattern")
+        )]
+        String,
+    ),
+    /// Forbids reads and writes of a global variable.
+    ForbidGlobal(
+        #[cfg_attr(
+            feature = "serde-config",
+            serde(deserialize_with = "deserialize_global_name")
+        )]
+        String,
+    ),
+    /// Forbids string literals containing the given text.
+    ForbidStringMatching(String),
+    /// Forbids comments.
+    ForbidComments,
+    /// Forbids lines longer than the given number of characters.
+    MaxLineLength(usize),
+    /// When true, the generated code must be parsed successfully. When false, the checks
+    /// that need to parse the code are skipped for code that cannot be parsed.
+    RequireParse(bool),
+}
+
+impl OutputCheck {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ForbidCall(_) => "forbid_call",
+            Self::ForbidGlobal(_) => "forbid_global",
+            Self::ForbidStringMatching(_) => "forbid_string_matching",
+            Self::ForbidComments => "forbid_comments",
+            Self::MaxLineLength(_) => "max_line_length",
+            Self::RequireParse(_) => "require_parse",
+        }
+    }
+
+    fn query(&self) -> Option<NodeQuery> {
+        let query = match self {
+            Self::ForbidCall(pattern) => format!("call:{}", pattern),
+            Self::ForbidGlobal(name) => format!("global:{}", name),
+            Self::ForbidStringMatching(text) => format!("string:{}", text),
+            Self::ForbidComments | Self::MaxLineLength(_) | Self::RequireParse(_) => return None,
+        };
+
+        Some(
+            query
+                .parse()
+                .expect("output check arguments are verified when deserialized"),
+        )
+    }
+
+    fn needs_parsed_code(&self) -> bool {
+        match self {
+            Self::ForbidCall(_)
+            | Self::ForbidGlobal(_)
+            | Self::ForbidStringMatching(_)
+            | Self::ForbidComments => true,
+            Self::RequireParse(required) => *required,
+            Self::MaxLineLength(_) => false,
+        }
+    }
+}
+
+/// A part of the generated code that does not pass an [`OutputCheck`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputViolation {
+    check: &'static str,
+    line: Option<usize>,
+    column: Option<usize>,
+    message: String,
+}
+
+impl OutputViolation {
+    fn new(check: &OutputCheck, message: impl Into<String>) -> Self {
+        Self {
+            check: check.name(),
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    fn at(mut self, line: Option<usize>, column: Option<usize>) -> Self {
+        self.line = line;
+        self.column = column;
+        self
+    }
+
+    /// The name of the check that is violated (like `forbid_call`).
+    #[inline]
+    pub fn check(&self) -> &str {
+        self.check
+    }
+
+    /// The line of the violation in the generated code, when it is known.
+    #[inline]
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// The column of the violation in the generated code, when it is known.
+    #[inline]
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for OutputViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "{}:{}: ", line, self.column.unwrap_or(1))?;
+        }
+        write!(f, "{} ({})", self.message, self.check)
+    }
+}
+
+fn column_at(code: &str, start: usize) -> Option<usize> {
+    let before = code.get(..start)?;
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Some(before[line_start..].chars().count() + 1)
+}
+
+fn find_comments(block: &mut Block, code: &str, check: &OutputCheck) -> Vec<OutputViolation> {
+    let mut violations = Vec::new();
+
+    visit_trivia(block, |trivia| {
+        for comment in trivia
+            .iter()
+            .filter(|trivia| trivia.kind() == TriviaKind::Comment)
+        {
+            violations.push(
+                OutputViolation::new(check, "forbidden comment").at(
+                    comment.get_line_number(),
+                    comment
+                        .get_start_position()
+                        .and_then(|start| column_at(code, start)),
+                ),
+            );
+        }
+    });
+
+    violations
+}
+
+fn find_long_lines(code: &str, max_length: usize, check: &OutputCheck) -> Vec<OutputViolation> {
+    code.lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let length = line.chars().count();
+
+            (length > max_length).then(|| {
+                OutputViolation::new(
+                    check,
+                    format!(
+                        "line of {} characters is longer than {} characters",
+                        length, max_length
+                    ),
+                )
+                .at(Some(index + 1), Some(max_length + 1))
+            })
+        })
+        .collect()
+}
+
+/// Runs the checks against generated code and returns the violations found, sorted by
+/// position. The checks that find nodes (like [`OutputCheck::ForbidCall`]) parse the code.
+pub fn check_output(checks: &[OutputCheck], code: &str) -> Vec<OutputViolation> {
+    let mut violations = Vec::new();
+
+    for check in checks {
+        if let OutputCheck::MaxLineLength(max_length) = check {
+            violations.extend(find_long_lines(code, *max_length, check));
+        }
+    }
+
+    if checks.iter().any(OutputCheck::needs_parsed_code) {
+        match Parser::default().preserve_tokens().parse(code) {
+            Ok(mut block) => {
+                for check in checks {
+                    if let Some(query) = check.query() {
+                        violations.extend(query.find(&mut block, code).into_iter().map(|found| {
+                            OutputViolation::new(
+                                check,
+                                format!("forbidden {} `{}`", found.kind(), found.snippet()),
+                            )
+                            .at(found.line(), found.column())
+                        }));
+                    } else if check == &OutputCheck::ForbidComments {
+                        violations.extend(find_comments(&mut block, code, check));
+                    }
+                }
+            }
+            Err(error) => {
+                let skips_unparsed_code = checks.contains(&OutputCheck::RequireParse(false));
+
+                if skips_unparsed_code {
+                    log::warn!(
+                        "unable to parse generated code to run output checks: {}",
+                        error
+                    );
+                } else {
+                    violations.push(OutputViolation::new(
+                        &OutputCheck::RequireParse(true),
+                        format!("unable to parse generated code: {}", error),
+                    ));
+                }
+            }
+        }
+    }
+
+    violations.sort_by_key(|violation| (violation.line, violation.column));
+    violations
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(checks: &[OutputCheck], code: &str) -> Vec<String> {
+        check_output(checks, code)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn deserialize_checks() {
+        let checks: Vec<OutputCheck> = json5::from_str(
+            r#"[
+                { forbid_call: 'string.dump' },
+                { forbid_global: 'getfenv' },
+                { forbid_string_matching: 'internal.example.com' },
+                'forbid_comments',
+                { max_line_length: 4000 },
+                { require_parse: true },
+            ]"#,
+        )
+        .unwrap();
+
+        pretty_assertions::assert_eq!(
+            checks,
+            vec![
+                OutputCheck::ForbidCall("string.dump".to_owned()),
+                OutputCheck::ForbidGlobal("getfenv".to_owned()),
+                OutputCheck::ForbidStringMatching("internal.example.com".to_owned()),
+                OutputCheck::ForbidComments,
+                OutputCheck::MaxLineLength(4000),
+                OutputCheck::RequireParse(true),
+            ]
+        );
+    }
+
+    #[test]
+    fn deserialize_invalid_global_name() {
+        let error = json5::from_str::<OutputCheck>("{ forbid_global: 'not valid' }").unwrap_err();
+
+        pretty_assertions::assert_eq!(
+            error.to_string(),
+            "invalid global pattern `global:not valid`: `not valid` is not an identifier"
+        );
+    }
+
+    #[test]
+    fn deserialize_invalid_call_pattern() {
+        assert!(json5::from_str::<OutputCheck>("{ forbid_call: 'a..b' }").is_err());
+    }
+
+    #[test]
+    fn code_without_violations() {
+        assert!(check(
+            &[
+                OutputCheck::ForbidCall("loadstring".to_owned()),
+                OutputCheck::ForbidComments,
+                OutputCheck::MaxLineLength(20),
+            ],
+            "local a = 1\nreturn a\n"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn forbidden_call() {
+        pretty_assertions::assert_eq!(
+            check(
+                &[OutputCheck::ForbidCall("loadstring".to_owned())],
+                "local f = 1\nreturn loadstring(code)"
+            ),
+            vec!["2:8: forbidden call `loadstring(code)` (forbid_call)"]
+        );
+    }
+
+    #[test]
+    fn forbidden_global() {
+        pretty_assertions::assert_eq!(
+            check(
+                &[OutputCheck::ForbidGlobal("getfenv".to_owned())],
+                "local getfenv = getfenv"
+            ),
+            vec!["1:17: forbidden global `getfenv` (forbid_global)"]
+        );
+    }
+
+    #[test]
+    fn forbidden_string() {
+        pretty_assertions::assert_eq!(
+            check(
+                &[OutputCheck::ForbidStringMatching("internal".to_owned())],
+                "return 'https://internal.example.com', 'public'"
+            ),
+            vec!["1:8: forbidden string `'https://internal.example.com'` (forbid_string_matching)"]
+        );
+    }
+
+    #[test]
+    fn forbidden_comments() {
+        pretty_assertions::assert_eq!(
+            check(
+                &[OutputCheck::ForbidComments],
+                "--!strict\nlocal a = 1 --[[ value ]]\nreturn a"
+            ),
+            vec![
+                "1:1: forbidden comment (forbid_comments)",
+                "2:13: forbidden comment (forbid_comments)",
+            ]
+        );
+    }
+
+    #[test]
+    fn line_longer_than_max_length() {
+        pretty_assertions::assert_eq!(
+            check(&[OutputCheck::MaxLineLength(10)], "local a = 1\nreturn a\n"),
+            vec!["1:11: line of 11 characters is longer than 10 characters (max_line_length)"]
+        );
+    }
+
+    #[test]
+    fn unparsable_code_with_require_parse() {
+        pretty_assertions::assert_eq!(
+            check(&[OutputCheck::RequireParse(true)], "local =").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn unparsable_code_with_forbid_call() {
+        let violations = check_output(
+            &[OutputCheck::ForbidCall("loadstring".to_owned())],
+            "local =",
+        );
+
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].check(), "require_parse");
+    }
+
+    #[test]
+    fn unparsable_code_without_require_parse() {
+        assert!(check(
+            &[
+                OutputCheck::ForbidCall("loadstring".to_owned()),
+                OutputCheck::RequireParse(false),
+            ],
+            "local ="
+        )
+        .is_empty());
+    }
+}
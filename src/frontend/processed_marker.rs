@@ -0,0 +1,239 @@
+use std::{collections::BTreeSet, str::FromStr};
+
+const MARKER_PREFIX: &str = "-- darklua-processed:";
+
+/// Defines what happens when a file that already contains a processed marker (the comment
+/// added at the end of the files generated with [`Options::with_processed_marker`]) is
+/// processed again.
+///
+/// [`Options::with_processed_marker`]: crate::Options::with_processed_marker
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReprocessPolicy {
+    /// Leave the file as is, without writing any output.
+    #[default]
+    Skip,
+    /// Apply every rule again, except the rules that are unsafe to apply twice.
+    Force,
+    /// Only apply the rules that are not recorded in the marker.
+    Differential,
+}
+
+impl ReprocessPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Force => "force",
+            Self::Differential => "differential",
+        }
+    }
+}
+
+impl FromStr for ReprocessPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "force" => Ok(Self::Force),
+            "differential" => Ok(Self::Differential),
+            _ => Err(format!(
+                "invalid reprocess policy `{}` (expected `skip`, `force` or `differential`)",
+                s
+            )),
+        }
+    }
+}
+
+/// The trailer comment that records which version of darklua generated a file, with
+/// which configuration and which rules. When the file was generated by a stage of the
+/// configuration, the marker also records the name of that stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProcessedMarker {
+    version: String,
+    config_hash: String,
+    rules: BTreeSet<String>,
+    stage: Option<String>,
+}
+
+impl ProcessedMarker {
+    pub(crate) fn new(config_hash: u64, rules: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            config_hash: format!("{:016x}", config_hash),
+            rules: rules.into_iter().collect(),
+            stage: None,
+        }
+    }
+
+    pub(crate) fn with_stage(mut self, stage: impl Into<String>) -> Self {
+        self.stage = Some(stage.into());
+        self
+    }
+
+    /// Removes the marker from the end of the given code, and returns the code that
+    /// precedes it with the marker.
+    pub(crate) fn split(code: &str) -> Option<(&str, Self)> {
+        let trimmed = code.trim_end();
+        let line_start = trimmed.rfind('\n').map(|index| index + 1).unwrap_or(0);
+        let marker = Self::parse(&trimmed[line_start..])?;
+
+        let content = trimmed[..line_start]
+            .strip_suffix('\n')
+            .map(|content| content.strip_suffix('\r').unwrap_or(content))
+            .unwrap_or(&trimmed[..line_start]);
+
+        Some((content, marker))
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let fields = line.strip_prefix(MARKER_PREFIX)?;
+
+        let mut version = None;
+        let mut config_hash = None;
+        let mut rules = None;
+        let mut stage = None;
+
+        for field in fields.split_whitespace() {
+            let (key, value) = field.split_once('=')?;
+            match key {
+                "version" => version = Some(value.to_owned()),
+                "config" => config_hash = Some(value.to_owned()),
+                "rules" => {
+                    rules = Some(
+                        value
+                            .split(',')
+                            .filter(|name| !name.is_empty())
+                            .map(str::to_owned)
+                            .collect(),
+                    )
+                }
+                "stage" => stage = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            version: version?,
+            config_hash: config_hash?,
+            rules: rules?,
+            stage,
+        })
+    }
+
+    pub(crate) fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub(crate) fn stage(&self) -> Option<&str> {
+        self.stage.as_deref()
+    }
+
+    pub(crate) fn has_rule(&self, rule_name: &str) -> bool {
+        self.rules.contains(rule_name)
+    }
+
+    pub(crate) fn iter_rules(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(String::as_str)
+    }
+
+    /// Appends the marker on a new line at the end of the given code.
+    pub(crate) fn append_to(&self, mut code: String) -> String {
+        if !code.is_empty() && !code.ends_with('\n') {
+            code.push('\n');
+        }
+        code.push_str(&format!(
+            "{} version={} config={} rules={}",
+            MARKER_PREFIX,
+            self.version,
+            self.config_hash,
+            self.iter_rules().collect::<Vec<_>>().join(",")
+        ));
+        if let Some(stage) = self.stage.as_ref() {
+            code.push_str(&format!(" stage={}", stage));
+        }
+        code
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn new_marker() -> ProcessedMarker {
+        ProcessedMarker::new(
+            0xabc,
+            ["rename_variables".to_owned(), "remove_comments".to_owned()],
+        )
+    }
+
+    #[test]
+    fn append_marker_to_code() {
+        let code = new_marker().append_to("return 1".to_owned());
+
+        assert_eq!(
+            code,
+            format!(
+                "return 1\n-- darklua-processed: version={} config=0000000000000abc rules=remove_comments,rename_variables",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn split_appended_marker() {
+        let marker = new_marker();
+        let code = marker.append_to("local a = 1\nreturn a\n".to_owned());
+
+        assert_eq!(
+            ProcessedMarker::split(&code),
+            Some(("local a = 1\nreturn a", marker))
+        );
+    }
+
+    #[test]
+    fn split_marker_without_rules() {
+        let marker = ProcessedMarker::new(1, Vec::new());
+        let code = marker.append_to("return nil".to_owned());
+
+        assert_eq!(ProcessedMarker::split(&code), Some(("return nil", marker)));
+    }
+
+    #[test]
+    fn split_marker_with_stage() {
+        let marker = new_marker().with_stage("pre");
+        let code = marker.append_to("return 1".to_owned());
+
+        assert!(code.ends_with(" stage=pre"));
+        assert_eq!(ProcessedMarker::split(&code), Some(("return 1", marker)));
+    }
+
+    #[test]
+    fn split_code_without_marker() {
+        assert_eq!(ProcessedMarker::split("return 1\n-- comment"), None);
+    }
+
+    #[test]
+    fn split_code_with_marker_before_the_end() {
+        let code = new_marker().append_to("return 1".to_owned()) + "\nprint('hello')";
+
+        assert_eq!(ProcessedMarker::split(&code), None);
+    }
+
+    #[test]
+    fn parse_policy() {
+        assert_eq!("skip".parse(), Ok(ReprocessPolicy::Skip));
+        assert_eq!("force".parse(), Ok(ReprocessPolicy::Force));
+        assert_eq!("differential".parse(), Ok(ReprocessPolicy::Differential));
+    }
+
+    #[test]
+    fn parse_invalid_policy() {
+        assert_eq!(
+            "always".parse::<ReprocessPolicy>(),
+            Err(
+                "invalid reprocess policy `always` (expected `skip`, `force` or `differential`)"
+                    .to_owned()
+            )
+        );
+    }
+}
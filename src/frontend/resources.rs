@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ffi::OsStr,
     fs::{self, File},
     io::{self, BufWriter, ErrorKind as IOErrorKind, Write},
@@ -8,7 +8,9 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use crate::utils::normalize_path;
+use crate::utils::{absolute_path, log, normalize_path};
+
+use super::{input_pattern::ExcludePatterns, DarkluaResult};
 
 #[derive(Debug, Clone)]
 enum Source {
@@ -55,6 +57,7 @@ impl Source {
         match self {
             Self::FileSystem => fs::read_to_string(location).map_err(|err| match err.kind() {
                 IOErrorKind::NotFound => ResourceError::not_found(location),
+                IOErrorKind::InvalidData => ResourceError::invalid_encoding(location),
                 _ => ResourceError::io_error(location, err),
             }),
             Self::Memory(data) => {
@@ -91,21 +94,95 @@ impl Source {
         }
     }
 
-    pub fn walk(&self, location: &Path) -> impl Iterator<Item = PathBuf> {
+    pub fn rename(&self, from: &Path, to: &Path) -> ResourceResult<()> {
+        match self {
+            Self::FileSystem => {
+                if let Some(parent) = to.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|err| ResourceError::io_error(parent, err))?;
+                };
+
+                if let Err(err) = fs::rename(from, to) {
+                    log::trace!(
+                        "unable to rename `{}` (copy the file instead): {}",
+                        from.display(),
+                        err
+                    );
+                    // the paths may be on different file systems
+                    fs::copy(from, to).map_err(|err| match err.kind() {
+                        IOErrorKind::StorageFull => ResourceError::IO {
+                            path: to.to_path_buf(),
+                            error: format!(
+                                "not enough space to copy `{}` from another file system: {}",
+                                from.display(),
+                                err
+                            ),
+                        },
+                        _ => ResourceError::io_error(to, err),
+                    })?;
+                    fs::remove_file(from).map_err(|err| ResourceError::io_error(from, err))?;
+                }
+                Ok(())
+            }
+            Self::Memory(data) => {
+                let mut data = data.lock().unwrap();
+                let content = data
+                    .remove(&normalize_path(from))
+                    .ok_or_else(|| ResourceError::not_found(from))?;
+                data.insert(normalize_path(to), content);
+                Ok(())
+            }
+        }
+    }
+
+    /// Walks the files of a location. The files and directories (except the location
+    /// itself) for which `is_excluded` returns `true` are skipped, and the content of the
+    /// skipped directories is not read.
+    pub fn walk<'a>(
+        &self,
+        location: &Path,
+        follow_symlinks: bool,
+        is_excluded: &'a dyn Fn(&Path) -> bool,
+    ) -> Box<dyn Iterator<Item = PathBuf> + 'a> {
         match self {
-            Self::FileSystem => Box::new(walk_file_system(location.to_path_buf()))
-                as Box<dyn Iterator<Item = PathBuf>>,
+            Self::FileSystem => Box::new(walk_file_system(
+                location.to_path_buf(),
+                follow_symlinks,
+                is_excluded,
+            )),
             Self::Memory(data) => {
                 let data = data.lock().unwrap();
                 let location = normalize_path(location);
                 let mut paths: Vec<_> = data.keys().map(normalize_path).collect();
-                paths.retain(|path| path.starts_with(&location));
+                // the current directory contains all the relative paths
+                if location != Path::new(".") {
+                    paths.retain(|path| path.starts_with(&location));
+                } else {
+                    paths.retain(|path| path.is_relative());
+                }
+                paths.retain(|path| {
+                    !path
+                        .ancestors()
+                        .take_while(|ancestor| *ancestor != location && *ancestor != Path::new(""))
+                        .any(is_excluded)
+                });
 
                 Box::new(paths.into_iter())
             }
         }
     }
 
+    /// Returns the location with its symlinks resolved. Locations that do not exist (or
+    /// that are not on the file system) are only normalized.
+    pub fn canonicalize(&self, location: &Path) -> PathBuf {
+        match self {
+            Self::FileSystem => {
+                fs::canonicalize(location).unwrap_or_else(|_| absolute_path(location))
+            }
+            Self::Memory(_) => normalize_path(location),
+        }
+    }
+
     fn remove(&self, location: &Path) -> Result<(), ResourceError> {
         match self {
             Self::FileSystem => {
@@ -136,21 +213,54 @@ impl Source {
     }
 }
 
-fn walk_file_system(location: PathBuf) -> impl Iterator<Item = PathBuf> {
-    let mut unknown_paths = vec![location];
+/// Walks a directory without following the symlinks found inside it, unless
+/// `follow_symlinks` is enabled. When symlinks are followed, each location is only visited
+/// once, so that symlink cycles terminate and files are not found twice. The excluded
+/// locations are skipped before reading their metadata.
+fn walk_file_system<'a>(
+    location: PathBuf,
+    follow_symlinks: bool,
+    is_excluded: &'a dyn Fn(&Path) -> bool,
+) -> impl Iterator<Item = PathBuf> + 'a {
+    let mut unknown_paths = vec![(location, true)];
     let mut file_paths = Vec::new();
     let mut dir_entries = Vec::new();
+    let mut visited = HashSet::new();
 
     iter::from_fn(move || loop {
-        if let Some(location) = unknown_paths.pop() {
-            match location.metadata() {
+        if let Some((location, is_root)) = unknown_paths.pop() {
+            if !is_root && is_excluded(&location) {
+                log::trace!("skip excluded path `{}`", location.display());
+                continue;
+            }
+
+            // the given location is always followed when it is a symlink
+            let metadata = if is_root || follow_symlinks {
+                location.metadata()
+            } else {
+                location.symlink_metadata()
+            };
+
+            match metadata {
                 Ok(metadata) => {
-                    if metadata.is_file() {
-                        file_paths.push(location.to_path_buf());
-                    } else if metadata.is_dir() {
-                        dir_entries.push(location.to_path_buf());
-                    } else if metadata.is_symlink() {
+                    if metadata.is_symlink() {
                         log::warn!("unexpected symlink `{}` not followed", location.display());
+                    } else if metadata.is_file() || metadata.is_dir() {
+                        // locations can only be found twice when symlinks are followed
+                        if follow_symlinks
+                            && !visited.insert(
+                                fs::canonicalize(&location).unwrap_or_else(|_| location.clone()),
+                            )
+                        {
+                            log::debug!(
+                                "skip `{}` because it was already visited through a symlink",
+                                location.display()
+                            );
+                        } else if metadata.is_file() {
+                            file_paths.push(location);
+                        } else {
+                            dir_entries.push(location);
+                        }
                     } else {
                         log::warn!(
                             concat!(
@@ -175,7 +285,7 @@ fn walk_file_system(location: PathBuf) -> impl Iterator<Item = PathBuf> {
                     for entry in read_dir {
                         match entry {
                             Ok(entry) => {
-                                unknown_paths.push(entry.path());
+                                unknown_paths.push((entry.path(), false));
                             }
                             Err(err) => {
                                 log::warn!(
@@ -222,12 +332,48 @@ impl Resources {
     }
 
     pub fn collect_work(&self, location: impl AsRef<Path>) -> impl Iterator<Item = PathBuf> {
-        self.source.walk(location.as_ref()).filter(|path| {
-            matches!(
-                path.extension().and_then(OsStr::to_str),
-                Some("lua") | Some("luau")
-            )
-        })
+        filter_lua_files(self.source.walk(location.as_ref(), false, &never_excluded))
+    }
+
+    /// Collects the Lua files like [`collect_work`](Self::collect_work), but also follows
+    /// the symlinks found in the directories.
+    pub fn collect_work_following_symlinks(
+        &self,
+        location: impl AsRef<Path>,
+    ) -> impl Iterator<Item = PathBuf> {
+        filter_lua_files(self.source.walk(location.as_ref(), true, &never_excluded))
+    }
+
+    /// Collects the Lua files like [`collect_work`](Self::collect_work), without the files
+    /// and directories for which `is_excluded` returns `true`. The excluded directories are
+    /// not walked.
+    pub(crate) fn collect_work_excluding<'a>(
+        &self,
+        location: impl AsRef<Path>,
+        follow_symlinks: bool,
+        is_excluded: &'a dyn Fn(&Path) -> bool,
+    ) -> impl Iterator<Item = PathBuf> + 'a {
+        filter_lua_files(
+            self.source
+                .walk(location.as_ref(), follow_symlinks, is_excluded),
+        )
+    }
+
+    /// Collects the Lua files like [`collect_work`](Self::collect_work), without the files
+    /// and directories matching one of the glob patterns (like `**/*.spec.lua`). The patterns
+    /// are matched against the paths relative to the location.
+    pub fn collect_work_without(
+        &self,
+        location: impl AsRef<Path>,
+        excludes: &[String],
+    ) -> DarkluaResult<Vec<PathBuf>> {
+        let location = location.as_ref();
+        let excludes = ExcludePatterns::new(location, excludes)?;
+        let is_excluded = |path: &Path| excludes.is_excluded(path);
+
+        Ok(self
+            .collect_work_excluding(location, false, &is_excluded)
+            .collect())
     }
 
     pub fn exists(&self, location: impl AsRef<Path>) -> ResourceResult<bool> {
@@ -250,18 +396,42 @@ impl Resources {
         self.source.write(location.as_ref(), content)
     }
 
+    /// Moves a file to another location. When both locations are not on the same file
+    /// system, the file is copied and then removed.
+    pub fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> ResourceResult<()> {
+        self.source.rename(from.as_ref(), to.as_ref())
+    }
+
     pub fn remove(&self, location: impl AsRef<Path>) -> ResourceResult<()> {
         self.source.remove(location.as_ref())
     }
 
     pub fn walk(&self, location: impl AsRef<Path>) -> impl Iterator<Item = PathBuf> {
-        self.source.walk(location.as_ref())
+        self.source.walk(location.as_ref(), false, &never_excluded)
     }
+
+    pub(crate) fn canonicalize(&self, location: impl AsRef<Path>) -> PathBuf {
+        self.source.canonicalize(location.as_ref())
+    }
+}
+
+fn never_excluded(_: &Path) -> bool {
+    false
+}
+
+fn filter_lua_files(paths: impl Iterator<Item = PathBuf>) -> impl Iterator<Item = PathBuf> {
+    paths.filter(|path| {
+        matches!(
+            path.extension().and_then(OsStr::to_str),
+            Some("lua") | Some("luau")
+        )
+    })
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ResourceError {
     NotFound(PathBuf),
+    InvalidEncoding(PathBuf),
     IO { path: PathBuf, error: String },
 }
 
@@ -270,6 +440,10 @@ impl ResourceError {
         Self::NotFound(path.into())
     }
 
+    pub(crate) fn invalid_encoding(path: impl Into<PathBuf>) -> Self {
+        Self::InvalidEncoding(path.into())
+    }
+
     pub(crate) fn io_error(path: impl Into<PathBuf>, error: io::Error) -> Self {
         Self::IO {
             path: path.into(),
@@ -346,6 +520,17 @@ mod test {
             assert_eq!(resources.get(any_path()), Ok(ANY_CONTENT.to_string()));
         }
 
+        #[test]
+        fn renamed_file_moves_its_content() {
+            let resources = new();
+            resources.write("a.lua", ANY_CONTENT).unwrap();
+
+            resources.rename("a.lua", "b/c.lua").unwrap();
+
+            assert_eq!(resources.exists("a.lua"), Ok(false));
+            assert_eq!(resources.get("b/c.lua"), Ok(ANY_CONTENT.to_string()));
+        }
+
         #[test]
         fn collect_work_contains_created_files() {
             let resources = new();
@@ -356,5 +541,71 @@ mod test {
                 vec![PathBuf::from("src/test.lua")]
             );
         }
+
+        #[test]
+        fn collect_work_excluding_skips_excluded_directories() {
+            let resources = new();
+            resources.write("src/init.lua", ANY_CONTENT).unwrap();
+            resources.write("src/Packages/a.lua", ANY_CONTENT).unwrap();
+            resources
+                .write("src/Packages/b/c.lua", ANY_CONTENT)
+                .unwrap();
+
+            let is_excluded = |path: &Path| path.ends_with("Packages");
+
+            assert_eq!(
+                Vec::from_iter(resources.collect_work_excluding("src", false, &is_excluded)),
+                vec![PathBuf::from("src/init.lua")]
+            );
+        }
+    }
+
+    mod file_system {
+        use std::cell::RefCell;
+
+        use super::*;
+
+        fn write(root: &Path, path: &str) {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, ANY_CONTENT).unwrap();
+        }
+
+        #[test]
+        fn collect_work_excluding_does_not_walk_excluded_directories() {
+            let directory = tempfile::tempdir().unwrap();
+            let root = directory.path();
+            write(root, "init.lua");
+            write(root, "lib/util.lua");
+            write(root, "lib/util.spec.lua");
+            write(root, "node_modules/a/index.lua");
+            write(root, "node_modules/b/index.lua");
+
+            let checked = RefCell::new(Vec::new());
+            let is_excluded = |path: &Path| {
+                let relative = path.strip_prefix(root).unwrap().to_path_buf();
+                checked.borrow_mut().push(relative.clone());
+                relative == Path::new("node_modules")
+                    || relative.to_string_lossy().ends_with(".spec.lua")
+            };
+
+            let mut files: Vec<_> = Resources::from_file_system()
+                .collect_work_excluding(root, false, &is_excluded)
+                .map(|path| path.strip_prefix(root).unwrap().to_path_buf())
+                .collect();
+            files.sort();
+
+            assert_eq!(
+                files,
+                vec![PathBuf::from("init.lua"), PathBuf::from("lib/util.lua")]
+            );
+            assert!(
+                checked
+                    .borrow()
+                    .iter()
+                    .all(|path| !path.starts_with("node_modules")
+                        || path == Path::new("node_modules"))
+            );
+        }
     }
 }
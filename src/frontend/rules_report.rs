@@ -0,0 +1,301 @@
+#[cfg(feature = "serde-config")]
+use serde::Serialize;
+#[cfg(feature = "serde-config")]
+use serde_json::Value;
+
+#[cfg(feature = "serde-config")]
+use crate::frontend::configuration_schema::get_property_schema;
+use crate::frontend::{process_code, Configuration, DarkluaError, DarkluaResult};
+use crate::rules::{
+    create_rule, get_all_rule_names, get_default_rules, Rule, RuleExample, RuleProperties,
+    RulePropertyKind, RulePropertyValue,
+};
+
+/// Whether a rule property can make the rule change the behavior of the code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "snake_case"))]
+pub enum PropertySafety {
+    /// No value of the property makes the rule unsafe.
+    Safe,
+    /// Some values of the property make the rule unsafe, so strict mode rejects them.
+    Unsafe,
+}
+
+/// The documentation of a rule property.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+pub struct PropertyDescription {
+    name: &'static str,
+    #[cfg(feature = "serde-config")]
+    #[cfg_attr(feature = "serde-config", serde(rename = "type"))]
+    schema: Value,
+    safety: PropertySafety,
+}
+
+impl PropertyDescription {
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the JSON Schema of the values accepted by the property.
+    #[cfg(feature = "serde-config")]
+    #[inline]
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+
+    #[inline]
+    pub fn safety(&self) -> PropertySafety {
+        self.safety
+    }
+}
+
+/// An example of a rule with the code generated by applying the rule on it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+pub struct RuleExampleOutput {
+    properties: RuleProperties,
+    input: &'static str,
+    output: String,
+}
+
+impl RuleExampleOutput {
+    #[inline]
+    pub fn properties(&self) -> &RuleProperties {
+        &self.properties
+    }
+
+    #[inline]
+    pub fn input(&self) -> &'static str {
+        self.input
+    }
+
+    #[inline]
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+}
+
+/// The documentation of a rule, generated from the rule itself.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+pub struct RuleDescription {
+    name: &'static str,
+    default: bool,
+    properties: Vec<PropertyDescription>,
+    formatting_rule: bool,
+    statement_local: bool,
+    unsafe_to_reapply: bool,
+    applies_to_data_modules: bool,
+    examples: Vec<RuleExampleOutput>,
+}
+
+impl RuleDescription {
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns true when the rule is part of the default rules.
+    #[inline]
+    pub fn is_default(&self) -> bool {
+        self.default
+    }
+
+    #[inline]
+    pub fn properties(&self) -> &[PropertyDescription] {
+        &self.properties
+    }
+
+    #[inline]
+    pub fn examples(&self) -> &[RuleExampleOutput] {
+        &self.examples
+    }
+}
+
+/// Returns the values of a property that are tried to find out if the property can make the
+/// rule unsafe.
+fn get_probe_values(kind: RulePropertyKind) -> Vec<RulePropertyValue> {
+    match kind {
+        RulePropertyKind::Boolean => vec![true.into(), false.into()],
+        RulePropertyKind::Choice(choices) => {
+            choices.iter().map(|choice| (*choice).into()).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn get_property_safety(
+    rule_name: &str,
+    property: &'static str,
+    kind: RulePropertyKind,
+) -> PropertySafety {
+    let is_unsafe = get_probe_values(kind).into_iter().any(|value| {
+        let mut properties = RuleProperties::new();
+        properties.insert(property.to_owned(), value);
+
+        create_rule(rule_name, properties)
+            .is_ok_and(|rule| rule.unsafe_properties().contains(&property))
+    });
+
+    if is_unsafe {
+        PropertySafety::Unsafe
+    } else {
+        PropertySafety::Safe
+    }
+}
+
+/// Applies a rule configured with the properties of an example on the code of the example,
+/// using the same pipeline as the `process` command. Returns the generated code.
+pub fn run_rule_example(rule_name: &str, example: &RuleExample) -> DarkluaResult<String> {
+    let rule = create_rule(rule_name, example.properties().clone()).map_err(|err| {
+        DarkluaError::custom(format!(
+            "unable to configure `{}` for its example: {}",
+            rule_name, err
+        ))
+    })?;
+
+    process_code(example.code(), Configuration::empty().with_rule(rule))
+}
+
+fn describe_rule(
+    rule: &dyn Rule,
+    default_rules: &[&'static str],
+) -> DarkluaResult<RuleDescription> {
+    let name = rule.get_name();
+
+    let properties = rule
+        .get_property_names()
+        .iter()
+        .map(|property| {
+            let kind = rule.get_property_kind(property);
+            PropertyDescription {
+                name: property,
+                #[cfg(feature = "serde-config")]
+                schema: get_property_schema(kind),
+                safety: get_property_safety(name, property, kind),
+            }
+        })
+        .collect();
+
+    let examples = rule
+        .examples()
+        .into_iter()
+        .map(|example| {
+            run_rule_example(name, &example).map(|output| RuleExampleOutput {
+                properties: example.properties().clone(),
+                input: example.code(),
+                output,
+            })
+        })
+        .collect::<DarkluaResult<_>>()?;
+
+    Ok(RuleDescription {
+        name,
+        default: default_rules.contains(&name),
+        properties,
+        formatting_rule: rule.is_formatting_rule(),
+        statement_local: rule.is_statement_local(),
+        unsafe_to_reapply: rule.is_unsafe_to_reapply(),
+        applies_to_data_modules: rule.applies_to_data_modules(),
+        examples,
+    })
+}
+
+/// Describes every built-in rule (with its default configuration): its properties, its
+/// capabilities and its examples. The output of each example is computed by applying the
+/// rule on it.
+pub fn describe_rules() -> DarkluaResult<Vec<RuleDescription>> {
+    let default_rules: Vec<_> = get_default_rules()
+        .iter()
+        .map(|rule| rule.get_name())
+        .collect();
+
+    get_all_rule_names()
+        .into_iter()
+        .map(|name| {
+            let rule: Box<dyn Rule> = name
+                .parse()
+                .expect("built-in rules should have a default configuration");
+            describe_rule(rule.as_ref(), &default_rules)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn every_rule_example_changes_the_code() {
+        for name in get_all_rule_names() {
+            let rule: Box<dyn Rule> = name.parse().unwrap();
+
+            for example in rule.examples() {
+                let output = run_rule_example(name, &example).unwrap_or_else(|err| {
+                    panic!("example of `{}` failed: {}\n{}", name, err, example.code())
+                });
+
+                assert_ne!(
+                    output,
+                    example.code(),
+                    "example of `{}` is not changed by the rule",
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn describe_default_rules() {
+        let descriptions: Vec<_> = describe_rules()
+            .unwrap()
+            .into_iter()
+            .filter(RuleDescription::is_default)
+            .collect();
+
+        assert_json_snapshot!("default_rules_description", descriptions);
+    }
+
+    #[test]
+    fn describe_every_rule() {
+        let descriptions = describe_rules().unwrap();
+
+        assert_eq!(descriptions.len(), get_all_rule_names().len());
+    }
+
+    #[test]
+    fn unsafe_property_is_classified_as_unsafe() {
+        let descriptions = describe_rules().unwrap();
+        let rule = descriptions
+            .iter()
+            .find(|rule| rule.name() == "compute_expression")
+            .unwrap();
+
+        let safety = |name: &str| {
+            rule.properties()
+                .iter()
+                .find(|property| property.name() == name)
+                .unwrap()
+                .safety()
+        };
+
+        assert_eq!(safety("assume_no_env_manipulation"), PropertySafety::Unsafe);
+        assert_eq!(safety("stdlib_version"), PropertySafety::Safe);
+    }
+
+    #[test]
+    fn rule_without_examples_has_no_examples() {
+        let descriptions = describe_rules().unwrap();
+        let rule = descriptions
+            .iter()
+            .find(|rule| rule.name() == "check_assignment_arity")
+            .unwrap();
+
+        assert!(rule.examples().is_empty());
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+#[cfg(test)]
+use std::convert::TryFrom;
+
+use crate::generator::utils::write_string;
+use crate::process::EnvironmentTarget;
+
+/// Starts a token of the payload. Any other character is copied to the output.
+const MARKER: u8 = b'`';
+/// Numbers in tokens are written with the printable ASCII characters, from the space (0)
+/// to the tilde (94).
+const DIGIT_OFFSET: u8 = b' ';
+const DIGIT_BASE: usize = 95;
+/// The length of a back-reference is written with a single digit, where 0 is reserved for
+/// escaped bytes. A back-reference takes 5 characters, so shorter matches do not make the
+/// payload smaller.
+const MIN_MATCH_LENGTH: usize = 6;
+const MAX_MATCH_LENGTH: usize = MIN_MATCH_LENGTH + DIGIT_BASE - 2;
+/// The distance of a back-reference is written with three digits.
+const MAX_MATCH_DISTANCE: usize = DIGIT_BASE * DIGIT_BASE * DIGIT_BASE;
+/// The number of previous positions compared when looking for the longest match.
+const MAX_MATCH_CANDIDATES: usize = 64;
+
+const DECOMPRESSOR: &str = r#"local function decompress(payload)
+    local byte, sub, find = string.byte, string.sub, string.find
+    local output, size, index, length = {}, 0, 1, #payload
+    while index <= length do
+        local marker = find(payload, "`", index, true) or length + 1
+        for position = index, marker - 1 do
+            size = size + 1
+            output[size] = sub(payload, position, position)
+        end
+        if marker > length then
+            break
+        end
+        local a, b, c, d = byte(payload, marker + 1, marker + 4)
+        if a == 32 then
+            size = size + 1
+            output[size] = string.char((b - 32) * 95 + c - 32)
+            index = marker + 4
+        else
+            local distance = ((b - 32) * 95 + c - 32) * 95 + d - 31
+            for _ = 1, a - 27 do
+                size = size + 1
+                output[size] = output[size - distance]
+            end
+            index = marker + 5
+        end
+    end
+    return table.concat(output)
+end
+"#;
+
+fn push_digits(payload: &mut String, value: usize, count: u32) {
+    for exponent in (0..count).rev() {
+        let digit = (value / DIGIT_BASE.pow(exponent)) % DIGIT_BASE;
+        payload.push(char::from(DIGIT_OFFSET + digit as u8));
+    }
+}
+
+fn is_literal_byte(value: u8) -> bool {
+    value == b'\n' || ((b' '..=b'~').contains(&value) && value != MARKER)
+}
+
+fn prefix_key(bytes: &[u8], index: usize) -> Option<[u8; 3]> {
+    bytes
+        .get(index..index + 3)
+        .map(|prefix| [prefix[0], prefix[1], prefix[2]])
+}
+
+fn record_position(positions: &mut HashMap<[u8; 3], Vec<usize>>, bytes: &[u8], index: usize) {
+    if let Some(key) = prefix_key(bytes, index) {
+        positions.entry(key).or_default().push(index);
+    }
+}
+
+/// Returns the distance and the length of the longest previous match of the bytes starting
+/// at the given index.
+fn find_match(
+    bytes: &[u8],
+    index: usize,
+    positions: &HashMap<[u8; 3], Vec<usize>>,
+) -> Option<(usize, usize)> {
+    let max_length = MAX_MATCH_LENGTH.min(bytes.len() - index);
+    if max_length < MIN_MATCH_LENGTH {
+        return None;
+    }
+
+    let candidates = positions.get(&prefix_key(bytes, index)?)?;
+    let mut best: Option<(usize, usize)> = None;
+
+    for &candidate in candidates.iter().rev().take(MAX_MATCH_CANDIDATES) {
+        let distance = index - candidate;
+        if distance > MAX_MATCH_DISTANCE {
+            break;
+        }
+
+        let length = (0..max_length)
+            .take_while(|offset| bytes[candidate + offset] == bytes[index + offset])
+            .count();
+
+        if length > best.map(|(_, length)| length).unwrap_or(0) {
+            best = Some((distance, length));
+            if length == max_length {
+                break;
+            }
+        }
+    }
+
+    best.filter(|(_, length)| *length >= MIN_MATCH_LENGTH)
+}
+
+/// Compresses code into a payload made of printable ASCII characters and new lines, so that
+/// it can be written in a Lua string without escape sequences. The payload is a sequence of
+/// characters copied to the output, and of tokens starting with a backtick:
+///
+/// - a space followed by two digits: a byte that is not printable (or a backtick)
+/// - a length digit followed by three distance digits: a copy of previous output
+pub(crate) fn compress(code: &str) -> String {
+    let bytes = code.as_bytes();
+    let mut payload = String::with_capacity(bytes.len() / 2);
+    let mut positions: HashMap<[u8; 3], Vec<usize>> = HashMap::new();
+
+    let mut index = 0;
+    while index < bytes.len() {
+        match find_match(bytes, index, &positions) {
+            Some((distance, length)) => {
+                payload.push(char::from(MARKER));
+                push_digits(&mut payload, length - MIN_MATCH_LENGTH + 1, 1);
+                push_digits(&mut payload, distance - 1, 3);
+
+                for position in index..index + length {
+                    record_position(&mut positions, bytes, position);
+                }
+                index += length;
+            }
+            None => {
+                let value = bytes[index];
+                if is_literal_byte(value) {
+                    payload.push(char::from(value));
+                } else {
+                    payload.push(char::from(MARKER));
+                    push_digits(&mut payload, 0, 1);
+                    push_digits(&mut payload, value as usize, 2);
+                }
+
+                record_position(&mut positions, bytes, index);
+                index += 1;
+            }
+        }
+    }
+
+    payload
+}
+
+/// Decompresses a payload created by [`compress`]. This is the same algorithm as the Lua
+/// decompressor of self-extracting files. Returns `None` when the payload is malformed.
+#[cfg(test)]
+fn decompress(payload: &str) -> Option<String> {
+    let bytes = payload.as_bytes();
+    let mut output: Vec<u8> = Vec::with_capacity(bytes.len() * 2);
+
+    let digit = |index: usize| -> Option<usize> {
+        bytes
+            .get(index)
+            .and_then(|value| value.checked_sub(DIGIT_OFFSET))
+            .map(usize::from)
+            .filter(|value| *value < DIGIT_BASE)
+    };
+
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] != MARKER {
+            output.push(bytes[index]);
+            index += 1;
+            continue;
+        }
+
+        let length = digit(index + 1)?;
+        if length == 0 {
+            let value = digit(index + 2)? * DIGIT_BASE + digit(index + 3)?;
+            output.push(u8::try_from(value).ok()?);
+            index += 4;
+        } else {
+            let distance = (digit(index + 2)? * DIGIT_BASE + digit(index + 3)?) * DIGIT_BASE
+                + digit(index + 4)?
+                + 1;
+            let start = output.len().checked_sub(distance)?;
+            for position in start..start + length + MIN_MATCH_LENGTH - 1 {
+                output.push(output[position]);
+            }
+            index += 5;
+        }
+    }
+
+    String::from_utf8(output).ok()
+}
+
+/// Wraps generated code into a file that decompresses and runs it, to fit large bundles
+/// under the size limits of some platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SelfExtract {
+    loader: &'static str,
+}
+
+impl SelfExtract {
+    /// Finds the function loading code from a string for the given target. Luau does not
+    /// provide a way to load code that works everywhere (`loadstring` is disabled by default
+    /// in Roblox), so self-extracting files can not be generated for it.
+    pub(crate) fn for_target(target: Option<EnvironmentTarget>) -> Result<Self, String> {
+        let loader = match target {
+            Some(EnvironmentTarget::Lua51) => "loadstring",
+            Some(EnvironmentTarget::Lua53 | EnvironmentTarget::Lua54) => "load",
+            Some(target @ (EnvironmentTarget::Luau | EnvironmentTarget::Roblox)) => {
+                return Err(format!(
+                    "unable to generate self-extracting files for the `{}` target (it can not load code from a string)",
+                    target.as_str()
+                ))
+            }
+            None => "(loadstring or load)",
+        };
+
+        Ok(Self { loader })
+    }
+
+    pub(crate) fn wrap(&self, code: &str) -> String {
+        format!(
+            "local payload = {}\n{}return assert({}(decompress(payload)))(...)\n",
+            write_string(&compress(code)),
+            DECOMPRESSOR,
+            self.loader
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::nodes::{Expression, LocalAssignStatement, Statement};
+    use crate::{Configuration, GeneratorParameters, Options, OutputCheck, Parser};
+    use crate::{ProcessingSession, Resources};
+
+    fn assert_round_trip(code: &str) {
+        let payload = compress(code);
+
+        assert!(
+            payload.bytes().all(is_literal_byte_or_marker),
+            "payload contains characters that need escaping: {:?}",
+            payload
+        );
+        assert_eq!(decompress(&payload).as_deref(), Some(code));
+    }
+
+    fn is_literal_byte_or_marker(value: u8) -> bool {
+        is_literal_byte(value) || value == MARKER
+    }
+
+    /// Finds the payload of a self-extracting file by parsing it.
+    fn read_payload(code: &str) -> String {
+        let block = Parser::default()
+            .parse(code)
+            .expect("self-extracting file should parse");
+
+        match block.first_statement() {
+            Some(Statement::LocalAssign(assign)) => match assign_value(assign) {
+                Some(Expression::String(string)) => string.get_value().to_owned(),
+                _ => panic!("payload should be a string"),
+            },
+            _ => panic!("self-extracting file should start with the payload"),
+        }
+    }
+
+    fn assign_value(assign: &LocalAssignStatement) -> Option<&Expression> {
+        assign.iter_values().next()
+    }
+
+    #[test]
+    fn round_trip_empty_code() {
+        assert_round_trip("");
+    }
+
+    #[test]
+    fn round_trip_short_code() {
+        assert_round_trip("return 1");
+    }
+
+    #[test]
+    fn round_trip_repeated_code() {
+        assert_round_trip(&"local value = call('argument')\n".repeat(50));
+    }
+
+    #[test]
+    fn round_trip_overlapping_match() {
+        assert_round_trip(&"a".repeat(1000));
+    }
+
+    #[test]
+    fn round_trip_special_characters() {
+        assert_round_trip("local a = 'caf\u{e9}`\t\r\n\u{0}' -- \u{1f600}\r\n");
+    }
+
+    #[test]
+    fn round_trip_long_distance() {
+        let mut code = String::new();
+        for index in 0..20_000 {
+            code.push_str(&format!("local v{} = {}\n", index, index * 7));
+        }
+        code.push_str("local v0 = 0\n");
+
+        assert_round_trip(&code);
+    }
+
+    #[test]
+    fn repeated_code_is_smaller() {
+        let code = "print('hello world')\n".repeat(100);
+
+        assert!(compress(&code).len() < code.len() / 10);
+    }
+
+    #[test]
+    fn escaped_byte_token() {
+        assert_eq!(compress("\t"), "`  )");
+    }
+
+    #[test]
+    fn back_reference_token() {
+        assert_eq!(compress("abcdefabcdef"), "abcdef`!  %");
+    }
+
+    #[test]
+    fn decompress_malformed_payload() {
+        assert_eq!(decompress("`"), None);
+        assert_eq!(decompress("`!  %"), None);
+    }
+
+    #[test]
+    fn luau_target_is_not_supported() {
+        pretty_assertions::assert_eq!(
+            SelfExtract::for_target(Some(EnvironmentTarget::Roblox)).unwrap_err(),
+            "unable to generate self-extracting files for the `roblox` target (it can not load code from a string)"
+        );
+        assert!(SelfExtract::for_target(Some(EnvironmentTarget::Luau)).is_err());
+    }
+
+    #[test]
+    fn loader_depends_on_target() {
+        let wrap = |target| SelfExtract::for_target(target).unwrap().wrap("return 1");
+
+        assert!(wrap(Some(EnvironmentTarget::Lua51))
+            .ends_with("return assert(loadstring(decompress(payload)))(...)\n"));
+        assert!(wrap(Some(EnvironmentTarget::Lua54))
+            .ends_with("return assert(load(decompress(payload)))(...)\n"));
+        assert!(
+            wrap(None).ends_with("return assert((loadstring or load)(decompress(payload)))(...)\n")
+        );
+    }
+
+    #[test]
+    fn wrapped_payload_decompresses_to_code() {
+        let code = "local value = 'abc' .. `interpolation` return value, '\\u{1F600}'\n".repeat(30);
+        let wrapped = SelfExtract::for_target(None).unwrap().wrap(&code);
+
+        assert_eq!(decompress(&read_payload(&wrapped)), Some(code));
+    }
+
+    #[test]
+    fn process_self_extracting_file() {
+        let code = "local function greet(name)\n  return 'hello ' .. name\nend\n".repeat(40);
+        let configuration = Configuration::empty()
+            .with_generator(GeneratorParameters::RetainLines)
+            .with_check(OutputCheck::RequireParse(true));
+
+        let mut session = ProcessingSession::new(
+            Options::new("src")
+                .with_configuration(configuration)
+                .with_self_extract(),
+        );
+        session.add_source("main.lua", code.clone());
+        session.run().unwrap();
+
+        let (_, outcome) = session.iter_outputs().next().unwrap();
+        assert!(outcome.is_success(), "{:?}", outcome.errors());
+
+        let output = outcome.code().unwrap();
+        assert_eq!(
+            decompress(&read_payload(output)).as_deref(),
+            Some(code.as_str())
+        );
+        assert_eq!(outcome.uncompressed_size(), Some(code.len() as u64));
+        assert_eq!(outcome.output_size(), Some(output.len() as u64));
+        assert!(outcome.output_size() < outcome.uncompressed_size());
+    }
+
+    #[test]
+    fn process_self_extracting_file_for_luau_target() {
+        let resources = Resources::from_memory();
+        resources.write("src/main.lua", "return 1").unwrap();
+
+        let mut session = ProcessingSession::from_resources(
+            resources,
+            Options::new("src")
+                .with_output("out")
+                .with_configuration(Configuration::empty().with_target(EnvironmentTarget::Luau))
+                .with_self_extract(),
+        );
+
+        pretty_assertions::assert_eq!(
+            session.run().unwrap_err().to_string(),
+            "unable to generate self-extracting files for the `luau` target (it can not load code from a string)"
+        );
+    }
+}
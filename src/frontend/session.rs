@@ -0,0 +1,282 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::rules::Artifact;
+
+use super::{
+    normalize_path, work_item::WorkStatus, DarkluaError, DarkluaResult, Options, Resources,
+    WorkerTree,
+};
+
+/// The result of processing a single source of a [`ProcessingSession`].
+#[derive(Debug, Clone)]
+pub struct ProcessOutcome {
+    code: Option<String>,
+    errors: Vec<DarkluaError>,
+    input_size: u64,
+    output_size: Option<u64>,
+    uncompressed_size: Option<u64>,
+    content_hash: Option<String>,
+    rule_profile: Option<Vec<(String, Duration)>>,
+    artifacts: Vec<Artifact>,
+    timed_out_rules: Vec<(String, Duration)>,
+}
+
+impl ProcessOutcome {
+    /// Returns `true` if the source was processed without errors.
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// The generated code. It is only available for sessions processing sources in memory,
+    /// when the source was processed successfully.
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn errors(&self) -> &[DarkluaError] {
+        &self.errors
+    }
+
+    /// The size of the source in bytes.
+    pub fn input_size(&self) -> u64 {
+        self.input_size
+    }
+
+    /// The size of the generated code in bytes, when code was generated for the source.
+    pub fn output_size(&self) -> Option<u64> {
+        self.output_size
+    }
+
+    /// The size of the generated code in bytes before it was compressed, when the options of
+    /// the session enable self-extracting files.
+    pub fn uncompressed_size(&self) -> Option<u64> {
+        self.uncompressed_size
+    }
+
+    /// The digest of the generated code, computed with the hash algorithm of the options.
+    /// It is only available when a hash algorithm, a hash manifest or an output template
+    /// using the digest is set in the options.
+    pub fn content_hash(&self) -> Option<&str> {
+        self.content_hash.as_deref()
+    }
+
+    /// The time spent by each rule on the source, in the order the rules were applied. This
+    /// is only measured when the options of the session enable rule profiling.
+    pub fn rule_profile(&self) -> Option<&[(String, Duration)]> {
+        self.rule_profile.as_deref()
+    }
+
+    /// The rules skipped because they exceeded the time budget set with
+    /// [`Options::with_rule_timeout`], with the time spent before each rule was cancelled.
+    /// The generated code does not contain any change from these rules.
+    pub fn timed_out_rules(&self) -> &[(String, Duration)] {
+        &self.timed_out_rules
+    }
+
+    /// The artifacts emitted by the rules applied to the source, in the order they were
+    /// emitted. Artifacts are available even when no path is configured to write them.
+    pub fn artifacts(&self) -> &[Artifact] {
+        &self.artifacts
+    }
+
+    /// Returns the last artifact emitted with the given name.
+    pub fn artifact(&self, name: &str) -> Option<&Artifact> {
+        self.artifacts
+            .iter()
+            .rev()
+            .find(|artifact| artifact.name() == name)
+    }
+}
+
+/// Processes a group of sources and keeps the outcome of each source.
+///
+/// A session created with [`ProcessingSession::new`] keeps its sources and the generated
+/// code in memory, so it never accesses the file system:
+///
+/// ```
+/// # use darklua_core::{Configuration, GeneratorParameters, Options, ProcessingSession};
+/// let configuration =
+///     Configuration::empty().with_generator(GeneratorParameters::default_dense());
+/// let options = Options::new("src").with_configuration(configuration);
+/// let mut session = ProcessingSession::new(options);
+///
+/// session.add_source("main.lua", "return  1 + 2".to_owned());
+/// session.run().unwrap();
+///
+/// for (name, outcome) in session.iter_outputs() {
+///     assert_eq!(name, "main.lua");
+///     assert_eq!(outcome.code(), Some("return 1+2"));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ProcessingSession {
+    resources: Resources,
+    options: Option<Options>,
+    input: PathBuf,
+    sources: Vec<(PathBuf, String)>,
+    pending_sources: Vec<(PathBuf, String)>,
+    in_memory: bool,
+    outcomes: Vec<(String, ProcessOutcome)>,
+}
+
+impl ProcessingSession {
+    /// Creates a session that processes sources kept in memory. The sources are placed in
+    /// the input path of the options, so the names given to [`add_source`](Self::add_source)
+    /// are relative to it.
+    pub fn new(options: Options) -> Self {
+        Self {
+            resources: Resources::from_memory(),
+            input: options.input().to_path_buf(),
+            options: Some(options),
+            sources: Vec::new(),
+            pending_sources: Vec::new(),
+            in_memory: true,
+            outcomes: Vec::new(),
+        }
+    }
+
+    /// Creates a session that processes the files found from the input path of the options
+    /// in the given resources.
+    pub fn from_resources(resources: Resources, options: Options) -> Self {
+        Self {
+            resources,
+            input: options.input().to_path_buf(),
+            options: Some(options),
+            sources: Vec::new(),
+            pending_sources: Vec::new(),
+            in_memory: false,
+            outcomes: Vec::new(),
+        }
+    }
+
+    /// Adds a source to process. For sessions created from resources, the source is written
+    /// in the resources when the session runs and it is processed with the other files of
+    /// the input path.
+    pub fn add_source(&mut self, name: &str, content: String) {
+        let path = normalize_path(self.input.join(name));
+        self.sources.push((path.clone(), name.to_owned()));
+        self.pending_sources.push((path, content));
+    }
+
+    /// Processes the sources of the session. Errors related to a single source are found in
+    /// its outcome, while errors that prevent the session from processing any source (like
+    /// an invalid configuration) are returned.
+    pub fn run(&mut self) -> DarkluaResult<()> {
+        let mut options = self
+            .options
+            .take()
+            .ok_or_else(|| DarkluaError::custom("processing session already ran"))?;
+
+        for (path, content) in self.pending_sources.drain(..) {
+            self.resources.write(path, &content)?;
+        }
+
+        if self.in_memory {
+            options = options.with_files(
+                self.sources
+                    .iter()
+                    .map(|(path, _)| path.clone())
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        let read_code = self.in_memory && !options.should_check();
+        let profile_rules = options.should_profile_rules();
+
+        let mut worker_tree = WorkerTree::default();
+        worker_tree.collect_work(&self.resources, &options)?;
+        worker_tree.process(&self.resources, options)?;
+
+        let names: HashMap<&Path, &str> = self
+            .sources
+            .iter()
+            .map(|(path, name)| (path.as_path(), name.as_str()))
+            .collect();
+
+        let mut outcomes: Vec<_> = worker_tree
+            .iter_work_items()
+            .filter_map(|work_item| {
+                let result = match &work_item.status {
+                    WorkStatus::NotStarted | WorkStatus::InProgress(_) => return None,
+                    WorkStatus::Done(result) => result,
+                };
+
+                let name = names
+                    .get(work_item.source())
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| self.source_name(work_item.source()));
+
+                let report = &work_item.report;
+                let code = if read_code && result.is_ok() && report.output_size.is_some() {
+                    self.resources.get(work_item.written_output()).ok()
+                } else {
+                    None
+                };
+
+                let outcome = ProcessOutcome {
+                    code,
+                    errors: result.as_ref().err().cloned().into_iter().collect(),
+                    input_size: report.input_size,
+                    output_size: report.output_size,
+                    uncompressed_size: report.uncompressed_size,
+                    content_hash: report.content_hash.clone(),
+                    rule_profile: profile_rules.then(|| report.rule_profile.clone()),
+                    artifacts: report.artifacts.clone(),
+                    timed_out_rules: report.timed_out_rules.clone(),
+                };
+
+                Some((name, outcome))
+            })
+            .collect();
+
+        if self.in_memory {
+            let order: HashMap<&str, usize> = self
+                .sources
+                .iter()
+                .enumerate()
+                .map(|(index, (_, name))| (name.as_str(), index))
+                .collect();
+            outcomes.sort_by_key(|(name, _)| order.get(name.as_str()).copied());
+        } else {
+            outcomes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+
+        self.outcomes = outcomes;
+
+        Ok(())
+    }
+
+    fn source_name(&self, source: &Path) -> String {
+        match source.strip_prefix(&self.input) {
+            Ok(relative) if relative != Path::new("") => relative.display().to_string(),
+            _ => source.display().to_string(),
+        }
+    }
+
+    /// Iterates over the outcome of each processed source, with the name of the source. For
+    /// sessions created from resources, the name is the path of the file relative to the
+    /// input path.
+    pub fn iter_outputs(&self) -> impl Iterator<Item = (&str, &ProcessOutcome)> {
+        self.outcomes
+            .iter()
+            .map(|(name, outcome)| (name.as_str(), outcome))
+    }
+
+    pub fn success_count(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|(_, outcome)| outcome.is_success())
+            .count()
+    }
+
+    pub fn collect_errors(&self) -> Vec<&DarkluaError> {
+        self.outcomes
+            .iter()
+            .flat_map(|(_, outcome)| outcome.errors.iter())
+            .collect()
+    }
+}
@@ -0,0 +1,480 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde-config")]
+use crate::utils::log;
+use crate::{
+    nodes::{Block, Identifier, TypeField},
+    process::{IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor},
+    rules::Rule,
+    utils::normalize_path,
+};
+
+use super::{DarkluaError, DarkluaResult, Resources};
+
+const STAGE_STATE_VERSION: u64 = 1;
+const DEFAULT_CACHE_DIRECTORY: &str = ".darklua-cache";
+
+/// A named part of the rules pipeline, defined in the `stages` field of the configuration.
+/// Each stage can run in its own darklua invocation: the state recorded by a stage is
+/// written in the cache directory and read by the next stage.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-config", serde(deny_unknown_fields))]
+pub struct StageConfiguration {
+    name: String,
+    #[cfg_attr(feature = "serde-config", serde(default))]
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl StageConfiguration {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn with_rule(mut self, rule: impl Into<Box<dyn Rule>>) -> Self {
+        self.rules.push(rule.into());
+        self
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub(crate) fn rules(&self) -> &[Box<dyn Rule>] {
+        &self.rules
+    }
+
+    pub(crate) fn retain_rules(&mut self, filter: impl FnMut(&Box<dyn Rule>) -> bool) {
+        self.rules.retain(filter);
+    }
+}
+
+/// Defines which stages of the configuration are run by a process.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StageSelection {
+    /// Run every stage in sequence. The code generated by a stage is given to the next
+    /// stage without being written.
+    #[default]
+    All,
+    /// Run a single stage, with the state written by the previous stage.
+    Named(String),
+}
+
+impl FromStr for StageSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" => Err("stage name must not be empty".to_owned()),
+            "all" => Ok(Self::All),
+            name => Ok(Self::Named(name.to_owned())),
+        }
+    }
+}
+
+/// What a stage recorded about a file it generated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+pub(crate) struct StageFileState {
+    /// The global names that the generated code uses but that the original code did not
+    /// use (like a global injected by a rule). Later stages must not declare locals with
+    /// these names.
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "BTreeSet::is_empty")
+    )]
+    pub(crate) reserved_identifiers: BTreeSet<String>,
+}
+
+/// The state of a stage, written to `<cache directory>/stages/<name>.json` with an entry
+/// for each generated file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+pub(crate) struct StageState {
+    version: u64,
+    stage: String,
+    files: BTreeMap<PathBuf, StageFileState>,
+}
+
+impl StageState {
+    pub(crate) fn new(stage: impl Into<String>) -> Self {
+        Self {
+            version: STAGE_STATE_VERSION,
+            stage: stage.into(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    #[cfg(feature = "serde-config")]
+    pub(crate) fn path(cache_directory: &Path, stage: &str) -> PathBuf {
+        cache_directory
+            .join("stages")
+            .join(format!("{}.json", stage))
+    }
+
+    /// Reads the state of a stage, or returns `None` when the stage did not write a state.
+    #[cfg(feature = "serde-config")]
+    pub(crate) fn read(
+        resources: &Resources,
+        cache_directory: &Path,
+        stage: &str,
+    ) -> DarkluaResult<Option<Self>> {
+        let path = Self::path(cache_directory, stage);
+
+        if !resources.exists(&path)? {
+            return Ok(None);
+        }
+
+        let state: Self = serde_json::from_str(&resources.get(&path)?).map_err(|err| {
+            DarkluaError::custom(format!(
+                "unable to read the state of stage `{}` from `{}`: {}",
+                stage,
+                path.display(),
+                err
+            ))
+        })?;
+
+        if state.version != STAGE_STATE_VERSION || state.stage != stage {
+            return Err(DarkluaError::custom(format!(
+                "unexpected state in `{}` (expected version {} of stage `{}`)",
+                path.display(),
+                STAGE_STATE_VERSION,
+                stage
+            ))
+            .context("remove the file and run the previous stage again"));
+        }
+
+        Ok(Some(state))
+    }
+
+    #[cfg(not(feature = "serde-config"))]
+    pub(crate) fn read(
+        _resources: &Resources,
+        _cache_directory: &Path,
+        stage: &str,
+    ) -> DarkluaResult<Option<Self>> {
+        Err(missing_serde_config(stage))
+    }
+
+    #[cfg(feature = "serde-config")]
+    pub(crate) fn write(&self, resources: &Resources, cache_directory: &Path) -> DarkluaResult<()> {
+        let path = Self::path(cache_directory, &self.stage);
+        let content = serde_json::to_string_pretty(self)?;
+
+        log::debug!(
+            "write state of stage `{}` to `{}`",
+            self.stage,
+            path.display()
+        );
+
+        resources.write(&path, &content).map_err(|err| {
+            DarkluaError::from(err).context(format!(
+                "unable to write the state of stage `{}`",
+                self.stage
+            ))
+        })
+    }
+
+    #[cfg(not(feature = "serde-config"))]
+    pub(crate) fn write(
+        &self,
+        _resources: &Resources,
+        _cache_directory: &Path,
+    ) -> DarkluaResult<()> {
+        Err(missing_serde_config(&self.stage))
+    }
+
+    pub(crate) fn get(&self, file: &Path) -> Option<&StageFileState> {
+        self.files.get(&normalize_path(file))
+    }
+
+    pub(crate) fn insert(&mut self, file: &Path, state: StageFileState) {
+        self.files.insert(normalize_path(file), state);
+    }
+}
+
+/// The state of the stages is written as JSON, which needs the `serde-config` feature.
+#[cfg(not(feature = "serde-config"))]
+fn missing_serde_config(stage: &str) -> DarkluaError {
+    DarkluaError::custom(format!(
+        "unable to run stage `{}` because the state of stages can only be read and written \
+        with the `serde-config` feature",
+        stage
+    ))
+}
+
+/// The stages run by a process, with the state read from the previous stage and the state
+/// written by the last stage that runs.
+#[derive(Debug)]
+pub(crate) struct StageRun {
+    indexes: Range<usize>,
+    cache_directory: PathBuf,
+    previous_state: Option<StageState>,
+    state: StageState,
+}
+
+impl StageRun {
+    /// Verifies the stages of the configuration and reads the state needed to run the
+    /// selected stages.
+    pub(crate) fn new(
+        resources: &Resources,
+        stages: &[StageConfiguration],
+        selection: &StageSelection,
+        cache_directory: Option<PathBuf>,
+    ) -> DarkluaResult<Self> {
+        let mut names = BTreeSet::new();
+        for stage in stages {
+            let name = stage.name();
+            if name.is_empty()
+                || name == "all"
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(DarkluaError::custom(format!(
+                    "invalid stage name `{}` (expected letters, digits, `_` or `-`, other than `all`)",
+                    name
+                )));
+            }
+            if !names.insert(name) {
+                return Err(DarkluaError::custom(format!(
+                    "stage `{}` is defined more than once",
+                    name
+                )));
+            }
+        }
+
+        let indexes = match selection {
+            StageSelection::All => 0..stages.len(),
+            StageSelection::Named(name) => {
+                let index = stages
+                    .iter()
+                    .position(|stage| stage.name() == name)
+                    .ok_or_else(|| {
+                        DarkluaError::custom(format!(
+                            "unknown stage `{}` (the configuration defines {})",
+                            name,
+                            stages
+                                .iter()
+                                .map(|stage| format!("`{}`", stage.name()))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ))
+                    })?;
+                index..index + 1
+            }
+        };
+
+        let cache_directory =
+            cache_directory.unwrap_or_else(|| PathBuf::from(DEFAULT_CACHE_DIRECTORY));
+
+        let previous_state = match indexes.start.checked_sub(1) {
+            Some(previous_index) => {
+                let previous = stages[previous_index].name();
+                let state = StageState::read(resources, &cache_directory, previous)?
+                    .ok_or_else(|| {
+                        DarkluaError::custom(format!(
+                            "unable to run stage `{}` because the state of stage `{}` is missing from `{}`",
+                            stages[indexes.start].name(),
+                            previous,
+                            cache_directory.display()
+                        ))
+                        .context(format!("run the `{}` stage first", previous))
+                    })?;
+                Some(state)
+            }
+            None => None,
+        };
+
+        let last = stages[indexes.end - 1].name();
+        let state = StageState::read(resources, &cache_directory, last)?
+            .unwrap_or_else(|| StageState::new(last));
+
+        Ok(Self {
+            indexes,
+            cache_directory,
+            previous_state,
+            state,
+        })
+    }
+
+    #[inline]
+    pub(crate) fn indexes(&self) -> Range<usize> {
+        self.indexes.clone()
+    }
+
+    #[inline]
+    pub(crate) fn first(&self) -> usize {
+        self.indexes.start
+    }
+
+    /// Returns the index of the stage that follows the given stage, when it also runs.
+    pub(crate) fn next(&self, index: usize) -> Option<usize> {
+        let next = index + 1;
+        self.indexes.contains(&next).then_some(next)
+    }
+
+    /// The identifiers reserved for a file by the stages that ran before this process.
+    pub(crate) fn previous_reserved_identifiers(&self, source: &Path) -> BTreeSet<String> {
+        self.previous_state
+            .as_ref()
+            .and_then(|state| state.get(source))
+            .map(|file| file.reserved_identifiers.clone())
+            .unwrap_or_default()
+    }
+
+    /// Records the state of a file generated by the last stage that runs.
+    pub(crate) fn record(&mut self, output: &Path, reserved_identifiers: BTreeSet<String>) {
+        self.state.insert(
+            output,
+            StageFileState {
+                reserved_identifiers,
+            },
+        );
+    }
+
+    pub(crate) fn write_state(&self, resources: &Resources) -> DarkluaResult<()> {
+        self.state.write(resources, &self.cache_directory)
+    }
+}
+
+#[derive(Default)]
+struct GlobalNameCollector {
+    identifier_tracker: IdentifierTracker,
+    names: BTreeSet<String>,
+}
+
+impl std::ops::Deref for GlobalNameCollector {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl std::ops::DerefMut for GlobalNameCollector {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl GlobalNameCollector {
+    fn verify_identifier(&mut self, identifier: &Identifier) {
+        let name = identifier.get_name();
+        if self.is_global(name) {
+            self.names.insert(name.to_owned());
+        }
+    }
+}
+
+impl NodeProcessor for GlobalNameCollector {
+    fn process_variable_expression(&mut self, variable: &mut Identifier) {
+        self.verify_identifier(variable);
+    }
+
+    fn process_type_field(&mut self, type_field: &mut TypeField) {
+        self.verify_identifier(type_field.get_namespace());
+    }
+}
+
+/// Returns the names of the global variables read or written in the block.
+pub(crate) fn find_global_names(block: &mut Block) -> BTreeSet<String> {
+    let mut collector = GlobalNameCollector::default();
+    ScopeVisitor::visit_block(block, &mut collector);
+    collector.names
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Parser;
+
+    fn global_names(code: &str) -> Vec<String> {
+        let mut block = Parser::default().parse(code).unwrap();
+        find_global_names(&mut block).into_iter().collect()
+    }
+
+    #[test]
+    fn find_global_reads_and_writes() {
+        assert_eq!(
+            global_names("local a = b\nc = a\nprint(d.e)"),
+            vec!["b", "c", "d", "print"]
+        );
+    }
+
+    #[test]
+    fn find_global_names_skip_locals_and_parameters() {
+        assert_eq!(
+            global_names("local function f(a) return a, g end\nreturn f"),
+            vec!["g"]
+        );
+    }
+
+    #[test]
+    fn parse_stage_selection() {
+        assert_eq!("all".parse(), Ok(StageSelection::All));
+        assert_eq!("pre".parse(), Ok(StageSelection::Named("pre".to_owned())));
+        assert!("".parse::<StageSelection>().is_err());
+    }
+
+    #[test]
+    fn stage_state_round_trip() {
+        let resources = Resources::from_memory();
+        let mut state = StageState::new("pre");
+        state.insert(
+            Path::new("./out/a.lua"),
+            StageFileState {
+                reserved_identifiers: vec!["a".to_owned()].into_iter().collect(),
+            },
+        );
+
+        state.write(&resources, Path::new("cache")).unwrap();
+
+        let read = StageState::read(&resources, Path::new("cache"), "pre")
+            .unwrap()
+            .unwrap();
+        assert_eq!(read, state);
+        assert_eq!(
+            read.get(Path::new("out/a.lua")),
+            Some(&StageFileState {
+                reserved_identifiers: vec!["a".to_owned()].into_iter().collect(),
+            })
+        );
+    }
+
+    #[test]
+    fn read_missing_stage_state() {
+        let resources = Resources::from_memory();
+
+        assert_eq!(
+            StageState::read(&resources, Path::new("cache"), "pre").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn read_state_of_another_stage() {
+        let resources = Resources::from_memory();
+        resources
+            .write(
+                "cache/stages/pre.json",
+                r#"{ "version": 1, "stage": "dist", "files": {} }"#,
+            )
+            .unwrap();
+
+        assert!(StageState::read(&resources, Path::new("cache"), "pre").is_err());
+    }
+}
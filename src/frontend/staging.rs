@@ -0,0 +1,223 @@
+use crate::utils::log;
+use std::{
+    ffi::OsString,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use super::{utils::maybe_plural, DarkluaError, DarkluaResult, Resources};
+
+/// Defines when the files generated by an atomic process are moved to their destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtomicMode {
+    /// Move the generated files only if every file was processed successfully.
+    All,
+    /// Move the files that were processed successfully, even if other files failed.
+    BestEffort,
+}
+
+impl AtomicMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::BestEffort => "best-effort",
+        }
+    }
+}
+
+impl FromStr for AtomicMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            "best-effort" | "best_effort" => Ok(Self::BestEffort),
+            _ => Err(format!(
+                "invalid atomic mode `{}` (expected `all` or `best-effort`)",
+                s
+            )),
+        }
+    }
+}
+
+/// Keeps track of the generated files written in a staging directory instead of their
+/// destination, so that they can all be moved in place at the end of the process.
+#[derive(Debug)]
+pub(crate) struct OutputStaging {
+    directory: PathBuf,
+    mode: AtomicMode,
+    keep: bool,
+    files: Vec<(PathBuf, PathBuf)>,
+}
+
+impl OutputStaging {
+    pub(crate) fn new(directory: impl Into<PathBuf>, mode: AtomicMode, keep: bool) -> Self {
+        Self {
+            directory: directory.into(),
+            mode,
+            keep,
+            files: Vec::new(),
+        }
+    }
+
+    pub(crate) fn default_directory() -> PathBuf {
+        PathBuf::from(format!(".darklua-staging-{}", std::process::id()))
+    }
+
+    /// Returns the path where the given output is written until it is moved in place. The
+    /// staged path mirrors the path of the output inside the staging directory.
+    pub(crate) fn stage(&mut self, output: &Path) -> PathBuf {
+        let relative_path: PathBuf = output
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name),
+                Component::Prefix(_)
+                | Component::RootDir
+                | Component::CurDir
+                | Component::ParentDir => None,
+            })
+            .collect();
+
+        let staged = self.directory.join(relative_path);
+
+        self.files.retain(|(_, destination)| destination != output);
+        self.files.push((staged.clone(), output.to_path_buf()));
+
+        staged
+    }
+
+    /// Moves the staged files to their destination, or drops them when some files failed
+    /// and the mode requires every file to succeed.
+    pub(crate) fn commit(self, resources: &Resources, error_count: usize) -> DarkluaResult<()> {
+        if error_count != 0 && self.mode == AtomicMode::All {
+            log::error!(
+                "{} staged file{} not written because {} file{} failed to process",
+                self.files.len(),
+                if self.files.len() == 1 {
+                    " was"
+                } else {
+                    "s were"
+                },
+                error_count,
+                maybe_plural(error_count),
+            );
+            return self.discard(resources);
+        }
+
+        log::debug!(
+            "move {} staged file{} in place",
+            self.files.len(),
+            maybe_plural(self.files.len())
+        );
+
+        // the staged files are first moved next to their destination, so that a failure (like
+        // a file system without enough space for the copied files) leaves the destination
+        // untouched. The last step only renames files inside the same directory.
+        let mut prepared = Vec::new();
+
+        for (staged, destination) in self.files.iter() {
+            let temporary = temporary_path(destination);
+
+            if let Err(err) = resources.rename(staged, &temporary) {
+                for (temporary, _) in prepared {
+                    if let Err(err) = resources.remove(&temporary) {
+                        log::warn!(
+                            "unable to remove temporary file: {}",
+                            DarkluaError::from(err)
+                        );
+                    }
+                }
+
+                let error = DarkluaError::from(err).context(format!(
+                    "unable to move staged files from `{}` (the destination was not modified)",
+                    self.directory.display()
+                ));
+
+                if let Err(err) = self.clean(resources) {
+                    log::warn!("{}", err);
+                }
+
+                return Err(error);
+            }
+
+            prepared.push((temporary, destination));
+        }
+
+        for (temporary, destination) in prepared {
+            resources.rename(&temporary, destination).map_err(|err| {
+                DarkluaError::from(err).context(format!(
+                    "unable to replace `{}` with its generated content",
+                    destination.display()
+                ))
+            })?;
+        }
+
+        self.clean(resources)
+    }
+
+    /// Drops the staged files without writing them to their destination.
+    pub(crate) fn discard(self, resources: &Resources) -> DarkluaResult<()> {
+        self.clean(resources)
+    }
+
+    fn clean(&self, resources: &Resources) -> DarkluaResult<()> {
+        if self.keep {
+            log::info!("staging directory kept at `{}`", self.directory.display());
+            Ok(())
+        } else {
+            log::trace!("remove staging directory `{}`", self.directory.display());
+            resources.remove(&self.directory).map_err(|err| {
+                DarkluaError::from(err).context("unable to remove the staging directory")
+            })
+        }
+    }
+}
+
+fn temporary_path(destination: &Path) -> PathBuf {
+    let mut file_name = OsString::from(".");
+    if let Some(name) = destination.file_name() {
+        file_name.push(name);
+    }
+    file_name.push(".darklua-tmp");
+    destination.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn staged_path_mirrors_relative_output() {
+        let mut staging = OutputStaging::new("staging", AtomicMode::All, false);
+
+        assert_eq!(
+            staging.stage(Path::new("out/src/init.lua")),
+            PathBuf::from("staging/out/src/init.lua")
+        );
+    }
+
+    #[test]
+    fn staged_path_of_absolute_output_stays_in_staging_directory() {
+        let mut staging = OutputStaging::new("staging", AtomicMode::All, false);
+
+        assert_eq!(
+            staging.stage(Path::new("/out/../init.lua")),
+            PathBuf::from("staging/out/init.lua")
+        );
+    }
+
+    #[test]
+    fn temporary_path_is_next_to_destination() {
+        assert_eq!(
+            temporary_path(Path::new("out/init.lua")),
+            PathBuf::from("out/.init.lua.darklua-tmp")
+        );
+    }
+
+    #[test]
+    fn parse_atomic_modes() {
+        assert_eq!("all".parse(), Ok(AtomicMode::All));
+        assert_eq!("best-effort".parse(), Ok(AtomicMode::BestEffort));
+        assert!("some".parse::<AtomicMode>().is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::nodes::{Block, FunctionExpression, FunctionStatement, LocalFunctionStatement};
+use crate::process::{DefaultVisitor, EnvironmentTarget, LuaVersion, NodeProcessor, NodeVisitor};
+
+#[derive(Default)]
+struct RemoveFunctionAttributes {
+    removed: usize,
+}
+
+impl NodeProcessor for RemoveFunctionAttributes {
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        self.removed += function.take_attributes().len();
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        self.removed += function.take_attributes().len();
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        self.removed += function.take_attributes().len();
+    }
+}
+
+/// Removes the syntax that can not be read by the given target (like Luau function
+/// attributes when targeting Lua 5.1). Returns the number of function attributes removed.
+pub(crate) fn lower_to_target(block: &mut Block, target: EnvironmentTarget) -> usize {
+    if target.lua_version() == LuaVersion::Luau {
+        return 0;
+    }
+
+    let mut processor = RemoveFunctionAttributes::default();
+    DefaultVisitor::visit_block(block, &mut processor);
+    processor.removed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{generator::LuaGenerator, nodes::FunctionAttribute, Parser};
+
+    fn lower(code: &str, target: EnvironmentTarget) -> (String, usize) {
+        let mut block = Parser::default().parse(code).expect("code should parse");
+
+        let removed = lower_to_target(&mut block, target);
+
+        let mut generator = crate::generator::DenseLuaGenerator::default();
+        generator.write_block(&block);
+        (generator.into_string(), removed)
+    }
+
+    #[test]
+    fn removes_attributes_for_lua51() {
+        assert_eq!(
+            lower(
+                "@native local function a() end @checked function b() end",
+                EnvironmentTarget::Lua51
+            ),
+            ("local function a()end function b()end".to_owned(), 2)
+        );
+    }
+
+    #[test]
+    fn keeps_attributes_for_luau() {
+        assert_eq!(
+            lower("@native local function a() end", EnvironmentTarget::Luau),
+            ("@native local function a()end".to_owned(), 0)
+        );
+    }
+
+    #[test]
+    fn keeps_attributes_for_roblox() {
+        assert_eq!(
+            lower("@native function a() end", EnvironmentTarget::Roblox).1,
+            0
+        );
+    }
+
+    #[test]
+    fn removes_attributes_of_function_expressions() {
+        let mut block = Block::default().with_last_statement(crate::nodes::ReturnStatement::one(
+            FunctionExpression::default().with_attribute(FunctionAttribute::new("native")),
+        ));
+
+        assert_eq!(lower_to_target(&mut block, EnvironmentTarget::Lua53), 1);
+    }
+}
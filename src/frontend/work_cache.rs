@@ -6,6 +6,7 @@ use std::{
 
 use elsa::FrozenMap;
 
+use crate::utils::log;
 use crate::{nodes::Block, DarkluaError, Parser, Resources};
 
 use super::DarkluaResult;
@@ -1,11 +1,18 @@
 use std::{
-    collections::HashSet,
+    collections::{BTreeSet, HashSet},
     path::{Path, PathBuf},
+    time::Duration,
 };
 
-use crate::{nodes::Block, utils::Timer};
+use crate::{
+    nodes::Block,
+    rules::{Artifact, TopStatementInjections},
+    utils::{is_same_path, Timer},
+};
 
-use super::{DarkluaError, DarkluaResult};
+use super::{
+    incremental::IncrementalState, processed_marker::ProcessedMarker, DarkluaError, DarkluaResult,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Progress {
@@ -13,6 +20,7 @@ pub(crate) struct Progress {
     next_rule: usize,
     required: Vec<PathBuf>,
     duration: Timer,
+    injections: TopStatementInjections,
 }
 
 impl Progress {
@@ -22,6 +30,7 @@ impl Progress {
             next_rule: 0,
             required: Vec::new(),
             duration: Timer::now(),
+            injections: TopStatementInjections::default(),
         }
     }
 
@@ -48,12 +57,30 @@ impl Progress {
     pub(crate) fn duration(&mut self) -> &mut Timer {
         &mut self.duration
     }
+
+    /// Keeps the statements injected by a rule until all the rules are applied.
+    pub(crate) fn extend_injections(&mut self, injections: TopStatementInjections) {
+        self.injections.extend(injections);
+    }
+
+    pub(crate) fn take_injections(&mut self) -> TopStatementInjections {
+        std::mem::take(&mut self.injections)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct WorkProgress {
     pub(crate) content: String,
     pub(crate) progress: Progress,
+    /// The block before any rule is applied, kept to process the file incrementally later.
+    pub(crate) original_block: Option<Block>,
+    /// The marker found at the end of the file when it was already processed.
+    pub(crate) previous_marker: Option<ProcessedMarker>,
+    /// The names of the rules applied to the file, recorded in its processed marker.
+    pub(crate) applied_rules: BTreeSet<String>,
+    /// The global names used by the code before any rule is applied, kept when the rules
+    /// come from a stage to find the globals introduced by the stage.
+    pub(crate) original_global_names: Option<BTreeSet<String>>,
 }
 
 impl WorkProgress {
@@ -61,9 +88,30 @@ impl WorkProgress {
         Self {
             content,
             progress: Progress::new(block),
+            original_block: None,
+            previous_marker: None,
+            applied_rules: BTreeSet::new(),
+            original_global_names: None,
         }
     }
 
+    pub(crate) fn with_original_global_names(mut self, names: BTreeSet<String>) -> Self {
+        self.original_global_names = Some(names);
+        self
+    }
+
+    pub(crate) fn with_original_block(mut self, block: Block) -> Self {
+        self.original_block = Some(block);
+        self
+    }
+
+    pub(crate) fn with_previous_marker(mut self, marker: ProcessedMarker) -> Self {
+        self.applied_rules
+            .extend(marker.iter_rules().map(str::to_owned));
+        self.previous_marker = Some(marker);
+        self
+    }
+
     pub(crate) fn required_content(&self) -> impl Iterator<Item = &Path> {
         self.progress.required.iter().map(AsRef::as_ref)
     }
@@ -110,7 +158,7 @@ pub(crate) struct WorkData {
 
 impl WorkData {
     pub(crate) fn is_in_place(&self) -> bool {
-        self.source == self.output
+        is_same_path(&self.source, &self.output)
     }
 
     pub(crate) fn source(&self) -> &Path {
@@ -122,11 +170,55 @@ impl WorkData {
     }
 }
 
+/// Measurements of the work done on a file, exposed by the outcomes of a processing session.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WorkReport {
+    pub(crate) input_size: u64,
+    pub(crate) output_size: Option<u64>,
+    /// The size of the generated code before it was compressed into a self-extracting file.
+    pub(crate) uncompressed_size: Option<u64>,
+    /// The path where the generated code was written, when it differs from the output of
+    /// the work data (like when an output template is used).
+    pub(crate) output: Option<PathBuf>,
+    pub(crate) content_hash: Option<String>,
+    pub(crate) rule_profile: Vec<(String, Duration)>,
+    /// The artifacts emitted by the rules applied to the file.
+    pub(crate) artifacts: Vec<Artifact>,
+    /// The rules skipped because they exceeded their time budget, with the time spent before
+    /// they were cancelled.
+    pub(crate) timed_out_rules: Vec<(String, Duration)>,
+}
+
+impl WorkReport {
+    pub(crate) fn record_rule(&mut self, rule_name: &str, duration: Duration) {
+        self.rule_profile.push((rule_name.to_owned(), duration));
+    }
+
+    pub(crate) fn record_timed_out_rule(&mut self, rule_name: &str, elapsed: Duration) {
+        self.timed_out_rules.push((rule_name.to_owned(), elapsed));
+    }
+}
+
+/// Where a work item is in the stages of the configuration.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StageProgress {
+    /// The index of the stage applied to the file.
+    pub(crate) index: usize,
+    /// The code generated by the previous stage, when the stages run in the same process.
+    pub(crate) content: Option<String>,
+    /// The identifiers reserved by the previous stages, extended with the globals
+    /// introduced by the current stage once its rules are applied.
+    pub(crate) reserved_identifiers: BTreeSet<String>,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct WorkItem {
     pub(crate) data: WorkData,
     pub(crate) status: WorkStatus,
     pub(crate) external_file_dependencies: HashSet<PathBuf>,
+    pub(crate) incremental: Option<Box<IncrementalState>>,
+    pub(crate) report: WorkReport,
+    pub(crate) stage: Option<StageProgress>,
 }
 
 impl WorkItem {
@@ -138,6 +230,9 @@ impl WorkItem {
             },
             status: Default::default(),
             external_file_dependencies: Default::default(),
+            incremental: None,
+            report: Default::default(),
+            stage: None,
         }
     }
 
@@ -150,6 +245,11 @@ impl WorkItem {
         &self.data.source
     }
 
+    /// The path where the generated code was last written.
+    pub(crate) fn written_output(&self) -> &Path {
+        self.report.output.as_deref().unwrap_or(&self.data.output)
+    }
+
     pub(crate) fn total_required_content(&self) -> usize {
         match &self.status {
             WorkStatus::NotStarted | WorkStatus::Done(_) => 0,
@@ -157,8 +257,12 @@ impl WorkItem {
         }
     }
 
+    /// Restarts the work. The incremental state is kept so that the file can be processed
+    /// incrementally.
     pub(crate) fn reset(&mut self) {
         self.status = WorkStatus::NotStarted;
         self.external_file_dependencies.clear();
+        self.report = Default::default();
+        self.stage = None;
     }
 }
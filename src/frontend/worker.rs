@@ -1,22 +1,56 @@
-use std::path::Path;
+use std::{
+    any::Any,
+    borrow::Cow,
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
+#[cfg(feature = "serde-config")]
+use super::configuration_extends::{deserialize_configuration, resolve_configuration_value};
 use super::{
     configuration::Configuration,
+    data_module::is_data_module,
+    incremental::{BlockChange, IncrementalState},
+    output_checks::check_output,
+    processed_marker::{ProcessedMarker, ReprocessPolicy},
     resources::Resources,
+    self_extract::SelfExtract,
+    stages::{find_global_names, StageRun},
+    staging::OutputStaging,
+    target_lowering::lower_to_target,
     utils::maybe_plural,
     work_cache::WorkCache,
-    work_item::{WorkItem, WorkProgress, WorkStatus},
-    DarkluaError, DarkluaResult, Options,
+    work_item::{StageProgress, WorkItem, WorkProgress, WorkReport, WorkStatus},
+    ArtifactTemplate, DarkluaError, DarkluaResult, DeprecationPolicy, HashAlgorithm, Options,
+    OutputTemplate, StageSelection,
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+use xxhash_rust::xxh3::xxh3_64;
+
+#[cfg(feature = "serde-config")]
+use crate::rules::take_rule_deprecations;
 use crate::{
+    generator::segments_len,
     nodes::Block,
-    rules::{bundle::Bundler, ContextBuilder, Rule, RuleConfiguration},
-    utils::{normalize_path, Timer},
+    process::{DeadlineTracker, Environment, GlobalEntry, NodePathTracker},
+    rules::{
+        bundle::Bundler, verify_generated_name_prefix, Context, ContextBuilder,
+        DynamicEnvironmentUsage, LineOffsets, Rule, RuleConfiguration,
+    },
+    utils::{log, normalize_path, Timer},
     GeneratorParameters,
 };
 
 const DEFAULT_CONFIG_PATHS: [&str; 2] = [".darklua.json", ".darklua.json5"];
+/// The maximum number of rules that can be run again after a single rule of the pipeline.
+const MAX_REPROCESS_RUNS: usize = 4;
 
 #[derive(Debug)]
 pub(crate) struct Worker<'a> {
@@ -24,6 +58,26 @@ pub(crate) struct Worker<'a> {
     cache: WorkCache<'a>,
     configuration: Configuration,
     cached_bundler: Option<Bundler>,
+    environment: Arc<Environment>,
+    roblox_paths: HashMap<PathBuf, String>,
+    check: bool,
+    incremental: bool,
+    verify_incremental: bool,
+    staging: Option<OutputStaging>,
+    mark_processed: bool,
+    reprocess_policy: ReprocessPolicy,
+    #[cfg_attr(not(feature = "serde-config"), allow(dead_code))]
+    deprecation_policy: DeprecationPolicy,
+    max_output_size: Option<u64>,
+    self_extract: Option<SelfExtract>,
+    hash_algorithm: Option<HashAlgorithm>,
+    output_template: Option<OutputTemplate>,
+    templated_outputs: HashMap<PathBuf, PathBuf>,
+    written_artifacts: HashMap<PathBuf, PathBuf>,
+    profile_rules: bool,
+    rule_timeout: Option<Duration>,
+    config_hash: u64,
+    stages: Option<StageRun>,
 }
 
 impl<'a> Worker<'a> {
@@ -33,12 +87,33 @@ impl<'a> Worker<'a> {
             cache: WorkCache::new(resources),
             configuration: Configuration::default(),
             cached_bundler: None,
+            environment: Default::default(),
+            roblox_paths: HashMap::new(),
+            check: false,
+            incremental: false,
+            verify_incremental: false,
+            staging: None,
+            mark_processed: false,
+            reprocess_policy: ReprocessPolicy::default(),
+            deprecation_policy: DeprecationPolicy::default(),
+            max_output_size: None,
+            self_extract: None,
+            hash_algorithm: None,
+            output_template: None,
+            templated_outputs: HashMap::new(),
+            written_artifacts: HashMap::new(),
+            profile_rules: false,
+            rule_timeout: None,
+            config_hash: 0,
+            stages: None,
         }
     }
 
     pub(crate) fn setup_worker(&mut self, options: &mut Options) -> DarkluaResult<()> {
         let configuration_setup_timer = Timer::now();
 
+        self.deprecation_policy = options.deprecation_policy();
+
         if let Some(config) = options.take_configuration() {
             self.configuration = config;
             if let Some(config_path) = options.configuration_path() {
@@ -95,37 +170,306 @@ impl<'a> Worker<'a> {
                     GeneratorParameters::RetainLines => "`retain_lines`".to_owned(),
                     GeneratorParameters::Dense { column_span } =>
                         format!("dense ({})", column_span),
-                    GeneratorParameters::Readable { column_span } =>
+                    GeneratorParameters::Readable { column_span, .. } =>
                         format!("readable ({})", column_span),
                 }
             );
             self.configuration.set_generator(generator.clone());
         }
 
+        if options.should_use_formatting_rules_only() {
+            log::trace!("only use formatting rules");
+            self.configuration.retain_formatting_rules();
+        }
+
+        self.check = options.should_check();
+
+        if let Some(mode) = options.atomic_mode().filter(|_| !self.check) {
+            let directory = options
+                .staging_directory()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(OutputStaging::default_directory);
+
+            if self.resources.exists(&directory)? || self.resources.is_directory(&directory)? {
+                return Err(DarkluaError::custom(format!(
+                    "staging directory `{}` already exists",
+                    directory.display()
+                ))
+                .context("the staging directory of an atomic process must not exist"));
+            }
+
+            log::trace!(
+                "stage generated files in `{}` ({})",
+                directory.display(),
+                mode.as_str()
+            );
+            self.staging = Some(OutputStaging::new(
+                directory,
+                mode,
+                options.should_keep_staging(),
+            ));
+        }
+
+        if let Some(target) = options.target() {
+            log::trace!("override with `{}` target", target.as_str());
+            self.configuration.set_target(target);
+        }
+
+        self.environment = Arc::new(self.configuration.build_environment());
+
+        if let Some(prefix) = options.generated_name_prefix() {
+            verify_generated_name_prefix(prefix).map_err(DarkluaError::custom)?;
+            log::trace!("override with `{}` generated name prefix", prefix);
+            self.configuration.set_generated_name_prefix(prefix);
+        }
+
+        if let Some(markers) = options.raw_markers() {
+            log::trace!(
+                "override with `{}` and `{}` raw markers",
+                markers.get_begin(),
+                markers.get_end()
+            );
+            self.configuration.set_raw_markers(markers.clone());
+        }
+
+        for (name, template) in options.artifacts() {
+            log::trace!("override with `{}` path for `{}` artifacts", template, name);
+            self.configuration.set_artifact(name, template.clone());
+        }
+
+        self.roblox_paths = options.take_roblox_paths();
+
+        if options.should_use_data_fast_path() {
+            log::trace!("override with data module fast path");
+            self.configuration.set_data_fast_path(true);
+        }
+
+        if options.is_strict() {
+            log::trace!("override with strict mode");
+            self.configuration.set_strict(true);
+        }
+
+        if self.configuration.is_strict() {
+            let unsafe_rules = self.configuration.unsafe_rules();
+            if !unsafe_rules.is_empty() {
+                return Err(DarkluaError::unsafe_rules(unsafe_rules));
+            }
+        }
+
+        self.stages = self.setup_stages(options)?;
+
+        for preprocessor in options.take_preprocessors() {
+            log::trace!("add preprocessor `{}`", preprocessor.get_name());
+            self.configuration.push_preprocessor(preprocessor);
+        }
+
+        for postprocessor in options.take_postprocessors() {
+            log::trace!("add postprocessor `{}`", postprocessor.get_name());
+            self.configuration.push_postprocessor(postprocessor);
+        }
+
+        self.incremental = options.should_use_incremental_generation()
+            && self.configuration.supports_incremental_work();
+        self.verify_incremental = options.should_verify_incremental_generation();
+
+        if self.incremental {
+            log::trace!("process files incrementally");
+        } else if options.should_use_incremental_generation() {
+            log::debug!(
+                "unable to process files incrementally (the configuration uses a bundle, the data module fast path, the `retain_lines` generator or rules that are not statement-local)"
+            );
+        }
+
+        self.mark_processed = options.should_mark_processed();
+        self.reprocess_policy = options.reprocess_policy();
+        self.max_output_size = options.max_output_size();
+        if options.should_self_extract() {
+            log::trace!("generate self-extracting files");
+            self.self_extract = Some(
+                SelfExtract::for_target(self.configuration.target())
+                    .map_err(DarkluaError::custom)?,
+            );
+        }
+        self.hash_algorithm = options.hash_algorithm().or_else(|| {
+            self.configuration
+                .artifacts()
+                .values()
+                .any(ArtifactTemplate::uses_content_hash)
+                .then(HashAlgorithm::default)
+        });
+        self.output_template = options.output_template().cloned();
+        self.profile_rules = options.should_profile_rules();
+        self.rule_timeout = options
+            .rule_timeout()
+            .or_else(|| self.configuration.rule_timeout());
+
+        if self.mark_processed {
+            log::trace!("mark generated files as processed");
+            self.config_hash = xxh3_64(self.configuration.fingerprint().as_bytes());
+        }
+
         log::trace!(
             "configuration setup in {}",
             configuration_setup_timer.duration_label()
         );
+        #[cfg(feature = "serde-config")]
         log::debug!(
             "using configuration: {}",
             json5::to_string(&self.configuration).unwrap_or_else(|err| {
                 format!("? (unable to serialize configuration: {})", err)
             })
         );
+        #[cfg(not(feature = "serde-config"))]
+        log::debug!("using configuration: {:?}", self.configuration);
 
         Ok(())
     }
 
+    fn setup_stages(&self, options: &Options) -> DarkluaResult<Option<StageRun>> {
+        let stages = self.configuration.stages();
+
+        if stages.is_empty() {
+            return match options.stage() {
+                Some(StageSelection::Named(name)) => Err(DarkluaError::custom(format!(
+                    "unable to run stage `{}` because the configuration does not define stages",
+                    name
+                ))),
+                Some(StageSelection::All) | None => Ok(None),
+            };
+        }
+
+        let cache_directory = options
+            .cache_directory()
+            .map(Path::to_path_buf)
+            .or_else(|| {
+                self.configuration.cache_directory().map(|directory| {
+                    match self.configuration.location() {
+                        Some(location) => location.join(directory),
+                        None => directory.to_path_buf(),
+                    }
+                })
+            });
+
+        let stage_run = StageRun::new(
+            self.resources,
+            stages,
+            options.stage().unwrap_or(&StageSelection::All),
+            cache_directory,
+        )?;
+
+        let selected = &stages[stage_run.indexes()];
+        log::trace!(
+            "run stage{} {}",
+            maybe_plural(selected.len()),
+            selected
+                .iter()
+                .map(|stage| format!("`{}`", stage.name()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        Ok(Some(stage_run))
+    }
+
     pub(crate) fn configuration(&self) -> &Configuration {
         &self.configuration
     }
 
+    /// Writes the state of the last stage that ran, so that the next stage can run in
+    /// another process.
+    pub(crate) fn write_stage_state(&self) -> DarkluaResult<()> {
+        match self.stages.as_ref() {
+            Some(stages) => stages.write_state(self.resources),
+            None => Ok(()),
+        }
+    }
+
+    fn stage_name(&self, stage: Option<usize>) -> Option<&str> {
+        stage.map(|index| self.configuration.stages()[index].name())
+    }
+
+    pub(crate) fn take_staging(&mut self) -> Option<OutputStaging> {
+        self.staging.take()
+    }
+
     pub(crate) fn advance_work(&mut self, work_item: &mut WorkItem) -> DarkluaResult<()> {
         match &work_item.status {
             WorkStatus::NotStarted => {
-                let source_display = work_item.source().display();
+                let previous_state = if self.incremental {
+                    work_item.incremental.take()
+                } else {
+                    None
+                };
+
+                if work_item.stage.is_none() {
+                    if let Some(stages) = self.stages.as_ref() {
+                        work_item.stage = Some(StageProgress {
+                            index: stages.first(),
+                            content: None,
+                            reserved_identifiers: stages
+                                .previous_reserved_identifiers(work_item.source()),
+                        });
+                    }
+                }
+                let stage = work_item.stage.as_ref().map(|stage| stage.index);
+
+                let source_display = work_item.data.source().display();
 
-                let content = self.resources.get(work_item.source())?;
+                let content = match work_item
+                    .stage
+                    .as_mut()
+                    .and_then(|stage| stage.content.take())
+                {
+                    Some(content) => content,
+                    None => {
+                        let content = self.resources.get(work_item.source())?;
+                        work_item.report.input_size = content.len() as u64;
+                        content
+                    }
+                };
+
+                let (content, previous_marker) = match ProcessedMarker::split(&content)
+                    .map(|(code, marker)| (code.to_owned(), marker))
+                {
+                    Some((code, marker)) => (code, Some(marker)),
+                    None => (content, None),
+                };
+
+                let previous_marker = previous_marker.filter(|marker| {
+                    let same_stage = marker.stage() == self.stage_name(stage);
+                    if !same_stage {
+                        log::debug!(
+                            "process `{}` (already processed by {})",
+                            source_display,
+                            marker
+                                .stage()
+                                .map(|name| format!("stage `{}`", name))
+                                .unwrap_or_else(|| "darklua without stages".to_owned())
+                        );
+                    }
+                    same_stage
+                });
+
+                if let Some(marker) = previous_marker.as_ref() {
+                    if self.reprocess_policy == ReprocessPolicy::Skip {
+                        log::info!(
+                            "skip `{}` (already processed by darklua {})",
+                            source_display,
+                            marker.version()
+                        );
+                        work_item.status = WorkStatus::done();
+                        return Ok(());
+                    }
+
+                    log::debug!(
+                        "process `{}` again with the `{}` policy (already processed by darklua {})",
+                        source_display,
+                        self.reprocess_policy.as_str(),
+                        marker.version()
+                    );
+                }
+
+                let (content, line_offsets) = self.preprocess(work_item.source(), content)?;
 
                 let parser = self.configuration.build_parser();
 
@@ -134,15 +478,55 @@ impl<'a> Worker<'a> {
                 let parser_timer = Timer::now();
 
                 let mut block = parser.parse(&content).map_err(|parser_error| {
-                    DarkluaError::parser_error(work_item.source(), parser_error)
+                    DarkluaError::parser_error(
+                        work_item.source(),
+                        parser_error.with_line_offsets(line_offsets),
+                    )
                 })?;
 
                 let parser_time = parser_timer.duration_label();
                 log::debug!("parsed `{}` in {}", source_display, parser_time);
 
+                if let Some(previous_state) = previous_state.filter(|_| previous_marker.is_none()) {
+                    match self.process_incrementally(
+                        work_item.source(),
+                        &content,
+                        &block,
+                        &previous_state,
+                    )? {
+                        Some(state) => {
+                            return self.complete_incremental_work(work_item, content, state)
+                        }
+                        None => {
+                            log::debug!(
+                                "[{}] unable to process incrementally, process the whole file",
+                                source_display
+                            );
+                        }
+                    }
+                }
+
+                let original_block = if self.incremental {
+                    Some(block.clone())
+                } else {
+                    None
+                };
+
+                let original_global_names = stage.map(|_| find_global_names(&mut block));
+
                 self.bundle(work_item, &mut block, &content)?;
 
-                work_item.status = WorkProgress::new(content, block).into();
+                let mut work_progress = WorkProgress::new(content, block);
+                if let Some(names) = original_global_names {
+                    work_progress = work_progress.with_original_global_names(names);
+                }
+                if let Some(original_block) = original_block {
+                    work_progress = work_progress.with_original_block(original_block);
+                }
+                if let Some(marker) = previous_marker {
+                    work_progress = work_progress.with_previous_marker(marker);
+                }
+                work_item.status = work_progress.into();
 
                 self.apply_rules(work_item)
             }
@@ -151,23 +535,122 @@ impl<'a> Worker<'a> {
         }
     }
 
+    fn preprocess(&self, source: &Path, content: String) -> DarkluaResult<(String, LineOffsets)> {
+        let mut content = content;
+        let mut line_offsets = LineOffsets::default();
+
+        for preprocessor in self.configuration.preprocessors() {
+            let preprocessor_timer = Timer::now();
+            let context = self.create_rule_context(source, &content).build();
+
+            let (new_content, new_line_offsets) = preprocessor
+                .preprocess(&content, &context)
+                .map_err(|error| {
+                    DarkluaError::text_processor_error(source, preprocessor.get_name(), error)
+                })?
+                .into_parts();
+
+            let new_content = match new_content {
+                Cow::Borrowed(_) => None,
+                Cow::Owned(new_content) => Some(new_content),
+            };
+            if let Some(new_content) = new_content {
+                content = new_content;
+            }
+            line_offsets = line_offsets.followed_by(&new_line_offsets);
+
+            log::trace!(
+                "[{}] preprocessor `{}` completed in {}",
+                source.display(),
+                preprocessor.get_name(),
+                preprocessor_timer.duration_label()
+            );
+        }
+
+        Ok((content, line_offsets))
+    }
+
+    fn postprocess(
+        &self,
+        source: &Path,
+        original_code: &str,
+        code: String,
+    ) -> DarkluaResult<String> {
+        let mut code = code;
+
+        for postprocessor in self.configuration.postprocessors() {
+            let postprocessor_timer = Timer::now();
+            let context = self.create_rule_context(source, original_code).build();
+
+            let new_code = match postprocessor
+                .postprocess(&code, &context)
+                .map_err(|error| {
+                    DarkluaError::text_processor_error(source, postprocessor.get_name(), error)
+                })? {
+                Cow::Borrowed(_) => None,
+                Cow::Owned(new_code) => Some(new_code),
+            };
+            if let Some(new_code) = new_code {
+                code = new_code;
+            }
+
+            log::trace!(
+                "[{}] postprocessor `{}` completed in {}",
+                source.display(),
+                postprocessor.get_name(),
+                postprocessor_timer.duration_label()
+            );
+        }
+
+        Ok(code)
+    }
+
+    #[cfg(feature = "serde-config")]
     fn read_configuration(&self, config: &Path) -> DarkluaResult<Configuration> {
-        let config_content = self.resources.get(config)?;
-        json5::from_str(&config_content)
-            .map_err(|err| {
-                DarkluaError::invalid_configuration_file(config).context(err.to_string())
-            })
-            .map(|configuration: Configuration| {
-                configuration.with_location({
-                    config.parent().unwrap_or_else(|| {
-                        log::warn!(
-                            "unexpected configuration path `{}` (unable to extract parent path)",
-                            config.display()
-                        );
-                        config
-                    })
-                })
-            })
+        let value = resolve_configuration_value(self.resources, config)?;
+
+        // discard notices left by rules parsed outside of a configuration file
+        take_rule_deprecations();
+
+        let configuration = deserialize_configuration(config, value)?;
+
+        self.check_deprecations(config)?;
+
+        Ok(configuration)
+    }
+
+    #[cfg(not(feature = "serde-config"))]
+    fn read_configuration(&self, config: &Path) -> DarkluaResult<Configuration> {
+        Err(DarkluaError::invalid_configuration_file(config)
+            .context("reading configuration files requires the `serde-config` feature"))
+    }
+
+    #[cfg(feature = "serde-config")]
+    fn check_deprecations(&self, config: &Path) -> DarkluaResult<()> {
+        let deprecations = take_rule_deprecations();
+
+        match self.deprecation_policy {
+            DeprecationPolicy::Warn => {
+                for deprecation in deprecations {
+                    log::warn!("{} (in `{}`)", deprecation, config.display());
+                }
+                Ok(())
+            }
+            DeprecationPolicy::Allow => Ok(()),
+            DeprecationPolicy::Deny => {
+                if deprecations.is_empty() {
+                    Ok(())
+                } else {
+                    Err(DarkluaError::invalid_configuration_file(config).context(
+                        deprecations
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ))
+                }
+            }
+        }
     }
 
     fn apply_rules(&mut self, work_item: &mut WorkItem) -> DarkluaResult<()> {
@@ -181,16 +664,80 @@ impl<'a> Worker<'a> {
         let source_display = work_item.data.source().display();
         let normalized_source = normalize_path(work_item.data.source());
 
+        let stage = work_item.stage.as_ref().map(|stage| stage.index);
+        let mut environment = self.environment.clone();
+        if let Some(stage) = work_item.stage.as_ref() {
+            reserve_identifiers(&mut environment, stage.reserved_identifiers.iter());
+        }
+        // the identifiers reserved by the applied rules, avoided by the rules generating names
+        let mut reserved_identifiers = Vec::new();
+
         progress.duration().start();
 
+        let use_data_fast_path =
+            self.configuration.data_fast_path() && is_data_module(progress.block());
+        let mut skipped_rules = 0;
+        let mut already_applied_rules = 0;
+
+        if use_data_fast_path {
+            log::debug!("using data module fast path for `{}`", source_display);
+        }
+
+        let dynamic_environment = Arc::new(DynamicEnvironmentUsage::find(progress.mutate_block()));
+
         for (index, rule) in self
             .configuration
-            .rules()
+            .rules(stage)
             .enumerate()
             .skip(progress.next_rule())
         {
-            let mut context_builder =
-                self.create_rule_context(work_item.data.source(), &work_progress.content);
+            if work_progress
+                .previous_marker
+                .as_ref()
+                .filter(|marker| marker.has_rule(rule.get_name()))
+                .is_some()
+            {
+                match self.reprocess_policy {
+                    ReprocessPolicy::Differential => {
+                        log::trace!(
+                            "[{}] skip rule `{}` (already applied)",
+                            source_display,
+                            rule.get_name(),
+                        );
+                        already_applied_rules += 1;
+                        continue;
+                    }
+                    ReprocessPolicy::Force | ReprocessPolicy::Skip
+                        if rule.is_unsafe_to_reapply() =>
+                    {
+                        log::info!(
+                            "[{}] skip rule `{}` (already applied and unsafe to apply again)",
+                            source_display,
+                            rule.get_name(),
+                        );
+                        already_applied_rules += 1;
+                        continue;
+                    }
+                    ReprocessPolicy::Force | ReprocessPolicy::Skip => {}
+                }
+            }
+
+            if use_data_fast_path && !rule.applies_to_data_modules() {
+                log::trace!(
+                    "[{}] skip rule `{}` (data module fast path)",
+                    source_display,
+                    rule.get_name(),
+                );
+                skipped_rules += 1;
+                continue;
+            }
+
+            let mut context_builder = self
+                .create_rule_context(work_item.data.source(), &work_progress.content)
+                .with_environment(environment.clone())
+                .with_dynamic_environment(dynamic_environment.clone())
+                .with_reserved_identifiers(reserved_identifiers.clone())
+                .with_deferred_injections();
             log::trace!(
                 "[{}] apply rule `{}`{}",
                 source_display,
@@ -254,31 +801,53 @@ impl<'a> Worker<'a> {
                 }
             }
 
+            if let Some(timeout) = self.rule_timeout {
+                context_builder = context_builder.with_deadline(Instant::now() + timeout);
+            }
             let context = context_builder.build();
             let block = progress.mutate_block();
             let rule_timer = Timer::now();
 
             let source = work_item.data.source();
 
-            let rule_result = rule.process(block, &context).map_err(|rule_error| {
-                let error = DarkluaError::rule_error(source, rule, index, rule_error);
-
+            let rule_result = process_rule_within_budget(rule, block, &context, |rule_error| {
+                DarkluaError::rule_error(source, rule, index, rule_error)
+            })
+            .inspect_err(|error| {
                 log::trace!(
                     "[{}] rule `{}` errored: {}",
                     source_display,
                     rule.get_name(),
                     error
                 );
-
-                error
             });
 
+            if let Ok(RuleRun::TimedOut(elapsed)) = rule_result {
+                self.report_timed_out_rule(source, &mut work_item.report, rule, elapsed);
+                continue;
+            }
+
+            let mut reprocess_requests = context.take_reprocess_requests();
+            progress.extend_injections(context.take_injections());
+            work_item.report.artifacts.extend(context.take_artifacts());
+            reserved_identifiers = context.take_reserved_identifiers();
+
             work_item
                 .external_file_dependencies
                 .extend(context.into_dependencies());
 
             rule_result?;
 
+            work_progress
+                .applied_rules
+                .insert(rule.get_name().to_owned());
+
+            if self.profile_rules {
+                work_item
+                    .report
+                    .record_rule(rule.get_name(), rule_timer.duration());
+            }
+
             let rule_duration = rule_timer.duration_label();
             log::trace!(
                 "[{}] ⨽completed `{}` in {}",
@@ -286,21 +855,190 @@ impl<'a> Worker<'a> {
                 rule.get_name(),
                 rule_duration
             );
+
+            let mut reprocess_runs = 0;
+
+            while !reprocess_requests.is_empty() {
+                let mut requested_rules: Vec<_> = reprocess_requests
+                    .drain(..)
+                    .filter_map(|rule_name| {
+                        let requested = self
+                            .configuration
+                            .rules(stage)
+                            .take(index)
+                            .enumerate()
+                            .filter(|(_, earlier_rule)| earlier_rule.get_name() == rule_name)
+                            .last();
+
+                        if requested.is_none() {
+                            log::debug!(
+                                "[{}] ignore reprocess request of rule `{}` from `{}` (rule did not run before)",
+                                source_display,
+                                rule_name,
+                                rule.get_name(),
+                            );
+                        }
+
+                        requested
+                    })
+                    .collect();
+                requested_rules.sort_by_key(|(requested_index, _)| *requested_index);
+                requested_rules.dedup_by_key(|(requested_index, _)| *requested_index);
+
+                for (requested_index, requested_rule) in requested_rules {
+                    if reprocess_runs >= MAX_REPROCESS_RUNS {
+                        log::debug!(
+                            "[{}] stop reprocessing rules after `{}` (limit of {} runs reached)",
+                            source_display,
+                            rule.get_name(),
+                            MAX_REPROCESS_RUNS,
+                        );
+                        reprocess_requests.clear();
+                        break;
+                    }
+
+                    if use_data_fast_path && !requested_rule.applies_to_data_modules() {
+                        continue;
+                    }
+
+                    if !requested_rule
+                        .require_content(&normalized_source, progress.block())
+                        .is_empty()
+                    {
+                        log::debug!(
+                            "[{}] skip reprocessing rule `{}` (it requires other files)",
+                            source_display,
+                            requested_rule.get_name(),
+                        );
+                        continue;
+                    }
+
+                    log::trace!(
+                        "[{}] reprocess rule `{}` requested by `{}`",
+                        source_display,
+                        requested_rule.get_name(),
+                        rule.get_name(),
+                    );
+
+                    let mut context_builder = self
+                        .create_rule_context(source, &work_progress.content)
+                        .with_environment(environment.clone())
+                        .with_dynamic_environment(dynamic_environment.clone())
+                        .with_reserved_identifiers(reserved_identifiers.clone())
+                        .with_deferred_injections();
+                    if let Some(timeout) = self.rule_timeout {
+                        context_builder = context_builder.with_deadline(Instant::now() + timeout);
+                    }
+                    let context = context_builder.build();
+
+                    let rule_result = process_rule_within_budget(
+                        requested_rule,
+                        progress.mutate_block(),
+                        &context,
+                        |rule_error| {
+                            DarkluaError::rule_error(
+                                source,
+                                requested_rule,
+                                requested_index,
+                                rule_error,
+                            )
+                        },
+                    );
+
+                    reprocess_runs += 1;
+
+                    if let Ok(RuleRun::TimedOut(elapsed)) = rule_result {
+                        self.report_timed_out_rule(
+                            source,
+                            &mut work_item.report,
+                            requested_rule,
+                            elapsed,
+                        );
+                        continue;
+                    }
+                    reprocess_requests.extend(context.take_reprocess_requests());
+                    progress.extend_injections(context.take_injections());
+                    work_item.report.artifacts.extend(context.take_artifacts());
+                    reserved_identifiers = context.take_reserved_identifiers();
+
+                    work_item
+                        .external_file_dependencies
+                        .extend(context.into_dependencies());
+
+                    rule_result?;
+                }
+            }
         }
 
         let rule_time = progress.duration().duration_label();
-        let total_rules = self.configuration.rules_len();
+        let applied_rules =
+            self.configuration.rules_len(stage) - skipped_rules - already_applied_rules;
         log::debug!(
-            "{} rule{} applied in {} for `{}`",
-            total_rules,
-            maybe_plural(total_rules),
+            "{} rule{} applied in {} for `{}`{}",
+            applied_rules,
+            maybe_plural(applied_rules),
             rule_time,
             source_display,
+            if skipped_rules > 0 {
+                format!(
+                    " ({} rule{} skipped by the data module fast path)",
+                    skipped_rules,
+                    maybe_plural(skipped_rules)
+                )
+            } else {
+                "".to_owned()
+            }
         );
+        if already_applied_rules > 0 {
+            log::debug!(
+                "{} rule{} already applied to `{}` skipped",
+                already_applied_rules,
+                maybe_plural(already_applied_rules),
+                source_display
+            );
+        }
+
+        let injections = progress.take_injections();
+        if !injections.is_empty() {
+            log::debug!(
+                "inject {} statement{} at the top of `{}`",
+                injections.len(),
+                maybe_plural(injections.len()),
+                source_display
+            );
+            injections.apply(progress.mutate_block());
+        }
+
+        self.lower_to_target(work_item.data.source(), progress.mutate_block());
+
+        if let (Some(stage_progress), Some(original_global_names)) = (
+            work_item.stage.as_mut(),
+            work_progress.original_global_names.as_ref(),
+        ) {
+            let introduced: Vec<_> = find_global_names(progress.mutate_block())
+                .into_iter()
+                .filter(|name| !original_global_names.contains(name))
+                .collect();
+
+            if !introduced.is_empty() {
+                log::debug!(
+                    "[{}] reserve identifier{} introduced by stage `{}`: {}",
+                    source_display,
+                    maybe_plural(introduced.len()),
+                    self.configuration.stages()[stage_progress.index].name(),
+                    introduced.join(", ")
+                );
+            }
+
+            stage_progress.reserved_identifiers.extend(introduced);
+        }
 
         log::trace!("begin generating code for `{}`", source_display);
 
-        if cfg!(test) || (cfg!(debug_assertions) && log::log_enabled!(log::Level::Trace)) {
+        if !self.check
+            && self.output_template.is_none()
+            && (cfg!(test) || (cfg!(debug_assertions) && log::log_enabled!(log::Level::Trace)))
+        {
             log::trace!(
                 "generate AST debugging view at `{}`",
                 work_item.data.output().display()
@@ -311,9 +1049,26 @@ impl<'a> Worker<'a> {
 
         let generator_timer = Timer::now();
 
-        let lua_code = self
-            .configuration
-            .generate_lua(progress.block(), &work_progress.content);
+        let generated = work_progress
+            .original_block
+            .take()
+            .and_then(|original_block| {
+                self.configuration
+                    .generate_segmented_lua(progress.block())
+                    .map(|generated| (original_block, generated))
+            });
+
+        let (lua_code, incremental_state) = match generated {
+            Some((original_block, generated)) => (
+                generated.code().to_owned(),
+                IncrementalState::new(original_block, progress.block().clone(), generated),
+            ),
+            None => (
+                self.configuration
+                    .generate_lua(progress.block(), &work_progress.content),
+                None,
+            ),
+        };
 
         let generator_time = generator_timer.duration_label();
         log::debug!(
@@ -322,21 +1077,431 @@ impl<'a> Worker<'a> {
             generator_time,
         );
 
-        self.resources.write(work_item.data.output(), &lua_code)?;
+        let lua_code =
+            self.postprocess(work_item.data.source(), &work_progress.content, lua_code)?;
+        let lua_code = self.compress_output(
+            work_item.data.source(),
+            work_item.stage.as_ref(),
+            &mut work_item.report,
+            lua_code,
+        );
+        let lua_code = self.append_processed_marker(
+            lua_code,
+            work_progress.applied_rules.iter().cloned(),
+            stage,
+        );
+
+        self.write_output(work_item, lua_code)?;
+
+        work_item.incremental = incremental_state.map(Box::new);
+        Ok(())
+    }
 
-        self.cache
-            .link_source_to_output(normalized_source, work_item.data.output());
+    /// Processes and generates only the top-level statements that changed since the previous
+    /// state. Returns `None` when the previous state cannot be used.
+    fn process_incrementally(
+        &self,
+        source: &Path,
+        content: &str,
+        block: &Block,
+        previous_state: &IncrementalState,
+    ) -> DarkluaResult<Option<IncrementalState>> {
+        let incremental_timer = Timer::now();
+
+        let change = BlockChange::find(previous_state.original(), block);
+
+        let mut changed_block = change.changed_block(block);
+        self.apply_statement_local_rules(source, content, &mut changed_block)?;
+
+        let processed = match change.splice(previous_state.processed(), block, changed_block) {
+            Some(processed) => processed,
+            None => return Ok(None),
+        };
+
+        let (generated, regenerated) = match self.configuration.regenerate_segmented_lua(
+            previous_state.generated(),
+            previous_state.processed(),
+            &processed,
+        ) {
+            Some(result) => result,
+            None => return Ok(None),
+        };
+
+        log::debug!(
+            "[{}] processed {} and generated {} of {} top-level statements in {}",
+            source.display(),
+            change.changed_len(block),
+            regenerated,
+            segments_len(&processed),
+            incremental_timer.duration_label(),
+        );
+
+        let state = match IncrementalState::new(block.clone(), processed, generated) {
+            Some(state) => state,
+            None => return Ok(None),
+        };
 
+        if self.verify_incremental {
+            self.verify_incremental_work(source, content, block, state)
+        } else {
+            Ok(Some(state))
+        }
+    }
+
+    /// Processes the whole block and compares the checksum of its generated code with the
+    /// incremental result. The result of the whole block is used when they differ.
+    fn verify_incremental_work(
+        &self,
+        source: &Path,
+        content: &str,
+        block: &Block,
+        state: IncrementalState,
+    ) -> DarkluaResult<Option<IncrementalState>> {
+        let mut processed = block.clone();
+        self.apply_statement_local_rules(source, content, &mut processed)?;
+
+        let generated = match self.configuration.generate_segmented_lua(&processed) {
+            Some(generated) => generated,
+            None => return Ok(None),
+        };
+
+        if xxh3_64(generated.code().as_bytes()) == xxh3_64(state.generated().code().as_bytes()) {
+            Ok(Some(state))
+        } else {
+            log::warn!(
+                "[{}] incremental generation does not match the generation of the whole file (the whole file is used instead)",
+                source.display()
+            );
+            Ok(IncrementalState::new(block.clone(), processed, generated))
+        }
+    }
+
+    fn apply_statement_local_rules(
+        &self,
+        source: &Path,
+        content: &str,
+        block: &mut Block,
+    ) -> DarkluaResult<()> {
+        let dynamic_environment = Arc::new(DynamicEnvironmentUsage::find(block));
+
+        for (index, rule) in self.configuration.rules(None).enumerate() {
+            let context = self
+                .create_rule_context(source, content)
+                .with_dynamic_environment(dynamic_environment.clone())
+                .build();
+
+            process_rule(rule, block, &context, |rule_error| {
+                DarkluaError::rule_error(source, rule, index, rule_error)
+            })?;
+        }
+
+        self.lower_to_target(source, block);
+
+        Ok(())
+    }
+
+    fn lower_to_target(&self, source: &Path, block: &mut Block) {
+        if let Some(target) = self.configuration.target() {
+            let removed = lower_to_target(block, target);
+
+            if removed > 0 {
+                log::debug!(
+                    "removed {} function attribute{} from `{}` (not supported by `{}` target)",
+                    removed,
+                    maybe_plural(removed),
+                    source.display(),
+                    target.as_str(),
+                );
+            }
+        }
+    }
+
+    fn complete_incremental_work(
+        &mut self,
+        work_item: &mut WorkItem,
+        content: String,
+        state: IncrementalState,
+    ) -> DarkluaResult<()> {
+        let lua_code = self.postprocess(
+            work_item.data.source(),
+            &content,
+            state.generated().code().to_owned(),
+        )?;
+        let lua_code = self.compress_output(
+            work_item.data.source(),
+            work_item.stage.as_ref(),
+            &mut work_item.report,
+            lua_code,
+        );
+        let lua_code = self.append_processed_marker(
+            lua_code,
+            self.configuration
+                .rules(None)
+                .map(|rule| rule.get_name().to_owned()),
+            None,
+        );
+
+        self.write_output(work_item, lua_code)?;
+
+        work_item.incremental = Some(Box::new(state));
+        Ok(())
+    }
+
+    /// Compresses the generated code into a self-extracting file when it is enabled. The code
+    /// given to the next stage is not compressed.
+    fn compress_output(
+        &self,
+        source: &Path,
+        stage: Option<&StageProgress>,
+        report: &mut WorkReport,
+        lua_code: String,
+    ) -> String {
+        let self_extract = match self.self_extract {
+            Some(self_extract) if self.next_stage(stage).is_none() => self_extract,
+            _ => return lua_code,
+        };
+
+        let compressed = self_extract.wrap(&lua_code);
+        log::debug!(
+            "[{}] compressed generated code from {} to {} bytes",
+            source.display(),
+            lua_code.len(),
+            compressed.len()
+        );
+        report.uncompressed_size = Some(lua_code.len() as u64);
+        compressed
+    }
+
+    fn next_stage(&self, stage: Option<&StageProgress>) -> Option<usize> {
+        stage
+            .zip(self.stages.as_ref())
+            .and_then(|(stage, stages)| stages.next(stage.index))
+    }
+
+    /// Appends the processed marker to the generated code when it is enabled. The marker is
+    /// added after the postprocessors so that it is never removed.
+    fn append_processed_marker(
+        &self,
+        lua_code: String,
+        rules: impl IntoIterator<Item = String>,
+        stage: Option<usize>,
+    ) -> String {
+        if self.mark_processed {
+            let marker = ProcessedMarker::new(self.config_hash, rules);
+            match self.stage_name(stage) {
+                Some(name) => marker.with_stage(name),
+                None => marker,
+            }
+            .append_to(lua_code)
+        } else {
+            lua_code
+        }
+    }
+
+    fn write_output(&mut self, work_item: &mut WorkItem, lua_code: String) -> DarkluaResult<()> {
+        let size = lua_code.len() as u64;
+        if let Some(max_size) = self.max_output_size {
+            if size > max_size {
+                return Err(DarkluaError::custom(format!(
+                    "generated code for `{}` is {} bytes, which is larger than the maximum size of {} bytes",
+                    work_item.data.output().display(),
+                    size,
+                    max_size
+                )));
+            }
+        }
+
+        let violations = check_output(self.configuration.checks(), &lua_code);
+        if !violations.is_empty() {
+            return Err(DarkluaError::custom(format!(
+                "generated code for `{}` does not pass the output checks:\n{}",
+                work_item.data.output().display(),
+                violations
+                    .iter()
+                    .map(|violation| format!("  {}", violation))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )));
+        }
+
+        if let Some(next_stage) = self.next_stage(work_item.stage.as_ref()) {
+            return self.continue_with_stage(work_item, next_stage, lua_code);
+        }
+
+        let content_hash = self
+            .hash_algorithm
+            .map(|algorithm| algorithm.digest(lua_code.as_bytes()));
+
+        let output = match &self.output_template {
+            Some(template) => {
+                let output = template.render(work_item.data.output(), content_hash.as_deref());
+
+                if let Some(previous_source) = self
+                    .templated_outputs
+                    .insert(output.clone(), work_item.source().to_path_buf())
+                    .filter(|previous_source| previous_source != work_item.source())
+                {
+                    return Err(DarkluaError::custom(format!(
+                        "`{}` and `{}` would both be written to `{}`",
+                        previous_source.display(),
+                        work_item.source().display(),
+                        output.display()
+                    )));
+                }
+
+                output
+            }
+            None => work_item.data.output().to_path_buf(),
+        };
+
+        if self.check {
+            let is_up_to_date =
+                self.resources.exists(&output)? && self.resources.get(&output)? == lua_code;
+
+            if !is_up_to_date {
+                return Err(DarkluaError::custom(format!(
+                    "`{}` would be modified",
+                    output.display()
+                )));
+            }
+            self.cache
+                .link_source_to_output(normalize_path(work_item.data.source()), &output);
+        } else {
+            let written_output = match self.staging.as_mut() {
+                Some(staging) => staging.stage(&output),
+                None => output.clone(),
+            };
+
+            self.resources.write(&written_output, &lua_code)?;
+
+            self.cache
+                .link_source_to_output(normalize_path(work_item.data.source()), written_output);
+
+            self.write_artifacts(work_item, &output, content_hash.as_deref())?;
+
+            if let Some((stages, stage)) = self.stages.as_mut().zip(work_item.stage.as_ref()) {
+                stages.record(&output, stage.reserved_identifiers.clone());
+            }
+        }
+
+        work_item.report.output_size = Some(size);
+        work_item.report.content_hash = content_hash;
+        if self.output_template.is_some() {
+            work_item.report.output = Some(output);
+        }
         work_item.status = WorkStatus::done();
         Ok(())
     }
 
-    fn create_rule_context<'block, 'src>(
+    /// Gives the code generated by a stage to the next stage, without writing it.
+    fn continue_with_stage(
+        &mut self,
+        work_item: &mut WorkItem,
+        next_stage: usize,
+        lua_code: String,
+    ) -> DarkluaResult<()> {
+        log::debug!(
+            "[{}] continue with stage `{}`",
+            work_item.source().display(),
+            self.configuration.stages()[next_stage].name()
+        );
+
+        if let Some(stage) = work_item.stage.as_mut() {
+            stage.index = next_stage;
+            stage.content = Some(lua_code);
+        }
+        work_item.status = WorkStatus::NotStarted;
+
+        self.advance_work(work_item)
+    }
+
+    /// Writes the artifacts emitted by the rules for which a path is configured. The other
+    /// artifacts are only available from the report of the file.
+    fn write_artifacts(
+        &mut self,
+        work_item: &WorkItem,
+        output: &Path,
+        content_hash: Option<&str>,
+    ) -> DarkluaResult<()> {
+        for artifact in work_item.report.artifacts.iter() {
+            let template = match self.configuration.artifacts().get(artifact.name()) {
+                Some(template) => template,
+                None => {
+                    log::debug!(
+                        "[{}] drop artifact `{}` (no path is configured for it)",
+                        work_item.source().display(),
+                        artifact.name()
+                    );
+                    continue;
+                }
+            };
+
+            let path = template.render(output, content_hash);
+
+            if let Some(previous_source) = self
+                .written_artifacts
+                .insert(path.clone(), work_item.source().to_path_buf())
+                .filter(|previous_source| previous_source != work_item.source())
+            {
+                return Err(DarkluaError::custom(format!(
+                    "`{}` and `{}` would both write an artifact to `{}`",
+                    previous_source.display(),
+                    work_item.source().display(),
+                    path.display()
+                )));
+            }
+
+            let written_path = match self.staging.as_mut() {
+                Some(staging) => staging.stage(&path),
+                None => path,
+            };
+
+            log::debug!(
+                "[{}] write artifact `{}` to `{}`",
+                work_item.source().display(),
+                artifact.name(),
+                written_path.display()
+            );
+
+            self.resources
+                .write(&written_path, &artifact.content().to_text())?;
+        }
+
+        Ok(())
+    }
+
+    fn report_timed_out_rule(
+        &self,
+        source: &Path,
+        report: &mut WorkReport,
+        rule: &dyn Rule,
+        elapsed: Duration,
+    ) {
+        log::warn!(
+            "rule `{}` exceeded its time budget of {} on `{}` and was skipped (stopped after {})",
+            rule.get_name(),
+            durationfmt::to_string(self.rule_timeout.unwrap_or_default()),
+            source.display(),
+            durationfmt::to_string(elapsed),
+        );
+        report.record_timed_out_rule(rule.get_name(), elapsed);
+    }
+
+    pub(crate) fn create_rule_context<'block, 'src>(
         &self,
         source: &Path,
         original_code: &'src str,
     ) -> ContextBuilder<'block, 'a, 'src> {
-        let builder = ContextBuilder::new(normalize_path(source), self.resources, original_code);
+        let source = normalize_path(source);
+        let roblox_path = self.roblox_paths.get(&source).cloned();
+        let mut builder = ContextBuilder::new(source, self.resources, original_code)
+            .with_environment(self.environment.clone());
+        if let Some(roblox_path) = roblox_path {
+            builder = builder.with_roblox_path(roblox_path);
+        }
+        if let Some(prefix) = self.configuration.generated_name_prefix() {
+            builder = builder.with_generated_name_prefix(prefix);
+        }
         if let Some(project_location) = self.configuration.location() {
             builder.with_project_location(project_location)
         } else {
@@ -368,17 +1533,16 @@ impl<'a> Worker<'a> {
             .create_rule_context(work_item.source(), original_code)
             .build();
 
-        let rule_result = bundler.process(block, &context).map_err(|rule_error| {
-            let error = DarkluaError::orphan_rule_error(work_item.source(), bundler, rule_error);
-
+        let rule_result = process_rule(bundler, block, &context, |rule_error| {
+            DarkluaError::orphan_rule_error(work_item.source(), bundler, rule_error)
+        })
+        .inspect_err(|error| {
             log::trace!(
                 "[{}] rule `{}` errored: {}",
                 work_item.source().display(),
                 bundler.get_name(),
                 error
             );
-
-            error
         });
 
         work_item
@@ -397,3 +1561,103 @@ impl<'a> Worker<'a> {
         Ok(())
     }
 }
+
+/// The result of a rule applied with [`process_rule_within_budget`].
+enum RuleRun {
+    Completed,
+    /// The rule ran past the deadline of its context after the given time, so the block was
+    /// restored to its state before the rule.
+    TimedOut(Duration),
+}
+
+/// Adds the given identifiers to the globals of the environment, so that the following rules
+/// do not generate them.
+fn reserve_identifiers<I: AsRef<str>>(
+    environment: &mut Arc<Environment>,
+    identifiers: impl IntoIterator<Item = I>,
+) {
+    for identifier in identifiers {
+        let identifier = identifier.as_ref();
+        if !environment.has_global(identifier) {
+            Arc::make_mut(environment).insert_global(GlobalEntry::value(identifier));
+        }
+    }
+}
+
+/// Applies a rule like [`process_rule`], cancelling it when the deadline of the context passes.
+/// The rule may leave the block partially processed when it is cancelled, so the block is
+/// cloned before applying the rule and restored when the deadline has passed. Errors from a
+/// cancelled rule are ignored.
+fn process_rule_within_budget(
+    rule: &dyn Rule,
+    block: &mut Block,
+    context: &Context,
+    create_error: impl FnOnce(String) -> DarkluaError,
+) -> DarkluaResult<RuleRun> {
+    let Some(deadline) = context.deadline() else {
+        return process_rule(rule, block, context, create_error).map(|()| RuleRun::Completed);
+    };
+
+    let snapshot = block.clone();
+    let rule_timer = Timer::now();
+
+    let tracker = DeadlineTracker::start(deadline);
+    let result = process_rule(rule, block, context, create_error);
+    drop(tracker);
+
+    if context.is_cancelled() {
+        *block = snapshot;
+        return Ok(RuleRun::TimedOut(rule_timer.duration()));
+    }
+
+    result.map(|()| RuleRun::Completed)
+}
+
+/// Applies a rule while tracking the path of the statement it processes. When the rule returns
+/// an error or panics, the error created from the rule message points at that statement.
+fn process_rule(
+    rule: &dyn Rule,
+    block: &mut Block,
+    context: &Context,
+    create_error: impl FnOnce(String) -> DarkluaError,
+) -> DarkluaResult<()> {
+    let tracker = NodePathTracker::start();
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| rule.process(block, context)));
+
+    let (message, node_path) = match result {
+        Ok(Ok(())) => return Ok(()),
+        Ok(Err(message)) => (
+            message,
+            context
+                .take_failure_path()
+                .or_else(NodePathTracker::current_path),
+        ),
+        Err(payload) => (
+            format!("rule panicked: {}", panic_message(payload.as_ref())),
+            NodePathTracker::current_path(),
+        ),
+    };
+
+    drop(tracker);
+
+    let error = create_error(message);
+
+    Err(match node_path.filter(|node_path| !node_path.is_empty()) {
+        Some(node_path) => {
+            let code = node_path.generate_statement(block);
+            error.with_rule_location(node_path.to_string(), code)
+        }
+        None => error,
+    })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
@@ -6,11 +6,18 @@ use std::{
 use petgraph::{algo::toposort, graph::NodeIndex, stable_graph::StableDiGraph, visit::Dfs};
 use xxhash_rust::xxh3::xxh3_64;
 
-use crate::{frontend::utils::maybe_plural, utils::Timer, DarkluaError};
+use crate::{
+    frontend::utils::maybe_plural,
+    utils::{absolute_path, log, relative_path, Timer},
+    DarkluaError,
+};
 
 use super::{
-    normalize_path, work_item::WorkStatus, Configuration, DarkluaResult, Options, Resources,
-    WorkItem, Worker,
+    content_hash::format_hash_manifest,
+    input_pattern::{is_input_pattern, ExcludePatterns, InputPattern},
+    normalize_path,
+    work_item::WorkStatus,
+    AtomicMode, Configuration, DarkluaResult, Options, Resources, WorkItem, Worker,
 };
 
 #[derive(Debug, Default)]
@@ -27,7 +34,9 @@ impl WorkerTree {
         log::trace!("start collecting work");
         let collect_work_timer = Timer::now();
 
-        if let Some(output) = options.output().map(Path::to_path_buf) {
+        if let Some(files) = options.files() {
+            self.collect_listed_files(files, options)?;
+        } else if let Some(output) = options.output().map(Path::to_path_buf) {
             if resources.is_file(options.input())? {
                 if resources.is_directory(&output)? {
                     let file_name = options.input().file_name().ok_or_else(|| {
@@ -51,17 +60,14 @@ impl WorkerTree {
                     self.add_source_if_missing(options.input(), Some(output.join(file_name)));
                 }
             } else {
-                let input = options.input().to_path_buf();
-
-                for source in resources.collect_work(&input) {
-                    let source = normalize_path(source);
+                let (input, sources) = collect_input_sources(resources, options)?;
 
-                    let relative_path = source.strip_prefix(&input).map_err(|err| {
+                for source in sources {
+                    let relative_path = relative_path(&source, &input).ok_or_else(|| {
                         DarkluaError::custom(format!(
-                            "unable to remove path prefix `{}` from `{}`: {}",
+                            "unable to remove path prefix `{}` from `{}`",
                             input.display(),
                             source.display(),
-                            err
                         ))
                     })?;
 
@@ -70,18 +76,84 @@ impl WorkerTree {
                 }
             }
         } else {
-            let input = options.input().to_path_buf();
+            let (_, sources) = collect_input_sources(resources, options)?;
 
-            for source in resources.collect_work(input) {
+            for source in sources {
                 self.add_source_if_missing(source, None);
             }
         }
 
+        self.verify_distinct_outputs(resources, options)?;
+
         log::trace!("work collected in {}", collect_work_timer.duration_label());
 
         Ok(())
     }
 
+    fn collect_listed_files(&mut self, files: &[PathBuf], options: &Options) -> DarkluaResult<()> {
+        let input = normalize_path(options.input());
+        let mut listed = HashSet::new();
+
+        for file in files {
+            let source = normalize_path(file);
+
+            // the same file can be listed with a relative and an absolute path
+            if !listed.insert(absolute_path(&source)) {
+                continue;
+            }
+
+            let output_path = if let Some(output) = options.output() {
+                let relative_path = relative_path(&source, &input).ok_or_else(|| {
+                    DarkluaError::custom(format!(
+                        "unable to process `{}` because it is not inside the input path `{}`",
+                        source.display(),
+                        input.display(),
+                    ))
+                })?;
+                Some(output.join(relative_path))
+            } else {
+                None
+            };
+
+            // missing files are still added so that they are reported as errors
+            self.add_source_if_missing(source, output_path);
+        }
+
+        Ok(())
+    }
+
+    /// Fails when two sources would be written to the same output. When symlinks are
+    /// followed, the outputs are compared after resolving them.
+    fn verify_distinct_outputs(
+        &self,
+        resources: &Resources,
+        options: &Options,
+    ) -> DarkluaResult<()> {
+        let mut outputs: HashMap<PathBuf, &Path> = HashMap::new();
+        let mut work_items: Vec<_> = self.graph.node_weights().collect();
+        work_items.sort_by(|a, b| a.source().cmp(b.source()));
+
+        for work_item in work_items {
+            let output = work_item.data.output();
+            let identity = if options.should_follow_symlinks() {
+                resources.canonicalize(output)
+            } else {
+                absolute_path(output)
+            };
+
+            if let Some(previous_source) = outputs.insert(identity, work_item.source()) {
+                return Err(DarkluaError::custom(format!(
+                    "`{}` and `{}` would both be written to `{}`",
+                    previous_source.display(),
+                    work_item.source().display(),
+                    output.display()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn process(&mut self, resources: &Resources, mut options: Options) -> DarkluaResult<()> {
         if !self.remove_files.is_empty() {
             let remove_count = self.remove_files.len();
@@ -200,6 +272,11 @@ impl WorkerTree {
                     }
                 }
                 Err(_cycle_err) => {
+                    if let Some(staging) = worker.take_staging() {
+                        if let Err(err) = staging.discard(resources) {
+                            log::warn!("{}", err);
+                        }
+                    }
                     return Err(DarkluaError::cyclic_work(
                         self.graph
                             .node_weights()
@@ -216,9 +293,77 @@ impl WorkerTree {
 
         log::info!("executed work in {}", work_timer.duration_label());
 
+        if let Some(staging) = worker.take_staging() {
+            staging.commit(resources, self.iter_errors().count())?;
+        }
+
+        let discarded =
+            options.atomic_mode() == Some(AtomicMode::All) && self.iter_errors().next().is_some();
+
+        if let Some(manifest) = options.hash_manifest() {
+            if !options.should_check() && !discarded {
+                self.write_hash_manifest(resources, &options, manifest)?;
+            }
+        }
+
+        if !options.should_check() && !discarded {
+            worker.write_stage_state()?;
+        }
+
         Ok(())
     }
 
+    /// Writes the digest of each generated file, with its path relative to the output
+    /// directory (or to the input when the files are processed in place).
+    fn write_hash_manifest(
+        &self,
+        resources: &Resources,
+        options: &Options,
+        manifest: &Path,
+    ) -> DarkluaResult<()> {
+        let input_root = options.input_root();
+        let base = options.output().unwrap_or(&input_root);
+
+        let entries: Vec<_> = self
+            .graph
+            .node_weights()
+            .filter(|work_item| matches!(work_item.status, WorkStatus::Done(Ok(()))))
+            .filter_map(|work_item| {
+                let digest = work_item.report.content_hash.as_deref()?;
+                let output = work_item.written_output();
+                let path = relative_path(output, base)
+                    .filter(|path| path != Path::new(""))
+                    .or_else(|| output.file_name().map(PathBuf::from))
+                    .unwrap_or_else(|| output.to_path_buf());
+
+                Some((path, digest))
+            })
+            .collect();
+
+        log::debug!(
+            "write hash manifest with {} file{} to `{}`",
+            entries.len(),
+            maybe_plural(entries.len()),
+            manifest.display()
+        );
+
+        resources
+            .write(
+                manifest,
+                &format_hash_manifest(
+                    entries
+                        .iter()
+                        .map(|(path, digest)| (path.as_path(), *digest)),
+                ),
+            )
+            .map_err(|err| {
+                DarkluaError::from(err).context(format!(
+                    "unable to write hash manifest to `{}`",
+                    manifest.display()
+                ))
+            })
+    }
+
     pub fn result(self) -> Result<(), Vec<DarkluaError>> {
         let errors: Vec<_> = self.iter_errors().cloned().collect();
         if errors.is_empty() {
@@ -251,6 +396,10 @@ impl WorkerTree {
             .count()
     }
 
+    pub(crate) fn iter_work_items(&self) -> impl Iterator<Item = &WorkItem> {
+        self.graph.node_weights()
+    }
+
     pub fn iter_external_dependencies(&self) -> impl Iterator<Item = &Path> {
         self.external_dependencies
             .iter()
@@ -260,6 +409,7 @@ impl WorkerTree {
     pub fn reset(&mut self) {
         self.graph.node_weights_mut().for_each(|work_item| {
             work_item.reset();
+            work_item.incremental = None;
         });
         self.external_dependencies.clear();
     }
@@ -309,7 +459,7 @@ impl WorkerTree {
 
             if !root_item.data.is_in_place() {
                 self.remove_files
-                    .push(root_item.data.output().to_path_buf());
+                    .push(root_item.written_output().to_path_buf());
             }
 
             self.restart_work(node_index);
@@ -332,7 +482,7 @@ impl WorkerTree {
                 if let Some(work_item) = self.graph.remove_node(node_index) {
                     if !work_item.data.is_in_place() {
                         self.remove_files
-                            .push(work_item.data.output().to_path_buf());
+                            .push(work_item.written_output().to_path_buf());
                     }
                 }
             }
@@ -395,9 +545,7 @@ impl WorkerTree {
     }
 
     fn has_configuration_changed(&mut self, config: &Configuration) -> bool {
-        let input = serde_json::to_vec(config).ok().unwrap_or_default();
-
-        let new_hash = xxh3_64(&input);
+        let new_hash = xxh3_64(config.fingerprint().as_bytes());
 
         let last_hash = self.last_configuration_hash.replace(new_hash);
 
@@ -406,3 +554,38 @@ impl WorkerTree {
             .unwrap_or_default()
     }
 }
+
+/// Collects the sources of the input (a directory or a glob pattern) without the excluded
+/// files. The sources are returned with the directory that their output paths are relative
+/// to.
+fn collect_input_sources(
+    resources: &Resources,
+    options: &Options,
+) -> DarkluaResult<(PathBuf, Vec<PathBuf>)> {
+    let input = options.input();
+
+    if is_input_pattern(input) && !resources.exists(input)? {
+        let pattern = InputPattern::new(input)?;
+        let excludes = ExcludePatterns::new(pattern.root(), options.excludes())?;
+        let sources = pattern.find(resources, options.should_follow_symlinks(), &excludes)?;
+
+        log::debug!(
+            "input pattern `{}` matched {} file{}",
+            input.display(),
+            sources.len(),
+            maybe_plural(sources.len())
+        );
+
+        Ok((pattern.root().to_path_buf(), sources))
+    } else {
+        let excludes = ExcludePatterns::new(input, options.excludes())?;
+        let is_excluded = |path: &Path| excludes.is_excluded(path);
+
+        let sources = resources
+            .collect_work_excluding(input, options.should_follow_symlinks(), &is_excluded)
+            .map(normalize_path)
+            .collect();
+
+        Ok((input.to_path_buf(), sources))
+    }
+}
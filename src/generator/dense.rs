@@ -1,4 +1,4 @@
-use crate::generator::{utils, LuaGenerator};
+use crate::generator::{lexical, segments_len, utils, LuaGenerator, SegmentedLuaGenerator};
 use crate::nodes;
 
 /// This implementation of [LuaGenerator](trait.LuaGenerator.html) attempts to produce Lua code as
@@ -44,7 +44,12 @@ impl DenseLuaGenerator {
     /// This function pushes a character into the string, without appending a new line
     /// or a space between the last pushed content.
     fn merge_char(&mut self, character: char) {
-        if self.fits_on_current_line(1) {
+        // without any content to move with the character, it would start a new line. This is
+        // ambiguous for `(` (`a\n(b)` may be parsed as two statements)
+        let nothing_to_move =
+            self.last_push_length == 0 || self.last_push_length >= self.current_line_length;
+
+        if self.fits_on_current_line(1) || nothing_to_move {
             self.raw_push_char(character);
         } else {
             let last_push_content = self.get_last_push_str().to_owned();
@@ -122,6 +127,11 @@ impl DenseLuaGenerator {
     fn needs_space(&self, next_character: char) -> bool {
         if let Some(previous) = self.output.chars().last() {
             utils::should_break_with_space(previous, next_character)
+                || (!previous.is_whitespace()
+                    && lexical::tokens_merge(
+                        self.get_last_push_str(),
+                        next_character.encode_utf8(&mut [0; 4]),
+                    ))
         } else {
             false
         }
@@ -274,6 +284,16 @@ impl DenseLuaGenerator {
         }
         self.push_char('>');
     }
+
+    fn write_attributes<'a>(
+        &mut self,
+        attributes: impl Iterator<Item = &'a nodes::FunctionAttribute>,
+    ) {
+        for attribute in attributes {
+            self.push_char('@');
+            self.raw_push_str(attribute.get_name().get_name());
+        }
+    }
 }
 
 impl Default for DenseLuaGenerator {
@@ -282,29 +302,65 @@ impl Default for DenseLuaGenerator {
     }
 }
 
-impl LuaGenerator for DenseLuaGenerator {
-    /// Consumes the LuaGenerator and produce a String object.
-    fn into_string(self) -> String {
-        self.output
+/// The state of a [`DenseLuaGenerator`] between two segments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenseGeneratorState {
+    current_line_length: usize,
+    last_push_length: usize,
+}
+
+impl SegmentedLuaGenerator for DenseLuaGenerator {
+    type State = DenseGeneratorState;
+
+    fn segment_state(&self) -> Self::State {
+        DenseGeneratorState {
+            current_line_length: self.current_line_length,
+            last_push_length: self.last_push_length,
+        }
     }
 
-    fn write_block(&mut self, block: &nodes::Block) {
-        let mut statements = block.iter_statements().peekable();
+    fn resume(&mut self, output: String, state: Self::State) {
+        self.output = output;
+        self.current_line_length = state.current_line_length;
+        self.last_push_length = state.last_push_length;
+    }
+
+    fn output(&self) -> &str {
+        &self.output
+    }
+
+    fn lookbehind(state: &Self::State) -> usize {
+        state.last_push_length
+    }
 
-        while let Some(statement) = statements.next() {
+    fn write_segment(&mut self, block: &nodes::Block, index: usize) {
+        let mut statements = block.iter_statements().skip(index);
+
+        if let Some(statement) = statements.next() {
             self.write_statement(statement);
 
-            if let Some(next_statement) = statements.peek() {
+            if let Some(next_statement) = statements.next() {
                 if utils::starts_with_parenthese(next_statement)
                     && utils::ends_with_prefix(statement)
                 {
                     self.push_char(';');
                 }
             }
+        } else if let Some(last_statement) = block.get_last_statement() {
+            self.write_last_statement(last_statement);
         }
+    }
+}
 
-        if let Some(last_statement) = block.get_last_statement() {
-            self.write_last_statement(last_statement);
+impl LuaGenerator for DenseLuaGenerator {
+    /// Consumes the LuaGenerator and produce a String object.
+    fn into_string(self) -> String {
+        self.output
+    }
+
+    fn write_block(&mut self, block: &nodes::Block) {
+        for index in 0..segments_len(block) {
+            self.write_segment(block, index);
         }
     }
 
@@ -398,6 +454,7 @@ impl LuaGenerator for DenseLuaGenerator {
     }
 
     fn write_function_statement(&mut self, function: &nodes::FunctionStatement) {
+        self.write_attributes(function.iter_attributes());
         self.push_str("function");
         let name = function.get_name();
 
@@ -500,6 +557,7 @@ impl LuaGenerator for DenseLuaGenerator {
     }
 
     fn write_local_function(&mut self, function: &nodes::LocalFunctionStatement) {
+        self.write_attributes(function.iter_attributes());
         self.push_str("local function");
         self.push_str(function.get_name());
 
@@ -585,6 +643,15 @@ impl LuaGenerator for DenseLuaGenerator {
         }
     }
 
+    fn write_verbatim_statement(&mut self, statement: &nodes::VerbatimStatement) {
+        if !self.output.is_empty() && !self.output.ends_with('\n') {
+            self.push_new_line();
+        }
+        self.output.push_str(statement.get_content());
+        self.push_new_line();
+        self.last_push_length = 0;
+    }
+
     fn write_type_declaration_statement(&mut self, statement: &nodes::TypeDeclarationStatement) {
         if statement.is_exported() {
             self.push_str("export");
@@ -679,7 +746,7 @@ impl LuaGenerator for DenseLuaGenerator {
     }
 
     fn write_unary_expression(&mut self, unary: &nodes::UnaryExpression) {
-        use nodes::{Expression, UnaryOperator::*};
+        use nodes::UnaryOperator::*;
 
         match unary.operator() {
             Length => self.push_char('#'),
@@ -689,15 +756,15 @@ impl LuaGenerator for DenseLuaGenerator {
 
         let expression = unary.get_expression();
 
-        match expression {
-            Expression::Binary(binary) if !binary.operator().precedes_unary_expression() => {
-                self.write_expression_in_parentheses(expression);
-            }
-            _ => self.write_expression(expression),
+        if nodes::UnaryExpression::operand_needs_parentheses(expression) {
+            self.write_expression_in_parentheses(expression);
+        } else {
+            self.write_expression(expression);
         }
     }
 
     fn write_function(&mut self, function: &nodes::FunctionExpression) {
+        self.write_attributes(function.iter_attributes());
         self.push_str("function");
 
         if let Some(generics) = function.get_generic_parameters() {
@@ -892,6 +959,13 @@ impl LuaGenerator for DenseLuaGenerator {
     }
 
     fn write_string(&mut self, string: &nodes::StringExpression) {
+        // a token that does not reference the original code holds the literal chosen by a
+        // rule (like `normalize_string_escapes`)
+        if let Some(literal) = string.get_token().and_then(nodes::Token::try_read) {
+            self.push_str(literal);
+            return;
+        }
+
         let result = utils::write_string(string.get_value());
         if result.starts_with('[') {
             self.push_str_and_break_if(&result, utils::break_long_string);
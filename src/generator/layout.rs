@@ -0,0 +1,238 @@
+//! Finds the tokens at the boundaries of statements, so that generators can read the
+//! comments and blank lines that surrounded them in the original code.
+
+use crate::nodes::{
+    Arguments, Block, Expression, FunctionCall, LastStatement, Prefix, Statement, Token, Variable,
+};
+
+/// Returns the first token of a statement, when the statement has tokens.
+pub(crate) fn statement_first_token(statement: &Statement) -> Option<&Token> {
+    match statement {
+        Statement::Assign(assign) => assign
+            .get_variables()
+            .first()
+            .and_then(variable_first_token),
+        Statement::Do(do_statement) => do_statement.get_tokens().map(|tokens| &tokens.r#do),
+        Statement::Call(call) => prefix_first_token(call.get_prefix()),
+        Statement::CompoundAssign(assign) => variable_first_token(assign.get_variable()),
+        Statement::Function(function) => function.get_tokens().map(|tokens| &tokens.function),
+        Statement::GenericFor(generic_for) => generic_for.get_tokens().map(|tokens| &tokens.r#for),
+        Statement::If(if_statement) => if_statement.get_tokens().map(|tokens| &tokens.r#if),
+        Statement::LocalAssign(assign) => assign.get_tokens().map(|tokens| &tokens.local),
+        Statement::LocalFunction(function) => function.get_tokens().map(|tokens| &tokens.local),
+        Statement::NumericFor(numeric_for) => numeric_for.get_tokens().map(|tokens| &tokens.r#for),
+        Statement::Repeat(repeat) => repeat.get_tokens().map(|tokens| &tokens.repeat),
+        Statement::While(while_statement) => {
+            while_statement.get_tokens().map(|tokens| &tokens.r#while)
+        }
+        Statement::TypeDeclaration(declaration) => {
+            declaration.get_tokens().map(|tokens| match &tokens.export {
+                Some(export) => export,
+                None => &tokens.r#type,
+            })
+        }
+        Statement::Verbatim(_) => None,
+    }
+}
+
+/// Returns the last token of a statement, when the statement has tokens.
+pub(crate) fn statement_last_token(statement: &Statement) -> Option<&Token> {
+    match statement {
+        Statement::Assign(assign) => assign.last_value().and_then(expression_last_token),
+        Statement::Do(do_statement) => do_statement.get_tokens().map(|tokens| &tokens.end),
+        Statement::Call(call) => call_last_token(call),
+        Statement::CompoundAssign(assign) => expression_last_token(assign.get_value()),
+        Statement::Function(function) => function.get_tokens().map(|tokens| &tokens.end),
+        Statement::GenericFor(generic_for) => generic_for.get_tokens().map(|tokens| &tokens.end),
+        Statement::If(if_statement) => if_statement.get_tokens().map(|tokens| &tokens.end),
+        Statement::LocalAssign(assign) => match assign.last_value() {
+            Some(value) => expression_last_token(value),
+            None => assign
+                .get_variables()
+                .last()
+                .filter(|variable| !variable.has_type())
+                .and_then(|variable| variable.get_token()),
+        },
+        Statement::LocalFunction(function) => function
+            .get_tokens()
+            .map(|tokens| &tokens.function_body.end),
+        Statement::NumericFor(numeric_for) => numeric_for.get_tokens().map(|tokens| &tokens.end),
+        Statement::Repeat(repeat) => expression_last_token(repeat.get_condition()),
+        Statement::While(while_statement) => while_statement.get_tokens().map(|tokens| &tokens.end),
+        Statement::TypeDeclaration(_) | Statement::Verbatim(_) => None,
+    }
+}
+
+/// Returns the first token of a last statement, when the statement has tokens.
+pub(crate) fn last_statement_first_token(statement: &LastStatement) -> Option<&Token> {
+    match statement {
+        LastStatement::Break(token) | LastStatement::Continue(token) => token.as_ref(),
+        LastStatement::Return(statement) => statement.get_tokens().map(|tokens| &tokens.r#return),
+    }
+}
+
+/// Returns the last token of a last statement, when the statement has tokens.
+pub(crate) fn last_statement_last_token(statement: &LastStatement) -> Option<&Token> {
+    match statement {
+        LastStatement::Break(token) | LastStatement::Continue(token) => token.as_ref(),
+        LastStatement::Return(statement) => match statement.iter_expressions().last() {
+            Some(expression) => expression_last_token(expression),
+            None => statement.get_tokens().map(|tokens| &tokens.r#return),
+        },
+    }
+}
+
+/// Returns the semicolon that follows the statement at the given index of a block.
+pub(crate) fn statement_semicolon(block: &Block, index: usize) -> Option<&Token> {
+    block
+        .get_tokens()
+        .and_then(|tokens| tokens.semicolons.get(index))
+        .and_then(Option::as_ref)
+}
+
+/// Returns the semicolon that follows the last statement of a block.
+pub(crate) fn last_statement_semicolon(block: &Block) -> Option<&Token> {
+    block
+        .get_tokens()
+        .and_then(|tokens| tokens.last_semicolon.as_ref())
+}
+
+/// Returns the token that holds the comments written after the last statement of a block.
+pub(crate) fn block_final_token(block: &Block) -> Option<&Token> {
+    block
+        .get_tokens()
+        .and_then(|tokens| tokens.final_token.as_ref())
+}
+
+fn variable_first_token(variable: &Variable) -> Option<&Token> {
+    match variable {
+        Variable::Identifier(identifier) => identifier.get_token(),
+        Variable::Field(field) => prefix_first_token(field.get_prefix()),
+        Variable::Index(index) => prefix_first_token(index.get_prefix()),
+    }
+}
+
+fn prefix_first_token(prefix: &Prefix) -> Option<&Token> {
+    match prefix {
+        Prefix::Call(call) => prefix_first_token(call.get_prefix()),
+        Prefix::Field(field) => prefix_first_token(field.get_prefix()),
+        Prefix::Index(index) => prefix_first_token(index.get_prefix()),
+        Prefix::Identifier(identifier) => identifier.get_token(),
+        Prefix::Parenthese(parenthese) => parenthese
+            .get_tokens()
+            .map(|tokens| &tokens.left_parenthese),
+    }
+}
+
+fn call_last_token(call: &FunctionCall) -> Option<&Token> {
+    match call.get_arguments() {
+        Arguments::Tuple(tuple) => tuple.get_tokens().map(|tokens| &tokens.closing_parenthese),
+        Arguments::String(string) => string.get_token(),
+        Arguments::Table(table) => table.get_tokens().map(|tokens| &tokens.closing_brace),
+    }
+}
+
+fn expression_last_token(expression: &Expression) -> Option<&Token> {
+    match expression {
+        Expression::Binary(binary) => expression_last_token(binary.right()),
+        Expression::Call(call) => call_last_token(call),
+        Expression::False(token)
+        | Expression::Nil(token)
+        | Expression::True(token)
+        | Expression::VariableArguments(token) => token.as_ref(),
+        Expression::Field(field) => field.get_field().get_token(),
+        Expression::Function(function) => function.get_tokens().map(|tokens| &tokens.end),
+        Expression::Identifier(identifier) => identifier.get_token(),
+        Expression::If(if_expression) => expression_last_token(if_expression.get_else_result()),
+        Expression::Index(index) => index.get_tokens().map(|tokens| &tokens.closing_bracket),
+        Expression::Number(number) => number.get_token(),
+        Expression::Parenthese(parenthese) => parenthese
+            .get_tokens()
+            .map(|tokens| &tokens.right_parenthese),
+        Expression::String(string) => string.get_token(),
+        Expression::InterpolatedString(string) => {
+            string.get_tokens().map(|tokens| &tokens.closing_tick)
+        }
+        Expression::Table(table) => table.get_tokens().map(|tokens| &tokens.closing_brace),
+        Expression::Unary(unary) => expression_last_token(unary.get_expression()),
+        Expression::TypeCast(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Parser;
+
+    fn parse(code: &str) -> Block {
+        Parser::default()
+            .preserve_tokens()
+            .parse(code)
+            .expect("code should parse")
+    }
+
+    fn read_boundaries(code: &str) -> (String, String) {
+        let block = parse(code);
+        let statement = block.first_statement().expect("expected a statement");
+
+        (
+            statement_first_token(statement)
+                .expect("expected a first token")
+                .read(code)
+                .to_owned(),
+            statement_last_token(statement)
+                .expect("expected a last token")
+                .read(code)
+                .to_owned(),
+        )
+    }
+
+    macro_rules! test_boundaries {
+        ($($name:ident ($code:literal) => ($first:literal, $last:literal)),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert_eq!(
+                        read_boundaries($code),
+                        ($first.to_owned(), $last.to_owned())
+                    );
+                }
+            )*
+        };
+    }
+
+    test_boundaries!(
+        assign("a.b = c + d") => ("a", "d"),
+        call_with_tuple("print(a)") => ("print", ")"),
+        method_call_with_table("obj:method { }") => ("obj", "}"),
+        compound_assign("a += f 'x'") => ("a", "'x'"),
+        local_assign_without_values("local a, b") => ("local", "b"),
+        local_assign_with_if_expression("local a = if c then 1 else 2") => ("local", "2"),
+        local_function("local function f() end") => ("local", "end"),
+        function_statement("function a.b() end") => ("function", "end"),
+        repeat("repeat until not done") => ("repeat", "done"),
+        parenthese_call("(f)()") => ("(", ")"),
+    );
+
+    #[test]
+    fn return_without_values_ends_with_return() {
+        let code = "return";
+        let block = parse(code);
+        let statement = block.get_last_statement().unwrap();
+
+        assert_eq!(
+            last_statement_last_token(statement).unwrap().read(code),
+            "return"
+        );
+    }
+
+    #[test]
+    fn statement_without_tokens_has_no_boundaries() {
+        let block = Parser::default().parse("local a = 1").unwrap();
+        let statement = block.first_statement().unwrap();
+
+        assert!(statement_first_token(statement).is_none());
+        assert!(statement_last_token(statement).is_none());
+    }
+}
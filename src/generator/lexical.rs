@@ -0,0 +1,490 @@
+use std::fmt;
+
+/// The kind of a token read by the [`lex_strict`] function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LexicalTokenKind {
+    Name,
+    Number,
+    String,
+    LongString,
+    Comment,
+    Symbol,
+}
+
+/// A token read by the [`lex_strict`] function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LexicalToken<'a> {
+    kind: LexicalTokenKind,
+    content: &'a str,
+    position: usize,
+}
+
+impl<'a> LexicalToken<'a> {
+    /// Returns the kind of the token.
+    pub fn kind(&self) -> LexicalTokenKind {
+        self.kind
+    }
+
+    /// Returns the text of the token.
+    pub fn content(&self) -> &'a str {
+        self.content
+    }
+
+    /// Returns the byte position of the token in the code.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// An error returned when some code can not be read by the [`lex_strict`] function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexicalError {
+    MalformedNumber { number: String, position: usize },
+    UnfinishedString { position: usize },
+    UnfinishedLongString { position: usize },
+    InvalidLongStringDelimiter { position: usize },
+    UnexpectedCharacter { character: char, position: usize },
+}
+
+impl LexicalError {
+    /// Returns the byte position of the error in the code.
+    pub fn position(&self) -> usize {
+        match self {
+            Self::MalformedNumber { position, .. }
+            | Self::UnfinishedString { position }
+            | Self::UnfinishedLongString { position }
+            | Self::InvalidLongStringDelimiter { position }
+            | Self::UnexpectedCharacter { position, .. } => *position,
+        }
+    }
+}
+
+impl fmt::Display for LexicalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedNumber { number, position } => {
+                write!(f, "malformed number `{}` at position {}", number, position)
+            }
+            Self::UnfinishedString { position } => {
+                write!(f, "unfinished string at position {}", position)
+            }
+            Self::UnfinishedLongString { position } => {
+                write!(f, "unfinished long string at position {}", position)
+            }
+            Self::InvalidLongStringDelimiter { position } => {
+                write!(f, "invalid long string delimiter at position {}", position)
+            }
+            Self::UnexpectedCharacter {
+                character,
+                position,
+            } => {
+                write!(
+                    f,
+                    "unexpected character `{}` at position {}",
+                    character, position
+                )
+            }
+        }
+    }
+}
+
+// sorted from the longest to the shortest so that the longest symbol is always read
+const SYMBOLS: &[&str] = &[
+    "...", "..=", "//=", "..", "==", "~=", "<=", ">=", "::", "//", "->", "+=", "-=", "*=", "/=",
+    "%=", "^=", "+", "-", "*", "/", "%", "^", "#", "&", "~", "|", "<", ">", "=", "(", ")", "{",
+    "}", "[", "]", ";", ":", ",", ".", "?", "@",
+];
+
+/// Splits Lua code into tokens, following the strictest rules of the Lua 5.1, LuaJIT and Luau
+/// lexers. Code that is read without errors by this function is read as the same tokens by
+/// each of these lexers.
+///
+/// Numbers are read like LuaJIT does (the most greedy of the three lexers): every letter,
+/// digit, underscore and dot is part of the number, as well as a sign following an exponent
+/// character. For example, `0xE-1` is read by LuaJIT as a single malformed number.
+pub fn lex_strict(code: &str) -> Result<Vec<LexicalToken<'_>>, LexicalError> {
+    let mut lexer = StrictLexer::new(code);
+    let mut tokens = Vec::new();
+
+    while let Some(token) = lexer.next_token()? {
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Returns `true` if writing `next` right after `previous` would change how the tokens are
+/// read by one of the Lua 5.1, LuaJIT or Luau lexers. When `previous` or `next` can not be
+/// read on their own, this function returns `false`.
+pub(crate) fn tokens_merge(previous: &str, next: &str) -> bool {
+    if previous.is_empty() || next.is_empty() {
+        return false;
+    }
+
+    let (previous_tokens, next_tokens) = match (lex_strict(previous), lex_strict(next)) {
+        (Ok(previous_tokens), Ok(next_tokens)) => (previous_tokens, next_tokens),
+        _ => return false,
+    };
+
+    let merged = format!("{}{}", previous, next);
+
+    match lex_strict(&merged) {
+        Ok(merged_tokens) => {
+            merged_tokens.len() != previous_tokens.len() + next_tokens.len()
+                || merged_tokens
+                    .iter()
+                    .zip(previous_tokens.iter().chain(next_tokens.iter()))
+                    .any(|(merged_token, token)| {
+                        merged_token.kind != token.kind || merged_token.content != token.content
+                    })
+        }
+        Err(_) => true,
+    }
+}
+
+struct StrictLexer<'a> {
+    code: &'a str,
+    position: usize,
+}
+
+impl<'a> StrictLexer<'a> {
+    fn new(code: &'a str) -> Self {
+        Self { code, position: 0 }
+    }
+
+    #[inline]
+    fn rest(&self) -> &'a str {
+        &self.code[self.position..]
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    #[inline]
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+
+    fn token(&mut self, kind: LexicalTokenKind, start: usize) -> Option<LexicalToken<'a>> {
+        Some(LexicalToken {
+            kind,
+            content: &self.code[start..self.position],
+            position: start,
+        })
+    }
+
+    fn next_token(&mut self) -> Result<Option<LexicalToken<'a>>, LexicalError> {
+        while let Some(character) = self.peek() {
+            if matches!(character, ' ' | '\t' | '\n' | '\r' | '\u{B}' | '\u{C}') {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+
+        let start = self.position;
+
+        let character = match self.peek() {
+            Some(character) => character,
+            None => return Ok(None),
+        };
+
+        match character {
+            'A'..='Z' | 'a'..='z' | '_' => {
+                self.skip_while(|character| character.is_ascii_alphanumeric() || character == '_');
+                Ok(self.token(LexicalTokenKind::Name, start))
+            }
+            '0'..='9' => self.read_number(start),
+            '.' if matches!(self.peek_nth(1), Some('0'..='9')) => self.read_number(start),
+            '"' | '\'' => self.read_string(character, start),
+            '-' if self.peek_nth(1) == Some('-') => {
+                self.position += 2;
+                // an invalid long bracket opening starts a regular comment
+                if let Ok(Some(level)) = self.long_bracket_level() {
+                    self.read_long_bracket(level, start)?;
+                } else {
+                    self.skip_while(|character| character != '\n');
+                }
+                Ok(self.token(LexicalTokenKind::Comment, start))
+            }
+            '[' => {
+                if let Some(level) = self.long_bracket_level()? {
+                    self.read_long_bracket(level, start)?;
+                    Ok(self.token(LexicalTokenKind::LongString, start))
+                } else {
+                    self.position += 1;
+                    Ok(self.token(LexicalTokenKind::Symbol, start))
+                }
+            }
+            _ => {
+                let rest = self.rest();
+                if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+                    self.position += symbol.len();
+                    Ok(self.token(LexicalTokenKind::Symbol, start))
+                } else {
+                    Err(LexicalError::UnexpectedCharacter {
+                        character,
+                        position: start,
+                    })
+                }
+            }
+        }
+    }
+
+    fn skip_while(&mut self, predicate: impl Fn(char) -> bool) {
+        while let Some(character) = self.peek() {
+            if predicate(character) {
+                self.position += character.len_utf8();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn read_number(&mut self, start: usize) -> Result<Option<LexicalToken<'a>>, LexicalError> {
+        let mut previous = None;
+
+        while let Some(character) = self.peek() {
+            let is_part_of_number = character.is_ascii_alphanumeric()
+                || matches!(character, '_' | '.')
+                || (matches!(character, '+' | '-')
+                    && matches!(previous, Some('e' | 'E' | 'p' | 'P')));
+
+            if !is_part_of_number {
+                break;
+            }
+
+            self.position += 1;
+            previous = Some(character);
+        }
+
+        let number = &self.code[start..self.position];
+
+        if is_valid_number(number) {
+            Ok(self.token(LexicalTokenKind::Number, start))
+        } else {
+            Err(LexicalError::MalformedNumber {
+                number: number.to_owned(),
+                position: start,
+            })
+        }
+    }
+
+    fn read_string(
+        &mut self,
+        quote: char,
+        start: usize,
+    ) -> Result<Option<LexicalToken<'a>>, LexicalError> {
+        self.position += 1;
+
+        while let Some(character) = self.peek() {
+            self.position += character.len_utf8();
+
+            match character {
+                '\\' => {
+                    if let Some(escaped) = self.peek() {
+                        self.position += escaped.len_utf8();
+                    }
+                }
+                '\n' | '\r' => break,
+                _ if character == quote => {
+                    return Ok(self.token(LexicalTokenKind::String, start));
+                }
+                _ => {}
+            }
+        }
+
+        Err(LexicalError::UnfinishedString { position: start })
+    }
+
+    /// Reads the opening of a long bracket (`[[`, `[==[`, ...) and returns its level. Returns
+    /// `None` if the code does not start with a long bracket opening.
+    fn long_bracket_level(&mut self) -> Result<Option<usize>, LexicalError> {
+        let rest = self.rest();
+
+        if !rest.starts_with('[') {
+            return Ok(None);
+        }
+
+        let level = rest[1..].chars().take_while(|c| *c == '=').count();
+
+        match rest[1 + level..].chars().next() {
+            Some('[') => {
+                self.position += level + 2;
+                Ok(Some(level))
+            }
+            _ if level == 0 => Ok(None),
+            _ => Err(LexicalError::InvalidLongStringDelimiter {
+                position: self.position,
+            }),
+        }
+    }
+
+    fn read_long_bracket(&mut self, level: usize, start: usize) -> Result<(), LexicalError> {
+        let closing = format!("]{}]", "=".repeat(level));
+
+        if let Some(index) = self.rest().find(&closing) {
+            self.position += index + closing.len();
+            Ok(())
+        } else {
+            Err(LexicalError::UnfinishedLongString { position: start })
+        }
+    }
+}
+
+fn is_valid_number(number: &str) -> bool {
+    if let Some(digits) = number
+        .strip_prefix("0x")
+        .or_else(|| number.strip_prefix("0X"))
+    {
+        let (digits, exponent) = match digits.find(['p', 'P']) {
+            Some(index) => (&digits[..index], Some(&digits[index + 1..])),
+            None => (digits, None),
+        };
+
+        !digits.is_empty()
+            && digits.chars().all(|c| c.is_ascii_hexdigit())
+            && exponent.map(is_valid_exponent).unwrap_or(true)
+    } else if let Some(digits) = number
+        .strip_prefix("0b")
+        .or_else(|| number.strip_prefix("0B"))
+    {
+        !digits.is_empty() && digits.chars().all(|c| c == '0' || c == '1')
+    } else {
+        let (mantissa, exponent) = match number.find(['e', 'E']) {
+            Some(index) => (&number[..index], Some(&number[index + 1..])),
+            None => (number, None),
+        };
+
+        let (integer, fraction) = match mantissa.split_once('.') {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (mantissa, ""),
+        };
+
+        (!integer.is_empty() || !fraction.is_empty())
+            && integer.chars().all(|c| c.is_ascii_digit())
+            && fraction.chars().all(|c| c.is_ascii_digit())
+            && exponent.map(is_valid_exponent).unwrap_or(true)
+    }
+}
+
+fn is_valid_exponent(exponent: &str) -> bool {
+    let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn token_contents(code: &str) -> Vec<&str> {
+        lex_strict(code)
+            .unwrap_or_else(|err| panic!("unable to lex `{}`: {}", code, err))
+            .into_iter()
+            .map(|token| token.content())
+            .collect()
+    }
+
+    macro_rules! test_tokens {
+        ($($name:ident ($code:literal) => [$($token:literal),* $(,)?]),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    let expected: Vec<&str> = vec![$($token),*];
+                    pretty_assertions::assert_eq!(token_contents($code), expected);
+                }
+            )*
+        };
+    }
+
+    macro_rules! test_errors {
+        ($($name:ident ($code:literal)),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert!(lex_strict($code).is_err());
+                }
+            )*
+        };
+    }
+
+    test_tokens!(
+        empty("") => [],
+        names("local a = b") => ["local", "a", "=", "b"],
+        concat_numbers("1 .. 2") => ["1", "..", "2"],
+        decimal_with_exponent("1e-5+1") => ["1e-5", "+", "1"],
+        hex_number("0xFF-1") => ["0xFF", "-", "1"],
+        hex_number_with_exponent("0x1p4") => ["0x1p4"],
+        binary_number("0b101") => ["0b101"],
+        fraction_number(".5+1.") => [".5", "+", "1."],
+        variable_arguments("...") => ["..."],
+        compound_concat("a..=b") => ["a", "..=", "b"],
+        comparisons("a<=b>=c~=d==e") => ["a", "<=", "b", ">=", "c", "~=", "d", "==", "e"],
+        type_cast("a::b") => ["a", "::", "b"],
+        long_string("a=[==[x]]]==]") => ["a", "=", "[==[x]]]==]"],
+        index_with_long_string("a[ [[x]] ]") => ["a", "[", "[[x]]", "]"],
+        comment("a--b\nc") => ["a", "--b", "c"],
+        long_comment("a--[[b\n]]c") => ["a", "--[[b\n]]", "c"],
+        strings("'a\\'b'..\"c\"") => ["'a\\'b'", "..", "\"c\""],
+    );
+
+    test_errors!(
+        hex_number_followed_by_minus("0xE-1"),
+        hex_number_followed_by_plus("0xe+a"),
+        number_followed_by_concat("1..a"),
+        number_followed_by_name("1a"),
+        number_with_underscore("1_000"),
+        unfinished_string("'abc"),
+        unfinished_long_string("[[abc"),
+        invalid_long_string_delimiter("[=a"),
+        unexpected_character("a = $"),
+    );
+
+    #[test]
+    fn tokens_merge_hex_number_and_minus() {
+        assert!(tokens_merge("0xE", "-"));
+    }
+
+    #[test]
+    fn tokens_merge_negative_number_and_concat() {
+        assert!(tokens_merge("-0", ".."));
+    }
+
+    #[test]
+    fn tokens_merge_minus_and_minus() {
+        assert!(tokens_merge("-", "-"));
+    }
+
+    #[test]
+    fn tokens_merge_bracket_and_long_string() {
+        assert!(tokens_merge("[", "[[a]]"));
+    }
+
+    #[test]
+    fn tokens_merge_bracket_and_equal() {
+        assert!(tokens_merge("[", "="));
+    }
+
+    #[test]
+    fn tokens_merge_colons() {
+        assert!(tokens_merge(":", ":"));
+    }
+
+    #[test]
+    fn tokens_do_not_merge_decimal_number_and_minus() {
+        assert!(!tokens_merge("1", "-"));
+    }
+
+    #[test]
+    fn tokens_do_not_merge_name_and_field() {
+        assert!(!tokens_merge("a", "."));
+    }
+
+    #[test]
+    fn tokens_do_not_merge_long_string_and_bracket() {
+        assert!(!tokens_merge("[[a]]", "]"));
+    }
+}
@@ -2,12 +2,17 @@
 //! and its implementations.
 
 mod dense;
+mod layout;
+mod lexical;
 mod readable;
+mod segments;
 mod token_based;
-mod utils;
+pub(crate) mod utils;
 
-pub use dense::DenseLuaGenerator;
-pub use readable::ReadableLuaGenerator;
+pub use dense::{DenseGeneratorState, DenseLuaGenerator};
+pub use lexical::{lex_strict, LexicalError, LexicalToken, LexicalTokenKind};
+pub use readable::{ReadableGeneratorState, ReadableLuaGenerator};
+pub use segments::*;
 pub use token_based::TokenBasedLuaGenerator;
 
 use crate::nodes;
@@ -37,6 +42,7 @@ pub trait LuaGenerator {
             Repeat(statement) => self.write_repeat_statement(statement),
             While(statement) => self.write_while_statement(statement),
             TypeDeclaration(statement) => self.write_type_declaration_statement(statement),
+            Verbatim(statement) => self.write_verbatim_statement(statement),
         }
     }
 
@@ -53,6 +59,8 @@ pub trait LuaGenerator {
     fn write_repeat_statement(&mut self, repeat: &nodes::RepeatStatement);
     fn write_while_statement(&mut self, while_statement: &nodes::WhileStatement);
     fn write_type_declaration_statement(&mut self, statement: &nodes::TypeDeclarationStatement);
+    /// Writes the content of the statement on its own lines, without any modification.
+    fn write_verbatim_statement(&mut self, statement: &nodes::VerbatimStatement);
 
     fn write_variable(&mut self, variable: &nodes::Variable) {
         use nodes::Variable::*;
@@ -399,7 +407,23 @@ mod $mod_name {
             concat_variable_arguments_with_number => "return ... ..1",
             double_unary_minus => "return - -10",
             binary_minus_with_unary_minus => "return 100- -10",
+            local_function_with_attribute => "@native local function foo() end",
+            function_with_attributes => "@native @checked function foo.bar() end",
+            method_with_attribute => "@native function foo:bar() end",
+            long_string_with_two_equals => "return [==[a]]b]=]c]==]",
+            long_string_with_leading_new_line => "return [[\n\nhello]]",
+            long_string_ending_with_equal => "return [==[\naaaa\nbbbb\ncccc\ndddd\neeee\nffff\ngggg]]\n]=]==]",
         ));
+
+        #[test]
+        fn function_expression_with_attribute() {
+            let mut generator = $generator;
+            generator.write_expression(
+                &FunctionExpression::default().with_attribute("native").into()
+            );
+
+            assert!(generator.into_string().starts_with("@native function"));
+        }
     }
 
     mod numbers {
@@ -412,6 +436,8 @@ mod $mod_name {
             hex_number => "0x12",
             hex_number_with_letter => "0x12a",
             hex_with_exponent => "0x12p4",
+            binary_number => "0b1010",
+            uppercase_binary_number => "0B11",
         ));
     }
 
@@ -1118,6 +1144,34 @@ mod $mod_name {
         };
     }
 
+    mod numbers_without_tokens {
+        use super::*;
+        use std::str::FromStr;
+
+        fn write_dense(value: &str) -> String {
+            let number = crate::nodes::NumberExpression::from_str(value).unwrap();
+
+            let mut generator = DenseLuaGenerator::default();
+            generator.write_expression(&number.into());
+            generator.into_string()
+        }
+
+        #[test]
+        fn decimal_separators_are_removed() {
+            assert_eq!(write_dense("1_000_000"), "1000000");
+        }
+
+        #[test]
+        fn binary_separators_are_removed() {
+            assert_eq!(write_dense("0b1010_0101"), "0b10100101");
+        }
+
+        #[test]
+        fn hex_separators_are_removed() {
+            assert_eq!(write_dense("0x_FF_FF"), "0xffff");
+        }
+    }
+
     snapshot_generator!(dense, DenseLuaGenerator::default());
     snapshot_generator!(readable, ReadableLuaGenerator::default());
     snapshot_generator!(token_based, TokenBasedLuaGenerator::new(""));
@@ -1,5 +1,7 @@
-use crate::generator::{utils, LuaGenerator};
-use crate::nodes;
+use crate::generator::{layout, lexical, segments_len, utils, LuaGenerator, SegmentedLuaGenerator};
+use crate::nodes::{self, TriviaKind};
+
+const DEFAULT_MAX_CONSECUTIVE_BLANK_LINES: usize = 1;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum StatementType {
@@ -16,6 +18,7 @@ enum StatementType {
     Repeat,
     While,
     TypeDeclaration,
+    Verbatim,
     Return,
     Break,
     Continue,
@@ -38,6 +41,7 @@ impl From<&nodes::Statement> for StatementType {
             Repeat(_) => Self::Repeat,
             While(_) => Self::While,
             TypeDeclaration(_) => Self::TypeDeclaration,
+            Verbatim(_) => Self::Verbatim,
         }
     }
 }
@@ -64,6 +68,9 @@ pub struct ReadableLuaGenerator {
     output: String,
     last_push_length: usize,
     can_add_new_line_stack: Vec<bool>,
+    /// The code where the tokens were parsed from, when the layout is preserved.
+    original_code: Option<String>,
+    max_consecutive_blank_lines: usize,
 }
 
 impl ReadableLuaGenerator {
@@ -76,6 +83,149 @@ impl ReadableLuaGenerator {
             output: String::new(),
             last_push_length: 0,
             can_add_new_line_stack: Vec::new(),
+            original_code: None,
+            max_consecutive_blank_lines: DEFAULT_MAX_CONSECUTIVE_BLANK_LINES,
+        }
+    }
+
+    /// Keeps the blank lines that separate statements and the comments written around them,
+    /// using the tokens of the statements. The given code must be the code where the tokens
+    /// were parsed from. Statements without tokens are written without blank lines.
+    pub fn with_preserved_layout(mut self, original_code: impl Into<String>) -> Self {
+        self.original_code = Some(original_code.into());
+        self
+    }
+
+    /// Limits the number of blank lines kept between two statements when the layout is
+    /// preserved (one by default).
+    pub fn with_max_consecutive_blank_lines(mut self, max_consecutive_blank_lines: usize) -> Self {
+        self.max_consecutive_blank_lines = max_consecutive_blank_lines;
+        self
+    }
+
+    #[inline]
+    fn preserves_layout(&self) -> bool {
+        self.original_code.is_some()
+    }
+
+    fn read_trivia(&self, trivia: &nodes::Trivia) -> Option<String> {
+        match &self.original_code {
+            Some(code) => Some(trivia.read(code).to_owned()),
+            None => trivia.try_read().map(str::to_owned),
+        }
+    }
+
+    fn push_blank_lines(&mut self, blank_lines: usize) {
+        for _ in 0..blank_lines.min(self.max_consecutive_blank_lines) {
+            self.push_new_line();
+        }
+    }
+
+    /// Writes the comments found before a statement, each on its own line, and the blank
+    /// lines that separated them. Blank lines are not written at the start of a block.
+    fn write_leading_trivia(&mut self, token: Option<&nodes::Token>, block_start: bool) {
+        let token = match token {
+            Some(token) => token,
+            None => return,
+        };
+
+        let mut new_lines = 0;
+        let mut after_comment = false;
+        let mut has_content = !block_start;
+
+        for trivia in token.iter_leading_trivia() {
+            let content = match self.read_trivia(trivia) {
+                Some(content) => content,
+                None => continue,
+            };
+
+            match trivia.kind() {
+                TriviaKind::Whitespace => new_lines += utils::count_new_lines(&content),
+                TriviaKind::Comment => {
+                    if has_content {
+                        self.push_blank_lines(Self::blank_lines(new_lines, after_comment));
+                    }
+                    self.write_comment_line(&content);
+                    new_lines = 0;
+                    after_comment = true;
+                    has_content = true;
+                }
+            }
+        }
+
+        if has_content {
+            self.push_blank_lines(Self::blank_lines(new_lines, after_comment));
+        }
+    }
+
+    /// The new line that ends a comment does not count as a blank line.
+    fn blank_lines(new_lines: usize, after_comment: bool) -> usize {
+        if after_comment {
+            new_lines.saturating_sub(1)
+        } else {
+            new_lines
+        }
+    }
+
+    fn write_comment_line(&mut self, comment: &str) {
+        if self.current_line_length == 0 && self.current_indentation != 0 {
+            self.write_indentation();
+        }
+        self.raw_push_str(comment.trim_end());
+        self.push_new_line();
+    }
+
+    /// Writes the comments that followed a statement on the same line.
+    fn write_trailing_comments<'a>(&mut self, tokens: impl IntoIterator<Item = &'a nodes::Token>) {
+        for token in tokens {
+            for trivia in token.iter_trailing_trivia() {
+                if trivia.kind() != TriviaKind::Comment {
+                    continue;
+                }
+                if let Some(content) = self.read_trivia(trivia) {
+                    self.raw_push_char(' ');
+                    self.raw_push_str(content.trim_end());
+                }
+            }
+        }
+    }
+
+    fn write_segment_with_layout(&mut self, block: &nodes::Block, index: usize) {
+        let mut statements = block.iter_statements().skip(index);
+
+        if let Some(statement) = statements.next() {
+            self.write_leading_trivia(layout::statement_first_token(statement), index == 0);
+
+            self.push_can_add_new_line(false);
+            self.write_statement(statement);
+
+            if let Some(next_statement) = statements.next() {
+                if utils::starts_with_parenthese(next_statement)
+                    && utils::ends_with_prefix(statement)
+                {
+                    self.push_char(';');
+                }
+            }
+            self.pop_can_add_new_line();
+
+            self.write_trailing_comments(
+                layout::statement_last_token(statement)
+                    .into_iter()
+                    .chain(layout::statement_semicolon(block, index)),
+            );
+            self.push_new_line();
+        } else if let Some(last_statement) = block.get_last_statement() {
+            self.write_leading_trivia(
+                layout::last_statement_first_token(last_statement),
+                index == 0,
+            );
+            self.write_last_statement(last_statement);
+            self.write_trailing_comments(
+                layout::last_statement_last_token(last_statement)
+                    .into_iter()
+                    .chain(layout::last_statement_semicolon(block)),
+            );
+            self.push_new_line();
         }
     }
 
@@ -163,6 +313,11 @@ impl ReadableLuaGenerator {
     fn needs_space(&self, next_character: char) -> bool {
         if let Some(previous) = self.output.chars().last() {
             utils::should_break_with_space(previous, next_character)
+                || (!previous.is_whitespace()
+                    && lexical::tokens_merge(
+                        self.get_last_push_str(),
+                        next_character.encode_utf8(&mut [0; 4]),
+                    ))
         } else {
             false
         }
@@ -434,6 +589,17 @@ impl ReadableLuaGenerator {
         }
         self.push_char('>');
     }
+
+    fn write_attributes<'a>(
+        &mut self,
+        attributes: impl Iterator<Item = &'a nodes::FunctionAttribute>,
+    ) {
+        for attribute in attributes {
+            self.push_char('@');
+            self.raw_push_str(attribute.get_name().get_name());
+            self.raw_push_char(' ');
+        }
+    }
 }
 
 impl Default for ReadableLuaGenerator {
@@ -442,37 +608,72 @@ impl Default for ReadableLuaGenerator {
     }
 }
 
-impl LuaGenerator for ReadableLuaGenerator {
-    fn into_string(self) -> String {
-        self.output
+/// The state of a [`ReadableLuaGenerator`] between two segments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadableGeneratorState {
+    current_line_length: usize,
+    current_indentation: usize,
+    last_push_length: usize,
+    can_add_new_line_stack: Vec<bool>,
+}
+
+impl SegmentedLuaGenerator for ReadableLuaGenerator {
+    type State = ReadableGeneratorState;
+
+    fn segment_state(&self) -> Self::State {
+        ReadableGeneratorState {
+            current_line_length: self.current_line_length,
+            current_indentation: self.current_indentation,
+            last_push_length: self.last_push_length,
+            can_add_new_line_stack: self.can_add_new_line_stack.clone(),
+        }
     }
 
-    fn write_block(&mut self, block: &nodes::Block) {
-        let mut statements = block.iter_statements().peekable();
+    fn resume(&mut self, output: String, state: Self::State) {
+        self.output = output;
+        self.current_line_length = state.current_line_length;
+        self.current_indentation = state.current_indentation;
+        self.last_push_length = state.last_push_length;
+        self.can_add_new_line_stack = state.can_add_new_line_stack;
+    }
+
+    fn output(&self) -> &str {
+        &self.output
+    }
+
+    fn lookbehind(state: &Self::State) -> usize {
+        state.last_push_length
+    }
+
+    fn write_segment(&mut self, block: &nodes::Block, index: usize) {
+        if self.preserves_layout() {
+            self.write_segment_with_layout(block, index);
+            return;
+        }
 
-        while let Some(statement) = statements.next() {
+        let mut statements = block.iter_statements().skip(index);
+
+        if let Some(statement) = statements.next() {
             let current_type: StatementType = statement.into();
 
             self.push_can_add_new_line(false);
             self.write_statement(statement);
 
-            if let Some(next_statement) = statements.peek() {
+            if let Some(next_statement) = statements.next() {
                 if utils::starts_with_parenthese(next_statement)
                     && utils::ends_with_prefix(statement)
                 {
                     self.push_char(';');
                 }
 
-                if current_type != (*next_statement).into() {
+                if current_type != next_statement.into() {
                     self.push_new_line();
                 }
             }
 
             self.pop_can_add_new_line();
             self.push_new_line();
-        }
-
-        if let Some(last_statement) = block.get_last_statement() {
+        } else if let Some(last_statement) = block.get_last_statement() {
             if block.iter_statements().next().is_some() {
                 self.push_new_line();
             }
@@ -480,6 +681,24 @@ impl LuaGenerator for ReadableLuaGenerator {
             self.push_new_line();
         }
     }
+}
+
+impl LuaGenerator for ReadableLuaGenerator {
+    fn into_string(self) -> String {
+        self.output
+    }
+
+    fn write_block(&mut self, block: &nodes::Block) {
+        let length = segments_len(block);
+
+        for index in 0..length {
+            self.write_segment(block, index);
+        }
+
+        if self.preserves_layout() {
+            self.write_leading_trivia(layout::block_final_token(block), length == 0);
+        }
+    }
 
     fn write_last_statement(&mut self, statement: &nodes::LastStatement) {
         use nodes::LastStatement::*;
@@ -592,6 +811,7 @@ impl LuaGenerator for ReadableLuaGenerator {
     }
 
     fn write_local_function(&mut self, function: &nodes::LocalFunctionStatement) {
+        self.write_attributes(function.iter_attributes());
         self.push_str("local function ");
         self.raw_push_str(function.get_name());
 
@@ -725,6 +945,7 @@ impl LuaGenerator for ReadableLuaGenerator {
     }
 
     fn write_function_statement(&mut self, function: &nodes::FunctionStatement) {
+        self.write_attributes(function.iter_attributes());
         self.push_str("function ");
         let name = function.get_name();
 
@@ -813,6 +1034,17 @@ impl LuaGenerator for ReadableLuaGenerator {
         }
     }
 
+    fn write_verbatim_statement(&mut self, statement: &nodes::VerbatimStatement) {
+        if self.current_line_length != 0 {
+            self.push_new_line();
+        }
+        let content = statement.get_content();
+        self.output.push_str(content);
+        // the new line after the statement is pushed when writing the segment
+        self.current_line_length = content.rsplit('\n').next().map(str::len).unwrap_or(0);
+        self.last_push_length = 0;
+    }
+
     fn write_type_declaration_statement(&mut self, statement: &nodes::TypeDeclarationStatement) {
         if statement.is_exported() {
             self.push_str("export");
@@ -910,7 +1142,7 @@ impl LuaGenerator for ReadableLuaGenerator {
     }
 
     fn write_unary_expression(&mut self, unary: &nodes::UnaryExpression) {
-        use nodes::{Expression, UnaryOperator::*};
+        use nodes::UnaryOperator::*;
 
         match unary.operator() {
             Length => self.push_char('#'),
@@ -920,15 +1152,15 @@ impl LuaGenerator for ReadableLuaGenerator {
 
         let expression = unary.get_expression();
 
-        match expression {
-            Expression::Binary(binary) if !binary.operator().precedes_unary_expression() => {
-                self.write_expression_in_parentheses(expression);
-            }
-            _ => self.write_expression(expression),
+        if nodes::UnaryExpression::operand_needs_parentheses(expression) {
+            self.write_expression_in_parentheses(expression);
+        } else {
+            self.write_expression(expression);
         }
     }
 
     fn write_function(&mut self, function: &nodes::FunctionExpression) {
+        self.write_attributes(function.iter_attributes());
         self.push_str("function");
 
         if let Some(generics) = function.get_generic_parameters() {
@@ -1117,6 +1349,13 @@ impl LuaGenerator for ReadableLuaGenerator {
     }
 
     fn write_string(&mut self, string: &nodes::StringExpression) {
+        // a token that does not reference the original code holds the literal chosen by a
+        // rule (like `normalize_string_escapes`)
+        if let Some(literal) = string.get_token().and_then(nodes::Token::try_read) {
+            self.push_str(literal);
+            return;
+        }
+
         let result = utils::write_string(string.get_value());
         if result.starts_with('[') {
             self.push_str_and_break_if(&result, utils::break_long_string);
@@ -0,0 +1,494 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::nodes::{Block, LastStatement, Statement};
+
+use super::LuaGenerator;
+
+/// A generator that writes the top-level statements of a block one at a time (a segment)
+/// and that can resume writing after code it generated previously. It makes it possible to
+/// only generate the statements of a block that changed since a previous generation.
+pub trait SegmentedLuaGenerator: LuaGenerator + Sized {
+    /// Everything that affects how the next code is generated, except the code that was
+    /// already generated.
+    type State: Clone + fmt::Debug + PartialEq;
+
+    /// Returns the current state of the generator.
+    fn segment_state(&self) -> Self::State;
+    /// Continues generating code after the given output, with the state the generator had
+    /// when it generated that output.
+    fn resume(&mut self, output: String, state: Self::State);
+    /// Returns the code generated so far.
+    fn output(&self) -> &str;
+    /// Returns the number of bytes at the end of the output that the generator may read or
+    /// modify when writing more code, in addition to the current line.
+    fn lookbehind(state: &Self::State) -> usize;
+    /// Writes a segment of a block: the statement at the given index (or the last statement
+    /// of the block when the index is the number of statements), followed by what separates
+    /// it from the next statement.
+    fn write_segment(&mut self, block: &Block, index: usize);
+}
+
+/// Returns the number of segments of a block (its statements and its last statement).
+pub fn segments_len(block: &Block) -> usize {
+    block.statements_len() + usize::from(block.get_last_statement().is_some())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Boundary<S> {
+    position: usize,
+    state: S,
+}
+
+/// The location of the code generated for each top-level statement of a block, with the
+/// state the generator had before writing each of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentMap<S> {
+    // one more boundary than segments, for the end of the code
+    boundaries: Vec<Boundary<S>>,
+}
+
+impl<S> SegmentMap<S> {
+    /// Returns the number of segments.
+    pub fn len(&self) -> usize {
+        self.boundaries.len().saturating_sub(1)
+    }
+
+    /// Returns `true` if there are no segments.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the byte range of the code generated for the segment at the given index.
+    pub fn range(&self, index: usize) -> Option<Range<usize>> {
+        Some(self.boundaries.get(index)?.position..self.boundaries.get(index + 1)?.position)
+    }
+}
+
+/// Generated code with its [`SegmentMap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentedCode<S> {
+    code: String,
+    segments: SegmentMap<S>,
+}
+
+impl<S> SegmentedCode<S> {
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn segments(&self) -> &SegmentMap<S> {
+        &self.segments
+    }
+
+    pub fn into_code(self) -> String {
+        self.code
+    }
+}
+
+/// Generates the code of a block and records the segment of each of its top-level
+/// statements. The code is the same as the one written by `write_block`.
+pub fn generate_segments<G: SegmentedLuaGenerator>(
+    mut generator: G,
+    block: &Block,
+) -> SegmentedCode<G::State> {
+    let length = segments_len(block);
+    let mut boundaries = Vec::with_capacity(length + 1);
+
+    for index in 0..length {
+        boundaries.push(Boundary {
+            position: generator.output().len(),
+            state: generator.segment_state(),
+        });
+        generator.write_segment(block, index);
+    }
+
+    boundaries.push(Boundary {
+        position: generator.output().len(),
+        state: generator.segment_state(),
+    });
+
+    SegmentedCode {
+        code: generator.into_string(),
+        segments: SegmentMap { boundaries },
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum SegmentItem<'a> {
+    Statement(&'a Statement),
+    // whether the block has other statements is part of the last segment, since generators
+    // may separate the last statement from the others
+    LastStatement(&'a LastStatement, bool),
+}
+
+fn segment_items(block: &Block) -> Vec<SegmentItem<'_>> {
+    let has_statements = block.statements_len() > 0;
+
+    block
+        .iter_statements()
+        .map(SegmentItem::Statement)
+        .chain(
+            block
+                .get_last_statement()
+                .map(|statement| SegmentItem::LastStatement(statement, has_statements)),
+        )
+        .collect()
+}
+
+/// For each segment of `block`, finds the segment of `previous` that can be reused. A segment
+/// can be reused when its statement and the statement that follows it are both equal, because
+/// generators may write a separator that depends on the next statement.
+pub fn match_segments(previous: &Block, block: &Block) -> Vec<Option<usize>> {
+    let previous_items = segment_items(previous);
+    let items = segment_items(block);
+
+    let prefix = previous_items
+        .iter()
+        .zip(items.iter())
+        .take_while(|(previous, item)| previous == item)
+        .count();
+    let suffix = previous_items
+        .iter()
+        .rev()
+        .zip(items.iter().rev())
+        .take(previous_items.len().min(items.len()) - prefix)
+        .take_while(|(previous, item)| previous == item)
+        .count();
+
+    (0..items.len())
+        .map(|index| {
+            let previous_index = if index < prefix {
+                Some(index)
+            } else if index >= items.len() - suffix {
+                Some(index + previous_items.len() - items.len())
+            } else {
+                None
+            };
+
+            previous_index.filter(|previous_index| {
+                items.get(index + 1) == previous_items.get(previous_index + 1)
+            })
+        })
+        .collect()
+}
+
+/// Returns the end of the code that a generator in the given state may depend on.
+fn tail<'a, G: SegmentedLuaGenerator>(code: &'a str, state: &G::State) -> &'a str {
+    let line_start = code.rfind('\n').unwrap_or(0);
+    let mut start = line_start.min(code.len().saturating_sub(G::lookbehind(state)));
+
+    while !code.is_char_boundary(start) {
+        start -= 1;
+    }
+
+    &code[start..]
+}
+
+/// Generates the code of a block by copying the code of the segments that did not change
+/// since a previous generation. The `reused` slice contains, for each segment of the block,
+/// the index of the previous segment that has the same content (see [`match_segments`]).
+///
+/// A segment is only copied when the generator would write it from the same state as
+/// before, so the result is the same as generating the whole block. Returns the code with
+/// the number of segments that were generated again.
+pub fn regenerate_segments<G: SegmentedLuaGenerator>(
+    new_generator: impl Fn() -> G,
+    previous: &SegmentedCode<G::State>,
+    reused: &[Option<usize>],
+    block: &Block,
+) -> (SegmentedCode<G::State>, usize) {
+    let length = segments_len(block);
+    let previous_boundaries = &previous.segments.boundaries;
+
+    let mut boundaries = Vec::with_capacity(length + 1);
+    let mut output = String::with_capacity(previous.code.len());
+    let mut generator: Option<G> = None;
+    // the generator state while segments are copied
+    let mut state = new_generator().segment_state();
+    let mut regenerated = 0;
+
+    for index in 0..length {
+        let reusable = reused
+            .get(index)
+            .copied()
+            .flatten()
+            .filter(|previous_index| *previous_index + 1 < previous_boundaries.len())
+            .filter(|previous_index| {
+                let boundary = &previous_boundaries[*previous_index];
+                let previous_code = &previous.code[..boundary.position];
+
+                match &generator {
+                    Some(generator) => {
+                        let current_state = generator.segment_state();
+                        current_state == boundary.state
+                            && tail::<G>(generator.output(), &current_state)
+                                == tail::<G>(previous_code, &boundary.state)
+                    }
+                    None => {
+                        state == boundary.state
+                            && tail::<G>(&output, &state) == tail::<G>(previous_code, &state)
+                    }
+                }
+            });
+
+        if let Some(previous_index) = reusable {
+            if let Some(generator) = generator.take() {
+                output = generator.into_string();
+            }
+
+            let start = &previous_boundaries[previous_index];
+            let end = &previous_boundaries[previous_index + 1];
+
+            boundaries.push(Boundary {
+                position: output.len(),
+                state: start.state.clone(),
+            });
+            output.push_str(&previous.code[start.position..end.position]);
+            state = end.state.clone();
+        } else {
+            let generator = generator.get_or_insert_with(|| {
+                let mut generator = new_generator();
+                generator.resume(std::mem::take(&mut output), state.clone());
+                generator
+            });
+
+            boundaries.push(Boundary {
+                position: generator.output().len(),
+                state: generator.segment_state(),
+            });
+            generator.write_segment(block, index);
+            regenerated += 1;
+        }
+    }
+
+    if let Some(generator) = generator.take() {
+        state = generator.segment_state();
+        output = generator.into_string();
+    }
+
+    boundaries.push(Boundary {
+        position: output.len(),
+        state,
+    });
+
+    (
+        SegmentedCode {
+            code: output,
+            segments: SegmentMap { boundaries },
+        },
+        regenerated,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generator::{DenseLuaGenerator, ReadableLuaGenerator};
+    use crate::Parser;
+
+    fn parse(code: &str) -> Block {
+        Parser::default().parse(code).expect("code should parse")
+    }
+
+    fn module_with_functions(edited: Option<(usize, &str)>) -> String {
+        let mut code = String::new();
+        for index in 0..300 {
+            let value = match edited {
+                Some((edited_index, name)) if edited_index == index => name.to_owned(),
+                _ => format!("value{}", index),
+            };
+            code.push_str(&format!(
+                "local function function{}(a, b)\n    local {} = a + b * {}\n    return {}\nend\n",
+                index, value, index, value
+            ));
+        }
+        code.push_str("return { value = function1 }\n");
+        code
+    }
+
+    fn assert_regenerate<G: SegmentedLuaGenerator>(
+        new_generator: impl Fn() -> G,
+        previous_code: &str,
+        code: &str,
+        expected_regenerated: usize,
+    ) {
+        let previous_block = parse(previous_code);
+        let block = parse(code);
+
+        let previous = generate_segments(new_generator(), &previous_block);
+        let expected = generate_segments(new_generator(), &block);
+
+        let (regenerated, regenerated_count) = regenerate_segments(
+            &new_generator,
+            &previous,
+            &match_segments(&previous_block, &block),
+            &block,
+        );
+
+        pretty_assertions::assert_eq!(regenerated.code(), expected.code());
+        pretty_assertions::assert_eq!(regenerated.segments(), expected.segments());
+        assert_eq!(regenerated_count, expected_regenerated);
+    }
+
+    #[test]
+    fn generate_segments_matches_write_block() {
+        let block = parse(&module_with_functions(None));
+
+        let mut generator = DenseLuaGenerator::default();
+        generator.write_block(&block);
+
+        let segmented = generate_segments(DenseLuaGenerator::default(), &block);
+
+        assert_eq!(segmented.code(), generator.into_string());
+        assert_eq!(segmented.segments().len(), 301);
+    }
+
+    #[test]
+    fn segment_ranges_cover_the_code() {
+        let block = parse("local a = 1 local b = 2 return a + b");
+
+        let segmented = generate_segments(ReadableLuaGenerator::default(), &block);
+        let segments = segmented.segments();
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments.range(0).unwrap().start, 0);
+        assert_eq!(segments.range(2).unwrap().end, segmented.code().len());
+        assert_eq!(
+            &segmented.code()[segments.range(1).unwrap()],
+            "local b = 2\n"
+        );
+    }
+
+    #[test]
+    fn match_segments_of_equal_blocks() {
+        let block = parse("local a = 1 print(a) return a");
+
+        assert_eq!(
+            match_segments(&block, &block),
+            vec![Some(0), Some(1), Some(2)]
+        );
+    }
+
+    #[test]
+    fn match_segments_with_changed_statement() {
+        assert_eq!(
+            match_segments(
+                &parse("local a = 1 local b = 2 local c = 3 return c"),
+                &parse("local a = 1 local b = 4 local c = 3 return c"),
+            ),
+            vec![None, None, Some(2), Some(3)]
+        );
+    }
+
+    #[test]
+    fn match_segments_with_inserted_statement() {
+        assert_eq!(
+            match_segments(
+                &parse("local a = 1 local c = 3 return c"),
+                &parse("local a = 1 local b = 2 local c = 3 return c"),
+            ),
+            vec![None, None, Some(1), Some(2)]
+        );
+    }
+
+    #[test]
+    fn match_segments_with_last_statement_after_removed_statements() {
+        assert_eq!(
+            match_segments(&parse("local a = 1 return a"), &parse("return a")),
+            vec![None]
+        );
+    }
+
+    // the dense generator writes the start of the next function on the line where the
+    // changed function ends, so the next function is generated again too
+    #[test]
+    fn regenerate_dense_with_one_changed_function() {
+        assert_regenerate(
+            DenseLuaGenerator::default,
+            &module_with_functions(None),
+            &module_with_functions(Some((150, "other150"))),
+            3,
+        );
+    }
+
+    #[test]
+    fn regenerate_dense_with_small_column_span() {
+        assert_regenerate(
+            || DenseLuaGenerator::new(40),
+            &module_with_functions(None),
+            &module_with_functions(Some((7, "other7"))),
+            3,
+        );
+    }
+
+    #[test]
+    fn regenerate_dense_with_function_changing_length() {
+        let previous = module_with_functions(None);
+        let code = module_with_functions(Some((150, "edited")));
+
+        let previous_block = parse(&previous);
+        let block = parse(&code);
+        let previous = generate_segments(DenseLuaGenerator::default(), &previous_block);
+
+        let (regenerated, _) = regenerate_segments(
+            DenseLuaGenerator::default,
+            &previous,
+            &match_segments(&previous_block, &block),
+            &block,
+        );
+
+        pretty_assertions::assert_eq!(
+            regenerated,
+            generate_segments(DenseLuaGenerator::default(), &block)
+        );
+    }
+
+    #[test]
+    fn regenerate_readable_with_one_changed_function() {
+        assert_regenerate(
+            ReadableLuaGenerator::default,
+            &module_with_functions(None),
+            &module_with_functions(Some((150, "edited"))),
+            2,
+        );
+    }
+
+    #[test]
+    fn regenerate_readable_with_changed_first_function() {
+        assert_regenerate(
+            ReadableLuaGenerator::default,
+            &module_with_functions(None),
+            &module_with_functions(Some((0, "edited"))),
+            1,
+        );
+    }
+
+    // the statement before the call is generated again to write the `;` that separates it
+    // from the parenthese, but the last statement is copied
+    #[test]
+    fn regenerate_dense_with_statement_starting_with_parenthese() {
+        assert_regenerate(
+            DenseLuaGenerator::default,
+            "local a = b print(a) print(a)",
+            "local a = b (f)(a) print(a)",
+            2,
+        );
+    }
+
+    #[test]
+    fn regenerate_readable_with_removed_statements() {
+        assert_regenerate(
+            ReadableLuaGenerator::default,
+            "local a = 1 local b = 2 return a",
+            "return a",
+            1,
+        );
+    }
+
+    #[test]
+    fn regenerate_without_changes() {
+        let code = module_with_functions(None);
+        assert_regenerate(DenseLuaGenerator::default, &code, &code, 0);
+    }
+}
@@ -13,6 +13,7 @@ pub struct TokenBasedLuaGenerator<'a> {
     output: String,
     currently_commenting: bool,
     current_line: usize,
+    last_token_end: usize,
 }
 
 impl<'a> TokenBasedLuaGenerator<'a> {
@@ -22,6 +23,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
             output: String::new(),
             currently_commenting: false,
             current_line: 1,
+            last_token_end: 0,
         }
     }
 
@@ -81,6 +83,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
             }
 
             self.push_str(content);
+            self.last_token_end = self.output.len();
         }
 
         for trivia in token.iter_trailing_trivia() {
@@ -95,7 +98,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
             self.write_statement(statement);
 
             if let Some(semicolon) = tokens.semicolons.get(index).unwrap_or(&None) {
-                self.write_token(semicolon);
+                self.write_semicolon(semicolon);
             } else if let Some((_, next_statement)) = iterator.peek() {
                 if utils::starts_with_parenthese(next_statement)
                     && utils::ends_with_prefix(statement)
@@ -107,6 +110,10 @@ impl<'a> TokenBasedLuaGenerator<'a> {
 
         if let Some(statement) = block.get_last_statement() {
             self.write_last_statement(statement);
+
+            if let Some(semicolon) = &tokens.last_semicolon {
+                self.write_semicolon(semicolon);
+            }
         }
 
         if let Some(token) = &tokens.final_token {
@@ -114,6 +121,25 @@ impl<'a> TokenBasedLuaGenerator<'a> {
         }
     }
 
+    /// Writes a semicolon token after a statement. When the semicolon was not
+    /// parsed from the original code, it is placed right after the statement
+    /// content (before the whitespaces that may follow it).
+    fn write_semicolon(&mut self, semicolon: &Token) {
+        if semicolon.get_line_number().is_none()
+            && !semicolon.has_trivia()
+            && self.last_token_end > 0
+            && self.output[self.last_token_end..]
+                .chars()
+                .all(char::is_whitespace)
+        {
+            let content = semicolon.read(self.original_code);
+            self.output.insert_str(self.last_token_end, content);
+            self.last_token_end += content.len();
+        } else {
+            self.write_token(semicolon);
+        }
+    }
+
     fn write_return_with_tokens(&mut self, statement: &ReturnStatement, tokens: &ReturnTokens) {
         self.write_token(&tokens.r#return);
 
@@ -326,6 +352,17 @@ impl<'a> TokenBasedLuaGenerator<'a> {
         self.write_expression(assign.get_value());
     }
 
+    fn write_attributes<'b>(&mut self, attributes: impl Iterator<Item = &'b FunctionAttribute>) {
+        for attribute in attributes {
+            if let Some(token) = attribute.get_token() {
+                self.write_token(token);
+            } else {
+                self.write_symbol("@");
+            }
+            self.write_identifier(attribute.get_name());
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn write_function_attributes<'b>(
         &mut self,
@@ -403,6 +440,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
         function: &FunctionStatement,
         tokens: &FunctionBodyTokens,
     ) {
+        self.write_attributes(function.iter_attributes());
         self.write_token(&tokens.function);
 
         let name = function.get_name();
@@ -586,6 +624,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
         function: &LocalFunctionStatement,
         tokens: &LocalFunctionTokens,
     ) {
+        self.write_attributes(function.iter_attributes());
         self.write_token(&tokens.local);
         self.write_token(&tokens.function);
         self.write_identifier(function.get_identifier());
@@ -734,6 +773,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
         function: &FunctionExpression,
         tokens: &FunctionBodyTokens,
     ) {
+        self.write_attributes(function.iter_attributes());
         self.write_token(&tokens.function);
 
         self.write_function_attributes(
@@ -1614,6 +1654,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
             self.output.push(' ');
         }
         self.push_str(symbol);
+        self.last_token_end = self.output.len();
     }
 
     fn write_symbol_without_space_check(&mut self, symbol: &str) {
@@ -1621,6 +1662,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
             self.uncomment();
         }
         self.push_str(symbol);
+        self.last_token_end = self.output.len();
     }
 
     fn write_typed_identifier(&mut self, typed_identifier: &TypedIdentifier) {
@@ -1631,7 +1673,7 @@ impl<'a> TokenBasedLuaGenerator<'a> {
                 self.write_token(token);
             } else {
                 let mut new_token = token.clone();
-                new_token.replace_with_content(typed_identifier.get_name().clone());
+                new_token.replace_with_content(typed_identifier.get_name().to_owned());
                 self.write_token(&new_token);
             }
         } else {
@@ -1839,6 +1881,18 @@ impl LuaGenerator for TokenBasedLuaGenerator<'_> {
         }
     }
 
+    fn write_verbatim_statement(&mut self, statement: &VerbatimStatement) {
+        if self.currently_commenting {
+            self.uncomment();
+        } else if !self.output.is_empty() && !self.output.ends_with('\n') {
+            self.push_str("\n");
+        }
+        self.push_str(statement.get_content());
+        // like after a single line comment, the next token is written on a new line
+        self.currently_commenting = true;
+        self.last_token_end = self.output.len();
+    }
+
     fn write_type_declaration_statement(&mut self, statement: &TypeDeclarationStatement) {
         if let Some(tokens) = statement.get_tokens() {
             self.write_type_declaration_with_tokens(statement, tokens);
@@ -1918,13 +1972,12 @@ impl LuaGenerator for TokenBasedLuaGenerator<'_> {
         }
 
         let expression = unary.get_expression();
-        match expression {
-            Expression::Binary(binary) if !binary.operator().precedes_unary_expression() => {
-                self.write_symbol("(");
-                self.write_expression(expression);
-                self.write_symbol(")");
-            }
-            _ => self.write_expression(expression),
+        if UnaryExpression::operand_needs_parentheses(expression) {
+            self.write_symbol("(");
+            self.write_expression(expression);
+            self.write_symbol(")");
+        } else {
+            self.write_expression(expression);
         }
     }
 
@@ -2051,7 +2104,7 @@ impl LuaGenerator for TokenBasedLuaGenerator<'_> {
                 self.write_token(token);
             } else {
                 let mut new_token = token.clone();
-                new_token.replace_with_content(identifier.get_name().clone());
+                new_token.replace_with_content(identifier.get_name().to_owned());
                 self.write_token(&new_token);
             }
         } else {
@@ -2376,6 +2429,11 @@ mod test {
         return_binary_and => "return a and b",
         return_binary_or => "return a or b",
         return_binary_plus => "return 10 + 15",
+        return_binary_number => "return 0b1010",
+        return_uppercase_binary_number_with_separators => "return 0B1010_0101",
+        return_decimal_number_with_separators => "return 1_000_000",
+        return_hex_number_with_separators => "return 0x_FF_FF",
+        return_float_with_separators => "return 1_000.000_1e1_0",
         return_empty_function => "return function() end",
         return_empty_variadic_function => "return function(...)\nend",
         return_empty_function_with_one_argument => "return function( a )\nend",
@@ -2426,7 +2484,7 @@ mod test {
 
         tuple.iter_mut_values().for_each(|value| match value {
             Expression::Identifier(identifier) => {
-                let name = identifier.mutate_name();
+                let name = identifier.get_name();
                 let new_token = {
                     Token::from_content(name.to_owned())
                         .with_trailing_trivia(TriviaKind::Comment.with_content("--new comment"))
@@ -1,6 +1,7 @@
 //! A module that contains the main [LuaGenerator](trait.LuaGenerator.html) trait
 //! and its implementations.
 
+use crate::generator::lexical::tokens_merge;
 use crate::nodes::{
     Expression, FieldExpression, FunctionCall, IndexExpression, NumberExpression, Prefix,
     Statement, StringSegment, TableExpression, Variable,
@@ -28,7 +29,7 @@ pub fn should_break_with_space(ending_character: char, next_character: char) ->
 
 pub fn break_long_string(last_str: &str) -> bool {
     if let Some(last_char) = last_str.chars().last() {
-        last_char == '['
+        last_char == '[' || tokens_merge(last_str, "[")
     } else {
         false
     }
@@ -38,7 +39,7 @@ pub fn break_variable_arguments(last_string: &str) -> bool {
     if let Some('.') = last_string.chars().last() {
         true
     } else if let Some(first_char) = last_string.chars().next() {
-        first_char == '.' || first_char.is_ascii_digit()
+        first_char == '.' || first_char.is_ascii_digit() || tokens_merge(last_string, "...")
     } else {
         false
     }
@@ -46,7 +47,7 @@ pub fn break_variable_arguments(last_string: &str) -> bool {
 
 pub fn break_minus(last_string: &str) -> bool {
     if let Some(last_char) = last_string.chars().last() {
-        last_char == '-'
+        last_char == '-' || tokens_merge(last_string, "-")
     } else {
         false
     }
@@ -54,7 +55,7 @@ pub fn break_minus(last_string: &str) -> bool {
 
 pub fn break_equal(last_string: &str) -> bool {
     if let Some(last_char) = last_string.chars().last() {
-        last_char == '>'
+        last_char == '>' || tokens_merge(last_string, "=")
     } else {
         false
     }
@@ -64,7 +65,7 @@ pub fn break_concat(last_string: &str) -> bool {
     if let Some('.') = last_string.chars().last() {
         true
     } else if let Some(first_char) = last_string.chars().next() {
-        first_char == '.' || first_char.is_ascii_digit()
+        first_char == '.' || first_char.is_ascii_digit() || tokens_merge(last_string, "..")
     } else {
         false
     }
@@ -343,22 +344,34 @@ pub fn write_interpolated_string_segment(segment: &StringSegment) -> String {
     result
 }
 
-fn write_long_bracket(value: &str) -> String {
-    let mut i: usize = value.ends_with(']').into();
-    let mut equals = "=".repeat(i);
+/// Returns `true` if the value can be written between long brackets. Long strings can not
+/// contain escape sequences, so values with special characters must be quoted.
+pub(crate) fn can_write_long_bracket(value: &str) -> bool {
+    !value.contains(needs_quoted_string)
+}
+
+/// Returns the smallest number of `=` needed in the long brackets around the value, so that
+/// the closing bracket can not appear before the end of the string.
+pub(crate) fn long_bracket_level(value: &str) -> usize {
+    let mut level = 0;
     loop {
-        if !value.contains(&format!("]{}]", equals)) {
-            break;
-        } else {
-            i += 1;
-            equals = "=".repeat(i);
-        };
+        let closing = format!("]{}]", "=".repeat(level));
+
+        if format!("{}{}", value, closing).find(&closing) == Some(value.len()) {
+            return level;
+        }
+
+        level += 1;
     }
+}
+
+pub(crate) fn write_long_bracket(value: &str) -> String {
+    let equals = "=".repeat(long_bracket_level(value));
     let needs_extra_new_line = if value.starts_with('\n') { "\n" } else { "" };
     format!("[{}[{}{}]{}]", equals, needs_extra_new_line, value, equals)
 }
 
-fn write_quoted(value: &str) -> String {
+pub(crate) fn write_quoted(value: &str) -> String {
     let mut quoted = String::new();
     quoted.reserve(value.len() + 2);
 
@@ -439,6 +452,8 @@ mod test {
                 => "[[ooof\nooof\nooof\nooof\nooof\nooof\nooof\nooof\noof]]",
             large_multiline_ends_with_closing_bracket("ooof\nooof\nooof\nooof\nooof\nooof\nooof\nooof\noof]")
                 => "[=[ooof\nooof\nooof\nooof\nooof\nooof\nooof\nooof\noof]]=]",
+            large_multiline_with_closing_brackets_ends_with_equal("ooof\nooof\nooof\nooof\nooof\nooof\nooof\nooof]]\noof]=")
+                => "[==[ooof\nooof\nooof\nooof\nooof\nooof\nooof\nooof]]\noof]=]==]",
             large_multiline_starts_with_new_line("\nooof\nooof\nooof\nooof\nooof\nooof\nooof\nooof\noof")
                 => "[[\n\nooof\nooof\nooof\nooof\nooof\nooof\nooof\nooof\noof]]",
 
@@ -7,10 +7,22 @@ pub mod nodes;
 mod parser;
 pub mod process;
 pub mod rules;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 mod utils;
 
 pub use frontend::{
-    convert_data, process, BundleConfiguration, Configuration, DarkluaError, GeneratorParameters,
-    Options, Resources, WorkerTree,
+    apply_edits, check_output, check_output_files, describe_rules, doctor, generate_expression,
+    process, process_code, run_rule_example, ArtifactTemplate, AtomicMode, BundleConfiguration,
+    Configuration, DarkluaError, DependencyGraph, DependencyGraphOptions, DeprecationPolicy,
+    DoctorReport, FileIssue, FileNotes, GeneratorParameters, HashAlgorithm, Options, OutputCheck,
+    OutputTemplate, OutputViolation, ProcessOutcome, ProcessingSession, PropertyDescription,
+    PropertySafety, ReprocessPolicy, Resources, RuleCompatibility, RuleDescription,
+    RuleExampleOutput, StageConfiguration, StageSelection, UnresolvedRequire, WorkerTree,
 };
-pub use parser::{Parser, ParserError};
+#[cfg(feature = "serde-config")]
+pub use frontend::{
+    convert_data, get_configuration_schema, read_resolved_configuration, ConfigurationIssue,
+    CONFIGURATION_SCHEMA_VERSION,
+};
+pub use parser::{Parser, ParserError, RawMarkers};
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::iter;
 
 use crate::nodes::{Expression, StringExpression, TableExpression, Token};
@@ -50,6 +51,11 @@ impl TupleArguments {
         self.tokens.as_ref()
     }
 
+    #[inline]
+    pub fn mutate_tokens(&mut self) -> Option<&mut TupleArgumentsTokens> {
+        self.tokens.as_mut()
+    }
+
     pub fn with_argument<T: Into<Expression>>(mut self, argument: T) -> Self {
         self.values.push(argument.into());
         self
@@ -80,11 +86,7 @@ impl TupleArguments {
 
 impl From<Arguments> for TupleArguments {
     fn from(arguments: Arguments) -> Self {
-        match arguments {
-            Arguments::Tuple(tuple) => tuple,
-            Arguments::String(string) => TupleArguments::default().with_argument(string),
-            Arguments::Table(table) => TupleArguments::default().with_argument(table),
-        }
+        arguments.to_tuple()
     }
 }
 
@@ -113,8 +115,68 @@ impl Arguments {
         }
     }
 
+    /// Returns the number of arguments. A call written with a string or a table (like
+    /// `f"str"` or `f{}`) has one argument.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Tuple(tuple) => tuple.len(),
+            Self::String(_) | Self::Table(_) => 1,
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the arguments, whatever the form of the call. The argument of a call
+    /// written with a string or a table is cloned into an expression.
+    pub fn iter_expressions(&self) -> impl Iterator<Item = Cow<'_, Expression>> {
+        let (values, single) = match self {
+            Self::Tuple(tuple) => (tuple.values.as_slice(), None),
+            Self::String(string) => (&[][..], Some(Expression::from(string.clone()))),
+            Self::Table(table) => (&[][..], Some(Expression::from(table.clone()))),
+        };
+
+        values
+            .iter()
+            .map(Cow::Borrowed)
+            .chain(single.map(Cow::Owned))
+    }
+
+    /// Converts the arguments into a tuple, so that arguments can be added or removed. When
+    /// the call is written with a string or a table, the trivia that follows it is moved after
+    /// the closing parenthese.
+    pub fn to_tuple(self) -> TupleArguments {
+        match self {
+            Self::Tuple(tuple) => tuple,
+            Self::String(mut string) => {
+                let tokens = string
+                    .mutate_token()
+                    .map(|token| closing_tuple_tokens(token.take_trailing_trivia()));
+                let tuple = TupleArguments::default().with_argument(string);
+
+                match tokens {
+                    Some(tokens) => tuple.with_tokens(tokens),
+                    None => tuple,
+                }
+            }
+            Self::Table(mut table) => {
+                let tokens = table.mutate_tokens().map(|tokens| {
+                    closing_tuple_tokens(tokens.closing_brace.take_trailing_trivia())
+                });
+                let tuple = TupleArguments::default().with_argument(table);
+
+                match tokens {
+                    Some(tokens) => tuple.with_tokens(tokens),
+                    None => tuple,
+                }
+            }
+        }
+    }
+
     pub fn with_argument<T: Into<Expression>>(self, argument: T) -> Self {
-        TupleArguments::from(self).with_argument(argument).into()
+        self.to_tuple().with_argument(argument).into()
     }
 
     pub fn clear_comments(&mut self) {
@@ -151,6 +213,26 @@ impl Arguments {
             Arguments::String(_) | Arguments::Table(_) => {}
         }
     }
+
+    pub(crate) fn mutate_trivia(&mut self, mutate: impl FnMut(&mut super::TriviaCollection)) {
+        match self {
+            Arguments::Tuple(tuple) => tuple.mutate_trivia(mutate),
+            Arguments::String(_) | Arguments::Table(_) => {}
+        }
+    }
+}
+
+fn closing_tuple_tokens(trailing_trivia: Vec<super::Trivia>) -> TupleArgumentsTokens {
+    let mut closing_parenthese = Token::from_content(")");
+    for trivia in trailing_trivia {
+        closing_parenthese.push_trailing_trivia(trivia);
+    }
+
+    TupleArgumentsTokens {
+        opening_parenthese: Token::from_content("("),
+        closing_parenthese,
+        commas: Vec::new(),
+    }
 }
 
 impl Default for Arguments {
@@ -176,3 +258,99 @@ impl From<StringExpression> for Arguments {
         Self::String(string)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nodes::TriviaKind;
+
+    #[test]
+    fn len_of_string_arguments() {
+        let arguments = Arguments::from(StringExpression::from_value("value"));
+
+        assert_eq!(arguments.len(), 1);
+        assert!(!arguments.is_empty());
+    }
+
+    #[test]
+    fn len_of_table_arguments() {
+        assert_eq!(Arguments::from(TableExpression::default()).len(), 1);
+    }
+
+    #[test]
+    fn empty_tuple_arguments() {
+        assert!(Arguments::default().is_empty());
+    }
+
+    #[test]
+    fn iter_expressions_of_tuple() {
+        let arguments = Arguments::default()
+            .with_argument(true)
+            .with_argument(false);
+
+        assert_eq!(
+            arguments
+                .iter_expressions()
+                .map(Cow::into_owned)
+                .collect::<Vec<_>>(),
+            vec![Expression::from(true), Expression::from(false)]
+        );
+    }
+
+    #[test]
+    fn iter_expressions_of_string() {
+        let string = StringExpression::from_value("value");
+        let arguments = Arguments::from(string.clone());
+
+        assert_eq!(
+            arguments
+                .iter_expressions()
+                .map(Cow::into_owned)
+                .collect::<Vec<_>>(),
+            vec![Expression::from(string)]
+        );
+    }
+
+    #[test]
+    fn with_argument_converts_string_to_tuple() {
+        let string = StringExpression::from_value("value");
+        let arguments = Arguments::from(string.clone()).with_argument(true);
+
+        assert_eq!(
+            arguments,
+            Arguments::Tuple(
+                TupleArguments::default()
+                    .with_argument(string)
+                    .with_argument(true)
+            )
+        );
+    }
+
+    #[test]
+    fn to_tuple_moves_trailing_trivia_of_string_token() {
+        let string = StringExpression::from_value("value").with_token(
+            Token::from_content("'value'")
+                .with_trailing_trivia(TriviaKind::Comment.with_content("--comment")),
+        );
+
+        let tuple = Arguments::from(string).to_tuple();
+
+        let tokens = tuple.get_tokens().expect("tuple should have tokens");
+        assert_eq!(tokens.closing_parenthese.iter_trailing_trivia().count(), 1);
+
+        match tuple.iter_values().next() {
+            Some(Expression::String(string)) => {
+                assert!(!string.get_token().unwrap().has_trivia())
+            }
+            _ => panic!("expected a string argument"),
+        };
+    }
+
+    #[test]
+    fn to_tuple_without_tokens() {
+        let tuple = Arguments::from(TableExpression::default()).to_tuple();
+
+        assert_eq!(tuple.get_tokens(), None);
+        assert_eq!(tuple.len(), 1);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::nodes::{
-    Block, FunctionBodyTokens, FunctionReturnType, FunctionVariadicType, GenericParameters,
-    TypedIdentifier,
+    Block, FunctionAttribute, FunctionBodyTokens, FunctionReturnType, FunctionVariadicType,
+    GenericParameters, TypedIdentifier,
 };
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -11,6 +11,7 @@ pub struct FunctionExpression {
     variadic_type: Option<FunctionVariadicType>,
     return_type: Option<FunctionReturnType>,
     generic_parameters: Option<GenericParameters>,
+    attributes: Vec<FunctionAttribute>,
     tokens: Option<Box<FunctionBodyTokens>>,
 }
 
@@ -23,6 +24,7 @@ impl FunctionExpression {
             variadic_type: None,
             return_type: None,
             generic_parameters: None,
+            attributes: Vec::new(),
             tokens: None,
         }
     }
@@ -35,6 +37,7 @@ impl FunctionExpression {
             variadic_type: None,
             return_type: None,
             generic_parameters: None,
+            attributes: Vec::new(),
             tokens: None,
         }
     }
@@ -127,6 +130,11 @@ impl FunctionExpression {
         self.generic_parameters.as_ref()
     }
 
+    #[inline]
+    pub fn mutate_generic_parameters(&mut self) -> Option<&mut GenericParameters> {
+        self.generic_parameters.as_mut()
+    }
+
     #[inline]
     pub fn is_generic(&self) -> bool {
         self.generic_parameters.is_some()
@@ -147,6 +155,11 @@ impl FunctionExpression {
         self.tokens.as_ref().map(|tokens| tokens.as_ref())
     }
 
+    #[inline]
+    pub fn mutate_tokens(&mut self) -> Option<&mut FunctionBodyTokens> {
+        self.tokens.as_mut().map(|tokens| tokens.as_mut())
+    }
+
     #[inline]
     pub fn get_block(&self) -> &Block {
         &self.block
@@ -192,6 +205,42 @@ impl FunctionExpression {
         !self.parameters.is_empty()
     }
 
+    pub fn with_attribute(mut self, attribute: impl Into<FunctionAttribute>) -> Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    #[inline]
+    pub fn push_attribute(&mut self, attribute: impl Into<FunctionAttribute>) {
+        self.attributes.push(attribute.into());
+    }
+
+    #[inline]
+    pub fn set_attributes(&mut self, attributes: Vec<FunctionAttribute>) {
+        self.attributes = attributes;
+    }
+
+    #[inline]
+    pub fn iter_attributes(&self) -> impl Iterator<Item = &FunctionAttribute> {
+        self.attributes.iter()
+    }
+
+    #[inline]
+    pub fn has_attributes(&self) -> bool {
+        !self.attributes.is_empty()
+    }
+
+    #[inline]
+    pub fn mutate_attributes(&mut self) -> &mut Vec<FunctionAttribute> {
+        &mut self.attributes
+    }
+
+    /// Removes the attributes of the function and returns them.
+    #[inline]
+    pub fn take_attributes(&mut self) -> Vec<FunctionAttribute> {
+        std::mem::take(&mut self.attributes)
+    }
+
     pub fn clear_types(&mut self) {
         self.return_type.take();
         self.variadic_type.take();
@@ -204,5 +253,5 @@ impl FunctionExpression {
         }
     }
 
-    super::impl_token_fns!(iter = [parameters, generic_parameters, tokens]);
+    super::impl_token_fns!(iter = [parameters, generic_parameters, attributes, tokens]);
 }
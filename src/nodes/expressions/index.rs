@@ -41,6 +41,11 @@ impl IndexExpression {
         self.tokens.as_ref()
     }
 
+    #[inline]
+    pub fn mutate_tokens(&mut self) -> Option<&mut IndexExpressionTokens> {
+        self.tokens.as_mut()
+    }
+
     #[inline]
     pub fn get_prefix(&self) -> &Prefix {
         &self.prefix
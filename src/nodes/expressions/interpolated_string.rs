@@ -1,6 +1,6 @@
 use std::iter::FromIterator;
 
-use crate::nodes::{StringError, Token, Trivia};
+use crate::nodes::{StringError, Token, Trivia, TriviaCollection};
 
 use super::{string_utils, Expression};
 
@@ -146,6 +146,13 @@ impl InterpolationSegment {
             InterpolationSegment::Value(segment) => segment.filter_comments(filter),
         }
     }
+
+    pub(crate) fn mutate_trivia(&mut self, mutate: impl FnMut(&mut TriviaCollection)) {
+        match self {
+            InterpolationSegment::String(segment) => segment.mutate_trivia(mutate),
+            InterpolationSegment::Value(segment) => segment.mutate_trivia(mutate),
+        }
+    }
 }
 
 impl From<StringSegment> for InterpolationSegment {
@@ -216,6 +223,10 @@ impl InterpolatedStringExpression {
         self.tokens.as_ref()
     }
 
+    pub fn mutate_tokens(&mut self) -> Option<&mut InterpolatedStringTokens> {
+        self.tokens.as_mut()
+    }
+
     pub fn set_tokens(&mut self, tokens: InterpolatedStringTokens) {
         self.tokens = Some(tokens);
     }
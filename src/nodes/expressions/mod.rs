@@ -9,6 +9,7 @@ mod parenthese;
 mod prefix;
 mod string;
 pub(crate) mod string_utils;
+mod substitution;
 mod table;
 mod type_cast;
 mod unary;
@@ -24,6 +25,7 @@ pub use parenthese::*;
 pub use prefix::*;
 pub use string::*;
 pub use string_utils::StringError;
+pub use substitution::*;
 pub use table::*;
 pub use type_cast::*;
 pub use unary::*;
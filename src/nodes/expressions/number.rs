@@ -1,7 +1,7 @@
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::str::FromStr;
 
-use crate::nodes::{Token, Trivia};
+use crate::nodes::{Token, Trivia, TriviaCollection};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct DecimalNumber {
@@ -36,6 +36,11 @@ impl DecimalNumber {
         self.token.as_ref()
     }
 
+    #[inline]
+    fn mutate_token(&mut self) -> Option<&mut Token> {
+        self.token.as_mut()
+    }
+
     pub fn with_exponent(mut self, exponent: i64, is_uppercase: bool) -> Self {
         self.exponent.replace((exponent, is_uppercase));
         self
@@ -105,6 +110,11 @@ impl HexNumber {
         self.token.as_ref()
     }
 
+    #[inline]
+    fn mutate_token(&mut self) -> Option<&mut Token> {
+        self.token.as_mut()
+    }
+
     pub fn with_exponent(mut self, exponent: u32, is_uppercase: bool) -> Self {
         self.exponent.replace((exponent, is_uppercase));
         self
@@ -177,6 +187,11 @@ impl BinaryNumber {
         self.token.as_ref()
     }
 
+    #[inline]
+    fn mutate_token(&mut self) -> Option<&mut Token> {
+        self.token.as_mut()
+    }
+
     pub fn set_uppercase(&mut self, is_uppercase: bool) {
         self.is_b_uppercase = is_uppercase;
     }
@@ -249,6 +264,15 @@ impl NumberExpression {
         }
     }
 
+    #[inline]
+    pub fn mutate_token(&mut self) -> Option<&mut Token> {
+        match self {
+            NumberExpression::Decimal(number) => number.mutate_token(),
+            NumberExpression::Hex(number) => number.mutate_token(),
+            NumberExpression::Binary(number) => number.mutate_token(),
+        }
+    }
+
     pub fn clear_comments(&mut self) {
         match self {
             NumberExpression::Decimal(number) => number.clear_comments(),
@@ -288,6 +312,14 @@ impl NumberExpression {
             NumberExpression::Binary(number) => number.filter_comments(filter),
         }
     }
+
+    pub(crate) fn mutate_trivia(&mut self, mutate: impl FnMut(&mut TriviaCollection)) {
+        match self {
+            NumberExpression::Decimal(number) => number.mutate_trivia(mutate),
+            NumberExpression::Hex(number) => number.mutate_trivia(mutate),
+            NumberExpression::Binary(number) => number.mutate_trivia(mutate),
+        }
+    }
 }
 
 impl From<DecimalNumber> for NumberExpression {
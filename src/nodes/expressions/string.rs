@@ -1,41 +1,57 @@
 use std::str::CharIndices;
 
+use crate::generator::utils;
 use crate::nodes::{StringError, Token};
 
 use super::string_utils;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// The form used to write a string in Lua code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringStyle {
+    /// A string written between single or double quotes, like `'hello'`.
+    Quoted,
+    /// A string written between long brackets, like `[[hello]]` or `[==[hello]==]`.
+    LongBracket,
+}
+
+/// A string expression. Strings with the same value are equal, whatever the form used to
+/// write them.
+#[derive(Clone, Debug)]
 pub struct StringExpression {
     value: String,
+    long_string_level: Option<usize>,
     token: Option<Token>,
 }
 
+impl PartialEq for StringExpression {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.token == other.token
+    }
+}
+
+impl Eq for StringExpression {}
+
 impl StringExpression {
     pub fn new(string: &str) -> Result<Self, StringError> {
         if string.starts_with('[') {
-            return string
+            let level = string
                 .chars()
                 .skip(1)
-                .enumerate()
-                .find_map(|(indice, character)| if character == '[' { Some(indice) } else { None })
-                .ok_or_else(|| StringError::invalid("unable to find `[` delimiter"))
-                .and_then(|indice| {
-                    let length = 2 + indice;
-                    let start = if string
-                        .get(length..length + 1)
-                        .filter(|char| char == &"\n")
-                        .is_some()
-                    {
-                        length + 1
-                    } else {
-                        length
-                    };
-                    string
-                        .get(start..string.len() - length)
-                        .map(str::to_owned)
-                        .ok_or_else(|| StringError::invalid(""))
-                })
-                .map(Self::from_value);
+                .position(|character| character != '=')
+                .filter(|level| string.get(level + 1..level + 2) == Some("["))
+                .ok_or_else(|| StringError::invalid("unable to find `[` delimiter"))?;
+
+            let length = 2 + level;
+            let content = string
+                .get(length..string.len().saturating_sub(length))
+                .filter(|_| string.len() >= length * 2)
+                .ok_or_else(|| StringError::invalid("unable to find `]` delimiter"))?;
+
+            return Ok(Self {
+                value: skip_long_string_first_new_line(content).to_owned(),
+                long_string_level: Some(level),
+                token: None,
+            });
         }
 
         let mut chars = string.char_indices();
@@ -55,6 +71,7 @@ impl StringExpression {
     pub fn empty() -> Self {
         Self {
             value: "".to_owned(),
+            long_string_level: None,
             token: None,
         }
     }
@@ -62,6 +79,7 @@ impl StringExpression {
     pub fn from_value<T: Into<String>>(value: T) -> Self {
         Self {
             value: value.into(),
+            long_string_level: None,
             token: None,
         }
     }
@@ -81,6 +99,11 @@ impl StringExpression {
         self.token.as_ref()
     }
 
+    #[inline]
+    pub fn mutate_token(&mut self) -> Option<&mut Token> {
+        self.token.as_mut()
+    }
+
     #[inline]
     pub fn get_value(&self) -> &str {
         &self.value
@@ -91,6 +114,45 @@ impl StringExpression {
         self.value
     }
 
+    /// Returns the bytes of the decoded value. For long strings, the new line directly
+    /// following the opening bracket is not part of the value.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.value.as_bytes()
+    }
+
+    /// Returns `true` if the string was written between long brackets (like `[[hello]]`).
+    #[inline]
+    pub fn is_long_string(&self) -> bool {
+        self.long_string_level.is_some()
+    }
+
+    /// Returns the number of `=` in the long brackets of the string (like 2 for
+    /// `[==[hello]==]`), or `None` if the string was not written between long brackets.
+    #[inline]
+    pub fn long_string_level(&self) -> Option<usize> {
+        self.long_string_level
+    }
+
+    /// Encodes the value of the string into Lua code. The shortest valid form is used, and
+    /// the given style is used when both forms have the same length. The long bracket form
+    /// is never used for values that it can not represent (like a carriage return).
+    pub fn encode_preferring(&self, style: StringStyle) -> String {
+        let quoted = utils::write_quoted(&self.value);
+
+        if !utils::can_write_long_bracket(&self.value) {
+            return quoted;
+        }
+
+        let long_bracket = utils::write_long_bracket(&self.value);
+
+        match style {
+            StringStyle::LongBracket if long_bracket.len() <= quoted.len() => long_bracket,
+            StringStyle::Quoted if long_bracket.len() < quoted.len() => long_bracket,
+            _ => quoted,
+        }
+    }
+
     pub fn is_multiline(&self) -> bool {
         self.value.contains('\n')
     }
@@ -134,6 +196,15 @@ impl StringExpression {
     super::impl_token_fns!(iter = [token]);
 }
 
+/// Removes the new line that directly follows the opening bracket of a long string, which is
+/// not part of its value.
+fn skip_long_string_first_new_line(content: &str) -> &str {
+    ["\r\n", "\n\r", "\n", "\r"]
+        .iter()
+        .find_map(|new_line| content.strip_prefix(new_line))
+        .unwrap_or(content)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -338,4 +409,129 @@ mod test {
 
         assert!(!string.has_double_quote());
     }
+
+    #[test]
+    fn new_quoted_string_is_not_a_long_string() {
+        let string = StringExpression::new("'hello'").unwrap();
+
+        assert!(!string.is_long_string());
+        assert_eq!(string.long_string_level(), None);
+    }
+
+    #[test]
+    fn new_long_string_with_two_equals_has_level_two() {
+        let string = StringExpression::new("[==[hello]==]").unwrap();
+
+        assert!(string.is_long_string());
+        assert_eq!(string.long_string_level(), Some(2));
+    }
+
+    #[test]
+    fn new_long_string_skips_first_carriage_return_and_new_line() {
+        let string = StringExpression::new("[[\r\nhello]]").unwrap();
+
+        assert_eq!(string.as_bytes(), b"hello");
+    }
+
+    #[test]
+    fn new_long_string_keeps_second_new_line() {
+        let string = StringExpression::new("[[\n\nhello]]").unwrap();
+
+        assert_eq!(string.get_value(), "\nhello");
+    }
+
+    #[test]
+    fn new_long_string_with_missing_bracket_errors() {
+        assert!(StringExpression::new("[=hello]=]").is_err());
+    }
+
+    #[test]
+    fn strings_with_the_same_value_are_equal() {
+        assert_eq!(
+            StringExpression::new("[[hello]]").unwrap(),
+            StringExpression::new("'hello'").unwrap()
+        );
+    }
+
+    mod encode_preferring {
+        use super::*;
+
+        fn round_trip(value: &str, style: StringStyle) -> String {
+            let encoded = StringExpression::from_value(value).encode_preferring(style);
+
+            assert_eq!(
+                StringExpression::new(&encoded).unwrap().get_value(),
+                value,
+                "unable to read back encoded string `{}`",
+                encoded
+            );
+
+            encoded
+        }
+
+        #[test]
+        fn short_value_is_quoted() {
+            assert_eq!(round_trip("hello", StringStyle::LongBracket), "'hello'");
+        }
+
+        #[test]
+        fn value_with_many_new_lines_stays_long() {
+            assert_eq!(
+                round_trip("a\nb\nc\nd", StringStyle::Quoted),
+                "[[a\nb\nc\nd]]"
+            );
+        }
+
+        #[test]
+        fn same_length_uses_preferred_style() {
+            assert_eq!(
+                round_trip("a\nb\nc", StringStyle::LongBracket),
+                "[[a\nb\nc]]"
+            );
+            assert_eq!(round_trip("a\nb\nc", StringStyle::Quoted), "'a\\nb\\nc'");
+        }
+
+        #[test]
+        fn value_with_leading_new_line() {
+            assert_eq!(
+                round_trip("\n\n\n\nhello", StringStyle::LongBracket),
+                "[[\n\n\n\n\nhello]]"
+            );
+        }
+
+        #[test]
+        fn value_containing_closing_bracket_with_one_equal() {
+            assert_eq!(
+                round_trip("a\n]=]\nb\n]]\nc\nd\ne\n", StringStyle::LongBracket),
+                "[==[a\n]=]\nb\n]]\nc\nd\ne\n]==]"
+            );
+        }
+
+        #[test]
+        fn value_ending_with_closing_bracket_and_equal() {
+            assert_eq!(
+                round_trip("a\n]]\nb\nc\nd\ne\nf\n]=", StringStyle::LongBracket),
+                "[==[a\n]]\nb\nc\nd\ne\nf\n]=]==]"
+            );
+        }
+
+        #[test]
+        fn value_with_carriage_return_is_quoted() {
+            assert_eq!(
+                round_trip("a\r\nb\r\nc\r\nd", StringStyle::LongBracket),
+                "'a\\r\\nb\\r\\nc\\r\\nd'"
+            );
+        }
+
+        #[test]
+        fn long_string_with_level_two_round_trips() {
+            let string = StringExpression::new("[==[\n]]\n]=]\na\nb\nc\nd\ne\n]==]").unwrap();
+
+            assert_eq!(string.get_value(), "]]\n]=]\na\nb\nc\nd\ne\n");
+            assert_eq!(
+                round_trip(string.get_value(), StringStyle::LongBracket),
+                "[==[]]\n]=]\na\nb\nc\nd\ne\n]==]"
+            );
+        }
+    }
 }
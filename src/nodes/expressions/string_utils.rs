@@ -42,13 +42,76 @@ impl StringError {
     }
 }
 
+/// The decoded value of a quoted string: escape sequences of numeric bytes (like `\233`)
+/// are pushed with `push_byte`, and every other character with `push_char`.
+trait DecodedString: Default {
+    fn reserve(&mut self, additional: usize);
+
+    fn push_char(&mut self, character: char);
+
+    fn push_byte(&mut self, byte: u8);
+
+    fn shrink_to_fit(&mut self);
+}
+
+impl DecodedString for String {
+    fn reserve(&mut self, additional: usize) {
+        String::reserve(self, additional);
+    }
+
+    fn push_char(&mut self, character: char) {
+        self.push(character);
+    }
+
+    fn push_byte(&mut self, byte: u8) {
+        self.push(byte as char);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        String::shrink_to_fit(self);
+    }
+}
+
+impl DecodedString for Vec<u8> {
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional);
+    }
+
+    fn push_char(&mut self, character: char) {
+        let mut buffer = [0; 4];
+        self.extend_from_slice(character.encode_utf8(&mut buffer).as_bytes());
+    }
+
+    fn push_byte(&mut self, byte: u8) {
+        self.push(byte);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        Vec::shrink_to_fit(self);
+    }
+}
+
 pub(crate) fn read_escaped_string(
     chars: CharIndices,
     reserve_size: Option<usize>,
 ) -> Result<String, StringError> {
+    read_escaped(chars, reserve_size)
+}
+
+/// Decodes the content of a quoted string into the bytes that Lua reads: numeric escapes
+/// (like `\233` or `\xE9`) produce a single byte, while unicode escapes and characters
+/// written directly produce their UTF-8 encoding.
+pub(crate) fn read_escaped_bytes(chars: CharIndices) -> Result<Vec<u8>, StringError> {
+    read_escaped(chars, None)
+}
+
+fn read_escaped<T: DecodedString>(
+    chars: CharIndices,
+    reserve_size: Option<usize>,
+) -> Result<T, StringError> {
     let mut chars = chars.peekable();
 
-    let mut value = String::new();
+    let mut value = T::default();
     if let Some(reserve_size) = reserve_size {
         value.reserve(reserve_size);
     }
@@ -57,19 +120,19 @@ pub(crate) fn read_escaped_string(
         if char == '\\' {
             if let Some((_, next_char)) = chars.next() {
                 match next_char {
-                    '\n' | '"' | '\'' | '\\' => value.push(next_char),
-                    'n' => value.push('\n'),
-                    't' => value.push('\t'),
-                    'a' => value.push('\u{7}'),
-                    'b' => value.push('\u{8}'),
-                    'v' => value.push('\u{B}'),
-                    'f' => value.push('\u{C}'),
-                    'r' => value.push('\r'),
+                    '\n' | '"' | '\'' | '\\' => value.push_char(next_char),
+                    'n' => value.push_char('\n'),
+                    't' => value.push_char('\t'),
+                    'a' => value.push_char('\u{7}'),
+                    'b' => value.push_char('\u{8}'),
+                    'v' => value.push_char('\u{B}'),
+                    'f' => value.push_char('\u{C}'),
+                    'r' => value.push_char('\r'),
                     first_digit if first_digit.is_ascii_digit() => {
                         let number = read_number(&mut chars, Some(first_digit), 10, 3);
 
                         if number < 256 {
-                            value.push(number as u8 as char);
+                            value.push_byte(number as u8);
                         } else {
                             return Err(StringError::malformed_escape_sequence(
                                 position,
@@ -86,7 +149,7 @@ pub(crate) fn read_escaped_string(
                                 + second_digit.to_digit(16).unwrap();
 
                             if number < 256 {
-                                value.push(number as u8 as char);
+                                value.push_byte(number as u8);
                             } else {
                                 return Err(StringError::malformed_escape_sequence(
                                     position,
@@ -124,7 +187,9 @@ pub(crate) fn read_escaped_string(
                             ));
                         }
 
-                        value.push(char::from_u32(number).expect("unable to convert u32 to char"));
+                        value.push_char(
+                            char::from_u32(number).expect("unable to convert u32 to char"),
+                        );
                     }
                     'z' => {
                         while chars
@@ -137,7 +202,7 @@ pub(crate) fn read_escaped_string(
                     }
                     _ => {
                         // an invalid escape does not error: it simply skips the backslash
-                        value.push(next_char);
+                        value.push_char(next_char);
                     }
                 }
             } else {
@@ -147,7 +212,7 @@ pub(crate) fn read_escaped_string(
                 ));
             }
         } else {
-            value.push(char);
+            value.push_char(char);
         }
     }
 
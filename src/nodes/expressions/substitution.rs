@@ -0,0 +1,390 @@
+use crate::nodes::{
+    BinaryOperator, Expression, ParentheseExpression, TypeCastExpression, UnaryExpression,
+};
+
+/// The syntactic position where an expression is written. It is used to find if an
+/// expression moved into that position needs to be wrapped in parentheses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpressionContext {
+    /// A position where any expression can be written and where only one value is kept
+    /// (like the value of a field in a table or a condition).
+    Single,
+    /// The left operand of a binary expression.
+    BinaryLeft(BinaryOperator),
+    /// The right operand of a binary expression.
+    BinaryRight(BinaryOperator),
+    /// The operand of a unary expression.
+    UnaryOperand,
+    /// The expression casted by a type cast.
+    TypeCast,
+    /// The prefix of a call, a field or an index expression. This also includes the prefix
+    /// of a call statement.
+    Prefix,
+    /// The key of a table entry written between brackets.
+    TableKey,
+    /// A position where all the values of a call or of `...` are kept, like the last
+    /// argument of a call or the last value of a return statement.
+    MultipleValues,
+}
+
+impl ExpressionContext {
+    /// Returns true when the expression needs to be wrapped in parentheses to be written
+    /// in this context without changing its meaning.
+    pub fn needs_parentheses(&self, expression: &Expression) -> bool {
+        if matches!(expression, Expression::Parenthese(_)) {
+            return false;
+        }
+
+        match self {
+            Self::Single | Self::TableKey => false,
+            Self::BinaryLeft(operator) => operator.left_needs_parentheses(expression),
+            Self::BinaryRight(operator) => operator.right_needs_parentheses(expression),
+            Self::UnaryOperand => UnaryExpression::operand_needs_parentheses(expression),
+            Self::TypeCast => TypeCastExpression::needs_parentheses(expression),
+            Self::Prefix => !is_prefix(expression),
+            Self::MultipleValues => can_produce_multiple_values(expression),
+        }
+    }
+}
+
+/// Wraps the expression in parentheses when it is required to write it in the given
+/// context.
+pub fn wrap_if_needed(replacement: Expression, context: ExpressionContext) -> Expression {
+    if context.needs_parentheses(&replacement) {
+        ParentheseExpression::new(replacement).into()
+    } else {
+        replacement
+    }
+}
+
+/// Replaces an expression while keeping the number of values it produces: when the
+/// target produces a single value, a call or `...` used as a replacement is wrapped in
+/// parentheses. Use [`wrap_if_needed`] when the position of the target is known, to avoid
+/// adding parentheses where they are not needed.
+pub fn substitute_expression(target: &mut Expression, replacement: Expression) {
+    *target = if can_produce_multiple_values(target) {
+        replacement
+    } else {
+        wrap_if_needed(replacement, ExpressionContext::MultipleValues)
+    };
+}
+
+fn is_prefix(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Call(_)
+            | Expression::Field(_)
+            | Expression::Identifier(_)
+            | Expression::Index(_)
+            | Expression::Parenthese(_)
+    )
+}
+
+fn can_produce_multiple_values(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Call(_) | Expression::VariableArguments(_)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::nodes::{
+        BinaryExpression, FieldExpression, FunctionCall, IfExpression, StringExpression,
+        UnaryOperator,
+    };
+
+    const ALL_OPERATORS: [BinaryOperator; 16] = [
+        BinaryOperator::And,
+        BinaryOperator::Or,
+        BinaryOperator::Equal,
+        BinaryOperator::NotEqual,
+        BinaryOperator::LowerThan,
+        BinaryOperator::LowerOrEqualThan,
+        BinaryOperator::GreaterThan,
+        BinaryOperator::GreaterOrEqualThan,
+        BinaryOperator::Plus,
+        BinaryOperator::Minus,
+        BinaryOperator::Asterisk,
+        BinaryOperator::Slash,
+        BinaryOperator::DoubleSlash,
+        BinaryOperator::Percent,
+        BinaryOperator::Caret,
+        BinaryOperator::Concat,
+    ];
+
+    // the precedence levels from the Lua reference manual, written independently from
+    // the operator methods so that the tests can catch a mistake in them
+    fn reference_level(operator: BinaryOperator) -> u8 {
+        use BinaryOperator::*;
+
+        match operator {
+            Or => 1,
+            And => 2,
+            Equal | NotEqual | LowerThan | LowerOrEqualThan | GreaterThan | GreaterOrEqualThan => 3,
+            Concat => 4,
+            Plus | Minus => 5,
+            Asterisk | Slash | DoubleSlash | Percent => 6,
+            Caret => 8,
+        }
+    }
+
+    fn is_reference_right_associative(operator: BinaryOperator) -> bool {
+        matches!(operator, BinaryOperator::Concat | BinaryOperator::Caret)
+    }
+
+    fn binary(operator: BinaryOperator) -> Expression {
+        BinaryExpression::new(
+            operator,
+            Expression::identifier("a"),
+            Expression::identifier("b"),
+        )
+        .into()
+    }
+
+    fn call() -> Expression {
+        FunctionCall::from_name("f").into()
+    }
+
+    fn is_wrapped(expression: &Expression) -> bool {
+        matches!(expression, Expression::Parenthese(_))
+    }
+
+    #[test]
+    fn binary_left_operand_for_every_operator_pair() {
+        for outer in ALL_OPERATORS {
+            for inner in ALL_OPERATORS {
+                let expected = reference_level(inner) < reference_level(outer)
+                    || (reference_level(inner) == reference_level(outer)
+                        && is_reference_right_associative(outer));
+
+                let result = wrap_if_needed(binary(inner), ExpressionContext::BinaryLeft(outer));
+
+                assert_eq!(
+                    is_wrapped(&result),
+                    expected,
+                    "`(a {} b) {} c`",
+                    inner.to_str(),
+                    outer.to_str()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn binary_right_operand_for_every_operator_pair() {
+        for outer in ALL_OPERATORS {
+            for inner in ALL_OPERATORS {
+                let expected = reference_level(inner) < reference_level(outer)
+                    || (reference_level(inner) == reference_level(outer)
+                        && !is_reference_right_associative(outer));
+
+                let result = wrap_if_needed(binary(inner), ExpressionContext::BinaryRight(outer));
+
+                assert_eq!(
+                    is_wrapped(&result),
+                    expected,
+                    "`c {} (a {} b)`",
+                    outer.to_str(),
+                    inner.to_str()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unary_operand_for_every_operator() {
+        for operator in ALL_OPERATORS {
+            let result = wrap_if_needed(binary(operator), ExpressionContext::UnaryOperand);
+
+            assert_eq!(
+                is_wrapped(&result),
+                reference_level(operator) < 7,
+                "`-(a {} b)`",
+                operator.to_str()
+            );
+        }
+    }
+
+    #[test]
+    fn unary_expression_as_binary_operand() {
+        let unary: Expression =
+            UnaryExpression::new(UnaryOperator::Minus, Expression::identifier("a")).into();
+
+        for operator in ALL_OPERATORS {
+            let left = wrap_if_needed(unary.clone(), ExpressionContext::BinaryLeft(operator));
+            let right = wrap_if_needed(unary.clone(), ExpressionContext::BinaryRight(operator));
+
+            assert_eq!(
+                is_wrapped(&left),
+                reference_level(operator) > 7,
+                "`(-a) {} b`",
+                operator.to_str()
+            );
+            assert!(!is_wrapped(&right), "`b {} -a`", operator.to_str());
+        }
+    }
+
+    #[test]
+    fn if_expression_as_binary_left_operand() {
+        let if_expression: Expression = IfExpression::new(
+            Expression::identifier("c"),
+            Expression::identifier("a"),
+            Expression::identifier("b"),
+        )
+        .into();
+
+        for operator in ALL_OPERATORS {
+            let result = wrap_if_needed(
+                if_expression.clone(),
+                ExpressionContext::BinaryLeft(operator),
+            );
+
+            assert!(is_wrapped(&result));
+        }
+    }
+
+    #[test]
+    fn literals_are_not_wrapped_as_operands() {
+        for operator in ALL_OPERATORS {
+            for context in [
+                ExpressionContext::BinaryLeft(operator),
+                ExpressionContext::BinaryRight(operator),
+            ] {
+                assert!(!is_wrapped(&wrap_if_needed(Expression::from(1.0), context)));
+                assert!(!is_wrapped(&wrap_if_needed(call(), context)));
+            }
+        }
+    }
+
+    #[test]
+    fn parenthese_is_never_wrapped_again() {
+        let parenthese: Expression = ParentheseExpression::new(binary(BinaryOperator::Or)).into();
+
+        for context in [
+            ExpressionContext::Single,
+            ExpressionContext::BinaryLeft(BinaryOperator::Caret),
+            ExpressionContext::BinaryRight(BinaryOperator::Caret),
+            ExpressionContext::UnaryOperand,
+            ExpressionContext::TypeCast,
+            ExpressionContext::Prefix,
+            ExpressionContext::TableKey,
+            ExpressionContext::MultipleValues,
+        ] {
+            let result = wrap_if_needed(parenthese.clone(), context);
+
+            assert_eq!(result, parenthese);
+        }
+    }
+
+    #[test]
+    fn prefix_position_wraps_expressions_that_are_not_prefixes() {
+        let wrapped = [
+            binary(BinaryOperator::Plus),
+            Expression::from(true),
+            Expression::nil(),
+            Expression::from(1.0),
+            StringExpression::from_value("text").into(),
+            Expression::variable_arguments(),
+            UnaryExpression::new(UnaryOperator::Not, Expression::identifier("a")).into(),
+        ];
+
+        for expression in wrapped {
+            let result = wrap_if_needed(expression.clone(), ExpressionContext::Prefix);
+
+            assert_eq!(result, ParentheseExpression::new(expression).into());
+        }
+    }
+
+    #[test]
+    fn prefix_position_keeps_prefixes() {
+        let kept = [
+            Expression::identifier("a"),
+            call(),
+            FieldExpression::new(Expression::identifier("a"), "b").into(),
+        ];
+
+        for expression in kept {
+            let result = wrap_if_needed(expression.clone(), ExpressionContext::Prefix);
+
+            assert_eq!(result, expression);
+        }
+    }
+
+    #[test]
+    fn multiple_values_position_wraps_call() {
+        let result = wrap_if_needed(call(), ExpressionContext::MultipleValues);
+
+        assert_eq!(result, ParentheseExpression::new(call()).into());
+    }
+
+    #[test]
+    fn multiple_values_position_wraps_variable_arguments() {
+        let result = wrap_if_needed(
+            Expression::variable_arguments(),
+            ExpressionContext::MultipleValues,
+        );
+
+        assert!(is_wrapped(&result));
+    }
+
+    #[test]
+    fn multiple_values_position_keeps_single_values() {
+        for expression in [
+            Expression::identifier("a"),
+            binary(BinaryOperator::And),
+            Expression::from(true),
+        ] {
+            let result = wrap_if_needed(expression.clone(), ExpressionContext::MultipleValues);
+
+            assert_eq!(result, expression);
+        }
+    }
+
+    #[test]
+    fn single_value_positions_keep_calls() {
+        for context in [ExpressionContext::Single, ExpressionContext::TableKey] {
+            assert_eq!(wrap_if_needed(call(), context), call());
+            assert_eq!(
+                wrap_if_needed(binary(BinaryOperator::Or), context),
+                binary(BinaryOperator::Or)
+            );
+        }
+    }
+
+    #[test]
+    fn type_cast_wraps_binary_expression() {
+        let result = wrap_if_needed(binary(BinaryOperator::Plus), ExpressionContext::TypeCast);
+
+        assert!(is_wrapped(&result));
+    }
+
+    #[test]
+    fn substitute_single_value_with_call_wraps_call() {
+        let mut target = Expression::identifier("a");
+
+        substitute_expression(&mut target, call());
+
+        assert_eq!(target, ParentheseExpression::new(call()).into());
+    }
+
+    #[test]
+    fn substitute_call_with_call_keeps_call() {
+        let mut target = Expression::variable_arguments();
+
+        substitute_expression(&mut target, call());
+
+        assert_eq!(target, call());
+    }
+
+    #[test]
+    fn substitute_call_with_single_value() {
+        let mut target = call();
+
+        substitute_expression(&mut target, Expression::from(true));
+
+        assert_eq!(target, Expression::from(true));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    nodes::{Expression, Identifier, Token, Trivia},
+    nodes::{Expression, Identifier, Token, Trivia, TriviaCollection},
     process::utils::is_valid_identifier,
 };
 
@@ -141,7 +141,7 @@ impl TableEntry {
     pub fn from_string_key_and_value(key: impl Into<String>, value: impl Into<Expression>) -> Self {
         let key = key.into();
         let value = value.into();
-        if is_valid_identifier(&key) {
+        if is_valid_identifier(&key, None) {
             Self::Field(TableFieldEntry {
                 field: Identifier::new(key),
                 value,
@@ -195,6 +195,14 @@ impl TableEntry {
             TableEntry::Value(_) => {}
         }
     }
+
+    pub(crate) fn mutate_trivia(&mut self, mutate: impl FnMut(&mut TriviaCollection)) {
+        match self {
+            TableEntry::Field(entry) => entry.mutate_trivia(mutate),
+            TableEntry::Index(entry) => entry.mutate_trivia(mutate),
+            TableEntry::Value(_) => {}
+        }
+    }
 }
 
 impl From<TableFieldEntry> for TableEntry {
@@ -252,6 +260,11 @@ impl TableExpression {
         self.tokens.as_ref()
     }
 
+    #[inline]
+    pub fn mutate_tokens(&mut self) -> Option<&mut TableTokens> {
+        self.tokens.as_mut()
+    }
+
     #[inline]
     pub fn get_entries(&self) -> &Vec<TableEntry> {
         &self.entries
@@ -282,6 +295,24 @@ impl TableExpression {
         &mut self.entries
     }
 
+    pub fn remove_entry(&mut self, index: usize) -> Option<TableEntry> {
+        if index < self.entries.len() {
+            let entry = self.entries.remove(index);
+
+            if let Some(tokens) = &mut self.tokens {
+                if index < tokens.separators.len() {
+                    tokens.separators.remove(index);
+                } else {
+                    tokens.separators.pop();
+                }
+            }
+
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
     pub fn append_entry<T: Into<TableEntry>>(mut self, entry: T) -> Self {
         self.entries.push(entry.into());
         self
@@ -63,5 +63,14 @@ impl UnaryExpression {
         self.operator
     }
 
+    /// Returns true when the given operand must be wrapped in parentheses to be used with
+    /// a unary operator.
+    pub fn operand_needs_parentheses(operand: &Expression) -> bool {
+        match operand {
+            Expression::Binary(binary) => !binary.operator().precedes_unary_expression(),
+            _ => false,
+        }
+    }
+
     super::impl_token_fns!(iter = [token]);
 }
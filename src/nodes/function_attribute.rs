@@ -0,0 +1,51 @@
+use crate::nodes::{Identifier, Token};
+
+/// A Luau function attribute, like `@native`, written before a function definition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionAttribute {
+    name: Identifier,
+    token: Option<Token>,
+}
+
+impl FunctionAttribute {
+    pub fn new(name: impl Into<Identifier>) -> Self {
+        Self {
+            name: name.into(),
+            token: None,
+        }
+    }
+
+    /// Sets the token of the `@` symbol.
+    pub fn with_token(mut self, token: Token) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    #[inline]
+    pub fn set_token(&mut self, token: Token) {
+        self.token = Some(token);
+    }
+
+    #[inline]
+    pub fn get_token(&self) -> Option<&Token> {
+        self.token.as_ref()
+    }
+
+    #[inline]
+    pub fn get_name(&self) -> &Identifier {
+        &self.name
+    }
+
+    #[inline]
+    pub fn mutate_name(&mut self) -> &mut Identifier {
+        &mut self.name
+    }
+
+    super::impl_token_fns!(target = [name] iter = [token]);
+}
+
+impl<IntoIdentifier: Into<Identifier>> From<IntoIdentifier> for FunctionAttribute {
+    fn from(name: IntoIdentifier) -> Self {
+        Self::new(name)
+    }
+}
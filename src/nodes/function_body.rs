@@ -1,6 +1,6 @@
 use super::{
-    Block, FunctionExpression, FunctionName, FunctionReturnType, FunctionStatement,
-    FunctionVariadicType, GenericParameters, Identifier, LocalFunctionStatement,
+    Block, FunctionAttribute, FunctionExpression, FunctionName, FunctionReturnType,
+    FunctionStatement, FunctionVariadicType, GenericParameters, Identifier, LocalFunctionStatement,
     LocalFunctionTokens, Token, TypedIdentifier,
 };
 
@@ -21,6 +21,7 @@ pub(crate) struct FunctionBuilder {
     variable_arguments_colon: Option<Token>,
     return_type_colon: Option<Token>,
     generic_parameters: Option<GenericParameters>,
+    attributes: Vec<FunctionAttribute>,
 }
 
 impl FunctionBuilder {
@@ -42,6 +43,7 @@ impl FunctionBuilder {
             variable_arguments_colon: None,
             return_type_colon: None,
             generic_parameters: None,
+            attributes: Vec::new(),
         }
     }
 
@@ -60,6 +62,8 @@ impl FunctionBuilder {
             expression.set_generic_parameters(generic_parameters);
         }
 
+        expression.set_attributes(self.attributes);
+
         if let (Some(function), Some(opening_parenthese), Some(closing_parenthese), Some(end)) = (
             self.function,
             self.opening_parenthese,
@@ -97,6 +101,8 @@ impl FunctionBuilder {
             statement.set_generic_parameters(generic_parameters);
         }
 
+        statement.set_attributes(self.attributes);
+
         if let (Some(function), Some(opening_parenthese), Some(closing_parenthese), Some(end)) = (
             self.function,
             self.opening_parenthese,
@@ -138,6 +144,8 @@ impl FunctionBuilder {
             statement.set_generic_parameters(generic_parameters);
         }
 
+        statement.set_attributes(self.attributes);
+
         if let (
             Some(local),
             Some(function),
@@ -222,6 +230,10 @@ impl FunctionBuilder {
     pub(crate) fn set_generic_parameters(&mut self, generic_parameters: GenericParameters) {
         self.generic_parameters = Some(generic_parameters);
     }
+
+    pub(crate) fn push_attribute(&mut self, attribute: FunctionAttribute) {
+        self.attributes.push(attribute);
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
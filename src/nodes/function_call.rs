@@ -60,6 +60,11 @@ impl FunctionCall {
         self.tokens.as_ref()
     }
 
+    #[inline]
+    pub fn mutate_tokens(&mut self) -> Option<&mut FunctionCallTokens> {
+        self.tokens.as_mut()
+    }
+
     pub fn with_arguments<A: Into<Arguments>>(mut self, arguments: A) -> Self {
         self.arguments = arguments.into();
         self
@@ -85,6 +90,11 @@ impl FunctionCall {
         self.method.as_ref()
     }
 
+    #[inline]
+    pub fn mutate_method(&mut self) -> Option<&mut Identifier> {
+        self.method.as_mut()
+    }
+
     #[inline]
     pub fn get_prefix(&self) -> &Prefix {
         &self.prefix
@@ -1,15 +1,18 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
 use crate::nodes::Token;
 
-use super::{Type, TypedIdentifier};
+use super::{Symbol, Type, TypedIdentifier};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Identifier {
-    name: String,
+    name: Symbol,
     token: Option<Token>,
 }
 
 impl Identifier {
-    pub fn new<S: Into<String>>(name: S) -> Self {
+    pub fn new<S: Into<Symbol>>(name: S) -> Self {
         Self {
             name: name.into(),
             token: None,
@@ -41,34 +44,75 @@ impl Identifier {
     }
 
     #[inline]
-    pub fn get_name(&self) -> &String {
-        &self.name
+    pub fn get_name(&self) -> &str {
+        self.name.as_str()
     }
 
+    /// Returns the name as a shared symbol, which is cheaper to clone and to compare with
+    /// the symbols of the same parsed block than the name itself.
     #[inline]
-    pub fn mutate_name(&mut self) -> &mut String {
-        &mut self.name
+    pub fn get_symbol(&self) -> &Symbol {
+        &self.name
+    }
+
+    /// Returns a mutable access to a copy of the name, which replaces the name of the
+    /// identifier (like [`Identifier::set_name`]) when it is dropped.
+    #[deprecated(note = "names are shared symbols now, use `set_name` instead")]
+    pub fn mutate_name(&mut self) -> IdentifierNameMut<'_> {
+        IdentifierNameMut {
+            name: self.name.to_string(),
+            identifier: self,
+        }
     }
 
     #[inline]
-    pub fn set_name<IntoString: Into<String>>(&mut self, name: IntoString) {
+    pub fn set_name<S: Into<Symbol>>(&mut self, name: S) {
         let name = name.into();
         if let Some(token) = &mut self.token {
-            token.replace_with_content(name.clone());
+            token.replace_with_content(name.to_string());
         }
         self.name = name;
     }
 
     #[inline]
     pub fn into_name(self) -> String {
-        self.name
+        self.name.into()
     }
 
     super::impl_token_fns!(iter = [token]);
 }
 
-impl<IntoString: Into<String>> From<IntoString> for Identifier {
-    fn from(identifier: IntoString) -> Self {
+/// The name of an identifier returned by [`Identifier::mutate_name`]. The name of the
+/// identifier is updated when the value is dropped, if it was modified.
+pub struct IdentifierNameMut<'a> {
+    identifier: &'a mut Identifier,
+    name: String,
+}
+
+impl Deref for IdentifierNameMut<'_> {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.name
+    }
+}
+
+impl DerefMut for IdentifierNameMut<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.name
+    }
+}
+
+impl Drop for IdentifierNameMut<'_> {
+    fn drop(&mut self) {
+        if self.identifier.get_name() != self.name {
+            self.identifier.set_name(mem::take(&mut self.name));
+        }
+    }
+}
+
+impl<S: Into<Symbol>> From<S> for Identifier {
+    fn from(identifier: S) -> Self {
         Self {
             name: identifier.into(),
             token: None,
@@ -82,6 +126,16 @@ mod test {
 
     use super::*;
 
+    #[test]
+    #[allow(deprecated)]
+    fn mutate_name_replaces_the_name_when_dropped() {
+        let mut identifier = Identifier::new("var");
+
+        identifier.mutate_name().push_str("Name");
+
+        assert_eq!(identifier.get_name(), "varName");
+    }
+
     #[test]
     fn set_name_replaces_the_token_content() {
         let token = Token::new_with_line(7, 10, 1);
@@ -3,10 +3,12 @@
 mod arguments;
 mod block;
 mod expressions;
+mod function_attribute;
 mod function_body;
 mod function_call;
 mod identifier;
 mod statements;
+mod symbol;
 mod token;
 mod typed_identifier;
 mod types;
@@ -15,10 +17,13 @@ mod variable;
 pub use arguments::*;
 pub use block::*;
 pub use expressions::*;
+pub use function_attribute::*;
 pub use function_body::*;
 pub use function_call::*;
 pub use identifier::*;
 pub use statements::*;
+pub(crate) use symbol::StringInterner;
+pub use symbol::Symbol;
 pub use token::*;
 pub use typed_identifier::*;
 pub use types::*;
@@ -109,6 +114,25 @@ macro_rules! impl_token_fns {
                 }
             )*)?
         }
+
+        pub(crate) fn mutate_trivia(
+            &mut self,
+            mut mutate: impl FnMut(&mut crate::nodes::TriviaCollection),
+        ) {
+            $(
+                self.$field.mutate_trivia(&mut mutate);
+            )*
+            $($(
+                for token in self.$iter_field.iter_mut() {
+                    token.mutate_trivia(&mut mutate);
+                }
+            )*)?
+            $($(
+                for token in self.$iter_flatten_field.iter_mut().flatten() {
+                    token.mutate_trivia(&mut mutate);
+                }
+            )*)?
+        }
     };
 
     (
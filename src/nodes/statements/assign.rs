@@ -68,6 +68,26 @@ impl AssignStatement {
         self.values.last()
     }
 
+    /// Removes the last value of the assignment, unless it is the only value.
+    pub fn pop_value(&mut self) -> Option<Expression> {
+        if self.values.len() > 1 {
+            let value = self.values.pop();
+            if let Some(tokens) = &mut self.tokens {
+                tokens
+                    .value_commas
+                    .truncate(self.values.len().saturating_sub(1));
+            }
+            value
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn push_value(&mut self, value: impl Into<Expression>) {
+        self.values.push(value.into());
+    }
+
     #[inline]
     pub fn iter_values(&self) -> impl Iterator<Item = &Expression> {
         self.values.iter()
@@ -1,6 +1,6 @@
 use crate::nodes::{
-    Block, FunctionBodyTokens, FunctionReturnType, FunctionVariadicType, GenericParameters,
-    Identifier, Token, TypedIdentifier,
+    Block, FunctionAttribute, FunctionBodyTokens, FunctionReturnType, FunctionVariadicType,
+    GenericParameters, Identifier, Token, TypedIdentifier,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -84,6 +84,11 @@ impl FunctionName {
         self.method.as_ref()
     }
 
+    #[inline]
+    pub fn mutate_method(&mut self) -> Option<&mut Identifier> {
+        self.method.as_mut()
+    }
+
     #[inline]
     pub fn has_method(&self) -> bool {
         self.method.is_some()
@@ -104,6 +109,11 @@ impl FunctionName {
         &self.field_names
     }
 
+    #[inline]
+    pub fn iter_mut_field_names(&mut self) -> impl Iterator<Item = &mut Identifier> {
+        self.field_names.iter_mut()
+    }
+
     #[inline]
     pub fn mutate_identifier(&mut self) -> &mut Identifier {
         &mut self.name
@@ -121,6 +131,7 @@ pub struct FunctionStatement {
     variadic_type: Option<FunctionVariadicType>,
     return_type: Option<FunctionReturnType>,
     generic_parameters: Option<GenericParameters>,
+    attributes: Vec<FunctionAttribute>,
     tokens: Option<Box<FunctionBodyTokens>>,
 }
 
@@ -139,19 +150,21 @@ impl FunctionStatement {
             variadic_type: None,
             return_type: None,
             generic_parameters: None,
+            attributes: Vec::new(),
             tokens: None,
         }
     }
 
     pub fn from_name<S: Into<String>, B: Into<Block>>(name: S, block: B) -> Self {
         Self {
-            name: FunctionName::from_name(name),
+            name: FunctionName::from_name(Into::<String>::into(name)),
             block: block.into(),
             parameters: Vec::new(),
             is_variadic: false,
             variadic_type: None,
             return_type: None,
             generic_parameters: None,
+            attributes: Vec::new(),
             tokens: None,
         }
     }
@@ -251,6 +264,11 @@ impl FunctionStatement {
         self.generic_parameters.as_ref()
     }
 
+    #[inline]
+    pub fn mutate_generic_parameters(&mut self) -> Option<&mut GenericParameters> {
+        self.generic_parameters.as_mut()
+    }
+
     #[inline]
     pub fn get_block(&self) -> &Block {
         &self.block
@@ -313,6 +331,42 @@ impl FunctionStatement {
         !self.parameters.is_empty()
     }
 
+    pub fn with_attribute(mut self, attribute: impl Into<FunctionAttribute>) -> Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    #[inline]
+    pub fn push_attribute(&mut self, attribute: impl Into<FunctionAttribute>) {
+        self.attributes.push(attribute.into());
+    }
+
+    #[inline]
+    pub fn set_attributes(&mut self, attributes: Vec<FunctionAttribute>) {
+        self.attributes = attributes;
+    }
+
+    #[inline]
+    pub fn iter_attributes(&self) -> impl Iterator<Item = &FunctionAttribute> {
+        self.attributes.iter()
+    }
+
+    #[inline]
+    pub fn has_attributes(&self) -> bool {
+        !self.attributes.is_empty()
+    }
+
+    #[inline]
+    pub fn mutate_attributes(&mut self) -> &mut Vec<FunctionAttribute> {
+        &mut self.attributes
+    }
+
+    /// Removes the attributes of the function and returns them.
+    #[inline]
+    pub fn take_attributes(&mut self) -> Vec<FunctionAttribute> {
+        std::mem::take(&mut self.attributes)
+    }
+
     pub fn clear_types(&mut self) {
         self.return_type.take();
         self.variadic_type.take();
@@ -327,6 +381,6 @@ impl FunctionStatement {
 
     super::impl_token_fns!(
         target = [name]
-        iter = [parameters, generic_parameters, tokens]
+        iter = [parameters, generic_parameters, attributes, tokens]
     );
 }
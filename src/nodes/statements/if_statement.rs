@@ -110,6 +110,11 @@ impl IfStatement {
         }
     }
 
+    /// Creates a builder that validates the branches of the if statement when it is built.
+    pub fn builder() -> IfStatementBuilder {
+        IfStatementBuilder::default()
+    }
+
     pub fn create(condition: impl Into<Expression>, block: impl Into<Block>) -> Self {
         Self {
             branches: vec![IfBranch::new(condition, block)],
@@ -235,3 +240,144 @@ impl IfStatement {
 
     super::impl_token_fns!(iter = [tokens, branches]);
 }
+
+/// A builder for if statements, which ensures that the built statement has a first branch with
+/// a condition.
+///
+/// ```
+/// # use darklua_core::nodes::{Block, Expression, IfStatement};
+/// let if_statement = IfStatement::builder()
+///     .with_condition(Expression::identifier("condition"))
+///     .with_block(Block::default())
+///     .with_branch(Expression::identifier("other"), Block::default())
+///     .with_else_block(Block::default())
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(if_statement.branch_count(), 2);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IfStatementBuilder {
+    main_condition: Option<Expression>,
+    main_block: Option<Block>,
+    branches: Vec<(Expression, Block)>,
+    else_block: Option<Block>,
+}
+
+impl IfStatementBuilder {
+    /// Sets the condition of the first branch (the `if` branch).
+    pub fn with_condition(mut self, condition: impl Into<Expression>) -> Self {
+        self.main_condition = Some(condition.into());
+        self
+    }
+
+    /// Sets the block of the first branch (the `if` branch). When it is not provided, the block
+    /// is empty.
+    pub fn with_block(mut self, block: impl Into<Block>) -> Self {
+        self.main_block = Some(block.into());
+        self
+    }
+
+    /// Adds an `elseif` branch.
+    pub fn with_branch(
+        mut self,
+        condition: impl Into<Expression>,
+        block: impl Into<Block>,
+    ) -> Self {
+        self.branches.push((condition.into(), block.into()));
+        self
+    }
+
+    pub fn with_else_block(mut self, block: impl Into<Block>) -> Self {
+        self.else_block = Some(block.into());
+        self
+    }
+
+    pub fn build(self) -> Result<IfStatement, &'static str> {
+        let condition = self
+            .main_condition
+            .ok_or("an if statement requires a condition for its first branch")?;
+
+        let branches = std::iter::once(IfBranch::new(
+            condition,
+            self.main_block.unwrap_or_default(),
+        ))
+        .chain(
+            self.branches
+                .into_iter()
+                .map(|(condition, block)| IfBranch::new(condition, block)),
+        )
+        .collect();
+
+        Ok(IfStatement::new(branches, self.else_block))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nodes::{LastStatement, ReturnStatement};
+
+    #[test]
+    fn build_with_condition() {
+        let if_statement = IfStatement::builder()
+            .with_condition(true)
+            .with_block(Block::default())
+            .build();
+
+        assert_eq!(
+            if_statement,
+            Ok(IfStatement::create(true, Block::default()))
+        );
+    }
+
+    #[test]
+    fn build_without_block_uses_empty_block() {
+        let if_statement = IfStatement::builder().with_condition(true).build();
+
+        assert_eq!(
+            if_statement,
+            Ok(IfStatement::create(true, Block::default()))
+        );
+    }
+
+    #[test]
+    fn build_with_branches_and_else_block() {
+        let else_block = Block::default().with_last_statement(LastStatement::new_break());
+
+        let if_statement = IfStatement::builder()
+            .with_condition(true)
+            .with_branch(false, ReturnStatement::default())
+            .with_branch(Expression::nil(), Block::default())
+            .with_else_block(else_block.clone())
+            .build();
+
+        assert_eq!(
+            if_statement,
+            Ok(IfStatement::create(true, Block::default())
+                .with_new_branch(false, ReturnStatement::default())
+                .with_new_branch(Expression::nil(), Block::default())
+                .with_else_block(else_block))
+        );
+    }
+
+    #[test]
+    fn build_without_condition_is_an_error() {
+        let result = IfStatement::builder().with_block(Block::default()).build();
+
+        assert_eq!(
+            result,
+            Err("an if statement requires a condition for its first branch")
+        );
+    }
+
+    #[test]
+    fn build_with_only_else_block_is_an_error() {
+        let result = IfStatement::builder()
+            .with_branch(true, Block::default())
+            .with_else_block(Block::default())
+            .build();
+
+        assert!(result.is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::nodes::{
-    Block, FunctionBodyTokens, FunctionReturnType, FunctionVariadicType, GenericParameters,
-    Identifier, Token, TypedIdentifier,
+    Block, FunctionAttribute, FunctionBodyTokens, FunctionReturnType, FunctionVariadicType,
+    GenericParameters, Identifier, Token, TypedIdentifier,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -36,6 +36,7 @@ pub struct LocalFunctionStatement {
     variadic_type: Option<FunctionVariadicType>,
     return_type: Option<FunctionReturnType>,
     generic_parameters: Option<GenericParameters>,
+    attributes: Vec<FunctionAttribute>,
     tokens: Option<Box<LocalFunctionTokens>>,
 }
 
@@ -54,6 +55,7 @@ impl LocalFunctionStatement {
             variadic_type: None,
             return_type: None,
             generic_parameters: None,
+            attributes: Vec::new(),
             tokens: None,
         }
     }
@@ -67,6 +69,7 @@ impl LocalFunctionStatement {
             variadic_type: None,
             return_type: None,
             generic_parameters: None,
+            attributes: Vec::new(),
             tokens: None,
         }
     }
@@ -101,6 +104,13 @@ impl LocalFunctionStatement {
         self
     }
 
+    pub fn set_variadic(&mut self, is_variadic: bool) {
+        self.is_variadic = is_variadic;
+        if !is_variadic && self.variadic_type.is_some() {
+            self.variadic_type.take();
+        }
+    }
+
     pub fn with_variadic_type(mut self, r#type: impl Into<FunctionVariadicType>) -> Self {
         self.is_variadic = true;
         self.variadic_type = Some(r#type.into());
@@ -151,6 +161,14 @@ impl LocalFunctionStatement {
         self.return_type.as_mut()
     }
 
+    /// Removes the return type of the function and returns it.
+    pub fn take_return_type(&mut self) -> Option<FunctionReturnType> {
+        if let Some(tokens) = &mut self.tokens {
+            tokens.return_type_colon.take();
+        }
+        self.return_type.take()
+    }
+
     pub fn with_generic_parameters(mut self, generic_parameters: GenericParameters) -> Self {
         self.generic_parameters = Some(generic_parameters);
         self
@@ -166,6 +184,11 @@ impl LocalFunctionStatement {
         self.generic_parameters.as_ref()
     }
 
+    #[inline]
+    pub fn mutate_generic_parameters(&mut self) -> Option<&mut GenericParameters> {
+        self.generic_parameters.as_mut()
+    }
+
     #[inline]
     pub fn mutate_parameters(&mut self) -> &mut Vec<TypedIdentifier> {
         &mut self.parameters
@@ -233,6 +256,42 @@ impl LocalFunctionStatement {
         self.parameters.len()
     }
 
+    pub fn with_attribute(mut self, attribute: impl Into<FunctionAttribute>) -> Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    #[inline]
+    pub fn push_attribute(&mut self, attribute: impl Into<FunctionAttribute>) {
+        self.attributes.push(attribute.into());
+    }
+
+    #[inline]
+    pub fn set_attributes(&mut self, attributes: Vec<FunctionAttribute>) {
+        self.attributes = attributes;
+    }
+
+    #[inline]
+    pub fn iter_attributes(&self) -> impl Iterator<Item = &FunctionAttribute> {
+        self.attributes.iter()
+    }
+
+    #[inline]
+    pub fn has_attributes(&self) -> bool {
+        !self.attributes.is_empty()
+    }
+
+    #[inline]
+    pub fn mutate_attributes(&mut self) -> &mut Vec<FunctionAttribute> {
+        &mut self.attributes
+    }
+
+    /// Removes the attributes of the function and returns them.
+    #[inline]
+    pub fn take_attributes(&mut self) -> Vec<FunctionAttribute> {
+        std::mem::take(&mut self.attributes)
+    }
+
     pub fn clear_types(&mut self) {
         self.return_type.take();
         self.variadic_type.take();
@@ -247,7 +306,7 @@ impl LocalFunctionStatement {
 
     super::impl_token_fns!(
         target = [identifier]
-        iter = [parameters, generic_parameters, tokens]
+        iter = [parameters, generic_parameters, attributes, tokens]
     );
 }
 
@@ -1,5 +1,6 @@
 use crate::nodes::{
-    GenericParameterMutRef, GenericParametersWithDefaults, Identifier, Token, Trivia, Type,
+    GenericParameterMutRef, GenericParametersWithDefaults, Identifier, Token, Trivia,
+    TriviaCollection, Type,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -248,4 +249,31 @@ impl TypeDeclarationStatement {
             }
         }
     }
+
+    pub(crate) fn mutate_trivia(&mut self, mut mutate: impl FnMut(&mut TriviaCollection)) {
+        self.name.mutate_trivia(&mut mutate);
+        if let Some(tokens) = &mut self.tokens {
+            tokens.mutate_trivia(&mut mutate);
+        }
+        if let Some(parameters) = self.generic_parameters.as_mut() {
+            parameters.mutate_trivia(&mut mutate);
+
+            for parameter in parameters {
+                match parameter {
+                    GenericParameterMutRef::TypeVariable(variable) => {
+                        variable.mutate_trivia(&mut mutate);
+                    }
+                    GenericParameterMutRef::TypeVariableWithDefault(variable_with_default) => {
+                        variable_with_default.mutate_trivia(&mut mutate);
+                    }
+                    GenericParameterMutRef::GenericTypePack(_) => {}
+                    GenericParameterMutRef::GenericTypePackWithDefault(
+                        generic_pack_with_default,
+                    ) => {
+                        generic_pack_with_default.mutate_trivia(&mut mutate);
+                    }
+                }
+            }
+        }
+    }
 }
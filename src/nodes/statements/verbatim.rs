@@ -0,0 +1,25 @@
+/// Source code that is copied to the generated code without any change, like a region
+/// delimited by raw markers (`--[[@raw]] ... --[[@endraw]]`). Rules do not process its
+/// content, which does not have to be valid Lua.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerbatimStatement {
+    content: String,
+}
+
+impl VerbatimStatement {
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+        }
+    }
+
+    #[inline]
+    pub fn get_content(&self) -> &str {
+        &self.content
+    }
+
+    #[inline]
+    pub fn into_content(self) -> String {
+        self.content
+    }
+}
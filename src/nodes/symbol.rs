@@ -0,0 +1,199 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// An immutable string shared by the nodes that contain the same name.
+///
+/// Symbols created by the parser are interned: every occurrence of a name in a parsed block
+/// points to the same allocation, which makes comparing them a pointer comparison. Symbols
+/// created from other strings (like names generated by rules) are compared by content, and
+/// hashing always uses the content so that a symbol can be found with a `&str`.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    pub fn new(value: &str) -> Self {
+        Self(Arc::from(value))
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns `true` if both symbols share the same allocation (like symbols interned by
+    /// the same parser).
+    #[inline]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl PartialEq for Symbol {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || self.0 == other.0
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    #[inline]
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    #[inline]
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(value: &String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(value: String) -> Self {
+        Self(Arc::from(value))
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(value: Symbol) -> Self {
+        value.as_str().to_owned()
+    }
+}
+
+/// Stores the symbols created while converting a parsed file, so that each distinct name is
+/// only allocated once.
+#[derive(Debug, Default)]
+pub(crate) struct StringInterner {
+    symbols: HashSet<Symbol>,
+}
+
+impl StringInterner {
+    pub(crate) fn intern(&mut self, value: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(value) {
+            return symbol.clone();
+        }
+
+        let symbol = Symbol::new(value);
+        self.symbols.insert(symbol.clone());
+        symbol
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interned_symbols_share_their_allocation() {
+        let mut interner = StringInterner::default();
+
+        let first = interner.intern("value");
+        let second = interner.intern("value");
+
+        assert!(first.ptr_eq(&second));
+        assert!(!first.ptr_eq(&interner.intern("other")));
+    }
+
+    #[test]
+    fn symbols_from_different_allocations_are_equal() {
+        let mut interner = StringInterner::default();
+
+        assert_eq!(interner.intern("value"), Symbol::from("value".to_owned()));
+        assert_ne!(interner.intern("value"), Symbol::from("other"));
+    }
+
+    #[test]
+    fn symbol_is_found_with_str() {
+        let mut set = HashSet::new();
+        set.insert(Symbol::from("value"));
+
+        assert!(set.contains("value"));
+    }
+
+    #[test]
+    fn nodes_can_be_sent_to_other_threads() {
+        fn assert_send<T: Send>() {}
+
+        assert_send::<Symbol>();
+        assert_send::<crate::nodes::Block>();
+    }
+}
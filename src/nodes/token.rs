@@ -90,6 +90,87 @@ impl Trivia {
             Position::Any { .. } => None,
         }
     }
+
+    /// Returns the byte offset where the trivia starts in the original code, when the trivia
+    /// references the original code.
+    pub fn get_start_position(&self) -> Option<usize> {
+        match &self.position {
+            Position::LineNumberReference { start, .. } => Some(*start),
+            Position::LineNumber { .. } | Position::Any { .. } => None,
+        }
+    }
+
+    /// Replaces the content of the trivia while keeping its line number.
+    pub fn replace_with_content<IntoCowStr: Into<Cow<'static, str>>>(
+        &mut self,
+        content: IntoCowStr,
+    ) {
+        self.position = match &self.position {
+            Position::LineNumber { line_number, .. }
+            | Position::LineNumberReference { line_number, .. } => Position::LineNumber {
+                line_number: *line_number,
+                content: content.into(),
+            },
+            Position::Any { .. } => Position::Any {
+                content: content.into(),
+            },
+        };
+    }
+}
+
+/// The trivia attached to a single token: the trivia written before the token (leading
+/// trivia) and the trivia written after it (trailing trivia).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TriviaCollection {
+    leading: Vec<Trivia>,
+    trailing: Vec<Trivia>,
+}
+
+impl TriviaCollection {
+    pub fn new(leading: Vec<Trivia>, trailing: Vec<Trivia>) -> Self {
+        Self { leading, trailing }
+    }
+
+    #[inline]
+    pub fn leading(&self) -> &[Trivia] {
+        &self.leading
+    }
+
+    #[inline]
+    pub fn trailing(&self) -> &[Trivia] {
+        &self.trailing
+    }
+
+    #[inline]
+    pub fn mutate_leading(&mut self) -> &mut Vec<Trivia> {
+        &mut self.leading
+    }
+
+    #[inline]
+    pub fn mutate_trailing(&mut self) -> &mut Vec<Trivia> {
+        &mut self.trailing
+    }
+
+    /// Iterates over the leading trivia and then over the trailing trivia.
+    pub fn iter(&self) -> impl Iterator<Item = &Trivia> {
+        self.leading.iter().chain(self.trailing.iter())
+    }
+
+    /// Iterates mutably over the leading trivia and then over the trailing trivia.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Trivia> {
+        self.leading.iter_mut().chain(self.trailing.iter_mut())
+    }
+
+    /// Removes the trivia (leading or trailing) for which the predicate returns `false`.
+    pub fn retain(&mut self, mut predicate: impl FnMut(&Trivia) -> bool) {
+        self.leading.retain(&mut predicate);
+        self.trailing.retain(predicate);
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.trailing.is_empty()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -159,6 +240,21 @@ impl Token {
         self.trailing_trivia.push(trivia);
     }
 
+    /// Inserts trivia before the existing leading trivia of the token.
+    pub(crate) fn prepend_leading_trivia(&mut self, trivia: impl IntoIterator<Item = Trivia>) {
+        self.leading_trivia.splice(0..0, trivia);
+    }
+
+    /// Removes the leading trivia of the token and returns it.
+    pub(crate) fn take_leading_trivia(&mut self) -> Vec<Trivia> {
+        std::mem::take(&mut self.leading_trivia)
+    }
+
+    /// Removes the trailing trivia of the token and returns it.
+    pub(crate) fn take_trailing_trivia(&mut self) -> Vec<Trivia> {
+        std::mem::take(&mut self.trailing_trivia)
+    }
+
     #[inline]
     pub fn iter_leading_trivia(&self) -> impl Iterator<Item = &Trivia> {
         self.leading_trivia.iter()
@@ -178,6 +274,14 @@ impl Token {
         }
     }
 
+    /// Returns the content of the token if it does not reference the original code.
+    pub fn try_read(&self) -> Option<&str> {
+        match &self.position {
+            Position::LineNumberReference { .. } => None,
+            Position::LineNumber { content, .. } | Position::Any { content } => Some(content),
+        }
+    }
+
     pub fn get_line_number(&self) -> Option<usize> {
         match &self.position {
             Position::LineNumber { line_number, .. }
@@ -186,6 +290,15 @@ impl Token {
         }
     }
 
+    /// Returns the byte offset where the token starts in the original code, when the token
+    /// references the original code.
+    pub fn get_start_position(&self) -> Option<usize> {
+        match &self.position {
+            Position::LineNumberReference { start, .. } => Some(*start),
+            Position::LineNumber { .. } | Position::Any { .. } => None,
+        }
+    }
+
     pub fn replace_with_content<IntoCowStr: Into<Cow<'static, str>>>(
         &mut self,
         content: IntoCowStr,
@@ -224,6 +337,24 @@ impl Token {
             .retain(|trivia| trivia.kind() != TriviaKind::Comment || filter(trivia));
     }
 
+    /// Gives the trivia of the token to the given function, which can modify it. Tokens
+    /// without trivia are skipped.
+    pub(crate) fn mutate_trivia(&mut self, mut mutate: impl FnMut(&mut TriviaCollection)) {
+        if !self.has_trivia() {
+            return;
+        }
+
+        let mut trivia = TriviaCollection::new(
+            std::mem::take(&mut self.leading_trivia),
+            std::mem::take(&mut self.trailing_trivia),
+        );
+
+        mutate(&mut trivia);
+
+        self.leading_trivia = trivia.leading;
+        self.trailing_trivia = trivia.trailing;
+    }
+
     pub(crate) fn replace_referenced_tokens(&mut self, code: &str) {
         if let Position::LineNumberReference {
             start,
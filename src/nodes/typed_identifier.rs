@@ -10,7 +10,7 @@ pub struct TypedIdentifier {
 impl TypedIdentifier {
     pub fn new(name: impl Into<String>) -> Self {
         Self {
-            name: Identifier::new(name.into()),
+            name: Identifier::new(Into::<String>::into(name)),
             r#type: None,
             token: None,
         }
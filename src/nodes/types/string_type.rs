@@ -52,6 +52,16 @@ impl StringType {
         self.value.is_multiline()
     }
 
+    #[inline]
+    pub fn is_long_string(&self) -> bool {
+        self.value.is_long_string()
+    }
+
+    #[inline]
+    pub fn long_string_level(&self) -> Option<usize> {
+        self.value.long_string_level()
+    }
+
     #[inline]
     pub fn has_single_quote(&self) -> bool {
         self.value.has_single_quote()
@@ -1,4 +1,4 @@
-use crate::nodes::{Identifier, Token, Trivia};
+use crate::nodes::{Identifier, Token, Trivia, TriviaCollection};
 
 use super::{StringType, Type};
 
@@ -222,6 +222,14 @@ impl TableEntryType {
             TableEntryType::Indexer(indexer) => indexer.filter_comments(filter),
         }
     }
+
+    pub(crate) fn mutate_trivia(&mut self, mutate: impl FnMut(&mut TriviaCollection)) {
+        match self {
+            TableEntryType::Property(property) => property.mutate_trivia(mutate),
+            TableEntryType::Literal(literal) => literal.mutate_trivia(mutate),
+            TableEntryType::Indexer(indexer) => indexer.mutate_trivia(mutate),
+        }
+    }
 }
 
 impl From<TablePropertyType> for TableEntryType {
@@ -9,7 +9,7 @@ pub enum Variable {
 
 impl Variable {
     pub fn new<S: Into<String>>(name: S) -> Self {
-        Self::Identifier(Identifier::new(name))
+        Self::Identifier(Identifier::new(Into::<String>::into(name)))
     }
 }
 
@@ -1,22 +1,161 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt;
 
 use full_moon::ast::Ast;
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
 
 use crate::{
     ast_converter::{AstConverter, ConvertError},
     nodes::*,
-    utils::Timer,
+    process::{DefaultVisitor, NodeProcessor, NodeVisitor},
+    rules::{ContextBuilder, FlawlessRule, LineOffsets, ReplaceReferencedTokens},
+    utils::{log, remove_function_attributes, replace_utf8_identifiers, SourceAttribute, Timer},
+    Resources,
 };
 
+const EXPRESSION_SNIPPET_PREFIX: &str = "return ";
+const RAW_REGION_PLACEHOLDER_PREFIX: &str = "__DARKLUA_RAW_";
+
+fn get_default_raw_begin_marker() -> String {
+    "--[[@raw]]".to_owned()
+}
+
+fn get_default_raw_end_marker() -> String {
+    "--[[@endraw]]".to_owned()
+}
+
+/// The comments that delimit the regions of code that are not parsed. The content of these
+/// regions (including the markers) is copied as is to the generated code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-config", serde(deny_unknown_fields))]
+pub struct RawMarkers {
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default = "get_default_raw_begin_marker")
+    )]
+    begin: String,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default = "get_default_raw_end_marker")
+    )]
+    end: String,
+}
+
+impl RawMarkers {
+    pub fn new(begin: impl Into<String>, end: impl Into<String>) -> Self {
+        Self {
+            begin: begin.into(),
+            end: end.into(),
+        }
+    }
+
+    #[inline]
+    pub fn get_begin(&self) -> &str {
+        &self.begin
+    }
+
+    #[inline]
+    pub fn get_end(&self) -> &str {
+        &self.end
+    }
+}
+
+impl Default for RawMarkers {
+    fn default() -> Self {
+        Self::new(get_default_raw_begin_marker(), get_default_raw_end_marker())
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Parser {
     hold_token_data: bool,
+    raw_markers: Option<RawMarkers>,
+    utf8_identifiers: bool,
 }
 
 impl Parser {
     pub fn parse(&self, code: &str) -> Result<Block, ParserError> {
+        if let Some(markers) = self.raw_markers.as_ref() {
+            let regions = find_raw_regions(code, markers)?;
+
+            if !regions.is_empty() {
+                return self.parse_with_raw_regions(code, regions);
+            }
+        }
+
+        self.parse_code(code)
+    }
+
+    fn parse_with_raw_regions(
+        &self,
+        code: &str,
+        regions: Vec<RawRegion>,
+    ) -> Result<Block, ParserError> {
+        log::trace!("replace {} raw region(s) before parsing", regions.len());
+
+        let mut replaced_code = String::with_capacity(code.len());
+        let mut last_end = 0;
+
+        for (index, region) in regions.iter().enumerate() {
+            replaced_code.push_str(&code[last_end..region.start]);
+            // the placeholder is written on the last line of the region, so that the
+            // following code keeps its line numbers
+            let content = &code[region.start..region.end];
+            replaced_code.extend(content.matches('\n'));
+            replaced_code.push_str(&format!("{}{}();", RAW_REGION_PLACEHOLDER_PREFIX, index));
+            last_end = region.end;
+        }
+        replaced_code.push_str(&code[last_end..]);
+
+        let mut block = self.parse_code(&replaced_code)?;
+
+        let mut processor = RawRegionProcessor::new(code, &regions);
+        DefaultVisitor::visit_block(&mut block, &mut processor);
+
+        if let Some(region) = processor.misplaced_region {
+            return Err(ParserError::raw_region(
+                "raw region must start at a statement boundary",
+                region,
+            ));
+        }
+
+        self.replace_snippet_tokens(&mut block, &replaced_code);
+
+        Ok(block)
+    }
+
+    fn parse_code(&self, code: &str) -> Result<Block, ParserError> {
+        let (code, function_attributes) = match remove_function_attributes(code) {
+            Some(removed) => {
+                log::trace!(
+                    "remove the attributes of {} function(s) before parsing",
+                    removed.attributes.len()
+                );
+                (Cow::Owned(removed.code), removed.attributes)
+            }
+            None => (Cow::Borrowed(code), HashMap::new()),
+        };
+
+        let (code, identifier_names) = match self
+            .utf8_identifiers
+            .then(|| replace_utf8_identifiers(&code))
+            .flatten()
+        {
+            Some(replaced) => {
+                log::trace!(
+                    "replace {} UTF-8 identifier(s) before parsing",
+                    replaced.names.len()
+                );
+                (Cow::Owned(replaced.code), replaced.names)
+            }
+            None => (code, HashMap::new()),
+        };
+
         let full_moon_parse_timer = Timer::now();
-        let parse_result = full_moon::parse(code);
+        let parse_result = full_moon::parse(&code);
         log::trace!(
             "full-moon parsing done in {}",
             full_moon_parse_timer.duration_label()
@@ -24,7 +163,9 @@ impl Parser {
         parse_result.map_err(ParserError::parsing).and_then(|ast| {
             log::trace!("start converting full-moon AST");
             let conversion_timer = Timer::now();
-            let block = self.convert_ast(ast).map_err(ParserError::converting);
+            let block = self
+                .convert_ast(ast, function_attributes, identifier_names)
+                .map_err(ParserError::converting);
             log::trace!(
                 " ⨽ completed AST conversion in {}",
                 conversion_timer.duration_label()
@@ -33,18 +174,271 @@ impl Parser {
         })
     }
 
+    /// Parses a snippet of code that contains a single expression (like `1 + 2 * x`). The
+    /// positions of the errors are relative to the snippet.
+    pub fn parse_expression(&self, code: &str) -> Result<Expression, ParserError> {
+        let wrapped_code = format!("{}{}", EXPRESSION_SNIPPET_PREFIX, code);
+
+        let mut block = self
+            .parse(&wrapped_code)
+            .map_err(|err| err.with_column_offset(EXPRESSION_SNIPPET_PREFIX.len()))?;
+
+        if block.statements_len() != 0 {
+            return Err(ParserError::snippet("expected a single expression"));
+        }
+
+        self.replace_snippet_tokens(&mut block, &wrapped_code);
+
+        match block.take_last_statement() {
+            Some(LastStatement::Return(statement)) if statement.len() == 1 => Ok(statement
+                .into_iter_expressions()
+                .next()
+                .expect("return statement should have one expression")),
+            _ => Err(ParserError::snippet("expected a single expression")),
+        }
+    }
+
+    /// Parses a snippet of code that contains a single statement (like `local a = 1`).
+    pub fn parse_statement(&self, code: &str) -> Result<Statement, ParserError> {
+        let mut block = self.parse(code)?;
+
+        if block.statements_len() != 1 || block.get_last_statement().is_some() {
+            return Err(ParserError::snippet("expected a single statement"));
+        }
+
+        self.replace_snippet_tokens(&mut block, code);
+
+        Ok(block
+            .take_statements()
+            .pop()
+            .expect("block should have one statement"))
+    }
+
+    /// Parses a snippet of code that contains a single last statement (like `return a, b`).
+    pub fn parse_last_statement(&self, code: &str) -> Result<LastStatement, ParserError> {
+        let mut block = self.parse(code)?;
+
+        if block.statements_len() != 0 {
+            return Err(ParserError::snippet("expected a single last statement"));
+        }
+
+        self.replace_snippet_tokens(&mut block, code);
+
+        block
+            .take_last_statement()
+            .ok_or_else(|| ParserError::snippet("expected a single last statement"))
+    }
+
+    fn replace_snippet_tokens(&self, block: &mut Block, code: &str) {
+        if self.hold_token_data {
+            // tokens reference the parsed code, which is not the same as the snippet
+            let resources = Resources::from_memory();
+            let context = ContextBuilder::new(".", &resources, code).build();
+            ReplaceReferencedTokens::default().flawless_process(block, &context);
+        }
+    }
+
     pub fn preserve_tokens(mut self) -> Self {
         self.hold_token_data = true;
         self
     }
 
+    /// Copies the regions of code delimited by the given markers to the generated code,
+    /// without parsing them. Each region becomes a verbatim statement.
+    pub fn with_raw_markers(mut self, markers: RawMarkers) -> Self {
+        self.raw_markers = Some(markers);
+        self
+    }
+
+    /// Accepts identifiers that contain UTF-8 characters (like `café`), which some targets
+    /// (like Luau) allow.
+    pub fn allow_utf8_identifiers(mut self) -> Self {
+        self.utf8_identifiers = true;
+        self
+    }
+
     pub(crate) fn is_preserving_tokens(&self) -> bool {
         self.hold_token_data
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
-    fn convert_ast(&self, ast: Ast) -> Result<Block, ConvertError> {
-        AstConverter::new(self.hold_token_data).convert(&ast)
+    fn convert_ast(
+        &self,
+        ast: Ast,
+        function_attributes: HashMap<usize, Vec<SourceAttribute>>,
+        identifier_names: HashMap<String, String>,
+    ) -> Result<Block, ConvertError> {
+        AstConverter::new(self.hold_token_data)
+            .with_function_attributes(function_attributes)
+            .with_identifier_names(identifier_names)
+            .convert(&ast)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RawRegion {
+    start: usize,
+    end: usize,
+    line: usize,
+    character: usize,
+}
+
+impl RawRegion {
+    fn new(code: &str, start: usize, end: usize) -> Self {
+        let (line, character) = position_of(code, start);
+        Self {
+            start,
+            end,
+            line,
+            character,
+        }
+    }
+}
+
+/// Returns the line and the character (both starting at 1) of a byte index.
+fn position_of(code: &str, index: usize) -> (usize, usize) {
+    let before = &code[..index];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn find_raw_regions(code: &str, markers: &RawMarkers) -> Result<Vec<RawRegion>, ParserError> {
+    if markers.get_begin().is_empty() || markers.get_end().is_empty() {
+        return Err(ParserError::snippet("raw region markers cannot be empty"));
+    }
+
+    let mut regions = Vec::new();
+    let mut region_start = None;
+    let mut position = 0;
+
+    loop {
+        let next_begin = code[position..]
+            .find(markers.get_begin())
+            .map(|index| index + position);
+        let next_end = code[position..]
+            .find(markers.get_end())
+            .map(|index| index + position);
+
+        // when both markers match at the same position, the longest marker is used
+        let is_begin = match (next_begin, next_end) {
+            (Some(begin), Some(end)) => {
+                begin < end || (begin == end && markers.get_begin().len() > markers.get_end().len())
+            }
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
+        };
+
+        if is_begin {
+            let begin = next_begin.expect("begin marker should be found");
+
+            if region_start.is_some() {
+                return Err(ParserError::raw_region(
+                    "raw regions cannot be nested",
+                    RawRegion::new(code, begin, begin + markers.get_begin().len()),
+                ));
+            }
+
+            region_start = Some(begin);
+            position = begin + markers.get_begin().len();
+        } else {
+            let end = next_end.expect("end marker should be found");
+            let end_position = end + markers.get_end().len();
+
+            match region_start.take() {
+                Some(start) => regions.push(RawRegion::new(code, start, end_position)),
+                None => {
+                    return Err(ParserError::raw_region(
+                        "raw region end marker found without a begin marker",
+                        RawRegion::new(code, end, end_position),
+                    ));
+                }
+            }
+
+            position = end_position;
+        }
+    }
+
+    if let Some(start) = region_start {
+        return Err(ParserError::raw_region(
+            "raw region is never closed",
+            RawRegion::new(code, start, code.len()),
+        ));
+    }
+
+    Ok(regions)
+}
+
+/// Replaces the placeholder statements of the raw regions with verbatim statements, and
+/// finds the placeholders that are not statements.
+struct RawRegionProcessor<'a> {
+    code: &'a str,
+    regions: &'a [RawRegion],
+    misplaced_region: Option<RawRegion>,
+}
+
+impl<'a> RawRegionProcessor<'a> {
+    fn new(code: &'a str, regions: &'a [RawRegion]) -> Self {
+        Self {
+            code,
+            regions,
+            misplaced_region: None,
+        }
+    }
+
+    fn get_region(&self, identifier: &Identifier) -> Option<RawRegion> {
+        identifier
+            .get_name()
+            .strip_prefix(RAW_REGION_PLACEHOLDER_PREFIX)
+            .and_then(|index| index.parse::<usize>().ok())
+            .and_then(|index| self.regions.get(index))
+            .copied()
+    }
+
+    fn get_statement_region(&self, statement: &Statement) -> Option<RawRegion> {
+        match statement {
+            Statement::Call(call)
+                if call.get_method().is_none() && call.get_arguments().is_empty() =>
+            {
+                match call.get_prefix() {
+                    Prefix::Identifier(identifier) => self.get_region(identifier),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl NodeProcessor for RawRegionProcessor<'_> {
+    fn process_block(&mut self, block: &mut Block) {
+        let mut replaced = Vec::new();
+
+        for (index, statement) in block.iter_mut_statements().enumerate() {
+            if let Some(region) = self.get_statement_region(statement) {
+                *statement = VerbatimStatement::new(&self.code[region.start..region.end]).into();
+                replaced.push(index);
+            }
+        }
+
+        if let Some(tokens) = block.mutate_tokens() {
+            for index in replaced {
+                if let Some(semicolon) = tokens.semicolons.get_mut(index) {
+                    *semicolon = None;
+                }
+            }
+        }
+    }
+
+    fn process_prefix_expression(&mut self, prefix: &mut Prefix) {
+        if self.misplaced_region.is_some() {
+            return;
+        }
+
+        if let Prefix::Identifier(identifier) = prefix {
+            self.misplaced_region = self.get_region(identifier);
+        }
     }
 }
 
@@ -52,23 +446,116 @@ impl Parser {
 enum ParserErrorKind {
     Parsing(Vec<full_moon::Error>),
     Converting(ConvertError),
+    Snippet(&'static str),
+    RawRegion {
+        message: &'static str,
+        line: usize,
+        character: usize,
+    },
 }
 
 #[derive(Clone, Debug)]
 pub struct ParserError {
     kind: Box<ParserErrorKind>,
+    line_offsets: LineOffsets,
+    column_offset: usize,
 }
 
 impl ParserError {
     fn parsing(err: Vec<full_moon::Error>) -> Self {
         Self {
             kind: ParserErrorKind::Parsing(err).into(),
+            line_offsets: LineOffsets::default(),
+            column_offset: 0,
         }
     }
 
     fn converting(err: ConvertError) -> Self {
         Self {
             kind: ParserErrorKind::Converting(err).into(),
+            line_offsets: LineOffsets::default(),
+            column_offset: 0,
+        }
+    }
+
+    fn snippet(message: &'static str) -> Self {
+        Self {
+            kind: ParserErrorKind::Snippet(message).into(),
+            line_offsets: LineOffsets::default(),
+            column_offset: 0,
+        }
+    }
+
+    fn raw_region(message: &'static str, region: RawRegion) -> Self {
+        Self {
+            kind: ParserErrorKind::RawRegion {
+                message,
+                line: region.line,
+                character: region.character,
+            }
+            .into(),
+            line_offsets: LineOffsets::default(),
+            column_offset: 0,
+        }
+    }
+
+    /// Shifts the characters of the error positions on the first line, when the parsed code
+    /// was prefixed.
+    fn with_column_offset(mut self, column_offset: usize) -> Self {
+        self.column_offset = column_offset;
+        self
+    }
+
+    fn original_character(&self, line: usize, character: usize) -> usize {
+        if line == 1 {
+            character.saturating_sub(self.column_offset).max(1)
+        } else {
+            character
+        }
+    }
+
+    /// Maps the lines of the error positions to the original code, when the parsed code
+    /// was preprocessed.
+    pub(crate) fn with_line_offsets(mut self, line_offsets: LineOffsets) -> Self {
+        self.line_offsets = line_offsets;
+        self
+    }
+
+    fn write_error_with_offsets(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        error: &full_moon::Error,
+    ) -> fmt::Result {
+        let (start, end) = error.range();
+        let start_line = self.line_offsets.original_line(start.line());
+        let end_line = self.line_offsets.original_line(end.line());
+        let start_character = self.original_character(start.line(), start.character());
+        let end_character = self.original_character(end.line(), end.character());
+
+        match error {
+            full_moon::Error::AstError(error) => write!(
+                f,
+                concat!(
+                    "error occurred while creating ast: unexpected token `{}`. ",
+                    "(starting from line {}, character {} and ending on line {}, character {})",
+                    "\nadditional information: {}"
+                ),
+                error.token(),
+                start_line,
+                start_character,
+                end_line,
+                end_character,
+                error.error_message(),
+            ),
+            full_moon::Error::TokenizerError(error) => write!(
+                f,
+                "error occurred while tokenizing: {} ({}:{} to {}:{})",
+                error.error(),
+                start_line,
+                start_character,
+                end_line,
+                end_character,
+            ),
         }
     }
 }
@@ -78,11 +565,28 @@ impl fmt::Display for ParserError {
         match &*self.kind {
             ParserErrorKind::Parsing(errors) => {
                 for err in errors {
-                    writeln!(f, "{}", err)?;
+                    if self.line_offsets.is_empty() && self.column_offset == 0 {
+                        writeln!(f, "{}", err)?;
+                    } else {
+                        self.write_error_with_offsets(f, err)?;
+                        writeln!(f)?;
+                    }
                 }
                 Ok(())
             }
             ParserErrorKind::Converting(err) => write!(f, "{}", err),
+            ParserErrorKind::Snippet(message) => write!(f, "{}", message),
+            ParserErrorKind::RawRegion {
+                message,
+                line,
+                character,
+            } => write!(
+                f,
+                "{} (line {}, character {})",
+                message,
+                self.line_offsets.original_line(*line),
+                character
+            ),
         }
     }
 }
@@ -510,6 +1014,95 @@ mod test {
         ),
     );
 
+    mod parse_snippets {
+        use super::*;
+
+        #[test]
+        fn parse_expression() {
+            pretty_assertions::assert_eq!(
+                Parser::default().parse_expression("a + 1").unwrap(),
+                BinaryExpression::new(
+                    BinaryOperator::Plus,
+                    Expression::identifier("a"),
+                    Expression::from(1.0),
+                )
+                .into()
+            );
+        }
+
+        #[test]
+        fn parse_expression_with_tokens_can_be_generated() {
+            let expression = Parser::default()
+                .preserve_tokens()
+                .parse_expression("a  + 1 -- one")
+                .unwrap();
+
+            pretty_assertions::assert_eq!(
+                crate::generate_expression(&expression, &crate::GeneratorParameters::RetainLines),
+                "a  + 1 -- one"
+            );
+        }
+
+        #[test]
+        fn parse_expression_with_multiple_expressions_errors() {
+            pretty_assertions::assert_eq!(
+                Parser::default()
+                    .parse_expression("a, b")
+                    .unwrap_err()
+                    .to_string(),
+                "expected a single expression"
+            );
+        }
+
+        #[test]
+        fn parse_expression_error_position_is_relative_to_snippet() {
+            let error = Parser::default()
+                .parse_expression("value + )")
+                .unwrap_err()
+                .to_string();
+
+            assert!(
+                error.contains(
+                    "unexpected token `+`. (starting from line 1, character 7 and ending on line 1, character 8)"
+                ),
+                "unexpected error: {}",
+                error
+            );
+        }
+
+        #[test]
+        fn parse_statement() {
+            pretty_assertions::assert_eq!(
+                Parser::default().parse_statement("local a = 1").unwrap(),
+                LocalAssignStatement::from_variable("a")
+                    .with_value(1.0)
+                    .into()
+            );
+        }
+
+        #[test]
+        fn parse_statement_with_two_statements_errors() {
+            pretty_assertions::assert_eq!(
+                Parser::default()
+                    .parse_statement("local a = 1 local b = 2")
+                    .unwrap_err()
+                    .to_string(),
+                "expected a single statement"
+            );
+        }
+
+        #[test]
+        fn parse_statement_with_last_statement_errors() {
+            pretty_assertions::assert_eq!(
+                Parser::default()
+                    .parse_statement("return 1")
+                    .unwrap_err()
+                    .to_string(),
+                "expected a single statement"
+            );
+        }
+    }
+
     mod parse_with_tokens {
         use super::*;
 
@@ -3426,4 +4019,178 @@ mod test {
             }),
         );
     }
+
+    mod raw_regions {
+        use super::*;
+        use crate::generator::{LuaGenerator, TokenBasedLuaGenerator};
+
+        fn parser() -> Parser {
+            Parser::default().with_raw_markers(RawMarkers::default())
+        }
+
+        #[test]
+        fn region_becomes_verbatim_statement() {
+            let block = parser()
+                .parse("local a = true\n--[[@raw]]\n#pragma a\n--[[@endraw]]\nreturn a")
+                .unwrap();
+
+            pretty_assertions::assert_eq!(
+                block,
+                Block::default()
+                    .with_statement(LocalAssignStatement::from_variable("a").with_value(true))
+                    .with_statement(VerbatimStatement::new(
+                        "--[[@raw]]\n#pragma a\n--[[@endraw]]"
+                    ))
+                    .with_last_statement(ReturnStatement::one(Expression::identifier("a")))
+            );
+        }
+
+        #[test]
+        fn region_inside_a_nested_block() {
+            let block = parser().parse("do --[[@raw]] $ --[[@endraw]] end").unwrap();
+
+            pretty_assertions::assert_eq!(
+                block,
+                Block::from(DoStatement::new(Block::from(VerbatimStatement::new(
+                    "--[[@raw]] $ --[[@endraw]]"
+                ))))
+            );
+        }
+
+        #[test]
+        fn markers_are_comments_without_configuration() {
+            let block = Parser::default().parse("--[[@raw]] --[[@endraw]]").unwrap();
+
+            pretty_assertions::assert_eq!(block, Block::default());
+        }
+
+        #[test]
+        fn custom_markers() {
+            let block = Parser::default()
+                .with_raw_markers(RawMarkers::new("--@begin", "--@end"))
+                .parse("--@begin\n%%\n--@end\n")
+                .unwrap();
+
+            pretty_assertions::assert_eq!(
+                block,
+                Block::from(VerbatimStatement::new("--@begin\n%%\n--@end"))
+            );
+        }
+
+        #[test]
+        fn token_based_generator_keeps_code_intact() {
+            let code = "local a = 1\n--[[@raw]]\n#pragma a\n--[[@endraw]]\nreturn a\n";
+            let block = parser().preserve_tokens().parse(code).unwrap();
+
+            let mut generator = TokenBasedLuaGenerator::new(code);
+            generator.write_block(&block);
+
+            pretty_assertions::assert_eq!(generator.into_string(), code);
+        }
+
+        #[test]
+        fn unterminated_region_errors() {
+            let error = parser()
+                .parse("local a = 1\n  --[[@raw]]\nreturn a")
+                .unwrap_err();
+
+            pretty_assertions::assert_eq!(
+                error.to_string(),
+                "raw region is never closed (line 2, character 3)"
+            );
+        }
+
+        #[test]
+        fn end_marker_without_begin_marker_errors() {
+            let error = parser().parse("return --[[@endraw]]").unwrap_err();
+
+            pretty_assertions::assert_eq!(
+                error.to_string(),
+                "raw region end marker found without a begin marker (line 1, character 8)"
+            );
+        }
+
+        #[test]
+        fn nested_regions_error() {
+            let error = parser()
+                .parse("--[[@raw]]\n--[[@raw]]\n--[[@endraw]]")
+                .unwrap_err();
+
+            pretty_assertions::assert_eq!(
+                error.to_string(),
+                "raw regions cannot be nested (line 2, character 1)"
+            );
+        }
+
+        #[test]
+        fn region_in_expression_errors() {
+            let error = parser()
+                .parse("local a = 1\nlocal b = --[[@raw]] 2 --[[@endraw]]\n")
+                .unwrap_err();
+
+            pretty_assertions::assert_eq!(
+                error.to_string(),
+                "raw region must start at a statement boundary (line 2, character 11)"
+            );
+        }
+    }
+
+    mod utf8_identifiers {
+        use super::*;
+        use crate::generator::{LuaGenerator, TokenBasedLuaGenerator};
+
+        fn parser() -> Parser {
+            Parser::default().allow_utf8_identifiers()
+        }
+
+        #[test]
+        fn utf8_identifiers_are_rejected_by_default() {
+            assert!(Parser::default().parse("local café = 1").is_err());
+        }
+
+        #[test]
+        fn parse_utf8_identifiers() {
+            let block = parser()
+                .parse("local café = { größe = 1 } return café.größe")
+                .unwrap();
+
+            pretty_assertions::assert_eq!(
+                block,
+                Block::default()
+                    .with_statement(LocalAssignStatement::from_variable("café").with_value(
+                        TableExpression::default().append_field("größe", Expression::from(1.0))
+                    ))
+                    .with_last_statement(ReturnStatement::one(FieldExpression::new(
+                        Prefix::from_name("café"),
+                        "größe"
+                    )))
+            );
+        }
+
+        #[test]
+        fn utf8_in_strings_is_not_modified() {
+            let block = parser().parse("local é = 'é' return é").unwrap();
+
+            pretty_assertions::assert_eq!(
+                block,
+                Block::default()
+                    .with_statement(
+                        LocalAssignStatement::from_variable("é")
+                            .with_value(StringExpression::from_value("é"))
+                    )
+                    .with_last_statement(ReturnStatement::one(Expression::identifier("é")))
+            );
+        }
+
+        #[test]
+        fn generate_utf8_identifiers_with_tokens() {
+            let code = "local café = 1 -- café\nreturn café";
+            let block = parser().preserve_tokens().parse(code).unwrap();
+
+            let mut generator = TokenBasedLuaGenerator::new(code);
+            generator.write_block(&block);
+
+            pretty_assertions::assert_eq!(generator.into_string(), code);
+        }
+    }
 }
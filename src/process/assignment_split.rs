@@ -0,0 +1,272 @@
+use crate::nodes::{AssignStatement, Expression, LocalAssignStatement, Variable};
+use crate::process::processors::FindVariables;
+use crate::process::{DefaultVisitor, Evaluator, NodeVisitor};
+
+/// What a variable of an assignment writes to, used to find variables that may be the same.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Identifier(String),
+    Field(String),
+    Index,
+}
+
+impl Target {
+    fn new(variable: &Variable) -> Self {
+        match variable {
+            Variable::Identifier(identifier) => Self::Identifier(identifier.get_name().to_owned()),
+            Variable::Field(field) => Self::Field(field.get_field().get_name().to_owned()),
+            Variable::Index(_) => Self::Index,
+        }
+    }
+
+    /// Two fields or indexes may write to the same table entry, since their prefixes can
+    /// reference the same table.
+    fn may_alias(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Identifier(name), Self::Identifier(other_name))
+            | (Self::Field(name), Self::Field(other_name)) => name == other_name,
+            (Self::Field(_), Self::Index)
+            | (Self::Index, Self::Field(_))
+            | (Self::Index, Self::Index) => true,
+            _ => false,
+        }
+    }
+}
+
+fn reads_variables(expression: &mut Expression, names: &[&str]) -> bool {
+    if names.is_empty() {
+        return false;
+    }
+    let mut find_variables: FindVariables = names.iter().copied().collect();
+    DefaultVisitor::visit_expression(expression, &mut find_variables);
+    find_variables.has_found_usage()
+}
+
+/// Returns true when the prefix or the index of a field or index variable reads one of the
+/// given names.
+fn target_reads_variables(variable: &mut Variable, names: &[&str]) -> bool {
+    if names.is_empty() || matches!(variable, Variable::Identifier(_)) {
+        return false;
+    }
+    let mut find_variables: FindVariables = names.iter().copied().collect();
+    DefaultVisitor::visit_variable(variable, &mut find_variables);
+    find_variables.has_found_usage()
+}
+
+fn target_has_side_effects(variable: &Variable, evaluator: &Evaluator) -> bool {
+    match variable {
+        Variable::Identifier(_) => false,
+        Variable::Field(field) => {
+            evaluator.has_side_effects(&Expression::from(field.get_prefix().clone()))
+        }
+        Variable::Index(index) => {
+            evaluator.has_side_effects(&Expression::from(index.get_prefix().clone()))
+                || evaluator.has_side_effects(index.get_index())
+        }
+    }
+}
+
+/// Returns true when each variable receives exactly the value at its position, so that
+/// the values can be distributed to separate statements.
+fn has_one_value_per_variable(
+    variables: usize,
+    values: usize,
+    last_value: Option<&Expression>,
+    evaluator: &Evaluator,
+) -> bool {
+    values == variables
+        || values == 0
+        || (values < variables
+            && !last_value.is_some_and(|value| evaluator.can_return_multiple_values(value)))
+}
+
+/// Returns true when an assignment like `a, b = x, y` can be split into one assignment per
+/// variable, in order (`a = x` then `b = y`), without changing the behavior of the code.
+///
+/// Lua evaluates all the values (and the prefixes of the variables) before assigning any
+/// variable, and assigns them in an unspecified order. Splitting is unsafe when:
+/// - a value or the prefix of a variable reads a variable assigned before it (like the
+///   swap `a, b = b, a`, or `i, t[i] = i + 1, v`)
+/// - two variables may be the same (like `t[i], t[j] = 1, 2` or `a.x, b.x = 1, 2`)
+/// - a value or a prefix after the first variable has side effects, since it would now
+///   run after the first assignments
+/// - a value provides several values (like `a, b = f()`) or extra values are discarded
+pub fn assignment_split_is_safe(assign: &mut AssignStatement) -> bool {
+    let evaluator = Evaluator::default();
+
+    if assign.values_len() > assign.variables_len()
+        || !has_one_value_per_variable(
+            assign.variables_len(),
+            assign.values_len(),
+            assign.last_value(),
+            &evaluator,
+        )
+    {
+        return false;
+    }
+
+    let targets: Vec<_> = assign.iter_variables().map(Target::new).collect();
+
+    for (index, target) in targets.iter().enumerate() {
+        if targets[..index].iter().any(|other| other.may_alias(target)) {
+            return false;
+        }
+    }
+
+    let names: Vec<&str> = targets
+        .iter()
+        .map(|target| match target {
+            Target::Identifier(name) => name.as_str(),
+            _ => "",
+        })
+        .collect();
+    let assigned_before = |index: usize| -> Vec<&str> {
+        names[..index]
+            .iter()
+            .copied()
+            .filter(|name| !name.is_empty())
+            .collect()
+    };
+
+    if assign
+        .iter_variables()
+        .skip(1)
+        .any(|variable| target_has_side_effects(variable, &evaluator))
+        || assign
+            .iter_values()
+            .skip(1)
+            .any(|value| evaluator.has_side_effects(value))
+    {
+        return false;
+    }
+
+    for (index, variable) in assign.iter_mut_variables().enumerate() {
+        if target_reads_variables(variable, &assigned_before(index)) {
+            return false;
+        }
+    }
+
+    for (index, value) in assign.iter_mut_values().enumerate() {
+        if reads_variables(value, &assigned_before(index)) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Returns true when a local assignment like `local a, b = x, y` can be split into one
+/// local assignment per variable, in order (`local a = x` then `local b = y`), without
+/// changing the behavior of the code. The values of a local assignment cannot see the
+/// new locals, so splitting is unsafe when a value reads a variable declared before it
+/// (like `local a, b = b, a`) or when a value provides several values.
+pub fn local_assignment_split_is_safe(assign: &mut LocalAssignStatement) -> bool {
+    let evaluator = Evaluator::default();
+
+    if assign.values_len() > assign.variables_len()
+        || !has_one_value_per_variable(
+            assign.variables_len(),
+            assign.values_len(),
+            assign.last_value(),
+            &evaluator,
+        )
+    {
+        return false;
+    }
+
+    let names: Vec<String> = assign
+        .iter_variables()
+        .map(|variable| variable.get_name().to_owned())
+        .collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+    assign
+        .iter_mut_values()
+        .enumerate()
+        .all(|(index, value)| !reads_variables(value, &names[..index]))
+}
+
+/// Returns true when two consecutive local assignments can be merged into a single local
+/// assignment: this is the inverse of splitting the merged statement between them, which
+/// is unsafe when the values of the next statement read the variables of the first one
+/// (like `local a = b` followed by `local b = a`).
+pub fn local_assignments_merge_is_safe(
+    first: &LocalAssignStatement,
+    next: &mut LocalAssignStatement,
+) -> bool {
+    let names: Vec<&str> = first
+        .iter_variables()
+        .map(|variable| variable.get_name())
+        .collect();
+
+    next.iter_mut_values()
+        .all(|value| !reads_variables(value, &names))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nodes::Statement;
+    use crate::Parser;
+
+    fn parse_statement(code: &str) -> Statement {
+        Parser::default().parse_statement(code).unwrap()
+    }
+
+    fn split_is_safe(code: &str) -> bool {
+        match parse_statement(code) {
+            Statement::Assign(mut assign) => assignment_split_is_safe(&mut assign),
+            Statement::LocalAssign(mut assign) => local_assignment_split_is_safe(&mut assign),
+            _ => panic!("expected an assignment"),
+        }
+    }
+
+    macro_rules! test_split {
+        ($($name:ident ($code:literal) => $expected:literal),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert_eq!(split_is_safe($code), $expected, "{}", $code);
+                }
+            )*
+        };
+    }
+
+    test_split!(
+        independent_assignments("a, b = 1, 2") => true,
+        independent_locals("local a, b = 1, 2") => true,
+        assign_reading_later_variable("a, b = b, 2") => true,
+        missing_values_are_nil("a, b = 1") => true,
+        swap("a, b = b, a") => false,
+        local_swap("local a, b = b, a") => false,
+        value_reading_previous_variable_in_function("a, b = 1, function() return a end") => false,
+        index_reading_previous_variable("i, t[i] = i + 1, 0") => false,
+        prefix_reading_previous_variable("t, t.x = {}, 1") => false,
+        index_reading_next_variable("t[i], i = 0, j") => true,
+        aliased_indexes("t[i], t[j] = 1, 2") => false,
+        aliased_fields("a.x, b.x = 1, 2") => false,
+        different_fields("a.x, a.y = 1, 2") => true,
+        duplicated_variable("a, a = 1, 2") => false,
+        call_after_first_assignment("a, b = 1, f()") => false,
+        call_in_first_value("a, b = f(), 1") => true,
+        call_in_prefix_after_first_assignment("a, f().x = 1, 2") => false,
+        multiple_values("a, b = f()") => false,
+        local_multiple_values("local a, b = ...") => false,
+        extra_values("a = 1, 2") => false,
+        local_calls_do_not_see_new_locals("local a, b = f(), g()") => true,
+    );
+
+    #[test]
+    fn merge_locals_reading_previous_variables_is_unsafe() {
+        let first = match parse_statement("local a = b") {
+            Statement::LocalAssign(assign) => assign,
+            _ => unreachable!(),
+        };
+        let mut next = match parse_statement("local b = a") {
+            Statement::LocalAssign(assign) => assign,
+            _ => unreachable!(),
+        };
+
+        assert!(!local_assignments_merge_is_safe(&first, &mut next));
+    }
+}
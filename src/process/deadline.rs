@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// The number of nodes entered by the visitors between two checks of the clock.
+const POLL_INTERVAL: u32 = 64;
+
+thread_local! {
+    static CURRENT_DEADLINE: RefCell<Option<DeadlineState>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DeadlineState {
+    deadline: Instant,
+    until_poll: u32,
+    expired: bool,
+}
+
+/// Cancels the node visitors of the current thread once the given instant has passed. The
+/// deadline applies until the tracker is dropped.
+///
+/// Cancellation is cooperative: the visitors check the deadline while entering nodes and stop
+/// visiting the remaining nodes once it has passed, which leaves the visited block partially
+/// processed. Rules that iterate over nodes themselves can check
+/// [`Context::is_cancelled`](crate::rules::Context::is_cancelled).
+#[derive(Debug)]
+pub struct DeadlineTracker {
+    previous: Option<DeadlineState>,
+}
+
+impl DeadlineTracker {
+    pub fn start(deadline: Instant) -> Self {
+        let previous = CURRENT_DEADLINE.with(|state| {
+            state.borrow_mut().replace(DeadlineState {
+                deadline,
+                until_poll: POLL_INTERVAL,
+                expired: false,
+            })
+        });
+        Self { previous }
+    }
+
+    /// Returns `true` if the visitors were cancelled because the deadline has passed.
+    pub fn is_expired(&self) -> bool {
+        is_expired()
+    }
+}
+
+impl Drop for DeadlineTracker {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT_DEADLINE.with(|state| *state.borrow_mut() = previous);
+    }
+}
+
+/// Counts a visited node, and checks the clock once every few nodes.
+#[inline]
+pub(crate) fn poll() {
+    CURRENT_DEADLINE.with(|state| {
+        if let Some(state) = state.borrow_mut().as_mut() {
+            if state.expired {
+                return;
+            }
+            state.until_poll -= 1;
+            if state.until_poll == 0 {
+                state.until_poll = POLL_INTERVAL;
+                state.expired = Instant::now() >= state.deadline;
+            }
+        }
+    });
+}
+
+/// Returns `true` if the deadline of the current thread has passed, in which case the
+/// visitors stop visiting nodes.
+#[inline]
+pub(crate) fn is_expired() -> bool {
+    CURRENT_DEADLINE.with(|state| state.borrow().as_ref().is_some_and(|state| state.expired))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+    use crate::Parser;
+
+    #[derive(Default)]
+    struct CountStatements {
+        count: usize,
+    }
+
+    impl NodeProcessor for CountStatements {
+        fn process_statement(&mut self, _: &mut crate::nodes::Statement) {
+            self.count += 1;
+        }
+    }
+
+    fn count_visited_statements(statements: usize) -> usize {
+        let mut block = Parser::default()
+            .parse(&"do local a = 1 end ".repeat(statements))
+            .unwrap();
+        let mut counter = CountStatements::default();
+        DefaultVisitor::visit_block(&mut block, &mut counter);
+        counter.count
+    }
+
+    #[test]
+    fn visitors_stop_after_deadline() {
+        let tracker = DeadlineTracker::start(Instant::now());
+
+        let count = count_visited_statements(1000);
+
+        assert!(tracker.is_expired());
+        assert!(count < 1000, "visited {} statements", count);
+    }
+
+    #[test]
+    fn visitors_visit_every_node_before_deadline() {
+        let tracker = DeadlineTracker::start(Instant::now() + Duration::from_secs(3600));
+
+        assert_eq!(count_visited_statements(1000), 2000);
+        assert!(!tracker.is_expired());
+    }
+
+    #[test]
+    fn deadline_is_removed_when_tracker_is_dropped() {
+        let tracker = DeadlineTracker::start(Instant::now());
+        count_visited_statements(1000);
+        drop(tracker);
+
+        assert!(!is_expired());
+        assert_eq!(count_visited_statements(1000), 2000);
+    }
+}
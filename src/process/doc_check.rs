@@ -0,0 +1,758 @@
+use std::collections::HashSet;
+use std::fmt;
+
+#[cfg(feature = "serde-config")]
+use serde::Serialize;
+
+use crate::nodes::{
+    Block, Expression, FunctionBodyTokens, FunctionStatement, LocalAssignStatement,
+    LocalFunctionStatement, Token, TriviaKind, TypedIdentifier,
+};
+use crate::process::doc_tags::{parse_doc_tags, DocTag};
+use crate::process::node_query::function_name_to_string;
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+
+const VARIADIC_PARAMETER: &str = "...";
+const STUB_DESCRIPTION: &str = "TODO";
+
+/// A difference between the `@param` tags of a documentation comment and the parameters
+/// of the documented function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[cfg_attr(
+    feature = "serde-config",
+    serde(tag = "kind", rename_all = "snake_case")
+)]
+pub enum DocMismatchKind {
+    /// A parameter of the function has no `@param` tag.
+    MissingParameter { parameter: String },
+    /// A `@param` tag names a parameter that the function does not have.
+    UnknownParameter { parameter: String },
+    /// A parameter has more than one `@param` tag.
+    DuplicateParameter { parameter: String },
+    /// The `@param` tags are not in the same order as the parameters.
+    ParameterOrder { expected: Vec<String> },
+}
+
+impl fmt::Display for DocMismatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingParameter { parameter } => {
+                write!(f, "parameter `{}` is not documented", parameter)
+            }
+            Self::UnknownParameter { parameter } => {
+                write!(f, "documented parameter `{}` does not exist", parameter)
+            }
+            Self::DuplicateParameter { parameter } => {
+                write!(f, "parameter `{}` is documented more than once", parameter)
+            }
+            Self::ParameterOrder { expected } => write!(
+                f,
+                "documented parameters are not in the order of the function parameters (`{}`)",
+                expected.join(", ")
+            ),
+        }
+    }
+}
+
+/// A documentation mismatch found by [`check_docs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+pub struct DocMismatch {
+    #[cfg_attr(feature = "serde-config", serde(flatten))]
+    kind: DocMismatchKind,
+    line: Option<usize>,
+    column: Option<usize>,
+    function: Option<String>,
+}
+
+impl DocMismatch {
+    #[inline]
+    pub fn kind(&self) -> &DocMismatchKind {
+        &self.kind
+    }
+
+    #[inline]
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    #[inline]
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    /// Returns the name of the documented function, if the function is named.
+    #[inline]
+    pub fn function(&self) -> Option<&str> {
+        self.function.as_deref()
+    }
+}
+
+impl fmt::Display for DocMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.line.unwrap_or_default(),
+            self.column.unwrap_or_default(),
+            self.kind
+        )?;
+        if let Some(function) = &self.function {
+            write!(f, " (in `{}`)", function)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SourcePosition {
+    offset: usize,
+    line: usize,
+}
+
+#[derive(Debug)]
+struct Parameter {
+    name: String,
+    position: Option<SourcePosition>,
+}
+
+#[derive(Debug)]
+struct DocLine {
+    tag: DocTag,
+    start: usize,
+    end: usize,
+    tag_start: usize,
+    line: usize,
+    standalone: bool,
+}
+
+impl DocLine {
+    fn parameter(&self) -> Option<&str> {
+        match &self.tag {
+            DocTag::Param(name) => Some(name),
+            DocTag::Return => None,
+        }
+    }
+
+    fn position(&self) -> SourcePosition {
+        SourcePosition {
+            offset: self.tag_start,
+            line: self.line,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DocumentedFunction {
+    name: Option<String>,
+    parameters: Vec<Parameter>,
+    is_method: bool,
+    docs: Vec<DocLine>,
+}
+
+#[derive(Debug)]
+struct Review<'a> {
+    mismatches: Vec<(DocMismatchKind, Option<SourcePosition>)>,
+    /// The documentation lines to remove.
+    extra_lines: Vec<&'a DocLine>,
+    /// The undocumented parameters, with their index in the parameter list.
+    missing_parameters: Vec<(usize, &'a Parameter)>,
+    /// The documentation lines of the existing parameters, with the parameter index.
+    documented: Vec<(usize, &'a DocLine)>,
+}
+
+impl DocumentedFunction {
+    fn parameter_index(&self, name: &str) -> Option<usize> {
+        self.parameters
+            .iter()
+            .position(|parameter| parameter.name == name)
+    }
+
+    fn review(&self) -> Review<'_> {
+        let mut mismatches = Vec::new();
+        let mut extra_lines = Vec::new();
+        let mut documented = Vec::new();
+        let mut seen = HashSet::new();
+
+        for doc in self.docs.iter() {
+            let Some(name) = doc.parameter() else {
+                continue;
+            };
+
+            if !seen.insert(name) {
+                mismatches.push((
+                    DocMismatchKind::DuplicateParameter {
+                        parameter: name.to_owned(),
+                    },
+                    Some(doc.position()),
+                ));
+                extra_lines.push(doc);
+            } else if let Some(index) = self.parameter_index(name) {
+                documented.push((index, doc));
+            } else if !(self.is_method && name == "self") {
+                mismatches.push((
+                    DocMismatchKind::UnknownParameter {
+                        parameter: name.to_owned(),
+                    },
+                    Some(doc.position()),
+                ));
+                extra_lines.push(doc);
+            }
+        }
+
+        let missing_parameters: Vec<_> = self
+            .parameters
+            .iter()
+            .enumerate()
+            .filter(|(_, parameter)| !seen.contains(parameter.name.as_str()))
+            .collect();
+
+        for (_, parameter) in missing_parameters.iter() {
+            mismatches.push((
+                DocMismatchKind::MissingParameter {
+                    parameter: parameter.name.clone(),
+                },
+                parameter.position,
+            ));
+        }
+
+        let mut expected: Vec<_> = documented.iter().map(|(index, _)| *index).collect();
+        expected.sort_unstable();
+
+        if let Some((_, first_misplaced)) = documented
+            .iter()
+            .zip(expected.iter())
+            .find(|((index, _), expected_index)| index != *expected_index)
+            .map(|(documented, _)| *documented)
+        {
+            mismatches.push((
+                DocMismatchKind::ParameterOrder {
+                    expected: expected
+                        .iter()
+                        .map(|index| self.parameters[*index].name.clone())
+                        .collect(),
+                },
+                Some(first_misplaced.position()),
+            ));
+        }
+
+        Review {
+            mismatches,
+            extra_lines,
+            missing_parameters,
+            documented,
+        }
+    }
+}
+
+fn line_start(code: &str, offset: usize) -> usize {
+    code[..offset].rfind('\n').map_or(0, |index| index + 1)
+}
+
+fn column(code: &str, offset: usize) -> Option<usize> {
+    let before = code.get(..offset)?;
+    Some(before[line_start(code, offset)..].chars().count() + 1)
+}
+
+/// An edit of the original code: the range is replaced by the content.
+#[derive(Debug)]
+struct CodeEdit {
+    start: usize,
+    end: usize,
+    content: String,
+}
+
+/// Returns the range of the whole line of a documentation line, including its indentation
+/// and its new line character, when the line does not contain other code.
+fn full_line_range(code: &str, doc: &DocLine) -> Option<(usize, usize)> {
+    if !doc.standalone {
+        return None;
+    }
+
+    let start = line_start(code, doc.start);
+    if !code[start..doc.start].chars().all(char::is_whitespace) {
+        return None;
+    }
+
+    let rest = &code[doc.end..];
+    let end = if rest.starts_with("\r\n") {
+        doc.end + 2
+    } else if rest.starts_with('\n') {
+        doc.end + 1
+    } else {
+        return None;
+    };
+
+    Some((start, end))
+}
+
+fn stub_edit(code: &str, anchor: &DocLine, insert_after: bool, name: &str) -> Option<CodeEdit> {
+    let (start, end) = full_line_range(code, anchor)?;
+    let line_ending = if code[..end].ends_with("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let position = if insert_after { end } else { start };
+
+    Some(CodeEdit {
+        start: position,
+        end: position,
+        content: format!(
+            "{}@param {} {}{}",
+            &code[start..anchor.tag_start],
+            name,
+            STUB_DESCRIPTION,
+            line_ending
+        ),
+    })
+}
+
+fn fix_edits(code: &str, function: &DocumentedFunction, review: &Review) -> Vec<CodeEdit> {
+    let mut edits = Vec::new();
+
+    for (index, parameter) in review.missing_parameters.iter() {
+        let previous = review
+            .documented
+            .iter()
+            .filter(|(documented_index, _)| documented_index < index)
+            .max_by_key(|(documented_index, _)| *documented_index);
+
+        let edit = if let Some((_, anchor)) = previous {
+            stub_edit(code, anchor, true, &parameter.name)
+        } else if let Some((_, anchor)) = review.documented.first() {
+            stub_edit(code, anchor, false, &parameter.name)
+        } else {
+            function
+                .docs
+                .first()
+                .and_then(|anchor| stub_edit(code, anchor, false, &parameter.name))
+        };
+
+        edits.extend(edit);
+    }
+
+    for doc in review.extra_lines.iter() {
+        if let Some((start, end)) = full_line_range(code, doc) {
+            edits.push(CodeEdit {
+                start,
+                end,
+                content: String::new(),
+            });
+        }
+    }
+
+    edits
+}
+
+fn apply_edits(code: &str, mut edits: Vec<CodeEdit>) -> String {
+    // insertions come before the removals starting at the same position
+    edits.sort_by_key(|edit| (edit.start, edit.end));
+
+    let mut output = String::with_capacity(code.len());
+    let mut cursor = 0;
+
+    for edit in edits {
+        if edit.start < cursor {
+            continue;
+        }
+        output.push_str(&code[cursor..edit.start]);
+        output.push_str(&edit.content);
+        cursor = edit.end;
+    }
+
+    output.push_str(&code[cursor..]);
+    output
+}
+
+fn token_position(token: &Token) -> Option<SourcePosition> {
+    Some(SourcePosition {
+        offset: token.get_start_position()?,
+        line: token.get_line_number()?,
+    })
+}
+
+struct DocCollector<'a> {
+    code: &'a str,
+    functions: Vec<DocumentedFunction>,
+}
+
+impl<'a> DocCollector<'a> {
+    fn new(code: &'a str) -> Self {
+        Self {
+            code,
+            functions: Vec::new(),
+        }
+    }
+
+    fn read_docs(&self, token: &Token) -> Vec<DocLine> {
+        token
+            .iter_leading_trivia()
+            .filter(|trivia| trivia.kind() == TriviaKind::Comment)
+            .filter_map(|trivia| {
+                let start = trivia.get_start_position()?;
+                let line = trivia.get_line_number()?;
+                let comment = trivia.read(self.code);
+
+                Some(parse_doc_tags(comment).into_iter().map(move |tag_line| {
+                    let line = line + comment[..tag_line.start()].matches('\n').count();
+                    DocLine {
+                        tag: tag_line.tag().clone(),
+                        start: start + tag_line.start(),
+                        end: start + tag_line.end(),
+                        tag_start: start + tag_line.tag_start(),
+                        line,
+                        standalone: tag_line.is_standalone(),
+                    }
+                }))
+            })
+            .flatten()
+            .collect()
+    }
+
+    fn push_function<'b>(
+        &mut self,
+        first_token: Option<&Token>,
+        name: Option<String>,
+        is_method: bool,
+        parameters: impl Iterator<Item = &'b TypedIdentifier>,
+        is_variadic: bool,
+        body_tokens: Option<&FunctionBodyTokens>,
+    ) {
+        let Some(first_token) = first_token else {
+            return;
+        };
+        let docs = self.read_docs(first_token);
+        if docs.is_empty() {
+            return;
+        }
+
+        let mut parameters: Vec<_> = parameters
+            .map(|parameter| {
+                let identifier = parameter.get_identifier();
+                Parameter {
+                    name: identifier.get_name().to_owned(),
+                    position: identifier.get_token().and_then(token_position),
+                }
+            })
+            .collect();
+
+        if is_variadic {
+            parameters.push(Parameter {
+                name: VARIADIC_PARAMETER.to_owned(),
+                position: body_tokens
+                    .and_then(|tokens| tokens.variable_arguments.as_ref())
+                    .and_then(token_position),
+            });
+        }
+
+        self.functions.push(DocumentedFunction {
+            name,
+            parameters,
+            is_method,
+            docs,
+        });
+    }
+}
+
+impl NodeProcessor for DocCollector<'_> {
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let tokens = function.get_tokens();
+        self.push_function(
+            tokens.map(|tokens| &tokens.function),
+            Some(function_name_to_string(function.get_name())),
+            function.get_name().has_method(),
+            function.iter_parameters(),
+            function.is_variadic(),
+            tokens,
+        );
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        let tokens = function.get_tokens();
+        self.push_function(
+            tokens.map(|tokens| &tokens.local),
+            Some(function.get_name().to_owned()),
+            false,
+            function.iter_parameters(),
+            function.is_variadic(),
+            tokens.map(|tokens| &tokens.function_body),
+        );
+    }
+
+    fn process_local_assign_statement(&mut self, assign: &mut LocalAssignStatement) {
+        if assign.variables_len() != 1 || assign.values_len() != 1 {
+            return;
+        }
+        let Some(Expression::Function(function)) = assign.iter_values().next() else {
+            return;
+        };
+
+        self.push_function(
+            assign.get_tokens().map(|tokens| &tokens.local),
+            assign
+                .get_variables()
+                .first()
+                .map(|variable| variable.get_identifier().get_name().to_owned()),
+            false,
+            function.iter_parameters(),
+            function.is_variadic(),
+            function.get_tokens(),
+        );
+    }
+}
+
+fn collect_documented_functions(block: &mut Block, code: &str) -> Vec<DocumentedFunction> {
+    let mut collector = DocCollector::new(code);
+    DefaultVisitor::visit_block(block, &mut collector);
+    collector.functions
+}
+
+/// Compares the `@param` tags of the documentation comments with the parameters of the
+/// functions they document. Functions without `@param` or `@return` tags are not verified.
+/// The block must be parsed with its tokens, which hold the comments.
+pub fn check_docs(block: &mut Block, code: &str) -> Vec<DocMismatch> {
+    let mut mismatches: Vec<_> = collect_documented_functions(block, code)
+        .iter()
+        .flat_map(|function| {
+            function
+                .review()
+                .mismatches
+                .into_iter()
+                .map(move |(kind, position)| DocMismatch {
+                    kind,
+                    line: position.map(|position| position.line),
+                    column: position.and_then(|position| column(code, position.offset)),
+                    function: function.name.clone(),
+                })
+        })
+        .collect();
+
+    mismatches.sort_by_key(|mismatch| (mismatch.line, mismatch.column));
+    mismatches
+}
+
+/// Rewrites the documentation comments so that they have a `@param` tag for each parameter:
+/// a `@param name TODO` stub is added for undocumented parameters and the tags of parameters
+/// that do not exist are removed. Only the edited comment lines differ from the original code.
+pub fn fix_docs(block: &mut Block, code: &str) -> String {
+    let edits = collect_documented_functions(block, code)
+        .iter()
+        .flat_map(|function| fix_edits(code, function, &function.review()))
+        .collect();
+
+    apply_edits(code, edits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Parser;
+
+    fn parse(code: &str) -> Block {
+        Parser::default()
+            .preserve_tokens()
+            .parse(code)
+            .expect("unable to parse code")
+    }
+
+    fn check(code: &str) -> Vec<String> {
+        check_docs(&mut parse(code), code)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn fix(code: &str) -> String {
+        fix_docs(&mut parse(code), code)
+    }
+
+    #[test]
+    fn documented_parameters_match() {
+        let code = r#"
+--- Adds two numbers
+--- @param a number
+--- @param b number
+--- @return number
+local function add(a, b)
+    return a + b
+end
+"#;
+        pretty_assertions::assert_eq!(check(code), Vec::<String>::new());
+    }
+
+    #[test]
+    fn function_without_doc_comment_is_ignored() {
+        let code = "-- adds two numbers\nlocal function add(a, b)\n    return a + b\nend\n";
+        pretty_assertions::assert_eq!(check(code), Vec::<String>::new());
+        pretty_assertions::assert_eq!(fix(code), code);
+    }
+
+    #[test]
+    fn missing_parameter() {
+        let code = "--- @param a number\nfunction add(a, b)\nend\n";
+        pretty_assertions::assert_eq!(
+            check(code),
+            vec!["2:17: parameter `b` is not documented (in `add`)"]
+        );
+    }
+
+    #[test]
+    fn unknown_parameter() {
+        let code = "--- @param a\n--- @param c\nlocal function add(a)\nend\n";
+        pretty_assertions::assert_eq!(
+            check(code),
+            vec!["2:5: documented parameter `c` does not exist (in `add`)"]
+        );
+    }
+
+    #[test]
+    fn duplicate_parameter() {
+        let code = "-- @param a\n-- @param a\nlocal function f(a)\nend\n";
+        pretty_assertions::assert_eq!(
+            check(code),
+            vec!["2:4: parameter `a` is documented more than once (in `f`)"]
+        );
+    }
+
+    #[test]
+    fn parameter_order() {
+        let code = "--- @param b\n--- @param a\nlocal function add(a, b)\nend\n";
+        pretty_assertions::assert_eq!(
+            check(code),
+            vec![
+                "1:5: documented parameters are not in the order of the function parameters (`a, b`) (in `add`)"
+            ]
+        );
+    }
+
+    #[test]
+    fn variadic_parameter_documented() {
+        let code =
+            "--- @param format string\n--- @param ... any\nlocal function log(format, ...)\nend\n";
+        pretty_assertions::assert_eq!(check(code), Vec::<String>::new());
+    }
+
+    #[test]
+    fn variadic_parameter_not_documented() {
+        let code = "--- @param format string\nlocal function log(format, ...)\nend\n";
+        pretty_assertions::assert_eq!(
+            check(code),
+            vec!["2:28: parameter `...` is not documented (in `log`)"]
+        );
+    }
+
+    #[test]
+    fn variadic_parameter_documented_without_varargs() {
+        let code = "--- @param ... any\nlocal function log()\nend\n";
+        pretty_assertions::assert_eq!(
+            check(code),
+            vec!["1:5: documented parameter `...` does not exist (in `log`)"]
+        );
+    }
+
+    #[test]
+    fn method_can_document_self() {
+        let code =
+            "--- @param self Class\n--- @param value number\nfunction Class:set(value)\nend\n";
+        pretty_assertions::assert_eq!(check(code), Vec::<String>::new());
+    }
+
+    #[test]
+    fn function_assigned_to_local() {
+        let code = "-- @return nil\nlocal callback = function(event)\nend\n";
+        pretty_assertions::assert_eq!(
+            check(code),
+            vec!["2:27: parameter `event` is not documented (in `callback`)"]
+        );
+    }
+
+    #[test]
+    fn nested_function() {
+        let code =
+            "local function outer()\n    --- @param b\n    local function inner(a)\n    end\nend\n";
+        pretty_assertions::assert_eq!(
+            check(code),
+            vec![
+                "2:9: documented parameter `b` does not exist (in `inner`)",
+                "3:26: parameter `a` is not documented (in `inner`)",
+            ]
+        );
+    }
+
+    #[test]
+    fn block_comment_documentation() {
+        let code =
+            "--[=[\n\t@param a number\n\t@param c number\n]=]\nlocal function add(a, b)\nend\n";
+        pretty_assertions::assert_eq!(
+            check(code),
+            vec![
+                "3:2: documented parameter `c` does not exist (in `add`)",
+                "5:23: parameter `b` is not documented (in `add`)",
+            ]
+        );
+    }
+
+    #[test]
+    fn fix_adds_stub_after_previous_parameter() {
+        let code = "--- Adds numbers\n--- @param a number\n--- @return number\nlocal function add(a, b)\nend\n";
+        pretty_assertions::assert_eq!(
+            fix(code),
+            "--- Adds numbers\n--- @param a number\n--- @param b TODO\n--- @return number\nlocal function add(a, b)\nend\n"
+        );
+    }
+
+    #[test]
+    fn fix_adds_stub_before_next_parameter() {
+        let code = "    -- @param b\n    local function add(a, b)\n    end\n";
+        pretty_assertions::assert_eq!(
+            fix(code),
+            "    -- @param a TODO\n    -- @param b\n    local function add(a, b)\n    end\n"
+        );
+    }
+
+    #[test]
+    fn fix_adds_stubs_before_return_tag() {
+        let code = "--- @return nil\nlocal function log(format, ...)\nend\n";
+        pretty_assertions::assert_eq!(
+            fix(code),
+            "--- @param format TODO\n--- @param ... TODO\n--- @return nil\nlocal function log(format, ...)\nend\n"
+        );
+    }
+
+    #[test]
+    fn fix_replaces_renamed_parameter() {
+        let code = "--- @param old number\n--- @return nil\nlocal function f(new)\nend\n";
+        pretty_assertions::assert_eq!(
+            fix(code),
+            "--- @param new TODO\n--- @return nil\nlocal function f(new)\nend\n"
+        );
+    }
+
+    #[test]
+    fn fix_removes_duplicate_and_unknown_parameters() {
+        let code = "--- @param a\n--- @param a\n--- @param c\nfunction f(a)\nend\n";
+        pretty_assertions::assert_eq!(fix(code), "--- @param a\nfunction f(a)\nend\n");
+    }
+
+    #[test]
+    fn fix_block_comment_documentation() {
+        let code =
+            "--[=[\n\t@param a number\n\t@param c number\n]=]\nlocal function add(a, b)\nend\n";
+        pretty_assertions::assert_eq!(
+            fix(code),
+            "--[=[\n\t@param a number\n\t@param b TODO\n]=]\nlocal function add(a, b)\nend\n"
+        );
+    }
+
+    #[test]
+    fn fix_keeps_other_code_unchanged() {
+        let code = "local x   =  1 -- comment\n\n--- @param a\nlocal function f(a, b) return a+b end\nreturn   x\n";
+        pretty_assertions::assert_eq!(
+            fix(code),
+            "local x   =  1 -- comment\n\n--- @param a\n--- @param b TODO\nlocal function f(a, b) return a+b end\nreturn   x\n"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! A small parser for the tags of documentation comments, like `@param name` and `@return`.
+
+/// A tag found in a documentation comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DocTag {
+    Param(String),
+    Return,
+}
+
+/// A line of a comment that contains a documentation tag. Offsets are in bytes from the
+/// start of the comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DocTagLine {
+    tag: DocTag,
+    start: usize,
+    end: usize,
+    tag_start: usize,
+    standalone: bool,
+}
+
+impl DocTagLine {
+    #[inline]
+    pub(crate) fn tag(&self) -> &DocTag {
+        &self.tag
+    }
+
+    /// The offset where the line starts.
+    #[inline]
+    pub(crate) fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The offset where the line ends, before its new line character.
+    #[inline]
+    pub(crate) fn end(&self) -> usize {
+        self.end
+    }
+
+    /// The offset of the `@` character of the tag.
+    #[inline]
+    pub(crate) fn tag_start(&self) -> usize {
+        self.tag_start
+    }
+
+    /// Returns true if the line only contains the tag, so it can be removed or have lines
+    /// inserted around it without editing the delimiters of a block comment.
+    #[inline]
+    pub(crate) fn is_standalone(&self) -> bool {
+        self.standalone
+    }
+}
+
+/// Returns the length of the opening delimiter of a block comment (like `--[==[`), if the
+/// comment is a block comment.
+fn block_comment_opening(comment: &str) -> Option<usize> {
+    let content = comment.strip_prefix("--")?.strip_prefix('[')?;
+    let equals = content.chars().take_while(|c| *c == '=').count();
+    content[equals..]
+        .starts_with('[')
+        .then_some(2 + 1 + equals + 1)
+}
+
+fn parse_tag(text: &str) -> Option<DocTag> {
+    if let Some(rest) = text.strip_prefix("@param") {
+        if !rest.starts_with(char::is_whitespace) && !rest.starts_with('[') {
+            return None;
+        }
+        let mut rest = rest.trim_start();
+        // skip modifiers like `@param[opt] name`
+        if let Some(modifiers) = rest.strip_prefix('[') {
+            rest = modifiers.split_once(']')?.1.trim_start();
+        }
+        let name: String = if rest.starts_with("...") {
+            "...".to_owned()
+        } else {
+            rest.chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+                .collect()
+        };
+        (!name.is_empty()).then_some(DocTag::Param(name))
+    } else {
+        let rest = text
+            .strip_prefix("@returns")
+            .or_else(|| text.strip_prefix("@return"))?;
+        (rest.is_empty() || rest.starts_with(char::is_whitespace)).then_some(DocTag::Return)
+    }
+}
+
+/// Finds the lines of a comment (including its `--` prefix) that start with a `@param` or a
+/// `@return` tag.
+pub(crate) fn parse_doc_tags(comment: &str) -> Vec<DocTagLine> {
+    let block_opening = block_comment_opening(comment);
+    let line_count = comment.split('\n').count();
+
+    let mut tags = Vec::new();
+    let mut start = 0;
+
+    for (index, line) in comment.split('\n').enumerate() {
+        let end = start + line.trim_end_matches('\r').len();
+
+        let content_start = if index == 0 {
+            block_opening.unwrap_or(2)
+        } else {
+            0
+        };
+        let content = &line[content_start.min(line.len())..];
+        let trimmed = content.trim_start_matches(|c: char| c == '-' || c.is_whitespace());
+
+        if let Some(tag) = parse_tag(trimmed) {
+            let standalone = match block_opening {
+                Some(_) => index != 0 && index + 1 != line_count,
+                None => true,
+            };
+            tags.push(DocTagLine {
+                tag,
+                start,
+                end,
+                tag_start: start + line.len() - trimmed.len(),
+                standalone,
+            });
+        }
+
+        start += line.len() + 1;
+    }
+
+    tags
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tags(comment: &str) -> Vec<DocTag> {
+        parse_doc_tags(comment)
+            .into_iter()
+            .map(|line| line.tag)
+            .collect()
+    }
+
+    fn param(name: &str) -> DocTag {
+        DocTag::Param(name.to_owned())
+    }
+
+    #[test]
+    fn parse_param_in_line_comment() {
+        assert_eq!(tags("--- @param name string"), vec![param("name")]);
+    }
+
+    #[test]
+    fn parse_param_without_space_after_dashes() {
+        assert_eq!(tags("--@param name"), vec![param("name")]);
+    }
+
+    #[test]
+    fn parse_param_with_type_after_colon() {
+        assert_eq!(tags("-- @param name: string"), vec![param("name")]);
+    }
+
+    #[test]
+    fn parse_param_with_modifiers() {
+        assert_eq!(tags("-- @param[opt] name"), vec![param("name")]);
+    }
+
+    #[test]
+    fn parse_variadic_param() {
+        assert_eq!(tags("-- @param ... any"), vec![param("...")]);
+    }
+
+    #[test]
+    fn parse_return_tags() {
+        assert_eq!(tags("-- @return number"), vec![DocTag::Return]);
+        assert_eq!(tags("-- @returns"), vec![DocTag::Return]);
+    }
+
+    #[test]
+    fn ignore_other_tags_and_text() {
+        assert_eq!(tags("-- @parameter name"), Vec::new());
+        assert_eq!(tags("-- @param"), Vec::new());
+        assert_eq!(tags("-- returns @param name"), Vec::new());
+    }
+
+    #[test]
+    fn parse_block_comment_lines() {
+        let comment = "--[=[\n\t@param first\n\t@param second\n\t@return number\n]=]";
+        let lines = parse_doc_tags(comment);
+
+        assert_eq!(
+            lines
+                .iter()
+                .map(DocTagLine::tag)
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![param("first"), param("second"), DocTag::Return]
+        );
+        assert_eq!(&comment[lines[0].start()..lines[0].end()], "\t@param first");
+        assert_eq!(lines[0].tag_start(), lines[0].start() + 1);
+        assert!(lines.iter().all(DocTagLine::is_standalone));
+    }
+
+    #[test]
+    fn block_comment_delimiter_lines_are_not_standalone() {
+        let lines = parse_doc_tags("--[[ @param first\n@param second ]]");
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| !line.is_standalone()));
+    }
+}
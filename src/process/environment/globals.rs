@@ -0,0 +1,514 @@
+//! The built-in globals available in each target environment.
+//!
+//! Each entry is a `table`, a `function` or a `value`. Entries are available in every target,
+//! unless a list of `targets` is given (the `Roblox` target also includes the entries of the
+//! `Luau` target). Other properties:
+//!  - `pure_arity`: calling the function with this number of arguments that do not have side
+//!    effects has no side effects
+//!  - `returns`: the type of the value returned by a function, when it is always the same
+//!  - `value_type`: the type of a value
+//!  - `children`: the entries of a table
+
+use super::{EnvironmentTarget, GlobalEntry, GlobalKind, LuaType};
+
+/// A built-in entry, defined with const functions so that the table is checked at compile
+/// time.
+#[derive(Debug)]
+pub(super) struct BuiltinGlobal {
+    name: &'static str,
+    kind: GlobalKind,
+    pure_arity: Option<usize>,
+    returns: Option<LuaType>,
+    value_type: Option<LuaType>,
+    targets: &'static [EnvironmentTarget],
+    children: &'static [BuiltinGlobal],
+}
+
+const fn entry(name: &'static str, kind: GlobalKind) -> BuiltinGlobal {
+    BuiltinGlobal {
+        name,
+        kind,
+        pure_arity: None,
+        returns: None,
+        value_type: None,
+        targets: &[],
+        children: &[],
+    }
+}
+
+const fn table(name: &'static str) -> BuiltinGlobal {
+    entry(name, GlobalKind::Table)
+}
+
+const fn function(name: &'static str) -> BuiltinGlobal {
+    entry(name, GlobalKind::Function)
+}
+
+const fn value(name: &'static str) -> BuiltinGlobal {
+    entry(name, GlobalKind::Value)
+}
+
+impl BuiltinGlobal {
+    const fn targets(mut self, targets: &'static [EnvironmentTarget]) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    const fn pure_arity(mut self, arity: usize) -> Self {
+        self.pure_arity = Some(arity);
+        self
+    }
+
+    const fn returns(mut self, lua_type: LuaType) -> Self {
+        self.returns = Some(lua_type);
+        self
+    }
+
+    const fn value_type(mut self, lua_type: LuaType) -> Self {
+        self.value_type = Some(lua_type);
+        self
+    }
+
+    const fn children(mut self, children: &'static [BuiltinGlobal]) -> Self {
+        self.children = children;
+        self
+    }
+
+    pub(super) fn to_entry(&self) -> GlobalEntry {
+        let mut entry = GlobalEntry::new(self.name, self.kind);
+        if let Some(arity) = self.pure_arity {
+            entry = entry.with_pure_arity(arity);
+        }
+        entry.returns = self.returns;
+        entry.value_type = self.value_type;
+        if !self.targets.is_empty() {
+            entry.targets = Some(self.targets.to_vec());
+        }
+        entry.children = self.children.iter().map(Self::to_entry).collect();
+        entry
+    }
+}
+
+pub(super) static BUILTIN_GLOBALS: &[BuiltinGlobal] = {
+    use EnvironmentTarget::*;
+    use LuaType::*;
+
+    &[
+        table("_G"),
+        value("_VERSION").value_type(String),
+        table("arg").targets(&[Lua51, Lua53, Lua54]),
+        function("assert"),
+        function("collectgarbage"),
+        function("dofile").targets(&[Lua51, Lua53, Lua54]),
+        function("error"),
+        function("gcinfo").targets(&[Lua51, Luau]).returns(Number),
+        function("getfenv").targets(&[Lua51, Luau]),
+        function("getmetatable"),
+        function("ipairs"),
+        function("load").targets(&[Lua51, Lua53, Lua54]),
+        function("loadfile").targets(&[Lua51, Lua53, Lua54]),
+        function("loadstring").targets(&[Lua51, Luau]),
+        function("module").targets(&[Lua51]),
+        function("newproxy").targets(&[Lua51, Luau]),
+        function("next"),
+        function("pairs"),
+        function("pcall"),
+        function("print"),
+        function("rawequal").returns(Boolean),
+        function("rawget"),
+        function("rawlen")
+            .targets(&[Lua53, Lua54, Luau])
+            .returns(Number),
+        function("rawset"),
+        function("require"),
+        function("select"),
+        function("setfenv").targets(&[Lua51, Luau]),
+        function("setmetatable"),
+        function("tonumber"),
+        function("tostring").returns(String),
+        function("type").returns(String).pure_arity(1),
+        function("typeof").targets(&[Luau]).returns(String),
+        function("unpack").targets(&[Lua51, Luau]),
+        function("warn").targets(&[Lua54, Roblox]),
+        function("xpcall"),
+        table("bit32").targets(&[Luau]).children(&[
+            function("arshift").returns(Number),
+            function("band").returns(Number),
+            function("bnot").returns(Number),
+            function("bor").returns(Number),
+            function("btest").returns(Boolean),
+            function("bxor").returns(Number),
+            function("byteswap").returns(Number),
+            function("countlz").returns(Number),
+            function("countrz").returns(Number),
+            function("extract").returns(Number),
+            function("lrotate").returns(Number),
+            function("lshift").returns(Number),
+            function("replace").returns(Number),
+            function("rrotate").returns(Number),
+            function("rshift").returns(Number),
+        ]),
+        table("buffer").targets(&[Luau]).children(&[
+            function("copy"),
+            function("create"),
+            function("fill"),
+            function("fromstring"),
+            function("len").returns(Number),
+            function("readf32").returns(Number),
+            function("readf64").returns(Number),
+            function("readi16").returns(Number),
+            function("readi32").returns(Number),
+            function("readi8").returns(Number),
+            function("readstring").returns(String),
+            function("readu16").returns(Number),
+            function("readu32").returns(Number),
+            function("readu8").returns(Number),
+            function("tostring").returns(String),
+            function("writef32"),
+            function("writef64"),
+            function("writei16"),
+            function("writei32"),
+            function("writei8"),
+            function("writestring"),
+            function("writeu16"),
+            function("writeu32"),
+            function("writeu8"),
+        ]),
+        table("coroutine").children(&[
+            function("close").targets(&[Lua54, Luau]),
+            function("create"),
+            function("isyieldable")
+                .targets(&[Lua53, Lua54, Luau])
+                .returns(Boolean),
+            function("resume"),
+            function("running"),
+            function("status").returns(String),
+            function("wrap").returns(Function),
+            function("yield"),
+        ]),
+        table("debug").children(&[
+            function("debug").targets(&[Lua51, Lua53, Lua54]),
+            function("getfenv").targets(&[Lua51]),
+            function("gethook").targets(&[Lua51, Lua53, Lua54]),
+            function("getinfo").targets(&[Lua51, Lua53, Lua54]),
+            function("getlocal").targets(&[Lua51, Lua53, Lua54]),
+            function("getmetatable").targets(&[Lua51, Lua53, Lua54]),
+            function("getregistry").targets(&[Lua51, Lua53, Lua54]),
+            function("getupvalue").targets(&[Lua51, Lua53, Lua54]),
+            function("getuservalue").targets(&[Lua53, Lua54]),
+            function("info").targets(&[Luau]),
+            function("profilebegin").targets(&[Roblox]),
+            function("profileend").targets(&[Roblox]),
+            function("setfenv").targets(&[Lua51]),
+            function("sethook").targets(&[Lua51, Lua53, Lua54]),
+            function("setlocal").targets(&[Lua51, Lua53, Lua54]),
+            function("setmetatable").targets(&[Lua51, Lua53, Lua54]),
+            function("setupvalue").targets(&[Lua51, Lua53, Lua54]),
+            function("setuservalue").targets(&[Lua53, Lua54]),
+            function("traceback"),
+            function("upvalueid").targets(&[Lua53, Lua54]),
+            function("upvaluejoin").targets(&[Lua53, Lua54]),
+        ]),
+        table("io").targets(&[Lua51, Lua53, Lua54]).children(&[
+            function("close"),
+            function("flush"),
+            function("input"),
+            function("lines").returns(Function),
+            function("open"),
+            function("output"),
+            function("popen"),
+            function("read"),
+            value("stderr"),
+            value("stdin"),
+            value("stdout"),
+            function("tmpfile"),
+            function("type"),
+            function("write"),
+        ]),
+        table("math").children(&[
+            function("abs").returns(Number),
+            function("acos").returns(Number),
+            function("asin").returns(Number),
+            function("atan").returns(Number),
+            function("atan2").targets(&[Lua51, Luau]).returns(Number),
+            function("ceil").returns(Number),
+            function("clamp").targets(&[Luau]).returns(Number),
+            function("cos").returns(Number),
+            function("cosh").targets(&[Lua51, Luau]).returns(Number),
+            function("deg").returns(Number),
+            function("exp").returns(Number),
+            function("floor").returns(Number),
+            function("fmod").returns(Number),
+            function("frexp").targets(&[Lua51, Luau]).returns(Number),
+            value("huge").value_type(Number),
+            function("ldexp").targets(&[Lua51, Luau]).returns(Number),
+            function("log").returns(Number),
+            function("log10").targets(&[Lua51, Luau]).returns(Number),
+            function("max").returns(Number),
+            value("maxinteger")
+                .targets(&[Lua53, Lua54])
+                .value_type(Number),
+            function("min").returns(Number),
+            value("mininteger")
+                .targets(&[Lua53, Lua54])
+                .value_type(Number),
+            function("modf").returns(Number),
+            function("noise").targets(&[Luau]).returns(Number),
+            value("pi").value_type(Number),
+            function("pow").targets(&[Lua51, Luau]).returns(Number),
+            function("rad").returns(Number),
+            function("random").returns(Number),
+            function("randomseed"),
+            function("round").targets(&[Luau]).returns(Number),
+            function("sign").targets(&[Luau]).returns(Number),
+            function("sin").returns(Number),
+            function("sinh").targets(&[Lua51, Luau]).returns(Number),
+            function("sqrt").returns(Number),
+            function("tan").returns(Number),
+            function("tanh").targets(&[Lua51, Luau]).returns(Number),
+            function("tointeger").targets(&[Lua53, Lua54]),
+            function("type").targets(&[Lua53, Lua54]),
+            function("ult").targets(&[Lua53, Lua54]).returns(Boolean),
+        ]),
+        table("os").children(&[
+            function("clock").returns(Number).pure_arity(0),
+            function("date"),
+            function("difftime").returns(Number),
+            function("execute").targets(&[Lua51, Lua53, Lua54]),
+            function("exit").targets(&[Lua51, Lua53, Lua54]),
+            function("getenv").targets(&[Lua51, Lua53, Lua54]),
+            function("remove").targets(&[Lua51, Lua53, Lua54]),
+            function("rename").targets(&[Lua51, Lua53, Lua54]),
+            function("setlocale").targets(&[Lua51, Lua53, Lua54]),
+            function("time").returns(Number).pure_arity(0),
+            function("tmpname")
+                .targets(&[Lua51, Lua53, Lua54])
+                .returns(String),
+        ]),
+        table("package").targets(&[Lua51, Lua53, Lua54]).children(&[
+            value("config").targets(&[Lua53, Lua54]).value_type(String),
+            value("cpath").value_type(String),
+            table("loaded"),
+            table("loaders").targets(&[Lua51]),
+            function("loadlib"),
+            value("path").value_type(String),
+            table("preload"),
+            table("searchers").targets(&[Lua53, Lua54]),
+            function("searchpath").targets(&[Lua53, Lua54]),
+            function("seeall").targets(&[Lua51]),
+        ]),
+        table("string").children(&[
+            function("byte"),
+            function("char").returns(String),
+            function("dump")
+                .targets(&[Lua51, Lua53, Lua54])
+                .returns(String),
+            function("find"),
+            function("format").returns(String),
+            function("gmatch").returns(Function),
+            function("gsub").returns(String),
+            function("len").returns(Number),
+            function("lower").returns(String),
+            function("match"),
+            function("pack")
+                .targets(&[Lua53, Lua54, Luau])
+                .returns(String),
+            function("packsize")
+                .targets(&[Lua53, Lua54, Luau])
+                .returns(Number),
+            function("rep").returns(String),
+            function("reverse").returns(String),
+            function("split").targets(&[Luau]).returns(Table),
+            function("sub").returns(String),
+            function("unpack").targets(&[Lua53, Lua54, Luau]),
+            function("upper").returns(String),
+        ]),
+        table("table").children(&[
+            function("clear").targets(&[Luau]),
+            function("clone").targets(&[Luau]).returns(Table),
+            function("concat").returns(String),
+            function("create").targets(&[Luau]).returns(Table),
+            function("find").targets(&[Luau]),
+            function("foreach").targets(&[Lua51, Luau]),
+            function("foreachi").targets(&[Lua51, Luau]),
+            function("freeze").targets(&[Luau]).returns(Table),
+            function("getn").targets(&[Lua51, Luau]).returns(Number),
+            function("insert"),
+            function("isfrozen").targets(&[Luau]).returns(Boolean),
+            function("maxn").targets(&[Lua51, Luau]).returns(Number),
+            function("move")
+                .targets(&[Lua53, Lua54, Luau])
+                .returns(Table),
+            function("pack")
+                .targets(&[Lua53, Lua54, Luau])
+                .returns(Table),
+            function("remove"),
+            function("sort"),
+            function("unpack").targets(&[Lua53, Lua54, Luau]),
+        ]),
+        table("utf8").targets(&[Lua53, Lua54, Luau]).children(&[
+            function("char").returns(String),
+            value("charpattern").value_type(String),
+            function("codepoint"),
+            function("codes"),
+            function("len"),
+            function("offset"),
+        ]),
+        table("Axes")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("BrickColor").targets(&[Roblox]).children(&[
+            function("new"),
+            function("palette"),
+            function("random"),
+        ]),
+        table("CatalogSearchParams")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("CellId")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("CFrame").targets(&[Roblox]).children(&[
+            function("Angles"),
+            function("fromAxisAngle"),
+            function("fromEulerAngles"),
+            function("fromEulerAnglesXYZ"),
+            function("fromEulerAnglesYXZ"),
+            function("fromMatrix"),
+            function("fromOrientation"),
+            value("identity"),
+            function("lookAt"),
+            function("new"),
+        ]),
+        table("Color3").targets(&[Roblox]).children(&[
+            function("fromHSV"),
+            function("fromHex"),
+            function("fromRGB"),
+            function("new"),
+        ]),
+        table("ColorSequence")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("ColorSequenceKeypoint")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("DateTime").targets(&[Roblox]).children(&[
+            function("fromIsoDate"),
+            function("fromLocalTime"),
+            function("fromUniversalTime"),
+            function("fromUnixTimestamp"),
+            function("fromUnixTimestampMillis"),
+            function("now"),
+        ]),
+        function("DebuggerManager").targets(&[Roblox]),
+        function("delay").targets(&[Roblox]),
+        table("DockWidgetPluginGuiInfo")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        function("elapsedTime").targets(&[Roblox]).returns(Number),
+        table("Enum").targets(&[Roblox]),
+        table("Faces")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        value("game").targets(&[Roblox]),
+        table("Instance")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        function("LoadLibrary").targets(&[Roblox]),
+        table("NumberRange")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("NumberSequence")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("NumberSequenceKeypoint")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("OverlapParams")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("PathWaypoint")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("PhysicalProperties")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        value("plugin").targets(&[Roblox]),
+        table("PluginDrag")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        function("PluginManager").targets(&[Roblox]),
+        function("printidentity").targets(&[Roblox]),
+        table("Random")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("Ray").targets(&[Roblox]).children(&[function("new")]),
+        table("RaycastParams")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("Rect")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("Region3")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("Region3int16")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        value("script").targets(&[Roblox]),
+        function("settings").targets(&[Roblox]),
+        table("shared").targets(&[Roblox]),
+        function("spawn").targets(&[Roblox]),
+        function("stats").targets(&[Roblox]),
+        table("task").targets(&[Roblox]).children(&[
+            function("cancel"),
+            function("defer"),
+            function("delay"),
+            function("desynchronize"),
+            function("spawn"),
+            function("synchronize"),
+            function("wait").returns(Number),
+        ]),
+        function("tick").targets(&[Roblox]).returns(Number),
+        function("time").targets(&[Roblox]).returns(Number),
+        table("TweenInfo")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("UDim")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("UDim2").targets(&[Roblox]).children(&[
+            function("fromOffset"),
+            function("fromScale"),
+            function("new"),
+        ]),
+        function("UserSettings").targets(&[Roblox]),
+        table("Vector2").targets(&[Roblox]).children(&[
+            function("new"),
+            value("one"),
+            value("xAxis"),
+            value("yAxis"),
+            value("zero"),
+        ]),
+        table("Vector2int16")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        table("Vector3").targets(&[Roblox]).children(&[
+            function("FromAxis"),
+            function("FromNormalId"),
+            function("new"),
+            value("one"),
+            value("xAxis"),
+            value("yAxis"),
+            value("zAxis"),
+            value("zero"),
+        ]),
+        table("Vector3int16")
+            .targets(&[Roblox])
+            .children(&[function("new")]),
+        function("version").targets(&[Roblox]).returns(String),
+        function("wait").targets(&[Roblox]),
+        value("workspace").targets(&[Roblox]),
+        function("ypcall").targets(&[Roblox]),
+    ]
+};
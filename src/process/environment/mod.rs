@@ -0,0 +1,529 @@
+mod globals;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+use super::{LuaType, LuaVersion, TypeInfo};
+
+/// The environment in which the code runs, used to know which globals exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "snake_case"))]
+pub enum EnvironmentTarget {
+    Lua51,
+    Lua53,
+    Lua54,
+    Luau,
+    Roblox,
+}
+
+impl EnvironmentTarget {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "lua51" => Some(Self::Lua51),
+            "lua53" => Some(Self::Lua53),
+            "lua54" => Some(Self::Lua54),
+            "luau" => Some(Self::Luau),
+            "roblox" => Some(Self::Roblox),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lua51 => "lua51",
+            Self::Lua53 => "lua53",
+            Self::Lua54 => "lua54",
+            Self::Luau => "luau",
+            Self::Roblox => "roblox",
+        }
+    }
+
+    /// Returns the Lua version used by the target.
+    pub fn lua_version(&self) -> LuaVersion {
+        match self {
+            Self::Lua51 => LuaVersion::Lua51,
+            Self::Lua53 => LuaVersion::Lua53,
+            Self::Lua54 => LuaVersion::Lua54,
+            Self::Luau | Self::Roblox => LuaVersion::Luau,
+        }
+    }
+
+    /// Returns true if the code written for this target can use identifiers that contain
+    /// UTF-8 characters.
+    pub fn allows_utf8_identifiers(&self) -> bool {
+        matches!(self, Self::Luau | Self::Roblox)
+    }
+
+    /// Returns true if the globals of the given target are also available in this target.
+    fn includes(&self, other: Self) -> bool {
+        *self == other || (*self == Self::Roblox && other == Self::Luau)
+    }
+}
+
+impl From<LuaVersion> for EnvironmentTarget {
+    fn from(version: LuaVersion) -> Self {
+        match version {
+            LuaVersion::Lua51 => Self::Lua51,
+            LuaVersion::Lua53 => Self::Lua53,
+            LuaVersion::Lua54 => Self::Lua54,
+            LuaVersion::Luau => Self::Luau,
+        }
+    }
+}
+
+impl std::str::FromStr for EnvironmentTarget {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value).ok_or_else(|| {
+            format!(
+                "invalid target `{}` (must be `lua51`, `lua53`, `lua54`, `luau` or `roblox`)",
+                value
+            )
+        })
+    }
+}
+
+/// The kind of value stored in a global entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "snake_case"))]
+pub enum GlobalKind {
+    Table,
+    Function,
+    Value,
+}
+
+/// A global variable (or a field of a global table) known to exist in an environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-config", serde(deny_unknown_fields))]
+pub struct GlobalEntry {
+    name: String,
+    #[cfg_attr(feature = "serde-config", serde(rename = "type"))]
+    kind: GlobalKind,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pure: bool,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    arity: Option<usize>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    returns: Option<LuaType>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    value_type: Option<LuaType>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    targets: Option<Vec<EnvironmentTarget>>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    children: Vec<GlobalEntry>,
+}
+
+impl GlobalEntry {
+    pub fn new(name: impl Into<String>, kind: GlobalKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            pure: false,
+            arity: None,
+            returns: None,
+            value_type: None,
+            targets: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn table(name: impl Into<String>) -> Self {
+        Self::new(name, GlobalKind::Table)
+    }
+
+    pub fn function(name: impl Into<String>) -> Self {
+        Self::new(name, GlobalKind::Function)
+    }
+
+    pub fn value(name: impl Into<String>) -> Self {
+        Self::new(name, GlobalKind::Value)
+    }
+
+    /// Marks a function as pure: calling it with `arity` arguments that do not have side
+    /// effects does not have side effects.
+    pub fn with_pure_arity(mut self, arity: usize) -> Self {
+        self.pure = true;
+        self.arity = Some(arity);
+        self
+    }
+
+    pub fn with_return_type(mut self, lua_type: LuaType) -> Self {
+        self.returns = Some(lua_type);
+        self
+    }
+
+    pub fn with_value_type(mut self, lua_type: LuaType) -> Self {
+        self.value_type = Some(lua_type);
+        self
+    }
+
+    pub fn with_child(mut self, child: GlobalEntry) -> Self {
+        self.insert_child(child);
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> GlobalKind {
+        self.kind
+    }
+
+    pub fn is_pure(&self) -> bool {
+        self.pure
+    }
+
+    pub fn is_callable(&self) -> bool {
+        self.kind == GlobalKind::Function
+    }
+
+    pub fn get_child(&self, name: &str) -> Option<&GlobalEntry> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    pub fn iter_children(&self) -> impl Iterator<Item = &GlobalEntry> {
+        self.children.iter()
+    }
+
+    /// Returns what is known about the type of the entry.
+    pub fn type_info(&self) -> Option<TypeInfo> {
+        match self.kind {
+            GlobalKind::Table => Some(TypeInfo::Value(LuaType::Table)),
+            GlobalKind::Function => Some(TypeInfo::Function {
+                returns: self.returns,
+                pure_arity: self.arity.filter(|_| self.pure),
+            }),
+            GlobalKind::Value => self.value_type.map(TypeInfo::Value),
+        }
+    }
+
+    fn insert_child(&mut self, child: GlobalEntry) {
+        if let Some(existing) = self
+            .children
+            .iter_mut()
+            .find(|existing| existing.name == child.name)
+        {
+            existing.merge(child);
+        } else {
+            self.children.push(child);
+        }
+    }
+
+    /// Merges the children of two tables, otherwise replaces the entry.
+    fn merge(&mut self, other: GlobalEntry) {
+        if self.kind == GlobalKind::Table && other.kind == GlobalKind::Table {
+            for child in other.children {
+                self.insert_child(child);
+            }
+        } else {
+            *self = other;
+        }
+    }
+
+    fn filter_target(&self, target: EnvironmentTarget) -> Option<Self> {
+        let is_available = self
+            .targets
+            .as_ref()
+            .is_none_or(|targets| targets.iter().any(|other| target.includes(*other)));
+
+        is_available.then(|| Self {
+            name: self.name.clone(),
+            kind: self.kind,
+            pure: self.pure,
+            arity: self.arity,
+            returns: self.returns,
+            value_type: self.value_type,
+            targets: None,
+            children: self
+                .children
+                .iter()
+                .filter_map(|child| child.filter_target(target))
+                .collect(),
+        })
+    }
+}
+
+fn get_builtin_entries() -> &'static [GlobalEntry] {
+    static ENTRIES: OnceLock<Vec<GlobalEntry>> = OnceLock::new();
+
+    ENTRIES.get_or_init(|| {
+        globals::BUILTIN_GLOBALS
+            .iter()
+            .map(globals::BuiltinGlobal::to_entry)
+            .collect()
+    })
+}
+
+/// A description of the globals available when running the code. Rules can use it to know
+/// which globals exist and which ones do not have side effects.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Environment {
+    target: Option<EnvironmentTarget>,
+    globals: BTreeMap<String, GlobalEntry>,
+}
+
+impl Environment {
+    /// Creates an environment with the built-in globals of the given target.
+    pub fn new(target: EnvironmentTarget) -> Self {
+        let mut environment = Self {
+            target: Some(target),
+            globals: BTreeMap::new(),
+        };
+
+        for entry in get_builtin_entries() {
+            environment.insert_global(entry.clone());
+        }
+
+        environment
+    }
+
+    /// Returns a shared environment with the built-in globals of the given target.
+    pub fn builtin(target: EnvironmentTarget) -> Arc<Self> {
+        static LUA_51: OnceLock<Arc<Environment>> = OnceLock::new();
+        static LUA_53: OnceLock<Arc<Environment>> = OnceLock::new();
+        static LUA_54: OnceLock<Arc<Environment>> = OnceLock::new();
+        static LUAU: OnceLock<Arc<Environment>> = OnceLock::new();
+        static ROBLOX: OnceLock<Arc<Environment>> = OnceLock::new();
+
+        let cell = match target {
+            EnvironmentTarget::Lua51 => &LUA_51,
+            EnvironmentTarget::Lua53 => &LUA_53,
+            EnvironmentTarget::Lua54 => &LUA_54,
+            EnvironmentTarget::Luau => &LUAU,
+            EnvironmentTarget::Roblox => &ROBLOX,
+        };
+
+        cell.get_or_init(|| Arc::new(Self::new(target))).clone()
+    }
+
+    pub fn with_global(mut self, entry: GlobalEntry) -> Self {
+        self.insert_global(entry);
+        self
+    }
+
+    /// Adds a global to the environment. When a table with the same name already exists
+    /// and the new entry is also a table, their children are merged. Otherwise, the new entry
+    /// replaces the existing one. Entries restricted to other targets are ignored.
+    pub fn insert_global(&mut self, entry: GlobalEntry) {
+        let entry = match self.target {
+            Some(target) => match entry.filter_target(target) {
+                Some(entry) => entry,
+                None => return,
+            },
+            None => entry,
+        };
+
+        if let Some(existing) = self.globals.get_mut(&entry.name) {
+            existing.merge(entry);
+        } else {
+            self.globals.insert(entry.name.clone(), entry);
+        }
+    }
+
+    pub fn target(&self) -> Option<EnvironmentTarget> {
+        self.target
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.globals.is_empty()
+    }
+
+    /// Returns the entry at the given path, where nested entries are separated by dots
+    /// (like `math.floor`).
+    pub fn get(&self, path: &str) -> Option<&GlobalEntry> {
+        let mut names = path.split('.');
+        let mut entry = self.globals.get(names.next()?)?;
+
+        for name in names {
+            entry = entry.get_child(name)?;
+        }
+
+        Some(entry)
+    }
+
+    pub fn has_global(&self, name: &str) -> bool {
+        self.globals.contains_key(name)
+    }
+
+    /// Iterates over the names of the globals, sorted alphabetically.
+    pub fn iter_global_names(&self) -> impl Iterator<Item = &str> {
+        self.globals.keys().map(String::as_str)
+    }
+
+    pub fn iter_globals(&self) -> impl Iterator<Item = &GlobalEntry> {
+        self.globals.values()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn builtin_entries_are_valid() {
+        assert!(!get_builtin_entries().is_empty());
+    }
+
+    #[test]
+    fn get_nested_entry() {
+        let environment = Environment::new(EnvironmentTarget::Lua51);
+
+        let floor = environment.get("math.floor").unwrap();
+
+        assert_eq!(floor.get_name(), "floor");
+        assert!(floor.is_callable());
+        assert_eq!(
+            floor.type_info(),
+            Some(TypeInfo::Function {
+                returns: Some(LuaType::Number),
+                pure_arity: None
+            })
+        );
+    }
+
+    #[test]
+    fn get_table_entry() {
+        let environment = Environment::new(EnvironmentTarget::Lua54);
+
+        assert_eq!(
+            environment.get("math").map(GlobalEntry::kind),
+            Some(GlobalKind::Table)
+        );
+    }
+
+    #[test]
+    fn get_missing_nested_entry() {
+        let environment = Environment::new(EnvironmentTarget::Lua51);
+
+        assert_eq!(environment.get("math.floor.value"), None);
+        assert_eq!(environment.get("math.unknown"), None);
+        assert_eq!(environment.get("unknown.floor"), None);
+    }
+
+    #[test]
+    fn pure_function_type_info() {
+        let environment = Environment::new(EnvironmentTarget::Luau);
+
+        assert_eq!(
+            environment.get("type").and_then(GlobalEntry::type_info),
+            Some(TypeInfo::Function {
+                returns: Some(LuaType::String),
+                pure_arity: Some(1)
+            })
+        );
+    }
+
+    #[test]
+    fn lua51_does_not_have_integer_limits() {
+        let environment = Environment::new(EnvironmentTarget::Lua51);
+
+        assert_eq!(environment.get("math.maxinteger"), None);
+        assert!(environment.get("math.pow").is_some());
+    }
+
+    #[test]
+    fn lua53_has_integer_limits() {
+        let environment = Environment::new(EnvironmentTarget::Lua53);
+
+        assert!(environment.get("math.maxinteger").is_some());
+        assert!(!environment.has_global("warn"));
+    }
+
+    #[test]
+    fn luau_does_not_have_io_library() {
+        assert!(!Environment::new(EnvironmentTarget::Luau).has_global("io"));
+    }
+
+    #[test]
+    fn roblox_includes_luau_globals() {
+        let environment = Environment::new(EnvironmentTarget::Roblox);
+
+        assert!(environment.has_global("game"));
+        assert!(environment.has_global("typeof"));
+        assert!(environment.get("math.clamp").is_some());
+        assert!(environment.get("debug.profilebegin").is_some());
+        assert!(Environment::new(EnvironmentTarget::Luau)
+            .get("debug.profilebegin")
+            .is_none());
+    }
+
+    #[test]
+    fn insert_new_global() {
+        let environment = Environment::default().with_global(GlobalEntry::table("MyFramework"));
+
+        assert_eq!(
+            environment.iter_global_names().collect::<Vec<_>>(),
+            ["MyFramework"]
+        );
+        assert_eq!(environment.target(), None);
+    }
+
+    #[test]
+    fn insert_table_merges_children() {
+        let environment = Environment::new(EnvironmentTarget::Lua51).with_global(
+            GlobalEntry::table("math")
+                .with_child(GlobalEntry::function("lerp").with_return_type(LuaType::Number)),
+        );
+
+        assert!(environment.get("math.lerp").is_some());
+        assert!(environment.get("math.floor").is_some());
+    }
+
+    #[test]
+    fn insert_function_replaces_table() {
+        let environment = Environment::new(EnvironmentTarget::Lua51)
+            .with_global(GlobalEntry::function("math").with_pure_arity(0));
+
+        let math = environment.get("math").unwrap();
+        assert!(math.is_callable());
+        assert!(math.is_pure());
+        assert_eq!(environment.get("math.floor"), None);
+    }
+
+    #[test]
+    fn deserialize_entry_from_configuration() {
+        let entry: GlobalEntry = json5::from_str(
+            "{ name: 'MyFramework', type: 'table', pure: false, children: [{ name: 'new', type: 'function', returns: 'table' }] }",
+        )
+        .unwrap();
+
+        assert_eq!(
+            entry,
+            GlobalEntry::table("MyFramework")
+                .with_child(GlobalEntry::function("new").with_return_type(LuaType::Table))
+        );
+    }
+
+    #[test]
+    fn deserialize_entry_with_unknown_field_errors() {
+        assert!(json5::from_str::<GlobalEntry>("{ name: 'a', type: 'table', prop: 1 }").is_err());
+    }
+}
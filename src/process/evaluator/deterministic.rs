@@ -0,0 +1,557 @@
+use std::convert::TryFrom;
+
+use super::{LuaValue, MAX_EXACT_INTEGER};
+
+/// The longest string that `string.rep` can produce, to avoid generating large literals.
+const MAX_REPEATED_LENGTH: usize = 256;
+
+/// A function of the standard library that the evaluator can call. Each function is computed
+/// to match the results of Lua 5.1, and is only evaluated with arguments for which the result
+/// is the same in every Lua version and on every platform (for example, `string.upper` is only
+/// evaluated on ASCII strings, since the result of other characters depends on the locale).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeterministicFunction {
+    MathAbs,
+    MathCeil,
+    MathFloor,
+    MathFmod,
+    MathMax,
+    MathMin,
+    StringByte,
+    StringChar,
+    StringFind,
+    StringFormat,
+    StringLen,
+    StringLower,
+    StringRep,
+    StringSub,
+    StringUpper,
+}
+
+const ALL_FUNCTIONS: [DeterministicFunction; 15] = [
+    DeterministicFunction::MathAbs,
+    DeterministicFunction::MathCeil,
+    DeterministicFunction::MathFloor,
+    DeterministicFunction::MathFmod,
+    DeterministicFunction::MathMax,
+    DeterministicFunction::MathMin,
+    DeterministicFunction::StringByte,
+    DeterministicFunction::StringChar,
+    DeterministicFunction::StringFind,
+    DeterministicFunction::StringFormat,
+    DeterministicFunction::StringLen,
+    DeterministicFunction::StringLower,
+    DeterministicFunction::StringRep,
+    DeterministicFunction::StringSub,
+    DeterministicFunction::StringUpper,
+];
+
+impl DeterministicFunction {
+    /// Returns every function that the evaluator can call.
+    pub fn all() -> &'static [Self] {
+        &ALL_FUNCTIONS
+    }
+
+    /// Finds a function from its name in the standard library (like `math.floor`).
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .find(|function| function.name() == name)
+            .copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MathAbs => "math.abs",
+            Self::MathCeil => "math.ceil",
+            Self::MathFloor => "math.floor",
+            Self::MathFmod => "math.fmod",
+            Self::MathMax => "math.max",
+            Self::MathMin => "math.min",
+            Self::StringByte => "string.byte",
+            Self::StringChar => "string.char",
+            Self::StringFind => "string.find",
+            Self::StringFormat => "string.format",
+            Self::StringLen => "string.len",
+            Self::StringLower => "string.lower",
+            Self::StringRep => "string.rep",
+            Self::StringSub => "string.sub",
+            Self::StringUpper => "string.upper",
+        }
+    }
+
+    /// Returns true when the number returned by the function is an integer or a float
+    /// depending on its arguments (in Lua 5.3 and later).
+    pub(crate) fn returns_argument_subtype(&self) -> bool {
+        matches!(
+            self,
+            Self::MathAbs | Self::MathFmod | Self::MathMax | Self::MathMin
+        )
+    }
+
+    /// Calls the function with the given arguments. Returns `None` when the call would fail
+    /// or when its result could be different in another Lua version or on another platform.
+    pub(crate) fn call(&self, arguments: &[LuaValue]) -> Option<Vec<LuaValue>> {
+        match self {
+            Self::MathAbs => match arguments {
+                [value] => number_result(number(value)?.abs()),
+                _ => None,
+            },
+            Self::MathCeil => match arguments {
+                [value] => integral_result(number(value)?.ceil()),
+                _ => None,
+            },
+            Self::MathFloor => match arguments {
+                [value] => integral_result(number(value)?.floor()),
+                _ => None,
+            },
+            Self::MathFmod => match arguments {
+                [dividend, divisor] => {
+                    let divisor = number(divisor)?;
+                    if divisor == 0.0 {
+                        return None;
+                    }
+                    // the remainder of Rust numbers has the sign of the dividend, like the
+                    // `fmod` function of C
+                    number_result(number(dividend)? % divisor)
+                }
+                _ => None,
+            },
+            Self::MathMax => fold_numbers(arguments, |value, current| value > current),
+            Self::MathMin => fold_numbers(arguments, |value, current| value < current),
+            Self::StringByte => string_byte(arguments),
+            Self::StringChar => arguments
+                .iter()
+                .map(|value| {
+                    integer(value)
+                        .filter(|code| (0..=0x7F).contains(code))
+                        .map(|code| code as u8 as char)
+                })
+                .collect::<Option<String>>()
+                .map(|string| vec![LuaValue::String(string)]),
+            Self::StringFind => string_find(arguments),
+            Self::StringFormat => match arguments {
+                [format, values @ ..] => {
+                    string_result(string_format(string(format)?, values)?.into_bytes())
+                }
+                _ => None,
+            },
+            Self::StringLen => match arguments {
+                [value] => Some(vec![LuaValue::Number(string(value)?.len() as f64)]),
+                _ => None,
+            },
+            Self::StringLower => match arguments {
+                [value] => ascii_string(value)
+                    .map(|value| vec![LuaValue::String(value.to_ascii_lowercase())]),
+                _ => None,
+            },
+            Self::StringRep => match arguments {
+                // Lua 5.2 and later accept a separator as the third argument
+                [value, count] => {
+                    let value = string(value)?;
+                    let count = integer(count)?.max(0) as usize;
+
+                    if value.len().checked_mul(count)? > MAX_REPEATED_LENGTH {
+                        return None;
+                    }
+
+                    Some(vec![LuaValue::String(value.repeat(count))])
+                }
+                _ => None,
+            },
+            Self::StringSub => string_sub(arguments),
+            Self::StringUpper => match arguments {
+                [value] => ascii_string(value)
+                    .map(|value| vec![LuaValue::String(value.to_ascii_uppercase())]),
+                _ => None,
+            },
+        }
+    }
+}
+
+fn number(value: &LuaValue) -> Option<f64> {
+    match value {
+        LuaValue::Number(number) if number.is_finite() => Some(*number),
+        _ => None,
+    }
+}
+
+fn integer(value: &LuaValue) -> Option<i64> {
+    number(value)
+        .filter(|number| number.fract() == 0.0 && number.abs() <= MAX_EXACT_INTEGER)
+        .map(|number| number as i64)
+}
+
+fn string(value: &LuaValue) -> Option<&str> {
+    match value {
+        LuaValue::String(string) => Some(string),
+        _ => None,
+    }
+}
+
+/// The characters outside of ASCII depend on the locale used by Lua.
+fn ascii_string(value: &LuaValue) -> Option<&str> {
+    string(value).filter(|string| string.is_ascii())
+}
+
+/// Non-finite numbers and `-0` cannot be written as literals (and `-0` becomes `0` when it is
+/// converted to an integer in Lua 5.3 and later).
+fn number_result(value: f64) -> Option<Vec<LuaValue>> {
+    if value.is_finite() && !(value == 0.0 && value.is_sign_negative()) {
+        Some(vec![LuaValue::Number(value)])
+    } else {
+        None
+    }
+}
+
+/// Lua 5.3 and later return a float when the result does not fit in an integer.
+fn integral_result(value: f64) -> Option<Vec<LuaValue>> {
+    if value.abs() <= MAX_EXACT_INTEGER {
+        number_result(value)
+    } else {
+        None
+    }
+}
+
+/// Strings are only known when they are valid UTF-8.
+fn string_result(bytes: Vec<u8>) -> Option<Vec<LuaValue>> {
+    String::from_utf8(bytes)
+        .ok()
+        .map(|string| vec![LuaValue::String(string)])
+}
+
+/// Keeps the first number for which `replace(number, current)` is false with every following
+/// number, like the `math.max` and `math.min` functions.
+fn fold_numbers(arguments: &[LuaValue], replace: fn(f64, f64) -> bool) -> Option<Vec<LuaValue>> {
+    let (first, rest) = arguments.split_first()?;
+    let mut current = number(first)?;
+
+    for value in rest {
+        let value = number(value)?;
+        if replace(value, current) {
+            current = value;
+        }
+    }
+
+    number_result(current)
+}
+
+/// Converts a relative string position (negative positions start from the end).
+fn relative_position(position: i64, length: usize) -> i64 {
+    if position < 0 {
+        (position + length as i64 + 1).max(0)
+    } else {
+        position
+    }
+}
+
+fn string_sub(arguments: &[LuaValue]) -> Option<Vec<LuaValue>> {
+    let (value, start, end) = match arguments {
+        [value, start] => (string(value)?, integer(start)?, -1),
+        [value, start, end] => (string(value)?, integer(start)?, integer(end)?),
+        _ => return None,
+    };
+    let length = value.len();
+
+    let start = relative_position(start, length).max(1);
+    let end = relative_position(end, length).min(length as i64);
+
+    if start > end {
+        return Some(vec![LuaValue::String(String::new())]);
+    }
+
+    string_result(value.as_bytes()[start as usize - 1..end as usize].to_vec())
+}
+
+fn string_byte(arguments: &[LuaValue]) -> Option<Vec<LuaValue>> {
+    let (value, start, end) = match arguments {
+        [value] => (string(value)?, 1, None),
+        [value, start] => (string(value)?, integer(start)?, None),
+        [value, start, end] => (string(value)?, integer(start)?, Some(integer(end)?)),
+        _ => return None,
+    };
+    let length = value.len();
+
+    let start = relative_position(start, length);
+    let end = end.map_or(start, |end| relative_position(end, length));
+
+    let start = start.max(1);
+    let end = end.min(length as i64);
+
+    if start > end {
+        return Some(Vec::new());
+    }
+
+    Some(
+        value.as_bytes()[start as usize - 1..end as usize]
+            .iter()
+            .map(|byte| LuaValue::Number(*byte as f64))
+            .collect(),
+    )
+}
+
+/// Only plain searches are evaluated (when the fourth argument is `true`), since patterns
+/// are not supported.
+fn string_find(arguments: &[LuaValue]) -> Option<Vec<LuaValue>> {
+    let (value, pattern, start) = match arguments {
+        [value, pattern, start, LuaValue::True] => (string(value)?, string(pattern)?, start),
+        _ => return None,
+    };
+    let length = value.len();
+
+    let start = (relative_position(integer(start)?, length) - 1).max(0) as usize;
+
+    // Lua 5.1 searches from the end of the string, while later versions do not find anything
+    if start > length {
+        return None;
+    }
+
+    let found = value.as_bytes()[start..]
+        .windows(pattern.len().max(1))
+        .position(|window| pattern.is_empty() || window == pattern.as_bytes())
+        .map(|index| index + start)
+        .or_else(|| {
+            // an empty pattern is also found at the end of the string
+            (pattern.is_empty() && start == length).then_some(start)
+        });
+
+    Some(match found {
+        Some(index) => vec![
+            LuaValue::Number((index + 1) as f64),
+            LuaValue::Number((index + pattern.len()) as f64),
+        ],
+        None => vec![LuaValue::Nil],
+    })
+}
+
+/// A conversion specification of `string.format`, like `%-5d`.
+#[derive(Debug, Default)]
+struct FormatSpecification {
+    left_align: bool,
+    zero_pad: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl FormatSpecification {
+    fn pad(&self, sign: &str, content: &str) -> String {
+        let length = sign.len() + content.len();
+        let padding = self.width.saturating_sub(length);
+
+        if self.left_align {
+            format!("{}{}{}", sign, content, " ".repeat(padding))
+        } else if self.zero_pad {
+            format!("{}{}{}", sign, "0".repeat(padding), content)
+        } else {
+            format!("{}{}{}", " ".repeat(padding), sign, content)
+        }
+    }
+}
+
+/// Reads up to two digits, like Lua does for the width and the precision.
+fn read_digits(bytes: &[u8], index: &mut usize) -> Option<usize> {
+    let start = *index;
+    while bytes.get(*index).is_some_and(u8::is_ascii_digit) {
+        *index += 1;
+    }
+
+    match *index - start {
+        0 => Some(0),
+        1 | 2 => std::str::from_utf8(&bytes[start..*index])
+            .ok()?
+            .parse()
+            .ok(),
+        _ => None,
+    }
+}
+
+/// Formats the `%d`, `%i`, `%x`, `%X` and `%s` specifications (with the `-` and `0` flags, a
+/// width and a precision for strings). Other specifications are not evaluated, since the
+/// text of floats can depend on the platform.
+fn string_format(format: &str, values: &[LuaValue]) -> Option<String> {
+    let bytes = format.as_bytes();
+    let mut result = String::new();
+    let mut values = values.iter();
+    let mut index = 0;
+
+    while let Some(byte) = bytes.get(index) {
+        index += 1;
+
+        if *byte != b'%' {
+            let next = bytes[index..]
+                .iter()
+                .position(|byte| *byte == b'%')
+                .map_or(bytes.len(), |position| position + index);
+            result.push_str(std::str::from_utf8(&bytes[index - 1..next]).ok()?);
+            index = next;
+            continue;
+        }
+
+        if bytes.get(index) == Some(&b'%') {
+            index += 1;
+            result.push('%');
+            continue;
+        }
+
+        let mut specification = FormatSpecification::default();
+        loop {
+            match bytes.get(index) {
+                Some(b'-') if !specification.left_align => specification.left_align = true,
+                Some(b'0') if !specification.zero_pad => specification.zero_pad = true,
+                _ => break,
+            }
+            index += 1;
+        }
+
+        specification.width = read_digits(bytes, &mut index)?;
+
+        if bytes.get(index) == Some(&b'.') {
+            index += 1;
+            specification.precision = Some(read_digits(bytes, &mut index)?);
+        }
+
+        let conversion = *bytes.get(index)?;
+        index += 1;
+
+        let value = values.next()?;
+
+        let formatted = match conversion {
+            // Lua 5.1 formats integers as a C `long`, which only has 32 bits on Windows
+            b'd' | b'i' if specification.precision.is_none() => {
+                let value = i32::try_from(integer(value)?).ok()?;
+                let sign = if value < 0 { "-" } else { "" };
+                specification.pad(sign, &value.unsigned_abs().to_string())
+            }
+            b'x' | b'X' if specification.precision.is_none() => {
+                let value = u32::try_from(i32::try_from(integer(value)?).ok()?).ok()?;
+                let digits = if conversion == b'x' {
+                    format!("{:x}", value)
+                } else {
+                    format!("{:X}", value)
+                };
+                specification.pad("", &digits)
+            }
+            b's' if !specification.zero_pad => {
+                let value = string(value).filter(|value| !value.contains('\0'))?;
+                let value = match specification.precision {
+                    Some(precision) if precision < value.len() => value.get(..precision)?,
+                    _ => value,
+                };
+                specification.pad("", value)
+            }
+            _ => return None,
+        };
+
+        result.push_str(&formatted);
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn call(function: &str, arguments: &[LuaValue]) -> Option<Vec<LuaValue>> {
+        DeterministicFunction::parse(function)
+            .expect("unknown function")
+            .call(arguments)
+    }
+
+    fn number(value: f64) -> LuaValue {
+        LuaValue::Number(value)
+    }
+
+    fn string(value: &str) -> LuaValue {
+        LuaValue::String(value.to_owned())
+    }
+
+    #[test]
+    fn parse_every_function_name() {
+        for function in DeterministicFunction::all() {
+            assert_eq!(
+                DeterministicFunction::parse(function.name()),
+                Some(*function)
+            );
+        }
+    }
+
+    #[test]
+    fn parse_unknown_function() {
+        assert_eq!(DeterministicFunction::parse("math.random"), None);
+    }
+
+    #[test]
+    fn floor_negative_zero_is_not_evaluated() {
+        assert_eq!(call("math.ceil", &[number(-0.5)]), None);
+    }
+
+    #[test]
+    fn floor_large_number_is_not_evaluated() {
+        assert_eq!(call("math.floor", &[number(1e300)]), None);
+    }
+
+    #[test]
+    fn fmod_by_zero_is_not_evaluated() {
+        assert_eq!(call("math.fmod", &[number(1.0), number(0.0)]), None);
+    }
+
+    #[test]
+    fn max_with_string_is_not_evaluated() {
+        assert_eq!(call("math.max", &[number(1.0), string("2")]), None);
+    }
+
+    #[test]
+    fn byte_range_returns_multiple_values() {
+        assert_eq!(
+            call("string.byte", &[string("abc"), number(1.0), number(2.0)]),
+            Some(vec![number(97.0), number(98.0)])
+        );
+    }
+
+    #[test]
+    fn sub_of_multibyte_character_is_not_evaluated() {
+        assert_eq!(
+            call("string.sub", &[string("é"), number(1.0), number(1.0)]),
+            None
+        );
+    }
+
+    #[test]
+    fn char_outside_of_ascii_is_not_evaluated() {
+        assert_eq!(call("string.char", &[number(200.0)]), None);
+    }
+
+    #[test]
+    fn rep_with_separator_is_not_evaluated() {
+        assert_eq!(
+            call("string.rep", &[string("a"), number(2.0), string(",")]),
+            None
+        );
+    }
+
+    #[test]
+    fn rep_longer_than_limit_is_not_evaluated() {
+        assert_eq!(call("string.rep", &[string("ab"), number(1000.0)]), None);
+    }
+
+    #[test]
+    fn find_with_pattern_is_not_evaluated() {
+        assert_eq!(call("string.find", &[string("abc"), string("b")]), None);
+    }
+
+    #[test]
+    fn format_float_is_not_evaluated() {
+        assert_eq!(call("string.format", &[string("%g"), number(0.1)]), None);
+    }
+
+    #[test]
+    fn format_with_missing_argument_is_not_evaluated() {
+        assert_eq!(call("string.format", &[string("%d")]), None);
+    }
+
+    #[test]
+    fn format_with_long_width_is_not_evaluated() {
+        assert_eq!(call("string.format", &[string("%100d"), number(1.0)]), None);
+    }
+}
@@ -1,13 +1,35 @@
+mod deterministic;
 mod lua_value;
+mod number_fold;
+mod stdlib;
 
+pub use deterministic::*;
 pub use lua_value::*;
+pub use number_fold::*;
+pub use stdlib::*;
+
+use std::borrow::Cow;
+use std::sync::Arc;
 
 use crate::nodes::*;
+use crate::process::{Environment, EnvironmentTarget, GlobalEntry};
+
+/// A call to the `select` function of the standard library, with arguments that do not
+/// expand to multiple values (no calls and no variable arguments).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SelectCall<'a> {
+    /// `select("#", ...)`, which returns the number of arguments.
+    Count(usize),
+    /// `select(n, ...)` with a positive integer, which returns the arguments starting at `n`.
+    Tail(Vec<Cow<'a, Expression>>),
+}
 
 /// A struct to convert an Expression node into a LuaValue object.
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Evaluator {
     pure_metamethods: bool,
+    stdlib: Option<Arc<Environment>>,
+    deterministic_functions: Vec<DeterministicFunction>,
 }
 
 impl Evaluator {
@@ -20,6 +42,234 @@ impl Evaluator {
         self
     }
 
+    /// Assumes that the globals of the standard library of the given Lua version are not
+    /// redefined. This lets the evaluator know the type of the values returned by the standard
+    /// library (for example, `type(math.pi)` evaluates to `"number"`).
+    pub fn with_stdlib(self, version: LuaVersion) -> Self {
+        self.with_environment(Environment::builtin(EnvironmentTarget::from(version)))
+    }
+
+    /// Assumes that the globals described by the given environment are not redefined. Like
+    /// [`with_stdlib`](Self::with_stdlib), but the environment can also describe globals that
+    /// are not part of the standard library.
+    pub fn with_environment(mut self, environment: Arc<Environment>) -> Self {
+        self.stdlib = Some(environment);
+        self
+    }
+
+    /// Calls the given functions of the standard library when their arguments are known (for
+    /// example, `string.rep("-", 3)` evaluates to `"---"`). The functions are only called when
+    /// the standard library is known (see [`with_stdlib`](Self::with_stdlib)).
+    pub fn with_deterministic_functions(
+        mut self,
+        functions: impl IntoIterator<Item = DeterministicFunction>,
+    ) -> Self {
+        self.deterministic_functions = functions.into_iter().collect();
+        self
+    }
+
+    /// Returns the type of the value of an expression, when it is known.
+    pub fn evaluate_type(&self, expression: &Expression) -> Option<LuaType> {
+        match self.evaluate(expression) {
+            LuaValue::False | LuaValue::True => Some(LuaType::Boolean),
+            LuaValue::Function => Some(LuaType::Function),
+            LuaValue::Nil => Some(LuaType::Nil),
+            LuaValue::Number(_) => Some(LuaType::Number),
+            LuaValue::String(_) => Some(LuaType::String),
+            LuaValue::Table => Some(LuaType::Table),
+            LuaValue::Unknown => match expression {
+                Expression::Parenthese(parenthese) => {
+                    self.evaluate_type(parenthese.inner_expression())
+                }
+                Expression::TypeCast(type_cast) => self.evaluate_type(type_cast.get_expression()),
+                Expression::Field(field) => match self.get_stdlib_field_info(field)? {
+                    TypeInfo::Value(lua_type) => Some(lua_type),
+                    TypeInfo::Function { .. } => Some(LuaType::Function),
+                },
+                Expression::Identifier(identifier) => {
+                    match self.get_stdlib_info(identifier.get_name())? {
+                        TypeInfo::Value(lua_type) => Some(lua_type),
+                        TypeInfo::Function { .. } => Some(LuaType::Function),
+                    }
+                }
+                Expression::Call(call) => match self.get_stdlib_function_info(call)? {
+                    TypeInfo::Function { returns, .. } => returns,
+                    TypeInfo::Value(_) => None,
+                },
+                _ => None,
+            },
+        }
+    }
+
+    fn get_stdlib_info(&self, name: &str) -> Option<TypeInfo> {
+        self.stdlib
+            .as_ref()
+            .and_then(|environment| environment.get(name))
+            .and_then(GlobalEntry::type_info)
+    }
+
+    fn get_stdlib_field_info(&self, field: &FieldExpression) -> Option<TypeInfo> {
+        match field.get_prefix() {
+            Prefix::Identifier(library) => self.get_stdlib_info(&format!(
+                "{}.{}",
+                library.get_name(),
+                field.get_field().get_name()
+            )),
+            _ => None,
+        }
+    }
+
+    fn get_stdlib_function_info(&self, call: &FunctionCall) -> Option<TypeInfo> {
+        if call.get_method().is_some() {
+            return None;
+        }
+
+        match call.get_prefix() {
+            Prefix::Identifier(identifier) => self.get_stdlib_info(identifier.get_name()),
+            Prefix::Field(field) => self.get_stdlib_field_info(field),
+            _ => None,
+        }
+    }
+
+    /// Returns the `select` call made by the given call, if its result is known from its
+    /// arguments.
+    pub(crate) fn get_select_call<'a>(&self, call: &'a FunctionCall) -> Option<SelectCall<'a>> {
+        let is_select_function = matches!(
+            call.get_prefix(),
+            Prefix::Identifier(identifier) if identifier.get_name() == "select"
+        );
+
+        if !is_select_function || self.get_stdlib_function_info(call).is_none() {
+            return None;
+        }
+
+        let mut arguments = call.get_arguments().iter_expressions();
+
+        let selector = arguments.next()?;
+        let values: Vec<_> = arguments.collect();
+
+        if values.iter().any(|value| {
+            matches!(
+                value.as_ref(),
+                Expression::Call(_) | Expression::VariableArguments(_)
+            )
+        }) {
+            return None;
+        }
+
+        match self.evaluate(&selector) {
+            LuaValue::String(string) if string == "#" => Some(SelectCall::Count(values.len())),
+            LuaValue::Number(index) if index >= 1.0 && index.fract() == 0.0 => {
+                let start = (index as usize - 1).min(values.len());
+                Some(SelectCall::Tail(values[start..].to_vec()))
+            }
+            _ => None,
+        }
+    }
+
+    fn get_deterministic_function(&self, call: &FunctionCall) -> Option<DeterministicFunction> {
+        if self.deterministic_functions.is_empty() || call.get_method().is_some() {
+            return None;
+        }
+
+        let Prefix::Field(field) = call.get_prefix() else {
+            return None;
+        };
+        let Prefix::Identifier(library) = field.get_prefix() else {
+            return None;
+        };
+
+        let function = DeterministicFunction::parse(&format!(
+            "{}.{}",
+            library.get_name(),
+            field.get_field().get_name()
+        ))?;
+
+        (self.deterministic_functions.contains(&function)
+            && self.get_stdlib_function_info(call).is_some())
+        .then_some(function)
+    }
+
+    /// Returns the values returned by a call to one of the deterministic functions given to
+    /// [`with_deterministic_functions`](Self::with_deterministic_functions), when all its
+    /// arguments are known.
+    pub fn evaluate_deterministic_call(&self, call: &FunctionCall) -> Option<Vec<LuaValue>> {
+        let function = self.get_deterministic_function(call)?;
+
+        let arguments: Vec<_> = call.get_arguments().iter_expressions().collect();
+
+        let mut values = Vec::with_capacity(arguments.len());
+
+        for (index, value) in arguments.iter().enumerate() {
+            match value.as_ref() {
+                // the last argument expands to all the values returned by the call
+                Expression::Call(call) if index + 1 == arguments.len() => {
+                    values.extend(self.evaluate_deterministic_call(call)?);
+                }
+                Expression::VariableArguments(_) => return None,
+                _ => match self.evaluate(value) {
+                    LuaValue::Unknown => return None,
+                    value => values.push(value),
+                },
+            }
+        }
+
+        let results = function.call(&values)?;
+
+        // in Lua 5.3 and later, an integral result is a float when one of the arguments is
+        if function.returns_argument_subtype()
+            && results
+                .iter()
+                .any(|result| matches!(result, LuaValue::Number(number) if number.fract() == 0.0))
+            && !arguments
+                .iter()
+                .all(|value| number_subtype(value) == Some(NumberSubtype::Integer))
+        {
+            return None;
+        }
+
+        Some(results)
+    }
+
+    fn evaluate_call(&self, call: &FunctionCall) -> LuaValue {
+        if let Some(results) = self.evaluate_deterministic_call(call) {
+            return results.into_iter().next().unwrap_or(LuaValue::Nil);
+        }
+
+        if let Some(select) = self.get_select_call(call) {
+            return match select {
+                SelectCall::Count(count) => LuaValue::Number(count as f64),
+                SelectCall::Tail(values) => values
+                    .first()
+                    .map(|value| self.evaluate(value))
+                    .unwrap_or(LuaValue::Nil),
+            };
+        }
+
+        let is_type_function = matches!(
+            call.get_prefix(),
+            Prefix::Identifier(identifier) if identifier.get_name() == "type"
+        );
+
+        if !is_type_function || self.get_stdlib_function_info(call).is_none() {
+            return LuaValue::Unknown;
+        }
+
+        let arguments = call.get_arguments();
+        let argument_type = if arguments.len() == 1 {
+            arguments
+                .iter_expressions()
+                .next()
+                .and_then(|value| self.evaluate_type(&value))
+        } else {
+            None
+        };
+
+        argument_type
+            .map(|lua_type| LuaValue::from(lua_type.as_str()))
+            .unwrap_or(LuaValue::Unknown)
+    }
+
     pub fn evaluate(&self, expression: &Expression) -> LuaValue {
         match expression {
             Expression::False(_) => LuaValue::False,
@@ -69,8 +319,8 @@ impl Evaluator {
                 LuaValue::String(result)
             }
             Expression::TypeCast(type_cast) => self.evaluate(type_cast.get_expression()),
-            Expression::Call(_)
-            | Expression::Field(_)
+            Expression::Call(call) => self.evaluate_call(call),
+            Expression::Field(_)
             | Expression::Identifier(_)
             | Expression::Index(_)
             | Expression::VariableArguments(_) => LuaValue::Unknown,
@@ -228,9 +478,35 @@ impl Evaluator {
         }
     }
 
-    #[inline]
-    fn call_has_side_effects(&self, _call: &FunctionCall) -> bool {
-        true
+    fn call_has_side_effects(&self, call: &FunctionCall) -> bool {
+        if self.get_select_call(call).is_some() || self.evaluate_deterministic_call(call).is_some()
+        {
+            return call
+                .get_arguments()
+                .iter_expressions()
+                .any(|value| self.has_side_effects(&value));
+        }
+
+        let pure_arity = match self.get_stdlib_function_info(call) {
+            Some(TypeInfo::Function {
+                pure_arity: Some(arity),
+                ..
+            }) => arity,
+            _ => return true,
+        };
+
+        let arguments = call.get_arguments();
+
+        arguments.len() != pure_arity
+            || arguments.iter_expressions().any(|value| {
+                self.has_side_effects(&value)
+                    || match value.as_ref() {
+                        // the argument count is only known when a call returns a value
+                        Expression::Call(_) => self.evaluate_type(&value).is_none(),
+                        Expression::VariableArguments(_) => true,
+                        _ => false,
+                    }
+            })
     }
 
     #[inline]
@@ -246,6 +522,9 @@ impl Evaluator {
 
     #[inline]
     fn field_has_side_effects(&self, field: &FieldExpression) -> bool {
+        if self.get_stdlib_field_info(field).is_some() {
+            return false;
+        }
         !self.pure_metamethods || self.prefix_has_side_effects(field.get_prefix())
     }
 
@@ -427,10 +706,37 @@ impl Evaluator {
                     _ => LuaValue::Unknown,
                 }
             }
-            _ => LuaValue::Unknown,
+            UnaryOperator::Length => match expression.get_expression() {
+                Expression::Table(table) => self.evaluate_table_length(table),
+                _ => LuaValue::Unknown,
+            },
         }
     }
 
+    /// The length of a table constructor is only known when it creates a sequence of values
+    /// that are never nil. The last value must not expand to multiple values (like a call).
+    fn evaluate_table_length(&self, table: &TableExpression) -> LuaValue {
+        let entries = table.get_entries();
+
+        if let Some(TableEntry::Value(Expression::Call(_) | Expression::VariableArguments(_))) =
+            entries.last()
+        {
+            return LuaValue::Unknown;
+        }
+
+        for entry in entries {
+            match entry {
+                TableEntry::Value(value) => match self.evaluate_type(value) {
+                    Some(LuaType::Nil) | None => return LuaValue::Unknown,
+                    Some(_) => {}
+                },
+                TableEntry::Field(_) | TableEntry::Index(_) => return LuaValue::Unknown,
+            }
+        }
+
+        LuaValue::Number(entries.len() as f64)
+    }
+
     fn evaluate_if(&self, expression: &IfExpression) -> LuaValue {
         let condition = self.evaluate(expression.get_condition());
 
@@ -525,6 +831,20 @@ mod test {
             => LuaValue::from(2.0),
         if_expression_elseif_always_false(IfExpression::new(false, 1.0, 0.0).with_branch(false, 2.0))
             => LuaValue::from(0.0),
+        length_of_empty_table(UnaryExpression::new(UnaryOperator::Length, TableExpression::default()))
+            => LuaValue::from(0.0),
+        length_of_table_with_values(UnaryExpression::new(
+            UnaryOperator::Length,
+            TableExpression::default().append_array_value(true).append_array_value(1.0),
+        )) => LuaValue::from(2.0),
+        length_of_table_with_nil_value(UnaryExpression::new(
+            UnaryOperator::Length,
+            TableExpression::default().append_array_value(true).append_array_value(Expression::nil()),
+        )) => LuaValue::Unknown,
+        length_of_table_with_trailing_variable_arguments(UnaryExpression::new(
+            UnaryOperator::Length,
+            TableExpression::default().append_array_value(Expression::variable_arguments()),
+        )) => LuaValue::Unknown,
     );
 
     mod binary_expressions {
@@ -0,0 +1,278 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::generator::{DenseLuaGenerator, LuaGenerator};
+use crate::nodes::{BinaryOperator, Expression, LastStatement, NumberExpression, UnaryOperator};
+use crate::Parser;
+
+use super::{Evaluator, LuaValue};
+
+/// The reason why a computed number should not replace the expression it was computed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NumberFoldIssue {
+    /// The literal generated for the number does not read back as the same number.
+    InexactLiteral { literal: String },
+    /// The operands are integers, but the computed number is not their exact result.
+    IntegerPrecisionLoss { exact: i128 },
+}
+
+impl fmt::Display for NumberFoldIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InexactLiteral { literal } => {
+                write!(
+                    f,
+                    "the literal `{}` does not read back as the same number",
+                    literal
+                )
+            }
+            Self::IntegerPrecisionLoss { exact } => write!(
+                f,
+                "the exact result `{}` cannot be represented without losing precision",
+                exact
+            ),
+        }
+    }
+}
+
+/// Converts a number computed from an expression into a number literal, unless replacing the
+/// expression with the literal could change the value observed by the program.
+pub fn fold_number(
+    expression: &Expression,
+    value: f64,
+    evaluator: &Evaluator,
+) -> Result<Expression, NumberFoldIssue> {
+    if let Some(exact) = exact_integer_result(expression, evaluator) {
+        if value as i128 != exact {
+            return Err(NumberFoldIssue::IntegerPrecisionLoss { exact });
+        }
+    }
+
+    let literal = Expression::from(value);
+    let mut generator = DenseLuaGenerator::default();
+    generator.write_expression(&literal);
+    let spelling = generator.into_string();
+
+    match read_number(&spelling) {
+        Some(read_value)
+            if read_value.to_bits() == value.to_bits()
+                || (read_value.is_nan() && value.is_nan()) =>
+        {
+            Ok(literal)
+        }
+        _ => Err(NumberFoldIssue::InexactLiteral { literal: spelling }),
+    }
+}
+
+/// The largest magnitude under which every integer can be represented by a number.
+pub(crate) const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+fn as_integer(value: LuaValue) -> Option<i128> {
+    match value {
+        LuaValue::Number(number)
+            if number.is_finite() && number.fract() == 0.0 && number.abs() <= MAX_EXACT_INTEGER =>
+        {
+            Some(number as i128)
+        }
+        _ => None,
+    }
+}
+
+/// Computes the exact result of an arithmetic operation on integer operands.
+fn exact_integer_result(expression: &Expression, evaluator: &Evaluator) -> Option<i128> {
+    let Expression::Binary(binary) = expression else {
+        return None;
+    };
+
+    let left = as_integer(evaluator.evaluate(binary.left()))?;
+    let right = as_integer(evaluator.evaluate(binary.right()))?;
+
+    match binary.operator() {
+        BinaryOperator::Plus => left.checked_add(right),
+        BinaryOperator::Minus => left.checked_sub(right),
+        BinaryOperator::Asterisk => left.checked_mul(right),
+        BinaryOperator::Caret => u32::try_from(right)
+            .ok()
+            .and_then(|exponent| left.checked_pow(exponent)),
+        _ => None,
+    }
+}
+
+/// The representation of a number in Lua 5.3 and later, which changes the text returned by
+/// `tostring` (like `4` and `4.0`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NumberSubtype {
+    Integer,
+    Float,
+}
+
+impl NumberSubtype {
+    fn combine(self, other: Self) -> Self {
+        if self == Self::Integer && other == Self::Integer {
+            Self::Integer
+        } else {
+            Self::Float
+        }
+    }
+}
+
+fn literal_subtype(number: &NumberExpression) -> NumberSubtype {
+    let mut generator = DenseLuaGenerator::default();
+    generator.write_expression(&Expression::from(number.clone()));
+    let spelling = generator.into_string();
+
+    let is_float = match number {
+        NumberExpression::Decimal(_) => {
+            spelling.contains(['.', 'e', 'E']) || spelling.parse::<i64>().is_err()
+        }
+        NumberExpression::Hex(_) => spelling.contains(['.', 'p', 'P']),
+        NumberExpression::Binary(_) => false,
+    };
+
+    if is_float {
+        NumberSubtype::Float
+    } else {
+        NumberSubtype::Integer
+    }
+}
+
+/// Returns the subtype of the number produced by an expression, when it is known.
+pub(crate) fn number_subtype(expression: &Expression) -> Option<NumberSubtype> {
+    match expression {
+        Expression::Number(number) => Some(literal_subtype(number)),
+        Expression::Parenthese(parenthese) => number_subtype(parenthese.inner_expression()),
+        Expression::TypeCast(type_cast) => number_subtype(type_cast.get_expression()),
+        Expression::Unary(unary) => match unary.operator() {
+            UnaryOperator::Minus => number_subtype(unary.get_expression()),
+            _ => None,
+        },
+        Expression::Binary(binary) => match binary.operator() {
+            BinaryOperator::Slash | BinaryOperator::Caret => Some(NumberSubtype::Float),
+            BinaryOperator::Plus
+            | BinaryOperator::Minus
+            | BinaryOperator::Asterisk
+            | BinaryOperator::DoubleSlash
+            | BinaryOperator::Percent => {
+                Some(number_subtype(binary.left())?.combine(number_subtype(binary.right())?))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn read_number(spelling: &str) -> Option<f64> {
+    let block = Parser::default()
+        .parse(&format!("return {}", spelling))
+        .ok()?;
+
+    match block.get_last_statement()? {
+        LastStatement::Return(statement) => {
+            match Evaluator::default().evaluate(statement.iter_expressions().next()?) {
+                LuaValue::Number(value) => Some(value),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    macro_rules! test_fold {
+        ($($name:ident ($code:literal) => $folded:expr),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    let block = Parser::default()
+                        .parse(&format!("return {}", $code))
+                        .expect("code should parse");
+                    let expression = match block.get_last_statement() {
+                        Some(LastStatement::Return(statement)) => {
+                            statement.iter_expressions().next().unwrap().clone()
+                        }
+                        _ => panic!("return statement expected"),
+                    };
+                    let evaluator = Evaluator::default();
+                    let value = match evaluator.evaluate(&expression) {
+                        LuaValue::Number(value) => value,
+                        value => panic!("number expected, got {:?}", value),
+                    };
+
+                    pretty_assertions::assert_eq!(
+                        fold_number(&expression, value, &evaluator).is_ok(),
+                        $folded
+                    );
+                }
+            )*
+        };
+    }
+
+    test_fold!(
+        integer_addition("1 + 2") => true,
+        integer_multiplication("12 * 34") => true,
+        power_of_two("2 ^ 10") => true,
+        power_of_two_at_limit("2 ^ 53") => true,
+        division("1 / 3") => true,
+        decimal_addition("0.1 + 0.2") => true,
+        fraction_addition("0.5 + 0.25") => true,
+        small_number("2 * 1e-50") => true,
+        negative_zero("-0 + -0") => true,
+        infinity("1 / 0") => true,
+        not_a_number("0 / 0") => true,
+        large_exact_multiplication("2 ^ 52 * 2") => true,
+        power_above_integer_precision("2 ^ 53 + 1") => false,
+        subtraction_above_integer_precision("-(2 ^ 53) - 1") => false,
+        multiplication_above_integer_precision("94906267 * 94906267") => false,
+        power_losing_precision("3 ^ 40") => false,
+    );
+
+    macro_rules! test_subtype {
+        ($($name:ident ($code:literal) => $subtype:expr),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    let block = Parser::default()
+                        .parse(&format!("return {}", $code))
+                        .expect("code should parse");
+                    let expression = match block.get_last_statement() {
+                        Some(LastStatement::Return(statement)) => {
+                            statement.iter_expressions().next().unwrap().clone()
+                        }
+                        _ => panic!("return statement expected"),
+                    };
+
+                    pretty_assertions::assert_eq!(number_subtype(&expression), $subtype);
+                }
+            )*
+        };
+    }
+
+    test_subtype!(
+        subtype_of_integer("12") => Some(NumberSubtype::Integer),
+        subtype_of_float("1.5") => Some(NumberSubtype::Float),
+        subtype_of_exponent("1e3") => Some(NumberSubtype::Float),
+        subtype_of_hex("0xFF") => Some(NumberSubtype::Integer),
+        subtype_of_integer_addition("1 + 2") => Some(NumberSubtype::Integer),
+        subtype_of_mixed_addition("1 + 2.5") => Some(NumberSubtype::Float),
+        subtype_of_division("4 / 2") => Some(NumberSubtype::Float),
+        subtype_of_power("2 ^ 2") => Some(NumberSubtype::Float),
+        subtype_of_floor_division("7 // 2") => Some(NumberSubtype::Integer),
+        subtype_of_negative_integer("-(3 * 2)") => Some(NumberSubtype::Integer),
+        subtype_of_string_coercion("'1' + 1") => None,
+        subtype_of_variable("a + 1") => None,
+    );
+
+    #[test]
+    fn integer_precision_loss_message() {
+        pretty_assertions::assert_eq!(
+            NumberFoldIssue::IntegerPrecisionLoss {
+                exact: 9007199254740993
+            }
+            .to_string(),
+            "the exact result `9007199254740993` cannot be represented without losing precision"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+use crate::process::{Environment, EnvironmentTarget};
+
+/// The version of the standard library used by the evaluator to know the type of the values
+/// returned by its functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LuaVersion {
+    Lua51,
+    Lua53,
+    Lua54,
+    Luau,
+}
+
+impl LuaVersion {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "lua51" => Some(Self::Lua51),
+            "lua53" => Some(Self::Lua53),
+            "lua54" => Some(Self::Lua54),
+            "luau" => Some(Self::Luau),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lua51 => "lua51",
+            Self::Lua53 => "lua53",
+            Self::Lua54 => "lua54",
+            Self::Luau => "luau",
+        }
+    }
+}
+
+/// The type of a Lua value, as returned by the `type` function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "snake_case"))]
+pub enum LuaType {
+    Nil,
+    Boolean,
+    Number,
+    String,
+    Table,
+    Function,
+}
+
+impl LuaType {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "nil" => Some(Self::Nil),
+            "boolean" => Some(Self::Boolean),
+            "number" => Some(Self::Number),
+            "string" => Some(Self::String),
+            "table" => Some(Self::Table),
+            "function" => Some(Self::Function),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Nil => "nil",
+            Self::Boolean => "boolean",
+            Self::Number => "number",
+            Self::String => "string",
+            Self::Table => "table",
+            Self::Function => "function",
+        }
+    }
+}
+
+/// What is known about a value of the standard library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeInfo {
+    /// A constant value of the given type (like `math.pi`).
+    Value(LuaType),
+    /// A function that returns a value of the given type (when the type is always the same).
+    /// When called with exactly `pure_arity` arguments that do not have side effects, the
+    /// function does not have side effects either.
+    Function {
+        returns: Option<LuaType>,
+        pure_arity: Option<usize>,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_type_info(version: LuaVersion, path: &str) -> Option<TypeInfo> {
+        Environment::builtin(EnvironmentTarget::from(version))
+            .get(path)
+            .and_then(|entry| entry.type_info())
+    }
+
+    #[test]
+    fn lua54_has_integer_limits() {
+        let number = Some(TypeInfo::Value(LuaType::Number));
+
+        assert_eq!(get_type_info(LuaVersion::Lua54, "math.maxinteger"), number);
+        assert_eq!(get_type_info(LuaVersion::Lua54, "math.mininteger"), number);
+    }
+
+    #[test]
+    fn lua51_does_not_have_integer_limits() {
+        assert_eq!(get_type_info(LuaVersion::Lua51, "math.maxinteger"), None);
+    }
+
+    #[test]
+    fn luau_does_not_have_io_library() {
+        assert!(!Environment::builtin(EnvironmentTarget::from(LuaVersion::Luau)).has_global("io"));
+    }
+
+    #[test]
+    fn parse_lua53() {
+        assert_eq!(LuaVersion::parse("lua53"), Some(LuaVersion::Lua53));
+        assert_eq!(LuaVersion::Lua53.as_str(), "lua53");
+    }
+}
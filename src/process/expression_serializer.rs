@@ -118,7 +118,7 @@ impl Serializer {
             match last_operation {
                 SerializeOperation::Table(entries) => {
                     if let Expression::String(string) = key {
-                        if is_valid_identifier(string.get_value()) {
+                        if is_valid_identifier(string.get_value(), None) {
                             entries.push(
                                 TableFieldEntry::new(string.into_value(), entry_value).into(),
                             );
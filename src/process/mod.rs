@@ -1,22 +1,46 @@
 //! Defines how rules can process and mutate Lua nodes.
 
+mod assignment_split;
+mod deadline;
+mod doc_check;
+mod doc_tags;
+mod environment;
 mod evaluator;
+#[cfg(feature = "serde-config")]
 mod expression_serializer;
 #[cfg(test)]
 mod node_counter;
+mod node_edit;
+mod node_path;
 mod node_processor;
+mod node_query;
 mod post_visitor;
 pub mod processors;
 mod scope_visitor;
+mod size_analysis;
+mod string_decisions;
 pub(crate) mod utils;
 mod visitors;
 
+pub use assignment_split::{
+    assignment_split_is_safe, local_assignment_split_is_safe, local_assignments_merge_is_safe,
+};
+pub use deadline::DeadlineTracker;
+pub use doc_check::{check_docs, fix_docs, DocMismatch, DocMismatchKind};
+pub use environment::*;
 pub use evaluator::*;
+#[cfg(feature = "serde-config")]
 pub(crate) use expression_serializer::*;
 #[cfg(test)]
 pub use node_counter::NodeCounter;
+pub use node_edit::{apply_node_edits, NodeEdit};
+pub use node_path::{NodePath, NodePathSegment, NodePathTracker};
 pub use node_processor::{NodePostProcessor, NodeProcessor};
+pub use node_query::{CallPattern, NodeQuery, QueryMatch};
 pub use post_visitor::{DefaultPostVisitor, NodePostVisitor};
-pub(crate) use scope_visitor::IdentifierTracker;
+pub(crate) use scope_visitor::{IdentifierTracker, ENVIRONMENT_IDENTIFIER};
 pub use scope_visitor::{Scope, ScopeVisitor};
+pub use size_analysis::{FunctionSize, Occurrences, SizeAnalysis, SizeCategory};
+pub use string_decisions::{StringDecisionContext, StringRepresentation};
+pub use utils::is_valid_identifier;
 pub use visitors::{DefaultVisitor, NodeVisitor};
@@ -22,6 +22,9 @@ pub struct NodeCounter {
     pub expression_count: usize,
     pub variable_count: usize,
     pub interpolated_string_count: usize,
+    pub type_declaration_count: usize,
+    pub type_count: usize,
+    pub generic_parameters_count: usize,
 }
 
 impl NodeCounter {
@@ -102,4 +105,16 @@ impl NodeProcessor for NodeCounter {
     fn process_interpolated_string_expression(&mut self, _: &mut InterpolatedStringExpression) {
         self.interpolated_string_count += 1;
     }
+
+    fn process_type_declaration(&mut self, _: &mut TypeDeclarationStatement) {
+        self.type_declaration_count += 1;
+    }
+
+    fn process_type(&mut self, _: &mut Type) {
+        self.type_count += 1;
+    }
+
+    fn process_generic_parameters(&mut self, _: &mut GenericParameters) {
+        self.generic_parameters_count += 1;
+    }
 }
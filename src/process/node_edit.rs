@@ -0,0 +1,498 @@
+#[cfg(feature = "serde-config")]
+use serde::{de, Deserialize, Deserializer};
+
+use crate::nodes::{Block, Expression, LastStatement, Statement};
+use crate::process::node_path::statement_kind;
+use crate::process::{NodePath, NodePathSegment};
+use crate::Parser;
+
+#[cfg(feature = "serde-config")]
+impl<'de> Deserialize<'de> for NodePath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// An edit of a node of a block, referenced by its [`NodePath`]. The new nodes are written as
+/// Lua code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Deserialize))]
+#[cfg_attr(
+    feature = "serde-config",
+    serde(tag = "operation", rename_all = "snake_case", deny_unknown_fields)
+)]
+pub enum NodeEdit {
+    /// Replaces a statement or a value with the given code.
+    Replace { path: NodePath, code: String },
+    /// Removes a statement.
+    Delete { path: NodePath },
+    /// Inserts the statement written in the given code before a statement.
+    InsertBefore { path: NodePath, code: String },
+}
+
+impl NodeEdit {
+    pub fn path(&self) -> &NodePath {
+        match self {
+            Self::Replace { path, .. }
+            | Self::Delete { path }
+            | Self::InsertBefore { path, .. } => path,
+        }
+    }
+}
+
+/// The kind of node a path points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlotKind {
+    Statement,
+    LastStatement,
+    Expression,
+}
+
+impl SlotKind {
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::Statement => "a statement",
+            Self::LastStatement => "a last statement",
+            Self::Expression => "an expression",
+        }
+    }
+}
+
+enum Slot<'a> {
+    Statement { block: &'a mut Block, index: usize },
+    LastStatement { block: &'a mut Block },
+    Expression(&'a mut Expression),
+}
+
+impl Slot<'_> {
+    fn kind(&self) -> SlotKind {
+        match self {
+            Self::Statement { .. } => SlotKind::Statement,
+            Self::LastStatement { .. } => SlotKind::LastStatement,
+            Self::Expression(_) => SlotKind::Expression,
+        }
+    }
+}
+
+fn resolve_in_block<'a>(block: &'a mut Block, segments: &[NodePathSegment]) -> Option<Slot<'a>> {
+    match segments {
+        [NodePathSegment::Statement(index)] => {
+            (*index < block.statements_len()).then_some(Slot::Statement {
+                block,
+                index: *index,
+            })
+        }
+        [NodePathSegment::LastStatement] => block
+            .get_last_statement()
+            .is_some()
+            .then_some(Slot::LastStatement { block }),
+        [NodePathSegment::Statement(index), rest @ ..] => {
+            resolve_in_statement(block.iter_mut_statements().nth(*index)?, rest)
+        }
+        [NodePathSegment::LastStatement, NodePathSegment::Value(index)] => {
+            match block.mutate_last_statement()? {
+                LastStatement::Return(statement) => statement
+                    .iter_mut_expressions()
+                    .nth(*index)
+                    .map(Slot::Expression),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn resolve_in_statement<'a>(
+    statement: &'a mut Statement,
+    segments: &[NodePathSegment],
+) -> Option<Slot<'a>> {
+    match segments {
+        [NodePathSegment::Value(index)] => {
+            let value = match statement {
+                Statement::Assign(assign) => assign.iter_mut_values().nth(*index),
+                Statement::LocalAssign(assign) => assign.iter_mut_values().nth(*index),
+                Statement::CompoundAssign(assign) if *index == 0 => Some(assign.mutate_value()),
+                _ => None,
+            };
+            value.map(Slot::Expression)
+        }
+        [NodePathSegment::Kind(kind), rest @ ..] if statement_kind(statement) == Some(*kind) => {
+            let block = match statement {
+                Statement::If(if_statement) => {
+                    return match rest {
+                        [NodePathSegment::Branch(index), rest @ ..] => resolve_in_block(
+                            if_statement
+                                .mutate_branches()
+                                .get_mut(*index)?
+                                .mutate_block(),
+                            rest,
+                        ),
+                        [NodePathSegment::Else, rest @ ..] => {
+                            resolve_in_block(if_statement.mutate_else_block().as_mut()?, rest)
+                        }
+                        _ => None,
+                    };
+                }
+                Statement::Do(statement) => statement.mutate_block(),
+                Statement::Function(statement) => statement.mutate_block(),
+                Statement::GenericFor(statement) => statement.mutate_block(),
+                Statement::LocalFunction(statement) => statement.mutate_block(),
+                Statement::NumericFor(statement) => statement.mutate_block(),
+                Statement::Repeat(statement) => statement.mutate_block(),
+                Statement::While(statement) => statement.mutate_block(),
+                _ => return None,
+            };
+            resolve_in_block(block, rest)
+        }
+        _ => None,
+    }
+}
+
+fn resolve<'a>(block: &'a mut Block, path: &NodePath) -> Result<Slot<'a>, String> {
+    let segments: Vec<_> = path.iter_segments().copied().collect();
+    resolve_in_block(block, &segments).ok_or_else(|| format!("path `{}` does not exist", path))
+}
+
+/// A node parsed from the code of an edit.
+enum NewNode {
+    Statement(Box<Statement>),
+    LastStatement(LastStatement),
+    Expression(Box<Expression>),
+}
+
+/// Parses the code for the given kind of slot. When the code cannot be parsed, the error
+/// tells if the code is another kind of node.
+fn parse_node(
+    parser: &Parser,
+    code: &str,
+    kind: SlotKind,
+    path: &NodePath,
+) -> Result<NewNode, String> {
+    let result = match kind {
+        SlotKind::Statement => parser
+            .parse_statement(code)
+            .map(|statement| NewNode::Statement(Box::new(statement))),
+        SlotKind::Expression => parser
+            .parse_expression(code)
+            .map(|expression| NewNode::Expression(Box::new(expression))),
+        SlotKind::LastStatement => {
+            return parser
+                .parse_last_statement(code)
+                .map(NewNode::LastStatement)
+                .ok()
+                .or_else(|| {
+                    parser
+                        .parse_statement(code)
+                        .ok()
+                        .map(|statement| NewNode::Statement(Box::new(statement)))
+                })
+                .ok_or_else(|| {
+                    format!("unable to parse code for `{}`: expected a statement", path)
+                });
+        }
+    };
+
+    result.map_err(|err| {
+        let found = if kind != SlotKind::Expression && parser.parse_expression(code).is_ok() {
+            Some(SlotKind::Expression)
+        } else if kind != SlotKind::Statement && parser.parse_statement(code).is_ok() {
+            Some(SlotKind::Statement)
+        } else if kind != SlotKind::LastStatement && parser.parse_last_statement(code).is_ok() {
+            Some(SlotKind::LastStatement)
+        } else {
+            None
+        };
+
+        match found {
+            Some(found) => format!(
+                "edit at `{}` expects {} but the code is {}: `{}`",
+                path,
+                kind.describe(),
+                found.describe(),
+                code
+            ),
+            None => format!("unable to parse code for `{}`: {}", path, err),
+        }
+    })
+}
+
+/// An edit checked against the block, with its code parsed.
+struct PreparedEdit {
+    path: NodePath,
+    operation: PreparedOperation,
+}
+
+enum PreparedOperation {
+    Replace(NewNode),
+    Delete,
+    InsertBefore(Box<Statement>),
+}
+
+fn prepare(block: &mut Block, edit: &NodeEdit, parser: &Parser) -> Result<PreparedEdit, String> {
+    let path = edit.path();
+    let kind = resolve(block, path)?.kind();
+
+    let operation = match edit {
+        NodeEdit::Replace { code, .. } => {
+            PreparedOperation::Replace(parse_node(parser, code, kind, path)?)
+        }
+        NodeEdit::Delete { .. } => {
+            if kind == SlotKind::Expression {
+                return Err(format!(
+                    "unable to delete `{}`: only statements can be deleted",
+                    path
+                ));
+            }
+            PreparedOperation::Delete
+        }
+        NodeEdit::InsertBefore { code, .. } => {
+            if kind == SlotKind::Expression {
+                return Err(format!(
+                    "unable to insert before `{}`: statements can only be inserted before statements",
+                    path
+                ));
+            }
+            // the inserted statement keeps its trailing whitespace when tokens are preserved,
+            // so it ends with a new line to be separated from the statement after it
+            let code = if code.ends_with(char::is_whitespace) {
+                code.to_owned()
+            } else {
+                format!("{}\n", code)
+            };
+            match parse_node(parser, &code, SlotKind::Statement, path)? {
+                NewNode::Statement(statement) => PreparedOperation::InsertBefore(statement),
+                _ => unreachable!("statement code should parse into a statement"),
+            }
+        }
+    };
+
+    Ok(PreparedEdit {
+        path: path.clone(),
+        operation,
+    })
+}
+
+fn apply(block: &mut Block, edit: PreparedEdit) -> Result<(), String> {
+    let slot = resolve(block, &edit.path)?;
+
+    match (slot, edit.operation) {
+        (Slot::Statement { block, index }, PreparedOperation::Replace(node)) => {
+            let statement = block
+                .iter_mut_statements()
+                .nth(index)
+                .expect("statement index should be valid");
+            match node {
+                NewNode::Statement(new_statement) => *statement = *new_statement,
+                _ => unreachable!("statement slot should be replaced with a statement"),
+            }
+        }
+        (Slot::LastStatement { block }, PreparedOperation::Replace(node)) => match node {
+            NewNode::LastStatement(last_statement) => {
+                block.replace_last_statement(last_statement);
+            }
+            NewNode::Statement(statement) => {
+                block.take_last_statement();
+                block.push_statement(*statement);
+            }
+            NewNode::Expression(_) => {
+                unreachable!("last statement slot should not be replaced with an expression")
+            }
+        },
+        (Slot::Expression(expression), PreparedOperation::Replace(node)) => match node {
+            NewNode::Expression(new_expression) => *expression = *new_expression,
+            _ => unreachable!("expression slot should be replaced with an expression"),
+        },
+        (Slot::Statement { block, index }, PreparedOperation::Delete) => {
+            let mut current = 0;
+            block.filter_statements(|_| {
+                let keep = current != index;
+                current += 1;
+                keep
+            });
+        }
+        (Slot::LastStatement { block }, PreparedOperation::Delete) => {
+            block.take_last_statement();
+        }
+        (Slot::Statement { block, index }, PreparedOperation::InsertBefore(statement)) => {
+            block.insert_statement(index, *statement);
+        }
+        (Slot::LastStatement { block }, PreparedOperation::InsertBefore(statement)) => {
+            block.push_statement(*statement);
+        }
+        (Slot::Expression(_), _) => unreachable!("expressions can only be replaced"),
+    }
+
+    Ok(())
+}
+
+/// Applies edits to a block. All edits are validated before the block is modified: each
+/// path must exist, the code must be of the kind of node found at the path, and two edits
+/// cannot target the same node or a node and one of its parents. The edits are applied from
+/// the last path to the first, so that the indices of the paths stay valid.
+pub fn apply_node_edits(
+    block: &mut Block,
+    edits: &[NodeEdit],
+    parser: &Parser,
+) -> Result<(), String> {
+    for (index, edit) in edits.iter().enumerate() {
+        for other in edits.iter().skip(index + 1) {
+            if edit.path().starts_with(other.path()) || other.path().starts_with(edit.path()) {
+                return Err(format!(
+                    "conflicting edits: `{}` and `{}` overlap",
+                    edit.path(),
+                    other.path()
+                ));
+            }
+        }
+    }
+
+    let mut prepared = edits
+        .iter()
+        .map(|edit| prepare(block, edit, parser))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    prepared.sort_by(|a, b| b.path.cmp(&a.path));
+
+    for edit in prepared {
+        apply(block, edit)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generator::{LuaGenerator, ReadableLuaGenerator};
+
+    fn apply_edits(code: &str, edits: &str) -> Result<String, String> {
+        let parser = Parser::default();
+        let mut block = parser.parse(code).unwrap();
+        let edits: Vec<NodeEdit> = serde_json::from_str(edits).unwrap();
+
+        apply_node_edits(&mut block, &edits, &parser)?;
+
+        let mut generator = ReadableLuaGenerator::new(80);
+        generator.write_block(&block);
+        Ok(generator.into_string())
+    }
+
+    #[test]
+    fn deserialize_edit_with_unknown_field() {
+        let result = serde_json::from_str::<NodeEdit>(
+            r#"{ "operation": "delete", "path": "block.statements[0]", "node": {} }"#,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn deserialize_edit_with_invalid_path() {
+        let error = serde_json::from_str::<NodeEdit>(
+            r#"{ "operation": "delete", "path": "block.statement[0]" }"#,
+        )
+        .unwrap_err();
+
+        assert!(
+            error
+                .to_string()
+                .contains("unknown path segment `statement[0]`"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn replace_value() {
+        pretty_assertions::assert_eq!(
+            apply_edits(
+                "local a = 1\nreturn a\n",
+                r#"[{ "operation": "replace", "path": "block.statements[0].values[0]", "code": "compute()" }]"#,
+            )
+            .unwrap(),
+            "local a = compute()\n\nreturn a\n"
+        );
+    }
+
+    #[test]
+    fn replace_return_value() {
+        pretty_assertions::assert_eq!(
+            apply_edits(
+                "return a\n",
+                r#"[{ "operation": "replace", "path": "block.last_statement.values[0]", "code": "b" }]"#,
+            )
+            .unwrap(),
+            "return b\n"
+        );
+    }
+
+    #[test]
+    fn delete_nested_statement() {
+        pretty_assertions::assert_eq!(
+            apply_edits(
+                "if a then\n    print(a)\n    f()\nend\n",
+                r#"[{ "operation": "delete", "path": "block.statements[0].if.branches[0].statements[0]" }]"#,
+            )
+            .unwrap(),
+            "if a then\n    f()\nend\n"
+        );
+    }
+
+    #[test]
+    fn replace_last_statement() {
+        pretty_assertions::assert_eq!(
+            apply_edits(
+                "local a = 1\nreturn a\n",
+                r#"[{ "operation": "replace", "path": "block.last_statement", "code": "return a, a" }]"#,
+            )
+            .unwrap(),
+            "local a = 1\n\nreturn a, a\n"
+        );
+    }
+
+    #[test]
+    fn delete_value_errors() {
+        pretty_assertions::assert_eq!(
+            apply_edits(
+                "local a = 1",
+                r#"[{ "operation": "delete", "path": "block.statements[0].values[0]" }]"#,
+            )
+            .unwrap_err(),
+            "unable to delete `block.statements[0].values[0]`: only statements can be deleted"
+        );
+    }
+
+    #[test]
+    fn value_of_call_statement_does_not_exist() {
+        pretty_assertions::assert_eq!(
+            apply_edits(
+                "print(1)",
+                r#"[{ "operation": "replace", "path": "block.statements[0].values[0]", "code": "2" }]"#,
+            )
+            .unwrap_err(),
+            "path `block.statements[0].values[0]` does not exist"
+        );
+    }
+
+    #[test]
+    fn edits_are_not_applied_when_one_is_invalid() {
+        let parser = Parser::default();
+        let mut block = parser.parse("local a = 1 f()").unwrap();
+        let original = block.clone();
+        let edits: Vec<NodeEdit> = serde_json::from_str(
+            r#"[
+                { "operation": "delete", "path": "block.statements[0]" },
+                { "operation": "delete", "path": "block.statements[4]" }
+            ]"#,
+        )
+        .unwrap();
+
+        assert!(apply_node_edits(&mut block, &edits, &parser).is_err());
+        pretty_assertions::assert_eq!(block, original);
+    }
+}
@@ -0,0 +1,476 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::generator::{DenseLuaGenerator, LuaGenerator};
+use crate::nodes::{Block, LastStatement, Statement};
+
+use super::deadline;
+
+thread_local! {
+    static CURRENT_PATH: RefCell<Option<Vec<NodePathSegment>>> = const { RefCell::new(None) };
+}
+
+/// A segment of a [`NodePath`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NodePathSegment {
+    /// A statement of a block, from its index.
+    Statement(usize),
+    /// The last statement of a block (a `return`, `break` or `continue` statement).
+    LastStatement,
+    /// The kind of statement or expression (like `while` or `function`) that contains the
+    /// next block of the path.
+    Kind(&'static str),
+    /// A branch of an if statement, from its index.
+    Branch(usize),
+    /// The else block of an if statement.
+    Else,
+    /// A value of an assignment or a return statement, from its index.
+    Value(usize),
+}
+
+impl fmt::Display for NodePathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Statement(index) => write!(f, "statements[{}]", index),
+            Self::LastStatement => write!(f, "last_statement"),
+            Self::Kind(kind) => write!(f, "{}", kind),
+            Self::Branch(index) => write!(f, "branches[{}]", index),
+            Self::Else => write!(f, "else"),
+            Self::Value(index) => write!(f, "values[{}]", index),
+        }
+    }
+}
+
+/// The kinds of statements that contain a block, as written in node paths.
+const BLOCK_KINDS: &[&str] = &[
+    "do",
+    "function",
+    "generic_for",
+    "if",
+    "local_function",
+    "numeric_for",
+    "repeat",
+    "while",
+];
+
+fn parse_index(segment: &str, name: &str) -> Option<Result<usize, String>> {
+    let index = segment
+        .strip_prefix(name)?
+        .strip_prefix('[')?
+        .strip_suffix(']')?;
+
+    Some(
+        index
+            .parse()
+            .map_err(|_| format!("invalid index `{}` in segment `{}`", index, segment)),
+    )
+}
+
+impl FromStr for NodePathSegment {
+    type Err = String;
+
+    fn from_str(segment: &str) -> Result<Self, Self::Err> {
+        if let Some(index) = parse_index(segment, "statements") {
+            return index.map(Self::Statement);
+        }
+        if let Some(index) = parse_index(segment, "branches") {
+            return index.map(Self::Branch);
+        }
+        if let Some(index) = parse_index(segment, "values") {
+            return index.map(Self::Value);
+        }
+
+        match segment {
+            "last_statement" => Ok(Self::LastStatement),
+            "else" => Ok(Self::Else),
+            _ => BLOCK_KINDS
+                .iter()
+                .find(|kind| **kind == segment)
+                .map(|kind| Self::Kind(kind))
+                .ok_or_else(|| format!("unknown path segment `{}`", segment)),
+        }
+    }
+}
+
+/// The location of a statement inside a block, like
+/// `block.statements[12].if.branches[0].statements[3]`, or of a value of a statement, like
+/// `block.statements[2].values[0]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NodePath {
+    segments: Vec<NodePathSegment>,
+}
+
+impl NodePath {
+    pub fn new(segments: impl Into<Vec<NodePathSegment>>) -> Self {
+        Self {
+            segments: segments.into(),
+        }
+    }
+
+    /// Returns `true` if the path points to the block itself.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn iter_segments(&self) -> impl Iterator<Item = &NodePathSegment> {
+        self.segments.iter()
+    }
+
+    /// Returns `true` if the given path points to the same node or to one of its parents.
+    pub fn starts_with(&self, other: &NodePath) -> bool {
+        self.segments.starts_with(&other.segments)
+    }
+
+    /// Generates the dense code of the statement pointed by the path. When the path goes
+    /// further than statements (like inside a function expression), the code of the deepest
+    /// statement found is generated.
+    pub fn generate_statement(&self, block: &Block) -> Option<String> {
+        let mut current_block = Some(block);
+        let mut current_statement: Option<&Statement> = None;
+        let mut found = None;
+
+        for segment in self.segments.iter() {
+            match segment {
+                NodePathSegment::Statement(index) => {
+                    let statement =
+                        match current_block.and_then(|block| block.iter_statements().nth(*index)) {
+                            Some(statement) => statement,
+                            None => break,
+                        };
+                    current_block = None;
+                    current_statement = Some(statement);
+                    found = Some(FoundStatement::Statement(statement));
+                }
+                NodePathSegment::LastStatement => {
+                    if let Some(last_statement) = current_block.and_then(Block::get_last_statement)
+                    {
+                        found = Some(FoundStatement::LastStatement(last_statement));
+                    }
+                    break;
+                }
+                NodePathSegment::Kind(_) => {
+                    current_block = match current_statement {
+                        Some(Statement::Do(statement)) => Some(statement.get_block()),
+                        Some(Statement::Function(statement)) => Some(statement.get_block()),
+                        Some(Statement::GenericFor(statement)) => Some(statement.get_block()),
+                        Some(Statement::LocalFunction(statement)) => Some(statement.get_block()),
+                        Some(Statement::NumericFor(statement)) => Some(statement.get_block()),
+                        Some(Statement::Repeat(statement)) => Some(statement.get_block()),
+                        Some(Statement::While(statement)) => Some(statement.get_block()),
+                        Some(Statement::If(_)) => None,
+                        _ => break,
+                    };
+                }
+                NodePathSegment::Branch(index) => {
+                    current_block = match current_statement {
+                        Some(Statement::If(statement)) => statement
+                            .get_branches()
+                            .get(*index)
+                            .map(|branch| branch.get_block()),
+                        _ => None,
+                    };
+                    if current_block.is_none() {
+                        break;
+                    }
+                }
+                NodePathSegment::Else => {
+                    current_block = match current_statement {
+                        Some(Statement::If(statement)) => statement.get_else_block(),
+                        _ => None,
+                    };
+                    if current_block.is_none() {
+                        break;
+                    }
+                }
+                NodePathSegment::Value(_) => break,
+            }
+        }
+
+        found.map(|found| {
+            let mut generator = DenseLuaGenerator::default();
+            match found {
+                FoundStatement::Statement(statement) => generator.write_statement(statement),
+                FoundStatement::LastStatement(statement) => {
+                    generator.write_last_statement(statement)
+                }
+            }
+            generator.into_string()
+        })
+    }
+}
+
+enum FoundStatement<'a> {
+    Statement(&'a Statement),
+    LastStatement(&'a LastStatement),
+}
+
+impl fmt::Display for NodePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "block")?;
+        for segment in self.segments.iter() {
+            write!(f, ".{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for NodePath {
+    type Err = String;
+
+    /// Parses a path written like its display form (`block.statements[0].values[1]`).
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let mut segments = path.split('.');
+
+        if segments.next() != Some("block") {
+            return Err(format!("path `{}` should start with `block`", path));
+        }
+
+        segments
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self::new)
+            .map_err(|err| format!("invalid path `{}`: {}", path, err))
+    }
+}
+
+/// Tracks the path of the statement visited by the node visitors of the current thread. The
+/// path is tracked until the tracker is dropped.
+///
+/// The path is not reset when a panic unwinds the visitors, so that the location where the
+/// panic happened can be obtained from the tracker.
+#[derive(Debug)]
+pub struct NodePathTracker {
+    previous: Option<Vec<NodePathSegment>>,
+}
+
+impl NodePathTracker {
+    pub fn start() -> Self {
+        let previous = CURRENT_PATH.with(|path| path.borrow_mut().replace(Vec::new()));
+        Self { previous }
+    }
+
+    /// Returns the path of the statement currently visited, or `None` when no tracker is
+    /// active on the current thread.
+    pub fn current_path() -> Option<NodePath> {
+        CURRENT_PATH.with(|path| path.borrow().as_deref().map(NodePath::new))
+    }
+}
+
+impl Drop for NodePathTracker {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT_PATH.with(|path| *path.borrow_mut() = previous);
+    }
+}
+
+#[inline]
+pub(crate) fn enter(segment: NodePathSegment) {
+    deadline::poll();
+    CURRENT_PATH.with(|path| {
+        if let Some(segments) = path.borrow_mut().as_mut() {
+            segments.push(segment);
+        }
+    });
+}
+
+#[inline]
+pub(crate) fn leave() {
+    CURRENT_PATH.with(|path| {
+        if let Some(segments) = path.borrow_mut().as_mut() {
+            segments.pop();
+        }
+    });
+}
+
+/// Returns the kind of the statement when it contains a block.
+pub(crate) fn statement_kind(statement: &Statement) -> Option<&'static str> {
+    match statement {
+        Statement::Do(_) => Some("do"),
+        Statement::Function(_) => Some("function"),
+        Statement::GenericFor(_) => Some("generic_for"),
+        Statement::If(_) => Some("if"),
+        Statement::LocalFunction(_) => Some("local_function"),
+        Statement::NumericFor(_) => Some("numeric_for"),
+        Statement::Repeat(_) => Some("repeat"),
+        Statement::While(_) => Some("while"),
+        Statement::Assign(_)
+        | Statement::Call(_)
+        | Statement::CompoundAssign(_)
+        | Statement::LocalAssign(_)
+        | Statement::TypeDeclaration(_)
+        | Statement::Verbatim(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+    use crate::Parser;
+
+    #[derive(Default)]
+    struct RecordCalls {
+        paths: Vec<String>,
+    }
+
+    impl NodeProcessor for RecordCalls {
+        fn process_function_call(&mut self, _: &mut crate::nodes::FunctionCall) {
+            if let Some(path) = NodePathTracker::current_path() {
+                self.paths.push(path.to_string());
+            }
+        }
+    }
+
+    fn record_calls(code: &str) -> Vec<String> {
+        let mut block = Parser::default().parse(code).unwrap();
+        let mut processor = RecordCalls::default();
+        let _tracker = NodePathTracker::start();
+        DefaultVisitor::visit_block(&mut block, &mut processor);
+        processor.paths
+    }
+
+    #[test]
+    fn current_path_is_none_without_tracker() {
+        assert_eq!(NodePathTracker::current_path(), None);
+    }
+
+    #[test]
+    fn track_top_level_statements() {
+        assert_eq!(
+            record_calls("f() local a = 1 g()"),
+            vec!["block.statements[0]", "block.statements[2]"]
+        );
+    }
+
+    #[test]
+    fn track_if_branches() {
+        assert_eq!(
+            record_calls("if a then f() elseif b then g() else h() end"),
+            vec![
+                "block.statements[0].if.branches[0].statements[0]",
+                "block.statements[0].if.branches[1].statements[0]",
+                "block.statements[0].if.else.statements[0]",
+            ]
+        );
+    }
+
+    #[test]
+    fn track_last_statement_in_loop() {
+        assert_eq!(
+            record_calls("while true do local a = 1 return f() end"),
+            vec!["block.statements[0].while.last_statement"]
+        );
+    }
+
+    #[test]
+    fn track_function_expression() {
+        assert_eq!(
+            record_calls("local a = 1 local b = function() f() end"),
+            vec!["block.statements[1].function.statements[0]"]
+        );
+    }
+
+    #[test]
+    fn tracker_is_reset_after_drop() {
+        record_calls("f()");
+        assert_eq!(NodePathTracker::current_path(), None);
+    }
+
+    fn generate(code: &str, segments: Vec<NodePathSegment>) -> Option<String> {
+        let block = Parser::default().parse(code).unwrap();
+        NodePath::new(segments).generate_statement(&block)
+    }
+
+    #[test]
+    fn display_empty_path() {
+        assert_eq!(NodePath::default().to_string(), "block");
+    }
+
+    #[test]
+    fn generate_nested_if_statement() {
+        assert_eq!(
+            generate(
+                "local a = 1 if a then local b = 2 else print(a) end",
+                vec![
+                    NodePathSegment::Statement(1),
+                    NodePathSegment::Kind("if"),
+                    NodePathSegment::Else,
+                    NodePathSegment::Statement(0),
+                ]
+            )
+            .as_deref(),
+            Some("print(a)")
+        );
+    }
+
+    #[test]
+    fn generate_deepest_statement_inside_function_expression() {
+        assert_eq!(
+            generate(
+                "local b = function() f() end",
+                vec![
+                    NodePathSegment::Statement(0),
+                    NodePathSegment::Kind("function"),
+                    NodePathSegment::Statement(0),
+                ]
+            )
+            .as_deref(),
+            Some("local b=function()f()end")
+        );
+    }
+
+    #[test]
+    fn parse_path_roundtrip() {
+        for path in [
+            "block",
+            "block.statements[3].values[0]",
+            "block.statements[0].if.branches[1].statements[2]",
+            "block.statements[1].while.last_statement.values[0]",
+            "block.statements[0].if.else.statements[0]",
+        ]
+        .iter()
+        {
+            assert_eq!(path.parse::<NodePath>().unwrap().to_string(), *path);
+        }
+    }
+
+    #[test]
+    fn parse_path_with_unknown_segment() {
+        assert_eq!(
+            "block.statements[0].loop".parse::<NodePath>().unwrap_err(),
+            "invalid path `block.statements[0].loop`: unknown path segment `loop`"
+        );
+    }
+
+    #[test]
+    fn parse_path_with_invalid_index() {
+        assert_eq!(
+            "block.statements[a]".parse::<NodePath>().unwrap_err(),
+            "invalid path `block.statements[a]`: invalid index `a` in segment `statements[a]`"
+        );
+    }
+
+    #[test]
+    fn parse_path_without_block() {
+        assert_eq!(
+            "statements[0]".parse::<NodePath>().unwrap_err(),
+            "path `statements[0]` should start with `block`"
+        );
+    }
+
+    #[test]
+    fn path_starts_with_parent() {
+        let parent: NodePath = "block.statements[1]".parse().unwrap();
+        let child: NodePath = "block.statements[1].values[0]".parse().unwrap();
+
+        assert!(child.starts_with(&parent));
+        assert!(!parent.starts_with(&child));
+    }
+
+    #[test]
+    fn generate_missing_statement() {
+        assert_eq!(generate("f()", vec![NodePathSegment::Statement(4)]), None);
+    }
+}
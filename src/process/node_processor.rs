@@ -59,6 +59,8 @@ pub trait NodeProcessor {
     fn process_type_pack(&mut self, _: &mut TypePack) {}
     fn process_generic_type_pack(&mut self, _: &mut GenericTypePack) {}
     fn process_variadic_type_pack(&mut self, _: &mut VariadicTypePack) {}
+
+    fn process_generic_parameters(&mut self, _: &mut GenericParameters) {}
 }
 
 pub trait NodePostProcessor {
@@ -122,4 +124,6 @@ pub trait NodePostProcessor {
     fn process_after_type_pack(&mut self, _: &mut TypePack) {}
     fn process_after_generic_type_pack(&mut self, _: &mut GenericTypePack) {}
     fn process_after_variadic_type_pack(&mut self, _: &mut VariadicTypePack) {}
+
+    fn process_after_generic_parameters(&mut self, _: &mut GenericParameters) {}
 }
@@ -0,0 +1,637 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "serde-config")]
+use serde::Serialize;
+
+use crate::generator::{DenseLuaGenerator, LuaGenerator};
+use crate::nodes::{
+    AssignStatement, Block, CompoundAssignStatement, Expression, FunctionCall, FunctionExpression,
+    FunctionName, FunctionStatement, Identifier, LocalFunctionStatement, NumberExpression, Prefix,
+    StringExpression, Token, Variable,
+};
+use crate::process::utils::is_valid_identifier;
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, Scope, ScopeVisitor};
+
+const MAX_SNIPPET_LENGTH: usize = 60;
+const WILDCARD: &str = "*";
+
+/// A pattern matching function calls from the names used to access the called function,
+/// like `game:GetService` or `table.insert`. A `*` matches any name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallPattern {
+    names: Vec<String>,
+    method: Option<String>,
+}
+
+impl CallPattern {
+    fn matches_name(pattern: &str, name: &str) -> bool {
+        pattern == WILDCARD || pattern == name
+    }
+
+    fn matches(&self, call: &FunctionCall) -> bool {
+        let names = match prefix_names(call.get_prefix()) {
+            Some(names) => names,
+            None => return false,
+        };
+
+        let method_matches = match (&self.method, call.get_method()) {
+            (Some(pattern), Some(method)) => Self::matches_name(pattern, method.get_name()),
+            (None, None) => true,
+            (Some(_), None) | (None, Some(_)) => false,
+        };
+
+        method_matches
+            && names.len() == self.names.len()
+            && self
+                .names
+                .iter()
+                .zip(names)
+                .all(|(pattern, name)| Self::matches_name(pattern, name))
+    }
+}
+
+impl FromStr for CallPattern {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (path, method) = match value.rsplit_once(':') {
+            Some((path, method)) => (path, Some(method.to_owned())),
+            None => (value, None),
+        };
+
+        let names: Vec<String> = path.split('.').map(ToOwned::to_owned).collect();
+
+        if let Some(invalid) = names
+            .iter()
+            .chain(method.iter())
+            .find(|name| name.as_str() != WILDCARD && !is_valid_identifier(name, None))
+        {
+            return Err(format!(
+                "invalid call pattern `{}`: `{}` is not an identifier or `{}`",
+                value, invalid, WILDCARD
+            ));
+        }
+
+        Ok(Self { names, method })
+    }
+}
+
+fn prefix_names(prefix: &Prefix) -> Option<Vec<&str>> {
+    match prefix {
+        Prefix::Identifier(identifier) => Some(vec![identifier.get_name()]),
+        Prefix::Field(field) => {
+            let mut names = prefix_names(field.get_prefix())?;
+            names.push(field.get_field().get_name());
+            Some(names)
+        }
+        Prefix::Call(_) | Prefix::Index(_) | Prefix::Parenthese(_) => None,
+    }
+}
+
+/// A query that finds nodes of a block.
+///
+/// Queries are parsed from strings of the form `<kind>:<argument>`:
+/// - `call:<path>`: calls of functions accessed through the given names (like
+///   `game:GetService` or `table.*`)
+/// - `global:<name>`: reads or writes of a global variable
+/// - `env-global:<name>`: reads or writes of a free variable in the scope of a local or a
+///   parameter named `_ENV`, which accesses a field of that value instead of a global
+/// - `string:<text>`: string literals containing the given text
+/// - `assign-global:*`: assignments to any global variable
+/// - `number:><value>`: number literals greater than the given value
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeQuery {
+    Call(CallPattern),
+    Global(String),
+    EnvGlobal(String),
+    String(String),
+    AssignGlobal,
+    NumberAbove(f64),
+}
+
+impl NodeQuery {
+    /// Finds the nodes of the block that match the query. The original code is used to
+    /// compute the column of each match, so the block should be parsed with its tokens.
+    pub fn find(&self, block: &mut Block, code: &str) -> Vec<QueryMatch> {
+        let mut finder = QueryFinder::new(self, code);
+        ScopeVisitor::visit_block(block, &mut finder);
+        finder.matches
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Call(_) => "call",
+            Self::Global(_) => "global",
+            Self::EnvGlobal(_) => "env-global",
+            Self::String(_) => "string",
+            Self::AssignGlobal => "assign-global",
+            Self::NumberAbove(_) => "number",
+        }
+    }
+}
+
+impl FromStr for NodeQuery {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (kind, argument) = value.split_once(':').ok_or_else(|| {
+            format!(
+                "invalid pattern `{}` (expected `<kind>:<argument>`, where kind is `call`, \
+                `global`, `env-global`, `string`, `assign-global` or `number`)",
+                value
+            )
+        })?;
+
+        match kind {
+            "call" => argument.parse().map(Self::Call),
+            "global" | "env-global" => {
+                if !is_valid_identifier(argument, None) {
+                    Err(format!(
+                        "invalid {} pattern `{}`: `{}` is not an identifier",
+                        kind, value, argument
+                    ))
+                } else if kind == "global" {
+                    Ok(Self::Global(argument.to_owned()))
+                } else {
+                    Ok(Self::EnvGlobal(argument.to_owned()))
+                }
+            }
+            "string" => Ok(Self::String(argument.to_owned())),
+            "assign-global" => {
+                if argument == WILDCARD {
+                    Ok(Self::AssignGlobal)
+                } else {
+                    Err(format!(
+                        "invalid assign-global pattern `{}` (only `assign-global:*` is supported)",
+                        value
+                    ))
+                }
+            }
+            "number" => argument
+                .strip_prefix('>')
+                .and_then(|threshold| threshold.trim().parse::<f64>().ok())
+                .map(Self::NumberAbove)
+                .ok_or_else(|| {
+                    format!(
+                        "invalid number pattern `{}` (expected `number:><value>`)",
+                        value
+                    )
+                }),
+            _ => Err(format!(
+                "unknown pattern kind `{}` (must be `call`, `global`, `env-global`, `string`, \
+                `assign-global` or `number`)",
+                kind
+            )),
+        }
+    }
+}
+
+/// A node found by a [`NodeQuery`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+pub struct QueryMatch {
+    kind: &'static str,
+    line: Option<usize>,
+    column: Option<usize>,
+    snippet: String,
+    function: Option<String>,
+}
+
+impl QueryMatch {
+    #[inline]
+    pub fn kind(&self) -> &str {
+        self.kind
+    }
+
+    #[inline]
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    #[inline]
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    #[inline]
+    pub fn snippet(&self) -> &str {
+        &self.snippet
+    }
+
+    /// Returns the name of the function that contains the match, if the match is inside a
+    /// named function.
+    #[inline]
+    pub fn function(&self) -> Option<&str> {
+        self.function.as_deref()
+    }
+}
+
+impl fmt::Display for QueryMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {} {}",
+            self.line.unwrap_or_default(),
+            self.column.unwrap_or_default(),
+            self.kind,
+            self.snippet
+        )?;
+        if let Some(function) = &self.function {
+            write!(f, " (in `{}`)", function)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn function_name_to_string(name: &FunctionName) -> String {
+    let mut string = name.get_name().get_name().to_owned();
+    for field in name.get_field_names() {
+        string.push('.');
+        string.push_str(field.get_name());
+    }
+    if let Some(method) = name.get_method() {
+        string.push(':');
+        string.push_str(method.get_name());
+    }
+    string
+}
+
+fn prefix_token(prefix: &Prefix) -> Option<&Token> {
+    match prefix {
+        Prefix::Identifier(identifier) => identifier.get_token(),
+        Prefix::Field(field) => prefix_token(field.get_prefix()),
+        Prefix::Index(index) => prefix_token(index.get_prefix()),
+        Prefix::Call(call) => prefix_token(call.get_prefix()),
+        Prefix::Parenthese(parenthese) => parenthese
+            .get_tokens()
+            .map(|tokens| &tokens.left_parenthese),
+    }
+}
+
+fn variable_identifier(variable: &Variable) -> Option<&Identifier> {
+    match variable {
+        Variable::Identifier(identifier) => Some(identifier),
+        Variable::Field(_) | Variable::Index(_) => None,
+    }
+}
+
+fn truncate_snippet(mut snippet: String) -> String {
+    if snippet.chars().count() > MAX_SNIPPET_LENGTH {
+        snippet = snippet.chars().take(MAX_SNIPPET_LENGTH - 3).collect();
+        snippet.push_str("...");
+    }
+    snippet
+}
+
+fn generate_snippet(expression: Expression) -> String {
+    let mut generator = DenseLuaGenerator::new(usize::MAX / 2);
+    generator.write_expression(&expression);
+    truncate_snippet(generator.into_string())
+}
+
+struct QueryFinder<'a> {
+    query: &'a NodeQuery,
+    code: &'a str,
+    matches: Vec<QueryMatch>,
+    identifier_tracker: IdentifierTracker,
+    // one entry for each scope: `Some` when the scope is the body of a function, with the
+    // function name when it is known
+    functions: Vec<Option<Option<String>>>,
+    next_function: Option<Option<String>>,
+}
+
+impl<'a> QueryFinder<'a> {
+    fn new(query: &'a NodeQuery, code: &'a str) -> Self {
+        Self {
+            query,
+            code,
+            matches: Vec::new(),
+            identifier_tracker: IdentifierTracker::new(),
+            functions: Vec::new(),
+            next_function: None,
+        }
+    }
+
+    fn current_function(&self) -> Option<String> {
+        self.functions.iter().rev().find_map(Clone::clone).flatten()
+    }
+
+    fn column(&self, start: usize) -> Option<usize> {
+        let before = self.code.get(..start)?;
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        Some(before[line_start..].chars().count() + 1)
+    }
+
+    fn push_match(&mut self, token: Option<&Token>, snippet: String) {
+        let line = token.and_then(Token::get_line_number);
+        let column = token
+            .and_then(Token::get_start_position)
+            .and_then(|start| self.column(start));
+
+        self.matches.push(QueryMatch {
+            kind: self.query.kind(),
+            line,
+            column,
+            snippet,
+            function: self.current_function(),
+        });
+    }
+
+    fn is_global(&self, identifier: &Identifier) -> bool {
+        self.identifier_tracker.is_global(identifier.get_name())
+    }
+
+    fn is_environment_field(&self, identifier: &Identifier) -> bool {
+        self.identifier_tracker.is_environment_replaced()
+            && !self
+                .identifier_tracker
+                .is_identifier_used(identifier.get_name())
+    }
+
+    fn verify_global_assignment(&mut self, identifier: &Identifier, snippet: String) {
+        if self.is_global(identifier) {
+            self.push_match(identifier.get_token(), snippet);
+        }
+    }
+}
+
+impl Scope for QueryFinder<'_> {
+    fn push(&mut self) {
+        self.functions.push(self.next_function.take());
+        self.identifier_tracker.push();
+    }
+
+    fn pop(&mut self) {
+        self.functions.pop();
+        self.identifier_tracker.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.identifier_tracker.insert(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.identifier_tracker.insert_self();
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>) {
+        self.identifier_tracker.insert_local(identifier, value);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.identifier_tracker.insert_local_function(function);
+    }
+}
+
+impl NodeProcessor for QueryFinder<'_> {
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name();
+        self.next_function = Some(Some(function_name_to_string(name)));
+
+        if matches!(self.query, NodeQuery::AssignGlobal)
+            && name.get_field_names().is_empty()
+            && !name.has_method()
+        {
+            let identifier = name.get_name();
+            self.verify_global_assignment(
+                identifier,
+                format!("function {}", identifier.get_name()),
+            );
+        }
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        self.next_function = Some(Some(function.get_name().to_owned()));
+    }
+
+    fn process_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.next_function = Some(None);
+    }
+
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        if matches!(self.query, NodeQuery::AssignGlobal) {
+            for identifier in assign.iter_variables().filter_map(variable_identifier) {
+                self.verify_global_assignment(identifier, identifier.get_name().to_owned());
+            }
+        }
+    }
+
+    fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
+        if matches!(self.query, NodeQuery::AssignGlobal) {
+            if let Some(identifier) = variable_identifier(assign.get_variable()) {
+                self.verify_global_assignment(identifier, identifier.get_name().to_owned());
+            }
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if let NodeQuery::Call(pattern) = self.query {
+            if pattern.matches(call) {
+                let snippet = generate_snippet(call.clone().into());
+                self.push_match(prefix_token(call.get_prefix()), snippet);
+            }
+        }
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        let is_match = match self.query {
+            NodeQuery::Global(name) => identifier.get_name() == name && self.is_global(identifier),
+            NodeQuery::EnvGlobal(name) => {
+                identifier.get_name() == name && self.is_environment_field(identifier)
+            }
+            _ => false,
+        };
+
+        if is_match {
+            let name = identifier.get_name().to_owned();
+            self.push_match(identifier.get_token(), name);
+        }
+    }
+
+    fn process_string_expression(&mut self, string: &mut StringExpression) {
+        if let NodeQuery::String(text) = self.query {
+            if string.get_value().contains(text.as_str()) {
+                let snippet = generate_snippet(string.clone().into());
+                self.push_match(string.get_token(), snippet);
+            }
+        }
+    }
+
+    fn process_number_expression(&mut self, number: &mut NumberExpression) {
+        if let NodeQuery::NumberAbove(threshold) = self.query {
+            if number.compute_value() > *threshold {
+                let snippet = generate_snippet(number.clone().into());
+                self.push_match(number.get_token(), snippet);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Parser;
+
+    fn find(query: &str, code: &str) -> Vec<QueryMatch> {
+        let query: NodeQuery = query.parse().expect("unable to parse query");
+        let mut block = Parser::default()
+            .preserve_tokens()
+            .parse(code)
+            .expect("unable to parse code");
+        query.find(&mut block, code)
+    }
+
+    fn find_positions(query: &str, code: &str) -> Vec<(usize, usize)> {
+        find(query, code)
+            .into_iter()
+            .map(|found| (found.line().unwrap(), found.column().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_call_pattern_with_method() {
+        pretty_assertions::assert_eq!(
+            "call:game:GetService".parse::<NodeQuery>(),
+            Ok(NodeQuery::Call(CallPattern {
+                names: vec!["game".to_owned()],
+                method: Some("GetService".to_owned()),
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_number_pattern() {
+        pretty_assertions::assert_eq!(
+            "number:>100".parse::<NodeQuery>(),
+            Ok(NodeQuery::NumberAbove(100.0))
+        );
+    }
+
+    #[test]
+    fn parse_unknown_kind_errors() {
+        pretty_assertions::assert_eq!(
+            "method:print".parse::<NodeQuery>(),
+            Err(
+                "unknown pattern kind `method` (must be `call`, `global`, `env-global`, \
+                `string`, `assign-global` or `number`)"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_invalid_call_pattern_errors() {
+        pretty_assertions::assert_eq!(
+            "call:game.1".parse::<NodeQuery>(),
+            Err("invalid call pattern `game.1`: `1` is not an identifier or `*`".to_owned())
+        );
+    }
+
+    #[test]
+    fn find_method_call() {
+        let code = "local Players = game:GetService('Players')\nreturn game.GetService(game)";
+
+        pretty_assertions::assert_eq!(find_positions("call:game:GetService", code), vec![(1, 17)]);
+    }
+
+    #[test]
+    fn find_call_with_wildcard() {
+        let code = "table.insert(t, 1)\n  table.remove(t)\nstring.format('')";
+
+        pretty_assertions::assert_eq!(find_positions("call:table.*", code), vec![(1, 1), (2, 3)]);
+    }
+
+    #[test]
+    fn find_call_snippet() {
+        let found = find("call:print", "print('hello', 1 + 2)");
+
+        pretty_assertions::assert_eq!(found[0].snippet(), "print('hello',1+2)");
+    }
+
+    #[test]
+    fn find_global_reads_and_writes() {
+        let code =
+            "counter = 1\nlocal function f(counter)\n    return counter\nend\nreturn counter";
+
+        pretty_assertions::assert_eq!(find_positions("global:counter", code), vec![(1, 1), (5, 8)]);
+    }
+
+    #[test]
+    fn find_global_reads_outside_env_scope() {
+        let code = "print(1)\nlocal function f(_ENV)\n    print(2)\nend\nprint(3)";
+
+        pretty_assertions::assert_eq!(find_positions("global:print", code), vec![(1, 1), (5, 1)]);
+    }
+
+    #[test]
+    fn find_env_global_reads() {
+        let code = "print(1)\nlocal function f(_ENV)\n    local print = print\n    print(2)\nend";
+
+        pretty_assertions::assert_eq!(find_positions("env-global:print", code), vec![(3, 19)]);
+    }
+
+    #[test]
+    fn find_global_assignments_outside_env_scope() {
+        let code = "a = 1\ndo\n    local _ENV = {}\n    b = 2\nend";
+
+        pretty_assertions::assert_eq!(find_positions("assign-global:*", code), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn find_string_literals() {
+        let code = "local a = 'http://example.com'\nlocal b = \"other\"\nfetch [[http://]]";
+
+        pretty_assertions::assert_eq!(find_positions("string:http", code), vec![(1, 11), (3, 7)]);
+    }
+
+    #[test]
+    fn find_global_assignments() {
+        let code = "local a\na, b = 1, 2\nfunction c() end\nfunction a.d() end\nc += 1";
+
+        let found = find("assign-global:*", code);
+
+        pretty_assertions::assert_eq!(
+            found
+                .iter()
+                .map(|found| (found.line().unwrap(), found.snippet()))
+                .collect::<Vec<_>>(),
+            vec![(2, "b"), (3, "function c"), (5, "c")]
+        );
+    }
+
+    #[test]
+    fn find_numbers_above_threshold() {
+        let code = "return 10, 200, 0xFF, 100";
+
+        pretty_assertions::assert_eq!(
+            find("number:>100", code)
+                .iter()
+                .map(QueryMatch::snippet)
+                .collect::<Vec<_>>(),
+            vec!["200", "0xff"]
+        );
+    }
+
+    #[test]
+    fn find_enclosing_function_name() {
+        let code =
+            "function M.load()\n    local f = function() return print end\n    return print\nend";
+
+        pretty_assertions::assert_eq!(
+            find("global:print", code)
+                .iter()
+                .map(|found| found.function().map(ToOwned::to_owned))
+                .collect::<Vec<_>>(),
+            vec![None, Some("M.load".to_owned())]
+        );
+    }
+
+    #[test]
+    fn display_match() {
+        let found = find("call:print", "local function run()\n  print('hi')\nend");
+
+        pretty_assertions::assert_eq!(found[0].to_string(), "2:3: call print('hi') (in `run`)");
+    }
+}
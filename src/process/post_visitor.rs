@@ -2,6 +2,8 @@ use std::marker::PhantomData;
 
 use crate::nodes::*;
 
+use super::deadline;
+use super::node_path::{self, NodePathSegment};
 use super::node_processor::{NodePostProcessor, NodeProcessor};
 
 /// Similar to the NodeVisitor, except that visits the AST using a NodePostVisitor, which
@@ -10,12 +12,24 @@ pub trait NodePostVisitor<T: NodeProcessor + NodePostProcessor> {
     fn visit_block(block: &mut Block, processor: &mut T) {
         processor.process_block(block);
 
-        block
-            .iter_mut_statements()
-            .for_each(|statement| Self::visit_statement(statement, processor));
+        for (index, statement) in block.iter_mut_statements().enumerate() {
+            // stop visiting when the deadline of the rule has passed
+            if deadline::is_expired() {
+                return;
+            }
+            node_path::enter(NodePathSegment::Statement(index));
+            Self::visit_statement(statement, processor);
+            node_path::leave();
+        }
+
+        if deadline::is_expired() {
+            return;
+        }
 
         if let Some(last_statement) = block.mutate_last_statement() {
+            node_path::enter(NodePathSegment::LastStatement);
             Self::visit_last_statement(last_statement, processor);
+            node_path::leave();
         };
         processor.process_after_block(block);
     }
@@ -23,6 +37,11 @@ pub trait NodePostVisitor<T: NodeProcessor + NodePostProcessor> {
     fn visit_statement(statement: &mut Statement, processor: &mut T) {
         processor.process_statement(statement);
 
+        let kind = node_path::statement_kind(statement);
+        if let Some(kind) = kind {
+            node_path::enter(NodePathSegment::Kind(kind));
+        }
+
         match statement {
             Statement::Assign(statement) => Self::visit_assign_statement(statement, processor),
             Statement::Do(statement) => Self::visit_do_statement(statement, processor),
@@ -41,7 +60,13 @@ pub trait NodePostVisitor<T: NodeProcessor + NodePostProcessor> {
             Statement::TypeDeclaration(statement) => {
                 Self::visit_type_declaration(statement, processor)
             }
+            // the content of verbatim statements is not Lua code
+            Statement::Verbatim(_) => {}
         };
+
+        if kind.is_some() {
+            node_path::leave();
+        }
         processor.process_after_statement(statement);
     }
 
@@ -153,7 +178,13 @@ pub trait NodePostVisitor<T: NodeProcessor + NodePostProcessor> {
 
         processor.process_scope(function.mutate_block(), None);
 
+        node_path::enter(NodePathSegment::Kind("function"));
         Self::visit_block(function.mutate_block(), processor);
+        node_path::leave();
+
+        if let Some(generic_parameters) = function.mutate_generic_parameters() {
+            Self::visit_generic_parameters(generic_parameters, processor);
+        }
 
         for r#type in function
             .iter_mut_parameters()
@@ -211,6 +242,10 @@ pub trait NodePostVisitor<T: NodeProcessor + NodePostProcessor> {
         processor.process_scope(statement.mutate_block(), None);
         Self::visit_block(statement.mutate_block(), processor);
 
+        if let Some(generic_parameters) = statement.mutate_generic_parameters() {
+            Self::visit_generic_parameters(generic_parameters, processor);
+        }
+
         for r#type in statement
             .iter_mut_parameters()
             .filter_map(TypedIdentifier::mutate_type)
@@ -250,15 +285,23 @@ pub trait NodePostVisitor<T: NodeProcessor + NodePostProcessor> {
     fn visit_if_statement(statement: &mut IfStatement, processor: &mut T) {
         processor.process_if_statement(statement);
 
-        statement.mutate_branches().iter_mut().for_each(|branch| {
-            Self::visit_expression(branch.mutate_condition(), processor);
-            processor.process_scope(branch.mutate_block(), None);
-            Self::visit_block(branch.mutate_block(), processor);
-        });
+        statement
+            .mutate_branches()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(index, branch)| {
+                node_path::enter(NodePathSegment::Branch(index));
+                Self::visit_expression(branch.mutate_condition(), processor);
+                processor.process_scope(branch.mutate_block(), None);
+                Self::visit_block(branch.mutate_block(), processor);
+                node_path::leave();
+            });
 
         if let Some(block) = statement.mutate_else_block() {
+            node_path::enter(NodePathSegment::Else);
             processor.process_scope(block, None);
             Self::visit_block(block, processor);
+            node_path::leave();
         }
         processor.process_after_if_statement(statement);
     }
@@ -284,6 +327,10 @@ pub trait NodePostVisitor<T: NodeProcessor + NodePostProcessor> {
         processor.process_scope(statement.mutate_block(), None);
         Self::visit_block(statement.mutate_block(), processor);
 
+        if let Some(generic_parameters) = statement.mutate_generic_parameters() {
+            Self::visit_generic_parameters(generic_parameters, processor);
+        }
+
         for r#type in statement
             .iter_mut_parameters()
             .filter_map(TypedIdentifier::mutate_type)
@@ -317,6 +364,15 @@ pub trait NodePostVisitor<T: NodeProcessor + NodePostProcessor> {
         processor.process_after_generic_type_pack(generic);
     }
 
+    fn visit_generic_parameters(generic_parameters: &mut GenericParameters, processor: &mut T) {
+        processor.process_generic_parameters(generic_parameters);
+
+        for generic_type_pack in generic_parameters.iter_mut_generic_type_pack() {
+            Self::visit_generic_type_pack(generic_type_pack, processor);
+        }
+        processor.process_after_generic_parameters(generic_parameters);
+    }
+
     fn visit_numeric_for(statement: &mut NumericForStatement, processor: &mut T) {
         processor.process_numeric_for_statement(statement);
 
@@ -587,6 +643,10 @@ pub trait NodePostVisitor<T: NodeProcessor + NodePostProcessor> {
     fn visit_function_type(function: &mut FunctionType, processor: &mut T) {
         processor.process_function_type(function);
 
+        if let Some(generic_parameters) = function.mutate_generic_parameters() {
+            Self::visit_generic_parameters(generic_parameters, processor);
+        }
+
         for argument in function.iter_mut_arguments() {
             Self::visit_type(argument.mutate_type(), processor);
         }
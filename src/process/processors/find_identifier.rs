@@ -64,7 +64,7 @@ impl NodeProcessor for FindVariables<'_> {
     fn process_variable_expression(&mut self, variable: &mut Identifier) {
         if !self.usage_found {
             let name = variable.get_name();
-            self.usage_found = self.variables.iter().any(|v| *v == name)
+            self.usage_found = self.variables.contains(&name)
         }
     }
 }
@@ -2,9 +2,12 @@ use std::collections::HashSet;
 use std::ops::DerefMut;
 
 use crate::nodes::*;
-use crate::process::utils::is_valid_identifier;
-use crate::process::{NodeProcessor, NodeVisitor};
+use crate::process::utils::is_valid_generated_identifier;
+use crate::process::{NodePathSegment, NodeProcessor, NodeVisitor};
+use crate::rules::DEFAULT_GENERATED_NAME_PREFIX;
 
+use super::deadline;
+use super::node_path;
 use super::utils::{identifier_permutator, Permutator};
 
 /// Defines methods to interact with the concept of lexical scoping. The struct implementing this
@@ -17,11 +20,11 @@ pub trait Scope {
     fn pop(&mut self);
     /// Called when entering a function block (with each parameters of the function), with the
     /// identifiers from a generic for statement or the identifier from a numeric for loop.
-    fn insert(&mut self, identifier: &mut String);
+    fn insert(&mut self, identifier: &mut Identifier);
     /// Called when entering a function defined with a method
     fn insert_self(&mut self);
     /// Called when a new local variable is initialized.
-    fn insert_local(&mut self, identifier: &mut String, value: Option<&mut Expression>);
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>);
     /// Called when a new local function is initialized.
     fn insert_local_function(&mut self, function: &mut LocalFunctionStatement);
 }
@@ -33,12 +36,24 @@ impl ScopeVisitor {
     fn visit_block_without_push<T: NodeProcessor + Scope>(block: &mut Block, scope: &mut T) {
         scope.process_block(block);
 
-        block
-            .iter_mut_statements()
-            .for_each(|statement| Self::visit_statement(statement, scope));
+        for (index, statement) in block.iter_mut_statements().enumerate() {
+            // stop visiting when the deadline of the rule has passed
+            if deadline::is_expired() {
+                return;
+            }
+            node_path::enter(NodePathSegment::Statement(index));
+            Self::visit_statement(statement, scope);
+            node_path::leave();
+        }
+
+        if deadline::is_expired() {
+            return;
+        }
 
         if let Some(last_statement) = block.mutate_last_statement() {
+            node_path::enter(NodePathSegment::LastStatement);
             Self::visit_last_statement(last_statement, scope);
+            node_path::leave();
         };
     }
 }
@@ -64,14 +79,17 @@ impl<T: NodeProcessor + Scope> NodeVisitor<T> for ScopeVisitor {
             Self::visit_type(r#type, scope);
         }
 
-        statement.for_each_assignment(|variable, expression| {
-            scope.insert_local(variable.mutate_name(), expression)
-        });
+        statement
+            .for_each_assignment(|variable, expression| scope.insert_local(variable, expression));
     }
 
     fn visit_function_expression(function: &mut FunctionExpression, scope: &mut T) {
         scope.process_function_expression(function);
 
+        if let Some(generic_parameters) = function.mutate_generic_parameters() {
+            Self::visit_generic_parameters(generic_parameters, scope);
+        }
+
         for r#type in function
             .iter_mut_parameters()
             .filter_map(TypedIdentifier::mutate_type)
@@ -91,11 +109,13 @@ impl<T: NodeProcessor + Scope> NodeVisitor<T> for ScopeVisitor {
         function
             .mutate_parameters()
             .iter_mut()
-            .for_each(|parameter| scope.insert(parameter.mutate_name()));
+            .for_each(|parameter| scope.insert(parameter));
 
         scope.process_scope(function.mutate_block(), None);
 
+        node_path::enter(NodePathSegment::Kind("function"));
         Self::visit_block(function.mutate_block(), scope);
+        node_path::leave();
         scope.pop();
     }
 
@@ -103,6 +123,10 @@ impl<T: NodeProcessor + Scope> NodeVisitor<T> for ScopeVisitor {
         scope.process_function_statement(statement);
         scope.process_variable_expression(statement.mutate_function_name().mutate_identifier());
 
+        if let Some(generic_parameters) = statement.mutate_generic_parameters() {
+            Self::visit_generic_parameters(generic_parameters, scope);
+        }
+
         for r#type in statement
             .iter_mut_parameters()
             .filter_map(TypedIdentifier::mutate_type)
@@ -125,7 +149,7 @@ impl<T: NodeProcessor + Scope> NodeVisitor<T> for ScopeVisitor {
         statement
             .mutate_parameters()
             .iter_mut()
-            .for_each(|parameter| scope.insert(parameter.mutate_name()));
+            .for_each(|parameter| scope.insert(parameter));
 
         scope.process_scope(statement.mutate_block(), None);
 
@@ -138,6 +162,10 @@ impl<T: NodeProcessor + Scope> NodeVisitor<T> for ScopeVisitor {
 
         scope.insert_local_function(statement);
 
+        if let Some(generic_parameters) = statement.mutate_generic_parameters() {
+            Self::visit_generic_parameters(generic_parameters, scope);
+        }
+
         for r#type in statement
             .iter_mut_parameters()
             .filter_map(TypedIdentifier::mutate_type)
@@ -157,7 +185,7 @@ impl<T: NodeProcessor + Scope> NodeVisitor<T> for ScopeVisitor {
         statement
             .mutate_parameters()
             .iter_mut()
-            .for_each(|parameter| scope.insert(parameter.mutate_name()));
+            .for_each(|parameter| scope.insert(parameter));
 
         scope.process_scope(statement.mutate_block(), None);
 
@@ -175,7 +203,7 @@ impl<T: NodeProcessor + Scope> NodeVisitor<T> for ScopeVisitor {
         scope.push();
         statement
             .iter_mut_identifiers()
-            .for_each(|identifier| scope.insert(identifier.mutate_name()));
+            .for_each(|identifier| scope.insert(identifier));
 
         for r#type in statement
             .iter_mut_identifiers()
@@ -205,7 +233,7 @@ impl<T: NodeProcessor + Scope> NodeVisitor<T> for ScopeVisitor {
         }
 
         scope.push();
-        scope.insert(statement.mutate_identifier().mutate_name());
+        scope.insert(statement.mutate_identifier());
 
         scope.process_scope(statement.mutate_block(), None);
 
@@ -228,13 +256,21 @@ impl<T: NodeProcessor + Scope> NodeVisitor<T> for ScopeVisitor {
     }
 }
 
+/// The name of the variable holding the environment in Lua 5.2. When a local variable or a
+/// parameter uses this name, free identifiers in its scope are read from that value instead
+/// of the global environment.
+pub(crate) const ENVIRONMENT_IDENTIFIER: &str = "_ENV";
+
 #[derive(Debug, Clone, Default)]
 pub(crate) struct IdentifierTracker {
     identifiers: Vec<HashSet<String>>,
+    reserved_identifiers: HashSet<String>,
+    generated_name_prefix: Option<String>,
+    assume_no_env_manipulation: bool,
 }
 
 impl IdentifierTracker {
-    fn insert_identifier(&mut self, identifier: &str) {
+    pub(crate) fn insert_identifier(&mut self, identifier: &str) {
         if let Some(set) = self.identifiers.last_mut() {
             set.insert(identifier.to_string());
         } else {
@@ -247,19 +283,68 @@ impl IdentifierTracker {
     pub fn new() -> IdentifierTracker {
         Self {
             identifiers: Vec::new(),
+            reserved_identifiers: HashSet::new(),
+            generated_name_prefix: None,
+            assume_no_env_manipulation: false,
         }
     }
 
+    /// Ignores locals and parameters named `_ENV`, so that free identifiers are always
+    /// considered to refer to the global environment.
+    pub fn with_assume_no_env_manipulation(mut self, value: bool) -> Self {
+        self.assume_no_env_manipulation = value;
+        self
+    }
+
+    /// Sets the prefix used by `generate_named_identifier`, usually obtained from the
+    /// `generated_name_prefix` of the rule context.
+    pub fn with_generated_name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.generated_name_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Avoids the given identifiers when generating identifiers, usually obtained from the
+    /// `reserved_identifiers` of the rule context.
+    pub fn with_reserved_identifiers(
+        mut self,
+        identifiers: impl IntoIterator<Item = String>,
+    ) -> Self {
+        self.reserved_identifiers.extend(identifiers);
+        self
+    }
+
+    /// Returns true when a previous rule reserved the identifier.
+    pub fn is_identifier_reserved(&self, identifier: &str) -> bool {
+        self.reserved_identifiers.contains(identifier)
+    }
+
+    fn is_identifier_available(&self, identifier: &str) -> bool {
+        !self.is_identifier_used(identifier) && !self.is_identifier_reserved(identifier)
+    }
+
     pub fn is_identifier_used(&self, identifier: &str) -> bool {
         self.identifiers.iter().any(|set| set.contains(identifier))
     }
 
+    /// Returns true when the current scope declares a local or a parameter named `_ENV`,
+    /// meaning that free identifiers are fields of that value rather than globals.
+    pub fn is_environment_replaced(&self) -> bool {
+        !self.assume_no_env_manipulation && self.is_identifier_used(ENVIRONMENT_IDENTIFIER)
+    }
+
+    /// Returns true when the identifier refers to the actual global variable: it is not
+    /// shadowed by a local and the environment has not been replaced.
+    pub fn is_global(&self, identifier: &str) -> bool {
+        !self.is_identifier_used(identifier) && !self.is_environment_replaced()
+    }
+
     pub fn generate_identifier(&mut self) -> String {
         let mut permutator = identifier_permutator();
 
         let identifier = permutator
             .find(|identifier| {
-                is_valid_identifier(identifier) && !self.is_identifier_used(identifier)
+                is_valid_generated_identifier(identifier)
+                    && self.is_identifier_available(identifier)
             })
             .expect("the permutator should always ultimately return a valid identifier");
         self.insert_identifier(&identifier);
@@ -274,7 +359,7 @@ impl IdentifierTracker {
         let initial_length = identifier.len();
         let mut permutator = Permutator::new("012345689".chars());
 
-        while self.is_identifier_used(&identifier) {
+        while !self.is_identifier_available(&identifier) {
             identifier.truncate(initial_length);
             let next_suffix = permutator.next().unwrap_or_else(|| "_".to_owned());
             identifier.push_str(&next_suffix);
@@ -282,6 +367,22 @@ impl IdentifierTracker {
         self.insert_identifier(&identifier);
         identifier
     }
+
+    /// Returns the given name with the generated name prefix, without verifying if the
+    /// identifier is already used.
+    pub fn generated_name(&self, name: &str) -> String {
+        let prefix = self
+            .generated_name_prefix
+            .as_deref()
+            .unwrap_or(DEFAULT_GENERATED_NAME_PREFIX);
+        format!("{}{}", prefix, name)
+    }
+
+    /// Generates an unused identifier made from the generated name prefix and the given name.
+    pub fn generate_named_identifier(&mut self, name: &str) -> String {
+        let identifier = self.generated_name(name);
+        self.generate_identifier_with_prefix(identifier)
+    }
 }
 
 impl Scope for IdentifierTracker {
@@ -293,16 +394,16 @@ impl Scope for IdentifierTracker {
         self.identifiers.pop();
     }
 
-    fn insert(&mut self, identifier: &mut String) {
-        self.insert_identifier(identifier);
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.insert_identifier(identifier.get_name());
     }
 
     fn insert_self(&mut self) {
         self.insert_identifier("self");
     }
 
-    fn insert_local(&mut self, identifier: &mut String, _value: Option<&mut Expression>) {
-        self.insert_identifier(identifier);
+    fn insert_local(&mut self, identifier: &mut Identifier, _value: Option<&mut Expression>) {
+        self.insert_identifier(identifier.get_name());
     }
 
     fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
@@ -327,7 +428,7 @@ where
     }
 
     #[inline]
-    fn insert(&mut self, identifier: &mut String) {
+    fn insert(&mut self, identifier: &mut Identifier) {
         self.deref_mut().insert(identifier);
     }
 
@@ -337,7 +438,7 @@ where
     }
 
     #[inline]
-    fn insert_local(&mut self, identifier: &mut String, value: Option<&mut Expression>) {
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>) {
         self.deref_mut().insert_local(identifier, value)
     }
 
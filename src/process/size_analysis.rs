@@ -0,0 +1,679 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::nodes::{
+    Block, Expression, FieldExpression, FunctionBodyTokens, FunctionCall, FunctionExpression,
+    FunctionStatement, GenericForStatement, Identifier, InterpolatedStringExpression,
+    InterpolationSegment, LocalAssignStatement, LocalFunctionStatement, NumberExpression,
+    NumericForStatement, StringExpression, StringType, TableEntry, TableExpression, Token,
+    TypedIdentifier,
+};
+use crate::process::node_query::function_name_to_string;
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, Scope, ScopeVisitor};
+
+/// The categories used by [`SizeAnalysis`] to attribute each byte of some Lua code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SizeCategory {
+    /// Names of local variables, local functions and parameters.
+    LocalIdentifiers,
+    /// Names of global variables.
+    GlobalIdentifiers,
+    /// Names of fields and methods (like `insert` in `table.insert`).
+    FieldIdentifiers,
+    Strings,
+    Numbers,
+    /// Keywords, operators and punctuation. Other names, like the names of types, are also
+    /// part of this category.
+    KeywordsAndSymbols,
+    Comments,
+    Whitespace,
+}
+
+impl SizeCategory {
+    /// Every category, in the order they are reported.
+    pub const ALL: [SizeCategory; 8] = [
+        Self::LocalIdentifiers,
+        Self::GlobalIdentifiers,
+        Self::FieldIdentifiers,
+        Self::Strings,
+        Self::Numbers,
+        Self::KeywordsAndSymbols,
+        Self::Comments,
+        Self::Whitespace,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::LocalIdentifiers => "local identifiers",
+            Self::GlobalIdentifiers => "global identifiers",
+            Self::FieldIdentifiers => "field identifiers",
+            Self::Strings => "strings",
+            Self::Numbers => "numbers",
+            Self::KeywordsAndSymbols => "keywords and symbols",
+            Self::Comments => "comments",
+            Self::Whitespace => "whitespace",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for SizeCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// An identifier or a string literal, with the number of times it appears in the code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrences {
+    content: String,
+    count: usize,
+}
+
+impl Occurrences {
+    /// The identifier, or the string literal as written in the code (with its quotes).
+    #[inline]
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// The size of the content in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The number of bytes used by all the occurrences.
+    #[inline]
+    pub fn total_size(&self) -> usize {
+        self.len() * self.count
+    }
+}
+
+/// The size of the code of a function, including the functions defined inside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSize {
+    name: Option<String>,
+    line: Option<usize>,
+    size: usize,
+}
+
+impl FunctionSize {
+    /// The name of the function, when it is defined with a function statement or a local
+    /// function statement.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    #[inline]
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// The size of the function in bytes, from its `local` or `function` keyword to its
+    /// `end` keyword.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Attributes each byte of some Lua code to a [`SizeCategory`], to find what takes space in
+/// generated code.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SizeAnalysis {
+    size: usize,
+    categories: [usize; SizeCategory::ALL.len()],
+    identifiers: HashMap<String, usize>,
+    strings: HashMap<String, usize>,
+    functions: Vec<FunctionSize>,
+}
+
+impl SizeAnalysis {
+    /// Analyzes the code of a block. The block must be parsed from the given code with its
+    /// tokens (see [`Parser::preserve_tokens`](crate::Parser::preserve_tokens)), since the
+    /// tokens locate each node in the code.
+    pub fn analyze(block: &mut Block, code: &str) -> Self {
+        let mut collector = SizeCollector::new(code);
+        ScopeVisitor::visit_block(block, &mut collector);
+        collector.into_analysis()
+    }
+
+    /// Adds the sizes of another analysis to this analysis.
+    pub fn merge(&mut self, other: &SizeAnalysis) {
+        self.size += other.size;
+
+        for (size, other_size) in self.categories.iter_mut().zip(other.categories) {
+            *size += other_size;
+        }
+
+        for (identifier, count) in other.identifiers.iter() {
+            *self.identifiers.entry(identifier.clone()).or_default() += count;
+        }
+
+        for (string, count) in other.strings.iter() {
+            *self.strings.entry(string.clone()).or_default() += count;
+        }
+
+        self.functions.extend(other.functions.iter().cloned());
+        self.functions
+            .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.line.cmp(&b.line)));
+    }
+
+    /// The size of the code in bytes.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The number of bytes attributed to the category. The sizes of all the categories add
+    /// up to the size of the code.
+    #[inline]
+    pub fn category_size(&self, category: SizeCategory) -> usize {
+        self.categories[category.index()]
+    }
+
+    /// The identifiers (variables and fields) of the code, from the longest to the shortest.
+    /// Identifiers of the same length are sorted from the most used.
+    pub fn longest_identifiers(&self) -> Vec<Occurrences> {
+        sort_occurrences(&self.identifiers)
+    }
+
+    /// The string literals of the code, from the longest to the shortest. Strings of the
+    /// same length are sorted from the most used.
+    pub fn longest_strings(&self) -> Vec<Occurrences> {
+        sort_occurrences(&self.strings)
+    }
+
+    /// The functions of the code, from the largest to the smallest.
+    #[inline]
+    pub fn functions(&self) -> &[FunctionSize] {
+        &self.functions
+    }
+}
+
+fn sort_occurrences(counts: &HashMap<String, usize>) -> Vec<Occurrences> {
+    let mut occurrences: Vec<_> = counts
+        .iter()
+        .map(|(content, count)| Occurrences {
+            content: content.clone(),
+            count: *count,
+        })
+        .collect();
+
+    occurrences.sort_by(|a, b| {
+        b.len()
+            .cmp(&a.len())
+            .then_with(|| b.count.cmp(&a.count))
+            .then_with(|| a.content.cmp(&b.content))
+    });
+
+    occurrences
+}
+
+struct SizeCollector<'a> {
+    code: &'a str,
+    // the category of each byte of the code, when it is known
+    bytes: Vec<Option<SizeCategory>>,
+    identifier_tracker: IdentifierTracker,
+    identifiers: HashMap<String, usize>,
+    strings: HashMap<String, usize>,
+    functions: Vec<FunctionSize>,
+}
+
+impl<'a> SizeCollector<'a> {
+    fn new(code: &'a str) -> Self {
+        Self {
+            code,
+            bytes: vec![None; code.len()],
+            identifier_tracker: IdentifierTracker::new(),
+            identifiers: HashMap::new(),
+            strings: HashMap::new(),
+            functions: Vec::new(),
+        }
+    }
+
+    /// Returns the content of a token that references the code, with its position.
+    fn token_content(&self, token: &Token) -> Option<(usize, &'a str)> {
+        let start = token.get_start_position()?;
+        let length = token.read(self.code).len();
+        Some((start, self.code.get(start..start + length)?))
+    }
+
+    fn mark(&mut self, token: &Token, category: SizeCategory) -> Option<&'a str> {
+        let (start, content) = self.token_content(token)?;
+
+        if let Some(bytes) = self.bytes.get_mut(start..start + content.len()) {
+            bytes.fill(Some(category));
+        }
+
+        Some(content)
+    }
+
+    fn mark_identifier(&mut self, identifier: &Identifier, category: SizeCategory) {
+        if let Some(token) = identifier.get_token() {
+            if let Some(content) = self.mark(token, category) {
+                *self.identifiers.entry(content.to_owned()).or_default() += 1;
+            }
+        }
+    }
+
+    fn mark_locals<'b>(&mut self, identifiers: impl Iterator<Item = &'b TypedIdentifier>) {
+        for identifier in identifiers {
+            self.mark_identifier(identifier.get_identifier(), SizeCategory::LocalIdentifiers);
+        }
+    }
+
+    fn mark_string(&mut self, token: Option<&Token>) {
+        if let Some(content) = token.and_then(|token| self.mark(token, SizeCategory::Strings)) {
+            *self.strings.entry(content.to_owned()).or_default() += 1;
+        }
+    }
+
+    fn push_function(&mut self, name: Option<String>, first: &Token, tokens: &FunctionBodyTokens) {
+        let end = self
+            .token_content(&tokens.end)
+            .map(|(start, content)| start + content.len());
+
+        if let (Some(start), Some(end)) = (first.get_start_position(), end) {
+            self.functions.push(FunctionSize {
+                name,
+                line: first.get_line_number(),
+                size: end.saturating_sub(start),
+            });
+        }
+    }
+
+    fn into_analysis(mut self) -> SizeAnalysis {
+        self.mark_remaining_bytes();
+
+        let mut categories = [0; SizeCategory::ALL.len()];
+        for category in self.bytes.iter().flatten() {
+            categories[category.index()] += 1;
+        }
+
+        self.functions
+            .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.line.cmp(&b.line)));
+
+        SizeAnalysis {
+            size: self.code.len(),
+            categories,
+            identifiers: self.identifiers,
+            strings: self.strings,
+            functions: self.functions,
+        }
+    }
+
+    /// The bytes that are not part of an identifier, a string or a number are comments,
+    /// whitespace, keywords and symbols.
+    fn mark_remaining_bytes(&mut self) {
+        let mut position = 0;
+
+        while position < self.code.len() {
+            if self.bytes[position].is_some() {
+                position += 1;
+                continue;
+            }
+
+            let rest = &self.code[position..];
+
+            let (length, category) = if rest.starts_with("--") {
+                (comment_length(rest), SizeCategory::Comments)
+            } else {
+                let character = rest.chars().next().expect("rest should not be empty");
+                let category = if character.is_whitespace() {
+                    SizeCategory::Whitespace
+                } else {
+                    SizeCategory::KeywordsAndSymbols
+                };
+                (character.len_utf8(), category)
+            };
+
+            self.bytes[position..position + length].fill(Some(category));
+            position += length;
+        }
+    }
+}
+
+fn long_bracket_level(code: &str) -> Option<usize> {
+    let rest = code.strip_prefix('[')?;
+    let level = rest
+        .chars()
+        .take_while(|character| *character == '=')
+        .count();
+    rest[level..].starts_with('[').then_some(level)
+}
+
+/// Returns the length of the comment at the start of the code.
+fn comment_length(code: &str) -> usize {
+    let content = &code[2..];
+
+    if let Some(level) = long_bracket_level(content) {
+        let closing = format!("]{}]", "=".repeat(level));
+        let opening_length = level + 2;
+
+        content[opening_length..]
+            .find(&closing)
+            .map(|index| 2 + opening_length + index + closing.len())
+            .unwrap_or(code.len())
+    } else {
+        code.find('\n').unwrap_or(code.len())
+    }
+}
+
+impl Scope for SizeCollector<'_> {
+    fn push(&mut self) {
+        self.identifier_tracker.push();
+    }
+
+    fn pop(&mut self) {
+        self.identifier_tracker.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.identifier_tracker.insert(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.identifier_tracker.insert_self();
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>) {
+        self.identifier_tracker.insert_local(identifier, value);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.identifier_tracker.insert_local_function(function);
+    }
+}
+
+impl NodeProcessor for SizeCollector<'_> {
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        let category = if self
+            .identifier_tracker
+            .is_identifier_used(identifier.get_name())
+        {
+            SizeCategory::LocalIdentifiers
+        } else {
+            SizeCategory::GlobalIdentifiers
+        };
+        self.mark_identifier(identifier, category);
+    }
+
+    fn process_field_expression(&mut self, field: &mut FieldExpression) {
+        self.mark_identifier(field.get_field(), SizeCategory::FieldIdentifiers);
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if let Some(method) = call.get_method() {
+            self.mark_identifier(method, SizeCategory::FieldIdentifiers);
+        }
+    }
+
+    fn process_table_expression(&mut self, table: &mut TableExpression) {
+        for entry in table.iter_entries() {
+            if let TableEntry::Field(entry) = entry {
+                self.mark_identifier(entry.get_field(), SizeCategory::FieldIdentifiers);
+            }
+        }
+    }
+
+    fn process_local_assign_statement(&mut self, assign: &mut LocalAssignStatement) {
+        self.mark_locals(assign.iter_variables());
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        self.mark_identifier(function.get_identifier(), SizeCategory::LocalIdentifiers);
+        self.mark_locals(function.iter_parameters());
+
+        if let Some(tokens) = function.get_tokens() {
+            self.push_function(Some(function.get_name().to_owned()), &tokens.local, tokens);
+        }
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name();
+        for field in name.get_field_names() {
+            self.mark_identifier(field, SizeCategory::FieldIdentifiers);
+        }
+        if let Some(method) = name.get_method() {
+            self.mark_identifier(method, SizeCategory::FieldIdentifiers);
+        }
+
+        self.mark_locals(function.iter_parameters());
+
+        if let Some(tokens) = function.get_tokens() {
+            self.push_function(
+                Some(function_name_to_string(function.get_name())),
+                &tokens.function,
+                tokens,
+            );
+        }
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        self.mark_locals(function.iter_parameters());
+
+        if let Some(tokens) = function.get_tokens() {
+            self.push_function(None, &tokens.function, tokens);
+        }
+    }
+
+    fn process_generic_for_statement(&mut self, generic_for: &mut GenericForStatement) {
+        self.mark_locals(generic_for.iter_identifiers());
+    }
+
+    fn process_numeric_for_statement(&mut self, numeric_for: &mut NumericForStatement) {
+        self.mark_locals(std::iter::once(numeric_for.get_identifier()));
+    }
+
+    fn process_string_expression(&mut self, string: &mut StringExpression) {
+        self.mark_string(string.get_token());
+    }
+
+    fn process_string_type(&mut self, string_type: &mut StringType) {
+        self.mark_string(string_type.get_token());
+    }
+
+    fn process_interpolated_string_expression(
+        &mut self,
+        interpolated_string: &mut InterpolatedStringExpression,
+    ) {
+        if let Some(tokens) = interpolated_string.get_tokens() {
+            self.mark(&tokens.opening_tick, SizeCategory::Strings);
+            self.mark(&tokens.closing_tick, SizeCategory::Strings);
+        }
+
+        for segment in interpolated_string.iter_segments() {
+            if let InterpolationSegment::String(segment) = segment {
+                if let Some(token) = segment.get_token() {
+                    self.mark(token, SizeCategory::Strings);
+                }
+            }
+        }
+    }
+
+    fn process_number_expression(&mut self, number: &mut NumberExpression) {
+        if let Some(token) = number.get_token() {
+            self.mark(token, SizeCategory::Numbers);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Parser;
+
+    fn analyze(code: &str) -> SizeAnalysis {
+        let mut block = Parser::default()
+            .preserve_tokens()
+            .parse(code)
+            .expect("unable to parse code");
+        SizeAnalysis::analyze(&mut block, code)
+    }
+
+    fn category_sizes(analysis: &SizeAnalysis) -> Vec<(SizeCategory, usize)> {
+        SizeCategory::ALL
+            .iter()
+            .map(|category| (*category, analysis.category_size(*category)))
+            .collect()
+    }
+
+    const FIXTURE: &str = r#"-- module
+local Players = game:GetService("Players")
+local function greet(name)
+    print("hello " .. name, 10)
+end
+return { greet = greet }
+"#;
+
+    #[test]
+    fn categories_add_up_to_code_size() {
+        let analysis = analyze(FIXTURE);
+
+        pretty_assertions::assert_eq!(analysis.size(), FIXTURE.len());
+        pretty_assertions::assert_eq!(
+            SizeCategory::ALL
+                .iter()
+                .map(|category| analysis.category_size(*category))
+                .sum::<usize>(),
+            FIXTURE.len()
+        );
+    }
+
+    #[test]
+    fn attribute_each_category() {
+        let analysis = analyze(FIXTURE);
+
+        pretty_assertions::assert_eq!(
+            category_sizes(&analysis),
+            vec![
+                // Players, greet, name, name, greet, greet
+                (SizeCategory::LocalIdentifiers, 7 + 5 + 4 + 4 + 5),
+                // game, print
+                (SizeCategory::GlobalIdentifiers, 4 + 5),
+                // GetService, greet (table field)
+                (SizeCategory::FieldIdentifiers, 10 + 5),
+                (SizeCategory::Strings, 9 + 8),
+                (SizeCategory::Numbers, 2),
+                // local, =, :, (, ), local, function, (, ), (, .., ,, ), end, return, {, =, }
+                (
+                    SizeCategory::KeywordsAndSymbols,
+                    5 + 1 + 1 + 1 + 1 + 5 + 8 + 1 + 1 + 1 + 2 + 1 + 1 + 3 + 6 + 1 + 1 + 1
+                ),
+                (SizeCategory::Comments, 9),
+                (SizeCategory::Whitespace, 23),
+            ]
+        );
+    }
+
+    #[test]
+    fn longest_identifiers() {
+        let analysis = analyze(FIXTURE);
+
+        pretty_assertions::assert_eq!(
+            analysis
+                .longest_identifiers()
+                .iter()
+                .map(|occurrences| (occurrences.content(), occurrences.count()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("GetService", 1),
+                ("Players", 1),
+                ("greet", 3),
+                ("print", 1),
+                ("name", 2),
+                ("game", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn longest_strings() {
+        let analysis = analyze("local a = 'abc' local b = 'abc' local c = [[long string]]");
+
+        pretty_assertions::assert_eq!(
+            analysis
+                .longest_strings()
+                .iter()
+                .map(|occurrences| (occurrences.content(), occurrences.total_size()))
+                .collect::<Vec<_>>(),
+            vec![("[[long string]]", 15), ("'abc'", 10)]
+        );
+    }
+
+    #[test]
+    fn long_comments() {
+        let code = "--[==[ a ]] b ]==] return --[[c]] 1 -- end";
+        let analysis = analyze(code);
+
+        pretty_assertions::assert_eq!(
+            analysis.category_size(SizeCategory::Comments),
+            "--[==[ a ]] b ]==]".len() + "--[[c]]".len() + "-- end".len()
+        );
+    }
+
+    #[test]
+    fn interpolated_strings() {
+        let code = "return `a - -b {value} c`";
+        let analysis = analyze(code);
+
+        pretty_assertions::assert_eq!(
+            analysis.category_size(SizeCategory::Strings),
+            "`a - -b ".len() + " c`".len()
+        );
+        pretty_assertions::assert_eq!(analysis.category_size(SizeCategory::Comments), 0);
+    }
+
+    #[test]
+    fn function_sizes() {
+        let code = "local function a() return function() end end\nfunction M.b(x) end";
+        let analysis = analyze(code);
+
+        pretty_assertions::assert_eq!(
+            analysis
+                .functions()
+                .iter()
+                .map(|function| (function.name(), function.line(), function.size()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some("a"), Some(1), 44),
+                (Some("M.b"), Some(2), 19),
+                (None, Some(1), 14),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_analyses() {
+        let mut analysis = analyze("local value = 1");
+        analysis.merge(&analyze("return value"));
+
+        pretty_assertions::assert_eq!(analysis.size(), 27);
+        pretty_assertions::assert_eq!(analysis.category_size(SizeCategory::LocalIdentifiers), 5);
+        pretty_assertions::assert_eq!(analysis.category_size(SizeCategory::GlobalIdentifiers), 5);
+        pretty_assertions::assert_eq!(
+            analysis.longest_identifiers()[0],
+            Occurrences {
+                content: "value".to_owned(),
+                count: 2
+            }
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::generator::utils::write_string;
+use crate::nodes::{Block, Expression, LocalAssignStatement, Statement, StringExpression};
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+
+/// The estimated length of the name of a local holding a shared string, once the names are
+/// minified.
+const DEFAULT_REFERENCE_LENGTH: usize = 2;
+/// The length of the code declaring a local, without its name and its value: `local =` and
+/// the character separating it from the next statement.
+const DECLARATION_LENGTH: usize = 8;
+const CONCAT_OPERATOR_LENGTH: usize = 2;
+const DEFAULT_MIN_OCCURRENCES: usize = 2;
+
+/// A way to write a string value in the generated code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StringRepresentation<'a> {
+    /// A string literal.
+    Literal(&'a str),
+    /// A local variable holding a shared string.
+    Reference,
+    /// A concatenation of other representations.
+    Concat(Vec<StringRepresentation<'a>>),
+}
+
+/// The number of times each string literal appears in a file, used to decide how strings are
+/// written so that rules producing and consuming strings agree on the result.
+///
+/// A string is shareable when storing it in a local variable and referencing that local is
+/// smaller than repeating the literal, or when a top-level local already holds it. Merging a
+/// shareable string into a longer literal prevents it from being shared, so
+/// [`should_merge`](Self::should_merge) declines it.
+#[derive(Debug, Clone)]
+pub struct StringDecisionContext {
+    occurrences: HashMap<String, usize>,
+    hoisted: HashSet<String>,
+    min_occurrences: usize,
+    reference_length: usize,
+}
+
+impl Default for StringDecisionContext {
+    fn default() -> Self {
+        Self {
+            occurrences: HashMap::new(),
+            hoisted: HashSet::new(),
+            min_occurrences: DEFAULT_MIN_OCCURRENCES,
+            reference_length: DEFAULT_REFERENCE_LENGTH,
+        }
+    }
+}
+
+struct OccurrenceCounter<'a> {
+    occurrences: &'a mut HashMap<String, usize>,
+}
+
+impl NodeProcessor for OccurrenceCounter<'_> {
+    fn process_string_expression(&mut self, string: &mut StringExpression) {
+        *self
+            .occurrences
+            .entry(string.get_value().to_owned())
+            .or_default() += 1;
+    }
+}
+
+fn hoisted_string(assign: &LocalAssignStatement) -> Option<&str> {
+    if assign.variables_len() != 1 || assign.values_len() != 1 {
+        return None;
+    }
+    match assign.iter_values().next()? {
+        Expression::String(string) => Some(string.get_value()),
+        _ => None,
+    }
+}
+
+impl StringDecisionContext {
+    /// Counts the string literals of a file and finds the top-level locals holding a string.
+    pub fn from_block(block: &mut Block) -> Self {
+        let hoisted = block
+            .iter_statements()
+            .filter_map(|statement| match statement {
+                Statement::LocalAssign(assign) => hoisted_string(assign).map(ToOwned::to_owned),
+                _ => None,
+            })
+            .collect();
+
+        let mut context = Self {
+            hoisted,
+            ..Self::default()
+        };
+
+        let mut counter = OccurrenceCounter {
+            occurrences: &mut context.occurrences,
+        };
+        DefaultVisitor::visit_block(block, &mut counter);
+
+        context
+    }
+
+    /// Sets the minimum number of occurrences for a string to be shared.
+    pub fn with_min_occurrences(mut self, min_occurrences: usize) -> Self {
+        self.min_occurrences = min_occurrences;
+        self
+    }
+
+    /// Sets the estimated length of the name of a local holding a shared string.
+    pub fn with_reference_length(mut self, reference_length: usize) -> Self {
+        self.reference_length = reference_length;
+        self
+    }
+
+    /// Returns the number of string literals with the given value.
+    pub fn occurrences(&self, value: &str) -> usize {
+        self.occurrences.get(value).copied().unwrap_or(0)
+    }
+
+    /// Returns true if a top-level local is assigned a literal of the given value.
+    pub fn is_hoisted(&self, value: &str) -> bool {
+        self.hoisted.contains(value)
+    }
+
+    /// Returns the estimated number of bytes saved by storing the string in a local,
+    /// or zero when sharing the string does not make the code smaller.
+    pub fn sharing_savings(&self, value: &str) -> usize {
+        let occurrences = self.occurrences(value);
+        if occurrences < self.min_occurrences {
+            return 0;
+        }
+
+        let literal = self.estimated_cost(&StringRepresentation::Literal(value));
+        let repeated = occurrences * literal;
+        let shared = DECLARATION_LENGTH
+            + self.reference_length
+            + literal
+            + occurrences * self.reference_length;
+
+        repeated.saturating_sub(shared)
+    }
+
+    /// Returns true if the string is held by a top-level local or if sharing it makes the
+    /// code smaller.
+    pub fn is_shareable(&self, value: &str) -> bool {
+        self.is_hoisted(value) || self.sharing_savings(value) > 0
+    }
+
+    /// Returns true if two adjacent literals of a concatenation can be merged into a single
+    /// literal, which is declined when one of them is shareable.
+    pub fn should_merge(&self, left: &str, right: &str) -> bool {
+        !self.is_shareable(left) && !self.is_shareable(right)
+    }
+
+    /// Returns the estimated number of bytes of a representation in the generated code.
+    pub fn estimated_cost(&self, representation: &StringRepresentation) -> usize {
+        match representation {
+            StringRepresentation::Literal(value) => write_string(value).len(),
+            StringRepresentation::Reference => self.reference_length,
+            StringRepresentation::Concat(parts) => {
+                parts
+                    .iter()
+                    .map(|part| self.estimated_cost(part))
+                    .sum::<usize>()
+                    + CONCAT_OPERATOR_LENGTH * parts.len().saturating_sub(1)
+            }
+        }
+    }
+
+    /// Returns the estimated number of bytes saved by sharing every shareable string of the
+    /// file that is not already held by a local.
+    pub fn estimated_total_savings(&self) -> usize {
+        self.occurrences
+            .keys()
+            .filter(|value| !self.is_hoisted(value))
+            .map(|value| self.sharing_savings(value))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Parser;
+
+    fn context(code: &str) -> StringDecisionContext {
+        let mut block = Parser::default().parse(code).expect("unable to parse code");
+        StringDecisionContext::from_block(&mut block)
+    }
+
+    #[test]
+    fn count_occurrences() {
+        let context = context("local a = 'abc' .. 'abc' print('abc', 'def')");
+
+        assert_eq!(context.occurrences("abc"), 3);
+        assert_eq!(context.occurrences("def"), 1);
+        assert_eq!(context.occurrences("xyz"), 0);
+    }
+
+    #[test]
+    fn find_hoisted_strings() {
+        let context = context("local URL = 'https://example.com' do local x = 'nested' end");
+
+        assert!(context.is_hoisted("https://example.com"));
+        assert!(!context.is_hoisted("nested"));
+    }
+
+    #[test]
+    fn estimated_cost_of_literal() {
+        let context = StringDecisionContext::default();
+
+        assert_eq!(
+            context.estimated_cost(&StringRepresentation::Literal("abc")),
+            5
+        );
+    }
+
+    #[test]
+    fn estimated_cost_of_concat() {
+        let context = StringDecisionContext::default();
+
+        assert_eq!(
+            context.estimated_cost(&StringRepresentation::Concat(vec![
+                StringRepresentation::Reference,
+                StringRepresentation::Literal("abc"),
+            ])),
+            2 + 2 + 5
+        );
+    }
+
+    #[test]
+    fn short_repeated_string_is_not_shareable() {
+        let context = context("print('a', 'a', 'a')");
+
+        assert_eq!(context.sharing_savings("a"), 0);
+        assert!(!context.is_shareable("a"));
+    }
+
+    #[test]
+    fn long_repeated_string_is_shareable() {
+        let context = context(
+            "print('https://example.com/', 'https://example.com/', 'https://example.com/')",
+        );
+
+        // 3 * 22 repeated bytes against a declaration of 8 + 2 + 22 bytes and 3 references
+        assert_eq!(context.sharing_savings("https://example.com/"), 28);
+        assert!(context.is_shareable("https://example.com/"));
+        assert_eq!(context.estimated_total_savings(), 28);
+    }
+
+    #[test]
+    fn string_below_min_occurrences_is_not_shareable() {
+        let context = context("print('https://example.com/', 'https://example.com/')")
+            .with_min_occurrences(3);
+
+        assert!(!context.is_shareable("https://example.com/"));
+    }
+
+    #[test]
+    fn should_merge_unique_strings() {
+        let context = context("local a = 'hello' .. 'world'");
+
+        assert!(context.should_merge("hello", "world"));
+    }
+
+    #[test]
+    fn should_not_merge_shareable_string() {
+        let context = context(
+            "local a = 'https://example.com/' .. 'a' \
+            local b = 'https://example.com/' .. 'b' \
+            local c = 'https://example.com/' .. 'c'",
+        );
+
+        assert!(!context.should_merge("https://example.com/", "a"));
+        assert!(!context.should_merge("b", "https://example.com/"));
+    }
+
+    #[test]
+    fn should_not_merge_hoisted_string() {
+        let context = context("local PREFIX = 'x' local a = 'x' .. 'y'");
+
+        assert!(!context.should_merge("x", "y"));
+    }
+}
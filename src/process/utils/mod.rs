@@ -2,6 +2,8 @@ mod permutator;
 
 pub(crate) use permutator::Permutator;
 
+use crate::process::EnvironmentTarget;
+
 pub(crate) type CharPermutator = Permutator<std::str::Chars<'static>>;
 
 pub(crate) fn identifier_permutator() -> CharPermutator {
@@ -10,7 +12,7 @@ pub(crate) fn identifier_permutator() -> CharPermutator {
 
 pub(crate) fn generate_identifier(permutator: &mut CharPermutator) -> String {
     permutator
-        .find(|identifier| is_valid_identifier(identifier))
+        .find(|identifier| is_valid_generated_identifier(identifier))
         .expect("the permutator should always ultimately return a valid identifier")
 }
 
@@ -45,43 +47,119 @@ macro_rules! matches_any_keyword {
     };
 }
 
-pub(crate) fn is_valid_identifier(identifier: &str) -> bool {
+/// The `goto` keyword is reserved since Lua 5.2, but Lua 5.1 and Luau accept it as a name.
+const GOTO_KEYWORD: &str = "goto";
+
+/// Words that Luau only treats as keywords in specific positions, so they remain valid names.
+pub(crate) const LUAU_CONTEXTUAL_KEYWORDS: [&str; 3] = ["continue", "export", "type"];
+
+/// Returns true if the given string can be written as an identifier (a variable name or
+/// a field name) for the given target. When the target is unknown, the string must be
+/// a valid identifier for every target.
+///
+/// Only ASCII letters, digits and underscores are accepted, since none of the targets
+/// guarantees support for other characters in identifiers.
+pub fn is_valid_identifier(identifier: &str, target: Option<EnvironmentTarget>) -> bool {
     !identifier.is_empty()
-        && identifier.is_ascii()
         && identifier
-            .char_indices()
-            .all(|(i, c)| c.is_alphabetic() || c == '_' || (c.is_ascii_digit() && i > 0))
+            .bytes()
+            .enumerate()
+            .all(|(i, c)| c.is_ascii_alphabetic() || c == b'_' || (c.is_ascii_digit() && i > 0))
         && !matches!(identifier, matches_any_keyword!())
+        && (identifier != GOTO_KEYWORD
+            || matches!(
+                target,
+                Some(
+                    EnvironmentTarget::Lua51 | EnvironmentTarget::Luau | EnvironmentTarget::Roblox
+                )
+            ))
+}
+
+/// Returns true if a generated identifier can be used for every target, without clashing
+/// with a contextual keyword of Luau.
+pub(crate) fn is_valid_generated_identifier(identifier: &str) -> bool {
+    is_valid_identifier(identifier, None) && !LUAU_CONTEXTUAL_KEYWORDS.contains(&identifier)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+
+    const ALL_TARGETS: [Option<EnvironmentTarget>; 6] = [
+        None,
+        Some(EnvironmentTarget::Lua51),
+        Some(EnvironmentTarget::Lua53),
+        Some(EnvironmentTarget::Lua54),
+        Some(EnvironmentTarget::Luau),
+        Some(EnvironmentTarget::Roblox),
+    ];
+
     #[test]
     fn is_valid_identifier_is_true() {
-        assert!(is_valid_identifier("hello"));
-        assert!(is_valid_identifier("foo"));
-        assert!(is_valid_identifier("bar"));
-        assert!(is_valid_identifier("VAR"));
-        assert!(is_valid_identifier("_VAR"));
-        assert!(is_valid_identifier("_0"));
+        for target in ALL_TARGETS {
+            assert!(is_valid_identifier("hello", target));
+            assert!(is_valid_identifier("foo", target));
+            assert!(is_valid_identifier("bar", target));
+            assert!(is_valid_identifier("VAR", target));
+            assert!(is_valid_identifier("_VAR", target));
+            assert!(is_valid_identifier("_0", target));
+        }
     }
 
     #[test]
     fn is_valid_identifier_is_false() {
-        assert!(!is_valid_identifier(""));
-        assert!(!is_valid_identifier("$hello"));
-        assert!(!is_valid_identifier(" "));
-        assert!(!is_valid_identifier("5"));
-        assert!(!is_valid_identifier("1bar"));
-        assert!(!is_valid_identifier("var "));
-        assert!(!is_valid_identifier("sp ace"));
+        for target in ALL_TARGETS {
+            assert!(!is_valid_identifier("", target));
+            assert!(!is_valid_identifier("$hello", target));
+            assert!(!is_valid_identifier(" ", target));
+            assert!(!is_valid_identifier("5", target));
+            assert!(!is_valid_identifier("1bar", target));
+            assert!(!is_valid_identifier("var ", target));
+            assert!(!is_valid_identifier("sp ace", target));
+            assert!(!is_valid_identifier("nul\0byte", target));
+            assert!(!is_valid_identifier("\0", target));
+            assert!(!is_valid_identifier("café", target));
+            assert!(!is_valid_identifier("ñ", target));
+        }
     }
 
     #[test]
     fn keywords_are_not_valid_identifiers() {
-        for keyword in KEYWORDS {
-            assert!(!is_valid_identifier(keyword));
+        for target in ALL_TARGETS {
+            for keyword in KEYWORDS {
+                assert!(!is_valid_identifier(keyword, target));
+            }
+        }
+    }
+
+    #[test]
+    fn goto_is_valid_identifier_for_lua51_and_luau() {
+        assert!(is_valid_identifier("goto", Some(EnvironmentTarget::Lua51)));
+        assert!(is_valid_identifier("goto", Some(EnvironmentTarget::Luau)));
+        assert!(is_valid_identifier("goto", Some(EnvironmentTarget::Roblox)));
+    }
+
+    #[test]
+    fn goto_is_not_valid_identifier_for_lua53_lua54_or_unknown_target() {
+        assert!(!is_valid_identifier("goto", None));
+        assert!(!is_valid_identifier("goto", Some(EnvironmentTarget::Lua53)));
+        assert!(!is_valid_identifier("goto", Some(EnvironmentTarget::Lua54)));
+    }
+
+    #[test]
+    fn luau_contextual_keywords_are_valid_identifiers() {
+        for target in ALL_TARGETS {
+            for keyword in LUAU_CONTEXTUAL_KEYWORDS {
+                assert!(is_valid_identifier(keyword, target));
+            }
+        }
+    }
+
+    #[test]
+    fn luau_contextual_keywords_are_not_generated() {
+        for keyword in LUAU_CONTEXTUAL_KEYWORDS {
+            assert!(!is_valid_generated_identifier(keyword));
         }
+        assert!(!is_valid_generated_identifier("goto"));
     }
 }
@@ -1,4 +1,6 @@
 use crate::nodes::*;
+use crate::process::deadline;
+use crate::process::node_path::{self, NodePathSegment};
 use crate::process::NodeProcessor;
 
 use std::marker::PhantomData;
@@ -8,18 +10,35 @@ pub trait NodeVisitor<T: NodeProcessor> {
     fn visit_block(block: &mut Block, processor: &mut T) {
         processor.process_block(block);
 
-        block
-            .iter_mut_statements()
-            .for_each(|statement| Self::visit_statement(statement, processor));
+        for (index, statement) in block.iter_mut_statements().enumerate() {
+            // stop visiting when the deadline of the rule has passed
+            if deadline::is_expired() {
+                return;
+            }
+            node_path::enter(NodePathSegment::Statement(index));
+            Self::visit_statement(statement, processor);
+            node_path::leave();
+        }
+
+        if deadline::is_expired() {
+            return;
+        }
 
         if let Some(last_statement) = block.mutate_last_statement() {
+            node_path::enter(NodePathSegment::LastStatement);
             Self::visit_last_statement(last_statement, processor);
+            node_path::leave();
         };
     }
 
     fn visit_statement(statement: &mut Statement, processor: &mut T) {
         processor.process_statement(statement);
 
+        let kind = node_path::statement_kind(statement);
+        if let Some(kind) = kind {
+            node_path::enter(NodePathSegment::Kind(kind));
+        }
+
         match statement {
             Statement::Assign(statement) => Self::visit_assign_statement(statement, processor),
             Statement::Do(statement) => Self::visit_do_statement(statement, processor),
@@ -38,7 +57,13 @@ pub trait NodeVisitor<T: NodeProcessor> {
             Statement::TypeDeclaration(statement) => {
                 Self::visit_type_declaration(statement, processor)
             }
+            // the content of verbatim statements is not Lua code
+            Statement::Verbatim(_) => {}
         };
+
+        if kind.is_some() {
+            node_path::leave();
+        }
     }
 
     fn visit_last_statement(last_statement: &mut LastStatement, processor: &mut T) {
@@ -140,7 +165,13 @@ pub trait NodeVisitor<T: NodeProcessor> {
 
         processor.process_scope(function.mutate_block(), None);
 
+        node_path::enter(NodePathSegment::Kind("function"));
         Self::visit_block(function.mutate_block(), processor);
+        node_path::leave();
+
+        if let Some(generic_parameters) = function.mutate_generic_parameters() {
+            Self::visit_generic_parameters(generic_parameters, processor);
+        }
 
         for r#type in function
             .iter_mut_parameters()
@@ -194,6 +225,10 @@ pub trait NodeVisitor<T: NodeProcessor> {
         processor.process_scope(statement.mutate_block(), None);
         Self::visit_block(statement.mutate_block(), processor);
 
+        if let Some(generic_parameters) = statement.mutate_generic_parameters() {
+            Self::visit_generic_parameters(generic_parameters, processor);
+        }
+
         for r#type in statement
             .iter_mut_parameters()
             .filter_map(TypedIdentifier::mutate_type)
@@ -231,15 +266,23 @@ pub trait NodeVisitor<T: NodeProcessor> {
     fn visit_if_statement(statement: &mut IfStatement, processor: &mut T) {
         processor.process_if_statement(statement);
 
-        statement.mutate_branches().iter_mut().for_each(|branch| {
-            Self::visit_expression(branch.mutate_condition(), processor);
-            processor.process_scope(branch.mutate_block(), None);
-            Self::visit_block(branch.mutate_block(), processor);
-        });
+        statement
+            .mutate_branches()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(index, branch)| {
+                node_path::enter(NodePathSegment::Branch(index));
+                Self::visit_expression(branch.mutate_condition(), processor);
+                processor.process_scope(branch.mutate_block(), None);
+                Self::visit_block(branch.mutate_block(), processor);
+                node_path::leave();
+            });
 
         if let Some(block) = statement.mutate_else_block() {
+            node_path::enter(NodePathSegment::Else);
             processor.process_scope(block, None);
             Self::visit_block(block, processor);
+            node_path::leave();
         }
     }
 
@@ -263,6 +306,10 @@ pub trait NodeVisitor<T: NodeProcessor> {
         processor.process_scope(statement.mutate_block(), None);
         Self::visit_block(statement.mutate_block(), processor);
 
+        if let Some(generic_parameters) = statement.mutate_generic_parameters() {
+            Self::visit_generic_parameters(generic_parameters, processor);
+        }
+
         for r#type in statement
             .iter_mut_parameters()
             .filter_map(TypedIdentifier::mutate_type)
@@ -294,6 +341,14 @@ pub trait NodeVisitor<T: NodeProcessor> {
         processor.process_generic_type_pack(generic);
     }
 
+    fn visit_generic_parameters(generic_parameters: &mut GenericParameters, processor: &mut T) {
+        processor.process_generic_parameters(generic_parameters);
+
+        for generic_type_pack in generic_parameters.iter_mut_generic_type_pack() {
+            Self::visit_generic_type_pack(generic_type_pack, processor);
+        }
+    }
+
     fn visit_numeric_for(statement: &mut NumericForStatement, processor: &mut T) {
         processor.process_numeric_for_statement(statement);
 
@@ -545,6 +600,10 @@ pub trait NodeVisitor<T: NodeProcessor> {
     fn visit_function_type(function: &mut FunctionType, processor: &mut T) {
         processor.process_function_type(function);
 
+        if let Some(generic_parameters) = function.mutate_generic_parameters() {
+            Self::visit_generic_parameters(generic_parameters, processor);
+        }
+
         for argument in function.iter_mut_arguments() {
             Self::visit_type(argument.mutate_type(), processor);
         }
@@ -638,6 +697,7 @@ impl<T: NodeProcessor> NodeVisitor<T> for DefaultVisitor<T> {}
 mod test {
     use super::*;
     use crate::process::NodeCounter;
+    use crate::Parser;
 
     #[test]
     fn visit_do_statement() {
@@ -782,4 +842,22 @@ mod test {
         assert_eq!(counter.interpolated_string_count, 1);
         assert_eq!(counter.expression_count, 2);
     }
+
+    #[test]
+    fn visit_generic_functions_and_type_declarations() {
+        let mut counter = NodeCounter::new();
+        let mut block = Parser::default()
+            .parse(
+                "export type Result<T, E> = T | E
+                local function identity<T>(value: T): T return value end
+                function Module.map<T, U...>(value: T, ...: U...) end",
+            )
+            .expect("unable to parse code");
+
+        DefaultVisitor::visit_block(&mut block, &mut counter);
+
+        assert_eq!(counter.type_declaration_count, 1);
+        assert_eq!(counter.generic_parameters_count, 2);
+        assert_eq!(counter.type_count, 6);
+    }
 }
@@ -3,16 +3,17 @@ use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
 use crate::nodes::{
-    Block, BlockTokens, DoTokens, FunctionBodyTokens, GenericForTokens, Identifier,
-    IfStatementTokens, LastStatement, LocalAssignTokens, LocalFunctionTokens, NumericForTokens,
-    ParentheseExpression, ParentheseTokens, Prefix, RepeatTokens, ReturnTokens, Statement, Token,
-    TriviaKind, TypeDeclarationTokens, Variable, WhileTokens,
+    Block, BlockTokens, DoTokens, FunctionBodyTokens, GenericForTokens, IfStatementTokens,
+    LastStatement, LocalAssignTokens, LocalFunctionTokens, NumericForTokens, RepeatTokens,
+    ReturnTokens, Statement, Token, TriviaKind, TypeDeclarationTokens, VerbatimStatement,
+    WhileTokens,
 };
 use crate::rules::{
     verify_property_collisions, verify_required_any_properties, Context, Rule, RuleConfiguration,
-    RuleConfigurationError, RuleProcessResult, RuleProperties,
+    RuleConfigurationError, RuleProcessResult, RuleProperties, RulePropertyKind,
 };
 
+use super::first_token::{prefix_get_first_token, variable_get_first_token};
 use super::{FlawlessRule, ShiftTokenLine};
 
 pub const APPEND_TEXT_COMMENT_RULE_NAME: &str = "append_text_comment";
@@ -101,7 +102,10 @@ impl Rule for AppendTextComment {
 
         match self.location {
             AppendLocation::Start => {
-                if let Some(statement) = block.first_mut_statement() {
+                if matches!(block.first_statement(), Some(Statement::Verbatim(_))) {
+                    // verbatim statements do not have tokens to attach the comment to
+                    block.insert_statement(0, VerbatimStatement::new(text));
+                } else if let Some(statement) = block.first_mut_statement() {
                     match statement {
                         Statement::Assign(assign_statement) => {
                             let variable = assign_statement
@@ -299,6 +303,8 @@ impl Rule for AppendTextComment {
                                 });
                             }
                         }
+                        // verbatim statements are handled before the match
+                        Statement::Verbatim(_) => {}
                     }
                 } else if let Some(statement) = block.mutate_last_statement() {
                     match statement {
@@ -341,57 +347,6 @@ impl Rule for AppendTextComment {
     }
 }
 
-fn variable_get_first_token(variable: &mut Variable) -> &mut Token {
-    match variable {
-        Variable::Identifier(identifier) => identifier_get_first_token(identifier),
-        Variable::Field(field_expression) => {
-            prefix_get_first_token(field_expression.mutate_prefix())
-        }
-        Variable::Index(index_expression) => {
-            prefix_get_first_token(index_expression.mutate_prefix())
-        }
-    }
-}
-
-fn prefix_get_first_token(prefix: &mut Prefix) -> &mut Token {
-    let mut current = prefix;
-    loop {
-        match current {
-            Prefix::Call(call) => {
-                current = call.mutate_prefix();
-            }
-            Prefix::Field(field_expression) => {
-                current = field_expression.mutate_prefix();
-            }
-            Prefix::Index(index_expression) => {
-                current = index_expression.mutate_prefix();
-            }
-            Prefix::Identifier(identifier) => break identifier_get_first_token(identifier),
-            Prefix::Parenthese(parenthese_expression) => {
-                break parentheses_get_first_token(parenthese_expression)
-            }
-        }
-    }
-}
-
-fn identifier_get_first_token(identifier: &mut Identifier) -> &mut Token {
-    if identifier.get_token().is_none() {
-        let name = identifier.get_name().to_owned();
-        identifier.set_token(Token::from_content(name));
-    }
-    identifier.mutate_token().unwrap()
-}
-
-fn parentheses_get_first_token(parentheses: &mut ParentheseExpression) -> &mut Token {
-    if parentheses.get_tokens().is_none() {
-        parentheses.set_tokens(ParentheseTokens {
-            left_parenthese: Token::from_content("("),
-            right_parenthese: Token::from_content(")"),
-        });
-    }
-    &mut parentheses.mutate_tokens().unwrap().left_parenthese
-}
-
 impl RuleConfiguration for AppendTextComment {
     fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
         verify_required_any_properties(&properties, &["text", "file"])?;
@@ -432,6 +387,21 @@ impl RuleConfiguration for AppendTextComment {
         APPEND_TEXT_COMMENT_RULE_NAME
     }
 
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["file", "location", "text"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "location" => RulePropertyKind::Choice(&["start", "end"]),
+            _ => RulePropertyKind::String,
+        }
+    }
+
+    fn is_unsafe_to_reapply(&self) -> bool {
+        true
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         let mut properties = RuleProperties::new();
 
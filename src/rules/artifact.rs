@@ -0,0 +1,144 @@
+use std::fmt;
+
+/// The format of the content of an [`Artifact`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArtifactKind {
+    Json,
+    Csv,
+    Text,
+}
+
+impl ArtifactKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Text => "text",
+        }
+    }
+}
+
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The content of an [`Artifact`]. Without the `serde-config` feature, JSON artifacts are
+/// written as text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArtifactContent {
+    #[cfg(feature = "serde-config")]
+    Json(serde_json::Value),
+    Text(String),
+}
+
+impl ArtifactContent {
+    /// Returns the text written to the file of the artifact. JSON values are pretty-printed.
+    pub fn to_text(&self) -> String {
+        match self {
+            #[cfg(feature = "serde-config")]
+            Self::Json(value) => {
+                let mut text =
+                    serde_json::to_string_pretty(value).expect("unable to serialize JSON value");
+                text.push('\n');
+                text
+            }
+            Self::Text(text) => text.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "serde-config")]
+impl From<serde_json::Value> for ArtifactContent {
+    fn from(value: serde_json::Value) -> Self {
+        Self::Json(value)
+    }
+}
+
+/// Writes a JSON string literal, escaped the same way as the JSON serializer used with the
+/// `serde-config` feature.
+#[cfg(not(feature = "serde-config"))]
+pub(crate) fn to_json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            '\u{8}' => json.push_str("\\b"),
+            '\u{c}' => json.push_str("\\f"),
+            character if (character as u32) < 0x20 => {
+                json.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => json.push(character),
+        }
+    }
+    json.push('"');
+    json
+}
+
+impl From<String> for ArtifactContent {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+/// A secondary output emitted by a rule while processing a file (like a table of the strings
+/// found in the file). Artifacts are written next to the generated code when a path is
+/// configured for their name, and they are available from the outcomes of a
+/// [`ProcessingSession`](crate::ProcessingSession).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artifact {
+    name: String,
+    kind: ArtifactKind,
+    content: ArtifactContent,
+}
+
+impl Artifact {
+    pub fn new(name: impl Into<String>, kind: ArtifactKind, content: ArtifactContent) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            content,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> ArtifactKind {
+        self.kind
+    }
+
+    pub fn content(&self) -> &ArtifactContent {
+        &self.content
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "serde-config")]
+    use serde_json::json;
+
+    #[cfg(feature = "serde-config")]
+    #[test]
+    fn json_content_to_text_is_pretty_printed() {
+        let content = ArtifactContent::from(json!({ "key": "value" }));
+
+        assert_eq!(content.to_text(), "{\n  \"key\": \"value\"\n}\n");
+    }
+
+    #[test]
+    fn text_content_to_text_is_unchanged() {
+        let content = ArtifactContent::from("a,b\n".to_owned());
+
+        assert_eq!(content.to_text(), "a,b\n");
+    }
+}
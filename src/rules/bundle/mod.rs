@@ -7,6 +7,7 @@ use crate::nodes::Block;
 use crate::rules::{
     Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult, RuleProperties,
 };
+use crate::utils::log;
 use crate::Parser;
 
 pub use require_mode::BundleRequireMode;
@@ -17,16 +18,12 @@ pub const BUNDLER_RULE_NAME: &str = "bundler";
 #[derive(Debug)]
 pub(crate) struct BundleOptions {
     parser: Parser,
-    modules_identifier: String,
+    modules_identifier: Option<String>,
     excludes: Option<wax::Any<'static>>,
 }
 
 impl BundleOptions {
-    fn new<'a>(
-        parser: Parser,
-        modules_identifier: impl Into<String>,
-        excludes: impl Iterator<Item = &'a str>,
-    ) -> Self {
+    fn new<'a>(parser: Parser, excludes: impl Iterator<Item = &'a str>) -> Self {
         let excludes: Vec<_> = excludes
             .filter_map(|exclusion| match wax::Glob::new(exclusion) {
                 Ok(glob) => Some(glob.into_owned()),
@@ -42,7 +39,7 @@ impl BundleOptions {
             .collect();
         Self {
             parser,
-            modules_identifier: modules_identifier.into(),
+            modules_identifier: None,
             excludes: if excludes.is_empty() {
                 None
             } else {
@@ -57,8 +54,10 @@ impl BundleOptions {
         &self.parser
     }
 
-    fn modules_identifier(&self) -> &str {
-        &self.modules_identifier
+    fn modules_identifier(&self, context: &Context) -> String {
+        self.modules_identifier
+            .clone()
+            .unwrap_or_else(|| context.generated_name(DEFAULT_MODULE_IDENTIFIER_NAME))
     }
 
     fn is_excluded(&self, require: &Path) -> bool {
@@ -84,12 +83,12 @@ impl Bundler {
     ) -> Self {
         Self {
             require_mode,
-            options: BundleOptions::new(parser, DEFAULT_MODULE_IDENTIFIER, excludes),
+            options: BundleOptions::new(parser, excludes),
         }
     }
 
     pub(crate) fn with_modules_identifier(mut self, modules_identifier: impl Into<String>) -> Self {
-        self.options.modules_identifier = modules_identifier.into();
+        self.options.modules_identifier = Some(modules_identifier.into());
         self
     }
 }
@@ -117,7 +116,7 @@ impl RuleConfiguration for Bundler {
     }
 }
 
-const DEFAULT_MODULE_IDENTIFIER: &str = "__DARKLUA_BUNDLE_MODULES";
+const DEFAULT_MODULE_IDENTIFIER_NAME: &str = "BUNDLE_MODULES";
 
 #[cfg(test)]
 mod test {
@@ -7,6 +7,7 @@ use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 use std::{iter, mem};
 
+#[cfg(feature = "serde-config")]
 use serde::Serialize;
 
 use crate::frontend::DarkluaResult;
@@ -14,15 +15,16 @@ use crate::nodes::{
     Block, DoStatement, Expression, FunctionCall, LocalAssignStatement, Prefix, Statement,
     StringExpression,
 };
-use crate::process::{
-    to_expression, DefaultVisitor, IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor,
-};
+#[cfg(feature = "serde-config")]
+use crate::process::to_expression;
+use crate::process::{DefaultVisitor, IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
 use crate::rules::require::{
     is_require_call, match_path_require_call, PathRequireMode, RequirePathLocator,
 };
 use crate::rules::{
     Context, ContextBuilder, FlawlessRule, ReplaceReferencedTokens, RuleProcessResult,
 };
+use crate::utils::log;
 use crate::utils::Timer;
 use crate::{DarkluaError, Resources};
 
@@ -66,7 +68,7 @@ impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources>
                 context.project_location(),
                 context.resources(),
             ),
-            module_definitions: BuildModuleDefinitions::new(options.modules_identifier()),
+            module_definitions: BuildModuleDefinitions::new(options.modules_identifier(context)),
             source: context.current_path().to_path_buf(),
             module_cache: Default::default(),
             require_stack: Default::default(),
@@ -241,15 +243,18 @@ impl<'a, 'b, 'code, 'resources> RequirePathProcessor<'a, 'b, 'code, 'resources>
 
                     Ok(RequiredResource::Block(block))
                 }
+                #[cfg(feature = "serde-config")]
                 "json" | "json5" => {
                     transcode("json", path, json5::from_str::<serde_json::Value>, &content)
                 }
+                #[cfg(feature = "data-formats")]
                 "yml" | "yaml" => transcode(
                     "yaml",
                     path,
                     serde_yaml::from_str::<serde_yaml::Value>,
                     &content,
                 ),
+                #[cfg(feature = "data-formats")]
                 "toml" => transcode("toml", path, toml::from_str::<toml::Value>, &content),
                 "txt" => Ok(RequiredResource::Expression(
                     StringExpression::from_value(content).into(),
@@ -275,6 +280,7 @@ impl DerefMut for RequirePathProcessor<'_, '_, '_, '_> {
     }
 }
 
+#[cfg(feature = "serde-config")]
 fn transcode<'a, T, E>(
     label: &'static str,
     path: &Path,
@@ -300,6 +300,7 @@ fn last_statement_token(statement: &Statement) -> Option<&Token> {
         Statement::TypeDeclaration(type_declaration) => {
             last_type_token(type_declaration.get_type())
         }
+        Statement::Verbatim(_) => None,
     }
 }
 
@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+#[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
 
 use crate::rules::{require::PathRequireMode, RuleProcessResult};
@@ -7,8 +8,12 @@ use crate::{nodes::Block, rules::Context};
 
 use super::{path_require_mode, BundleOptions};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "snake_case", tag = "name")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde-config",
+    serde(deny_unknown_fields, rename_all = "snake_case", tag = "name")
+)]
 pub enum BundleRequireMode {
     Path(PathRequireMode),
 }
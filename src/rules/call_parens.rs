@@ -1,7 +1,7 @@
 use crate::nodes::{Arguments, Block, Expression, FunctionCall, StringExpression, TableExpression};
 use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
 };
 
 use std::mem;
@@ -15,17 +15,41 @@ impl NodeProcessor for Processor {
     fn process_function_call(&mut self, call: &mut FunctionCall) {
         let new_arguments = match call.mutate_arguments() {
             Arguments::Tuple(tuple) if tuple.len() == 1 => {
+                // the trivia after the closing parenthese (like a comment) is kept after the
+                // string or the table
+                let removes_parens = matches!(
+                    tuple.iter_values().next(),
+                    Some(Expression::String(_) | Expression::Table(_))
+                );
+                let trailing_trivia = if removes_parens {
+                    tuple
+                        .mutate_tokens()
+                        .map(|tokens| tokens.closing_parenthese.take_trailing_trivia())
+                        .unwrap_or_default()
+                } else {
+                    Vec::new()
+                };
                 let expression = tuple.iter_mut_values().next().unwrap();
 
                 match expression {
                     Expression::String(string) => {
                         let mut steal_string = StringExpression::empty();
                         mem::swap(string, &mut steal_string);
+                        if let Some(token) = steal_string.mutate_token() {
+                            trailing_trivia
+                                .into_iter()
+                                .for_each(|trivia| token.push_trailing_trivia(trivia));
+                        }
                         Some(Arguments::String(steal_string))
                     }
                     Expression::Table(table) => {
                         let mut steal_table = TableExpression::default();
                         mem::swap(table, &mut steal_table);
+                        if let Some(tokens) = steal_table.mutate_tokens() {
+                            trailing_trivia.into_iter().for_each(|trivia| {
+                                tokens.closing_brace.push_trailing_trivia(trivia)
+                            });
+                        }
                         Some(Arguments::Table(steal_table))
                     }
                     _ => None,
@@ -64,9 +88,24 @@ impl RuleConfiguration for RemoveFunctionCallParens {
         REMOVE_FUNCTION_CALL_PARENS_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "print(\"hello\")\n\
+                setup({ debug = true })",
+        )]
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+
+    fn is_statement_local(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,398 @@
+use std::fmt;
+
+use crate::nodes::{AssignStatement, Block, Expression, LocalAssignStatement};
+use crate::process::{DefaultVisitor, Evaluator, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult, RuleProperties,
+    RulePropertyKind,
+};
+use crate::utils::log;
+
+/// An assignment where the number of values does not match the number of variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ArityMismatch {
+    kind: &'static str,
+    line: Option<usize>,
+    variables: usize,
+    values: usize,
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, word)
+    } else {
+        format!("{} {}s", count, word)
+    }
+}
+
+impl fmt::Display for ArityMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(line) = self.line {
+            write!(f, " (line {})", line)?;
+        }
+        write!(
+            f,
+            " assigns {} to {}",
+            plural(self.values, "value"),
+            plural(self.variables, "variable")
+        )?;
+        if self.values < self.variables {
+            write!(f, ": the last variables are assigned `nil`")
+        } else {
+            write!(f, ": the extra values are evaluated and discarded")
+        }
+    }
+}
+
+/// Returns true for expressions that can produce any number of values.
+fn is_multiple_values(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Call(_) | Expression::VariableArguments(_)
+    )
+}
+
+struct ArityChecker {
+    fix: Option<ArityFix>,
+    evaluator: Evaluator,
+    mismatches: Vec<ArityMismatch>,
+}
+
+impl ArityChecker {
+    fn new(fix: Option<ArityFix>) -> Self {
+        Self {
+            fix,
+            evaluator: Evaluator::default(),
+            mismatches: Vec::new(),
+        }
+    }
+
+    /// Returns true when the values after the given number of variables can be removed
+    /// without changing the behavior of the code.
+    fn can_truncate<'a>(
+        &self,
+        variables: usize,
+        values: impl Iterator<Item = &'a Expression>,
+    ) -> bool {
+        values
+            .skip(variables)
+            .all(|value| !is_multiple_values(value) && !self.evaluator.has_side_effects(value))
+    }
+
+    fn is_mismatch(variables: usize, values: usize, last_value: Option<&Expression>) -> bool {
+        values != 0 && values != variables && !last_value.is_some_and(is_multiple_values)
+    }
+}
+
+impl NodeProcessor for ArityChecker {
+    fn process_local_assign_statement(&mut self, assign: &mut LocalAssignStatement) {
+        let variables = assign.variables_len();
+        let values = assign.values_len();
+
+        if !Self::is_mismatch(variables, values, assign.last_value()) {
+            return;
+        }
+
+        match self.fix {
+            Some(ArityFix::PadNil) if values < variables => {
+                for _ in values..variables {
+                    assign.push_value(Expression::nil());
+                }
+            }
+            Some(ArityFix::Truncate)
+                if values > variables && self.can_truncate(variables, assign.iter_values()) =>
+            {
+                for _ in variables..values {
+                    assign.pop_value();
+                }
+            }
+            _ => {
+                self.mismatches.push(ArityMismatch {
+                    kind: "local assignment",
+                    line: assign
+                        .get_tokens()
+                        .and_then(|tokens| tokens.local.get_line_number()),
+                    variables,
+                    values,
+                });
+            }
+        }
+    }
+
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        let variables = assign.variables_len();
+        let values = assign.values_len();
+
+        if !Self::is_mismatch(variables, values, assign.last_value()) {
+            return;
+        }
+
+        match self.fix {
+            Some(ArityFix::PadNil) if values < variables => {
+                for _ in values..variables {
+                    assign.push_value(Expression::nil());
+                }
+            }
+            Some(ArityFix::Truncate)
+                if values > variables && self.can_truncate(variables, assign.iter_values()) =>
+            {
+                for _ in variables..values {
+                    assign.pop_value();
+                }
+            }
+            _ => {
+                self.mismatches.push(ArityMismatch {
+                    kind: "assignment",
+                    line: assign
+                        .get_tokens()
+                        .and_then(|tokens| tokens.equal.get_line_number()),
+                    variables,
+                    values,
+                });
+            }
+        }
+    }
+}
+
+pub const CHECK_ASSIGNMENT_ARITY_RULE_NAME: &str = "check_assignment_arity";
+
+/// How mismatches found by the [`CheckAssignmentArity`] rule are reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AritySeverity {
+    /// Log a warning for each mismatch.
+    #[default]
+    Warning,
+    /// Fail the processing of the file.
+    Error,
+}
+
+impl AritySeverity {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "warning" => Some(Self::Warning),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// How the [`CheckAssignmentArity`] rule changes mismatched assignments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArityFix {
+    /// Add explicit `nil` values when there are fewer values than variables.
+    PadNil,
+    /// Remove extra values that do not have side effects.
+    Truncate,
+}
+
+impl ArityFix {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pad_nil" => Some(Self::PadNil),
+            "truncate" => Some(Self::Truncate),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::PadNil => "pad_nil",
+            Self::Truncate => "truncate",
+        }
+    }
+}
+
+/// A rule that reports assignments where the number of values does not match the number of
+/// variables, and can optionally align them.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CheckAssignmentArity {
+    severity: AritySeverity,
+    fix: Option<ArityFix>,
+}
+
+impl CheckAssignmentArity {
+    pub fn with_severity(mut self, severity: AritySeverity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_fix(mut self, fix: ArityFix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+impl Rule for CheckAssignmentArity {
+    fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
+        let mut checker = ArityChecker::new(self.fix);
+        DefaultVisitor::visit_block(block, &mut checker);
+
+        if checker.mismatches.is_empty() {
+            return Ok(());
+        }
+
+        match self.severity {
+            AritySeverity::Warning => {
+                for mismatch in checker.mismatches {
+                    log::warn!("[{}] {}", context.current_path().display(), mismatch);
+                }
+                Ok(())
+            }
+            AritySeverity::Error => Err(checker
+                .mismatches
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")),
+        }
+    }
+}
+
+impl RuleConfiguration for CheckAssignmentArity {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "severity" => {
+                    let severity = value.expect_string(&key)?;
+                    self.severity = AritySeverity::parse(&severity).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: key.clone(),
+                            message: format!(
+                                "invalid severity `{}` (must be `warning` or `error`)",
+                                severity
+                            ),
+                        }
+                    })?;
+                }
+                "fix" => {
+                    let fix = value.expect_string(&key)?;
+                    self.fix = Some(ArityFix::parse(&fix).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: key.clone(),
+                            message: format!(
+                                "invalid fix `{}` (must be `pad_nil` or `truncate`)",
+                                fix
+                            ),
+                        }
+                    })?);
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        CHECK_ASSIGNMENT_ARITY_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["fix", "severity"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "fix" => RulePropertyKind::Choice(&["pad_nil", "truncate"]),
+            _ => RulePropertyKind::Choice(&["warning", "error"]),
+        }
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.severity != AritySeverity::default() {
+            properties.insert("severity".to_owned(), self.severity.as_str().into());
+        }
+
+        if let Some(fix) = self.fix {
+            properties.insert("fix".to_owned(), fix.as_str().into());
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> CheckAssignmentArity {
+        CheckAssignmentArity::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_check_assignment_arity", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_fix_and_severity() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .with_fix(ArityFix::Truncate)
+                .with_severity(AritySeverity::Error),
+        );
+
+        assert_json_snapshot!("check_assignment_arity_with_fix_and_severity", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_fix_should_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'check_assignment_arity',
+            fix: 'remove',
+        }"#,
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'fix': invalid fix `remove` (must be `pad_nil` or `truncate`)"
+        );
+    }
+
+    #[test]
+    fn display_fewer_values_mismatch() {
+        let mismatch = ArityMismatch {
+            kind: "local assignment",
+            line: Some(4),
+            variables: 3,
+            values: 1,
+        };
+
+        pretty_assertions::assert_eq!(
+            mismatch.to_string(),
+            "local assignment (line 4) assigns 1 value to 3 variables: \
+            the last variables are assigned `nil`"
+        );
+    }
+
+    #[test]
+    fn display_extra_values_mismatch() {
+        let mismatch = ArityMismatch {
+            kind: "assignment",
+            line: None,
+            variables: 1,
+            values: 2,
+        };
+
+        pretty_assertions::assert_eq!(
+            mismatch.to_string(),
+            "assignment assigns 2 values to 1 variable: the extra values are evaluated and discarded"
+        );
+    }
+}
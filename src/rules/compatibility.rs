@@ -0,0 +1,123 @@
+use std::fmt;
+
+#[cfg(feature = "serde-config")]
+use serde::Serialize;
+
+use crate::nodes::Block;
+use crate::rules::Context;
+
+/// How applying a rule to a file is expected to behave, as reported by a
+/// [`CompatibilityNote`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "snake_case"))]
+pub enum CompatibilityKind {
+    /// The rule leaves the whole file unchanged.
+    Skipped,
+    /// The rule may change the behavior of the code or fail on the file.
+    Risky,
+    /// The rule leaves some parts of the file unchanged.
+    Conservative,
+}
+
+impl CompatibilityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Skipped => "skipped",
+            Self::Risky => "risky",
+            Self::Conservative => "conservative",
+        }
+    }
+}
+
+impl fmt::Display for CompatibilityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A prediction of how a rule behaves on a file, found without applying the rule (see
+/// [`Rule::compatibility_check`](crate::rules::Rule::compatibility_check)).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+pub struct CompatibilityNote {
+    kind: CompatibilityKind,
+    message: String,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    line: Option<usize>,
+}
+
+impl CompatibilityNote {
+    pub fn new(kind: CompatibilityKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            line: None,
+        }
+    }
+
+    pub fn skipped(message: impl Into<String>) -> Self {
+        Self::new(CompatibilityKind::Skipped, message)
+    }
+
+    pub fn risky(message: impl Into<String>) -> Self {
+        Self::new(CompatibilityKind::Risky, message)
+    }
+
+    pub fn conservative(message: impl Into<String>) -> Self {
+        Self::new(CompatibilityKind::Conservative, message)
+    }
+
+    pub fn with_line(mut self, line: Option<usize>) -> Self {
+        self.line = line;
+        self
+    }
+
+    #[inline]
+    pub fn kind(&self) -> CompatibilityKind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    #[inline]
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+}
+
+impl fmt::Display for CompatibilityNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "{} (line {}): {}", self.kind, line, self.message)
+        } else {
+            write!(f, "{}: {}", self.kind, self.message)
+        }
+    }
+}
+
+/// Returns a note when the file accesses its environment dynamically, which makes the rules
+/// relying on static scoping leave the file unchanged (see `skip_dynamic_environment`).
+pub(crate) fn dynamic_environment_note(
+    block: &Block,
+    context: &Context,
+) -> Option<CompatibilityNote> {
+    let usage = context.dynamic_environment_of(block);
+
+    usage.is_dynamic().then(|| {
+        CompatibilityNote::skipped(format!(
+            "the file uses {} to access its environment dynamically",
+            usage
+                .iter_functions()
+                .map(|function| format!("`{}`", function))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    })
+}
@@ -1,90 +1,334 @@
-use crate::nodes::{BinaryOperator, Block, Expression};
-use crate::process::{DefaultVisitor, Evaluator, NodeProcessor, NodeVisitor};
+use std::collections::{HashMap, HashSet};
+use std::ops;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::generator::{DenseLuaGenerator, LuaGenerator};
+use crate::nodes::{
+    wrap_if_needed, Arguments, AssignStatement, BinaryExpression, BinaryOperator, Block,
+    Expression, ExpressionContext, FunctionCall, GenericForStatement, LastStatement,
+    LocalAssignStatement, Prefix, TableEntry, TableExpression,
+};
+use crate::process::{
+    fold_number, number_subtype, DeterministicFunction, Environment, EnvironmentTarget, Evaluator,
+    IdentifierTracker, LuaValue, LuaVersion, NodeProcessor, NodeVisitor, ScopeVisitor, SelectCall,
+    StringDecisionContext,
+};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
+    RulePropertyKind, RulePropertyValue,
 };
+use crate::utils::log;
+
+/// A function of the standard library that observes more than the value of its argument:
+/// `type` observes its type and `tostring` observes its text (like `4` and `4.0`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SensitiveCall {
+    Type,
+    ToString,
+}
+
+impl SensitiveCall {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Type => "type",
+            Self::ToString => "tostring",
+        }
+    }
 
-use super::verify_no_rule_properties;
+    fn observed_property(&self) -> &'static str {
+        match self {
+            Self::Type => "type",
+            Self::ToString => "text",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Default)]
 struct Computer {
     evaluator: Evaluator,
+    environment: Option<Arc<Environment>>,
+    identifier_tracker: IdentifierTracker,
+    path: PathBuf,
+    // the addresses of the expressions written where multiple values can be used (like the
+    // last argument of a call), which are only compared with the visited expressions
+    multiple_values_positions: HashSet<*const Expression>,
+    // the addresses of the expressions passed to `type` or `tostring`, where a computed value
+    // must keep the same type (and the same text for `tostring`)
+    sensitive_positions: HashMap<*const Expression, SensitiveCall>,
+    // when defined, concatenations are only folded when none of their strings is shareable
+    string_decisions: Option<StringDecisionContext>,
 }
 
 impl Computer {
+    fn new(
+        evaluator: Evaluator,
+        environment: Option<Arc<Environment>>,
+        path: impl Into<PathBuf>,
+        identifier_tracker: IdentifierTracker,
+    ) -> Self {
+        Self {
+            evaluator,
+            environment,
+            path: path.into(),
+            identifier_tracker,
+            ..Default::default()
+        }
+    }
+
+    fn with_string_decisions(mut self, string_decisions: StringDecisionContext) -> Self {
+        self.string_decisions = Some(string_decisions);
+        self
+    }
+
+    /// Returns false when folding the concatenation would merge a string that is worth
+    /// sharing in a local into a longer literal.
+    fn can_fold_concat(&self, binary: &BinaryExpression) -> bool {
+        let Some(string_decisions) = &self.string_decisions else {
+            return true;
+        };
+
+        let mut operands = Vec::new();
+        collect_concat_operands(binary, &mut operands);
+
+        let can_fold =
+            operands.windows(2).all(
+                |pair| match (string_value(pair[0]), string_value(pair[1])) {
+                    (Some(left), Some(right)) => string_decisions.should_merge(left, right),
+                    (Some(value), None) | (None, Some(value)) => {
+                        !string_decisions.is_shareable(value)
+                    }
+                    (None, None) => true,
+                },
+            );
+
+        if !can_fold {
+            let mut generator = DenseLuaGenerator::default();
+            generator.write_binary_expression(binary);
+            log::info!(
+                "[{}] skip computing `{}`: a string is shareable",
+                self.path.display(),
+                generator.into_string(),
+            );
+        }
+
+        can_fold
+    }
+
+    /// Evaluates an expression into a literal. Numbers are only folded when the literal
+    /// cannot change the value observed by the program.
+    fn fold(&self, expression: &Expression, evaluator: &Evaluator) -> Option<Expression> {
+        match evaluator.evaluate(expression) {
+            LuaValue::Number(value) => match fold_number(expression, value, evaluator) {
+                Ok(literal) => Some(literal),
+                Err(issue) => {
+                    let mut generator = DenseLuaGenerator::default();
+                    generator.write_expression(expression);
+                    log::info!(
+                        "[{}] skip computing `{}`: {}",
+                        self.path.display(),
+                        generator.into_string(),
+                        issue
+                    );
+                    None
+                }
+            },
+            value => value.to_expression(),
+        }
+    }
+
+    fn mark_multiple_values_position(&mut self, expression: Option<&Expression>) {
+        if let Some(expression) = expression {
+            self.multiple_values_positions
+                .insert(expression as *const Expression);
+        }
+    }
+
+    fn is_multiple_values_position(&self, expression: &Expression) -> bool {
+        self.multiple_values_positions
+            .contains(&(expression as *const Expression))
+    }
+
+    fn get_sensitive_call(&self, call: &FunctionCall) -> Option<SensitiveCall> {
+        if call.get_method().is_some() {
+            return None;
+        }
+
+        let sensitive_call = match call.get_prefix() {
+            Prefix::Identifier(identifier) => match identifier.get_name() {
+                "type" => SensitiveCall::Type,
+                "tostring" => SensitiveCall::ToString,
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        self.is_global(sensitive_call.name())
+            .then_some(sensitive_call)
+    }
+
+    fn mark_sensitive_position(&mut self, expression: &Expression, call: SensitiveCall) {
+        self.sensitive_positions
+            .insert(expression as *const Expression, call);
+    }
+
+    fn get_sensitive_position(&self, expression: &Expression) -> Option<SensitiveCall> {
+        self.sensitive_positions
+            .get(&(expression as *const Expression))
+            .copied()
+    }
+
+    /// Marks the operands of an expression passed to `type` or `tostring` that was not
+    /// computed, since computing an operand can also change the result of the call.
+    fn mark_sensitive_operands(&mut self, expression: &Expression, call: SensitiveCall) {
+        match expression {
+            Expression::Parenthese(parenthese) => {
+                self.mark_sensitive_position(parenthese.inner_expression(), call);
+            }
+            Expression::TypeCast(type_cast) => {
+                self.mark_sensitive_position(type_cast.get_expression(), call);
+            }
+            Expression::Binary(binary) if call == SensitiveCall::ToString => {
+                self.mark_sensitive_position(binary.left(), call);
+                self.mark_sensitive_position(binary.right(), call);
+            }
+            Expression::Unary(unary) if call == SensitiveCall::ToString => {
+                self.mark_sensitive_position(unary.get_expression(), call);
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns true when replacing the expression does not change what the `type` or
+    /// `tostring` call observes from its value.
+    fn preserves_call_result(
+        &self,
+        call: SensitiveCall,
+        expression: &Expression,
+        replacement: &Expression,
+    ) -> bool {
+        let evaluator = self.get_evaluator();
+
+        let same_type = matches!(
+            (evaluator.evaluate_type(expression), evaluator.evaluate_type(replacement)),
+            (Some(expression_type), Some(replacement_type)) if expression_type == replacement_type
+        );
+
+        let preserved = same_type
+            && (call == SensitiveCall::Type
+                || !matches!(evaluator.evaluate(replacement), LuaValue::Number(_))
+                || matches!(
+                    (number_subtype(expression), number_subtype(replacement)),
+                    (Some(expression_subtype), Some(replacement_subtype))
+                        if expression_subtype == replacement_subtype
+                ));
+
+        if !preserved {
+            let mut generator = DenseLuaGenerator::default();
+            generator.write_expression(expression);
+            log::info!(
+                "[{}] skip computing `{}`: the {} observed by `{}` could change",
+                self.path.display(),
+                generator.into_string(),
+                call.observed_property(),
+                call.name(),
+            );
+        }
+
+        preserved
+    }
+
+    /// The environment is only used when none of its globals is shadowed by a local variable
+    /// and when the scope does not define its own `_ENV`.
+    fn get_evaluator(&self) -> Evaluator {
+        match &self.environment {
+            Some(environment)
+                if environment
+                    .iter_global_names()
+                    .all(|name| self.is_global(name)) =>
+            {
+                self.evaluator.clone().with_environment(environment.clone())
+            }
+            _ => self.evaluator.clone(),
+        }
+    }
+
     fn replace_with(&mut self, expression: &Expression) -> Option<Expression> {
+        let evaluator = self.get_evaluator();
+
         match expression {
             Expression::Unary(_) => {
-                if !self.evaluator.has_side_effects(expression) {
-                    self.evaluator.evaluate(expression).to_expression()
+                if !evaluator.has_side_effects(expression) {
+                    self.fold(expression, &evaluator)
                 } else {
                     None
                 }
             }
             Expression::Binary(binary) => {
-                if !self.evaluator.has_side_effects(expression) {
-                    self.evaluator
-                        .evaluate(expression)
-                        .to_expression()
-                        .or_else(|| {
-                            match binary.operator() {
-                                BinaryOperator::And => {
-                                    self.evaluator.evaluate(binary.left()).is_truthy().map(
-                                        |is_truthy| {
-                                            if is_truthy {
-                                                binary.right().clone()
-                                            } else {
-                                                binary.left().clone()
-                                            }
-                                        },
-                                    )
-                                }
-                                BinaryOperator::Or => {
-                                    self.evaluator.evaluate(binary.left()).is_truthy().map(
-                                        |is_truthy| {
-                                            if is_truthy {
-                                                binary.left().clone()
-                                            } else {
-                                                binary.right().clone()
-                                            }
-                                        },
-                                    )
-                                }
-                                _ => None,
-                            }
-                            .map(|mut expression| {
-                                self.process_expression(&mut expression);
-                                expression
-                            })
+                if binary.operator() == BinaryOperator::Concat && !self.can_fold_concat(binary) {
+                    return None;
+                }
+
+                if !evaluator.has_side_effects(expression) {
+                    self.fold(expression, &evaluator).or_else(|| {
+                        match binary.operator() {
+                            BinaryOperator::And => evaluator
+                                .evaluate(binary.left())
+                                .is_truthy()
+                                .map(|is_truthy| {
+                                    if is_truthy {
+                                        binary.right().clone()
+                                    } else {
+                                        binary.left().clone()
+                                    }
+                                }),
+                            BinaryOperator::Or => evaluator
+                                .evaluate(binary.left())
+                                .is_truthy()
+                                .map(|is_truthy| {
+                                    if is_truthy {
+                                        binary.left().clone()
+                                    } else {
+                                        binary.right().clone()
+                                    }
+                                }),
+                            _ => None,
+                        }
+                        .map(|mut expression| {
+                            self.process_expression(&mut expression);
+                            expression
                         })
+                    })
                 } else {
                     match binary.operator() {
                         BinaryOperator::And => {
-                            if !self.evaluator.has_side_effects(binary.left()) {
-                                self.evaluator.evaluate(binary.left()).is_truthy().map(
-                                    |is_truthy| {
+                            if !evaluator.has_side_effects(binary.left()) {
+                                evaluator
+                                    .evaluate(binary.left())
+                                    .is_truthy()
+                                    .map(|is_truthy| {
                                         if is_truthy {
                                             binary.right().clone()
                                         } else {
                                             binary.left().clone()
                                         }
-                                    },
-                                )
+                                    })
                             } else {
                                 None
                             }
                         }
                         BinaryOperator::Or => {
-                            if !self.evaluator.has_side_effects(binary.left()) {
-                                self.evaluator.evaluate(binary.left()).is_truthy().map(
-                                    |is_truthy| {
+                            if !evaluator.has_side_effects(binary.left()) {
+                                evaluator
+                                    .evaluate(binary.left())
+                                    .is_truthy()
+                                    .map(|is_truthy| {
                                         if is_truthy {
                                             binary.left().clone()
                                         } else {
                                             binary.right().clone()
                                         }
-                                    },
-                                )
+                                    })
                             } else {
                                 None
                             }
@@ -93,9 +337,43 @@ impl Computer {
                     }
                 }
             }
+            Expression::Call(call) => {
+                if evaluator.has_side_effects(expression) {
+                    return None;
+                }
+
+                match evaluator.get_select_call(call) {
+                    // the selected values can only be replaced by the first one where a
+                    // single value is used
+                    Some(SelectCall::Tail(values)) => {
+                        if self.is_multiple_values_position(expression) {
+                            None
+                        } else {
+                            Some(
+                                values
+                                    .first()
+                                    .map_or_else(Expression::nil, |value| value.as_ref().clone()),
+                            )
+                        }
+                    }
+                    Some(SelectCall::Count(_)) | None => {
+                        // a function returning zero or multiple values can only be replaced
+                        // where a single value is used
+                        let returns_single_value = evaluator
+                            .evaluate_deterministic_call(call)
+                            .is_none_or(|results| results.len() == 1);
+
+                        if returns_single_value || !self.is_multiple_values_position(expression) {
+                            self.fold(expression, &evaluator)
+                        } else {
+                            None
+                        }
+                    }
+                }
+            }
             Expression::If(_) => {
-                if !self.evaluator.has_side_effects(expression) {
-                    self.evaluator.evaluate(expression).to_expression()
+                if !evaluator.has_side_effects(expression) {
+                    self.fold(expression, &evaluator)
                 } else {
                     None
                 }
@@ -105,10 +383,108 @@ impl Computer {
     }
 }
 
+fn collect_concat_operands<'a>(binary: &'a BinaryExpression, operands: &mut Vec<&'a Expression>) {
+    for operand in [binary.left(), binary.right()] {
+        match operand {
+            Expression::Binary(inner) if inner.operator() == BinaryOperator::Concat => {
+                collect_concat_operands(inner, operands);
+            }
+            Expression::Parenthese(parenthese) => match parenthese.inner_expression() {
+                Expression::Binary(inner) if inner.operator() == BinaryOperator::Concat => {
+                    collect_concat_operands(inner, operands);
+                }
+                inner => operands.push(inner),
+            },
+            _ => operands.push(operand),
+        }
+    }
+}
+
+fn string_value(expression: &Expression) -> Option<&str> {
+    match expression {
+        Expression::String(string) => Some(string.get_value()),
+        _ => None,
+    }
+}
+
+impl ops::Deref for Computer {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for Computer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
 impl NodeProcessor for Computer {
     fn process_expression(&mut self, expression: &mut Expression) {
-        if let Some(replace_with) = self.replace_with(expression) {
-            *expression = replace_with;
+        let sensitive_call = self.get_sensitive_position(expression);
+
+        let replacement = self.replace_with(expression).filter(|replacement| {
+            sensitive_call
+                .is_none_or(|call| self.preserves_call_result(call, expression, replacement))
+        });
+
+        if replacement.is_none() {
+            if let Some(call) = sensitive_call {
+                self.mark_sensitive_operands(expression, call);
+            }
+        }
+
+        if let Some(replace_with) = replacement {
+            // a binary expression can be replaced by one of its operands, which must not
+            // expand into multiple values where the binary expression produced one
+            let context = if self.is_multiple_values_position(expression) {
+                ExpressionContext::MultipleValues
+            } else {
+                ExpressionContext::Single
+            };
+            *expression = wrap_if_needed(replace_with, context);
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if let Arguments::Tuple(tuple) = call.get_arguments() {
+            self.mark_multiple_values_position(tuple.iter_values().last());
+
+            if let Some(sensitive_call) = self.get_sensitive_call(call) {
+                if let Some(argument) = tuple.iter_values().next() {
+                    self.mark_sensitive_position(argument, sensitive_call);
+                }
+            }
+        }
+    }
+
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        if assign.variables_len() > assign.values_len() {
+            self.mark_multiple_values_position(assign.last_value());
+        }
+    }
+
+    fn process_local_assign_statement(&mut self, assign: &mut LocalAssignStatement) {
+        if assign.variables_len() > assign.values_len() {
+            self.mark_multiple_values_position(assign.last_value());
+        }
+    }
+
+    fn process_generic_for_statement(&mut self, generic_for: &mut GenericForStatement) {
+        self.mark_multiple_values_position(generic_for.iter_expressions().last());
+    }
+
+    fn process_last_statement(&mut self, statement: &mut LastStatement) {
+        if let LastStatement::Return(statement) = statement {
+            self.mark_multiple_values_position(statement.iter_expressions().last());
+        }
+    }
+
+    fn process_table_expression(&mut self, table: &mut TableExpression) {
+        if let Some(TableEntry::Value(value)) = table.get_entries().last() {
+            self.mark_multiple_values_position(Some(value));
         }
     }
 }
@@ -117,18 +493,115 @@ pub const COMPUTE_EXPRESSIONS_RULE_NAME: &str = "compute_expression";
 
 /// A rule that compute expressions that do not have any side-effects.
 #[derive(Debug, Default, PartialEq, Eq)]
-pub struct ComputeExpression {}
+pub struct ComputeExpression {
+    stdlib_version: Option<LuaVersion>,
+    assume_no_env_manipulation: bool,
+    evaluate_functions: Vec<DeterministicFunction>,
+    keep_shareable_strings: bool,
+}
+
+impl ComputeExpression {
+    /// Uses the types of the standard library of the given Lua version to compute more
+    /// expressions (like `type(math.pi) == "number"`).
+    pub fn with_stdlib_version(mut self, version: LuaVersion) -> Self {
+        self.stdlib_version = Some(version);
+        self
+    }
+
+    /// Uses the environment even in scopes where a local or a parameter named `_ENV`
+    /// is defined.
+    pub fn with_assume_no_env_manipulation(mut self, value: bool) -> Self {
+        self.assume_no_env_manipulation = value;
+        self
+    }
+
+    /// Computes the calls to the given function of the standard library when its arguments
+    /// are known. Calls are only computed when the standard library is known (from the
+    /// `stdlib_version` property or from the environment).
+    pub fn with_evaluated_function(mut self, function: DeterministicFunction) -> Self {
+        if !self.evaluate_functions.contains(&function) {
+            self.evaluate_functions.push(function);
+        }
+        self
+    }
+
+    /// Keeps the concatenations of strings that are worth sharing in a local variable (like
+    /// a string repeated in many concatenations), so that they can be deduplicated instead of
+    /// being merged into longer literals.
+    pub fn with_keep_shareable_strings(mut self, value: bool) -> Self {
+        self.keep_shareable_strings = value;
+        self
+    }
+}
 
 impl FlawlessRule for ComputeExpression {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
-        let mut processor = Computer::default();
-        DefaultVisitor::visit_block(block, &mut processor);
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let environment = match self.stdlib_version {
+            Some(version) => Some(Environment::builtin(EnvironmentTarget::from(version))),
+            None if !context.environment().is_empty() => Some(context.environment().clone()),
+            None => None,
+        };
+        let mut processor = Computer::new(
+            Evaluator::default().with_deterministic_functions(self.evaluate_functions.clone()),
+            environment,
+            context.current_path(),
+            IdentifierTracker::new()
+                .with_assume_no_env_manipulation(self.assume_no_env_manipulation),
+        );
+        if self.keep_shareable_strings {
+            processor = processor.with_string_decisions(StringDecisionContext::from_block(block));
+        }
+        ScopeVisitor::visit_block(block, &mut processor);
     }
 }
 
 impl RuleConfiguration for ComputeExpression {
     fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
-        verify_no_rule_properties(&properties)?;
+        for (key, value) in properties {
+            match key.as_str() {
+                "stdlib_version" => {
+                    let version = value.expect_string(&key)?;
+                    self.stdlib_version = Some(LuaVersion::parse(&version).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: "stdlib_version".to_owned(),
+                            message: format!(
+                                "invalid value `{}` (must be `lua51`, `lua53`, `lua54` or `luau`)",
+                                version
+                            ),
+                        }
+                    })?);
+                }
+                "assume_no_env_manipulation" => {
+                    self.assume_no_env_manipulation = value.expect_bool(&key)?;
+                }
+                "keep_shareable_strings" => {
+                    self.keep_shareable_strings = value.expect_bool(&key)?;
+                }
+                "evaluate_functions" => {
+                    self.evaluate_functions.clear();
+                    for name in value.expect_string_list(&key)? {
+                        let function = DeterministicFunction::parse(&name).ok_or_else(|| {
+                            RuleConfigurationError::UnexpectedValue {
+                                property: "evaluate_functions".to_owned(),
+                                message: format!(
+                                    "`{}` cannot be evaluated (must be one of {})",
+                                    name,
+                                    DeterministicFunction::all()
+                                        .iter()
+                                        .map(|function| format!("`{}`", function.name()))
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
+                                ),
+                            }
+                        })?;
+                        if !self.evaluate_functions.contains(&function) {
+                            self.evaluate_functions.push(function);
+                        }
+                    }
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
 
         Ok(())
     }
@@ -137,8 +610,68 @@ impl RuleConfiguration for ComputeExpression {
         COMPUTE_EXPRESSIONS_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "local value = 1 + 2 * 3\n\
+                local message = \"a\" .. \"b\"",
+        )]
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &[
+            "assume_no_env_manipulation",
+            "evaluate_functions",
+            "keep_shareable_strings",
+            "stdlib_version",
+        ]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "assume_no_env_manipulation" | "keep_shareable_strings" => RulePropertyKind::Boolean,
+            "evaluate_functions" => RulePropertyKind::StringList,
+            _ => RulePropertyKind::Choice(&["lua51", "lua53", "lua54", "luau"]),
+        }
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
-        RuleProperties::new()
+        let mut properties = RuleProperties::new();
+
+        if let Some(version) = self.stdlib_version {
+            properties.insert("stdlib_version".to_owned(), version.as_str().into());
+        }
+
+        if self.assume_no_env_manipulation {
+            properties.insert("assume_no_env_manipulation".to_owned(), true.into());
+        }
+
+        if self.keep_shareable_strings {
+            properties.insert("keep_shareable_strings".to_owned(), true.into());
+        }
+
+        if !self.evaluate_functions.is_empty() {
+            properties.insert(
+                "evaluate_functions".to_owned(),
+                RulePropertyValue::StringList(
+                    self.evaluate_functions
+                        .iter()
+                        .map(|function| function.name().to_owned())
+                        .collect(),
+                ),
+            );
+        }
+
+        properties
+    }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if self.assume_no_env_manipulation {
+            properties.push("assume_no_env_manipulation");
+        }
+
+        properties
     }
 }
 
@@ -159,4 +692,68 @@ mod test {
 
         assert_json_snapshot!("default_compute_expression", rule);
     }
+
+    #[test]
+    fn serialize_rule_with_stdlib_version() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_stdlib_version(LuaVersion::Lua54));
+
+        assert_json_snapshot!("compute_expression_with_stdlib_version", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_evaluated_functions() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .with_evaluated_function(DeterministicFunction::MathFloor)
+                .with_evaluated_function(DeterministicFunction::StringRep),
+        );
+
+        assert_json_snapshot!("compute_expression_with_evaluated_functions", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_keep_shareable_strings() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_keep_shareable_strings(true));
+
+        assert_json_snapshot!("compute_expression_with_keep_shareable_strings", rule);
+    }
+
+    #[test]
+    fn configure_with_function_that_cannot_be_evaluated_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'compute_expression',
+            evaluate_functions: ['math.floor', 'math.random'],
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'evaluate_functions': `math.random` cannot be evaluated (must be one of `math.abs`, `math.ceil`, `math.floor`, `math.fmod`, `math.max`, `math.min`, `string.byte`, `string.char`, `string.find`, `string.format`, `string.len`, `string.lower`, `string.rep`, `string.sub`, `string.upper`)"
+        );
+    }
+
+    #[test]
+    fn configure_with_invalid_stdlib_version_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'compute_expression',
+            stdlib_version: 'lua52',
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'stdlib_version': invalid value `lua52` (must be `lua51`, `lua53`, `lua54` or `luau`)"
+        );
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'compute_expression',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
 }
@@ -0,0 +1,909 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::nodes::{
+    AssignStatement, BinaryOperator, Block, CompoundAssignStatement, DecimalNumber, DoStatement,
+    Expression, FunctionCall, FunctionStatement, Identifier, LocalFunctionStatement,
+    ParentheseExpression, Prefix, Statement, StringExpression, UnaryOperator, Variable,
+};
+use crate::process::processors::FindUsage;
+use crate::process::utils::is_valid_identifier;
+use crate::process::{
+    fold_number, DefaultPostVisitor, DefaultVisitor, Evaluator, LuaValue, NodePostProcessor,
+    NodePostVisitor, NodeProcessor, NodeVisitor, Scope, ScopeVisitor,
+};
+use crate::rules::{
+    Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult, RuleProperties,
+    RulePropertyKind, RulePropertyValue,
+};
+
+use super::unused_if_branch::{FilterResult, IfFilter};
+use super::{skip_dynamic_environment, verify_required_properties};
+
+/// Returns the dotted path of a variable read (like `Config.DEBUG`) with the name of its root.
+fn prefix_path(prefix: &Prefix) -> Option<(String, &str)> {
+    match prefix {
+        Prefix::Identifier(identifier) => {
+            Some((identifier.get_name().to_owned(), identifier.get_name()))
+        }
+        Prefix::Field(field) => field_path(field.get_prefix(), field.get_field().get_name()),
+        Prefix::Index(index) => match index.get_index() {
+            Expression::String(string) => field_path(index.get_prefix(), string.get_value()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn field_path<'a>(prefix: &'a Prefix, field: &str) -> Option<(String, &'a str)> {
+    let (mut path, root) = prefix_path(prefix)?;
+    path.push('.');
+    path.push_str(field);
+    Some((path, root))
+}
+
+fn expression_path(expression: &Expression) -> Option<(String, &str)> {
+    match expression {
+        Expression::Identifier(identifier) => {
+            Some((identifier.get_name().to_owned(), identifier.get_name()))
+        }
+        Expression::Field(field) => field_path(field.get_prefix(), field.get_field().get_name()),
+        Expression::Index(index) => match index.get_index() {
+            Expression::String(string) => field_path(index.get_prefix(), string.get_value()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn variable_path(variable: &Variable) -> Option<(String, &str)> {
+    match variable {
+        Variable::Identifier(identifier) => {
+            Some((identifier.get_name().to_owned(), identifier.get_name()))
+        }
+        Variable::Field(field) => field_path(field.get_prefix(), field.get_field().get_name()),
+        Variable::Index(index) => match index.get_index() {
+            Expression::String(string) => field_path(index.get_prefix(), string.get_value()),
+            _ => None,
+        },
+    }
+}
+
+fn is_require_call(expression: &Expression) -> bool {
+    match expression {
+        Expression::Call(call) => is_require(call),
+        _ => false,
+    }
+}
+
+fn is_require(call: &FunctionCall) -> bool {
+    call.get_method().is_none()
+        && matches!(call.get_prefix(), Prefix::Identifier(identifier) if identifier.get_name() == "require")
+}
+
+fn is_literal(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::True(_)
+            | Expression::False(_)
+            | Expression::Nil(_)
+            | Expression::Number(_)
+            | Expression::String(_)
+    )
+}
+
+fn remove_statements(block: &mut Block, indexes: &HashSet<usize>) {
+    if indexes.is_empty() {
+        return;
+    }
+
+    let mut index = 0;
+    block.filter_statements(|_| {
+        let keep = !indexes.contains(&index);
+        index += 1;
+        keep
+    });
+}
+
+/// Collects the names of the variables that are assigned after their declaration.
+#[derive(Default)]
+struct AssignedNames {
+    names: HashSet<String>,
+}
+
+impl AssignedNames {
+    fn insert(&mut self, variable: &Variable) {
+        if let Variable::Identifier(identifier) = variable {
+            self.names.insert(identifier.get_name().to_owned());
+        }
+    }
+}
+
+impl NodeProcessor for AssignedNames {
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        for variable in assign.iter_variables() {
+            self.insert(variable);
+        }
+    }
+
+    fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
+        self.insert(assign.get_variable());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LocalKind {
+    /// A local assigned the result of a `require` call, with a name listed in the roots.
+    Root,
+    /// A local function or a local assigned a function or a table, that is never assigned
+    /// again, so its value is always truthy.
+    Truthy,
+    Other,
+}
+
+/// Replaces the reads of the flags with their values and finds the writes to the flags.
+struct FlagSubstitution<'a, 'b, 'resources, 'code> {
+    flags: &'a BTreeMap<String, Expression>,
+    roots: &'a [String],
+    assigned_names: HashSet<String>,
+    context: &'a Context<'b, 'resources, 'code>,
+    scopes: Vec<HashMap<String, LocalKind>>,
+    substituted: HashSet<*const Expression>,
+    truthy_identifiers: HashSet<*const Expression>,
+    replaced_roots: HashSet<String>,
+    errors: Vec<String>,
+}
+
+impl<'a, 'b, 'resources, 'code> FlagSubstitution<'a, 'b, 'resources, 'code> {
+    fn new(
+        flags: &'a BTreeMap<String, Expression>,
+        roots: &'a [String],
+        assigned_names: HashSet<String>,
+        context: &'a Context<'b, 'resources, 'code>,
+    ) -> Self {
+        Self {
+            flags,
+            roots,
+            assigned_names,
+            context,
+            scopes: Vec::new(),
+            substituted: HashSet::new(),
+            truthy_identifiers: HashSet::new(),
+            replaced_roots: HashSet::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn local_kind(&self, name: &str) -> Option<LocalKind> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    /// Returns `true` if the name refers to a global or to a local identified as a root.
+    fn is_root(&self, name: &str) -> bool {
+        matches!(self.local_kind(name), None | Some(LocalKind::Root))
+    }
+
+    fn find_flag(&self, path: Option<(String, &str)>) -> Option<(String, &'a Expression)> {
+        let (path, root) = path?;
+        let value = self.flags.get(&path)?;
+        self.is_root(root).then(|| (root.to_owned(), value))
+    }
+
+    fn verify_write(&mut self, path: Option<(String, &str)>) {
+        if let Some((path, root)) = path {
+            if self.flags.contains_key(&path) && self.is_root(root) {
+                let message = format!("cannot assign to compile-time constant `{}`", path);
+                let message = if self.errors.is_empty() {
+                    self.context.error_at_current_node(message)
+                } else {
+                    message
+                };
+                self.errors.push(message);
+            }
+        }
+    }
+
+    fn insert_identifier(&mut self, identifier: &str, kind: LocalKind) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(identifier.to_owned(), kind);
+        } else {
+            self.scopes
+                .push(HashMap::from([(identifier.to_owned(), kind)]));
+        }
+    }
+
+    fn truthy_kind(&self, identifier: &str) -> LocalKind {
+        if self.assigned_names.contains(identifier) {
+            LocalKind::Other
+        } else {
+            LocalKind::Truthy
+        }
+    }
+}
+
+impl Scope for FlagSubstitution<'_, '_, '_, '_> {
+    fn push(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.insert_identifier(identifier.get_name(), LocalKind::Other);
+    }
+
+    fn insert_self(&mut self) {
+        self.insert_identifier("self", LocalKind::Other);
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>) {
+        let name = identifier.get_name();
+        let kind = match value.as_deref() {
+            Some(value) if is_require_call(value) && self.roots.iter().any(|root| root == name) => {
+                LocalKind::Root
+            }
+            Some(Expression::Function(_) | Expression::Table(_)) => self.truthy_kind(name),
+            _ => LocalKind::Other,
+        };
+        self.insert_identifier(name, kind);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        let kind = self.truthy_kind(function.get_name());
+        self.insert_identifier(function.get_name(), kind);
+    }
+}
+
+impl NodeProcessor for FlagSubstitution<'_, '_, '_, '_> {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if let Some((root, value)) = self.find_flag(expression_path(expression)) {
+            *expression = value.clone();
+            self.substituted.insert(expression as *const Expression);
+            self.replaced_roots.insert(root);
+        } else if let Expression::Identifier(identifier) = expression {
+            if self.local_kind(identifier.get_name()) == Some(LocalKind::Truthy) {
+                self.truthy_identifiers
+                    .insert(expression as *const Expression);
+            }
+        }
+    }
+
+    fn process_prefix_expression(&mut self, prefix: &mut Prefix) {
+        if let Some((root, value)) = self.find_flag(prefix_path(prefix)) {
+            *prefix = ParentheseExpression::new(value.clone()).into();
+            self.replaced_roots.insert(root);
+        }
+    }
+
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        for variable in assign.iter_variables() {
+            self.verify_write(variable_path(variable));
+        }
+    }
+
+    fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
+        self.verify_write(variable_path(assign.get_variable()));
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name();
+        let root = name.get_name().get_name();
+        let path = name.get_field_names().iter().chain(name.get_method()).fold(
+            root.to_owned(),
+            |mut path, field| {
+                path.push('.');
+                path.push_str(field.get_name());
+                path
+            },
+        );
+        self.verify_write(Some((path, root)));
+    }
+}
+
+/// Collects the names of the variables referenced in a node.
+#[derive(Default)]
+struct ReferencedNames {
+    names: HashSet<String>,
+}
+
+impl NodeProcessor for ReferencedNames {
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        if !self.names.contains(identifier.get_name()) {
+            self.names.insert(identifier.get_name().to_owned());
+        }
+    }
+}
+
+/// Folds the expressions that contain a flag value and removes the if branches that the
+/// flag values disable. The names referenced in the removed code are collected, since they
+/// may not be used anymore.
+struct BranchPruner {
+    evaluator: Evaluator,
+    if_filter: IfFilter,
+    affected: HashSet<*const Expression>,
+    // expressions known to always evaluate to a truthy value
+    truthy: HashSet<*const Expression>,
+    removed_statements: HashSet<*const Statement>,
+    dropped_names: ReferencedNames,
+}
+
+impl BranchPruner {
+    fn new(affected: HashSet<*const Expression>, truthy: HashSet<*const Expression>) -> Self {
+        Self {
+            evaluator: Evaluator::default(),
+            if_filter: IfFilter::default(),
+            affected,
+            truthy,
+            removed_statements: HashSet::new(),
+            dropped_names: ReferencedNames::default(),
+        }
+    }
+
+    fn is_affected(&self, expression: &Expression) -> bool {
+        self.affected.contains(&(expression as *const Expression))
+    }
+
+    fn has_affected_operand(&self, expression: &Expression) -> bool {
+        match expression {
+            Expression::Binary(binary) => {
+                self.is_affected(binary.left()) || self.is_affected(binary.right())
+            }
+            Expression::Unary(unary) => self.is_affected(unary.get_expression()),
+            Expression::Parenthese(parenthese) => self.is_affected(parenthese.inner_expression()),
+            Expression::If(if_expression) => {
+                self.is_affected(if_expression.get_condition())
+                    || if_expression
+                        .iter_branches()
+                        .any(|branch| self.is_affected(branch.get_condition()))
+            }
+            _ => false,
+        }
+    }
+
+    fn evaluate_literal(&self, expression: &Expression) -> Option<Expression> {
+        match self.evaluator.evaluate(expression) {
+            LuaValue::Number(value) => fold_number(expression, value, &self.evaluator).ok(),
+            value => value.to_expression(),
+        }
+    }
+
+    fn is_truthy(&self, expression: &Expression) -> bool {
+        self.truthy.contains(&(expression as *const Expression))
+    }
+
+    /// Returns the operand that a `and` or `or` expression evaluates to, when the left
+    /// operand has a known truthiness.
+    fn short_circuit<'e>(&self, expression: &'e Expression) -> Option<&'e Expression> {
+        let binary = match expression {
+            Expression::Binary(binary) => binary,
+            _ => return None,
+        };
+
+        if self.evaluator.has_side_effects(binary.left()) {
+            return None;
+        }
+
+        let is_truthy = if self.is_truthy(binary.left()) {
+            true
+        } else {
+            self.evaluator.evaluate(binary.left()).is_truthy()?
+        };
+
+        match binary.operator() {
+            BinaryOperator::And => Some(if is_truthy {
+                binary.right()
+            } else {
+                binary.left()
+            }),
+            BinaryOperator::Or => Some(if is_truthy {
+                binary.left()
+            } else {
+                binary.right()
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the folded expression, with `true` when the folded expression is known to be
+    /// truthy.
+    fn fold(&mut self, expression: &mut Expression) -> Option<(Expression, bool)> {
+        match expression {
+            Expression::Parenthese(parenthese) => is_literal(parenthese.inner_expression())
+                .then(|| (parenthese.inner_expression().clone(), false)),
+            Expression::Unary(_) => (!self.evaluator.has_side_effects(expression))
+                .then(|| self.evaluate_literal(expression))
+                .flatten()
+                .map(|folded| (folded, false)),
+            Expression::Binary(_) => {
+                let literal = if self.evaluator.has_side_effects(expression) {
+                    None
+                } else {
+                    self.evaluate_literal(expression)
+                };
+                literal.map(|folded| (folded, false)).or_else(|| {
+                    self.short_circuit(expression)
+                        .map(|operand| (operand.clone(), self.is_truthy(operand)))
+                })
+            }
+            Expression::If(if_expression) => self
+                .if_filter
+                .simplify_if(if_expression)
+                .map(|folded| (folded, false)),
+            _ => None,
+        }
+    }
+}
+
+impl NodeProcessor for BranchPruner {}
+
+impl NodePostProcessor for BranchPruner {
+    fn process_after_expression(&mut self, expression: &mut Expression) {
+        if !self.has_affected_operand(expression) {
+            return;
+        }
+
+        let mut original = expression.clone();
+
+        if let Some((folded, is_truthy)) = self.fold(expression) {
+            DefaultVisitor::visit_expression(&mut original, &mut self.dropped_names);
+            *expression = folded;
+            if is_truthy {
+                self.truthy.insert(expression as *const Expression);
+            }
+        }
+
+        self.affected.insert(expression as *const Expression);
+    }
+
+    fn process_after_statement(&mut self, statement: &mut Statement) {
+        let if_statement = match statement {
+            Statement::If(if_statement) => if_statement,
+            _ => return,
+        };
+
+        if !if_statement
+            .iter_branches()
+            .any(|branch| self.is_affected(branch.get_condition()))
+        {
+            return;
+        }
+
+        DefaultVisitor::visit_if_statement(if_statement, &mut self.dropped_names);
+
+        match self.if_filter.simplify_if_statement(if_statement) {
+            FilterResult::Keep => {}
+            FilterResult::Remove => {
+                *statement = DoStatement::default().into();
+                self.removed_statements
+                    .insert(statement as *const Statement);
+            }
+            FilterResult::Replace(new_statement) => {
+                *statement = new_statement;
+            }
+        }
+    }
+
+    fn process_after_block(&mut self, block: &mut Block) {
+        if self.removed_statements.is_empty() {
+            return;
+        }
+
+        let remove_indexes: HashSet<_> = block
+            .iter_statements()
+            .enumerate()
+            .filter(|(_, statement)| {
+                self.removed_statements
+                    .remove(&(*statement as *const Statement))
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        remove_statements(block, &remove_indexes);
+    }
+}
+
+/// Removes the local variables and functions that were used by the removed code, when
+/// they are not used anymore.
+struct UnusedLocalRemover<'a> {
+    candidates: &'a HashSet<String>,
+    roots: &'a [String],
+    evaluator: Evaluator,
+    dropped_names: ReferencedNames,
+}
+
+impl<'a> UnusedLocalRemover<'a> {
+    fn new(candidates: &'a HashSet<String>, roots: &'a [String]) -> Self {
+        Self {
+            candidates,
+            roots,
+            evaluator: Evaluator::default(),
+            dropped_names: ReferencedNames::default(),
+        }
+    }
+
+    /// Returns the name of the local declared by the statement, when it can be removed if it
+    /// is not used.
+    fn removable_local<'b>(&self, statement: &'b Statement) -> Option<&'b str> {
+        match statement {
+            Statement::LocalAssign(assign) if assign.variables_len() == 1 => {
+                let name = assign.get_variables()[0].get_name();
+                let removable_values = assign
+                    .iter_values()
+                    .all(|value| !self.evaluator.has_side_effects(value))
+                    || (self.roots.iter().any(|root| root == name)
+                        && assign.values_len() == 1
+                        && assign.iter_values().all(is_require_call));
+
+                (self.candidates.contains(name) && removable_values).then_some(name)
+            }
+            Statement::LocalFunction(function) => {
+                let name = function.get_name();
+                self.candidates.contains(name).then_some(name)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_used_after(block: &mut Block, index: usize, name: &str) -> bool {
+        let mut find_usage = FindUsage::new(name);
+
+        for statement in block.iter_mut_statements().skip(index + 1) {
+            ScopeVisitor::visit_statement(statement, &mut find_usage);
+            if find_usage.has_found_usage() {
+                return true;
+            }
+        }
+
+        if let Some(last_statement) = block.mutate_last_statement() {
+            ScopeVisitor::visit_last_statement(last_statement, &mut find_usage);
+        }
+
+        find_usage.has_found_usage()
+    }
+}
+
+impl NodeProcessor for UnusedLocalRemover<'_> {
+    fn process_block(&mut self, block: &mut Block) {
+        let removable: Vec<(usize, String)> = block
+            .iter_statements()
+            .enumerate()
+            .filter_map(|(index, statement)| {
+                self.removable_local(statement)
+                    .map(|name| (index, name.to_owned()))
+            })
+            .collect();
+
+        let mut remove_indexes = HashSet::new();
+
+        for (index, name) in removable {
+            if !Self::is_used_after(block, index, &name) {
+                remove_indexes.insert(index);
+            }
+        }
+
+        if remove_indexes.is_empty() {
+            return;
+        }
+
+        for (index, statement) in block.iter_mut_statements().enumerate() {
+            if remove_indexes.contains(&index) {
+                DefaultVisitor::visit_statement(statement, &mut self.dropped_names);
+            }
+        }
+
+        remove_statements(block, &remove_indexes);
+    }
+}
+
+fn literal_from_property(value: RulePropertyValue) -> Option<Expression> {
+    match value {
+        RulePropertyValue::None => Some(Expression::nil()),
+        RulePropertyValue::Boolean(value) => Some(Expression::from(value)),
+        RulePropertyValue::String(value) => Some(StringExpression::from_value(value).into()),
+        RulePropertyValue::Usize(value) => Some(DecimalNumber::new(value as f64).into()),
+        RulePropertyValue::Float(value) => Some(Expression::from(value)),
+        _ => None,
+    }
+}
+
+fn literal_to_property(value: &Expression) -> RulePropertyValue {
+    match value {
+        Expression::True(_) => RulePropertyValue::Boolean(true),
+        Expression::False(_) => RulePropertyValue::Boolean(false),
+        Expression::Number(number) => {
+            let value = number.compute_value();
+            if value.trunc() == value && value >= 0.0 && value < usize::MAX as f64 {
+                RulePropertyValue::Usize(value as usize)
+            } else {
+                RulePropertyValue::Float(value)
+            }
+        }
+        Expression::String(string) => RulePropertyValue::from(string.get_value()),
+        Expression::Unary(unary) if matches!(unary.operator(), UnaryOperator::Minus) => {
+            match unary.get_expression() {
+                Expression::Number(number) => RulePropertyValue::Float(-number.compute_value()),
+                _ => RulePropertyValue::None,
+            }
+        }
+        _ => RulePropertyValue::None,
+    }
+}
+
+pub const CONDITIONAL_COMPILATION_RULE_NAME: &str = "conditional_compilation";
+
+/// A rule that replaces compile-time flags (like `Config.DEBUG`) with their values, and
+/// removes the code disabled by these values.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ConditionalCompilation {
+    flags: BTreeMap<String, Expression>,
+    roots: Vec<String>,
+}
+
+impl ConditionalCompilation {
+    /// Replaces the reads of the given path (like `FAST_PATH` or `Config.DEBUG`) with a
+    /// literal value.
+    pub fn with_flag(mut self, path: impl Into<String>, value: impl Into<Expression>) -> Self {
+        self.flags.insert(path.into(), value.into());
+        self
+    }
+
+    /// Allows flag paths to start from a local variable with the given name, when the local
+    /// is assigned the result of a `require` call.
+    pub fn with_root(mut self, name: impl Into<String>) -> Self {
+        self.roots.push(name.into());
+        self
+    }
+}
+
+impl Rule for ConditionalCompilation {
+    fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
+        let mut assigned_names = AssignedNames::default();
+        DefaultVisitor::visit_block(block, &mut assigned_names);
+
+        let mut substitution =
+            FlagSubstitution::new(&self.flags, &self.roots, assigned_names.names, context);
+        ScopeVisitor::visit_block(block, &mut substitution);
+
+        if !substitution.errors.is_empty() {
+            return Err(substitution.errors.join("\n"));
+        }
+
+        if substitution.substituted.is_empty() && substitution.replaced_roots.is_empty() {
+            return Ok(());
+        }
+
+        let mut pruner =
+            BranchPruner::new(substitution.substituted, substitution.truthy_identifiers);
+        DefaultPostVisitor::visit_block(block, &mut pruner);
+
+        if skip_dynamic_environment(CONDITIONAL_COMPILATION_RULE_NAME, block, context) {
+            return Ok(());
+        }
+
+        let mut candidates = pruner.dropped_names.names;
+        candidates.extend(substitution.replaced_roots);
+
+        while !candidates.is_empty() {
+            let mut remover = UnusedLocalRemover::new(&candidates, &self.roots);
+            DefaultVisitor::visit_block(block, &mut remover);
+            candidates = remover.dropped_names.names;
+        }
+
+        Ok(())
+    }
+}
+
+impl RuleConfiguration for ConditionalCompilation {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_required_properties(&properties, &["flags"])?;
+
+        for (key, value) in properties {
+            match key.as_str() {
+                "flags" => {
+                    for (path, value) in value.expect_value_map(&key)? {
+                        if !path.split('.').all(|name| is_valid_identifier(name, None)) {
+                            return Err(RuleConfigurationError::UnexpectedValue {
+                                property: key,
+                                message: format!(
+                                    "invalid flag path `{}` (expected identifiers separated by dots)",
+                                    path
+                                ),
+                            });
+                        }
+                        let literal = literal_from_property(value).ok_or_else(|| {
+                            RuleConfigurationError::UnexpectedValue {
+                                property: key.clone(),
+                                message: format!(
+                                    "the value of `{}` must be a boolean, a number, a string or null",
+                                    path
+                                ),
+                            }
+                        })?;
+                        self.flags.insert(path, literal);
+                    }
+                }
+                "roots" => {
+                    self.roots = value.expect_string_list(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        CONDITIONAL_COMPILATION_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["flags", "roots"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "flags" => RulePropertyKind::ValueMap,
+            "roots" => RulePropertyKind::StringList,
+            _ => RulePropertyKind::Any,
+        }
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        properties.insert(
+            "flags".to_owned(),
+            RulePropertyValue::ValueMap(
+                self.flags
+                    .iter()
+                    .map(|(path, value)| (path.clone(), literal_to_property(value)))
+                    .collect(),
+            ),
+        );
+
+        if !self.roots.is_empty() {
+            properties.insert(
+                "roots".to_owned(),
+                RulePropertyValue::StringList(self.roots.clone()),
+            );
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+    use crate::test_utils::{assert_rule_config_roundtrip, assert_rule_output};
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> ConditionalCompilation {
+        ConditionalCompilation::default()
+            .with_flag("Config.DEBUG", false)
+            .with_flag("FAST_PATH", true)
+    }
+
+    #[test]
+    fn replace_global_flag() {
+        assert_rule_output(&new_rule(), "return FAST_PATH", "return true");
+    }
+
+    #[test]
+    fn replace_indexed_flag() {
+        assert_rule_output(&new_rule(), "return Config['DEBUG']", "return false");
+    }
+
+    #[test]
+    fn keep_shadowed_global_root() {
+        assert_rule_output(
+            &new_rule(),
+            "local FAST_PATH = false return FAST_PATH",
+            "local FAST_PATH = false return FAST_PATH",
+        );
+    }
+
+    #[test]
+    fn keep_local_that_is_not_a_root() {
+        assert_rule_output(
+            &new_rule().with_root("Config"),
+            "local Config = {} if Config.DEBUG then print() end",
+            "local Config = {} if Config.DEBUG then print() end",
+        );
+    }
+
+    #[test]
+    fn fold_negated_flag() {
+        assert_rule_output(
+            &new_rule(),
+            "if not Config.DEBUG then run() end",
+            "do run() end",
+        );
+    }
+
+    #[test]
+    fn keep_unrelated_expressions() {
+        assert_rule_output(
+            &new_rule(),
+            "local a = true and value if false then b() end",
+            "local a = true and value if false then b() end",
+        );
+    }
+
+    #[test]
+    fn remove_function_only_used_in_removed_branch() {
+        assert_rule_output(
+            &new_rule(),
+            "local function dump() end if Config.DEBUG then dump() end",
+            "",
+        );
+    }
+
+    #[test]
+    fn keep_local_with_side_effects() {
+        assert_rule_output(
+            &new_rule(),
+            "local value = compute() if Config.DEBUG then print(value) end",
+            "local value = compute()",
+        );
+    }
+
+    #[test]
+    fn configuration_roundtrip() {
+        assert_rule_config_roundtrip(
+            "{ rule: 'conditional_compilation', flags: { 'Config.DEBUG': false, FAST_PATH: true, LEVEL: 2, NAME: 'game', OPTIONAL: null }, roots: ['Config'] }",
+        );
+    }
+
+    #[test]
+    fn serialize_rule_with_properties() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_root("Config"));
+
+        assert_json_snapshot!("conditional_compilation_with_properties", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_flag_path_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'conditional_compilation',
+            flags: { 'Config..DEBUG': true },
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'flags': invalid flag path `Config..DEBUG` (expected identifiers separated by dots)"
+        );
+    }
+
+    #[test]
+    fn configure_without_flags_error() {
+        let result = json5::from_str::<Box<dyn Rule>>("{ rule: 'conditional_compilation' }");
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "missing required field 'flags'"
+        );
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'conditional_compilation',
+            flags: {},
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -22,9 +22,21 @@ pub enum RuleConfigurationError {
     /// When a property is associated with something else than an expected float. The string is the
     /// property name.
     FloatExpected(String),
+    /// When a property is associated with something else than an expected size in bytes. The
+    /// string is the property name.
+    ByteSizeExpected(String),
+    /// When a property is associated with something else than an expected duration. The string
+    /// is the property name.
+    DurationExpected(String),
     /// When a property is associated with something else than an expected list of strings. The
     /// string is the property name.
     StringListExpected(String),
+    /// When a property is associated with something else than an expected map of strings. The
+    /// string is the property name.
+    StringMapExpected(String),
+    /// When a property is associated with something else than an expected list of string maps.
+    /// The string is the property name.
+    StringMapListExpected(String),
     /// When a property is associated with something else than an expected require mode. The
     /// string is the property name.
     RequireModeExpected(String),
@@ -57,6 +69,46 @@ fn enumerate_properties(properties: &[String]) -> String {
         .collect()
 }
 
+/// Computes the number of single character edits (insertions, deletions or substitutions)
+/// needed to change one string into the other.
+pub(crate) fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b_chars.len()).collect();
+    let mut current_row = vec![0; b_chars.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current_row[0] = i + 1;
+
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution_cost = if a_char == *b_char { 0 } else { 1 };
+
+            current_row[j + 1] = (previous_row[j] + substitution_cost)
+                .min(previous_row[j + 1] + 1)
+                .min(current_row[j] + 1);
+        }
+
+        std::mem::swap(&mut previous_row, &mut current_row);
+    }
+
+    previous_row[b_chars.len()]
+}
+
+const MAXIMUM_SUGGESTION_DISTANCE: usize = 2;
+
+/// Finds the closest property name to the given unknown property, if it is close enough to
+/// be a likely typo.
+pub(crate) fn suggest_property<'a>(
+    property: &str,
+    known_properties: &'a [String],
+) -> Option<&'a str> {
+    known_properties
+        .iter()
+        .map(|known| (levenshtein_distance(property, known), known))
+        .filter(|(distance, _)| *distance <= MAXIMUM_SUGGESTION_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known.as_str())
+}
+
 impl fmt::Display for RuleConfigurationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use RuleConfigurationError::*;
@@ -77,9 +129,25 @@ impl fmt::Display for RuleConfigurationError {
                 write!(f, "unsigned integer expected for field '{}'", property)
             }
             FloatExpected(property) => write!(f, "float value expected for field '{}'", property),
+            ByteSizeExpected(property) => write!(
+                f,
+                "size in bytes (a number or a string like \"1 MB\") expected for field '{}'",
+                property
+            ),
+            DurationExpected(property) => write!(
+                f,
+                "duration (a number of seconds or a string like \"30s\") expected for field '{}'",
+                property
+            ),
             StringListExpected(property) => {
                 write!(f, "list of string expected for field '{}'", property)
             }
+            StringMapExpected(property) => {
+                write!(f, "map of strings expected for field '{}'", property)
+            }
+            StringMapListExpected(property) => {
+                write!(f, "list of string maps expected for field '{}'", property)
+            }
             RequireModeExpected(property) => {
                 write!(f, "require mode value expected for field `{}`", property)
             }
@@ -107,6 +175,41 @@ impl fmt::Display for RuleConfigurationError {
 mod test {
     use super::*;
 
+    #[test]
+    fn levenshtein_distance_of_equal_strings() {
+        assert_eq!(levenshtein_distance("renaming", "renaming"), 0)
+    }
+
+    #[test]
+    fn levenshtein_distance_with_empty_string() {
+        assert_eq!(levenshtein_distance("", "abc"), 3);
+        assert_eq!(levenshtein_distance("abc", ""), 3);
+    }
+
+    #[test]
+    fn levenshtein_distance_with_insertion() {
+        assert_eq!(levenshtein_distance("renamming", "renaming"), 1)
+    }
+
+    #[test]
+    fn levenshtein_distance_with_substitution_and_deletion() {
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3)
+    }
+
+    #[test]
+    fn suggest_closest_property() {
+        let known = vec!["globals".to_owned(), "include_functions".to_owned()];
+
+        assert_eq!(suggest_property("global", &known), Some("globals"))
+    }
+
+    #[test]
+    fn suggest_no_property_when_too_different() {
+        let known = vec!["globals".to_owned(), "include_functions".to_owned()];
+
+        assert_eq!(suggest_property("prop", &known), None)
+    }
+
     #[test]
     fn enumerate_one_property() {
         assert_eq!(enumerate_properties(&["prop".to_owned()]), "`prop`")
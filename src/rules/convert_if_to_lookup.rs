@@ -0,0 +1,655 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use crate::nodes::{
+    AssignStatement, BinaryExpression, BinaryOperator, Block, DoStatement, Expression,
+    FunctionCall, FunctionExpression, FunctionStatement, GenericForStatement, Identifier,
+    IfStatement, IndexExpression, LastStatement, LocalAssignStatement, LocalFunctionStatement,
+    NumericForStatement, ParentheseExpression, Prefix, RepeatStatement, ReturnStatement, Statement,
+    TableEntry, TableExpression, TableIndexEntry, Variable, WhileStatement,
+};
+use crate::process::{
+    DefaultVisitor, IdentifierTracker, NodeProcessor, NodeVisitor, Scope, ScopeVisitor,
+};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum LookupKey {
+    String(String),
+    Number(f64),
+}
+
+impl LookupKey {
+    fn from_literal(expression: &Expression) -> Option<Self> {
+        match expression {
+            Expression::String(string) => Some(Self::String(string.get_value().to_owned())),
+            Expression::Number(number) => Some(Self::Number(number.compute_value())),
+            _ => None,
+        }
+    }
+
+    fn into_table_entry(self, value: impl Into<Expression>) -> TableEntry {
+        match self {
+            Self::String(string) => TableEntry::from_string_key_and_value(string, value),
+            Self::Number(number) => TableIndexEntry::new(number, value).into(),
+        }
+    }
+}
+
+/// Returns the identifier and the literal key of a condition like `value == "key"`
+/// or `1 == value`.
+fn match_condition(condition: &Expression) -> Option<(&str, LookupKey)> {
+    match condition {
+        Expression::Parenthese(parenthese) => match_condition(parenthese.inner_expression()),
+        Expression::Binary(binary) if binary.operator() == BinaryOperator::Equal => {
+            match (binary.left(), binary.right()) {
+                (Expression::Identifier(identifier), literal)
+                | (literal, Expression::Identifier(identifier)) => {
+                    LookupKey::from_literal(literal).map(|key| (identifier.get_name(), key))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn is_literal(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::False(_)
+            | Expression::Nil(_)
+            | Expression::Number(_)
+            | Expression::String(_)
+            | Expression::True(_)
+    )
+}
+
+fn is_truthy_literal(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Number(_) | Expression::String(_) | Expression::True(_)
+    )
+}
+
+/// Returns the variable name and the value of a block that only contains an assignment
+/// of a literal value to an identifier.
+fn get_literal_assignment(block: &Block) -> Option<(&str, &Expression)> {
+    if block.statements_len() != 1 || block.get_last_statement().is_some() {
+        return None;
+    }
+    match block.first_statement() {
+        Some(Statement::Assign(assign))
+            if assign.variables_len() == 1 && assign.values_len() == 1 =>
+        {
+            match (assign.get_variables().first(), assign.iter_values().next()) {
+                (Some(Variable::Identifier(identifier)), Some(value)) if is_literal(value) => {
+                    Some((identifier.get_name(), value))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The statements of a branch block that would behave differently once the block is moved
+/// into a function.
+#[derive(Debug, Default)]
+struct BranchExits {
+    has_return: bool,
+    has_loop_exit: bool,
+}
+
+impl BranchExits {
+    fn find(block: &Block) -> Self {
+        let mut exits = Self::default();
+        exits.visit_block(block, false);
+        exits
+    }
+
+    fn visit_block(&mut self, block: &Block, in_loop: bool) {
+        for statement in block.iter_statements() {
+            match statement {
+                Statement::Do(do_statement) => self.visit_block(do_statement.get_block(), in_loop),
+                Statement::If(if_statement) => {
+                    for branch in if_statement.iter_branches() {
+                        self.visit_block(branch.get_block(), in_loop);
+                    }
+                    if let Some(else_block) = if_statement.get_else_block() {
+                        self.visit_block(else_block, in_loop);
+                    }
+                }
+                Statement::GenericFor(generic_for) => {
+                    self.visit_block(generic_for.get_block(), true)
+                }
+                Statement::NumericFor(numeric_for) => {
+                    self.visit_block(numeric_for.get_block(), true)
+                }
+                Statement::Repeat(repeat) => self.visit_block(repeat.get_block(), true),
+                Statement::While(while_statement) => {
+                    self.visit_block(while_statement.get_block(), true)
+                }
+                Statement::Assign(_)
+                | Statement::Call(_)
+                | Statement::CompoundAssign(_)
+                | Statement::Function(_)
+                | Statement::LocalAssign(_)
+                | Statement::LocalFunction(_)
+                | Statement::TypeDeclaration(_)
+                | Statement::Verbatim(_) => {}
+            }
+        }
+
+        match block.get_last_statement() {
+            Some(LastStatement::Return(_)) => self.has_return = true,
+            Some(LastStatement::Break(_)) | Some(LastStatement::Continue(_)) if !in_loop => {
+                self.has_loop_exit = true;
+            }
+            Some(LastStatement::Break(_)) | Some(LastStatement::Continue(_)) | None => {}
+        }
+    }
+}
+
+#[derive(Default)]
+struct VariableArgumentsFinder {
+    found: bool,
+}
+
+impl NodeProcessor for VariableArgumentsFinder {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if matches!(expression, Expression::VariableArguments(_)) {
+            self.found = true;
+        }
+    }
+}
+
+fn uses_variable_arguments(block: &mut Block) -> bool {
+    let mut finder = VariableArgumentsFinder::default();
+    DefaultVisitor::visit_block(block, &mut finder);
+    finder.found
+}
+
+/// Finds if a block uses local variables declared outside of it.
+struct CaptureFinder<'a> {
+    outer_locals: &'a IdentifierTracker,
+    identifier_tracker: IdentifierTracker,
+    captures_locals: bool,
+}
+
+impl Deref for CaptureFinder<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl DerefMut for CaptureFinder<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for CaptureFinder<'_> {
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        let name = identifier.get_name();
+        if !self.is_identifier_used(name) && self.outer_locals.is_identifier_used(name) {
+            self.captures_locals = true;
+        }
+    }
+}
+
+fn captures_locals(block: &mut Block, outer_locals: &IdentifierTracker) -> bool {
+    let mut finder = CaptureFinder {
+        outer_locals,
+        identifier_tracker: IdentifierTracker::new(),
+        captures_locals: false,
+    };
+    ScopeVisitor::visit_block(block, &mut finder);
+    finder.captures_locals
+}
+
+enum Conversion {
+    /// Every branch assigns a literal value to the same variable.
+    Assignment,
+    /// Every branch is moved into a function, called with its results returned when
+    /// `returns` is true.
+    Dispatch { returns: bool },
+}
+
+struct Processor {
+    identifier_tracker: IdentifierTracker,
+    min_branches: usize,
+    lookup_tables: Vec<LocalAssignStatement>,
+    scope_depth: usize,
+    /// The scope depths where the functions and loops being visited start, so that the
+    /// last one is removed when its scope is left.
+    function_or_loop_depths: Vec<usize>,
+}
+
+impl Processor {
+    fn new(min_branches: usize, identifier_tracker: IdentifierTracker) -> Self {
+        Self {
+            identifier_tracker,
+            min_branches,
+            lookup_tables: Vec::new(),
+            scope_depth: 0,
+            function_or_loop_depths: Vec::new(),
+        }
+    }
+
+    fn is_in_function_or_loop(&self) -> bool {
+        !self.function_or_loop_depths.is_empty()
+    }
+
+    fn enter_function_or_loop(&mut self) {
+        self.function_or_loop_depths.push(self.scope_depth);
+    }
+
+    fn analyze(&self, if_statement: &mut IfStatement) -> Option<(String, Conversion)> {
+        if if_statement.branch_count() < self.min_branches.max(1) {
+            return None;
+        }
+
+        let mut identifier: Option<&str> = None;
+        let mut keys = Vec::new();
+
+        for branch in if_statement.iter_branches() {
+            let (name, key) = match_condition(branch.get_condition())?;
+
+            if *identifier.get_or_insert(name) != name || keys.contains(&key) {
+                return None;
+            }
+            keys.push(key);
+        }
+        let identifier = identifier?.to_owned();
+
+        if let Some(else_block) = if_statement.get_else_block() {
+            if let Some((variable, default_value)) = get_literal_assignment(else_block) {
+                let is_assignment = if_statement.iter_branches().all(|branch| {
+                    get_literal_assignment(branch.get_block())
+                        .is_some_and(|(name, value)| name == variable && is_truthy_literal(value))
+                });
+
+                if is_assignment && is_literal(default_value) {
+                    return Some((identifier, Conversion::Assignment));
+                }
+            }
+        }
+
+        let mut returns = None;
+        for branch in if_statement.mutate_branches() {
+            let block = branch.mutate_block();
+            let exits = BranchExits::find(block);
+
+            if exits.has_loop_exit || uses_variable_arguments(block) {
+                return None;
+            }
+
+            let branch_returns =
+                matches!(block.get_last_statement(), Some(LastStatement::Return(_)));
+
+            if !branch_returns && exits.has_return {
+                return None;
+            }
+
+            if *returns.get_or_insert(branch_returns) != branch_returns {
+                return None;
+            }
+        }
+
+        Some((
+            identifier,
+            Conversion::Dispatch {
+                returns: returns.unwrap_or_default(),
+            },
+        ))
+    }
+
+    fn generate_lookup_identifier(&mut self) -> String {
+        let mut index = 0;
+        loop {
+            let name = if index == 0 {
+                "LOOKUP".to_owned()
+            } else {
+                format!("LOOKUP_{}", index)
+            };
+            let identifier = self.identifier_tracker.generate_named_identifier(&name);
+
+            if !self.lookup_tables.iter().any(|statement| {
+                statement
+                    .iter_variables()
+                    .any(|variable| variable.get_name() == identifier)
+            }) {
+                return identifier;
+            }
+            index += 1;
+        }
+    }
+
+    fn convert_assignment(
+        &mut self,
+        identifier: String,
+        if_statement: &mut IfStatement,
+    ) -> Statement {
+        let mut table = TableExpression::default();
+        let mut variable = None;
+
+        for branch in if_statement.mutate_branches() {
+            let (_, key) =
+                match_condition(branch.get_condition()).expect("condition should be valid");
+            let value = take_assigned_value(branch.mutate_block(), &mut variable);
+            table.mutate_entries().push(key.into_table_entry(value));
+        }
+
+        let default_value = take_assigned_value(
+            if_statement
+                .mutate_else_block()
+                .as_mut()
+                .expect("else block should exist"),
+            &mut variable,
+        );
+        let variable = variable.expect("variable should be defined");
+
+        let lookup_identifier = self.generate_lookup_identifier();
+        self.lookup_tables.push(
+            LocalAssignStatement::from_variable(lookup_identifier.as_str()).with_value(table),
+        );
+
+        AssignStatement::from_variable(
+            variable,
+            BinaryExpression::new(
+                BinaryOperator::Or,
+                IndexExpression::new(
+                    Prefix::from_name(lookup_identifier),
+                    Expression::identifier(identifier),
+                ),
+                default_value,
+            ),
+        )
+        .into()
+    }
+
+    fn convert_dispatch(
+        &mut self,
+        identifier: String,
+        returns: bool,
+        if_statement: &mut IfStatement,
+    ) -> Option<Statement> {
+        let captures = if_statement
+            .mutate_branches()
+            .iter_mut()
+            .any(|branch| captures_locals(branch.mutate_block(), &self.identifier_tracker));
+
+        // a table of closures capturing locals would be created at each call or iteration
+        if captures && self.is_in_function_or_loop() {
+            return None;
+        }
+
+        let mut table = TableExpression::default();
+
+        for branch in if_statement.mutate_branches() {
+            let (_, key) =
+                match_condition(branch.get_condition()).expect("condition should be valid");
+            let function = FunctionExpression::from_block(branch.take_block());
+            table.mutate_entries().push(key.into_table_entry(function));
+        }
+
+        let lookup: Prefix = if captures {
+            ParentheseExpression::new(table).into()
+        } else {
+            // the table is moved out of the visited block, so its functions are visited here
+            let mut table = Expression::from(table);
+            ScopeVisitor::visit_expression(&mut table, self);
+
+            let lookup_identifier = self.generate_lookup_identifier();
+            self.lookup_tables.push(
+                LocalAssignStatement::from_variable(lookup_identifier.as_str()).with_value(table),
+            );
+            Prefix::from_name(lookup_identifier)
+        };
+
+        let branch_identifier = self.identifier_tracker.generate_named_identifier("BRANCH");
+
+        let call = FunctionCall::from_name(branch_identifier.as_str());
+        let dispatch_block = if returns {
+            Block::default().with_last_statement(ReturnStatement::one(call))
+        } else {
+            Block::default().with_statement(call)
+        };
+
+        let mut dispatch =
+            IfStatement::create(Expression::identifier(&branch_identifier), dispatch_block);
+        if let Some(else_block) = if_statement.take_else_block() {
+            dispatch.set_else_block(else_block);
+        }
+
+        Some(
+            DoStatement::new(
+                Block::default()
+                    .with_statement(
+                        LocalAssignStatement::from_variable(branch_identifier).with_value(
+                            IndexExpression::new(lookup, Expression::identifier(identifier)),
+                        ),
+                    )
+                    .with_statement(dispatch),
+            )
+            .into(),
+        )
+    }
+}
+
+fn take_assigned_value(block: &mut Block, variable: &mut Option<Variable>) -> Expression {
+    match block.take_statements().pop() {
+        Some(Statement::Assign(mut assign)) => {
+            if variable.is_none() {
+                *variable = assign.mutate_variables().pop();
+            }
+            assign
+                .iter_mut_values()
+                .next()
+                .map(|value| mem::replace(value, Expression::nil()))
+                .expect("assignment should have a value")
+        }
+        _ => unreachable!("block should contain an assignment"),
+    }
+}
+
+impl Scope for Processor {
+    fn push(&mut self) {
+        self.scope_depth += 1;
+        self.identifier_tracker.push();
+    }
+
+    fn pop(&mut self) {
+        self.identifier_tracker.pop();
+        self.scope_depth -= 1;
+
+        if self.function_or_loop_depths.last() == Some(&self.scope_depth) {
+            self.function_or_loop_depths.pop();
+        }
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.identifier_tracker.insert(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.identifier_tracker.insert_self();
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>) {
+        self.identifier_tracker.insert_local(identifier, value);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.identifier_tracker.insert_local_function(function);
+    }
+}
+
+impl NodeProcessor for Processor {
+    fn process_statement(&mut self, statement: &mut Statement) {
+        if let Statement::If(if_statement) = statement {
+            let new_statement = match self.analyze(if_statement) {
+                Some((identifier, Conversion::Assignment)) => {
+                    self.convert_assignment(identifier, if_statement)
+                }
+                Some((identifier, Conversion::Dispatch { returns })) => {
+                    match self.convert_dispatch(identifier, returns, if_statement) {
+                        Some(statement) => statement,
+                        None => return,
+                    }
+                }
+                None => return,
+            };
+            *statement = new_statement;
+        }
+    }
+
+    fn process_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.enter_function_or_loop();
+    }
+
+    fn process_function_statement(&mut self, _: &mut FunctionStatement) {
+        self.enter_function_or_loop();
+    }
+
+    fn process_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.enter_function_or_loop();
+    }
+
+    fn process_generic_for_statement(&mut self, _: &mut GenericForStatement) {
+        self.enter_function_or_loop();
+    }
+
+    fn process_numeric_for_statement(&mut self, _: &mut NumericForStatement) {
+        self.enter_function_or_loop();
+    }
+
+    fn process_repeat_statement(&mut self, _: &mut RepeatStatement) {
+        self.enter_function_or_loop();
+    }
+
+    fn process_while_statement(&mut self, _: &mut WhileStatement) {
+        self.enter_function_or_loop();
+    }
+}
+
+pub const CONVERT_IF_TO_LOOKUP_RULE_NAME: &str = "convert_if_to_lookup";
+
+const DEFAULT_MIN_BRANCHES: usize = 5;
+
+/// A rule that converts if statements comparing a variable with literal values into a
+/// table lookup.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConvertIfToLookup {
+    min_branches: usize,
+}
+
+impl Default for ConvertIfToLookup {
+    fn default() -> Self {
+        Self {
+            min_branches: DEFAULT_MIN_BRANCHES,
+        }
+    }
+}
+
+impl ConvertIfToLookup {
+    pub fn with_min_branches(mut self, min_branches: usize) -> Self {
+        self.min_branches = min_branches;
+        self
+    }
+}
+
+impl FlawlessRule for ConvertIfToLookup {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor = Processor::new(self.min_branches, context.identifier_tracker());
+        ScopeVisitor::visit_block(block, &mut processor);
+
+        for (index, lookup_table) in processor.lookup_tables.into_iter().enumerate() {
+            block.insert_statement(index, lookup_table);
+        }
+    }
+}
+
+impl RuleConfiguration for ConvertIfToLookup {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "min_branches" => {
+                    self.min_branches = value.expect_usize(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        CONVERT_IF_TO_LOOKUP_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["min_branches"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Usize
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.min_branches != DEFAULT_MIN_BRANCHES {
+            properties.insert("min_branches".to_owned(), self.min_branches.into());
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> ConvertIfToLookup {
+        ConvertIfToLookup::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_convert_if_to_lookup", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_min_branches() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_min_branches(3));
+
+        assert_json_snapshot!("convert_if_to_lookup_with_min_branches", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'convert_if_to_lookup',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -2,22 +2,31 @@ use crate::nodes::{
     Block, Expression, FieldExpression, Identifier, IndexExpression, Prefix, TableEntry,
     TableExpression, TableFieldEntry, Variable,
 };
-use crate::process::utils::is_valid_identifier;
-use crate::process::{DefaultVisitor, Evaluator, LuaValue, NodeProcessor, NodeVisitor};
+use crate::process::{
+    is_valid_identifier, DefaultVisitor, EnvironmentTarget, Evaluator, LuaValue, NodeProcessor,
+    NodeVisitor,
+};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
+    RulePropertyKind,
 };
 
-use super::verify_no_rule_properties;
-
 use std::mem;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 struct Converter {
     evaluator: Evaluator,
+    target: Option<EnvironmentTarget>,
 }
 
 impl Converter {
+    fn new(target: Option<EnvironmentTarget>) -> Self {
+        Self {
+            evaluator: Evaluator::default(),
+            target,
+        }
+    }
+
     #[inline]
     fn convert_index_to_field(&self, index: &IndexExpression) -> Option<FieldExpression> {
         self.convert_to_field(index.get_index())
@@ -26,7 +35,7 @@ impl Converter {
 
     fn convert_to_field(&self, key_expression: &Expression) -> Option<String> {
         if let LuaValue::String(string) = self.evaluator.evaluate(key_expression) {
-            if is_valid_identifier(&string) {
+            if is_valid_identifier(&string, self.target) {
                 return Some(string);
             }
         }
@@ -89,18 +98,41 @@ pub const CONVERT_INDEX_TO_FIELD_RULE_NAME: &str = "convert_index_to_field";
 
 /// A rule that converts index expression into field expression.
 #[derive(Debug, Default, PartialEq, Eq)]
-pub struct ConvertIndexToField {}
+pub struct ConvertIndexToField {
+    conservative_ascii: bool,
+}
+
+impl ConvertIndexToField {
+    /// When enabled, only converts keys that are valid identifiers for every target,
+    /// regardless of the configured target.
+    pub fn with_conservative_ascii(mut self, conservative_ascii: bool) -> Self {
+        self.conservative_ascii = conservative_ascii;
+        self
+    }
+}
 
 impl FlawlessRule for ConvertIndexToField {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
-        let mut processor = Converter::default();
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let target = if self.conservative_ascii {
+            None
+        } else {
+            context.environment().target()
+        };
+        let mut processor = Converter::new(target);
         DefaultVisitor::visit_block(block, &mut processor);
     }
 }
 
 impl RuleConfiguration for ConvertIndexToField {
     fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
-        verify_no_rule_properties(&properties)?;
+        for (key, value) in properties {
+            match key.as_str() {
+                "conservative_ascii" => {
+                    self.conservative_ascii = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
 
         Ok(())
     }
@@ -109,8 +141,30 @@ impl RuleConfiguration for ConvertIndexToField {
         CONVERT_INDEX_TO_FIELD_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new("local value = object[\"name\"]")]
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["conservative_ascii"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Boolean
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
-        RuleProperties::new()
+        let mut properties = RuleProperties::new();
+
+        if self.conservative_ascii {
+            properties.insert("conservative_ascii".to_owned(), true.into());
+        }
+
+        properties
+    }
+
+    fn is_statement_local(&self) -> bool {
+        true
     }
 }
 
@@ -132,6 +186,13 @@ mod test {
         assert_json_snapshot!("default_convert_index_to_field", rule);
     }
 
+    #[test]
+    fn serialize_rule_with_conservative_ascii() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_conservative_ascii(true));
+
+        assert_json_snapshot!("convert_index_to_field_with_conservative_ascii", rule);
+    }
+
     #[test]
     fn configure_with_extra_field_error() {
         let result = json5::from_str::<Box<dyn Rule>>(
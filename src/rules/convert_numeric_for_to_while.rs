@@ -0,0 +1,285 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use crate::nodes::{
+    AssignStatement, BinaryExpression, BinaryOperator, Block, DoStatement, Expression,
+    LocalAssignStatement, NumericForStatement, Statement, Variable, WhileStatement,
+};
+use crate::process::{
+    Evaluator, IdentifierTracker, LuaValue, NodeProcessor, NodeVisitor, ScopeVisitor,
+};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind,
+};
+
+use super::LoopExits;
+
+struct Processor {
+    identifier_tracker: IdentifierTracker,
+    evaluator: Evaluator,
+    preserve_break: bool,
+}
+
+impl Processor {
+    fn new(preserve_break: bool, identifier_tracker: IdentifierTracker) -> Self {
+        Self {
+            identifier_tracker,
+            evaluator: Evaluator::default(),
+            preserve_break,
+        }
+    }
+
+    fn evaluate_constant_number(&self, expression: &Expression) -> Option<f64> {
+        if self.evaluator.has_side_effects(expression) {
+            return None;
+        }
+        match self.evaluator.evaluate(expression) {
+            LuaValue::Number(value) if value.is_finite() => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns an expression that can be evaluated multiple times to get the value of the
+    /// given expression. If the expression is not a constant number, a new local variable
+    /// is declared to hold the value.
+    fn reusable_expression(
+        &mut self,
+        expression: Expression,
+        constant: Option<f64>,
+        name: &str,
+        declaration: &mut LocalAssignStatement,
+    ) -> Expression {
+        match constant {
+            Some(value) => Expression::from(value),
+            None => {
+                let identifier = self.generate_named_identifier(name);
+                declaration.push_variable(identifier.clone());
+                declaration.push_value(expression);
+                Expression::identifier(identifier)
+            }
+        }
+    }
+
+    fn convert(&mut self, numeric_for: &mut NumericForStatement) -> Option<Statement> {
+        let exits = LoopExits::find(numeric_for.get_block());
+
+        // a `continue` statement would skip the increment of the loop variable
+        if exits.has_continue || (exits.has_break && !self.preserve_break) {
+            return None;
+        }
+
+        let step_value = match numeric_for.get_step() {
+            Some(step) => self.evaluate_constant_number(step),
+            None => Some(1.0),
+        };
+        let end_value = self.evaluate_constant_number(numeric_for.get_end());
+
+        let counter = self.generate_named_identifier("FOR_INDEX");
+        let mut declaration = LocalAssignStatement::from_variable(counter.clone())
+            .with_value(mem::replace(numeric_for.mutate_start(), Expression::nil()));
+
+        let end = self.reusable_expression(
+            mem::replace(numeric_for.mutate_end(), Expression::nil()),
+            end_value,
+            "FOR_LIMIT",
+            &mut declaration,
+        );
+        let step = self.reusable_expression(
+            numeric_for
+                .mutate_step()
+                .take()
+                .unwrap_or_else(|| 1.0.into()),
+            step_value,
+            "FOR_STEP",
+            &mut declaration,
+        );
+
+        let ascending_condition = BinaryExpression::new(
+            BinaryOperator::LowerOrEqualThan,
+            Expression::identifier(&counter),
+            end.clone(),
+        );
+        let descending_condition = BinaryExpression::new(
+            BinaryOperator::GreaterOrEqualThan,
+            Expression::identifier(&counter),
+            end,
+        );
+
+        let condition: Expression = match step_value {
+            Some(value) if value > 0.0 => ascending_condition.into(),
+            Some(value) if value < 0.0 => descending_condition.into(),
+            _ => BinaryExpression::new(
+                BinaryOperator::Or,
+                BinaryExpression::new(
+                    BinaryOperator::And,
+                    BinaryExpression::new(BinaryOperator::GreaterThan, step.clone(), 0.0),
+                    ascending_condition,
+                ),
+                BinaryExpression::new(
+                    BinaryOperator::And,
+                    BinaryExpression::new(BinaryOperator::LowerOrEqualThan, step.clone(), 0.0),
+                    descending_condition,
+                ),
+            )
+            .into(),
+        };
+
+        let mut block = mem::take(numeric_for.mutate_block());
+
+        block.insert_statement(
+            0,
+            LocalAssignStatement::from_variable(numeric_for.get_identifier().clone())
+                .with_value(Expression::identifier(&counter)),
+        );
+
+        // when the block ends with a `break` or a `return`, the next iteration is never reached
+        if block.get_last_statement().is_none() {
+            block.push_statement(AssignStatement::from_variable(
+                Variable::new(&counter),
+                BinaryExpression::new(BinaryOperator::Plus, Expression::identifier(&counter), step),
+            ));
+        }
+
+        Some(
+            DoStatement::new(
+                Block::default()
+                    .with_statement(declaration)
+                    .with_statement(WhileStatement::new(block, condition)),
+            )
+            .into(),
+        )
+    }
+}
+
+impl Deref for Processor {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl DerefMut for Processor {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for Processor {
+    fn process_statement(&mut self, statement: &mut Statement) {
+        if let Statement::NumericFor(numeric_for) = statement {
+            if let Some(new_statement) = self.convert(numeric_for) {
+                *statement = new_statement;
+            }
+        }
+    }
+}
+
+pub const CONVERT_NUMERIC_FOR_TO_WHILE_RULE_NAME: &str = "convert_numeric_for_to_while";
+
+/// A rule that converts numeric for loops into while loops.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConvertNumericForToWhile {
+    preserve_break: bool,
+}
+
+impl Default for ConvertNumericForToWhile {
+    fn default() -> Self {
+        Self {
+            preserve_break: true,
+        }
+    }
+}
+
+impl ConvertNumericForToWhile {
+    pub fn with_preserve_break(mut self, preserve_break: bool) -> Self {
+        self.preserve_break = preserve_break;
+        self
+    }
+}
+
+impl FlawlessRule for ConvertNumericForToWhile {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor = Processor::new(self.preserve_break, context.identifier_tracker());
+        ScopeVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for ConvertNumericForToWhile {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "preserve_break" => {
+                    self.preserve_break = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        CONVERT_NUMERIC_FOR_TO_WHILE_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["preserve_break"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Boolean
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if !self.preserve_break {
+            properties.insert("preserve_break".to_owned(), false.into());
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> ConvertNumericForToWhile {
+        ConvertNumericForToWhile::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_convert_numeric_for_to_while", rule);
+    }
+
+    #[test]
+    fn serialize_rule_without_preserve_break() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_preserve_break(false));
+
+        assert_json_snapshot!("convert_numeric_for_to_while_without_preserve_break", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'convert_numeric_for_to_while',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -32,6 +32,14 @@ impl InstancePath {
             .push(InstancePathComponent::Child(child_name.into()));
     }
 
+    pub(crate) fn root(&self) -> &InstancePathRoot {
+        &self.root
+    }
+
+    pub(crate) fn components(&self) -> &[InstancePathComponent] {
+        &self.components
+    }
+
     pub(crate) fn convert(&self, index_style: &RobloxIndexStyle) -> Prefix {
         let mut components_iter = self.components.iter();
 
@@ -1,29 +1,41 @@
 mod instance_path;
+mod require_style;
 mod roblox_index_style;
 mod roblox_require_mode;
 mod rojo_sourcemap;
 
+#[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
 
 use crate::frontend::DarkluaResult;
-use crate::nodes::{Arguments, Block, FunctionCall};
+use crate::nodes::{Arguments, Block, FunctionCall, Prefix};
 use crate::process::{DefaultVisitor, IdentifierTracker, NodeProcessor, NodeVisitor};
 use crate::rules::require::{is_require_call, PathRequireMode};
-use crate::rules::{Context, RuleConfiguration, RuleConfigurationError, RuleProperties};
-
-use instance_path::InstancePath;
+use crate::rules::{
+    CompatibilityNote, Context, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind, RulePropertyValue,
+};
+use crate::utils::log;
+
+pub(crate) use instance_path::{InstancePath, InstancePathComponent, InstancePathRoot};
+use require_style::{RequireStyle, StyleMatch};
 pub use roblox_index_style::RobloxIndexStyle;
 pub use roblox_require_mode::RobloxRequireMode;
 
 use super::{verify_required_properties, Rule, RuleProcessResult};
 
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "snake_case", tag = "name")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde-config",
+    serde(deny_unknown_fields, rename_all = "snake_case", tag = "name")
+)]
 pub enum RequireMode {
     Path(PathRequireMode),
     Roblox(RobloxRequireMode),
@@ -144,6 +156,204 @@ impl NodeProcessor for RequireConverter<'_> {
     }
 }
 
+/// Finds the require calls that the rule would leave unchanged or fail to convert.
+struct RequireChecker<'a> {
+    converter: RequireConverter<'a>,
+    notes: Vec<CompatibilityNote>,
+}
+
+impl<'a> RequireChecker<'a> {
+    fn new(converter: RequireConverter<'a>) -> Self {
+        Self {
+            converter,
+            notes: Vec::new(),
+        }
+    }
+
+    fn check_call(&self, call: &FunctionCall) -> Option<CompatibilityNote> {
+        let converter = &self.converter;
+
+        match converter.current.find_require(call, converter.context) {
+            Ok(Some(require_path)) => {
+                match converter.target.generate_require(
+                    &require_path,
+                    &converter.current,
+                    converter.context,
+                ) {
+                    Ok(Some(_)) => None,
+                    Ok(None) => Some(CompatibilityNote::conservative(format!(
+                        "require call to `{}` cannot be generated and is left unchanged",
+                        require_path.display()
+                    ))),
+                    Err(err) => Some(CompatibilityNote::risky(format!(
+                        "unable to convert require call: {}",
+                        err
+                    ))),
+                }
+            }
+            Ok(None) => Some(CompatibilityNote::conservative(
+                "require call cannot be resolved with the current require mode and is left unchanged",
+            )),
+            Err(err) => Some(CompatibilityNote::risky(format!(
+                "unable to convert require call: {}",
+                err
+            ))),
+        }
+    }
+}
+
+impl Deref for RequireChecker<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.converter.identifier_tracker
+    }
+}
+
+impl DerefMut for RequireChecker<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.converter.identifier_tracker
+    }
+}
+
+impl NodeProcessor for RequireChecker<'_> {
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if is_require_call(call, self) {
+            if let Some(note) = self.check_call(call) {
+                let line = match call.get_prefix() {
+                    Prefix::Identifier(identifier) => identifier
+                        .get_token()
+                        .and_then(|token| token.get_line_number()),
+                    _ => None,
+                };
+                self.notes.push(note.with_line(line));
+            }
+        }
+    }
+}
+
+/// Converts the arguments of require calls from a require style to another, without
+/// resolving the required modules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StyleConversion {
+    current: RequireStyle,
+    target: RequireStyle,
+    /// The arguments written in the current style, associated with the arguments written in
+    /// the target style that replace them.
+    module_map: BTreeMap<String, String>,
+}
+
+impl StyleConversion {
+    fn new(
+        current: RequireStyle,
+        target: RequireStyle,
+        module_map: BTreeMap<String, String>,
+    ) -> Result<Self, RuleConfigurationError> {
+        let module_map = module_map
+            .into_iter()
+            .map(|(current_argument, target_argument)| {
+                Ok((
+                    current.normalize_written_argument(&current_argument)?,
+                    target
+                        .normalize_written_argument(&target_argument)
+                        .map(|_| target_argument)?,
+                ))
+            })
+            .collect::<Result<_, String>>()
+            .map_err(|message| RuleConfigurationError::UnexpectedValue {
+                property: "module_map".to_owned(),
+                message,
+            })?;
+
+        Ok(Self {
+            current,
+            target,
+            module_map,
+        })
+    }
+
+    /// Returns the new arguments of the require call, or a diagnostic when the call cannot
+    /// be converted.
+    fn convert(&self, call: &FunctionCall) -> Result<Option<Arguments>, String> {
+        if let Some(target_argument) = self
+            .current
+            .written_argument(call)
+            .and_then(|argument| self.module_map.get(&argument))
+        {
+            return Ok(Some(self.target.arguments_from_written(target_argument)));
+        }
+
+        match self.current.read(call) {
+            StyleMatch::Module(module_path) => self
+                .target
+                .write(&module_path)
+                .map(Some)
+                .map_err(|err| format!("unable to convert require call: {}", err)),
+            StyleMatch::Dynamic => Err(
+                "require call with a dynamic argument is left unchanged (only literal arguments can be converted)"
+                    .to_owned(),
+            ),
+            StyleMatch::Unmatched => Ok(None),
+        }
+    }
+}
+
+/// Converts require calls with a [`StyleConversion`], and collects the calls that cannot be
+/// converted.
+struct StyleConverter<'a> {
+    identifier_tracker: IdentifierTracker,
+    conversion: &'a StyleConversion,
+    diagnostics: Vec<(Option<usize>, String)>,
+}
+
+impl<'a> StyleConverter<'a> {
+    fn new(conversion: &'a StyleConversion) -> Self {
+        Self {
+            identifier_tracker: IdentifierTracker::new(),
+            conversion,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl Deref for StyleConverter<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl DerefMut for StyleConverter<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for StyleConverter<'_> {
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if is_require_call(call, self) {
+            match self.conversion.convert(call) {
+                Ok(Some(arguments)) => {
+                    call.set_arguments(arguments);
+                }
+                Ok(None) => {
+                    log::debug!("require call does not match the current require style");
+                }
+                Err(diagnostic) => {
+                    let line = match call.get_prefix() {
+                        Prefix::Identifier(identifier) => identifier
+                            .get_token()
+                            .and_then(|token| token.get_line_number()),
+                        _ => None,
+                    };
+                    self.diagnostics.push((line, diagnostic));
+                }
+            }
+        }
+    }
+}
+
 pub const CONVERT_REQUIRE_RULE_NAME: &str = "convert_require";
 
 /// A rule that converts require calls between environments
@@ -151,6 +361,7 @@ pub const CONVERT_REQUIRE_RULE_NAME: &str = "convert_require";
 pub struct ConvertRequire {
     current: RequireMode,
     target: RequireMode,
+    styles: Option<StyleConversion>,
 }
 
 impl Default for ConvertRequire {
@@ -158,12 +369,29 @@ impl Default for ConvertRequire {
         Self {
             current: RequireMode::Path(Default::default()),
             target: RequireMode::Roblox(Default::default()),
+            styles: None,
         }
     }
 }
 
 impl Rule for ConvertRequire {
     fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
+        if let Some(styles) = &self.styles {
+            let mut converter = StyleConverter::new(styles);
+            DefaultVisitor::visit_block(block, &mut converter);
+
+            for (line, diagnostic) in converter.diagnostics {
+                log::warn!(
+                    "[{}{}] {}: {}",
+                    context.current_path().display(),
+                    line.map(|line| format!(":{}", line)).unwrap_or_default(),
+                    CONVERT_REQUIRE_RULE_NAME,
+                    diagnostic
+                );
+            }
+            return Ok(());
+        }
+
         let mut current_mode = self.current.clone();
         current_mode
             .initialize(context)
@@ -178,24 +406,93 @@ impl Rule for ConvertRequire {
         DefaultVisitor::visit_block(block, &mut processor);
         Ok(())
     }
+
+    fn compatibility_check(&self, block: &Block, context: &Context) -> Vec<CompatibilityNote> {
+        if let Some(styles) = &self.styles {
+            let mut converter = StyleConverter::new(styles);
+            DefaultVisitor::visit_block(&mut block.clone(), &mut converter);
+
+            return converter
+                .diagnostics
+                .into_iter()
+                .map(|(line, diagnostic)| {
+                    CompatibilityNote::conservative(diagnostic).with_line(line)
+                })
+                .collect();
+        }
+
+        let mut current_mode = self.current.clone();
+        let mut target_mode = self.target.clone();
+
+        if let Err(err) = current_mode
+            .initialize(context)
+            .and_then(|()| target_mode.initialize(context))
+        {
+            return vec![CompatibilityNote::risky(err.to_string())];
+        }
+
+        let mut checker =
+            RequireChecker::new(RequireConverter::new(current_mode, target_mode, context));
+        DefaultVisitor::visit_block(&mut block.clone(), &mut checker);
+        checker.notes
+    }
 }
 
 impl RuleConfiguration for ConvertRequire {
     fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
         verify_required_properties(&properties, &["current", "target"])?;
 
+        let mut current_style = None;
+        let mut target_style = None;
+        let mut module_map = None;
+
         for (key, value) in properties {
             match key.as_str() {
-                "current" => {
-                    self.current = value.expect_require_mode(&key)?;
-                }
-                "target" => {
-                    self.target = value.expect_require_mode(&key)?;
+                "current" => match RequireStyle::from_property(&value, &key)? {
+                    Some(style) => current_style = Some(style),
+                    None => self.current = value.expect_require_mode(&key)?,
+                },
+                "target" => match RequireStyle::from_property(&value, &key)? {
+                    Some(style) => target_style = Some(style),
+                    None => self.target = value.expect_require_mode(&key)?,
+                },
+                "module_map" => {
+                    module_map = Some(value.expect_string_map(&key)?);
                 }
                 _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
             }
         }
 
+        self.styles = match (&current_style, target_style) {
+            (Some(current), Some(target)) => Some(StyleConversion::new(
+                current.clone(),
+                target,
+                module_map.unwrap_or_default(),
+            )?),
+            (None, None) => {
+                if module_map.is_some() {
+                    return Err(RuleConfigurationError::UnexpectedValue {
+                        property: "module_map".to_owned(),
+                        message: "a module map can only be used to convert between require styles"
+                            .to_owned(),
+                    });
+                }
+                None
+            }
+            (Some(_), None) | (None, Some(_)) => {
+                let mode_property = if current_style.is_some() {
+                    "target"
+                } else {
+                    "current"
+                };
+                return Err(RuleConfigurationError::UnexpectedValue {
+                    property: mode_property.to_owned(),
+                    message: "require styles (with a `style` field) cannot be converted to or from require modes"
+                        .to_owned(),
+                });
+            }
+        };
+
         Ok(())
     }
 
@@ -203,8 +500,36 @@ impl RuleConfiguration for ConvertRequire {
         CONVERT_REQUIRE_RULE_NAME
     }
 
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["current", "target", "module_map"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "module_map" => RulePropertyKind::StringMap,
+            _ => RulePropertyKind::RequireMode,
+        }
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
-        RuleProperties::new()
+        let mut properties = RuleProperties::new();
+
+        if let Some(styles) = &self.styles {
+            properties.insert("current".to_owned(), styles.current.to_property());
+            properties.insert("target".to_owned(), styles.target.to_property());
+            if !styles.module_map.is_empty() {
+                properties.insert(
+                    "module_map".to_owned(),
+                    RulePropertyValue::from(styles.module_map.clone()),
+                );
+            }
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
     }
 }
 
@@ -253,4 +578,66 @@ mod test {
         );
         pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
     }
+
+    #[test]
+    fn serialize_rule_with_require_styles() {
+        let rule = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'convert_require',
+            current: { style: 'string', prefix: 'Shared/' },
+            target: { style: 'roblox', root: 'game:GetService("ReplicatedStorage").Shared' },
+            module_map: { 'Shared/legacy/OldUtil': 'game.ReplicatedStorage.Common.Util' },
+        }"#,
+        )
+        .unwrap();
+
+        assert_json_snapshot!("convert_require_with_require_styles", rule);
+    }
+
+    #[test]
+    fn configure_require_style_with_require_mode_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'convert_require',
+            current: { style: 'string', prefix: 'Shared/' },
+            target: 'roblox',
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'target': require styles (with a `style` field) cannot be converted to or from require modes"
+        );
+    }
+
+    #[test]
+    fn configure_module_map_with_require_modes_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'convert_require',
+            current: 'path',
+            target: 'roblox',
+            module_map: { './a': 'script.Parent.b' },
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'module_map': a module map can only be used to convert between require styles"
+        );
+    }
+
+    #[test]
+    fn configure_module_map_with_invalid_target_argument_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'convert_require',
+            current: { style: 'string', prefix: 'Shared/' },
+            target: { style: 'roblox' },
+            module_map: { 'Shared/a': 'script.Parent +' },
+        }"#,
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("unexpected value for field 'module_map': "));
+    }
 }
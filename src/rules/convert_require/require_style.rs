@@ -0,0 +1,693 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::generator::{DenseLuaGenerator, LuaGenerator};
+use crate::nodes::{Arguments, Expression, FunctionCall, Prefix, StringExpression};
+use crate::rules::{RuleConfigurationError, RulePropertyValue};
+use crate::Parser;
+
+use super::instance_path::{get_parent_instance, script_identifier};
+use super::RobloxIndexStyle;
+
+const STYLE_KEY: &str = "style";
+const DEFAULT_SEPARATOR: &str = "/";
+const PARENT_FIELD: &str = "Parent";
+
+/// A module path read from the argument of a require call, independently of the style used
+/// to write it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ModulePath {
+    /// The number of parent directories of a relative path (`./` is 0 and `../` is 1), or
+    /// `None` when the path starts from the root of the style (its prefix or its root instance).
+    parents: Option<usize>,
+    components: Vec<String>,
+}
+
+impl fmt::Display for ModulePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.parents {
+            Some(0) => write!(f, "./")?,
+            Some(parents) => write!(f, "{}", "../".repeat(parents))?,
+            None => {}
+        }
+        write!(f, "{}", self.components.join("/"))
+    }
+}
+
+/// The result of reading the argument of a require call with a [`RequireStyle`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum StyleMatch {
+    Module(ModulePath),
+    /// The argument follows the style, but it is computed when the code runs.
+    Dynamic,
+    /// The argument does not follow the style.
+    Unmatched,
+}
+
+/// A convention used to write the argument of require calls, converted by `convert_require`
+/// without resolving the required modules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RequireStyle {
+    /// A string starting with a prefix (like `require("Shared/Util")`).
+    String { prefix: String, separator: String },
+    /// A chain of instances from a root (like `require(ReplicatedStorage.Shared.Util)`), or
+    /// from `script` when no root is defined (like `require(script.Parent.Util)`).
+    Roblox {
+        root: Option<RobloxRoot>,
+        indexing_style: RobloxIndexStyle,
+    },
+    /// A path relative to the current file (like `require("../Util")`).
+    RelativePath { separator: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RobloxRoot {
+    code: String,
+    prefix: Box<Prefix>,
+}
+
+impl RobloxRoot {
+    fn parse(code: &str) -> Result<Self, String> {
+        let expression = Parser::default()
+            .parse_expression(code)
+            .map_err(|err| format!("unable to parse root `{}`: {}", code, err))?;
+
+        match expression {
+            Expression::Call(_)
+            | Expression::Field(_)
+            | Expression::Identifier(_)
+            | Expression::Index(_) => {
+                let prefix = Prefix::from(expression);
+                Ok(Self {
+                    code: generate_prefix(&prefix),
+                    prefix: Box::new(prefix),
+                })
+            }
+            _ => Err(format!(
+                "root `{}` must be a variable, a field or a call (like `game:GetService(\"ReplicatedStorage\")`)",
+                code
+            )),
+        }
+    }
+}
+
+impl RequireStyle {
+    /// Reads a require style from a rule property. Returns `None` when the property is not an
+    /// object with a `style` field (like a require mode).
+    pub(crate) fn from_property(
+        value: &RulePropertyValue,
+        key: &str,
+    ) -> Result<Option<Self>, RuleConfigurationError> {
+        let fields = match value {
+            RulePropertyValue::StringMap(fields) if fields.contains_key(STYLE_KEY) => fields,
+            _ => return Ok(None),
+        };
+
+        Self::from_fields(fields).map(Some).map_err(|message| {
+            RuleConfigurationError::UnexpectedValue {
+                property: key.to_owned(),
+                message,
+            }
+        })
+    }
+
+    fn from_fields(fields: &BTreeMap<String, String>) -> Result<Self, String> {
+        let style_name = fields[STYLE_KEY].as_str();
+
+        let (mut style, allowed_fields): (Self, &[&str]) = match style_name {
+            "string" => (
+                Self::String {
+                    prefix: String::new(),
+                    separator: DEFAULT_SEPARATOR.to_owned(),
+                },
+                &["prefix", "separator"],
+            ),
+            "roblox" => (
+                Self::Roblox {
+                    root: None,
+                    indexing_style: RobloxIndexStyle::Property,
+                },
+                &["root", "indexing_style"],
+            ),
+            "relative-path" => (
+                Self::RelativePath {
+                    separator: DEFAULT_SEPARATOR.to_owned(),
+                },
+                &["separator"],
+            ),
+            _ => {
+                return Err(format!(
+                    "invalid require style `{}` (expected `string`, `roblox` or `relative-path`)",
+                    style_name
+                ))
+            }
+        };
+
+        for (field, value) in fields {
+            if field == STYLE_KEY {
+                continue;
+            }
+            if !allowed_fields.contains(&field.as_str()) {
+                return Err(format!(
+                    "unexpected field `{}` for the `{}` require style",
+                    field, style_name
+                ));
+            }
+
+            match (&mut style, field.as_str()) {
+                (Self::String { prefix, .. }, "prefix") => {
+                    *prefix = value.to_owned();
+                }
+                (
+                    Self::String { separator, .. } | Self::RelativePath { separator },
+                    "separator",
+                ) => {
+                    if value.is_empty() {
+                        return Err("the separator of a require style cannot be empty".to_owned());
+                    }
+                    *separator = value.to_owned();
+                }
+                (Self::Roblox { root, .. }, "root") => {
+                    *root = Some(RobloxRoot::parse(value)?);
+                }
+                (Self::Roblox { indexing_style, .. }, "indexing_style") => {
+                    *indexing_style = value.parse()?;
+                }
+                _ => unreachable!("unexpected field `{}`", field),
+            }
+        }
+
+        Ok(style)
+    }
+
+    pub(crate) fn to_property(&self) -> RulePropertyValue {
+        let mut fields = BTreeMap::new();
+
+        match self {
+            Self::String { prefix, separator } => {
+                fields.insert(STYLE_KEY.to_owned(), "string".to_owned());
+                if !prefix.is_empty() {
+                    fields.insert("prefix".to_owned(), prefix.to_owned());
+                }
+                if separator != DEFAULT_SEPARATOR {
+                    fields.insert("separator".to_owned(), separator.to_owned());
+                }
+            }
+            Self::Roblox {
+                root,
+                indexing_style,
+            } => {
+                fields.insert(STYLE_KEY.to_owned(), "roblox".to_owned());
+                if let Some(root) = root {
+                    fields.insert("root".to_owned(), root.code.to_owned());
+                }
+                let indexing_style = match indexing_style {
+                    RobloxIndexStyle::FindFirstChild => Some("find_first_child"),
+                    RobloxIndexStyle::WaitForChild => Some("wait_for_child"),
+                    RobloxIndexStyle::Property => None,
+                };
+                if let Some(indexing_style) = indexing_style {
+                    fields.insert("indexing_style".to_owned(), indexing_style.to_owned());
+                }
+            }
+            Self::RelativePath { separator } => {
+                fields.insert(STYLE_KEY.to_owned(), "relative-path".to_owned());
+                if separator != DEFAULT_SEPARATOR {
+                    fields.insert("separator".to_owned(), separator.to_owned());
+                }
+            }
+        }
+
+        RulePropertyValue::StringMap(fields)
+    }
+
+    fn is_roblox(&self) -> bool {
+        matches!(self, Self::Roblox { .. })
+    }
+
+    /// Normalizes a require argument written in this style, as written in the keys and the
+    /// values of a module map: the string of the string styles, or the code of the expression
+    /// of the Roblox style.
+    pub(crate) fn normalize_written_argument(&self, argument: &str) -> Result<String, String> {
+        if self.is_roblox() {
+            Parser::default()
+                .parse_expression(argument)
+                .map(|expression| generate_expression(&expression))
+                .map_err(|err| format!("unable to parse `{}`: {}", argument, err))
+        } else {
+            Ok(argument.to_owned())
+        }
+    }
+
+    /// Returns the argument of the call written like the keys of a module map.
+    pub(crate) fn written_argument(&self, call: &FunctionCall) -> Option<String> {
+        let argument = single_argument(call)?;
+
+        if self.is_roblox() {
+            Some(generate_expression(&argument))
+        } else {
+            match argument {
+                Expression::String(string) => Some(string.get_value().to_owned()),
+                _ => None,
+            }
+        }
+    }
+
+    /// Creates the arguments of a require call from an argument written like the values of a
+    /// module map.
+    pub(crate) fn arguments_from_written(&self, argument: &str) -> Arguments {
+        let argument = if self.is_roblox() {
+            Parser::default()
+                .parse_expression(argument)
+                .expect("module map values should be verified when configuring the rule")
+        } else {
+            StringExpression::from_value(argument).into()
+        };
+
+        Arguments::default().with_argument(argument)
+    }
+
+    pub(crate) fn read(&self, call: &FunctionCall) -> StyleMatch {
+        let Some(argument) = single_argument(call) else {
+            return StyleMatch::Unmatched;
+        };
+
+        match self {
+            Self::String { prefix, separator } => match argument {
+                Expression::String(string) => string
+                    .get_value()
+                    .strip_prefix(prefix.as_str())
+                    .and_then(|path| split_components(path, separator))
+                    .map(|components| {
+                        StyleMatch::Module(ModulePath {
+                            parents: None,
+                            components,
+                        })
+                    })
+                    .unwrap_or(StyleMatch::Unmatched),
+                argument => read_dynamic_string(&argument),
+            },
+            Self::RelativePath { separator } => match argument {
+                Expression::String(string) => read_relative_path(string.get_value(), separator)
+                    .map(StyleMatch::Module)
+                    .unwrap_or(StyleMatch::Unmatched),
+                argument => read_dynamic_string(&argument),
+            },
+            Self::Roblox { root, .. } => read_instance_path(Prefix::from(argument), root.as_ref()),
+        }
+    }
+
+    pub(crate) fn write(&self, path: &ModulePath) -> Result<Arguments, String> {
+        let argument: Expression = match self {
+            Self::String { prefix, separator } => {
+                if path.parents.is_some() {
+                    return Err(format!(
+                        "the relative path `{}` cannot be written with the `string` style",
+                        path
+                    ));
+                }
+                verify_components_separator(path, separator)?;
+                StringExpression::from_value(format!(
+                    "{}{}",
+                    prefix,
+                    path.components.join(separator)
+                ))
+                .into()
+            }
+            Self::RelativePath { separator } => {
+                let Some(parents) = path.parents else {
+                    return Err(format!(
+                        "the path `{}` is not relative and cannot be written with the `relative-path` style",
+                        path
+                    ));
+                };
+                verify_components_separator(path, separator)?;
+                let start = if parents == 0 {
+                    format!(".{}", separator)
+                } else {
+                    format!("..{}", separator).repeat(parents)
+                };
+                StringExpression::from_value(format!(
+                    "{}{}",
+                    start,
+                    path.components.join(separator)
+                ))
+                .into()
+            }
+            Self::Roblox {
+                root,
+                indexing_style,
+            } => {
+                let mut prefix = match (root, path.parents) {
+                    (Some(root), None) => (*root.prefix).clone(),
+                    (None, Some(parents)) => {
+                        if path.components.iter().any(|name| name == PARENT_FIELD) {
+                            return Err(format!(
+                                "the path `{}` contains an instance named `{}`, which cannot be written relative to `script`",
+                                path, PARENT_FIELD
+                            ));
+                        }
+                        (0..=parents).fold(script_identifier().into(), |instance, _| {
+                            get_parent_instance(instance)
+                        })
+                    }
+                    (Some(_), Some(_)) => {
+                        return Err(format!(
+                            "the relative path `{}` cannot be written from the root of the `roblox` style",
+                            path
+                        ))
+                    }
+                    (None, None) => {
+                        return Err(format!(
+                            "the path `{}` is not relative and the `roblox` style has no `root`",
+                            path
+                        ))
+                    }
+                };
+
+                for name in path.components.iter() {
+                    prefix = indexing_style.index(prefix, name);
+                }
+
+                prefix.into()
+            }
+        };
+
+        Ok(Arguments::default().with_argument(argument))
+    }
+}
+
+fn single_argument(call: &FunctionCall) -> Option<Expression> {
+    let arguments = call.get_arguments();
+
+    if arguments.len() != 1 {
+        return None;
+    }
+
+    arguments
+        .iter_expressions()
+        .next()
+        .map(|argument| argument.into_owned())
+}
+
+/// A string built when the code runs (like `"Shared/" .. name`) cannot be converted.
+fn read_dynamic_string(argument: &Expression) -> StyleMatch {
+    match argument {
+        Expression::Binary(_) | Expression::InterpolatedString(_) => StyleMatch::Dynamic,
+        _ => StyleMatch::Unmatched,
+    }
+}
+
+fn split_components(path: &str, separator: &str) -> Option<Vec<String>> {
+    let components: Vec<_> = path.split(separator).map(str::to_owned).collect();
+
+    components
+        .iter()
+        .all(|component| !component.is_empty() && component != "." && component != "..")
+        .then_some(components)
+}
+
+fn read_relative_path(path: &str, separator: &str) -> Option<ModulePath> {
+    let (parents, rest) = if let Some(rest) = path.strip_prefix(&format!(".{}", separator)) {
+        (0, rest)
+    } else {
+        let parent = format!("..{}", separator);
+        let mut parents = 0;
+        let mut rest = path;
+        while let Some(next) = rest.strip_prefix(&parent) {
+            parents += 1;
+            rest = next;
+        }
+        (parents, rest)
+    };
+
+    if parents == 0 && rest.len() == path.len() {
+        return None;
+    }
+
+    split_components(rest, separator).map(|components| ModulePath {
+        parents: Some(parents),
+        components,
+    })
+}
+
+fn verify_components_separator(path: &ModulePath, separator: &str) -> Result<(), String> {
+    match path
+        .components
+        .iter()
+        .find(|component| component.contains(separator))
+    {
+        Some(component) => Err(format!(
+            "the name `{}` contains the separator `{}`",
+            component, separator
+        )),
+        None => Ok(()),
+    }
+}
+
+enum InstanceStep<'a> {
+    Child(&'a Prefix, String),
+    /// A child indexed with a name computed when the code runs.
+    DynamicChild(&'a Prefix),
+    End,
+}
+
+fn next_instance_step(prefix: &Prefix) -> InstanceStep<'_> {
+    match prefix {
+        Prefix::Field(field) => {
+            InstanceStep::Child(field.get_prefix(), field.get_field().get_name().to_owned())
+        }
+        Prefix::Index(index) => match index.get_index() {
+            Expression::String(name) => {
+                InstanceStep::Child(index.get_prefix(), name.get_value().to_owned())
+            }
+            _ => InstanceStep::DynamicChild(index.get_prefix()),
+        },
+        Prefix::Call(call)
+            if call.get_method().is_some_and(|method| {
+                matches!(method.get_name(), "FindFirstChild" | "WaitForChild")
+            }) && call.get_arguments().len() == 1 =>
+        {
+            match single_argument(call) {
+                Some(Expression::String(name)) => {
+                    InstanceStep::Child(call.get_prefix(), name.get_value().to_owned())
+                }
+                _ => InstanceStep::DynamicChild(call.get_prefix()),
+            }
+        }
+        _ => InstanceStep::End,
+    }
+}
+
+fn is_script_identifier(prefix: &Prefix) -> bool {
+    matches!(prefix, Prefix::Identifier(identifier) if identifier.get_name() == "script")
+}
+
+fn read_instance_path(argument: Prefix, root: Option<&RobloxRoot>) -> StyleMatch {
+    let mut components = Vec::new();
+    let mut dynamic = false;
+    let mut current = &argument;
+
+    loop {
+        let reached_root = match root {
+            Some(root) => generate_prefix(current) == root.code,
+            None => is_script_identifier(current),
+        };
+        if reached_root {
+            break;
+        }
+
+        match next_instance_step(current) {
+            InstanceStep::Child(parent, name) => {
+                components.push(name);
+                current = parent;
+            }
+            InstanceStep::DynamicChild(parent) => {
+                dynamic = true;
+                current = parent;
+            }
+            InstanceStep::End => return StyleMatch::Unmatched,
+        }
+    }
+
+    if dynamic {
+        return StyleMatch::Dynamic;
+    }
+
+    components.reverse();
+
+    let parents = if root.is_none() {
+        // `script.Parent` is the folder of the current file
+        let parents = components
+            .iter()
+            .take_while(|name| *name == PARENT_FIELD)
+            .count();
+        if parents == 0 {
+            return StyleMatch::Unmatched;
+        }
+        components.drain(..parents);
+        if components.iter().any(|name| name == PARENT_FIELD) {
+            return StyleMatch::Unmatched;
+        }
+        Some(parents - 1)
+    } else {
+        None
+    };
+
+    if components.is_empty() {
+        return StyleMatch::Unmatched;
+    }
+
+    StyleMatch::Module(ModulePath {
+        parents,
+        components,
+    })
+}
+
+fn generate_expression(expression: &Expression) -> String {
+    let mut generator = DenseLuaGenerator::new(usize::MAX);
+    generator.write_expression(expression);
+    generator.into_string()
+}
+
+fn generate_prefix(prefix: &Prefix) -> String {
+    generate_expression(&prefix.clone().into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn style(code: &str) -> RequireStyle {
+        let fields: BTreeMap<String, String> = json5::from_str(code).unwrap();
+        RequireStyle::from_fields(&fields).unwrap()
+    }
+
+    fn read(style: &RequireStyle, argument: &str) -> StyleMatch {
+        let call = match Parser::default()
+            .parse_expression(&format!("require({})", argument))
+            .unwrap()
+        {
+            Expression::Call(call) => *call,
+            _ => unreachable!(),
+        };
+        style.read(&call)
+    }
+
+    fn module(parents: Option<usize>, components: &[&str]) -> StyleMatch {
+        StyleMatch::Module(ModulePath {
+            parents,
+            components: components.iter().map(ToString::to_string).collect(),
+        })
+    }
+
+    #[test]
+    fn read_string_with_prefix() {
+        let style = style("{ style: 'string', prefix: 'Shared/' }");
+
+        assert_eq!(
+            read(&style, "'Shared/Util/Signal'"),
+            module(None, &["Util", "Signal"])
+        );
+        assert_eq!(read(&style, "'Client/Util'"), StyleMatch::Unmatched);
+        assert_eq!(read(&style, "'Shared/'"), StyleMatch::Unmatched);
+        assert_eq!(read(&style, "'Shared/a//b'"), StyleMatch::Unmatched);
+        assert_eq!(read(&style, "'Shared/' .. name"), StyleMatch::Dynamic);
+        assert_eq!(read(&style, "script.Util"), StyleMatch::Unmatched);
+    }
+
+    #[test]
+    fn read_relative_path() {
+        let style = style("{ style: 'relative-path' }");
+
+        assert_eq!(read(&style, "'./Util'"), module(Some(0), &["Util"]));
+        assert_eq!(read(&style, "'../../a/b'"), module(Some(2), &["a", "b"]));
+        assert_eq!(read(&style, "'Util'"), StyleMatch::Unmatched);
+        assert_eq!(read(&style, "'./../Util'"), StyleMatch::Unmatched);
+    }
+
+    #[test]
+    fn read_roblox_from_root() {
+        let style =
+            style("{ style: 'roblox', root: 'game:GetService(\"ReplicatedStorage\").Shared' }");
+
+        assert_eq!(
+            read(
+                &style,
+                "game:GetService('ReplicatedStorage').Shared.Util:WaitForChild('Signal')"
+            ),
+            module(None, &["Util", "Signal"])
+        );
+        assert_eq!(
+            read(
+                &style,
+                "game:GetService('ReplicatedStorage').Shared['a module']"
+            ),
+            module(None, &["a module"])
+        );
+        assert_eq!(
+            read(
+                &style,
+                "game:GetService('ReplicatedStorage').Shared[name].Util"
+            ),
+            StyleMatch::Dynamic
+        );
+        assert_eq!(
+            read(&style, "game:GetService('ServerStorage').Shared.Util"),
+            StyleMatch::Unmatched
+        );
+        assert_eq!(read(&style, "'Shared/Util'"), StyleMatch::Unmatched);
+    }
+
+    #[test]
+    fn read_roblox_from_script() {
+        let style = style("{ style: 'roblox' }");
+
+        assert_eq!(
+            read(&style, "script.Parent.Util"),
+            module(Some(0), &["Util"])
+        );
+        assert_eq!(
+            read(&style, "script.Parent.Parent.Parent.a.b"),
+            module(Some(2), &["a", "b"])
+        );
+        assert_eq!(read(&style, "script.Util"), StyleMatch::Unmatched);
+        assert_eq!(read(&style, "script.Parent"), StyleMatch::Unmatched);
+        assert_eq!(
+            read(&style, "script.Parent.a.Parent"),
+            StyleMatch::Unmatched
+        );
+    }
+
+    #[test]
+    fn unknown_style_errors() {
+        let fields: BTreeMap<String, String> = json5::from_str("{ style: 'path' }").unwrap();
+
+        assert_eq!(
+            RequireStyle::from_fields(&fields).unwrap_err(),
+            "invalid require style `path` (expected `string`, `roblox` or `relative-path`)"
+        );
+    }
+
+    #[test]
+    fn unexpected_field_errors() {
+        let fields: BTreeMap<String, String> =
+            json5::from_str("{ style: 'string', root: 'game' }").unwrap();
+
+        assert_eq!(
+            RequireStyle::from_fields(&fields).unwrap_err(),
+            "unexpected field `root` for the `string` require style"
+        );
+    }
+
+    #[test]
+    fn invalid_root_errors() {
+        let fields: BTreeMap<String, String> =
+            json5::from_str("{ style: 'roblox', root: '1 + 1' }").unwrap();
+
+        assert_eq!(
+            RequireStyle::from_fields(&fields).unwrap_err(),
+            "root `1 + 1` must be a variable, a field or a call (like `game:GetService(\"ReplicatedStorage\")`)"
+        );
+    }
+}
@@ -1,12 +1,17 @@
+#[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
 
 use crate::nodes::{FieldExpression, FunctionCall, IndexExpression, Prefix, StringExpression};
-use crate::process::utils::is_valid_identifier;
+use crate::process::{is_valid_identifier, EnvironmentTarget};
 
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "snake_case", tag = "name")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde-config",
+    serde(rename_all = "snake_case", tag = "name")
+)]
 pub enum RobloxIndexStyle {
     FindFirstChild,
     WaitForChild,
@@ -38,7 +43,7 @@ impl RobloxIndexStyle {
                 .with_argument(StringExpression::from_value(child_name))
                 .into(),
             RobloxIndexStyle::Property => {
-                if is_valid_identifier(child_name) {
+                if is_valid_identifier(child_name, Some(EnvironmentTarget::Roblox)) {
                     FieldExpression::new(instance, child_name).into()
                 } else {
                     IndexExpression::new(instance, StringExpression::from_value(child_name)).into()
@@ -1,5 +1,7 @@
+#[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
 
+use crate::utils::log;
 use crate::{
     frontend::DarkluaResult,
     nodes::{Arguments, FunctionCall, Prefix},
@@ -14,13 +16,20 @@ use super::{
     RequireMode, RobloxIndexStyle,
 };
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "snake_case")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde-config",
+    serde(deny_unknown_fields, rename_all = "snake_case")
+)]
 pub struct RobloxRequireMode {
     rojo_sourcemap: Option<PathBuf>,
-    #[serde(default, deserialize_with = "crate::utils::string_or_struct")]
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, deserialize_with = "crate::utils::string_or_struct")
+    )]
     indexing_style: RobloxIndexStyle,
-    #[serde(skip)]
+    #[cfg_attr(feature = "serde-config", serde(skip))]
     cached_sourcemap: Option<RojoSourcemap>,
 }
 
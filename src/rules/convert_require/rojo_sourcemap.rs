@@ -1,29 +1,39 @@
 use std::path::{Path, PathBuf};
 
+#[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
 
+use crate::utils::log;
 use crate::{utils, DarkluaError};
 
 use super::InstancePath;
 
 type NodeId = usize;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "camelCase"))]
 struct RojoSourcemapNode {
     name: String,
     class_name: String,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
     file_paths: Vec<PathBuf>,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
     children: Vec<RojoSourcemapNode>,
-    #[serde(skip)]
+    #[cfg_attr(feature = "serde-config", serde(skip))]
     id: NodeId,
-    #[serde(skip)]
+    #[cfg_attr(feature = "serde-config", serde(skip))]
     parent_id: NodeId,
 }
 
 impl RojoSourcemapNode {
+    #[cfg_attr(not(feature = "serde-config"), allow(dead_code))]
     fn initialize(mut self, relative_to: &Path) -> Self {
         let mut queue = vec![&mut self];
         let mut index = 0;
@@ -102,6 +112,7 @@ pub(crate) struct RojoSourcemap {
 }
 
 impl RojoSourcemap {
+    #[cfg(feature = "serde-config")]
     pub(crate) fn parse(
         content: &str,
         relative_to: impl AsRef<Path>,
@@ -116,6 +127,16 @@ impl RojoSourcemap {
         })
     }
 
+    #[cfg(not(feature = "serde-config"))]
+    pub(crate) fn parse(
+        _content: &str,
+        _relative_to: impl AsRef<Path>,
+    ) -> Result<Self, DarkluaError> {
+        Err(DarkluaError::custom(
+            "unable to read Rojo sourcemaps without the `serde-config` feature",
+        ))
+    }
+
     pub(crate) fn get_instance_path(
         &self,
         from_file: impl AsRef<Path>,
@@ -0,0 +1,411 @@
+use std::mem;
+
+use crate::nodes::{
+    AssignStatement, BinaryOperator, Block, CompoundAssignStatement, CompoundOperator, Expression,
+    FunctionExpression, FunctionStatement, GenericForStatement, Identifier, LocalAssignStatement,
+    LocalFunctionStatement, NumericForStatement, Statement, Variable, WhileStatement,
+};
+use crate::process::{DefaultVisitor, Evaluator, LuaValue, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind,
+};
+
+use super::LoopExits;
+
+/// Finds references to a variable.
+struct ReferenceFinder<'a> {
+    name: &'a str,
+    found: bool,
+}
+
+impl<'a> ReferenceFinder<'a> {
+    fn new(name: &'a str) -> Self {
+        Self { name, found: false }
+    }
+}
+
+impl NodeProcessor for ReferenceFinder<'_> {
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        if identifier.get_name() == self.name {
+            self.found = true;
+        }
+    }
+}
+
+/// Verifies that the loop variable is only read by the statements of the loop body, so
+/// that it can become the variable of a numeric for loop.
+struct LoopVariableUsage<'a> {
+    name: &'a str,
+    is_valid: bool,
+}
+
+impl<'a> LoopVariableUsage<'a> {
+    fn new(name: &'a str) -> Self {
+        Self {
+            name,
+            is_valid: true,
+        }
+    }
+
+    fn verify_not_declared<'b>(&mut self, mut names: impl Iterator<Item = &'b str>) {
+        if names.any(|name| name == self.name) {
+            self.is_valid = false;
+        }
+    }
+
+    fn verify_not_assigned<'b>(&mut self, mut variables: impl Iterator<Item = &'b Variable>) {
+        if variables.any(|variable| {
+            matches!(variable, Variable::Identifier(identifier) if identifier.get_name() == self.name)
+        }) {
+            self.is_valid = false;
+        }
+    }
+
+    /// Each iteration of a numeric for loop has its own loop variable, so functions that
+    /// capture the variable would not observe the same value.
+    fn verify_not_captured(&mut self, block: &mut Block) {
+        let mut finder = ReferenceFinder::new(self.name);
+        DefaultVisitor::visit_block(block, &mut finder);
+        if finder.found {
+            self.is_valid = false;
+        }
+    }
+}
+
+impl NodeProcessor for LoopVariableUsage<'_> {
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        self.verify_not_assigned(assign.iter_variables());
+    }
+
+    fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
+        self.verify_not_assigned(std::iter::once(assign.get_variable()));
+    }
+
+    fn process_local_assign_statement(&mut self, assign: &mut LocalAssignStatement) {
+        self.verify_not_declared(assign.iter_variables().map(|variable| variable.get_name()));
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        self.verify_not_declared(std::iter::once(function.get_identifier().get_name()));
+        self.verify_not_captured(function.mutate_block());
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name();
+        if name.get_field_names().is_empty() && name.get_method().is_none() {
+            self.verify_not_declared(std::iter::once(name.get_name().get_name()));
+        }
+        self.verify_not_captured(function.mutate_block());
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        self.verify_not_captured(function.mutate_block());
+    }
+
+    fn process_generic_for_statement(&mut self, generic_for: &mut GenericForStatement) {
+        self.verify_not_declared(
+            generic_for
+                .iter_identifiers()
+                .map(|identifier| identifier.get_name()),
+        );
+    }
+
+    fn process_numeric_for_statement(&mut self, numeric_for: &mut NumericForStatement) {
+        self.verify_not_declared(std::iter::once(numeric_for.get_identifier().get_name()));
+    }
+}
+
+struct Processor {
+    evaluator: Evaluator,
+    preserve_break: bool,
+}
+
+impl Processor {
+    fn new(preserve_break: bool) -> Self {
+        Self {
+            evaluator: Evaluator::default(),
+            preserve_break,
+        }
+    }
+
+    fn evaluate_constant_number(&self, expression: &Expression) -> Option<f64> {
+        if self.evaluator.has_side_effects(expression) {
+            return None;
+        }
+        match self.evaluator.evaluate(expression) {
+            LuaValue::Number(value) if value.is_finite() => Some(value),
+            _ => None,
+        }
+    }
+
+    fn is_variable(&self, expression: &Expression, name: &str) -> bool {
+        matches!(expression, Expression::Identifier(identifier) if identifier.get_name() == name)
+    }
+
+    /// Returns the step of a statement that increments the given variable by a constant
+    /// number (like `i = i + 1` or `i -= 2`).
+    fn get_increment_step(&self, statement: &Statement, name: &str) -> Option<f64> {
+        match statement {
+            Statement::Assign(assign) => {
+                if assign.variables_len() != 1 || assign.values_len() != 1 {
+                    return None;
+                }
+                let is_loop_variable = matches!(
+                    assign.get_variables().first(),
+                    Some(Variable::Identifier(identifier)) if identifier.get_name() == name
+                );
+                if !is_loop_variable {
+                    return None;
+                }
+                match assign.last_value()? {
+                    Expression::Binary(binary) if self.is_variable(binary.left(), name) => {
+                        let step = self.evaluate_constant_number(binary.right())?;
+                        match binary.operator() {
+                            BinaryOperator::Plus => Some(step),
+                            BinaryOperator::Minus => Some(-step),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
+            }
+            Statement::CompoundAssign(assign) => {
+                let is_loop_variable = matches!(
+                    assign.get_variable(),
+                    Variable::Identifier(identifier) if identifier.get_name() == name
+                );
+                if !is_loop_variable {
+                    return None;
+                }
+                let step = self.evaluate_constant_number(assign.get_value())?;
+                match assign.get_operator() {
+                    CompoundOperator::Plus => Some(step),
+                    CompoundOperator::Minus => Some(-step),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Attempts to convert a local declaration followed by a while loop into a numeric for
+    /// loop. The statements after the loop are needed to verify that the loop variable is
+    /// not used after the loop.
+    fn convert(
+        &self,
+        declaration: &mut LocalAssignStatement,
+        while_statement: &mut WhileStatement,
+        next_statements: &mut [Statement],
+        block: &mut Block,
+    ) -> Option<NumericForStatement> {
+        if declaration.variables_len() != 1 || declaration.values_len() != 1 {
+            return None;
+        }
+        let name = declaration.get_variables().first()?.get_name().to_owned();
+
+        let (ascending, end) = match while_statement.get_condition() {
+            Expression::Binary(binary) if self.is_variable(binary.left(), &name) => {
+                let ascending = match binary.operator() {
+                    BinaryOperator::LowerOrEqualThan => true,
+                    BinaryOperator::GreaterOrEqualThan => false,
+                    _ => return None,
+                };
+                self.evaluate_constant_number(binary.right())?;
+                (ascending, binary.right().clone())
+            }
+            _ => return None,
+        };
+
+        let loop_block = while_statement.get_block();
+        if loop_block.get_last_statement().is_some() {
+            return None;
+        }
+        let step = self.get_increment_step(loop_block.iter_statements().last()?, &name)?;
+        if (ascending && step <= 0.0) || (!ascending && step >= 0.0) {
+            return None;
+        }
+
+        let exits = LoopExits::find(loop_block);
+        // a `continue` statement skips the increment of a while loop, but not in a numeric for
+        if exits.has_continue || (exits.has_break && !self.preserve_break) {
+            return None;
+        }
+
+        let mut loop_block = while_statement.get_block().clone();
+        loop_block.truncate(loop_block.statements_len() - 1);
+
+        let mut usage = LoopVariableUsage::new(&name);
+        DefaultVisitor::visit_block(&mut loop_block, &mut usage);
+        if !usage.is_valid {
+            return None;
+        }
+
+        let mut finder = ReferenceFinder::new(&name);
+        for statement in next_statements.iter_mut() {
+            DefaultVisitor::visit_statement(statement, &mut finder);
+        }
+        if let Some(last_statement) = block.mutate_last_statement() {
+            DefaultVisitor::visit_last_statement(last_statement, &mut finder);
+        }
+        if finder.found {
+            return None;
+        }
+
+        let identifier = declaration.iter_variables().next()?.clone();
+        let start = declaration.iter_values().next()?.clone();
+
+        Some(NumericForStatement::new(
+            identifier,
+            start,
+            end,
+            (step != 1.0).then(|| Expression::from(step)),
+            loop_block,
+        ))
+    }
+}
+
+impl NodeProcessor for Processor {
+    fn process_block(&mut self, block: &mut Block) {
+        let has_candidate = block
+            .iter_statements()
+            .zip(block.iter_statements().skip(1))
+            .any(|pair| matches!(pair, (Statement::LocalAssign(_), Statement::While(_))));
+
+        if !has_candidate {
+            return;
+        }
+
+        let mut statements = block.take_statements();
+        let mut index = 1;
+
+        while index < statements.len() {
+            let (before, after) = statements.split_at_mut(index);
+            let (current, next_statements) = after.split_first_mut().expect("index is in bounds");
+
+            let numeric_for = match (before.last_mut(), current) {
+                (Some(Statement::LocalAssign(declaration)), Statement::While(while_statement)) => {
+                    self.convert(declaration, while_statement, next_statements, block)
+                }
+                _ => None,
+            };
+
+            if let Some(numeric_for) = numeric_for {
+                statements[index] = numeric_for.into();
+                statements.remove(index - 1);
+            } else {
+                index += 1;
+            }
+        }
+
+        block.set_statements(mem::take(&mut statements));
+    }
+}
+
+pub const CONVERT_WHILE_TO_NUMERIC_FOR_RULE_NAME: &str = "convert_while_to_numeric_for";
+
+/// A rule that converts while loops that count with a local variable into numeric for loops.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConvertWhileToNumericFor {
+    preserve_break: bool,
+}
+
+impl Default for ConvertWhileToNumericFor {
+    fn default() -> Self {
+        Self {
+            preserve_break: true,
+        }
+    }
+}
+
+impl ConvertWhileToNumericFor {
+    pub fn with_preserve_break(mut self, preserve_break: bool) -> Self {
+        self.preserve_break = preserve_break;
+        self
+    }
+}
+
+impl FlawlessRule for ConvertWhileToNumericFor {
+    fn flawless_process(&self, block: &mut Block, _: &Context) {
+        let mut processor = Processor::new(self.preserve_break);
+        DefaultVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for ConvertWhileToNumericFor {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "preserve_break" => {
+                    self.preserve_break = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        CONVERT_WHILE_TO_NUMERIC_FOR_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["preserve_break"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Boolean
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if !self.preserve_break {
+            properties.insert("preserve_break".to_owned(), false.into());
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> ConvertWhileToNumericFor {
+        ConvertWhileToNumericFor::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_convert_while_to_numeric_for", rule);
+    }
+
+    #[test]
+    fn serialize_rule_without_preserve_break() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_preserve_break(false));
+
+        assert_json_snapshot!("convert_while_to_numeric_for_without_preserve_break", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'convert_while_to_numeric_for',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
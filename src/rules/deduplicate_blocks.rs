@@ -0,0 +1,933 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+
+use crate::generator::{DenseLuaGenerator, LuaGenerator};
+use crate::nodes::{
+    Block, Expression, FunctionCall, GenericTypePack, Identifier, LastStatement,
+    LocalFunctionStatement, Statement, Type, TypeCastExpression, TypeDeclarationStatement,
+    TypePack, TypedIdentifier, Variable, VariadicTypePack,
+};
+use crate::process::{
+    DefaultVisitor, IdentifierTracker, NodeProcessor, NodeVisitor, Scope, ScopeVisitor,
+};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind,
+};
+
+const FUNCTION_NAME: &str = "BLOCK";
+/// Globals that can observe the function environment or the call stack, which changes when
+/// statements are moved into a new function.
+const UNSAFE_GLOBALS: [&str; 3] = ["debug", "getfenv", "setfenv"];
+/// Each deduplicated sequence shrinks the code, but this guards against a size estimation
+/// that would not.
+const MAX_ROUNDS: usize = 64;
+/// The maximum number of statements compared during a round.
+const MAX_REQUESTED_STATEMENTS: usize = 5_000;
+
+fn generate_dense(statements: &[Statement]) -> String {
+    let mut generator = DenseLuaGenerator::new(usize::MAX);
+    generator.write_block(&Block::new(statements.to_vec(), None));
+    generator.into_string()
+}
+
+fn get_declared_names(statement: &Statement) -> Vec<String> {
+    match statement {
+        Statement::LocalAssign(local_assign) => local_assign
+            .iter_variables()
+            .map(|variable| variable.get_name().to_owned())
+            .collect(),
+        Statement::LocalFunction(function) => vec![function.get_name().to_owned()],
+        _ => Vec::new(),
+    }
+}
+
+/// Returns true when a `return`, or a `break` or `continue` that does not belong to a loop
+/// of the block, can exit the block.
+fn has_exit(block: &Block, in_loop: bool) -> bool {
+    block
+        .iter_statements()
+        .any(|statement| statement_has_exit(statement, in_loop))
+        || match block.get_last_statement() {
+            Some(LastStatement::Return(_)) => true,
+            Some(LastStatement::Break(_)) | Some(LastStatement::Continue(_)) => !in_loop,
+            None => false,
+        }
+}
+
+fn statement_has_exit(statement: &Statement, in_loop: bool) -> bool {
+    match statement {
+        Statement::Do(do_statement) => has_exit(do_statement.get_block(), in_loop),
+        Statement::If(if_statement) => {
+            if_statement
+                .iter_branches()
+                .any(|branch| has_exit(branch.get_block(), in_loop))
+                || if_statement
+                    .get_else_block()
+                    .is_some_and(|block| has_exit(block, in_loop))
+        }
+        Statement::GenericFor(generic_for) => has_exit(generic_for.get_block(), true),
+        Statement::NumericFor(numeric_for) => has_exit(numeric_for.get_block(), true),
+        Statement::Repeat(repeat) => has_exit(repeat.get_block(), true),
+        Statement::While(while_statement) => has_exit(while_statement.get_block(), true),
+        Statement::Assign(_)
+        | Statement::Call(_)
+        | Statement::CompoundAssign(_)
+        | Statement::Function(_)
+        | Statement::LocalAssign(_)
+        | Statement::LocalFunction(_)
+        | Statement::TypeDeclaration(_)
+        | Statement::Verbatim(_) => false,
+    }
+}
+
+/// Collects what prevents a statement from being moved into another function, the
+/// identifiers it uses and the number of blocks it contains.
+#[derive(Default)]
+struct StatementScan {
+    has_variable_arguments: bool,
+    has_types: bool,
+    has_verbatim: bool,
+    identifiers: HashSet<String>,
+    block_count: usize,
+}
+
+impl StatementScan {
+    fn scan(statement: &mut Statement) -> Self {
+        let mut scan = Self::default();
+        DefaultVisitor::visit_statement(statement, &mut scan);
+        scan
+    }
+
+    fn scan_last_statement(statement: &mut LastStatement) -> Self {
+        let mut scan = Self::default();
+        DefaultVisitor::visit_last_statement(statement, &mut scan);
+        scan
+    }
+
+    fn is_extractable(&self) -> bool {
+        !self.has_variable_arguments && !self.has_types && !self.has_verbatim
+    }
+}
+
+impl NodeProcessor for StatementScan {
+    fn process_block(&mut self, _: &mut Block) {
+        self.block_count += 1;
+    }
+
+    fn process_statement(&mut self, statement: &mut Statement) {
+        // the identifiers used by verbatim code are unknown
+        if matches!(statement, Statement::Verbatim(_)) {
+            self.has_verbatim = true;
+        }
+    }
+
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if matches!(expression, Expression::VariableArguments(_)) {
+            self.has_variable_arguments = true;
+        }
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        self.identifiers.insert(identifier.get_name().to_owned());
+    }
+
+    fn process_type_declaration(&mut self, _: &mut TypeDeclarationStatement) {
+        self.has_types = true;
+    }
+
+    fn process_type_cast_expression(&mut self, _: &mut TypeCastExpression) {
+        self.has_types = true;
+    }
+
+    fn process_type(&mut self, _: &mut Type) {
+        self.has_types = true;
+    }
+
+    fn process_type_pack(&mut self, _: &mut TypePack) {
+        self.has_types = true;
+    }
+
+    fn process_generic_type_pack(&mut self, _: &mut GenericTypePack) {
+        self.has_types = true;
+    }
+
+    fn process_variadic_type_pack(&mut self, _: &mut VariadicTypePack) {
+        self.has_types = true;
+    }
+}
+
+/// Collects every identifier name of a file, and the names that are assigned after being
+/// declared.
+#[derive(Default)]
+struct NameCollector {
+    names: HashSet<String>,
+    assigned: HashSet<String>,
+}
+
+impl Scope for NameCollector {
+    fn push(&mut self) {}
+
+    fn pop(&mut self) {}
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.names.insert(identifier.get_name().to_owned());
+    }
+
+    fn insert_self(&mut self) {}
+
+    fn insert_local(&mut self, identifier: &mut Identifier, _value: Option<&mut Expression>) {
+        self.insert(identifier);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.insert(function.mutate_identifier());
+    }
+}
+
+impl NodeProcessor for NameCollector {
+    fn process_statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::Assign(assign) => {
+                for variable in assign.iter_variables() {
+                    if let Variable::Identifier(identifier) = variable {
+                        self.assigned.insert(identifier.get_name().to_owned());
+                    }
+                }
+            }
+            Statement::CompoundAssign(assign) => {
+                if let Variable::Identifier(identifier) = assign.get_variable() {
+                    self.assigned.insert(identifier.get_name().to_owned());
+                }
+            }
+            Statement::Function(function) => {
+                let name = function.get_name();
+                if name.get_field_names().is_empty() && !name.has_method() {
+                    self.assigned.insert(name.get_name().get_name().to_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        self.names.insert(identifier.get_name().to_owned());
+    }
+}
+
+/// Renames the locals declared in a sequence of statements and the locals it captures, so
+/// that sequences that only differ by the names of their locals generate the same code.
+struct Normalizer<'a> {
+    is_outer_local: &'a dyn Fn(&str) -> bool,
+    scopes: Vec<HashMap<String, String>>,
+    local_count: usize,
+    parameters: Vec<String>,
+}
+
+impl<'a> Normalizer<'a> {
+    /// Returns the normalized code and the captured locals, in the order they first appear.
+    fn normalize(
+        statements: &[Statement],
+        is_outer_local: &'a dyn Fn(&str) -> bool,
+    ) -> (String, Vec<String>) {
+        let mut normalizer = Self {
+            is_outer_local,
+            scopes: Vec::new(),
+            local_count: 0,
+            parameters: Vec::new(),
+        };
+        let mut block = Block::new(statements.to_vec(), None);
+        ScopeVisitor::visit_block(&mut block, &mut normalizer);
+
+        let mut generator = DenseLuaGenerator::new(usize::MAX);
+        generator.write_block(&block);
+        (generator.into_string(), normalizer.parameters)
+    }
+
+    fn declare(&mut self, name: &str, normalized: String) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_owned(), normalized);
+        }
+    }
+}
+
+impl Scope for Normalizer<'_> {
+    fn push(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        // `@` can not appear in an identifier, so normalized names never collide with globals
+        let normalized = format!("@{}", self.local_count);
+        self.local_count += 1;
+        self.declare(identifier.get_name(), normalized.clone());
+        identifier.set_name(normalized);
+    }
+
+    fn insert_self(&mut self) {
+        self.declare("self", "self".to_owned());
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, _value: Option<&mut Expression>) {
+        self.insert(identifier);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.insert(function.mutate_identifier());
+    }
+}
+
+impl NodeProcessor for Normalizer<'_> {
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        let name = identifier.get_name();
+
+        if let Some(normalized) = self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            identifier.set_name(normalized.clone());
+        } else if (self.is_outer_local)(name) {
+            let index = match self
+                .parameters
+                .iter()
+                .position(|parameter| parameter == name)
+            {
+                Some(index) => index,
+                None => {
+                    self.parameters.push(name.to_owned());
+                    self.parameters.len() - 1
+                }
+            };
+            identifier.set_name(format!("@p{}", index));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct StatementSummary {
+    hash: u64,
+    extractable: bool,
+    block_count: usize,
+}
+
+impl StatementSummary {
+    fn matches(&self, other: &Self) -> bool {
+        self.extractable && other.extractable && self.hash == other.hash
+    }
+}
+
+/// Summarizes each statement of each block, in the order the blocks are visited.
+#[derive(Default)]
+struct Analyzer {
+    identifier_tracker: IdentifierTracker,
+    blocks: Vec<Vec<StatementSummary>>,
+}
+
+impl Analyzer {
+    fn summarize(&self, block: &mut Block) -> Vec<StatementSummary> {
+        let mut declared_before = HashSet::new();
+        let mut summaries = Vec::new();
+
+        for statement in block.iter_mut_statements() {
+            let scan = StatementScan::scan(statement);
+            let is_outer_local =
+                |name: &str| declared_before.contains(name) || self.is_identifier_used(name);
+            let (code, _) = Normalizer::normalize(std::slice::from_ref(statement), &is_outer_local);
+
+            let mut hasher = DefaultHasher::new();
+            code.hash(&mut hasher);
+
+            summaries.push(StatementSummary {
+                hash: hasher.finish(),
+                extractable: scan.is_extractable() && !statement_has_exit(statement, false),
+                block_count: scan.block_count,
+            });
+            declared_before.extend(get_declared_names(statement));
+        }
+
+        summaries
+    }
+}
+
+impl Deref for Analyzer {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl DerefMut for Analyzer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for Analyzer {
+    fn process_block(&mut self, block: &mut Block) {
+        let summaries = self.summarize(block);
+        self.blocks.push(summaries);
+    }
+}
+
+/// A sequence of statements that can be moved into a function.
+#[derive(Debug)]
+struct Occurrence {
+    block: usize,
+    start: usize,
+    length: usize,
+    code: String,
+    parameters: Vec<String>,
+    statements: Vec<Statement>,
+}
+
+impl Occurrence {
+    fn overlaps(&self, other: &Self) -> bool {
+        self.block == other.block
+            && self.start < other.start + other.length
+            && other.start < self.start + self.length
+    }
+}
+
+/// Verifies that the requested sequences can be moved into a function.
+struct Verifier<'a> {
+    identifier_tracker: IdentifierTracker,
+    requests: &'a HashMap<usize, Vec<(usize, usize)>>,
+    assigned: &'a HashSet<String>,
+    block_count: usize,
+    occurrences: Vec<Occurrence>,
+}
+
+impl Verifier<'_> {
+    fn verify(
+        &self,
+        id: usize,
+        block: &Block,
+        last_uses: &HashMap<String, usize>,
+        start: usize,
+        length: usize,
+    ) -> Option<Occurrence> {
+        let declared_before: HashSet<String> = block
+            .iter_statements()
+            .take(start)
+            .flat_map(get_declared_names)
+            .collect();
+        let statements: Vec<Statement> = block
+            .iter_statements()
+            .skip(start)
+            .take(length)
+            .cloned()
+            .collect();
+
+        let is_outer_local =
+            |name: &str| declared_before.contains(name) || self.is_identifier_used(name);
+        let (code, parameters) = Normalizer::normalize(&statements, &is_outer_local);
+
+        if parameters
+            .iter()
+            .any(|parameter| self.assigned.contains(parameter))
+        {
+            return None;
+        }
+
+        let is_used_after = |name: &String| {
+            last_uses
+                .get(name)
+                .is_some_and(|last_use| *last_use >= start + length)
+        };
+
+        if statements
+            .iter()
+            .flat_map(get_declared_names)
+            .any(|name| is_used_after(&name))
+        {
+            return None;
+        }
+
+        Some(Occurrence {
+            block: id,
+            start,
+            length,
+            code,
+            parameters,
+            statements,
+        })
+    }
+}
+
+impl Deref for Verifier<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl DerefMut for Verifier<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for Verifier<'_> {
+    fn process_block(&mut self, block: &mut Block) {
+        let id = self.block_count;
+        self.block_count += 1;
+
+        let Some(requests) = self.requests.get(&id) else {
+            return;
+        };
+
+        // the index of the last statement that uses each identifier
+        let mut last_uses = HashMap::new();
+        for (index, statement) in block.iter_mut_statements().enumerate() {
+            for identifier in StatementScan::scan(statement).identifiers {
+                last_uses.insert(identifier, index);
+            }
+        }
+        let statements_len = block.statements_len();
+        if let Some(last_statement) = block.mutate_last_statement() {
+            for identifier in StatementScan::scan_last_statement(last_statement).identifiers {
+                last_uses.insert(identifier, statements_len);
+            }
+        }
+
+        for &(start, length) in requests {
+            if let Some(occurrence) = self.verify(id, block, &last_uses, start, length) {
+                self.occurrences.push(occurrence);
+            }
+        }
+    }
+}
+
+/// Replaces sequences of statements with calls. The blocks are identified with the order in
+/// which they were visited before any replacement.
+struct Replacer {
+    identifier_tracker: IdentifierTracker,
+    replacements: HashMap<usize, Vec<(usize, usize, Statement)>>,
+    /// The blocks nested in replaced statements, with the number of blocks to skip.
+    removed_blocks: HashMap<usize, usize>,
+    block_count: usize,
+}
+
+impl Deref for Replacer {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl DerefMut for Replacer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for Replacer {
+    fn process_block(&mut self, block: &mut Block) {
+        while let Some(skip) = self.removed_blocks.get(&self.block_count) {
+            self.block_count += skip;
+        }
+        let id = self.block_count;
+        self.block_count += 1;
+
+        let Some(mut replacements) = self.replacements.remove(&id) else {
+            return;
+        };
+        replacements.sort_by_key(|(start, _, _)| Reverse(*start));
+
+        for (start, length, call) in replacements {
+            let mut index = 0;
+            block.filter_statements(|_| {
+                let keep = index <= start || index >= start + length;
+                index += 1;
+                keep
+            });
+            if let Some(statement) = block.iter_mut_statements().nth(start) {
+                *statement = call;
+            }
+        }
+    }
+}
+
+/// Finds the pairs of positions that start the same maximal sequence of statements, and
+/// requests to verify these sequences.
+fn find_candidates(
+    blocks: &[Vec<StatementSummary>],
+    min_statements: usize,
+) -> HashMap<usize, Vec<(usize, usize)>> {
+    let mut group_indexes: HashMap<u64, usize> = HashMap::new();
+    let mut groups: Vec<Vec<(usize, usize)>> = Vec::new();
+
+    for (block, summaries) in blocks.iter().enumerate() {
+        for (index, summary) in summaries.iter().enumerate() {
+            if summary.extractable {
+                let group_index = *group_indexes.entry(summary.hash).or_insert_with(|| {
+                    groups.push(Vec::new());
+                    groups.len() - 1
+                });
+                groups[group_index].push((block, index));
+            }
+        }
+    }
+
+    let mut requests: Vec<(usize, usize, usize)> = Vec::new();
+    let mut requested_statements = 0;
+
+    'groups: for group in groups.iter().filter(|group| group.len() > 1) {
+        for (i, &(first_block, first)) in group.iter().enumerate() {
+            for &(second_block, second) in group.iter().skip(i + 1) {
+                let first_summaries = &blocks[first_block];
+                let second_summaries = &blocks[second_block];
+
+                // only start from the first statement of a repeated sequence
+                if first > 0
+                    && second > 0
+                    && first_summaries[first - 1].matches(&second_summaries[second - 1])
+                {
+                    continue;
+                }
+
+                let max_length = if first_block == second_block {
+                    second - first
+                } else {
+                    usize::MAX
+                };
+
+                let mut length = 1;
+                while length < max_length
+                    && first + length < first_summaries.len()
+                    && second + length < second_summaries.len()
+                    && first_summaries[first + length].matches(&second_summaries[second + length])
+                {
+                    length += 1;
+                }
+
+                if length < min_statements {
+                    continue;
+                }
+
+                requested_statements += 2 * length;
+                if requested_statements > MAX_REQUESTED_STATEMENTS {
+                    break 'groups;
+                }
+
+                requests.push((first_block, first, length));
+                requests.push((second_block, second, length));
+            }
+        }
+    }
+
+    let mut result: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+    for (block, start, length) in requests {
+        let block_requests = result.entry(block).or_default();
+        if !block_requests.contains(&(start, length)) {
+            block_requests.push((start, length));
+        }
+    }
+    result
+}
+
+fn create_call(name: &str, occurrence: &Occurrence) -> Statement {
+    occurrence
+        .parameters
+        .iter()
+        .fold(FunctionCall::from_name(name), |call, parameter| {
+            call.with_argument(Expression::identifier(parameter.as_str()))
+        })
+        .into()
+}
+
+fn create_function(name: &str, occurrence: &Occurrence) -> Statement {
+    LocalFunctionStatement::new(
+        name,
+        Block::new(occurrence.statements.clone(), None),
+        occurrence
+            .parameters
+            .iter()
+            .map(|parameter| TypedIdentifier::from(parameter.as_str()))
+            .collect(),
+        false,
+    )
+    .into()
+}
+
+/// A group of sequences that generate the same code, with the number of bytes saved by
+/// moving them into a function.
+struct Deduplication {
+    occurrences: Vec<Occurrence>,
+    savings: usize,
+}
+
+fn select_deduplication(occurrences: Vec<Occurrence>, name: &str) -> Option<Deduplication> {
+    let mut group_indexes: HashMap<String, usize> = HashMap::new();
+    let mut groups: Vec<Vec<Occurrence>> = Vec::new();
+    for occurrence in occurrences {
+        let group_index = *group_indexes
+            .entry(occurrence.code.clone())
+            .or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+        groups[group_index].push(occurrence);
+    }
+
+    let mut best: Option<Deduplication> = None;
+
+    for mut group in groups {
+        group.sort_by_key(|occurrence| (occurrence.block, occurrence.start));
+
+        let mut selected: Vec<Occurrence> = Vec::new();
+        for occurrence in group {
+            if !selected.iter().any(|other| other.overlaps(&occurrence)) {
+                selected.push(occurrence);
+            }
+        }
+
+        if selected.len() < 2 {
+            continue;
+        }
+
+        let original_size: usize = selected
+            .iter()
+            .map(|occurrence| generate_dense(&occurrence.statements).len())
+            .sum();
+        let new_size = generate_dense(&[create_function(name, &selected[0])]).len()
+            + selected
+                .iter()
+                .map(|occurrence| generate_dense(&[create_call(name, occurrence)]).len())
+                .sum::<usize>();
+
+        if new_size >= original_size {
+            continue;
+        }
+        let savings = original_size - new_size;
+
+        if best
+            .as_ref()
+            .is_none_or(|deduplication| savings > deduplication.savings)
+        {
+            best = Some(Deduplication {
+                occurrences: selected,
+                savings,
+            });
+        }
+    }
+
+    best
+}
+
+fn generate_function_name(names: &HashSet<String>, generated_name_prefix: &str) -> String {
+    let function_name = format!("{}{}", generated_name_prefix, FUNCTION_NAME);
+
+    if !names.contains(&function_name) {
+        return function_name;
+    }
+
+    (0..)
+        .map(|index| format!("{}{}", function_name, index))
+        .find(|name| !names.contains(name))
+        .expect("unable to generate a function name")
+}
+
+/// Moves one group of repeated sequences into a function. Returns false when no group is
+/// found.
+fn deduplicate_once(block: &mut Block, min_statements: usize, context: &Context) -> bool {
+    let mut names = NameCollector::default();
+    ScopeVisitor::visit_block(block, &mut names);
+
+    if UNSAFE_GLOBALS
+        .iter()
+        .any(|name| names.names.contains(*name))
+    {
+        return false;
+    }
+
+    let mut analyzer = Analyzer::default();
+    ScopeVisitor::visit_block(block, &mut analyzer);
+
+    let requests = find_candidates(&analyzer.blocks, min_statements);
+    if requests.is_empty() {
+        return false;
+    }
+
+    let mut verifier = Verifier {
+        identifier_tracker: IdentifierTracker::new(),
+        requests: &requests,
+        assigned: &names.assigned,
+        block_count: 0,
+        occurrences: Vec::new(),
+    };
+    ScopeVisitor::visit_block(block, &mut verifier);
+
+    names.names.extend(context.reserved_identifiers());
+    let name = generate_function_name(&names.names, context.generated_name_prefix());
+
+    let Some(deduplication) = select_deduplication(verifier.occurrences, &name) else {
+        return false;
+    };
+
+    let mut replacer = Replacer {
+        identifier_tracker: IdentifierTracker::new(),
+        replacements: HashMap::new(),
+        removed_blocks: HashMap::new(),
+        block_count: 0,
+    };
+
+    for occurrence in deduplication.occurrences.iter() {
+        let summaries = &analyzer.blocks[occurrence.block];
+        let first_removed_block = occurrence.block
+            + 1
+            + summaries[..occurrence.start]
+                .iter()
+                .map(|summary| summary.block_count)
+                .sum::<usize>();
+        let removed_count: usize = summaries
+            [occurrence.start..occurrence.start + occurrence.length]
+            .iter()
+            .map(|summary| summary.block_count)
+            .sum();
+
+        if removed_count > 0 {
+            replacer
+                .removed_blocks
+                .insert(first_removed_block, removed_count);
+        }
+
+        replacer
+            .replacements
+            .entry(occurrence.block)
+            .or_default()
+            .push((
+                occurrence.start,
+                occurrence.length,
+                create_call(&name, occurrence),
+            ));
+    }
+
+    ScopeVisitor::visit_block(block, &mut replacer);
+
+    block.insert_statement(0, create_function(&name, &deduplication.occurrences[0]));
+
+    true
+}
+
+pub const DEDUPLICATE_BLOCKS_RULE_NAME: &str = "deduplicate_blocks";
+
+const DEFAULT_MIN_STATEMENTS: usize = 3;
+
+/// A rule that moves repeated sequences of statements into a local function.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeduplicateBlocks {
+    min_statements: usize,
+}
+
+impl Default for DeduplicateBlocks {
+    fn default() -> Self {
+        Self {
+            min_statements: DEFAULT_MIN_STATEMENTS,
+        }
+    }
+}
+
+impl DeduplicateBlocks {
+    pub fn with_min_statements(mut self, min_statements: usize) -> Self {
+        self.min_statements = min_statements;
+        self
+    }
+}
+
+impl FlawlessRule for DeduplicateBlocks {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let min_statements = self.min_statements.max(1);
+
+        for _ in 0..MAX_ROUNDS {
+            if !deduplicate_once(block, min_statements, context) {
+                break;
+            }
+        }
+    }
+}
+
+impl RuleConfiguration for DeduplicateBlocks {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "min_statements" => {
+                    self.min_statements = value.expect_usize(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        DEDUPLICATE_BLOCKS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["min_statements"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Usize
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.min_statements != DEFAULT_MIN_STATEMENTS {
+            properties.insert("min_statements".to_owned(), self.min_statements.into());
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> DeduplicateBlocks {
+        DeduplicateBlocks::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_deduplicate_blocks", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_min_statements() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_min_statements(5));
+
+        assert_json_snapshot!("deduplicate_blocks_with_min_statements", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'deduplicate_blocks',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+
+use crate::rules::{
+    get_all_rule_names, get_default_rules, Rule, REMOVE_COMMENTS_RULE_NAME,
+    REMOVE_SPACES_RULE_NAME, RENAME_VARIABLES_RULE_NAME,
+};
+
+/// A named starting point for the rules created by the [`DefaultRulesBuilder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Profile {
+    /// The default rule stack (the same rules as [`get_default_rules`]).
+    #[default]
+    Default,
+    /// The default rule stack without the rules that make the code harder to read (removing
+    /// spaces and comments and renaming variables).
+    Readable,
+}
+
+impl Profile {
+    fn excluded_rules(&self) -> &'static [&'static str] {
+        match self {
+            Self::Default => &[],
+            Self::Readable => &[
+                REMOVE_SPACES_RULE_NAME,
+                REMOVE_COMMENTS_RULE_NAME,
+                RENAME_VARIABLES_RULE_NAME,
+            ],
+        }
+    }
+}
+
+/// A builder to create the default rule stack of darklua while excluding some of its rules or
+/// adding new ones.
+///
+/// ```
+/// # use darklua_core::rules::DefaultRulesBuilder;
+/// let rules = DefaultRulesBuilder::new().without_rename().build();
+///
+/// assert!(rules.iter().all(|rule| rule.get_name() != "rename_variables"));
+/// ```
+#[derive(Debug, Default)]
+pub struct DefaultRulesBuilder {
+    excluded: HashSet<&'static str>,
+    extra: Vec<Box<dyn Rule>>,
+}
+
+impl DefaultRulesBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_profile(profile: Profile) -> Self {
+        Self {
+            excluded: profile.excluded_rules().iter().copied().collect(),
+            extra: Vec::new(),
+        }
+    }
+
+    /// Excludes the `rename_variables` rule.
+    pub fn without_rename(self) -> Self {
+        self.without(RENAME_VARIABLES_RULE_NAME)
+    }
+
+    /// Excludes the `remove_spaces` rule.
+    pub fn without_remove_spaces(self) -> Self {
+        self.without(REMOVE_SPACES_RULE_NAME)
+    }
+
+    /// Excludes the rule with the given name. Names that do not match any rule are ignored.
+    pub fn without(mut self, rule_name: &str) -> Self {
+        if let Some(name) = get_all_rule_names()
+            .into_iter()
+            .find(|name| *name == rule_name)
+        {
+            self.excluded.insert(name);
+        }
+        self
+    }
+
+    /// Adds a rule after the default rules.
+    pub fn with_extra(mut self, rule: Box<dyn Rule>) -> Self {
+        self.extra.push(rule);
+        self
+    }
+
+    pub fn build(self) -> Vec<Box<dyn Rule>> {
+        let excluded = self.excluded;
+
+        get_default_rules()
+            .into_iter()
+            .filter(|rule| !excluded.contains(rule.get_name()))
+            .chain(self.extra)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::{RemoveTypes, REMOVE_TYPES_RULE_NAME};
+
+    fn get_names(rules: &[Box<dyn Rule>]) -> Vec<&'static str> {
+        rules.iter().map(|rule| rule.get_name()).collect()
+    }
+
+    #[test]
+    fn build_default_rules() {
+        pretty_assertions::assert_eq!(
+            get_names(&DefaultRulesBuilder::new().build()),
+            get_names(&get_default_rules())
+        );
+    }
+
+    #[test]
+    fn without_rename() {
+        let rules = DefaultRulesBuilder::new().without_rename().build();
+
+        assert!(!get_names(&rules).contains(&RENAME_VARIABLES_RULE_NAME));
+        assert_eq!(rules.len(), get_default_rules().len() - 1);
+    }
+
+    #[test]
+    fn without_remove_spaces() {
+        let rules = DefaultRulesBuilder::new().without_remove_spaces().build();
+
+        assert!(!get_names(&rules).contains(&REMOVE_SPACES_RULE_NAME));
+        assert_eq!(rules.len(), get_default_rules().len() - 1);
+    }
+
+    #[test]
+    fn without_rule_name() {
+        let rules = DefaultRulesBuilder::new()
+            .without(REMOVE_COMMENTS_RULE_NAME)
+            .build();
+
+        assert!(!get_names(&rules).contains(&REMOVE_COMMENTS_RULE_NAME));
+        assert_eq!(rules.len(), get_default_rules().len() - 1);
+    }
+
+    #[test]
+    fn without_unknown_rule_name() {
+        let rules = DefaultRulesBuilder::new().without("unknown_rule").build();
+
+        assert_eq!(rules.len(), get_default_rules().len());
+    }
+
+    #[test]
+    fn with_extra_rule() {
+        let rules = DefaultRulesBuilder::new()
+            .with_extra(Box::<RemoveTypes>::default())
+            .build();
+
+        assert_eq!(get_names(&rules).last(), Some(&REMOVE_TYPES_RULE_NAME));
+        assert_eq!(rules.len(), get_default_rules().len() + 1);
+    }
+
+    #[test]
+    fn from_readable_profile() {
+        let names = get_names(&DefaultRulesBuilder::from_profile(Profile::Readable).build());
+
+        assert!(!names.contains(&REMOVE_SPACES_RULE_NAME));
+        assert!(!names.contains(&REMOVE_COMMENTS_RULE_NAME));
+        assert!(!names.contains(&RENAME_VARIABLES_RULE_NAME));
+        assert_eq!(names.len(), get_default_rules().len() - 3);
+    }
+
+    #[test]
+    fn from_default_profile() {
+        pretty_assertions::assert_eq!(
+            get_names(&DefaultRulesBuilder::from_profile(Profile::Default).build()),
+            get_names(&get_default_rules())
+        );
+    }
+}
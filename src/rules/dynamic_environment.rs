@@ -0,0 +1,180 @@
+use std::collections::BTreeSet;
+use std::ops;
+
+use crate::nodes::{Arguments, Block, Expression, FunctionCall, Identifier, Prefix};
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
+use crate::rules::Context;
+use crate::utils::log;
+
+const ENVIRONMENT_FUNCTIONS: [&str; 2] = ["getfenv", "setfenv"];
+const LOAD_FUNCTIONS: [&str; 2] = ["load", "loadstring"];
+
+/// The functions used by a block to access or replace the environment of its code at runtime.
+///
+/// Any reference to `getfenv` or `setfenv` is considered dynamic. Calls to `load` or
+/// `loadstring` are only considered dynamic when their first argument is not a string literal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DynamicEnvironmentUsage {
+    functions: BTreeSet<&'static str>,
+}
+
+impl DynamicEnvironmentUsage {
+    pub fn find(block: &mut Block) -> Self {
+        let mut finder = DynamicEnvironmentFinder::default();
+        ScopeVisitor::visit_block(block, &mut finder);
+        Self {
+            functions: finder.functions,
+        }
+    }
+
+    #[inline]
+    pub fn is_dynamic(&self) -> bool {
+        !self.functions.is_empty()
+    }
+
+    #[inline]
+    pub fn iter_functions(&self) -> impl Iterator<Item = &str> {
+        self.functions.iter().copied()
+    }
+}
+
+/// Returns true (and logs the reason) when the current file accesses its environment
+/// dynamically, so that rules relying on static scoping can leave it unchanged.
+pub(crate) fn skip_dynamic_environment(
+    rule_name: &str,
+    block: &mut Block,
+    context: &Context,
+) -> bool {
+    let usage = context.dynamic_environment(block);
+
+    if usage.is_dynamic() {
+        log::warn!(
+            "[{}] skip rule `{}` because the file uses {} to access its environment dynamically",
+            context.current_path().display(),
+            rule_name,
+            usage
+                .iter_functions()
+                .map(|function| format!("`{}`", function))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        true
+    } else {
+        false
+    }
+}
+
+#[derive(Debug, Default)]
+struct DynamicEnvironmentFinder {
+    functions: BTreeSet<&'static str>,
+    // the number of upcoming `load` identifiers that are called with a literal source
+    literal_loads: usize,
+    identifier_tracker: IdentifierTracker,
+}
+
+impl ops::Deref for DynamicEnvironmentFinder {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for DynamicEnvironmentFinder {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl DynamicEnvironmentFinder {
+    fn find_global(
+        &self,
+        functions: &[&'static str],
+        identifier: &Identifier,
+    ) -> Option<&'static str> {
+        let name = identifier.get_name();
+        functions
+            .iter()
+            .copied()
+            .find(|function| *function == name)
+            .filter(|function| !self.is_identifier_used(function))
+    }
+}
+
+fn has_literal_source(arguments: &Arguments) -> bool {
+    match arguments {
+        Arguments::String(_) => true,
+        Arguments::Tuple(tuple) => {
+            matches!(tuple.iter_values().next(), Some(Expression::String(_)))
+        }
+        Arguments::Table(_) => false,
+    }
+}
+
+impl NodeProcessor for DynamicEnvironmentFinder {
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if let Prefix::Identifier(identifier) = call.get_prefix() {
+            if call.get_method().is_none()
+                && self.find_global(&LOAD_FUNCTIONS, identifier).is_some()
+                && has_literal_source(call.get_arguments())
+            {
+                self.literal_loads += 1;
+            }
+        }
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        if let Some(function) = self.find_global(&ENVIRONMENT_FUNCTIONS, identifier) {
+            self.functions.insert(function);
+        } else if let Some(function) = self.find_global(&LOAD_FUNCTIONS, identifier) {
+            if self.literal_loads > 0 {
+                self.literal_loads -= 1;
+            } else {
+                self.functions.insert(function);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Parser;
+
+    fn find_functions(code: &str) -> Vec<String> {
+        let mut block = Parser::default().parse(code).expect("unable to parse code");
+
+        DynamicEnvironmentUsage::find(&mut block)
+            .iter_functions()
+            .map(ToOwned::to_owned)
+            .collect()
+    }
+
+    macro_rules! test_usage {
+        ($($name:ident ($code:literal) => [$($function:literal),*]),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    let expected: Vec<String> = vec![$($function.to_owned()),*];
+                    pretty_assertions::assert_eq!(find_functions($code), expected);
+                }
+            )*
+        };
+    }
+
+    test_usage!(
+        empty_block("") => [],
+        call_setfenv("setfenv(1, {})") => ["setfenv"],
+        call_getfenv("return getfenv().print") => ["getfenv"],
+        reference_getfenv("local get = getfenv") => ["getfenv"],
+        both_environment_functions("setfenv(2, getfenv(1))") => ["getfenv", "setfenv"],
+        local_getfenv("local getfenv = function() return {} end return getfenv().value") => [],
+        loadstring_with_literal("loadstring('return 1')()") => [],
+        loadstring_with_string_argument("loadstring 'return 1'") => [],
+        loadstring_with_variable("local code = ... return loadstring(code)()") => ["loadstring"],
+        load_with_variable("return load(...)") => ["load"],
+        literal_load_with_dynamic_argument("load('return 1', loadstring(...))") => ["loadstring"],
+        reference_loadstring("local compile = loadstring") => ["loadstring"],
+    );
+}
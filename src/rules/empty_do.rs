@@ -1,30 +1,155 @@
-use crate::nodes::{Block, Statement};
-use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+use crate::nodes::{
+    Block, BlockTokens, DoStatement, LastStatement, Statement, Token, Trivia, TriviaKind,
+};
+use crate::process::{DefaultPostVisitor, NodePostProcessor, NodePostVisitor, NodeProcessor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
+    RulePropertyKind,
 };
 
-use super::verify_no_rule_properties;
+use super::first_token::{prefix_get_first_token, variable_get_first_token};
 
-#[derive(Debug, Default)]
 struct EmptyDoFilter {
-    mutated: bool,
+    preserve_comments: bool,
 }
 
 impl EmptyDoFilter {
-    pub fn has_mutated(&self) -> bool {
-        self.mutated
+    fn new(preserve_comments: bool) -> Self {
+        Self { preserve_comments }
     }
 }
 
-impl NodeProcessor for EmptyDoFilter {
-    fn process_block(&mut self, block: &mut Block) {
-        block.filter_statements(|statement| match statement {
-            Statement::Do(do_statement) => {
-                self.mutated = do_statement.get_block().is_empty();
-                !self.mutated
+impl NodeProcessor for EmptyDoFilter {}
+
+impl NodePostProcessor for EmptyDoFilter {
+    fn process_after_block(&mut self, block: &mut Block) {
+        let preserve_comments = self.preserve_comments;
+        let mut comments = Vec::new();
+
+        block.filter_mut_statements(|statement| {
+            if let Statement::Do(do_statement) = statement {
+                if do_statement.get_block().is_empty() {
+                    if preserve_comments {
+                        collect_comments(do_statement, &mut comments);
+                    }
+                    return false;
+                }
+            }
+
+            if !comments.is_empty() {
+                if let Some(token) = statement_get_first_token(statement) {
+                    token.prepend_leading_trivia(comments.drain(..));
+                }
             }
-            _ => true,
+
+            true
+        });
+
+        if !comments.is_empty() {
+            attach_to_block_end(block, comments);
+        }
+    }
+}
+
+fn collect_comments(do_statement: &DoStatement, comments: &mut Vec<Trivia>) {
+    let block_tokens = do_statement
+        .get_block()
+        .get_tokens()
+        .into_iter()
+        .flat_map(|tokens| tokens.final_token.iter());
+
+    let tokens: Vec<&Token> = if let Some(tokens) = do_statement.get_tokens() {
+        std::iter::once(&tokens.r#do)
+            .chain(block_tokens)
+            .chain(std::iter::once(&tokens.end))
+            .collect()
+    } else {
+        block_tokens.collect()
+    };
+
+    for comment in tokens
+        .into_iter()
+        .flat_map(|token| {
+            token
+                .iter_leading_trivia()
+                .chain(token.iter_trailing_trivia())
+        })
+        .filter(|trivia| trivia.kind() == TriviaKind::Comment)
+    {
+        comments.push(comment.clone());
+        // each comment is moved on its own line so that it does not comment out what follows
+        comments.push(TriviaKind::Whitespace.with_content("\n"));
+    }
+}
+
+/// Returns the first token of a statement, when the statement has tokens.
+pub(crate) fn statement_get_first_token(statement: &mut Statement) -> Option<&mut Token> {
+    match statement {
+        Statement::Assign(assign) => assign
+            .iter_mut_variables()
+            .next()
+            .map(variable_get_first_token),
+        Statement::Do(do_statement) => do_statement.mutate_tokens().map(|tokens| &mut tokens.r#do),
+        Statement::Call(call) => Some(prefix_get_first_token(call.mutate_prefix())),
+        Statement::CompoundAssign(assign) => {
+            Some(variable_get_first_token(assign.mutate_variable()))
+        }
+        Statement::Function(function) => {
+            function.mutate_tokens().map(|tokens| &mut tokens.function)
+        }
+        Statement::GenericFor(generic_for) => {
+            generic_for.mutate_tokens().map(|tokens| &mut tokens.r#for)
+        }
+        Statement::If(if_statement) => if_statement.mutate_tokens().map(|tokens| &mut tokens.r#if),
+        Statement::LocalAssign(local_assign) => {
+            local_assign.mutate_tokens().map(|tokens| &mut tokens.local)
+        }
+        Statement::LocalFunction(local_function) => local_function
+            .mutate_tokens()
+            .map(|tokens| &mut tokens.local),
+        Statement::NumericFor(numeric_for) => {
+            numeric_for.mutate_tokens().map(|tokens| &mut tokens.r#for)
+        }
+        Statement::Repeat(repeat) => repeat.mutate_tokens().map(|tokens| &mut tokens.repeat),
+        Statement::While(while_statement) => while_statement
+            .mutate_tokens()
+            .map(|tokens| &mut tokens.r#while),
+        Statement::TypeDeclaration(type_declaration) => {
+            type_declaration
+                .mutate_tokens()
+                .map(|tokens| match &mut tokens.export {
+                    Some(export) => export,
+                    None => &mut tokens.r#type,
+                })
+        }
+        Statement::Verbatim(_) => None,
+    }
+}
+
+fn attach_to_block_end(block: &mut Block, comments: Vec<Trivia>) {
+    let last_statement_token = match block.mutate_last_statement() {
+        Some(LastStatement::Break(token)) | Some(LastStatement::Continue(token)) => token.as_mut(),
+        Some(LastStatement::Return(return_statement)) => return_statement
+            .mutate_tokens()
+            .map(|tokens| &mut tokens.r#return),
+        None => None,
+    };
+
+    if let Some(token) = last_statement_token {
+        token.prepend_leading_trivia(comments);
+    } else if let Some(tokens) = block.mutate_tokens() {
+        tokens
+            .final_token
+            .get_or_insert_with(|| Token::from_content(""))
+            .prepend_leading_trivia(comments);
+    } else {
+        let mut token = Token::from_content("");
+        token.prepend_leading_trivia(comments);
+
+        block.set_tokens(BlockTokens {
+            semicolons: Vec::new(),
+            last_semicolon: None,
+            final_token: Some(token),
         });
     }
 }
@@ -32,24 +157,43 @@ impl NodeProcessor for EmptyDoFilter {
 pub const REMOVE_EMPTY_DO_RULE_NAME: &str = "remove_empty_do";
 
 /// A rule that removes empty do statements.
-#[derive(Debug, Default, PartialEq, Eq)]
-pub struct RemoveEmptyDo {}
+#[derive(Debug, PartialEq, Eq)]
+pub struct RemoveEmptyDo {
+    preserve_comments: bool,
+}
+
+impl Default for RemoveEmptyDo {
+    fn default() -> Self {
+        Self {
+            preserve_comments: true,
+        }
+    }
+}
+
+impl RemoveEmptyDo {
+    pub fn with_preserve_comments(mut self, preserve_comments: bool) -> Self {
+        self.preserve_comments = preserve_comments;
+        self
+    }
+}
 
 impl FlawlessRule for RemoveEmptyDo {
     fn flawless_process(&self, block: &mut Block, _: &Context) {
-        loop {
-            let mut processor = EmptyDoFilter::default();
-            DefaultVisitor::visit_block(block, &mut processor);
-            if !processor.has_mutated() {
-                break;
-            }
-        }
+        let mut processor = EmptyDoFilter::new(self.preserve_comments);
+        DefaultPostVisitor::visit_block(block, &mut processor);
     }
 }
 
 impl RuleConfiguration for RemoveEmptyDo {
     fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
-        verify_no_rule_properties(&properties)?;
+        for (key, value) in properties {
+            match key.as_str() {
+                "preserve_comments" => {
+                    self.preserve_comments = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
 
         Ok(())
     }
@@ -58,17 +202,43 @@ impl RuleConfiguration for RemoveEmptyDo {
         REMOVE_EMPTY_DO_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "do end\n\
+                print(\"done\")",
+        )]
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["preserve_comments"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Boolean
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
-        RuleProperties::new()
+        let mut properties = RuleProperties::new();
+
+        if !self.preserve_comments {
+            properties.insert("preserve_comments".to_owned(), false.into());
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::nodes::DoStatement;
-    use crate::rules::{ContextBuilder, Rule};
-    use crate::Resources;
+    use crate::rules::Rule;
+    use crate::test_utils::{
+        assert_rule_config_roundtrip, assert_rule_no_change, assert_rule_output,
+    };
 
     use insta::assert_json_snapshot;
 
@@ -78,33 +248,22 @@ mod test {
 
     #[test]
     fn remove_empty_do_statement() {
-        let rule = new_rule();
-
-        let mut block = Block::default().with_statement(DoStatement::new(Block::default()));
-
-        rule.process(
-            &mut block,
-            &ContextBuilder::new(".", &Resources::from_memory(), "").build(),
-        )
-        .expect("rule should succeed");
-
-        assert_eq!(block, Block::default());
+        assert_rule_output(&new_rule(), "do end", "");
     }
 
     #[test]
     fn remove_nested_empty_do_statement() {
-        let rule = new_rule();
-
-        let block_with_do_statement = Block::default().with_statement(DoStatement::default());
-        let mut block = Block::default().with_statement(DoStatement::new(block_with_do_statement));
+        assert_rule_output(&new_rule(), "do do end end", "");
+    }
 
-        rule.process(
-            &mut block,
-            &ContextBuilder::new(".", &Resources::from_memory(), "").build(),
-        )
-        .expect("rule should succeed");
+    #[test]
+    fn remove_deeply_nested_empty_do_statement() {
+        assert_rule_output(&new_rule(), "do do end do do end end end", "");
+    }
 
-        assert_eq!(block, Block::default());
+    #[test]
+    fn keep_do_statement_with_locals() {
+        assert_rule_no_change(&new_rule(), "do local a = 1 end");
     }
 
     #[test]
@@ -114,6 +273,18 @@ mod test {
         assert_json_snapshot!("default_remove_empty_do", rule);
     }
 
+    #[test]
+    fn serialize_rule_without_preserve_comments() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_preserve_comments(false));
+
+        assert_json_snapshot!("remove_empty_do_without_preserve_comments", rule);
+    }
+
+    #[test]
+    fn configuration_roundtrip() {
+        assert_rule_config_roundtrip("{ rule: 'remove_empty_do', preserve_comments: false }");
+    }
+
     #[test]
     fn configure_with_extra_field_error() {
         let result = json5::from_str::<Box<dyn Rule>>(
@@ -0,0 +1,362 @@
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use crate::nodes::{
+    Arguments, Block, Expression, FunctionCall, Identifier, LastStatement, LocalAssignStatement,
+    LocalFunctionStatement, Prefix, Statement, Variable,
+};
+use crate::process::{
+    DefaultPostVisitor, IdentifierTracker, NodePostProcessor, NodePostVisitor, NodeProcessor,
+    NodeVisitor, Scope, ScopeVisitor,
+};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind,
+};
+
+/// Collects every name declared or referenced in a block, so that the hoisted functions never
+/// shadow a variable used in the code.
+#[derive(Default)]
+struct NameCollector {
+    names: HashSet<String>,
+}
+
+impl Scope for NameCollector {
+    fn push(&mut self) {}
+
+    fn pop(&mut self) {}
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.names.insert(identifier.get_name().to_owned());
+    }
+
+    fn insert_self(&mut self) {}
+
+    fn insert_local(&mut self, identifier: &mut Identifier, _value: Option<&mut Expression>) {
+        self.insert(identifier);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.insert(function.mutate_identifier());
+    }
+}
+
+impl NodeProcessor for NameCollector {
+    fn process_statement(&mut self, statement: &mut Statement) {
+        if let Statement::Function(function) = statement {
+            self.names
+                .insert(function.get_name().get_name().get_name().to_owned());
+        }
+    }
+
+    fn process_variable(&mut self, variable: &mut Variable) {
+        if let Variable::Identifier(identifier) = variable {
+            self.names.insert(identifier.get_name().to_owned());
+        }
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        self.names.insert(identifier.get_name().to_owned());
+    }
+}
+
+/// The statements that are inserted in a block, with the index of the statement they are
+/// inserted before.
+#[derive(Default)]
+struct BlockHoisting {
+    next_statement: usize,
+    // `None` when functions can not be moved before the current statement
+    current_statement: Option<usize>,
+    hoisted: Vec<(usize, Statement)>,
+}
+
+struct Processor {
+    identifier_tracker: IdentifierTracker,
+    min_statements: usize,
+    blocks: Vec<BlockHoisting>,
+    counters: HashMap<String, usize>,
+}
+
+impl Processor {
+    fn new(names: HashSet<String>, min_statements: usize) -> Self {
+        let mut identifier_tracker = IdentifierTracker::new();
+        for name in names {
+            identifier_tracker.insert_identifier(&name);
+        }
+
+        Self {
+            identifier_tracker,
+            min_statements,
+            blocks: Vec::new(),
+            counters: HashMap::new(),
+        }
+    }
+
+    fn is_extractable(&self, expression: &Expression) -> bool {
+        match expression {
+            Expression::Function(function) => {
+                let block = function.get_block();
+                let length =
+                    block.statements_len() + usize::from(block.get_last_statement().is_some());
+                length > self.min_statements
+            }
+            _ => false,
+        }
+    }
+
+    fn generate_callback_name(&mut self, call: &FunctionCall) -> String {
+        let called_name =
+            call.get_method()
+                .map(Identifier::get_name)
+                .or_else(|| match call.get_prefix() {
+                    Prefix::Identifier(identifier) => Some(identifier.get_name()),
+                    Prefix::Field(field) => Some(field.get_field().get_name()),
+                    _ => None,
+                });
+
+        let base_name = match called_name {
+            Some(name) => {
+                let mut characters = name.chars();
+                match characters.next() {
+                    Some(first) => {
+                        format!("on{}{}", first.to_ascii_uppercase(), characters.as_str())
+                    }
+                    None => "callback".to_owned(),
+                }
+            }
+            None => "callback".to_owned(),
+        };
+
+        let counter = self.counters.entry(base_name.clone()).or_insert(0);
+
+        loop {
+            *counter += 1;
+            let name = format!("{}_{}", base_name, counter);
+
+            if !self.identifier_tracker.is_identifier_used(&name) {
+                self.identifier_tracker.insert_identifier(&name);
+                return name;
+            }
+        }
+    }
+}
+
+impl NodeProcessor for Processor {
+    fn process_block(&mut self, _: &mut Block) {
+        self.blocks.push(BlockHoisting::default());
+    }
+
+    fn process_statement(&mut self, statement: &mut Statement) {
+        if let Some(block) = self.blocks.last_mut() {
+            let index = block.next_statement;
+            block.next_statement += 1;
+
+            // the condition of a loop is evaluated at each iteration, and the condition of a
+            // repeat statement can access the locals of its block
+            block.current_statement = match statement {
+                Statement::While(_) | Statement::Repeat(_) => None,
+                _ => Some(index),
+            };
+        }
+    }
+
+    fn process_last_statement(&mut self, _: &mut LastStatement) {
+        if let Some(block) = self.blocks.last_mut() {
+            block.current_statement = Some(block.next_statement);
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        let index = match self.blocks.last().and_then(|block| block.current_statement) {
+            Some(index) => index,
+            None => return,
+        };
+
+        let arguments = match call.get_arguments() {
+            Arguments::Tuple(arguments) => arguments,
+            _ => return,
+        };
+
+        let extract: Vec<_> = arguments
+            .iter_values()
+            .map(|argument| self.is_extractable(argument))
+            .collect();
+
+        if !extract.iter().any(|extract| *extract) {
+            return;
+        }
+
+        let names: Vec<_> = extract
+            .iter()
+            .map(|extract| extract.then(|| self.generate_callback_name(call)))
+            .collect();
+
+        let mut functions = Vec::new();
+
+        if let Arguments::Tuple(arguments) = call.mutate_arguments() {
+            for (argument, name) in arguments.iter_mut_values().zip(names) {
+                if let Some(name) = name {
+                    let function = mem::replace(argument, Expression::identifier(&name));
+                    functions.push((name, function));
+                }
+            }
+        }
+
+        for (name, mut function) in functions {
+            // callbacks nested in the extracted function are extracted inside its block
+            DefaultPostVisitor::visit_expression(&mut function, self);
+
+            if let Some(block) = self.blocks.last_mut() {
+                block.hoisted.push((
+                    index,
+                    LocalAssignStatement::from_variable(name)
+                        .with_value(function)
+                        .into(),
+                ));
+            }
+        }
+    }
+}
+
+impl NodePostProcessor for Processor {
+    fn process_after_block(&mut self, block: &mut Block) {
+        if let Some(hoisting) = self.blocks.pop() {
+            for (index, statement) in hoisting.hoisted.into_iter().rev() {
+                block.insert_statement(index, statement);
+            }
+        }
+    }
+}
+
+pub const EXTRACT_CALLBACKS_RULE_NAME: &str = "extract_callbacks";
+
+const DEFAULT_MIN_STATEMENTS: usize = 8;
+
+/// A rule that moves large anonymous functions passed to function calls into local variables
+/// declared before the statement containing the call.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExtractCallbacks {
+    min_statements: usize,
+}
+
+impl Default for ExtractCallbacks {
+    fn default() -> Self {
+        Self {
+            min_statements: DEFAULT_MIN_STATEMENTS,
+        }
+    }
+}
+
+impl ExtractCallbacks {
+    pub fn with_min_statements(mut self, min_statements: usize) -> Self {
+        self.min_statements = min_statements;
+        self
+    }
+}
+
+impl FlawlessRule for ExtractCallbacks {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut names = NameCollector::default();
+        ScopeVisitor::visit_block(block, &mut names);
+        names.names.extend(context.reserved_identifiers());
+
+        let mut processor = Processor::new(names.names, self.min_statements);
+        DefaultPostVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for ExtractCallbacks {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "min_statements" => {
+                    self.min_statements = value.expect_usize(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        EXTRACT_CALLBACKS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["min_statements"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Usize
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.min_statements != DEFAULT_MIN_STATEMENTS {
+            properties.insert("min_statements".to_owned(), self.min_statements.into());
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+    use crate::test_utils::{
+        assert_rule_config_roundtrip, assert_rule_no_change, assert_rule_output,
+    };
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> ExtractCallbacks {
+        ExtractCallbacks::default()
+    }
+
+    #[test]
+    fn extract_callback_with_more_statements_than_minimum() {
+        assert_rule_output(
+            &new_rule().with_min_statements(1),
+            "foo(function() a() b() end)",
+            "local onFoo_1 = function() a() b() end foo(onFoo_1)",
+        );
+    }
+
+    #[test]
+    fn keep_callback_with_fewer_statements_than_minimum() {
+        assert_rule_no_change(&new_rule(), "foo(function() a() b() end)");
+    }
+
+    #[test]
+    fn configuration_roundtrip() {
+        assert_rule_config_roundtrip("{ rule: 'extract_callbacks', min_statements: 3 }");
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_extract_callbacks", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_min_statements() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_min_statements(2));
+
+        assert_json_snapshot!("extract_callbacks_with_min_statements", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'extract_callbacks',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
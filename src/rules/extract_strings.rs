@@ -0,0 +1,332 @@
+#[cfg(feature = "serde-config")]
+use serde_json::json;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::nodes::{Block, StringExpression, Token};
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+#[cfg(not(feature = "serde-config"))]
+use crate::rules::artifact::to_json_string;
+use crate::rules::{
+    ArtifactContent, ArtifactKind, Context, FlawlessRule, RuleConfiguration,
+    RuleConfigurationError, RuleProperties, RulePropertyKind,
+};
+
+/// The name of the artifact emitted by the [`ExtractStrings`] rule.
+pub const STRINGS_ARTIFACT_NAME: &str = "strings";
+
+const DEFAULT_MIN_LENGTH: usize = 1;
+const KEY_LENGTH: usize = 8;
+
+/// A string literal found in the processed code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ExtractedString {
+    key: String,
+    value: String,
+    line: Option<usize>,
+}
+
+impl ExtractedString {
+    fn new(value: &str, line: Option<usize>) -> Self {
+        let mut key = format!("{:016x}", xxh3_64(value.as_bytes()));
+        key.truncate(KEY_LENGTH);
+
+        Self {
+            key,
+            value: value.to_owned(),
+            line,
+        }
+    }
+}
+
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+struct StringCollector {
+    min_length: usize,
+    strings: Vec<ExtractedString>,
+}
+
+impl NodeProcessor for StringCollector {
+    fn process_string_expression(&mut self, string: &mut StringExpression) {
+        let value = string.get_value();
+
+        if value.chars().count() < self.min_length {
+            return;
+        }
+
+        let line = string.get_token().and_then(Token::get_line_number);
+        self.strings.push(ExtractedString::new(value, line));
+    }
+}
+
+pub const EXTRACT_STRINGS_RULE_NAME: &str = "extract_strings";
+
+/// The format of the table emitted by the [`ExtractStrings`] rule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StringTableFormat {
+    /// A JSON array of objects with a `key`, a `value` and a `line` field.
+    #[default]
+    Json,
+    /// A CSV table with a `key`, a `line` and a `value` column.
+    Csv,
+}
+
+impl StringTableFormat {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// A rule that emits a table of the string literals of the code (for example, to translate
+/// them), without changing the code.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExtractStrings {
+    min_length: usize,
+    format: StringTableFormat,
+}
+
+impl Default for ExtractStrings {
+    fn default() -> Self {
+        Self {
+            min_length: DEFAULT_MIN_LENGTH,
+            format: StringTableFormat::default(),
+        }
+    }
+}
+
+impl ExtractStrings {
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    pub fn with_format(mut self, format: StringTableFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    fn format_table(&self, strings: &[ExtractedString]) -> ArtifactContent {
+        match self.format {
+            #[cfg(feature = "serde-config")]
+            StringTableFormat::Json => ArtifactContent::Json(
+                strings
+                    .iter()
+                    .map(|string| {
+                        json!({
+                            "key": string.key,
+                            "value": string.value,
+                            "line": string.line,
+                        })
+                    })
+                    .collect(),
+            ),
+            #[cfg(not(feature = "serde-config"))]
+            StringTableFormat::Json => {
+                let entries: Vec<_> = strings
+                    .iter()
+                    .map(|string| {
+                        format!(
+                            "  {{\n    \"key\": {},\n    \"line\": {},\n    \"value\": {}\n  }}",
+                            to_json_string(&string.key),
+                            string
+                                .line
+                                .map(|line| line.to_string())
+                                .unwrap_or_else(|| "null".to_owned()),
+                            to_json_string(&string.value)
+                        )
+                    })
+                    .collect();
+
+                ArtifactContent::Text(if entries.is_empty() {
+                    "[]\n".to_owned()
+                } else {
+                    format!("[\n{}\n]\n", entries.join(",\n"))
+                })
+            }
+            StringTableFormat::Csv => {
+                let mut table = "key,line,value\n".to_owned();
+
+                for string in strings {
+                    table.push_str(&format!(
+                        "{},{},{}\n",
+                        string.key,
+                        string.line.map(|line| line.to_string()).unwrap_or_default(),
+                        escape_csv_field(&string.value)
+                    ));
+                }
+
+                ArtifactContent::Text(table)
+            }
+        }
+    }
+}
+
+impl FlawlessRule for ExtractStrings {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut collector = StringCollector {
+            min_length: self.min_length,
+            strings: Vec::new(),
+        };
+        DefaultVisitor::visit_block(block, &mut collector);
+
+        let kind = match self.format {
+            StringTableFormat::Json => ArtifactKind::Json,
+            StringTableFormat::Csv => ArtifactKind::Csv,
+        };
+
+        context.emit_artifact(
+            STRINGS_ARTIFACT_NAME,
+            kind,
+            self.format_table(&collector.strings),
+        );
+    }
+}
+
+impl RuleConfiguration for ExtractStrings {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "min_length" => {
+                    self.min_length = value.expect_usize(&key)?;
+                }
+                "format" => {
+                    let format = value.expect_string(&key)?;
+                    self.format = StringTableFormat::parse(&format).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: key.clone(),
+                            message: format!(
+                                "invalid format `{}` (must be `json` or `csv`)",
+                                format
+                            ),
+                        }
+                    })?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        EXTRACT_STRINGS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["format", "min_length"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "format" => RulePropertyKind::Choice(&["json", "csv"]),
+            _ => RulePropertyKind::Usize,
+        }
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.min_length != DEFAULT_MIN_LENGTH {
+            properties.insert("min_length".to_owned(), self.min_length.into());
+        }
+
+        if self.format != StringTableFormat::default() {
+            properties.insert("format".to_owned(), self.format.as_str().into());
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> ExtractStrings {
+        ExtractStrings::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_extract_strings", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_properties() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .with_min_length(4)
+                .with_format(StringTableFormat::Csv),
+        );
+
+        assert_json_snapshot!("extract_strings_with_properties", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_format_should_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'extract_strings',
+            format: 'xml',
+        }"#,
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'format': invalid format `xml` (must be `json` or `csv`)"
+        );
+    }
+
+    #[test]
+    fn key_is_stable_for_same_value() {
+        let first = ExtractedString::new("hello", Some(1));
+        let second = ExtractedString::new("hello", Some(7));
+
+        assert_eq!(first.key, second.key);
+        assert_eq!(first.key.len(), KEY_LENGTH);
+    }
+
+    #[test]
+    fn key_is_different_for_different_values() {
+        assert_ne!(
+            ExtractedString::new("hello", None).key,
+            ExtractedString::new("world", None).key
+        );
+    }
+
+    #[test]
+    fn escape_csv_field_without_special_characters() {
+        assert_eq!(escape_csv_field("hello world"), "hello world");
+    }
+
+    #[test]
+    fn escape_csv_field_with_comma_and_quotes() {
+        assert_eq!(escape_csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+    }
+
+    #[test]
+    fn escape_csv_field_with_new_line() {
+        assert_eq!(escape_csv_field("a\nb"), "\"a\nb\"");
+    }
+}
@@ -0,0 +1,609 @@
+use std::collections::HashSet;
+use std::iter;
+
+use crate::generator::{DenseLuaGenerator, LuaGenerator};
+use crate::nodes::{
+    AssignStatement, Block, CompoundAssignStatement, Expression, FieldExpression, FunctionCall,
+    FunctionStatement, Identifier, IfBranch, IfStatement, IndexExpression,
+    InterpolatedStringExpression, LastStatement, LocalFunctionStatement, Statement, Variable,
+};
+use crate::process::{
+    DefaultPostVisitor, DefaultVisitor, Evaluator, IdentifierTracker, NodePostVisitor,
+    NodeProcessor, NodeVisitor, Scope, ScopeVisitor,
+};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind,
+};
+
+use super::hoist_loop_invariants::FunctionAssignments;
+
+fn generate_dense(statement: &Statement) -> String {
+    let mut generator = DenseLuaGenerator::new(usize::MAX);
+    generator.write_statement(statement);
+    generator.into_string()
+}
+
+/// Returns the names of the locals (and types) declared by a statement in its block.
+fn get_declared_names(statement: &Statement) -> Vec<String> {
+    match statement {
+        Statement::LocalAssign(local_assign) => local_assign
+            .iter_variables()
+            .map(|variable| variable.get_name().to_owned())
+            .collect(),
+        Statement::LocalFunction(function) => vec![function.get_name().to_owned()],
+        Statement::TypeDeclaration(type_declaration) => {
+            vec![type_declaration.get_name().get_name().to_owned()]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Collects the variables referenced and assigned by the visited nodes, and if they can
+/// run code (by calling a function or a metamethod).
+#[derive(Default)]
+struct Usage {
+    referenced: HashSet<String>,
+    assigned: HashSet<String>,
+    may_run_code: bool,
+}
+
+impl Usage {
+    fn scan<'a>(statements: impl Iterator<Item = &'a Statement>) -> Self {
+        let mut usage = Self::default();
+        for statement in statements {
+            DefaultVisitor::visit_statement(&mut statement.clone(), &mut usage);
+        }
+        usage
+    }
+
+    fn scan_last_statement(&mut self, last_statement: &LastStatement) {
+        DefaultVisitor::visit_last_statement(&mut last_statement.clone(), self);
+    }
+
+    fn scan_expression(&mut self, expression: &Expression) {
+        DefaultVisitor::visit_expression(&mut expression.clone(), self);
+    }
+
+    fn insert_assigned(&mut self, variable: &Variable) {
+        match variable {
+            Variable::Identifier(identifier) => {
+                self.assigned.insert(identifier.get_name().to_owned());
+            }
+            // assigning a field can call the `__newindex` metamethod
+            Variable::Field(_) | Variable::Index(_) => {
+                self.may_run_code = true;
+            }
+        }
+    }
+}
+
+impl NodeProcessor for Usage {
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        for variable in assign.iter_variables() {
+            self.insert_assigned(variable);
+        }
+    }
+
+    fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
+        self.insert_assigned(assign.get_variable());
+        self.may_run_code = true;
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name();
+        if name.get_field_names().is_empty() && !name.has_method() {
+            self.assigned.insert(name.get_name().get_name().to_owned());
+        } else {
+            self.may_run_code = true;
+        }
+    }
+
+    fn process_function_call(&mut self, _: &mut FunctionCall) {
+        self.may_run_code = true;
+    }
+
+    fn process_field_expression(&mut self, _: &mut FieldExpression) {
+        self.may_run_code = true;
+    }
+
+    fn process_index_expression(&mut self, _: &mut IndexExpression) {
+        self.may_run_code = true;
+    }
+
+    fn process_interpolated_string_expression(&mut self, _: &mut InterpolatedStringExpression) {
+        self.may_run_code = true;
+    }
+
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if matches!(expression, Expression::Binary(_) | Expression::Unary(_)) {
+            self.may_run_code = true;
+        }
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        self.referenced.insert(identifier.get_name().to_owned());
+    }
+}
+
+/// The number of statements moved before and after an if statement.
+struct Factoring {
+    prefix_len: usize,
+    suffix_len: usize,
+}
+
+struct Processor {
+    identifier_tracker: IdentifierTracker,
+    evaluator: Evaluator,
+    function_assignments: HashSet<String>,
+    leading: bool,
+    allow_scope_widening: bool,
+}
+
+impl Processor {
+    fn find_factoring(
+        &self,
+        if_statement: &IfStatement,
+        block: &Block,
+        index: usize,
+        block_locals: &HashSet<String>,
+    ) -> Option<Factoring> {
+        // without an else block, the factored statements would also run when no branch is
+        // taken
+        let else_block = if_statement.get_else_block()?;
+
+        let blocks: Vec<&Block> = if_statement
+            .iter_branches()
+            .map(IfBranch::get_block)
+            .chain(iter::once(else_block))
+            .collect();
+
+        if blocks
+            .iter()
+            .any(|block| block.get_last_statement().is_some())
+        {
+            return None;
+        }
+
+        let codes: Vec<Vec<String>> = blocks
+            .iter()
+            .map(|block| block.iter_statements().map(generate_dense).collect())
+            .collect();
+
+        let min_len = codes.iter().map(Vec::len).min().unwrap_or(0);
+
+        if min_len == 0 {
+            return None;
+        }
+
+        let mut names_after = None;
+
+        let mut suffix_len = (0..min_len)
+            .take_while(|offset| {
+                let expected = &codes[0][codes[0].len() - 1 - offset];
+                codes
+                    .iter()
+                    .all(|code| &code[code.len() - 1 - offset] == expected)
+            })
+            .count();
+
+        while suffix_len > 0
+            && !self.can_move_suffix(&blocks, suffix_len, || {
+                names_after.get_or_insert_with(|| get_names_after(block, index))
+            })
+        {
+            suffix_len -= 1;
+        }
+
+        let mut prefix_len = 0;
+
+        if self.leading && self.has_pure_conditions(if_statement) {
+            prefix_len = (0..min_len - suffix_len)
+                .take_while(|index| codes.iter().all(|code| code[*index] == codes[0][*index]))
+                .count();
+
+            if prefix_len > 0 {
+                let mut conditions = Usage::default();
+                for branch in if_statement.iter_branches() {
+                    conditions.scan_expression(branch.get_condition());
+                }
+
+                while prefix_len > 0
+                    && !self.can_move_prefix(&blocks, prefix_len, &conditions, block_locals, || {
+                        names_after.get_or_insert_with(|| get_names_after(block, index))
+                    })
+                {
+                    prefix_len -= 1;
+                }
+            }
+        }
+
+        (prefix_len > 0 || suffix_len > 0).then_some(Factoring {
+            prefix_len,
+            suffix_len,
+        })
+    }
+
+    fn can_move_suffix<'a>(
+        &self,
+        blocks: &[&Block],
+        suffix_len: usize,
+        names_after: impl FnOnce() -> &'a HashSet<String>,
+    ) -> bool {
+        let statements_len = blocks[0].statements_len();
+        let suffix: Vec<&Statement> = blocks[0]
+            .iter_statements()
+            .skip(statements_len - suffix_len)
+            .collect();
+
+        // the statements must not use a local declared before them in one of the branches
+        let declared_before: HashSet<String> = blocks
+            .iter()
+            .flat_map(|block| {
+                block
+                    .iter_statements()
+                    .take(block.statements_len() - suffix_len)
+            })
+            .flat_map(get_declared_names)
+            .collect();
+
+        let usage = Usage::scan(suffix.iter().copied());
+
+        if usage
+            .referenced
+            .iter()
+            .any(|name| declared_before.contains(name))
+        {
+            return false;
+        }
+
+        self.can_widen_scope(suffix.into_iter().flat_map(get_declared_names), names_after)
+    }
+
+    fn can_move_prefix<'a>(
+        &self,
+        blocks: &[&Block],
+        prefix_len: usize,
+        conditions: &Usage,
+        block_locals: &HashSet<String>,
+        names_after: impl FnOnce() -> &'a HashSet<String>,
+    ) -> bool {
+        let prefix: Vec<&Statement> = blocks[0].iter_statements().take(prefix_len).collect();
+
+        let usage = Usage::scan(prefix.iter().copied());
+
+        // the statements now run before the conditions, so they must not change the
+        // variables read by the conditions
+        let changes_conditions = conditions.referenced.iter().any(|name| {
+            usage.assigned.contains(name)
+                || (usage.may_run_code
+                    && (!self.is_local(name, block_locals)
+                        || self.function_assignments.contains(name)))
+        });
+
+        if changes_conditions {
+            return false;
+        }
+
+        let declared: Vec<String> = prefix.into_iter().flat_map(get_declared_names).collect();
+
+        if declared
+            .iter()
+            .any(|name| conditions.referenced.contains(name))
+        {
+            return false;
+        }
+
+        self.can_widen_scope(declared.into_iter(), names_after)
+    }
+
+    /// Returns true when the given locals can be declared in the block that contains the if
+    /// statement, where they are visible to the statements after it.
+    fn can_widen_scope<'a>(
+        &self,
+        mut declared: impl Iterator<Item = String>,
+        names_after: impl FnOnce() -> &'a HashSet<String>,
+    ) -> bool {
+        let first = match declared.next() {
+            Some(name) => name,
+            None => return true,
+        };
+
+        if !self.allow_scope_widening {
+            return false;
+        }
+
+        let names_after = names_after();
+        iter::once(first)
+            .chain(declared)
+            .all(|name| !names_after.contains(&name))
+    }
+
+    fn has_pure_conditions(&self, if_statement: &IfStatement) -> bool {
+        if_statement
+            .iter_branches()
+            .all(|branch| !self.evaluator.has_side_effects(branch.get_condition()))
+    }
+
+    fn is_local(&self, name: &str, block_locals: &HashSet<String>) -> bool {
+        block_locals.contains(name) || self.identifier_tracker.is_identifier_used(name)
+    }
+}
+
+/// Returns the names referenced by the statements that follow the statement at the given
+/// index.
+fn get_names_after(block: &Block, index: usize) -> HashSet<String> {
+    let mut usage = Usage::scan(block.iter_statements().skip(index + 1));
+    if let Some(last_statement) = block.get_last_statement() {
+        usage.scan_last_statement(last_statement);
+    }
+    usage.referenced
+}
+
+fn split_branches(
+    if_statement: &mut IfStatement,
+    factoring: &Factoring,
+) -> (Vec<Statement>, Vec<Statement>) {
+    let mut prefix = Vec::new();
+    let mut suffix = Vec::new();
+
+    for block in if_statement.mutate_all_blocks() {
+        let mut statements = block.take_statements();
+        let suffix_start = statements.len() - factoring.suffix_len;
+        // all the branches contain the same statements, so the ones of the first branch
+        // are kept
+        suffix = statements.split_off(suffix_start);
+        prefix = statements.drain(..factoring.prefix_len).collect();
+        block.set_statements(statements);
+    }
+
+    (prefix, suffix)
+}
+
+impl Scope for Processor {
+    fn push(&mut self) {
+        self.identifier_tracker.push()
+    }
+
+    fn pop(&mut self) {
+        self.identifier_tracker.pop()
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.identifier_tracker.insert(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.identifier_tracker.insert_self();
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>) {
+        self.identifier_tracker.insert_local(identifier, value);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.identifier_tracker.insert_local_function(function);
+    }
+}
+
+impl NodeProcessor for Processor {
+    fn process_block(&mut self, block: &mut Block) {
+        let mut block_locals = HashSet::new();
+        let mut index = 0;
+
+        while index < block.statements_len() {
+            let factoring = match block.iter_statements().nth(index) {
+                Some(Statement::If(if_statement)) => {
+                    self.find_factoring(if_statement, block, index, &block_locals)
+                }
+                _ => None,
+            };
+
+            let moved = if let Some(factoring) = factoring {
+                let (prefix, suffix) = match block.iter_mut_statements().nth(index) {
+                    Some(Statement::If(if_statement)) => split_branches(if_statement, &factoring),
+                    _ => unreachable!("statement should be an if statement"),
+                };
+                let suffix_len = suffix.len();
+
+                for (offset, statement) in prefix.into_iter().enumerate() {
+                    block.insert_statement(index + offset, statement);
+                }
+                let if_index = index + factoring.prefix_len;
+                for (offset, statement) in suffix.into_iter().enumerate() {
+                    block.insert_statement(if_index + 1 + offset, statement);
+                }
+
+                factoring.prefix_len + 1 + suffix_len
+            } else {
+                1
+            };
+
+            block_locals.extend(
+                block
+                    .iter_statements()
+                    .skip(index)
+                    .take(moved)
+                    .flat_map(get_declared_names),
+            );
+            index += moved;
+        }
+    }
+}
+
+pub const FACTOR_COMMON_BRANCH_CODE_RULE_NAME: &str = "factor_common_branch_code";
+
+/// A rule that moves the statements found at the end (or at the beginning) of every branch
+/// of an if statement after (or before) the if statement.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FactorCommonBranchCode {
+    leading: bool,
+    allow_scope_widening: bool,
+}
+
+impl FactorCommonBranchCode {
+    /// Also moves the statements found at the beginning of every branch before the if
+    /// statement, when the conditions have no side effects.
+    pub fn with_leading(mut self) -> Self {
+        self.leading = true;
+        self
+    }
+
+    /// Allows moving local declarations out of the branches, when their names are not used
+    /// after the if statement.
+    pub fn allow_scope_widening(mut self) -> Self {
+        self.allow_scope_widening = true;
+        self
+    }
+}
+
+impl FlawlessRule for FactorCommonBranchCode {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let function_assignments = if self.leading {
+            let mut function_assignments = FunctionAssignments::default();
+            DefaultPostVisitor::visit_block(block, &mut function_assignments);
+            function_assignments.names
+        } else {
+            HashSet::new()
+        };
+
+        let mut evaluator = Evaluator::default();
+        if !context.environment().is_empty() {
+            evaluator = evaluator.with_environment(context.environment().clone());
+        }
+
+        let mut processor = Processor {
+            identifier_tracker: IdentifierTracker::new(),
+            evaluator,
+            function_assignments,
+            leading: self.leading,
+            allow_scope_widening: self.allow_scope_widening,
+        };
+        ScopeVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for FactorCommonBranchCode {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "leading" => {
+                    self.leading = value.expect_bool(&key)?;
+                }
+                "allow_scope_widening" => {
+                    self.allow_scope_widening = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        FACTOR_COMMON_BRANCH_CODE_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["leading", "allow_scope_widening"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "leading" | "allow_scope_widening" => RulePropertyKind::Boolean,
+            _ => RulePropertyKind::Any,
+        }
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.leading {
+            properties.insert("leading".to_owned(), true.into());
+        }
+        if self.allow_scope_widening {
+            properties.insert("allow_scope_widening".to_owned(), true.into());
+        }
+
+        properties
+    }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if self.allow_scope_widening {
+            properties.push("allow_scope_widening");
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+    use crate::test_utils::{
+        assert_rule_config_roundtrip, assert_rule_no_change, assert_rule_output,
+    };
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> FactorCommonBranchCode {
+        FactorCommonBranchCode::default()
+    }
+
+    #[test]
+    fn factor_common_suffix() {
+        assert_rule_output(
+            &new_rule(),
+            "if c then a() log() else b() log() end",
+            "if c then a() else b() end log()",
+        );
+    }
+
+    #[test]
+    fn keep_common_suffix_without_else() {
+        assert_rule_no_change(
+            &new_rule(),
+            "if c then a() log() elseif d then b() log() end",
+        );
+    }
+
+    #[test]
+    fn keep_common_prefix_without_leading() {
+        assert_rule_no_change(&new_rule(), "if c then log() a() else log() b() end");
+    }
+
+    #[test]
+    fn configuration_roundtrip() {
+        assert_rule_config_roundtrip(
+            "{ rule: 'factor_common_branch_code', leading: true, allow_scope_widening: true }",
+        );
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_factor_common_branch_code", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_properties() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_leading().allow_scope_widening());
+
+        assert_json_snapshot!("factor_common_branch_code_with_properties", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'factor_common_branch_code',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
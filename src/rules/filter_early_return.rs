@@ -1,7 +1,7 @@
 use crate::nodes::{Block, LastStatement, Statement};
 use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
 };
 
 use super::verify_no_rule_properties;
@@ -38,7 +38,8 @@ impl Processor {
                 | Statement::NumericFor(_)
                 | Statement::Repeat(_)
                 | Statement::While(_)
-                | Statement::TypeDeclaration(_) => None,
+                | Statement::TypeDeclaration(_)
+                | Statement::Verbatim(_) => None,
             })
     }
 }
@@ -76,9 +77,22 @@ impl RuleConfiguration for FilterAfterEarlyReturn {
         FILTER_AFTER_EARLY_RETURN_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "local function f()\n\
+                \tdo return end\n\
+                \tprint(\"unreachable\")\n\
+                end",
+        )]
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
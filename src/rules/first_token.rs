@@ -0,0 +1,54 @@
+use crate::nodes::{Identifier, ParentheseExpression, ParentheseTokens, Prefix, Token, Variable};
+
+/// Returns the first token of a variable, creating the tokens it needs when they are missing.
+pub(crate) fn variable_get_first_token(variable: &mut Variable) -> &mut Token {
+    match variable {
+        Variable::Identifier(identifier) => identifier_get_first_token(identifier),
+        Variable::Field(field_expression) => {
+            prefix_get_first_token(field_expression.mutate_prefix())
+        }
+        Variable::Index(index_expression) => {
+            prefix_get_first_token(index_expression.mutate_prefix())
+        }
+    }
+}
+
+/// Returns the first token of a prefix, creating the tokens it needs when they are missing.
+pub(crate) fn prefix_get_first_token(prefix: &mut Prefix) -> &mut Token {
+    let mut current = prefix;
+    loop {
+        match current {
+            Prefix::Call(call) => {
+                current = call.mutate_prefix();
+            }
+            Prefix::Field(field_expression) => {
+                current = field_expression.mutate_prefix();
+            }
+            Prefix::Index(index_expression) => {
+                current = index_expression.mutate_prefix();
+            }
+            Prefix::Identifier(identifier) => break identifier_get_first_token(identifier),
+            Prefix::Parenthese(parenthese_expression) => {
+                break parentheses_get_first_token(parenthese_expression)
+            }
+        }
+    }
+}
+
+fn identifier_get_first_token(identifier: &mut Identifier) -> &mut Token {
+    if identifier.get_token().is_none() {
+        let name = identifier.get_name().to_owned();
+        identifier.set_token(Token::from_content(name));
+    }
+    identifier.mutate_token().unwrap()
+}
+
+fn parentheses_get_first_token(parentheses: &mut ParentheseExpression) -> &mut Token {
+    if parentheses.get_tokens().is_none() {
+        parentheses.set_tokens(ParentheseTokens {
+            left_parenthese: Token::from_content("("),
+            right_parenthese: Token::from_content(")"),
+        });
+    }
+    &mut parentheses.mutate_tokens().unwrap().left_parenthese
+}
@@ -0,0 +1,620 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::nodes::{
+    AssignStatement, Block, CompoundAssignStatement, Expression, FunctionExpression,
+    FunctionStatement, Identifier, LocalAssignStatement, LocalFunctionStatement, Token, Variable,
+};
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor, Scope, ScopeVisitor};
+use crate::rules::{
+    levenshtein_distance, Context, FlawlessRule, RuleConfiguration, RuleConfigurationError,
+    RuleProperties, RulePropertyKind, RulePropertyValue,
+};
+use crate::utils::log;
+
+const MAXIMUM_TYPO_DISTANCE: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GlobalLeakKind {
+    /// The global is assigned while a local with a close name is in scope.
+    Typo { local: String },
+    /// The global only appears inside a single function.
+    MissingLocal { function_line: Option<usize> },
+}
+
+/// An assignment to a global variable that was probably meant to be a local variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GlobalLeak {
+    name: String,
+    line: Option<usize>,
+    kind: GlobalLeakKind,
+}
+
+impl fmt::Display for GlobalLeak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "global `{}`", self.name)?;
+        if let Some(line) = self.line {
+            write!(f, " (line {})", line)?;
+        }
+        match &self.kind {
+            GlobalLeakKind::Typo { local } => write!(
+                f,
+                " is assigned before being read: did you mean the local `{}`?",
+                local
+            ),
+            GlobalLeakKind::MissingLocal { function_line } => {
+                write!(f, " is only used inside the function")?;
+                if let Some(line) = function_line {
+                    write!(f, " at line {}", line)?;
+                }
+                write!(
+                    f,
+                    ": declare it with `local {}` at the start of the function",
+                    self.name
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AccessKind {
+    Read,
+    /// An assignment, with a flag to know if the assigned values read the global.
+    Write {
+        read_in_value: bool,
+    },
+}
+
+#[derive(Debug)]
+struct GlobalAccess {
+    kind: AccessKind,
+    function: Option<usize>,
+    line: Option<usize>,
+    similar_local: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct ScopeNames {
+    locals: HashSet<String>,
+    function: Option<usize>,
+}
+
+/// Collects the names of the variables read by an expression.
+#[derive(Default)]
+struct ReadNames {
+    names: HashSet<String>,
+}
+
+impl NodeProcessor for ReadNames {
+    fn process_variable_expression(&mut self, variable: &mut Identifier) {
+        self.names.insert(variable.get_name().to_owned());
+    }
+}
+
+/// Records every access to a global variable, in the order of the code. When fixes are
+/// given, a local declaration is added at the start of the functions that need it.
+#[derive(Default)]
+struct GlobalAccessFinder {
+    scopes: Vec<ScopeNames>,
+    accesses: Vec<(String, Vec<GlobalAccess>)>,
+    access_indexes: HashMap<String, usize>,
+    function_lines: Vec<Option<usize>>,
+    entering_function: Option<usize>,
+    next_write: Option<bool>,
+    fixes: HashMap<usize, Vec<String>>,
+}
+
+impl GlobalAccessFinder {
+    fn with_fixes(fixes: HashMap<usize, Vec<String>>) -> Self {
+        Self {
+            fixes,
+            ..Default::default()
+        }
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.locals.contains(name))
+    }
+
+    fn current_function(&self) -> Option<usize> {
+        self.scopes.iter().rev().find_map(|scope| scope.function)
+    }
+
+    fn declare(&mut self, name: &str) {
+        if self.scopes.is_empty() {
+            self.scopes.push(ScopeNames::default());
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.locals.insert(name.to_owned());
+        }
+    }
+
+    fn enter_function(&mut self, line: Option<usize>) {
+        self.entering_function = Some(self.function_lines.len());
+        self.function_lines.push(line);
+    }
+
+    /// Finds the closest local name, if it is close enough to be a likely typo.
+    fn find_similar_local(&self, name: &str) -> Option<String> {
+        let length = name.chars().count();
+
+        self.scopes
+            .iter()
+            .flat_map(|scope| scope.locals.iter())
+            .map(|local| (levenshtein_distance(name, local), local))
+            .filter(|(distance, _)| {
+                *distance > 0 && *distance <= MAXIMUM_TYPO_DISTANCE && distance * 3 <= length
+            })
+            .min_by(|(a, a_name), (b, b_name)| a.cmp(b).then_with(|| a_name.cmp(b_name)))
+            .map(|(_, local)| local.clone())
+    }
+
+    fn record(&mut self, identifier: &Identifier, kind: AccessKind) {
+        let name = identifier.get_name();
+
+        if self.is_local(name) {
+            return;
+        }
+
+        let access = GlobalAccess {
+            kind,
+            function: self.current_function(),
+            line: identifier.get_token().and_then(Token::get_line_number),
+            similar_local: match kind {
+                AccessKind::Write { .. } => self.find_similar_local(name),
+                AccessKind::Read => None,
+            },
+        };
+
+        match self.access_indexes.get(name) {
+            Some(index) => self.accesses[*index].1.push(access),
+            None => {
+                self.access_indexes
+                    .insert(name.to_owned(), self.accesses.len());
+                self.accesses.push((name.to_owned(), vec![access]));
+            }
+        }
+    }
+
+    fn find_leaks(&self, known_globals: &[String]) -> Vec<(GlobalLeak, Option<usize>)> {
+        let mut leaks = Vec::new();
+
+        for (name, accesses) in self.accesses.iter() {
+            if known_globals.contains(name) {
+                continue;
+            }
+
+            let mut has_read = false;
+            let mut has_typo = false;
+
+            for access in accesses {
+                match access.kind {
+                    AccessKind::Read => has_read = true,
+                    AccessKind::Write { read_in_value } => {
+                        if has_read || read_in_value {
+                            has_read = true;
+                            continue;
+                        }
+                        if let Some(local) = access.similar_local.as_ref() {
+                            has_typo = true;
+                            leaks.push((
+                                GlobalLeak {
+                                    name: name.clone(),
+                                    line: access.line,
+                                    kind: GlobalLeakKind::Typo {
+                                        local: local.clone(),
+                                    },
+                                },
+                                None,
+                            ));
+                        }
+                    }
+                }
+            }
+
+            if has_typo {
+                continue;
+            }
+
+            let first = &accesses[0];
+            let starts_with_write = matches!(
+                first.kind,
+                AccessKind::Write {
+                    read_in_value: false
+                }
+            );
+
+            if let Some(function) = first.function.filter(|_| starts_with_write) {
+                if accesses
+                    .iter()
+                    .all(|access| access.function == Some(function))
+                {
+                    leaks.push((
+                        GlobalLeak {
+                            name: name.clone(),
+                            line: first.line,
+                            kind: GlobalLeakKind::MissingLocal {
+                                function_line: self.function_lines[function],
+                            },
+                        },
+                        Some(function),
+                    ));
+                }
+            }
+        }
+
+        leaks
+    }
+}
+
+impl Scope for GlobalAccessFinder {
+    fn push(&mut self) {
+        self.scopes.push(ScopeNames {
+            locals: HashSet::new(),
+            function: self.entering_function.take(),
+        });
+    }
+
+    fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.declare(identifier.get_name());
+    }
+
+    fn insert_self(&mut self) {
+        self.declare("self");
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, _value: Option<&mut Expression>) {
+        self.declare(identifier.get_name());
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.declare(function.get_name());
+    }
+}
+
+impl NodeProcessor for GlobalAccessFinder {
+    fn process_scope(&mut self, block: &mut Block, _extra: Option<&mut Expression>) {
+        let Some(function) = self.scopes.last().and_then(|scope| scope.function) else {
+            return;
+        };
+
+        if let Some(names) = self.fixes.remove(&function) {
+            let mut declaration = LocalAssignStatement::new(Vec::new(), Vec::new());
+            for name in names {
+                declaration.push_variable(Identifier::new(name));
+            }
+            block.insert_statement(0, declaration);
+        }
+    }
+
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        let mut reads = ReadNames::default();
+        for value in assign.iter_mut_values() {
+            DefaultVisitor::visit_expression(value, &mut reads);
+        }
+
+        for variable in assign.iter_variables() {
+            if let Variable::Identifier(identifier) = variable {
+                self.record(
+                    identifier,
+                    AccessKind::Write {
+                        read_in_value: reads.names.contains(identifier.get_name()),
+                    },
+                );
+            }
+        }
+    }
+
+    fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
+        if let Variable::Identifier(identifier) = assign.get_variable() {
+            self.record(identifier, AccessKind::Read);
+        }
+    }
+
+    fn process_variable(&mut self, variable: &mut Variable) {
+        // assigned identifiers are recorded with their statement
+        self.next_write = Some(matches!(variable, Variable::Identifier(_)));
+    }
+
+    fn process_variable_expression(&mut self, variable: &mut Identifier) {
+        if self.next_write.take() == Some(true) {
+            return;
+        }
+        self.record(variable, AccessKind::Read);
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let line = function
+            .get_tokens()
+            .and_then(|tokens| tokens.function.get_line_number());
+        self.enter_function(line);
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        let line = function
+            .get_tokens()
+            .and_then(|tokens| tokens.function_body.function.get_line_number());
+        self.enter_function(line);
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        let line = function
+            .get_tokens()
+            .and_then(|tokens| tokens.function.get_line_number());
+        self.enter_function(line);
+    }
+}
+
+pub const FIX_GLOBAL_LEAKS_RULE_NAME: &str = "fix_global_leaks";
+
+/// What the [`FixGlobalLeaks`] rule does with the global leaks it finds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GlobalLeakMode {
+    /// Log a warning for each global leak.
+    #[default]
+    Report,
+    /// Declare the globals only used inside a single function as locals of that function,
+    /// and log a warning for the other global leaks.
+    Fix,
+}
+
+impl GlobalLeakMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "report" => Some(Self::Report),
+            "fix" => Some(Self::Fix),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Report => "report",
+            Self::Fix => "fix",
+        }
+    }
+}
+
+/// A rule that finds assignments to globals that were probably meant to be locals: globals
+/// with a name close to a local in scope (a typo), and globals only used inside a single
+/// function (a missing `local`).
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FixGlobalLeaks {
+    mode: GlobalLeakMode,
+    known_globals: Vec<String>,
+}
+
+impl FixGlobalLeaks {
+    pub fn with_mode(mut self, mode: GlobalLeakMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_known_global(mut self, name: impl Into<String>) -> Self {
+        self.known_globals.push(name.into());
+        self
+    }
+}
+
+impl FlawlessRule for FixGlobalLeaks {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut finder = GlobalAccessFinder::default();
+        ScopeVisitor::visit_block(block, &mut finder);
+
+        let mut fixes: HashMap<usize, Vec<String>> = HashMap::new();
+
+        for (leak, function) in finder.find_leaks(&self.known_globals) {
+            match function.filter(|_| self.mode == GlobalLeakMode::Fix) {
+                Some(function) => {
+                    log::debug!(
+                        "[{}] declare `{}` as a local of its function",
+                        context.current_path().display(),
+                        leak.name
+                    );
+                    fixes.entry(function).or_default().push(leak.name);
+                }
+                None => {
+                    log::warn!("[{}] {}", context.current_path().display(), leak);
+                }
+            }
+        }
+
+        if !fixes.is_empty() {
+            let mut fixer = GlobalAccessFinder::with_fixes(fixes);
+            ScopeVisitor::visit_block(block, &mut fixer);
+        }
+    }
+}
+
+impl RuleConfiguration for FixGlobalLeaks {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "mode" => {
+                    let mode = value.expect_string(&key)?;
+                    self.mode = GlobalLeakMode::parse(&mode).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: key.clone(),
+                            message: format!("invalid mode `{}` (must be `report` or `fix`)", mode),
+                        }
+                    })?;
+                }
+                "known_globals" => {
+                    self.known_globals = value.expect_string_list(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        FIX_GLOBAL_LEAKS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["mode", "known_globals"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "known_globals" => RulePropertyKind::StringList,
+            _ => RulePropertyKind::Choice(&["report", "fix"]),
+        }
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.mode != GlobalLeakMode::default() {
+            properties.insert("mode".to_owned(), self.mode.as_str().into());
+        }
+
+        if !self.known_globals.is_empty() {
+            properties.insert(
+                "known_globals".to_owned(),
+                RulePropertyValue::StringList(self.known_globals.clone()),
+            );
+        }
+
+        properties
+    }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if self.mode == GlobalLeakMode::Fix {
+            properties.push("mode");
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+    use crate::Parser;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> FixGlobalLeaks {
+        FixGlobalLeaks::default()
+    }
+
+    fn find_leaks(code: &str, known_globals: &[&str]) -> Vec<String> {
+        let mut block = Parser::default().preserve_tokens().parse(code).unwrap();
+        let mut finder = GlobalAccessFinder::default();
+        ScopeVisitor::visit_block(&mut block, &mut finder);
+
+        let known_globals: Vec<_> = known_globals.iter().map(ToString::to_string).collect();
+        finder
+            .find_leaks(&known_globals)
+            .iter()
+            .map(|(leak, _)| leak.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_fix_global_leaks", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_properties() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .with_mode(GlobalLeakMode::Fix)
+                .with_known_global("Config"),
+        );
+
+        assert_json_snapshot!("fix_global_leaks_with_properties", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_mode_should_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'fix_global_leaks',
+            mode: 'rewrite',
+        }"#,
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'mode': invalid mode `rewrite` (must be `report` or `fix`)"
+        );
+    }
+
+    #[test]
+    fn typo_of_local_name() {
+        pretty_assertions::assert_eq!(
+            find_leaks(
+                "local playerScore = 0\nlocal function add(x)\n  playerscore = playerScore + x\nend",
+                &[]
+            ),
+            vec!["global `playerscore` (line 3) is assigned before being read: did you mean the local `playerScore`?"]
+        );
+    }
+
+    #[test]
+    fn short_names_are_not_typos() {
+        assert!(find_leaks("local a = 1 b = 2 print(b)", &[]).is_empty());
+    }
+
+    #[test]
+    fn global_only_used_in_one_function() {
+        pretty_assertions::assert_eq!(
+            find_leaks(
+                "local function sum(list)\n  total = 0\n  for _, v in ipairs(list) do\n    total = total + v\n  end\n  return total\nend",
+                &[]
+            ),
+            vec!["global `total` (line 2) is only used inside the function at line 1: declare it with `local total` at the start of the function"]
+        );
+    }
+
+    #[test]
+    fn global_read_before_assignment_is_not_reported() {
+        assert!(find_leaks(
+            "local function get()\n  if not cache then\n    cache = {}\n  end\n  return cache\nend",
+            &[]
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn global_read_by_its_own_assignment_is_not_reported() {
+        assert!(find_leaks("local function f()\n  cache = cache or {}\nend", &[]).is_empty());
+    }
+
+    #[test]
+    fn global_used_in_two_functions_is_not_reported() {
+        assert!(find_leaks(
+            "local function set()\n  value = 1\nend\nlocal function get()\n  return value\nend",
+            &[]
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn known_global_is_not_reported() {
+        assert!(find_leaks(
+            "local playerScore = 0\nplayerscore = 1\nlocal function f()\n  total = 0\nend",
+            &["playerscore", "total"]
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,638 @@
+use std::collections::HashSet;
+use std::mem;
+use std::ops;
+
+use crate::nodes::{
+    Arguments, Block, Expression, FunctionCall, Identifier, LastStatement, LocalFunctionStatement,
+    Prefix, Statement, Token, Trivia, TriviaKind, TupleArguments, TupleArgumentsTokens,
+    TypedIdentifier,
+};
+use crate::process::{
+    DefaultVisitor, Evaluator, IdentifierTracker, NodeProcessor, NodeVisitor, Scope, ScopeVisitor,
+};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
+};
+use crate::utils::log;
+
+use super::verify_no_rule_properties;
+
+/// The comment that must be placed before a local function to flatten it.
+pub const FLATTEN_NEXT_DIRECTIVE: &str = "--!darklua flatten-next";
+
+fn has_directive(function: &LocalFunctionStatement, code: &str) -> bool {
+    function.get_tokens().is_some_and(|tokens| {
+        tokens
+            .local
+            .iter_leading_trivia()
+            .any(|trivia| is_directive(trivia, code))
+    })
+}
+
+fn is_directive(trivia: &Trivia, code: &str) -> bool {
+    trivia.kind() == TriviaKind::Comment && trivia.read(code).trim_end() == FLATTEN_NEXT_DIRECTIVE
+}
+
+/// Removes the directive comment, with the spaces that follow it.
+fn remove_directive(function: &mut LocalFunctionStatement, code: &str) {
+    if let Some(tokens) = function.mutate_tokens() {
+        let mut removed = false;
+        let trivia: Vec<_> = tokens
+            .local
+            .take_leading_trivia()
+            .into_iter()
+            .filter(|trivia| {
+                let removing = is_directive(trivia, code)
+                    || (removed && trivia.kind() == TriviaKind::Whitespace);
+                removed = removing;
+                !removing
+            })
+            .collect();
+        tokens.local.prepend_leading_trivia(trivia);
+    }
+}
+
+fn comma_token() -> Token {
+    Token::from_content(",").with_trailing_trivia(TriviaKind::Whitespace.with_content(" "))
+}
+
+/// Returns true if the block returns from its function, without looking into the
+/// functions defined in the block.
+fn has_return(block: &Block) -> bool {
+    matches!(block.get_last_statement(), Some(LastStatement::Return(_)))
+        || block.iter_statements().any(statement_has_return)
+}
+
+fn statement_has_return(statement: &Statement) -> bool {
+    match statement {
+        Statement::Do(do_statement) => has_return(do_statement.get_block()),
+        Statement::If(if_statement) => {
+            if_statement
+                .iter_branches()
+                .any(|branch| has_return(branch.get_block()))
+                || if_statement.get_else_block().is_some_and(has_return)
+        }
+        Statement::While(while_statement) => has_return(while_statement.get_block()),
+        Statement::Repeat(repeat) => has_return(repeat.get_block()),
+        Statement::NumericFor(numeric_for) => has_return(numeric_for.get_block()),
+        Statement::GenericFor(generic_for) => has_return(generic_for.get_block()),
+        _ => false,
+    }
+}
+
+/// A processor that collects every name declared or referenced in the visited nodes.
+#[derive(Default)]
+struct NameCollector {
+    names: HashSet<String>,
+}
+
+impl Scope for NameCollector {
+    fn push(&mut self) {}
+
+    fn pop(&mut self) {}
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.names.insert(identifier.get_name().to_owned());
+    }
+
+    fn insert_self(&mut self) {
+        self.names.insert("self".to_owned());
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, _value: Option<&mut Expression>) {
+        self.names.insert(identifier.get_name().to_owned());
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.names.insert(function.get_name().to_owned());
+    }
+}
+
+impl NodeProcessor for NameCollector {
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        self.names.insert(identifier.get_name().to_owned());
+    }
+}
+
+/// Verifies that a function can be merged with the function it returns. Returns the
+/// number of parameters of the function.
+fn check_function(function: &mut LocalFunctionStatement) -> Result<usize, String> {
+    if function.is_variadic() {
+        return Err("the function is variadic".to_owned());
+    }
+
+    let block = function.get_block();
+    let inner_function = match block.get_last_statement() {
+        Some(LastStatement::Return(statement)) if statement.len() == 1 => {
+            match statement.iter_expressions().next() {
+                Some(Expression::Function(inner_function)) => inner_function,
+                _ => return Err("the function does not return a function".to_owned()),
+            }
+        }
+        _ => return Err("the function does not return a function".to_owned()),
+    };
+
+    if inner_function.is_generic() {
+        return Err("the returned function has generic parameters".to_owned());
+    }
+
+    if block.iter_statements().any(statement_has_return) {
+        return Err("the function has more than one return statement".to_owned());
+    }
+
+    let inner_parameters: Vec<String> = inner_function
+        .iter_parameters()
+        .map(|parameter| parameter.get_name().to_owned())
+        .collect();
+    let parameter_count = function.parameters_count();
+    let name = function.get_name().to_owned();
+
+    let mut collector = NameCollector::default();
+    for parameter in function.iter_parameters() {
+        collector.names.insert(parameter.get_name().to_owned());
+    }
+    for statement in function.mutate_block().iter_mut_statements() {
+        ScopeVisitor::visit_statement(statement, &mut collector);
+    }
+
+    if let Some(parameter) = inner_parameters
+        .iter()
+        .find(|parameter| collector.names.contains(*parameter))
+    {
+        return Err(format!(
+            "the parameter `{}` of the returned function is also used by the function",
+            parameter
+        ));
+    }
+
+    if let Some(last_statement) = function.mutate_block().mutate_last_statement() {
+        ScopeVisitor::visit_last_statement(last_statement, &mut collector);
+    }
+
+    if collector.names.contains(&name) {
+        return Err("the function refers to its own name".to_owned());
+    }
+
+    Ok(parameter_count)
+}
+
+/// Merges the function with the function it returns, once `check_function` succeeds.
+fn merge_function(function: &mut LocalFunctionStatement) {
+    let block = function.mutate_block();
+    let mut inner_function = match block.take_last_statement() {
+        Some(LastStatement::Return(statement)) => match statement.into_iter_expressions().next() {
+            Some(Expression::Function(inner_function)) => inner_function,
+            _ => unreachable!("the function should return a function"),
+        },
+        _ => unreachable!("the function should end with a return statement"),
+    };
+
+    let inner_block = inner_function.mutate_block();
+    for statement in inner_block.take_statements() {
+        block.push_statement(statement);
+    }
+    if let Some(last_statement) = inner_block.take_last_statement() {
+        block.set_last_statement(last_statement);
+    }
+
+    // the parameters are moved without their tokens, since their position is on the line of
+    // the returned function
+    for mut parameter in inner_function.mutate_parameters().drain(..) {
+        let mut moved_parameter = TypedIdentifier::new(parameter.get_name().to_owned());
+        if let Some(r#type) = parameter.remove_type() {
+            moved_parameter = moved_parameter.with_type(r#type);
+        }
+        function.mutate_parameters().push(moved_parameter);
+    }
+
+    if inner_function.is_variadic() {
+        match inner_function.get_variadic_type() {
+            Some(variadic_type) => function.set_variadic_type(variadic_type.clone()),
+            None => function.set_variadic(true),
+        }
+    }
+
+    // a comma is written between each parameter and before `...`
+    let comma_count =
+        (function.parameters_count() + usize::from(function.is_variadic())).saturating_sub(1);
+    if let Some(tokens) = function.mutate_tokens() {
+        while tokens.parameter_commas.len() < comma_count {
+            tokens.parameter_commas.push(comma_token());
+        }
+    }
+
+    function.take_return_type();
+    if let Some(return_type) = inner_function.get_return_type() {
+        function.set_return_type(return_type.clone());
+    }
+}
+
+/// Returns the first call of `name(a)(b)`, when the call matches this shape.
+fn get_first_call<'a>(call: &'a FunctionCall, name: &str) -> Option<&'a FunctionCall> {
+    if call.get_method().is_some() {
+        return None;
+    }
+    match call.get_prefix() {
+        Prefix::Call(first_call) if first_call.get_method().is_none() => {
+            match first_call.get_prefix() {
+                Prefix::Identifier(identifier) if identifier.get_name() == name => Some(first_call),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn check_call_site(
+    first_arguments: &Arguments,
+    second_arguments: &Arguments,
+    parameter_count: usize,
+) -> Result<(), String> {
+    let evaluator = Evaluator::default();
+
+    if first_arguments.len() > parameter_count {
+        return Err("a call passes more arguments than the function parameters".to_owned());
+    }
+
+    if first_arguments.len() < parameter_count
+        && first_arguments
+            .iter_expressions()
+            .last()
+            .is_some_and(|argument| evaluator.can_return_multiple_values(&argument))
+    {
+        return Err("a call passes a variable number of arguments".to_owned());
+    }
+
+    if second_arguments
+        .iter_expressions()
+        .any(|argument| evaluator.has_side_effects(&argument))
+    {
+        return Err(
+            "the arguments of a call to the returned function may have side effects".to_owned(),
+        );
+    }
+
+    Ok(())
+}
+
+/// A processor that verifies that every reference to the function is called and that its
+/// result is immediately called.
+struct CallSiteUsage<'a> {
+    name: &'a str,
+    parameter_count: usize,
+    identifier_tracker: IdentifierTracker,
+    references: usize,
+    collapsible_calls: usize,
+    issue: Option<String>,
+}
+
+impl<'a> CallSiteUsage<'a> {
+    fn new(name: &'a str, parameter_count: usize) -> Self {
+        Self {
+            name,
+            parameter_count,
+            identifier_tracker: IdentifierTracker::default(),
+            references: 0,
+            collapsible_calls: 0,
+            issue: None,
+        }
+    }
+
+    fn is_function_visible(&self) -> bool {
+        !self.identifier_tracker.is_identifier_used(self.name)
+    }
+
+    fn result(self) -> Result<(), String> {
+        if let Some(issue) = self.issue {
+            Err(issue)
+        } else if self.references != self.collapsible_calls {
+            Err(format!(
+                "the result of a call to `{}` is not immediately called (like `local f = {}(...)`)",
+                self.name, self.name
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl ops::Deref for CallSiteUsage<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for CallSiteUsage<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for CallSiteUsage<'_> {
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if !self.is_function_visible() {
+            return;
+        }
+        if let Some(first_call) = get_first_call(call, self.name) {
+            match check_call_site(
+                first_call.get_arguments(),
+                call.get_arguments(),
+                self.parameter_count,
+            ) {
+                Ok(()) => self.collapsible_calls += 1,
+                Err(issue) => {
+                    self.issue.get_or_insert(issue);
+                }
+            }
+        }
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        if identifier.get_name() == self.name && self.is_function_visible() {
+            self.references += 1;
+        }
+    }
+}
+
+/// A processor that replaces the calls `name(a)(b)` with `name(a, b)`.
+struct CallSiteReplacer<'a> {
+    name: &'a str,
+    parameter_count: usize,
+    identifier_tracker: IdentifierTracker,
+}
+
+impl<'a> CallSiteReplacer<'a> {
+    fn new(name: &'a str, parameter_count: usize) -> Self {
+        Self {
+            name,
+            parameter_count,
+            identifier_tracker: IdentifierTracker::default(),
+        }
+    }
+}
+
+impl ops::Deref for CallSiteReplacer<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for CallSiteReplacer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for CallSiteReplacer<'_> {
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if self.is_identifier_used(self.name) || get_first_call(call, self.name).is_none() {
+            return;
+        }
+
+        let mut first_call = match mem::replace(call.mutate_prefix(), Prefix::from_name("")) {
+            Prefix::Call(first_call) => first_call,
+            _ => unreachable!("the call should be made on the result of a call"),
+        };
+
+        let first_arguments = mem::replace(
+            first_call.mutate_arguments(),
+            TupleArguments::default().into(),
+        )
+        .to_tuple();
+        let second_arguments =
+            mem::replace(call.mutate_arguments(), TupleArguments::default().into()).to_tuple();
+
+        let tokens = match (first_arguments.get_tokens(), second_arguments.get_tokens()) {
+            (Some(first_tokens), Some(second_tokens)) => Some(TupleArgumentsTokens {
+                opening_parenthese: first_tokens.opening_parenthese.clone(),
+                closing_parenthese: second_tokens.closing_parenthese.clone(),
+                commas: first_tokens.commas.clone(),
+            }),
+            _ => None,
+        };
+
+        let missing_arguments = self.parameter_count - first_arguments.len();
+        let mut arguments: TupleArguments = first_arguments
+            .to_expressions()
+            .into_iter()
+            .chain((0..missing_arguments).map(|_| Expression::nil()))
+            .chain(second_arguments.to_expressions())
+            .collect();
+
+        if let Some(mut tokens) = tokens {
+            while tokens.commas.len() + 1 < arguments.len() {
+                tokens.commas.push(comma_token());
+            }
+            arguments.set_tokens(tokens);
+        }
+
+        first_call.set_arguments(arguments.into());
+        *call = first_call;
+    }
+}
+
+struct FlattenClosuresProcessor<'a> {
+    original_code: &'a str,
+    diagnostics: Vec<String>,
+}
+
+impl<'a> FlattenClosuresProcessor<'a> {
+    fn new(original_code: &'a str) -> Self {
+        Self {
+            original_code,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn check_call_sites(
+        name: &str,
+        parameter_count: usize,
+        block: &mut Block,
+        index: usize,
+        extra: &mut Option<&mut Expression>,
+    ) -> Result<(), String> {
+        let mut usage = CallSiteUsage::new(name, parameter_count);
+
+        for statement in block.iter_mut_statements().skip(index + 1) {
+            ScopeVisitor::visit_statement(statement, &mut usage);
+        }
+        if let Some(last_statement) = block.mutate_last_statement() {
+            ScopeVisitor::visit_last_statement(last_statement, &mut usage);
+        }
+        if let Some(expression) = extra {
+            ScopeVisitor::visit_expression(expression, &mut usage);
+        }
+
+        usage.result()
+    }
+
+    fn replace_call_sites(
+        name: &str,
+        parameter_count: usize,
+        block: &mut Block,
+        index: usize,
+        extra: &mut Option<&mut Expression>,
+    ) {
+        let mut replacer = CallSiteReplacer::new(name, parameter_count);
+
+        for statement in block.iter_mut_statements().skip(index + 1) {
+            ScopeVisitor::visit_statement(statement, &mut replacer);
+        }
+        if let Some(last_statement) = block.mutate_last_statement() {
+            ScopeVisitor::visit_last_statement(last_statement, &mut replacer);
+        }
+        if let Some(expression) = extra {
+            ScopeVisitor::visit_expression(expression, &mut replacer);
+        }
+    }
+
+    fn get_function(block: &mut Block, index: usize) -> Option<&mut LocalFunctionStatement> {
+        match block.iter_mut_statements().nth(index) {
+            Some(Statement::LocalFunction(function)) => Some(function),
+            _ => None,
+        }
+    }
+
+    /// Merges the function with the function it returns, as long as every call site calls
+    /// its result immediately. Returns the number of merged functions.
+    fn flatten(
+        block: &mut Block,
+        index: usize,
+        extra: &mut Option<&mut Expression>,
+    ) -> Result<usize, String> {
+        let mut merged = 0;
+
+        loop {
+            let function = match Self::get_function(block, index) {
+                Some(function) => function,
+                None => return Ok(merged),
+            };
+            let name = function.get_name().to_owned();
+
+            let result = check_function(function).and_then(|parameter_count| {
+                Self::check_call_sites(&name, parameter_count, block, index, extra)
+                    .map(|()| parameter_count)
+            });
+
+            let parameter_count = match result {
+                Ok(parameter_count) => parameter_count,
+                Err(issue) if merged == 0 => return Err(issue),
+                Err(_) => return Ok(merged),
+            };
+
+            if let Some(function) = Self::get_function(block, index) {
+                merge_function(function);
+            }
+            Self::replace_call_sites(&name, parameter_count, block, index, extra);
+            merged += 1;
+        }
+    }
+}
+
+impl NodeProcessor for FlattenClosuresProcessor<'_> {
+    fn process_scope(&mut self, block: &mut Block, mut extra: Option<&mut Expression>) {
+        for index in 0..block.statements_len() {
+            let name = match Self::get_function(block, index) {
+                Some(function) if has_directive(function, self.original_code) => {
+                    function.get_name().to_owned()
+                }
+                _ => continue,
+            };
+
+            match Self::flatten(block, index, &mut extra) {
+                Ok(_) => {
+                    if let Some(function) = Self::get_function(block, index) {
+                        remove_directive(function, self.original_code);
+                    }
+                }
+                Err(issue) => {
+                    self.diagnostics
+                        .push(format!("unable to flatten `{}`: {}", name, issue));
+                }
+            }
+        }
+    }
+}
+
+pub const FLATTEN_CLOSURES_RULE_NAME: &str = "flatten_closures";
+
+/// A rule that merges a local function annotated with a `--!darklua flatten-next` comment
+/// with the function it returns, when every call to the function immediately calls its
+/// result (`f(a)(b)` becomes `f(a, b)`).
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FlattenClosures {}
+
+impl FlawlessRule for FlattenClosures {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor = FlattenClosuresProcessor::new(context.original_code());
+        processor.process_scope(block, None);
+        DefaultVisitor::visit_block(block, &mut processor);
+
+        for diagnostic in processor.diagnostics {
+            log::warn!(
+                "[{}] {}: {}",
+                context.current_path().display(),
+                FLATTEN_CLOSURES_RULE_NAME,
+                diagnostic
+            );
+        }
+    }
+}
+
+impl RuleConfiguration for FlattenClosures {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)?;
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        FLATTEN_CLOSURES_RULE_NAME
+    }
+
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "--!darklua flatten-next\n\
+                local function add(a) return function(b) return a + b end end\n\
+                print(add(1)(2), add(3)(4))",
+        )]
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> FlattenClosures {
+        FlattenClosures::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_flatten_closures", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'flatten_closures',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -0,0 +1,286 @@
+use crate::nodes::{Arguments, Block, FunctionCall, Prefix, Token, Trivia, TriviaKind};
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind,
+};
+
+const DEFAULT_MIN_LINKS: usize = 3;
+
+struct Processor<'a> {
+    code: &'a str,
+    min_links: usize,
+}
+
+impl<'a> Processor<'a> {
+    fn new(code: &'a str, min_links: usize) -> Self {
+        Self { code, min_links }
+    }
+
+    /// Returns the indentation used for the links of the chain: the indentation of the line
+    /// where the chain starts, plus one level.
+    fn link_indentation(&self, call: &FunctionCall) -> String {
+        let line_indentation = first_token(call.get_prefix())
+            .and_then(Token::get_start_position)
+            .and_then(|start| {
+                let line_start = self
+                    .code
+                    .get(..start)?
+                    .rfind('\n')
+                    .map(|index| index + 1)
+                    .unwrap_or(0);
+                Some(
+                    self.code[line_start..]
+                        .chars()
+                        .take_while(|character| *character == ' ' || *character == '\t')
+                        .collect::<String>(),
+                )
+            })
+            .unwrap_or_default();
+
+        let level = if line_indentation.contains('\t') {
+            "\t"
+        } else {
+            "    "
+        };
+
+        format!("{}{}", line_indentation, level)
+    }
+
+    fn format_prefix(&self, prefix: &mut Prefix, indentation: &str) {
+        match prefix {
+            Prefix::Call(call) => self.format_link(call, indentation),
+            Prefix::Field(field) => self.format_prefix(field.mutate_prefix(), indentation),
+            Prefix::Index(index) => self.format_prefix(index.mutate_prefix(), indentation),
+            Prefix::Identifier(_) | Prefix::Parenthese(_) => {}
+        }
+    }
+
+    fn format_link(&self, call: &mut FunctionCall, indentation: &str) {
+        self.format_prefix(call.mutate_prefix(), indentation);
+
+        if call.get_method().is_none() {
+            return;
+        }
+
+        let colon_exists = call
+            .get_tokens()
+            .and_then(|tokens| tokens.colon.as_ref())
+            .is_some();
+        if !colon_exists {
+            return;
+        }
+
+        let Some(previous_token) = last_token_mut(call.mutate_prefix()) else {
+            return;
+        };
+
+        let mut cursor_line = previous_token.get_line_number();
+
+        // keep the comments that follow the previous link on its line
+        let trailing_comments: Vec<_> = previous_token
+            .take_trailing_trivia()
+            .into_iter()
+            .filter(|trivia| trivia.kind() == TriviaKind::Comment)
+            .collect();
+        for comment in trailing_comments {
+            previous_token.push_trailing_trivia(TriviaKind::Whitespace.with_content(" "));
+            previous_token.push_trailing_trivia(comment);
+        }
+
+        let colon = call
+            .mutate_tokens()
+            .and_then(|tokens| tokens.colon.as_mut())
+            .expect("colon token should exist");
+
+        let leading_comments: Vec<_> = colon
+            .take_leading_trivia()
+            .into_iter()
+            .filter(|trivia| trivia.kind() == TriviaKind::Comment)
+            .collect();
+
+        for comment in leading_comments {
+            let comment_line = comment.get_line_number();
+            colon.push_leading_trivia(new_line(cursor_line, comment_line, indentation));
+            colon.push_leading_trivia(comment);
+            cursor_line = comment_line.or(cursor_line);
+        }
+
+        let colon_line = colon.get_line_number();
+        colon.push_leading_trivia(new_line(cursor_line, colon_line, indentation));
+    }
+}
+
+/// Creates the whitespace that starts a new line before a link. Blank lines from the
+/// original code are preserved.
+fn new_line(cursor_line: Option<usize>, target_line: Option<usize>, indentation: &str) -> Trivia {
+    let line_count = cursor_line
+        .zip(target_line)
+        .map(|(cursor, target)| target.saturating_sub(cursor))
+        .unwrap_or(0)
+        .max(1);
+
+    TriviaKind::Whitespace.with_content(format!("{}{}", "\n".repeat(line_count), indentation))
+}
+
+fn count_links(prefix: &Prefix) -> usize {
+    match prefix {
+        Prefix::Call(call) => 1 + count_links(call.get_prefix()),
+        Prefix::Field(field) => count_links(field.get_prefix()),
+        Prefix::Index(index) => count_links(index.get_prefix()),
+        Prefix::Identifier(_) | Prefix::Parenthese(_) => 0,
+    }
+}
+
+fn first_token(prefix: &Prefix) -> Option<&Token> {
+    match prefix {
+        Prefix::Call(call) => first_token(call.get_prefix()),
+        Prefix::Field(field) => first_token(field.get_prefix()),
+        Prefix::Index(index) => first_token(index.get_prefix()),
+        Prefix::Identifier(identifier) => identifier.get_token(),
+        Prefix::Parenthese(parenthese) => parenthese
+            .get_tokens()
+            .map(|tokens| &tokens.left_parenthese),
+    }
+}
+
+fn last_token_mut(prefix: &mut Prefix) -> Option<&mut Token> {
+    match prefix {
+        Prefix::Call(call) => match call.mutate_arguments() {
+            Arguments::Tuple(tuple) => tuple
+                .mutate_tokens()
+                .map(|tokens| &mut tokens.closing_parenthese),
+            Arguments::String(string) => string.mutate_token(),
+            Arguments::Table(table) => table
+                .mutate_tokens()
+                .map(|tokens| &mut tokens.closing_brace),
+        },
+        Prefix::Field(field) => field.mutate_field().mutate_token(),
+        Prefix::Index(index) => index
+            .mutate_tokens()
+            .map(|tokens| &mut tokens.closing_bracket),
+        Prefix::Identifier(identifier) => identifier.mutate_token(),
+        Prefix::Parenthese(parenthese) => parenthese
+            .mutate_tokens()
+            .map(|tokens| &mut tokens.right_parenthese),
+    }
+}
+
+impl NodeProcessor for Processor<'_> {
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if 1 + count_links(call.get_prefix()) < self.min_links {
+            return;
+        }
+
+        let indentation = self.link_indentation(call);
+        self.format_link(call, &indentation);
+    }
+}
+
+pub const FORMAT_CALL_CHAINS_RULE_NAME: &str = "format_call_chains";
+
+/// A rule that puts each method call of long call chains on its own line.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FormatCallChains {
+    min_links: usize,
+}
+
+impl Default for FormatCallChains {
+    fn default() -> Self {
+        Self {
+            min_links: DEFAULT_MIN_LINKS,
+        }
+    }
+}
+
+impl FormatCallChains {
+    pub fn with_min_links(mut self, min_links: usize) -> Self {
+        self.min_links = min_links;
+        self
+    }
+}
+
+impl FlawlessRule for FormatCallChains {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor = Processor::new(context.original_code(), self.min_links);
+        DefaultVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for FormatCallChains {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "min_links" => {
+                    self.min_links = value.expect_usize(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        FORMAT_CALL_CHAINS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["min_links"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Usize
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.min_links != DEFAULT_MIN_LINKS {
+            properties.insert("min_links".to_owned(), self.min_links.into());
+        }
+
+        properties
+    }
+
+    fn is_formatting_rule(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> FormatCallChains {
+        FormatCallChains::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_format_call_chains", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_min_links() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_min_links(5));
+
+        assert_json_snapshot!("format_call_chains_min_links", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'format_call_chains',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -0,0 +1,786 @@
+use std::mem;
+
+use crate::nodes::{
+    Arguments, BinaryExpression, BinaryOperator, Block, Expression, FieldExpression, FunctionCall,
+    FunctionStatement, Identifier, LastStatement, LocalAssignStatement, LocalFunctionStatement,
+    Prefix, Statement, TableEntry, TableExpression, Token, TupleArguments, TupleArgumentsTokens,
+    UnaryExpression, Variable,
+};
+use crate::process::{
+    DefaultVisitor, IdentifierTracker, LuaVersion, NodeProcessor, NodeVisitor, Scope, ScopeVisitor,
+};
+use crate::rules::{
+    Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult, RuleProperties,
+    RulePropertyKind, RulePropertyValue,
+};
+use crate::utils::log;
+
+const DEFAULT_ALLOWED_FUNCTIONS: &[&str] = &[
+    "ipairs",
+    "next",
+    "pairs",
+    "rawequal",
+    "rawget",
+    "rawlen",
+    "table.concat",
+    "table.find",
+    "table.unpack",
+    "type",
+    "typeof",
+    "unpack",
+];
+
+/// Returns the table of a `table.freeze({ ... })` call.
+fn get_frozen_table(call: &FunctionCall) -> Option<&TableExpression> {
+    if call.get_method().is_some() {
+        return None;
+    }
+    match call.get_prefix() {
+        Prefix::Field(field)
+            if field.get_field().get_name() == "freeze"
+                && matches!(field.get_prefix(), Prefix::Identifier(identifier) if identifier.get_name() == "table") =>
+            {}
+        _ => return None,
+    }
+    match call.get_arguments() {
+        Arguments::Table(table) => Some(table),
+        Arguments::Tuple(tuple) if tuple.len() == 1 => match tuple.iter_values().next() {
+            Some(Expression::Table(table)) => Some(table),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn mutate_frozen_table(call: &mut FunctionCall) -> Option<&mut TableExpression> {
+    get_frozen_table(call)?;
+    match call.mutate_arguments() {
+        Arguments::Table(table) => Some(table),
+        Arguments::Tuple(tuple) => match tuple.iter_mut_values().next() {
+            Some(Expression::Table(table)) => Some(table),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the table of a constructor and whether it is already frozen.
+fn as_table(expression: &Expression) -> Option<(&TableExpression, bool)> {
+    match expression {
+        Expression::Table(table) => Some((table, false)),
+        Expression::Call(call) => get_frozen_table(call).map(|table| (table, true)),
+        _ => None,
+    }
+}
+
+fn is_literal(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::False(_)
+            | Expression::Nil(_)
+            | Expression::Number(_)
+            | Expression::String(_)
+            | Expression::True(_)
+    )
+}
+
+fn is_constant_value(expression: &Expression) -> bool {
+    is_literal(expression)
+        || as_table(expression).is_some_and(|(table, _)| is_constant_table(table))
+}
+
+/// Returns true when the table only contains literal entries (with literal keys) or nested
+/// tables that are also constant.
+fn is_constant_table(table: &TableExpression) -> bool {
+    table.iter_entries().all(|entry| match entry {
+        TableEntry::Field(entry) => is_constant_value(entry.get_value()),
+        TableEntry::Index(entry) => {
+            is_literal(entry.get_key()) && is_constant_value(entry.get_value())
+        }
+        TableEntry::Value(value) => is_constant_value(value),
+    })
+}
+
+/// Returns true when a nested table of the given table is not frozen yet.
+fn contains_unfrozen_table(table: &TableExpression) -> bool {
+    table.iter_entries().any(|entry| {
+        let value = match entry {
+            TableEntry::Field(entry) => entry.get_value(),
+            TableEntry::Index(entry) => entry.get_value(),
+            TableEntry::Value(value) => value,
+        };
+        as_table(value).is_some_and(|(table, frozen)| !frozen || contains_unfrozen_table(table))
+    })
+}
+
+/// A key of an access chain: the name of a field (or a string index) or `None` when the key
+/// is not known.
+type Key = Option<String>;
+
+fn push_prefix_chain<'a>(prefix: &'a Prefix, keys: &mut Vec<Key>) -> Option<&'a Identifier> {
+    match prefix {
+        Prefix::Identifier(identifier) => Some(identifier),
+        Prefix::Field(field) => push_field_chain(field, keys),
+        Prefix::Index(index) => {
+            let root = push_prefix_chain(index.get_prefix(), keys)?;
+            keys.push(match index.get_index() {
+                Expression::String(string) => Some(string.get_value().to_owned()),
+                _ => None,
+            });
+            Some(root)
+        }
+        Prefix::Call(_) | Prefix::Parenthese(_) => None,
+    }
+}
+
+fn push_field_chain<'a>(field: &'a FieldExpression, keys: &mut Vec<Key>) -> Option<&'a Identifier> {
+    let root = push_prefix_chain(field.get_prefix(), keys)?;
+    keys.push(Some(field.get_field().get_name().to_owned()));
+    Some(root)
+}
+
+/// Returns the identifier at the root of a chain of field and index accesses (like
+/// `t.a[1].b`) with the keys of the chain.
+fn get_prefix_chain(prefix: &Prefix) -> Option<(&Identifier, Vec<Key>)> {
+    let mut keys = Vec::new();
+    push_prefix_chain(prefix, &mut keys).map(|root| (root, keys))
+}
+
+fn get_expression_chain(expression: &Expression) -> Option<(&Identifier, Vec<Key>)> {
+    let mut keys = Vec::new();
+    let root = match expression {
+        Expression::Identifier(identifier) => Some(identifier),
+        Expression::Field(field) => push_field_chain(field, &mut keys),
+        Expression::Index(index) => {
+            let root = push_prefix_chain(index.get_prefix(), &mut keys)?;
+            keys.push(match index.get_index() {
+                Expression::String(string) => Some(string.get_value().to_owned()),
+                _ => None,
+            });
+            Some(root)
+        }
+        _ => None,
+    }?;
+    Some((root, keys))
+}
+
+/// What an access chain can evaluate to: a table of the constant or some other value.
+enum Resolved<'a> {
+    Table {
+        table: &'a TableExpression,
+        frozen: bool,
+    },
+    Value,
+}
+
+impl Resolved<'_> {
+    /// Returns true when the value can be mutated, or gives access to a table that can be
+    /// mutated.
+    fn is_mutable(&self) -> bool {
+        match self {
+            Self::Table { table, frozen } => !frozen || contains_unfrozen_table(table),
+            Self::Value => false,
+        }
+    }
+
+    fn contains_unfrozen_table(&self) -> bool {
+        match self {
+            Self::Table { table, .. } => contains_unfrozen_table(table),
+            Self::Value => false,
+        }
+    }
+}
+
+fn entry_value<'a>(entry: &'a TableEntry, key: &Key) -> Option<&'a Expression> {
+    match (entry, key) {
+        (TableEntry::Field(entry), None) => Some(entry.get_value()),
+        (TableEntry::Index(entry), None) => Some(entry.get_value()),
+        (TableEntry::Value(value), None) => Some(value),
+        (TableEntry::Field(entry), Some(key)) => {
+            (entry.get_field().get_name() == key).then(|| entry.get_value())
+        }
+        (TableEntry::Index(entry), Some(key)) => matches!(
+            entry.get_key(),
+            Expression::String(string) if string.get_value() == key
+        )
+        .then(|| entry.get_value()),
+        (TableEntry::Value(_), Some(_)) => None,
+    }
+}
+
+/// Returns the values that an access chain can evaluate to, starting from the given value.
+fn resolve<'a>(value: &'a Expression, keys: &[Key]) -> Vec<Resolved<'a>> {
+    let (table, frozen) = match as_table(value) {
+        Some(table) => table,
+        None => {
+            return if keys.is_empty() {
+                vec![Resolved::Value]
+            } else {
+                Vec::new()
+            }
+        }
+    };
+
+    match keys.split_first() {
+        None => vec![Resolved::Table { table, frozen }],
+        Some((key, next_keys)) => table
+            .iter_entries()
+            .filter_map(|entry| entry_value(entry, key))
+            .flat_map(|value| resolve(value, next_keys))
+            .collect(),
+    }
+}
+
+/// A processor that finds out if a local table can be mutated after its declaration.
+///
+/// Each read of the variable is counted, and reads that cannot lead to a mutation are
+/// counted separately: the table can be frozen when both counts are equal.
+struct TableUsage<'a> {
+    variable: &'a str,
+    value: &'a Expression,
+    allowed_functions: &'a [String],
+    freeze_exports: bool,
+    identifier_tracker: IdentifierTracker,
+    reads: usize,
+    safe_reads: usize,
+    mutated: bool,
+}
+
+impl<'a> TableUsage<'a> {
+    fn new(
+        variable: &'a str,
+        value: &'a Expression,
+        allowed_functions: &'a [String],
+        freeze_exports: bool,
+    ) -> Self {
+        Self {
+            variable,
+            value,
+            allowed_functions,
+            freeze_exports,
+            identifier_tracker: IdentifierTracker::default(),
+            reads: 0,
+            safe_reads: 0,
+            mutated: false,
+        }
+    }
+
+    fn is_variable(&self, identifier: &Identifier) -> bool {
+        identifier.get_name() == self.variable
+            && !self.identifier_tracker.is_identifier_used(self.variable)
+    }
+
+    fn resolve_chain(&self, chain: Option<(&Identifier, Vec<Key>)>) -> Option<Vec<Resolved<'a>>> {
+        let (root, keys) = chain?;
+        if self.is_variable(root) {
+            Some(resolve(self.value, &keys))
+        } else {
+            None
+        }
+    }
+
+    fn resolve_expression(&self, expression: &Expression) -> Option<Vec<Resolved<'a>>> {
+        self.resolve_chain(get_expression_chain(expression))
+    }
+
+    fn is_mutable(resolved: &[Resolved]) -> bool {
+        resolved.iter().any(Resolved::is_mutable)
+    }
+
+    /// Counts the given expression as a safe read when it is a mutable part of the table
+    /// and the given predicate accepts it. Immutable reads are counted from
+    /// `process_expression`.
+    fn add_safe_read(&mut self, expression: &Expression, predicate: impl Fn(&[Resolved]) -> bool) {
+        if let Some(resolved) = self.resolve_expression(expression) {
+            if Self::is_mutable(&resolved) && predicate(&resolved) {
+                self.safe_reads += 1;
+            }
+        }
+    }
+
+    fn is_allowed_function(&self, call: &FunctionCall) -> bool {
+        if call.get_method().is_some() {
+            return false;
+        }
+        let name = match call.get_prefix() {
+            Prefix::Identifier(identifier) => identifier.get_name().to_owned(),
+            Prefix::Field(field) => match field.get_prefix() {
+                Prefix::Identifier(identifier) => {
+                    format!("{}.{}", identifier.get_name(), field.get_field().get_name())
+                }
+                _ => return false,
+            },
+            _ => return false,
+        };
+        let root = name.split('.').next().unwrap_or_default();
+
+        !self.identifier_tracker.is_identifier_used(root) && self.allowed_functions.contains(&name)
+    }
+}
+
+impl Scope for TableUsage<'_> {
+    fn push(&mut self) {
+        self.identifier_tracker.push();
+    }
+
+    fn pop(&mut self) {
+        self.identifier_tracker.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.identifier_tracker.insert(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.identifier_tracker.insert_self();
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>) {
+        self.identifier_tracker.insert_local(identifier, value);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.identifier_tracker.insert_local_function(function);
+    }
+}
+
+impl NodeProcessor for TableUsage<'_> {
+    fn process_variable(&mut self, variable: &mut Variable) {
+        let root = match variable {
+            Variable::Identifier(identifier) => Some(&*identifier),
+            Variable::Field(field) => get_prefix_chain(field.get_prefix()).map(|(root, _)| root),
+            Variable::Index(index) => get_prefix_chain(index.get_prefix()).map(|(root, _)| root),
+        };
+        if root.is_some_and(|root| self.is_variable(root)) {
+            self.mutated = true;
+        }
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        if self.is_variable(function.get_name().get_name()) {
+            self.mutated = true;
+        }
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        if self.is_variable(identifier) {
+            self.reads += 1;
+        }
+    }
+
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if let Some(resolved) = self.resolve_expression(expression) {
+            if !Self::is_mutable(&resolved) {
+                self.safe_reads += 1;
+            }
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if let Some(resolved) = self.resolve_chain(get_prefix_chain(call.get_prefix())) {
+            if !Self::is_mutable(&resolved) {
+                self.safe_reads += 1;
+            }
+        }
+
+        if !self.is_allowed_function(call) {
+            return;
+        }
+        // allowed functions do not mutate their arguments, but they can return the
+        // values of a table (like `next` or `rawget`), so nested tables must be frozen
+        if let Arguments::Tuple(tuple) = call.get_arguments() {
+            for argument in tuple.iter_values() {
+                self.add_safe_read(argument, |resolved| {
+                    !resolved.iter().any(Resolved::contains_unfrozen_table)
+                });
+            }
+        }
+    }
+
+    fn process_unary_expression(&mut self, unary: &mut UnaryExpression) {
+        self.add_safe_read(unary.get_expression(), |_| true);
+    }
+
+    fn process_binary_expression(&mut self, binary: &mut BinaryExpression) {
+        // `and` and `or` evaluate to one of their operands
+        if matches!(binary.operator(), BinaryOperator::And | BinaryOperator::Or) {
+            return;
+        }
+        self.add_safe_read(binary.left(), |_| true);
+        self.add_safe_read(binary.right(), |_| true);
+    }
+
+    fn process_last_statement(&mut self, statement: &mut LastStatement) {
+        if !self.freeze_exports {
+            return;
+        }
+        if let LastStatement::Return(statement) = statement {
+            for expression in statement.iter_expressions() {
+                self.add_safe_read(expression, |_| true);
+            }
+        }
+    }
+}
+
+fn freeze_table(table: &mut TableExpression) {
+    for entry in table.iter_mut_entries() {
+        let value = match entry {
+            TableEntry::Field(entry) => entry.mutate_value(),
+            TableEntry::Index(entry) => entry.mutate_value(),
+            TableEntry::Value(value) => value,
+        };
+        freeze_value(value);
+    }
+}
+
+/// Wraps the table constructors of the value into `table.freeze` calls, innermost first.
+fn freeze_value(value: &mut Expression) {
+    match value {
+        Expression::Table(table) => {
+            freeze_table(table);
+            // the trivia after the table (like a comment) is kept after the call
+            let trailing_trivia = table
+                .mutate_tokens()
+                .map(|tokens| tokens.closing_brace.take_trailing_trivia());
+            let table = mem::replace(value, Expression::nil());
+
+            let mut arguments = TupleArguments::default().with_argument(table);
+            if let Some(trailing_trivia) = trailing_trivia {
+                let mut closing_parenthese = Token::from_content(")");
+                trailing_trivia
+                    .into_iter()
+                    .for_each(|trivia| closing_parenthese.push_trailing_trivia(trivia));
+                arguments.set_tokens(TupleArgumentsTokens {
+                    opening_parenthese: Token::from_content("("),
+                    closing_parenthese,
+                    commas: Vec::new(),
+                });
+            }
+
+            *value = FunctionCall::from_prefix(FieldExpression::new(
+                Prefix::from_name("table"),
+                "freeze",
+            ))
+            .with_arguments(arguments)
+            .into();
+        }
+        Expression::Call(call) => {
+            if let Some(table) = mutate_frozen_table(call) {
+                freeze_table(table);
+            }
+        }
+        _ => {}
+    }
+}
+
+struct FreezeConstantTablesProcessor<'a> {
+    allowed_functions: &'a [String],
+    freeze_exports: bool,
+}
+
+impl<'a> FreezeConstantTablesProcessor<'a> {
+    fn new(allowed_functions: &'a [String], freeze_exports: bool) -> Self {
+        Self {
+            allowed_functions,
+            freeze_exports,
+        }
+    }
+
+    fn can_freeze(
+        &self,
+        variable: &str,
+        value: &Expression,
+        block: &mut Block,
+        index: usize,
+        extra: &mut Option<&mut Expression>,
+    ) -> bool {
+        let mut usage =
+            TableUsage::new(variable, value, self.allowed_functions, self.freeze_exports);
+
+        for statement in block.iter_mut_statements().skip(index + 1) {
+            ScopeVisitor::visit_statement(statement, &mut usage);
+        }
+        if let Some(last_statement) = block.mutate_last_statement() {
+            ScopeVisitor::visit_last_statement(last_statement, &mut usage);
+        }
+        if let Some(expression) = extra {
+            ScopeVisitor::visit_expression(expression, &mut usage);
+        }
+
+        !usage.mutated && usage.reads == usage.safe_reads
+    }
+
+    /// Returns the name and the value of each variable assigned to a constant table
+    /// constructor that is not frozen yet.
+    fn find_constant_tables(assign: &LocalAssignStatement) -> Vec<(usize, String, Expression)> {
+        let variables = assign.get_variables();
+
+        assign
+            .iter_values()
+            .enumerate()
+            .filter(
+                |(_, value)| matches!(value, Expression::Table(table) if is_constant_table(table)),
+            )
+            .filter_map(|(i, value)| {
+                let name = variables.get(i)?.get_identifier().get_name();
+
+                // when a name is repeated, only the last variable is visible
+                if variables
+                    .iter()
+                    .skip(i + 1)
+                    .any(|next| next.get_identifier().get_name() == name)
+                {
+                    return None;
+                }
+
+                Some((i, name.to_owned(), value.clone()))
+            })
+            .collect()
+    }
+}
+
+impl NodeProcessor for FreezeConstantTablesProcessor<'_> {
+    fn process_scope(&mut self, block: &mut Block, mut extra: Option<&mut Expression>) {
+        for index in 0..block.statements_len() {
+            let tables = match block.iter_statements().nth(index) {
+                Some(Statement::LocalAssign(assign)) => Self::find_constant_tables(assign),
+                _ => continue,
+            };
+
+            for (value_index, variable, value) in tables {
+                if !self.can_freeze(&variable, &value, block, index, &mut extra) {
+                    continue;
+                }
+                if let Some(Statement::LocalAssign(assign)) = block.iter_mut_statements().nth(index)
+                {
+                    if let Some(value) = assign.iter_mut_values().nth(value_index) {
+                        freeze_value(value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub const FREEZE_CONSTANT_TABLES_RULE_NAME: &str = "freeze_constant_tables";
+
+/// A rule that wraps local tables that are never mutated into `table.freeze` calls, on Luau
+/// targets.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FreezeConstantTables {
+    strict_target: bool,
+    freeze_exports: bool,
+    allowed_functions: Vec<String>,
+}
+
+impl Default for FreezeConstantTables {
+    fn default() -> Self {
+        Self {
+            strict_target: false,
+            freeze_exports: false,
+            allowed_functions: DEFAULT_ALLOWED_FUNCTIONS
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl FreezeConstantTables {
+    pub fn with_strict_target(mut self, strict_target: bool) -> Self {
+        self.strict_target = strict_target;
+        self
+    }
+
+    pub fn with_freeze_exports(mut self, freeze_exports: bool) -> Self {
+        self.freeze_exports = freeze_exports;
+        self
+    }
+
+    pub fn with_allowed_function(mut self, name: impl Into<String>) -> Self {
+        self.allowed_functions.push(name.into());
+        self
+    }
+}
+
+impl Rule for FreezeConstantTables {
+    fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
+        let target = context.environment().target();
+
+        if !target.is_some_and(|target| target.lua_version() == LuaVersion::Luau) {
+            let message = match target {
+                Some(target) => format!(
+                    "`table.freeze` is not available for the `{}` target",
+                    target.as_str()
+                ),
+                None => "`table.freeze` is only available on Luau targets".to_owned(),
+            };
+            if self.strict_target {
+                return Err(message);
+            }
+            log::warn!(
+                "[{}] {}: {}",
+                context.current_path().display(),
+                FREEZE_CONSTANT_TABLES_RULE_NAME,
+                message
+            );
+            return Ok(());
+        }
+
+        let mut processor =
+            FreezeConstantTablesProcessor::new(&self.allowed_functions, self.freeze_exports);
+        processor.process_scope(block, None);
+        DefaultVisitor::visit_block(block, &mut processor);
+
+        Ok(())
+    }
+}
+
+impl RuleConfiguration for FreezeConstantTables {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "strict_target" => {
+                    self.strict_target = value.expect_bool(&key)?;
+                }
+                "freeze_exports" => {
+                    self.freeze_exports = value.expect_bool(&key)?;
+                }
+                "allowed_functions" => {
+                    self.allowed_functions = value.expect_string_list(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        FREEZE_CONSTANT_TABLES_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["allowed_functions", "freeze_exports", "strict_target"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "allowed_functions" => RulePropertyKind::StringList,
+            _ => RulePropertyKind::Boolean,
+        }
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.strict_target {
+            properties.insert("strict_target".to_owned(), true.into());
+        }
+
+        if self.freeze_exports {
+            properties.insert("freeze_exports".to_owned(), true.into());
+        }
+
+        if self.allowed_functions != Self::default().allowed_functions {
+            properties.insert(
+                "allowed_functions".to_owned(),
+                RulePropertyValue::StringList(self.allowed_functions.clone()),
+            );
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if self.freeze_exports {
+            properties.push("freeze_exports");
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generator::{LuaGenerator, ReadableLuaGenerator};
+    use crate::process::{Environment, EnvironmentTarget};
+    use crate::rules::ContextBuilder;
+    use crate::{Parser, Resources};
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> FreezeConstantTables {
+        FreezeConstantTables::default()
+    }
+
+    fn process(rule: &FreezeConstantTables, target: EnvironmentTarget, code: &str) -> String {
+        let mut block = Parser::default().parse(code).unwrap();
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new(".", &resources, code)
+            .with_environment(Environment::builtin(target))
+            .build();
+
+        rule.process(&mut block, &context).unwrap();
+
+        let mut generator = ReadableLuaGenerator::new(80);
+        generator.write_block(&block);
+        generator.into_string()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_freeze_constant_tables", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_freeze_exports() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_freeze_exports(true));
+
+        assert_json_snapshot!("freeze_constant_tables_with_freeze_exports", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'freeze_constant_tables',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+
+    #[test]
+    fn freeze_on_roblox_target() {
+        assert_eq!(
+            process(
+                &new_rule(),
+                EnvironmentTarget::Roblox,
+                "local t = { a = 1 } return t.a"
+            ),
+            "local t = table.freeze({a = 1})\n\nreturn t.a\n"
+        );
+    }
+
+    #[test]
+    fn strict_target_errors_without_target() {
+        let code = "local t = { a = 1 } return t.a";
+        let mut block = Parser::default().parse(code).unwrap();
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new(".", &resources, code).build();
+
+        let rule = new_rule().with_strict_target(true);
+
+        assert!(rule.process(&mut block, &context).is_err());
+    }
+}
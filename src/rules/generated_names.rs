@@ -0,0 +1,122 @@
+use xxhash_rust::xxh3::xxh3_64;
+
+/// The prefix given to names generated by darklua when no prefix is configured.
+pub const DEFAULT_GENERATED_NAME_PREFIX: &str = "__DARKLUA_";
+
+/// A placeholder of the generated name prefix replaced with a short hash of the file content.
+pub const GENERATED_NAME_HASH_PLACEHOLDER: &str = "{hash}";
+
+const HASH_LENGTH: usize = 8;
+
+/// Verifies that a generated name prefix can start a Lua identifier once the `{hash}`
+/// placeholder is expanded.
+pub fn verify_generated_name_prefix(prefix: &str) -> Result<(), String> {
+    let expanded = prefix.replace(GENERATED_NAME_HASH_PLACEHOLDER, "0");
+
+    if let Some(invalid) = expanded
+        .chars()
+        .find(|character| !character.is_ascii_alphanumeric() && *character != '_')
+    {
+        return Err(format!(
+            "invalid generated name prefix `{}`: unexpected character `{}` (only letters, digits, \
+            underscores and the `{}` placeholder are allowed)",
+            prefix, invalid, GENERATED_NAME_HASH_PLACEHOLDER
+        ));
+    }
+
+    if expanded
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_digit())
+    {
+        return Err(format!(
+            "invalid generated name prefix `{}`: identifiers cannot start with a digit",
+            prefix
+        ));
+    }
+
+    Ok(())
+}
+
+/// Replaces the `{hash}` placeholder of a generated name prefix with a hash of the given code.
+pub(crate) fn expand_generated_name_prefix(prefix: &str, code: &str) -> String {
+    if prefix.contains(GENERATED_NAME_HASH_PLACEHOLDER) {
+        let hash = format!("{:016x}", xxh3_64(code.as_bytes()));
+        prefix.replace(GENERATED_NAME_HASH_PLACEHOLDER, &hash[..HASH_LENGTH])
+    } else {
+        prefix.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_prefix_is_valid() {
+        assert_eq!(
+            verify_generated_name_prefix(DEFAULT_GENERATED_NAME_PREFIX),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn empty_prefix_is_valid() {
+        assert_eq!(verify_generated_name_prefix(""), Ok(()));
+    }
+
+    #[test]
+    fn prefix_with_hash_is_valid() {
+        assert_eq!(verify_generated_name_prefix("__dk_{hash}_"), Ok(()));
+    }
+
+    #[test]
+    fn prefix_starting_with_hash_is_invalid() {
+        pretty_assertions::assert_eq!(
+            verify_generated_name_prefix("{hash}_"),
+            Err(
+                "invalid generated name prefix `{hash}_`: identifiers cannot start with a digit"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn prefix_starting_with_digit_is_invalid() {
+        assert!(verify_generated_name_prefix("1_").is_err());
+    }
+
+    #[test]
+    fn prefix_with_dash_is_invalid() {
+        pretty_assertions::assert_eq!(
+            verify_generated_name_prefix("dk-"),
+            Err(
+                "invalid generated name prefix `dk-`: unexpected character `-` (only letters, \
+            digits, underscores and the `{hash}` placeholder are allowed)"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn expand_prefix_without_placeholder() {
+        assert_eq!(expand_generated_name_prefix("__dk_", "return 1"), "__dk_");
+    }
+
+    #[test]
+    fn expand_prefix_with_placeholder() {
+        let expanded = expand_generated_name_prefix("__dk_{hash}_", "return 1");
+
+        assert_eq!(expanded.len(), "__dk__".len() + HASH_LENGTH);
+        assert!(expanded.starts_with("__dk_"));
+        assert!(expanded.ends_with('_'));
+    }
+
+    #[test]
+    fn expand_prefix_with_placeholder_differs_by_code() {
+        assert_ne!(
+            expand_generated_name_prefix("__dk_{hash}_", "return 1"),
+            expand_generated_name_prefix("__dk_{hash}_", "return 2")
+        );
+    }
+}
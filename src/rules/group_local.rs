@@ -1,10 +1,12 @@
-use crate::nodes::{Block, Expression, LocalAssignStatement, Statement};
+use crate::nodes::{Block, Expression, LastStatement, LocalAssignStatement, Statement};
 use crate::process::processors::FindVariables;
-use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+use crate::process::{local_assignments_merge_is_safe, DefaultVisitor, NodeProcessor, NodeVisitor};
 use crate::rules::{
     Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
 };
+use crate::utils::log;
 
+use std::collections::VecDeque;
 use std::iter;
 
 use super::verify_no_rule_properties;
@@ -14,11 +16,10 @@ struct GroupLocalProcessor {}
 
 impl GroupLocalProcessor {
     fn filter_statements(&self, block: &mut Block) -> Vec<Statement> {
-        let mut statements = block.take_statements();
+        let mut statements: VecDeque<_> = block.take_statements().into();
         let mut filter_statements = Vec::new();
-        let mut iter = statements.drain(..);
-        let mut previous_statement = iter.next();
-        let mut current_statement = iter.next();
+        let mut previous_statement = statements.pop_front();
+        let mut current_statement = statements.pop_front();
 
         while let Some(current) = current_statement {
             previous_statement = if let Some(previous) = previous_statement {
@@ -26,7 +27,14 @@ impl GroupLocalProcessor {
 
                 match (previous, current) {
                     (LocalAssign(mut previous), LocalAssign(mut current)) => {
-                        if self.should_merge(&previous, &mut current) {
+                        if self.should_merge(&previous, &mut current)
+                            && !self.shadows_used_variable(
+                                &previous,
+                                &current,
+                                &mut statements,
+                                block.mutate_last_statement(),
+                            )
+                        {
                             self.merge(&mut previous, current);
 
                             Some(LocalAssign(previous))
@@ -44,7 +52,7 @@ impl GroupLocalProcessor {
                 None
             };
 
-            current_statement = iter.next();
+            current_statement = statements.pop_front();
         }
 
         if let Some(previous) = previous_statement {
@@ -57,19 +65,62 @@ impl GroupLocalProcessor {
     fn should_merge(&self, first: &LocalAssignStatement, next: &mut LocalAssignStatement) -> bool {
         let first_value_count = first.values_len();
 
-        if first.variables_len() > first_value_count && first_value_count != 0 {
+        // the values of the next statement would be assigned to the extra values of the
+        // first statement (like `local a = 1, f()`) or to its missing values
+        if first.variables_len() != first_value_count && first_value_count != 0 {
             return false;
         }
 
-        let mut find_variables: FindVariables = first
+        if !local_assignments_merge_is_safe(first, next) {
+            log::debug!(
+                "{}: keep local assignments separated because the values of the second one read the variables of the first one",
+                GROUP_LOCAL_ASSIGNMENT_RULE_NAME
+            );
+            return false;
+        }
+
+        true
+    }
+
+    /// Returns true when the next statement declares a name that is already declared by the
+    /// first statement and that is used after the next statement. Merging them would put
+    /// both declarations in a single statement, where the code after it depends on which
+    /// of the duplicated names is visible.
+    fn shadows_used_variable(
+        &self,
+        first: &LocalAssignStatement,
+        next: &LocalAssignStatement,
+        following_statements: &mut VecDeque<Statement>,
+        last_statement: Option<&mut LastStatement>,
+    ) -> bool {
+        let shadowed: Vec<_> = next
             .iter_variables()
-            .map(|variable| variable.get_name().as_str())
+            .map(|variable| variable.get_name())
+            .filter(|name| {
+                first
+                    .iter_variables()
+                    .any(|variable| variable.get_name() == *name)
+            })
             .collect();
 
-        next.iter_mut_values().all(|expression| {
-            DefaultVisitor::visit_expression(expression, &mut find_variables);
-            !find_variables.has_found_usage()
-        })
+        if shadowed.is_empty() {
+            return false;
+        }
+
+        let mut find_variables: FindVariables = shadowed.into_iter().collect();
+
+        for statement in following_statements.iter_mut() {
+            DefaultVisitor::visit_statement(statement, &mut find_variables);
+            if find_variables.has_found_usage() {
+                return true;
+            }
+        }
+
+        if let Some(last_statement) = last_statement {
+            DefaultVisitor::visit_last_statement(last_statement, &mut find_variables);
+        }
+
+        find_variables.has_found_usage()
     }
 
     fn merge(&self, first: &mut LocalAssignStatement, mut other: LocalAssignStatement) {
@@ -124,6 +175,10 @@ impl RuleConfiguration for GroupLocalAssignment {
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
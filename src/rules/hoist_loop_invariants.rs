@@ -0,0 +1,1236 @@
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use crate::generator::{DenseLuaGenerator, LuaGenerator};
+use crate::nodes::{
+    Arguments, AssignStatement, BinaryOperator, Block, CompoundAssignStatement, Expression,
+    FunctionCall, FunctionExpression, FunctionStatement, GenericForStatement, Identifier,
+    InterpolationSegment, LocalAssignStatement, LocalFunctionStatement, NumericForStatement,
+    Prefix, RepeatStatement, Statement, TableEntry, UnaryOperator, Variable, WhileStatement,
+};
+use crate::process::{
+    DefaultPostVisitor, DefaultVisitor, Evaluator, IdentifierTracker, LuaValue, NodePostProcessor,
+    NodePostVisitor, NodeProcessor, NodeVisitor, Scope, ScopeVisitor,
+};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind,
+};
+
+/// Collects the names assigned inside functions. When a loop calls a function, these
+/// variables may change between two iterations.
+#[derive(Default)]
+pub(crate) struct FunctionAssignments {
+    function_depth: usize,
+    pub(crate) names: HashSet<String>,
+}
+
+impl FunctionAssignments {
+    fn insert_variable(&mut self, variable: &Variable) {
+        if self.function_depth != 0 {
+            if let Variable::Identifier(identifier) = variable {
+                self.names.insert(identifier.get_name().to_owned());
+            }
+        }
+    }
+}
+
+impl NodeProcessor for FunctionAssignments {
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        for variable in assign.iter_variables() {
+            self.insert_variable(variable);
+        }
+    }
+
+    fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
+        self.insert_variable(assign.get_variable());
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        if self.function_depth != 0 {
+            self.names
+                .insert(function.get_name().get_name().get_name().to_owned());
+        }
+        self.function_depth += 1;
+    }
+
+    fn process_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.function_depth += 1;
+    }
+
+    fn process_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.function_depth += 1;
+    }
+}
+
+impl NodePostProcessor for FunctionAssignments {
+    fn process_after_function_statement(&mut self, _: &mut FunctionStatement) {
+        self.function_depth -= 1;
+    }
+
+    fn process_after_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.function_depth -= 1;
+    }
+
+    fn process_after_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.function_depth -= 1;
+    }
+}
+
+fn root_name(prefix: &Prefix) -> Option<&str> {
+    match prefix {
+        Prefix::Identifier(identifier) => Some(identifier.get_name()),
+        Prefix::Field(field) => root_name(field.get_prefix()),
+        Prefix::Index(index) => root_name(index.get_prefix()),
+        Prefix::Call(_) | Prefix::Parenthese(_) => None,
+    }
+}
+
+/// Returns true for the iterators of the standard library, which do not run any code
+/// from the processed file when their metamethods are assumed to be pure.
+fn is_standard_iterator(generic_for: &GenericForStatement) -> bool {
+    match generic_for.iter_expressions().next() {
+        Some(Expression::Identifier(identifier)) => identifier.get_name() == "next",
+        Some(Expression::Call(call)) => {
+            call.get_method().is_none()
+                && matches!(
+                    call.get_prefix(),
+                    Prefix::Identifier(identifier)
+                        if matches!(identifier.get_name(), "pairs" | "ipairs")
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Describes what may change while a loop runs: the variables assigned or declared inside
+/// the loop, the tables that get modified, and whether the loop can run code that is not
+/// visible in its body (like a function call).
+struct LoopEffects<'a> {
+    evaluator: &'a Evaluator,
+    assume_no_metatables: bool,
+    assigned: HashSet<String>,
+    mutated_tables: HashSet<String>,
+    mutates_unknown_tables: bool,
+    may_run_code: bool,
+}
+
+impl<'a> LoopEffects<'a> {
+    fn new(evaluator: &'a Evaluator, assume_no_metatables: bool) -> Self {
+        Self {
+            evaluator,
+            assume_no_metatables,
+            assigned: HashSet::new(),
+            mutated_tables: HashSet::new(),
+            mutates_unknown_tables: false,
+            may_run_code: false,
+        }
+    }
+
+    fn insert_name(&mut self, name: &str) {
+        self.assigned.insert(name.to_owned());
+    }
+
+    fn insert_variable(&mut self, variable: &Variable) {
+        let prefix = match variable {
+            Variable::Identifier(identifier) => {
+                self.insert_name(identifier.get_name());
+                return;
+            }
+            Variable::Field(field) => field.get_prefix(),
+            Variable::Index(index) => index.get_prefix(),
+        };
+
+        match root_name(prefix) {
+            Some(name) => {
+                self.mutated_tables.insert(name.to_owned());
+            }
+            None => {
+                self.mutates_unknown_tables = true;
+            }
+        }
+
+        // assigning a field can call a `__newindex` metamethod
+        if !self.assume_no_metatables {
+            self.may_run_code = true;
+        }
+    }
+
+    fn insert_generic_for(&mut self, generic_for: &GenericForStatement) {
+        for identifier in generic_for.iter_identifiers() {
+            self.insert_name(identifier.get_name());
+        }
+        // the iterator function is called before each iteration
+        if !(self.assume_no_metatables && is_standard_iterator(generic_for)) {
+            self.may_run_code = true;
+        }
+    }
+}
+
+impl NodeProcessor for LoopEffects<'_> {
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        for variable in assign.iter_variables() {
+            self.insert_variable(variable);
+        }
+    }
+
+    fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
+        self.insert_variable(assign.get_variable());
+    }
+
+    fn process_local_assign_statement(&mut self, assign: &mut LocalAssignStatement) {
+        for variable in assign.iter_variables() {
+            self.insert_name(variable.get_name());
+        }
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        self.insert_name(function.get_name());
+        for parameter in function.iter_parameters() {
+            self.insert_name(parameter.get_name());
+        }
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name();
+        if name.get_field_names().is_empty() && !name.has_method() {
+            self.insert_name(name.get_name().get_name());
+        } else {
+            self.mutated_tables
+                .insert(name.get_name().get_name().to_owned());
+        }
+        for parameter in function.iter_parameters() {
+            self.insert_name(parameter.get_name());
+        }
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        for parameter in function.iter_parameters() {
+            self.insert_name(parameter.get_name());
+        }
+    }
+
+    fn process_numeric_for_statement(&mut self, numeric_for: &mut NumericForStatement) {
+        self.insert_name(numeric_for.get_identifier().get_name());
+    }
+
+    fn process_generic_for_statement(&mut self, generic_for: &mut GenericForStatement) {
+        self.insert_generic_for(generic_for);
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if !self.may_run_code
+            && self
+                .evaluator
+                .has_side_effects(&Expression::from(call.clone()))
+        {
+            self.may_run_code = true;
+        }
+    }
+
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if !self.may_run_code
+            && !matches!(expression, Expression::Call(_))
+            && self.evaluator.has_side_effects(expression)
+        {
+            self.may_run_code = true;
+        }
+    }
+}
+
+impl NodePostProcessor for LoopEffects<'_> {}
+
+/// Decides if an expression evaluates to the same value at each iteration of a loop.
+struct InvariantChecker<'a> {
+    effects: &'a LoopEffects<'a>,
+    evaluator: &'a Evaluator,
+    identifier_tracker: &'a IdentifierTracker,
+    block_locals: &'a HashSet<String>,
+    function_assignments: &'a HashSet<String>,
+}
+
+impl InvariantChecker<'_> {
+    /// Returns true if the expression can be moved before the loop and replaced by a local
+    /// variable. Identifiers and constant values are already as cheap as a local variable.
+    fn is_hoistable(&self, expression: &Expression) -> bool {
+        match expression {
+            Expression::False(_)
+            | Expression::Function(_)
+            | Expression::Identifier(_)
+            | Expression::Nil(_)
+            | Expression::Number(_)
+            | Expression::String(_)
+            | Expression::Table(_)
+            | Expression::True(_)
+            | Expression::Call(_)
+            | Expression::VariableArguments(_) => false,
+            _ => {
+                self.evaluator.evaluate(expression) == LuaValue::Unknown
+                    && self.is_invariant(expression)
+                    && !self.evaluator.has_side_effects(expression)
+            }
+        }
+    }
+
+    fn is_invariant(&self, expression: &Expression) -> bool {
+        match expression {
+            Expression::False(_)
+            | Expression::Nil(_)
+            | Expression::Number(_)
+            | Expression::String(_)
+            | Expression::True(_) => true,
+            Expression::Identifier(identifier) => self.is_invariant_identifier(identifier),
+            Expression::Binary(binary) => {
+                self.is_invariant(binary.left()) && self.is_invariant(binary.right())
+            }
+            Expression::Unary(unary) => self.is_invariant(unary.get_expression()),
+            Expression::Parenthese(parenthese) => self.is_invariant(parenthese.inner_expression()),
+            Expression::TypeCast(type_cast) => self.is_invariant(type_cast.get_expression()),
+            Expression::If(if_expression) => {
+                self.is_invariant(if_expression.get_condition())
+                    && self.is_invariant(if_expression.get_result())
+                    && if_expression.iter_branches().all(|branch| {
+                        self.is_invariant(branch.get_condition())
+                            && self.is_invariant(branch.get_result())
+                    })
+                    && self.is_invariant(if_expression.get_else_result())
+            }
+            Expression::InterpolatedString(interpolated_string) => interpolated_string
+                .iter_segments()
+                .all(|segment| match segment {
+                    InterpolationSegment::String(_) => true,
+                    InterpolationSegment::Value(value) => self.is_invariant(value.get_expression()),
+                }),
+            Expression::Field(field) => {
+                self.is_unchanged_table(field.get_prefix())
+                    && self.is_invariant_prefix(field.get_prefix())
+            }
+            Expression::Index(index) => {
+                self.is_unchanged_table(index.get_prefix())
+                    && self.is_invariant_prefix(index.get_prefix())
+                    && self.is_invariant(index.get_index())
+            }
+            // a call may return a different value each time, and functions and tables
+            // create a new value each time they are evaluated
+            Expression::Call(_)
+            | Expression::Function(_)
+            | Expression::Table(_)
+            | Expression::VariableArguments(_) => false,
+        }
+    }
+
+    fn is_invariant_prefix(&self, prefix: &Prefix) -> bool {
+        match prefix {
+            Prefix::Identifier(identifier) => self.is_invariant_identifier(identifier),
+            Prefix::Field(field) => {
+                self.is_unchanged_table(field.get_prefix())
+                    && self.is_invariant_prefix(field.get_prefix())
+            }
+            Prefix::Index(index) => {
+                self.is_unchanged_table(index.get_prefix())
+                    && self.is_invariant_prefix(index.get_prefix())
+                    && self.is_invariant(index.get_index())
+            }
+            Prefix::Parenthese(parenthese) => self.is_invariant(parenthese.inner_expression()),
+            Prefix::Call(_) => false,
+        }
+    }
+
+    fn is_invariant_identifier(&self, identifier: &Identifier) -> bool {
+        let name = identifier.get_name();
+
+        if self.effects.assigned.contains(name) {
+            return false;
+        }
+
+        if self.effects.may_run_code {
+            // globals and the locals assigned by functions can be changed by the code that
+            // runs during the loop
+            self.is_local(name) && !self.function_assignments.contains(name)
+        } else {
+            true
+        }
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        self.block_locals.contains(name) || self.identifier_tracker.is_identifier_used(name)
+    }
+
+    fn is_unchanged_table(&self, prefix: &Prefix) -> bool {
+        if self.effects.may_run_code || self.effects.mutates_unknown_tables {
+            return false;
+        }
+
+        match root_name(prefix) {
+            Some(name) => !self.effects.mutated_tables.contains(name),
+            None => self.effects.mutated_tables.is_empty(),
+        }
+    }
+}
+
+fn expression_key(expression: &Expression) -> String {
+    let mut generator = DenseLuaGenerator::default();
+    generator.write_expression(expression);
+    generator.into_string()
+}
+
+fn prefix_as_expression(prefix: &Prefix) -> Option<Expression> {
+    match prefix {
+        Prefix::Field(_) | Prefix::Index(_) | Prefix::Parenthese(_) => {
+            Some(Expression::from(prefix.clone()))
+        }
+        Prefix::Call(_) | Prefix::Identifier(_) => None,
+    }
+}
+
+/// Returns true if evaluating the expression can raise an error, like indexing a `nil` value
+/// or adding a number to a table.
+fn may_error(expression: &Expression) -> bool {
+    match expression {
+        Expression::False(_)
+        | Expression::Function(_)
+        | Expression::Identifier(_)
+        | Expression::Nil(_)
+        | Expression::Number(_)
+        | Expression::String(_)
+        | Expression::True(_)
+        | Expression::VariableArguments(_) => false,
+        Expression::Binary(binary) => match binary.operator() {
+            BinaryOperator::And
+            | BinaryOperator::Or
+            | BinaryOperator::Equal
+            | BinaryOperator::NotEqual => may_error(binary.left()) || may_error(binary.right()),
+            _ => true,
+        },
+        Expression::Unary(unary) => match unary.operator() {
+            UnaryOperator::Not => may_error(unary.get_expression()),
+            UnaryOperator::Length | UnaryOperator::Minus => true,
+        },
+        Expression::Parenthese(parenthese) => may_error(parenthese.inner_expression()),
+        Expression::TypeCast(type_cast) => may_error(type_cast.get_expression()),
+        Expression::If(if_expression) => {
+            may_error(if_expression.get_condition())
+                || may_error(if_expression.get_result())
+                || if_expression.iter_branches().any(|branch| {
+                    may_error(branch.get_condition()) || may_error(branch.get_result())
+                })
+                || may_error(if_expression.get_else_result())
+        }
+        Expression::InterpolatedString(interpolated_string) => interpolated_string
+            .iter_segments()
+            .any(|segment| match segment {
+                InterpolationSegment::String(_) => false,
+                InterpolationSegment::Value(value) => may_error(value.get_expression()),
+            }),
+        Expression::Call(_)
+        | Expression::Field(_)
+        | Expression::Index(_)
+        | Expression::Table(_) => true,
+    }
+}
+
+/// Returns true if the block contains a `break`, `continue` or `return` statement outside of
+/// functions, so that the code following it may not run.
+fn has_exit(block: &Block) -> bool {
+    block.get_last_statement().is_some() || block.iter_statements().any(statement_has_exit)
+}
+
+fn statement_has_exit(statement: &Statement) -> bool {
+    match statement {
+        Statement::Do(do_statement) => has_exit(do_statement.get_block()),
+        Statement::If(if_statement) => {
+            if_statement
+                .iter_branches()
+                .any(|branch| has_exit(branch.get_block()))
+                || if_statement.get_else_block().is_some_and(has_exit)
+        }
+        Statement::NumericFor(numeric_for) => has_exit(numeric_for.get_block()),
+        Statement::GenericFor(generic_for) => has_exit(generic_for.get_block()),
+        Statement::While(while_statement) => has_exit(while_statement.get_block()),
+        Statement::Repeat(repeat) => has_exit(repeat.get_block()),
+        _ => false,
+    }
+}
+
+/// Collects the expressions that are evaluated each time the body of a loop runs. The code
+/// that may be skipped (like the blocks of an `if` statement, the right side of `and` and
+/// `or`, or the statements after a `break`) is left out, as well as the body of loops that
+/// may not run at all.
+struct EvaluatedExpressions<'a> {
+    evaluator: &'a Evaluator,
+    keys: HashSet<String>,
+}
+
+impl<'a> EvaluatedExpressions<'a> {
+    fn new(evaluator: &'a Evaluator) -> Self {
+        Self {
+            evaluator,
+            keys: HashSet::new(),
+        }
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+
+    fn insert_loop(&mut self, statement: &Statement) {
+        match statement {
+            Statement::NumericFor(numeric_for) => self.insert_numeric_for_block(numeric_for),
+            Statement::While(while_statement) => self.insert_while(while_statement),
+            Statement::Repeat(repeat) => self.insert_repeat(repeat),
+            // the iterator can end before the first iteration
+            _ => {}
+        }
+    }
+
+    fn runs_at_least_once(&self, numeric_for: &NumericForStatement) -> bool {
+        let step = numeric_for
+            .get_step()
+            .map(|step| self.evaluator.evaluate(step))
+            .unwrap_or(LuaValue::Number(1.0));
+
+        match (
+            self.evaluator.evaluate(numeric_for.get_start()),
+            self.evaluator.evaluate(numeric_for.get_end()),
+            step,
+        ) {
+            (LuaValue::Number(start), LuaValue::Number(end), LuaValue::Number(step)) => {
+                (step > 0.0 && start <= end) || (step < 0.0 && start >= end)
+            }
+            _ => false,
+        }
+    }
+
+    fn insert_numeric_for_block(&mut self, numeric_for: &NumericForStatement) {
+        if self.runs_at_least_once(numeric_for) {
+            self.insert_block(numeric_for.get_block());
+        }
+    }
+
+    fn insert_while(&mut self, while_statement: &WhileStatement) {
+        let condition = while_statement.get_condition();
+        self.insert_expression(condition);
+        if self.evaluator.evaluate(condition).is_truthy() == Some(true) {
+            self.insert_block(while_statement.get_block());
+        }
+    }
+
+    fn insert_repeat(&mut self, repeat: &RepeatStatement) {
+        if self.insert_block(repeat.get_block()) {
+            self.insert_expression(repeat.get_condition());
+        }
+    }
+
+    /// Returns true if the end of the block is always reached.
+    fn insert_block(&mut self, block: &Block) -> bool {
+        for statement in block.iter_statements() {
+            self.insert_statement(statement);
+
+            if statement_has_exit(statement) {
+                return false;
+            }
+        }
+        block.get_last_statement().is_none()
+    }
+
+    fn insert_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Assign(assign) => {
+                for value in assign.iter_values() {
+                    self.insert_expression(value);
+                }
+                for variable in assign.iter_variables() {
+                    self.insert_variable(variable);
+                }
+            }
+            Statement::CompoundAssign(assign) => {
+                self.insert_variable(assign.get_variable());
+                self.insert_expression(assign.get_value());
+            }
+            Statement::LocalAssign(assign) => {
+                for value in assign.iter_values() {
+                    self.insert_expression(value);
+                }
+            }
+            Statement::Call(call) => self.insert_call(call),
+            Statement::Do(do_statement) => {
+                self.insert_block(do_statement.get_block());
+            }
+            Statement::If(if_statement) => {
+                if let Some(branch) = if_statement.iter_branches().next() {
+                    self.insert_expression(branch.get_condition());
+                }
+            }
+            Statement::NumericFor(numeric_for) => {
+                self.insert_expression(numeric_for.get_start());
+                self.insert_expression(numeric_for.get_end());
+                if let Some(step) = numeric_for.get_step() {
+                    self.insert_expression(step);
+                }
+                self.insert_numeric_for_block(numeric_for);
+            }
+            Statement::GenericFor(generic_for) => {
+                for expression in generic_for.iter_expressions() {
+                    self.insert_expression(expression);
+                }
+            }
+            Statement::While(while_statement) => self.insert_while(while_statement),
+            Statement::Repeat(repeat) => self.insert_repeat(repeat),
+            Statement::Function(_)
+            | Statement::LocalFunction(_)
+            | Statement::TypeDeclaration(_)
+            | Statement::Verbatim(_) => {}
+        }
+    }
+
+    fn insert_variable(&mut self, variable: &Variable) {
+        match variable {
+            Variable::Identifier(_) => {}
+            Variable::Field(field) => self.insert_prefix(field.get_prefix()),
+            Variable::Index(index) => {
+                self.insert_prefix(index.get_prefix());
+                self.insert_expression(index.get_index());
+            }
+        }
+    }
+
+    fn insert_call(&mut self, call: &FunctionCall) {
+        self.insert_prefix(call.get_prefix());
+
+        match call.get_arguments() {
+            Arguments::Tuple(tuple) => {
+                for value in tuple.iter_values() {
+                    self.insert_expression(value);
+                }
+            }
+            Arguments::Table(table) => {
+                self.insert_table_entries(table.iter_entries());
+            }
+            Arguments::String(_) => {}
+        }
+    }
+
+    fn insert_table_entries<'b>(&mut self, entries: impl Iterator<Item = &'b TableEntry>) {
+        for entry in entries {
+            match entry {
+                TableEntry::Field(field) => self.insert_expression(field.get_value()),
+                TableEntry::Index(index) => {
+                    self.insert_expression(index.get_key());
+                    self.insert_expression(index.get_value());
+                }
+                TableEntry::Value(value) => self.insert_expression(value),
+            }
+        }
+    }
+
+    fn insert_prefix(&mut self, prefix: &Prefix) {
+        match prefix {
+            Prefix::Call(call) => self.insert_call(call),
+            Prefix::Identifier(_) => {}
+            Prefix::Field(_) | Prefix::Index(_) | Prefix::Parenthese(_) => {
+                if let Some(expression) = prefix_as_expression(prefix) {
+                    self.insert_expression(&expression);
+                }
+            }
+        }
+    }
+
+    fn insert_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::False(_)
+            | Expression::Function(_)
+            | Expression::Identifier(_)
+            | Expression::Nil(_)
+            | Expression::Number(_)
+            | Expression::String(_)
+            | Expression::True(_)
+            | Expression::VariableArguments(_) => return,
+            _ => {}
+        }
+
+        self.keys.insert(expression_key(expression));
+
+        match expression {
+            Expression::Binary(binary) => {
+                self.insert_expression(binary.left());
+                // the right side of `and` and `or` is not always evaluated
+                if !matches!(binary.operator(), BinaryOperator::And | BinaryOperator::Or) {
+                    self.insert_expression(binary.right());
+                }
+            }
+            Expression::Unary(unary) => self.insert_expression(unary.get_expression()),
+            Expression::Parenthese(parenthese) => {
+                self.insert_expression(parenthese.inner_expression())
+            }
+            Expression::TypeCast(type_cast) => self.insert_expression(type_cast.get_expression()),
+            Expression::If(if_expression) => self.insert_expression(if_expression.get_condition()),
+            Expression::InterpolatedString(interpolated_string) => {
+                for segment in interpolated_string.iter_segments() {
+                    if let InterpolationSegment::Value(value) = segment {
+                        self.insert_expression(value.get_expression());
+                    }
+                }
+            }
+            Expression::Field(field) => self.insert_prefix(field.get_prefix()),
+            Expression::Index(index) => {
+                self.insert_prefix(index.get_prefix());
+                self.insert_expression(index.get_index());
+            }
+            Expression::Call(call) => self.insert_call(call),
+            Expression::Table(table) => self.insert_table_entries(table.iter_entries()),
+            _ => {}
+        }
+    }
+}
+
+/// Counts the expressions of a candidate, to compare its size with the `min_size` property.
+#[derive(Default)]
+struct ExpressionCounter {
+    count: usize,
+}
+
+impl NodeProcessor for ExpressionCounter {
+    fn process_expression(&mut self, _: &mut Expression) {
+        self.count += 1;
+    }
+}
+
+struct Candidate {
+    value: Expression,
+    occurrences: usize,
+    size: usize,
+    selected: bool,
+    name: Option<String>,
+}
+
+/// Finds the invariant expressions of a loop (with the number of times they appear), and then
+/// replaces the selected ones with the name of a local variable.
+struct CandidateVisitor<'a> {
+    checker: Option<&'a InvariantChecker<'a>>,
+    identifier_tracker: Option<&'a mut IdentifierTracker>,
+    function_depth: usize,
+    candidates: Vec<Candidate>,
+    indexes: HashMap<String, usize>,
+    hoisted: Vec<usize>,
+}
+
+impl<'a> CandidateVisitor<'a> {
+    fn finder(checker: &'a InvariantChecker<'a>) -> Self {
+        Self {
+            checker: Some(checker),
+            identifier_tracker: None,
+            function_depth: 0,
+            candidates: Vec::new(),
+            indexes: HashMap::new(),
+            hoisted: Vec::new(),
+        }
+    }
+
+    fn replacer(
+        candidates: Vec<Candidate>,
+        indexes: HashMap<String, usize>,
+        identifier_tracker: &'a mut IdentifierTracker,
+    ) -> Self {
+        Self {
+            checker: None,
+            identifier_tracker: Some(identifier_tracker),
+            function_depth: 0,
+            candidates,
+            indexes,
+            hoisted: Vec::new(),
+        }
+    }
+
+    fn count(&mut self, expression: &mut Expression) {
+        let checker = match self.checker {
+            Some(checker) => checker,
+            None => return,
+        };
+
+        if !checker.is_hoistable(expression) {
+            return;
+        }
+
+        let key = expression_key(expression);
+
+        if let Some(index) = self.indexes.get(&key) {
+            self.candidates[*index].occurrences += 1;
+        } else {
+            let mut counter = ExpressionCounter::default();
+            let mut value = expression.clone();
+            DefaultVisitor::visit_expression(&mut value, &mut counter);
+
+            self.indexes.insert(key, self.candidates.len());
+            self.candidates.push(Candidate {
+                value,
+                occurrences: 1,
+                size: counter.count,
+                selected: false,
+                name: None,
+            });
+        }
+    }
+
+    /// Returns the name of the local variable holding the value of the expression, if the
+    /// expression is hoisted.
+    fn replacement(&mut self, expression: &Expression) -> Option<String> {
+        let identifier_tracker = self.identifier_tracker.as_mut()?;
+
+        let index = *self.indexes.get(&expression_key(expression))?;
+        let candidate = &mut self.candidates[index];
+
+        if !candidate.selected {
+            return None;
+        }
+
+        if candidate.name.is_none() {
+            candidate.name = Some(identifier_tracker.generate_named_identifier("LOOP_INVARIANT"));
+            self.hoisted.push(index);
+        }
+
+        candidate.name.clone()
+    }
+
+    fn into_hoisted_statements(mut self) -> Vec<Statement> {
+        mem::take(&mut self.hoisted)
+            .into_iter()
+            .filter_map(|index| {
+                let candidate = &mut self.candidates[index];
+                let name = candidate.name.take()?;
+                let value = mem::replace(&mut candidate.value, Expression::nil());
+                Some(
+                    LocalAssignStatement::from_variable(name)
+                        .with_value(value)
+                        .into(),
+                )
+            })
+            .collect()
+    }
+}
+
+impl NodeProcessor for CandidateVisitor<'_> {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if self.function_depth != 0 {
+            return;
+        }
+
+        if self.checker.is_some() {
+            self.count(expression);
+        } else if let Some(name) = self.replacement(expression) {
+            *expression = Expression::identifier(name);
+        }
+    }
+
+    fn process_prefix_expression(&mut self, prefix: &mut Prefix) {
+        if self.function_depth != 0 {
+            return;
+        }
+
+        let mut expression = match prefix_as_expression(prefix) {
+            Some(expression) => expression,
+            None => return,
+        };
+
+        if self.checker.is_some() {
+            self.count(&mut expression);
+        } else if let Some(name) = self.replacement(&expression) {
+            *prefix = Prefix::from(Identifier::new(name));
+        }
+    }
+
+    // the code inside functions defined in the loop does not run at each iteration
+    fn process_function_statement(&mut self, _: &mut FunctionStatement) {
+        self.function_depth += 1;
+    }
+
+    fn process_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.function_depth += 1;
+    }
+
+    fn process_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.function_depth += 1;
+    }
+}
+
+impl NodePostProcessor for CandidateVisitor<'_> {
+    fn process_after_function_statement(&mut self, _: &mut FunctionStatement) {
+        self.function_depth -= 1;
+    }
+
+    fn process_after_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.function_depth -= 1;
+    }
+
+    fn process_after_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.function_depth -= 1;
+    }
+}
+
+/// Visits the parts of a loop that are evaluated at each iteration: the block and, for
+/// `while` and `repeat` loops, the condition.
+fn visit_loop<T: NodeProcessor + NodePostProcessor>(statement: &mut Statement, processor: &mut T) {
+    match statement {
+        Statement::NumericFor(numeric_for) => {
+            DefaultPostVisitor::visit_block(numeric_for.mutate_block(), processor);
+        }
+        Statement::GenericFor(generic_for) => {
+            DefaultPostVisitor::visit_block(generic_for.mutate_block(), processor);
+        }
+        Statement::While(while_statement) => {
+            DefaultPostVisitor::visit_expression(while_statement.mutate_condition(), processor);
+            DefaultPostVisitor::visit_block(while_statement.mutate_block(), processor);
+        }
+        Statement::Repeat(repeat) => {
+            DefaultPostVisitor::visit_block(repeat.mutate_block(), processor);
+            DefaultPostVisitor::visit_expression(repeat.mutate_condition(), processor);
+        }
+        _ => {}
+    }
+}
+
+struct Processor {
+    identifier_tracker: IdentifierTracker,
+    evaluator: Evaluator,
+    assume_no_metatables: bool,
+    min_occurrences: usize,
+    min_size: usize,
+    function_assignments: HashSet<String>,
+}
+
+impl Processor {
+    fn hoist(
+        &mut self,
+        statement: &mut Statement,
+        block_locals: &HashSet<String>,
+    ) -> Vec<Statement> {
+        let mut effects = LoopEffects::new(&self.evaluator, self.assume_no_metatables);
+
+        match statement {
+            Statement::NumericFor(numeric_for) => {
+                effects.insert_name(numeric_for.get_identifier().get_name());
+            }
+            Statement::GenericFor(generic_for) => {
+                effects.insert_generic_for(generic_for);
+            }
+            Statement::While(_) | Statement::Repeat(_) => {}
+            _ => return Vec::new(),
+        }
+
+        visit_loop(statement, &mut effects);
+
+        let checker = InvariantChecker {
+            effects: &effects,
+            evaluator: &self.evaluator,
+            identifier_tracker: &self.identifier_tracker,
+            block_locals,
+            function_assignments: &self.function_assignments,
+        };
+
+        let mut finder = CandidateVisitor::finder(&checker);
+        visit_loop(statement, &mut finder);
+
+        let mut evaluated = EvaluatedExpressions::new(&self.evaluator);
+        evaluated.insert_loop(statement);
+
+        let mut any_selected = false;
+        for (key, index) in finder.indexes.iter() {
+            let candidate = &mut finder.candidates[*index];
+            // an expression that can raise an error is moved only when the loop evaluates it
+            // at each iteration, otherwise the error could happen where the loop avoids it
+            candidate.selected = (candidate.occurrences >= self.min_occurrences
+                || candidate.size >= self.min_size)
+                && (!may_error(&candidate.value) || evaluated.contains(key));
+            any_selected |= candidate.selected;
+        }
+
+        if !any_selected {
+            return Vec::new();
+        }
+
+        // the finder borrows the identifier tracker through the checker, so its candidates
+        // are taken before the replacer borrows the tracker mutably
+        let CandidateVisitor {
+            candidates,
+            indexes,
+            ..
+        } = finder;
+        let mut replacer =
+            CandidateVisitor::replacer(candidates, indexes, &mut self.identifier_tracker);
+        visit_loop(statement, &mut replacer);
+
+        replacer.into_hoisted_statements()
+    }
+}
+
+impl Scope for Processor {
+    fn push(&mut self) {
+        self.identifier_tracker.push()
+    }
+
+    fn pop(&mut self) {
+        self.identifier_tracker.pop()
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.identifier_tracker.insert(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.identifier_tracker.insert_self();
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>) {
+        self.identifier_tracker.insert_local(identifier, value);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.identifier_tracker.insert_local_function(function);
+    }
+}
+
+impl NodeProcessor for Processor {
+    // loops are processed from the outermost one, so that an expression is moved out of as
+    // many loops as possible. The blocks of the inner loops are processed after, when the
+    // visitor reaches them.
+    fn process_block(&mut self, block: &mut Block) {
+        let mut block_locals = HashSet::new();
+        let mut insertions = Vec::new();
+
+        for (index, statement) in block.iter_mut_statements().enumerate() {
+            let hoisted = self.hoist(statement, &block_locals);
+
+            if !hoisted.is_empty() {
+                insertions.push((index, hoisted));
+            }
+
+            // the locals of the block are added to the scope when the visitor reaches them
+            match statement {
+                Statement::LocalAssign(assign) => {
+                    block_locals.extend(
+                        assign
+                            .iter_variables()
+                            .map(|variable| variable.get_name().to_owned()),
+                    );
+                }
+                Statement::LocalFunction(function) => {
+                    block_locals.insert(function.get_name().to_owned());
+                }
+                _ => {}
+            }
+        }
+
+        for (index, statements) in insertions.into_iter().rev() {
+            for statement in statements.into_iter().rev() {
+                block.insert_statement(index, statement);
+            }
+        }
+    }
+}
+
+pub const HOIST_LOOP_INVARIANTS_RULE_NAME: &str = "hoist_loop_invariants";
+
+const DEFAULT_MIN_OCCURRENCES: usize = 2;
+const DEFAULT_MIN_SIZE: usize = 3;
+
+/// A rule that moves expressions computing the same value at each iteration of a loop into
+/// local variables declared before the loop.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HoistLoopInvariants {
+    assume_no_metatables: bool,
+    min_occurrences: usize,
+    min_size: usize,
+}
+
+impl Default for HoistLoopInvariants {
+    fn default() -> Self {
+        Self {
+            assume_no_metatables: false,
+            min_occurrences: DEFAULT_MIN_OCCURRENCES,
+            min_size: DEFAULT_MIN_SIZE,
+        }
+    }
+}
+
+impl HoistLoopInvariants {
+    /// Assumes that values do not have metamethods, so that reading table fields and using
+    /// operators do not run any code.
+    pub fn assume_no_metatables(mut self) -> Self {
+        self.assume_no_metatables = true;
+        self
+    }
+
+    pub fn with_min_occurrences(mut self, min_occurrences: usize) -> Self {
+        self.min_occurrences = min_occurrences;
+        self
+    }
+
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+}
+
+impl FlawlessRule for HoistLoopInvariants {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut function_assignments = FunctionAssignments::default();
+        DefaultPostVisitor::visit_block(block, &mut function_assignments);
+
+        let mut evaluator = Evaluator::default();
+        if self.assume_no_metatables {
+            evaluator = evaluator.assume_pure_metamethods();
+        }
+        if !context.environment().is_empty() {
+            evaluator = evaluator.with_environment(context.environment().clone());
+        }
+
+        let mut processor = Processor {
+            identifier_tracker: context.identifier_tracker(),
+            evaluator,
+            assume_no_metatables: self.assume_no_metatables,
+            min_occurrences: self.min_occurrences,
+            min_size: self.min_size,
+            function_assignments: function_assignments.names,
+        };
+        ScopeVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for HoistLoopInvariants {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "assume_no_metatables" => {
+                    self.assume_no_metatables = value.expect_bool(&key)?;
+                }
+                "min_occurrences" => {
+                    self.min_occurrences = value.expect_usize(&key)?;
+                }
+                "min_size" => {
+                    self.min_size = value.expect_usize(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        HOIST_LOOP_INVARIANTS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["assume_no_metatables", "min_occurrences", "min_size"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "assume_no_metatables" => RulePropertyKind::Boolean,
+            "min_occurrences" | "min_size" => RulePropertyKind::Usize,
+            _ => RulePropertyKind::Any,
+        }
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.assume_no_metatables {
+            properties.insert("assume_no_metatables".to_owned(), true.into());
+        }
+        if self.min_occurrences != DEFAULT_MIN_OCCURRENCES {
+            properties.insert("min_occurrences".to_owned(), self.min_occurrences.into());
+        }
+        if self.min_size != DEFAULT_MIN_SIZE {
+            properties.insert("min_size".to_owned(), self.min_size.into());
+        }
+
+        properties
+    }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if self.assume_no_metatables {
+            properties.push("assume_no_metatables");
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+    use crate::test_utils::{
+        assert_rule_config_roundtrip, assert_rule_no_change, assert_rule_output,
+    };
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> HoistLoopInvariants {
+        HoistLoopInvariants::default()
+    }
+
+    #[test]
+    fn hoist_expression_used_twice() {
+        assert_rule_output(
+            &new_rule().assume_no_metatables(),
+            "for i = 1, 10 do a[i] = base.scale b[i] = base.scale end",
+            "local __DARKLUA_LOOP_INVARIANT = base.scale for i = 1, 10 do a[i] = __DARKLUA_LOOP_INVARIANT b[i] = __DARKLUA_LOOP_INVARIANT end",
+        );
+    }
+
+    #[test]
+    fn keep_small_expression_used_once() {
+        assert_rule_no_change(
+            &new_rule().assume_no_metatables(),
+            "for i = 1, 10 do a[i] = base.scale end",
+        );
+    }
+
+    #[test]
+    fn keep_field_read_with_metatables() {
+        assert_rule_no_change(
+            &new_rule(),
+            "for i = 1, 10 do a[i] = base.scale b[i] = base.scale end",
+        );
+    }
+
+    #[test]
+    fn configuration_roundtrip() {
+        assert_rule_config_roundtrip(
+            "{ rule: 'hoist_loop_invariants', assume_no_metatables: true, min_occurrences: 3, min_size: 5 }",
+        );
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_hoist_loop_invariants", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_properties() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .assume_no_metatables()
+                .with_min_occurrences(3)
+                .with_min_size(4),
+        );
+
+        assert_json_snapshot!("hoist_loop_invariants_with_properties", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'hoist_loop_invariants',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
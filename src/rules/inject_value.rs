@@ -1,11 +1,13 @@
 use crate::nodes::{
-    Block, DecimalNumber, Expression, ParentheseExpression, Prefix, StringExpression, UnaryOperator,
+    Arguments, BinaryExpression, BinaryOperator, Block, DecimalNumber, Expression,
+    ParentheseExpression, Prefix, StringExpression, UnaryOperator,
 };
-use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
+use crate::process::{IdentifierTracker, LuaType, NodeProcessor, NodeVisitor, ScopeVisitor};
 use crate::rules::{
     Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
-    RulePropertyValue,
+    RulePropertyKind, RulePropertyValue,
 };
+use crate::utils::log;
 
 use std::{env, ops};
 
@@ -19,11 +21,15 @@ struct ValueInjection {
 }
 
 impl ValueInjection {
-    pub fn new<S: Into<String>, E: Into<Expression>>(identifier: S, expression: E) -> Self {
+    pub fn new<S: Into<String>, E: Into<Expression>>(
+        identifier: S,
+        expression: E,
+        identifier_tracker: IdentifierTracker,
+    ) -> Self {
         Self {
             identifier: identifier.into(),
             expression: expression.into(),
-            identifier_tracker: IdentifierTracker::default(),
+            identifier_tracker,
         }
     }
 }
@@ -46,16 +52,15 @@ impl NodeProcessor for ValueInjection {
     fn process_expression(&mut self, expression: &mut Expression) {
         let replace = match expression {
             Expression::Identifier(identifier) => {
-                &self.identifier == identifier.get_name()
-                    && !self.is_identifier_used(&self.identifier)
+                self.identifier == identifier.get_name() && self.is_global(&self.identifier)
             }
             Expression::Field(field) => {
-                &self.identifier == field.get_field().get_name()
-                    && !self.is_identifier_used("_G")
+                self.identifier == field.get_field().get_name()
+                    && self.is_global("_G")
                     && matches!(field.get_prefix(), Prefix::Identifier(prefix) if prefix.get_name() == "_G")
             }
             Expression::Index(index) => {
-                !self.is_identifier_used("_G")
+                self.is_global("_G")
                     && matches!(index.get_index(), Expression::String(string) if string.get_value() == self.identifier)
                     && matches!(index.get_prefix(), Prefix::Identifier(prefix) if prefix.get_name() == "_G")
             }
@@ -70,7 +75,9 @@ impl NodeProcessor for ValueInjection {
 
     fn process_prefix_expression(&mut self, prefix: &mut Prefix) {
         let replace = match prefix {
-            Prefix::Identifier(identifier) => &self.identifier == identifier.get_name(),
+            Prefix::Identifier(identifier) => {
+                self.identifier == identifier.get_name() && !self.is_environment_replaced()
+            }
             _ => false,
         };
 
@@ -81,6 +88,99 @@ impl NodeProcessor for ValueInjection {
     }
 }
 
+/// A processor that finds the comparisons of the type of a global (like
+/// `type(DEBUG) == "string"`) with a type name other than the expected type.
+struct TypeComparisonChecker<'a> {
+    identifier: &'a str,
+    expected_type: LuaType,
+    identifier_tracker: IdentifierTracker,
+    mismatches: Vec<String>,
+}
+
+impl<'a> TypeComparisonChecker<'a> {
+    fn new(
+        identifier: &'a str,
+        expected_type: LuaType,
+        identifier_tracker: IdentifierTracker,
+    ) -> Self {
+        Self {
+            identifier,
+            expected_type,
+            identifier_tracker,
+            mismatches: Vec::new(),
+        }
+    }
+
+    fn is_type_of_identifier(&self, expression: &Expression) -> bool {
+        let call = match expression {
+            Expression::Call(call) => call,
+            _ => return false,
+        };
+
+        let is_type_function = call.get_method().is_none()
+            && matches!(
+                call.get_prefix(),
+                Prefix::Identifier(identifier) if identifier.get_name() == "type"
+            )
+            && self.is_global("type");
+
+        let is_identifier_argument = match call.get_arguments() {
+            Arguments::Tuple(tuple) if tuple.len() == 1 => matches!(
+                tuple.iter_values().next(),
+                Some(Expression::Identifier(identifier))
+                    if identifier.get_name() == self.identifier
+            ),
+            _ => false,
+        };
+
+        is_type_function && is_identifier_argument && self.is_global(self.identifier)
+    }
+}
+
+impl ops::Deref for TypeComparisonChecker<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for TypeComparisonChecker<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for TypeComparisonChecker<'_> {
+    fn process_binary_expression(&mut self, binary: &mut BinaryExpression) {
+        if !matches!(
+            binary.operator(),
+            BinaryOperator::Equal | BinaryOperator::NotEqual
+        ) {
+            return;
+        }
+
+        let type_name = match (binary.left(), binary.right()) {
+            (call, Expression::String(string)) | (Expression::String(string), call)
+                if self.is_type_of_identifier(call) =>
+            {
+                string.get_value()
+            }
+            _ => return,
+        };
+
+        if type_name != self.expected_type.as_str() {
+            self.mismatches.push(format!(
+                "`type({})` is compared with `\"{}\"` but `{}` is expected to be a `{}`",
+                self.identifier,
+                type_name,
+                self.identifier,
+                self.expected_type.as_str()
+            ));
+        }
+    }
+}
+
 pub const INJECT_GLOBAL_VALUE_RULE_NAME: &str = "inject_global_value";
 
 /// A rule to replace global variables with values.
@@ -88,6 +188,8 @@ pub const INJECT_GLOBAL_VALUE_RULE_NAME: &str = "inject_global_value";
 pub struct InjectGlobalValue {
     identifier: String,
     value: Expression,
+    assume_no_env_manipulation: bool,
+    expected_type: Option<LuaType>,
 }
 
 impl InjectGlobalValue {
@@ -95,6 +197,8 @@ impl InjectGlobalValue {
         Self {
             identifier: identifier.into(),
             value: Expression::nil(),
+            assume_no_env_manipulation: false,
+            expected_type: None,
         }
     }
 
@@ -102,6 +206,8 @@ impl InjectGlobalValue {
         Self {
             identifier: identifier.into(),
             value: Expression::from(value),
+            assume_no_env_manipulation: false,
+            expected_type: None,
         }
     }
 
@@ -109,6 +215,8 @@ impl InjectGlobalValue {
         Self {
             identifier: identifier.into(),
             value: StringExpression::from_value(value).into(),
+            assume_no_env_manipulation: false,
+            expected_type: None,
         }
     }
 
@@ -116,8 +224,24 @@ impl InjectGlobalValue {
         Self {
             identifier: identifier.into(),
             value: Expression::from(value),
+            assume_no_env_manipulation: false,
+            expected_type: None,
         }
     }
+
+    /// Replaces the global even in scopes where a local or a parameter named `_ENV`
+    /// is defined.
+    pub fn with_assume_no_env_manipulation(mut self, value: bool) -> Self {
+        self.assume_no_env_manipulation = value;
+        self
+    }
+
+    /// Warns about the comparisons of the type of the global (like `type(DEBUG) == "string"`)
+    /// with another type than the given one.
+    pub fn with_expected_type(mut self, expected_type: LuaType) -> Self {
+        self.expected_type = Some(expected_type);
+        self
+    }
 }
 
 impl Default for InjectGlobalValue {
@@ -125,13 +249,39 @@ impl Default for InjectGlobalValue {
         Self {
             identifier: "".to_owned(),
             value: Expression::nil(),
+            assume_no_env_manipulation: false,
+            expected_type: None,
         }
     }
 }
 
 impl FlawlessRule for InjectGlobalValue {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
-        let mut processor = ValueInjection::new(&self.identifier, self.value.clone());
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        if let Some(expected_type) = self.expected_type {
+            let mut checker = TypeComparisonChecker::new(
+                &self.identifier,
+                expected_type,
+                IdentifierTracker::new()
+                    .with_assume_no_env_manipulation(self.assume_no_env_manipulation),
+            );
+            ScopeVisitor::visit_block(block, &mut checker);
+
+            for mismatch in checker.mismatches {
+                log::warn!(
+                    "[{}] {}: {}",
+                    context.current_path().display(),
+                    INJECT_GLOBAL_VALUE_RULE_NAME,
+                    mismatch
+                );
+            }
+        }
+
+        let mut processor = ValueInjection::new(
+            &self.identifier,
+            self.value.clone(),
+            IdentifierTracker::new()
+                .with_assume_no_env_manipulation(self.assume_no_env_manipulation),
+        );
         ScopeVisitor::visit_block(block, &mut processor);
     }
 }
@@ -184,6 +334,22 @@ impl RuleConfiguration for InjectGlobalValue {
                         );
                     };
                 }
+                "assume_no_env_manipulation" => {
+                    self.assume_no_env_manipulation = value.expect_bool(&key)?;
+                }
+                "expected_type" => {
+                    let expected_type = value.expect_string(&key)?;
+                    self.expected_type = Some(LuaType::parse(&expected_type).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: "expected_type".to_owned(),
+                            message: format!(
+                                "invalid value `{}` (must be `nil`, `boolean`, `number`, \
+                                `string`, `table` or `function`)",
+                                expected_type
+                            ),
+                        }
+                    })?);
+                }
                 _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
             }
         }
@@ -195,6 +361,27 @@ impl RuleConfiguration for InjectGlobalValue {
         INJECT_GLOBAL_VALUE_RULE_NAME
     }
 
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &[
+            "assume_no_env_manipulation",
+            "env",
+            "expected_type",
+            "identifier",
+            "value",
+        ]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "value" => RulePropertyKind::Any,
+            "assume_no_env_manipulation" => RulePropertyKind::Boolean,
+            "expected_type" => RulePropertyKind::Choice(&[
+                "nil", "boolean", "number", "string", "table", "function",
+            ]),
+            _ => RulePropertyKind::String,
+        }
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         let mut rules = RuleProperties::new();
         rules.insert(
@@ -233,8 +420,36 @@ impl RuleConfiguration for InjectGlobalValue {
         };
         rules.insert("value".to_owned(), property_value);
 
+        if self.assume_no_env_manipulation {
+            rules.insert(
+                "assume_no_env_manipulation".to_owned(),
+                RulePropertyValue::Boolean(true),
+            );
+        }
+
+        if let Some(expected_type) = self.expected_type {
+            rules.insert(
+                "expected_type".to_owned(),
+                RulePropertyValue::from(expected_type.as_str()),
+            );
+        }
+
         rules
     }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if self.assume_no_env_manipulation {
+            properties.push("assume_no_env_manipulation");
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
@@ -317,6 +532,85 @@ mod test {
         assert_json_snapshot!("inject_negative_integer_value_as_var", rule);
     }
 
+    fn find_mismatches(code: &str, expected_type: LuaType) -> Vec<String> {
+        let mut block = crate::Parser::default().parse(code).unwrap();
+        let mut checker =
+            TypeComparisonChecker::new("FLAG", expected_type, IdentifierTracker::new());
+        ScopeVisitor::visit_block(&mut block, &mut checker);
+        checker.mismatches
+    }
+
+    #[test]
+    fn find_comparison_with_other_type() {
+        pretty_assertions::assert_eq!(
+            find_mismatches("if type(FLAG) == 'string' then end", LuaType::Number),
+            vec!["`type(FLAG)` is compared with `\"string\"` but `FLAG` is expected to be a `number`"]
+        );
+    }
+
+    #[test]
+    fn find_reversed_not_equal_comparison_with_other_type() {
+        pretty_assertions::assert_eq!(
+            find_mismatches("return 'nil' ~= type(FLAG)", LuaType::Boolean).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn ignore_comparison_with_expected_type() {
+        assert!(find_mismatches("return type(FLAG) == 'number'", LuaType::Number).is_empty());
+    }
+
+    #[test]
+    fn ignore_comparison_with_shadowed_type_function() {
+        assert!(find_mismatches(
+            "local function type() end return type(FLAG) == 'string'",
+            LuaType::Number
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn ignore_comparison_with_local_variable() {
+        assert!(find_mismatches(
+            "local FLAG = 'on' return type(FLAG) == 'string'",
+            LuaType::Number
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn ignore_comparison_of_other_identifier() {
+        assert!(find_mismatches("return type(OTHER) == 'string'", LuaType::Number).is_empty());
+    }
+
+    #[test]
+    fn serialize_inject_with_expected_type() {
+        let rule: Box<dyn Rule> = Box::new(
+            InjectGlobalValue::boolean("DEBUG", true).with_expected_type(LuaType::Boolean),
+        );
+
+        assert_json_snapshot!("inject_true_value_with_expected_type", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_expected_type_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'inject_global_value',
+            identifier: 'DEBUG',
+            value: true,
+            expected_type: 'bool',
+        }"#,
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'expected_type': invalid value `bool` (must be `nil`, \
+            `boolean`, `number`, `string`, `table` or `function`)"
+        );
+    }
+
     #[test]
     fn serialize_inject_float_as_var() {
         let rule: Box<dyn Rule> = Box::new(InjectGlobalValue::number("VAR", 123.45));
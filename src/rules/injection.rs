@@ -0,0 +1,158 @@
+use crate::nodes::{Block, LastStatement, Statement, Token};
+
+use super::empty_do::statement_get_first_token;
+
+/// The priority of a statement injected at the top of a block. Injected statements are
+/// ordered by priority, so that a statement always comes after the statements it may depend
+/// on (for example, a helper function is placed after the required modules it uses).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InjectionPriority {
+    /// Statements that must run before anything else in the file.
+    Directives,
+    /// Statements describing the generated file.
+    Banner,
+    /// Statements that load modules or keep a reference to global values.
+    Requires,
+    /// Helper values and functions used by the processed code.
+    Helpers,
+    /// Constant values extracted from the processed code.
+    Constants,
+    Other,
+}
+
+/// A collection of statements injected at the top of a block by rules.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopStatementInjections {
+    statements: Vec<(InjectionPriority, Statement)>,
+}
+
+impl TopStatementInjections {
+    pub fn push(&mut self, statement: impl Into<Statement>, priority: InjectionPriority) {
+        self.statements.push((priority, statement.into()));
+    }
+
+    pub fn extend(&mut self, injections: TopStatementInjections) {
+        self.statements.extend(injections.statements);
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Inserts the statements at the top of the block, ordered by priority and then by
+    /// insertion order. The comments leading the block (like a license header or a
+    /// `--!strict` directive) stay above the injected statements when possible.
+    pub fn apply(self, block: &mut Block) {
+        let mut statements = self.statements;
+        // the sort is stable, so statements with the same priority keep their insertion order
+        statements.sort_by_key(|(priority, _)| *priority);
+
+        let mut statements: Vec<_> = statements
+            .into_iter()
+            .map(|(_, statement)| statement)
+            .collect();
+
+        if let Some(token) = statements.first_mut().and_then(statement_get_first_token) {
+            if let Some(first_token) = block_get_first_token(block) {
+                token.prepend_leading_trivia(first_token.take_leading_trivia());
+            }
+        }
+
+        for (index, statement) in statements.into_iter().enumerate() {
+            block.insert_statement(index, statement);
+        }
+    }
+}
+
+fn block_get_first_token(block: &mut Block) -> Option<&mut Token> {
+    if block.statements_len() != 0 {
+        return block
+            .first_mut_statement()
+            .and_then(statement_get_first_token);
+    }
+
+    match block.mutate_last_statement() {
+        Some(LastStatement::Break(token)) | Some(LastStatement::Continue(token)) => token.as_mut(),
+        Some(LastStatement::Return(return_statement)) => return_statement
+            .mutate_tokens()
+            .map(|tokens| &mut tokens.r#return),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generator::{DenseLuaGenerator, LuaGenerator};
+    use crate::nodes::{Expression, LocalAssignStatement};
+
+    fn local(name: &str) -> LocalAssignStatement {
+        LocalAssignStatement::from_variable(name).with_value(Expression::nil())
+    }
+
+    fn apply(injections: TopStatementInjections, code: &str) -> String {
+        let mut block = crate::Parser::default().parse(code).unwrap();
+        injections.apply(&mut block);
+
+        let mut generator = DenseLuaGenerator::default();
+        generator.write_block(&block);
+        generator.into_string()
+    }
+
+    #[test]
+    fn orders_statements_by_priority() {
+        let mut injections = TopStatementInjections::default();
+        injections.push(local("constant"), InjectionPriority::Constants);
+        injections.push(local("helper"), InjectionPriority::Helpers);
+        injections.push(local("module"), InjectionPriority::Requires);
+
+        assert_eq!(
+            apply(injections, "return helper"),
+            "local module=nil local helper=nil local constant=nil return helper"
+        );
+    }
+
+    #[test]
+    fn keeps_insertion_order_of_statements_with_the_same_priority() {
+        let mut injections = TopStatementInjections::default();
+        injections.push(local("b"), InjectionPriority::Helpers);
+        injections.push(local("a"), InjectionPriority::Helpers);
+        injections.push(local("c"), InjectionPriority::Other);
+
+        assert_eq!(
+            apply(injections, "print(a, b, c)"),
+            "local b=nil local a=nil local c=nil print(a,b,c)"
+        );
+    }
+
+    #[test]
+    fn moves_leading_comments_above_injected_statements() {
+        let code = "--!strict\nlocal value = 1";
+        let mut block = crate::Parser::default()
+            .preserve_tokens()
+            .parse(code)
+            .unwrap();
+
+        let injected = crate::Parser::default()
+            .preserve_tokens()
+            .parse_statement("local helper = nil\n")
+            .unwrap();
+
+        let mut injections = TopStatementInjections::default();
+        injections.push(injected, InjectionPriority::Helpers);
+        injections.apply(&mut block);
+
+        let first_token = block
+            .first_mut_statement()
+            .and_then(statement_get_first_token)
+            .unwrap();
+
+        assert_eq!(first_token.iter_leading_trivia().count(), 2);
+    }
+}
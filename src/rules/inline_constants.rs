@@ -0,0 +1,444 @@
+use std::mem;
+use std::ops;
+
+use crate::nodes::{
+    Block, Expression, FunctionExpression, FunctionStatement, Identifier, LocalAssignStatement,
+    LocalFunctionStatement, ParentheseExpression, Prefix, Statement, Variable,
+};
+use crate::process::{
+    DefaultVisitor, IdentifierTracker, NodeProcessor, NodeVisitor, Scope, ScopeVisitor,
+};
+use crate::rules::{
+    dynamic_environment_note, skip_dynamic_environment, CompatibilityNote, Context, FlawlessRule,
+    RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties, RulePropertyKind,
+};
+
+/// A processor that finds out how a local variable is used after its declaration.
+struct ConstantUsage<'a> {
+    variable: &'a str,
+    identifier_tracker: IdentifierTracker,
+    function_scopes: Vec<bool>,
+    entering_function: bool,
+    reassigned: bool,
+    captured: bool,
+}
+
+impl<'a> ConstantUsage<'a> {
+    fn new(variable: &'a str) -> Self {
+        Self {
+            variable,
+            identifier_tracker: IdentifierTracker::default(),
+            function_scopes: Vec::new(),
+            entering_function: false,
+            reassigned: false,
+            captured: false,
+        }
+    }
+
+    fn is_variable(&self, identifier: &Identifier) -> bool {
+        identifier.get_name() == self.variable
+            && !self.identifier_tracker.is_identifier_used(self.variable)
+    }
+
+    fn is_in_function(&self) -> bool {
+        self.function_scopes.iter().any(|is_function| *is_function)
+    }
+}
+
+impl Scope for ConstantUsage<'_> {
+    fn push(&mut self) {
+        self.identifier_tracker.push();
+        self.function_scopes
+            .push(mem::take(&mut self.entering_function));
+    }
+
+    fn pop(&mut self) {
+        self.identifier_tracker.pop();
+        self.function_scopes.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.identifier_tracker.insert(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.identifier_tracker.insert_self();
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>) {
+        self.identifier_tracker.insert_local(identifier, value);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.identifier_tracker.insert_local_function(function);
+    }
+}
+
+impl NodeProcessor for ConstantUsage<'_> {
+    fn process_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.entering_function = true;
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name();
+        if name.get_field_names().is_empty()
+            && !name.has_method()
+            && self.is_variable(name.get_name())
+        {
+            self.reassigned = true;
+        }
+        self.entering_function = true;
+    }
+
+    fn process_local_function_statement(&mut self, _: &mut LocalFunctionStatement) {
+        self.entering_function = true;
+    }
+
+    fn process_variable(&mut self, variable: &mut Variable) {
+        if let Variable::Identifier(identifier) = variable {
+            if self.is_variable(identifier) {
+                self.reassigned = true;
+            }
+        }
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        if !self.captured && self.is_in_function() && self.is_variable(identifier) {
+            self.captured = true;
+        }
+    }
+}
+
+/// A processor that replaces the reads of a local variable with its value.
+struct ConstantReplacer<'a> {
+    variable: &'a str,
+    value: &'a Expression,
+    identifier_tracker: IdentifierTracker,
+}
+
+impl<'a> ConstantReplacer<'a> {
+    fn new(variable: &'a str, value: &'a Expression) -> Self {
+        Self {
+            variable,
+            value,
+            identifier_tracker: IdentifierTracker::default(),
+        }
+    }
+
+    fn is_variable(&self, identifier: &Identifier) -> bool {
+        identifier.get_name() == self.variable && !self.is_identifier_used(self.variable)
+    }
+}
+
+impl ops::Deref for ConstantReplacer<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for ConstantReplacer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for ConstantReplacer<'_> {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if matches!(expression, Expression::Identifier(identifier) if self.is_variable(identifier))
+        {
+            *expression = self.value.clone();
+        }
+    }
+
+    fn process_prefix_expression(&mut self, prefix: &mut Prefix) {
+        if matches!(prefix, Prefix::Identifier(identifier) if self.is_variable(identifier)) {
+            *prefix = ParentheseExpression::new(self.value.clone()).into();
+        }
+    }
+}
+
+fn is_literal(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::False(_)
+            | Expression::Nil(_)
+            | Expression::Number(_)
+            | Expression::String(_)
+            | Expression::True(_)
+    )
+}
+
+/// Copies a literal value without the comments and spaces attached to it.
+fn copy_literal(expression: &Expression) -> Expression {
+    match expression {
+        Expression::False(_) => Expression::False(None),
+        Expression::Nil(_) => Expression::Nil(None),
+        Expression::True(_) => Expression::True(None),
+        Expression::Number(number) => {
+            let mut number = number.clone();
+            number.clear_comments();
+            number.clear_whitespaces();
+            number.into()
+        }
+        Expression::String(string) => {
+            let mut string = string.clone();
+            string.clear_comments();
+            string.clear_whitespaces();
+            string.into()
+        }
+        _ => expression.clone(),
+    }
+}
+
+struct InlineConstantsProcessor {
+    conservative: bool,
+}
+
+impl InlineConstantsProcessor {
+    fn new(conservative: bool) -> Self {
+        Self { conservative }
+    }
+
+    fn can_inline(
+        &self,
+        variable: &str,
+        block: &mut Block,
+        index: usize,
+        extra: &mut Option<&mut Expression>,
+    ) -> bool {
+        let mut usage = ConstantUsage::new(variable);
+
+        for statement in block.iter_mut_statements().skip(index + 1) {
+            ScopeVisitor::visit_statement(statement, &mut usage);
+        }
+        if let Some(last_statement) = block.mutate_last_statement() {
+            ScopeVisitor::visit_last_statement(last_statement, &mut usage);
+        }
+        if let Some(expression) = extra {
+            ScopeVisitor::visit_expression(expression, &mut usage);
+        }
+
+        !(usage.reassigned || (self.conservative && usage.captured))
+    }
+
+    fn inline(
+        variable: &str,
+        value: &Expression,
+        block: &mut Block,
+        index: usize,
+        extra: &mut Option<&mut Expression>,
+    ) {
+        let mut replacer = ConstantReplacer::new(variable, value);
+
+        for statement in block.iter_mut_statements().skip(index + 1) {
+            ScopeVisitor::visit_statement(statement, &mut replacer);
+        }
+        if let Some(last_statement) = block.mutate_last_statement() {
+            ScopeVisitor::visit_last_statement(last_statement, &mut replacer);
+        }
+        if let Some(expression) = extra {
+            ScopeVisitor::visit_expression(expression, &mut replacer);
+        }
+    }
+
+    /// Returns the literal values of the variables declared by the given local assignment, for
+    /// each variable that is assigned to a literal.
+    fn find_constants(assign: &LocalAssignStatement) -> Vec<Option<(String, Expression)>> {
+        let variables = assign.get_variables();
+        let mut values = assign.iter_values();
+
+        variables
+            .iter()
+            .enumerate()
+            .map(|(i, variable)| {
+                let name = variable.get_identifier().get_name();
+                let value = values.next().filter(|value| is_literal(value))?;
+
+                // when a name is repeated, only the last variable is visible
+                if variables
+                    .iter()
+                    .skip(i + 1)
+                    .any(|next| next.get_identifier().get_name() == name)
+                {
+                    return None;
+                }
+
+                Some((name.to_owned(), copy_literal(value)))
+            })
+            .collect()
+    }
+}
+
+impl NodeProcessor for InlineConstantsProcessor {
+    fn process_scope(&mut self, block: &mut Block, mut extra: Option<&mut Expression>) {
+        let mut removed_statements = Vec::new();
+
+        for index in 0..block.statements_len() {
+            let constants = match block.iter_statements().nth(index) {
+                Some(Statement::LocalAssign(assign)) => Self::find_constants(assign),
+                _ => continue,
+            };
+
+            let mut all_inlined = true;
+
+            for constant in constants {
+                match constant {
+                    Some((variable, value))
+                        if self.can_inline(&variable, block, index, &mut extra) =>
+                    {
+                        Self::inline(&variable, &value, block, index, &mut extra);
+                    }
+                    _ => {
+                        all_inlined = false;
+                    }
+                }
+            }
+
+            if all_inlined {
+                if let Some(Statement::LocalAssign(assign)) = block.iter_statements().nth(index) {
+                    if assign.iter_values().all(is_literal) {
+                        removed_statements.push(index);
+                    }
+                }
+            }
+        }
+
+        if !removed_statements.is_empty() {
+            let mut index = 0;
+            block.filter_statements(|_| {
+                let keep = !removed_statements.contains(&index);
+                index += 1;
+                keep
+            });
+        }
+    }
+}
+
+pub const INLINE_CONSTANTS_RULE_NAME: &str = "inline_constants";
+
+/// A rule that replaces local variables assigned to a literal value and never reassigned
+/// with their value.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InlineConstants {
+    conservative: bool,
+}
+
+impl Default for InlineConstants {
+    fn default() -> Self {
+        Self { conservative: true }
+    }
+}
+
+impl InlineConstants {
+    pub fn with_conservative(mut self, conservative: bool) -> Self {
+        self.conservative = conservative;
+        self
+    }
+}
+
+impl FlawlessRule for InlineConstants {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        if skip_dynamic_environment(INLINE_CONSTANTS_RULE_NAME, block, context) {
+            return;
+        }
+
+        let mut processor = InlineConstantsProcessor::new(self.conservative);
+        processor.process_scope(block, None);
+        DefaultVisitor::visit_block(block, &mut processor);
+    }
+
+    fn compatibility_check(&self, block: &Block, context: &Context) -> Vec<CompatibilityNote> {
+        dynamic_environment_note(block, context)
+            .into_iter()
+            .collect()
+    }
+}
+
+impl RuleConfiguration for InlineConstants {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "conservative" => {
+                    self.conservative = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        INLINE_CONSTANTS_RULE_NAME
+    }
+
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "local DEBUG = false\n\
+                if DEBUG then\n\
+                \tprint(\"debug\")\n\
+                end",
+        )]
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["conservative"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Boolean
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if !self.conservative {
+            properties.insert("conservative".to_owned(), false.into());
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> InlineConstants {
+        InlineConstants::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_inline_constants", rule);
+    }
+
+    #[test]
+    fn serialize_rule_without_conservative() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_conservative(false));
+
+        assert_json_snapshot!("inline_constants_not_conservative", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'inline_constants',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
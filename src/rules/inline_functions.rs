@@ -0,0 +1,688 @@
+use std::ops;
+
+use crate::nodes::{
+    Block, Expression, FunctionCall, FunctionExpression, FunctionStatement, Identifier,
+    LastStatement, LocalFunctionStatement, ParentheseExpression, Prefix, Statement, Variable,
+};
+use crate::process::processors::FindUsage;
+use crate::process::{
+    DefaultPostVisitor, DefaultVisitor, Evaluator, IdentifierTracker, NodePostProcessor,
+    NodePostVisitor, NodeProcessor, NodeVisitor, ScopeVisitor,
+};
+use crate::rules::{
+    dynamic_environment_note, skip_dynamic_environment, CompatibilityNote, Context, FlawlessRule,
+    RuleConfiguration, RuleConfigurationError, RuleProperties, RulePropertyKind,
+};
+
+const DEFAULT_MAX_BODY_SIZE: usize = 12;
+const DEFAULT_MAX_CALL_SITES: usize = 4;
+
+/// A processor that collects information about the expression returned by a function.
+#[derive(Default)]
+struct BodyAnalysis {
+    identifiers: Vec<String>,
+    expression_count: usize,
+    has_function: bool,
+}
+
+impl NodeProcessor for BodyAnalysis {
+    fn process_expression(&mut self, _: &mut Expression) {
+        self.expression_count += 1;
+    }
+
+    fn process_function_expression(&mut self, _: &mut FunctionExpression) {
+        self.has_function = true;
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        self.identifiers.push(identifier.get_name().to_owned());
+    }
+}
+
+/// A local function that can be inlined at its call sites.
+struct InlineCandidate {
+    name: String,
+    parameters: Vec<String>,
+    /// The returned expression, or `None` when the function does not return anything.
+    body: Option<Expression>,
+    /// The number of times each parameter is used in the body.
+    parameter_uses: Vec<usize>,
+    /// The identifiers used in the body that are not parameters.
+    free_identifiers: Vec<String>,
+    pure_body: bool,
+}
+
+impl InlineCandidate {
+    fn new(function: &LocalFunctionStatement, max_body_size: usize) -> Option<Self> {
+        if function.is_variadic() {
+            return None;
+        }
+
+        let block = function.get_block();
+        if block.statements_len() != 0 {
+            return None;
+        }
+
+        let mut body = match block.get_last_statement() {
+            None => None,
+            Some(LastStatement::Return(statement)) => match statement.len() {
+                0 => None,
+                1 => statement.iter_expressions().next().cloned(),
+                _ => return None,
+            },
+            Some(_) => return None,
+        };
+
+        let parameters: Vec<String> = function
+            .iter_parameters()
+            .map(|parameter| parameter.get_name().to_owned())
+            .collect();
+
+        let mut analysis = BodyAnalysis::default();
+        if let Some(body) = body.as_mut() {
+            DefaultVisitor::visit_expression(body, &mut analysis);
+        }
+
+        if analysis.has_function || analysis.expression_count > max_body_size {
+            return None;
+        }
+
+        let name = function.get_name().to_owned();
+
+        if analysis.identifiers.contains(&name) && !parameters.contains(&name) {
+            // recursive function
+            return None;
+        }
+
+        let parameter_uses = parameters
+            .iter()
+            .enumerate()
+            .map(|(i, parameter)| {
+                // when a parameter name is repeated, only the last one is visible
+                if parameters.iter().skip(i + 1).any(|next| next == parameter) {
+                    0
+                } else {
+                    analysis
+                        .identifiers
+                        .iter()
+                        .filter(|identifier| *identifier == parameter)
+                        .count()
+                }
+            })
+            .collect();
+
+        let mut free_identifiers: Vec<String> = analysis
+            .identifiers
+            .into_iter()
+            .filter(|identifier| !parameters.contains(identifier))
+            .collect();
+        free_identifiers.sort();
+        free_identifiers.dedup();
+
+        let pure_body = body
+            .as_ref()
+            .map(|body| !Evaluator::default().has_side_effects(body))
+            .unwrap_or(true);
+
+        Some(Self {
+            name,
+            parameters,
+            body,
+            parameter_uses,
+            free_identifiers,
+            pure_body,
+        })
+    }
+
+    fn is_call(&self, call: &FunctionCall) -> bool {
+        call.get_method().is_none()
+            && matches!(call.get_prefix(), Prefix::Identifier(identifier) if identifier.get_name() == self.name)
+    }
+}
+
+/// Returns `true` if the expression can be duplicated without changing the behavior of the code.
+fn is_duplicable(expression: &Expression) -> bool {
+    match expression {
+        Expression::False(_)
+        | Expression::Identifier(_)
+        | Expression::Nil(_)
+        | Expression::Number(_)
+        | Expression::String(_)
+        | Expression::True(_) => true,
+        Expression::Parenthese(parenthese) => is_duplicable(parenthese.inner_expression()),
+        _ => false,
+    }
+}
+
+fn is_literal(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::False(_)
+            | Expression::Nil(_)
+            | Expression::Number(_)
+            | Expression::String(_)
+            | Expression::True(_)
+    )
+}
+
+/// A processor that replaces the parameters of a function with the arguments of a call.
+struct ParameterReplacer<'a> {
+    parameters: &'a [String],
+    arguments: &'a [Expression],
+}
+
+impl ParameterReplacer<'_> {
+    fn get_argument(&self, identifier: &Identifier) -> Option<Expression> {
+        let name = identifier.get_name();
+        let index = self
+            .parameters
+            .iter()
+            .rposition(|parameter| parameter == name)?;
+
+        Some(
+            self.arguments
+                .get(index)
+                .cloned()
+                .unwrap_or_else(Expression::nil),
+        )
+    }
+}
+
+impl NodeProcessor for ParameterReplacer<'_> {}
+
+impl NodePostProcessor for ParameterReplacer<'_> {
+    fn process_after_expression(&mut self, expression: &mut Expression) {
+        if let Expression::Identifier(identifier) = expression {
+            if let Some(argument) = self.get_argument(identifier) {
+                *expression = argument;
+            }
+        }
+    }
+
+    fn process_after_prefix_expression(&mut self, prefix: &mut Prefix) {
+        if let Prefix::Identifier(identifier) = prefix {
+            if let Some(argument) = self.get_argument(identifier) {
+                *prefix = argument.into();
+            }
+        }
+    }
+}
+
+/// A processor that finds out how a local function is used after its declaration.
+struct FunctionUsage<'a> {
+    candidate: &'a InlineCandidate,
+    identifier_tracker: IdentifierTracker,
+    reassigned: bool,
+    call_sites: usize,
+}
+
+impl<'a> FunctionUsage<'a> {
+    fn new(candidate: &'a InlineCandidate) -> Self {
+        Self {
+            candidate,
+            identifier_tracker: IdentifierTracker::default(),
+            reassigned: false,
+            call_sites: 0,
+        }
+    }
+
+    /// Returns `true` if the identifier refers to the function or to one of the variables used
+    /// by the function.
+    fn is_tracked(&self, identifier: &Identifier) -> bool {
+        let name = identifier.get_name();
+
+        (name == self.candidate.name
+            || self
+                .candidate
+                .free_identifiers
+                .iter()
+                .any(|free| free == name))
+            && !self.is_identifier_used(name)
+    }
+}
+
+impl ops::Deref for FunctionUsage<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for FunctionUsage<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for FunctionUsage<'_> {
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if self.candidate.is_call(call) && !self.is_identifier_used(&self.candidate.name) {
+            self.call_sites += 1;
+        }
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name();
+        if name.get_field_names().is_empty()
+            && !name.has_method()
+            && self.is_tracked(name.get_name())
+        {
+            self.reassigned = true;
+        }
+    }
+
+    fn process_variable(&mut self, variable: &mut Variable) {
+        if let Variable::Identifier(identifier) = variable {
+            if self.is_tracked(identifier) {
+                self.reassigned = true;
+            }
+        }
+    }
+}
+
+/// A processor that replaces the calls to a local function with its returned expression.
+struct FunctionInliner<'a> {
+    candidate: &'a InlineCandidate,
+    identifier_tracker: IdentifierTracker,
+    evaluator: Evaluator,
+    inlined_calls: usize,
+}
+
+impl<'a> FunctionInliner<'a> {
+    fn new(candidate: &'a InlineCandidate) -> Self {
+        Self {
+            candidate,
+            identifier_tracker: IdentifierTracker::default(),
+            evaluator: Evaluator::default(),
+            inlined_calls: 0,
+        }
+    }
+
+    fn inline_call_site(&mut self, call: &mut FunctionCall) -> Option<Expression> {
+        if !self.candidate.is_call(call) {
+            return None;
+        }
+
+        // inline the calls in the arguments first, so that nested calls like `f(f(1))` can
+        // be inlined when the inner call becomes free of side effects
+        ScopeVisitor::visit_arguments(call.mutate_arguments(), self);
+
+        self.inline_call(call)
+    }
+
+    fn inline_call(&mut self, call: &FunctionCall) -> Option<Expression> {
+        let candidate = self.candidate;
+
+        if !candidate.is_call(call)
+            || self.is_identifier_used(&candidate.name)
+            || candidate
+                .free_identifiers
+                .iter()
+                .any(|identifier| self.is_identifier_used(identifier))
+        {
+            return None;
+        }
+
+        let arguments = call.get_arguments().clone().to_expressions();
+
+        if arguments.len() < candidate.parameters.len()
+            && arguments
+                .last()
+                .filter(|argument| self.evaluator.can_return_multiple_values(argument))
+                .is_some()
+        {
+            // the last argument may provide the values of multiple parameters
+            return None;
+        }
+
+        let mut effectful_arguments = 0;
+
+        for (i, argument) in arguments.iter().enumerate() {
+            let uses = candidate.parameter_uses.get(i).copied().unwrap_or(0);
+            let has_side_effects = self.evaluator.has_side_effects(argument);
+
+            if has_side_effects {
+                effectful_arguments += 1;
+            }
+
+            let can_substitute = match uses {
+                0 => !has_side_effects,
+                1 => true,
+                _ => is_duplicable(argument),
+            };
+
+            if !can_substitute {
+                return None;
+            }
+        }
+
+        if effectful_arguments > 1 {
+            return None;
+        }
+
+        if effectful_arguments == 1 {
+            // the argument side effects must not be observable from the rest of the code
+            let other_arguments_are_literals = arguments
+                .iter()
+                .filter(|argument| !self.evaluator.has_side_effects(argument))
+                .all(is_literal);
+
+            if !candidate.pure_body
+                || !candidate.free_identifiers.is_empty()
+                || !other_arguments_are_literals
+            {
+                return None;
+            }
+        }
+
+        let arguments: Vec<Expression> = arguments
+            .into_iter()
+            .map(|argument| {
+                if self.evaluator.can_return_multiple_values(&argument) {
+                    ParentheseExpression::new(argument).into()
+                } else {
+                    argument
+                }
+            })
+            .collect();
+
+        let mut expression = candidate.body.clone().unwrap_or_else(Expression::nil);
+
+        let mut replacer = ParameterReplacer {
+            parameters: &candidate.parameters,
+            arguments: &arguments,
+        };
+        DefaultPostVisitor::visit_expression(&mut expression, &mut replacer);
+
+        self.inlined_calls += 1;
+
+        Some(expression)
+    }
+}
+
+impl ops::Deref for FunctionInliner<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for FunctionInliner<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for FunctionInliner<'_> {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if let Expression::Call(call) = expression {
+            if let Some(inlined) = self.inline_call_site(call) {
+                *expression = inlined;
+            }
+        }
+    }
+
+    fn process_prefix_expression(&mut self, prefix: &mut Prefix) {
+        if let Prefix::Call(call) = prefix {
+            if let Some(inlined) = self.inline_call_site(call) {
+                *prefix = inlined.into();
+            }
+        }
+    }
+}
+
+struct InlineFunctionsProcessor {
+    max_body_size: usize,
+    max_call_sites: usize,
+}
+
+impl InlineFunctionsProcessor {
+    fn new(max_body_size: usize, max_call_sites: usize) -> Self {
+        Self {
+            max_body_size,
+            max_call_sites,
+        }
+    }
+
+    fn visit_after<T>(
+        processor: &mut T,
+        block: &mut Block,
+        index: usize,
+        extra: &mut Option<&mut Expression>,
+    ) where
+        T: NodeProcessor + ops::DerefMut<Target = IdentifierTracker>,
+    {
+        for statement in block.iter_mut_statements().skip(index + 1) {
+            ScopeVisitor::visit_statement(statement, processor);
+        }
+        if let Some(last_statement) = block.mutate_last_statement() {
+            ScopeVisitor::visit_last_statement(last_statement, processor);
+        }
+        if let Some(expression) = extra {
+            ScopeVisitor::visit_expression(expression, processor);
+        }
+    }
+
+    /// Inlines the calls to the local function at the given index and returns `true` if
+    /// the function is not used anymore.
+    fn inline_function(
+        &self,
+        block: &mut Block,
+        index: usize,
+        extra: &mut Option<&mut Expression>,
+    ) -> bool {
+        let candidate = match block.iter_statements().nth(index) {
+            Some(Statement::LocalFunction(function)) => {
+                match InlineCandidate::new(function, self.max_body_size) {
+                    Some(candidate) => candidate,
+                    None => return false,
+                }
+            }
+            _ => return false,
+        };
+
+        let mut usage = FunctionUsage::new(&candidate);
+        Self::visit_after(&mut usage, block, index, extra);
+
+        if usage.reassigned || usage.call_sites == 0 || usage.call_sites > self.max_call_sites {
+            return false;
+        }
+
+        let mut inliner = FunctionInliner::new(&candidate);
+        Self::visit_after(&mut inliner, block, index, extra);
+
+        if inliner.inlined_calls == 0 {
+            return false;
+        }
+
+        let mut find_usage = FindUsage::new(&candidate.name);
+        Self::visit_after(&mut find_usage, block, index, extra);
+
+        !find_usage.has_found_usage()
+    }
+}
+
+impl NodeProcessor for InlineFunctionsProcessor {
+    fn process_scope(&mut self, block: &mut Block, mut extra: Option<&mut Expression>) {
+        let mut removed_statements = Vec::new();
+
+        for index in 0..block.statements_len() {
+            if self.inline_function(block, index, &mut extra) {
+                removed_statements.push(index);
+            }
+        }
+
+        if !removed_statements.is_empty() {
+            let mut index = 0;
+            block.filter_statements(|_| {
+                let keep = !removed_statements.contains(&index);
+                index += 1;
+                keep
+            });
+        }
+    }
+}
+
+/// Collects the local functions that are never inlined because they are variadic.
+#[derive(Default)]
+struct VariadicFunctionFinder {
+    notes: Vec<CompatibilityNote>,
+}
+
+impl NodeProcessor for VariadicFunctionFinder {
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        if function.is_variadic() {
+            let identifier = function.get_identifier();
+            self.notes.push(
+                CompatibilityNote::conservative(format!(
+                    "local function `{}` is variadic and is never inlined",
+                    identifier.get_name()
+                ))
+                .with_line(
+                    identifier
+                        .get_token()
+                        .and_then(|token| token.get_line_number()),
+                ),
+            );
+        }
+    }
+}
+
+pub const INLINE_FUNCTIONS_RULE_NAME: &str = "inline_functions";
+
+/// A rule that replaces calls to small local functions with the expression they return.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InlineFunctions {
+    max_body_size: usize,
+    max_call_sites: usize,
+}
+
+impl Default for InlineFunctions {
+    fn default() -> Self {
+        Self {
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            max_call_sites: DEFAULT_MAX_CALL_SITES,
+        }
+    }
+}
+
+impl InlineFunctions {
+    pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    pub fn with_max_call_sites(mut self, max_call_sites: usize) -> Self {
+        self.max_call_sites = max_call_sites;
+        self
+    }
+}
+
+impl FlawlessRule for InlineFunctions {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        if skip_dynamic_environment(INLINE_FUNCTIONS_RULE_NAME, block, context) {
+            return;
+        }
+
+        let mut processor = InlineFunctionsProcessor::new(self.max_body_size, self.max_call_sites);
+        processor.process_scope(block, None);
+        DefaultVisitor::visit_block(block, &mut processor);
+    }
+
+    fn compatibility_check(&self, block: &Block, context: &Context) -> Vec<CompatibilityNote> {
+        if let Some(note) = dynamic_environment_note(block, context) {
+            return vec![note];
+        }
+
+        let mut finder = VariadicFunctionFinder::default();
+        DefaultVisitor::visit_block(&mut block.clone(), &mut finder);
+        finder.notes
+    }
+}
+
+impl RuleConfiguration for InlineFunctions {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "max_body_size" => {
+                    self.max_body_size = value.expect_usize(&key)?;
+                }
+                "max_call_sites" => {
+                    self.max_call_sites = value.expect_usize(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        INLINE_FUNCTIONS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["max_body_size", "max_call_sites"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Usize
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.max_body_size != DEFAULT_MAX_BODY_SIZE {
+            properties.insert("max_body_size".to_owned(), self.max_body_size.into());
+        }
+
+        if self.max_call_sites != DEFAULT_MAX_CALL_SITES {
+            properties.insert("max_call_sites".to_owned(), self.max_call_sites.into());
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> InlineFunctions {
+        InlineFunctions::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_inline_functions", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_limits() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_max_body_size(4).with_max_call_sites(1));
+
+        assert_json_snapshot!("inline_functions_with_limits", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'inline_functions',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
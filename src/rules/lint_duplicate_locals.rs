@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::nodes::{Block, Statement, Token, TypedIdentifier};
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult, RuleProperties,
+    RulePropertyKind,
+};
+use crate::utils::log;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DuplicateKind {
+    /// The name is declared more than once by the same local statement.
+    SameStatement,
+    /// The name was already declared by a previous local statement of the same block.
+    Redeclared { previous_line: Option<usize> },
+}
+
+/// A local name that is declared while another local with the same name is visible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DuplicateLocal {
+    name: String,
+    line: Option<usize>,
+    kind: DuplicateKind,
+}
+
+impl fmt::Display for DuplicateLocal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "local `{}`", self.name)?;
+        if let Some(line) = self.line {
+            write!(f, " (line {})", line)?;
+        }
+        match &self.kind {
+            DuplicateKind::SameStatement => write!(
+                f,
+                " is declared more than once in the same statement: only the last one is visible"
+            ),
+            DuplicateKind::Redeclared { previous_line } => {
+                write!(f, " shadows a local declared in the same block")?;
+                if let Some(line) = previous_line {
+                    write!(f, " (line {})", line)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn identifier_line(identifier: &TypedIdentifier) -> Option<usize> {
+    identifier.get_token().and_then(Token::get_line_number)
+}
+
+#[derive(Default)]
+struct DuplicateLocalsFinder {
+    duplicates: Vec<DuplicateLocal>,
+}
+
+impl DuplicateLocalsFinder {
+    fn declare(
+        &mut self,
+        declared: &mut HashMap<String, Option<usize>>,
+        name: &str,
+        line: Option<usize>,
+    ) {
+        if let Some(previous_line) = declared.insert(name.to_owned(), line) {
+            self.duplicates.push(DuplicateLocal {
+                name: name.to_owned(),
+                line,
+                kind: DuplicateKind::Redeclared { previous_line },
+            });
+        }
+    }
+}
+
+impl NodeProcessor for DuplicateLocalsFinder {
+    fn process_block(&mut self, block: &mut Block) {
+        let mut declared = HashMap::new();
+
+        for statement in block.iter_statements() {
+            match statement {
+                Statement::LocalAssign(assign) => {
+                    let mut statement_names: Vec<(&str, Option<usize>)> = Vec::new();
+
+                    for variable in assign.iter_variables() {
+                        let name = variable.get_name();
+                        let line = identifier_line(variable);
+
+                        if statement_names.iter().any(|(other, _)| *other == name) {
+                            self.duplicates.push(DuplicateLocal {
+                                name: name.to_owned(),
+                                line,
+                                kind: DuplicateKind::SameStatement,
+                            });
+                        } else {
+                            statement_names.push((name, line));
+                        }
+                    }
+
+                    for (name, line) in statement_names {
+                        self.declare(&mut declared, name, line);
+                    }
+                }
+                Statement::LocalFunction(function) => {
+                    let line = function
+                        .get_identifier()
+                        .get_token()
+                        .and_then(Token::get_line_number);
+                    self.declare(&mut declared, function.get_name(), line);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+pub const LINT_DUPLICATE_LOCALS_RULE_NAME: &str = "lint_duplicate_locals";
+
+/// How duplicated locals found by the [`LintDuplicateLocals`] rule are reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateLocalSeverity {
+    /// Log a warning for each duplicated local.
+    #[default]
+    Warning,
+    /// Fail the processing of the file.
+    Error,
+}
+
+impl DuplicateLocalSeverity {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "warning" => Some(Self::Warning),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// A rule that reports local names declared more than once in a single statement, and
+/// locals that shadow another local declared in the same block.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LintDuplicateLocals {
+    severity: DuplicateLocalSeverity,
+}
+
+impl LintDuplicateLocals {
+    pub fn with_severity(mut self, severity: DuplicateLocalSeverity) -> Self {
+        self.severity = severity;
+        self
+    }
+}
+
+impl Rule for LintDuplicateLocals {
+    fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
+        let mut finder = DuplicateLocalsFinder::default();
+        DefaultVisitor::visit_block(block, &mut finder);
+
+        if finder.duplicates.is_empty() {
+            return Ok(());
+        }
+
+        match self.severity {
+            DuplicateLocalSeverity::Warning => {
+                for duplicate in finder.duplicates {
+                    log::warn!("[{}] {}", context.current_path().display(), duplicate);
+                }
+                Ok(())
+            }
+            DuplicateLocalSeverity::Error => Err(finder
+                .duplicates
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")),
+        }
+    }
+}
+
+impl RuleConfiguration for LintDuplicateLocals {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "severity" => {
+                    let severity = value.expect_string(&key)?;
+                    self.severity = DuplicateLocalSeverity::parse(&severity).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: key.clone(),
+                            message: format!(
+                                "invalid severity `{}` (must be `warning` or `error`)",
+                                severity
+                            ),
+                        }
+                    })?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        LINT_DUPLICATE_LOCALS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["severity"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Choice(&["warning", "error"])
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.severity != DuplicateLocalSeverity::default() {
+            properties.insert("severity".to_owned(), self.severity.as_str().into());
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+    use crate::Parser;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> LintDuplicateLocals {
+        LintDuplicateLocals::default()
+    }
+
+    fn find_duplicates(code: &str) -> Vec<String> {
+        let mut block = Parser::default().preserve_tokens().parse(code).unwrap();
+        let mut finder = DuplicateLocalsFinder::default();
+        DefaultVisitor::visit_block(&mut block, &mut finder);
+        finder.duplicates.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_lint_duplicate_locals", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_severity() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_severity(DuplicateLocalSeverity::Error));
+
+        assert_json_snapshot!("lint_duplicate_locals_with_severity", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_severity_should_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'lint_duplicate_locals',
+            severity: 'fatal',
+        }"#,
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'severity': invalid severity `fatal` (must be `warning` or `error`)"
+        );
+    }
+
+    #[test]
+    fn distinct_locals_have_no_duplicates() {
+        assert!(find_duplicates("local a, b = 1, 2 local c = a + b").is_empty());
+    }
+
+    #[test]
+    fn local_in_nested_block_is_not_reported() {
+        assert!(find_duplicates("local a = 1 do local a = 2 end").is_empty());
+    }
+
+    #[test]
+    fn function_parameter_is_not_reported() {
+        assert!(find_duplicates("local a = 1 local function f(a) local b = a end").is_empty());
+    }
+
+    #[test]
+    fn duplicate_in_same_statement() {
+        pretty_assertions::assert_eq!(
+            find_duplicates("local x, y, x = 1, 2, 3"),
+            vec![
+                "local `x` (line 1) is declared more than once in the same statement: \
+                only the last one is visible"
+            ]
+        );
+    }
+
+    #[test]
+    fn redeclared_in_same_block() {
+        pretty_assertions::assert_eq!(
+            find_duplicates("local x = 1\nprint(x)\nlocal x = 2"),
+            vec!["local `x` (line 3) shadows a local declared in the same block (line 1)"]
+        );
+    }
+
+    #[test]
+    fn local_function_redeclares_local() {
+        pretty_assertions::assert_eq!(
+            find_duplicates("local f = nil\nlocal function f() end"),
+            vec!["local `f` (line 2) shadows a local declared in the same block (line 1)"]
+        );
+    }
+
+    #[test]
+    fn redeclared_in_nested_function_block() {
+        pretty_assertions::assert_eq!(
+            find_duplicates("local function f()\n  local a = 1\n  local a = 2\nend"),
+            vec!["local `a` (line 3) shadows a local declared in the same block (line 2)"]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use crate::nodes::{Block, LastStatement, Statement};
+
+/// The statements found in a loop body that exit the current iteration of that loop.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LoopExits {
+    pub(crate) has_break: bool,
+    pub(crate) has_continue: bool,
+}
+
+impl LoopExits {
+    /// Finds the `break` and `continue` statements that apply to the loop owning the given
+    /// block. Nested loops and functions are not inspected.
+    pub(crate) fn find(block: &Block) -> Self {
+        let mut exits = Self::default();
+        exits.visit_block(block);
+        exits
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        for statement in block.iter_statements() {
+            match statement {
+                Statement::Do(do_statement) => self.visit_block(do_statement.get_block()),
+                Statement::If(if_statement) => {
+                    for branch in if_statement.iter_branches() {
+                        self.visit_block(branch.get_block());
+                    }
+                    if let Some(else_block) = if_statement.get_else_block() {
+                        self.visit_block(else_block);
+                    }
+                }
+                Statement::Assign(_)
+                | Statement::Call(_)
+                | Statement::CompoundAssign(_)
+                | Statement::Function(_)
+                | Statement::GenericFor(_)
+                | Statement::LocalAssign(_)
+                | Statement::LocalFunction(_)
+                | Statement::NumericFor(_)
+                | Statement::Repeat(_)
+                | Statement::While(_)
+                | Statement::TypeDeclaration(_)
+                | Statement::Verbatim(_) => {}
+            }
+        }
+
+        match block.get_last_statement() {
+            Some(LastStatement::Break(_)) => self.has_break = true,
+            Some(LastStatement::Continue(_)) => self.has_continue = true,
+            Some(LastStatement::Return(_)) | None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Parser;
+
+    fn find_exits(code: &str) -> LoopExits {
+        LoopExits::find(&Parser::default().parse(code).expect("unable to parse code"))
+    }
+
+    #[test]
+    fn empty_block() {
+        assert_eq!(find_exits(""), LoopExits::default());
+    }
+
+    #[test]
+    fn break_in_if_statement() {
+        assert!(find_exits("if a then print(a) else break end").has_break);
+    }
+
+    #[test]
+    fn continue_in_do_statement() {
+        assert!(find_exits("do continue end").has_continue);
+    }
+
+    #[test]
+    fn break_in_nested_loop_is_ignored() {
+        assert_eq!(
+            find_exits("while true do break end repeat break until false"),
+            LoopExits::default()
+        );
+    }
+}
@@ -1,35 +1,252 @@
-use crate::nodes::{Block, FunctionStatement};
-use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+use std::collections::HashSet;
+use std::mem;
+use std::ops;
+use std::path::Path;
+
+use crate::nodes::{
+    Block, Expression, FieldExpression, FunctionCall, FunctionExpression, FunctionName,
+    FunctionStatement, GenericForStatement, LocalAssignStatement, LocalFunctionStatement,
+    NumericForStatement, Prefix, TupleArguments, TypedIdentifier,
+};
+use crate::process::{DefaultVisitor, IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
+    RulePropertyKind,
 };
+use crate::utils::log;
+
+const SELF_IDENTIFIER: &str = "self";
+
+fn format_function_name(name: &FunctionName) -> String {
+    let mut formatted = name.get_name().get_name().to_owned();
+    for field in name.get_field_names() {
+        formatted.push('.');
+        formatted.push_str(field.get_name());
+    }
+    if let Some(method) = name.get_method() {
+        formatted.push(':');
+        formatted.push_str(method.get_name());
+    }
+    formatted
+}
+
+fn declares_self<'a>(mut identifiers: impl Iterator<Item = &'a TypedIdentifier>) -> bool {
+    identifiers.any(|identifier| identifier.get_name() == SELF_IDENTIFIER)
+}
+
+/// Counts the declarations of `self` in a block, including the implicit `self` of nested
+/// method definitions.
+#[derive(Default)]
+struct NestedSelfCounter {
+    count: usize,
+}
+
+impl NodeProcessor for NestedSelfCounter {
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        if function.get_name().has_method() || declares_self(function.get_parameters().iter()) {
+            self.count += 1;
+        }
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        if declares_self(function.iter_parameters()) {
+            self.count += 1;
+        }
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        if function.get_name() == SELF_IDENTIFIER || declares_self(function.iter_parameters()) {
+            self.count += 1;
+        }
+    }
+
+    fn process_local_assign_statement(&mut self, assign: &mut LocalAssignStatement) {
+        if declares_self(assign.iter_variables()) {
+            self.count += 1;
+        }
+    }
+
+    fn process_generic_for_statement(&mut self, generic_for: &mut GenericForStatement) {
+        if declares_self(generic_for.iter_identifiers()) {
+            self.count += 1;
+        }
+    }
+
+    fn process_numeric_for_statement(&mut self, numeric_for: &mut NumericForStatement) {
+        if numeric_for.get_identifier().get_name() == SELF_IDENTIFIER {
+            self.count += 1;
+        }
+    }
+}
 
-use super::verify_no_rule_properties;
+struct FunctionMutator<'a> {
+    path: &'a Path,
+    converted: usize,
+    /// The methods defined directly on a variable (like `Counter:increment`), with the name
+    /// of the variable and the name of the method.
+    converted_methods: HashSet<(String, String)>,
+}
 
-struct FunctionMutator;
+impl<'a> FunctionMutator<'a> {
+    fn new(path: &'a Path) -> Self {
+        Self {
+            path,
+            converted: 0,
+            converted_methods: HashSet::new(),
+        }
+    }
+}
 
-impl NodeProcessor for FunctionMutator {
+impl NodeProcessor for FunctionMutator<'_> {
     fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        if !function.get_name().has_method() {
+            return;
+        }
+
+        let mut counter = NestedSelfCounter::default();
+        DefaultVisitor::visit_block(function.mutate_block(), &mut counter);
+
+        let name = function.get_name();
+
+        if counter.count > 0 {
+            log::debug!(
+                "[{}] `{}` declares `self` {} time{} in nested scopes: these shadow the \
+                 `self` parameter added when converting the method",
+                self.path.display(),
+                format_function_name(name),
+                counter.count,
+                if counter.count > 1 { "s" } else { "" },
+            );
+        }
+
+        if let (Some(method), true) = (name.get_method(), name.get_field_names().is_empty()) {
+            self.converted_methods.insert((
+                name.get_name().get_name().to_owned(),
+                method.get_name().to_owned(),
+            ));
+        }
+
         function.remove_method();
+        self.converted += 1;
+    }
+}
+
+/// Rewrites the method calls to the converted methods into regular calls, when the called
+/// value is a local variable (so that reading it twice does not change the behavior).
+struct MethodCallMutator<'a> {
+    converted_methods: &'a HashSet<(String, String)>,
+    identifier_tracker: IdentifierTracker,
+}
+
+impl ops::Deref for MethodCallMutator<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for MethodCallMutator<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl<'a> MethodCallMutator<'a> {
+    fn new(converted_methods: &'a HashSet<(String, String)>) -> Self {
+        Self {
+            converted_methods,
+            identifier_tracker: IdentifierTracker::new(),
+        }
+    }
+
+    fn should_convert(&self, call: &FunctionCall) -> bool {
+        match (call.get_prefix(), call.get_method()) {
+            (Prefix::Identifier(table), Some(method)) => {
+                self.is_identifier_used(table.get_name())
+                    && self
+                        .converted_methods
+                        .contains(&(table.get_name().to_owned(), method.get_name().to_owned()))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl NodeProcessor for MethodCallMutator<'_> {
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if !self.should_convert(call) {
+            return;
+        }
+
+        let (table, method) = match (call.get_prefix().clone(), call.take_method()) {
+            (Prefix::Identifier(table), Some(method)) => (table, method),
+            _ => return,
+        };
+
+        let mut arguments = mem::take(call.mutate_arguments()).to_expressions();
+        arguments.insert(0, Expression::identifier(table.get_name()));
+
+        *call.mutate_prefix() = FieldExpression::new(table, method).into();
+        call.set_arguments(TupleArguments::new(arguments).into());
     }
 }
 
 pub const REMOVE_METHOD_DEFINITION_RULE_NAME: &str = "remove_method_definition";
 
 /// Change method functions into regular functions.
-#[derive(Debug, Default, PartialEq, Eq)]
-pub struct RemoveMethodDefinition {}
+#[derive(Debug, PartialEq, Eq)]
+pub struct RemoveMethodDefinition {
+    preserve_method_calls: bool,
+}
+
+impl Default for RemoveMethodDefinition {
+    fn default() -> Self {
+        Self {
+            preserve_method_calls: true,
+        }
+    }
+}
+
+impl RemoveMethodDefinition {
+    /// When `false`, converts the method calls to the converted methods (like
+    /// `Counter:increment()`) into regular calls (like `Counter.increment(Counter)`) when the
+    /// called value is a local variable.
+    pub fn with_preserve_method_calls(mut self, value: bool) -> Self {
+        self.preserve_method_calls = value;
+        self
+    }
+}
 
 impl FlawlessRule for RemoveMethodDefinition {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
-        let mut processor = FunctionMutator;
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor = FunctionMutator::new(context.current_path());
         DefaultVisitor::visit_block(block, &mut processor);
+
+        if processor.converted == 0 {
+            return;
+        }
+
+        // the `self` parameters are regular locals now: other rules must not generate it
+        context.reserve_identifier(SELF_IDENTIFIER);
+
+        if !self.preserve_method_calls {
+            let mut call_mutator = MethodCallMutator::new(&processor.converted_methods);
+            ScopeVisitor::visit_block(block, &mut call_mutator);
+        }
     }
 }
 
 impl RuleConfiguration for RemoveMethodDefinition {
     fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
-        verify_no_rule_properties(&properties)?;
+        for (key, value) in properties {
+            match key.as_str() {
+                "preserve_method_calls" => {
+                    self.preserve_method_calls = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
 
         Ok(())
     }
@@ -38,15 +255,43 @@ impl RuleConfiguration for RemoveMethodDefinition {
         REMOVE_METHOD_DEFINITION_RULE_NAME
     }
 
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["preserve_method_calls"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Boolean
+    }
+
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "local Counter = {}\n\
+                function Counter:increment()\n\
+                \tself.value = self.value + 1\n\
+                end",
+        )]
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
-        RuleProperties::new()
+        let mut properties = RuleProperties::new();
+
+        if !self.preserve_method_calls {
+            properties.insert("preserve_method_calls".to_owned(), false.into());
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::rules::Rule;
+    use crate::rules::{ContextBuilder, Rule};
+    use crate::{Parser, Resources};
 
     use insta::assert_json_snapshot;
 
@@ -58,11 +303,29 @@ mod test {
         Box::new(rule)
     }
 
+    fn reserved_identifiers(code: &str) -> Vec<String> {
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new("test.lua", &resources, code).build();
+        let mut block = Parser::default().parse(code).unwrap();
+
+        new_rule().flawless_process(&mut block, &context);
+
+        context.take_reserved_identifiers()
+    }
+
     #[test]
     fn serialize_default_rule() {
         assert_json_snapshot!("default_remove_method_definition", wrap(new_rule()));
     }
 
+    #[test]
+    fn serialize_rule_without_preserving_method_calls() {
+        assert_json_snapshot!(
+            "remove_method_definition_without_preserving_method_calls",
+            wrap(new_rule().with_preserve_method_calls(false))
+        );
+    }
+
     #[test]
     fn configure_with_extra_field_error() {
         let result = json5::from_str::<Box<dyn Rule>>(
@@ -73,4 +336,34 @@ mod test {
         );
         pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
     }
+
+    #[test]
+    fn reserve_self_when_a_method_is_converted() {
+        pretty_assertions::assert_eq!(
+            reserved_identifiers("function Counter:increment() end"),
+            vec!["self".to_owned()]
+        );
+    }
+
+    #[test]
+    fn does_not_reserve_self_without_methods() {
+        assert!(reserved_identifiers("function Counter.increment(self) end").is_empty());
+    }
+
+    #[test]
+    fn count_nested_self_declarations() {
+        let mut block = Parser::default()
+            .parse(
+                "local self = 1 \
+                function self:helper() end \
+                local function f(self) end \
+                for self in pairs({}) do end \
+                return function(a, self) end",
+            )
+            .unwrap();
+        let mut counter = NestedSelfCounter::default();
+        DefaultVisitor::visit_block(&mut block, &mut counter);
+
+        assert_eq!(counter.count, 5);
+    }
 }
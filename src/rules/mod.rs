@@ -1,80 +1,188 @@
 //! A module that contains the different rules that mutates a Lua block.
 
 mod append_text_comment;
+mod artifact;
 pub mod bundle;
 mod call_parens;
+mod check_assignment_arity;
+mod compatibility;
 mod compute_expression;
+mod conditional_compilation;
 mod configuration_error;
+mod convert_if_to_lookup;
 mod convert_index_to_field;
+mod convert_numeric_for_to_while;
 mod convert_require;
+mod convert_while_to_numeric_for;
+mod deduplicate_blocks;
+mod default_rules;
+mod dynamic_environment;
 mod empty_do;
+mod extract_callbacks;
+mod extract_strings;
+mod factor_common_branch_code;
 mod filter_early_return;
+mod first_token;
+mod fix_global_leaks;
+mod flatten_closures;
+mod format_call_chains;
+mod freeze_constant_tables;
+mod generated_names;
 mod group_local;
+mod hoist_loop_invariants;
 mod inject_value;
+mod injection;
+mod inline_constants;
+mod inline_functions;
+mod lint_duplicate_locals;
+mod loop_exits;
 mod method_def;
+mod module_exports;
 mod no_local_function;
+mod normalize_comment_style;
+mod normalize_semicolons;
+mod normalize_string_escapes;
+mod obfuscate_identifiers;
+mod property_units;
 mod remove_assertions;
 mod remove_call_match;
 mod remove_comments;
 mod remove_compound_assign;
 mod remove_continue;
+mod remove_dead_stores;
 mod remove_debug_profiling;
 mod remove_floor_division;
 mod remove_if_expression;
 mod remove_interpolated_string;
 mod remove_nil_declarations;
 mod remove_spaces;
+mod remove_trailing_nil_values;
 mod remove_types;
 mod remove_unused_variable;
 mod rename_variables;
 mod replace_referenced_tokens;
 pub(crate) mod require;
+mod rewrite;
+mod rewrite_env_access;
+mod rule_aliases;
+mod rule_example;
 mod rule_property;
 mod shift_token_line;
+mod simplify_boolean_comparisons;
+mod simplify_parameter_defaults;
+mod sort_local_functions;
+mod sort_table_entries;
+mod text_processor;
+mod transliterate_identifiers;
+mod trim_trailing_whitespace;
+mod trivia_rule;
 mod unused_if_branch;
 mod unused_while;
+mod validate_limits;
+mod wrap_module_return;
 
 pub use append_text_comment::*;
+pub use artifact::*;
 pub use call_parens::*;
+pub use check_assignment_arity::*;
+pub use compatibility::*;
 pub use compute_expression::*;
+pub use conditional_compilation::*;
 pub use configuration_error::RuleConfigurationError;
+pub(crate) use configuration_error::{levenshtein_distance, suggest_property};
+pub use convert_if_to_lookup::*;
 pub use convert_index_to_field::*;
+pub use convert_numeric_for_to_while::*;
 pub use convert_require::*;
+pub use convert_while_to_numeric_for::*;
+pub use deduplicate_blocks::*;
+pub use default_rules::*;
+pub use dynamic_environment::*;
 pub use empty_do::*;
+pub use extract_callbacks::*;
+pub use extract_strings::*;
+pub use factor_common_branch_code::*;
 pub use filter_early_return::*;
+pub use fix_global_leaks::*;
+pub use flatten_closures::*;
+pub use format_call_chains::*;
+pub use freeze_constant_tables::*;
+pub use generated_names::*;
 pub use group_local::*;
+pub use hoist_loop_invariants::*;
 pub use inject_value::*;
+pub use injection::{InjectionPriority, TopStatementInjections};
+pub use inline_constants::*;
+pub use inline_functions::*;
+pub use lint_duplicate_locals::*;
+pub(crate) use loop_exits::*;
 pub use method_def::*;
+pub(crate) use module_exports::*;
 pub use no_local_function::*;
+pub use normalize_comment_style::*;
+pub use normalize_semicolons::*;
+pub use normalize_string_escapes::*;
+pub use obfuscate_identifiers::*;
+pub use property_units::*;
 pub use remove_assertions::*;
 pub use remove_comments::*;
 pub use remove_compound_assign::*;
 pub use remove_continue::*;
+pub use remove_dead_stores::*;
 pub use remove_debug_profiling::*;
 pub use remove_floor_division::*;
 pub use remove_if_expression::*;
 pub use remove_interpolated_string::*;
 pub use remove_nil_declarations::*;
 pub use remove_spaces::*;
+pub use remove_trailing_nil_values::*;
 pub use remove_types::*;
 pub use remove_unused_variable::*;
 pub use rename_variables::*;
 pub(crate) use replace_referenced_tokens::*;
+pub use rewrite::*;
+pub use rewrite_env_access::*;
+pub(crate) use rule_aliases::{find_rule_alias, record_deprecation};
+pub use rule_aliases::{
+    get_rule_aliases, take_rule_deprecations, PropertiesTranslation, RuleAlias, RuleDeprecation,
+};
+pub use rule_example::RuleExample;
 pub use rule_property::*;
 pub(crate) use shift_token_line::*;
+pub use simplify_boolean_comparisons::*;
+pub use simplify_parameter_defaults::*;
+pub use sort_local_functions::*;
+pub use sort_table_entries::*;
+pub use text_processor::*;
+pub use transliterate_identifiers::*;
+pub use trim_trailing_whitespace::*;
+pub(crate) use trivia_rule::visit_trivia;
+pub use trivia_rule::TriviaRule;
 pub use unused_if_branch::*;
 pub use unused_while::*;
+pub use validate_limits::*;
+pub use wrap_module_return::*;
 
-use crate::nodes::Block;
+use crate::nodes::{Block, Statement};
+use crate::process::{Environment, IdentifierTracker, NodePath, NodePathTracker};
+use crate::utils::log;
 use crate::Resources;
 
+#[cfg(feature = "serde-config")]
 use serde::de::{self, MapAccess, Visitor};
+#[cfg(feature = "serde-config")]
 use serde::ser::SerializeMap;
+#[cfg(feature = "serde-config")]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 use std::fmt;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 
 #[derive(Debug, Clone)]
 pub struct ContextBuilder<'a, 'resources, 'code> {
@@ -83,6 +191,13 @@ pub struct ContextBuilder<'a, 'resources, 'code> {
     original_code: &'code str,
     blocks: HashMap<PathBuf, &'a Block>,
     project_location: Option<PathBuf>,
+    environment: Arc<Environment>,
+    dynamic_environment: Option<Arc<DynamicEnvironmentUsage>>,
+    generated_name_prefix: Option<String>,
+    roblox_path: Option<String>,
+    defer_injections: bool,
+    deadline: Option<Instant>,
+    reserved_identifiers: Vec<String>,
 }
 
 impl<'a, 'resources, 'code> ContextBuilder<'a, 'resources, 'code> {
@@ -97,6 +212,13 @@ impl<'a, 'resources, 'code> ContextBuilder<'a, 'resources, 'code> {
             original_code,
             blocks: Default::default(),
             project_location: None,
+            environment: Default::default(),
+            dynamic_environment: None,
+            generated_name_prefix: None,
+            roblox_path: None,
+            defer_injections: false,
+            deadline: None,
+            reserved_identifiers: Vec::new(),
         }
     }
 
@@ -105,14 +227,84 @@ impl<'a, 'resources, 'code> ContextBuilder<'a, 'resources, 'code> {
         self
     }
 
+    /// Sets the globals known to exist when the processed code runs.
+    pub fn with_environment(mut self, environment: Arc<Environment>) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Sets the dynamic environment usage of the processed file, usually computed once before
+    /// applying the rules.
+    pub fn with_dynamic_environment(mut self, usage: Arc<DynamicEnvironmentUsage>) -> Self {
+        self.dynamic_environment = Some(usage);
+        self
+    }
+
+    /// Sets the prefix given to names generated by rules. A `{hash}` placeholder in the prefix
+    /// is replaced with a short hash of the original code.
+    pub fn with_generated_name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.generated_name_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets the path of the Roblox instance created from the processed file (like
+    /// `ReplicatedStorage.Shared.Util`).
+    pub fn with_roblox_path(mut self, roblox_path: impl Into<String>) -> Self {
+        self.roblox_path = Some(roblox_path.into());
+        self
+    }
+
+    /// Keeps the statements injected at the top of the block by the rules in the context
+    /// instead of inserting them after each rule, so that the injections of multiple rules
+    /// can be inserted together once all the rules are applied.
+    pub fn with_deferred_injections(mut self) -> Self {
+        self.defer_injections = true;
+        self
+    }
+
+    /// Sets the instant after which the rule applied with the context is cancelled. The node
+    /// visitors stop visiting nodes once the deadline has passed (see
+    /// [`DeadlineTracker`](crate::process::DeadlineTracker)).
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the identifiers reserved by the rules applied before (see
+    /// [`Context::reserve_identifier`]).
+    pub fn with_reserved_identifiers(
+        mut self,
+        identifiers: impl IntoIterator<Item = String>,
+    ) -> Self {
+        self.reserved_identifiers = identifiers.into_iter().collect();
+        self
+    }
+
     pub fn build(self) -> Context<'a, 'resources, 'code> {
+        let generated_name_prefix = expand_generated_name_prefix(
+            self.generated_name_prefix
+                .as_deref()
+                .unwrap_or(DEFAULT_GENERATED_NAME_PREFIX),
+            self.original_code,
+        );
         Context {
             path: self.path,
             resources: self.resources,
             original_code: self.original_code,
             blocks: self.blocks,
             project_location: self.project_location,
+            environment: self.environment,
+            dynamic_environment: self.dynamic_environment,
+            generated_name_prefix,
+            roblox_path: self.roblox_path,
+            defer_injections: self.defer_injections,
+            deadline: self.deadline,
             dependencies: Default::default(),
+            reprocess_requests: Default::default(),
+            injections: Default::default(),
+            failure_path: Default::default(),
+            artifacts: Default::default(),
+            reserved_identifiers: std::cell::RefCell::new(self.reserved_identifiers),
         }
     }
 
@@ -129,7 +321,18 @@ pub struct Context<'a, 'resources, 'code> {
     original_code: &'code str,
     blocks: HashMap<PathBuf, &'a Block>,
     project_location: Option<PathBuf>,
+    environment: Arc<Environment>,
+    dynamic_environment: Option<Arc<DynamicEnvironmentUsage>>,
+    generated_name_prefix: String,
+    roblox_path: Option<String>,
+    defer_injections: bool,
+    deadline: Option<Instant>,
     dependencies: std::cell::RefCell<Vec<PathBuf>>,
+    reprocess_requests: std::cell::RefCell<Vec<String>>,
+    injections: std::cell::RefCell<TopStatementInjections>,
+    failure_path: std::cell::RefCell<Option<NodePath>>,
+    artifacts: std::cell::RefCell<Vec<Artifact>>,
+    reserved_identifiers: std::cell::RefCell<Vec<String>>,
 }
 
 impl Context<'_, '_, '_> {
@@ -141,6 +344,77 @@ impl Context<'_, '_, '_> {
         self.path.as_ref()
     }
 
+    /// Returns the globals known to exist when the processed code runs. The environment is
+    /// empty when no target or globals are configured.
+    pub fn environment(&self) -> &Arc<Environment> {
+        &self.environment
+    }
+
+    /// Returns the dynamic environment usage of the processed file. When it was not provided
+    /// to the context builder, it is computed from the given block.
+    pub fn dynamic_environment(&self, block: &mut Block) -> Arc<DynamicEnvironmentUsage> {
+        self.dynamic_environment
+            .clone()
+            .unwrap_or_else(|| Arc::new(DynamicEnvironmentUsage::find(block)))
+    }
+
+    /// Like [`Context::dynamic_environment`], for analyses that do not mutate the block. The
+    /// block is only copied when the usage was not provided to the context builder.
+    pub(crate) fn dynamic_environment_of(&self, block: &Block) -> Arc<DynamicEnvironmentUsage> {
+        self.dynamic_environment
+            .clone()
+            .unwrap_or_else(|| Arc::new(DynamicEnvironmentUsage::find(&mut block.clone())))
+    }
+
+    /// Returns the prefix of names generated by rules, with its `{hash}` placeholder expanded.
+    pub fn generated_name_prefix(&self) -> &str {
+        &self.generated_name_prefix
+    }
+
+    /// Returns a name generated by a rule, made from the configured prefix and the given name.
+    /// When that name is [reserved](Context::reserve_identifier), a numeric suffix is added.
+    pub fn generated_name(&self, name: &str) -> String {
+        let generated = format!("{}{}", self.generated_name_prefix, name);
+        let reserved = self.reserved_identifiers.borrow();
+
+        if !reserved.contains(&generated) {
+            return generated;
+        }
+
+        (2..)
+            .map(|index| format!("{}_{}", generated, index))
+            .find(|name| !reserved.contains(name))
+            .expect("unable to generate a name that is not reserved")
+    }
+
+    /// Returns an identifier tracker that generates names with the configured prefix and
+    /// avoids the reserved identifiers.
+    pub(crate) fn identifier_tracker(&self) -> IdentifierTracker {
+        IdentifierTracker::new()
+            .with_generated_name_prefix(self.generated_name_prefix())
+            .with_reserved_identifiers(self.reserved_identifiers())
+    }
+
+    /// Returns the path of the Roblox instance created from the processed file, when the file
+    /// is mapped by a Rojo project.
+    pub fn roblox_path(&self) -> Option<&str> {
+        self.roblox_path.as_deref()
+    }
+
+    /// Returns the instant after which the rule is cancelled, when the rule has a time
+    /// budget.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns `true` once the deadline of the rule has passed. Rules that loop over nodes
+    /// without the node visitors can check it to return early, since the result of a
+    /// cancelled rule is discarded.
+    pub fn is_cancelled(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
     pub fn add_file_dependency(&self, path: PathBuf) {
         if let Ok(mut dependencies) = self.dependencies.try_borrow_mut() {
             log::trace!("add file dependency {}", path.display());
@@ -150,6 +424,113 @@ impl Context<'_, '_, '_> {
         }
     }
 
+    /// Requests that an earlier rule of the process pipeline runs again once the current rule
+    /// completes. Requests for rules that are not part of the configured rules, or that have
+    /// not run yet, are ignored.
+    pub fn request_reprocess(&self, rule_name: &str) {
+        if let Ok(mut requests) = self.reprocess_requests.try_borrow_mut() {
+            if !requests.iter().any(|name| name == rule_name) {
+                log::trace!("request reprocess of rule `{}`", rule_name);
+                requests.push(rule_name.to_owned());
+            }
+        } else {
+            log::warn!("unable to submit reprocess request (internal error)");
+        }
+    }
+
+    pub(crate) fn take_reprocess_requests(&self) -> Vec<String> {
+        self.reprocess_requests.take()
+    }
+
+    /// Records the path of the statement currently visited as the location where the rule
+    /// failed, and returns the given message so that it can be used as the rule error. When
+    /// the rule returns an error, the location is reported with the code of the statement.
+    pub fn error_at_current_node(&self, message: impl Into<String>) -> String {
+        if let Some(path) = NodePathTracker::current_path() {
+            if let Ok(mut failure_path) = self.failure_path.try_borrow_mut() {
+                log::trace!("record rule failure at {}", path);
+                *failure_path = Some(path);
+            } else {
+                log::warn!("unable to record rule failure location (internal error)");
+            }
+        }
+        message.into()
+    }
+
+    pub(crate) fn take_failure_path(&self) -> Option<NodePath> {
+        self.failure_path.take()
+    }
+
+    /// Injects a statement at the top of the processed block. Injected statements are
+    /// inserted after the rule completes (or once all rules are applied when the context
+    /// defers injections), ordered by priority and then by insertion order.
+    pub fn inject_top_statement(
+        &self,
+        statement: impl Into<Statement>,
+        priority: InjectionPriority,
+    ) {
+        if let Ok(mut injections) = self.injections.try_borrow_mut() {
+            log::trace!("inject top statement with priority {:?}", priority);
+            injections.push(statement, priority);
+        } else {
+            log::warn!("unable to inject statement (internal error)");
+        }
+    }
+
+    /// Removes the statements injected in the context and returns them.
+    pub fn take_injections(&self) -> TopStatementInjections {
+        self.injections.take()
+    }
+
+    /// Emits a secondary output of the processed file (like a table of its strings). The
+    /// artifact is written when a path is configured for its name, otherwise it is only
+    /// available from the outcome of the file.
+    pub fn emit_artifact(&self, name: &str, kind: ArtifactKind, content: ArtifactContent) {
+        if let Ok(mut artifacts) = self.artifacts.try_borrow_mut() {
+            log::trace!("emit artifact `{}` ({})", name, kind);
+            artifacts.push(Artifact::new(name, kind, content));
+        } else {
+            log::warn!("unable to emit artifact `{}` (internal error)", name);
+        }
+    }
+
+    pub(crate) fn take_artifacts(&self) -> Vec<Artifact> {
+        self.artifacts.take()
+    }
+
+    /// Reserves an identifier that the rule introduced in the code. Once the rule completes,
+    /// the identifier is given to the following rules, so that the rules generating names
+    /// (like `rename_variables` or the locals added by other rules) do not produce it. Unlike
+    /// the globals of the [environment](Context::environment), reserved identifiers are not
+    /// assumed to exist.
+    pub fn reserve_identifier(&self, identifier: &str) {
+        if let Ok(mut identifiers) = self.reserved_identifiers.try_borrow_mut() {
+            if !identifiers.iter().any(|name| name == identifier) {
+                log::trace!("reserve identifier `{}`", identifier);
+                identifiers.push(identifier.to_owned());
+            }
+        } else {
+            log::warn!(
+                "unable to reserve identifier `{}` (internal error)",
+                identifier
+            );
+        }
+    }
+
+    /// Returns the identifiers reserved by the current rule and by the rules applied before.
+    pub fn reserved_identifiers(&self) -> Vec<String> {
+        self.reserved_identifiers.borrow().clone()
+    }
+
+    pub(crate) fn take_reserved_identifiers(&self) -> Vec<String> {
+        self.reserved_identifiers.take()
+    }
+
+    #[inline]
+    pub(crate) fn defers_injections(&self) -> bool {
+        self.defer_injections
+    }
+
     pub fn into_dependencies(self) -> impl Iterator<Item = PathBuf> {
         self.dependencies.into_inner().into_iter()
     }
@@ -158,7 +539,9 @@ impl Context<'_, '_, '_> {
         self.resources
     }
 
-    fn original_code(&self) -> &str {
+    /// The code of the file being processed. The tokens and trivia parsed from the file
+    /// reference this code.
+    pub fn original_code(&self) -> &str {
         self.original_code
     }
 
@@ -184,6 +567,13 @@ pub trait Rule: RuleConfiguration + fmt::Debug {
     /// This method should mutate the given block to apply the rule
     fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult;
 
+    /// Predicts how the rule would behave on the given block without applying it, for the
+    /// compatibility report of the `doctor` command. Returns a note for each part of the
+    /// file that the rule would skip, leave unchanged or could break.
+    fn compatibility_check(&self, _block: &Block, _context: &Context) -> Vec<CompatibilityNote> {
+        Vec::new()
+    }
+
     /// Return the list of paths to Lua files that is necessary to apply this rule. This will load
     /// each AST block from these files into the context object.
     fn require_content(&self, _current_source: &Path, _current_block: &Block) -> Vec<PathBuf> {
@@ -200,26 +590,93 @@ pub trait RuleConfiguration {
     /// For implementing the serialize trait on the Rule trait, this method should return all
     /// properties that differs from their default value.
     fn serialize_to_properties(&self) -> RuleProperties;
+    /// Returns the names of all the properties accepted by the rule. It is used to suggest a
+    /// property name when the rule is configured with an unknown property.
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+    /// Returns the kind of value accepted by one of the properties returned by
+    /// `get_property_names`. It is used to validate configuration files and to generate the
+    /// configuration schema.
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Any
+    }
+    /// Returns snippets of code that show the effect of the rule, used to document the rule.
+    /// Each example must be changed by the rule.
+    fn examples(&self) -> Vec<RuleExample> {
+        Vec::new()
+    }
     /// Returns `true` if the rule has at least one property.
     fn has_properties(&self) -> bool {
         !self.serialize_to_properties().is_empty()
     }
+    /// Returns `false` if the rule never has any effect on data modules (modules that only
+    /// return a table of literal values). When the data module fast path is enabled, these
+    /// rules are skipped on such modules.
+    fn applies_to_data_modules(&self) -> bool {
+        true
+    }
+    /// Returns `true` if the rule only changes how the code is formatted (like spaces or
+    /// semicolons) and never changes its behavior. Only these rules are used by the format
+    /// commands.
+    fn is_formatting_rule(&self) -> bool {
+        false
+    }
+    /// Returns `true` if the rule processes each top-level statement independently from the
+    /// others, without adding, removing or moving top-level statements. When all the rules
+    /// are statement-local, only the statements that changed in a file need to be processed
+    /// again in watch mode.
+    fn is_statement_local(&self) -> bool {
+        false
+    }
+    /// Returns `true` if applying the rule on code that it already processed damages the
+    /// code (like adding a comment twice). When a file marked as processed is processed
+    /// again, these rules are not applied a second time.
+    fn is_unsafe_to_reapply(&self) -> bool {
+        false
+    }
+    /// Returns the names of the properties that are set to a value that trades the exact
+    /// behavior of the code for a smaller output (like assuming that no metatables are
+    /// used). Strict mode rejects rules that return any property.
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+    /// Returns `true` if the rule is configured to always preserve the behavior of the code.
+    fn is_strictly_safe(&self) -> bool {
+        self.unsafe_properties().is_empty()
+    }
 }
 
 pub trait FlawlessRule {
     fn flawless_process(&self, block: &mut Block, context: &Context);
+
+    /// See [`Rule::compatibility_check`].
+    fn compatibility_check(&self, _block: &Block, _context: &Context) -> Vec<CompatibilityNote> {
+        Vec::new()
+    }
 }
 
 impl<T: FlawlessRule + RuleConfiguration + fmt::Debug> Rule for T {
     fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
         self.flawless_process(block, context);
+
+        if !context.defers_injections() {
+            context.take_injections().apply(block);
+        }
+
         Ok(())
     }
+
+    fn compatibility_check(&self, block: &Block, context: &Context) -> Vec<CompatibilityNote> {
+        FlawlessRule::compatibility_check(self, block, context)
+    }
 }
 
 /// A function to get the default rule stack for darklua. All the rules here must preserve all the
 /// functionalities of the original code after being applied. They must guarantee that the
 /// processed block will work as much as the original one.
+///
+/// To exclude some of these rules, use the [`DefaultRulesBuilder`].
 pub fn get_default_rules() -> Vec<Box<dyn Rule>> {
     vec![
         Box::<RemoveSpaces>::default(),
@@ -233,6 +690,7 @@ pub fn get_default_rules() -> Vec<Box<dyn Rule>> {
         Box::<RemoveMethodDefinition>::default(),
         Box::<ConvertIndexToField>::default(),
         Box::<RemoveNilDeclaration>::default(),
+        Box::<RemoveTrailingNilValues>::default(),
         Box::<RenameVariables>::default(),
         Box::<RemoveFunctionCallParens>::default(),
     ]
@@ -241,23 +699,47 @@ pub fn get_default_rules() -> Vec<Box<dyn Rule>> {
 pub fn get_all_rule_names() -> Vec<&'static str> {
     vec![
         APPEND_TEXT_COMMENT_RULE_NAME,
+        CHECK_ASSIGNMENT_ARITY_RULE_NAME,
         COMPUTE_EXPRESSIONS_RULE_NAME,
+        CONDITIONAL_COMPILATION_RULE_NAME,
+        CONVERT_IF_TO_LOOKUP_RULE_NAME,
         CONVERT_INDEX_TO_FIELD_RULE_NAME,
         CONVERT_LOCAL_FUNCTION_TO_ASSIGN_RULE_NAME,
+        CONVERT_NUMERIC_FOR_TO_WHILE_RULE_NAME,
         CONVERT_REQUIRE_RULE_NAME,
+        CONVERT_WHILE_TO_NUMERIC_FOR_RULE_NAME,
+        DEDUPLICATE_BLOCKS_RULE_NAME,
+        EXTRACT_CALLBACKS_RULE_NAME,
+        EXTRACT_STRINGS_RULE_NAME,
+        FACTOR_COMMON_BRANCH_CODE_RULE_NAME,
         FILTER_AFTER_EARLY_RETURN_RULE_NAME,
+        FIX_GLOBAL_LEAKS_RULE_NAME,
+        FLATTEN_CLOSURES_RULE_NAME,
+        FORMAT_CALL_CHAINS_RULE_NAME,
+        FREEZE_CONSTANT_TABLES_RULE_NAME,
         GROUP_LOCAL_ASSIGNMENT_RULE_NAME,
+        HOIST_LOOP_INVARIANTS_RULE_NAME,
         INJECT_GLOBAL_VALUE_RULE_NAME,
+        INLINE_CONSTANTS_RULE_NAME,
+        INLINE_FUNCTIONS_RULE_NAME,
+        LINT_DUPLICATE_LOCALS_RULE_NAME,
+        NORMALIZE_COMMENT_STYLE_RULE_NAME,
+        NORMALIZE_SEMICOLONS_RULE_NAME,
+        NORMALIZE_STRING_ESCAPES_RULE_NAME,
+        OBFUSCATE_IDENTIFIERS_RULE_NAME,
         REMOVE_ASSERTIONS_RULE_NAME,
         REMOVE_COMMENTS_RULE_NAME,
         REMOVE_COMPOUND_ASSIGNMENT_RULE_NAME,
+        REMOVE_DEAD_STORES_RULE_NAME,
         REMOVE_DEBUG_PROFILING_RULE_NAME,
         REMOVE_EMPTY_DO_RULE_NAME,
+        REMOVE_FLOOR_DIVISION_RULE_NAME,
         REMOVE_FUNCTION_CALL_PARENS_RULE_NAME,
         REMOVE_INTERPOLATED_STRING_RULE_NAME,
         REMOVE_METHOD_DEFINITION_RULE_NAME,
         REMOVE_NIL_DECLARATION_RULE_NAME,
         REMOVE_SPACES_RULE_NAME,
+        REMOVE_TRAILING_NIL_VALUES_RULE_NAME,
         REMOVE_TYPES_RULE_NAME,
         REMOVE_UNUSED_IF_BRANCH_RULE_NAME,
         REMOVE_UNUSED_VARIABLE_RULE_NAME,
@@ -265,6 +747,16 @@ pub fn get_all_rule_names() -> Vec<&'static str> {
         RENAME_VARIABLES_RULE_NAME,
         REMOVE_IF_EXPRESSION_RULE_NAME,
         REMOVE_CONTINUE_RULE_NAME,
+        REWRITE_RULE_NAME,
+        REWRITE_ENV_ACCESS_RULE_NAME,
+        SIMPLIFY_BOOLEAN_COMPARISONS_RULE_NAME,
+        SIMPLIFY_PARAMETER_DEFAULTS_RULE_NAME,
+        SORT_LOCAL_FUNCTIONS_RULE_NAME,
+        SORT_TABLE_ENTRIES_RULE_NAME,
+        TRANSLITERATE_IDENTIFIERS_RULE_NAME,
+        TRIM_TRAILING_WHITESPACE_RULE_NAME,
+        VALIDATE_LIMITS_RULE_NAME,
+        WRAP_MODULE_RETURN_RULE_NAME,
     ]
 }
 
@@ -274,18 +766,40 @@ impl FromStr for Box<dyn Rule> {
     fn from_str(string: &str) -> Result<Self, Self::Err> {
         let rule: Box<dyn Rule> = match string {
             APPEND_TEXT_COMMENT_RULE_NAME => Box::<AppendTextComment>::default(),
+            CHECK_ASSIGNMENT_ARITY_RULE_NAME => Box::<CheckAssignmentArity>::default(),
             COMPUTE_EXPRESSIONS_RULE_NAME => Box::<ComputeExpression>::default(),
+            CONDITIONAL_COMPILATION_RULE_NAME => Box::<ConditionalCompilation>::default(),
+            CONVERT_IF_TO_LOOKUP_RULE_NAME => Box::<ConvertIfToLookup>::default(),
             CONVERT_INDEX_TO_FIELD_RULE_NAME => Box::<ConvertIndexToField>::default(),
             CONVERT_LOCAL_FUNCTION_TO_ASSIGN_RULE_NAME => {
                 Box::<ConvertLocalFunctionToAssign>::default()
             }
+            CONVERT_NUMERIC_FOR_TO_WHILE_RULE_NAME => Box::<ConvertNumericForToWhile>::default(),
             CONVERT_REQUIRE_RULE_NAME => Box::<ConvertRequire>::default(),
+            CONVERT_WHILE_TO_NUMERIC_FOR_RULE_NAME => Box::<ConvertWhileToNumericFor>::default(),
+            DEDUPLICATE_BLOCKS_RULE_NAME => Box::<DeduplicateBlocks>::default(),
+            EXTRACT_CALLBACKS_RULE_NAME => Box::<ExtractCallbacks>::default(),
+            EXTRACT_STRINGS_RULE_NAME => Box::<ExtractStrings>::default(),
+            FACTOR_COMMON_BRANCH_CODE_RULE_NAME => Box::<FactorCommonBranchCode>::default(),
             FILTER_AFTER_EARLY_RETURN_RULE_NAME => Box::<FilterAfterEarlyReturn>::default(),
+            FIX_GLOBAL_LEAKS_RULE_NAME => Box::<FixGlobalLeaks>::default(),
+            FLATTEN_CLOSURES_RULE_NAME => Box::<FlattenClosures>::default(),
+            FORMAT_CALL_CHAINS_RULE_NAME => Box::<FormatCallChains>::default(),
+            FREEZE_CONSTANT_TABLES_RULE_NAME => Box::<FreezeConstantTables>::default(),
             GROUP_LOCAL_ASSIGNMENT_RULE_NAME => Box::<GroupLocalAssignment>::default(),
+            HOIST_LOOP_INVARIANTS_RULE_NAME => Box::<HoistLoopInvariants>::default(),
             INJECT_GLOBAL_VALUE_RULE_NAME => Box::<InjectGlobalValue>::default(),
+            INLINE_CONSTANTS_RULE_NAME => Box::<InlineConstants>::default(),
+            INLINE_FUNCTIONS_RULE_NAME => Box::<InlineFunctions>::default(),
+            LINT_DUPLICATE_LOCALS_RULE_NAME => Box::<LintDuplicateLocals>::default(),
+            NORMALIZE_COMMENT_STYLE_RULE_NAME => Box::<NormalizeCommentStyle>::default(),
+            NORMALIZE_SEMICOLONS_RULE_NAME => Box::<NormalizeSemicolons>::default(),
+            NORMALIZE_STRING_ESCAPES_RULE_NAME => Box::<NormalizeStringEscapes>::default(),
+            OBFUSCATE_IDENTIFIERS_RULE_NAME => Box::<ObfuscateIdentifiers>::default(),
             REMOVE_ASSERTIONS_RULE_NAME => Box::<RemoveAssertions>::default(),
             REMOVE_COMMENTS_RULE_NAME => Box::<RemoveComments>::default(),
             REMOVE_COMPOUND_ASSIGNMENT_RULE_NAME => Box::<RemoveCompoundAssignment>::default(),
+            REMOVE_DEAD_STORES_RULE_NAME => Box::<RemoveDeadStores>::default(),
             REMOVE_DEBUG_PROFILING_RULE_NAME => Box::<RemoveDebugProfiling>::default(),
             REMOVE_EMPTY_DO_RULE_NAME => Box::<RemoveEmptyDo>::default(),
             REMOVE_FLOOR_DIVISION_RULE_NAME => Box::<RemoveFloorDivision>::default(),
@@ -294,6 +808,7 @@ impl FromStr for Box<dyn Rule> {
             REMOVE_METHOD_DEFINITION_RULE_NAME => Box::<RemoveMethodDefinition>::default(),
             REMOVE_NIL_DECLARATION_RULE_NAME => Box::<RemoveNilDeclaration>::default(),
             REMOVE_SPACES_RULE_NAME => Box::<RemoveSpaces>::default(),
+            REMOVE_TRAILING_NIL_VALUES_RULE_NAME => Box::<RemoveTrailingNilValues>::default(),
             REMOVE_TYPES_RULE_NAME => Box::<RemoveTypes>::default(),
             REMOVE_UNUSED_IF_BRANCH_RULE_NAME => Box::<RemoveUnusedIfBranch>::default(),
             REMOVE_UNUSED_VARIABLE_RULE_NAME => Box::<RemoveUnusedVariable>::default(),
@@ -301,13 +816,32 @@ impl FromStr for Box<dyn Rule> {
             RENAME_VARIABLES_RULE_NAME => Box::<RenameVariables>::default(),
             REMOVE_IF_EXPRESSION_RULE_NAME => Box::<RemoveIfExpression>::default(),
             REMOVE_CONTINUE_RULE_NAME => Box::<RemoveContinue>::default(),
-            _ => return Err(format!("invalid rule name: {}", string)),
+            REWRITE_RULE_NAME => Box::<Rewrite>::default(),
+            REWRITE_ENV_ACCESS_RULE_NAME => Box::<RewriteEnvAccess>::default(),
+            SIMPLIFY_BOOLEAN_COMPARISONS_RULE_NAME => Box::<SimplifyBooleanComparisons>::default(),
+            SIMPLIFY_PARAMETER_DEFAULTS_RULE_NAME => Box::<SimplifyParameterDefaults>::default(),
+            SORT_LOCAL_FUNCTIONS_RULE_NAME => Box::<SortLocalFunctions>::default(),
+            SORT_TABLE_ENTRIES_RULE_NAME => Box::<SortTableEntries>::default(),
+            TRANSLITERATE_IDENTIFIERS_RULE_NAME => Box::<TransliterateIdentifiers>::default(),
+            TRIM_TRAILING_WHITESPACE_RULE_NAME => Box::<TrimTrailingWhitespace>::default(),
+            VALIDATE_LIMITS_RULE_NAME => Box::<ValidateLimits>::default(),
+            WRAP_MODULE_RETURN_RULE_NAME => Box::<WrapModuleReturn>::default(),
+            _ => {
+                return match find_rule_alias(string) {
+                    Some(alias) => {
+                        record_deprecation(alias.deprecation());
+                        alias.target().parse()
+                    }
+                    None => Err(format!("invalid rule name: {}", string)),
+                }
+            }
         };
 
         Ok(rule)
     }
 }
 
+#[cfg(feature = "serde-config")]
 impl Serialize for dyn Rule {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         let properties = self.serialize_to_properties();
@@ -321,11 +855,7 @@ impl Serialize for dyn Rule {
 
             map.serialize_entry("rule", rule_name)?;
 
-            let mut ordered: Vec<(String, RulePropertyValue)> = properties.into_iter().collect();
-
-            ordered.sort_by(|a, b| a.0.cmp(&b.0));
-
-            for (key, value) in ordered {
+            for (key, value) in properties {
                 map.serialize_entry(&key, &value)?;
             }
 
@@ -334,6 +864,7 @@ impl Serialize for dyn Rule {
     }
 }
 
+#[cfg(feature = "serde-config")]
 impl<'de> Deserialize<'de> for Box<dyn Rule> {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Box<dyn Rule>, D::Error> {
         struct StringOrStruct;
@@ -349,12 +880,7 @@ impl<'de> Deserialize<'de> for Box<dyn Rule> {
             where
                 E: de::Error,
             {
-                let mut rule: Self::Value = FromStr::from_str(value).map_err(de::Error::custom)?;
-
-                rule.configure(RuleProperties::new())
-                    .map_err(de::Error::custom)?;
-
-                Ok(rule)
+                create_rule(value, RuleProperties::new()).map_err(de::Error::custom)
             }
 
             fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
@@ -362,7 +888,7 @@ impl<'de> Deserialize<'de> for Box<dyn Rule> {
                 M: MapAccess<'de>,
             {
                 let mut rule_name = None;
-                let mut properties = HashMap::new();
+                let mut properties = RuleProperties::new();
 
                 while let Some(key) = map.next_key::<String>()? {
                     match key.as_str() {
@@ -387,12 +913,7 @@ impl<'de> Deserialize<'de> for Box<dyn Rule> {
                 }
 
                 if let Some(rule_name) = rule_name {
-                    let mut rule: Self::Value =
-                        FromStr::from_str(&rule_name).map_err(de::Error::custom)?;
-
-                    rule.configure(properties).map_err(de::Error::custom)?;
-
-                    Ok(rule)
+                    create_rule(&rule_name, properties).map_err(de::Error::custom)
                 } else {
                     Err(de::Error::missing_field("rule"))
                 }
@@ -403,6 +924,55 @@ impl<'de> Deserialize<'de> for Box<dyn Rule> {
     }
 }
 
+/// Returns the names of the properties accepted by the given rule.
+pub(crate) fn get_known_properties(rule: &dyn Rule) -> Vec<String> {
+    let default_rule = rule.get_name().parse::<Box<dyn Rule>>().ok();
+    let default_rule = default_rule.as_deref().unwrap_or(rule);
+
+    let mut properties: Vec<String> = default_rule
+        .serialize_to_properties()
+        .into_keys()
+        .chain(
+            default_rule
+                .get_property_names()
+                .iter()
+                .map(ToString::to_string),
+        )
+        .collect();
+
+    properties.sort();
+    properties.dedup();
+    properties
+}
+
+/// Creates a rule from its name (or a deprecated alias of its name) and its properties.
+pub(crate) fn create_rule(name: &str, properties: RuleProperties) -> Result<Box<dyn Rule>, String> {
+    let properties = match find_rule_alias(name) {
+        Some(alias) => alias
+            .translate_properties(properties)
+            .map_err(|error| error.to_string())?,
+        None => properties,
+    };
+
+    let mut rule: Box<dyn Rule> = name.parse()?;
+    configure_rule(rule.as_mut(), properties)?;
+    Ok(rule)
+}
+
+fn configure_rule(rule: &mut dyn Rule, properties: RuleProperties) -> Result<(), String> {
+    rule.configure(properties).map_err(|error| match &error {
+        RuleConfigurationError::UnexpectedProperty(property) => {
+            let known_properties = get_known_properties(rule);
+
+            match suggest_property(property, &known_properties) {
+                Some(suggestion) => format!("{} (did you mean '{}'?)", error, suggestion),
+                None => error.to_string(),
+            }
+        }
+        _ => error.to_string(),
+    })
+}
+
 fn verify_no_rule_properties(properties: &RuleProperties) -> Result<(), RuleConfigurationError> {
     if let Some((key, _value)) = properties.iter().next() {
         return Err(RuleConfigurationError::UnexpectedProperty(key.to_owned()));
@@ -458,6 +1028,8 @@ fn verify_property_collisions(
 mod test {
     use super::*;
 
+    use std::{ffi::OsStr, fs, path::Path};
+
     use insta::assert_json_snapshot;
 
     #[test]
@@ -474,6 +1046,34 @@ mod test {
         assert_json_snapshot!("all_rule_names", rule_names);
     }
 
+    #[test]
+    fn generated_name_avoids_reserved_identifiers() {
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new("test.lua", &resources, "")
+            .with_generated_name_prefix("")
+            .with_reserved_identifiers(vec!["VAR".to_owned(), "VAR_2".to_owned()])
+            .build();
+
+        assert_eq!(context.generated_name("VAR"), "VAR_3");
+        assert_eq!(context.generated_name("OTHER"), "OTHER");
+    }
+
+    #[test]
+    fn identifier_tracker_avoids_reserved_identifiers() {
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new("test.lua", &resources, "")
+            .with_generated_name_prefix("")
+            .with_reserved_identifiers(vec!["VAR".to_owned()])
+            .build();
+
+        assert_eq!(
+            context
+                .identifier_tracker()
+                .generate_named_identifier("VAR"),
+            "VAR0"
+        );
+    }
+
     #[test]
     fn verify_no_rule_properties_is_ok_when_empty() {
         let empty_properties = RuleProperties::default();
@@ -495,6 +1095,205 @@ mod test {
         );
     }
 
+    #[test]
+    fn verify_no_rule_properties_reports_first_property_in_order() {
+        let mut properties = RuleProperties::default();
+        for name in ["zeta", "beta", "alpha", "gamma"] {
+            properties.insert(name.to_owned(), RulePropertyValue::None);
+        }
+
+        assert_eq!(
+            verify_no_rule_properties(&properties),
+            Err(RuleConfigurationError::UnexpectedProperty(
+                "alpha".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn configure_with_many_unknown_properties_reports_first_property_in_order() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'remove_empty_do',
+            zeta: true,
+            beta: true,
+            alpha: true,
+        }"#,
+        );
+
+        assert_eq!(result.unwrap_err().to_string(), "unexpected field 'alpha'");
+    }
+
+    #[test]
+    fn configure_with_misspelled_property_suggests_property() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'rename_variables',
+            include_function: true,
+        }"#,
+        );
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected field 'include_function' (did you mean 'include_functions'?)"
+        );
+    }
+
+    #[test]
+    fn configure_with_unknown_property_does_not_suggest_property() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'rename_variables',
+            prop: true,
+        }"#,
+        );
+
+        assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+
+    #[test]
+    fn get_known_properties_of_rule_with_properties() {
+        let rule: Box<dyn Rule> = Box::new(InjectGlobalValue::boolean("DEBUG", false));
+
+        assert_eq!(
+            get_known_properties(rule.as_ref()),
+            vec![
+                "assume_no_env_manipulation".to_owned(),
+                "env".to_owned(),
+                "expected_type".to_owned(),
+                "identifier".to_owned(),
+                "value".to_owned()
+            ]
+        );
+    }
+
+    #[test]
+    fn default_rules_are_strictly_safe() {
+        for rule in get_default_rules() {
+            assert!(
+                rule.is_strictly_safe(),
+                "`{}` is not strictly safe: {:?}",
+                rule.get_name(),
+                rule.unsafe_properties()
+            );
+        }
+    }
+
+    fn configure_boolean_property(
+        name: &str,
+        property: &str,
+        value: bool,
+    ) -> Option<Box<dyn Rule>> {
+        let mut properties = RuleProperties::new();
+        properties.insert(property.to_owned(), value.into());
+        create_rule(name, properties).ok()
+    }
+
+    #[test]
+    fn every_rule_reports_its_unsafe_properties() {
+        for name in get_all_rule_names() {
+            let rule: Box<dyn Rule> = name.parse().unwrap();
+
+            assert!(rule.is_strictly_safe(), "`{}` is not safe by default", name);
+
+            let property_names = rule.get_property_names();
+            let mut all_properties = RuleProperties::new();
+
+            for property in property_names {
+                for value in [true, false] {
+                    if let Some(rule) = configure_boolean_property(name, property, value) {
+                        for unsafe_property in rule.unsafe_properties() {
+                            assert!(
+                                property_names.contains(&unsafe_property),
+                                "`{}` reports unknown property `{}`",
+                                name,
+                                unsafe_property
+                            );
+                        }
+                    }
+                }
+                if configure_boolean_property(name, property, true).is_some() {
+                    all_properties.insert((*property).to_owned(), true.into());
+                }
+            }
+
+            if let Ok(rule) = create_rule(name, all_properties) {
+                assert_eq!(
+                    rule.is_strictly_safe(),
+                    rule.unsafe_properties().is_empty(),
+                    "`{}`",
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unsafe_properties_of_rules() {
+        let cases = [
+            (
+                "{ rule: 'compute_expression', assume_no_env_manipulation: true }",
+                "assume_no_env_manipulation",
+            ),
+            (
+                "{ rule: 'inject_global_value', identifier: 'DEBUG', assume_no_env_manipulation: true }",
+                "assume_no_env_manipulation",
+            ),
+            (
+                "{ rule: 'remove_assertions', assume_no_env_manipulation: true }",
+                "assume_no_env_manipulation",
+            ),
+            (
+                "{ rule: 'remove_assertions', preserve_arguments_side_effects: false }",
+                "preserve_arguments_side_effects",
+            ),
+            (
+                "{ rule: 'remove_debug_profiling', assume_no_env_manipulation: true }",
+                "assume_no_env_manipulation",
+            ),
+            (
+                "{ rule: 'remove_debug_profiling', preserve_arguments_side_effects: false }",
+                "preserve_arguments_side_effects",
+            ),
+            (
+                "{ rule: 'hoist_loop_invariants', assume_no_metatables: true }",
+                "assume_no_metatables",
+            ),
+            (
+                "{ rule: 'simplify_parameter_defaults', assume_no_false: true }",
+                "assume_no_false",
+            ),
+            (
+                "{ rule: 'simplify_boolean_comparisons', mode: 'assume_boolean' }",
+                "mode",
+            ),
+            (
+                "{ rule: 'factor_common_branch_code', allow_scope_widening: true }",
+                "allow_scope_widening",
+            ),
+            (
+                "{ rule: 'freeze_constant_tables', freeze_exports: true }",
+                "freeze_exports",
+            ),
+            (
+                "{ rule: 'obfuscate_identifiers', include_globals: true }",
+                "include_globals",
+            ),
+        ];
+
+        for (configuration, property) in cases {
+            let rule: Box<dyn Rule> = json5::from_str(configuration).unwrap();
+
+            assert_eq!(
+                rule.unsafe_properties(),
+                vec![property],
+                "{}",
+                configuration
+            );
+            assert!(!rule.is_strictly_safe());
+        }
+    }
+
     #[test]
     fn get_all_rule_names_are_deserializable() {
         for name in get_all_rule_names() {
@@ -503,6 +1302,28 @@ mod test {
         }
     }
 
+    #[test]
+    fn documented_rules_are_in_all_rule_names() {
+        let rule_names = get_all_rule_names();
+        let documentation = Path::new(env!("CARGO_MANIFEST_DIR")).join("site/content/rules");
+
+        for entry in fs::read_dir(documentation).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_stem().and_then(OsStr::to_str).unwrap();
+
+            assert!(
+                name.parse::<Box<dyn Rule>>().is_ok(),
+                "unknown rule `{}`",
+                name
+            );
+            assert!(
+                rule_names.contains(&name),
+                "`{}` is missing from the rule names",
+                name
+            );
+        }
+    }
+
     #[test]
     fn get_all_rule_names_are_serializable() {
         for name in get_all_rule_names() {
@@ -0,0 +1,238 @@
+use std::collections::HashSet;
+
+use crate::nodes::{
+    Block, Expression, LastStatement, Prefix, Statement, TableEntry, TableExpression, Variable,
+};
+
+/// The top-level locals of a module that are part of the value it returns.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct ModuleExports {
+    locals: HashSet<String>,
+}
+
+impl ModuleExports {
+    /// Finds the top-level locals returned by the module, and the top-level locals stored
+    /// into the table it returns (a table constructor, or a local table with its fields
+    /// assigned like `M.name = name`). Only the exports whose name passes the filter are
+    /// collected: the name of the field when the local is stored in a field, or the name of
+    /// the local otherwise.
+    pub(crate) fn find(block: &Block, filter: impl Fn(&str) -> bool) -> Self {
+        let mut exports = ModuleExports::default();
+
+        let Some(LastStatement::Return(statement)) = block.get_last_statement() else {
+            return exports;
+        };
+
+        let top_level_locals = collect_top_level_locals(block);
+        let is_top_level_local = |name: &str| top_level_locals.contains(name);
+
+        for value in statement.iter_expressions() {
+            match value {
+                Expression::Identifier(identifier) => {
+                    let name = identifier.get_name();
+                    if !is_top_level_local(name) {
+                        continue;
+                    }
+                    if filter(name) {
+                        exports.locals.insert(name.to_owned());
+                    }
+
+                    for statement in block.iter_statements() {
+                        match statement {
+                            Statement::LocalAssign(local_assign) => {
+                                for (variable, value) in local_assign
+                                    .iter_variables()
+                                    .zip(local_assign.iter_values())
+                                {
+                                    if variable.get_name() != name {
+                                        continue;
+                                    }
+                                    if let Expression::Table(table) = value {
+                                        exports.insert_table_entries(
+                                            table,
+                                            &is_top_level_local,
+                                            &filter,
+                                        );
+                                    }
+                                }
+                            }
+                            Statement::Assign(assign)
+                                if assign.variables_len() == assign.values_len() =>
+                            {
+                                for (variable, value) in
+                                    assign.iter_variables().zip(assign.iter_values())
+                                {
+                                    if let Some(field) = exported_field(variable, name) {
+                                        exports.insert_value(
+                                            value,
+                                            Some(&field),
+                                            &is_top_level_local,
+                                            &filter,
+                                        );
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Expression::Table(table) => {
+                    exports.insert_table_entries(table, &is_top_level_local, &filter);
+                }
+                _ => {}
+            }
+        }
+
+        exports
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &String> {
+        self.locals.iter()
+    }
+
+    fn insert_table_entries(
+        &mut self,
+        table: &TableExpression,
+        is_top_level_local: &impl Fn(&str) -> bool,
+        filter: &impl Fn(&str) -> bool,
+    ) {
+        for entry in table.iter_entries() {
+            match entry {
+                TableEntry::Field(entry) => self.insert_value(
+                    entry.get_value(),
+                    Some(entry.get_field().get_name()),
+                    is_top_level_local,
+                    filter,
+                ),
+                TableEntry::Index(entry) => {
+                    if let Expression::String(key) = entry.get_key() {
+                        self.insert_value(
+                            entry.get_value(),
+                            Some(key.get_value()),
+                            is_top_level_local,
+                            filter,
+                        );
+                    }
+                }
+                TableEntry::Value(value) => {
+                    self.insert_value(value, None, is_top_level_local, filter)
+                }
+            }
+        }
+    }
+
+    fn insert_value(
+        &mut self,
+        value: &Expression,
+        field: Option<&str>,
+        is_top_level_local: &impl Fn(&str) -> bool,
+        filter: &impl Fn(&str) -> bool,
+    ) {
+        if let Expression::Identifier(identifier) = value {
+            let name = identifier.get_name();
+            if is_top_level_local(name) && filter(field.unwrap_or(name)) {
+                self.locals.insert(name.to_owned());
+            }
+        }
+    }
+}
+
+fn collect_top_level_locals(block: &Block) -> HashSet<&str> {
+    let mut locals = HashSet::new();
+
+    for statement in block.iter_statements() {
+        match statement {
+            Statement::LocalAssign(local_assign) => {
+                locals.extend(
+                    local_assign
+                        .iter_variables()
+                        .map(|variable| variable.get_name()),
+                );
+            }
+            Statement::LocalFunction(function) => {
+                locals.insert(function.get_identifier().get_name());
+            }
+            _ => {}
+        }
+    }
+
+    locals
+}
+
+/// Returns the name of the field assigned by the variable when it indexes the given table
+/// (like `M.name` or `M["name"]`).
+fn exported_field(variable: &Variable, table_name: &str) -> Option<String> {
+    let (prefix, field) = match variable {
+        Variable::Field(field) => (field.get_prefix(), field.get_field().get_name().to_owned()),
+        Variable::Index(index) => match index.get_index() {
+            Expression::String(key) => (index.get_prefix(), key.get_value().to_owned()),
+            _ => return None,
+        },
+        Variable::Identifier(_) => return None,
+    };
+
+    match prefix {
+        Prefix::Identifier(identifier) if identifier.get_name() == table_name => Some(field),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Parser;
+
+    fn find_exports(code: &str, filter: impl Fn(&str) -> bool) -> Vec<String> {
+        let block = Parser::default().parse(code).unwrap();
+        let mut exports: Vec<_> = ModuleExports::find(&block, filter)
+            .iter()
+            .cloned()
+            .collect();
+        exports.sort();
+        exports
+    }
+
+    #[test]
+    fn table_constructor_values() {
+        assert_eq!(
+            find_exports(
+                "local a, b local function c() end local d return { a = a, ['b'] = b, c, e = d.e }",
+                |_| true
+            ),
+            vec!["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn assigned_fields_of_returned_local() {
+        assert_eq!(
+            find_exports(
+                "local M = { a = a } local a, b local function c() end \
+                M.a = a M['b'] = b M.c, M.d = c, nil N.x = a return M",
+                |_| true
+            ),
+            vec!["M", "a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn globals_are_not_exported() {
+        assert!(find_exports("return { a = a, b = b }", |_| true).is_empty());
+    }
+
+    #[test]
+    fn filter_uses_field_names() {
+        assert_eq!(
+            find_exports("local a, b, c return { public_a = a, b = b, c }", |name| {
+                name.starts_with("public_")
+            }),
+            vec!["a"]
+        );
+    }
+
+    #[test]
+    fn without_return_statement() {
+        assert!(find_exports("local a", |_| true).is_empty());
+    }
+}
@@ -6,6 +6,7 @@ use crate::rules::{
     Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
 };
 
+#[cfg(feature = "serde-config")]
 use serde::ser::{Serialize, Serializer};
 use std::mem;
 
@@ -17,6 +18,7 @@ impl Processor {
     fn convert(&self, local_function: &mut LocalFunctionStatement) -> Statement {
         let mut function_expression = FunctionExpression::default();
         function_expression.set_variadic(local_function.is_variadic());
+        function_expression.set_attributes(local_function.take_attributes());
         mem::swap(
             function_expression.mutate_block(),
             local_function.mutate_block(),
@@ -80,8 +82,13 @@ impl RuleConfiguration for ConvertLocalFunctionToAssign {
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
+#[cfg(feature = "serde-config")]
 impl Serialize for ConvertLocalFunctionToAssign {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         serializer.serialize_str(CONVERT_LOCAL_FUNCTION_TO_ASSIGN_RULE_NAME)
@@ -0,0 +1,207 @@
+use std::borrow::Cow;
+
+use crate::nodes::{Trivia, TriviaCollection, TriviaKind};
+use crate::rules::{
+    verify_no_rule_properties, Context, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    TriviaRule,
+};
+
+use super::trivia_rule::trim_whitespace_before_new_lines;
+
+pub const NORMALIZE_COMMENT_STYLE_RULE_NAME: &str = "normalize_comment_style";
+
+/// A rule that converts block comments written on a single line (like `--[[ note ]]`) into
+/// line comments and removes the trailing whitespace of comment lines.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct NormalizeCommentStyle {}
+
+impl NormalizeCommentStyle {
+    fn normalize_trivia_list(&self, trivia_list: &mut [Trivia], code: &str) {
+        for index in 0..trivia_list.len() {
+            if trivia_list[index].kind() != TriviaKind::Comment {
+                continue;
+            }
+
+            let content = trivia_list[index].read(code);
+
+            // a line comment ends at the end of the line, so a block comment can only be
+            // converted when a new line follows it
+            let followed_by_new_line = trivia_list.get(index + 1).is_some_and(|next| {
+                next.kind() == TriviaKind::Whitespace && next.read(code).contains('\n')
+            });
+
+            let normalized = if followed_by_new_line {
+                convert_to_line_comment(content)
+                    .map(Cow::Owned)
+                    .unwrap_or(Cow::Borrowed(content))
+            } else {
+                Cow::Borrowed(content)
+            };
+
+            let normalized = trim_comment_lines(&normalized);
+
+            if normalized != content {
+                let normalized = normalized.into_owned();
+                trivia_list[index].replace_with_content(normalized);
+            }
+        }
+    }
+}
+
+/// Returns the level of the long brackets that start the given content (like `[==[`).
+fn long_bracket_level(content: &str) -> Option<usize> {
+    let rest = content.strip_prefix('[')?;
+    let level = rest
+        .chars()
+        .take_while(|character| *character == '=')
+        .count();
+    rest[level..].starts_with('[').then_some(level)
+}
+
+fn convert_to_line_comment(comment: &str) -> Option<String> {
+    let content = comment.strip_prefix("--")?;
+    let level = long_bracket_level(content)?;
+
+    let closing = format!("]{}]", "=".repeat(level));
+    let text = content[level + 2..].strip_suffix(&closing)?;
+
+    if text.contains(['\n', '\r']) {
+        return None;
+    }
+
+    let text = text.trim_end_matches([' ', '\t']);
+
+    // the text must not be read as the beginning of a new block comment
+    if long_bracket_level(text).is_some() {
+        return None;
+    }
+
+    Some(format!("--{}", text))
+}
+
+fn trim_comment_lines(comment: &str) -> Cow<'_, str> {
+    let trimmed = match trim_whitespace_before_new_lines(comment) {
+        Some(trimmed) => Cow::Owned(trimmed),
+        None => Cow::Borrowed(comment),
+    };
+
+    let (line, carriage_return) = match trimmed.strip_suffix('\r') {
+        Some(line) => (line, "\r"),
+        None => (trimmed.as_ref(), ""),
+    };
+    let trimmed_line = line.trim_end_matches([' ', '\t']);
+
+    if trimmed_line.len() == line.len() {
+        trimmed
+    } else {
+        Cow::Owned(format!("{}{}", trimmed_line, carriage_return))
+    }
+}
+
+impl TriviaRule for NormalizeCommentStyle {
+    fn process_trivia(&self, trivia: &mut TriviaCollection, context: &Context) {
+        let code = context.original_code();
+        self.normalize_trivia_list(trivia.mutate_leading(), code);
+        self.normalize_trivia_list(trivia.mutate_trailing(), code);
+    }
+}
+
+impl RuleConfiguration for NormalizeCommentStyle {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)?;
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        NORMALIZE_COMMENT_STYLE_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+
+    fn is_formatting_rule(&self) -> bool {
+        true
+    }
+
+    fn is_statement_local(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> NormalizeCommentStyle {
+        NormalizeCommentStyle::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_normalize_comment_style", rule);
+    }
+
+    #[test]
+    fn convert_block_comment() {
+        assert_eq!(
+            convert_to_line_comment("--[[ note ]]"),
+            Some("-- note".to_owned())
+        );
+    }
+
+    #[test]
+    fn convert_block_comment_with_level() {
+        assert_eq!(
+            convert_to_line_comment("--[==[ a ]] b ]==]"),
+            Some("-- a ]] b".to_owned())
+        );
+    }
+
+    #[test]
+    fn do_not_convert_line_comment() {
+        assert_eq!(convert_to_line_comment("-- note"), None);
+    }
+
+    #[test]
+    fn do_not_convert_multiline_block_comment() {
+        assert_eq!(convert_to_line_comment("--[[ a\nb ]]"), None);
+    }
+
+    #[test]
+    fn do_not_convert_block_comment_starting_with_long_bracket() {
+        assert_eq!(convert_to_line_comment("--[[[=[ a ]]"), None);
+    }
+
+    #[test]
+    fn trim_line_comment() {
+        assert_eq!(trim_comment_lines("-- note \t"), "-- note");
+    }
+
+    #[test]
+    fn trim_line_comment_before_carriage_return() {
+        assert_eq!(trim_comment_lines("-- note  \r"), "-- note\r");
+    }
+
+    #[test]
+    fn trim_lines_of_block_comment() {
+        assert_eq!(trim_comment_lines("--[[ a  \n b\t\n]]"), "--[[ a\n b\n]]");
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'normalize_comment_style',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
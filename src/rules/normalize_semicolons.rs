@@ -0,0 +1,258 @@
+use crate::generator::utils::{ends_with_prefix, starts_with_parenthese};
+use crate::nodes::{Block, BlockTokens, Statement, Token};
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind,
+};
+
+/// Defines where the `normalize_semicolons` rule puts semicolons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SemicolonStyle {
+    /// Every statement is terminated by a semicolon.
+    Always,
+    /// Semicolons are removed, except the ones needed to avoid ambiguous syntax.
+    #[default]
+    Never,
+    /// Only function call statements are terminated by a semicolon.
+    OnlyAfterFunctionCalls,
+}
+
+impl SemicolonStyle {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "always" => Some(Self::Always),
+            "never" => Some(Self::Never),
+            "only_after_function_calls" => Some(Self::OnlyAfterFunctionCalls),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Always => "always",
+            Self::Never => "never",
+            Self::OnlyAfterFunctionCalls => "only_after_function_calls",
+        }
+    }
+
+    fn wants_semicolon(&self, statement: &Statement) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::OnlyAfterFunctionCalls => matches!(statement, Statement::Call(_)),
+        }
+    }
+}
+
+struct Processor {
+    style: SemicolonStyle,
+}
+
+impl Processor {
+    fn new(style: SemicolonStyle) -> Self {
+        Self { style }
+    }
+}
+
+fn new_semicolon() -> Token {
+    Token::from_content(";")
+}
+
+/// Removes a semicolon while keeping the comments attached to it.
+fn remove_semicolon(semicolon: &mut Option<Token>) {
+    if let Some(token) = semicolon {
+        if token.has_trivia() {
+            token.replace_with_content("");
+        } else {
+            semicolon.take();
+        }
+    }
+}
+
+fn add_semicolon(semicolon: &mut Option<Token>) {
+    match semicolon {
+        Some(token) => {
+            if token.try_read() == Some("") {
+                token.replace_with_content(";");
+            }
+        }
+        None => {
+            semicolon.replace(new_semicolon());
+        }
+    }
+}
+
+impl NodeProcessor for Processor {
+    fn process_block(&mut self, block: &mut Block) {
+        let statements: Vec<_> = block.iter_statements().collect();
+
+        let expected: Vec<bool> = statements
+            .iter()
+            .enumerate()
+            .map(|(index, statement)| {
+                self.style.wants_semicolon(statement)
+                    || statements
+                        .get(index + 1)
+                        .filter(|next_statement| {
+                            ends_with_prefix(statement) && starts_with_parenthese(next_statement)
+                        })
+                        .is_some()
+            })
+            .collect();
+        let expect_last_semicolon =
+            self.style == SemicolonStyle::Always && block.get_last_statement().is_some();
+
+        if block.get_tokens().is_none() {
+            if !expected.iter().any(|value| *value) && !expect_last_semicolon {
+                return;
+            }
+            block.set_tokens(BlockTokens {
+                semicolons: Vec::new(),
+                last_semicolon: None,
+                final_token: None,
+            });
+        }
+
+        let tokens = block.mutate_tokens().expect("block should have tokens");
+
+        tokens.semicolons.resize(expected.len(), None);
+
+        for (semicolon, expect_semicolon) in tokens.semicolons.iter_mut().zip(expected) {
+            if expect_semicolon {
+                add_semicolon(semicolon);
+            } else {
+                remove_semicolon(semicolon);
+            }
+        }
+
+        if expect_last_semicolon {
+            add_semicolon(&mut tokens.last_semicolon);
+        } else {
+            remove_semicolon(&mut tokens.last_semicolon);
+        }
+    }
+}
+
+pub const NORMALIZE_SEMICOLONS_RULE_NAME: &str = "normalize_semicolons";
+
+/// A rule that adds or removes semicolons after statements.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct NormalizeSemicolons {
+    style: SemicolonStyle,
+}
+
+impl NormalizeSemicolons {
+    pub fn with_style(mut self, style: SemicolonStyle) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl FlawlessRule for NormalizeSemicolons {
+    fn flawless_process(&self, block: &mut Block, _: &Context) {
+        let mut processor = Processor::new(self.style);
+        DefaultVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for NormalizeSemicolons {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "style" => {
+                    let style = value.expect_string(&key)?;
+                    self.style = SemicolonStyle::parse(&style).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: "style".to_owned(),
+                            message: format!(
+                                "invalid value `{}` (must be `always`, `never` or `only_after_function_calls`)",
+                                style
+                            ),
+                        }
+                    })?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        NORMALIZE_SEMICOLONS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["style"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Choice(&["always", "never", "only_after_function_calls"])
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.style != SemicolonStyle::default() {
+            properties.insert("style".to_owned(), self.style.as_str().into());
+        }
+
+        properties
+    }
+
+    fn is_formatting_rule(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> NormalizeSemicolons {
+        NormalizeSemicolons::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_normalize_semicolons", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_always_style() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_style(SemicolonStyle::Always));
+
+        assert_json_snapshot!("normalize_semicolons_always_style", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_style_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'normalize_semicolons',
+            style: 'sometimes',
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'style': invalid value `sometimes` (must be `always`, `never` or `only_after_function_calls`)"
+        );
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'normalize_semicolons',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
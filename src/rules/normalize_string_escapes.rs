@@ -0,0 +1,456 @@
+use crate::nodes::{Block, StringError, StringExpression, Token};
+use crate::process::{DefaultVisitor, LuaVersion, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult, RuleProperties,
+    RulePropertyKind,
+};
+
+pub const NORMALIZE_STRING_ESCAPES_RULE_NAME: &str = "normalize_string_escapes";
+
+/// The escape sequences that can be written in the strings of the generated code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EscapeForms {
+    hex: bool,
+    unicode: bool,
+}
+
+impl EscapeForms {
+    fn new(version: Option<LuaVersion>, prefer_hex: bool) -> Self {
+        // `\x`, `\z` and `\u{...}` escapes do not exist in Lua 5.1. Without a target, only
+        // the escapes valid in every version are used.
+        let extended = matches!(
+            version,
+            Some(LuaVersion::Lua53 | LuaVersion::Lua54 | LuaVersion::Luau)
+        );
+
+        Self {
+            hex: extended && prefer_hex,
+            unicode: extended,
+        }
+    }
+}
+
+/// The bytes of a string, with the quote used to write it when it is known.
+struct DecodedLiteral {
+    bytes: Vec<u8>,
+    quote: Option<char>,
+}
+
+/// Returns the bytes of the string as Lua reads them. When the string token references the
+/// original code, the bytes are decoded from the code so that numeric escapes (like `\233`)
+/// are not confused with unicode characters. Returns `None` for long strings, which can not
+/// contain escape sequences.
+fn decode_string(
+    string: &StringExpression,
+    code: &str,
+) -> Option<Result<DecodedLiteral, StringError>> {
+    if string.is_long_string() {
+        return None;
+    }
+
+    let Some(literal) = string.get_token().map(|token| token.read(code)) else {
+        return Some(Ok(DecodedLiteral {
+            bytes: string.as_bytes().to_vec(),
+            quote: None,
+        }));
+    };
+
+    let mut chars = literal.char_indices();
+
+    match (chars.next(), chars.next_back()) {
+        (Some((_, '[')), _) => None,
+        (Some((_, quote)), Some((_, last_char))) if quote == last_char => Some(
+            crate::nodes::string_utils::read_escaped_bytes(chars).map(|bytes| DecodedLiteral {
+                bytes,
+                quote: Some(quote),
+            }),
+        ),
+        _ => Some(Err(StringError::invalid("quotes do not match"))),
+    }
+}
+
+fn choose_quote(bytes: &[u8]) -> char {
+    if bytes.contains(&b'"') {
+        '\''
+    } else if bytes.contains(&b'\'') {
+        '"'
+    } else {
+        '\''
+    }
+}
+
+/// Returns the length of the UTF-8 character that starts the given bytes, if they start
+/// with a valid non-ASCII character.
+fn utf8_character_length(bytes: &[u8]) -> Option<usize> {
+    let length = match bytes.first()? {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => return None,
+    };
+
+    bytes
+        .get(..length)
+        .filter(|character| std::str::from_utf8(character).is_ok())
+        .map(|_| length)
+}
+
+fn push_numeric_escape(encoded: &mut String, byte: u8, next: Option<&u8>, forms: EscapeForms) {
+    if forms.hex {
+        encoded.push_str(&format!("\\x{:02x}", byte));
+    } else if next.is_some_and(u8::is_ascii_digit) {
+        encoded.push_str(&format!("\\{:03}", byte));
+    } else {
+        encoded.push_str(&format!("\\{}", byte));
+    }
+}
+
+/// Writes the bytes as a quoted string, using only the escape sequences of the given forms.
+fn encode_string(bytes: &[u8], quote: char, forms: EscapeForms) -> String {
+    let mut encoded = String::with_capacity(bytes.len() + 2);
+    encoded.push(quote);
+
+    let mut index = 0;
+
+    while let Some(&byte) = bytes.get(index) {
+        if !byte.is_ascii() {
+            if let Some(length) = utf8_character_length(&bytes[index..]).filter(|_| forms.unicode) {
+                let character = std::str::from_utf8(&bytes[index..index + length])
+                    .ok()
+                    .and_then(|character| character.chars().next())
+                    .expect("bytes should be a valid UTF-8 character");
+                encoded.push_str(&format!("\\u{{{:x}}}", character as u32));
+                index += length;
+            } else {
+                push_numeric_escape(&mut encoded, byte, bytes.get(index + 1), forms);
+                index += 1;
+            }
+            continue;
+        }
+
+        match byte {
+            b'\n' => encoded.push_str("\\n"),
+            b'\t' => encoded.push_str("\\t"),
+            b'\r' => encoded.push_str("\\r"),
+            b'\\' => encoded.push_str("\\\\"),
+            0x7 => encoded.push_str("\\a"),
+            0x8 => encoded.push_str("\\b"),
+            0xB => encoded.push_str("\\v"),
+            0xC => encoded.push_str("\\f"),
+            _ if byte as char == quote => {
+                encoded.push('\\');
+                encoded.push(quote);
+            }
+            b' '..=b'~' => encoded.push(byte as char),
+            _ => push_numeric_escape(&mut encoded, byte, bytes.get(index + 1), forms),
+        }
+
+        index += 1;
+    }
+
+    encoded.push(quote);
+    encoded
+}
+
+struct Processor<'a, 'b, 'resources, 'code> {
+    code: &'a str,
+    forms: EscapeForms,
+    context: &'a Context<'b, 'resources, 'code>,
+    errors: Vec<String>,
+}
+
+impl NodeProcessor for Processor<'_, '_, '_, '_> {
+    fn process_string_expression(&mut self, string: &mut StringExpression) {
+        let DecodedLiteral { bytes, quote } = match decode_string(string, self.code) {
+            Some(Ok(decoded)) => decoded,
+            Some(Err(err)) => {
+                let message = format!("unable to decode string escape sequences: {}", err);
+                let message = if self.errors.is_empty() {
+                    self.context.error_at_current_node(message)
+                } else {
+                    message
+                };
+                self.errors.push(message);
+                return;
+            }
+            None => return,
+        };
+
+        let quote = quote.unwrap_or_else(|| choose_quote(&bytes));
+        let encoded = encode_string(&bytes, quote, self.forms);
+
+        if let Some(token) = string.mutate_token() {
+            token.replace_with_content(encoded);
+        } else {
+            string.set_token(Token::from_content(encoded));
+        }
+    }
+}
+
+/// A rule that rewrites the escape sequences of strings using only the forms valid for the
+/// Lua version of the target.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NormalizeStringEscapes {
+    prefer_hex: bool,
+}
+
+impl NormalizeStringEscapes {
+    /// Writes numeric escapes in hexadecimal (like `\x1b`) when the target supports them.
+    pub fn with_prefer_hex(mut self, value: bool) -> Self {
+        self.prefer_hex = value;
+        self
+    }
+}
+
+impl Rule for NormalizeStringEscapes {
+    fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
+        let version = context
+            .environment()
+            .target()
+            .map(|target| target.lua_version());
+
+        let mut processor = Processor {
+            code: context.original_code(),
+            forms: EscapeForms::new(version, self.prefer_hex),
+            context,
+            errors: Vec::new(),
+        };
+        DefaultVisitor::visit_block(block, &mut processor);
+
+        if processor.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(processor.errors.join("\n"))
+        }
+    }
+}
+
+impl RuleConfiguration for NormalizeStringEscapes {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "prefer_hex" => {
+                    self.prefer_hex = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        NORMALIZE_STRING_ESCAPES_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["prefer_hex"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Boolean
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.prefer_hex {
+            properties.insert("prefer_hex".to_owned(), self.prefer_hex.into());
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generator::{DenseLuaGenerator, LuaGenerator, TokenBasedLuaGenerator};
+    use crate::nodes::string_utils::read_escaped_bytes;
+    use crate::process::{Environment, EnvironmentTarget};
+    use crate::rules::ContextBuilder;
+    use crate::{Parser, Resources};
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> NormalizeStringEscapes {
+        NormalizeStringEscapes::default()
+    }
+
+    fn literal_bytes(literal: &str) -> Vec<u8> {
+        let mut chars = literal.char_indices();
+        chars.next();
+        chars.next_back();
+        read_escaped_bytes(chars).expect("literal should be a valid string")
+    }
+
+    fn apply(
+        rule: &NormalizeStringEscapes,
+        code: &str,
+        target: EnvironmentTarget,
+        parser: Parser,
+    ) -> Block {
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new("test.lua", &resources, code)
+            .with_environment(Environment::builtin(target))
+            .build();
+
+        let mut block = parser.parse(code).expect("code should parse");
+        rule.process(&mut block, &context)
+            .expect("rule should succeed");
+        block
+    }
+
+    fn apply_with_tokens(
+        rule: &NormalizeStringEscapes,
+        code: &str,
+        target: EnvironmentTarget,
+    ) -> String {
+        let block = apply(rule, code, target, Parser::default().preserve_tokens());
+
+        let mut generator = TokenBasedLuaGenerator::new(code);
+        generator.write_block(&block);
+        generator.into_string()
+    }
+
+    fn apply_dense(rule: &NormalizeStringEscapes, code: &str, target: EnvironmentTarget) -> String {
+        let block = apply(rule, code, target, Parser::default());
+
+        let mut generator = DenseLuaGenerator::default();
+        generator.write_block(&block);
+        generator.into_string()
+    }
+
+    macro_rules! test_escapes_with_tokens {
+        ($($name:ident($rule:expr, $target:ident, $input:literal) => $output:literal),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    let code = format!("return {}", $input);
+
+                    let result = apply_with_tokens(&$rule, &code, EnvironmentTarget::$target);
+
+                    assert_eq!(result, format!("return {}", $output));
+                    assert_eq!(literal_bytes($input), literal_bytes($output));
+                }
+            )*
+        };
+    }
+
+    macro_rules! test_escapes {
+        ($($name:ident($rule:expr, $target:ident, $input:literal) => $output:literal),* $(,)?) => {
+            test_escapes_with_tokens!(
+                $($name($rule, $target, $input) => $output,)*
+            );
+
+            mod dense {
+                use super::*;
+
+                $(
+                    #[test]
+                    fn $name() {
+                        let code = format!("return {}", $input);
+
+                        let result = apply_dense(&$rule, &code, EnvironmentTarget::$target);
+
+                        assert_eq!(result, format!("return{}", $output));
+                    }
+                )*
+            }
+        };
+    }
+
+    test_escapes!(
+        hex_to_character_for_lua51(new_rule(), Lua51, r"'\x41'") => "'A'",
+        hex_to_decimal_for_lua51(new_rule(), Lua51, r"'\x1b'") => r"'\27'",
+        hex_followed_by_digit_for_lua51(new_rule(), Lua51, r"'\x1b1'") => r"'\0271'",
+        unicode_to_bytes_for_lua51(new_rule(), Lua51, r"'\u{e9}'") => r"'\195\169'",
+        unicode_emoji_to_bytes_for_lua51(new_rule(), Lua51, r"'\u{1F600}'")
+            => r"'\240\159\152\128'",
+        raw_unicode_to_bytes_for_lua51(new_rule(), Lua51, "'é'") => r"'\195\169'",
+        skip_whitespace_removed_for_lua51(new_rule(), Lua51, "'a\\z   \n  b'") => "'ab'",
+        decimal_kept_for_lua51(new_rule(), Lua51, r"'\27'") => r"'\27'",
+        decimal_to_character_for_lua51(new_rule(), Lua51, r"'\65\66'") => "'AB'",
+        escaped_new_line_for_lua51(new_rule(), Lua51, "'a\\\nb'") => r"'a\nb'",
+        prefer_hex_ignored_for_lua51(new_rule().with_prefer_hex(true), Lua51, r"'\x1b'")
+            => r"'\27'",
+        keep_double_quotes(new_rule(), Lua51, r#""it's\x21""#) => r#""it's!""#,
+        unicode_kept_for_luau(new_rule(), Luau, r"'\u{1F600}'") => r"'\u{1f600}'",
+        unicode_kept_for_lua53(new_rule(), Lua53, r"'\u{e9}'") => r"'\u{e9}'",
+        skip_whitespace_removed_for_luau(new_rule(), Luau, "'a\\z   b'") => "'ab'",
+        decimal_to_hex_for_luau(new_rule().with_prefer_hex(true), Luau, r"'\27'") => r"'\x1b'",
+        decimal_followed_by_digit_to_hex_for_lua54(
+            new_rule().with_prefer_hex(true),
+            Lua54,
+            r"'\0001'"
+        ) => r"'\x001'",
+        hex_to_decimal_without_prefer_hex_for_luau(new_rule(), Luau, r"'\x1b'") => r"'\27'",
+    );
+
+    // without tokens, numeric escapes of bytes above 127 can not be told apart from unicode
+    // characters in the value of strings, and the quotes of the string are not known
+    test_escapes_with_tokens!(
+        hex_high_byte_for_lua51(new_rule(), Lua51, r"'\xff'") => r"'\255'",
+        decimal_bytes_to_unicode_for_luau(new_rule(), Luau, r"'\240\159\152\128'")
+            => r"'\u{1f600}'",
+        invalid_utf8_bytes_for_luau(new_rule(), Luau, r"'\233'") => r"'\233'",
+        keep_escaped_quote(new_rule(), Lua51, r"'it\'s'") => r"'it\'s'",
+        decimal_high_byte_kept_for_lua51(new_rule(), Lua51, r"'\233'") => r"'\233'",
+    );
+
+    #[test]
+    fn long_string_is_not_changed() {
+        let code = "return [[\\u{1F600}]]";
+
+        assert_eq!(
+            apply_with_tokens(&new_rule(), code, EnvironmentTarget::Lua51),
+            code
+        );
+        assert_eq!(
+            apply_dense(&new_rule(), code, EnvironmentTarget::Lua51),
+            r"return'\\u{1F600}'"
+        );
+    }
+
+    #[test]
+    fn without_target_uses_escapes_valid_in_every_version() {
+        let resources = Resources::from_memory();
+        let code = r"return '\u{e9}'";
+        let context = ContextBuilder::new("test.lua", &resources, code).build();
+        let mut block = Parser::default().parse(code).expect("code should parse");
+
+        new_rule()
+            .process(&mut block, &context)
+            .expect("rule should succeed");
+
+        let mut generator = DenseLuaGenerator::default();
+        generator.write_block(&block);
+        assert_eq!(generator.into_string(), r"return'\195\169'");
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_normalize_string_escapes", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_prefer_hex() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_prefer_hex(true));
+
+        assert_json_snapshot!("normalize_string_escapes_with_prefer_hex", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_prefer_hex_should_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            "{ rule: 'normalize_string_escapes', prefer_hex: 'yes' }",
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "boolean value expected for field 'prefer_hex'"
+        );
+    }
+}
@@ -0,0 +1,467 @@
+use std::collections::BTreeMap;
+
+use crate::nodes::{Block, Expression, Identifier, LocalFunctionStatement, TypeField};
+use crate::process::utils::{is_valid_identifier, KEYWORDS};
+use crate::process::{NodeProcessor, NodeVisitor, Scope, ScopeVisitor};
+use crate::rules::{
+    Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult, RuleProperties,
+    RulePropertyKind,
+};
+
+#[derive(Debug)]
+struct Binding {
+    original: String,
+    obfuscated: String,
+    id: usize,
+}
+
+struct ObfuscateProcessor<'a> {
+    mappings: &'a BTreeMap<String, String>,
+    include_globals: bool,
+    scopes: Vec<Vec<Binding>>,
+    next_id: usize,
+    error: Option<String>,
+}
+
+impl<'a> ObfuscateProcessor<'a> {
+    fn new(mappings: &'a BTreeMap<String, String>, include_globals: bool) -> Self {
+        Self {
+            mappings,
+            include_globals,
+            scopes: Vec::new(),
+            next_id: 0,
+            error: None,
+        }
+    }
+
+    fn add(&mut self, original: String, obfuscated: String) {
+        let binding = Binding {
+            original,
+            obfuscated,
+            id: self.next_id,
+        };
+        self.next_id += 1;
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(binding);
+        } else {
+            self.scopes.push(vec![binding]);
+        }
+    }
+
+    fn declare(&mut self, identifier: &mut Identifier) {
+        let original = identifier.get_name().to_owned();
+
+        if let Some(obfuscated) = self.mappings.get(&original) {
+            identifier.set_name(obfuscated);
+        }
+
+        let obfuscated = identifier.get_name().to_owned();
+        self.add(original, obfuscated);
+    }
+
+    fn find_binding(&self, predicate: impl Fn(&Binding) -> bool) -> Option<&Binding> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.iter().rev().find(|binding| predicate(binding)))
+    }
+
+    fn is_global_target(&self, name: &str) -> bool {
+        self.mappings.values().any(|obfuscated| obfuscated == name)
+    }
+
+    /// Computes the new name of a variable reference and verifies that the new name still
+    /// refers to the same variable.
+    fn rename_reference(&mut self, identifier: &mut Identifier) {
+        let name = identifier.get_name();
+
+        let (id, new_name) = match self.find_binding(|binding| binding.original == name) {
+            Some(binding) => (Some(binding.id), binding.obfuscated.clone()),
+            None => {
+                let new_name = if self.include_globals {
+                    self.mappings
+                        .get(name)
+                        .map(String::as_str)
+                        .unwrap_or(name)
+                        .to_owned()
+                } else {
+                    name.to_owned()
+                };
+                (None, new_name)
+            }
+        };
+
+        let resolved_id = self
+            .find_binding(|binding| binding.obfuscated == new_name)
+            .map(|binding| binding.id);
+
+        let global_collision = id.is_none()
+            && self.include_globals
+            && !self.mappings.contains_key(name)
+            && self.is_global_target(name);
+
+        if id != resolved_id || global_collision {
+            if self.error.is_none() {
+                self.error = Some(if new_name == name {
+                    format!("`{}` would be shadowed by a renamed variable", name)
+                } else {
+                    format!(
+                        "unable to rename `{}` to `{}` because it would refer to another variable",
+                        name, new_name
+                    )
+                });
+            }
+        } else if new_name != name {
+            identifier.set_name(new_name);
+        }
+    }
+}
+
+impl Scope for ObfuscateProcessor<'_> {
+    fn push(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.declare(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.add("self".to_owned(), "self".to_owned());
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, _value: Option<&mut Expression>) {
+        self.declare(identifier);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.declare(function.mutate_identifier());
+    }
+}
+
+impl NodeProcessor for ObfuscateProcessor<'_> {
+    fn process_variable_expression(&mut self, variable: &mut Identifier) {
+        self.rename_reference(variable);
+    }
+
+    fn process_type_field(&mut self, type_field: &mut TypeField) {
+        self.rename_reference(type_field.mutate_namespace());
+    }
+}
+
+pub const OBFUSCATE_IDENTIFIERS_RULE_NAME: &str = "obfuscate_identifiers";
+
+/// A rule that renames identifiers using a fixed mapping of original names to obfuscated names.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ObfuscateIdentifiers {
+    mappings: BTreeMap<String, String>,
+    include_globals: bool,
+}
+
+impl ObfuscateIdentifiers {
+    pub fn with_mapping(
+        mut self,
+        original: impl Into<String>,
+        obfuscated: impl Into<String>,
+    ) -> Self {
+        self.mappings.insert(original.into(), obfuscated.into());
+        self
+    }
+
+    pub fn with_include_globals(mut self, include_globals: bool) -> Self {
+        self.include_globals = include_globals;
+        self
+    }
+
+    fn verify_mappings(&self) -> Result<(), RuleConfigurationError> {
+        let mut targets: BTreeMap<&str, &str> = BTreeMap::new();
+
+        for (original, obfuscated) in &self.mappings {
+            if KEYWORDS.contains(&obfuscated.as_str()) {
+                return Err(RuleConfigurationError::UnexpectedValue {
+                    property: "mappings".to_owned(),
+                    message: format!(
+                        "`{}` cannot be renamed to `{}` because it is a keyword",
+                        original, obfuscated
+                    ),
+                });
+            }
+
+            if !is_valid_identifier(obfuscated, None) {
+                return Err(RuleConfigurationError::UnexpectedValue {
+                    property: "mappings".to_owned(),
+                    message: format!(
+                        "`{}` cannot be renamed to `{}` because it is not a valid identifier",
+                        original, obfuscated
+                    ),
+                });
+            }
+
+            if let Some(other) = targets.insert(obfuscated, original) {
+                return Err(RuleConfigurationError::UnexpectedValue {
+                    property: "mappings".to_owned(),
+                    message: format!(
+                        "`{}` and `{}` cannot both be renamed to `{}`",
+                        other, original, obfuscated
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Rule for ObfuscateIdentifiers {
+    fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
+        self.verify_mappings().map_err(|err| err.to_string())?;
+
+        if self.mappings.is_empty() {
+            return Ok(());
+        }
+
+        let reserved_identifiers = context.reserved_identifiers();
+        if let Some((original, obfuscated)) = self.mappings.iter().find(|(original, obfuscated)| {
+            original != obfuscated && reserved_identifiers.contains(obfuscated)
+        }) {
+            return Err(format!(
+                "unable to rename `{}` to `{}` because `{}` is reserved by a previous rule",
+                original, obfuscated, obfuscated
+            ));
+        }
+
+        let mut processor = ObfuscateProcessor::new(&self.mappings, self.include_globals);
+        ScopeVisitor::visit_block(block, &mut processor);
+
+        match processor.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl RuleConfiguration for ObfuscateIdentifiers {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "mappings" => {
+                    self.mappings = value.expect_string_map(&key)?;
+                }
+                "include_globals" => {
+                    self.include_globals = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        self.verify_mappings()
+    }
+
+    fn get_name(&self) -> &'static str {
+        OBFUSCATE_IDENTIFIERS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["mappings", "include_globals"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "include_globals" => RulePropertyKind::Boolean,
+            _ => RulePropertyKind::StringMap,
+        }
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+
+    fn is_unsafe_to_reapply(&self) -> bool {
+        // mappings can be chained (like `a` to `b` and `b` to `c`)
+        true
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if !self.mappings.is_empty() {
+            properties.insert("mappings".to_owned(), self.mappings.clone().into());
+        }
+
+        if self.include_globals {
+            properties.insert("include_globals".to_owned(), true.into());
+        }
+
+        properties
+    }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if self.include_globals {
+            properties.push("include_globals");
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{rules::ContextBuilder, Parser, Resources};
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> ObfuscateIdentifiers {
+        ObfuscateIdentifiers::default()
+    }
+
+    fn process(rule: &ObfuscateIdentifiers, code: &str) -> RuleProcessResult {
+        let mut block = Parser::default().parse(code).expect("unable to parse code");
+
+        rule.process(
+            &mut block,
+            &ContextBuilder::new(".", &Resources::from_memory(), code).build(),
+        )
+    }
+
+    #[test]
+    fn process_with_shadowed_local_error() {
+        let rule = new_rule().with_mapping("b", "a");
+
+        pretty_assertions::assert_eq!(
+            process(&rule, "local a = 1 local b = 2 return a + b").unwrap_err(),
+            "`a` would be shadowed by a renamed variable"
+        );
+    }
+
+    #[test]
+    fn process_with_reserved_target_error() {
+        let rule = new_rule().with_mapping("object", "self");
+        let code = "local object = {} return object";
+        let mut block = Parser::default().parse(code).expect("unable to parse code");
+
+        pretty_assertions::assert_eq!(
+            rule.process(
+                &mut block,
+                &ContextBuilder::new(".", &Resources::from_memory(), code)
+                    .with_reserved_identifiers(vec!["self".to_owned()])
+                    .build(),
+            )
+            .unwrap_err(),
+            "unable to rename `object` to `self` because `self` is reserved by a previous rule"
+        );
+    }
+
+    #[test]
+    fn process_with_shadowed_global_error() {
+        let rule = new_rule().with_mapping("value", "print");
+
+        pretty_assertions::assert_eq!(
+            process(&rule, "local value = 1 print(value)").unwrap_err(),
+            "`print` would be shadowed by a renamed variable"
+        );
+    }
+
+    #[test]
+    fn process_with_colliding_globals_error() {
+        let rule = new_rule().with_mapping("a", "b").with_include_globals(true);
+
+        pretty_assertions::assert_eq!(
+            process(&rule, "return a, b").unwrap_err(),
+            "`b` would be shadowed by a renamed variable"
+        );
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_obfuscate_identifiers", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_mappings() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .with_mapping("value", "v")
+                .with_mapping("count", "c")
+                .with_include_globals(true),
+        );
+
+        assert_json_snapshot!("obfuscate_identifiers_with_mappings", rule);
+    }
+
+    #[test]
+    fn configure_with_keyword_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'obfuscate_identifiers',
+            mappings: { value: 'end' },
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'mappings': `value` cannot be renamed to `end` because it is a keyword"
+        );
+    }
+
+    #[test]
+    fn configure_with_invalid_identifier_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'obfuscate_identifiers',
+            mappings: { value: '1v' },
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'mappings': `value` cannot be renamed to `1v` because it is not a valid identifier"
+        );
+    }
+
+    #[test]
+    fn configure_with_duplicated_target_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'obfuscate_identifiers',
+            mappings: { count: 'x', value: 'x' },
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'mappings': `count` and `value` cannot both be renamed to `x`"
+        );
+    }
+
+    #[test]
+    fn configure_with_list_mappings_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'obfuscate_identifiers',
+            mappings: ['value'],
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "map of strings expected for field 'mappings'"
+        );
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'obfuscate_identifiers',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -0,0 +1,360 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::{RuleConfigurationError, RulePropertyValue};
+
+const BYTE_SIZE_UNITS: &[(&str, u64)] = &[
+    ("b", 1),
+    ("kb", 1_000),
+    ("mb", 1_000_000),
+    ("gb", 1_000_000_000),
+    ("tb", 1_000_000_000_000),
+    ("kib", 1 << 10),
+    ("mib", 1 << 20),
+    ("gib", 1 << 30),
+    ("tib", 1 << 40),
+];
+const BYTE_SIZE_SUFFIXES: &str = "`B`, `KB`, `MB`, `GB`, `TB`, `KiB`, `MiB`, `GiB` or `TiB`";
+
+const NANOSECONDS_PER_SECOND: u64 = 1_000_000_000;
+const DURATION_UNITS: &[(&str, u64)] = &[
+    ("ms", 1_000_000),
+    ("s", NANOSECONDS_PER_SECOND),
+    ("sec", NANOSECONDS_PER_SECOND),
+    ("min", 60 * NANOSECONDS_PER_SECOND),
+    ("h", 3600 * NANOSECONDS_PER_SECOND),
+];
+const DURATION_SUFFIXES: &str = "`ms`, `s`, `sec`, `min` or `h`";
+
+/// A number written with decimal digits, kept as its integer and fractional digits to
+/// convert it to a unit without rounding errors.
+struct Decimal<'a> {
+    integer: &'a str,
+    fraction: &'a str,
+}
+
+impl<'a> Decimal<'a> {
+    /// Splits the leading number of the value from its unit.
+    fn split(value: &'a str) -> Result<(Self, &'a str), String> {
+        let number_end = value
+            .find(|character: char| !character.is_ascii_digit() && character != '.')
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(number_end);
+
+        if number.is_empty() {
+            return Err(format!("`{}` does not start with a number", value));
+        }
+
+        let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+
+        if fraction.contains('.') || (integer.is_empty() && fraction.is_empty()) {
+            return Err(format!("`{}` is not a valid number", number));
+        }
+
+        Ok((Self { integer, fraction }, unit.trim_start()))
+    }
+
+    /// Multiplies the number by the given factor. Returns the result and `true` if the
+    /// result is exact.
+    fn scale(&self, factor: u64) -> Option<(u64, bool)> {
+        let digits = format!("{}{}", self.integer, self.fraction);
+        let mantissa: u128 = if digits.is_empty() {
+            0
+        } else {
+            digits.parse().ok()?
+        };
+        let divisor = 10u128.checked_pow(self.fraction.len() as u32)?;
+        let scaled = mantissa.checked_mul(factor as u128)?;
+
+        let result = u64::try_from(scaled / divisor).ok()?;
+        Some((result, scaled % divisor == 0))
+    }
+}
+
+/// Parses a size in bytes, written as a number of bytes or as a number followed by a unit
+/// (like `1 MB`, `512KiB` or `1.5 GB`). Units are not case sensitive.
+pub fn parse_byte_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let (number, unit) = Decimal::split(value)?;
+
+    let lowercase_unit = unit.to_lowercase();
+    let factor = if unit.is_empty() {
+        1
+    } else if let Some((_, factor)) = BYTE_SIZE_UNITS
+        .iter()
+        .find(|(name, _)| *name == lowercase_unit)
+    {
+        *factor
+    } else if matches!(lowercase_unit.as_str(), "k" | "m" | "g" | "t") {
+        let prefix = unit.to_uppercase();
+        return Err(format!(
+            "ambiguous unit `{}` in `{}` (use `{}B` for powers of 1000 or `{}iB` for powers of 1024)",
+            unit, value, prefix, prefix
+        ));
+    } else {
+        return Err(format!(
+            "unknown unit `{}` in `{}` (expected a number of bytes or a number followed by {})",
+            unit, value, BYTE_SIZE_SUFFIXES
+        ));
+    };
+
+    match number.scale(factor) {
+        Some((bytes, true)) => Ok(bytes),
+        Some((_, false)) => Err(format!("`{}` is not a whole number of bytes", value)),
+        None => Err(format!("`{}` is too large", value)),
+    }
+}
+
+/// Parses a duration, written as a number of seconds or as a number followed by a unit
+/// (like `30s`, `2min` or `250 ms`).
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, unit) = Decimal::split(value)?;
+
+    let factor = if unit.is_empty() {
+        NANOSECONDS_PER_SECOND
+    } else if let Some((_, factor)) = DURATION_UNITS.iter().find(|(name, _)| *name == unit) {
+        *factor
+    } else if unit == "m" {
+        return Err(format!(
+            "ambiguous unit `m` in `{}` (use `min` for minutes or `ms` for milliseconds)",
+            value
+        ));
+    } else {
+        return Err(format!(
+            "unknown unit `{}` in `{}` (expected a number of seconds or a number followed by {})",
+            unit, value, DURATION_SUFFIXES
+        ));
+    };
+
+    number
+        .scale(factor)
+        .map(|(nanoseconds, _)| Duration::from_nanos(nanoseconds))
+        .ok_or_else(|| format!("`{}` is too large", value))
+}
+
+/// A size in bytes that remembers how it was written, so that a configuration containing
+/// `"1 MB"` is serialized back as `"1 MB"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ByteSize {
+    bytes: u64,
+    written: Option<String>,
+}
+
+impl ByteSize {
+    pub fn new(bytes: u64) -> Self {
+        Self {
+            bytes,
+            written: None,
+        }
+    }
+
+    #[inline]
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    pub(crate) fn from_property(
+        value: RulePropertyValue,
+        key: &str,
+    ) -> Result<Self, RuleConfigurationError> {
+        let bytes = value.expect_byte_size(key)?;
+
+        Ok(Self {
+            bytes,
+            written: match value {
+                RulePropertyValue::String(written) => Some(written),
+                _ => None,
+            },
+        })
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        parse_byte_size(value).map(|bytes| Self {
+            bytes,
+            written: Some(value.to_owned()),
+        })
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.written {
+            Some(written) => write!(f, "{}", written),
+            None => write!(f, "{} bytes", self.bytes),
+        }
+    }
+}
+
+impl From<&ByteSize> for RulePropertyValue {
+    fn from(size: &ByteSize) -> Self {
+        match &size.written {
+            Some(written) => Self::String(written.clone()),
+            None => Self::Usize(size.bytes as usize),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    macro_rules! test_byte_sizes {
+        ($($name:ident ($input:literal) => $bytes:expr),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert_eq!(parse_byte_size($input), Ok($bytes));
+                }
+            )*
+        };
+    }
+
+    macro_rules! test_durations {
+        ($($name:ident ($input:literal) => $duration:expr),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert_eq!(parse_duration($input), Ok($duration));
+                }
+            )*
+        };
+    }
+
+    test_byte_sizes!(
+        bare_number_of_bytes("1048576") => 1_048_576,
+        zero_bytes("0") => 0,
+        bytes_suffix("12 B") => 12,
+        kilobytes("2KB") => 2_000,
+        megabytes("1 MB") => 1_000_000,
+        gigabytes("3 GB") => 3_000_000_000,
+        terabytes("1TB") => 1_000_000_000_000,
+        kibibytes("512KiB") => 524_288,
+        mebibytes("1 MiB") => 1_048_576,
+        gibibytes("2 GiB") => 2_147_483_648,
+        tebibytes("1 TiB") => 1_099_511_627_776,
+        lowercase_unit("4 kb") => 4_000,
+        decimal_megabytes("1.5 MB") => 1_500_000,
+        decimal_kibibytes("0.5KiB") => 512,
+        surrounding_spaces("  8 KB ") => 8_000,
+    );
+
+    test_durations!(
+        bare_number_of_seconds("30") => Duration::from_secs(30),
+        milliseconds("250ms") => Duration::from_millis(250),
+        seconds("30s") => Duration::from_secs(30),
+        seconds_with_sec("5 sec") => Duration::from_secs(5),
+        minutes("2min") => Duration::from_secs(120),
+        hours("1 h") => Duration::from_secs(3600),
+        decimal_seconds("1.5s") => Duration::from_millis(1500),
+    );
+
+    #[test]
+    fn ambiguous_megabyte_unit_errors() {
+        assert_eq!(
+            parse_byte_size("1M"),
+            Err("ambiguous unit `M` in `1M` (use `MB` for powers of 1000 or `MiB` for powers of 1024)".to_owned())
+        );
+    }
+
+    #[test]
+    fn ambiguous_minute_unit_errors() {
+        assert_eq!(
+            parse_duration("2m"),
+            Err(
+                "ambiguous unit `m` in `2m` (use `min` for minutes or `ms` for milliseconds)"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_byte_size_unit_errors() {
+        assert_eq!(
+            parse_byte_size("1 PB"),
+            Err(format!(
+                "unknown unit `PB` in `1 PB` (expected a number of bytes or a number followed by {})",
+                BYTE_SIZE_SUFFIXES
+            ))
+        );
+    }
+
+    #[test]
+    fn unknown_duration_unit_errors() {
+        assert_eq!(
+            parse_duration("1 day"),
+            Err(format!(
+                "unknown unit `day` in `1 day` (expected a number of seconds or a number followed by {})",
+                DURATION_SUFFIXES
+            ))
+        );
+    }
+
+    #[test]
+    fn fraction_of_byte_errors() {
+        assert_eq!(
+            parse_byte_size("1.5 B"),
+            Err("`1.5 B` is not a whole number of bytes".to_owned())
+        );
+    }
+
+    #[test]
+    fn missing_number_errors() {
+        assert_eq!(
+            parse_byte_size("MB"),
+            Err("`MB` does not start with a number".to_owned())
+        );
+    }
+
+    #[test]
+    fn number_with_two_dots_errors() {
+        assert_eq!(
+            parse_duration("1.2.3s"),
+            Err("`1.2.3` is not a valid number".to_owned())
+        );
+    }
+
+    #[test]
+    fn negative_size_errors() {
+        assert_eq!(
+            parse_byte_size("-1 KB"),
+            Err("`-1 KB` does not start with a number".to_owned())
+        );
+    }
+
+    #[test]
+    fn too_large_size_errors() {
+        assert_eq!(
+            parse_byte_size("100000000 TiB"),
+            Err("`100000000 TiB` is too large".to_owned())
+        );
+    }
+
+    #[test]
+    fn byte_size_from_string_property_keeps_written_value() {
+        let size = ByteSize::from_property(RulePropertyValue::from("1 MB"), "max_size").unwrap();
+
+        assert_eq!(size.bytes(), 1_000_000);
+        assert_eq!(
+            RulePropertyValue::from(&size),
+            RulePropertyValue::String("1 MB".to_owned())
+        );
+    }
+
+    #[test]
+    fn byte_size_from_number_property_serializes_to_number() {
+        let size = ByteSize::from_property(RulePropertyValue::from(2048usize), "max_size").unwrap();
+
+        assert_eq!(size.bytes(), 2048);
+        assert_eq!(
+            RulePropertyValue::from(&size),
+            RulePropertyValue::Usize(2048)
+        );
+    }
+}
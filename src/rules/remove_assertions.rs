@@ -1,10 +1,11 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::iter::{self, FromIterator};
 
 use crate::nodes::{Block, Expression, FunctionCall, Prefix, TupleArguments};
 use crate::process::{IdentifierTracker, NodeVisitor, ScopeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, InjectionPriority, RuleConfiguration, RuleConfigurationError,
+    RuleProperties, RulePropertyKind,
 };
 
 use super::remove_call_match::{CallMatch, RemoveFunctionCallProcessor};
@@ -17,12 +18,14 @@ pub const REMOVE_ASSERTIONS_RULE_NAME: &str = "remove_assertions";
 #[derive(Debug, PartialEq, Eq)]
 pub struct RemoveAssertions {
     preserve_args_side_effects: bool,
+    assume_no_env_manipulation: bool,
 }
 
 impl Default for RemoveAssertions {
     fn default() -> Self {
         Self {
             preserve_args_side_effects: true,
+            assume_no_env_manipulation: false,
         }
     }
 }
@@ -31,7 +34,7 @@ struct AssertMatcher;
 
 impl CallMatch<()> for AssertMatcher {
     fn matches(&self, identifiers: &IdentifierTracker, prefix: &Prefix) -> bool {
-        if identifiers.is_identifier_used(ASSERT_FUNCTION_NAME) {
+        if !identifiers.is_global(ASSERT_FUNCTION_NAME) {
             return false;
         }
 
@@ -44,7 +47,7 @@ impl CallMatch<()> for AssertMatcher {
     fn compute_result(
         &self,
         call: &FunctionCall,
-        mappings: &HashMap<&'static str, String>,
+        mappings: &BTreeMap<&'static str, String>,
     ) -> Option<Expression> {
         let expressions = call.get_arguments().clone().to_expressions();
 
@@ -73,13 +76,16 @@ impl CallMatch<()> for AssertMatcher {
 }
 
 impl FlawlessRule for RemoveAssertions {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
         let mut processor =
-            RemoveFunctionCallProcessor::new(self.preserve_args_side_effects, AssertMatcher);
+            RemoveFunctionCallProcessor::new(self.preserve_args_side_effects, AssertMatcher)
+                .with_generated_name_prefix(context.generated_name_prefix())
+                .with_reserved_identifiers(context.reserved_identifiers())
+                .with_assume_no_env_manipulation(self.assume_no_env_manipulation);
         ScopeVisitor::visit_block(block, &mut processor);
 
         if let Some(statement) = processor.extract_reserved_globals() {
-            block.insert_statement(0, statement);
+            context.inject_top_statement(statement, InjectionPriority::Requires);
         }
     }
 }
@@ -91,6 +97,9 @@ impl RuleConfiguration for RemoveAssertions {
                 "preserve_arguments_side_effects" => {
                     self.preserve_args_side_effects = value.expect_bool(&key)?;
                 }
+                "assume_no_env_manipulation" => {
+                    self.assume_no_env_manipulation = value.expect_bool(&key)?;
+                }
                 _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
             }
         }
@@ -102,6 +111,17 @@ impl RuleConfiguration for RemoveAssertions {
         REMOVE_ASSERTIONS_RULE_NAME
     }
 
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &[
+            "assume_no_env_manipulation",
+            "preserve_arguments_side_effects",
+        ]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Boolean
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         let mut properties = RuleProperties::new();
 
@@ -109,8 +129,29 @@ impl RuleConfiguration for RemoveAssertions {
             properties.insert("preserve_arguments_side_effects".to_owned(), false.into());
         }
 
+        if self.assume_no_env_manipulation {
+            properties.insert("assume_no_env_manipulation".to_owned(), true.into());
+        }
+
         properties
     }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if !self.preserve_args_side_effects {
+            properties.push("preserve_arguments_side_effects");
+        }
+        if self.assume_no_env_manipulation {
+            properties.push("assume_no_env_manipulation");
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
@@ -135,6 +176,7 @@ mod test {
     fn serialize_rule_without_side_effects() {
         let rule: Box<dyn Rule> = Box::new(RemoveAssertions {
             preserve_args_side_effects: false,
+            assume_no_env_manipulation: false,
         });
 
         assert_json_snapshot!("remove_assertions_without_side_effects", rule);
@@ -1,5 +1,5 @@
-use std::collections::HashMap;
-use std::{iter, ops};
+use std::collections::BTreeMap;
+use std::{iter, mem, ops};
 
 use crate::nodes::{
     Arguments, DoStatement, Expression, FunctionCall, Identifier, LocalAssignStatement, Prefix,
@@ -14,7 +14,7 @@ pub(crate) trait CallMatch<T> {
     fn compute_result(
         &self,
         _call: &FunctionCall,
-        _mappings: &HashMap<&'static str, String>,
+        _mappings: &BTreeMap<&'static str, String>,
     ) -> Option<Expression> {
         None
     }
@@ -27,7 +27,7 @@ pub(crate) trait CallMatch<T> {
 #[derive(Default)]
 pub(crate) struct RemoveFunctionCallProcessor<Args, T: CallMatch<Args>> {
     identifier_tracker: IdentifierTracker,
-    global_mappings: HashMap<&'static str, String>,
+    global_mappings: BTreeMap<&'static str, String>,
     global_counter: u32,
     evaluator: Evaluator,
     preserve_args_side_effects: bool,
@@ -66,8 +66,27 @@ impl<Args, T: CallMatch<Args>> RemoveFunctionCallProcessor<Args, T> {
         }
     }
 
+    pub(crate) fn with_generated_name_prefix(mut self, prefix: &str) -> Self {
+        self.identifier_tracker = self.identifier_tracker.with_generated_name_prefix(prefix);
+        self
+    }
+
+    pub(crate) fn with_reserved_identifiers(mut self, identifiers: Vec<String>) -> Self {
+        self.identifier_tracker = self
+            .identifier_tracker
+            .with_reserved_identifiers(identifiers);
+        self
+    }
+
+    pub(crate) fn with_assume_no_env_manipulation(mut self, value: bool) -> Self {
+        self.identifier_tracker = self
+            .identifier_tracker
+            .with_assume_no_env_manipulation(value);
+        self
+    }
+
     pub(crate) fn extract_reserved_globals(&mut self) -> Option<Statement> {
-        let (variables, values) = self.global_mappings.drain().fold(
+        let (variables, values) = mem::take(&mut self.global_mappings).into_iter().fold(
             (Vec::new(), Vec::new()),
             |(mut variables, mut values), (global, reserved_name)| {
                 variables.push(TypedIdentifier::new(reserved_name));
@@ -127,8 +146,16 @@ impl<Args, T: CallMatch<Args>> RemoveFunctionCallProcessor<Args, T> {
     }
 
     fn get_reserved_global(&mut self) -> String {
-        self.global_counter += 1;
-        format!("__DARKLUA_REMOVE_CALL_RESERVED_{}", self.global_counter)
+        loop {
+            self.global_counter += 1;
+            let name = self
+                .identifier_tracker
+                .generated_name(&format!("REMOVE_CALL_RESERVED_{}", self.global_counter));
+
+            if !self.identifier_tracker.is_identifier_reserved(&name) {
+                break name;
+            }
+        }
     }
 }
 
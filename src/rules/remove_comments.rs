@@ -3,7 +3,8 @@ use regex::Regex;
 use crate::nodes::*;
 use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
+    RulePropertyKind,
 };
 
 #[derive(Debug, Default)]
@@ -228,6 +229,10 @@ impl NodeProcessor for RemoveCommentProcessor {
     fn process_variadic_type_pack(&mut self, variadic_type_pack: &mut VariadicTypePack) {
         variadic_type_pack.clear_comments();
     }
+
+    fn process_generic_parameters(&mut self, generic_parameters: &mut GenericParameters) {
+        generic_parameters.clear_comments();
+    }
 }
 
 #[derive(Debug)]
@@ -472,6 +477,10 @@ impl NodeProcessor for FilterCommentProcessor<'_> {
     fn process_variadic_type_pack(&mut self, variadic_type_pack: &mut VariadicTypePack) {
         variadic_type_pack.filter_comments(|trivia| self.ignore_trivia(trivia));
     }
+
+    fn process_generic_parameters(&mut self, generic_parameters: &mut GenericParameters) {
+        generic_parameters.filter_comments(|trivia| self.ignore_trivia(trivia));
+    }
 }
 
 pub const REMOVE_COMMENTS_RULE_NAME: &str = "remove_comments";
@@ -512,9 +521,29 @@ impl RuleConfiguration for RemoveComments {
         REMOVE_COMMENTS_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "-- the initial value\n\
+                local value = 1 -- a number\n\
+                print(value)",
+        )]
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["except"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::StringList
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn is_statement_local(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
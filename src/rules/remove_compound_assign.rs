@@ -20,8 +20,7 @@ struct Processor {
 impl Processor {
     #[inline]
     fn generate_variable(&mut self) -> String {
-        self.identifier_tracker
-            .generate_identifier_with_prefix("__DARKLUA_VAR")
+        self.identifier_tracker.generate_named_identifier("VAR")
     }
 
     fn simplify_prefix(&self, prefix: &Prefix) -> Option<Prefix> {
@@ -256,10 +255,10 @@ impl Processor {
     }
 }
 
-impl Default for Processor {
-    fn default() -> Self {
+impl Processor {
+    fn new(identifier_tracker: IdentifierTracker) -> Self {
         Self {
-            identifier_tracker: IdentifierTracker::new(),
+            identifier_tracker,
             remove_comments: RemoveCommentProcessor::default(),
             remove_spaces: RemoveWhitespacesProcessor::default(),
         }
@@ -298,15 +297,19 @@ pub const REMOVE_COMPOUND_ASSIGNMENT_RULE_NAME: &str = "remove_compound_assignme
 pub struct RemoveCompoundAssignment {}
 
 impl RemoveCompoundAssignment {
-    pub(crate) fn replace_compound_assignment(&self, statement: &mut Statement) {
-        let mut processor = Processor::default();
+    pub(crate) fn replace_compound_assignment(
+        &self,
+        statement: &mut Statement,
+        identifier_tracker: IdentifierTracker,
+    ) {
+        let mut processor = Processor::new(identifier_tracker);
         ScopeVisitor::visit_statement(statement, &mut processor);
     }
 }
 
 impl FlawlessRule for RemoveCompoundAssignment {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
-        let mut processor = Processor::default();
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor = Processor::new(context.identifier_tracker());
         ScopeVisitor::visit_block(block, &mut processor);
     }
 }
@@ -325,6 +328,10 @@ impl RuleConfiguration for RemoveCompoundAssignment {
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
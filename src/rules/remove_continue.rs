@@ -6,40 +6,59 @@ use crate::nodes::{
     LocalAssignStatement, NumericForStatement, RepeatStatement, UnaryExpression, UnaryOperator,
     WhileStatement,
 };
-use crate::process::{DefaultPostVisitor, NodePostProcessor, NodePostVisitor, NodeProcessor};
+use crate::process::{
+    DefaultPostVisitor, IdentifierTracker, NodePostProcessor, NodePostVisitor, NodeProcessor,
+};
 use crate::rules::{Context, RuleConfiguration, RuleConfigurationError, RuleProperties};
 
 use super::{verify_no_rule_properties, FlawlessRule};
 
-#[derive(Default)]
 struct Processor {
     loop_stack: Vec<Option<LoopData>>,
     loop_identifier_count: u16,
+    name_generator: IdentifierTracker,
 }
 
 struct LoopData {
     has_continue_statement: bool,
-    loop_break_id: u16,
+    loop_break_identifier: String,
 }
 
 impl LoopData {
-    fn new(loop_break_id: u16) -> Self {
+    fn new(loop_break_identifier: String) -> Self {
         Self {
             has_continue_statement: false,
-            loop_break_id,
+            loop_break_identifier,
         }
     }
 
     fn get_identifier(&self) -> Identifier {
-        Identifier::new(format!("__DARKLUA_CONTINUE_{}", self.loop_break_id))
+        Identifier::new(&self.loop_break_identifier)
     }
 }
 
 impl Processor {
+    fn new(name_generator: IdentifierTracker) -> Self {
+        Self {
+            loop_stack: Vec::new(),
+            loop_identifier_count: 0,
+            name_generator,
+        }
+    }
+
     fn push_loop(&mut self) {
-        self.loop_identifier_count += 1;
+        let loop_break_identifier = loop {
+            self.loop_identifier_count += 1;
+            let name = self
+                .name_generator
+                .generated_name(&format!("CONTINUE_{}", self.loop_identifier_count));
+
+            if !self.name_generator.is_identifier_reserved(&name) {
+                break name;
+            }
+        };
         self.loop_stack
-            .push(Some(LoopData::new(self.loop_identifier_count)));
+            .push(Some(LoopData::new(loop_break_identifier)));
     }
 
     fn push_no_loop(&mut self) {
@@ -169,8 +188,8 @@ pub const REMOVE_CONTINUE_RULE_NAME: &str = "remove_continue";
 pub struct RemoveContinue {}
 
 impl FlawlessRule for RemoveContinue {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
-        let mut processor = Processor::default();
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut processor = Processor::new(context.identifier_tracker());
         DefaultPostVisitor::visit_block(block, &mut processor);
     }
 }
@@ -189,6 +208,10 @@ impl RuleConfiguration for RemoveContinue {
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
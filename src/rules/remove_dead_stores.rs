@@ -0,0 +1,563 @@
+use std::collections::HashMap;
+use std::ops;
+
+use crate::nodes::*;
+use crate::process::processors::FindUsage;
+use crate::process::{
+    DefaultVisitor, Evaluator, IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor,
+    ENVIRONMENT_IDENTIFIER,
+};
+use crate::rules::{
+    dynamic_environment_note, skip_dynamic_environment, CompatibilityNote, Context, FlawlessRule,
+    RuleConfiguration, RuleConfigurationError, RuleProperties,
+};
+use crate::utils::expressions_as_statement;
+
+use super::verify_no_rule_properties;
+
+/// A processor to find if a function defined in a part of a block can access a given
+/// variable. Verbatim statements are also considered to capture it, since their content
+/// is unknown.
+struct FindCapture<'a> {
+    variable: &'a str,
+    capture_found: bool,
+    identifier_tracker: IdentifierTracker,
+}
+
+impl ops::Deref for FindCapture<'_> {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for FindCapture<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl<'a> FindCapture<'a> {
+    fn new(variable: &'a str) -> Self {
+        Self {
+            variable,
+            capture_found: false,
+            identifier_tracker: Default::default(),
+        }
+    }
+
+    fn verify_function<'b>(
+        &mut self,
+        block: &mut Block,
+        mut parameters: impl Iterator<Item = &'b TypedIdentifier>,
+    ) {
+        if self.capture_found
+            || self.is_identifier_used(self.variable)
+            || parameters.any(|parameter| parameter.get_name() == self.variable)
+        {
+            return;
+        }
+
+        let mut find_usage = FindUsage::new(self.variable);
+        ScopeVisitor::visit_block(block, &mut find_usage);
+        self.capture_found = find_usage.has_found_usage();
+    }
+}
+
+impl NodeProcessor for FindCapture<'_> {
+    fn process_statement(&mut self, statement: &mut Statement) {
+        if matches!(statement, Statement::Verbatim(_)) {
+            self.capture_found = true;
+        }
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        let parameters: Vec<_> = function.iter_parameters().cloned().collect();
+        self.verify_function(function.mutate_block(), parameters.iter());
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        // the body of the function refers to the function itself
+        if function.get_name() == self.variable {
+            return;
+        }
+        let parameters: Vec<_> = function.iter_parameters().cloned().collect();
+        self.verify_function(function.mutate_block(), parameters.iter());
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        if function.get_name().has_method() && self.variable == "self" {
+            return;
+        }
+        let parameters: Vec<_> = function.iter_parameters().cloned().collect();
+        self.verify_function(function.mutate_block(), parameters.iter());
+    }
+}
+
+fn is_identifier(variable: &Variable, name: &str) -> bool {
+    matches!(variable, Variable::Identifier(identifier) if identifier.get_name() == name)
+}
+
+fn reads_variable(statement: &mut Statement, name: &str) -> bool {
+    let mut find_usage = FindUsage::new(name);
+    ScopeVisitor::visit_statement(statement, &mut find_usage);
+    find_usage.has_found_usage()
+}
+
+/// Returns true when the statement replaces the value of the variable without reading it,
+/// either by assigning it or by declaring a new local with the same name.
+fn overwrites_variable(statement: &mut Statement, name: &str) -> bool {
+    match statement {
+        Statement::Assign(assign) => {
+            if !assign
+                .iter_variables()
+                .any(|variable| is_identifier(variable, name))
+            {
+                return false;
+            }
+
+            let mut find_usage = FindUsage::new(name);
+            for variable in assign.iter_mut_variables() {
+                if !is_identifier(variable, name) {
+                    ScopeVisitor::visit_variable(variable, &mut find_usage);
+                }
+            }
+            for value in assign.iter_mut_values() {
+                ScopeVisitor::visit_expression(value, &mut find_usage);
+            }
+            !find_usage.has_found_usage()
+        }
+        Statement::LocalAssign(assign) => {
+            assign
+                .iter_variables()
+                .any(|variable| variable.get_name() == name)
+                && !reads_variable(statement, name)
+        }
+        Statement::LocalFunction(function) => function.get_name() == name,
+        _ => false,
+    }
+}
+
+fn declares_variable(statement: &Statement, name: &str) -> bool {
+    match statement {
+        Statement::LocalAssign(assign) => assign
+            .iter_variables()
+            .any(|variable| variable.get_name() == name),
+        Statement::LocalFunction(function) => function.get_name() == name,
+        _ => false,
+    }
+}
+
+/// Returns true when the value stored by the statement at the given index is replaced
+/// before being read. The scan only follows the statements of the block declaring the
+/// variable: a read anywhere inside a nested block (including any branch of an if
+/// statement or the body of a loop) keeps the value.
+fn is_dead_store(block: &mut Block, name: &str, index: usize) -> bool {
+    for statement in block.iter_mut_statements().skip(index + 1) {
+        if overwrites_variable(statement, name) {
+            return true;
+        }
+        if reads_variable(statement, name) {
+            return false;
+        }
+    }
+
+    if let Some(last_statement) = block.mutate_last_statement() {
+        let mut find_usage = FindUsage::new(name);
+        ScopeVisitor::visit_last_statement(last_statement, &mut find_usage);
+        if find_usage.has_found_usage() {
+            return false;
+        }
+    }
+
+    // the block ends and the variable goes out of scope
+    true
+}
+
+#[derive(Default)]
+struct RemoveDeadStoresProcessor {
+    evaluator: Evaluator,
+    mutated: bool,
+}
+
+impl RemoveDeadStoresProcessor {
+    fn has_mutated(&self) -> bool {
+        self.mutated
+    }
+
+    /// Finds the stores to the locals declared directly in the block that are never
+    /// read. The result maps statement indexes to the indexes of their dead variables.
+    fn find_dead_stores(
+        &self,
+        block: &mut Block,
+        mut extra: Option<&mut Expression>,
+    ) -> HashMap<usize, Vec<usize>> {
+        let declarations: Vec<_> = block
+            .iter_statements()
+            .enumerate()
+            .filter_map(|(index, statement)| match statement {
+                Statement::LocalAssign(assign) => Some((
+                    index,
+                    assign
+                        .iter_variables()
+                        .map(|variable| variable.get_name().to_owned())
+                        .collect::<Vec<_>>(),
+                )),
+                _ => None,
+            })
+            .collect();
+
+        let mut dead_stores: HashMap<usize, Vec<usize>> = HashMap::new();
+
+        for (declaration_index, names) in declarations {
+            for (slot, name) in names.iter().enumerate() {
+                if name == ENVIRONMENT_IDENTIFIER
+                    || names.iter().filter(|other| *other == name).count() > 1
+                {
+                    continue;
+                }
+
+                if let Some(expression) = extra.as_deref_mut() {
+                    let mut find_usage = FindUsage::new(name);
+                    ScopeVisitor::visit_expression(expression, &mut find_usage);
+                    if find_usage.has_found_usage() {
+                        continue;
+                    }
+                }
+
+                let scope_end = block
+                    .iter_statements()
+                    .enumerate()
+                    .skip(declaration_index + 1)
+                    .find(|(_, statement)| declares_variable(statement, name))
+                    .map(|(index, _)| index);
+
+                if is_captured(block, name, declaration_index, scope_end) {
+                    continue;
+                }
+
+                let mut stores = vec![(declaration_index, slot)];
+
+                for (index, statement) in block
+                    .iter_statements()
+                    .enumerate()
+                    .take(scope_end.unwrap_or(usize::MAX))
+                    .skip(declaration_index + 1)
+                {
+                    if let Statement::Assign(assign) = statement {
+                        let mut slots = assign
+                            .iter_variables()
+                            .enumerate()
+                            .filter(|(_, variable)| is_identifier(variable, name))
+                            .map(|(slot, _)| slot);
+
+                        // the order in which a variable assigned twice receives its
+                        // values is not specified
+                        if let (Some(slot), None) = (slots.next(), slots.next()) {
+                            stores.push((index, slot));
+                        }
+                    }
+                }
+
+                for (index, slot) in stores {
+                    if is_dead_store(block, name, index) {
+                        dead_stores.entry(index).or_default().push(slot);
+                    }
+                }
+            }
+        }
+
+        dead_stores
+    }
+
+    fn remove_assign_stores(&self, assign: &AssignStatement, dead: &[bool]) -> Option<Statement> {
+        if dead.iter().all(|is_dead| *is_dead) {
+            let values: Vec<_> = assign
+                .iter_values()
+                .filter(|value| self.evaluator.has_side_effects(value))
+                .cloned()
+                .collect();
+
+            return if values.is_empty() {
+                None
+            } else {
+                Some(expressions_as_statement(values))
+            };
+        }
+
+        let variables_len = assign.variables_len();
+        let values_len = assign.values_len();
+
+        let mut assignments: Vec<_> = assign
+            .iter_variables()
+            .zip(dead.iter().copied())
+            .map(|slot| vec![slot])
+            .zip(assign.iter_values())
+            .collect();
+
+        let mut unassigned: Vec<_> = assign
+            .iter_variables()
+            .zip(dead.iter().copied())
+            .skip(values_len)
+            .collect();
+
+        if let Some((last, value)) = assignments.last_mut() {
+            if self.evaluator.can_return_multiple_values(value) {
+                last.append(&mut unassigned);
+            }
+        }
+
+        let mut variables = Vec::new();
+        let mut values = Vec::new();
+
+        for (mut slots, value) in assignments {
+            if !self.evaluator.has_side_effects(value) {
+                while slots.last().filter(|(_, is_dead)| *is_dead).is_some() {
+                    slots.pop();
+                }
+            }
+
+            if !slots.is_empty() {
+                variables.extend(slots.into_iter().map(|(variable, _)| variable.clone()));
+                values.push(value.clone());
+            }
+        }
+
+        // variables without a value are assigned nil: the value is written explicitly
+        // so that they do not receive the extra values of a call
+        for (variable, _) in unassigned.into_iter().filter(|(_, is_dead)| !is_dead) {
+            variables.push(variable.clone());
+            values.push(Expression::nil());
+        }
+
+        values.extend(assign.iter_values().skip(variables_len).cloned());
+
+        Some(AssignStatement::new(variables, values).into())
+    }
+
+    fn remove_local_assign_stores(
+        &self,
+        assign: &LocalAssignStatement,
+        dead: &[bool],
+    ) -> Vec<Statement> {
+        let variables_len = assign.variables_len();
+        let values_len = assign.values_len();
+
+        if dead.iter().all(|is_dead| *is_dead) {
+            let values: Vec<_> = assign
+                .iter_values()
+                .filter(|value| self.evaluator.has_side_effects(value))
+                .cloned()
+                .collect();
+
+            let mut declaration = assign.clone();
+            while declaration.pop_value().is_some() {}
+
+            if values.is_empty() {
+                return vec![declaration.into()];
+            }
+
+            return match expressions_as_statement(values) {
+                statement @ Statement::Call(_) => vec![statement, declaration.into()],
+                // values that are not calls would be kept in a new local declaration
+                _ => vec![assign.clone().into()],
+            };
+        }
+
+        let mut declaration = assign.clone();
+
+        if values_len <= variables_len {
+            let values: Vec<_> = assign.iter_values().collect();
+            let mut length = values_len;
+
+            while length > 0 {
+                let value = values[length - 1];
+                let receiving_variables = if self.evaluator.can_return_multiple_values(value) {
+                    &dead[length - 1..]
+                } else {
+                    &dead[length - 1..length]
+                };
+
+                if self.evaluator.has_side_effects(value)
+                    || !receiving_variables.iter().all(|is_dead| *is_dead)
+                {
+                    break;
+                }
+
+                // the new last value would give its extra values to the next variables
+                if length > 1
+                    && self
+                        .evaluator
+                        .can_return_multiple_values(values[length - 2])
+                    && !dead[length - 1..].iter().all(|is_dead| *is_dead)
+                {
+                    break;
+                }
+
+                length -= 1;
+            }
+
+            while declaration.values_len() > length {
+                declaration.pop_value();
+            }
+        }
+
+        vec![declaration.into()]
+    }
+}
+
+fn is_captured(
+    block: &mut Block,
+    name: &str,
+    declaration_index: usize,
+    scope_end: Option<usize>,
+) -> bool {
+    let mut find_capture = FindCapture::new(name);
+
+    for statement in block
+        .iter_mut_statements()
+        .take(scope_end.map(|index| index + 1).unwrap_or(usize::MAX))
+        .skip(declaration_index + 1)
+    {
+        ScopeVisitor::visit_statement(statement, &mut find_capture);
+        if find_capture.capture_found {
+            return true;
+        }
+    }
+
+    if scope_end.is_none() {
+        if let Some(last_statement) = block.mutate_last_statement() {
+            ScopeVisitor::visit_last_statement(last_statement, &mut find_capture);
+        }
+    }
+
+    find_capture.capture_found
+}
+
+impl NodeProcessor for RemoveDeadStoresProcessor {
+    fn process_scope(&mut self, block: &mut Block, extra: Option<&mut Expression>) {
+        let dead_stores = self.find_dead_stores(block, extra);
+
+        if dead_stores.is_empty() {
+            return;
+        }
+
+        let statements = block.take_statements();
+        let mut new_statements = Vec::with_capacity(statements.len());
+
+        for (index, statement) in statements.into_iter().enumerate() {
+            let Some(slots) = dead_stores.get(&index) else {
+                new_statements.push(statement);
+                continue;
+            };
+
+            match &statement {
+                Statement::Assign(assign) => {
+                    let dead: Vec<_> = (0..assign.variables_len())
+                        .map(|slot| slots.contains(&slot))
+                        .collect();
+                    let replacement = self.remove_assign_stores(assign, &dead);
+
+                    if replacement.as_ref() != Some(&statement) {
+                        self.mutated = true;
+                    }
+                    new_statements.extend(replacement);
+                }
+                Statement::LocalAssign(assign) => {
+                    let dead: Vec<_> = (0..assign.variables_len())
+                        .map(|slot| slots.contains(&slot))
+                        .collect();
+                    let replacement = self.remove_local_assign_stores(assign, &dead);
+
+                    if replacement.len() != 1 || replacement[0] != statement {
+                        self.mutated = true;
+                    }
+                    new_statements.extend(replacement);
+                }
+                _ => new_statements.push(statement),
+            }
+        }
+
+        block.set_statements(new_statements);
+    }
+}
+
+pub const REMOVE_DEAD_STORES_RULE_NAME: &str = "remove_dead_stores";
+
+/// A rule that removes assignments to local variables when the assigned value is replaced
+/// before being read.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RemoveDeadStores {}
+
+impl FlawlessRule for RemoveDeadStores {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        if skip_dynamic_environment(REMOVE_DEAD_STORES_RULE_NAME, block, context) {
+            return;
+        }
+
+        loop {
+            let mut processor = RemoveDeadStoresProcessor::default();
+            processor.process_scope(block, None);
+            DefaultVisitor::visit_block(block, &mut processor);
+            if !processor.has_mutated() {
+                break;
+            }
+        }
+    }
+
+    fn compatibility_check(&self, block: &Block, context: &Context) -> Vec<CompatibilityNote> {
+        dynamic_environment_note(block, context)
+            .into_iter()
+            .collect()
+    }
+}
+
+impl RuleConfiguration for RemoveDeadStores {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)?;
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        REMOVE_DEAD_STORES_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> RemoveDeadStores {
+        RemoveDeadStores::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_remove_dead_stores", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'remove_dead_stores',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -1,7 +1,8 @@
 use crate::nodes::{Block, Prefix};
 use crate::process::{IdentifierTracker, NodeVisitor, ScopeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, InjectionPriority, RuleConfiguration, RuleConfigurationError,
+    RuleProperties, RulePropertyKind,
 };
 
 use super::remove_call_match::RemoveFunctionCallProcessor;
@@ -16,18 +17,20 @@ pub const REMOVE_DEBUG_PROFILING_RULE_NAME: &str = "remove_debug_profiling";
 #[derive(Debug, PartialEq, Eq)]
 pub struct RemoveDebugProfiling {
     preserve_args_side_effects: bool,
+    assume_no_env_manipulation: bool,
 }
 
 impl Default for RemoveDebugProfiling {
     fn default() -> Self {
         Self {
             preserve_args_side_effects: true,
+            assume_no_env_manipulation: false,
         }
     }
 }
 
 fn should_remove_call(identifiers: &IdentifierTracker, prefix: &Prefix) -> bool {
-    if identifiers.is_identifier_used(DEBUG_LIBRARY_NAME) {
+    if !identifiers.is_global(DEBUG_LIBRARY_NAME) {
         return false;
     }
 
@@ -43,13 +46,16 @@ fn should_remove_call(identifiers: &IdentifierTracker, prefix: &Prefix) -> bool
 }
 
 impl FlawlessRule for RemoveDebugProfiling {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
         let mut processor =
-            RemoveFunctionCallProcessor::new(self.preserve_args_side_effects, should_remove_call);
+            RemoveFunctionCallProcessor::new(self.preserve_args_side_effects, should_remove_call)
+                .with_generated_name_prefix(context.generated_name_prefix())
+                .with_reserved_identifiers(context.reserved_identifiers())
+                .with_assume_no_env_manipulation(self.assume_no_env_manipulation);
         ScopeVisitor::visit_block(block, &mut processor);
 
         if let Some(statement) = processor.extract_reserved_globals() {
-            block.insert_statement(0, statement);
+            context.inject_top_statement(statement, InjectionPriority::Requires);
         }
     }
 }
@@ -61,6 +67,9 @@ impl RuleConfiguration for RemoveDebugProfiling {
                 "preserve_arguments_side_effects" => {
                     self.preserve_args_side_effects = value.expect_bool(&key)?;
                 }
+                "assume_no_env_manipulation" => {
+                    self.assume_no_env_manipulation = value.expect_bool(&key)?;
+                }
                 _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
             }
         }
@@ -72,6 +81,17 @@ impl RuleConfiguration for RemoveDebugProfiling {
         REMOVE_DEBUG_PROFILING_RULE_NAME
     }
 
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &[
+            "assume_no_env_manipulation",
+            "preserve_arguments_side_effects",
+        ]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Boolean
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         let mut properties = RuleProperties::new();
 
@@ -79,8 +99,29 @@ impl RuleConfiguration for RemoveDebugProfiling {
             properties.insert("preserve_arguments_side_effects".to_owned(), false.into());
         }
 
+        if self.assume_no_env_manipulation {
+            properties.insert("assume_no_env_manipulation".to_owned(), true.into());
+        }
+
         properties
     }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if !self.preserve_args_side_effects {
+            properties.push("preserve_arguments_side_effects");
+        }
+        if self.assume_no_env_manipulation {
+            properties.push("assume_no_env_manipulation");
+        }
+
+        properties
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
@@ -105,6 +146,7 @@ mod test {
     fn serialize_rule_without_side_effects() {
         let rule: Box<dyn Rule> = Box::new(RemoveDebugProfiling {
             preserve_args_side_effects: false,
+            assume_no_env_manipulation: false,
         });
 
         assert_json_snapshot!("remove_debug_profiling_without_side_effects", rule);
@@ -6,12 +6,14 @@ use crate::nodes::{
 };
 use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
 use crate::rules::{
-    verify_no_rule_properties, Context, FlawlessRule, RemoveCompoundAssignment, RuleConfiguration,
-    RuleConfigurationError, RuleProperties,
+    verify_no_rule_properties, Context, FlawlessRule, InjectionPriority, RemoveCompoundAssignment,
+    RuleConfiguration, RuleConfigurationError, RuleProperties,
 };
 
 struct RemoveFloorDivisionProcessor {
     math_floor_identifier: String,
+    /// Generates the names of the locals added when converting compound assignments.
+    name_generator: IdentifierTracker,
     define_math_floor: bool,
     identifier_tracker: IdentifierTracker,
 }
@@ -34,9 +36,10 @@ const DEFAULT_MATH_LIBRARY: &str = "math";
 const DEFAULT_MATH_FLOOR_NAME: &str = "floor";
 
 impl RemoveFloorDivisionProcessor {
-    fn new(math_floor_identifier: impl Into<String>) -> Self {
+    fn new(math_floor_identifier: impl Into<String>, name_generator: IdentifierTracker) -> Self {
         Self {
             math_floor_identifier: math_floor_identifier.into(),
+            name_generator,
             define_math_floor: false,
             identifier_tracker: Default::default(),
         }
@@ -64,7 +67,8 @@ impl NodeProcessor for RemoveFloorDivisionProcessor {
             Statement::CompoundAssign(assign_statement)
                 if assign_statement.get_operator() == CompoundOperator::DoubleSlash =>
             {
-                RemoveCompoundAssignment::default().replace_compound_assignment(statement);
+                RemoveCompoundAssignment::default()
+                    .replace_compound_assignment(statement, self.name_generator.clone());
             }
             _ => {}
         }
@@ -90,21 +94,22 @@ pub const REMOVE_FLOOR_DIVISION_RULE_NAME: &str = "remove_floor_division";
 pub struct RemoveFloorDivision {}
 
 impl FlawlessRule for RemoveFloorDivision {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
-        const MATH_FLOOR_IDENTIFIER: &str = "__DARKLUA_MATH_FLOOR";
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let math_floor_identifier = context.generated_name("MATH_FLOOR");
 
-        let mut processor = RemoveFloorDivisionProcessor::new(MATH_FLOOR_IDENTIFIER);
+        let mut processor =
+            RemoveFloorDivisionProcessor::new(&math_floor_identifier, context.identifier_tracker());
         ScopeVisitor::visit_block(block, &mut processor);
 
         if processor.define_math_floor {
-            block.insert_statement(
-                0,
-                LocalAssignStatement::from_variable(MATH_FLOOR_IDENTIFIER).with_value(
+            context.inject_top_statement(
+                LocalAssignStatement::from_variable(math_floor_identifier).with_value(
                     FieldExpression::new(
                         Prefix::from_name(DEFAULT_MATH_LIBRARY),
                         DEFAULT_MATH_FLOOR_NAME,
                     ),
                 ),
+                InjectionPriority::Helpers,
             );
         }
     }
@@ -124,6 +129,10 @@ impl RuleConfiguration for RemoveFloorDivision {
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
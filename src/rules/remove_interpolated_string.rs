@@ -7,7 +7,8 @@ use crate::nodes::{
 };
 use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, InjectionPriority, RuleConfiguration, RuleConfigurationError,
+    RuleProperties, RulePropertyKind,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -164,14 +165,14 @@ pub struct RemoveInterpolatedString {
 }
 
 impl FlawlessRule for RemoveInterpolatedString {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
-        const STRING_FORMAT_IDENTIFIER: &str = "__DARKLUA_STR_FMT";
-        const TOSTRING_IDENTIFIER: &str = "__DARKLUA_TO_STR";
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let string_format_identifier = context.generated_name("STR_FMT");
+        let tostring_identifier = context.generated_name("TO_STR");
 
         let mut processor = RemoveInterpolatedStringProcessor::new(
             self.strategy,
-            STRING_FORMAT_IDENTIFIER,
-            TOSTRING_IDENTIFIER,
+            &string_format_identifier,
+            &tostring_identifier,
         );
         ScopeVisitor::visit_block(block, &mut processor);
 
@@ -180,7 +181,7 @@ impl FlawlessRule for RemoveInterpolatedString {
             let mut values = Vec::new();
 
             if processor.define_string_format {
-                variables.push(TypedIdentifier::new(STRING_FORMAT_IDENTIFIER));
+                variables.push(TypedIdentifier::new(string_format_identifier));
                 values.push(
                     FieldExpression::new(
                         Prefix::from_name(DEFAULT_STRING_LIBRARY),
@@ -191,11 +192,14 @@ impl FlawlessRule for RemoveInterpolatedString {
             }
 
             if processor.define_tostring {
-                variables.push(TypedIdentifier::new(TOSTRING_IDENTIFIER));
+                variables.push(TypedIdentifier::new(tostring_identifier));
                 values.push(Identifier::new(DEFAULT_TOSTRING_IDENTIFIER).into());
             }
 
-            block.insert_statement(0, LocalAssignStatement::new(variables, values));
+            context.inject_top_statement(
+                LocalAssignStatement::new(variables, values),
+                InjectionPriority::Helpers,
+            );
         }
     }
 }
@@ -230,6 +234,14 @@ impl RuleConfiguration for RemoveInterpolatedString {
         REMOVE_INTERPOLATED_STRING_RULE_NAME
     }
 
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["strategy"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Choice(&["string", "tostring"])
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         let mut properties = RuleProperties::new();
 
@@ -242,6 +254,10 @@ impl RuleConfiguration for RemoveInterpolatedString {
 
         properties
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
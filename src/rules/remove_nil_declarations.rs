@@ -1,7 +1,11 @@
-use crate::nodes::{Block, Expression, LocalAssignStatement};
-use crate::process::{DefaultVisitor, Evaluator, NodeProcessor, NodeVisitor};
+use std::mem;
+
+use crate::nodes::{
+    AssignStatement, Block, Expression, LocalAssignStatement, TableEntry, TableExpression,
+};
+use crate::process::{DefaultVisitor, Evaluator, LuaValue, NodeProcessor, NodeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
 };
 
 use super::verify_no_rule_properties;
@@ -11,7 +15,119 @@ struct Processor {
     evaluator: Evaluator,
 }
 
+impl Processor {
+    /// Returns the key of a table entry when it is known. Keys that would error (like `nil`
+    /// or NaN) are not considered known.
+    fn get_known_key(&self, entry: &TableEntry) -> Option<LuaValue> {
+        match entry {
+            TableEntry::Field(field) => {
+                Some(LuaValue::String(field.get_field().get_name().to_owned()))
+            }
+            TableEntry::Index(index) => {
+                let key = index.get_key();
+                if self.evaluator.has_side_effects(key) {
+                    return None;
+                }
+                match self.evaluator.evaluate(key) {
+                    LuaValue::Number(number) if number.is_nan() => None,
+                    value @ (LuaValue::String(_)
+                    | LuaValue::Number(_)
+                    | LuaValue::True
+                    | LuaValue::False) => Some(value),
+                    _ => None,
+                }
+            }
+            TableEntry::Value(_) => None,
+        }
+    }
+
+    fn can_remove_nil_entry(&self, table: &TableExpression, index: usize) -> bool {
+        let entries = table.get_entries();
+
+        let key = match self.get_known_key(&entries[index]) {
+            Some(key) => key,
+            None => return false,
+        };
+
+        let has_array_values = entries
+            .iter()
+            .any(|entry| matches!(entry, TableEntry::Value(_)));
+
+        // numeric keys may overlap with array values
+        if matches!(key, LuaValue::Number(_)) && has_array_values {
+            return false;
+        }
+
+        // an entry assigned to `nil` erases the value of a previous entry with the same key
+        entries
+            .iter()
+            .take(index)
+            .filter(|entry| !matches!(entry, TableEntry::Value(_)))
+            .all(|entry| {
+                self.get_known_key(entry)
+                    .filter(|other_key| other_key != &key)
+                    .is_some()
+            })
+    }
+}
+
 impl NodeProcessor for Processor {
+    fn process_assign_statement(&mut self, assignment: &mut AssignStatement) {
+        let mut removed_nil = false;
+
+        while assignment
+            .last_value()
+            .filter(|value| matches!(value, Expression::Nil(_)))
+            .is_some()
+        {
+            if assignment.pop_value().is_none() {
+                break;
+            }
+            removed_nil = true;
+        }
+
+        if removed_nil && assignment.variables_len() > assignment.values_len() {
+            if let Some(last_value) = assignment.iter_mut_values().last() {
+                if self.evaluator.can_return_multiple_values(last_value) {
+                    let value = mem::replace(last_value, Expression::nil());
+                    *last_value = value.in_parentheses();
+                }
+            }
+        }
+    }
+
+    fn process_table_expression(&mut self, table: &mut TableExpression) {
+        // removing every entry after a call or `...` value would make it the last entry
+        // of the table, which expands it to all its values
+        let keep_entries_after = table
+            .iter_entries()
+            .enumerate()
+            .filter_map(|(index, entry)| match entry {
+                TableEntry::Value(value) => Some((index, value)),
+                _ => None,
+            })
+            .last()
+            .filter(|(_, value)| self.evaluator.can_return_multiple_values(value))
+            .map(|(index, _)| index);
+
+        let remove_entries_at: Vec<_> = table
+            .iter_entries()
+            .enumerate()
+            .filter(|(index, _)| keep_entries_after.is_none_or(|keep_after| *index < keep_after))
+            .filter(|(_, entry)| match entry {
+                TableEntry::Field(field) => matches!(field.get_value(), Expression::Nil(_)),
+                TableEntry::Index(index) => matches!(index.get_value(), Expression::Nil(_)),
+                TableEntry::Value(_) => false,
+            })
+            .map(|(index, _)| index)
+            .filter(|index| self.can_remove_nil_entry(table, *index))
+            .collect();
+
+        for index in remove_entries_at.into_iter().rev() {
+            table.remove_entry(index);
+        }
+    }
+
     fn process_local_assign_statement(&mut self, assignment: &mut LocalAssignStatement) {
         {
             let mut pop_extra_value_at = Vec::new();
@@ -82,7 +198,8 @@ impl NodeProcessor for Processor {
 
 pub const REMOVE_NIL_DECLARATION_RULE_NAME: &str = "remove_nil_declaration";
 
-/// A rule that removes trailing `nil` in local assignments.
+/// A rule that removes `nil` values in local assignments, trailing `nil` values in
+/// assignments and fields assigned to `nil` in table constructors.
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct RemoveNilDeclaration {}
 
@@ -104,6 +221,13 @@ impl RuleConfiguration for RemoveNilDeclaration {
         REMOVE_NIL_DECLARATION_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "local value = nil\n\
+                local a, b = 1, nil",
+        )]
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
@@ -1,7 +1,7 @@
 use crate::nodes::*;
 use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
 };
 
 use super::verify_no_rule_properties;
@@ -228,6 +228,10 @@ impl NodeProcessor for RemoveWhitespacesProcessor {
     fn process_variadic_type_pack(&mut self, variadic_type_pack: &mut VariadicTypePack) {
         variadic_type_pack.clear_whitespaces();
     }
+
+    fn process_generic_parameters(&mut self, generic_parameters: &mut GenericParameters) {
+        generic_parameters.clear_whitespaces();
+    }
 }
 
 pub const REMOVE_SPACES_RULE_NAME: &str = "remove_spaces";
@@ -253,9 +257,24 @@ impl RuleConfiguration for RemoveSpaces {
         REMOVE_SPACES_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "local   value  =  1 +  2\n\
+                print( value )",
+        )]
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn is_formatting_rule(&self) -> bool {
+        true
+    }
+
+    fn is_statement_local(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
@@ -312,4 +331,27 @@ mod test {
 
         insta::assert_snapshot!("remove_spaces_in_code", code_output);
     }
+
+    #[test]
+    fn remove_spaces_keeps_number_separators() {
+        let code = "return 1_000 + 0b1010_0101 + 0x_FF + 1_0.5e1_0";
+
+        let parser = Parser::default().preserve_tokens();
+
+        let mut block = parser.parse(code).expect("unable to parse code");
+
+        RemoveSpaces::default().flawless_process(
+            &mut block,
+            &ContextBuilder::new(".", &Resources::from_memory(), code).build(),
+        );
+
+        let mut generator = TokenBasedLuaGenerator::new(code);
+
+        generator.write_block(&block);
+
+        pretty_assertions::assert_eq!(
+            generator.into_string(),
+            "return 1_000+0b1010_0101+0x_FF+1_0.5e1_0"
+        );
+    }
 }
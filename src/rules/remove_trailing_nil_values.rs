@@ -0,0 +1,98 @@
+use std::mem;
+
+use crate::nodes::{Block, Expression, LocalAssignStatement};
+use crate::process::{DefaultVisitor, Evaluator, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+};
+
+use super::verify_no_rule_properties;
+
+#[derive(Default)]
+struct Processor {
+    evaluator: Evaluator,
+}
+
+impl NodeProcessor for Processor {
+    fn process_local_assign_statement(&mut self, assignment: &mut LocalAssignStatement) {
+        let mut removed_nil = false;
+
+        while matches!(assignment.last_value(), Some(Expression::Nil(_))) {
+            assignment.pop_value();
+            removed_nil = true;
+        }
+
+        // the last value must not fill the variables that were assigned to `nil`
+        if removed_nil && assignment.variables_len() > assignment.values_len() {
+            if let Some(last_value) = assignment.iter_mut_values().last() {
+                if self.evaluator.can_return_multiple_values(last_value) {
+                    let value = mem::replace(last_value, Expression::nil());
+                    *last_value = value.in_parentheses();
+                }
+            }
+        }
+    }
+}
+
+pub const REMOVE_TRAILING_NIL_VALUES_RULE_NAME: &str = "remove_trailing_nil_values";
+
+/// A rule that removes the trailing `nil` values of local assignments.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RemoveTrailingNilValues {}
+
+impl FlawlessRule for RemoveTrailingNilValues {
+    fn flawless_process(&self, block: &mut Block, _: &Context) {
+        let mut processor = Processor::default();
+        DefaultVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for RemoveTrailingNilValues {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)?;
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        REMOVE_TRAILING_NIL_VALUES_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> RemoveTrailingNilValues {
+        RemoveTrailingNilValues::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_remove_trailing_nil_values", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'remove_trailing_nil_values',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -2,7 +2,8 @@ use crate::nodes::*;
 use crate::process::processors::FindUsage;
 use crate::process::{DefaultVisitor, Evaluator, NodeProcessor, NodeVisitor, ScopeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    dynamic_environment_note, skip_dynamic_environment, CompatibilityNote, Context, FlawlessRule,
+    RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
 };
 use crate::utils::expressions_as_statement;
 
@@ -205,7 +206,11 @@ pub const REMOVE_UNUSED_VARIABLE_RULE_NAME: &str = "remove_unused_variable";
 pub struct RemoveUnusedVariable {}
 
 impl FlawlessRule for RemoveUnusedVariable {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        if skip_dynamic_environment(REMOVE_UNUSED_VARIABLE_RULE_NAME, block, context) {
+            return;
+        }
+
         loop {
             let mut processor = RemoveUnusedVariableProcessor::default();
             processor.process_scope(block, None);
@@ -215,6 +220,12 @@ impl FlawlessRule for RemoveUnusedVariable {
             }
         }
     }
+
+    fn compatibility_check(&self, block: &Block, context: &Context) -> Vec<CompatibilityNote> {
+        dynamic_environment_note(block, context)
+            .into_iter()
+            .collect()
+    }
 }
 
 impl RuleConfiguration for RemoveUnusedVariable {
@@ -227,9 +238,21 @@ impl RuleConfiguration for RemoveUnusedVariable {
         REMOVE_UNUSED_VARIABLE_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "local unused = 1\n\
+                local used = 2\n\
+                print(used)",
+        )]
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
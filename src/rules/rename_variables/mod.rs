@@ -8,8 +8,9 @@ use crate::nodes::Block;
 use crate::process::utils::is_valid_identifier;
 use crate::process::{DefaultVisitor, NodeVisitor, ScopeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
-    RulePropertyValue,
+    dynamic_environment_note, skip_dynamic_environment, CompatibilityNote, Context, FlawlessRule,
+    ModuleExports, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
+    RulePropertyKind, RulePropertyValue,
 };
 
 use std::collections::HashSet;
@@ -22,6 +23,8 @@ pub const RENAME_VARIABLES_RULE_NAME: &str = "rename_variables";
 pub struct RenameVariables {
     globals: Vec<String>,
     include_functions: bool,
+    keep_exported: bool,
+    keep_exported_fields_matching: Vec<String>,
 }
 
 impl RenameVariables {
@@ -29,6 +32,8 @@ impl RenameVariables {
         Self {
             globals: Vec::from_iter(iter),
             include_functions: false,
+            keep_exported: false,
+            keep_exported_fields_matching: Vec::new(),
         }
     }
 
@@ -37,16 +42,40 @@ impl RenameVariables {
         self
     }
 
+    /// Keeps the names of the top-level locals exported by the module (returned directly
+    /// or stored in the returned table).
+    pub fn with_exported_names(mut self) -> Self {
+        self.keep_exported = true;
+        self
+    }
+
+    /// Only keeps the exported locals whose field names start with one of the prefixes.
+    pub fn with_exported_fields_matching<I: IntoIterator<Item = String>>(
+        mut self,
+        prefixes: I,
+    ) -> Self {
+        self.keep_exported_fields_matching = prefixes.into_iter().collect();
+        self
+    }
+
+    fn is_kept_export(&self, name: &str) -> bool {
+        self.keep_exported_fields_matching.is_empty()
+            || self
+                .keep_exported_fields_matching
+                .iter()
+                .any(|prefix| name.starts_with(prefix.as_str()))
+    }
+
     fn set_globals(&mut self, list: Vec<String>) -> Result<(), RuleConfigurationError> {
         for value in list {
             match value.as_str() {
                 "$default" => self
                     .globals
-                    .extend(globals::DEFAULT.iter().map(ToString::to_string)),
+                    .extend(globals::default_globals().iter().cloned()),
                 "$roblox" => self
                     .globals
-                    .extend(globals::ROBLOX.iter().map(ToString::to_string)),
-                identifier if !is_valid_identifier(identifier) => {
+                    .extend(globals::roblox_globals().iter().cloned()),
+                identifier if !is_valid_identifier(identifier, None) => {
                     return Err(RuleConfigurationError::StringExpected("".to_owned()))
                 }
                 _ => self.globals.push(value),
@@ -61,22 +90,22 @@ impl RenameVariables {
 
         let mut result = Vec::new();
 
-        if globals::DEFAULT
+        if globals::default_globals()
             .iter()
-            .all(|identifier| globals_set.contains(*identifier))
+            .all(|identifier| globals_set.contains(identifier))
         {
-            globals::DEFAULT.iter().for_each(|identifier| {
-                globals_set.remove(*identifier);
+            globals::default_globals().iter().for_each(|identifier| {
+                globals_set.remove(identifier);
             });
             result.push("$default".to_owned());
         }
 
-        if globals::ROBLOX
+        if globals::roblox_globals()
             .iter()
-            .all(|identifier| globals_set.contains(*identifier))
+            .all(|identifier| globals_set.contains(identifier))
         {
-            globals::ROBLOX.iter().for_each(|identifier| {
-                globals_set.remove(*identifier);
+            globals::roblox_globals().iter().for_each(|identifier| {
+                globals_set.remove(identifier);
             });
             result.push("$roblox".to_owned());
         }
@@ -89,12 +118,22 @@ impl RenameVariables {
 
 impl Default for RenameVariables {
     fn default() -> Self {
-        Self::new(globals::DEFAULT.iter().map(|string| (*string).to_owned()))
+        Self::new(globals::default_globals().iter().cloned())
     }
 }
 
 impl FlawlessRule for RenameVariables {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        if skip_dynamic_environment(RENAME_VARIABLES_RULE_NAME, block, context) {
+            return;
+        }
+
+        let environment_globals = context
+            .environment()
+            .iter_global_names()
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>();
+
         let avoid_identifiers = if self.include_functions {
             Vec::new()
         } else {
@@ -103,12 +142,30 @@ impl FlawlessRule for RenameVariables {
             collect_functions.into()
         };
 
+        let exports = if self.keep_exported {
+            ModuleExports::find(block, |name| self.is_kept_export(name))
+        } else {
+            ModuleExports::default()
+        };
+
         let mut processor = RenameProcessor::new(
-            self.globals.clone().into_iter().chain(avoid_identifiers),
+            self.globals
+                .clone()
+                .into_iter()
+                .chain(environment_globals)
+                .chain(context.reserved_identifiers())
+                .chain(avoid_identifiers),
             self.include_functions,
-        );
+        )
+        .keep_top_level_locals(exports.iter().cloned());
         ScopeVisitor::visit_block(block, &mut processor);
     }
+
+    fn compatibility_check(&self, block: &Block, context: &Context) -> Vec<CompatibilityNote> {
+        dynamic_environment_note(block, context)
+            .into_iter()
+            .collect()
+    }
 }
 
 impl RuleConfiguration for RenameVariables {
@@ -121,10 +178,23 @@ impl RuleConfiguration for RenameVariables {
                 "include_functions" => {
                     self.include_functions = value.expect_bool(&key)?;
                 }
+                "keep_exported" => {
+                    self.keep_exported = value.expect_bool(&key)?;
+                }
+                "keep_exported_fields_matching" => {
+                    self.keep_exported_fields_matching = value.expect_string_list(&key)?;
+                }
                 _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
             }
         }
 
+        if !self.keep_exported && !self.keep_exported_fields_matching.is_empty() {
+            return Err(RuleConfigurationError::UnexpectedValue {
+                property: "keep_exported_fields_matching".to_owned(),
+                message: "`keep_exported` must be enabled to keep exported fields".to_owned(),
+            });
+        }
+
         Ok(())
     }
 
@@ -132,6 +202,53 @@ impl RuleConfiguration for RenameVariables {
         RENAME_VARIABLES_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![
+            RuleExample::new(
+                "local counter = 0\n\
+                local function increment(amount)\n\
+                \tcounter = counter + amount\n\
+                end\n\
+                increment(1)",
+            ),
+            RuleExample::new(
+                "local function increment(amount)\n\
+                \treturn amount + 1\n\
+                end\n\
+                return increment(1)",
+            )
+            .with_property("include_functions", true),
+            RuleExample::new(
+                "local function format(value)\n\
+                \treturn tostring(value)\n\
+                end\n\
+                local function parse(text)\n\
+                \tlocal value = tonumber(text)\n\
+                \treturn value\n\
+                end\n\
+                return { format = format, parse = parse }",
+            )
+            .with_property("include_functions", true)
+            .with_property("keep_exported", true),
+        ]
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &[
+            "globals",
+            "include_functions",
+            "keep_exported",
+            "keep_exported_fields_matching",
+        ]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "include_functions" | "keep_exported" => RulePropertyKind::Boolean,
+            _ => RulePropertyKind::StringList,
+        }
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         let mut properties = RuleProperties::new();
 
@@ -150,8 +267,26 @@ impl RuleConfiguration for RenameVariables {
             );
         }
 
+        if self.keep_exported {
+            properties.insert(
+                "keep_exported".to_owned(),
+                RulePropertyValue::Boolean(self.keep_exported),
+            );
+        }
+
+        if !self.keep_exported_fields_matching.is_empty() {
+            properties.insert(
+                "keep_exported_fields_matching".to_owned(),
+                RulePropertyValue::StringList(self.keep_exported_fields_matching.clone()),
+            );
+        }
+
         properties
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
@@ -182,7 +317,7 @@ mod test {
     #[test]
     fn serialize_roblox_globals_rule() {
         let rule = Box::new(RenameVariables::new(
-            globals::ROBLOX.iter().map(ToString::to_string),
+            globals::roblox_globals().iter().cloned(),
         ));
 
         assert_json_snapshot!("roblox_globals_rename_variables", rule as Box<dyn Rule>);
@@ -191,8 +326,7 @@ mod test {
     #[test]
     fn serialize_with_function_names() {
         let rule = Box::new(
-            RenameVariables::new(globals::DEFAULT.iter().map(ToString::to_string))
-                .with_function_names(),
+            RenameVariables::new(globals::default_globals().iter().cloned()).with_function_names(),
         );
 
         assert_json_snapshot!(
@@ -201,10 +335,38 @@ mod test {
         );
     }
 
+    #[test]
+    fn serialize_with_exported_names() {
+        let rule = Box::new(
+            RenameVariables::default()
+                .with_exported_names()
+                .with_exported_fields_matching(vec!["public_".to_owned()]),
+        );
+
+        assert_json_snapshot!(
+            "rename_variables_with_exported_names",
+            rule as Box<dyn Rule>
+        );
+    }
+
+    #[test]
+    fn configure_exported_fields_without_keep_exported_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'rename_variables',
+            keep_exported_fields_matching: ['public_'],
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'keep_exported_fields_matching': `keep_exported` must be enabled to keep exported fields"
+        );
+    }
+
     #[test]
     fn serialize_skip_functions() {
         let rule = Box::new(RenameVariables::new(
-            globals::ROBLOX.iter().map(ToString::to_string),
+            globals::roblox_globals().iter().cloned(),
         ));
 
         assert_json_snapshot!("roblox_globals_rename_variables", rule as Box<dyn Rule>);
@@ -1,25 +1,25 @@
-use crate::nodes::{Expression, Identifier, LocalFunctionStatement, TypeField};
-use crate::process::utils::{identifier_permutator, CharPermutator};
-use crate::process::{utils::KEYWORDS, NodeProcessor, Scope};
+use crate::nodes::{Expression, Identifier, LocalFunctionStatement, Symbol, TypeField};
+use crate::process::utils::{identifier_permutator, is_valid_generated_identifier, CharPermutator};
+use crate::process::{NodeProcessor, Scope, ENVIRONMENT_IDENTIFIER};
 
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
-use std::mem;
 
 #[derive(Debug)]
 pub struct RenameProcessor {
-    real_to_obfuscated: Vec<HashMap<String, (String, bool)>>,
+    real_to_obfuscated: Vec<HashMap<Symbol, (Symbol, bool)>>,
     permutator: CharPermutator,
     avoid_identifier: HashSet<String>,
-    reuse_identifiers: Vec<String>,
+    reuse_identifiers: Vec<Symbol>,
     include_functions: bool,
+    keep_top_level_locals: HashSet<String>,
 }
 
 impl RenameProcessor {
     pub fn new<I: IntoIterator<Item = String>>(iter: I, include_functions: bool) -> Self {
         let mut avoid_identifier = HashSet::from_iter(iter);
-        avoid_identifier.extend(KEYWORDS.iter().map(|s| (*s).to_owned()));
+        avoid_identifier.insert(ENVIRONMENT_IDENTIFIER.to_owned());
 
         Self {
             real_to_obfuscated: Vec::new(),
@@ -27,10 +27,21 @@ impl RenameProcessor {
             avoid_identifier,
             reuse_identifiers: Vec::new(),
             include_functions,
+            keep_top_level_locals: HashSet::new(),
         }
     }
 
-    pub fn add(&mut self, real: String, obfuscated: String, reuse: bool) {
+    /// Keeps the names of the top-level locals with the given names. The names are also
+    /// never generated, so that other locals cannot shadow them.
+    pub fn keep_top_level_locals<I: IntoIterator<Item = String>>(mut self, iter: I) -> Self {
+        for name in iter {
+            self.avoid_identifier.insert(name.clone());
+            self.keep_top_level_locals.insert(name);
+        }
+        self
+    }
+
+    pub fn add(&mut self, real: Symbol, obfuscated: Symbol, reuse: bool) {
         if let Some(dictionary) = self.real_to_obfuscated.last_mut() {
             dictionary.insert(real, (obfuscated, reuse));
         } else {
@@ -40,21 +51,21 @@ impl RenameProcessor {
         }
     }
 
-    pub fn get_obfuscated_name(&self, real: &str) -> Option<&String> {
+    pub fn get_obfuscated_name(&self, real: &str) -> Option<&Symbol> {
         self.real_to_obfuscated
             .iter()
             .rev()
             .find_map(|dictionary| dictionary.get(real).map(|(name, _)| name))
     }
 
-    pub fn generate_identifier(&mut self) -> String {
+    pub fn generate_identifier(&mut self) -> Symbol {
         if let Some(identifier) = self.reuse_identifiers.pop() {
             identifier
         } else {
             let generated = self.permutator.next().unwrap();
 
             if self.filter_identifier(&generated) {
-                generated
+                generated.into()
             } else {
                 self.generate_identifier()
             }
@@ -62,15 +73,28 @@ impl RenameProcessor {
     }
 
     fn filter_identifier(&self, identifier: &str) -> bool {
-        !self.avoid_identifier.contains(identifier)
-            && !identifier.chars().next().unwrap().is_ascii_digit()
+        !self.avoid_identifier.contains(identifier) && is_valid_generated_identifier(identifier)
+    }
+
+    fn keep_identifier(&mut self, identifier: &Symbol) {
+        self.add(identifier.clone(), identifier.clone(), false);
+    }
+
+    fn is_kept_top_level_local(&self, identifier: &str) -> bool {
+        self.real_to_obfuscated.len() <= 1 && self.keep_top_level_locals.contains(identifier)
     }
 
-    fn replace_identifier(&mut self, identifier: &mut String) {
-        let original = mem::take(identifier);
+    fn replace_identifier(&mut self, identifier: &mut Identifier) {
+        // renaming `_ENV` would change where the free identifiers of its scope are read from
+        if identifier.get_name() == ENVIRONMENT_IDENTIFIER {
+            self.keep_identifier(&identifier.get_symbol().clone());
+            return;
+        }
+
+        let original = identifier.get_symbol().clone();
         let obfuscated_name = self.generate_identifier();
 
-        identifier.push_str(&obfuscated_name);
+        identifier.set_name(obfuscated_name.clone());
 
         self.add(original, obfuscated_name, true);
     }
@@ -132,28 +156,34 @@ impl Scope for RenameProcessor {
                     .filter_map(|(name, reuse)| reuse.then_some(name)),
             );
             self.reuse_identifiers
-                .sort_by(|a, b| sort_identifiers(a, b).reverse());
+                .sort_by(|a, b| sort_identifiers(a, b).then_with(|| a.cmp(b)).reverse());
         }
     }
 
-    fn insert(&mut self, identifier: &mut String) {
+    fn insert(&mut self, identifier: &mut Identifier) {
         self.replace_identifier(identifier);
     }
 
     fn insert_self(&mut self) {
-        self.add("self".to_owned(), "self".to_owned(), false);
+        let name = Symbol::from("self");
+        self.add(name.clone(), name, false);
     }
 
-    fn insert_local(&mut self, identifier: &mut String, _value: Option<&mut Expression>) {
-        self.replace_identifier(identifier);
+    fn insert_local(&mut self, identifier: &mut Identifier, _value: Option<&mut Expression>) {
+        if self.is_kept_top_level_local(identifier.get_name()) {
+            self.keep_identifier(&identifier.get_symbol().clone());
+        } else {
+            self.replace_identifier(identifier);
+        }
     }
 
     fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
-        if self.include_functions {
-            self.replace_identifier(function.mutate_identifier().mutate_name());
+        let name = function.get_identifier().get_name();
+        if self.include_functions && !self.is_kept_top_level_local(name) {
+            self.replace_identifier(function.mutate_identifier());
         } else {
-            let name = function.mutate_identifier().get_name();
-            self.add(name.clone(), name.to_owned(), false);
+            let name = function.get_identifier().get_symbol().clone();
+            self.keep_identifier(&name);
         }
     }
 }
@@ -161,7 +191,7 @@ impl Scope for RenameProcessor {
 impl NodeProcessor for RenameProcessor {
     fn process_variable_expression(&mut self, variable: &mut Identifier) {
         if let Some(obfuscated_name) = self.get_obfuscated_name(variable.get_name()) {
-            variable.set_name(obfuscated_name);
+            variable.set_name(obfuscated_name.clone());
         }
     }
 
@@ -169,7 +199,9 @@ impl NodeProcessor for RenameProcessor {
         if let Some(obfuscated_name) =
             self.get_obfuscated_name(type_field.get_namespace().get_name())
         {
-            type_field.mutate_namespace().set_name(obfuscated_name);
+            type_field
+                .mutate_namespace()
+                .set_name(obfuscated_name.clone());
         }
     }
 }
@@ -190,8 +222,8 @@ mod test {
     #[test]
     fn should_get_mapped_name_from_inserted_names() {
         let mut scope = new_scope();
-        let real = "a".to_owned();
-        let obfuscated = "b".to_owned();
+        let real = Symbol::from("a");
+        let obfuscated = Symbol::from("b");
 
         scope.add(real.clone(), obfuscated.clone(), true);
 
@@ -201,8 +233,8 @@ mod test {
     #[test]
     fn mapped_name_should_not_exist_after_pop() {
         let mut scope = new_scope();
-        let real = "a".to_owned();
-        let obfuscated = "def".to_owned();
+        let real = Symbol::from("a");
+        let obfuscated = Symbol::from("def");
 
         scope.push();
         scope.add(real.clone(), obfuscated, true);
@@ -214,9 +246,9 @@ mod test {
     #[test]
     fn remapped_name_should_exist_after_pop() {
         let mut scope = new_scope();
-        let real = "a".to_owned();
-        let obfuscated = "b".to_owned();
-        let other_obfuscated = "c".to_owned();
+        let real = Symbol::from("a");
+        let obfuscated = Symbol::from("b");
+        let other_obfuscated = Symbol::from("c");
 
         scope.add(real.clone(), obfuscated.clone(), true);
 
@@ -1,7 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use crate::{
-    nodes::{Arguments, Expression, FunctionCall, Prefix},
+    nodes::{Expression, FunctionCall, Prefix},
     process::IdentifierTracker,
     utils,
 };
@@ -23,18 +23,16 @@ pub(crate) fn is_require_call(call: &FunctionCall, identifier_tracker: &Identifi
 }
 
 pub(crate) fn match_path_require_call(call: &FunctionCall) -> Option<PathBuf> {
-    match call.get_arguments() {
-        Arguments::String(string) => Some(string.get_value()),
-        Arguments::Tuple(tuple) if tuple.len() == 1 => {
-            let expression = tuple.iter_values().next().unwrap();
+    let arguments = call.get_arguments();
 
-            match expression {
-                Expression::String(string) => Some(string.get_value()),
-                _ => None,
-            }
-        }
+    if arguments.len() != 1 {
+        return None;
+    }
+
+    match arguments.iter_expressions().next()?.as_ref() {
+        Expression::String(string) => Some(utils::normalize_path_with_current_dir(Path::new(
+            string.get_value(),
+        ))),
         _ => None,
     }
-    .map(Path::new)
-    .map(utils::normalize_path_with_current_dir)
 }
@@ -2,7 +2,9 @@ mod match_require;
 mod path_iterator;
 mod path_locator;
 mod path_require_mode;
+mod require_resolver;
 
 pub(crate) use match_require::{is_require_call, match_path_require_call};
 pub(crate) use path_locator::RequirePathLocator;
 pub(crate) use path_require_mode::PathRequireMode;
+pub(crate) use require_resolver::{InstanceTree, RequireResolver};
@@ -1,6 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use super::{path_iterator, PathRequireMode};
+use crate::utils::log;
 use crate::{utils, DarkluaError, Resources};
 
 #[derive(Debug)]
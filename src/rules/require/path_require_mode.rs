@@ -1,3 +1,4 @@
+#[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
 
 use crate::frontend::DarkluaResult;
@@ -6,22 +7,32 @@ use crate::rules::require::match_path_require_call;
 use crate::rules::Context;
 use crate::DarkluaError;
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 
 use super::RequirePathLocator;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "snake_case")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde-config",
+    serde(deny_unknown_fields, rename_all = "snake_case")
+)]
 pub struct PathRequireMode {
-    #[serde(
-        skip_serializing_if = "is_default_module_folder_name",
-        default = "get_default_module_folder_name"
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(
+            skip_serializing_if = "is_default_module_folder_name",
+            default = "get_default_module_folder_name"
+        )
     )]
     module_folder_name: String,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    sources: HashMap<String, PathBuf>,
+    #[cfg_attr(
+        feature = "serde-config",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    sources: BTreeMap<String, PathBuf>,
 }
 
 impl Default for PathRequireMode {
@@ -40,6 +51,7 @@ fn get_default_module_folder_name() -> String {
     DEFAULT_MODULE_FOLDER_NAME.to_owned()
 }
 
+#[cfg(feature = "serde-config")]
 fn is_default_module_folder_name(value: &String) -> bool {
     value == DEFAULT_MODULE_FOLDER_NAME
 }
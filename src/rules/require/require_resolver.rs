@@ -0,0 +1,649 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+use crate::generator::{DenseLuaGenerator, LuaGenerator};
+use crate::nodes::{
+    Arguments, AssignStatement, Block, Expression, FieldExpression, FunctionCall, Identifier,
+    IndexExpression, LocalFunctionStatement, Prefix, Variable,
+};
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, Scope, ScopeVisitor};
+use crate::rules::convert_require::{InstancePath, InstancePathComponent, InstancePathRoot};
+use crate::{utils, Resources};
+
+use super::{is_require_call, match_path_require_call, PathRequireMode, RequirePathLocator};
+
+/// Associates Lua files with the Roblox instance they create, to resolve requires written
+/// with instances (like `require(script.Parent.Util)`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct InstanceTree {
+    files: HashMap<Vec<String>, PathBuf>,
+    instances: HashMap<PathBuf, Vec<String>>,
+    has_data_model: bool,
+}
+
+impl InstanceTree {
+    /// Creates the tree from the instance paths of a Rojo project (like
+    /// `ReplicatedStorage.Shared.Util`). The root of these paths is the data model, so
+    /// instances from `game` can also be resolved.
+    pub(crate) fn from_roblox_paths(
+        roblox_paths: impl IntoIterator<Item = (PathBuf, String)>,
+    ) -> Self {
+        let mut tree = Self {
+            has_data_model: true,
+            ..Default::default()
+        };
+
+        for (file, roblox_path) in roblox_paths {
+            tree.insert(file, roblox_path.split('.').map(str::to_owned).collect());
+        }
+
+        tree
+    }
+
+    /// Creates the tree from the location of the files: each directory under the root
+    /// becomes a folder instance and each file a module named after the file. An `init`
+    /// file creates the instance of its directory.
+    pub(crate) fn from_files(root: &Path, files: impl IntoIterator<Item = PathBuf>) -> Self {
+        let root = utils::normalize_path(root);
+        let mut tree = Self::default();
+
+        for file in files {
+            let file = utils::normalize_path(file);
+
+            if let Some(names) = file
+                .strip_prefix(&root)
+                .ok()
+                .and_then(instance_names_from_path)
+            {
+                tree.insert(file, names);
+            }
+        }
+
+        tree
+    }
+
+    fn insert(&mut self, file: PathBuf, names: Vec<String>) {
+        let file = utils::normalize_path(file);
+        self.files.insert(names.clone(), file.clone());
+        self.instances.insert(file, names);
+    }
+
+    fn resolve(&self, instance_path: &InstancePath, source: &Path) -> Result<PathBuf, String> {
+        let mut names = match instance_path.root() {
+            InstancePathRoot::Script => self
+                .instances
+                .get(&utils::normalize_path(source))
+                .cloned()
+                .ok_or_else(|| {
+                    format!(
+                        "unable to find the instance created by `{}`",
+                        source.display()
+                    )
+                })?,
+            InstancePathRoot::Root => {
+                if !self.has_data_model {
+                    return Err(
+                        "instances from `game` can only be resolved with a Rojo project".to_owned(),
+                    );
+                }
+                Vec::new()
+            }
+        };
+
+        for component in instance_path.components() {
+            match component {
+                InstancePathComponent::Parent => {
+                    if names.pop().is_none() {
+                        return Err("the instance is outside of the project".to_owned());
+                    }
+                }
+                InstancePathComponent::Child(name) => names.push(name.clone()),
+            }
+        }
+
+        self.files
+            .get(&names)
+            .cloned()
+            .ok_or_else(|| format!("no file creates the instance `{}`", names.join(".")))
+    }
+}
+
+fn instance_names_from_path(relative_path: &Path) -> Option<Vec<String>> {
+    let components = relative_path
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let (file_name, directories) = components.split_last()?;
+    let mut names: Vec<String> = directories.iter().map(|name| name.to_string()).collect();
+
+    let name = script_name(file_name)?;
+    // `init` scripts create the instance of their directory
+    if name != "init" {
+        names.push(name.to_owned());
+    }
+
+    Some(names)
+}
+
+fn script_name(file_name: &str) -> Option<&str> {
+    let path = Path::new(file_name);
+    if !matches!(
+        path.extension().and_then(OsStr::to_str),
+        Some("lua") | Some("luau")
+    ) {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+
+    Some(
+        stem.strip_suffix(".server")
+            .or_else(|| stem.strip_suffix(".client"))
+            .unwrap_or(stem),
+    )
+}
+
+/// A require call found in a file, with the file it requires when it can be resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FoundRequire {
+    pub(crate) line: Option<usize>,
+    pub(crate) snippet: String,
+    pub(crate) required: Result<PathBuf, String>,
+}
+
+/// Finds the files required by a Lua file. String requires are resolved like the `path`
+/// require mode, and requires of Roblox instances are resolved with an [`InstanceTree`].
+#[derive(Debug)]
+pub(crate) struct RequireResolver<'a> {
+    resources: &'a Resources,
+    project_location: PathBuf,
+    path_require_mode: PathRequireMode,
+    instances: InstanceTree,
+}
+
+impl<'a> RequireResolver<'a> {
+    pub(crate) fn new(
+        resources: &'a Resources,
+        project_location: impl Into<PathBuf>,
+        instances: InstanceTree,
+    ) -> Self {
+        Self {
+            resources,
+            project_location: project_location.into(),
+            path_require_mode: PathRequireMode::default(),
+            instances,
+        }
+    }
+
+    /// Returns the require calls of the given block, in the order they appear. The block
+    /// must be parsed with its tokens to get the line of each require.
+    pub(crate) fn find_requires(&self, block: &mut Block, source: &Path) -> Vec<FoundRequire> {
+        let mut collector = RequireCollector::new(self, source);
+        ScopeVisitor::visit_block(block, &mut collector);
+        collector.requires
+    }
+
+    fn resolve(
+        &self,
+        call: &FunctionCall,
+        instance: Option<InstancePath>,
+        source: &Path,
+    ) -> Result<PathBuf, String> {
+        if let Some(literal_path) = match_path_require_call(call) {
+            RequirePathLocator::new(
+                &self.path_require_mode,
+                &self.project_location,
+                self.resources,
+            )
+            .find_require_path(literal_path, source)
+            .map(utils::normalize_path)
+            .map_err(|err| err.to_string())
+        } else if let Some(instance) = instance {
+            self.instances.resolve(&instance, source)
+        } else {
+            Err("the required value is not a string or a known instance".to_owned())
+        }
+    }
+}
+
+struct RequireCollector<'a, 'b> {
+    resolver: &'a RequireResolver<'b>,
+    source: &'a Path,
+    identifier_tracker: IdentifierTracker,
+    // the local variables of each scope, with the instance they contain when it is known
+    aliases: Vec<HashMap<String, Option<InstancePath>>>,
+    requires: Vec<FoundRequire>,
+}
+
+impl<'a, 'b> RequireCollector<'a, 'b> {
+    fn new(resolver: &'a RequireResolver<'b>, source: &'a Path) -> Self {
+        Self {
+            resolver,
+            source,
+            identifier_tracker: IdentifierTracker::new(),
+            aliases: vec![HashMap::new()],
+            requires: Vec::new(),
+        }
+    }
+
+    fn insert_alias(&mut self, name: &str, instance: Option<InstancePath>) {
+        if let Some(scope) = self.aliases.last_mut() {
+            scope.insert(name.to_owned(), instance);
+        }
+    }
+
+    fn find_alias(&self, name: &str) -> Option<Option<&InstancePath>> {
+        self.aliases
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .map(Option::as_ref)
+    }
+
+    fn match_instance_expression(&self, expression: &Expression) -> Option<InstancePath> {
+        match expression {
+            Expression::Call(call) => self.match_instance_call(call),
+            Expression::Field(field) => self.match_instance_field(field),
+            Expression::Identifier(identifier) => {
+                self.match_instance_identifier(identifier.get_name())
+            }
+            Expression::Index(index) => self.match_instance_index(index),
+            Expression::Parenthese(parenthese) => {
+                self.match_instance_expression(parenthese.inner_expression())
+            }
+            Expression::TypeCast(type_cast) => {
+                self.match_instance_expression(type_cast.get_expression())
+            }
+            _ => None,
+        }
+    }
+
+    fn match_instance_prefix(&self, prefix: &Prefix) -> Option<InstancePath> {
+        match prefix {
+            Prefix::Call(call) => self.match_instance_call(call),
+            Prefix::Field(field) => self.match_instance_field(field),
+            Prefix::Identifier(identifier) => self.match_instance_identifier(identifier.get_name()),
+            Prefix::Index(index) => self.match_instance_index(index),
+            Prefix::Parenthese(parenthese) => {
+                self.match_instance_expression(parenthese.inner_expression())
+            }
+        }
+    }
+
+    fn match_instance_field(&self, field: &FieldExpression) -> Option<InstancePath> {
+        let mut instance = self.match_instance_prefix(field.get_prefix())?;
+        match field.get_field().get_name() {
+            "Parent" => instance.parent(),
+            name => instance.child(name),
+        }
+        Some(instance)
+    }
+
+    fn match_instance_index(&self, index: &IndexExpression) -> Option<InstancePath> {
+        match index.get_index() {
+            Expression::String(string) => {
+                let mut instance = self.match_instance_prefix(index.get_prefix())?;
+                instance.child(string.get_value());
+                Some(instance)
+            }
+            _ => None,
+        }
+    }
+
+    fn match_instance_identifier(&self, name: &str) -> Option<InstancePath> {
+        match self.find_alias(name) {
+            Some(instance) => instance.cloned(),
+            None => match name {
+                "script" if !self.identifier_tracker.is_identifier_used(name) => {
+                    Some(InstancePath::from_script())
+                }
+                "game" if !self.identifier_tracker.is_identifier_used(name) => {
+                    Some(InstancePath::from_root())
+                }
+                _ => None,
+            },
+        }
+    }
+
+    fn match_instance_call(&self, call: &FunctionCall) -> Option<InstancePath> {
+        let method = call.get_method()?;
+        let child_name = match call.get_arguments() {
+            Arguments::String(string) => string.get_value(),
+            Arguments::Tuple(tuple) => match tuple.iter_values().next() {
+                Some(Expression::String(string)) => string.get_value(),
+                _ => return None,
+            },
+            Arguments::Table(_) => return None,
+        };
+
+        let mut instance = self.match_instance_prefix(call.get_prefix())?;
+
+        match method.get_name() {
+            "FindFirstChild" | "WaitForChild" => {}
+            "GetService"
+                if *instance.root() == InstancePathRoot::Root
+                    && instance.components().is_empty() => {}
+            _ => return None,
+        }
+
+        instance.child(child_name);
+        Some(instance)
+    }
+}
+
+impl Scope for RequireCollector<'_, '_> {
+    fn push(&mut self) {
+        self.identifier_tracker.push();
+        self.aliases.push(HashMap::new());
+    }
+
+    fn pop(&mut self) {
+        self.identifier_tracker.pop();
+        self.aliases.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.identifier_tracker.insert(identifier);
+        self.insert_alias(identifier.get_name(), None);
+    }
+
+    fn insert_self(&mut self) {
+        self.identifier_tracker.insert_self();
+        self.insert_alias("self", None);
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>) {
+        let instance = value
+            .as_deref()
+            .and_then(|value| self.match_instance_expression(value));
+        self.identifier_tracker.insert_local(identifier, value);
+        self.insert_alias(identifier.get_name(), instance);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.identifier_tracker.insert_local_function(function);
+        self.insert_alias(function.get_name(), None);
+    }
+}
+
+impl NodeProcessor for RequireCollector<'_, '_> {
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        // a reassigned variable may not contain the same instance anymore
+        for variable in assign.iter_variables() {
+            if let Variable::Identifier(identifier) = variable {
+                let name = identifier.get_name();
+                if let Some(scope) = self
+                    .aliases
+                    .iter_mut()
+                    .rev()
+                    .find(|scope| scope.contains_key(name))
+                {
+                    scope.insert(name.to_owned(), None);
+                }
+            }
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if !is_require_call(call, &self.identifier_tracker) {
+            return;
+        }
+
+        let arguments = call.get_arguments();
+        let instance = if arguments.len() == 1 {
+            arguments
+                .iter_expressions()
+                .next()
+                .and_then(|value| self.match_instance_expression(&value))
+        } else {
+            None
+        };
+
+        let line = match call.get_prefix() {
+            Prefix::Identifier(identifier) => identifier
+                .get_token()
+                .and_then(|token| token.get_line_number()),
+            _ => None,
+        };
+
+        let mut generator = DenseLuaGenerator::new(usize::MAX / 2);
+        generator.write_expression(&Expression::from(call.clone()));
+
+        self.requires.push(FoundRequire {
+            line,
+            snippet: generator.into_string(),
+            required: self.resolver.resolve(call, instance, self.source),
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Parser;
+
+    fn find_requires(
+        resources: &Resources,
+        instances: InstanceTree,
+        source: &str,
+    ) -> Vec<Result<PathBuf, String>> {
+        let code = resources.get(source).expect("source file should exist");
+        let mut block = Parser::default()
+            .preserve_tokens()
+            .parse(&code)
+            .expect("source should parse");
+
+        RequireResolver::new(resources, "", instances)
+            .find_requires(&mut block, Path::new(source))
+            .into_iter()
+            .map(|found| found.required)
+            .collect()
+    }
+
+    fn memory_resources(files: &[(&str, &str)]) -> Resources {
+        let resources = Resources::from_memory();
+        for (path, content) in files {
+            resources.write(path, content).unwrap();
+        }
+        resources
+    }
+
+    fn tree_from_files(resources: &Resources, root: &str) -> InstanceTree {
+        InstanceTree::from_files(Path::new(root), resources.collect_work(root))
+    }
+
+    #[test]
+    fn resolve_relative_require() {
+        let resources = memory_resources(&[
+            ("src/main.lua", "local value = require('./value')"),
+            ("src/value.lua", "return 1"),
+        ]);
+
+        assert_eq!(
+            find_requires(&resources, InstanceTree::default(), "src/main.lua"),
+            vec![Ok(PathBuf::from("src/value.lua"))]
+        );
+    }
+
+    #[test]
+    fn resolve_relative_require_in_parent_directory() {
+        let resources = memory_resources(&[
+            ("src/lib/main.lua", "local value = require('../value')"),
+            ("src/value.luau", "return 1"),
+        ]);
+
+        assert_eq!(
+            find_requires(&resources, InstanceTree::default(), "src/lib/main.lua"),
+            vec![Ok(PathBuf::from("src/value.luau"))]
+        );
+    }
+
+    #[test]
+    fn resolve_relative_require_of_init_folder() {
+        let resources = memory_resources(&[
+            ("src/main.lua", "local lib = require('./lib')"),
+            ("src/lib/init.lua", "return {}"),
+        ]);
+
+        assert_eq!(
+            find_requires(&resources, InstanceTree::default(), "src/main.lua"),
+            vec![Ok(PathBuf::from("src/lib/init.lua"))]
+        );
+    }
+
+    #[test]
+    fn relative_require_of_missing_file_is_unresolved() {
+        let resources = memory_resources(&[("src/main.lua", "local value = require('./value')")]);
+
+        let requires = find_requires(&resources, InstanceTree::default(), "src/main.lua");
+
+        assert_eq!(requires.len(), 1);
+        assert!(requires[0].is_err());
+    }
+
+    #[test]
+    fn resolve_script_parent_child() {
+        let resources = memory_resources(&[
+            ("src/main.lua", "local value = require(script.Parent.Value)"),
+            ("src/Value.lua", "return 1"),
+        ]);
+        let instances = tree_from_files(&resources, "src");
+
+        assert_eq!(
+            find_requires(&resources, instances, "src/main.lua"),
+            vec![Ok(PathBuf::from("src/Value.lua"))]
+        );
+    }
+
+    #[test]
+    fn resolve_script_child_from_init_file() {
+        let resources = memory_resources(&[
+            ("src/lib/init.lua", "local util = require(script.Util)"),
+            ("src/lib/Util.lua", "return 1"),
+        ]);
+        let instances = tree_from_files(&resources, "src");
+
+        assert_eq!(
+            find_requires(&resources, instances, "src/lib/init.lua"),
+            vec![Ok(PathBuf::from("src/lib/Util.lua"))]
+        );
+    }
+
+    #[test]
+    fn resolve_script_parent_child_of_init_folder() {
+        let resources = memory_resources(&[
+            (
+                "src/main.lua",
+                "local lib = require(script.Parent:WaitForChild('Lib'))",
+            ),
+            ("src/Lib/init.luau", "return {}"),
+        ]);
+        let instances = tree_from_files(&resources, "src");
+
+        assert_eq!(
+            find_requires(&resources, instances, "src/main.lua"),
+            vec![Ok(PathBuf::from("src/Lib/init.luau"))]
+        );
+    }
+
+    #[test]
+    fn resolve_instance_from_local_alias() {
+        let resources = memory_resources(&[
+            (
+                "src/main.lua",
+                "local Shared = script.Parent.Shared local value = require(Shared['Value'])",
+            ),
+            ("src/Shared/Value.lua", "return 1"),
+        ]);
+        let instances = tree_from_files(&resources, "src");
+
+        assert_eq!(
+            find_requires(&resources, instances, "src/main.lua"),
+            vec![Ok(PathBuf::from("src/Shared/Value.lua"))]
+        );
+    }
+
+    #[test]
+    fn resolve_game_service_with_roblox_paths() {
+        let resources = memory_resources(&[
+            (
+                "src/client/main.lua",
+                "local value = require(game:GetService('ReplicatedStorage').Shared.Value)",
+            ),
+            ("src/shared/Value.lua", "return 1"),
+        ]);
+        let instances = InstanceTree::from_roblox_paths(vec![
+            (
+                PathBuf::from("src/client/main.lua"),
+                "StarterPlayer.StarterPlayerScripts.main".to_owned(),
+            ),
+            (
+                PathBuf::from("src/shared/Value.lua"),
+                "ReplicatedStorage.Shared.Value".to_owned(),
+            ),
+        ]);
+
+        assert_eq!(
+            find_requires(&resources, instances, "src/client/main.lua"),
+            vec![Ok(PathBuf::from("src/shared/Value.lua"))]
+        );
+    }
+
+    #[test]
+    fn game_instance_without_roblox_paths_is_unresolved() {
+        let resources = memory_resources(&[(
+            "src/main.lua",
+            "local value = require(game.ReplicatedStorage.Value)",
+        )]);
+        let instances = tree_from_files(&resources, "src");
+
+        let requires = find_requires(&resources, instances, "src/main.lua");
+
+        assert_eq!(requires.len(), 1);
+        assert!(requires[0].is_err());
+    }
+
+    #[test]
+    fn require_of_unknown_value_is_unresolved() {
+        let resources = memory_resources(&[("src/main.lua", "local value = require(getModule())")]);
+
+        let requires = find_requires(&resources, InstanceTree::default(), "src/main.lua");
+
+        assert_eq!(requires.len(), 1);
+        assert!(requires[0].is_err());
+    }
+
+    #[test]
+    fn shadowed_require_is_ignored() {
+        let resources = memory_resources(&[(
+            "src/main.lua",
+            "local require = load local value = require('./value')",
+        )]);
+
+        assert_eq!(
+            find_requires(&resources, InstanceTree::default(), "src/main.lua"),
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn shadowed_script_is_not_an_instance() {
+        let resources = memory_resources(&[
+            (
+                "src/main.lua",
+                "local script = {} local value = require(script.Value)",
+            ),
+            ("src/Value.lua", "return 1"),
+        ]);
+        let instances = tree_from_files(&resources, "src");
+
+        let requires = find_requires(&resources, instances, "src/main.lua");
+
+        assert_eq!(requires.len(), 1);
+        assert!(requires[0].is_err());
+    }
+}
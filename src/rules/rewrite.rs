@@ -0,0 +1,820 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::generator::{DenseLuaGenerator, LuaGenerator};
+use crate::nodes::{Arguments, Block, Expression, FunctionCall, Prefix, Statement, TupleArguments};
+use crate::process::{
+    DefaultPostVisitor, DefaultVisitor, Evaluator, NodePostProcessor, NodePostVisitor,
+    NodeProcessor, NodeVisitor,
+};
+use crate::rules::{
+    Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult, RuleProperties,
+    RulePropertyKind,
+};
+use crate::utils::log;
+use crate::Parser;
+
+pub const REWRITE_RULE_NAME: &str = "rewrite";
+
+const REWRITES_PROPERTY: &str = "rewrites";
+const FIND_KEY: &str = "find";
+const REPLACE_KEY: &str = "replace";
+
+// metavariables are replaced with these identifiers so that snippets can be parsed as Lua
+const METAVARIABLE_PREFIX: &str = "__DARKLUA_METAVARIABLE_";
+const VARIADIC_METAVARIABLE_PREFIX: &str = "__DARKLUA_VARIADIC_METAVARIABLE_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MetavariableKind {
+    Expression,
+    Arguments,
+}
+
+/// The metavariables of a snippet, with their kind and their number of uses.
+type Metavariables = BTreeMap<String, (MetavariableKind, usize)>;
+
+#[derive(Debug, Clone)]
+enum Binding {
+    Expression(Box<Expression>),
+    Arguments(Vec<Expression>),
+}
+
+fn metavariable_name(identifier: &str) -> Option<&str> {
+    identifier.strip_prefix(METAVARIABLE_PREFIX)
+}
+
+fn variadic_metavariable_name(identifier: &str) -> Option<&str> {
+    identifier.strip_prefix(VARIADIC_METAVARIABLE_PREFIX)
+}
+
+fn expression_metavariable(expression: &Expression) -> Option<&str> {
+    match expression {
+        Expression::Identifier(identifier) => metavariable_name(identifier.get_name()),
+        _ => None,
+    }
+}
+
+fn prefix_metavariable(prefix: &Prefix) -> Option<&str> {
+    match prefix {
+        Prefix::Identifier(identifier) => metavariable_name(identifier.get_name()),
+        _ => None,
+    }
+}
+
+fn variadic_metavariable(expression: &Expression) -> Option<&str> {
+    match expression {
+        Expression::Identifier(identifier) => variadic_metavariable_name(identifier.get_name()),
+        _ => None,
+    }
+}
+
+/// Replaces the metavariables (`$name` and `$name...`) of a snippet with identifiers. Returns
+/// the new snippet and the number of times each metavariable is used.
+fn replace_metavariables(snippet: &str) -> Result<(String, Metavariables), String> {
+    let mut code = String::with_capacity(snippet.len());
+    let mut metavariables = Metavariables::new();
+    let mut rest = snippet;
+
+    while let Some(index) = rest.find('$') {
+        code.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+
+        let name_length = rest
+            .find(|character: char| !(character.is_ascii_alphanumeric() || character == '_'))
+            .unwrap_or(rest.len());
+        let name = &rest[..name_length];
+
+        if name.is_empty() || name.starts_with(|character: char| character.is_ascii_digit()) {
+            return Err(format!(
+                "expected a metavariable name after `$` in `{}`",
+                snippet
+            ));
+        }
+        rest = &rest[name_length..];
+
+        let kind = if let Some(after_dots) = rest.strip_prefix("...") {
+            rest = after_dots;
+            code.push_str(VARIADIC_METAVARIABLE_PREFIX);
+            MetavariableKind::Arguments
+        } else {
+            code.push_str(METAVARIABLE_PREFIX);
+            MetavariableKind::Expression
+        };
+        code.push_str(name);
+
+        let entry = metavariables.entry(name.to_owned()).or_insert((kind, 0));
+        if entry.0 != kind {
+            return Err(format!(
+                "metavariable `${}` is used both as `${}` and `${}...` in `{}`",
+                name, name, name, snippet
+            ));
+        }
+        entry.1 += 1;
+    }
+
+    code.push_str(rest);
+
+    Ok((code, metavariables))
+}
+
+/// Counts the variadic metavariables found in a pattern and the ones that are the last
+/// argument of a call.
+#[derive(Debug, Default)]
+struct VariadicPlacementCounter {
+    total: usize,
+    valid: usize,
+}
+
+impl NodeProcessor for VariadicPlacementCounter {
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if let Arguments::Tuple(tuple) = call.get_arguments() {
+            if tuple
+                .iter_values()
+                .last()
+                .and_then(variadic_metavariable)
+                .is_some()
+            {
+                self.valid += 1;
+            }
+        }
+    }
+
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if variadic_metavariable(expression).is_some() {
+            self.total += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RewritePattern {
+    snippet: String,
+    find: Expression,
+    replace: Expression,
+    // the number of times each metavariable of the pattern is used in the replacement
+    replacement_uses: BTreeMap<String, usize>,
+}
+
+impl RewritePattern {
+    fn new(find: &str, replace: &str) -> Result<Self, String> {
+        let (find_code, find_metavariables) = replace_metavariables(find)?;
+        let (replace_code, replace_metavariables) = replace_metavariables(replace)?;
+
+        let parser = Parser::default();
+        let find_expression = parse_pattern(&parser, &find_code, find, FIND_KEY)?;
+        let replace_expression = parse_pattern(&parser, &replace_code, replace, REPLACE_KEY)?;
+
+        if expression_metavariable(&find_expression).is_some() {
+            return Err(format!(
+                "the `find` pattern `{}` cannot be only a metavariable",
+                find
+            ));
+        }
+
+        for (name, (kind, _)) in replace_metavariables.iter() {
+            match find_metavariables.get(name) {
+                Some((find_kind, _)) if find_kind == kind => {}
+                Some(_) => {
+                    return Err(format!(
+                        "metavariable `${}` is not used the same way in `{}` and `{}`",
+                        name, find, replace
+                    ))
+                }
+                None => {
+                    return Err(format!(
+                        "metavariable `${}` of `{}` is not defined in the `find` pattern `{}`",
+                        name, replace, find
+                    ))
+                }
+            }
+        }
+
+        for (expression, snippet) in [(&find_expression, find), (&replace_expression, replace)] {
+            let mut counter = VariadicPlacementCounter::default();
+            DefaultVisitor::visit_expression(&mut expression.clone(), &mut counter);
+            if counter.total != counter.valid {
+                return Err(format!(
+                    "variadic metavariables (`$name...`) can only be used as the last argument of a call in `{}`",
+                    snippet
+                ));
+            }
+        }
+
+        Ok(Self {
+            snippet: find.to_owned(),
+            find: find_expression,
+            replace: replace_expression,
+            replacement_uses: find_metavariables
+                .keys()
+                .map(|name| {
+                    let uses = replace_metavariables
+                        .get(name)
+                        .map(|(_, uses)| *uses)
+                        .unwrap_or(0);
+                    (name.clone(), uses)
+                })
+                .collect(),
+        })
+    }
+
+    fn matches_prefix(&self) -> bool {
+        matches!(
+            self.find,
+            Expression::Call(_)
+                | Expression::Field(_)
+                | Expression::Identifier(_)
+                | Expression::Index(_)
+                | Expression::Parenthese(_)
+        )
+    }
+
+    fn find_call(&self) -> Option<&FunctionCall> {
+        match &self.find {
+            Expression::Call(call) => Some(call),
+            _ => None,
+        }
+    }
+
+    fn replace_call(&self) -> Option<&FunctionCall> {
+        match &self.replace {
+            Expression::Call(call) => Some(call),
+            _ => None,
+        }
+    }
+}
+
+fn parse_pattern(
+    parser: &Parser,
+    code: &str,
+    snippet: &str,
+    key: &str,
+) -> Result<Expression, String> {
+    parser
+        .parse_expression(code)
+        .map_err(|err| format!("unable to parse `{}` pattern `{}`: {}", key, snippet, err))
+}
+
+fn generate_code(expression: &Expression) -> String {
+    let mut generator = DenseLuaGenerator::new(usize::MAX / 2);
+    generator.write_expression(expression);
+    generator.into_string()
+}
+
+/// Compares two expressions without their tokens.
+fn same_expressions(a: &Expression, b: &Expression) -> bool {
+    a == b || generate_code(a) == generate_code(b)
+}
+
+#[derive(Debug, Default)]
+struct Matcher {
+    bindings: HashMap<String, Binding>,
+}
+
+impl Matcher {
+    fn bind_expression(&mut self, name: &str, expression: &Expression) -> bool {
+        match self.bindings.get(name) {
+            Some(Binding::Expression(bound)) => same_expressions(bound, expression),
+            Some(Binding::Arguments(_)) => false,
+            None => {
+                self.bindings.insert(
+                    name.to_owned(),
+                    Binding::Expression(Box::new(expression.clone())),
+                );
+                true
+            }
+        }
+    }
+
+    fn bind_arguments(&mut self, name: &str, arguments: &[Expression]) -> bool {
+        match self.bindings.get(name) {
+            Some(Binding::Arguments(bound)) => {
+                bound.len() == arguments.len()
+                    && bound
+                        .iter()
+                        .zip(arguments)
+                        .all(|(bound, argument)| same_expressions(bound, argument))
+            }
+            Some(Binding::Expression(_)) => false,
+            None => {
+                self.bindings
+                    .insert(name.to_owned(), Binding::Arguments(arguments.to_vec()));
+                true
+            }
+        }
+    }
+
+    fn match_expression(&mut self, pattern: &Expression, expression: &Expression) -> bool {
+        if let Some(name) = expression_metavariable(pattern) {
+            return self.bind_expression(name, expression);
+        }
+
+        match (pattern, expression) {
+            (Expression::Identifier(pattern), Expression::Identifier(identifier)) => {
+                pattern.get_name() == identifier.get_name()
+            }
+            (Expression::Binary(pattern), Expression::Binary(binary)) => {
+                pattern.operator() == binary.operator()
+                    && self.match_expression(pattern.left(), binary.left())
+                    && self.match_expression(pattern.right(), binary.right())
+            }
+            (Expression::Unary(pattern), Expression::Unary(unary)) => {
+                pattern.operator() == unary.operator()
+                    && self.match_expression(pattern.get_expression(), unary.get_expression())
+            }
+            (Expression::Call(pattern), Expression::Call(call)) => self.match_call(pattern, call),
+            (Expression::Field(pattern), Expression::Field(field)) => {
+                pattern.get_field().get_name() == field.get_field().get_name()
+                    && self.match_prefix(pattern.get_prefix(), field.get_prefix())
+            }
+            (Expression::Index(pattern), Expression::Index(index)) => {
+                self.match_prefix(pattern.get_prefix(), index.get_prefix())
+                    && self.match_expression(pattern.get_index(), index.get_index())
+            }
+            (Expression::Parenthese(pattern), Expression::Parenthese(parenthese)) => {
+                self.match_expression(pattern.inner_expression(), parenthese.inner_expression())
+            }
+            (Expression::Number(pattern), Expression::Number(number)) => {
+                pattern.compute_value() == number.compute_value()
+            }
+            (Expression::String(pattern), Expression::String(string)) => {
+                pattern.get_value() == string.get_value()
+            }
+            (Expression::True(_), Expression::True(_))
+            | (Expression::False(_), Expression::False(_))
+            | (Expression::Nil(_), Expression::Nil(_))
+            | (Expression::VariableArguments(_), Expression::VariableArguments(_)) => true,
+            (Expression::Function(_), Expression::Function(_))
+            | (Expression::If(_), Expression::If(_))
+            | (Expression::InterpolatedString(_), Expression::InterpolatedString(_))
+            | (Expression::Table(_), Expression::Table(_))
+            | (Expression::TypeCast(_), Expression::TypeCast(_)) => {
+                same_expressions(pattern, expression)
+            }
+            _ => false,
+        }
+    }
+
+    fn match_prefix(&mut self, pattern: &Prefix, prefix: &Prefix) -> bool {
+        if let Some(name) = prefix_metavariable(pattern) {
+            return self.bind_expression(name, &Expression::from(prefix.clone()));
+        }
+
+        match (pattern, prefix) {
+            (Prefix::Identifier(pattern), Prefix::Identifier(identifier)) => {
+                pattern.get_name() == identifier.get_name()
+            }
+            (Prefix::Call(pattern), Prefix::Call(call)) => self.match_call(pattern, call),
+            (Prefix::Field(pattern), Prefix::Field(field)) => {
+                pattern.get_field().get_name() == field.get_field().get_name()
+                    && self.match_prefix(pattern.get_prefix(), field.get_prefix())
+            }
+            (Prefix::Index(pattern), Prefix::Index(index)) => {
+                self.match_prefix(pattern.get_prefix(), index.get_prefix())
+                    && self.match_expression(pattern.get_index(), index.get_index())
+            }
+            (Prefix::Parenthese(pattern), Prefix::Parenthese(parenthese)) => {
+                self.match_expression(pattern.inner_expression(), parenthese.inner_expression())
+            }
+            _ => false,
+        }
+    }
+
+    fn match_call(&mut self, pattern: &FunctionCall, call: &FunctionCall) -> bool {
+        let methods_match = match (pattern.get_method(), call.get_method()) {
+            (Some(pattern), Some(method)) => pattern.get_name() == method.get_name(),
+            (None, None) => true,
+            (Some(_), None) | (None, Some(_)) => false,
+        };
+
+        if !methods_match || !self.match_prefix(pattern.get_prefix(), call.get_prefix()) {
+            return false;
+        }
+
+        let pattern_arguments = pattern.get_arguments().clone().to_expressions();
+        let arguments = call.get_arguments().clone().to_expressions();
+
+        match pattern_arguments.split_last() {
+            Some((last, leading_patterns)) => {
+                if let Some(name) = variadic_metavariable(last) {
+                    arguments.len() >= leading_patterns.len()
+                        && self
+                            .match_arguments(leading_patterns, &arguments[..leading_patterns.len()])
+                        && self.bind_arguments(name, &arguments[leading_patterns.len()..])
+                } else {
+                    pattern_arguments.len() == arguments.len()
+                        && self.match_arguments(&pattern_arguments, &arguments)
+                }
+            }
+            None => arguments.is_empty(),
+        }
+    }
+
+    fn match_arguments(&mut self, patterns: &[Expression], arguments: &[Expression]) -> bool {
+        patterns
+            .iter()
+            .zip(arguments)
+            .all(|(pattern, argument)| self.match_expression(pattern, argument))
+    }
+
+    fn into_bindings(self) -> HashMap<String, Binding> {
+        self.bindings
+    }
+}
+
+/// Replaces the metavariables of a replacement template with their bound values.
+struct Substitution<'a> {
+    bindings: &'a HashMap<String, Binding>,
+}
+
+impl Substitution<'_> {
+    fn bound_expression(&self, name: &str) -> Option<&Expression> {
+        match self.bindings.get(name) {
+            Some(Binding::Expression(expression)) => Some(expression.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl NodeProcessor for Substitution<'_> {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        if let Some(bound) = expression_metavariable(expression)
+            .and_then(|name| self.bound_expression(name))
+            .cloned()
+        {
+            *expression = bound;
+        }
+    }
+
+    fn process_prefix_expression(&mut self, prefix: &mut Prefix) {
+        if let Some(bound) = prefix_metavariable(prefix)
+            .and_then(|name| self.bound_expression(name))
+            .cloned()
+        {
+            *prefix = Prefix::from(bound);
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        let bound = match call.get_arguments() {
+            Arguments::Tuple(tuple) => tuple
+                .iter_values()
+                .last()
+                .and_then(variadic_metavariable)
+                .and_then(|name| match self.bindings.get(name) {
+                    Some(Binding::Arguments(arguments)) => Some(arguments.clone()),
+                    _ => None,
+                }),
+            Arguments::String(_) | Arguments::Table(_) => None,
+        };
+
+        if let Some(bound) = bound {
+            let mut values = call.get_arguments().clone().to_expressions();
+            values.pop();
+            values.extend(bound);
+            call.set_arguments(TupleArguments::new(values).into());
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RewriteProcessor<'a> {
+    patterns: &'a [RewritePattern],
+    evaluator: Evaluator,
+}
+
+impl<'a> RewriteProcessor<'a> {
+    fn new(patterns: &'a [RewritePattern]) -> Self {
+        Self {
+            patterns,
+            evaluator: Evaluator::default(),
+        }
+    }
+
+    /// Verifies that the rewrite does not duplicate or remove expressions with side effects.
+    fn verify_side_effects(
+        &self,
+        pattern: &RewritePattern,
+        bindings: &HashMap<String, Binding>,
+    ) -> bool {
+        for (name, uses) in pattern.replacement_uses.iter() {
+            if *uses == 1 {
+                continue;
+            }
+
+            let has_side_effects = match bindings.get(name) {
+                Some(Binding::Expression(expression)) => {
+                    self.evaluator.has_side_effects(expression)
+                }
+                Some(Binding::Arguments(arguments)) => arguments
+                    .iter()
+                    .any(|argument| self.evaluator.has_side_effects(argument)),
+                None => false,
+            };
+
+            if has_side_effects {
+                log::warn!(
+                    "unable to rewrite `{}`: metavariable `${}` is bound to an expression with side effects that would be {}",
+                    pattern.snippet,
+                    name,
+                    if *uses == 0 { "removed" } else { "duplicated" }
+                );
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn substitute(pattern: &RewritePattern, bindings: &HashMap<String, Binding>) -> Expression {
+        let mut expression = pattern.replace.clone();
+        DefaultVisitor::visit_expression(&mut expression, &mut Substitution { bindings });
+        expression
+    }
+
+    fn rewrite_expression(&self, expression: &Expression) -> Option<Expression> {
+        self.patterns.iter().find_map(|pattern| {
+            let mut matcher = Matcher::default();
+
+            if !matcher.match_expression(&pattern.find, expression) {
+                return None;
+            }
+
+            let bindings = matcher.into_bindings();
+
+            if self.verify_side_effects(pattern, &bindings) {
+                Some(Self::substitute(pattern, &bindings))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn rewrite_call_statement(&self, call: &FunctionCall) -> Option<FunctionCall> {
+        self.patterns.iter().find_map(|pattern| {
+            let (find, replace) = (pattern.find_call()?, pattern.replace_call()?);
+
+            let mut matcher = Matcher::default();
+
+            if !matcher.match_call(find, call) {
+                return None;
+            }
+
+            let bindings = matcher.into_bindings();
+
+            if !self.verify_side_effects(pattern, &bindings) {
+                return None;
+            }
+
+            let mut call = replace.clone();
+            DefaultVisitor::visit_function_call(
+                &mut call,
+                &mut Substitution {
+                    bindings: &bindings,
+                },
+            );
+            Some(call)
+        })
+    }
+}
+
+impl NodeProcessor for RewriteProcessor<'_> {}
+
+impl NodePostProcessor for RewriteProcessor<'_> {
+    fn process_after_statement(&mut self, statement: &mut Statement) {
+        if let Statement::Call(call) = statement {
+            if let Some(new_call) = self.rewrite_call_statement(call) {
+                *call = new_call;
+            }
+        }
+    }
+
+    fn process_after_expression(&mut self, expression: &mut Expression) {
+        if let Some(new_expression) = self.rewrite_expression(expression) {
+            *expression = new_expression;
+        }
+    }
+
+    fn process_after_prefix_expression(&mut self, prefix: &mut Prefix) {
+        if !self.patterns.iter().any(RewritePattern::matches_prefix) {
+            return;
+        }
+
+        if let Some(new_expression) = self.rewrite_expression(&Expression::from(prefix.clone())) {
+            *prefix = Prefix::from(new_expression);
+        }
+    }
+}
+
+/// A rule that rewrites expressions and call statements matching patterns written as Lua
+/// snippets with metavariables.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Rewrite {
+    rewrites: Vec<(String, String)>,
+}
+
+impl Rewrite {
+    pub fn with_rewrite(mut self, find: impl Into<String>, replace: impl Into<String>) -> Self {
+        self.rewrites.push((find.into(), replace.into()));
+        self
+    }
+
+    fn build_patterns(&self) -> Result<Vec<RewritePattern>, RuleConfigurationError> {
+        self.rewrites
+            .iter()
+            .map(|(find, replace)| {
+                RewritePattern::new(find, replace).map_err(|message| {
+                    RuleConfigurationError::UnexpectedValue {
+                        property: REWRITES_PROPERTY.to_owned(),
+                        message,
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+impl Rule for Rewrite {
+    fn process(&self, block: &mut Block, _: &Context) -> RuleProcessResult {
+        let patterns = self.build_patterns().map_err(|err| err.to_string())?;
+
+        if patterns.is_empty() {
+            return Ok(());
+        }
+
+        let mut processor = RewriteProcessor::new(&patterns);
+        DefaultPostVisitor::visit_block(block, &mut processor);
+
+        Ok(())
+    }
+}
+
+impl RuleConfiguration for Rewrite {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                REWRITES_PROPERTY => {
+                    self.rewrites = value
+                        .expect_string_map_list(&key)?
+                        .into_iter()
+                        .map(|mut rewrite| {
+                            let find = rewrite.remove(FIND_KEY);
+                            let replace = rewrite.remove(REPLACE_KEY);
+
+                            if let Some(unexpected) = rewrite.keys().next() {
+                                return Err(RuleConfigurationError::UnexpectedValue {
+                                    property: key.clone(),
+                                    message: format!(
+                                        "unexpected field `{}` (a rewrite only has `{}` and `{}`)",
+                                        unexpected, FIND_KEY, REPLACE_KEY
+                                    ),
+                                });
+                            }
+
+                            match (find, replace) {
+                                (Some(find), Some(replace)) => Ok((find, replace)),
+                                _ => Err(RuleConfigurationError::UnexpectedValue {
+                                    property: key.clone(),
+                                    message: format!(
+                                        "each rewrite must define `{}` and `{}`",
+                                        FIND_KEY, REPLACE_KEY
+                                    ),
+                                }),
+                            }
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        self.build_patterns().map(|_| ())
+    }
+
+    fn get_name(&self) -> &'static str {
+        REWRITE_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &[REWRITES_PROPERTY]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::StringMapList
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if !self.rewrites.is_empty() {
+            properties.insert(
+                REWRITES_PROPERTY.to_owned(),
+                self.rewrites
+                    .iter()
+                    .map(|(find, replace)| {
+                        BTreeMap::from([
+                            (FIND_KEY.to_owned(), find.clone()),
+                            (REPLACE_KEY.to_owned(), replace.clone()),
+                        ])
+                    })
+                    .collect::<Vec<_>>()
+                    .into(),
+            );
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> Rewrite {
+        Rewrite::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_rewrite", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_rewrites() {
+        let rule: Box<dyn Rule> =
+            Box::new(new_rule().with_rewrite("Vector3.new(0, 0, 0)", "Vector3.zero"));
+
+        assert_json_snapshot!("rewrite_with_rewrites", rule);
+    }
+
+    #[test]
+    fn replace_metavariables_in_snippet() {
+        let (code, metavariables) = replace_metavariables("f($x, $x, $rest...)").unwrap();
+
+        pretty_assertions::assert_eq!(
+            code,
+            "f(__DARKLUA_METAVARIABLE_x, __DARKLUA_METAVARIABLE_x, __DARKLUA_VARIADIC_METAVARIABLE_rest)"
+        );
+        pretty_assertions::assert_eq!(
+            metavariables,
+            BTreeMap::from([
+                ("x".to_owned(), (MetavariableKind::Expression, 2)),
+                ("rest".to_owned(), (MetavariableKind::Arguments, 1)),
+            ])
+        );
+    }
+
+    #[test]
+    fn replace_metavariables_without_name_errors() {
+        assert!(replace_metavariables("f($)").is_err());
+    }
+
+    #[test]
+    fn configure_with_undefined_replacement_metavariable_errors() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            "{ rule: 'rewrite', rewrites: [{ find: 'f($x)', replace: 'g($y)' }] }",
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'rewrites': metavariable `$y` of `g($y)` is not defined in the `find` pattern `f($x)`"
+        );
+    }
+
+    #[test]
+    fn configure_with_misplaced_variadic_metavariable_errors() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            "{ rule: 'rewrite', rewrites: [{ find: 'f($args..., 1)', replace: 'g($args...)' }] }",
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'rewrites': variadic metavariables (`$name...`) can only be used as the last argument of a call in `f($args..., 1)`"
+        );
+    }
+
+    #[test]
+    fn configure_with_metavariable_only_pattern_errors() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            "{ rule: 'rewrite', rewrites: [{ find: '$x', replace: '($x)' }] }",
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'rewrites': the `find` pattern `$x` cannot be only a metavariable"
+        );
+    }
+
+    #[test]
+    fn configure_with_missing_replace_errors() {
+        let result =
+            json5::from_str::<Box<dyn Rule>>("{ rule: 'rewrite', rewrites: [{ find: 'f()' }] }");
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'rewrites': each rewrite must define `find` and `replace`"
+        );
+    }
+}
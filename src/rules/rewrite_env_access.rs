@@ -0,0 +1,301 @@
+use std::ops;
+
+use crate::nodes::{
+    Block, Expression, FieldExpression, FunctionCall, FunctionStatement, Identifier,
+    IndexExpression, Prefix, Variable,
+};
+use crate::process::utils::is_valid_identifier;
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind, RulePropertyValue,
+};
+use crate::utils::log;
+
+const GLOBAL_TABLE: &str = "_G";
+const GET_ENVIRONMENT: &str = "getfenv";
+
+/// A processor that finds assignments to the `_G` global variable.
+#[derive(Default)]
+struct GlobalTableAssignment {
+    assigned: bool,
+    identifier_tracker: IdentifierTracker,
+}
+
+impl ops::Deref for GlobalTableAssignment {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for GlobalTableAssignment {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl GlobalTableAssignment {
+    fn verify_identifier(&mut self, identifier: &Identifier) {
+        if !self.assigned
+            && identifier.get_name() == GLOBAL_TABLE
+            && !self.is_identifier_used(GLOBAL_TABLE)
+        {
+            self.assigned = true;
+        }
+    }
+}
+
+impl NodeProcessor for GlobalTableAssignment {
+    fn process_variable(&mut self, variable: &mut Variable) {
+        if let Variable::Identifier(identifier) = variable {
+            self.verify_identifier(identifier);
+        }
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name();
+        if name.get_field_names().is_empty() && !name.has_method() {
+            self.verify_identifier(name.get_name());
+        }
+    }
+}
+
+/// A processor that replaces fields of the environment with global variables.
+struct EnvironmentAccessRewriter {
+    rewrite_global_table: bool,
+    identifier_tracker: IdentifierTracker,
+}
+
+impl ops::Deref for EnvironmentAccessRewriter {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for EnvironmentAccessRewriter {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl EnvironmentAccessRewriter {
+    fn new(rewrite_global_table: bool) -> Self {
+        Self {
+            rewrite_global_table,
+            identifier_tracker: IdentifierTracker::new(),
+        }
+    }
+
+    fn is_global(&self, identifier: &Identifier, name: &str) -> bool {
+        identifier.get_name() == name && !self.is_identifier_used(name)
+    }
+
+    fn is_environment_call(&self, call: &FunctionCall) -> bool {
+        call.get_method().is_none()
+            && call.get_arguments().is_empty()
+            && matches!(
+                call.get_prefix(),
+                Prefix::Identifier(identifier) if self.is_global(identifier, GET_ENVIRONMENT)
+            )
+    }
+
+    fn is_environment(&self, prefix: &Prefix) -> bool {
+        match prefix {
+            Prefix::Identifier(identifier) => {
+                self.rewrite_global_table && self.is_global(identifier, GLOBAL_TABLE)
+            }
+            Prefix::Call(call) => self.is_environment_call(call),
+            Prefix::Field(_) | Prefix::Index(_) | Prefix::Parenthese(_) => false,
+        }
+    }
+
+    fn convert_field(&self, field: &FieldExpression) -> Option<Identifier> {
+        let name = field.get_field();
+
+        if self.is_environment(field.get_prefix()) && !self.is_identifier_used(name.get_name()) {
+            Some(name.clone())
+        } else {
+            None
+        }
+    }
+
+    fn convert_index(&self, index: &IndexExpression) -> Option<Identifier> {
+        let name = match index.get_index() {
+            Expression::String(string) => string.get_value(),
+            _ => return None,
+        };
+
+        if is_valid_identifier(name, None)
+            && self.is_environment(index.get_prefix())
+            && !self.is_identifier_used(name)
+        {
+            Some(Identifier::new(name))
+        } else {
+            None
+        }
+    }
+}
+
+impl NodeProcessor for EnvironmentAccessRewriter {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        let global = match expression {
+            Expression::Field(field) => self.convert_field(field),
+            Expression::Index(index) => self.convert_index(index),
+            _ => None,
+        };
+        if let Some(global) = global {
+            *expression = global.into();
+        }
+    }
+
+    fn process_prefix_expression(&mut self, prefix: &mut Prefix) {
+        let global = match prefix {
+            Prefix::Field(field) => self.convert_field(field),
+            Prefix::Index(index) => self.convert_index(index),
+            _ => None,
+        };
+        if let Some(global) = global {
+            *prefix = global.into();
+        }
+    }
+
+    fn process_variable(&mut self, variable: &mut Variable) {
+        let global = match variable {
+            Variable::Field(field) => self.convert_field(field),
+            Variable::Index(index) => self.convert_index(index),
+            Variable::Identifier(_) => None,
+        };
+        if let Some(global) = global {
+            *variable = global.into();
+        }
+    }
+}
+
+pub const REWRITE_ENV_ACCESS_RULE_NAME: &str = "rewrite_env_access";
+
+/// A rule that converts fields accessed through `getfenv()` or `_G` into global variables.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RewriteEnvAccess {
+    global_table: bool,
+}
+
+impl Default for RewriteEnvAccess {
+    fn default() -> Self {
+        Self { global_table: true }
+    }
+}
+
+impl RewriteEnvAccess {
+    pub fn with_global_table(mut self, global_table: bool) -> Self {
+        self.global_table = global_table;
+        self
+    }
+
+    fn can_rewrite_global_table(&self, block: &mut Block, context: &Context) -> bool {
+        if !self.global_table {
+            return false;
+        }
+
+        if context.dynamic_environment(block).is_dynamic() {
+            log::debug!(
+                "[{}] `{}` keeps accesses to `{}` because the environment is dynamic",
+                context.current_path().display(),
+                REWRITE_ENV_ACCESS_RULE_NAME,
+                GLOBAL_TABLE,
+            );
+            return false;
+        }
+
+        let mut assignment = GlobalTableAssignment::default();
+        ScopeVisitor::visit_block(block, &mut assignment);
+
+        !assignment.assigned
+    }
+}
+
+impl FlawlessRule for RewriteEnvAccess {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let rewrite_global_table = self.can_rewrite_global_table(block, context);
+
+        let mut processor = EnvironmentAccessRewriter::new(rewrite_global_table);
+        ScopeVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for RewriteEnvAccess {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "global_table" => {
+                    self.global_table = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        REWRITE_ENV_ACCESS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["global_table"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::Boolean
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if !self.global_table {
+            properties.insert("global_table".to_owned(), RulePropertyValue::Boolean(false));
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> RewriteEnvAccess {
+        RewriteEnvAccess::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_rewrite_env_access", rule);
+    }
+
+    #[test]
+    fn serialize_rule_without_global_table() {
+        let rule: Box<dyn Rule> = Box::new(new_rule().with_global_table(false));
+
+        assert_json_snapshot!("rewrite_env_access_without_global_table", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'rewrite_env_access',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -0,0 +1,220 @@
+use std::cell::RefCell;
+use std::fmt;
+
+use crate::rules::{RuleConfigurationError, RuleProperties, REMOVE_COMPOUND_ASSIGNMENT_RULE_NAME};
+
+thread_local! {
+    static DEPRECATIONS: RefCell<Vec<RuleDeprecation>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Converts the properties written for a deprecated rule name into the properties of the
+/// rule that replaces it.
+pub type PropertiesTranslation =
+    fn(RuleProperties) -> Result<RuleProperties, RuleConfigurationError>;
+
+/// A deprecated rule name that is still accepted in configuration files. Rules loaded with
+/// an alias are created and serialized with the name of the rule that replaces it.
+#[derive(Debug, Clone, Copy)]
+pub struct RuleAlias {
+    name: &'static str,
+    target: &'static str,
+    translate_properties: Option<PropertiesTranslation>,
+}
+
+impl RuleAlias {
+    pub const fn new(name: &'static str, target: &'static str) -> Self {
+        Self {
+            name,
+            target,
+            translate_properties: None,
+        }
+    }
+
+    pub const fn with_properties_translation(mut self, translation: PropertiesTranslation) -> Self {
+        self.translate_properties = Some(translation);
+        self
+    }
+
+    /// The deprecated name of the rule.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The name of the rule that replaces the deprecated name.
+    pub fn target(&self) -> &'static str {
+        self.target
+    }
+
+    pub fn has_properties_translation(&self) -> bool {
+        self.translate_properties.is_some()
+    }
+
+    pub(crate) fn translate_properties(
+        &self,
+        properties: RuleProperties,
+    ) -> Result<RuleProperties, RuleConfigurationError> {
+        match self.translate_properties {
+            Some(translate) => translate(properties),
+            None => Ok(properties),
+        }
+    }
+
+    pub(crate) fn deprecation(&self) -> RuleDeprecation {
+        RuleDeprecation {
+            alias: self.name,
+            target: self.target,
+        }
+    }
+}
+
+const RULE_ALIASES: &[RuleAlias] = &[RuleAlias::new(
+    "remove_compound_assign",
+    REMOVE_COMPOUND_ASSIGNMENT_RULE_NAME,
+)];
+
+/// Returns the deprecated rule names that are still accepted.
+pub fn get_rule_aliases() -> &'static [RuleAlias] {
+    RULE_ALIASES
+}
+
+pub(crate) fn find_rule_alias(name: &str) -> Option<&'static RuleAlias> {
+    RULE_ALIASES.iter().find(|alias| alias.name == name)
+}
+
+/// A notice recorded when a rule is loaded with a deprecated name.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RuleDeprecation {
+    alias: &'static str,
+    target: &'static str,
+}
+
+impl RuleDeprecation {
+    pub fn alias(&self) -> &'static str {
+        self.alias
+    }
+
+    pub fn target(&self) -> &'static str {
+        self.target
+    }
+}
+
+impl fmt::Display for RuleDeprecation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rule `{}` is deprecated, use `{}` instead",
+            self.alias, self.target
+        )
+    }
+}
+
+pub(crate) fn record_deprecation(deprecation: RuleDeprecation) {
+    DEPRECATIONS.with(|deprecations| deprecations.borrow_mut().push(deprecation));
+}
+
+/// Returns the deprecated rule names used since the last call on the current thread, each
+/// name listed once.
+pub fn take_rule_deprecations() -> Vec<RuleDeprecation> {
+    let mut deprecations = DEPRECATIONS.with(|deprecations| deprecations.take());
+    deprecations.sort();
+    deprecations.dedup();
+    deprecations
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::rules::{get_all_rule_names, Rule, RulePropertyValue};
+
+    #[test]
+    fn every_alias_target_is_a_rule() {
+        let rule_names = get_all_rule_names();
+
+        for alias in get_rule_aliases() {
+            assert!(
+                rule_names.contains(&alias.target()),
+                "alias `{}` refers to unknown rule `{}`",
+                alias.name(),
+                alias.target()
+            );
+        }
+    }
+
+    #[test]
+    fn alias_names_are_not_rule_names() {
+        let rule_names = get_all_rule_names();
+
+        for alias in get_rule_aliases() {
+            assert!(!rule_names.contains(&alias.name()));
+        }
+    }
+
+    #[test]
+    fn parse_alias_creates_target_rule_and_records_deprecation() {
+        take_rule_deprecations();
+
+        let rule: Box<dyn Rule> = "remove_compound_assign".parse().unwrap();
+
+        assert_eq!(rule.get_name(), REMOVE_COMPOUND_ASSIGNMENT_RULE_NAME);
+        assert_eq!(
+            take_rule_deprecations(),
+            vec![find_rule_alias("remove_compound_assign")
+                .unwrap()
+                .deprecation()]
+        );
+    }
+
+    #[test]
+    fn take_rule_deprecations_lists_each_alias_once() {
+        take_rule_deprecations();
+
+        let _rules: Vec<Box<dyn Rule>> =
+            json5::from_str("['remove_compound_assign', { rule: 'remove_compound_assign' }]")
+                .unwrap();
+
+        assert_eq!(take_rule_deprecations().len(), 1);
+        assert!(take_rule_deprecations().is_empty());
+    }
+
+    #[test]
+    fn serialize_rule_loaded_with_alias_uses_target_name() {
+        let rule: Box<dyn Rule> = json5::from_str("'remove_compound_assign'").unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&rule).unwrap(),
+            "\"remove_compound_assignment\""
+        );
+    }
+
+    #[test]
+    fn translate_properties() {
+        let alias =
+            RuleAlias::new("old_rule", "new_rule").with_properties_translation(|mut properties| {
+                if let Some(value) = properties.remove("old_name") {
+                    properties.insert("new_name".to_owned(), value);
+                }
+                Ok(properties)
+            });
+        let mut properties = RuleProperties::new();
+        properties.insert("old_name".to_owned(), RulePropertyValue::Boolean(true));
+
+        let translated = alias.translate_properties(properties).unwrap();
+
+        assert_eq!(
+            translated.get("new_name"),
+            Some(&RulePropertyValue::Boolean(true))
+        );
+        assert!(!translated.contains_key("old_name"));
+    }
+
+    #[test]
+    fn display_deprecation() {
+        let deprecation = RuleAlias::new("old_rule", "new_rule").deprecation();
+
+        assert_eq!(
+            deprecation.to_string(),
+            "rule `old_rule` is deprecated, use `new_rule` instead"
+        );
+    }
+}
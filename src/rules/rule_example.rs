@@ -0,0 +1,38 @@
+#[cfg(feature = "serde-config")]
+use serde::Serialize;
+
+use super::{RuleProperties, RulePropertyValue};
+
+/// A snippet of code that shows the effect of a rule, with the properties used to configure
+/// the rule. The output of an example is not written by hand: it is computed by applying the
+/// rule on the snippet, so that examples always match the behavior of the rule.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+pub struct RuleExample {
+    code: &'static str,
+    properties: RuleProperties,
+}
+
+impl RuleExample {
+    pub fn new(code: &'static str) -> Self {
+        Self {
+            code,
+            properties: RuleProperties::new(),
+        }
+    }
+
+    pub fn with_property(mut self, name: &str, value: impl Into<RulePropertyValue>) -> Self {
+        self.properties.insert(name.to_owned(), value.into());
+        self
+    }
+
+    #[inline]
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    #[inline]
+    pub fn properties(&self) -> &RuleProperties {
+        &self.properties
+    }
+}
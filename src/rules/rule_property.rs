@@ -1,16 +1,22 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::time::Duration;
 
 use regex::Regex;
+#[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
 
-use super::{require::PathRequireMode, RequireMode, RobloxRequireMode, RuleConfigurationError};
+use super::{
+    parse_byte_size, parse_duration, require::PathRequireMode, RequireMode, RobloxRequireMode,
+    RuleConfigurationError,
+};
 
-pub type RuleProperties = HashMap<String, RulePropertyValue>;
+pub type RuleProperties = BTreeMap<String, RulePropertyValue>;
 
 /// In order to be able to weakly-type the properties of any rule, this enum makes it possible to
 /// easily use serde to gather the value associated with a property.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
-#[serde(untagged, rename_all = "snake_case")]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-config", serde(untagged, rename_all = "snake_case"))]
 pub enum RulePropertyValue {
     Boolean(bool),
     String(String),
@@ -18,9 +24,36 @@ pub enum RulePropertyValue {
     Float(f64),
     StringList(Vec<String>),
     RequireMode(RequireMode),
+    StringMap(BTreeMap<String, String>),
+    StringMapList(Vec<BTreeMap<String, String>>),
+    ValueMap(BTreeMap<String, RulePropertyValue>),
     None,
 }
 
+/// The kind of value accepted by a rule property. It is used to validate configuration files
+/// and to describe the rule properties in the configuration schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RulePropertyKind {
+    Boolean,
+    String,
+    /// A string that must be one of the given values.
+    Choice(&'static [&'static str]),
+    Usize,
+    Float,
+    /// A number of bytes, or a string with a unit (like `"1 MB"` or `"512KiB"`).
+    ByteSize,
+    /// A number of seconds, or a string with a unit (like `"30s"` or `"2min"`).
+    Duration,
+    StringList,
+    StringMap,
+    StringMapList,
+    /// A map of literal values (booleans, numbers, strings or `null`).
+    ValueMap,
+    RequireMode,
+    /// Any value is accepted (the rule validates the value itself).
+    Any,
+}
+
 impl RulePropertyValue {
     pub(crate) fn expect_bool(self, key: &str) -> Result<bool, RuleConfigurationError> {
         if let Self::Boolean(value) = self {
@@ -38,6 +71,50 @@ impl RulePropertyValue {
         }
     }
 
+    pub(crate) fn expect_usize(self, key: &str) -> Result<usize, RuleConfigurationError> {
+        if let Self::Usize(value) = self {
+            Ok(value)
+        } else {
+            Err(RuleConfigurationError::UsizeExpected(key.to_owned()))
+        }
+    }
+
+    /// Reads a size in bytes, given as a number of bytes or as a string with a unit (like
+    /// `"1 MB"` or `"512KiB"`).
+    pub fn expect_byte_size(&self, key: &str) -> Result<u64, RuleConfigurationError> {
+        match self {
+            Self::Usize(value) => Ok(*value as u64),
+            Self::String(value) => {
+                parse_byte_size(value).map_err(|message| RuleConfigurationError::UnexpectedValue {
+                    property: key.to_owned(),
+                    message,
+                })
+            }
+            _ => Err(RuleConfigurationError::ByteSizeExpected(key.to_owned())),
+        }
+    }
+
+    /// Reads a duration, given as a number of seconds or as a string with a unit (like
+    /// `"30s"` or `"2min"`).
+    pub fn expect_duration(&self, key: &str) -> Result<Duration, RuleConfigurationError> {
+        match self {
+            Self::Usize(value) => Ok(Duration::from_secs(*value as u64)),
+            Self::Float(value) => Duration::try_from_secs_f64(*value).map_err(|_| {
+                RuleConfigurationError::UnexpectedValue {
+                    property: key.to_owned(),
+                    message: format!("`{}` is not a valid number of seconds", value),
+                }
+            }),
+            Self::String(value) => {
+                parse_duration(value).map_err(|message| RuleConfigurationError::UnexpectedValue {
+                    property: key.to_owned(),
+                    message,
+                })
+            }
+            _ => Err(RuleConfigurationError::DurationExpected(key.to_owned())),
+        }
+    }
+
     pub(crate) fn expect_string_list(
         self,
         key: &str,
@@ -49,6 +126,46 @@ impl RulePropertyValue {
         }
     }
 
+    pub(crate) fn expect_string_map(
+        self,
+        key: &str,
+    ) -> Result<BTreeMap<String, String>, RuleConfigurationError> {
+        if let Self::StringMap(value) = self {
+            Ok(value)
+        } else {
+            Err(RuleConfigurationError::StringMapExpected(key.to_owned()))
+        }
+    }
+
+    /// Returns a map of values. A map where every value is a string is also accepted.
+    pub(crate) fn expect_value_map(
+        self,
+        key: &str,
+    ) -> Result<BTreeMap<String, RulePropertyValue>, RuleConfigurationError> {
+        match self {
+            Self::ValueMap(value) => Ok(value),
+            Self::StringMap(value) => Ok(value
+                .into_iter()
+                .map(|(key, value)| (key, Self::String(value)))
+                .collect()),
+            _ => Err(RuleConfigurationError::UnexpectedValueType(key.to_owned())),
+        }
+    }
+
+    pub(crate) fn expect_string_map_list(
+        self,
+        key: &str,
+    ) -> Result<Vec<BTreeMap<String, String>>, RuleConfigurationError> {
+        match self {
+            Self::StringMapList(value) => Ok(value),
+            // an empty list is deserialized as a list of strings
+            Self::StringList(value) if value.is_empty() => Ok(Vec::new()),
+            _ => Err(RuleConfigurationError::StringMapListExpected(
+                key.to_owned(),
+            )),
+        }
+    }
+
     pub(crate) fn expect_regex_list(self, key: &str) -> Result<Vec<Regex>, RuleConfigurationError> {
         if let Self::StringList(value) = self {
             value
@@ -120,6 +237,24 @@ impl From<f64> for RulePropertyValue {
     }
 }
 
+impl From<BTreeMap<String, String>> for RulePropertyValue {
+    fn from(value: BTreeMap<String, String>) -> Self {
+        Self::StringMap(value)
+    }
+}
+
+impl From<Vec<BTreeMap<String, String>>> for RulePropertyValue {
+    fn from(value: Vec<BTreeMap<String, String>>) -> Self {
+        Self::StringMapList(value)
+    }
+}
+
+impl From<BTreeMap<String, RulePropertyValue>> for RulePropertyValue {
+    fn from(value: BTreeMap<String, RulePropertyValue>) -> Self {
+        Self::ValueMap(value)
+    }
+}
+
 impl From<&RequireMode> for RulePropertyValue {
     fn from(value: &RequireMode) -> Self {
         match value {
@@ -202,6 +337,24 @@ mod test {
         assert_eq!(RulePropertyValue::from(1.0), RulePropertyValue::Float(1.0));
     }
 
+    #[test]
+    fn from_string_map() {
+        let map: BTreeMap<String, String> = [("a".to_owned(), "b".to_owned())].into();
+        assert_eq!(
+            RulePropertyValue::from(map.clone()),
+            RulePropertyValue::StringMap(map)
+        );
+    }
+
+    #[test]
+    fn from_string_map_list() {
+        let list: Vec<BTreeMap<String, String>> = vec![[("a".to_owned(), "b".to_owned())].into()];
+        assert_eq!(
+            RulePropertyValue::from(list.clone()),
+            RulePropertyValue::StringMapList(list)
+        );
+    }
+
     #[test]
     fn from_boolean_option_some() {
         let bool = Some(true);
@@ -216,4 +369,57 @@ mod test {
         let bool: Option<bool> = None;
         assert_eq!(RulePropertyValue::from(bool), RulePropertyValue::None);
     }
+
+    #[test]
+    fn expect_byte_size_from_number() {
+        assert_eq!(
+            RulePropertyValue::from(512usize).expect_byte_size("size"),
+            Ok(512)
+        );
+    }
+
+    #[test]
+    fn expect_byte_size_from_string_with_unit() {
+        assert_eq!(
+            RulePropertyValue::from("2 KiB").expect_byte_size("size"),
+            Ok(2048)
+        );
+    }
+
+    #[test]
+    fn expect_byte_size_from_boolean_errors() {
+        assert_eq!(
+            RulePropertyValue::from(true).expect_byte_size("size"),
+            Err(RuleConfigurationError::ByteSizeExpected("size".to_owned()))
+        );
+    }
+
+    #[test]
+    fn expect_duration_from_number_of_seconds() {
+        assert_eq!(
+            RulePropertyValue::from(1.5).expect_duration("timeout"),
+            Ok(Duration::from_millis(1500))
+        );
+    }
+
+    #[test]
+    fn expect_duration_from_string_with_unit() {
+        assert_eq!(
+            RulePropertyValue::from("2min").expect_duration("timeout"),
+            Ok(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn expect_duration_with_ambiguous_unit_errors() {
+        assert_eq!(
+            RulePropertyValue::from("5m").expect_duration("timeout"),
+            Err(RuleConfigurationError::UnexpectedValue {
+                property: "timeout".to_owned(),
+                message:
+                    "ambiguous unit `m` in `5m` (use `min` for minutes or `ms` for milliseconds)"
+                        .to_owned(),
+            })
+        );
+    }
 }
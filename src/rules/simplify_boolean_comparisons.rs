@@ -0,0 +1,530 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::nodes::{
+    BinaryExpression, BinaryOperator, Block, Expression, FunctionExpression, FunctionStatement,
+    GenericForStatement, Identifier, IfExpression, IfStatement, LocalAssignStatement,
+    LocalFunctionStatement, NumericForStatement, RepeatStatement, Statement, Type, TypedIdentifier,
+    UnaryExpression, UnaryOperator, WhileStatement,
+};
+use crate::process::{Evaluator, NodeProcessor, NodeVisitor, Scope, ScopeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind, RulePropertyValue,
+};
+
+/// Defines which comparisons the `simplify_boolean_comparisons` rule rewrites.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BooleanComparisonsMode {
+    /// Only rewrites comparisons that keep the exact same result for any value.
+    #[default]
+    Exact,
+    /// Also rewrites comparisons of variables known to be booleans, like `x == true` into
+    /// `x`, in condition positions.
+    AssumeBoolean,
+}
+
+impl BooleanComparisonsMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "exact" => Some(Self::Exact),
+            "assume_boolean" => Some(Self::AssumeBoolean),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::AssumeBoolean => "assume_boolean",
+        }
+    }
+}
+
+fn get_boolean_literal(expression: &Expression) -> Option<bool> {
+    match expression {
+        Expression::True(_) => Some(true),
+        Expression::False(_) => Some(false),
+        _ => None,
+    }
+}
+
+fn strip_parentheses(expression: &Expression) -> &Expression {
+    match expression {
+        Expression::Parenthese(parenthese) => strip_parentheses(parenthese.inner_expression()),
+        _ => expression,
+    }
+}
+
+fn is_comparison(operator: BinaryOperator) -> bool {
+    matches!(operator, BinaryOperator::Equal | BinaryOperator::NotEqual)
+}
+
+/// Returns true for `x == true`, `false ~= x` and similar comparisons with a boolean literal.
+fn compares_with_boolean_literal(binary: &BinaryExpression) -> bool {
+    is_comparison(binary.operator())
+        && (get_boolean_literal(binary.left()).is_some()
+            || get_boolean_literal(binary.right()).is_some())
+}
+
+/// Returns true for the types that only accept `true` and `false`. Optional types like
+/// `boolean?` also accept `nil`, which compares differently than `false`.
+fn is_boolean_type(r#type: &Type) -> bool {
+    match r#type {
+        Type::Name(name) => {
+            name.get_type_name().get_name() == "boolean" && name.get_type_parameters().is_none()
+        }
+        Type::True(_) | Type::False(_) => true,
+        Type::Parenthese(parenthese) => is_boolean_type(parenthese.get_inner_type()),
+        _ => false,
+    }
+}
+
+fn is_annotated_boolean(identifier: &TypedIdentifier) -> bool {
+    identifier.get_type().is_some_and(is_boolean_type)
+}
+
+struct Processor<'a> {
+    mode: BooleanComparisonsMode,
+    boolean_variables: &'a HashSet<String>,
+    evaluator: Evaluator,
+    /// The local variables of each scope, associated with true when they are annotated with
+    /// a boolean type.
+    scopes: Vec<HashMap<String, bool>>,
+    /// The variables about to be declared, in the reverse order of their declaration.
+    pending_declarations: Vec<(String, bool)>,
+}
+
+impl<'a> Processor<'a> {
+    fn new(mode: BooleanComparisonsMode, boolean_variables: &'a HashSet<String>) -> Self {
+        Self {
+            mode,
+            boolean_variables,
+            evaluator: Evaluator::default(),
+            scopes: vec![HashMap::new()],
+            pending_declarations: Vec::new(),
+        }
+    }
+
+    fn expect_declarations<'b>(&mut self, declarations: impl Iterator<Item = (&'b str, bool)>) {
+        let declarations: Vec<_> = declarations
+            .map(|(name, is_boolean)| (name.to_owned(), is_boolean))
+            .collect();
+        self.pending_declarations
+            .extend(declarations.into_iter().rev());
+    }
+
+    fn expect_typed_declarations<'b>(
+        &mut self,
+        identifiers: impl Iterator<Item = &'b TypedIdentifier>,
+    ) {
+        self.expect_declarations(
+            identifiers.map(|identifier| (identifier.get_name(), is_annotated_boolean(identifier))),
+        );
+    }
+
+    fn declare(&mut self, name: &str) {
+        let is_boolean = match self.pending_declarations.last() {
+            Some((pending_name, is_boolean)) if pending_name == name => {
+                let is_boolean = *is_boolean;
+                self.pending_declarations.pop();
+                is_boolean
+            }
+            _ => false,
+        };
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_owned(), is_boolean);
+        }
+    }
+
+    /// Returns true when the identifier is listed in the `boolean_variables` property or
+    /// refers to a local variable annotated with a boolean type. Names in `ignored_locals`
+    /// are declared after the current scope information and are only looked up in the list.
+    fn is_boolean_variable(&self, expression: &Expression, ignored_locals: &[String]) -> bool {
+        let name = match expression {
+            Expression::Identifier(identifier) => identifier.get_name(),
+            _ => return false,
+        };
+
+        if self.boolean_variables.contains(name) {
+            return true;
+        }
+
+        if ignored_locals.iter().any(|local| local == name) {
+            return false;
+        }
+
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Rewrites comparisons where both operands are boolean literals and negated
+    /// comparisons with a boolean literal.
+    fn simplify_exact(&self, expression: &mut Expression) {
+        let replacement = match expression {
+            Expression::Binary(binary)
+                if is_comparison(binary.operator())
+                    && get_boolean_literal(binary.left()).is_some()
+                    && get_boolean_literal(binary.right()).is_some() =>
+            {
+                self.evaluator.evaluate(expression).to_expression()
+            }
+            Expression::Unary(unary) if unary.operator() == UnaryOperator::Not => {
+                match strip_parentheses(unary.get_expression()) {
+                    Expression::Binary(binary) if compares_with_boolean_literal(binary) => {
+                        let mut binary = binary.as_ref().clone();
+                        binary.set_operator(match binary.operator() {
+                            BinaryOperator::Equal => BinaryOperator::NotEqual,
+                            _ => BinaryOperator::Equal,
+                        });
+                        Some(binary.into())
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(replacement) = replacement {
+            *expression = replacement;
+        }
+    }
+
+    /// Rewrites the comparisons of boolean variables in an expression whose result is only
+    /// used as a condition.
+    fn simplify_condition(&self, condition: &mut Expression, ignored_locals: &[String]) {
+        if self.mode != BooleanComparisonsMode::AssumeBoolean {
+            return;
+        }
+
+        let replacement = match condition {
+            Expression::Parenthese(parenthese) => {
+                let inner = parenthese.mutate_inner_expression();
+                let is_comparison = matches!(
+                    inner,
+                    Expression::Binary(binary) if is_comparison(binary.operator())
+                );
+
+                self.simplify_condition(inner, ignored_locals);
+
+                // the parentheses are not needed anymore when the comparison was replaced
+                // with a primary expression (like `not (x == true)` to `not x`)
+                let is_primary = matches!(
+                    inner,
+                    Expression::Identifier(_)
+                        | Expression::Field(_)
+                        | Expression::Index(_)
+                        | Expression::Call(_)
+                        | Expression::Parenthese(_)
+                );
+
+                (is_comparison && is_primary).then(|| inner.clone())
+            }
+            Expression::Unary(unary) if unary.operator() == UnaryOperator::Not => {
+                self.simplify_condition(unary.mutate_expression(), ignored_locals);
+                None
+            }
+            Expression::Binary(binary)
+                if matches!(binary.operator(), BinaryOperator::And | BinaryOperator::Or) =>
+            {
+                self.simplify_condition(binary.mutate_left(), ignored_locals);
+                self.simplify_condition(binary.mutate_right(), ignored_locals);
+                None
+            }
+            Expression::Binary(binary) if is_comparison(binary.operator()) => {
+                let (variable, literal) = match (
+                    get_boolean_literal(binary.left()),
+                    get_boolean_literal(binary.right()),
+                ) {
+                    (None, Some(literal)) => (binary.left(), literal),
+                    (Some(literal), None) => (binary.right(), literal),
+                    _ => return,
+                };
+
+                if !self.is_boolean_variable(variable, ignored_locals) {
+                    return;
+                }
+
+                // `x == true` and `x ~= false` are true when `x` is true
+                let keeps_value = literal == (binary.operator() == BinaryOperator::Equal);
+                let variable = variable.clone();
+
+                Some(if keeps_value {
+                    variable
+                } else {
+                    UnaryExpression::new(UnaryOperator::Not, variable).into()
+                })
+            }
+            _ => None,
+        };
+
+        if let Some(replacement) = replacement {
+            *condition = replacement;
+        }
+    }
+}
+
+impl Scope for Processor<'_> {
+    fn push(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.declare(identifier.get_name());
+    }
+
+    fn insert_self(&mut self) {
+        self.declare("self");
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, _value: Option<&mut Expression>) {
+        self.declare(identifier.get_name());
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.declare(function.get_name());
+    }
+}
+
+impl NodeProcessor for Processor<'_> {
+    fn process_expression(&mut self, expression: &mut Expression) {
+        self.simplify_exact(expression);
+    }
+
+    fn process_if_statement(&mut self, if_statement: &mut IfStatement) {
+        for branch in if_statement.mutate_branches() {
+            self.simplify_condition(branch.mutate_condition(), &[]);
+        }
+    }
+
+    fn process_while_statement(&mut self, statement: &mut WhileStatement) {
+        self.simplify_condition(statement.mutate_condition(), &[]);
+    }
+
+    fn process_repeat_statement(&mut self, statement: &mut RepeatStatement) {
+        // the condition can access the locals declared in the block of the loop
+        let block_locals: Vec<_> = statement
+            .get_block()
+            .iter_statements()
+            .flat_map(|statement| match statement {
+                Statement::LocalAssign(assign) => assign
+                    .get_variables()
+                    .iter()
+                    .map(|variable| variable.get_name().to_owned())
+                    .collect(),
+                Statement::LocalFunction(function) => vec![function.get_name().to_owned()],
+                _ => Vec::new(),
+            })
+            .collect();
+
+        self.simplify_condition(statement.mutate_condition(), &block_locals);
+    }
+
+    fn process_if_expression(&mut self, if_expression: &mut IfExpression) {
+        self.simplify_condition(if_expression.mutate_condition(), &[]);
+        for branch in if_expression.iter_mut_branches() {
+            self.simplify_condition(branch.mutate_condition(), &[]);
+        }
+    }
+
+    fn process_local_assign_statement(&mut self, assign: &mut LocalAssignStatement) {
+        self.expect_typed_declarations(assign.get_variables().iter());
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        self.expect_typed_declarations(function.iter_parameters());
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        self.expect_typed_declarations(function.iter_parameters());
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        self.expect_typed_declarations(function.iter_parameters());
+    }
+
+    fn process_numeric_for_statement(&mut self, numeric_for: &mut NumericForStatement) {
+        self.expect_declarations(std::iter::once((
+            numeric_for.get_identifier().get_name(),
+            false,
+        )));
+    }
+
+    fn process_generic_for_statement(&mut self, generic_for: &mut GenericForStatement) {
+        self.expect_declarations(
+            generic_for
+                .get_identifiers()
+                .iter()
+                .map(|identifier| (identifier.get_name(), false)),
+        );
+    }
+}
+
+pub const SIMPLIFY_BOOLEAN_COMPARISONS_RULE_NAME: &str = "simplify_boolean_comparisons";
+
+/// A rule that removes redundant comparisons with boolean literals, like `true == false` or
+/// `not (x == true)`. With the `assume_boolean` mode, comparisons of variables known to be
+/// booleans are also rewritten in conditions (like `if x == true then` to `if x then`).
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SimplifyBooleanComparisons {
+    mode: BooleanComparisonsMode,
+    boolean_variables: HashSet<String>,
+}
+
+impl SimplifyBooleanComparisons {
+    pub fn with_mode(mut self, mode: BooleanComparisonsMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Adds a variable name that is assumed to always contain `true` or `false`, for the
+    /// `assume_boolean` mode.
+    pub fn with_boolean_variable(mut self, name: impl Into<String>) -> Self {
+        self.boolean_variables.insert(name.into());
+        self
+    }
+}
+
+impl FlawlessRule for SimplifyBooleanComparisons {
+    fn flawless_process(&self, block: &mut Block, _: &Context) {
+        let mut processor = Processor::new(self.mode, &self.boolean_variables);
+        ScopeVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for SimplifyBooleanComparisons {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "mode" => {
+                    let mode = value.expect_string(&key)?;
+                    self.mode = BooleanComparisonsMode::parse(&mode).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: "mode".to_owned(),
+                            message: format!(
+                                "invalid value `{}` (must be `exact` or `assume_boolean`)",
+                                mode
+                            ),
+                        }
+                    })?;
+                }
+                "boolean_variables" => {
+                    self.boolean_variables = value.expect_string_list(&key)?.into_iter().collect();
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        SIMPLIFY_BOOLEAN_COMPARISONS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["boolean_variables", "mode"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "mode" => RulePropertyKind::Choice(&["exact", "assume_boolean"]),
+            _ => RulePropertyKind::StringList,
+        }
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.mode != BooleanComparisonsMode::default() {
+            properties.insert("mode".to_owned(), self.mode.as_str().into());
+        }
+        if !self.boolean_variables.is_empty() {
+            let mut names: Vec<_> = self.boolean_variables.iter().cloned().collect();
+            names.sort();
+            properties.insert(
+                "boolean_variables".to_owned(),
+                RulePropertyValue::StringList(names),
+            );
+        }
+
+        properties
+    }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if self.mode == BooleanComparisonsMode::AssumeBoolean {
+            properties.push("mode");
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+    use crate::test_utils::{assert_rule_config_roundtrip, assert_rule_output};
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> SimplifyBooleanComparisons {
+        SimplifyBooleanComparisons::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_simplify_boolean_comparisons", rule);
+    }
+
+    #[test]
+    fn serialize_assume_boolean_rule() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .with_mode(BooleanComparisonsMode::AssumeBoolean)
+                .with_boolean_variable("enabled")
+                .with_boolean_variable("done"),
+        );
+
+        assert_json_snapshot!("assume_boolean_simplify_boolean_comparisons", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_mode_errors() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'simplify_boolean_comparisons',
+            mode: 'truthy',
+        }"#,
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'mode': invalid value `truthy` (must be `exact` or `assume_boolean`)"
+        );
+    }
+
+    #[test]
+    fn config_roundtrip() {
+        assert_rule_config_roundtrip(
+            "{ rule: 'simplify_boolean_comparisons', mode: 'assume_boolean', boolean_variables: ['done'] }",
+        );
+    }
+
+    #[test]
+    fn compare_boolean_literals() {
+        assert_rule_output(&new_rule(), "return true == false", "return false");
+    }
+}
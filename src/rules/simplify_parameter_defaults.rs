@@ -0,0 +1,533 @@
+use std::collections::HashSet;
+
+use crate::nodes::{
+    AssignStatement, BinaryExpression, BinaryOperator, Block, Expression, FunctionExpression,
+    FunctionStatement, Identifier, IfExpression, IfStatement, LocalFunctionStatement, Statement,
+    UnaryExpression, UnaryOperator, Variable,
+};
+use crate::process::{
+    IdentifierTracker, LuaVersion, NodeProcessor, NodeVisitor, Scope, ScopeVisitor,
+};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind,
+};
+
+/// Defines in which direction the `simplify_parameter_defaults` rule rewrites statements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParameterDefaultsMode {
+    /// Rewrites `if x == nil then x = default end` into `x = x or default`.
+    #[default]
+    Simplify,
+    /// Rewrites `x = x or default` into an explicit check.
+    Expand,
+}
+
+impl ParameterDefaultsMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "simplify" => Some(Self::Simplify),
+            "expand" => Some(Self::Expand),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Simplify => "simplify",
+            Self::Expand => "expand",
+        }
+    }
+}
+
+/// The ways a default value can be assigned to a variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DefaultingForm {
+    /// `if x == nil then x = default end`
+    NilCheck,
+    /// `if not x then x = default end`
+    FalsyCheck,
+    /// `x = x or default`
+    Or,
+}
+
+struct Defaulting<'a> {
+    form: DefaultingForm,
+    name: &'a str,
+    default: &'a Expression,
+}
+
+fn get_identifier_name(expression: &Expression) -> Option<&str> {
+    match expression {
+        Expression::Identifier(identifier) => Some(identifier.get_name()),
+        _ => None,
+    }
+}
+
+/// Returns the name of the variable and the value of an assignment with a single identifier.
+fn get_single_assignment(statement: &Statement) -> Option<(&str, &Expression)> {
+    match statement {
+        Statement::Assign(assign) if assign.variables_len() == 1 && assign.values_len() == 1 => {
+            match (
+                assign.get_variables().first()?,
+                assign.iter_values().next()?,
+            ) {
+                (Variable::Identifier(identifier), value) => Some((identifier.get_name(), value)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn get_checked_name(condition: &Expression) -> Option<(DefaultingForm, &str)> {
+    match condition {
+        Expression::Binary(binary) if binary.operator() == BinaryOperator::Equal => {
+            match (binary.left(), binary.right()) {
+                (Expression::Nil(_), other) | (other, Expression::Nil(_)) => {
+                    get_identifier_name(other).map(|name| (DefaultingForm::NilCheck, name))
+                }
+                _ => None,
+            }
+        }
+        Expression::Unary(unary) if unary.operator() == UnaryOperator::Not => {
+            get_identifier_name(unary.get_expression())
+                .map(|name| (DefaultingForm::FalsyCheck, name))
+        }
+        _ => None,
+    }
+}
+
+fn get_defaulting(statement: &Statement) -> Option<Defaulting<'_>> {
+    match statement {
+        Statement::If(if_statement) => {
+            if if_statement.branch_count() != 1 || if_statement.get_else_block().is_some() {
+                return None;
+            }
+            let branch = if_statement.get_branches().first()?;
+            let block = branch.get_block();
+            if block.statements_len() != 1 || block.get_last_statement().is_some() {
+                return None;
+            }
+
+            let (form, checked_name) = get_checked_name(branch.get_condition())?;
+            let (name, default) = get_single_assignment(block.first_statement()?)?;
+
+            (name == checked_name).then_some(Defaulting {
+                form,
+                name,
+                default,
+            })
+        }
+        Statement::Assign(_) => {
+            let (name, value) = get_single_assignment(statement)?;
+            match value {
+                Expression::Binary(binary)
+                    if binary.operator() == BinaryOperator::Or
+                        && get_identifier_name(binary.left()) == Some(name) =>
+                {
+                    Some(Defaulting {
+                        form: DefaultingForm::Or,
+                        name,
+                        default: binary.right(),
+                    })
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn or_assignment(name: &str, default: Expression) -> Statement {
+    AssignStatement::from_variable(
+        Identifier::new(name),
+        BinaryExpression::new(BinaryOperator::Or, Identifier::new(name), default),
+    )
+    .into()
+}
+
+fn nil_check_assignment(name: &str, default: Expression) -> Statement {
+    AssignStatement::from_variable(
+        Identifier::new(name),
+        IfExpression::new(
+            BinaryExpression::new(
+                BinaryOperator::Equal,
+                Identifier::new(name),
+                Expression::nil(),
+            ),
+            default,
+            Identifier::new(name),
+        ),
+    )
+    .into()
+}
+
+fn check_statement(condition: impl Into<Expression>, name: &str, default: Expression) -> Statement {
+    IfStatement::create(
+        condition,
+        AssignStatement::from_variable(Identifier::new(name), default),
+    )
+    .into()
+}
+
+struct Processor {
+    mode: ParameterDefaultsMode,
+    assume_no_false: bool,
+    anywhere: bool,
+    luau: bool,
+    identifier_tracker: IdentifierTracker,
+}
+
+impl Processor {
+    /// `x = x or default` only differs from `if x == nil then x = default end` when `x` is
+    /// `false`, which does not matter when the default value is also `false`.
+    fn ignores_false(&self, default: &Expression) -> bool {
+        self.assume_no_false || matches!(default, Expression::False(_))
+    }
+
+    fn rewrite(&self, defaulting: Defaulting) -> Option<Statement> {
+        let name = defaulting.name;
+        let default = defaulting.default.clone();
+
+        match (self.mode, defaulting.form) {
+            // `not x` is true for the exact values where `x or default` uses the default
+            (ParameterDefaultsMode::Simplify, DefaultingForm::FalsyCheck) => {
+                Some(or_assignment(name, default))
+            }
+            (ParameterDefaultsMode::Simplify, DefaultingForm::NilCheck) => {
+                if self.ignores_false(&default) {
+                    Some(or_assignment(name, default))
+                } else if self.luau {
+                    Some(nil_check_assignment(name, default))
+                } else {
+                    None
+                }
+            }
+            (ParameterDefaultsMode::Expand, DefaultingForm::Or) => {
+                if self.ignores_false(&default) {
+                    Some(check_statement(
+                        BinaryExpression::new(
+                            BinaryOperator::Equal,
+                            Identifier::new(name),
+                            Expression::nil(),
+                        ),
+                        name,
+                        default,
+                    ))
+                } else {
+                    Some(check_statement(
+                        UnaryExpression::new(UnaryOperator::Not, Identifier::new(name)),
+                        name,
+                        default,
+                    ))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn process_function_body<'a>(
+        &self,
+        parameters: impl Iterator<Item = &'a str>,
+        block: &mut Block,
+    ) {
+        if self.anywhere {
+            return;
+        }
+
+        let parameters: HashSet<_> = parameters.collect();
+
+        for statement in block.iter_mut_statements() {
+            let Some(defaulting) = get_defaulting(statement) else {
+                break;
+            };
+            if !parameters.contains(defaulting.name) {
+                break;
+            }
+
+            if let Some(new_statement) = self.rewrite(defaulting) {
+                *statement = new_statement;
+            }
+        }
+    }
+}
+
+impl Scope for Processor {
+    fn push(&mut self) {
+        self.identifier_tracker.push()
+    }
+
+    fn pop(&mut self) {
+        self.identifier_tracker.pop()
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.identifier_tracker.insert(identifier);
+    }
+
+    fn insert_self(&mut self) {
+        self.identifier_tracker.insert_self();
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, value: Option<&mut Expression>) {
+        self.identifier_tracker.insert_local(identifier, value);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.identifier_tracker.insert_local_function(function);
+    }
+}
+
+impl NodeProcessor for Processor {
+    fn process_statement(&mut self, statement: &mut Statement) {
+        if !self.anywhere {
+            return;
+        }
+
+        // assigning a global may call the metamethods of the environment, so only locals
+        // can be assigned unconditionally
+        let new_statement = get_defaulting(statement)
+            .filter(|defaulting| self.identifier_tracker.is_identifier_used(defaulting.name))
+            .and_then(|defaulting| self.rewrite(defaulting));
+
+        if let Some(new_statement) = new_statement {
+            *statement = new_statement;
+        }
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        let parameters: Vec<_> = function
+            .iter_parameters()
+            .map(|parameter| parameter.get_name().to_owned())
+            .collect();
+        self.process_function_body(
+            parameters.iter().map(String::as_str),
+            function.mutate_block(),
+        );
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let parameters: Vec<_> = function
+            .iter_parameters()
+            .map(|parameter| parameter.get_name().to_owned())
+            .collect();
+        self.process_function_body(
+            parameters.iter().map(String::as_str),
+            function.mutate_block(),
+        );
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        let parameters: Vec<_> = function
+            .iter_parameters()
+            .map(|parameter| parameter.get_name().to_owned())
+            .collect();
+        self.process_function_body(
+            parameters.iter().map(String::as_str),
+            function.mutate_block(),
+        );
+    }
+}
+
+pub const SIMPLIFY_PARAMETER_DEFAULTS_RULE_NAME: &str = "simplify_parameter_defaults";
+
+/// A rule that rewrites `if x == nil then x = default end` into `x = x or default` when both
+/// forms have the same behavior (or the inverse with the `expand` mode).
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SimplifyParameterDefaults {
+    mode: ParameterDefaultsMode,
+    assume_no_false: bool,
+    anywhere: bool,
+}
+
+impl SimplifyParameterDefaults {
+    pub fn with_mode(mut self, mode: ParameterDefaultsMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Assumes that the variables are never `false`, so that `x == nil` and `not x` can
+    /// be used in place of each other.
+    pub fn with_assume_no_false(mut self, value: bool) -> Self {
+        self.assume_no_false = value;
+        self
+    }
+
+    /// Rewrites the statements found anywhere when they assign a local variable, instead
+    /// of only the statements at the top of a function that assign a parameter.
+    pub fn with_anywhere(mut self, value: bool) -> Self {
+        self.anywhere = value;
+        self
+    }
+}
+
+impl FlawlessRule for SimplifyParameterDefaults {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let luau = context
+            .environment()
+            .target()
+            .is_some_and(|target| target.lua_version() == LuaVersion::Luau);
+
+        let mut processor = Processor {
+            mode: self.mode,
+            assume_no_false: self.assume_no_false,
+            anywhere: self.anywhere,
+            luau,
+            identifier_tracker: IdentifierTracker::new(),
+        };
+        ScopeVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for SimplifyParameterDefaults {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "mode" => {
+                    let mode = value.expect_string(&key)?;
+                    self.mode = ParameterDefaultsMode::parse(&mode).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: "mode".to_owned(),
+                            message: format!(
+                                "invalid value `{}` (must be `simplify` or `expand`)",
+                                mode
+                            ),
+                        }
+                    })?;
+                }
+                "assume_no_false" => {
+                    self.assume_no_false = value.expect_bool(&key)?;
+                }
+                "anywhere" => {
+                    self.anywhere = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        SIMPLIFY_PARAMETER_DEFAULTS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["anywhere", "assume_no_false", "mode"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "mode" => RulePropertyKind::Choice(&["simplify", "expand"]),
+            _ => RulePropertyKind::Boolean,
+        }
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.mode != ParameterDefaultsMode::default() {
+            properties.insert("mode".to_owned(), self.mode.as_str().into());
+        }
+        if self.assume_no_false {
+            properties.insert("assume_no_false".to_owned(), true.into());
+        }
+        if self.anywhere {
+            properties.insert("anywhere".to_owned(), true.into());
+        }
+
+        properties
+    }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if self.assume_no_false {
+            properties.push("assume_no_false");
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+    use crate::test_utils::{
+        assert_rule_config_roundtrip, assert_rule_no_change, assert_rule_output,
+    };
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> SimplifyParameterDefaults {
+        SimplifyParameterDefaults::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_simplify_parameter_defaults", rule);
+    }
+
+    #[test]
+    fn serialize_expand_rule() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .with_mode(ParameterDefaultsMode::Expand)
+                .with_assume_no_false(true)
+                .with_anywhere(true),
+        );
+
+        assert_json_snapshot!("expand_simplify_parameter_defaults", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_mode_errors() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'simplify_parameter_defaults',
+            mode: 'inline',
+        }"#,
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'mode': invalid value `inline` (must be `simplify` or `expand`)"
+        );
+    }
+
+    #[test]
+    fn config_roundtrip() {
+        assert_rule_config_roundtrip(
+            "{ rule: 'simplify_parameter_defaults', mode: 'expand', assume_no_false: true }",
+        );
+    }
+
+    #[test]
+    fn simplify_falsy_check() {
+        assert_rule_output(
+            &new_rule(),
+            "local function f(x) if not x then x = 1 end return x end",
+            "local function f(x) x = x or 1 return x end",
+        );
+    }
+
+    #[test]
+    fn keep_nil_check_without_luau_target() {
+        assert_rule_no_change(
+            &new_rule(),
+            "local function f(x) if x == nil then x = 1 end return x end",
+        );
+    }
+
+    #[test]
+    fn keep_statement_after_other_statements() {
+        assert_rule_no_change(
+            &new_rule(),
+            "local function f(x) print(x) if not x then x = 1 end return x end",
+        );
+    }
+}
@@ -0,0 +1,388 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::ops;
+
+use crate::nodes::{Block, FunctionStatement, Identifier, Statement, Variable};
+use crate::process::{IdentifierTracker, NodeProcessor, NodeVisitor, ScopeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
+};
+use crate::utils::log;
+
+use super::verify_no_rule_properties;
+
+/// A processor that collects the names referenced or assigned by a statement that do not refer
+/// to a local declared inside of the statement.
+#[derive(Default)]
+struct ReferenceCollector {
+    identifier_tracker: IdentifierTracker,
+    references: HashSet<String>,
+    assigned: HashSet<String>,
+}
+
+impl ops::Deref for ReferenceCollector {
+    type Target = IdentifierTracker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.identifier_tracker
+    }
+}
+
+impl ops::DerefMut for ReferenceCollector {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.identifier_tracker
+    }
+}
+
+impl NodeProcessor for ReferenceCollector {
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        let name = identifier.get_name();
+        if !self.is_identifier_used(name) {
+            self.references.insert(name.to_owned());
+        }
+    }
+
+    fn process_variable(&mut self, variable: &mut Variable) {
+        if let Variable::Identifier(identifier) = variable {
+            let name = identifier.get_name();
+            if !self.is_identifier_used(name) {
+                self.assigned.insert(name.to_owned());
+            }
+        }
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name();
+        if name.get_field_names().is_empty() && !name.has_method() {
+            let name = name.get_name().get_name();
+            if !self.is_identifier_used(name) {
+                self.assigned.insert(name.to_owned());
+            }
+        }
+    }
+}
+
+/// What a top-level statement declares and references.
+struct StatementInfo {
+    function: Option<String>,
+    declared: Vec<String>,
+    references: HashSet<String>,
+    assigned: HashSet<String>,
+}
+
+impl StatementInfo {
+    fn new(statement: &mut Statement) -> Self {
+        let (function, declared) = match statement {
+            Statement::LocalFunction(function) => {
+                let name = function.get_name().to_owned();
+                (Some(name.clone()), vec![name])
+            }
+            Statement::LocalAssign(assign) => (
+                None,
+                assign
+                    .get_variables()
+                    .iter()
+                    .map(|variable| variable.get_name().to_owned())
+                    .collect(),
+            ),
+            _ => (None, Vec::new()),
+        };
+
+        let mut collector = ReferenceCollector::default();
+        ScopeVisitor::visit_statement(statement, &mut collector);
+
+        Self {
+            function,
+            declared,
+            references: collector.references,
+            assigned: collector.assigned,
+        }
+    }
+}
+
+/// The order constraints between the top-level statements of a block.
+struct StatementGraph {
+    successors: Vec<HashSet<usize>>,
+}
+
+impl StatementGraph {
+    fn new(length: usize) -> Self {
+        Self {
+            successors: vec![HashSet::new(); length],
+        }
+    }
+
+    /// Requires the statement at `before` to come before the statement at `after`.
+    fn add_order(&mut self, before: usize, after: usize) {
+        if before != after {
+            self.successors[before].insert(after);
+        }
+    }
+
+    /// Sorts the statements so that every constraint is satisfied, keeping statements in
+    /// their original order when possible. Returns the statements that could not be sorted
+    /// when the constraints form a cycle.
+    fn sort(&self) -> Result<Vec<usize>, Vec<usize>> {
+        let length = self.successors.len();
+        let mut predecessors_count = vec![0; length];
+        for successors in &self.successors {
+            for successor in successors {
+                predecessors_count[*successor] += 1;
+            }
+        }
+
+        let mut ready: BinaryHeap<_> = (0..length)
+            .filter(|index| predecessors_count[*index] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = Vec::with_capacity(length);
+
+        while let Some(Reverse(index)) = ready.pop() {
+            order.push(index);
+            for successor in &self.successors[index] {
+                predecessors_count[*successor] -= 1;
+                if predecessors_count[*successor] == 0 {
+                    ready.push(Reverse(*successor));
+                }
+            }
+        }
+
+        if order.len() == length {
+            Ok(order)
+        } else {
+            Err((0..length)
+                .filter(|index| predecessors_count[*index] != 0)
+                .collect())
+        }
+    }
+}
+
+/// Returns the local functions that can be called when calling the given function (including
+/// itself), following the references between local functions.
+fn reachable_functions(function: usize, dependencies: &HashMap<usize, Vec<usize>>) -> Vec<usize> {
+    let mut visited = HashSet::new();
+    let mut stack = vec![function];
+    let mut reachable = Vec::new();
+
+    while let Some(current) = stack.pop() {
+        if visited.insert(current) {
+            reachable.push(current);
+            if let Some(next) = dependencies.get(&current) {
+                stack.extend(next.iter().copied());
+            }
+        }
+    }
+
+    reachable
+}
+
+/// Computes the new order of the top-level statements. Returns `Ok(None)` when the block
+/// cannot be sorted safely (for example when a local function is assigned or redeclared),
+/// or a diagnostic for each local function that cannot be defined before its use.
+fn sort_statements(statements: &[StatementInfo]) -> Result<Option<Vec<usize>>, Vec<String>> {
+    let functions: HashMap<&str, usize> = statements
+        .iter()
+        .enumerate()
+        .filter_map(|(index, info)| info.function.as_deref().map(|name| (name, index)))
+        .collect();
+
+    if functions.is_empty() {
+        return Ok(None);
+    }
+
+    let declaration_count = |name: &str| {
+        statements
+            .iter()
+            .flat_map(|info| info.declared.iter())
+            .filter(|declared| *declared == name)
+            .count()
+    };
+
+    if functions.keys().any(|name| {
+        declaration_count(name) > 1 || statements.iter().any(|info| info.assigned.contains(*name))
+    }) {
+        return Ok(None);
+    }
+
+    let dependencies: HashMap<usize, Vec<usize>> = functions
+        .values()
+        .map(|index| {
+            let mut referenced: Vec<_> = statements[*index]
+                .references
+                .iter()
+                .filter_map(|name| functions.get(name.as_str()).copied())
+                .collect();
+            referenced.sort_unstable();
+            (*index, referenced)
+        })
+        .collect();
+
+    let reachable: HashMap<usize, Vec<usize>> = functions
+        .values()
+        .map(|index| (*index, reachable_functions(*index, &dependencies)))
+        .collect();
+
+    let mut graph = StatementGraph::new(statements.len());
+    let mut previous_statement = None;
+
+    for (index, info) in statements.iter().enumerate() {
+        if info.function.is_some() {
+            for dependency in &dependencies[&index] {
+                // mutually recursive functions can be defined in any order, since they are
+                // only called after their definitions
+                if !reachable[dependency].contains(&index) {
+                    graph.add_order(*dependency, index);
+                }
+            }
+
+            // the locals declared by other statements must remain visible (or hidden) to
+            // the function
+            for (other, other_info) in statements.iter().enumerate() {
+                if other_info.function.is_none()
+                    && other_info
+                        .declared
+                        .iter()
+                        .any(|name| info.references.contains(name))
+                {
+                    if other < index {
+                        graph.add_order(other, index);
+                    } else {
+                        graph.add_order(index, other);
+                    }
+                }
+            }
+        } else {
+            if let Some(previous) = previous_statement {
+                graph.add_order(previous, index);
+            }
+            previous_statement = Some(index);
+
+            // the functions used by a statement must be defined before the statement
+            // runs, including the functions they call
+            for name in &info.references {
+                if let Some(function) = functions.get(name.as_str()) {
+                    for called in &reachable[function] {
+                        graph.add_order(*called, index);
+                    }
+                }
+            }
+        }
+    }
+
+    match graph.sort() {
+        Ok(order) => Ok(Some(order)),
+        Err(unsorted) => Err(unsorted
+            .iter()
+            .filter_map(|index| statements[*index].function.as_deref())
+            .map(|name| {
+                format!(
+                    "unable to define `{}` before its first use: it depends on a local \
+                    declared after that use",
+                    name
+                )
+            })
+            .collect()),
+    }
+}
+
+pub const SORT_LOCAL_FUNCTIONS_RULE_NAME: &str = "sort_local_functions";
+
+/// A rule that moves the top-level local function declarations so that they are defined
+/// before the top-level statements that use them.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SortLocalFunctions {}
+
+impl FlawlessRule for SortLocalFunctions {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let statements: Vec<_> = block
+            .iter_mut_statements()
+            .map(StatementInfo::new)
+            .collect();
+
+        let order = match sort_statements(&statements) {
+            Ok(Some(order)) => order,
+            Ok(None) => return,
+            Err(diagnostics) => {
+                for diagnostic in diagnostics {
+                    log::warn!(
+                        "[{}] {}: {}",
+                        context.current_path().display(),
+                        SORT_LOCAL_FUNCTIONS_RULE_NAME,
+                        diagnostic
+                    );
+                }
+                return;
+            }
+        };
+
+        if order.iter().enumerate().all(|(i, index)| i == *index) {
+            return;
+        }
+
+        let mut statements: Vec<_> = block.take_statements().into_iter().map(Some).collect();
+        block.set_statements(
+            order
+                .into_iter()
+                .filter_map(|index| statements[index].take())
+                .collect(),
+        );
+    }
+}
+
+impl RuleConfiguration for SortLocalFunctions {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)?;
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        SORT_LOCAL_FUNCTIONS_RULE_NAME
+    }
+
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "local function greet(name)\n\
+                \treturn format(name)\n\
+                end\n\
+                print(greet(\"darklua\"))\n\
+                local function format(name)\n\
+                \treturn \"Hello \" .. name\n\
+                end",
+        )]
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> SortLocalFunctions {
+        SortLocalFunctions::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_sort_local_functions", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'sort_local_functions',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
@@ -0,0 +1,492 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::mem;
+
+use crate::nodes::{Arguments, Block, Expression, TableEntry, TableExpression, Token};
+use crate::process::{Evaluator, NodeProcessor, NodeVisitor};
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind,
+};
+
+/// Defines how the `sort_table_entries` rule orders the entries of a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableEntriesOrder {
+    /// Entries are sorted by their key (numbers first, then strings).
+    #[default]
+    Alphabetical,
+    /// Entries are sorted by the length of their key, then by their key.
+    Length,
+    /// Entries with the same value shape (like strings, numbers or functions) are grouped
+    /// together, starting with the most frequent shape. Within a group, entries are sorted by
+    /// their key.
+    Frequency,
+}
+
+impl TableEntriesOrder {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "alphabetical" => Some(Self::Alphabetical),
+            "length" => Some(Self::Length),
+            "frequency" => Some(Self::Frequency),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Alphabetical => "alphabetical",
+            Self::Length => "length",
+            Self::Frequency => "frequency",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum EntryKey {
+    Number(f64),
+    String(String),
+}
+
+impl EntryKey {
+    fn from_entry(entry: &TableEntry) -> Option<Self> {
+        match entry {
+            TableEntry::Field(field) => Some(Self::String(field.get_field().get_name().to_owned())),
+            TableEntry::Index(index) => match index.get_key() {
+                Expression::String(string) => Some(Self::String(string.get_value().to_owned())),
+                Expression::Number(number) => Some(Self::Number(number.compute_value())),
+                _ => None,
+            },
+            TableEntry::Value(_) => None,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Number(number) => number.to_string().len(),
+            Self::String(string) => string.len(),
+        }
+    }
+
+    fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (Self::Number(_), Self::String(_)) => Ordering::Less,
+            (Self::String(_), Self::Number(_)) => Ordering::Greater,
+            (Self::String(a), Self::String(b)) => a.cmp(b),
+        }
+    }
+}
+
+fn get_entry_value(entry: &TableEntry) -> &Expression {
+    match entry {
+        TableEntry::Field(field) => field.get_value(),
+        TableEntry::Index(index) => index.get_value(),
+        TableEntry::Value(value) => value,
+    }
+}
+
+/// Returns the last token of a table entry, which holds the whitespaces and comments written
+/// before the separator (or the closing brace) that follows the entry.
+fn entry_last_token_mut(entry: &mut TableEntry) -> Option<&mut Token> {
+    match entry {
+        TableEntry::Field(field) => expression_last_token_mut(field.mutate_value()),
+        TableEntry::Index(index) => expression_last_token_mut(index.mutate_value()),
+        TableEntry::Value(value) => expression_last_token_mut(value),
+    }
+}
+
+fn expression_last_token_mut(expression: &mut Expression) -> Option<&mut Token> {
+    match expression {
+        Expression::Binary(binary) => expression_last_token_mut(binary.mutate_right()),
+        Expression::Call(call) => match call.mutate_arguments() {
+            Arguments::Tuple(tuple) => tuple
+                .mutate_tokens()
+                .map(|tokens| &mut tokens.closing_parenthese),
+            Arguments::String(string) => string.mutate_token(),
+            Arguments::Table(table) => table
+                .mutate_tokens()
+                .map(|tokens| &mut tokens.closing_brace),
+        },
+        Expression::False(token)
+        | Expression::Nil(token)
+        | Expression::True(token)
+        | Expression::VariableArguments(token) => token.as_mut(),
+        Expression::Field(field) => field.mutate_field().mutate_token(),
+        Expression::Function(function) => function.mutate_tokens().map(|tokens| &mut tokens.end),
+        Expression::Identifier(identifier) => identifier.mutate_token(),
+        Expression::If(if_expression) => {
+            expression_last_token_mut(if_expression.mutate_else_result())
+        }
+        Expression::Index(index) => index
+            .mutate_tokens()
+            .map(|tokens| &mut tokens.closing_bracket),
+        Expression::Number(number) => number.mutate_token(),
+        Expression::Parenthese(parenthese) => parenthese
+            .mutate_tokens()
+            .map(|tokens| &mut tokens.right_parenthese),
+        Expression::String(string) => string.mutate_token(),
+        Expression::InterpolatedString(string) => string
+            .mutate_tokens()
+            .map(|tokens| &mut tokens.closing_tick),
+        Expression::Table(table) => table
+            .mutate_tokens()
+            .map(|tokens| &mut tokens.closing_brace),
+        Expression::Unary(unary) => expression_last_token_mut(unary.mutate_expression()),
+        Expression::TypeCast(_) => None,
+    }
+}
+
+/// Moves the items of the range starting at `start` so that the item at each position is the
+/// one found at the given index (relative to `start`) of `order`.
+fn reorder<T>(items: &mut Vec<T>, start: usize, order: &[usize]) {
+    let mut run: Vec<Option<T>> = items.drain(start..start + order.len()).map(Some).collect();
+    items.splice(
+        start..start,
+        order
+            .iter()
+            .map(|index| run[*index].take().expect("index should appear once")),
+    );
+}
+
+fn get_value_shape(value: &Expression) -> &'static str {
+    match value {
+        Expression::False(_) | Expression::True(_) => "boolean",
+        Expression::Nil(_) => "nil",
+        Expression::Number(_) => "number",
+        Expression::String(_) | Expression::InterpolatedString(_) => "string",
+        Expression::Table(_) => "table",
+        Expression::Function(_) => "function",
+        Expression::Identifier(_) => "identifier",
+        Expression::Parenthese(parenthese) => get_value_shape(parenthese.inner_expression()),
+        Expression::TypeCast(type_cast) => get_value_shape(type_cast.get_expression()),
+        _ => "expression",
+    }
+}
+
+struct SortTableEntriesProcessor {
+    order: TableEntriesOrder,
+    skip_call_arguments: bool,
+    is_call_argument: bool,
+    evaluator: Evaluator,
+}
+
+impl SortTableEntriesProcessor {
+    fn new(order: TableEntriesOrder, skip_call_arguments: bool) -> Self {
+        Self {
+            order,
+            skip_call_arguments,
+            is_call_argument: false,
+            evaluator: Evaluator::default(),
+        }
+    }
+
+    /// Returns the sorted order of the entries (each value is the index of an entry), or
+    /// `None` when the entries can not be sorted.
+    fn sort_run(&self, entries: &[TableEntry]) -> Option<Vec<usize>> {
+        if entries.len() < 2 {
+            return None;
+        }
+
+        if entries
+            .iter()
+            .any(|entry| self.evaluator.has_side_effects(get_entry_value(entry)))
+        {
+            return None;
+        }
+
+        let keys: Vec<EntryKey> = entries.iter().filter_map(EntryKey::from_entry).collect();
+
+        // when a key is repeated, the last entry wins, so the order must be kept
+        if keys
+            .iter()
+            .enumerate()
+            .any(|(i, key)| keys.iter().skip(i + 1).any(|other| key == other))
+        {
+            return None;
+        }
+
+        let mut order: Vec<usize> = (0..entries.len()).collect();
+
+        match self.order {
+            TableEntriesOrder::Alphabetical => {
+                order.sort_by(|a, b| keys[*a].compare(&keys[*b]));
+            }
+            TableEntriesOrder::Length => {
+                order.sort_by(|a, b| {
+                    let (a, b) = (&keys[*a], &keys[*b]);
+                    a.len().cmp(&b.len()).then_with(|| a.compare(b))
+                });
+            }
+            TableEntriesOrder::Frequency => {
+                let shapes: Vec<&'static str> = entries
+                    .iter()
+                    .map(|entry| get_value_shape(get_entry_value(entry)))
+                    .collect();
+                let mut shape_counts: HashMap<&'static str, (usize, usize)> = HashMap::new();
+
+                for (index, shape) in shapes.iter().enumerate() {
+                    shape_counts.entry(shape).or_insert((0, index)).0 += 1;
+                }
+
+                order.sort_by(|a, b| {
+                    let (a_count, a_first) = shape_counts[shapes[*a]];
+                    let (b_count, b_first) = shape_counts[shapes[*b]];
+
+                    b_count
+                        .cmp(&a_count)
+                        .then(a_first.cmp(&b_first))
+                        .then_with(|| keys[*a].compare(&keys[*b]))
+                });
+            }
+        }
+
+        Some(order)
+    }
+
+    /// Sorts the entries of the range starting at `start`, with the separator that follows
+    /// each entry. When the last entry of the table is not followed by a separator, the
+    /// entry sorted last takes its place: their separators and the trivia before them are
+    /// swapped.
+    fn sort_entries(&self, table: &mut TableExpression, start: usize, order: &[usize]) {
+        let end = start + order.len();
+        let entries = table.mutate_entries();
+        reorder(entries, start, order);
+
+        let Some(tokens) = table.mutate_tokens() else {
+            return;
+        };
+
+        let separators = &mut tokens.separators;
+        if separators.len() >= end {
+            reorder(separators, start, order);
+            return;
+        }
+
+        let mut run_separators: Vec<Option<Token>> = separators.drain(start..).map(Some).collect();
+        run_separators.push(None);
+        reorder(&mut run_separators, 0, order);
+
+        let last = order.len() - 1;
+        let moved = order
+            .iter()
+            .position(|index| *index == last)
+            .expect("last entry should be sorted");
+
+        if moved != last {
+            run_separators.swap(moved, last);
+
+            let entries = table.mutate_entries();
+            let last_trivia = entry_last_token_mut(&mut entries[start + last])
+                .map(Token::take_trailing_trivia)
+                .unwrap_or_default();
+            let moved_trivia = entry_last_token_mut(&mut entries[start + moved])
+                .map(Token::take_trailing_trivia)
+                .unwrap_or_default();
+
+            for (position, trivia) in [(moved, last_trivia), (last, moved_trivia)] {
+                if let Some(token) = entry_last_token_mut(&mut entries[start + position]) {
+                    trivia
+                        .into_iter()
+                        .for_each(|trivia| token.push_trailing_trivia(trivia));
+                }
+            }
+        }
+
+        if let Some(tokens) = table.mutate_tokens() {
+            tokens
+                .separators
+                .extend(run_separators.into_iter().flatten());
+        }
+    }
+}
+
+impl NodeProcessor for SortTableEntriesProcessor {
+    fn process_table_expression(&mut self, table: &mut TableExpression) {
+        if mem::take(&mut self.is_call_argument) && self.skip_call_arguments {
+            return;
+        }
+
+        let mut start = 0;
+
+        // only consecutive entries with literal keys are sorted: array values must keep
+        // their position relative to each other
+        while start < table.len() {
+            let entries = &table.get_entries()[start..];
+            let length = entries
+                .iter()
+                .take_while(|entry| EntryKey::from_entry(entry).is_some())
+                .count();
+
+            if length == 0 {
+                start += 1;
+            } else {
+                if let Some(order) = self.sort_run(&entries[..length]) {
+                    self.sort_entries(table, start, &order);
+                }
+                start += length;
+            }
+        }
+    }
+}
+
+/// A visitor that lets the processor know when a table is used as a call argument.
+struct SortTableEntriesVisitor;
+
+impl NodeVisitor<SortTableEntriesProcessor> for SortTableEntriesVisitor {
+    fn visit_arguments(arguments: &mut Arguments, processor: &mut SortTableEntriesProcessor) {
+        match arguments {
+            Arguments::String(string) => Self::visit_string_expression(string, processor),
+            Arguments::Table(table) => {
+                processor.is_call_argument = true;
+                Self::visit_table(table, processor);
+            }
+            Arguments::Tuple(expressions) => {
+                for expression in expressions.iter_mut_values() {
+                    processor.is_call_argument = matches!(expression, Expression::Table(_));
+                    Self::visit_expression(expression, processor);
+                }
+            }
+        }
+    }
+}
+
+pub const SORT_TABLE_ENTRIES_RULE_NAME: &str = "sort_table_entries";
+
+/// A rule that sorts the entries of table constructors when it does not change the
+/// resulting table, which can make the generated code compress better.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SortTableEntries {
+    order: TableEntriesOrder,
+    skip_call_arguments: bool,
+}
+
+impl SortTableEntries {
+    pub fn with_order(mut self, order: TableEntriesOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn with_skip_call_arguments(mut self, skip_call_arguments: bool) -> Self {
+        self.skip_call_arguments = skip_call_arguments;
+        self
+    }
+}
+
+impl FlawlessRule for SortTableEntries {
+    fn flawless_process(&self, block: &mut Block, _: &Context) {
+        let mut processor = SortTableEntriesProcessor::new(self.order, self.skip_call_arguments);
+        SortTableEntriesVisitor::visit_block(block, &mut processor);
+    }
+}
+
+impl RuleConfiguration for SortTableEntries {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "order" => {
+                    let order = value.expect_string(&key)?;
+                    self.order = TableEntriesOrder::parse(&order).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: "order".to_owned(),
+                            message: format!(
+                                "invalid value `{}` (must be `alphabetical`, `length` or `frequency`)",
+                                order
+                            ),
+                        }
+                    })?;
+                }
+                "skip_call_arguments" => {
+                    self.skip_call_arguments = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        SORT_TABLE_ENTRIES_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["order", "skip_call_arguments"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "skip_call_arguments" => RulePropertyKind::Boolean,
+            _ => RulePropertyKind::Choice(&["alphabetical", "length", "frequency"]),
+        }
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.order != TableEntriesOrder::default() {
+            properties.insert("order".to_owned(), self.order.as_str().into());
+        }
+
+        if self.skip_call_arguments {
+            properties.insert("skip_call_arguments".to_owned(), true.into());
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> SortTableEntries {
+        SortTableEntries::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_sort_table_entries", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_frequency_order() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .with_order(TableEntriesOrder::Frequency)
+                .with_skip_call_arguments(true),
+        );
+
+        assert_json_snapshot!("sort_table_entries_frequency_order", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_order_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'sort_table_entries',
+            order: 'random',
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'order': invalid value `random` (must be `alphabetical`, `length` or `frequency`)"
+        );
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'sort_table_entries',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
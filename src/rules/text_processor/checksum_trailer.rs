@@ -0,0 +1,104 @@
+use std::borrow::Cow;
+
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::rules::{
+    verify_no_rule_properties, Context, RuleConfiguration, RuleConfigurationError, RuleProperties,
+};
+
+use super::TextPostprocessor;
+
+pub const CHECKSUM_TRAILER_POSTPROCESSOR_NAME: &str = "checksum_trailer";
+
+/// A postprocessor that appends a comment with the checksum (xxh3) and the length (in bytes)
+/// of the generated code.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ChecksumTrailer {}
+
+impl TextPostprocessor for ChecksumTrailer {
+    fn postprocess<'code>(&self, code: &'code str, _: &Context) -> Result<Cow<'code, str>, String> {
+        let separator = if code.is_empty() || code.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+
+        Ok(format!(
+            "{}{}-- checksum: xxh3={:016x} length={}\n",
+            code,
+            separator,
+            xxh3_64(code.as_bytes()),
+            code.len()
+        )
+        .into())
+    }
+}
+
+impl RuleConfiguration for ChecksumTrailer {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)?;
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        CHECKSUM_TRAILER_POSTPROCESSOR_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::ContextBuilder;
+    use crate::Resources;
+
+    fn postprocess(code: &str) -> String {
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new(".", &resources, code).build();
+
+        ChecksumTrailer::default()
+            .postprocess(code, &context)
+            .expect("postprocessor should succeed")
+            .into_owned()
+    }
+
+    #[test]
+    fn append_trailer_on_new_line() {
+        let code = "return 1";
+
+        pretty_assertions::assert_eq!(
+            postprocess(code),
+            format!(
+                "return 1\n-- checksum: xxh3={:016x} length=8\n",
+                xxh3_64(code.as_bytes())
+            )
+        );
+    }
+
+    #[test]
+    fn append_trailer_after_final_new_line() {
+        let code = "return 1\n";
+
+        pretty_assertions::assert_eq!(
+            postprocess(code),
+            format!(
+                "return 1\n-- checksum: xxh3={:016x} length=9\n",
+                xxh3_64(code.as_bytes())
+            )
+        );
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn TextPostprocessor>>(
+            r#"{
+            name: 'checksum_trailer',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
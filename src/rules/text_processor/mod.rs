@@ -0,0 +1,450 @@
+//! Text transformations applied on the source code before parsing it (preprocessors) or on
+//! the generated code (postprocessors), for cases that can't be expressed on a Lua block.
+
+mod checksum_trailer;
+mod strip_line_directives;
+
+pub use checksum_trailer::*;
+pub use strip_line_directives::*;
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "serde-config")]
+use serde::de::{self, MapAccess, Visitor};
+#[cfg(feature = "serde-config")]
+use serde::ser::SerializeMap;
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Context, RuleConfiguration};
+#[cfg(feature = "serde-config")]
+use super::{RuleConfigurationError, RuleProperties, RulePropertyValue};
+
+/// Tracks the lines removed by a preprocessor, so that positions in the preprocessed code can
+/// be mapped back to the original code.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineOffsets {
+    // sorted pairs of a line in the preprocessed code (starting at 1) with the number of
+    // lines removed right before it
+    removed_lines: Vec<(usize, usize)>,
+}
+
+impl LineOffsets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `count` lines of the original code were removed right before the given
+    /// line (starting at 1) of the preprocessed code.
+    pub fn remove_lines(&mut self, line: usize, count: usize) {
+        if count == 0 {
+            return;
+        }
+        match self
+            .removed_lines
+            .binary_search_by_key(&line, |(removed_line, _)| *removed_line)
+        {
+            Ok(index) => self.removed_lines[index].1 += count,
+            Err(index) => self.removed_lines.insert(index, (line, count)),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.removed_lines.is_empty()
+    }
+
+    /// Returns the line in the original code that corresponds to the given line (starting
+    /// at 1) of the preprocessed code.
+    pub fn original_line(&self, line: usize) -> usize {
+        line + self
+            .removed_lines
+            .iter()
+            .take_while(|(removed_line, _)| *removed_line <= line)
+            .map(|(_, count)| count)
+            .sum::<usize>()
+    }
+
+    /// Combines these offsets with the offsets reported by a preprocessor that ran on the
+    /// resulting code.
+    pub(crate) fn followed_by(&self, next: &LineOffsets) -> LineOffsets {
+        let mut offsets = next.clone();
+
+        for (line, count) in self.removed_lines.iter() {
+            // find the first line of the final code that comes after the removed lines
+            let mut low = 1;
+            let mut high = *line;
+            while low < high {
+                let middle = low + (high - low) / 2;
+                if next.original_line(middle) < *line {
+                    low = middle + 1;
+                } else {
+                    high = middle;
+                }
+            }
+            offsets.remove_lines(low, *count);
+        }
+
+        offsets
+    }
+}
+
+/// The result of a preprocessor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessedText<'src> {
+    content: Cow<'src, str>,
+    line_offsets: LineOffsets,
+}
+
+impl<'src> PreprocessedText<'src> {
+    pub fn new(content: impl Into<Cow<'src, str>>) -> Self {
+        Self {
+            content: content.into(),
+            line_offsets: LineOffsets::default(),
+        }
+    }
+
+    pub fn with_line_offsets(mut self, line_offsets: LineOffsets) -> Self {
+        self.line_offsets = line_offsets;
+        self
+    }
+
+    #[inline]
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    #[inline]
+    pub fn line_offsets(&self) -> &LineOffsets {
+        &self.line_offsets
+    }
+
+    pub fn into_parts(self) -> (Cow<'src, str>, LineOffsets) {
+        (self.content, self.line_offsets)
+    }
+}
+
+impl<'src> From<&'src str> for PreprocessedText<'src> {
+    fn from(content: &'src str) -> Self {
+        Self::new(content)
+    }
+}
+
+impl From<String> for PreprocessedText<'_> {
+    fn from(content: String) -> Self {
+        Self::new(content)
+    }
+}
+
+impl<'src> From<Cow<'src, str>> for PreprocessedText<'src> {
+    fn from(content: Cow<'src, str>) -> Self {
+        Self::new(content)
+    }
+}
+
+/// Defines a transformation of the source code that runs before parsing it. When lines are
+/// removed, the preprocessor must report them so that parsing errors point at the original
+/// code.
+pub trait TextPreprocessor: RuleConfiguration + fmt::Debug {
+    fn preprocess<'src>(
+        &self,
+        source: &'src str,
+        context: &Context,
+    ) -> Result<PreprocessedText<'src>, String>;
+}
+
+/// Defines a transformation of the generated code.
+pub trait TextPostprocessor: RuleConfiguration + fmt::Debug {
+    fn postprocess<'code>(
+        &self,
+        code: &'code str,
+        context: &Context,
+    ) -> Result<Cow<'code, str>, String>;
+}
+
+pub fn get_all_preprocessor_names() -> Vec<&'static str> {
+    vec![STRIP_LINE_DIRECTIVES_PREPROCESSOR_NAME]
+}
+
+pub fn get_all_postprocessor_names() -> Vec<&'static str> {
+    vec![CHECKSUM_TRAILER_POSTPROCESSOR_NAME]
+}
+
+impl FromStr for Box<dyn TextPreprocessor> {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let preprocessor: Box<dyn TextPreprocessor> = match string {
+            STRIP_LINE_DIRECTIVES_PREPROCESSOR_NAME => Box::<StripLineDirectives>::default(),
+            _ => return Err(format!("invalid preprocessor name: {}", string)),
+        };
+
+        Ok(preprocessor)
+    }
+}
+
+impl FromStr for Box<dyn TextPostprocessor> {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let postprocessor: Box<dyn TextPostprocessor> = match string {
+            CHECKSUM_TRAILER_POSTPROCESSOR_NAME => Box::<ChecksumTrailer>::default(),
+            _ => return Err(format!("invalid postprocessor name: {}", string)),
+        };
+
+        Ok(postprocessor)
+    }
+}
+
+#[cfg(feature = "serde-config")]
+fn serialize_processor<S: Serializer>(
+    name: &str,
+    properties: RuleProperties,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if properties.is_empty() {
+        serializer.serialize_str(name)
+    } else {
+        let mut map = serializer.serialize_map(Some(properties.len() + 1))?;
+
+        map.serialize_entry("name", name)?;
+
+        for (key, value) in properties {
+            map.serialize_entry(&key, &value)?;
+        }
+
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde-config")]
+impl Serialize for dyn TextPreprocessor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_processor(self.get_name(), self.serialize_to_properties(), serializer)
+    }
+}
+
+#[cfg(feature = "serde-config")]
+impl Serialize for dyn TextPostprocessor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_processor(self.get_name(), self.serialize_to_properties(), serializer)
+    }
+}
+
+#[cfg(feature = "serde-config")]
+trait ConfigurableProcessor: FromStr<Err = String> {
+    const EXPECTING: &'static str;
+
+    fn configure_processor(
+        &mut self,
+        properties: RuleProperties,
+    ) -> Result<(), RuleConfigurationError>;
+}
+
+#[cfg(feature = "serde-config")]
+impl ConfigurableProcessor for Box<dyn TextPreprocessor> {
+    const EXPECTING: &'static str = "preprocessor name or preprocessor object";
+
+    fn configure_processor(
+        &mut self,
+        properties: RuleProperties,
+    ) -> Result<(), RuleConfigurationError> {
+        self.configure(properties)
+    }
+}
+
+#[cfg(feature = "serde-config")]
+impl ConfigurableProcessor for Box<dyn TextPostprocessor> {
+    const EXPECTING: &'static str = "postprocessor name or postprocessor object";
+
+    fn configure_processor(
+        &mut self,
+        properties: RuleProperties,
+    ) -> Result<(), RuleConfigurationError> {
+        self.configure(properties)
+    }
+}
+
+#[cfg(feature = "serde-config")]
+struct NameOrStruct<T>(std::marker::PhantomData<T>);
+
+#[cfg(feature = "serde-config")]
+impl<'de, T: ConfigurableProcessor> Visitor<'de> for NameOrStruct<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(T::EXPECTING)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let mut processor: T = FromStr::from_str(value).map_err(de::Error::custom)?;
+
+        processor
+            .configure_processor(RuleProperties::new())
+            .map_err(de::Error::custom)?;
+
+        Ok(processor)
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut name = None;
+        let mut properties = RuleProperties::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "name" => {
+                    if name.is_none() {
+                        name.replace(map.next_value::<String>()?);
+                    } else {
+                        return Err(de::Error::duplicate_field("name"));
+                    }
+                }
+                property => {
+                    let value = map.next_value::<RulePropertyValue>()?;
+
+                    if properties.insert(property.to_owned(), value).is_some() {
+                        return Err(de::Error::custom(format!(
+                            "duplicate field {} in processor object",
+                            property
+                        )));
+                    }
+                }
+            }
+        }
+
+        if let Some(name) = name {
+            let mut processor: T = FromStr::from_str(&name).map_err(de::Error::custom)?;
+
+            processor
+                .configure_processor(properties)
+                .map_err(de::Error::custom)?;
+
+            Ok(processor)
+        } else {
+            Err(de::Error::missing_field("name"))
+        }
+    }
+}
+
+#[cfg(feature = "serde-config")]
+impl<'de> Deserialize<'de> for Box<dyn TextPreprocessor> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NameOrStruct(std::marker::PhantomData))
+    }
+}
+
+#[cfg(feature = "serde-config")]
+impl<'de> Deserialize<'de> for Box<dyn TextPostprocessor> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NameOrStruct(std::marker::PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn offsets(removed_lines: &[(usize, usize)]) -> LineOffsets {
+        let mut offsets = LineOffsets::new();
+        for (line, count) in removed_lines {
+            offsets.remove_lines(*line, *count);
+        }
+        offsets
+    }
+
+    #[test]
+    fn original_line_without_offsets() {
+        assert_eq!(LineOffsets::new().original_line(4), 4);
+    }
+
+    #[test]
+    fn original_line_before_removed_lines() {
+        assert_eq!(offsets(&[(3, 2)]).original_line(2), 2);
+    }
+
+    #[test]
+    fn original_line_after_removed_lines() {
+        assert_eq!(offsets(&[(3, 2)]).original_line(3), 5);
+    }
+
+    #[test]
+    fn original_line_after_multiple_removed_lines() {
+        assert_eq!(offsets(&[(1, 1), (3, 2)]).original_line(4), 7);
+    }
+
+    #[test]
+    fn remove_lines_at_same_line_merges_counts() {
+        assert_eq!(offsets(&[(2, 1), (2, 3)]), offsets(&[(2, 4)]));
+    }
+
+    #[test]
+    fn followed_by_combines_offsets() {
+        // original: a b c d e f
+        // first preprocessor removes `b`: a c d e f
+        // second preprocessor removes `d`: a c e f
+        let first = offsets(&[(2, 1)]);
+        let second = offsets(&[(3, 1)]);
+
+        let combined = first.followed_by(&second);
+
+        assert_eq!(
+            (1..=4)
+                .map(|line| combined.original_line(line))
+                .collect::<Vec<_>>(),
+            vec![1, 3, 5, 6]
+        );
+    }
+
+    #[test]
+    fn followed_by_with_removed_lines_in_removed_region() {
+        // original: a b c d e
+        // first preprocessor removes `c`: a b d e
+        // second preprocessor removes `b` and `d`: a e
+        let first = offsets(&[(3, 1)]);
+        let second = offsets(&[(2, 2)]);
+
+        let combined = first.followed_by(&second);
+
+        assert_eq!(
+            (1..=2)
+                .map(|line| combined.original_line(line))
+                .collect::<Vec<_>>(),
+            vec![1, 5]
+        );
+    }
+
+    #[test]
+    fn deserialize_unknown_preprocessor_error() {
+        let result = json5::from_str::<Box<dyn TextPreprocessor>>("'unknown'");
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid preprocessor name: unknown"
+        );
+    }
+
+    #[test]
+    fn deserialize_preprocessor_without_name_error() {
+        let result = json5::from_str::<Box<dyn TextPreprocessor>>("{ directives: [] }");
+
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "missing field `name`");
+    }
+
+    #[test]
+    fn deserialize_unknown_postprocessor_error() {
+        let result = json5::from_str::<Box<dyn TextPostprocessor>>("'unknown'");
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid postprocessor name: unknown"
+        );
+    }
+}
@@ -0,0 +1,363 @@
+use crate::rules::{
+    Context, RuleConfiguration, RuleConfigurationError, RuleProperties, RulePropertyKind,
+    RulePropertyValue,
+};
+
+use super::{LineOffsets, PreprocessedText, TextPreprocessor};
+
+pub const STRIP_LINE_DIRECTIVES_PREPROCESSOR_NAME: &str = "strip_line_directives";
+
+const DEFAULT_DIRECTIVES: [&str; 13] = [
+    "define", "elif", "else", "endif", "error", "if", "ifdef", "ifndef", "include", "line",
+    "pragma", "undef", "warning",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    Code,
+    LongBracket(usize),
+    QuotedString(u8),
+}
+
+/// Returns the level of the long bracket (like `[==[`) starting at the given index and the
+/// index right after it.
+fn long_bracket_start(bytes: &[u8], start: usize) -> Option<(usize, usize)> {
+    if bytes.get(start) != Some(&b'[') {
+        return None;
+    }
+    let level = bytes[start + 1..]
+        .iter()
+        .take_while(|byte| **byte == b'=')
+        .count();
+    let end = start + 1 + level;
+    if bytes.get(end) == Some(&b'[') {
+        Some((level, end + 1))
+    } else {
+        None
+    }
+}
+
+/// Returns the index right after the closing long bracket of the given level.
+fn long_bracket_end(bytes: &[u8], start: usize, level: usize) -> Option<usize> {
+    let mut index = start;
+    while index < bytes.len() {
+        if bytes[index] == b']' {
+            let equals = bytes[index + 1..]
+                .iter()
+                .take_while(|byte| **byte == b'=')
+                .count();
+            let end = index + 1 + equals;
+            if equals == level && bytes.get(end) == Some(&b']') {
+                return Some(end + 1);
+            }
+        }
+        index += 1;
+    }
+    None
+}
+
+/// Scans a line of code to find out if the next line starts inside a string or a comment.
+fn scan_line(line: &str, mut state: ScanState) -> ScanState {
+    let bytes = line.as_bytes();
+    let mut index = 0;
+
+    loop {
+        match state {
+            ScanState::LongBracket(level) => match long_bracket_end(bytes, index, level) {
+                Some(end) => {
+                    index = end;
+                    state = ScanState::Code;
+                }
+                None => return state,
+            },
+            ScanState::QuotedString(quote) => {
+                loop {
+                    match bytes.get(index) {
+                        None => {
+                            // an unfinished string is not valid, so the next line is code
+                            return ScanState::Code;
+                        }
+                        Some(b'\\') => {
+                            let rest = &line[index + 1..];
+                            if rest.trim().is_empty()
+                                || (rest.starts_with('z') && rest[1..].trim().is_empty())
+                            {
+                                return state;
+                            }
+                            index += 2;
+                        }
+                        Some(byte) if *byte == quote => {
+                            index += 1;
+                            state = ScanState::Code;
+                            break;
+                        }
+                        Some(_) => {
+                            index += 1;
+                        }
+                    }
+                }
+            }
+            ScanState::Code => loop {
+                match bytes.get(index) {
+                    None => return ScanState::Code,
+                    Some(b'-') if bytes.get(index + 1) == Some(&b'-') => {
+                        match long_bracket_start(bytes, index + 2) {
+                            Some((level, end)) => {
+                                index = end;
+                                state = ScanState::LongBracket(level);
+                                break;
+                            }
+                            None => return ScanState::Code,
+                        }
+                    }
+                    Some(b'[') => match long_bracket_start(bytes, index) {
+                        Some((level, end)) => {
+                            index = end;
+                            state = ScanState::LongBracket(level);
+                            break;
+                        }
+                        None => {
+                            index += 1;
+                        }
+                    },
+                    Some(quote @ (b'\'' | b'"' | b'`')) => {
+                        state = ScanState::QuotedString(*quote);
+                        index += 1;
+                        break;
+                    }
+                    Some(_) => {
+                        index += 1;
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// A preprocessor that removes preprocessor directive lines (like `#if DEBUG` or `#endif`).
+/// Lines inside strings or comments are never removed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct StripLineDirectives {
+    directives: Vec<String>,
+}
+
+impl Default for StripLineDirectives {
+    fn default() -> Self {
+        Self {
+            directives: DEFAULT_DIRECTIVES.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+impl StripLineDirectives {
+    pub fn with_directives<I: IntoIterator<Item = S>, S: Into<String>>(
+        mut self,
+        directives: I,
+    ) -> Self {
+        self.directives = directives.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn is_directive(&self, line: &str) -> bool {
+        line.trim_start()
+            .strip_prefix('#')
+            .map(|directive| {
+                let name_length = directive
+                    .find(|character: char| {
+                        !(character.is_ascii_alphanumeric() || character == '_')
+                    })
+                    .unwrap_or(directive.len());
+                let name = &directive[..name_length];
+
+                self.directives.iter().any(|directive| directive == name)
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl TextPreprocessor for StripLineDirectives {
+    fn preprocess<'src>(
+        &self,
+        source: &'src str,
+        _: &Context,
+    ) -> Result<PreprocessedText<'src>, String> {
+        let mut content = String::with_capacity(source.len());
+        let mut line_offsets = LineOffsets::new();
+        let mut state = ScanState::Code;
+        let mut current_line = 1;
+        let mut removed_lines = 0;
+
+        for line in source.split_inclusive('\n') {
+            if state == ScanState::Code && self.is_directive(line) {
+                removed_lines += 1;
+                continue;
+            }
+
+            line_offsets.remove_lines(current_line, removed_lines);
+            removed_lines = 0;
+
+            state = scan_line(line, state);
+            content.push_str(line);
+            current_line += 1;
+        }
+
+        line_offsets.remove_lines(current_line, removed_lines);
+
+        if line_offsets.is_empty() {
+            Ok(source.into())
+        } else {
+            Ok(PreprocessedText::new(content).with_line_offsets(line_offsets))
+        }
+    }
+}
+
+impl RuleConfiguration for StripLineDirectives {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "directives" => {
+                    self.directives = value.expect_string_list(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        STRIP_LINE_DIRECTIVES_PREPROCESSOR_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["directives"]
+    }
+
+    fn get_property_kind(&self, _property: &str) -> RulePropertyKind {
+        RulePropertyKind::StringList
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self != &Self::default() {
+            properties.insert(
+                "directives".to_owned(),
+                RulePropertyValue::StringList(self.directives.clone()),
+            );
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::ContextBuilder;
+    use crate::Resources;
+
+    fn preprocess(code: &str) -> (String, LineOffsets) {
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new(".", &resources, code).build();
+
+        let (content, line_offsets) = StripLineDirectives::default()
+            .preprocess(code, &context)
+            .expect("preprocessor should succeed")
+            .into_parts();
+
+        (content.into_owned(), line_offsets)
+    }
+
+    macro_rules! test_strip_line_directives {
+        ($($name:ident ($code:literal) => $expect:literal),* $(,)?) => {
+            $(
+                #[test]
+                fn $name() {
+                    pretty_assertions::assert_eq!(preprocess($code).0, $expect);
+                }
+            )*
+        };
+    }
+
+    test_strip_line_directives!(
+        keep_code_without_directives("local a = 1\nreturn a") => "local a = 1\nreturn a",
+        remove_if_directive("#if DEBUG\nprint(a)\n#endif\n") => "print(a)\n",
+        remove_indented_directive("do\n  #if DEBUG\n  print(a)\n  #endif\nend")
+            => "do\n  print(a)\nend",
+        remove_last_line_directive("print(a)\n#endif") => "print(a)\n",
+        keep_length_operator("local n =\n  #list\nreturn n") => "local n =\n  #list\nreturn n",
+        keep_unknown_directive("#region\nreturn a") => "#region\nreturn a",
+        keep_directive_in_long_string("local s = [[\n#if DEBUG\n]]\nreturn s")
+            => "local s = [[\n#if DEBUG\n]]\nreturn s",
+        keep_directive_in_long_string_with_level("local s = [==[\n]]\n#if DEBUG\n]==]\n#endif\nreturn s")
+            => "local s = [==[\n]]\n#if DEBUG\n]==]\nreturn s",
+        keep_directive_in_long_comment("--[[\n#if DEBUG\n]]\n#endif\nreturn a")
+            => "--[[\n#if DEBUG\n]]\nreturn a",
+        remove_directive_after_line_comment("-- [[\n#if DEBUG\nreturn a") => "-- [[\nreturn a",
+        remove_directive_after_closed_long_string("local s = [[ a ]]\n#if DEBUG\nreturn s")
+            => "local s = [[ a ]]\nreturn s",
+        keep_directive_in_string_continuation("local s = 'a\\\n#if DEBUG'\nreturn s")
+            => "local s = 'a\\\n#if DEBUG'\nreturn s",
+        keep_directive_in_string_skip_whitespace("local s = \"a\\z\n#if DEBUG\"\nreturn s")
+            => "local s = \"a\\z\n#if DEBUG\"\nreturn s",
+        remove_directive_after_string_with_bracket("local s = '[['\n#if DEBUG\nreturn s")
+            => "local s = '[['\nreturn s",
+        remove_directive_with_crlf("#if DEBUG\r\nprint(a)\r\n#endif\r\n") => "print(a)\r\n",
+    );
+
+    #[test]
+    fn line_offsets_of_removed_region() {
+        let (_, line_offsets) =
+            preprocess("local a = 1\n#if DEBUG\n#define X\nprint(a)\n#endif\nreturn a");
+
+        assert_eq!(
+            (1..=3)
+                .map(|line| line_offsets.original_line(line))
+                .collect::<Vec<_>>(),
+            vec![1, 4, 6]
+        );
+    }
+
+    #[test]
+    fn no_line_offsets_without_directives() {
+        let (_, line_offsets) = preprocess("local a = 1\nreturn a");
+
+        assert!(line_offsets.is_empty());
+    }
+
+    #[test]
+    fn custom_directives() {
+        let code = "#region\nreturn a\n#if X";
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new(".", &resources, code).build();
+
+        let preprocessed = StripLineDirectives::default()
+            .with_directives(["region"])
+            .preprocess(code, &context)
+            .expect("preprocessor should succeed");
+
+        pretty_assertions::assert_eq!(preprocessed.content(), "return a\n#if X");
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn TextPreprocessor>>(
+            r#"{
+            name: 'strip_line_directives',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+
+    #[test]
+    fn serialize_default_preprocessor() {
+        let preprocessor: Box<dyn TextPreprocessor> = Box::<StripLineDirectives>::default();
+
+        pretty_assertions::assert_eq!(
+            json5::to_string(&preprocessor).unwrap(),
+            "\"strip_line_directives\""
+        );
+    }
+}
@@ -0,0 +1,525 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+#[cfg(feature = "serde-config")]
+use serde_json::json;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::nodes::{
+    Block, Expression, FieldExpression, FunctionCall, FunctionStatement, Identifier,
+    LocalFunctionStatement, TableEntry, TableExpression, TypeField,
+};
+use crate::process::utils::is_valid_identifier;
+use crate::process::{NodeProcessor, NodeVisitor, Scope, ScopeVisitor};
+#[cfg(not(feature = "serde-config"))]
+use crate::rules::artifact::to_json_string;
+use crate::rules::{
+    ArtifactContent, ArtifactKind, Context, FlawlessRule, RuleConfiguration,
+    RuleConfigurationError, RuleProperties, RulePropertyKind,
+};
+use crate::utils::log;
+
+/// The name of the artifact emitted by the [`TransliterateIdentifiers`] rule.
+pub const IDENTIFIERS_ARTIFACT_NAME: &str = "identifiers";
+
+const HASH_PREFIX: &str = "_u_";
+const HASH_LENGTH: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IdentifierKind {
+    Variable,
+    Field,
+}
+
+/// Calls a function with each identifier of a block that names a variable or a field.
+struct IdentifierVisitor<F> {
+    callback: F,
+}
+
+impl<F: FnMut(&mut Identifier, IdentifierKind)> IdentifierVisitor<F> {
+    fn variable(&mut self, identifier: &mut Identifier) {
+        (self.callback)(identifier, IdentifierKind::Variable);
+    }
+
+    fn field(&mut self, identifier: &mut Identifier) {
+        (self.callback)(identifier, IdentifierKind::Field);
+    }
+}
+
+impl<F: FnMut(&mut Identifier, IdentifierKind)> Scope for IdentifierVisitor<F> {
+    fn push(&mut self) {}
+
+    fn pop(&mut self) {}
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.variable(identifier);
+    }
+
+    fn insert_self(&mut self) {}
+
+    fn insert_local(&mut self, identifier: &mut Identifier, _value: Option<&mut Expression>) {
+        self.variable(identifier);
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.variable(function.mutate_identifier());
+    }
+}
+
+impl<F: FnMut(&mut Identifier, IdentifierKind)> NodeProcessor for IdentifierVisitor<F> {
+    fn process_variable_expression(&mut self, variable: &mut Identifier) {
+        self.variable(variable);
+    }
+
+    fn process_type_field(&mut self, type_field: &mut TypeField) {
+        self.variable(type_field.mutate_namespace());
+    }
+
+    fn process_field_expression(&mut self, field: &mut FieldExpression) {
+        self.field(field.mutate_field());
+    }
+
+    fn process_table_expression(&mut self, table: &mut TableExpression) {
+        for entry in table.iter_mut_entries() {
+            if let TableEntry::Field(entry) = entry {
+                self.field(entry.mutate_field());
+            }
+        }
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        if let Some(method) = call.mutate_method() {
+            self.field(method);
+        }
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.mutate_function_name();
+        for field in name.iter_mut_field_names() {
+            self.field(field);
+        }
+        if let Some(method) = name.mutate_method() {
+            self.field(method);
+        }
+    }
+}
+
+fn visit_identifiers(block: &mut Block, callback: impl FnMut(&mut Identifier, IdentifierKind)) {
+    let mut visitor = IdentifierVisitor { callback };
+    ScopeVisitor::visit_block(block, &mut visitor);
+}
+
+/// Returns an ASCII replacement for the characters that have an obvious one.
+fn transliterate_character(character: char) -> Option<&'static str> {
+    Some(match character {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+        'ď' | 'đ' | 'ð' => "d",
+        'Ď' | 'Đ' | 'Ð' => "D",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+        'ĥ' | 'ħ' => "h",
+        'Ĥ' | 'Ħ' => "H",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+        'ĵ' => "j",
+        'Ĵ' => "J",
+        'ķ' => "k",
+        'Ķ' => "K",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'Ĺ' | 'Ļ' | 'Ľ' | 'Ŀ' | 'Ł' => "L",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'Ñ' | 'Ń' | 'Ņ' | 'Ň' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ŏ' | 'Ő' => "O",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'Ŕ' | 'Ŗ' | 'Ř' => "R",
+        'ś' | 'ŝ' | 'ş' | 'š' => "s",
+        'Ś' | 'Ŝ' | 'Ş' | 'Š' => "S",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'Ţ' | 'Ť' | 'Ŧ' => "T",
+        'þ' => "th",
+        'Þ' => "TH",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ŵ' => "w",
+        'Ŵ' => "W",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'Ý' | 'Ÿ' | 'Ŷ' => "Y",
+        'ź' | 'ż' | 'ž' => "z",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'д' => "d",
+        'е' | 'э' => "e",
+        'ё' => "yo",
+        'ж' => "zh",
+        'з' => "z",
+        'и' => "i",
+        'й' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ы' => "y",
+        'ю' => "yu",
+        'я' => "ya",
+        _ => return None,
+    })
+}
+
+/// The way the [`TransliterateIdentifiers`] rule computes the ASCII name of an identifier.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransliterationStrategy {
+    /// A name computed from a hash of the original name (like `_u_3f2a91`).
+    #[default]
+    Hash,
+    /// A name where each character is replaced with a similar ASCII text when possible
+    /// (like `cafe` for `café`), or with its code point otherwise.
+    Ascii,
+}
+
+impl TransliterationStrategy {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hash" => Some(Self::Hash),
+            "ascii" => Some(Self::Ascii),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hash => "hash",
+            Self::Ascii => "ascii",
+        }
+    }
+
+    fn convert(&self, name: &str) -> String {
+        match self {
+            Self::Hash => {
+                let mut hash = format!("{:016x}", xxh3_64(name.as_bytes()));
+                hash.truncate(HASH_LENGTH);
+                format!("{}{}", HASH_PREFIX, hash)
+            }
+            Self::Ascii => {
+                let mut converted = String::new();
+                for character in name.chars() {
+                    if character.is_ascii() {
+                        converted.push(character);
+                    } else if let Some(replacement) = transliterate_character(character) {
+                        converted.push_str(replacement);
+                    } else {
+                        converted.push_str(&format!("u{:x}", character as u32));
+                    }
+                }
+                if converted.is_empty() || converted.starts_with(|c: char| c.is_ascii_digit()) {
+                    converted.insert(0, '_');
+                }
+                converted
+            }
+        }
+    }
+}
+
+pub const TRANSLITERATE_IDENTIFIERS_RULE_NAME: &str = "transliterate_identifiers";
+
+/// A rule that renames the identifiers containing non-ASCII characters, for targets that only
+/// accept ASCII identifiers.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TransliterateIdentifiers {
+    strategy: TransliterationStrategy,
+    include_fields: bool,
+}
+
+impl TransliterateIdentifiers {
+    pub fn with_strategy(mut self, strategy: TransliterationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn with_include_fields(mut self, include_fields: bool) -> Self {
+        self.include_fields = include_fields;
+        self
+    }
+
+    /// Computes the new name of each given name, in alphabetical order so that the result
+    /// does not depend on the order of the code. New names never collide with used names.
+    fn build_mapping(
+        &self,
+        names: &BTreeSet<String>,
+        mut used: HashSet<String>,
+    ) -> BTreeMap<String, String> {
+        let mut mapping = BTreeMap::new();
+
+        for name in names {
+            let base = self.strategy.convert(name);
+            let mut new_name = base.clone();
+            let mut suffix = 2;
+
+            while used.contains(&new_name) || !is_valid_identifier(&new_name, None) {
+                new_name = format!("{}_{}", base, suffix);
+                suffix += 1;
+            }
+
+            used.insert(new_name.clone());
+            mapping.insert(name.clone(), new_name);
+        }
+
+        mapping
+    }
+}
+
+impl FlawlessRule for TransliterateIdentifiers {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let mut used: HashSet<String> = context.reserved_identifiers().into_iter().collect();
+        let mut names = BTreeSet::new();
+        let mut remaining_fields = BTreeSet::new();
+
+        visit_identifiers(block, |identifier, kind| {
+            let name = identifier.get_name();
+
+            if name.is_ascii() {
+                used.insert(name.to_owned());
+            } else if kind == IdentifierKind::Variable || self.include_fields {
+                names.insert(name.to_owned());
+            } else {
+                remaining_fields.insert(name.to_owned());
+            }
+        });
+
+        if !remaining_fields.is_empty() {
+            log::warn!(
+                "[{}] non-ASCII field names are not transliterated: {} (enable `include_fields` to rename them)",
+                context.current_path().display(),
+                remaining_fields
+                    .iter()
+                    .map(|name| format!("`{}`", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        if names.is_empty() {
+            return;
+        }
+
+        let mapping = self.build_mapping(&names, used);
+        let include_fields = self.include_fields;
+
+        visit_identifiers(block, |identifier, kind| {
+            if kind == IdentifierKind::Field && !include_fields {
+                return;
+            }
+            if let Some(new_name) = mapping.get(identifier.get_name()) {
+                identifier.set_name(new_name);
+            }
+        });
+
+        #[cfg(feature = "serde-config")]
+        let content = ArtifactContent::Json(json!(mapping));
+        #[cfg(not(feature = "serde-config"))]
+        let content = ArtifactContent::Text(format!(
+            "{{\n{}\n}}\n",
+            mapping
+                .iter()
+                .map(|(name, new_name)| format!(
+                    "  {}: {}",
+                    to_json_string(name),
+                    to_json_string(new_name)
+                ))
+                .collect::<Vec<_>>()
+                .join(",\n")
+        ));
+
+        context.emit_artifact(IDENTIFIERS_ARTIFACT_NAME, ArtifactKind::Json, content);
+    }
+}
+
+impl RuleConfiguration for TransliterateIdentifiers {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "strategy" => {
+                    let strategy = value.expect_string(&key)?;
+                    self.strategy = TransliterationStrategy::parse(&strategy).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: key.clone(),
+                            message: format!(
+                                "invalid strategy `{}` (must be `hash` or `ascii`)",
+                                strategy
+                            ),
+                        }
+                    })?;
+                }
+                "include_fields" => {
+                    self.include_fields = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        TRANSLITERATE_IDENTIFIERS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["strategy", "include_fields"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "include_fields" => RulePropertyKind::Boolean,
+            _ => RulePropertyKind::Choice(&["hash", "ascii"]),
+        }
+    }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.strategy != TransliterationStrategy::default() {
+            properties.insert("strategy".to_owned(), self.strategy.as_str().into());
+        }
+
+        if self.include_fields {
+            properties.insert("include_fields".to_owned(), true.into());
+        }
+
+        properties
+    }
+
+    fn unsafe_properties(&self) -> Vec<&'static str> {
+        let mut properties = Vec::new();
+
+        if self.include_fields {
+            properties.push("include_fields");
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> TransliterateIdentifiers {
+        TransliterateIdentifiers::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_transliterate_identifiers", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_properties() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .with_strategy(TransliterationStrategy::Ascii)
+                .with_include_fields(true),
+        );
+
+        assert_json_snapshot!("transliterate_identifiers_with_properties", rule);
+    }
+
+    #[test]
+    fn configure_with_invalid_strategy_should_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'transliterate_identifiers',
+            strategy: 'latin',
+        }"#,
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'strategy': invalid strategy `latin` (must be `hash` or `ascii`)"
+        );
+    }
+
+    #[test]
+    fn hash_strategy_name() {
+        let name = TransliterationStrategy::Hash.convert("café");
+
+        assert!(name.starts_with(HASH_PREFIX));
+        assert_eq!(name.len(), HASH_PREFIX.len() + HASH_LENGTH);
+        assert_eq!(name, TransliterationStrategy::Hash.convert("café"));
+        assert_ne!(name, TransliterationStrategy::Hash.convert("cafè"));
+    }
+
+    #[test]
+    fn ascii_strategy_replaces_accents() {
+        assert_eq!(TransliterationStrategy::Ascii.convert("café"), "cafe");
+        assert_eq!(TransliterationStrategy::Ascii.convert("Straße"), "Strasse");
+    }
+
+    #[test]
+    fn ascii_strategy_replaces_cyrillic() {
+        assert_eq!(TransliterationStrategy::Ascii.convert("счёт"), "schyot");
+    }
+
+    #[test]
+    fn ascii_strategy_uses_code_points_of_unknown_characters() {
+        assert_eq!(TransliterationStrategy::Ascii.convert("値"), "u5024");
+    }
+
+    #[test]
+    fn build_mapping_adds_suffix_on_collision() {
+        let names = ["café", "cafè"].iter().map(|s| s.to_string()).collect();
+        let used = vec!["cafe".to_owned()].into_iter().collect();
+
+        let mapping = new_rule()
+            .with_strategy(TransliterationStrategy::Ascii)
+            .build_mapping(&names, used);
+
+        assert_eq!(mapping["cafè"], "cafe_2");
+        assert_eq!(mapping["café"], "cafe_3");
+    }
+
+    #[test]
+    fn build_mapping_avoids_keywords() {
+        let names = vec!["énd".to_owned()].into_iter().collect();
+
+        let mapping = new_rule()
+            .with_strategy(TransliterationStrategy::Ascii)
+            .build_mapping(&names, HashSet::new());
+
+        assert_eq!(mapping["énd"], "end_2");
+    }
+}
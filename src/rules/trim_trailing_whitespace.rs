@@ -0,0 +1,83 @@
+use crate::nodes::{TriviaCollection, TriviaKind};
+use crate::rules::{
+    verify_no_rule_properties, Context, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    TriviaRule,
+};
+
+use super::trivia_rule::trim_whitespace_before_new_lines;
+
+pub const TRIM_TRAILING_WHITESPACE_RULE_NAME: &str = "trim_trailing_whitespace";
+
+/// A rule that removes the spaces and tabs written at the end of lines.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TrimTrailingWhitespace {}
+
+impl TriviaRule for TrimTrailingWhitespace {
+    fn process_trivia(&self, trivia: &mut TriviaCollection, context: &Context) {
+        let code = context.original_code();
+
+        for trivia in trivia.iter_mut() {
+            if trivia.kind() != TriviaKind::Whitespace {
+                continue;
+            }
+
+            if let Some(trimmed) = trim_whitespace_before_new_lines(trivia.read(code)) {
+                trivia.replace_with_content(trimmed);
+            }
+        }
+    }
+}
+
+impl RuleConfiguration for TrimTrailingWhitespace {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_no_rule_properties(&properties)?;
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        TRIM_TRAILING_WHITESPACE_RULE_NAME
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        RuleProperties::new()
+    }
+
+    fn is_formatting_rule(&self) -> bool {
+        true
+    }
+
+    fn is_statement_local(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> TrimTrailingWhitespace {
+        TrimTrailingWhitespace::default()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_trim_trailing_whitespace", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'trim_trailing_whitespace',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+}
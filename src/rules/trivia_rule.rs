@@ -0,0 +1,331 @@
+use crate::nodes::*;
+use crate::process::{DefaultVisitor, NodeProcessor, NodeVisitor};
+use crate::rules::{Context, FlawlessRule};
+
+/// Defines a rule that only modifies trivia (the comments and whitespace attached to each
+/// token), without modifying the structure of the code. Rules implementing this trait are
+/// applied to the trivia of every token of the block.
+///
+/// Since the `dense` generator does not write trivia, these rules only have an effect
+/// with the `readable` and `retain_lines` generators.
+pub trait TriviaRule {
+    /// This method should modify the trivia of a single token. Trivia that reference the
+    /// original code can be read with [`Context::original_code`].
+    fn process_trivia(&self, trivia: &mut TriviaCollection, context: &Context);
+}
+
+impl<T: TriviaRule> FlawlessRule for T {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        visit_trivia(block, |trivia| self.process_trivia(trivia, context));
+    }
+}
+
+/// Calls the given function with the trivia of every token of the block.
+pub(crate) fn visit_trivia(block: &mut Block, process: impl FnMut(&mut TriviaCollection)) {
+    let mut processor = TriviaProcessor { process };
+    DefaultVisitor::visit_block(block, &mut processor);
+}
+
+struct TriviaProcessor<F> {
+    process: F,
+}
+
+impl<F: FnMut(&mut TriviaCollection)> TriviaProcessor<F> {
+    fn process(&mut self, trivia: &mut TriviaCollection) {
+        (self.process)(trivia);
+    }
+}
+
+impl<F: FnMut(&mut TriviaCollection)> NodeProcessor for TriviaProcessor<F> {
+    fn process_block(&mut self, block: &mut Block) {
+        block.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_function_call(&mut self, call: &mut FunctionCall) {
+        call.mutate_trivia(|trivia| self.process(trivia));
+        call.mutate_arguments()
+            .mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_assign_statement(&mut self, assign: &mut AssignStatement) {
+        assign.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_compound_assign_statement(&mut self, assign: &mut CompoundAssignStatement) {
+        assign.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_do_statement(&mut self, statement: &mut DoStatement) {
+        statement.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        function.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_generic_for_statement(&mut self, generic_for: &mut GenericForStatement) {
+        generic_for.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_if_statement(&mut self, if_statement: &mut IfStatement) {
+        if_statement.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_last_statement(&mut self, statement: &mut LastStatement) {
+        match statement {
+            LastStatement::Break(token) | LastStatement::Continue(token) => {
+                if let Some(token) = token {
+                    token.mutate_trivia(|trivia| self.process(trivia));
+                }
+            }
+            LastStatement::Return(statement) => {
+                statement.mutate_trivia(|trivia| self.process(trivia))
+            }
+        }
+    }
+
+    fn process_local_assign_statement(&mut self, assign: &mut LocalAssignStatement) {
+        assign.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        function.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_numeric_for_statement(&mut self, numeric_for: &mut NumericForStatement) {
+        numeric_for.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_repeat_statement(&mut self, repeat: &mut RepeatStatement) {
+        repeat.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_while_statement(&mut self, statement: &mut WhileStatement) {
+        statement.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_type_declaration(&mut self, type_declaration: &mut TypeDeclarationStatement) {
+        type_declaration.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_expression(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::False(token)
+            | Expression::Nil(token)
+            | Expression::True(token)
+            | Expression::VariableArguments(token) => {
+                if let Some(token) = token {
+                    token.mutate_trivia(|trivia| self.process(trivia))
+                }
+            }
+            Expression::Binary(_)
+            | Expression::Call(_)
+            | Expression::Field(_)
+            | Expression::Function(_)
+            | Expression::Identifier(_)
+            | Expression::If(_)
+            | Expression::Index(_)
+            | Expression::Number(_)
+            | Expression::Parenthese(_)
+            | Expression::String(_)
+            | Expression::InterpolatedString(_)
+            | Expression::Table(_)
+            | Expression::Unary(_)
+            | Expression::TypeCast(_) => {}
+        }
+    }
+
+    fn process_binary_expression(&mut self, binary: &mut BinaryExpression) {
+        binary.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_field_expression(&mut self, field: &mut FieldExpression) {
+        field.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        function.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_if_expression(&mut self, if_expression: &mut IfExpression) {
+        if_expression.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        identifier.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_index_expression(&mut self, index: &mut IndexExpression) {
+        index.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_number_expression(&mut self, number: &mut NumberExpression) {
+        number.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_parenthese_expression(&mut self, expression: &mut ParentheseExpression) {
+        expression.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_string_expression(&mut self, string: &mut StringExpression) {
+        string.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_table_expression(&mut self, table: &mut TableExpression) {
+        table.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_unary_expression(&mut self, unary: &mut UnaryExpression) {
+        unary.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_interpolated_string_expression(
+        &mut self,
+        string: &mut InterpolatedStringExpression,
+    ) {
+        string.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_type_cast_expression(&mut self, type_cast: &mut TypeCastExpression) {
+        type_cast.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_prefix_expression(&mut self, _: &mut Prefix) {}
+
+    fn process_type(&mut self, r#type: &mut Type) {
+        match r#type {
+            Type::True(token) | Type::False(token) | Type::Nil(token) => {
+                if let Some(token) = token {
+                    token.mutate_trivia(|trivia| self.process(trivia));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn process_type_name(&mut self, type_name: &mut TypeName) {
+        type_name.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_type_field(&mut self, type_field: &mut TypeField) {
+        type_field.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_string_type(&mut self, string_type: &mut StringType) {
+        string_type.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_array_type(&mut self, array: &mut ArrayType) {
+        array.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_table_type(&mut self, table: &mut TableType) {
+        table.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_expression_type(&mut self, expression_type: &mut ExpressionType) {
+        expression_type.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_parenthese_type(&mut self, parenthese_type: &mut ParentheseType) {
+        parenthese_type.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_function_type(&mut self, function_type: &mut FunctionType) {
+        function_type.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_optional_type(&mut self, optional: &mut OptionalType) {
+        optional.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_intersection_type(&mut self, intersection: &mut IntersectionType) {
+        intersection.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_union_type(&mut self, union: &mut UnionType) {
+        union.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_type_pack(&mut self, type_pack: &mut TypePack) {
+        type_pack.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_generic_type_pack(&mut self, generic_type_pack: &mut GenericTypePack) {
+        generic_type_pack.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_variadic_type_pack(&mut self, variadic_type_pack: &mut VariadicTypePack) {
+        variadic_type_pack.mutate_trivia(|trivia| self.process(trivia));
+    }
+
+    fn process_generic_parameters(&mut self, generic_parameters: &mut GenericParameters) {
+        generic_parameters.mutate_trivia(|trivia| self.process(trivia));
+    }
+}
+
+/// Removes the spaces and tabs written before each new line of the content. The content
+/// after the last new line is not modified.
+pub(crate) fn trim_whitespace_before_new_lines(content: &str) -> Option<String> {
+    if !content.contains('\n') {
+        return None;
+    }
+
+    let mut result = String::with_capacity(content.len());
+
+    for line in content.split_inclusive('\n') {
+        match line.strip_suffix('\n') {
+            Some(line) => {
+                let (line, carriage_return) = match line.strip_suffix('\r') {
+                    Some(line) => (line, "\r"),
+                    None => (line, ""),
+                };
+                result.push_str(line.trim_end_matches([' ', '\t']));
+                result.push_str(carriage_return);
+                result.push('\n');
+            }
+            None => result.push_str(line),
+        }
+    }
+
+    (result != content).then_some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn trim_spaces_before_new_line() {
+        assert_eq!(
+            trim_whitespace_before_new_lines("  \n    "),
+            Some("\n    ".to_owned())
+        );
+    }
+
+    #[test]
+    fn trim_tabs_before_carriage_return() {
+        assert_eq!(
+            trim_whitespace_before_new_lines("a \t\r\nb"),
+            Some("a\r\nb".to_owned())
+        );
+    }
+
+    #[test]
+    fn trim_each_line() {
+        assert_eq!(
+            trim_whitespace_before_new_lines("a  \nb\t\nc  "),
+            Some("a\nb\nc  ".to_owned())
+        );
+    }
+
+    #[test]
+    fn content_without_new_line_is_unchanged() {
+        assert_eq!(trim_whitespace_before_new_lines("   "), None);
+    }
+
+    #[test]
+    fn content_without_trailing_whitespace_is_unchanged() {
+        assert_eq!(trim_whitespace_before_new_lines("\n\n  "), None);
+    }
+}
@@ -1,24 +1,26 @@
 use crate::nodes::{Block, DoStatement, Expression, IfExpression, IfStatement, Statement};
 use crate::process::{DefaultVisitor, Evaluator, NodeProcessor, NodeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
+    COMPUTE_EXPRESSIONS_RULE_NAME,
 };
 
 use super::verify_no_rule_properties;
 
-enum FilterResult {
+pub(crate) enum FilterResult {
     Keep,
     Remove,
     Replace(Statement),
 }
 
 #[derive(Debug, Clone, Default)]
-struct IfFilter {
+pub(crate) struct IfFilter {
     evaluator: Evaluator,
+    folded_expressions: bool,
 }
 
 impl IfFilter {
-    fn simplify_if_statement(&self, if_statement: &mut IfStatement) -> FilterResult {
+    pub(crate) fn simplify_if_statement(&self, if_statement: &mut IfStatement) -> FilterResult {
         if let Some(else_block) = if_statement.get_else_block() {
             if else_block.is_empty() {
                 if_statement.take_else_block();
@@ -83,7 +85,7 @@ impl IfFilter {
         }
     }
 
-    fn simplify_if(&self, if_expression: &mut IfExpression) -> Option<Expression> {
+    pub(crate) fn simplify_if(&self, if_expression: &mut IfExpression) -> Option<Expression> {
         let condition_value = self.evaluator.evaluate(if_expression.get_condition());
         match condition_value.is_truthy() {
             Some(true) => {
@@ -194,6 +196,7 @@ impl NodeProcessor for IfFilter {
         if let Expression::If(if_expression) = expression {
             if let Some(replace_with) = self.simplify_if(if_expression) {
                 *expression = replace_with;
+                self.folded_expressions = true;
             }
         }
     }
@@ -207,9 +210,15 @@ pub const REMOVE_UNUSED_IF_BRANCH_RULE_NAME: &str = "remove_unused_if_branch";
 pub struct RemoveUnusedIfBranch {}
 
 impl FlawlessRule for RemoveUnusedIfBranch {
-    fn flawless_process(&self, block: &mut Block, _: &Context) {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
         let mut processor = IfFilter::default();
         DefaultVisitor::visit_block(block, &mut processor);
+
+        // replacing an if expression with one of its results can make the surrounding
+        // expression computable
+        if processor.folded_expressions {
+            context.request_reprocess(COMPUTE_EXPRESSIONS_RULE_NAME);
+        }
     }
 }
 
@@ -224,9 +233,23 @@ impl RuleConfiguration for RemoveUnusedIfBranch {
         REMOVE_UNUSED_IF_BRANCH_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "if true then\n\
+                \tprint(\"always\")\n\
+                else\n\
+                \tprint(\"never\")\n\
+                end",
+        )]
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
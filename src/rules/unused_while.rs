@@ -1,7 +1,7 @@
 use crate::nodes::{Block, Statement};
 use crate::process::{DefaultVisitor, Evaluator, NodeProcessor, NodeVisitor};
 use crate::rules::{
-    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleExample, RuleProperties,
 };
 
 use super::verify_no_rule_properties;
@@ -53,9 +53,22 @@ impl RuleConfiguration for RemoveUnusedWhile {
         REMOVE_UNUSED_WHILE_RULE_NAME
     }
 
+    fn examples(&self) -> Vec<RuleExample> {
+        vec![RuleExample::new(
+            "while false do\n\
+                \tprint(\"never\")\n\
+                end\n\
+                print(\"done\")",
+        )]
+    }
+
     fn serialize_to_properties(&self) -> RuleProperties {
         RuleProperties::new()
     }
+
+    fn applies_to_data_modules(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,884 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::nodes::{
+    Block, Expression, FunctionExpression, FunctionStatement, Identifier, LocalFunctionStatement,
+    NumberExpression, StringExpression, Token,
+};
+use crate::process::{LuaVersion, NodeProcessor, NodeVisitor, Scope, ScopeVisitor};
+use crate::rules::{
+    ByteSize, CompatibilityNote, Context, Rule, RuleConfiguration, RuleConfigurationError,
+    RuleProcessResult, RuleProperties, RulePropertyKind,
+};
+use crate::utils::log;
+
+/// The limits of a function prototype for a given Lua version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Limits {
+    locals: usize,
+    upvalues: usize,
+    constants: usize,
+}
+
+impl Limits {
+    fn for_version(version: LuaVersion) -> Self {
+        match version {
+            LuaVersion::Lua51 => Self {
+                locals: 200,
+                upvalues: 60,
+                constants: 262_143,
+            },
+            LuaVersion::Lua53 => Self {
+                locals: 200,
+                upvalues: 255,
+                constants: 67_108_863,
+            },
+            LuaVersion::Lua54 => Self {
+                locals: 200,
+                upvalues: 255,
+                constants: 33_554_431,
+            },
+            LuaVersion::Luau => Self {
+                locals: 200,
+                upvalues: 200,
+                constants: 8_388_607,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LimitKind {
+    Locals,
+    Upvalues,
+    Constants,
+    StringSize,
+}
+
+/// A function that goes over one of the configured budgets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LimitViolation {
+    kind: LimitKind,
+    location: String,
+    count: usize,
+    budget: usize,
+}
+
+impl fmt::Display for LimitViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            LimitKind::Locals => write!(
+                f,
+                "{} has {} local variables active at the same time (budget is {}): \
+                group related values into tables or scope variables inside `do` blocks",
+                self.location, self.count, self.budget
+            ),
+            LimitKind::Upvalues => write!(
+                f,
+                "{} captures {} upvalues (budget is {}): \
+                pass values as parameters or group captured variables into a table",
+                self.location, self.count, self.budget
+            ),
+            LimitKind::Constants => write!(
+                f,
+                "{} uses {} distinct constants (budget is {}): \
+                split the function or move large tables into separate modules",
+                self.location, self.count, self.budget
+            ),
+            LimitKind::StringSize => write!(
+                f,
+                "{} contains a string of {} bytes (budget is {} bytes): \
+                load large data from a separate file instead of embedding it",
+                self.location, self.count, self.budget
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Constant {
+    String(String),
+    Number(u64),
+}
+
+#[derive(Debug)]
+struct FunctionMeasure {
+    location: String,
+    /// The number of scopes that were opened before the function scope
+    scope_depth: usize,
+    active_locals: usize,
+    max_locals: usize,
+    upvalues: HashSet<usize>,
+    constants: HashSet<Constant>,
+}
+
+impl FunctionMeasure {
+    fn new(location: String, scope_depth: usize) -> Self {
+        Self {
+            location,
+            scope_depth,
+            active_locals: 0,
+            max_locals: 0,
+            upvalues: HashSet::new(),
+            constants: HashSet::new(),
+        }
+    }
+}
+
+struct LimitsCounter {
+    limits: Limits,
+    max_string_size: Option<usize>,
+    scopes: Vec<Vec<(String, usize)>>,
+    next_variable_id: usize,
+    functions: Vec<FunctionMeasure>,
+    next_function: Option<String>,
+    violations: Vec<LimitViolation>,
+}
+
+impl LimitsCounter {
+    fn new(limits: Limits, max_string_size: Option<usize>) -> Self {
+        Self {
+            limits,
+            max_string_size,
+            scopes: Vec::new(),
+            next_variable_id: 0,
+            functions: Vec::new(),
+            next_function: Some("main chunk".to_owned()),
+            violations: Vec::new(),
+        }
+    }
+
+    fn declare(&mut self, name: &str) {
+        let id = self.next_variable_id;
+        self.next_variable_id += 1;
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name.to_owned(), id));
+        }
+
+        if let Some(function) = self.functions.last_mut() {
+            function.active_locals += 1;
+            function.max_locals = function.max_locals.max(function.active_locals);
+        }
+    }
+
+    fn add_constant(&mut self, constant: Constant) {
+        if let Some(function) = self.functions.last_mut() {
+            function.constants.insert(constant);
+        }
+    }
+
+    fn finish_function(&mut self, function: FunctionMeasure) {
+        let measures = [
+            (LimitKind::Locals, function.max_locals, self.limits.locals),
+            (
+                LimitKind::Upvalues,
+                function.upvalues.len(),
+                self.limits.upvalues,
+            ),
+            (
+                LimitKind::Constants,
+                function.constants.len(),
+                self.limits.constants,
+            ),
+        ];
+
+        for (kind, count, budget) in measures {
+            if count > budget {
+                self.violations.push(LimitViolation {
+                    kind,
+                    location: function.location.clone(),
+                    count,
+                    budget,
+                });
+            }
+        }
+    }
+}
+
+fn describe_function(description: String, token: Option<&Token>) -> String {
+    match token.and_then(Token::get_line_number) {
+        Some(line) => format!("{} (line {})", description, line),
+        None => description,
+    }
+}
+
+impl Scope for LimitsCounter {
+    fn push(&mut self) {
+        if let Some(location) = self.next_function.take() {
+            self.functions
+                .push(FunctionMeasure::new(location, self.scopes.len()));
+        }
+        self.scopes.push(Vec::new());
+    }
+
+    fn pop(&mut self) {
+        let scope = self.scopes.pop().unwrap_or_default();
+
+        if let Some(function) = self.functions.last_mut() {
+            function.active_locals -= scope.len();
+
+            if function.scope_depth == self.scopes.len() {
+                if let Some(function) = self.functions.pop() {
+                    self.finish_function(function);
+                }
+            }
+        }
+    }
+
+    fn insert(&mut self, identifier: &mut Identifier) {
+        self.declare(identifier.get_name());
+    }
+
+    fn insert_self(&mut self) {
+        self.declare("self");
+    }
+
+    fn insert_local(&mut self, identifier: &mut Identifier, _value: Option<&mut Expression>) {
+        self.declare(identifier.get_name());
+    }
+
+    fn insert_local_function(&mut self, function: &mut LocalFunctionStatement) {
+        self.declare(function.get_name());
+    }
+}
+
+impl NodeProcessor for LimitsCounter {
+    fn process_function_expression(&mut self, function: &mut FunctionExpression) {
+        self.next_function = Some(describe_function(
+            "anonymous function".to_owned(),
+            function.get_tokens().map(|tokens| &tokens.function),
+        ));
+    }
+
+    fn process_function_statement(&mut self, function: &mut FunctionStatement) {
+        let name = function.get_name();
+        let mut full_name = name.get_name().get_name().to_owned();
+        for field in name.get_field_names() {
+            full_name.push('.');
+            full_name.push_str(field.get_name());
+        }
+        if let Some(method) = name.get_method() {
+            full_name.push(':');
+            full_name.push_str(method.get_name());
+        }
+
+        self.next_function = Some(describe_function(
+            format!("function `{}`", full_name),
+            function.get_tokens().map(|tokens| &tokens.function),
+        ));
+    }
+
+    fn process_local_function_statement(&mut self, function: &mut LocalFunctionStatement) {
+        self.next_function = Some(describe_function(
+            format!("local function `{}`", function.get_name()),
+            function.get_tokens().map(|tokens| &tokens.local),
+        ));
+    }
+
+    fn process_variable_expression(&mut self, identifier: &mut Identifier) {
+        let name = identifier.get_name();
+
+        let declaration = self
+            .scopes
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(depth, scope)| {
+                scope
+                    .iter()
+                    .rev()
+                    .find(|(variable, _)| variable == name)
+                    .map(|(_, id)| (depth, *id))
+            });
+
+        if let Some((depth, id)) = declaration {
+            // a variable declared in an enclosing function has to be an upvalue of every
+            // function in between
+            for function in self.functions.iter_mut().rev() {
+                if function.scope_depth <= depth {
+                    break;
+                }
+                function.upvalues.insert(id);
+            }
+        }
+    }
+
+    fn process_number_expression(&mut self, number: &mut NumberExpression) {
+        self.add_constant(Constant::Number(number.compute_value().to_bits()));
+    }
+
+    fn process_string_expression(&mut self, string: &mut StringExpression) {
+        let size = string.get_value().len();
+
+        if let Some(budget) = self.max_string_size.filter(|budget| size > *budget) {
+            let location = self
+                .functions
+                .last()
+                .map(|function| function.location.clone())
+                .unwrap_or_else(|| "main chunk".to_owned());
+
+            self.violations.push(LimitViolation {
+                kind: LimitKind::StringSize,
+                location,
+                count: size,
+                budget,
+            });
+        }
+
+        self.add_constant(Constant::String(string.get_value().to_owned()));
+    }
+}
+
+pub const VALIDATE_LIMITS_RULE_NAME: &str = "validate_limits";
+
+/// How violations found by the [`ValidateLimits`] rule are reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LimitSeverity {
+    /// Log a warning for each violation.
+    #[default]
+    Warning,
+    /// Fail the processing of the file.
+    Error,
+}
+
+impl LimitSeverity {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "warning" => Some(Self::Warning),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// A rule that does not change the code, but reports functions that have more local
+/// variables, upvalues or constants than what a Lua version can load, and string literals
+/// larger than a size budget.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ValidateLimits {
+    lua_version: LuaVersion,
+    max_locals: Option<usize>,
+    max_upvalues: Option<usize>,
+    max_constants: Option<usize>,
+    max_string_size: Option<ByteSize>,
+    severity: LimitSeverity,
+}
+
+impl Default for ValidateLimits {
+    fn default() -> Self {
+        Self {
+            lua_version: LuaVersion::Lua51,
+            max_locals: None,
+            max_upvalues: None,
+            max_constants: None,
+            max_string_size: None,
+            severity: LimitSeverity::default(),
+        }
+    }
+}
+
+impl ValidateLimits {
+    /// Uses the limits of the given Lua version for the budgets that are not set.
+    pub fn with_lua_version(mut self, version: LuaVersion) -> Self {
+        self.lua_version = version;
+        self
+    }
+
+    pub fn with_max_locals(mut self, max_locals: usize) -> Self {
+        self.max_locals = Some(max_locals);
+        self
+    }
+
+    pub fn with_max_upvalues(mut self, max_upvalues: usize) -> Self {
+        self.max_upvalues = Some(max_upvalues);
+        self
+    }
+
+    pub fn with_max_constants(mut self, max_constants: usize) -> Self {
+        self.max_constants = Some(max_constants);
+        self
+    }
+
+    /// Reports string literals that are larger than the given size.
+    pub fn with_max_string_size(mut self, max_string_size: ByteSize) -> Self {
+        self.max_string_size = Some(max_string_size);
+        self
+    }
+
+    pub fn with_severity(mut self, severity: LimitSeverity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    fn limits(&self) -> Limits {
+        let default_limits = Limits::for_version(self.lua_version);
+        Limits {
+            locals: self.max_locals.unwrap_or(default_limits.locals),
+            upvalues: self.max_upvalues.unwrap_or(default_limits.upvalues),
+            constants: self.max_constants.unwrap_or(default_limits.constants),
+        }
+    }
+
+    fn find_violations(&self, block: &mut Block) -> Vec<LimitViolation> {
+        let max_string_size = self
+            .max_string_size
+            .as_ref()
+            .map(|size| usize::try_from(size.bytes()).unwrap_or(usize::MAX));
+        let mut counter = LimitsCounter::new(self.limits(), max_string_size);
+        ScopeVisitor::visit_block(block, &mut counter);
+        counter.violations
+    }
+}
+
+impl Rule for ValidateLimits {
+    fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
+        let violations = self.find_violations(block);
+
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        match self.severity {
+            LimitSeverity::Warning => {
+                for violation in violations {
+                    log::warn!("[{}] {}", context.current_path().display(), violation);
+                }
+                Ok(())
+            }
+            LimitSeverity::Error => Err(violations
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")),
+        }
+    }
+
+    fn compatibility_check(&self, block: &Block, _context: &Context) -> Vec<CompatibilityNote> {
+        self.find_violations(&mut block.clone())
+            .iter()
+            .map(|violation| CompatibilityNote::risky(violation.to_string()))
+            .collect()
+    }
+}
+
+impl RuleConfiguration for ValidateLimits {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        for (key, value) in properties {
+            match key.as_str() {
+                "lua_version" => {
+                    let version = value.expect_string(&key)?;
+                    self.lua_version = LuaVersion::parse(&version).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: "lua_version".to_owned(),
+                            message: format!(
+                                "invalid value `{}` (must be `lua51`, `lua53`, `lua54` or `luau`)",
+                                version
+                            ),
+                        }
+                    })?;
+                }
+                "max_locals" => {
+                    self.max_locals = Some(value.expect_usize(&key)?);
+                }
+                "max_upvalues" => {
+                    self.max_upvalues = Some(value.expect_usize(&key)?);
+                }
+                "max_constants" => {
+                    self.max_constants = Some(value.expect_usize(&key)?);
+                }
+                "max_string_size" => {
+                    self.max_string_size = Some(ByteSize::from_property(value, &key)?);
+                }
+                "severity" => {
+                    let severity = value.expect_string(&key)?;
+                    self.severity = LimitSeverity::parse(&severity).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: "severity".to_owned(),
+                            message: format!(
+                                "invalid value `{}` (must be `warning` or `error`)",
+                                severity
+                            ),
+                        }
+                    })?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        VALIDATE_LIMITS_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &[
+            "lua_version",
+            "max_locals",
+            "max_upvalues",
+            "max_constants",
+            "max_string_size",
+            "severity",
+        ]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "lua_version" => RulePropertyKind::Choice(&["lua51", "lua53", "lua54", "luau"]),
+            "severity" => RulePropertyKind::Choice(&["warning", "error"]),
+            "max_string_size" => RulePropertyKind::ByteSize,
+            _ => RulePropertyKind::Usize,
+        }
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        if self.lua_version != LuaVersion::Lua51 {
+            properties.insert("lua_version".to_owned(), self.lua_version.as_str().into());
+        }
+        if let Some(max_locals) = self.max_locals {
+            properties.insert("max_locals".to_owned(), max_locals.into());
+        }
+        if let Some(max_upvalues) = self.max_upvalues {
+            properties.insert("max_upvalues".to_owned(), max_upvalues.into());
+        }
+        if let Some(max_constants) = self.max_constants {
+            properties.insert("max_constants".to_owned(), max_constants.into());
+        }
+        if let Some(max_string_size) = &self.max_string_size {
+            properties.insert("max_string_size".to_owned(), max_string_size.into());
+        }
+        if self.severity != LimitSeverity::Warning {
+            properties.insert("severity".to_owned(), self.severity.as_str().into());
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::{ContextBuilder, Rule, RulePropertyValue};
+    use crate::{Parser, Resources};
+
+    use insta::assert_json_snapshot;
+
+    fn new_rule() -> ValidateLimits {
+        ValidateLimits::default()
+    }
+
+    fn find_violations(rule: &ValidateLimits, code: &str) -> Vec<LimitViolation> {
+        let mut block = Parser::default().parse(code).expect("unable to parse code");
+        rule.find_violations(&mut block)
+    }
+
+    fn violation(kind: LimitKind, location: &str, count: usize, budget: usize) -> LimitViolation {
+        LimitViolation {
+            kind,
+            location: location.to_owned(),
+            count,
+            budget,
+        }
+    }
+
+    fn declare_locals(prefix: &str, count: usize) -> String {
+        (0..count)
+            .map(|i| format!("local {}{} = {}\n", prefix, i, i))
+            .collect()
+    }
+
+    #[test]
+    fn serialize_default_rule() {
+        let rule: Box<dyn Rule> = Box::new(new_rule());
+
+        assert_json_snapshot!("default_validate_limits", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_budgets() {
+        let rule: Box<dyn Rule> = Box::new(
+            new_rule()
+                .with_lua_version(LuaVersion::Luau)
+                .with_max_locals(100)
+                .with_max_upvalues(20)
+                .with_max_constants(1000)
+                .with_max_string_size("64 KiB".parse().unwrap())
+                .with_severity(LimitSeverity::Error),
+        );
+
+        assert_json_snapshot!("validate_limits_with_budgets", rule);
+    }
+
+    #[test]
+    fn configure_with_extra_field_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'validate_limits',
+            prop: "something",
+        }"#,
+        );
+        pretty_assertions::assert_eq!(result.unwrap_err().to_string(), "unexpected field 'prop'");
+    }
+
+    #[test]
+    fn configure_with_invalid_severity_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'validate_limits',
+            severity: 'fatal',
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'severity': invalid value `fatal` (must be `warning` or `error`)"
+        );
+    }
+
+    #[test]
+    fn configure_with_invalid_string_size_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'validate_limits',
+            max_string_size: '10M',
+        }"#,
+        );
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'max_string_size': ambiguous unit `M` in `10M` \
+            (use `MB` for powers of 1000 or `MiB` for powers of 1024)"
+        );
+    }
+
+    #[test]
+    fn configure_string_size_from_number() {
+        let rule = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'validate_limits',
+            max_string_size: 1024,
+        }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            rule.serialize_to_properties().get("max_string_size"),
+            Some(&RulePropertyValue::Usize(1024))
+        );
+    }
+
+    #[test]
+    fn passing_code_has_no_violations() {
+        let code = r#"
+            local module = {}
+            local count = 0
+            function module.increment(amount)
+                count = count + amount
+                return count
+            end
+            return module
+        "#;
+
+        assert_eq!(find_violations(&new_rule(), code), Vec::new());
+    }
+
+    #[test]
+    fn too_many_locals_in_main_chunk() {
+        let code = declare_locals("var", 201);
+
+        assert_eq!(
+            find_violations(&new_rule(), &code),
+            vec![violation(LimitKind::Locals, "main chunk", 201, 200)]
+        );
+    }
+
+    #[test]
+    fn too_many_locals_in_function() {
+        let code = format!(
+            "local function process(a, b)\n{}end\nlocal c = 1",
+            declare_locals("var", 3)
+        );
+
+        assert_eq!(
+            find_violations(&new_rule().with_max_locals(4), &code),
+            vec![violation(
+                LimitKind::Locals,
+                "local function `process`",
+                5,
+                4
+            )]
+        );
+    }
+
+    #[test]
+    fn locals_from_closed_scopes_are_not_active() {
+        let code = "do local a, b end do local c, d end local e";
+
+        assert_eq!(
+            find_violations(&new_rule().with_max_locals(3), code),
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn too_many_upvalues_in_lua51() {
+        let code = format!(
+            "{}return function() return {} end",
+            declare_locals("up", 61),
+            (0..61)
+                .map(|i| format!("up{}", i))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        assert_eq!(
+            find_violations(&new_rule(), &code),
+            vec![violation(LimitKind::Upvalues, "anonymous function", 61, 60)]
+        );
+    }
+
+    #[test]
+    fn upvalues_of_nested_functions_are_upvalues_of_parent_functions() {
+        let code = r#"
+            local a, b = 1, 2
+            function outer()
+                local c = 3
+                return function()
+                    return a + b + c
+                end
+            end
+        "#;
+
+        assert_eq!(
+            find_violations(&new_rule().with_max_upvalues(2), code),
+            vec![violation(LimitKind::Upvalues, "anonymous function", 3, 2)]
+        );
+        assert_eq!(
+            find_violations(&new_rule().with_max_upvalues(1), code),
+            vec![
+                violation(LimitKind::Upvalues, "anonymous function", 3, 1),
+                violation(LimitKind::Upvalues, "function `outer`", 2, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn too_many_constants() {
+        let code = r#"
+            local t = { 'a', 'b', 'a', 1, 2.0, 1, 0x1 }
+            local function f()
+                return 'c'
+            end
+        "#;
+
+        assert_eq!(
+            find_violations(&new_rule().with_max_constants(3), code),
+            vec![violation(LimitKind::Constants, "main chunk", 4, 3)]
+        );
+    }
+
+    #[test]
+    fn each_budget_reports_its_own_violation() {
+        let code = r#"
+            local a, b, c = 1, 2, 3
+            local function f(x)
+                local y = 'y'
+                return a + b + c + x
+            end
+        "#;
+
+        assert_eq!(
+            find_violations(
+                &new_rule()
+                    .with_max_locals(3)
+                    .with_max_upvalues(2)
+                    .with_max_constants(2),
+                code
+            ),
+            vec![
+                violation(LimitKind::Upvalues, "local function `f`", 3, 2),
+                violation(LimitKind::Locals, "main chunk", 4, 3),
+                violation(LimitKind::Constants, "main chunk", 3, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn string_larger_than_budget() {
+        let code = r#"
+            local small = 'abc'
+            local function load()
+                return 'abcdefgh'
+            end
+        "#;
+
+        assert_eq!(
+            find_violations(&new_rule().with_max_string_size(ByteSize::new(4)), code),
+            vec![violation(
+                LimitKind::StringSize,
+                "local function `load`",
+                8,
+                4
+            )]
+        );
+    }
+
+    #[test]
+    fn location_includes_line_number_when_tokens_are_preserved() {
+        let mut block = Parser::default()
+            .preserve_tokens()
+            .parse("local a, b\n\nfunction module.run()\n    return a, b\nend")
+            .expect("unable to parse code");
+
+        assert_eq!(
+            new_rule().with_max_upvalues(1).find_violations(&mut block),
+            vec![violation(
+                LimitKind::Upvalues,
+                "function `module.run` (line 3)",
+                2,
+                1
+            )]
+        );
+    }
+
+    #[test]
+    fn error_severity_fails_with_every_violation() {
+        let rule = new_rule()
+            .with_max_locals(1)
+            .with_max_constants(0)
+            .with_severity(LimitSeverity::Error);
+        let code = "local a = 1 local b = 2";
+        let mut block = Parser::default().parse(code).unwrap();
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new(".", &resources, code).build();
+
+        pretty_assertions::assert_eq!(
+            rule.process(&mut block, &context).unwrap_err(),
+            "main chunk has 2 local variables active at the same time (budget is 1): \
+            group related values into tables or scope variables inside `do` blocks\n\
+            main chunk uses 2 distinct constants (budget is 0): \
+            split the function or move large tables into separate modules"
+        );
+    }
+
+    #[test]
+    fn warning_severity_does_not_fail() {
+        let rule = new_rule().with_max_locals(0);
+        let code = "local a = 1";
+        let mut block = Parser::default().parse(code).unwrap();
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new(".", &resources, code).build();
+
+        assert_eq!(rule.process(&mut block, &context), Ok(()));
+    }
+}
@@ -0,0 +1,367 @@
+use std::mem;
+use std::path::Path;
+
+use crate::nodes::{
+    Arguments, Block, Expression, FieldExpression, FunctionCall, Identifier, LastStatement, Prefix,
+    StringExpression,
+};
+use crate::process::utils::is_valid_identifier;
+use crate::rules::{
+    Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+    RulePropertyKind, RulePropertyValue,
+};
+use crate::utils::log;
+use crate::utils::normalize_path;
+
+use super::verify_required_properties;
+
+const PATH_KEY: &str = "path";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ModuleKey {
+    Path,
+    Literal(String),
+}
+
+impl ModuleKey {
+    fn get_value(&self, path: &Path) -> String {
+        match self {
+            Self::Path => normalize_path(path.with_extension(""))
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            Self::Literal(key) => key.clone(),
+        }
+    }
+}
+
+/// Where the key of a module is computed from when the key comes from the module location.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ModuleKeySource {
+    /// Use the path of the file without its extension.
+    #[default]
+    Path,
+    /// Use the path of the Roblox instance given by a Rojo project (like
+    /// `ReplicatedStorage.Shared.Util`), or the file path when the file is not mapped.
+    Rojo,
+}
+
+impl ModuleKeySource {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "path" => Some(Self::Path),
+            "rojo" => Some(Self::Rojo),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Path => "path",
+            Self::Rojo => "rojo",
+        }
+    }
+}
+
+fn create_wrapper_prefix(wrapper: &str) -> Prefix {
+    let mut names = wrapper.split('.');
+    let root = Prefix::from(Identifier::new(names.next().unwrap_or(wrapper)));
+
+    names.fold(root, |prefix, field| {
+        FieldExpression::new(prefix, Identifier::new(field)).into()
+    })
+}
+
+fn is_non_table_literal(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::False(_)
+            | Expression::Nil(_)
+            | Expression::Number(_)
+            | Expression::String(_)
+            | Expression::InterpolatedString(_)
+            | Expression::True(_)
+    )
+}
+
+pub const WRAP_MODULE_RETURN_RULE_NAME: &str = "wrap_module_return";
+
+/// A rule that wraps the value returned by a module in a call to a function.
+#[derive(Debug, PartialEq, Eq)]
+pub struct WrapModuleReturn {
+    wrapper: String,
+    key_from: ModuleKey,
+    key_source: ModuleKeySource,
+    only_tables: bool,
+}
+
+impl Default for WrapModuleReturn {
+    fn default() -> Self {
+        Self {
+            wrapper: "".to_owned(),
+            key_from: ModuleKey::Path,
+            key_source: ModuleKeySource::default(),
+            only_tables: false,
+        }
+    }
+}
+
+impl WrapModuleReturn {
+    pub fn new(wrapper: impl Into<String>) -> Self {
+        Self {
+            wrapper: wrapper.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key_from = ModuleKey::Literal(key.into());
+        self
+    }
+
+    pub fn with_key_source(mut self, key_source: ModuleKeySource) -> Self {
+        self.key_source = key_source;
+        self
+    }
+
+    pub fn with_only_tables(mut self, only_tables: bool) -> Self {
+        self.only_tables = only_tables;
+        self
+    }
+}
+
+impl FlawlessRule for WrapModuleReturn {
+    fn flawless_process(&self, block: &mut Block, context: &Context) {
+        let Some(LastStatement::Return(return_statement)) = block.mutate_last_statement() else {
+            return;
+        };
+
+        match return_statement.len() {
+            1 => {}
+            0 => return,
+            count => {
+                log::warn!(
+                    "[{}] unable to wrap the module return with `{}`: the module returns {} values",
+                    context.current_path().display(),
+                    self.wrapper,
+                    count,
+                );
+                return;
+            }
+        }
+
+        let Some(value) = return_statement.iter_mut_expressions().next() else {
+            return;
+        };
+
+        if matches!(
+            value,
+            Expression::Call(_) | Expression::VariableArguments(_)
+        ) {
+            log::warn!(
+                "[{}] unable to wrap the module return with `{}`: the returned expression can \
+                 return multiple values (wrap it in parentheses to only return its first value)",
+                context.current_path().display(),
+                self.wrapper,
+            );
+            return;
+        }
+
+        if self.only_tables && is_non_table_literal(value) {
+            return;
+        }
+
+        let key = match (&self.key_from, self.key_source, context.roblox_path()) {
+            (ModuleKey::Path, ModuleKeySource::Rojo, Some(roblox_path)) => roblox_path.to_owned(),
+            _ => self.key_from.get_value(context.current_path()),
+        };
+        let wrapped = mem::replace(value, Expression::nil());
+
+        *value = FunctionCall::new(
+            create_wrapper_prefix(&self.wrapper),
+            Arguments::default()
+                .with_argument(StringExpression::from_value(key))
+                .with_argument(wrapped),
+            None,
+        )
+        .into();
+    }
+}
+
+impl RuleConfiguration for WrapModuleReturn {
+    fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+        verify_required_properties(&properties, &["wrapper"])?;
+
+        for (key, value) in properties {
+            match key.as_str() {
+                "wrapper" => {
+                    let wrapper = value.expect_string(&key)?;
+                    if !wrapper
+                        .split('.')
+                        .all(|name| is_valid_identifier(name, None))
+                    {
+                        return Err(RuleConfigurationError::UnexpectedValue {
+                            property: key,
+                            message: format!(
+                                "invalid function name `{}` (must be an identifier or \
+                                 identifiers separated by `.`)",
+                                wrapper
+                            ),
+                        });
+                    }
+                    self.wrapper = wrapper;
+                }
+                "key_from" => {
+                    let key_from = value.expect_string(&key)?;
+                    self.key_from = if key_from == PATH_KEY {
+                        ModuleKey::Path
+                    } else {
+                        ModuleKey::Literal(key_from)
+                    };
+                }
+                "key_source" => {
+                    let key_source = value.expect_string(&key)?;
+                    self.key_source = ModuleKeySource::parse(&key_source).ok_or_else(|| {
+                        RuleConfigurationError::UnexpectedValue {
+                            property: key.clone(),
+                            message: format!(
+                                "invalid key source `{}` (must be `path` or `rojo`)",
+                                key_source
+                            ),
+                        }
+                    })?;
+                }
+                "only_tables" => {
+                    self.only_tables = value.expect_bool(&key)?;
+                }
+                _ => return Err(RuleConfigurationError::UnexpectedProperty(key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        WRAP_MODULE_RETURN_RULE_NAME
+    }
+
+    fn get_property_names(&self) -> &'static [&'static str] {
+        &["key_from", "key_source", "only_tables", "wrapper"]
+    }
+
+    fn get_property_kind(&self, property: &str) -> RulePropertyKind {
+        match property {
+            "key_source" => RulePropertyKind::Choice(&["path", "rojo"]),
+            "only_tables" => RulePropertyKind::Boolean,
+            _ => RulePropertyKind::String,
+        }
+    }
+
+    fn serialize_to_properties(&self) -> RuleProperties {
+        let mut properties = RuleProperties::new();
+
+        properties.insert(
+            "wrapper".to_owned(),
+            RulePropertyValue::String(self.wrapper.clone()),
+        );
+
+        if let ModuleKey::Literal(key) = &self.key_from {
+            properties.insert(
+                "key_from".to_owned(),
+                RulePropertyValue::String(key.clone()),
+            );
+        }
+
+        if self.key_source != ModuleKeySource::default() {
+            properties.insert(
+                "key_source".to_owned(),
+                RulePropertyValue::String(self.key_source.as_str().to_owned()),
+            );
+        }
+
+        if self.only_tables {
+            properties.insert("only_tables".to_owned(), RulePropertyValue::Boolean(true));
+        }
+
+        properties
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rule;
+
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn serialize_rule_with_wrapper() {
+        let rule: Box<dyn Rule> = Box::new(WrapModuleReturn::new("instrument"));
+
+        assert_json_snapshot!("wrap_module_return_with_wrapper", rule);
+    }
+
+    #[test]
+    fn serialize_rule_with_all_properties() {
+        let rule: Box<dyn Rule> = Box::new(
+            WrapModuleReturn::new("Instrument.wrap")
+                .with_key("module")
+                .with_key_source(ModuleKeySource::Rojo)
+                .with_only_tables(true),
+        );
+
+        assert_json_snapshot!("wrap_module_return_with_all_properties", rule);
+    }
+
+    #[test]
+    fn configure_without_wrapper_property_should_error() {
+        let result = json5::from_str::<Box<dyn Rule>>("'wrap_module_return'");
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "missing required field 'wrapper'"
+        );
+    }
+
+    #[test]
+    fn configure_with_invalid_wrapper_should_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'wrap_module_return',
+            wrapper: 'Instrument:wrap',
+        }"#,
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'wrapper': invalid function name `Instrument:wrap` \
+            (must be an identifier or identifiers separated by `.`)"
+        );
+    }
+
+    #[test]
+    fn configure_with_invalid_key_source_should_error() {
+        let result = json5::from_str::<Box<dyn Rule>>(
+            r#"{
+            rule: 'wrap_module_return',
+            wrapper: 'instrument',
+            key_source: 'roblox',
+        }"#,
+        );
+
+        pretty_assertions::assert_eq!(
+            result.unwrap_err().to_string(),
+            "unexpected value for field 'key_source': invalid key source `roblox` \
+            (must be `path` or `rojo`)"
+        );
+    }
+
+    #[test]
+    fn get_path_key_without_extension() {
+        pretty_assertions::assert_eq!(
+            ModuleKey::Path.get_value(Path::new("./src/module/init.lua")),
+            "src/module/init"
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! Helpers to test rules, available with the `test-utils` feature.
+//!
+//! These helpers parse a Lua snippet, run a rule on it and compare the result with the
+//! expected code. Code is compared by its syntax tree, so the expected code can be written
+//! with any formatting. When the code differs, the failure message contains both generated
+//! sources and the path to the first node of the syntax tree that differs.
+//!
+//! A crate defining its own rule can test it like this:
+//!
+//! ```
+//! use darklua_core::nodes::{Block, Expression, ReturnStatement};
+//! use darklua_core::rules::{
+//!     Context, FlawlessRule, RuleConfiguration, RuleConfigurationError, RuleProperties,
+//! };
+//! use darklua_core::test_utils::{assert_rule_no_change, assert_rule_output, run_rule};
+//!
+//! /// Replaces an empty file with `return nil`.
+//! #[derive(Debug, Default)]
+//! struct ReturnNil;
+//!
+//! impl FlawlessRule for ReturnNil {
+//!     fn flawless_process(&self, block: &mut Block, _: &Context) {
+//!         if block.is_empty() {
+//!             block.set_last_statement(ReturnStatement::one(Expression::nil()));
+//!         }
+//!     }
+//! }
+//!
+//! impl RuleConfiguration for ReturnNil {
+//!     fn configure(&mut self, properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+//!         match properties.into_iter().next() {
+//!             Some((key, _)) => Err(RuleConfigurationError::UnexpectedProperty(key)),
+//!             None => Ok(()),
+//!         }
+//!     }
+//!
+//!     fn get_name(&self) -> &'static str {
+//!         "return_nil"
+//!     }
+//!
+//!     fn serialize_to_properties(&self) -> RuleProperties {
+//!         RuleProperties::new()
+//!     }
+//! }
+//!
+//! assert_rule_output(&ReturnNil, "", "return nil");
+//! assert_rule_no_change(&ReturnNil, "return 1");
+//! assert_eq!(run_rule(&ReturnNil, "-- empty"), "return nil");
+//! ```
+//!
+//! Rules registered in a [`Configuration`](crate::Configuration) with `with_rule` can be
+//! tested the same way before they are added to the configuration.
+
+use crate::{
+    generator::{DenseLuaGenerator, LuaGenerator},
+    nodes::Block,
+    rules::{ContextBuilder, Rule},
+    Parser, Resources,
+};
+
+const TEST_FILE_NAME: &str = "src/test.lua";
+
+/// Applies the rule to the given code and returns the generated code, written with the
+/// dense generator.
+#[track_caller]
+pub fn run_rule(rule: &dyn Rule, input: &str) -> String {
+    generate(&process(rule, input))
+}
+
+/// Applies the rule to the given code and verifies that the result has the same syntax tree
+/// as the expected code.
+#[track_caller]
+pub fn assert_rule_output(rule: &dyn Rule, input: &str, expected: &str) {
+    let block = process(rule, input);
+    let expected_block = parse(expected, "expected code");
+
+    if block != expected_block {
+        panic!(
+            "{}",
+            describe_difference(
+                rule,
+                input,
+                &generate(&expected_block),
+                &block,
+                &expected_block
+            )
+        );
+    }
+}
+
+/// Applies the rule to the given code and verifies that the syntax tree did not change.
+#[track_caller]
+pub fn assert_rule_no_change(rule: &dyn Rule, input: &str) {
+    assert_rule_output(rule, input, input);
+}
+
+/// Verifies that a rule configuration (written in JSON or JSON5) gives the same configuration
+/// when it is serialized and read again. Only rules that can be created from their name (the
+/// rules included in darklua) can be read from a configuration.
+#[track_caller]
+pub fn assert_rule_config_roundtrip(config: &str) {
+    let rule: Box<dyn Rule> = json5::from_str(config)
+        .unwrap_or_else(|err| panic!("unable to read rule configuration: {}\n{}", err, config));
+
+    let serialized = serde_json::to_string_pretty(&rule)
+        .unwrap_or_else(|err| panic!("unable to serialize rule `{}`: {}", rule.get_name(), err));
+
+    let read_again: Box<dyn Rule> = json5::from_str(&serialized).unwrap_or_else(|err| {
+        panic!(
+            "unable to read serialized configuration of rule `{}`: {}\n{}",
+            rule.get_name(),
+            err,
+            serialized
+        )
+    });
+
+    let serialized_again = serde_json::to_string_pretty(&read_again).unwrap_or_else(|err| {
+        panic!(
+            "unable to serialize rule `{}` a second time: {}",
+            rule.get_name(),
+            err
+        )
+    });
+
+    if serialized != serialized_again {
+        panic!(
+            "configuration of rule `{}` changed after being serialized and read again\nfirst serialization:\n{}\nsecond serialization:\n{}",
+            rule.get_name(),
+            serialized,
+            serialized_again
+        );
+    }
+}
+
+#[track_caller]
+fn parse(code: &str, description: &str) -> Block {
+    Parser::default()
+        .parse(code)
+        .unwrap_or_else(|err| panic!("unable to parse {}: {}\n{}", description, err, code))
+}
+
+#[track_caller]
+fn process(rule: &dyn Rule, input: &str) -> Block {
+    let mut block = parse(input, "input code");
+
+    let resources = Resources::from_memory();
+    resources
+        .write(TEST_FILE_NAME, input)
+        .expect("unable to write test file");
+
+    let context = ContextBuilder::new(TEST_FILE_NAME, &resources, input).build();
+
+    if let Err(err) = rule.process(&mut block, &context) {
+        panic!("rule `{}` failed: {}\n{}", rule.get_name(), err, input);
+    }
+
+    block
+}
+
+fn generate(block: &Block) -> String {
+    let mut generator = DenseLuaGenerator::default();
+    generator.write_block(block);
+    generator.into_string()
+}
+
+fn describe_difference(
+    rule: &dyn Rule,
+    input: &str,
+    expected_code: &str,
+    block: &Block,
+    expected_block: &Block,
+) -> String {
+    let tree = format!("{:#?}", block);
+    let expected_tree = format!("{:#?}", expected_block);
+
+    let mut message = format!(
+        "rule `{}` did not produce the expected code\ninput code:\n{}\nexpected code:\n{}\nreceived code:\n{}",
+        rule.get_name(),
+        input,
+        expected_code,
+        generate(block),
+    );
+
+    if let Some(difference) = find_first_difference(&expected_tree, &tree) {
+        message.push_str(&format!(
+            "\nfirst difference at `{}`:\n  expected: {}\n  received: {}",
+            difference.path, difference.expected, difference.received
+        ));
+    }
+
+    message
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct TreeDifference {
+    path: String,
+    expected: String,
+    received: String,
+}
+
+struct PathSegment {
+    depth: usize,
+    label: String,
+    is_list: bool,
+    children: usize,
+}
+
+/// Finds the first line that differs between two pretty-printed (`{:#?}`) syntax trees, and
+/// computes the path to that line from the lines that contain it.
+fn find_first_difference(expected: &str, received: &str) -> Option<TreeDifference> {
+    let expected_lines: Vec<_> = expected.lines().collect();
+    let received_lines: Vec<_> = received.lines().collect();
+
+    let index = (0..expected_lines.len().max(received_lines.len()))
+        .find(|&index| expected_lines.get(index) != received_lines.get(index))?;
+
+    let mut stack: Vec<PathSegment> = Vec::new();
+    let mut path_to_line = |line: &str| -> Vec<String> {
+        let content = line.trim_start();
+        let depth = line.len() - content.len();
+
+        while matches!(stack.last(), Some(segment) if segment.depth >= depth) {
+            stack.pop();
+        }
+
+        if content.starts_with(['}', ')', ']']) {
+            return Vec::new();
+        }
+
+        let content = content.trim_end_matches(',');
+        let opens = content.ends_with(['{', '(', '[']);
+        let label = content
+            .split_once(": ")
+            .map(|(key, _)| key)
+            .unwrap_or(content)
+            .trim_end_matches(['{', '(', '['])
+            .trim()
+            .to_owned();
+
+        let label = match stack.last_mut() {
+            Some(parent) if parent.is_list => {
+                parent.children += 1;
+                format!("[{}]", parent.children - 1)
+            }
+            _ => label,
+        };
+
+        let mut path: Vec<_> = stack.iter().map(|segment| segment.label.clone()).collect();
+        path.push(label.clone());
+
+        if opens {
+            stack.push(PathSegment {
+                depth,
+                label,
+                is_list: content.ends_with('['),
+                children: 0,
+            });
+        }
+
+        path
+    };
+
+    for line in &expected_lines[..index.min(expected_lines.len())] {
+        path_to_line(line);
+    }
+
+    let line = expected_lines
+        .get(index)
+        .or_else(|| received_lines.get(index))
+        .copied()
+        .unwrap_or_default();
+
+    let path = path_to_line(line)
+        .into_iter()
+        .filter(|label| !label.is_empty())
+        .fold(String::new(), |mut path, label| {
+            if !path.is_empty() && !label.starts_with('[') {
+                path.push('.');
+            }
+            path.push_str(&label);
+            path
+        });
+
+    let describe = |line: Option<&&str>| {
+        line.map(|line| line.trim().to_owned())
+            .unwrap_or_else(|| "<nothing>".to_owned())
+    };
+
+    Some(TreeDifference {
+        path,
+        expected: describe(expected_lines.get(index)),
+        received: describe(received_lines.get(index)),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::RemoveEmptyDo;
+
+    #[test]
+    fn run_rule_returns_dense_code() {
+        assert_eq!(
+            run_rule(&RemoveEmptyDo::default(), "do end return  1"),
+            "return 1"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "first difference at `Block.statements`")]
+    fn assert_rule_output_shows_first_difference() {
+        assert_rule_output(&RemoveEmptyDo::default(), "do end", "do end");
+    }
+
+    #[test]
+    fn find_first_difference_in_list_element() {
+        let expected = format!("{:#?}", parse("local a = 1", ""));
+        let received = format!("{:#?}", parse("local a = 2", ""));
+
+        let difference = find_first_difference(&expected, &received).unwrap();
+
+        assert!(
+            difference.path.starts_with("Block.statements[0]"),
+            "unexpected path `{}`",
+            difference.path
+        );
+        assert_ne!(difference.expected, difference.received);
+    }
+
+    #[test]
+    fn find_no_difference_in_identical_trees() {
+        let tree = format!("{:#?}", parse("return 1", ""));
+
+        assert_eq!(find_first_difference(&tree, &tree), None);
+    }
+
+    #[test]
+    fn config_roundtrip_of_rule_with_properties() {
+        assert_rule_config_roundtrip("{ rule: 'remove_empty_do', preserve_comments: false }");
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use crate::nodes::{Trivia, TriviaKind};
+
+use super::source_words::{find_words, long_bracket_level, skip_line, skip_long_bracket};
+
+/// Code where the Luau function attributes (like `@native`) were replaced with spaces, so
+/// that it can be given to a parser that does not support them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FunctionAttributesCode {
+    pub code: String,
+    /// The attributes of each function, indexed by the position of the keyword that starts
+    /// the function definition (`function` or `local`).
+    pub attributes: HashMap<usize, Vec<SourceAttribute>>,
+}
+
+/// An attribute removed from the code, with the position of its `@` symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SourceAttribute {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    /// The whitespaces and comments between the attribute and the next token.
+    pub trailing_trivia: Vec<Trivia>,
+}
+
+/// Replaces each attribute written before a function definition with spaces, so that the
+/// positions of the tokens do not change. Strings and comments are not modified. Returns
+/// `None` when the code does not contain attributes.
+pub(crate) fn remove_function_attributes(code: &str) -> Option<FunctionAttributesCode> {
+    if !code.contains('@') {
+        return None;
+    }
+
+    let bytes = code.as_bytes();
+    let words = find_words(code);
+    let is_attribute = |(start, _): (usize, usize)| start > 0 && bytes[start - 1] == b'@';
+
+    let mut attributes = HashMap::new();
+    let mut index = 0;
+
+    while index < words.len() {
+        let mut group: Vec<SourceAttribute> = Vec::new();
+        let mut next = index;
+
+        while let Some(&(start, end)) = words.get(next).filter(|word| is_attribute(**word)) {
+            if let Some(previous) = group.last_mut() {
+                match read_trivia(code, previous.end, start - 1) {
+                    Some(trivia) => previous.trailing_trivia = trivia,
+                    None => break,
+                }
+            }
+            group.push(SourceAttribute {
+                name: code[start..end].to_owned(),
+                start: start - 1,
+                end,
+                line: line_at(code, start),
+                trailing_trivia: Vec::new(),
+            });
+            next += 1;
+        }
+
+        let Some(last) = group.last_mut() else {
+            index += 1;
+            continue;
+        };
+        index = next.max(index + 1);
+
+        let Some(&(keyword_start, keyword_end)) = words.get(next) else {
+            continue;
+        };
+
+        let starts_function = match &code[keyword_start..keyword_end] {
+            "function" => true,
+            "local" => words.get(next + 1).is_some_and(|&(start, end)| {
+                &code[start..end] == "function" && read_trivia(code, keyword_end, start).is_some()
+            }),
+            _ => false,
+        };
+
+        if !starts_function {
+            continue;
+        }
+
+        if let Some(trivia) = read_trivia(code, last.end, keyword_start) {
+            last.trailing_trivia = trivia;
+            attributes.insert(keyword_start, group);
+        }
+    }
+
+    if attributes.is_empty() {
+        return None;
+    }
+
+    let mut output = code.to_owned();
+    for attribute in attributes.values().flatten() {
+        output.replace_range(
+            attribute.start..attribute.end,
+            &" ".repeat(attribute.end - attribute.start),
+        );
+    }
+
+    Some(FunctionAttributesCode {
+        code: output,
+        attributes,
+    })
+}
+
+/// Returns the whitespaces and comments between the given positions, or `None` if there is
+/// something else.
+fn read_trivia(code: &str, start: usize, end: usize) -> Option<Vec<Trivia>> {
+    let bytes = code.as_bytes();
+    let mut trivia = Vec::new();
+    let mut index = start;
+    let mut line = line_at(code, start);
+
+    while index < end {
+        let trivia_start = index;
+        let kind = if bytes[index].is_ascii_whitespace() {
+            while index < end && bytes[index].is_ascii_whitespace() {
+                index += 1;
+            }
+            TriviaKind::Whitespace
+        } else if code[index..end].starts_with("--") {
+            index = match long_bracket_level(bytes, index + 2) {
+                Some(level) => skip_long_bracket(bytes, index + 2, level),
+                None => skip_line(bytes, index + 2),
+            };
+            TriviaKind::Comment
+        } else {
+            return None;
+        };
+
+        trivia.push(kind.at(trivia_start, index, line));
+        line += code[trivia_start..index].matches('\n').count();
+    }
+
+    Some(trivia)
+}
+
+fn line_at(code: &str, position: usize) -> usize {
+    code[..position].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type AttributeNames = Vec<(usize, Vec<String>)>;
+
+    fn remove(code: &str) -> Option<(String, AttributeNames)> {
+        remove_function_attributes(code).map(|removed| {
+            let mut attributes: Vec<_> = removed
+                .attributes
+                .into_iter()
+                .map(|(position, attributes)| {
+                    (
+                        position,
+                        attributes
+                            .into_iter()
+                            .map(|attribute| attribute.name)
+                            .collect(),
+                    )
+                })
+                .collect();
+            attributes.sort();
+            (removed.code, attributes)
+        })
+    }
+
+    #[test]
+    fn code_without_attributes_is_not_modified() {
+        assert_eq!(remove("local function foo() end"), None);
+    }
+
+    #[test]
+    fn attributes_in_strings_and_comments_are_not_removed() {
+        assert_eq!(
+            remove("-- @native\nlocal a = '@native' .. [[@native]] function foo() end"),
+            None
+        );
+    }
+
+    #[test]
+    fn remove_attribute_of_local_function() {
+        assert_eq!(
+            remove("@native local function foo() end"),
+            Some((
+                "        local function foo() end".to_owned(),
+                vec![(8, vec!["native".to_owned()])]
+            ))
+        );
+    }
+
+    #[test]
+    fn remove_attributes_of_function_expression() {
+        assert_eq!(
+            remove("return @native @checked\nfunction() end"),
+            Some((
+                "return                 \nfunction() end".to_owned(),
+                vec![(24, vec!["native".to_owned(), "checked".to_owned()])]
+            ))
+        );
+    }
+
+    #[test]
+    fn attribute_followed_by_comment() {
+        let removed = remove_function_attributes("@native --[[ fast ]] function foo() end")
+            .expect("attribute should be removed");
+
+        assert_eq!(
+            removed.attributes[&21][0].trailing_trivia,
+            vec![
+                TriviaKind::Whitespace.at(7, 8, 1),
+                TriviaKind::Comment.at(8, 20, 1),
+                TriviaKind::Whitespace.at(20, 21, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn attribute_not_followed_by_function_is_not_removed() {
+        assert_eq!(remove("@native local a = 1"), None);
+    }
+}
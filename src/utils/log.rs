@@ -0,0 +1,33 @@
+//! The logging macros used by the library. They forward to the `log` crate when the `log`
+//! feature is enabled and do nothing otherwise, so that the library can be used without
+//! depending on a logging implementation.
+
+#[cfg(feature = "log")]
+pub(crate) use ::log::{debug, error, info, log_enabled, trace, warn, Level};
+
+#[cfg(not(feature = "log"))]
+mod disabled {
+    // the arguments are still type-checked so that the code compiles the same way with or
+    // without the `log` feature
+    macro_rules! log_disabled {
+        ($($arg:tt)+) => {
+            if false {
+                let _ = format_args!($($arg)+);
+            }
+        };
+    }
+
+    macro_rules! log_enabled {
+        ($($arg:tt)+) => {
+            false
+        };
+    }
+
+    pub(crate) use {log_disabled, log_enabled};
+}
+
+#[cfg(not(feature = "log"))]
+pub(crate) use disabled::{
+    log_disabled as debug, log_disabled as error, log_disabled as info, log_disabled as trace,
+    log_disabled as warn, log_enabled,
+};
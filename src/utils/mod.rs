@@ -1,10 +1,20 @@
 mod expressions_as_statement;
+mod function_attributes;
+pub(crate) mod log;
+mod paths;
+#[cfg(feature = "serde-config")]
 mod serde_string_or_struct;
+mod source_words;
 mod timer;
+mod utf8_identifiers;
 
 pub(crate) use expressions_as_statement::{expressions_as_expression, expressions_as_statement};
+pub(crate) use function_attributes::{remove_function_attributes, SourceAttribute};
+pub(crate) use paths::{absolute_path, is_same_path, relative_path};
+#[cfg(feature = "serde-config")]
 pub(crate) use serde_string_or_struct::string_or_struct;
 pub use timer::Timer;
+pub(crate) use utf8_identifiers::replace_utf8_identifiers;
 
 use std::{
     ffi::OsStr,
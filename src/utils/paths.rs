@@ -0,0 +1,288 @@
+//! Compares and maps paths that may be written differently (relative or absolute, with
+//! `.` or `..` components) without resolving symlinks.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use super::normalize_path;
+
+/// Joins a relative path to the current directory and normalizes the result. Symlinks
+/// are not resolved.
+pub(crate) fn absolute_path(path: impl AsRef<Path>) -> PathBuf {
+    absolute_path_from(path.as_ref(), env::current_dir().ok().as_deref())
+}
+
+fn absolute_path_from(path: &Path, current_dir: Option<&Path>) -> PathBuf {
+    match current_dir {
+        Some(current_dir) if !path.is_absolute() => normalize_path(current_dir.join(path)),
+        _ => normalize_path(path),
+    }
+}
+
+/// Returns `true` if both paths point to the same location once normalized. When only
+/// one of them is absolute, the other one is resolved from the current directory.
+pub(crate) fn is_same_path(a: impl AsRef<Path>, b: impl AsRef<Path>) -> bool {
+    is_same_path_from(a.as_ref(), b.as_ref(), env::current_dir().ok().as_deref())
+}
+
+fn is_same_path_from(a: &Path, b: &Path, current_dir: Option<&Path>) -> bool {
+    let (a, b) = comparable_paths(a, b, current_dir);
+    a == b
+}
+
+/// Returns the path relative to the given base directory, or `None` if the path is not
+/// inside it. The paths are normalized first and, when only one of them is absolute, the
+/// other one is resolved from the current directory.
+pub(crate) fn relative_path(path: impl AsRef<Path>, base: impl AsRef<Path>) -> Option<PathBuf> {
+    relative_path_from(
+        path.as_ref(),
+        base.as_ref(),
+        env::current_dir().ok().as_deref(),
+    )
+}
+
+fn relative_path_from(path: &Path, base: &Path, current_dir: Option<&Path>) -> Option<PathBuf> {
+    let (path, base) = comparable_paths(path, base, current_dir);
+
+    // a normalized relative path is inside the current directory unless it starts
+    // with a parent directory component
+    if base == Path::new(".") {
+        return (!path.starts_with("..") && path != Path::new(".")).then_some(path);
+    }
+
+    path.strip_prefix(&base).ok().map(Path::to_path_buf)
+}
+
+fn comparable_paths(a: &Path, b: &Path, current_dir: Option<&Path>) -> (PathBuf, PathBuf) {
+    if a.is_absolute() == b.is_absolute() {
+        (normalize_path(a), normalize_path(b))
+    } else {
+        (
+            absolute_path_from(a, current_dir),
+            absolute_path_from(b, current_dir),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn path(components: &[&str]) -> PathBuf {
+        components.iter().collect()
+    }
+
+    fn root() -> PathBuf {
+        if cfg!(windows) {
+            PathBuf::from(r"C:\")
+        } else {
+            PathBuf::from("/")
+        }
+    }
+
+    fn absolute(components: &[&str]) -> PathBuf {
+        root().join(path(components))
+    }
+
+    fn current_dir() -> PathBuf {
+        absolute(&["project"])
+    }
+
+    mod absolute_path {
+        use super::*;
+
+        #[test]
+        fn relative_path_is_joined_to_current_dir() {
+            assert_eq!(
+                absolute_path_from(&path(&["src", "main.lua"]), Some(&current_dir())),
+                absolute(&["project", "src", "main.lua"])
+            );
+        }
+
+        #[test]
+        fn relative_path_with_parent_is_normalized() {
+            assert_eq!(
+                absolute_path_from(&path(&[".", "..", "other"]), Some(&current_dir())),
+                absolute(&["other"])
+            );
+        }
+
+        #[test]
+        fn absolute_path_is_normalized() {
+            assert_eq!(
+                absolute_path_from(&absolute(&["a", ".", "b", "..", "c"]), Some(&current_dir())),
+                absolute(&["a", "c"])
+            );
+        }
+
+        #[test]
+        fn relative_path_without_current_dir_is_only_normalized() {
+            assert_eq!(
+                absolute_path_from(&path(&[".", "src"]), None),
+                path(&["src"])
+            );
+        }
+    }
+
+    mod is_same_path {
+        use super::*;
+
+        #[test]
+        fn same_relative_path() {
+            assert!(is_same_path_from(
+                &path(&["src", "main.lua"]),
+                &path(&["src", "main.lua"]),
+                Some(&current_dir())
+            ));
+        }
+
+        #[test]
+        fn relative_path_with_current_dir_component() {
+            assert!(is_same_path_from(
+                &path(&[".", "src", "main.lua"]),
+                &path(&["src", "main.lua"]),
+                Some(&current_dir())
+            ));
+        }
+
+        #[test]
+        fn relative_and_absolute_paths() {
+            assert!(is_same_path_from(
+                &path(&["src", "main.lua"]),
+                &absolute(&["project", "src", "main.lua"]),
+                Some(&current_dir())
+            ));
+        }
+
+        #[test]
+        fn absolute_and_relative_paths_with_parent() {
+            assert!(is_same_path_from(
+                &absolute(&["other", "main.lua"]),
+                &path(&["..", "other", "main.lua"]),
+                Some(&current_dir())
+            ));
+        }
+
+        #[test]
+        fn different_paths() {
+            assert!(!is_same_path_from(
+                &path(&["src", "main.lua"]),
+                &path(&["out", "main.lua"]),
+                Some(&current_dir())
+            ));
+        }
+
+        #[test]
+        fn relative_and_absolute_paths_in_different_directories() {
+            assert!(!is_same_path_from(
+                &path(&["src", "main.lua"]),
+                &absolute(&["other", "src", "main.lua"]),
+                Some(&current_dir())
+            ));
+        }
+
+        #[cfg(windows)]
+        #[test]
+        fn paths_with_both_separators() {
+            assert!(is_same_path_from(
+                Path::new(r"src\lib/main.lua"),
+                Path::new(r"src/lib\main.lua"),
+                Some(&current_dir())
+            ));
+        }
+    }
+
+    mod relative_path {
+        use super::*;
+
+        fn relative(path: &Path, base: &Path) -> Option<PathBuf> {
+            relative_path_from(path, base, Some(&current_dir()))
+        }
+
+        #[test]
+        fn file_in_directory() {
+            assert_eq!(
+                relative(&path(&["src", "main.lua"]), &path(&["src"])),
+                Some(path(&["main.lua"]))
+            );
+        }
+
+        #[test]
+        fn file_in_directory_with_current_dir_components() {
+            assert_eq!(
+                relative(
+                    &path(&["src", "lib", "main.lua"]),
+                    &path(&[".", "src", "."])
+                ),
+                Some(path(&["lib", "main.lua"]))
+            );
+        }
+
+        #[test]
+        fn file_in_current_directory() {
+            assert_eq!(
+                relative(&path(&[".", "src", "main.lua"]), Path::new(".")),
+                Some(path(&["src", "main.lua"]))
+            );
+        }
+
+        #[test]
+        fn current_directory_in_itself() {
+            assert_eq!(relative(Path::new("."), Path::new(".")), None);
+        }
+
+        #[test]
+        fn parent_file_in_current_directory() {
+            assert_eq!(relative(&path(&["..", "main.lua"]), Path::new(".")), None);
+        }
+
+        #[test]
+        fn relative_file_in_absolute_directory() {
+            assert_eq!(
+                relative(&path(&["src", "main.lua"]), &absolute(&["project", "src"])),
+                Some(path(&["main.lua"]))
+            );
+        }
+
+        #[test]
+        fn absolute_file_in_relative_directory() {
+            assert_eq!(
+                relative(&absolute(&["project", "src", "main.lua"]), &path(&["src"])),
+                Some(path(&["main.lua"]))
+            );
+        }
+
+        #[test]
+        fn file_outside_directory() {
+            assert_eq!(relative(&path(&["lib", "main.lua"]), &path(&["src"])), None);
+        }
+
+        #[test]
+        fn directory_with_name_prefix() {
+            assert_eq!(
+                relative(&path(&["src-old", "main.lua"]), &path(&["src"])),
+                None
+            );
+        }
+
+        #[cfg(windows)]
+        #[test]
+        fn directory_with_trailing_backslash() {
+            assert_eq!(
+                relative(Path::new(r"src\lib\main.lua"), Path::new(r"src\")),
+                Some(path(&["lib", "main.lua"]))
+            );
+        }
+
+        #[cfg(windows)]
+        #[test]
+        fn file_with_forward_slashes_in_directory_with_backslashes() {
+            assert_eq!(
+                relative(Path::new("src/lib/main.lua"), Path::new(r".\src")),
+                Some(path(&["lib", "main.lua"]))
+            );
+        }
+    }
+}
@@ -0,0 +1,152 @@
+fn is_word_character(character: char) -> bool {
+    character == '_' || character.is_alphanumeric()
+}
+
+/// Returns the byte ranges of the words of the code that can be identifiers (outside of
+/// strings, comments and numbers).
+pub(super) fn find_words(code: &str) -> Vec<(usize, usize)> {
+    let bytes = code.as_bytes();
+    let mut words = Vec::new();
+    // the depth of braces where each interpolated string (like `{value}`) started
+    let mut interpolations: Vec<usize> = Vec::new();
+    let mut brace_depth = 0;
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let byte = bytes[index];
+
+        match byte {
+            b'-' if bytes.get(index + 1) == Some(&b'-') => {
+                index += 2;
+                index = match long_bracket_level(bytes, index) {
+                    Some(level) => skip_long_bracket(bytes, index, level),
+                    None => skip_line(bytes, index),
+                };
+            }
+            b'[' if long_bracket_level(bytes, index).is_some() => {
+                let level = long_bracket_level(bytes, index).unwrap_or_default();
+                index = skip_long_bracket(bytes, index, level);
+            }
+            b'\'' | b'"' => {
+                index = skip_quoted_string(bytes, index + 1, byte);
+            }
+            b'`' => {
+                index = skip_interpolated_string(bytes, index + 1);
+                if bytes.get(index.wrapping_sub(1)) == Some(&b'{') {
+                    interpolations.push(brace_depth);
+                }
+            }
+            b'{' => {
+                brace_depth += 1;
+                index += 1;
+            }
+            b'}' => {
+                index += 1;
+                if interpolations.last() == Some(&brace_depth) {
+                    interpolations.pop();
+                    index = skip_interpolated_string(bytes, index);
+                    if bytes.get(index.wrapping_sub(1)) == Some(&b'{') {
+                        interpolations.push(brace_depth);
+                    }
+                } else {
+                    brace_depth = brace_depth.saturating_sub(1);
+                }
+            }
+            b'0'..=b'9' => {
+                // numbers (like `0x1F` or `1e10`) are skipped with their letters
+                index += 1;
+                while index < bytes.len()
+                    && (bytes[index].is_ascii_alphanumeric()
+                        || bytes[index] == b'_'
+                        || bytes[index] == b'.')
+                {
+                    index += 1;
+                }
+            }
+            _ => {
+                let character = code[index..].chars().next().unwrap_or_default();
+
+                if is_word_character(character) {
+                    let start = index;
+                    for character in code[start..].chars() {
+                        if !is_word_character(character) {
+                            break;
+                        }
+                        index += character.len_utf8();
+                    }
+                    words.push((start, index));
+                } else {
+                    index += character.len_utf8().max(1);
+                }
+            }
+        }
+    }
+
+    words
+}
+
+/// Returns the level of the long bracket (the number of `=`) that opens at the given index.
+pub(super) fn long_bracket_level(bytes: &[u8], index: usize) -> Option<usize> {
+    if bytes.get(index) != Some(&b'[') {
+        return None;
+    }
+
+    let level = bytes[index + 1..]
+        .iter()
+        .take_while(|byte| **byte == b'=')
+        .count();
+
+    (bytes.get(index + 1 + level) == Some(&b'[')).then_some(level)
+}
+
+/// Returns the index after the long bracket that closes the one that opens at the given
+/// index.
+pub(super) fn skip_long_bracket(bytes: &[u8], index: usize, level: usize) -> usize {
+    let mut closing = vec![b']'];
+    closing.extend(std::iter::repeat_n(b'=', level));
+    closing.push(b']');
+
+    let content_start = index + level + 2;
+
+    bytes
+        .get(content_start..)
+        .and_then(|content| {
+            content
+                .windows(closing.len())
+                .position(|window| window == closing.as_slice())
+        })
+        .map(|position| content_start + position + closing.len())
+        .unwrap_or(bytes.len())
+}
+
+pub(super) fn skip_line(bytes: &[u8], index: usize) -> usize {
+    bytes[index..]
+        .iter()
+        .position(|byte| *byte == b'\n')
+        .map(|position| index + position)
+        .unwrap_or(bytes.len())
+}
+
+fn skip_quoted_string(bytes: &[u8], mut index: usize, quote: u8) -> usize {
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            byte if byte == quote || byte == b'\n' => return index + 1,
+            _ => index += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Returns the index after the end of the interpolated string, or after the `{` that starts
+/// an interpolated expression.
+fn skip_interpolated_string(bytes: &[u8], mut index: usize) -> usize {
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            b'`' | b'{' => return index + 1,
+            _ => index += 1,
+        }
+    }
+    bytes.len()
+}
@@ -26,8 +26,11 @@ impl Timer {
         self.start = Instant::now();
     }
 
+    pub fn duration(&self) -> Duration {
+        self.start.elapsed() + self.accumulated_time
+    }
+
     pub fn duration_label(&self) -> String {
-        let duration = self.start.elapsed();
-        durationfmt::to_string(duration + self.accumulated_time)
+        durationfmt::to_string(self.duration())
     }
 }
@@ -0,0 +1,153 @@
+use std::collections::{HashMap, HashSet};
+
+use super::source_words::find_words;
+
+const PLACEHOLDER_CHARACTERS: &[u8] =
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_";
+
+/// Code where the identifiers containing UTF-8 characters were replaced with ASCII
+/// placeholders, so that it can be given to a parser that does not support them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Utf8IdentifiersCode {
+    pub code: String,
+    /// The original name of each placeholder.
+    pub names: HashMap<String, String>,
+}
+
+/// Returns the placeholder at the given index, padded with underscores to the given length.
+fn placeholder(mut index: usize, length: usize) -> Option<String> {
+    let base = PLACEHOLDER_CHARACTERS.len();
+    let mut digits = Vec::new();
+
+    loop {
+        digits.push(PLACEHOLDER_CHARACTERS[index % base]);
+        index /= base;
+        if index == 0 {
+            break;
+        }
+    }
+
+    if digits.len() + 1 > length {
+        return None;
+    }
+
+    let mut placeholder = "_".repeat(length - digits.len());
+    placeholder.extend(digits.into_iter().rev().map(char::from));
+    Some(placeholder)
+}
+
+/// Replaces each identifier that contains UTF-8 characters with an ASCII placeholder of the
+/// same length, so that the positions of the tokens do not change. The same name is always
+/// replaced with the same placeholder. Strings and comments are not modified. Returns `None`
+/// when the code does not contain such identifiers.
+pub(crate) fn replace_utf8_identifiers(code: &str) -> Option<Utf8IdentifiersCode> {
+    if code.is_ascii() {
+        return None;
+    }
+
+    let words = find_words(code);
+    let used: HashSet<&str> = words
+        .iter()
+        .map(|&(start, end)| &code[start..end])
+        .filter(|word| word.is_ascii())
+        .collect();
+
+    let mut placeholders: HashMap<&str, String> = HashMap::new();
+    let mut replacements = Vec::new();
+    let mut next_index = 0;
+
+    for &(start, end) in words.iter() {
+        let name = &code[start..end];
+        if name.is_ascii() {
+            continue;
+        }
+
+        if !placeholders.contains_key(name) {
+            let mut index = next_index;
+            let found = loop {
+                match placeholder(index, name.len()) {
+                    Some(candidate) if used.contains(candidate.as_str()) => index += 1,
+                    found => break found,
+                }
+            };
+            // names that are too short to fit a placeholder are left to the parser
+            let Some(found) = found else {
+                continue;
+            };
+            next_index = index + 1;
+            placeholders.insert(name, found);
+        }
+
+        let placeholder = &placeholders[name];
+        replacements.push((start, end, placeholder.clone()));
+    }
+
+    if replacements.is_empty() {
+        return None;
+    }
+
+    let mut output = code.to_owned();
+    for (start, end, placeholder) in replacements {
+        output.replace_range(start..end, &placeholder);
+    }
+
+    let names = placeholders
+        .into_iter()
+        .map(|(name, placeholder)| (placeholder, name.to_owned()))
+        .collect();
+
+    Some(Utf8IdentifiersCode {
+        code: output,
+        names,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn replace(code: &str) -> Option<String> {
+        replace_utf8_identifiers(code).map(|replaced| replaced.code)
+    }
+
+    #[test]
+    fn ascii_code_is_not_modified() {
+        assert_eq!(replace("local cafe = 1"), None);
+    }
+
+    #[test]
+    fn utf8_in_strings_and_comments_is_not_replaced() {
+        assert_eq!(replace("-- café\nlocal a = 'café' .. [[naïve]]"), None);
+    }
+
+    #[test]
+    fn replace_identifier_with_placeholder_of_same_length() {
+        let replaced = replace_utf8_identifiers("local café = 1 return café").unwrap();
+
+        assert_eq!(replaced.code, "local ____a = 1 return ____a");
+        assert_eq!(replaced.names["____a"], "café");
+    }
+
+    #[test]
+    fn placeholders_avoid_existing_names() {
+        let replaced = replace_utf8_identifiers("local _a = 1 local é = 2").unwrap();
+
+        assert_eq!(replaced.code, "local _a = 1 local _b = 2");
+    }
+
+    #[test]
+    fn different_names_get_different_placeholders() {
+        let replaced = replace_utf8_identifiers("local é, ü = 1, 2").unwrap();
+
+        assert_eq!(replaced.code, "local _a, _b = 1, 2");
+        assert_eq!(replaced.names["_a"], "é");
+        assert_eq!(replaced.names["_b"], "ü");
+    }
+
+    #[test]
+    fn placeholder_is_padded_to_length() {
+        assert_eq!(placeholder(0, 4), Some("___a".to_owned()));
+        assert_eq!(placeholder(63, 4), Some("__ba".to_owned()));
+        assert_eq!(placeholder(63, 2), None);
+    }
+}
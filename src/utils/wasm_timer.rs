@@ -37,9 +37,12 @@ impl Timer {
         self.start = instant_now();
     }
 
-    pub fn duration_label(&self) -> String {
+    pub fn duration(&self) -> Duration {
         let now = instant_now();
-        let duration = now - self.start;
-        durationfmt::to_string(duration + self.accumulated_time)
+        now - self.start + self.accumulated_time
+    }
+
+    pub fn duration_label(&self) -> String {
+        durationfmt::to_string(self.duration())
     }
 }
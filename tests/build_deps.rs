@@ -0,0 +1,61 @@
+//! Uses darklua the way a build script would: the crate is compiled without its default
+//! features, so this test only relies on the parser, the rules and the generators.
+//!
+//! Run it with `cargo test --no-default-features --test build_deps`.
+
+use darklua_core::{
+    generator::{DenseLuaGenerator, LuaGenerator},
+    process_code,
+    rules::{ContextBuilder, RemoveComments, RemoveSpaces, Rule},
+    Configuration, GeneratorParameters, Parser, Resources,
+};
+
+const FIXTURE: &str = include_str!("./test_cases/build_deps/module.lua");
+
+fn minify_configuration() -> Configuration {
+    Configuration::default().with_generator(GeneratorParameters::default_dense())
+}
+
+#[test]
+fn minify_fixture() {
+    let output = process_code(FIXTURE, minify_configuration()).unwrap();
+
+    insta::assert_snapshot!("build_deps_minify_fixture", output);
+}
+
+#[test]
+fn minified_fixture_is_parsable() {
+    let output = process_code(FIXTURE, minify_configuration()).unwrap();
+
+    assert!(!output.contains("--"));
+    Parser::default().parse(&output).unwrap();
+}
+
+#[test]
+fn process_code_with_parser_error() {
+    let result = process_code("local = 1", Configuration::default());
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn apply_rules_and_generate_code() {
+    let mut block = Parser::default().parse(FIXTURE).unwrap();
+
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("module.lua", &resources, FIXTURE).build();
+
+    let rules: Vec<Box<dyn Rule>> = vec![
+        Box::<RemoveComments>::default(),
+        Box::<RemoveSpaces>::default(),
+    ];
+    for rule in rules {
+        rule.process(&mut block, &context).unwrap();
+    }
+
+    let mut generator = DenseLuaGenerator::new(80);
+    generator.write_block(&block);
+    let output = generator.into_string();
+
+    insta::assert_snapshot!("build_deps_apply_rules", output);
+}
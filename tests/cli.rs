@@ -40,6 +40,11 @@ impl Context {
         self
     }
 
+    pub fn write_stdin(mut self, content: &str) -> Self {
+        self.command.write_stdin(content);
+        self
+    }
+
     pub fn expect_file<P: AsRef<Path>>(&self, file_path: P) -> &Self {
         let file_path = file_path.as_ref();
         if !file_path.exists() || !file_path.is_file() {
@@ -109,6 +114,22 @@ impl Context {
         self
     }
 
+    pub fn write_bytes<P: AsRef<Path>>(self, relative_path: P, content: &[u8]) -> Self {
+        let file_path = self.path_from_working_directory(relative_path);
+        fs::write(file_path, content).expect("unable to write file");
+        self
+    }
+
+    #[cfg(unix)]
+    pub fn symlink<P: AsRef<Path>>(self, relative_path: P, target: &str) -> Self {
+        let link = self.path_from_working_directory(relative_path);
+        if let Some(parent) = link.parent() {
+            fs::create_dir_all(parent).expect("unable to create directories");
+        }
+        std::os::unix::fs::symlink(target, link).expect("unable to create symlink");
+        self
+    }
+
     fn path_from_working_directory<P: AsRef<Path>>(&self, path: P) -> PathBuf {
         self.working_directory.path().join(path)
     }
@@ -146,6 +167,48 @@ impl Context {
         self
     }
 
+    pub fn expect_code(mut self, code: i32) -> Self {
+        self.command.assert().code(code);
+        self
+    }
+
+    pub fn expect_output_contains(mut self, expected: &str) -> Self {
+        let output = self.full_output();
+        assert!(
+            output.contains(expected),
+            "expected command output to contain `{}`\noutput:\n{}",
+            expected,
+            output
+        );
+        self
+    }
+
+    pub fn expect_output_occurrences(mut self, expected: &str, count: usize) -> Self {
+        let output = self.full_output();
+        assert_eq!(
+            output.matches(expected).count(),
+            count,
+            "expected command output to contain `{}` {} time(s)\noutput:\n{}",
+            expected,
+            count,
+            output
+        );
+        self
+    }
+
+    pub fn expect_file_contains<P: AsRef<Path>>(self, file_path: P, expected: &str) -> Self {
+        let file_path = self.path_from_working_directory(file_path.as_ref());
+        self.expect_file(&file_path);
+        let content = fs::read_to_string(file_path).expect("unable to read file");
+        assert!(
+            content.contains(expected),
+            "expected file to contain `{}`\ncontent:\n{}",
+            expected,
+            content
+        );
+        self
+    }
+
     pub fn replace_snapshot_content(
         mut self,
         matcher: impl Into<String>,
@@ -204,6 +267,30 @@ fn snapshot_convert_help_command() {
         .snapshot_command("convert_help_command");
 }
 
+#[test]
+fn snapshot_eval_help_command() {
+    Context::default()
+        .arg("eval")
+        .arg("--help")
+        .snapshot_command("eval_help_command");
+}
+
+#[test]
+fn snapshot_format_help_command() {
+    Context::default()
+        .arg("format")
+        .arg("--help")
+        .snapshot_command("format_help_command");
+}
+
+#[test]
+fn snapshot_format_check_help_command() {
+    Context::default()
+        .arg("format-check")
+        .arg("--help")
+        .snapshot_command("format_check_help_command");
+}
+
 #[test]
 fn run_minify_command() {
     Context::default()
@@ -276,6 +363,67 @@ fn run_process_command() {
         .snapshot_file("run_process_command_init_out", "out/init.lua");
 }
 
+#[test]
+fn run_process_with_rule_timeout() {
+    Context::default()
+        .write_file("src/init.lua", "return 1 + 1\n")
+        .arg("process")
+        .arg("src")
+        .arg("out")
+        .arg("--rule-timeout")
+        .arg("30s")
+        .expect_success()
+        .expect_file_contains("out/init.lua", "return 2");
+}
+
+#[test]
+fn run_process_with_invalid_rule_timeout() {
+    Context::default()
+        .write_file("src/init.lua", "return 1 + 1\n")
+        .arg("process")
+        .arg("src")
+        .arg("out")
+        .arg("--rule-timeout")
+        .arg("10m")
+        .expect_code(2)
+        .expect_output_contains("ambiguous unit `m`");
+}
+
+#[test]
+fn run_process_with_self_extract() {
+    Context::default()
+        .write_file(
+            "src/init.lua",
+            &"local function add(a, b) return a + b end\nprint(add(1, 2))\n".repeat(20),
+        )
+        .arg("process")
+        .arg("src")
+        .arg("out")
+        .arg("--self-extract")
+        .arg("--format")
+        .arg("retain_lines")
+        .expect_success()
+        .expect_output_contains("-> `init.lua` is ")
+        .expect_file_contains(
+            "out/init.lua",
+            "return assert((loadstring or load)(decompress(payload)))(...)",
+        );
+}
+
+#[test]
+fn run_process_with_self_extract_for_roblox_target() {
+    Context::default()
+        .write_file("src/init.lua", "return 1\n")
+        .arg("process")
+        .arg("src")
+        .arg("out")
+        .arg("--self-extract")
+        .arg("--target")
+        .arg("roblox")
+        .expect_code(1)
+        .expect_output_contains("unable to generate self-extracting files for the `roblox` target");
+}
+
 #[test]
 fn run_process_verbose_command() {
     Context::default()
@@ -325,6 +473,130 @@ fn run_process_single_file_custom_config_command_deprecated_config_path() {
         .snapshot_file("run_process_custom_config_command_out", "out.lua");
 }
 
+#[test]
+fn run_process_command_with_files_from_stdin() {
+    Context::default()
+        .write_file("src/a.lua", "return 1 + 1\n")
+        .write_file("src/nested/b.lua", "return 2 + 2\n")
+        .write_file("src/c.lua", "return 3 + 3\n")
+        .arg("process")
+        .arg("src")
+        .arg("out")
+        .arg("--files-from")
+        .arg("-")
+        .write_stdin("src/a.lua\nsrc/missing.lua\nsrc/nested/b.lua\nsrc/a.lua\n")
+        .replace_duration_labels()
+        .replace_backslashes()
+        .snapshot_command("run_process_command_with_files_from_stdin")
+        .snapshot_file("run_process_command_with_files_from_a_out", "out/a.lua")
+        .snapshot_file(
+            "run_process_command_with_files_from_b_out",
+            "out/nested/b.lua",
+        );
+}
+
+#[test]
+fn run_process_command_with_nul_separated_files_from() {
+    Context::default()
+        .write_file("src/a.lua", "return 1 + 1\n")
+        .write_file("src/nested/b.lua", "return 2 + 2\n")
+        .write_file("files.txt", "src/a.lua\0src/nested/b.lua")
+        .arg("process")
+        .arg("src")
+        .arg("out")
+        .arg("--files-from0")
+        .arg("files.txt")
+        .expect_success()
+        .snapshot_file("run_process_command_with_files_from_a_out", "out/a.lua")
+        .snapshot_file(
+            "run_process_command_with_files_from_b_out",
+            "out/nested/b.lua",
+        );
+}
+
+#[test]
+fn run_process_command_skips_renaming_with_dynamic_environment() {
+    Context::default()
+        .write_file(".darklua.json", "{ \"rules\": [\"rename_variables\"] }")
+        .write_file(
+            "src/init.lua",
+            "local value = 1\nsetfenv(1, { value = value })\nreturn value\n",
+        )
+        .arg("process")
+        .arg("src")
+        .arg("out")
+        .expect_output_contains(
+            "skip rule `rename_variables` because the file uses `setfenv` to access its environment dynamically",
+        )
+        .expect_file_contains("out/init.lua", "local value");
+}
+
+#[test]
+fn run_process_command_with_rojo_project() {
+    Context::default()
+        .write_file(
+            "default.project.json",
+            r#"{
+                "name": "game",
+                "tree": {
+                    "$className": "DataModel",
+                    "ReplicatedStorage": {
+                        "Shared": { "$path": "src/shared" }
+                    }
+                }
+            }"#,
+        )
+        .write_file(
+            ".darklua.json",
+            "{ \"rules\": [{ \"rule\": \"wrap_module_return\", \"wrapper\": \"wrap\", \"key_source\": \"rojo\" }] }",
+        )
+        .write_file("src/shared/Util.lua", "return {}\n")
+        .write_file("src/shared/Signal/init.lua", "return {}\n")
+        .write_file("src/other.lua", "return {}\n")
+        .arg("process")
+        .arg("src")
+        .arg("out")
+        .arg("--rojo-project")
+        .arg("default.project.json")
+        .expect_success()
+        .expect_file_contains("out/shared/Util.lua", "ReplicatedStorage.Shared.Util")
+        .expect_file_contains("out/shared/Signal/init.lua", "ReplicatedStorage.Shared.Signal")
+        .expect_file_contains("out/other.lua", "src/other");
+}
+
+#[test]
+fn run_format_command() {
+    Context::default()
+        .write_file(
+            ".darklua.json",
+            "{ \"rules\": [\"rename_variables\", \"normalize_semicolons\"] }",
+        )
+        .write_file("src/init.lua", "local value = 1;\nreturn value;\n")
+        .arg("format")
+        .arg("src")
+        .replace_duration_labels()
+        .expect_success()
+        .snapshot_command("run_format_command")
+        .snapshot_file("run_format_command_out", "src/init.lua");
+}
+
+#[test]
+fn run_format_check_command_on_unformatted_file() {
+    Context::default()
+        .write_file(
+            ".darklua.json",
+            "{ \"rules\": [\"rename_variables\", \"normalize_semicolons\"] }",
+        )
+        .write_file("src/init.lua", "local value = 1;\nreturn value;\n")
+        .write_file("src/formatted.lua", "local value = 1\nreturn value\n")
+        .arg("format-check")
+        .arg("src")
+        .replace_duration_labels()
+        .replace_backslashes()
+        .snapshot_command("run_format_check_command_on_unformatted_file")
+        .snapshot_file("run_format_check_command_unchanged_file", "src/init.lua");
+}
+
 #[test]
 fn run_convert_command_on_json_file_with_output() {
     Context::default()
@@ -382,3 +654,1180 @@ fn run_convert_command_errors_when_unrecognized_extension() {
         .replace_duration_labels()
         .snapshot_command("run_convert_command_errors_when_unrecognized_extension");
 }
+
+#[test]
+fn run_eval_command_with_value() {
+    Context::default()
+        .arg("eval")
+        .arg("--value")
+        .arg("--expression")
+        .arg("1 + 2 * 3")
+        .expect_success()
+        .snapshot_command("run_eval_command_with_value");
+}
+
+#[test]
+fn run_eval_command_with_unknown_variable() {
+    Context::default()
+        .arg("eval")
+        .arg("--expression")
+        .arg("value  +  2 * 3")
+        .expect_success()
+        .snapshot_command("run_eval_command_with_unknown_variable");
+}
+
+#[test]
+fn run_eval_command_errors_when_value_is_unknown() {
+    Context::default()
+        .arg("eval")
+        .arg("--value")
+        .arg("--expression")
+        .arg("value + 1")
+        .snapshot_command("run_eval_command_errors_when_value_is_unknown");
+}
+
+#[test]
+fn run_eval_command_errors_on_syntax_error() {
+    Context::default()
+        .arg("eval")
+        .arg("--expression")
+        .arg("1 + * 2")
+        .snapshot_command("run_eval_command_errors_on_syntax_error");
+}
+
+mod find {
+    use super::*;
+
+    const FIXTURE: &str = r#"local Players = game:GetService("Players")
+local URL = "https://example.com"
+counter = 0
+local function increment(step)
+    counter += step
+    return counter > 1000
+end
+return increment
+"#;
+
+    fn find_command(pattern: &str) -> Context {
+        Context::default()
+            .write_file("src/a.lua", FIXTURE)
+            .arg("find")
+            .arg("src")
+            .arg("--pattern")
+            .arg(pattern)
+    }
+
+    #[test]
+    fn find_method_call() {
+        find_command("call:game:GetService")
+            .expect_success()
+            .expect_output_contains("a.lua:1:17: call game:GetService(");
+    }
+
+    #[test]
+    fn find_global_reads_and_writes() {
+        find_command("global:counter")
+            .expect_success()
+            .expect_output_contains("a.lua:3:1: global counter\n")
+            .expect_output_contains("a.lua:5:5: global counter (in `increment`)")
+            .expect_output_contains("a.lua:6:12: global counter (in `increment`)");
+    }
+
+    #[test]
+    fn find_string_literal() {
+        find_command("string:example")
+            .expect_success()
+            .expect_output_contains("a.lua:2:13: string ");
+    }
+
+    #[test]
+    fn find_global_assignments() {
+        find_command("assign-global:*")
+            .expect_success()
+            .expect_output_contains("a.lua:3:1: assign-global counter\n")
+            .expect_output_contains("a.lua:5:5: assign-global counter (in `increment`)");
+    }
+
+    #[test]
+    fn find_number_above_threshold() {
+        find_command("number:>100")
+            .expect_success()
+            .expect_output_contains("a.lua:6:22: number 1000 (in `increment`)");
+    }
+
+    #[test]
+    fn find_with_json_format() {
+        find_command("number:>100")
+            .arg("--format")
+            .arg("json")
+            .expect_success()
+            .expect_output_contains("\"kind\": \"number\"")
+            .expect_output_contains("\"line\": 6")
+            .expect_output_contains("\"column\": 22")
+            .expect_output_contains("\"snippet\": \"1000\"")
+            .expect_output_contains("\"function\": \"increment\"")
+            .expect_output_contains("\"path\": ");
+    }
+
+    #[test]
+    fn find_without_match_fails() {
+        find_command("call:require").expect_code(1);
+    }
+
+    #[test]
+    fn find_with_fail_if_found() {
+        find_command("global:counter")
+            .arg("--fail-if-found")
+            .expect_code(1);
+    }
+
+    #[test]
+    fn find_without_match_with_fail_if_found_succeeds() {
+        find_command("call:require")
+            .arg("--fail-if-found")
+            .expect_success();
+    }
+
+    #[test]
+    fn find_with_invalid_pattern() {
+        find_command("method:print").expect_output_contains("unknown pattern kind `method`");
+    }
+}
+
+mod compare {
+    use super::*;
+
+    fn compare_command(config_b: &str) -> Context {
+        Context::default()
+            .write_file("src/plain.lua", "local value = true\nreturn value\n")
+            .write_file("src/comments.lua", "-- comment\nlocal a = 1\nreturn a\n")
+            .write_file("src/math.lua", "return 1 + 2\n")
+            .write_file(
+                "a.json",
+                "{ \"generator\": \"retain_lines\", \"rules\": [] }",
+            )
+            .write_file("b.json", config_b)
+            .arg("compare")
+            .arg("src")
+            .arg("--config-a")
+            .arg("a.json")
+            .arg("--config-b")
+            .arg("b.json")
+    }
+
+    const FORMATTING_CONFIG: &str =
+        "{ \"generator\": \"retain_lines\", \"rules\": [\"remove_comments\"] }";
+    const SEMANTIC_CONFIG: &str = "{ \"generator\": \"retain_lines\", \"rules\": [\"remove_comments\", \"compute_expression\"] }";
+
+    #[test]
+    fn compare_reports_each_file() {
+        compare_command(SEMANTIC_CONFIG)
+            .expect_success()
+            .expect_output_contains("plain.lua\n")
+            .expect_output_contains("formatting only: src")
+            .expect_output_contains("math.lua (at last statement (return))")
+            .expect_output_contains("compared 3 files: 1 identical, 1 formatting only, 1 different")
+            .expect_output_contains("with `a.json`");
+    }
+
+    #[test]
+    fn compare_with_show_diff() {
+        compare_command(SEMANTIC_CONFIG)
+            .arg("--show-diff")
+            .expect_success()
+            .expect_output_contains("-return 1 + 2\n+return 3\n")
+            .expect_output_contains("--- a/src");
+    }
+
+    #[test]
+    fn compare_with_require_ast_equal_fails_on_semantic_difference() {
+        compare_command(SEMANTIC_CONFIG)
+            .arg("--require-ast-equal")
+            .expect_code(1);
+    }
+
+    #[test]
+    fn compare_with_require_ast_equal_allows_formatting_difference() {
+        compare_command(FORMATTING_CONFIG)
+            .arg("--require-ast-equal")
+            .expect_success()
+            .expect_output_contains(
+                "compared 3 files: 2 identical, 1 formatting only, 0 different",
+            );
+    }
+
+    #[test]
+    fn compare_with_missing_configuration() {
+        Context::default()
+            .write_file("src/init.lua", "return nil\n")
+            .write_file("a.json", "{ \"rules\": [] }")
+            .arg("compare")
+            .arg("src")
+            .arg("--config-a")
+            .arg("a.json")
+            .arg("--config-b")
+            .arg("missing.json")
+            .expect_code(2);
+    }
+}
+
+mod graph {
+    use super::*;
+
+    fn graph_command() -> Context {
+        Context::default()
+            .write_file("src/main.lua", "local a = require('./a')\nreturn a")
+            .write_file("src/a.lua", "local b = require(script.Parent.b)\nreturn b")
+            .write_file("src/b.lua", "local a = require('./a')\nreturn a")
+            .write_file("src/lib/init.lua", "return require('./missing')")
+            .arg("graph")
+            .arg("src")
+    }
+
+    #[test]
+    fn graph_with_dot_format() {
+        graph_command()
+            .expect_success()
+            .expect_output_contains(
+                "digraph dependencies {\n    \"src/a.lua\";\n    \"src/b.lua\";\n    \"src/lib/init.lua\";\n    \"src/main.lua\";\n",
+            )
+            .expect_output_contains("    \"src/a.lua\" -> \"src/b.lua\" [color=red];\n")
+            .expect_output_contains("    \"src/b.lua\" -> \"src/a.lua\" [color=red];\n")
+            .expect_output_contains("    \"src/main.lua\" -> \"src/a.lua\";\n}")
+            .expect_output_contains("found 1 dependency cycle:\n-> src/a.lua -> src/b.lua -> src/a.lua")
+            .expect_output_contains("src/lib/init.lua:1: unable to resolve");
+    }
+
+    #[test]
+    fn graph_with_edges_format() {
+        graph_command()
+            .arg("--format")
+            .arg("edges")
+            .expect_success()
+            .expect_output_contains(
+                "src/a.lua -> src/b.lua\nsrc/b.lua -> src/a.lua\nsrc/main.lua -> src/a.lua\n",
+            );
+    }
+
+    #[test]
+    fn graph_with_json_format() {
+        graph_command()
+            .arg("--format")
+            .arg("json")
+            .expect_success()
+            .expect_output_contains("\"src/main.lua\": [\n      \"src/a.lua\"\n    ]")
+            .expect_output_contains(
+                "\"cycles\": [\n    [\n      \"src/a.lua\",\n      \"src/b.lua\"\n    ]\n  ]",
+            )
+            .expect_output_contains("\"source\": \"src/lib/init.lua\"");
+    }
+
+    #[test]
+    fn graph_with_focus() {
+        graph_command()
+            .arg("--format")
+            .arg("edges")
+            .arg("--focus")
+            .arg("src/lib/init.lua")
+            .expect_success()
+            .expect_output_contains("src/lib/init.lua:1: unable to resolve");
+    }
+
+    #[test]
+    fn graph_with_focus_on_unknown_module() {
+        graph_command()
+            .arg("--focus")
+            .arg("src/other.lua")
+            .expect_code(1)
+            .expect_output_contains("unable to focus on `src/other.lua`");
+    }
+}
+
+mod analyze_size {
+    use super::*;
+
+    fn analyze_size_command() -> Context {
+        Context::default()
+            .write_file(
+                "src/main.lua",
+                "-- module\nlocal Players = game:GetService(\"Players\")\nlocal function greet(name)\n    print(\"hello \" .. name, 10)\nend\nreturn { greet = greet }\n",
+            )
+            .arg("analyze-size")
+            .arg("src")
+    }
+
+    #[test]
+    fn analyze_raw_file() {
+        analyze_size_command()
+            .arg("--raw")
+            .expect_success()
+            .expect_output_contains("src/main.lua: 141 bytes\n")
+            .expect_output_contains("total: 141 bytes in 1 file\n")
+            .expect_output_contains("  local identifiers             25    17.7%\n")
+            .expect_output_contains("  comments                       9     6.4%\n")
+            .expect_output_contains(
+                "longest identifiers:\n  GetService (10 bytes, 1 occurrence)\n  Players (7 bytes, 1 occurrence)\n  greet (5 bytes, 3 occurrences)\n",
+            )
+            .expect_output_contains("longest strings:\n  \"Players\" (9 bytes, 1 occurrence)\n");
+    }
+
+    #[test]
+    fn analyze_raw_files_without_excluded_files() {
+        analyze_size_command()
+            .write_file("src/main.spec.lua", "return nil\n")
+            .arg("--raw")
+            .arg("--exclude")
+            .arg("*.spec.lua")
+            .expect_success()
+            .expect_output_contains("total: 141 bytes in 1 file\n");
+    }
+
+    #[test]
+    fn analyze_processed_file() {
+        analyze_size_command()
+            .write_file(
+                "config.json",
+                "{ \"generator\": \"dense\", \"rules\": [\"remove_comments\"] }",
+            )
+            .arg("--config")
+            .arg("config.json")
+            .expect_success()
+            .expect_output_contains("  comments                       0     0.0%\n");
+    }
+
+    #[test]
+    fn analyze_by_function_with_json_format() {
+        analyze_size_command()
+            .arg("--raw")
+            .arg("--by-function")
+            .arg("--format")
+            .arg("json")
+            .expect_success()
+            .expect_output_contains("\"size\": 141,")
+            .expect_output_contains("\"local_identifiers\": 25,")
+            .expect_output_contains(
+                "\"functions\": [\n    {\n      \"path\": \"src/main.lua\",\n      \"name\": \"greet\",\n      \"line\": 3,\n      \"size\": 62\n    }\n  ]",
+            );
+    }
+
+    #[test]
+    fn analyze_file_with_syntax_error() {
+        Context::default()
+            .write_file("src/main.lua", "local = 1")
+            .arg("analyze-size")
+            .arg("src")
+            .arg("--raw")
+            .expect_code(1)
+            .expect_output_contains("unable to parse");
+    }
+}
+
+mod deprecated_rules {
+    use super::*;
+
+    const DEPRECATION_WARNING: &str =
+        "rule `remove_compound_assign` is deprecated, use `remove_compound_assignment` instead";
+    const EXPECTED_OUTPUT: &str = "local count=0 count=count+1 return count";
+
+    fn process_command(rule_name: &str) -> Context {
+        Context::default()
+            .write_file(
+                "src/main.lua",
+                "local count = 0\ncount += 1\nreturn count\n",
+            )
+            .write_file(
+                "config.json",
+                &format!(
+                    "{{ \"generator\": \"dense\", \"rules\": [\"{}\", {{ \"rule\": \"{}\" }}] }}",
+                    rule_name, rule_name
+                ),
+            )
+            .arg("process")
+            .arg("src")
+            .arg("out")
+            .arg("--config")
+            .arg("config.json")
+    }
+
+    #[test]
+    fn process_with_canonical_rule_name() {
+        process_command("remove_compound_assignment")
+            .expect_success()
+            .expect_output_occurrences("is deprecated", 0)
+            .expect_file_contains("out/main.lua", EXPECTED_OUTPUT);
+    }
+
+    #[test]
+    fn process_with_deprecated_rule_name_warns_once() {
+        process_command("remove_compound_assign")
+            .expect_success()
+            .expect_output_occurrences(DEPRECATION_WARNING, 1)
+            .expect_file_contains("out/main.lua", EXPECTED_OUTPUT);
+    }
+
+    #[test]
+    fn process_with_deprecated_rule_name_without_warnings() {
+        process_command("remove_compound_assign")
+            .arg("--no-deprecation-warnings")
+            .expect_success()
+            .expect_output_occurrences("is deprecated", 0)
+            .expect_file_contains("out/main.lua", EXPECTED_OUTPUT);
+    }
+
+    #[test]
+    fn process_with_deprecated_rule_name_denied() {
+        process_command("remove_compound_assign")
+            .arg("--deny-deprecated")
+            .expect_code(1)
+            .expect_output_occurrences(DEPRECATION_WARNING, 1);
+    }
+}
+
+mod strict_mode {
+    use super::*;
+
+    const UNSAFE_RULES_ERROR: &str = "strict mode does not allow rules configured to change the behavior of the code: `hoist_loop_invariants` (`assume_no_metatables`), `remove_assertions` (`preserve_arguments_side_effects`, `assume_no_env_manipulation`)";
+    const UNSAFE_RULES: &str = concat!(
+        "\"rules\": [",
+        "\"compute_expression\", ",
+        "{ \"rule\": \"hoist_loop_invariants\", \"assume_no_metatables\": true }, ",
+        "{ \"rule\": \"remove_assertions\", \"preserve_arguments_side_effects\": false, \"assume_no_env_manipulation\": true }",
+        "]"
+    );
+
+    fn process_command(config: &str) -> Context {
+        Context::default()
+            .write_file("src/main.lua", "return 1 + 2\n")
+            .write_file("config.json", config)
+            .arg("process")
+            .arg("src")
+            .arg("out")
+            .arg("--config")
+            .arg("config.json")
+    }
+
+    #[test]
+    fn process_unsafe_rules_without_strict_mode() {
+        process_command(&format!("{{ \"generator\": \"dense\", {} }}", UNSAFE_RULES))
+            .expect_success()
+            .expect_file_contains("out/main.lua", "return 3");
+    }
+
+    #[test]
+    fn process_unsafe_rules_with_strict_flag() {
+        process_command(&format!("{{ \"generator\": \"dense\", {} }}", UNSAFE_RULES))
+            .arg("--strict")
+            .expect_code(1)
+            .expect_output_occurrences(UNSAFE_RULES_ERROR, 1);
+    }
+
+    #[test]
+    fn process_unsafe_rules_with_strict_configuration() {
+        process_command(&format!(
+            "{{ \"generator\": \"dense\", \"strict\": true, {} }}",
+            UNSAFE_RULES
+        ))
+        .expect_code(1)
+        .expect_output_occurrences(UNSAFE_RULES_ERROR, 1);
+    }
+
+    #[test]
+    fn process_safe_rules_with_strict_flag() {
+        process_command(
+            "{ \"generator\": \"dense\", \"rules\": [\"compute_expression\", \"hoist_loop_invariants\"] }",
+        )
+        .arg("--strict")
+        .expect_success()
+        .expect_file_contains("out/main.lua", "return 3");
+    }
+}
+
+mod paths {
+    use super::*;
+
+    const CODE: &str = "local value = 1\nreturn value\n";
+    const GENERATED: &str = "local value=1 return value";
+
+    fn process_command() -> Context {
+        Context::default()
+            .write_file("src/main.lua", CODE)
+            .write_file("config.json", "{ \"generator\": \"dense\", \"rules\": [] }")
+            .arg("process")
+            .arg("--config")
+            .arg("config.json")
+    }
+
+    #[test]
+    fn process_input_with_current_dir_component() {
+        process_command()
+            .arg("./src")
+            .arg("out")
+            .expect_success()
+            .expect_file_contains("out/main.lua", GENERATED);
+    }
+
+    #[test]
+    fn process_files_listed_twice_with_different_paths() {
+        let context = process_command();
+        let absolute_source = context.path_from_working_directory("src/main.lua");
+
+        context
+            .write_file(
+                "files.txt",
+                &format!("src/main.lua\n{}\n", absolute_source.display()),
+            )
+            .arg("src")
+            .arg("src")
+            .arg("--files-from")
+            .arg("files.txt")
+            .expect_success()
+            .expect_output_contains("successfully processed 1 file ")
+            .expect_file_contains("src/main.lua", GENERATED);
+    }
+
+    #[test]
+    fn process_glob_input_without_excluded_files() {
+        process_command()
+            .write_file("src/main.spec.lua", CODE)
+            .write_file("src/nested/util.lua", CODE)
+            .arg("src/**/*.lua")
+            .arg("out")
+            .arg("--exclude")
+            .arg("**/*.spec.lua")
+            .expect_success()
+            .expect_output_contains("successfully processed 2 files")
+            .expect_file_contains("out/main.lua", GENERATED)
+            .expect_file_contains("out/nested/util.lua", GENERATED);
+    }
+
+    #[test]
+    fn process_directory_without_excluded_directory() {
+        process_command()
+            .write_file("src/Packages/module.lua", CODE)
+            .write_file("src/nested/Packages/module.lua", CODE)
+            .arg("src")
+            .arg("out")
+            .arg("--exclude")
+            .arg("Packages")
+            .expect_success()
+            .expect_output_contains("successfully processed 2 files")
+            .expect_file_contains("out/main.lua", GENERATED)
+            .expect_file_contains("out/nested/Packages/module.lua", GENERATED);
+    }
+
+    #[test]
+    fn minify_directory_without_excluded_files() {
+        Context::default()
+            .write_file("src/main.lua", CODE)
+            .write_file("src/main.spec.lua", CODE)
+            .arg("minify")
+            .arg("src")
+            .arg("out")
+            .arg("--exclude")
+            .arg("*.spec.lua")
+            .expect_success()
+            .expect_output_contains("successfully minified 1 file ")
+            .expect_file_contains("out/main.lua", GENERATED);
+    }
+
+    #[test]
+    fn eval_does_not_accept_exclude() {
+        Context::default()
+            .arg("eval")
+            .arg("--expression")
+            .arg("1 + 2")
+            .arg("--exclude")
+            .arg("*.spec.lua")
+            .expect_code(2)
+            .expect_output_contains("unexpected argument '--exclude'");
+    }
+
+    #[test]
+    fn process_glob_input_without_matches() {
+        process_command()
+            .arg("src/**/*.luau")
+            .arg("out")
+            .expect_code(1)
+            .expect_output_contains("no Lua files match the input pattern `src/**/*.luau`");
+    }
+
+    #[cfg(unix)]
+    mod symlinks {
+        use super::*;
+
+        #[test]
+        fn process_directory_with_symlink_cycle_without_following_symlinks() {
+            process_command()
+                .symlink("src/cycle", ".")
+                .arg("src")
+                .arg("out")
+                .expect_success()
+                .expect_output_contains("successfully processed 1 file ")
+                .expect_file_contains("out/main.lua", GENERATED);
+        }
+
+        #[test]
+        fn process_directory_with_symlink_cycle_following_symlinks() {
+            process_command()
+                .symlink("src/cycle", ".")
+                .arg("src")
+                .arg("out")
+                .arg("--follow-symlinks")
+                .expect_success()
+                .expect_output_contains("successfully processed 1 file ")
+                .expect_file_contains("out/main.lua", GENERATED);
+        }
+
+        #[test]
+        fn process_symlinked_directory_once_in_place() {
+            process_command()
+                .write_file("src/lib/util.lua", CODE)
+                .symlink("src/alias", "lib")
+                .arg("src")
+                .arg("./src")
+                .arg("--follow-symlinks")
+                .expect_success()
+                .expect_output_contains("successfully processed 2 files ")
+                .expect_file_contains("src/lib/util.lua", GENERATED);
+        }
+
+        #[test]
+        fn process_symlinked_directory_following_symlinks() {
+            process_command()
+                .write_file("lib/util.lua", CODE)
+                .symlink("src/lib", "../lib")
+                .arg("src")
+                .arg("out")
+                .arg("--follow-symlinks")
+                .expect_success()
+                .expect_file_contains("out/lib/util.lua", GENERATED);
+        }
+
+        #[test]
+        fn process_listed_files_with_same_output_following_symlinks() {
+            process_command()
+                .symlink("src/alias.lua", "main.lua")
+                .write_file("files.txt", "src/main.lua\nsrc/alias.lua\n")
+                .arg("src")
+                .arg("src")
+                .arg("--files-from")
+                .arg("files.txt")
+                .arg("--follow-symlinks")
+                .expect_code(1)
+                .expect_output_contains(
+                    "`src/alias.lua` and `src/main.lua` would both be written to",
+                );
+        }
+    }
+}
+
+mod check_output {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+    checks: [
+        { forbid_call: 'loadstring' },
+        { forbid_global: 'getfenv' },
+        'forbid_comments',
+        { max_line_length: 30 },
+    ],
+}"#;
+
+    fn check_output_command() -> Context {
+        Context::default()
+            .write_file(".darklua.json5", CONFIG)
+            .arg("check-output")
+            .arg("out")
+    }
+
+    #[test]
+    fn check_output_on_clean_files() {
+        check_output_command()
+            .write_file("out/a.lua", "local a = 1\nreturn a\n")
+            .write_file("out/b.lua", "return {}\n")
+            .expect_success();
+    }
+
+    #[test]
+    fn check_output_reports_violations() {
+        check_output_command()
+            .write_file("out/clean.lua", "return 1\n")
+            .write_file(
+                "out/dirty.lua",
+                "-- generated\nlocal env = getfenv()\nreturn loadstring(env.code)\n",
+            )
+            .expect_code(1)
+            .expect_output_contains("dirty.lua:1:1: forbidden comment (forbid_comments)\n")
+            .expect_output_contains("dirty.lua:2:13: forbidden global `getfenv` (forbid_global)\n")
+            .expect_output_contains(
+                "dirty.lua:3:8: forbidden call `loadstring(env.code)` (forbid_call)\n",
+            )
+            .expect_output_occurrences("clean.lua", 0)
+            .expect_output_contains("found 3 violations in 1 file");
+    }
+
+    #[test]
+    fn check_output_reports_long_lines() {
+        check_output_command()
+            .write_file("out/a.lua", "local value = 'a very long string value'\n")
+            .expect_code(1)
+            .expect_output_contains(
+                "a.lua:1:31: line of 40 characters is longer than 30 characters (max_line_length)",
+            );
+    }
+
+    #[test]
+    fn check_output_reports_unparsable_files() {
+        check_output_command()
+            .write_file("out/a.lua", "local =\n")
+            .expect_code(1)
+            .expect_output_contains("unable to parse generated code")
+            .expect_output_contains("(require_parse)");
+    }
+
+    #[test]
+    fn check_output_with_custom_config() {
+        Context::default()
+            .write_file(
+                "config.json5",
+                "{ checks: [{ forbid_string_matching: 'secret' }] }",
+            )
+            .write_file("out/a.lua", "return 'my secret'\n")
+            .arg("check-output")
+            .arg("out")
+            .arg("--config")
+            .arg("config.json5")
+            .expect_code(1)
+            .expect_output_contains(
+                "a.lua:1:8: forbidden string `'my secret'` (forbid_string_matching)",
+            );
+    }
+}
+
+mod check_docs {
+    use super::*;
+
+    const FIXTURE: &str = r#"--- Adds two numbers
+--- @param a number
+--- @param c number
+--- @return number
+local function add(a, b)
+    return a + b
+end
+
+-- not documented
+local function sub(a, b)
+    return a - b
+end
+
+return { add = add, sub = sub }
+"#;
+
+    fn check_docs_command() -> Context {
+        Context::default()
+            .write_file("src/a.lua", FIXTURE)
+            .arg("check-docs")
+            .arg("src")
+    }
+
+    #[test]
+    fn check_docs_on_documented_functions() {
+        Context::default()
+            .write_file(
+                "src/a.lua",
+                "--- @param ... any\nlocal function log(...)\n    print(...)\nend\n",
+            )
+            .arg("check-docs")
+            .arg("src")
+            .expect_success();
+    }
+
+    #[test]
+    fn check_docs_reports_mismatches() {
+        check_docs_command()
+            .expect_code(1)
+            .expect_output_contains(
+                "a.lua:3:5: documented parameter `c` does not exist (in `add`)\n",
+            )
+            .expect_output_contains("a.lua:5:23: parameter `b` is not documented (in `add`)\n")
+            .expect_output_occurrences("sub", 0)
+            .expect_output_contains("found 2 documentation issues");
+    }
+
+    #[test]
+    fn check_docs_with_json_format() {
+        check_docs_command()
+            .arg("--format")
+            .arg("json")
+            .expect_code(1)
+            .expect_output_contains("\"kind\": \"missing_parameter\"")
+            .expect_output_contains("\"kind\": \"unknown_parameter\"")
+            .expect_output_contains("\"parameter\": \"b\"")
+            .expect_output_contains("\"line\": 5")
+            .expect_output_contains("\"column\": 23")
+            .expect_output_contains("\"function\": \"add\"")
+            .expect_output_contains("\"path\": ");
+    }
+
+    #[test]
+    fn check_docs_with_fix() {
+        check_docs_command()
+            .arg("--fix")
+            .expect_success()
+            .expect_file_contains(
+                "src/a.lua",
+                "--- Adds two numbers\n--- @param a number\n--- @param b TODO\n--- @return number\nlocal function add(a, b)\n",
+            );
+    }
+
+    #[test]
+    fn check_docs_with_fix_reports_order_mismatches() {
+        Context::default()
+            .write_file(
+                "src/a.lua",
+                "-- @param b\n-- @param a\nlocal function f(a, b) end\n",
+            )
+            .arg("check-docs")
+            .arg("src")
+            .arg("--fix")
+            .expect_code(1)
+            .expect_output_contains(
+                "a.lua:1:4: documented parameters are not in the order of the function parameters (`a, b`) (in `f`)",
+            );
+    }
+
+    #[test]
+    fn check_docs_reports_unparsable_files() {
+        Context::default()
+            .write_file("src/a.lua", "local =\n")
+            .arg("check-docs")
+            .arg("src")
+            .expect_code(2)
+            .expect_output_contains("unable to parse");
+    }
+}
+
+mod doctor {
+    use super::*;
+
+    fn doctor_command() -> Context {
+        Context::default()
+            .write_file(
+                "src/env.lua",
+                "setfenv(1, {})\nlocal value = 1\nreturn value\n",
+            )
+            .write_file(
+                "src/vararg.lua",
+                "local function pack(...)\n    return ...\nend\nreturn pack(1)\n",
+            )
+            .write_file("src/bad.lua", "local =\n")
+            .write_bytes("src/latin.lua", b"return '\xE9t\xE9'\n")
+            .arg("doctor")
+            .arg("src")
+    }
+
+    #[test]
+    fn doctor_prints_human_summary() {
+        doctor_command()
+            .expect_success()
+            .expect_output_contains("checked 4 files")
+            .expect_output_contains("2 files could not be analyzed:")
+            .expect_output_contains("bad.lua: unable to parse file: ")
+            .expect_output_contains("latin.lua: file is not valid UTF-8")
+            .expect_output_contains("rename_variables: skipped in 1 file\n")
+            .expect_output_contains(
+                "env.lua: skipped: the file uses `setfenv` to access its environment dynamically",
+            )
+            .expect_output_contains("inline_functions: skipped in 1 file, conservative in 1 file")
+            .expect_output_contains(
+                "vararg.lua: conservative (line 1): local function `pack` is variadic and is never inlined",
+            )
+            .expect_output_contains("rules without compatibility notes");
+    }
+
+    #[test]
+    fn doctor_prints_json_report() {
+        doctor_command()
+            .arg("--format")
+            .arg("json")
+            .expect_success()
+            .expect_output_contains("\"checked_files\": 4")
+            .expect_output_contains("\"message\": \"file is not valid UTF-8\"")
+            .expect_output_contains("\"rule\": \"rename_variables\",\n      \"skipped\": 1,")
+            .expect_output_contains("\"kind\": \"conservative\"")
+            .expect_output_contains("\"line\": 1");
+    }
+
+    #[test]
+    fn doctor_with_unknown_format() {
+        doctor_command()
+            .arg("--format")
+            .arg("yaml")
+            .expect_code(2)
+            .expect_output_contains("format 'yaml' does not exist!");
+    }
+}
+
+mod apply_edits {
+    use super::*;
+
+    const EDITS: &str = r#"[
+        { "operation": "replace", "path": "block.statements[0].values[0]", "code": "2" },
+        { "operation": "delete", "path": "block.statements[1]" }
+    ]"#;
+
+    fn apply_edits_command(edits: &str) -> Context {
+        Context::default()
+            .write_file("src/main.lua", "local a = 1\nprint(a)\nreturn a\n")
+            .write_file("edits.json", edits)
+            .arg("apply-edits")
+            .arg("src/main.lua")
+            .arg("--edits")
+            .arg("edits.json")
+    }
+
+    #[test]
+    fn apply_edits_writes_file() {
+        apply_edits_command(EDITS)
+            .expect_success()
+            .expect_file_contains("src/main.lua", "local a = 2\n\nreturn a\n");
+    }
+
+    #[test]
+    fn apply_edits_check_does_not_write_file() {
+        apply_edits_command(EDITS)
+            .arg("--check")
+            .expect_success()
+            .expect_file_contains("src/main.lua", "local a = 1\nprint(a)\n");
+    }
+
+    #[test]
+    fn apply_edits_with_unknown_field() {
+        apply_edits_command(
+            r#"[{ "operation": "replace", "path": "block.statements[0]", "node": {} }]"#,
+        )
+        .expect_code(1)
+        .expect_output_contains("unable to read edits from `edits.json`: unknown field `node`")
+        .expect_file_contains("src/main.lua", "local a = 1\nprint(a)\n");
+    }
+}
+
+mod rules {
+    use super::*;
+
+    #[test]
+    fn rules_lists_rule_names() {
+        Context::default()
+            .arg("rules")
+            .expect_success()
+            .expect_output_contains("remove_spaces\n");
+    }
+
+    #[test]
+    fn rules_full_json_runs_examples() {
+        Context::default()
+            .arg("rules")
+            .arg("--format")
+            .arg("json")
+            .arg("--full")
+            .expect_success()
+            .expect_output_contains(r#""name": "convert_index_to_field""#)
+            .expect_output_contains(r#""output": "local value = object.name""#);
+    }
+
+    #[test]
+    fn rules_full_requires_json_format() {
+        Context::default()
+            .arg("rules")
+            .arg("--full")
+            .expect_code(1)
+            .expect_output_contains("`--full` can only be used with `--format json`");
+    }
+}
+
+mod flatten_closures {
+    use super::*;
+
+    fn process_command(code: &str) -> Context {
+        Context::default()
+            .write_file("src/main.lua", code)
+            .write_file("config.json", "{ \"rules\": [\"flatten_closures\"] }")
+            .arg("process")
+            .arg("src")
+            .arg("out")
+            .arg("--config")
+            .arg("config.json")
+    }
+
+    #[test]
+    fn flatten_closure_called_immediately() {
+        process_command(
+            "--!darklua flatten-next\nlocal function add(a) return function(b) return a + b end end\nprint(add(1)(2))\n",
+        )
+        .expect_success()
+        .expect_output_occurrences("unable to flatten", 0)
+        .expect_file_contains("out/main.lua", "local function add(a, b) return a + b end\nprint(add(1, 2))");
+    }
+
+    #[test]
+    fn stored_intermediate_function_warns() {
+        process_command(
+            "--!darklua flatten-next\nlocal function add(a) return function(b) return a + b end end\nlocal inc = add(1)\nprint(inc(2))\n",
+        )
+        .expect_success()
+        .expect_output_contains(
+            "flatten_closures: unable to flatten `add`: the result of a call to `add` is not immediately called",
+        )
+        .expect_file_contains("out/main.lua", "local function add(a) return function(b)");
+    }
+}
+
+mod sort_local_functions {
+    use super::*;
+
+    fn process_command(code: &str) -> Context {
+        Context::default()
+            .write_file("src/main.lua", code)
+            .write_file("config.json", "{ \"rules\": [\"sort_local_functions\"] }")
+            .arg("process")
+            .arg("src")
+            .arg("out")
+            .arg("--config")
+            .arg("config.json")
+    }
+
+    #[test]
+    fn move_function_before_its_use() {
+        process_command("print(f())\nlocal function f() return 1 end\n")
+            .expect_success()
+            .expect_output_occurrences("unable to define", 0)
+            .expect_file_contains(
+                "out/main.lua",
+                "local function f() return 1 end\nprint(f())",
+            );
+    }
+
+    #[test]
+    fn function_depending_on_local_declared_after_use_warns() {
+        process_command(
+            "print(f())\nlocal prefix = 'x'\nlocal function f() return prefix end\n",
+        )
+        .expect_success()
+        .expect_output_contains(
+            "sort_local_functions: unable to define `f` before its first use: it depends on a local declared after that use",
+        )
+        .expect_file_contains("out/main.lua", "print(f())\nlocal prefix = 'x'");
+    }
+}
+
+mod config {
+    use super::*;
+
+    const BASE_CONFIG: &str = r#"{
+    rules: ["remove_comments"],
+    generator: { name: "readable", column_span: 80 },
+}"#;
+
+    fn config_command(config: &str) -> Context {
+        Context::default()
+            .write_file("shared/base.json5", BASE_CONFIG)
+            .write_file(".darklua.json5", config)
+    }
+
+    #[test]
+    fn process_with_extended_configuration() {
+        config_command(
+            "{ extends: 'shared/base.json5', rules_mode: 'append', rules: ['remove_spaces'] }",
+        )
+        .write_file("src/main.lua", "-- comment\nlocal a   =   1\n")
+        .arg("process")
+        .arg("src")
+        .arg("out")
+        .expect_success()
+        .expect_file_contains("out/main.lua", "local a = 1");
+    }
+
+    #[test]
+    fn show_resolved_configuration() {
+        config_command(
+            "{ extends: 'shared/base.json5', rules_mode: 'append', rules: ['remove_spaces'], generator: { name: 'readable', column_span: 120 } }",
+        )
+        .arg("config")
+        .arg("show")
+        .arg("--resolved")
+        .expect_success()
+        .expect_output_contains("\"remove_comments\"")
+        .expect_output_contains("\"remove_spaces\"")
+        .expect_output_contains("\"column_span\": 120")
+        .expect_output_occurrences("extends", 0);
+    }
+
+    #[test]
+    fn show_configuration_without_resolving() {
+        config_command("{ extends: 'shared/base.json5' }")
+            .arg("config")
+            .arg("show")
+            .expect_success()
+            .expect_output_contains("\"extends\": \"shared/base.json5\"")
+            .expect_output_occurrences("remove_comments", 0);
+    }
+
+    #[test]
+    fn show_configuration_with_missing_extended_file() {
+        config_command("{ extends: 'shared/missing.json5' }")
+            .arg("config")
+            .arg("show")
+            .arg("--resolved")
+            .expect_code(1)
+            .expect_output_contains("extended by `.darklua.json5`");
+    }
+
+    #[test]
+    fn show_configuration_with_cycle() {
+        Context::default()
+            .write_file("a.json", "{ \"extends\": \"b.json\" }")
+            .write_file("b.json", "{ \"extends\": \"a.json\" }")
+            .arg("config")
+            .arg("show")
+            .arg("--resolved")
+            .arg("--config")
+            .arg("a.json")
+            .expect_code(1)
+            .expect_output_contains("`a.json` -> `b.json` -> `a.json`");
+    }
+}
+
+mod inject_global_value {
+    use super::*;
+
+    fn process_command(code: &str) -> Context {
+        Context::default()
+            .write_file("src/main.lua", code)
+            .write_file(
+                "config.json5",
+                "{ rules: [{ rule: 'inject_global_value', identifier: 'FLAG', value: 1, expected_type: 'number' }] }",
+            )
+            .arg("process")
+            .arg("src")
+            .arg("out")
+            .arg("--config")
+            .arg("config.json5")
+    }
+
+    #[test]
+    fn type_comparison_with_other_type_warns() {
+        process_command("if type(FLAG) == 'string' then print(FLAG) end\n")
+            .expect_success()
+            .expect_output_contains(
+                "inject_global_value: `type(FLAG)` is compared with `\"string\"` but `FLAG` is expected to be a `number`",
+            )
+            .expect_file_contains("out/main.lua", "if type(1) == 'string' then print(1) end");
+    }
+
+    #[test]
+    fn type_comparison_with_expected_type_does_not_warn() {
+        process_command("if type(FLAG) == 'number' then print(FLAG) end\n")
+            .expect_success()
+            .expect_output_occurrences("is expected to be", 0);
+    }
+}
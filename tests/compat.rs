@@ -0,0 +1,91 @@
+// this test file processes code containing lexical edge cases where the Lua 5.1, LuaJIT and
+// Luau lexers disagree, and validates that the generated code is read the same way by each of
+// them
+
+use darklua_core::{
+    generator::{lex_strict, LexicalTokenKind},
+    process, Configuration, GeneratorParameters, Options, Parser, Resources,
+};
+
+fn process_with_generator(input: &str, generator: GeneratorParameters) -> String {
+    let resources = Resources::from_memory();
+    resources.write("src/test.lua", input).unwrap();
+
+    process(
+        &resources,
+        Options::new("src/test.lua")
+            .with_output("out/test.lua")
+            .with_configuration(Configuration::default().with_generator(generator)),
+    )
+    .unwrap()
+    .result()
+    .unwrap();
+
+    resources.get("out/test.lua").unwrap()
+}
+
+fn assert_compatible(output: &str) {
+    let tokens = lex_strict(output).unwrap_or_else(|err| {
+        panic!(
+            "generated code would not be read the same by every lexer: {}\ncode:\n{}",
+            err, output
+        )
+    });
+
+    if let Some(comment) = tokens
+        .iter()
+        .find(|token| token.kind() == LexicalTokenKind::Comment)
+    {
+        panic!(
+            "generated code contains an unexpected comment `{}`\ncode:\n{}",
+            comment.content(),
+            output
+        );
+    }
+
+    Parser::default()
+        .parse(output)
+        .unwrap_or_else(|err| panic!("unable to parse generated code: {}\ncode:\n{}", err, output));
+}
+
+macro_rules! generate_tests {
+    ($( $name:ident =>  $file_name:literal ),* $(,)?) => {
+        $(
+            mod $name {
+                use super::*;
+
+                const INPUT: &str = include_str!($file_name);
+
+                #[test]
+                fn dense() {
+                    assert_compatible(&process_with_generator(
+                        INPUT,
+                        GeneratorParameters::default_dense(),
+                    ));
+                }
+
+                #[test]
+                fn readable() {
+                    assert_compatible(&process_with_generator(
+                        INPUT,
+                        GeneratorParameters::default_readable(),
+                    ));
+                }
+
+                #[test]
+                fn input_is_compatible() {
+                    assert_compatible(INPUT);
+                }
+            }
+        )*
+    }
+}
+
+generate_tests!(
+    long_string_index => "./compat/long_string_index.lua",
+    minus_negative_literal => "./compat/minus_negative_literal.lua",
+    number_concat => "./compat/number_concat.lua",
+    hex_number_sign => "./compat/hex_number_sign.lua",
+    double_colon => "./compat/double_colon.lua",
+    unary_minus_chain => "./compat/unary_minus_chain.lua",
+);
@@ -0,0 +1,5 @@
+local a = ...
+local b = a :: number
+local c = (a :: any) :: string
+local d = a:method()
+return b, c, d
@@ -0,0 +1,6 @@
+local a = ...
+local b = 0xE - a
+local c = 0xe + a
+local d = 0x1E - 1
+local e = { 0xAE, -a }
+return b, c, d, e
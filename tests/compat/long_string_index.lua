@@ -0,0 +1,5 @@
+local t = {}
+t[ [[key]] ] = 1
+t[ [==[other]==] ] = t[ [[key]] ]
+local value = { [ [[a]] ] = t[ [=[b]=] ] }
+return t, value
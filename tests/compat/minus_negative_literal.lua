@@ -0,0 +1,6 @@
+local a = ...
+local b = a - -1
+local c = a - -0.5
+local d = a - 0 * -1
+local e = a - -a
+return b, c, d, e
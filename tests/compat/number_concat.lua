@@ -0,0 +1,7 @@
+local a = ...
+local b = 1 .. a
+local c = 0xA .. a
+local d = 1.5 .. a
+local e = 0 * -1 .. a
+local f = 1e5 .. a
+return b, c, d, e, f
@@ -0,0 +1,6 @@
+local a = ...
+local b = - - -a
+local c = -(-a)
+local d = - - 1
+local e = -a ^ - -a
+return b, c, d, e
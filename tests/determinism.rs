@@ -0,0 +1,147 @@
+use darklua_core::{
+    process,
+    rules::{get_all_rule_names, get_default_rules, Rule},
+    Configuration, Options, Resources,
+};
+
+use pretty_assertions::assert_eq;
+
+const RUN_COUNT: usize = 20;
+
+const CORPUS: &[(&str, &str)] = &[
+    ("src/a.lua", include_str!("./fuzzed_test_cases/a.lua")),
+    ("src/b.lua", include_str!("./fuzzed_test_cases/b.lua")),
+    ("src/c.lua", include_str!("./fuzzed_test_cases/c.lua")),
+    (
+        "src/spaces_and_comments.lua",
+        include_str!("./test_cases/spaces_and_comments.lua"),
+    ),
+    (
+        "src/module.lua",
+        r#"
+local Module = {}
+local config = { zeta = 1, alpha = "a", beta = true, [1] = 0, gamma = { x = 1, y = 2 } }
+
+local function log(kind, value)
+    if kind == "a" then
+        print("received the first kind of value", value)
+    elseif kind == "b" then
+        print("received the second kind of value", value)
+    elseif kind == "c" then
+        print("received the third kind of value", value)
+    elseif kind == "d" then
+        print("received the fourth kind of value", value)
+    elseif kind == "e" then
+        print("received the fifth kind of value", value)
+    end
+end
+
+function Module.first(player)
+    local character = player.Character
+    print("setting up the character of", player.Name)
+    character:SetAttribute("Health", 100)
+    character:SetAttribute("Stamina", 100)
+end
+
+function Module.second(target)
+    local model = target.Character
+    print("setting up the character of", target.Name)
+    model:SetAttribute("Health", 100)
+    model:SetAttribute("Stamina", 100)
+end
+
+function Module:run(...)
+    local value, other = nil, nil
+    for i = 1, 10 do
+        value = (value or 0) + i * 2 ^ 3
+    end
+    while value > 0 do
+        value = value - 1
+        if value % 7 == 0 then
+            continue
+        end
+        log("a", value)
+    end
+    return config, value, other, ...
+end
+
+return Module
+"#,
+    ),
+];
+
+/// Creates the rules of the default stack followed by every rule that can be created without
+/// any property.
+fn create_rules() -> Vec<Box<dyn Rule>> {
+    get_default_rules()
+        .into_iter()
+        .chain(
+            get_all_rule_names()
+                .into_iter()
+                .filter_map(|name| json5::from_str::<Box<dyn Rule>>(&format!("'{}'", name)).ok()),
+        )
+        .collect()
+}
+
+fn process_corpus() -> Vec<String> {
+    let resources = Resources::from_memory();
+    for (path, content) in CORPUS {
+        resources.write(path, content).unwrap();
+    }
+
+    let configuration = create_rules()
+        .into_iter()
+        .fold(Configuration::empty(), |configuration, rule| {
+            configuration.with_rule(rule)
+        });
+
+    let errors = match process(
+        &resources,
+        Options::new("src")
+            .with_output("out")
+            .with_configuration(configuration),
+    )
+    .unwrap()
+    .result()
+    {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.iter().map(ToString::to_string).collect(),
+    };
+
+    CORPUS
+        .iter()
+        .map(|(path, _)| {
+            let output_path = path.replacen("src/", "out/", 1);
+            resources
+                .get(&output_path)
+                .unwrap_or_else(|_| format!("<no output for {}>", output_path))
+        })
+        .chain(errors)
+        .collect()
+}
+
+#[test]
+fn rule_serialization_is_deterministic() {
+    let expected: Vec<String> = create_rules()
+        .iter()
+        .map(|rule| json5::to_string(rule).unwrap())
+        .collect();
+
+    for _ in 0..RUN_COUNT {
+        let serialized: Vec<String> = create_rules()
+            .iter()
+            .map(|rule| json5::to_string(rule).unwrap())
+            .collect();
+
+        assert_eq!(serialized, expected);
+    }
+}
+
+#[test]
+fn processing_is_deterministic() {
+    let expected = process_corpus();
+
+    for _ in 1..RUN_COUNT {
+        assert_eq!(process_corpus(), expected);
+    }
+}
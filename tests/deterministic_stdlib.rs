@@ -0,0 +1,153 @@
+// this test file compares the functions of the standard library called by the evaluator with
+// the results of Lua 5.1 (see `scripts/generate-stdlib-reference.lua`)
+
+use darklua_core::{
+    nodes::{Expression, LastStatement},
+    process::{DeterministicFunction, Evaluator, LuaValue, LuaVersion},
+    Parser,
+};
+
+const LUA51_REFERENCE: &str = include_str!("./test_cases/deterministic_stdlib/lua51.txt");
+
+#[derive(Debug)]
+enum Expected {
+    Values(Vec<LuaValue>),
+    Error,
+    NotEvaluated,
+}
+
+struct ReferenceCase {
+    line: usize,
+    expression: String,
+    expected: Expected,
+}
+
+fn parse_return_values(code: &str) -> Vec<Expression> {
+    let block = Parser::default()
+        .parse(&format!("return {}", code))
+        .unwrap_or_else(|err| panic!("unable to parse `{}`: {}", code, err));
+
+    match block.get_last_statement() {
+        Some(LastStatement::Return(statement)) => statement.iter_expressions().cloned().collect(),
+        _ => panic!("expected a return statement for `{}`", code),
+    }
+}
+
+fn read_reference(content: &str) -> Vec<ReferenceCase> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with("--"))
+        .map(|(index, line)| {
+            let (expression, results) = line
+                .split_once("=>")
+                .unwrap_or_else(|| panic!("invalid reference line {}: `{}`", index + 1, line));
+
+            let (expression, not_evaluated) = match expression.strip_prefix('!') {
+                Some(expression) => (expression, true),
+                None => (expression, false),
+            };
+            let results = results.trim();
+
+            let expected = if not_evaluated {
+                Expected::NotEvaluated
+            } else if results == "error" {
+                Expected::Error
+            } else {
+                let evaluator = Evaluator::default();
+                Expected::Values(
+                    parse_return_values(results)
+                        .iter()
+                        .map(|value| evaluator.evaluate(value))
+                        .collect(),
+                )
+            };
+
+            ReferenceCase {
+                line: index + 1,
+                expression: expression.trim().to_owned(),
+                expected,
+            }
+        })
+        .collect()
+}
+
+fn evaluate(expression: &str) -> Option<Vec<LuaValue>> {
+    let evaluator = Evaluator::default()
+        .with_stdlib(LuaVersion::Lua51)
+        .with_deterministic_functions(DeterministicFunction::all().iter().copied());
+
+    match parse_return_values(expression).as_slice() {
+        [Expression::Call(call)] => evaluator.evaluate_deterministic_call(call),
+        _ => panic!("expected a function call: `{}`", expression),
+    }
+}
+
+#[test]
+fn evaluate_lua51_reference() {
+    let mut failures = Vec::new();
+
+    for case in read_reference(LUA51_REFERENCE) {
+        let result = evaluate(&case.expression);
+
+        let matches_reference = match (&case.expected, &result) {
+            (Expected::Values(expected), Some(values)) => expected == values,
+            (Expected::Error | Expected::NotEvaluated, None) => true,
+            _ => false,
+        };
+
+        if !matches_reference {
+            failures.push(format!(
+                "line {}: `{}` evaluated to {:?} (expected {:?})",
+                case.line, case.expression, result, case.expected
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn lua51_reference_covers_every_function() {
+    let cases = read_reference(LUA51_REFERENCE);
+
+    for function in DeterministicFunction::all() {
+        let prefix = format!("{}(", function.name());
+        assert!(
+            cases.iter().any(|case| case.expression.starts_with(&prefix)
+                && matches!(case.expected, Expected::Values(_))),
+            "the reference does not evaluate `{}`",
+            function.name()
+        );
+    }
+}
+
+#[test]
+fn functions_are_not_evaluated_when_not_allowed() {
+    let evaluator = Evaluator::default()
+        .with_stdlib(LuaVersion::Lua51)
+        .with_deterministic_functions([DeterministicFunction::MathFloor]);
+
+    let expressions = parse_return_values("math.floor(2.5), math.ceil(2.5)");
+
+    let results: Vec<_> = expressions
+        .iter()
+        .map(|expression| match expression {
+            Expression::Call(call) => evaluator.evaluate_deterministic_call(call),
+            _ => unreachable!(),
+        })
+        .collect();
+
+    assert_eq!(results, vec![Some(vec![LuaValue::Number(2.0)]), None]);
+}
+
+#[test]
+fn functions_are_not_evaluated_without_stdlib() {
+    let evaluator = Evaluator::default()
+        .with_deterministic_functions(DeterministicFunction::all().iter().copied());
+
+    match parse_return_values("math.floor(2.5)").as_slice() {
+        [Expression::Call(call)] => assert_eq!(evaluator.evaluate_deterministic_call(call), None),
+        _ => unreachable!(),
+    }
+}
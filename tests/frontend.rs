@@ -172,45 +172,396 @@ fn use_default_json5_config_in_place() {
     assert_eq!(resources.get("src/test.lua").unwrap(), "return 'Hello'");
 }
 
-mod errors {
-    use std::path::{Path, PathBuf};
+mod files_list {
+    use super::{
+        memory_resources, process, Options, Resources, ANY_CODE, ANY_CODE_DEFAULT_PROCESS,
+    };
+    use pretty_assertions::assert_eq;
 
-    use darklua_core::{
-        nodes::Block,
-        rules::{
-            Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult,
-            RuleProperties,
-        },
-        Configuration, WorkerTree,
+    #[test]
+    fn process_listed_files_to_output() {
+        let resources = memory_resources!(
+            "src/a.lua" => ANY_CODE,
+            "src/nested/b.lua" => ANY_CODE,
+            "src/c.lua" => ANY_CODE,
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("src").with_output("out").with_files([
+                "src/a.lua",
+                "src/missing.lua",
+                "./src/nested/b.lua",
+                "src/a.lua",
+            ]),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["unable to find `src/missing.lua`"]
+        );
+        assert_eq!(
+            resources.get("out/a.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert_eq!(
+            resources.get("out/nested/b.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert!(!resources.exists("out/c.lua").unwrap());
+    }
+
+    #[test]
+    fn process_listed_files_in_place() {
+        let resources = memory_resources!(
+            "src/a.lua" => ANY_CODE,
+            "src/b.lua" => ANY_CODE,
+        );
+
+        process(&resources, Options::new("src").with_files(["src/a.lua"]))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(
+            resources.get("src/a.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert_eq!(resources.get("src/b.lua").unwrap(), ANY_CODE);
+    }
+
+    #[test]
+    fn listed_file_outside_of_input_errors() {
+        let resources = memory_resources!(
+            "src/a.lua" => ANY_CODE,
+            "lib/b.lua" => ANY_CODE,
+        );
+
+        let error = process(
+            &resources,
+            Options::new("src")
+                .with_output("out")
+                .with_files(["src/a.lua", "lib/b.lua"]),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unable to process `lib/b.lua` because it is not inside the input path `src`"
+        );
+    }
+}
+
+mod input_patterns {
+    use super::{
+        memory_resources, process, Options, Resources, ANY_CODE, ANY_CODE_DEFAULT_PROCESS,
     };
+    use pretty_assertions::assert_eq;
 
-    use super::*;
+    fn resources() -> Resources {
+        memory_resources!(
+            "src/a.lua" => ANY_CODE,
+            "src/a.spec.lua" => ANY_CODE,
+            "src/nested/b.luau" => ANY_CODE,
+            "src/nested/b.spec.luau" => ANY_CODE,
+        )
+    }
 
-    fn assert_errors(snapshot_name: &'static str, resources: &Resources, options: Options) {
-        let errors = process(resources, options)
-            .map_err(|err| vec![err])
-            .and_then(WorkerTree::result)
+    #[test]
+    fn process_matched_files_to_output() {
+        let resources = resources();
+
+        process(&resources, Options::new("src/**/*.lua").with_output("out"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(
+            resources.get("out/a.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert_eq!(
+            resources.get("out/a.spec.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert!(!resources.exists("out/nested/b.luau").unwrap());
+    }
+
+    #[test]
+    fn process_files_of_matched_directories_to_output() {
+        let resources = resources();
+
+        process(&resources, Options::new("src/ne*").with_output("out"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(
+            resources.get("out/nested/b.luau").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert_eq!(
+            resources.get("out/nested/b.spec.luau").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert!(!resources.exists("out/a.lua").unwrap());
+    }
+
+    #[test]
+    fn process_matched_files_in_place_without_excluded_files() {
+        let resources = resources();
+
+        process(
+            &resources,
+            Options::new("src/**/*.{lua,luau}").with_exclude("**/*.spec.{lua,luau}"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(
+            resources.get("src/a.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert_eq!(
+            resources.get("src/nested/b.luau").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert_eq!(resources.get("src/a.spec.lua").unwrap(), ANY_CODE);
+        assert_eq!(resources.get("src/nested/b.spec.luau").unwrap(), ANY_CODE);
+    }
+
+    #[test]
+    fn process_matched_files_without_negated_files() {
+        let resources = resources();
+
+        process(
+            &resources,
+            Options::new("src/**/!(*.spec).lua").with_output("out"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(
+            resources.get("out/a.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert!(!resources.exists("out/a.spec.lua").unwrap());
+        assert!(!resources.exists("out/nested/b.luau").unwrap());
+    }
+
+    #[test]
+    fn process_directory_without_excluded_files() {
+        let resources = resources();
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_output("out")
+                .with_exclude("nested"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert!(resources.exists("out/a.lua").unwrap());
+        assert!(!resources.exists("out/nested/b.luau").unwrap());
+    }
+
+    #[test]
+    fn overlapping_alternatives_process_files_once() {
+        let resources = resources();
+
+        let worker_tree = process(
+            &resources,
+            Options::new("src/{*.lua,a.lua,nested}").with_output("out"),
+        )
+        .unwrap();
+
+        assert_eq!(worker_tree.success_count(), 4);
+    }
+
+    #[test]
+    fn pattern_without_matches_errors() {
+        let resources = resources();
+
+        let error =
+            process(&resources, Options::new("src/**/*.txt").with_output("out")).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "no Lua files match the input pattern `src/**/*.txt`"
+        );
+    }
+
+    #[test]
+    fn invalid_exclude_pattern_errors() {
+        let resources = resources();
+
+        let error = process(
+            &resources,
+            Options::new("src").with_output("out").with_exclude("{a"),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "invalid glob pattern `{a`: failed to parse glob expression"
+        );
+    }
+}
+
+mod formatting_rules {
+    use super::{memory_resources, process, Options, Resources};
+    use pretty_assertions::assert_eq;
+
+    const CONFIG: &str =
+        "{ generator: 'retain_lines', rules: ['rename_variables', 'normalize_semicolons'] }";
+    const UNFORMATTED_CODE: &str = "local value = 1; return value;";
+    const FORMATTED_CODE: &str = "local value = 1 return value";
+
+    #[test]
+    fn format_only_applies_formatting_rules() {
+        let resources = memory_resources!(
+            "src/a.lua" => UNFORMATTED_CODE,
+            ".darklua.json5" => CONFIG,
+        );
+
+        process(&resources, Options::new("src").with_formatting_rules_only())
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(resources.get("src/a.lua").unwrap(), FORMATTED_CODE);
+    }
+
+    #[test]
+    fn check_formatted_file_succeeds() {
+        let resources = memory_resources!(
+            "src/a.lua" => FORMATTED_CODE,
+            ".darklua.json5" => CONFIG,
+        );
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_formatting_rules_only()
+                .with_check(),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(resources.get("src/a.lua").unwrap(), FORMATTED_CODE);
+    }
+
+    #[test]
+    fn check_unformatted_file_errors_without_writing() {
+        let resources = memory_resources!(
+            "src/a.lua" => UNFORMATTED_CODE,
+            ".darklua.json5" => CONFIG,
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("src")
+                .with_formatting_rules_only()
+                .with_check(),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["`src/a.lua` would be modified"]
+        );
+        assert_eq!(resources.get("src/a.lua").unwrap(), UNFORMATTED_CODE);
+    }
+
+    #[test]
+    fn check_without_formatting_rules_only_uses_all_rules() {
+        let resources = memory_resources!(
+            "src/a.lua" => FORMATTED_CODE,
+            ".darklua.json5" => CONFIG,
+        );
+
+        let errors = process(&resources, Options::new("src").with_check())
+            .unwrap()
+            .result()
             .unwrap_err();
 
-        let errors_display = errors
-            .into_iter()
-            .map(|err| format!("- {}", err).replace('\\', "/"))
-            .collect::<Vec<_>>()
-            .join("\n");
-        insta::assert_snapshot!(snapshot_name, errors_display);
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["`src/a.lua` would be modified"]
+        );
     }
+}
+
+mod environment {
+    use super::{memory_resources, process, Options, Resources};
+    use darklua_core::process::EnvironmentTarget;
+    use pretty_assertions::assert_eq;
 
     #[test]
-    fn snapshot_simple_cyclic_work_error() {
+    fn configured_globals_are_shared_by_rules() {
         let resources = memory_resources!(
-            "src/a.lua" => "return 'module a'",
-            "src/b.lua" => "return 'module b'",
+            "src/a.lua" => "local value = type(a) return value, a",
+            ".darklua.json5" => r#"{
+                generator: 'dense',
+                target: 'lua51',
+                globals: [{ name: 'a', type: 'table' }],
+                rules: [{ rule: 'rename_variables', globals: [] }, 'compute_expression'],
+            }"#,
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(
+            resources.get("src/a.lua").unwrap(),
+            "local b='table'return b,a"
         );
+    }
+
+    #[test]
+    fn reserved_identifiers_are_given_to_following_rules_without_adding_globals() {
+        use std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        };
+
+        use darklua_core::Configuration;
+        use darklua_core::{
+            nodes::Block,
+            rules::{
+                Context, RemoveMethodDefinition, Rule, RuleConfiguration, RuleConfigurationError,
+                RuleProcessResult, RuleProperties,
+            },
+        };
 
         #[derive(Debug)]
-        struct CustomRule;
+        struct FindSelf {
+            reserved: Arc<AtomicBool>,
+            global: Arc<AtomicBool>,
+        }
 
-        impl RuleConfiguration for CustomRule {
+        impl FindSelf {
+            fn new() -> Self {
+                Self {
+                    reserved: Arc::new(AtomicBool::new(false)),
+                    global: Arc::new(AtomicBool::new(false)),
+                }
+            }
+        }
+
+        impl RuleConfiguration for FindSelf {
             fn configure(
                 &mut self,
                 _properties: RuleProperties,
@@ -219,7 +570,7 @@ mod errors {
             }
 
             fn get_name(&self) -> &'static str {
-                "custom-rule"
+                "find_self"
             }
 
             fn serialize_to_properties(&self) -> RuleProperties {
@@ -227,50 +578,3523 @@ mod errors {
             }
         }
 
-        impl Rule for CustomRule {
-            fn process(&self, _: &mut Block, _: &Context) -> RuleProcessResult {
+        impl Rule for FindSelf {
+            fn process(&self, _: &mut Block, context: &Context) -> RuleProcessResult {
+                self.reserved.store(
+                    context
+                        .reserved_identifiers()
+                        .iter()
+                        .any(|name| name == "self"),
+                    Ordering::SeqCst,
+                );
+                self.global
+                    .store(context.environment().has_global("self"), Ordering::SeqCst);
                 Ok(())
             }
-
-            fn require_content(&self, _: &Path, _: &Block) -> Vec<PathBuf> {
-                vec!["src/a.lua".into(), "src/b.lua".into()]
-            }
         }
 
-        let rule: Box<dyn Rule> = Box::new(CustomRule);
+        let before = FindSelf::new();
+        let after = FindSelf::new();
+        let (reserved_before, reserved_after) = (before.reserved.clone(), after.reserved.clone());
+        let global_after = after.global.clone();
 
-        assert_errors(
-            "simple_cyclic_work_error",
+        let resources = memory_resources!(
+            "src/a.lua" => "local A = {} function A:get() return self end return A",
+        );
+
+        let rules: Vec<Box<dyn Rule>> = vec![
+            Box::new(before),
+            Box::<RemoveMethodDefinition>::default(),
+            Box::new(after),
+        ];
+
+        process(
             &resources,
-            Options::new("src").with_configuration(Configuration::empty().with_rule(rule)),
+            Options::new("src").with_configuration(
+                rules
+                    .into_iter()
+                    .fold(Configuration::empty(), Configuration::with_rule),
+            ),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert!(!reserved_before.load(Ordering::SeqCst));
+        assert!(reserved_after.load(Ordering::SeqCst));
+        assert!(!global_after.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn configured_target_is_used_by_compute_expression() {
+        let resources = memory_resources!(
+            "src/a.lua" => "return type(math.pi)",
+            ".darklua.json5" => "{ generator: 'dense', target: 'lua51', rules: ['compute_expression'] }",
         );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(resources.get("src/a.lua").unwrap(), "return'number'");
     }
 
     #[test]
-    fn snapshot_missing_configuration_file() {
+    fn without_target_compute_expression_does_not_assume_globals() {
         let resources = memory_resources!(
-            "src/init.lua" => "return ''",
+            "src/a.lua" => "return type(math.pi)",
+            ".darklua.json5" => "{ generator: 'dense', rules: ['compute_expression'] }",
         );
 
-        assert_errors(
-            "missing_configuration_file",
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(resources.get("src/a.lua").unwrap(), "return type(math.pi)");
+    }
+
+    #[test]
+    fn target_option_overrides_configuration() {
+        let resources = memory_resources!(
+            "src/a.lua" => "return type(math.maxinteger)",
+            ".darklua.json5" => "{ generator: 'dense', target: 'lua51', rules: ['compute_expression'] }",
+        );
+
+        process(
             &resources,
-            Options::new("src").with_configuration_at("missing/config.json"),
+            Options::new("src").with_target(EnvironmentTarget::Lua54),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(resources.get("src/a.lua").unwrap(), "return'number'");
+    }
+
+    #[test]
+    fn utf8_identifiers_are_rejected_without_source_target() {
+        let resources = memory_resources!(
+            "src/a.lua" => "local café = 1 return café",
+            ".darklua.json5" => "{ generator: 'dense', rules: [] }",
         );
+
+        assert!(process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .is_err());
     }
 
     #[test]
-    fn snapshot_multiple_configuration_file_found() {
+    fn source_target_allows_utf8_identifiers() {
         let resources = memory_resources!(
-            "src/init.lua" => "return ''",
-            ".darklua.json" => "{ rules: [] }",
-            ".darklua.json5" => "{ rules: [] }",
+            "src/a.lua" => "local café = 1 return café",
+            ".darklua.json5" => "{ generator: 'retain_lines', source_target: 'luau', rules: [] }",
         );
 
-        assert_errors(
-            "multiple_configuration_file_found",
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(
+            resources.get("src/a.lua").unwrap(),
+            "local café = 1 return café"
+        );
+    }
+
+    #[test]
+    fn transliterate_utf8_identifiers_for_lua51() {
+        let resources = memory_resources!(
+            "src/a.lua" => "local café = { größe = 1 } return café.größe",
+            ".darklua.json5" => r#"{
+                generator: 'dense',
+                source_target: 'luau',
+                target: 'lua51',
+                rules: [{ rule: 'transliterate_identifiers', strategy: 'ascii', include_fields: true }],
+                artifacts: { identifiers: '{name}.identifiers.json' },
+            }"#,
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(
+            resources.get("src/a.lua").unwrap(),
+            "local cafe={grosse=1}return cafe.grosse"
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(
+                &resources.get("src/a.identifiers.json").unwrap()
+            )
+            .unwrap(),
+            serde_json::json!({ "café": "cafe", "größe": "grosse" })
+        );
+    }
+
+    #[test]
+    fn function_attributes_are_removed_for_lua51_target() {
+        let resources = memory_resources!(
+            "src/a.lua" => "@native local function a() end @checked function b() end",
+            ".darklua.json5" => "{ generator: 'dense', rules: [] }",
+        );
+
+        process(
             &resources,
-            Options::new("src"),
+            Options::new("src").with_target(EnvironmentTarget::Lua51),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(
+            resources.get("src/a.lua").unwrap(),
+            "local function a()end function b()end"
+        );
+    }
+
+    #[test]
+    fn function_attributes_are_kept_for_luau_target() {
+        let resources = memory_resources!(
+            "src/a.lua" => "@native local function a() end @checked function b() end",
+            ".darklua.json5" => "{ generator: 'dense', target: 'luau', rules: [] }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(
+            resources.get("src/a.lua").unwrap(),
+            "@native local function a()end@checked function b()end"
+        );
+    }
+
+    #[test]
+    fn function_attributes_are_kept_without_target() {
+        let resources = memory_resources!(
+            "src/a.lua" => "@native\nlocal function a() end\n",
+            ".darklua.json5" => "{ generator: 'retain_lines', rules: [] }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(
+            resources.get("src/a.lua").unwrap(),
+            "@native\nlocal function a() end\n"
+        );
+    }
+}
+
+mod data_fast_path {
+    use darklua_core::{
+        rules::{RenameVariables, Rule},
+        Configuration, GeneratorParameters,
+    };
+
+    use super::{memory_resources, process, Options, Resources};
+    use pretty_assertions::assert_eq;
+
+    const DATA_MODULES: &[&str] = &[
+        "return {}",
+        "return { 1, 2, 3 }",
+        "-- comment\nreturn {\n    name = 'darklua', -- name\n    version = \"0.15.0\",\n}\n",
+        "return { ['key'] = true, [1] = false, [\"other key\"] = nil }",
+        "return { a = { b = { -1, 0.5, 0x10 } }, { 'value', [[long string]] } }",
+        "return {\n\tlist = {\n\t\t'a',\n\t\t'b';\n\t},\n};",
+    ];
+
+    fn process_data_modules(configuration: Configuration, options: Options) -> Vec<String> {
+        let resources = Resources::from_memory();
+
+        for (i, content) in DATA_MODULES.iter().enumerate() {
+            resources
+                .write(format!("src/data{}.lua", i), content)
+                .unwrap();
+        }
+
+        process(&resources, options.with_configuration(configuration))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        (0..DATA_MODULES.len())
+            .map(|i| resources.get(format!("src/data{}.lua", i)).unwrap())
+            .collect()
+    }
+
+    fn assert_same_output(generator: GeneratorParameters) {
+        let expected = process_data_modules(
+            Configuration::default().with_generator(generator.clone()),
+            Options::new("src"),
+        );
+
+        let from_configuration = process_data_modules(
+            Configuration::default()
+                .with_generator(generator.clone())
+                .with_data_fast_path(),
+            Options::new("src"),
+        );
+        assert_eq!(expected, from_configuration);
+
+        let from_options = process_data_modules(
+            Configuration::default().with_generator(generator),
+            Options::new("src").with_data_fast_path(),
+        );
+        assert_eq!(expected, from_options);
+    }
+
+    #[test]
+    fn retain_lines_output_is_identical() {
+        assert_same_output(GeneratorParameters::RetainLines);
+    }
+
+    #[test]
+    fn dense_output_is_identical() {
+        assert_same_output(GeneratorParameters::default_dense());
+    }
+
+    #[test]
+    fn readable_output_is_identical() {
+        assert_same_output(GeneratorParameters::default_readable());
+    }
+
+    #[test]
+    fn use_data_fast_path_from_configuration_file() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return { ['key'] = true }",
+            ".darklua.json5" => "{ data_fast_path: true, rules: ['rename_variables', 'convert_index_to_field'] }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(
+            resources.get("src/test.lua").unwrap(),
+            "return { key=true }"
+        );
+    }
+
+    #[test]
+    fn non_data_modules_are_fully_processed() {
+        let resources = memory_resources!(
+            "src/test.lua" => "local value = 1 return { value = value }",
+        );
+
+        process(
+            &resources,
+            Options::new("src").with_configuration(
+                Configuration::empty()
+                    .with_rule(Box::<RenameVariables>::default() as Box<dyn Rule>)
+                    .with_data_fast_path(),
+            ),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(
+            resources.get("src/test.lua").unwrap(),
+            "local a = 1 return { value = a }"
+        );
+    }
+}
+
+mod injections {
+    use super::{memory_resources, process, Options, Resources};
+
+    fn find(code: &str, pattern: &str) -> usize {
+        code.find(pattern)
+            .unwrap_or_else(|| panic!("unable to find `{}` in:\n{}", pattern, code))
+    }
+
+    #[test]
+    fn injected_statements_are_ordered_by_priority() {
+        let resources = memory_resources!(
+            "src/a.luau" => r#"
+local function check(select, value)
+    return assert(value, 'value is missing')
+end
+local function f(string)
+    print(`the first value is {string}`)
+    print(`the second value is {string}`)
+    print(`the third value is {string}`)
+end
+local function g(string)
+    print(`the first value is {string}`)
+    print(`the second value is {string}`)
+    print(`the third value is {string}`)
+end
+return check, f, g
+"#,
+            ".darklua.json5" => "{ generator: 'readable', rules: ['remove_interpolated_string', 'deduplicate_blocks', 'remove_assertions'] }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        let code = resources.get("src/a.luau").unwrap();
+
+        let reserved_global = find(&code, "= select");
+        let string_format = find(&code, "= string.format");
+        let helper_function = find(&code, "function __DARKLUA_BLOCK");
+        let check_function = find(&code, "function check");
+
+        assert!(
+            reserved_global < string_format,
+            "reserved globals must be injected before helpers:\n{}",
+            code
+        );
+        assert!(
+            string_format < helper_function,
+            "helpers must be injected before the functions using them:\n{}",
+            code
+        );
+        assert!(
+            helper_function < check_function,
+            "unexpected code:\n{}",
+            code
+        );
+    }
+}
+
+mod reprocess {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use darklua_core::{
+        nodes::Block,
+        rules::{
+            ComputeExpression, Context, RemoveUnusedIfBranch, Rule, RuleConfiguration,
+            RuleConfigurationError, RuleProcessResult, RuleProperties,
+        },
+        Configuration, GeneratorParameters,
+    };
+
+    use super::{memory_resources, process, Options, Resources};
+    use pretty_assertions::assert_eq;
+
+    const CODE: &str = "return (if true then x else f()) + 1, if false then f() else 2";
+
+    #[derive(Debug)]
+    struct CountingRule {
+        rule: Box<dyn Rule>,
+        name: &'static str,
+        request: Option<&'static str>,
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl CountingRule {
+        fn new(rule: Box<dyn Rule>) -> Self {
+            Self {
+                name: rule.get_name(),
+                rule,
+                request: None,
+                runs: Default::default(),
+            }
+        }
+
+        fn named(name: &'static str) -> Self {
+            Self {
+                name,
+                ..Self::new(Box::<ComputeExpression>::default())
+            }
+        }
+
+        fn with_request(mut self, rule_name: &'static str) -> Self {
+            self.request = Some(rule_name);
+            self
+        }
+
+        fn runs(&self) -> Arc<AtomicUsize> {
+            self.runs.clone()
+        }
+    }
+
+    impl RuleConfiguration for CountingRule {
+        fn configure(&mut self, _: RuleProperties) -> Result<(), RuleConfigurationError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            self.name
+        }
+
+        fn serialize_to_properties(&self) -> RuleProperties {
+            RuleProperties::new()
+        }
+    }
+
+    impl Rule for CountingRule {
+        fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            if let Some(rule_name) = self.request {
+                context.request_reprocess(rule_name);
+            }
+            self.rule.process(block, context)
+        }
+    }
+
+    fn process_code(code: &str, configuration: Configuration) -> String {
+        let resources = memory_resources!(
+            "src/test.lua" => code,
+        );
+
+        process(
+            &resources,
+            Options::new("src").with_configuration(
+                configuration.with_generator(GeneratorParameters::default_dense()),
+            ),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("src/test.lua").unwrap()
+    }
+
+    #[test]
+    fn remove_unused_if_branch_reprocesses_compute_expression() {
+        let compute_expression = CountingRule::new(Box::<ComputeExpression>::default());
+        let compute_expression_runs = compute_expression.runs();
+        let remove_unused_if_branch = CountingRule::new(Box::<RemoveUnusedIfBranch>::default());
+        let remove_unused_if_branch_runs = remove_unused_if_branch.runs();
+
+        let output = process_code(
+            CODE,
+            Configuration::empty()
+                .with_rule(Box::new(compute_expression) as Box<dyn Rule>)
+                .with_rule(Box::new(remove_unused_if_branch) as Box<dyn Rule>),
+        );
+
+        let two_passes = process_code(
+            CODE,
+            Configuration::empty()
+                .with_rule(Box::<ComputeExpression>::default() as Box<dyn Rule>)
+                .with_rule(Box::<RemoveUnusedIfBranch>::default() as Box<dyn Rule>)
+                .with_rule(Box::<ComputeExpression>::default() as Box<dyn Rule>)
+                .with_rule(Box::<RemoveUnusedIfBranch>::default() as Box<dyn Rule>),
+        );
+
+        assert_eq!(output, two_passes);
+        assert_eq!(compute_expression_runs.load(Ordering::SeqCst), 2);
+        assert_eq!(remove_unused_if_branch_runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn ignore_request_for_rule_not_in_configuration() {
+        let output = process_code(
+            CODE,
+            Configuration::empty()
+                .with_rule(Box::<RemoveUnusedIfBranch>::default() as Box<dyn Rule>),
+        );
+
+        assert_eq!(output, "return(x)+1,2");
+    }
+
+    #[test]
+    fn ignore_request_for_rule_running_later() {
+        let first = CountingRule::named("first").with_request("second");
+        let first_runs = first.runs();
+        let second = CountingRule::named("second");
+        let second_runs = second.runs();
+
+        process_code(
+            CODE,
+            Configuration::empty()
+                .with_rule(Box::new(first) as Box<dyn Rule>)
+                .with_rule(Box::new(second) as Box<dyn Rule>),
+        );
+
+        assert_eq!(first_runs.load(Ordering::SeqCst), 1);
+        assert_eq!(second_runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn reprocess_runs_are_limited() {
+        let repeat = CountingRule::named("repeat").with_request("repeat");
+        let repeat_runs = repeat.runs();
+        let trigger = CountingRule::named("trigger").with_request("repeat");
+
+        process_code(
+            CODE,
+            Configuration::empty()
+                .with_rule(Box::new(repeat) as Box<dyn Rule>)
+                .with_rule(Box::new(trigger) as Box<dyn Rule>),
+        );
+
+        assert_eq!(repeat_runs.load(Ordering::SeqCst), 5);
+    }
+}
+
+mod text_processors {
+    use std::borrow::Cow;
+
+    use darklua_core::{
+        rules::{
+            ChecksumTrailer, Context, RuleConfiguration, RuleConfigurationError, RuleProperties,
+            StripLineDirectives, TextPostprocessor, TextPreprocessor,
+        },
+        Configuration, GeneratorParameters,
+    };
+
+    use super::{memory_resources, process, Options, Resources};
+    use pretty_assertions::assert_eq;
+
+    const DIRECTIVES_FIXTURE: &str = r#"local a = 1
+#if DEBUG
+print('debug', a)
+#endif
+local s = [[
+#if DEBUG
+]]
+local t = [==[
+]]
+#endif
+]==]
+--[[
+#define COMMENT
+]]
+return a, s, t
+"#;
+
+    const DIRECTIVES_FIXTURE_OUTPUT: &str = r#"local a = 1
+print('debug', a)
+local s = [[
+#if DEBUG
+]]
+local t = [==[
+]]
+#endif
+]==]
+--[[
+#define COMMENT
+]]
+return a, s, t
+"#;
+
+    #[test]
+    fn strip_line_directives_from_configuration_file() {
+        let resources = memory_resources!(
+            "src/test.lua" => DIRECTIVES_FIXTURE,
+            ".darklua.json5" => "{ rules: [], preprocessors: ['strip_line_directives'] }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(
+            resources.get("src/test.lua").unwrap(),
+            DIRECTIVES_FIXTURE_OUTPUT
+        );
+    }
+
+    #[test]
+    fn strip_line_directives_from_options() {
+        let resources = memory_resources!(
+            "src/test.lua" => DIRECTIVES_FIXTURE,
+        );
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_configuration(Configuration::empty())
+                .with_preprocessor(
+                    Box::<StripLineDirectives>::default() as Box<dyn TextPreprocessor>
+                ),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(
+            resources.get("src/test.lua").unwrap(),
+            DIRECTIVES_FIXTURE_OUTPUT
+        );
+    }
+
+    #[test]
+    fn parse_error_points_at_original_line_after_stripped_region() {
+        let resources = memory_resources!(
+            "src/test.lua" => "local a = 1\n#if DEBUG\n#define X\nprint(a)\n#endif\nlocal = 2\n",
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("src").with_configuration(Configuration::empty().with_preprocessor(
+                Box::<StripLineDirectives>::default() as Box<dyn TextPreprocessor>,
+            )),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        let message = errors[0].to_string();
+        assert!(
+            message.contains("starting from line 6, character 7"),
+            "unexpected error message: {}",
+            message
+        );
+    }
+
+    #[test]
+    fn parse_error_without_stripped_lines_is_unchanged() {
+        let resources = memory_resources!(
+            "src/test.lua" => "local a = 1\nlocal = 2\n",
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("src").with_configuration(Configuration::empty().with_preprocessor(
+                Box::<StripLineDirectives>::default() as Box<dyn TextPreprocessor>,
+            )),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        let message = errors[0].to_string();
+        assert!(
+            message.contains("starting from line 2, character 7"),
+            "unexpected error message: {}",
+            message
+        );
+    }
+
+    #[test]
+    fn append_checksum_trailer() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return 1",
+            ".darklua.json5" => "{ rules: [], generator: 'dense', postprocessors: ['checksum_trailer'] }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        let output = resources.get("src/test.lua").unwrap();
+        assert!(
+            output.starts_with("return 1\n-- checksum: xxh3="),
+            "unexpected output: {}",
+            output
+        );
+        assert!(
+            output.ends_with(" length=8\n"),
+            "unexpected output: {}",
+            output
+        );
+    }
+
+    #[derive(Debug)]
+    struct FailingPreprocessor;
+
+    impl RuleConfiguration for FailingPreprocessor {
+        fn configure(&mut self, _: RuleProperties) -> Result<(), RuleConfigurationError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "failing_preprocessor"
+        }
+
+        fn serialize_to_properties(&self) -> RuleProperties {
+            RuleProperties::new()
+        }
+    }
+
+    impl TextPreprocessor for FailingPreprocessor {
+        fn preprocess<'src>(
+            &self,
+            _: &'src str,
+            _: &Context,
+        ) -> Result<darklua_core::rules::PreprocessedText<'src>, String> {
+            Err("oops".to_owned())
+        }
+    }
+
+    #[derive(Debug)]
+    struct UppercasePostprocessor;
+
+    impl RuleConfiguration for UppercasePostprocessor {
+        fn configure(&mut self, _: RuleProperties) -> Result<(), RuleConfigurationError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "uppercase_postprocessor"
+        }
+
+        fn serialize_to_properties(&self) -> RuleProperties {
+            RuleProperties::new()
+        }
+    }
+
+    impl TextPostprocessor for UppercasePostprocessor {
+        fn postprocess<'code>(
+            &self,
+            code: &'code str,
+            _: &Context,
+        ) -> Result<Cow<'code, str>, String> {
+            Ok(code.to_uppercase().into())
+        }
+    }
+
+    #[test]
+    fn preprocessor_error() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return 1",
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("src")
+                .with_configuration(Configuration::empty())
+                .with_preprocessor(Box::new(FailingPreprocessor) as Box<dyn TextPreprocessor>),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.to_string().replace('\\', "/"))
+                .collect::<Vec<_>>(),
+            vec!["error processing `src/test.lua` (failing_preprocessor): oops"]
+        );
+    }
+
+    #[test]
+    fn postprocessors_run_in_order() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return 'a'",
+        );
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_configuration(
+                    Configuration::empty()
+                        .with_generator(GeneratorParameters::default_dense())
+                        .with_postprocessor(
+                            Box::new(UppercasePostprocessor) as Box<dyn TextPostprocessor>
+                        ),
+                )
+                .with_postprocessor(
+                    Box::<ChecksumTrailer>::default() as Box<dyn TextPostprocessor>
+                ),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let output = resources.get("src/test.lua").unwrap();
+        assert!(
+            output.starts_with("RETURN'A'\n-- checksum: xxh3="),
+            "unexpected output: {}",
+            output
+        );
+    }
+}
+
+mod generated_names {
+    use super::{memory_resources, process, Options, Resources};
+    use pretty_assertions::assert_eq;
+
+    const HELPERS_CODE: &str = r#"local math, string, tostring = {}, {}, nil
+local n = ...
+for i = 1, n do
+    print(i)
+end
+for _, value in ipairs(n) do
+    if value then
+        continue
+    end
+    print(value)
+end
+return n // 2, `value: {n}`
+"#;
+
+    const HELPERS_CONFIG: &str = "{ rules: ['remove_continue', 'remove_interpolated_string', \
+        'remove_floor_division', 'convert_numeric_for_to_while'] }";
+
+    fn process_helpers(options: Options) -> String {
+        let resources = memory_resources!(
+            "src/a.lua" => HELPERS_CODE,
+            ".darklua.json5" => HELPERS_CONFIG,
+        );
+
+        process(&resources, options).unwrap().result().unwrap();
+
+        resources.get("src/a.lua").unwrap()
+    }
+
+    fn assert_helpers_use_prefix(code: &str, prefix: &str) {
+        for name in [
+            // loops are numbered in order, so the loop with `continue` is the second one
+            "CONTINUE_2",
+            "STR_FMT",
+            "TO_STR",
+            "MATH_FLOOR",
+            "FOR_INDEX",
+            "FOR_LIMIT",
+        ] {
+            let helper = format!("{}{}", prefix, name);
+            assert!(
+                code.contains(&helper),
+                "expected `{}` in generated code:\n{}",
+                helper,
+                code
+            );
+        }
+        assert!(
+            prefix == "__DARKLUA_" || !code.contains("__DARKLUA_"),
+            "unexpected default prefix:\n{}",
+            code
+        );
+    }
+
+    fn find_generated_name(code: &str, prefix: &str) -> String {
+        let start = code.find(prefix).expect("generated name should be found");
+        code[start..]
+            .chars()
+            .take_while(|character| character.is_ascii_alphanumeric() || *character == '_')
+            .collect()
+    }
+
+    #[test]
+    fn default_prefix() {
+        let code = process_helpers(Options::new("src"));
+
+        assert_helpers_use_prefix(&code, "__DARKLUA_");
+    }
+
+    #[test]
+    fn prefix_from_options() {
+        let code = process_helpers(Options::new("src").with_generated_name_prefix("__first_"));
+
+        assert_helpers_use_prefix(&code, "__first_");
+    }
+
+    #[test]
+    fn other_prefix_from_options() {
+        let code = process_helpers(Options::new("src").with_generated_name_prefix("_dk2_"));
+
+        assert_helpers_use_prefix(&code, "_dk2_");
+    }
+
+    #[test]
+    fn prefix_from_configuration() {
+        let resources = memory_resources!(
+            "src/a.lua" => "local math = {} return ... // 2",
+            ".darklua.json5" => "{ generated_name_prefix: '__dk_', rules: ['remove_floor_division'] }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        let code = resources.get("src/a.lua").unwrap();
+
+        assert!(code.contains("local __dk_MATH_FLOOR"), "{}", code);
+        assert!(code.contains("__dk_MATH_FLOOR(... / 2)"), "{}", code);
+    }
+
+    #[test]
+    fn hash_placeholder_is_distinct_per_file() {
+        let resources = memory_resources!(
+            "src/a.lua" => "local math = {} return ... // 2",
+            "src/b.lua" => "local math = {} return ... // 3",
+            ".darklua.json5" => "{ generated_name_prefix: '__dk_{hash}_', rules: ['remove_floor_division'] }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        let first = find_generated_name(&resources.get("src/a.lua").unwrap(), "__dk_");
+        let second = find_generated_name(&resources.get("src/b.lua").unwrap(), "__dk_");
+
+        assert_eq!(first.len(), "__dk__MATH_FLOOR".len() + 8);
+        assert!(first.ends_with("_MATH_FLOOR"));
+        assert!(second.ends_with("_MATH_FLOOR"));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn invalid_prefix_in_configuration_errors() {
+        let resources = memory_resources!(
+            "src/a.lua" => "return ... // 2",
+            ".darklua.json5" => "{ generated_name_prefix: '{hash}', rules: [] }",
+        );
+
+        let errors = process(&resources, Options::new("src"))
+            .map_err(|err| vec![err])
+            .and_then(|tree| tree.result())
+            .expect_err("processing should fail");
+
+        assert!(errors[0]
+            .to_string()
+            .contains("invalid generated name prefix `{hash}`"));
+    }
+
+    #[test]
+    fn invalid_prefix_in_options_errors() {
+        let resources = memory_resources!(
+            "src/a.lua" => "return ... // 2",
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("src").with_generated_name_prefix("my-prefix"),
+        )
+        .map_err(|err| vec![err])
+        .and_then(|tree| tree.result())
+        .expect_err("processing should fail");
+
+        assert!(errors[0]
+            .to_string()
+            .contains("invalid generated name prefix `my-prefix`"));
+    }
+}
+
+mod roblox_paths {
+    use super::{memory_resources, process, Options, Resources};
+
+    const WRAP_CONFIG: &str =
+        "{ rules: [{ rule: 'wrap_module_return', wrapper: 'wrap', key_source: 'rojo' }] }";
+
+    #[test]
+    fn mapped_file_uses_roblox_path() {
+        let resources = memory_resources!(
+            "src/shared/Util.lua" => "return {}",
+            "src/other.lua" => "return {}",
+            ".darklua.json5" => WRAP_CONFIG,
+        );
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_roblox_paths([("./src/shared/Util.lua", "ReplicatedStorage.Shared.Util")]),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let util = resources.get("src/shared/Util.lua").unwrap();
+        assert!(
+            util.contains("ReplicatedStorage.Shared.Util"),
+            "unexpected code: {}",
+            util
+        );
+
+        let other = resources.get("src/other.lua").unwrap();
+        assert!(other.contains("src/other"), "unexpected code: {}", other);
+    }
+}
+
+mod errors {
+    use std::path::{Path, PathBuf};
+
+    use darklua_core::{
+        nodes::{Block, FunctionCall, Prefix},
+        process::{DefaultVisitor, NodeProcessor, NodeVisitor},
+        rules::{
+            Context, Rule, RuleConfiguration, RuleConfigurationError, RuleProcessResult,
+            RuleProperties,
+        },
+        Configuration, GeneratorParameters, WorkerTree,
+    };
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn assert_errors(snapshot_name: &'static str, resources: &Resources, options: Options) {
+        let errors = process(resources, options)
+            .map_err(|err| vec![err])
+            .and_then(WorkerTree::result)
+            .unwrap_err();
+
+        let errors_display = errors
+            .into_iter()
+            .map(|err| format!("- {}", err).replace('\\', "/"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        insta::assert_snapshot!(snapshot_name, errors_display);
+    }
+
+    const FAILING_CODE: &str = r#"
+local value = 1
+if value > 0 then
+    print("positive")
+    explode(value, "the statement that fails")
+end
+return value
+"#;
+
+    fn is_call_to(call: &FunctionCall, name: &str) -> bool {
+        matches!(call.get_prefix(), Prefix::Identifier(identifier) if identifier.get_name() == name)
+    }
+
+    struct ExplodeProcessor<'a, 'b, 'c, 'd> {
+        context: &'a Context<'b, 'c, 'd>,
+        panic: bool,
+        error: Option<String>,
+    }
+
+    impl NodeProcessor for ExplodeProcessor<'_, '_, '_, '_> {
+        fn process_function_call(&mut self, call: &mut FunctionCall) {
+            if is_call_to(call, "explode") {
+                if self.panic {
+                    panic!("unexpected call to explode");
+                }
+                self.error = Some(self.context.error_at_current_node("found call to explode"));
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct ExplodeRule {
+        panic: bool,
+    }
+
+    impl RuleConfiguration for ExplodeRule {
+        fn configure(&mut self, _properties: RuleProperties) -> Result<(), RuleConfigurationError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            if self.panic {
+                "panicking-rule"
+            } else {
+                "failing-rule"
+            }
+        }
+
+        fn serialize_to_properties(&self) -> RuleProperties {
+            Default::default()
+        }
+    }
+
+    impl Rule for ExplodeRule {
+        fn process(&self, block: &mut Block, context: &Context) -> RuleProcessResult {
+            let mut processor = ExplodeProcessor {
+                context,
+                panic: self.panic,
+                error: None,
+            };
+            DefaultVisitor::visit_block(block, &mut processor);
+            match processor.error {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        }
+    }
+
+    fn process_with_explode_rule(panic: bool) -> (Resources, String) {
+        let resources = memory_resources!(
+            "src/a.lua" => "return 'module a'",
+            "src/fail.lua" => FAILING_CODE,
+            "src/z.lua" => "return 'module z'",
+        );
+        let rule: Box<dyn Rule> = Box::new(ExplodeRule { panic });
+
+        let errors = process(
+            &resources,
+            Options::new("src").with_configuration(
+                Configuration::empty()
+                    .with_rule(rule)
+                    .with_generator(GeneratorParameters::default_dense()),
+            ),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        let error = errors[0].to_string().replace('\\', "/");
+
+        (resources, error)
+    }
+
+    fn assert_other_files_completed(resources: &Resources) {
+        assert_eq!(resources.get("src/a.lua").unwrap(), "return'module a'");
+        assert_eq!(resources.get("src/z.lua").unwrap(), "return'module z'");
+    }
+
+    #[test]
+    fn failing_rule_error_shows_statement_location() {
+        let (resources, error) = process_with_explode_rule(false);
+
+        assert_eq!(
+            error,
+            "error processing `src/fail.lua` (failing-rule [#0]): found call to explode\n  \
+            while processing statement at block.statements[1].if.branches[0].statements[1]\n  \
+            --> explode(value,'the statement that fails')"
+        );
+        assert_other_files_completed(&resources);
+    }
+
+    #[test]
+    fn panicking_rule_error_shows_statement_location() {
+        let (resources, error) = process_with_explode_rule(true);
+
+        assert_eq!(
+            error,
+            "error processing `src/fail.lua` (panicking-rule [#0]): rule panicked: unexpected call to explode\n  \
+            while processing statement at block.statements[1].if.branches[0].statements[1]\n  \
+            --> explode(value,'the statement that fails')"
+        );
+        assert_other_files_completed(&resources);
+    }
+
+    #[test]
+    fn snapshot_simple_cyclic_work_error() {
+        let resources = memory_resources!(
+            "src/a.lua" => "return 'module a'",
+            "src/b.lua" => "return 'module b'",
+        );
+
+        #[derive(Debug)]
+        struct CustomRule;
+
+        impl RuleConfiguration for CustomRule {
+            fn configure(
+                &mut self,
+                _properties: RuleProperties,
+            ) -> Result<(), RuleConfigurationError> {
+                Ok(())
+            }
+
+            fn get_name(&self) -> &'static str {
+                "custom-rule"
+            }
+
+            fn serialize_to_properties(&self) -> RuleProperties {
+                Default::default()
+            }
+        }
+
+        impl Rule for CustomRule {
+            fn process(&self, _: &mut Block, _: &Context) -> RuleProcessResult {
+                Ok(())
+            }
+
+            fn require_content(&self, _: &Path, _: &Block) -> Vec<PathBuf> {
+                vec!["src/a.lua".into(), "src/b.lua".into()]
+            }
+        }
+
+        let rule: Box<dyn Rule> = Box::new(CustomRule);
+
+        assert_errors(
+            "simple_cyclic_work_error",
+            &resources,
+            Options::new("src").with_configuration(Configuration::empty().with_rule(rule)),
+        );
+    }
+
+    #[test]
+    fn snapshot_missing_configuration_file() {
+        let resources = memory_resources!(
+            "src/init.lua" => "return ''",
+        );
+
+        assert_errors(
+            "missing_configuration_file",
+            &resources,
+            Options::new("src").with_configuration_at("missing/config.json"),
+        );
+    }
+
+    #[test]
+    fn snapshot_multiple_configuration_file_found() {
+        let resources = memory_resources!(
+            "src/init.lua" => "return ''",
+            ".darklua.json" => "{ rules: [] }",
+            ".darklua.json5" => "{ rules: [] }",
+        );
+
+        assert_errors(
+            "multiple_configuration_file_found",
+            &resources,
+            Options::new("src"),
+        );
+    }
+}
+
+mod incremental {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use darklua_core::{
+        nodes::Block,
+        rules::{
+            Context, RemoveComments, RemoveSpaces, Rule, RuleConfiguration, RuleConfigurationError,
+            RuleProcessResult, RuleProperties,
+        },
+        Configuration, GeneratorParameters, WorkerTree,
+    };
+
+    use super::{memory_resources, process, Options, Resources};
+    use pretty_assertions::assert_eq;
+
+    const FUNCTION_COUNT: usize = 300;
+
+    /// Counts the top-level statements of the blocks it processes.
+    #[derive(Debug, Default)]
+    struct StatementCounter {
+        statements: Arc<AtomicUsize>,
+    }
+
+    impl RuleConfiguration for StatementCounter {
+        fn configure(&mut self, _: RuleProperties) -> Result<(), RuleConfigurationError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "statement_counter"
+        }
+
+        fn serialize_to_properties(&self) -> RuleProperties {
+            RuleProperties::new()
+        }
+
+        fn is_statement_local(&self) -> bool {
+            true
+        }
+    }
+
+    impl Rule for StatementCounter {
+        fn process(&self, block: &mut Block, _: &Context) -> RuleProcessResult {
+            self.statements
+                .fetch_add(block.statements_len(), Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn module(edited_function: Option<usize>) -> String {
+        let mut code = String::new();
+        for i in 0..FUNCTION_COUNT {
+            let operator = if edited_function == Some(i) { "*" } else { "+" };
+            code.push_str(&format!(
+                "-- function {}\nlocal function f{}(a)\n    return a {} {}\nend\n",
+                i, i, operator, i
+            ));
+        }
+        code.push_str("return f0\n");
+        code
+    }
+
+    fn configuration(generator: GeneratorParameters, counter: StatementCounter) -> Configuration {
+        Configuration::empty()
+            .with_rule(Box::<RemoveComments>::default() as Box<dyn Rule>)
+            .with_rule(Box::<RemoveSpaces>::default() as Box<dyn Rule>)
+            .with_rule(Box::new(counter) as Box<dyn Rule>)
+            .with_generator(generator)
+    }
+
+    fn options(generator: &GeneratorParameters, counter: StatementCounter) -> Options {
+        Options::new("src/test.lua")
+            .with_output("out/test.lua")
+            .with_configuration(configuration(generator.clone(), counter))
+            .with_incremental_verification()
+    }
+
+    fn process_edited_function(generator: GeneratorParameters, previous: &str, edited: &str) {
+        let resources = memory_resources!(
+            "src/test.lua" => module(None),
+        );
+        let counter = StatementCounter::default();
+        let statements = counter.statements.clone();
+
+        let mut worker_tree = process(&resources, options(&generator, counter)).unwrap();
+        assert!(worker_tree.collect_errors().is_empty());
+        assert_eq!(statements.load(Ordering::SeqCst), FUNCTION_COUNT);
+
+        let previous_output = resources.get("out/test.lua").unwrap();
+
+        resources.write("src/test.lua", &module(Some(150))).unwrap();
+        worker_tree.source_changed("src/test.lua");
+
+        let counter = StatementCounter::default();
+        let statements = counter.statements.clone();
+        worker_tree
+            .process(&resources, options(&generator, counter))
+            .unwrap();
+        assert!(worker_tree.collect_errors().is_empty());
+
+        // only the edited function is processed again (the verification processes the whole
+        // file once more)
+        assert_eq!(statements.load(Ordering::SeqCst), 1 + FUNCTION_COUNT);
+
+        let output = resources.get("out/test.lua").unwrap();
+        assert_eq!(output, previous_output.replace(previous, edited));
+
+        let full_resources = memory_resources!(
+            "src/test.lua" => module(Some(150)),
+        );
+        process(
+            &full_resources,
+            Options::new("src/test.lua")
+                .with_output("out/test.lua")
+                .with_configuration(configuration(generator, StatementCounter::default())),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(output, full_resources.get("out/test.lua").unwrap());
+    }
+
+    #[test]
+    fn dense_generator_processes_edited_function_only() {
+        process_edited_function(GeneratorParameters::default_dense(), "a+150", "a*150");
+    }
+
+    #[test]
+    fn readable_generator_processes_edited_function_only() {
+        process_edited_function(
+            GeneratorParameters::default_readable(),
+            "a + 150",
+            "a * 150",
+        );
+    }
+
+    #[test]
+    fn retain_lines_generator_processes_whole_file() {
+        let resources = memory_resources!(
+            "src/test.lua" => module(None),
+        );
+        let generator = GeneratorParameters::RetainLines;
+
+        let mut worker_tree =
+            process(&resources, options(&generator, StatementCounter::default())).unwrap();
+
+        resources.write("src/test.lua", &module(Some(150))).unwrap();
+        worker_tree.source_changed("src/test.lua");
+
+        let counter = StatementCounter::default();
+        let statements = counter.statements.clone();
+        worker_tree
+            .process(&resources, options(&generator, counter))
+            .unwrap();
+        assert!(worker_tree.collect_errors().is_empty());
+
+        assert_eq!(statements.load(Ordering::SeqCst), FUNCTION_COUNT);
+        assert!(resources.get("out/test.lua").unwrap().contains('*'));
+    }
+
+    #[test]
+    fn worker_tree_without_previous_state_processes_whole_file() {
+        let resources = memory_resources!(
+            "src/test.lua" => module(Some(3)),
+        );
+        let counter = StatementCounter::default();
+        let statements = counter.statements.clone();
+
+        let mut worker_tree = WorkerTree::default();
+        let options = options(&GeneratorParameters::default_dense(), counter);
+        worker_tree.collect_work(&resources, &options).unwrap();
+        worker_tree.process(&resources, options).unwrap();
+
+        assert_eq!(statements.load(Ordering::SeqCst), FUNCTION_COUNT);
+        assert!(resources.get("out/test.lua").unwrap().contains("a*3"));
+    }
+}
+
+mod atomic {
+    use darklua_core::AtomicMode;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const FAILING_CODE: &str = "local = 1";
+    const FILES: [&str; 5] = ["a.lua", "b.lua", "c.lua", "d.lua", "e.lua"];
+
+    fn resources_with_failing_file() -> Resources {
+        memory_resources!(
+            "src/a.lua" => ANY_CODE,
+            "src/b.lua" => ANY_CODE,
+            "src/c.lua" => FAILING_CODE,
+            "src/d.lua" => ANY_CODE,
+            "src/e.lua" => ANY_CODE,
+            "out/a.lua" => "return 'previous'",
+        )
+    }
+
+    fn atomic_options(mode: AtomicMode) -> Options {
+        Options::new("src")
+            .with_output("out")
+            .with_atomic_mode(mode)
+            .with_staging_directory("staging")
+    }
+
+    fn list_files(resources: &Resources, directory: &str) -> Vec<String> {
+        let mut files: Vec<_> = resources
+            .walk(directory)
+            .map(|path| path.display().to_string().replace('\\', "/"))
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn failure_does_not_modify_output() {
+        let resources = resources_with_failing_file();
+
+        let errors = process(&resources, atomic_options(AtomicMode::All))
+            .unwrap()
+            .result()
+            .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(list_files(&resources, "out"), vec!["out/a.lua"]);
+        assert_eq!(resources.get("out/a.lua").unwrap(), "return 'previous'");
+        assert!(list_files(&resources, "staging").is_empty());
+    }
+
+    #[test]
+    fn failure_does_not_modify_sources_in_place() {
+        let resources = resources_with_failing_file();
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_atomic_mode(AtomicMode::All)
+                .with_staging_directory("staging"),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        for file in FILES {
+            let expected = if file == "c.lua" {
+                FAILING_CODE
+            } else {
+                ANY_CODE
+            };
+            assert_eq!(resources.get(format!("src/{}", file)).unwrap(), expected);
+        }
+        assert!(list_files(&resources, "staging").is_empty());
+    }
+
+    #[test]
+    fn failure_with_fail_fast_does_not_modify_output() {
+        let resources = resources_with_failing_file();
+
+        process(&resources, atomic_options(AtomicMode::All).fail_fast())
+            .unwrap()
+            .result()
+            .unwrap_err();
+
+        assert_eq!(list_files(&resources, "out"), vec!["out/a.lua"]);
+        assert!(list_files(&resources, "staging").is_empty());
+    }
+
+    #[test]
+    fn successful_process_moves_every_file() {
+        let resources = resources_with_failing_file();
+        resources.write("src/c.lua", ANY_CODE).unwrap();
+
+        process(&resources, atomic_options(AtomicMode::All))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        for file in FILES {
+            assert_eq!(
+                resources.get(format!("out/{}", file)).unwrap(),
+                ANY_CODE_DEFAULT_PROCESS
+            );
+        }
+        assert_eq!(list_files(&resources, "out").len(), FILES.len());
+        assert!(list_files(&resources, "staging").is_empty());
+    }
+
+    #[test]
+    fn best_effort_moves_successful_files() {
+        let resources = resources_with_failing_file();
+
+        process(&resources, atomic_options(AtomicMode::BestEffort))
+            .unwrap()
+            .result()
+            .unwrap_err();
+
+        assert_eq!(
+            list_files(&resources, "out"),
+            vec!["out/a.lua", "out/b.lua", "out/d.lua", "out/e.lua"]
+        );
+        assert_eq!(
+            resources.get("out/a.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert!(list_files(&resources, "staging").is_empty());
+    }
+
+    #[test]
+    fn keep_staging_after_failure() {
+        let resources = resources_with_failing_file();
+
+        process(&resources, atomic_options(AtomicMode::All).keep_staging())
+            .unwrap()
+            .result()
+            .unwrap_err();
+
+        assert_eq!(list_files(&resources, "out"), vec!["out/a.lua"]);
+        assert_eq!(
+            list_files(&resources, "staging"),
+            vec![
+                "staging/out/a.lua",
+                "staging/out/b.lua",
+                "staging/out/d.lua",
+                "staging/out/e.lua"
+            ]
+        );
+    }
+
+    #[test]
+    fn existing_staging_directory_is_an_error() {
+        let resources = memory_resources!(
+            "src/a.lua" => ANY_CODE,
+            "staging/file.txt" => "",
+        );
+
+        process(&resources, atomic_options(AtomicMode::All)).unwrap_err();
+
+        assert!(list_files(&resources, "out").is_empty());
+        assert_eq!(resources.get("staging/file.txt").unwrap(), "");
+    }
+}
+
+mod raw_regions {
+    use darklua_core::RawMarkers;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const FIRST_REGION: &str =
+        "--[[@raw]]\n#if VENDOR\n  ::weird:: <<code>>\n#endif\n--[[@endraw]]";
+    const SECOND_REGION: &str = "--[[@raw]] @@inline raw@@ --[[@endraw]]";
+
+    #[test]
+    fn regions_are_copied_with_default_rules() {
+        let code = format!(
+            "local value = 1\n{}\nlocal function get()\n    -- comment\n    return value\nend\n{}\nreturn get()\n",
+            FIRST_REGION, SECOND_REGION
+        );
+        let resources = memory_resources!(
+            "src/a.lua" => &code,
+            ".darklua.json5" => "{ raw_markers: {} }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        let output = resources.get("src/a.lua").unwrap();
+
+        assert!(
+            output.contains(FIRST_REGION),
+            "unexpected output: {}",
+            output
+        );
+        assert!(
+            output.contains(SECOND_REGION),
+            "unexpected output: {}",
+            output
+        );
+        assert!(
+            !output.contains("-- comment"),
+            "unexpected output: {}",
+            output
+        );
+    }
+
+    #[test]
+    fn regions_are_written_on_their_own_lines_with_dense_generator() {
+        let resources = memory_resources!(
+            "src/a.lua" => "local a = 1 --[[@raw]]$$--[[@endraw]] return a",
+            ".darklua.json5" => "{ generator: 'dense', rules: [] }",
+        );
+
+        process(
+            &resources,
+            Options::new("src").with_raw_markers(RawMarkers::default()),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(
+            resources.get("src/a.lua").unwrap(),
+            "local a=1\n--[[@raw]]$$--[[@endraw]]\nreturn a"
+        );
+    }
+
+    #[test]
+    fn markers_are_removed_as_comments_without_raw_markers() {
+        let resources = memory_resources!(
+            "src/a.lua" => "--[[@raw]]--[[@endraw]]\nreturn 1",
+            ".darklua.json5" => "{ generator: 'dense' }",
+        );
+
+        process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(resources.get("src/a.lua").unwrap(), "return 1");
+    }
+
+    #[test]
+    fn unterminated_region_errors() {
+        let resources = memory_resources!(
+            "src/a.lua" => "local a = 1\n--[[@raw]]\nprint(a)\n",
+            ".darklua.json5" => "{ raw_markers: {} }",
+        );
+
+        let errors = process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "unable to parse `src/a.lua`: raw region is never closed (line 2, character 1)"
+        );
+    }
+
+    #[test]
+    fn region_in_expression_errors() {
+        let resources = memory_resources!(
+            "src/a.lua" => "local a = 1\nlocal b = a + --[[@raw]] 2 --[[@endraw]]\nreturn b\n",
+            ".darklua.json5" => "{ raw_markers: {} }",
+        );
+
+        let errors = process(&resources, Options::new("src"))
+            .unwrap()
+            .result()
+            .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "unable to parse `src/a.lua`: raw region must start at a statement boundary (line 2, character 15)"
+        );
+    }
+}
+
+mod configuration_validation {
+    use super::{memory_resources, process, Options, Resources};
+
+    #[test]
+    fn reports_every_problem_of_the_configuration() {
+        let resources = memory_resources!(
+            "src/a.lua" => "return 1",
+            ".darklua.json5" => r#"{
+                generator: { name: 'dense', column_span: 'wide' },
+                bundel: {},
+                rules: [
+                    'remove_comments',
+                    'remove_spaces',
+                    'compute_expression',
+                    { rule: 'inject_global_value', identifier: 1 },
+                ],
+            }"#,
+        );
+
+        let errors = process(&resources, Options::new("src"))
+            .map_err(|err| vec![err])
+            .and_then(|tree| tree.result())
+            .expect_err("processing should fail");
+        let error = errors[0].to_string();
+
+        for expected in [
+            "/generator/column_span: expected unsigned integer, found string",
+            "/bundel: unknown field `bundel` (did you mean `bundle`?)",
+            "/rules/3/identifier: expected string, found number",
+        ] {
+            assert!(
+                error.contains(expected),
+                "expected `{}` in error: {}",
+                expected,
+                error
+            );
+        }
+        assert!(error.contains("3 problems found"), "{}", error);
+    }
+}
+
+mod processed_marker {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use darklua_core::{
+        nodes::Block,
+        rules::{
+            Context, ObfuscateIdentifiers, RemoveComments, Rule, RuleConfiguration,
+            RuleConfigurationError, RuleProcessResult, RuleProperties,
+        },
+        Configuration, GeneratorParameters, ReprocessPolicy,
+    };
+
+    use super::{memory_resources, process, Options, Resources};
+    use pretty_assertions::assert_eq;
+
+    const MARKER_PREFIX: &str = "-- darklua-processed:";
+
+    #[derive(Debug)]
+    struct CountingRule {
+        name: &'static str,
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl CountingRule {
+        fn named(name: &'static str) -> Self {
+            Self {
+                name,
+                runs: Default::default(),
+            }
+        }
+
+        fn runs(&self) -> Arc<AtomicUsize> {
+            self.runs.clone()
+        }
+    }
+
+    impl RuleConfiguration for CountingRule {
+        fn configure(&mut self, _: RuleProperties) -> Result<(), RuleConfigurationError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            self.name
+        }
+
+        fn serialize_to_properties(&self) -> RuleProperties {
+            RuleProperties::new()
+        }
+    }
+
+    impl Rule for CountingRule {
+        fn process(&self, _: &mut Block, _: &Context) -> RuleProcessResult {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn options(configuration: Configuration, policy: ReprocessPolicy) -> Options {
+        Options::new("src")
+            .with_configuration(configuration.with_generator(GeneratorParameters::default_dense()))
+            .with_processed_marker()
+            .with_reprocess_policy(policy)
+    }
+
+    fn process_in_place(
+        resources: &Resources,
+        configuration: Configuration,
+        policy: ReprocessPolicy,
+    ) {
+        process(resources, options(configuration, policy))
+            .unwrap()
+            .result()
+            .unwrap();
+    }
+
+    fn get_marker(code: &str) -> &str {
+        code.lines()
+            .last()
+            .filter(|line| line.starts_with(MARKER_PREFIX))
+            .unwrap_or_else(|| panic!("expected a processed marker at the end of:\n{}", code))
+    }
+
+    #[test]
+    fn marker_is_appended_after_rules() {
+        let resources = memory_resources!(
+            "src/test.lua" => "-- comment\nreturn 1",
+        );
+
+        process_in_place(
+            &resources,
+            Configuration::empty().with_rule(Box::<RemoveComments>::default() as Box<dyn Rule>),
+            ReprocessPolicy::Skip,
+        );
+
+        let output = resources.get("src/test.lua").unwrap();
+        assert!(!output.contains("-- comment"), "{}", output);
+        assert!(
+            get_marker(&output).ends_with(" rules=remove_comments"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn processing_twice_keeps_a_single_marker() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return 1",
+        );
+
+        process_in_place(&resources, Configuration::empty(), ReprocessPolicy::Skip);
+        process_in_place(&resources, Configuration::empty(), ReprocessPolicy::Force);
+
+        let output = resources.get("src/test.lua").unwrap();
+        assert_eq!(output.matches(MARKER_PREFIX).count(), 1, "{}", output);
+    }
+
+    #[test]
+    fn skip_policy_does_not_write_processed_files() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return 1",
+        );
+        process_in_place(&resources, Configuration::empty(), ReprocessPolicy::Skip);
+
+        let rule = CountingRule::named("counting");
+        let runs = rule.runs();
+
+        process(
+            &resources,
+            options(
+                Configuration::empty().with_rule(Box::new(rule) as Box<dyn Rule>),
+                ReprocessPolicy::Skip,
+            )
+            .with_output("out"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert!(!resources.exists("out/test.lua").unwrap());
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn differential_policy_only_applies_new_rules() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return 1",
+        );
+        process_in_place(
+            &resources,
+            Configuration::empty()
+                .with_rule(Box::new(CountingRule::named("first")) as Box<dyn Rule>),
+            ReprocessPolicy::Skip,
+        );
+
+        let first = CountingRule::named("first");
+        let first_runs = first.runs();
+        let second = CountingRule::named("second");
+        let second_runs = second.runs();
+
+        process_in_place(
+            &resources,
+            Configuration::empty()
+                .with_rule(Box::new(first) as Box<dyn Rule>)
+                .with_rule(Box::new(second) as Box<dyn Rule>),
+            ReprocessPolicy::Differential,
+        );
+
+        assert_eq!(first_runs.load(Ordering::SeqCst), 0);
+        assert_eq!(second_runs.load(Ordering::SeqCst), 1);
+
+        let output = resources.get("src/test.lua").unwrap();
+        assert!(
+            get_marker(&output).ends_with(" rules=first,second"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn force_policy_applies_rules_again() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return 1",
+        );
+        process_in_place(
+            &resources,
+            Configuration::empty()
+                .with_rule(Box::new(CountingRule::named("first")) as Box<dyn Rule>),
+            ReprocessPolicy::Skip,
+        );
+
+        let first = CountingRule::named("first");
+        let first_runs = first.runs();
+
+        process_in_place(
+            &resources,
+            Configuration::empty().with_rule(Box::new(first) as Box<dyn Rule>),
+            ReprocessPolicy::Force,
+        );
+
+        assert_eq!(first_runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn force_policy_does_not_apply_unsafe_rules_twice() {
+        let resources = memory_resources!(
+            "src/test.lua" => "local a = 1 return a",
+        );
+        let configuration = || {
+            Configuration::empty().with_rule(Box::new(
+                ObfuscateIdentifiers::default()
+                    .with_mapping("a", "b")
+                    .with_mapping("b", "c"),
+            ) as Box<dyn Rule>)
+        };
+
+        process_in_place(&resources, configuration(), ReprocessPolicy::Skip);
+        let first_output = resources.get("src/test.lua").unwrap();
+        assert!(
+            first_output.starts_with("local b=1 return b"),
+            "{}",
+            first_output
+        );
+
+        process_in_place(&resources, configuration(), ReprocessPolicy::Force);
+
+        assert_eq!(resources.get("src/test.lua").unwrap(), first_output);
+    }
+}
+
+mod max_output_size {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn write_output_smaller_than_max_size() {
+        let resources = memory_resources!(
+            "src/test.lua" => ANY_CODE,
+        );
+
+        process(&resources, Options::new("src").with_max_output_size(1_000))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        assert_eq!(
+            resources.get("src/test.lua").unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+    }
+
+    #[test]
+    fn fail_when_output_is_larger_than_max_size() {
+        let resources = memory_resources!(
+            "src/test.lua" => ANY_CODE,
+        );
+
+        let errors = process(&resources, Options::new("src").with_max_output_size(1))
+            .unwrap()
+            .result()
+            .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .to_string()
+            .contains("which is larger than the maximum size of 1 bytes"));
+        assert_eq!(resources.get("src/test.lua").unwrap(), ANY_CODE);
+    }
+}
+
+mod rule_timeout {
+    use std::{thread, time::Duration};
+
+    use darklua_core::{
+        nodes::{Block, DoStatement, Statement},
+        process::{DefaultVisitor, NodeProcessor, NodeVisitor},
+        rules::{
+            ComputeExpression, Context, Rule, RuleConfiguration, RuleConfigurationError,
+            RuleProcessResult, RuleProperties,
+        },
+        Configuration, GeneratorParameters, ProcessingSession,
+    };
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// Replaces every statement with an empty `do` statement, slowly.
+    #[derive(Debug)]
+    struct SlowRule {
+        delay: Duration,
+    }
+
+    struct SlowProcessor {
+        delay: Duration,
+    }
+
+    impl NodeProcessor for SlowProcessor {
+        fn process_statement(&mut self, statement: &mut Statement) {
+            thread::sleep(self.delay);
+            *statement = DoStatement::default().into();
+        }
+    }
+
+    impl RuleConfiguration for SlowRule {
+        fn configure(&mut self, _: RuleProperties) -> Result<(), RuleConfigurationError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "slow_rule"
+        }
+
+        fn serialize_to_properties(&self) -> RuleProperties {
+            RuleProperties::new()
+        }
+    }
+
+    impl Rule for SlowRule {
+        fn process(&self, block: &mut Block, _: &Context) -> RuleProcessResult {
+            let mut processor = SlowProcessor { delay: self.delay };
+            DefaultVisitor::visit_block(block, &mut processor);
+            Ok(())
+        }
+    }
+
+    fn statements(count: usize) -> String {
+        "local a = 1 + 1\n".repeat(count)
+    }
+
+    fn slow_rule(delay: Duration) -> Box<dyn Rule> {
+        Box::new(SlowRule { delay })
+    }
+
+    fn compute_expression() -> Box<dyn Rule> {
+        Box::<ComputeExpression>::default()
+    }
+
+    fn run_session(code: &str, rules: Vec<Box<dyn Rule>>, options: Options) -> ProcessingSession {
+        let configuration = rules.into_iter().fold(
+            Configuration::empty().with_generator(GeneratorParameters::default_dense()),
+            Configuration::with_rule,
+        );
+
+        let mut session = ProcessingSession::new(options.with_configuration(configuration));
+        session.add_source("test.lua", code.to_owned());
+        session.run().unwrap();
+        session
+    }
+
+    fn generated_code(session: &ProcessingSession) -> String {
+        let (_, outcome) = session.iter_outputs().next().unwrap();
+        outcome.code().unwrap().to_owned()
+    }
+
+    #[test]
+    fn skip_rule_exceeding_time_budget() {
+        let code = statements(200);
+        let session = run_session(
+            &code,
+            vec![slow_rule(Duration::from_millis(5)), compute_expression()],
+            Options::new("src").with_rule_timeout(Duration::from_millis(100)),
+        );
+
+        let (_, outcome) = session.iter_outputs().next().unwrap();
+        assert!(outcome.is_success());
+
+        // the block is restored to its state before the slow rule, and the next rule is
+        // still applied
+        let expected = run_session(&code, vec![compute_expression()], Options::new("src"));
+        assert_eq!(generated_code(&session), generated_code(&expected));
+        assert!(generated_code(&session).starts_with("local a=2 local a=2"));
+
+        let timed_out_rules = outcome.timed_out_rules();
+        assert_eq!(timed_out_rules.len(), 1);
+        let (rule, elapsed) = &timed_out_rules[0];
+        assert_eq!(rule, "slow_rule");
+        assert!(
+            *elapsed >= Duration::from_millis(100),
+            "elapsed {:?}",
+            elapsed
+        );
+    }
+
+    #[test]
+    fn skip_rule_exceeding_time_budget_from_configuration() {
+        let code = statements(500);
+        let configuration = Configuration::empty()
+            .with_rule(slow_rule(Duration::from_millis(1)))
+            .with_rule_timeout(Duration::from_millis(10))
+            .with_generator(GeneratorParameters::default_dense());
+
+        let resources = memory_resources!(
+            "src/test.lua" => &code,
+        );
+
+        process(
+            &resources,
+            Options::new("src").with_configuration(configuration),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let expected = run_session(&code, Vec::new(), Options::new("src"));
+        assert_eq!(
+            resources.get("src/test.lua").unwrap(),
+            generated_code(&expected)
+        );
+    }
+
+    #[test]
+    fn apply_rule_within_time_budget() {
+        let session = run_session(
+            &statements(3),
+            vec![slow_rule(Duration::ZERO), compute_expression()],
+            Options::new("src").with_rule_timeout(Duration::from_secs(3600)),
+        );
+
+        let (_, outcome) = session.iter_outputs().next().unwrap();
+
+        assert_eq!(outcome.code().unwrap(), "do end do end do end");
+        assert!(outcome.timed_out_rules().is_empty());
+    }
+
+    #[test]
+    fn options_time_budget_overrides_configuration() {
+        let configuration = Configuration::empty()
+            .with_rule(slow_rule(Duration::ZERO))
+            .with_rule_timeout(Duration::ZERO)
+            .with_generator(GeneratorParameters::default_dense());
+
+        let mut session = ProcessingSession::new(
+            Options::new("src")
+                .with_configuration(configuration)
+                .with_rule_timeout(Duration::from_secs(3600)),
+        );
+        session.add_source("test.lua", statements(3));
+        session.run().unwrap();
+
+        assert_eq!(generated_code(&session), "do end do end do end");
+    }
+
+    #[test]
+    fn deserialize_rule_timeout() {
+        for (value, expected) in [
+            ("30", Duration::from_secs(30)),
+            ("0.5", Duration::from_millis(500)),
+            ("'250ms'", Duration::from_millis(250)),
+            ("'2min'", Duration::from_secs(120)),
+        ] {
+            let configuration: Configuration =
+                json5::from_str(&format!("{{ rules: [], rule_timeout: {} }}", value)).unwrap();
+
+            assert_eq!(
+                json5::to_string(&configuration).unwrap(),
+                json5::to_string(&Configuration::empty().with_rule_timeout(expected)).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize_invalid_rule_timeout_errors() {
+        let error = json5::from_str::<Configuration>("{ rule_timeout: '10m' }")
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("ambiguous unit `m`"), "{}", error);
+    }
+}
+
+mod session {
+    use std::path::Path;
+
+    use darklua_core::{Configuration, ProcessingSession};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const SESSION_INPUT: &str = "darklua-session-input";
+    const SESSION_OUTPUT: &str = "darklua-session-output";
+
+    fn run_session(options: Options) -> ProcessingSession {
+        let mut session = ProcessingSession::new(options);
+
+        session.add_source("a.lua", ANY_CODE.to_owned());
+        session.add_source("nested/b.lua", "local  value = 1 return value".to_owned());
+        session.add_source("c.lua", "return +".to_owned());
+
+        session.run().unwrap();
+
+        assert!(!Path::new(SESSION_INPUT).exists());
+        assert!(!Path::new(SESSION_OUTPUT).exists());
+
+        session
+    }
+
+    #[test]
+    fn process_sources_in_memory() {
+        let session = run_session(Options::new(SESSION_INPUT).with_output(SESSION_OUTPUT));
+
+        let outputs: Vec<_> = session.iter_outputs().collect();
+
+        assert_eq!(
+            outputs.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            vec!["a.lua", "nested/b.lua", "c.lua"]
+        );
+
+        let (_, a) = outputs[0];
+        assert!(a.is_success());
+        assert_eq!(a.code(), Some(ANY_CODE_DEFAULT_PROCESS));
+        assert_eq!(a.input_size(), ANY_CODE.len() as u64);
+        assert_eq!(a.output_size(), Some(ANY_CODE_DEFAULT_PROCESS.len() as u64));
+        assert_eq!(a.rule_profile(), None);
+
+        let (_, b) = outputs[1];
+        assert!(b.is_success());
+        assert_eq!(b.code(), Some("local a=1 return a"));
+
+        let (_, c) = outputs[2];
+        assert!(!c.is_success());
+        assert_eq!(c.code(), None);
+        assert_eq!(c.output_size(), None);
+        assert_eq!(c.errors().len(), 1);
+
+        assert_eq!(session.success_count(), 2);
+        assert_eq!(session.collect_errors().len(), 1);
+    }
+
+    #[test]
+    fn process_sources_in_place() {
+        let session = run_session(Options::new(SESSION_INPUT));
+
+        let codes: Vec<_> = session
+            .iter_outputs()
+            .map(|(_, outcome)| outcome.code())
+            .collect();
+
+        assert_eq!(
+            codes,
+            vec![
+                Some(ANY_CODE_DEFAULT_PROCESS),
+                Some("local a=1 return a"),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn profile_rules() {
+        let session = run_session(
+            Options::new(SESSION_INPUT)
+                .with_configuration(
+                    json5::from_str::<Configuration>(
+                        "{ rules: ['remove_empty_do', 'remove_spaces'] }",
+                    )
+                    .unwrap(),
+                )
+                .with_rule_profiling(),
+        );
+
+        let (_, outcome) = session.iter_outputs().next().unwrap();
+
+        assert_eq!(
+            outcome
+                .rule_profile()
+                .unwrap()
+                .iter()
+                .map(|(rule, _)| rule.as_str())
+                .collect::<Vec<_>>(),
+            vec!["remove_empty_do", "remove_spaces"]
+        );
+    }
+
+    #[test]
+    fn run_session_twice_errors() {
+        let mut session = run_session(Options::new(SESSION_INPUT));
+
+        assert!(session.run().is_err());
+    }
+}
+
+mod readable_layout {
+    use super::{memory_resources, process, Options, Resources};
+
+    const FIXTURE: &str = r#"--[[
+    Tracks the score of a player.
+]]
+local Players = game:GetService("Players")
+local RunService = game:GetService("RunService")
+
+local DEFAULT_SCORE = 0 -- why: scores start empty
+local MAX_SCORE = 100
+
+
+--[[ The score of every player ]]
+local Scores = {}
+
+-- increments the score of the given player
+function Scores:increment(player, amount)
+    local score = self[player] or DEFAULT_SCORE
+
+    -- never go past the maximum
+    self[player] = math.min(score + amount, MAX_SCORE)
+end
+
+return Scores
+-- end of module
+"#;
+
+    fn process_fixture(config: &str) -> String {
+        let resources = memory_resources!(
+            "src/scores.lua" => FIXTURE,
+            ".darklua.json5" => config,
+        );
+
+        process(&resources, Options::new("src").with_output("out"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        resources.get("out/scores.lua").unwrap()
+    }
+
+    #[test]
+    fn preserve_layout_without_rules() {
+        insta::assert_snapshot!(
+            "readable_preserve_layout_without_rules",
+            process_fixture(
+                "{ generator: { name: 'readable', preserve_layout: true }, rules: [] }"
+            )
+        );
+    }
+
+    #[test]
+    fn preserve_layout_with_remove_method_definition() {
+        insta::assert_snapshot!(
+            "readable_preserve_layout_with_remove_method_definition",
+            process_fixture(
+                "{ generator: { name: 'readable', preserve_layout: true }, rules: ['remove_method_definition'] }"
+            )
+        );
+    }
+
+    #[test]
+    fn preserve_layout_with_two_blank_lines() {
+        insta::assert_snapshot!(
+            "readable_preserve_layout_with_two_blank_lines",
+            process_fixture(
+                "{ generator: { name: 'readable', preserve_layout: true, max_consecutive_blank_lines: 2 }, rules: [] }"
+            )
+        );
+    }
+
+    #[test]
+    fn preserve_layout_without_blank_lines() {
+        insta::assert_snapshot!(
+            "readable_preserve_layout_without_blank_lines",
+            process_fixture(
+                "{ generator: { name: 'readable', preserve_layout: true, max_consecutive_blank_lines: 0 }, rules: [] }"
+            )
+        );
+    }
+
+    #[test]
+    fn readable_without_layout_drops_comments() {
+        let output = process_fixture("{ generator: 'readable', rules: [] }");
+
+        assert!(!output.contains("--"), "unexpected comment in:\n{}", output);
+    }
+}
+
+mod content_hash {
+    use darklua_core::{HashAlgorithm, OutputTemplate, ProcessingSession};
+    use pretty_assertions::assert_eq;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    fn resources() -> Resources {
+        memory_resources!(
+            "src/init.lua" => ANY_CODE,
+            "src/lib/util.lua" => "local  value = 1 return value",
+        )
+    }
+
+    fn process_with_manifest(resources: &Resources) -> String {
+        process(
+            resources,
+            Options::new("src")
+                .with_output("out")
+                .with_hash_manifest("manifest.txt"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        resources.get("manifest.txt").unwrap()
+    }
+
+    fn sha256(content: &str) -> String {
+        Sha256::digest(content.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    #[test]
+    fn manifest_is_deterministic() {
+        let first = process_with_manifest(&resources());
+        let second = process_with_manifest(&resources());
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn manifest_lists_digest_of_each_output() {
+        let resources = resources();
+
+        let manifest = process_with_manifest(&resources);
+
+        assert_eq!(
+            manifest,
+            format!(
+                "init.lua: {}\nlib/util.lua: {}\n",
+                sha256(&resources.get("out/init.lua").unwrap()),
+                sha256(&resources.get("out/lib/util.lua").unwrap()),
+            )
+        );
+    }
+
+    #[test]
+    fn manifest_uses_hash_algorithm() {
+        let resources = resources();
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_output("out")
+                .with_hash_manifest("manifest.txt")
+                .with_hash_algorithm(HashAlgorithm::Xxh3),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let manifest = resources.get("manifest.txt").unwrap();
+        let init_line = manifest.lines().next().unwrap();
+
+        assert_eq!(
+            init_line,
+            format!(
+                "init.lua: {}",
+                HashAlgorithm::Xxh3.digest(resources.get("out/init.lua").unwrap().as_bytes())
+            )
+        );
+    }
+
+    #[test]
+    fn output_template_uses_content_hash() {
+        let resources = resources();
+        let template: OutputTemplate = "{name}.{content_hash8}.{extension}".parse().unwrap();
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_output("out")
+                .with_output_template(template),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let expected_hash = sha256(ANY_CODE_DEFAULT_PROCESS);
+        let expected_path = format!("out/init.{}.lua", &expected_hash[..8]);
+
+        assert_eq!(
+            resources.get(&expected_path).unwrap(),
+            ANY_CODE_DEFAULT_PROCESS
+        );
+        assert!(!resources.exists("out/init.lua").unwrap());
+    }
+
+    #[test]
+    fn manifest_lists_templated_outputs() {
+        let resources = memory_resources!(
+            "src/init.lua" => ANY_CODE,
+        );
+        let template: OutputTemplate = "{name}.{content_hash8}.lua".parse().unwrap();
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_output("out")
+                .with_output_template(template)
+                .with_hash_manifest("manifest.txt"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let expected_hash = sha256(ANY_CODE_DEFAULT_PROCESS);
+
+        assert_eq!(
+            resources.get("manifest.txt").unwrap(),
+            format!("init.{}.lua: {}\n", &expected_hash[..8], expected_hash)
+        );
+    }
+
+    #[test]
+    fn output_template_collision_errors() {
+        let resources = memory_resources!(
+            "src/a.lua" => ANY_CODE,
+            "src/b.lua" => ANY_CODE,
+        );
+        let template: OutputTemplate = "{content_hash8}.lua".parse().unwrap();
+
+        let errors = process(
+            &resources,
+            Options::new("src")
+                .with_output("out")
+                .with_output_template(template),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("would both be written to"));
+    }
+
+    #[test]
+    fn session_outcome_has_content_hash() {
+        let mut session =
+            ProcessingSession::new(Options::new("src").with_hash_algorithm(HashAlgorithm::Sha256));
+
+        session.add_source("init.lua", ANY_CODE.to_owned());
+        session.run().unwrap();
+
+        let (_, outcome) = session.iter_outputs().next().unwrap();
+
+        assert_eq!(
+            outcome.content_hash(),
+            Some(sha256(ANY_CODE_DEFAULT_PROCESS).as_str())
+        );
+    }
+
+    #[test]
+    fn session_outcome_without_hash_algorithm() {
+        let mut session = ProcessingSession::new(Options::new("src"));
+
+        session.add_source("init.lua", ANY_CODE.to_owned());
+        session.run().unwrap();
+
+        let (_, outcome) = session.iter_outputs().next().unwrap();
+
+        assert_eq!(outcome.content_hash(), None);
+    }
+}
+
+mod artifacts {
+    use darklua_core::{
+        rules::{ArtifactContent, ArtifactKind, ExtractStrings, Rule, StringTableFormat},
+        ArtifactTemplate, Configuration, ProcessingSession,
+    };
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const CODE_WITH_STRINGS: &str = "print('hello')\nreturn 'world'";
+
+    fn extract_strings_configuration() -> Configuration {
+        Configuration::empty().with_rule(Box::<ExtractStrings>::default() as Box<dyn Rule>)
+    }
+
+    fn template(template: &str) -> ArtifactTemplate {
+        template.parse().unwrap()
+    }
+
+    fn collect_outputs(resources: &Resources) -> Vec<String> {
+        let mut outputs: Vec<_> = resources
+            .walk("out")
+            .map(|path| path.display().to_string().replace('\\', "/"))
+            .collect();
+        outputs.sort();
+        outputs
+    }
+
+    #[test]
+    fn artifact_is_written_relative_to_generated_file() {
+        let resources = memory_resources!(
+            "src/lib/util.lua" => CODE_WITH_STRINGS,
+        );
+
+        process(
+            &resources,
+            Options::new("src").with_output("out").with_configuration(
+                extract_strings_configuration()
+                    .with_artifact("strings", template("i18n/{name}.{extension}.csv")),
+            ),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(
+            collect_outputs(&resources),
+            vec!["out/lib/i18n/util.lua.csv", "out/lib/util.lua"]
+        );
+        assert!(resources
+            .get("out/lib/i18n/util.lua.csv")
+            .unwrap()
+            .contains("\"key\": "));
+    }
+
+    #[test]
+    fn artifact_template_from_configuration_file() {
+        let resources = memory_resources!(
+            "src/init.lua" => CODE_WITH_STRINGS,
+            ".darklua.json" => r#"{
+                "rules": [{ "rule": "extract_strings", "format": "csv" }],
+                "artifacts": { "strings": "{name}.strings.csv" }
+            }"#,
+        );
+
+        process(&resources, Options::new("src").with_output("out"))
+            .unwrap()
+            .result()
+            .unwrap();
+
+        let table = resources.get("out/init.strings.csv").unwrap();
+        let rows: Vec<_> = table.lines().collect();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], "key,line,value");
+        assert!(rows[1].ends_with(",1,hello"));
+        assert!(rows[2].ends_with(",2,world"));
+    }
+
+    #[test]
+    fn options_artifact_overrides_configuration() {
+        let resources = memory_resources!(
+            "src/init.lua" => CODE_WITH_STRINGS,
+        );
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_output("out")
+                .with_configuration(
+                    extract_strings_configuration()
+                        .with_artifact("strings", template("{name}.json")),
+                )
+                .with_artifact("strings", template("strings/{name}.json")),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(
+            collect_outputs(&resources),
+            vec!["out/init.lua", "out/strings/init.json"]
+        );
+    }
+
+    #[test]
+    fn artifact_template_uses_content_hash() {
+        let resources = memory_resources!(
+            "src/init.lua" => CODE_WITH_STRINGS,
+        );
+
+        process(
+            &resources,
+            Options::new("src").with_output("out").with_configuration(
+                extract_strings_configuration()
+                    .with_artifact("strings", template("{name}.{content_hash8}.json")),
+            ),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let code = resources.get("out/init.lua").unwrap();
+        let hash = darklua_core::HashAlgorithm::Sha256.digest(code.as_bytes());
+
+        assert!(resources
+            .exists(format!("out/init.{}.json", &hash[..8]))
+            .unwrap());
+    }
+
+    #[test]
+    fn unconfigured_artifact_is_not_written() {
+        let resources = memory_resources!(
+            "src/init.lua" => CODE_WITH_STRINGS,
+        );
+
+        process(
+            &resources,
+            Options::new("src").with_output("out").with_configuration(
+                extract_strings_configuration()
+                    .with_artifact("rename_map", template("{name}.json")),
+            ),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(collect_outputs(&resources), vec!["out/init.lua"]);
+    }
+
+    #[test]
+    fn artifacts_are_not_written_in_check_mode() {
+        let resources = memory_resources!(
+            "src/init.lua" => CODE_WITH_STRINGS,
+            "out/init.lua" => CODE_WITH_STRINGS,
+        );
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_output("out")
+                .with_configuration(
+                    extract_strings_configuration()
+                        .with_generator(darklua_core::GeneratorParameters::RetainLines)
+                        .with_artifact("strings", template("{name}.json")),
+                )
+                .with_check(),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert_eq!(collect_outputs(&resources), vec!["out/init.lua"]);
+    }
+
+    #[test]
+    fn session_outcome_has_artifacts() {
+        let mut session = ProcessingSession::new(
+            Options::new("src").with_configuration(
+                Configuration::empty().with_rule(Box::new(
+                    ExtractStrings::default()
+                        .with_min_length(5)
+                        .with_format(StringTableFormat::Csv),
+                ) as Box<dyn Rule>),
+            ),
+        );
+
+        session.add_source("init.lua", "return 'hi', 'hello'".to_owned());
+        session.run().unwrap();
+
+        let (_, outcome) = session.iter_outputs().next().unwrap();
+
+        assert_eq!(outcome.artifacts().len(), 1);
+
+        let artifact = outcome.artifact("strings").unwrap();
+        assert_eq!(artifact.kind(), ArtifactKind::Csv);
+
+        match artifact.content() {
+            ArtifactContent::Text(table) => {
+                assert_eq!(table.lines().count(), 2);
+                assert!(table.ends_with(",1,hello\n"));
+            }
+            ArtifactContent::Json(_) => panic!("expected a CSV table"),
+        }
+    }
+
+    #[test]
+    fn artifact_collision_errors() {
+        let resources = memory_resources!(
+            "src/a.lua" => CODE_WITH_STRINGS,
+            "src/b.lua" => CODE_WITH_STRINGS,
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("src").with_output("out").with_configuration(
+                extract_strings_configuration().with_artifact("strings", template("strings.json")),
+            ),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .to_string()
+            .contains("would both write an artifact to"));
+    }
+}
+
+mod output_checks {
+    use darklua_core::{check_output_files, Configuration, OutputCheck};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn process_with_checks(code: &str, checks: &str) -> Result<(), Vec<String>> {
+        let resources = memory_resources!(
+            "src/test.lua" => code,
+            ".darklua.json5" => &format!(
+                "{{ rules: [], generator: 'retain_lines', checks: {} }}",
+                checks
+            ),
+        );
+
+        process(&resources, Options::new("src").with_output("out"))
+            .unwrap()
+            .result()
+            .map_err(|errors| errors.iter().map(ToString::to_string).collect())
+    }
+
+    fn expect_violations(code: &str, checks: &str, expected: &[&str]) {
+        let errors = process_with_checks(code, checks).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0],
+            format!(
+                "generated code for `out/test.lua` does not pass the output checks:\n{}",
+                expected
+                    .iter()
+                    .map(|violation| format!("  {}", violation))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        );
+    }
+
+    #[test]
+    fn clean_code_passes_all_checks() {
+        process_with_checks(
+            "local value = 'public'\nreturn value\n",
+            r#"[
+                { forbid_call: 'loadstring' },
+                { forbid_global: 'getfenv' },
+                { forbid_string_matching: 'internal' },
+                'forbid_comments',
+                { max_line_length: 40 },
+                { require_parse: true },
+            ]"#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn forbid_call() {
+        expect_violations(
+            "local f = string.dump(print)\nreturn f",
+            "[{ forbid_call: 'string.dump' }]",
+            &["1:11: forbidden call `string.dump(print)` (forbid_call)"],
+        );
+    }
+
+    #[test]
+    fn forbid_global() {
+        expect_violations(
+            "local env = getfenv()\nreturn env",
+            "[{ forbid_global: 'getfenv' }]",
+            &["1:13: forbidden global `getfenv` (forbid_global)"],
+        );
+    }
+
+    #[test]
+    fn forbid_string_matching() {
+        expect_violations(
+            "return 'https://internal.example.com'",
+            "[{ forbid_string_matching: 'internal.example.com' }]",
+            &["1:8: forbidden string `'https://internal.example.com'` (forbid_string_matching)"],
+        );
+    }
+
+    #[test]
+    fn forbid_comments() {
+        expect_violations(
+            "-- todo\nreturn 1",
+            "['forbid_comments']",
+            &["1:1: forbidden comment (forbid_comments)"],
+        );
+    }
+
+    #[test]
+    fn max_line_length() {
+        expect_violations(
+            "local value = 1\nreturn value",
+            "[{ max_line_length: 12 }]",
+            &["1:13: line of 15 characters is longer than 12 characters (max_line_length)"],
+        );
+    }
+
+    #[test]
+    fn require_parse_passes_on_generated_code() {
+        process_with_checks("return 1", "[{ require_parse: true }]").unwrap();
+    }
+
+    #[test]
+    fn violations_of_multiple_checks_are_sorted_by_position() {
+        expect_violations(
+            "local env = getfenv()\nreturn loadstring('')",
+            "[{ forbid_call: 'loadstring' }, { forbid_global: 'getfenv' }]",
+            &[
+                "1:13: forbidden global `getfenv` (forbid_global)",
+                "2:8: forbidden call `loadstring('')` (forbid_call)",
+            ],
+        );
+    }
+
+    #[test]
+    fn checks_run_after_postprocessors() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return 1",
+            ".darklua.json5" => "{ rules: [], postprocessors: ['checksum_trailer'], checks: ['forbid_comments'] }",
+        );
+
+        let errors = process(&resources, Options::new("src").with_output("out"))
+            .unwrap()
+            .result()
+            .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .to_string()
+            .contains("2:1: forbidden comment (forbid_comments)"));
+        assert!(!resources.exists("out/test.lua").unwrap());
+    }
+
+    #[test]
+    fn checks_run_on_processed_marker() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return 1",
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("src")
+                .with_output("out")
+                .with_processed_marker()
+                .with_configuration(Configuration::empty().with_check(OutputCheck::ForbidComments)),
+        )
+        .unwrap()
+        .result()
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("(forbid_comments)"));
+    }
+
+    #[test]
+    fn check_output_files_reports_files_with_violations() {
+        let resources = memory_resources!(
+            "out/clean.lua" => "return 1",
+            "out/dirty.lua" => "return loadstring('')",
+            ".darklua.json5" => "{ checks: [{ forbid_call: 'loadstring' }] }",
+        );
+
+        let failures = check_output_files(&resources, Options::new("out")).unwrap();
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, std::path::PathBuf::from("out/dirty.lua"));
+        assert_eq!(
+            failures[0]
+                .1
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["1:8: forbidden call `loadstring('')` (forbid_call)"]
+        );
+    }
+
+    #[test]
+    fn invalid_check_in_configuration_file() {
+        let resources = memory_resources!(
+            "src/test.lua" => "return 1",
+            ".darklua.json5" => "{ checks: [{ forbid_global: 'not valid' }] }",
+        );
+
+        let errors = process(&resources, Options::new("src"))
+            .map_err(|err| vec![err])
+            .and_then(|tree| tree.result())
+            .expect_err("processing should fail");
+        let error = errors[0].to_string();
+
+        assert!(
+            error.contains("`not valid` is not an identifier"),
+            "unexpected error: {}",
+            error
+        );
+    }
+}
+
+mod stages {
+    use super::{memory_resources, process, Options, Resources};
+    use darklua_core::StageSelection;
+
+    use pretty_assertions::assert_eq;
+
+    const STAGES_CONFIG: &str = r#"{
+        stages: [
+            {
+                name: "pre",
+                rules: [
+                    { rule: "rewrite", rewrites: [{ find: "trace($v)", replace: "a($v)" }] },
+                ],
+            },
+            {
+                name: "dist",
+                rules: ["remove_spaces", "rename_variables"],
+            },
+        ],
+    }"#;
+    const CODE: &str = "local value = 1 trace(value) return value";
+
+    fn run_stage(resources: &Resources, input: &str, output: &str, stage: &str) {
+        process(
+            resources,
+            Options::new(input)
+                .with_output(output)
+                .with_stage(StageSelection::Named(stage.to_owned())),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+    }
+
+    #[test]
+    fn separate_invocations_match_all_stages() {
+        let resources = memory_resources!(
+            "src/test.lua" => CODE,
+            ".darklua.json5" => STAGES_CONFIG,
+        );
+
+        run_stage(&resources, "src", "build/pre", "pre");
+        run_stage(&resources, "build/pre", "build/dist", "dist");
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_output("build/all")
+                .with_stage(StageSelection::All),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        let separate = resources.get("build/dist/test.lua").unwrap();
+
+        assert_eq!(separate, resources.get("build/all/test.lua").unwrap());
+        assert!(
+            !separate.contains("local a="),
+            "unexpected code: {}",
+            separate
+        );
+        assert!(separate.contains("a(b)"), "unexpected code: {}", separate);
+    }
+
+    #[test]
+    fn pre_stage_writes_reserved_identifiers() {
+        let resources = memory_resources!(
+            "src/test.lua" => CODE,
+            ".darklua.json5" => STAGES_CONFIG,
+        );
+
+        run_stage(&resources, "src", "build/pre", "pre");
+
+        let state = resources.get(".darklua-cache/stages/pre.json").unwrap();
+
+        assert!(state.contains("\"a\""), "unexpected state: {}", state);
+        assert!(!state.contains("\"trace\""), "unexpected state: {}", state);
+    }
+
+    #[test]
+    fn cache_directory_from_options() {
+        let resources = memory_resources!(
+            "src/test.lua" => CODE,
+            ".darklua.json5" => STAGES_CONFIG,
+        );
+
+        process(
+            &resources,
+            Options::new("src")
+                .with_output("build/pre")
+                .with_stage(StageSelection::Named("pre".to_owned()))
+                .with_cache_directory("cache"),
+        )
+        .unwrap()
+        .result()
+        .unwrap();
+
+        assert!(resources.exists("cache/stages/pre.json").unwrap());
+        assert!(!resources.exists(".darklua-cache/stages/pre.json").unwrap());
+    }
+
+    #[test]
+    fn dist_stage_without_pre_state_errors() {
+        let resources = memory_resources!(
+            "build/pre/test.lua" => CODE,
+            ".darklua.json5" => STAGES_CONFIG,
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("build/pre")
+                .with_output("build/dist")
+                .with_stage(StageSelection::Named("dist".to_owned())),
+        )
+        .map_err(|err| vec![err])
+        .and_then(|tree| tree.result())
+        .expect_err("processing should fail");
+        let error = errors[0].to_string();
+
+        assert!(
+            error
+                .contains("unable to run stage `dist` because the state of stage `pre` is missing"),
+            "unexpected error: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn unknown_stage_errors() {
+        let resources = memory_resources!(
+            "src/test.lua" => CODE,
+            ".darklua.json5" => STAGES_CONFIG,
+        );
+
+        let errors = process(
+            &resources,
+            Options::new("src").with_stage(StageSelection::Named("final".to_owned())),
+        )
+        .map_err(|err| vec![err])
+        .and_then(|tree| tree.result())
+        .expect_err("processing should fail");
+        let error = errors[0].to_string();
+
+        assert!(error.contains("`final`"), "unexpected error: {}", error);
+    }
+
+    #[test]
+    fn rules_with_stages_errors() {
+        let resources = memory_resources!(
+            "src/test.lua" => CODE,
+            ".darklua.json5" => "{ rules: [], stages: [{ name: 'pre' }] }",
+        );
+
+        let errors = process(&resources, Options::new("src"))
+            .map_err(|err| vec![err])
+            .and_then(|tree| tree.result())
+            .expect_err("processing should fail");
+        let error = errors[0].to_string();
+
+        assert!(
+            error.contains("the `rules` field cannot be used with `stages`"),
+            "unexpected error: {}",
+            error
+        );
+    }
+}
+
+mod doctor {
+    use super::{memory_resources, Options, Resources};
+    use darklua_core::rules::{get_all_rule_names, CompatibilityKind};
+    use darklua_core::{doctor, DoctorReport};
+    use pretty_assertions::assert_eq;
+
+    const SCOPE_SENSITIVE_RULES: &[&str] = &[
+        "inline_constants",
+        "inline_functions",
+        "remove_dead_stores",
+        "remove_unused_variable",
+        "rename_variables",
+    ];
+
+    fn big_function() -> String {
+        let mut code = "local function big()\n".to_owned();
+        for i in 0..210 {
+            code.push_str(&format!("    local v{} = {}\n", i, i));
+        }
+        code.push_str("end\nreturn big\n");
+        code
+    }
+
+    fn run_doctor(resources: &Resources) -> DoctorReport {
+        doctor(resources, Options::new("src")).unwrap()
+    }
+
+    fn notes(report: &DoctorReport, rule: &str) -> Vec<(String, CompatibilityKind, String)> {
+        report
+            .get_rule(rule)
+            .unwrap_or_else(|| panic!("rule `{}` should be in the report", rule))
+            .files()
+            .iter()
+            .flat_map(|file| {
+                file.notes().iter().map(move |note| {
+                    (
+                        file.path().display().to_string(),
+                        note.kind(),
+                        note.to_string(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn report_contains_every_built_in_rule() {
+        let resources = memory_resources!(
+            "src/a.lua" => "return 1",
+        );
+
+        let report = run_doctor(&resources);
+
+        assert_eq!(
+            report
+                .rules()
+                .iter()
+                .map(|rule| rule.rule())
+                .collect::<Vec<_>>(),
+            get_all_rule_names()
+        );
+    }
+
+    #[test]
+    fn compatible_files_have_no_notes() {
+        let resources = memory_resources!(
+            "src/a.lua" => "local value = 1\nreturn value",
+            "src/b.lua" => "local function add(a, b) return a + b end\nreturn add(1, 2)",
+        );
+
+        let report = run_doctor(&resources);
+
+        assert_eq!(report.checked_files(), 2);
+        assert!(report.file_issues().is_empty());
+        assert!(report.rules().iter().all(|rule| rule.is_compatible()));
+    }
+
+    #[test]
+    fn setfenv_skips_scope_sensitive_rules() {
+        let resources = memory_resources!(
+            "src/env.lua" => "setfenv(1, {})\nlocal value = 1\nreturn value",
+        );
+
+        let report = run_doctor(&resources);
+
+        for rule in SCOPE_SENSITIVE_RULES {
+            assert_eq!(
+                notes(&report, rule),
+                vec![(
+                    "src/env.lua".to_owned(),
+                    CompatibilityKind::Skipped,
+                    "skipped: the file uses `setfenv` to access its environment dynamically"
+                        .to_owned()
+                )],
+                "unexpected notes for `{}`",
+                rule
+            );
+            assert_eq!(
+                report
+                    .get_rule(rule)
+                    .unwrap()
+                    .file_count(CompatibilityKind::Skipped),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn variadic_local_function_is_conservative_for_inline_functions() {
+        let resources = memory_resources!(
+            "src/vararg.lua" => "local value = 1\nlocal function pack(...)\n    return ...\nend\nreturn pack(value)",
+        );
+
+        let report = run_doctor(&resources);
+
+        assert_eq!(
+            notes(&report, "inline_functions"),
+            vec![(
+                "src/vararg.lua".to_owned(),
+                CompatibilityKind::Conservative,
+                "conservative (line 2): local function `pack` is variadic and is never inlined"
+                    .to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn dynamic_require_is_conservative_for_convert_require() {
+        let resources = memory_resources!(
+            "src/dynamic.lua" => "local name = 'module'\nreturn require(name)",
+        );
+
+        let report = run_doctor(&resources);
+
+        assert_eq!(
+            notes(&report, "convert_require"),
+            vec![(
+                "src/dynamic.lua".to_owned(),
+                CompatibilityKind::Conservative,
+                "conservative (line 2): require call cannot be resolved with the current \
+                require mode and is left unchanged"
+                    .to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn function_over_locals_limit_is_risky_for_validate_limits() {
+        let resources = memory_resources!(
+            "src/big.lua" => &big_function(),
+        );
+
+        let report = run_doctor(&resources);
+
+        let notes = notes(&report, "validate_limits");
+        assert_eq!(notes.len(), 1);
+        let (path, kind, message) = &notes[0];
+        assert_eq!(path, "src/big.lua");
+        assert_eq!(*kind, CompatibilityKind::Risky);
+        assert!(
+            message.contains("local function `big` (line 1) has 210 local variables"),
+            "unexpected message: {}",
+            message
+        );
+    }
+
+    #[test]
+    fn rules_without_specific_checks_have_no_notes() {
+        let resources = memory_resources!(
+            "src/env.lua" => "setfenv(1, {})\nlocal value = 1\nreturn value",
+            "src/vararg.lua" => "local function pack(...) return ... end\nreturn pack(1)",
+            "src/dynamic.lua" => "return require(getName())",
+        );
+
+        let report = run_doctor(&resources);
+
+        for rule in ["remove_comments", "remove_spaces", "compute_expression"].iter() {
+            assert!(
+                report.get_rule(rule).unwrap().is_compatible(),
+                "rule `{}` should use the default compatibility check",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn parse_errors_do_not_stop_the_report() {
+        let resources = memory_resources!(
+            "src/bad.lua" => "local =",
+            "src/env.lua" => "setfenv(1, {})\nreturn nil",
+        );
+
+        let report = run_doctor(&resources);
+
+        assert_eq!(report.checked_files(), 2);
+        assert_eq!(report.file_issues().len(), 1);
+        let issue = &report.file_issues()[0];
+        assert_eq!(issue.path().display().to_string(), "src/bad.lua");
+        assert!(
+            issue.message().starts_with("unable to parse file: "),
+            "unexpected message: {}",
+            issue.message()
+        );
+        assert_eq!(notes(&report, "rename_variables").len(), 1);
+    }
+}
+
+mod apply_edits {
+    use super::{memory_resources, Resources};
+    use darklua_core::apply_edits;
+    use darklua_core::process::NodeEdit;
+    use pretty_assertions::assert_eq;
+
+    const CODE: &str = "local a = 1 -- first\nlocal b = 2\nprint(a)\n\nreturn a + b\n";
+
+    fn edits(json: &str) -> Vec<NodeEdit> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn apply(resources: &Resources, json: &str) -> Result<String, String> {
+        apply_edits(resources, "src/main.lua", &edits(json)).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn replace_delete_and_insert() {
+        let resources = memory_resources!("src/main.lua" => CODE);
+
+        let code = apply(
+            &resources,
+            r#"[
+                { "operation": "replace", "path": "block.statements[1].values[0]", "code": "a * 2" },
+                { "operation": "delete", "path": "block.statements[2]" },
+                { "operation": "insert_before", "path": "block.statements[0]", "code": "local start = os.clock()" }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            code,
+            "local start = os.clock()\nlocal a = 1 -- first\nlocal b = a * 2\n\nreturn a + b\n"
+        );
+    }
+
+    #[test]
+    fn invalid_path_is_an_error() {
+        let resources = memory_resources!("src/main.lua" => CODE);
+
+        assert_eq!(
+            apply(
+                &resources,
+                r#"[{ "operation": "delete", "path": "block.statements[7]" }]"#,
+            )
+            .unwrap_err(),
+            "unable to apply edits to `src/main.lua`: path `block.statements[7]` does not exist"
+        );
+    }
+
+    #[test]
+    fn code_of_another_kind_is_an_error() {
+        let resources = memory_resources!("src/main.lua" => CODE);
+
+        assert_eq!(
+            apply(
+                &resources,
+                r#"[{ "operation": "replace", "path": "block.statements[0].values[0]", "code": "local c = 3" }]"#,
+            )
+            .unwrap_err(),
+            "unable to apply edits to `src/main.lua`: edit at `block.statements[0].values[0]` \
+            expects an expression but the code is a statement: `local c = 3`"
+        );
+    }
+
+    #[test]
+    fn overlapping_edits_are_rejected() {
+        let resources = memory_resources!("src/main.lua" => CODE);
+
+        assert_eq!(
+            apply(
+                &resources,
+                r#"[
+                    { "operation": "replace", "path": "block.statements[1].values[0]", "code": "3" },
+                    { "operation": "delete", "path": "block.statements[1]" }
+                ]"#,
+            )
+            .unwrap_err(),
+            "unable to apply edits to `src/main.lua`: conflicting edits: \
+            `block.statements[1].values[0]` and `block.statements[1]` overlap"
         );
     }
 }
@@ -0,0 +1,104 @@
+use darklua_core::rules::{ArityFix, AritySeverity, CheckAssignmentArity, Rule};
+
+use super::process_rules;
+
+test_rule_without_effects!(
+    CheckAssignmentArity::default(),
+    fewer_values_ending_with_call("local a, b, c = f()"),
+    fewer_literal_values("local a, b, c = 1"),
+    more_literal_values("local a, b = 1, 2, 3"),
+    more_values_ending_with_varargs("local a = 1, ..."),
+    declaration_without_values("local a, b"),
+    assignment_with_fewer_values("a, b = 1"),
+);
+
+test_rule!(
+    check_assignment_arity_pad_nil,
+    CheckAssignmentArity::default().with_fix(ArityFix::PadNil),
+    pad_local_assignment("local a, b, c = 1") => "local a, b, c = 1, nil, nil",
+    pad_assignment("a, b = true") => "a, b = true, nil",
+    pad_local_assignment_with_parenthese_call("local a, b = (f())") => "local a, b = (f()), nil",
+    pad_nested_local_assignment("local function f() local a, b = 1 return a, b end")
+        => "local function f() local a, b = 1, nil return a, b end",
+);
+
+test_rule_without_effects!(
+    CheckAssignmentArity::default().with_fix(ArityFix::PadNil),
+    pad_nil_fewer_values_ending_with_call("local a, b, c = f()"),
+    pad_nil_fewer_values_ending_with_varargs("local a, b = ..."),
+    pad_nil_more_literal_values("local a, b = 1, 2, 3"),
+);
+
+test_rule!(
+    check_assignment_arity_truncate,
+    CheckAssignmentArity::default().with_fix(ArityFix::Truncate),
+    truncate_local_assignment("local a, b = 1, 2, 3") => "local a, b = 1, 2",
+    truncate_assignment("a = 1, 'two'") => "a = 1",
+    truncate_table_value("local a = 1, {}") => "local a = 1",
+    truncate_swap("x, y = y, x, nil") => "x, y = y, x",
+);
+
+test_rule_without_effects!(
+    CheckAssignmentArity::default().with_fix(ArityFix::Truncate),
+    truncate_more_values_ending_with_varargs("local a = 1, ..."),
+    truncate_more_values_ending_with_call("local a = 1, f()"),
+    truncate_extra_call_value("local a = 1, f(), 2"),
+    truncate_extra_field_value("local a = 1, object.field"),
+    truncate_fewer_literal_values("local a, b, c = 1"),
+);
+
+fn process_with_error_severity(code: &str) -> Vec<String> {
+    let rule: Box<dyn Rule> =
+        Box::new(CheckAssignmentArity::default().with_severity(AritySeverity::Error));
+
+    process_rules([rule], None, code).err().unwrap_or_default()
+}
+
+#[test]
+fn error_severity_reports_fewer_literal_values() {
+    pretty_assertions::assert_eq!(
+        process_with_error_severity("local a, b = 1\nreturn a, b"),
+        vec![
+            "error processing `src/test.lua` (check_assignment_arity [#0]): local assignment \
+        (line 1) assigns 1 value to 2 variables: the last variables are assigned `nil`"
+                .to_owned()
+        ]
+    );
+}
+
+#[test]
+fn error_severity_reports_more_literal_values() {
+    pretty_assertions::assert_eq!(
+        process_with_error_severity("local a\na = 1, 2\nreturn a"),
+        vec![
+            "error processing `src/test.lua` (check_assignment_arity [#0]): assignment \
+        (line 2) assigns 2 values to 1 variable: the extra values are evaluated and discarded"
+                .to_owned()
+        ]
+    );
+}
+
+#[test]
+fn error_severity_ignores_values_ending_with_call() {
+    pretty_assertions::assert_eq!(
+        process_with_error_severity("local a, b, c = f()\nlocal d = 1, ...\nreturn a, b, c, d"),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'check_assignment_arity',
+        severity: 'error',
+        fix: 'pad_nil',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'check_assignment_arity'").unwrap();
+}
@@ -1,20 +1,31 @@
-use darklua_core::rules::{ComputeExpression, Rule};
+use darklua_core::generator::{DenseLuaGenerator, LuaGenerator};
+use darklua_core::process::{DeterministicFunction, LuaVersion, StringDecisionContext};
+use darklua_core::rules::{ComputeExpression, ContextBuilder, Rule};
+use darklua_core::{Parser, Resources};
 
 test_rule!(
     compute_expression,
     ComputeExpression::default(),
     binary_true_and_false("return true and false") => "return false",
-    binary_true_and_call("return true and call()") => "return call()",
+    binary_true_and_call("return true and call()") => "return (call())",
     binary_false_and_true("return false and true") => "return false",
     binary_false_and_variable("return false and var") => "return false",
     binary_false_and_call("return false and func()") => "return false",
     binary_true_or_call("return true or func()") => "return true",
     binary_true_or_function("return false or function() print('ok') end") => "return function() print('ok') end",
-    binary_false_or_call("return false or call()") => "return call()",
-    binary_nil_or_call("return nil or call()") => "return call()",
+    binary_false_or_call("return false or call()") => "return (call())",
+    binary_nil_or_call("return nil or call()") => "return (call())",
+    binary_true_and_call_as_last_argument("print(true and call())") => "print((call()))",
+    binary_true_and_call_as_first_argument("print(true and call(), 1)") => "print(call(), 1)",
+    binary_false_or_variable_arguments_in_table("return { false or ... }") => "return { (...) }",
+    binary_true_and_call_in_local_assign("local a = true and call()") => "local a = call()",
+    binary_true_and_call_in_multiple_local_assign("local a, b = true and call()")
+        => "local a, b = (call())",
     binary_number_equals("return 1 == 1") => "return true",
     binary_number_equals_in_different_notation("return 1 == 1.0") => "return true",
     binary_number_equals_in_different_exponent_notation("return 2.5e3 == 25e2") => "return true",
+    binary_plus_binary_and_hex_numbers("return 0b1010 + 0x10") => "return 26",
+    binary_plus_numbers_with_separators("return 1_000 + 0b1_0") => "return 1002",
     binary_table_or_call("return {} or func()") => "return {}",
     true_and_func_or_call("return true and function() end or call()") => "return function() end",
     nil_and_call_or_func("return nil and call() or function() end") => "return function() end",
@@ -37,18 +48,334 @@ test_rule!(
     preserve_negative_zero("return -0") => "return -0",
     addition_preserve_negative_zero("return -0 + -0") => "return -0",
     subtract_preserve_negative_zero("return -0 - 0") => "return -0",
+    decimal_addition("return 0.5 + 0.25") => "return 0.75",
+    power_at_integer_precision_limit("return 2^53") => "return 9007199254740992",
+    power_plus_one_above_integer_precision("return 2^53 + 1") => "return 9007199254740992 + 1",
 );
 
 test_rule_without_effects!(
     ComputeExpression::default(),
     if_expression_unknown_condition("return if condition then func() else func2()"),
+    multiplication_above_integer_precision("return 94906267 * 94906267"),
+    power_above_integer_precision("return 3 ^ 40"),
 );
 
+test_rule!(
+    compute_expression_near_type_and_tostring,
+    ComputeExpression::default(),
+    division_outside_tostring("print(10 / 2)") => "print(5)",
+    power_operand_outside_tostring("print(2 ^ 2 + x)") => "print(4 + x)",
+    true_and_variable_outside_type("return true and value") => "return value",
+    integer_addition_in_tostring("return tostring(1 + 2)") => "return tostring(3)",
+    float_division_in_tostring("return tostring(3 / 2)") => "return tostring(1.5)",
+    concatenation_in_tostring("return tostring('a' .. 'b')") => "return tostring('ab')",
+    integer_operand_in_tostring("return tostring((1 + 2) / x)") => "return tostring((3) / x)",
+    comparison_in_type("return type(1 == 2)") => "return type(false)",
+    division_in_shadowed_tostring("local function tostring(v) return v end return tostring(10 / 2)")
+        => "local function tostring(v) return v end return tostring(5)",
+    division_in_tostring_method("return value:tostring(10 / 2)") => "return value:tostring(5)",
+);
+
+test_rule_without_effects!(
+    ComputeExpression::default(),
+    division_in_tostring("return tostring(10 / 2)"),
+    power_in_tostring("return tostring(2 ^ 2)"),
+    power_operand_in_tostring("return tostring(2 ^ 2 + x)"),
+    parenthesized_division_in_tostring("return tostring((10 / 2))"),
+    integer_written_with_exponent_in_tostring("return tostring(10 * 100)"),
+    true_and_variable_in_type("return type(true and value)"),
+    false_or_call_in_type("return type(false or call())"),
+);
+
+test_rule_without_effects!(
+    ComputeExpression::default(),
+    type_of_math_constant_without_stdlib("return type(math.pi) == 'number'"),
+);
+
+test_rule!(
+    compute_expression_with_lua54_stdlib,
+    ComputeExpression::default().with_stdlib_version(LuaVersion::Lua54),
+    type_of_math_pi("return type(math.pi) == 'number'") => "return true",
+    type_of_math_huge("return type(math.huge)") => "return 'number'",
+    type_of_math_maxinteger("return type(math.maxinteger) ~= 'number'") => "return false",
+    type_of_os_time_call("return type(os.time()) == 'number'") => "return true",
+    type_of_os_clock_call("return type(os.clock())") => "return 'number'",
+    type_of_type_call("return type(type(value)) == 'string'") => "return true",
+    type_of_string_literal("return type('hello')") => "return 'string'",
+    type_of_table_argument("return type {}") => "return 'table'",
+    type_of_math_function("return type(math.floor) == 'function'") => "return true",
+);
+
+test_rule_without_effects!(
+    ComputeExpression::default().with_stdlib_version(LuaVersion::Lua54),
+    type_of_unknown_variable("return type(value) == 'number'"),
+    type_of_call_with_side_effects("return type(string.format('%s', value)) == 'string'"),
+    type_of_os_time_with_argument("return type(os.time(date)) == 'number'"),
+    shadowed_library("local math = { pi = 'pi' } return type(math.pi) == 'number'"),
+    shadowed_type_function(
+        "local function type() return 'number' end return type(math.pi) == 'number'"
+    ),
+);
+
+test_rule_without_effects!(
+    ComputeExpression::default().with_stdlib_version(LuaVersion::Lua51),
+    lua51_does_not_have_maxinteger("return type(math.maxinteger) == 'number'"),
+);
+
+test_rule_without_effects!(
+    ComputeExpression::default().with_stdlib_version(LuaVersion::Lua54),
+    type_of_math_pi_in_env_local_scope("local _ENV = {} return type(math.pi) == 'number'"),
+    type_of_math_pi_in_env_parameter_scope(
+        "local function f(_ENV) return type(math.pi) == 'number' end"
+    ),
+);
+
+test_rule!(
+    compute_expression_with_env_scope,
+    ComputeExpression::default().with_stdlib_version(LuaVersion::Lua54),
+    type_of_math_pi_outside_env_scope(
+        "local function f(_ENV) end return type(math.pi) == 'number'"
+    ) => "local function f(_ENV) end return true",
+);
+
+test_rule!(
+    compute_expression_assume_no_env_manipulation,
+    ComputeExpression::default()
+        .with_stdlib_version(LuaVersion::Lua54)
+        .with_assume_no_env_manipulation(true),
+    type_of_math_pi_in_env_local_scope("local _ENV = {} return type(math.pi) == 'number'")
+        => "local _ENV = {} return true",
+);
+
+test_rule!(
+    compute_table_length,
+    ComputeExpression::default(),
+    length_of_table_with_numbers("return #{1, 2, 3}") => "return 3",
+    length_of_empty_table("return #{}") => "return 0",
+    length_of_table_with_false_value("return #{'a', true, false}") => "return 3",
+    length_of_table_with_nested_tables("return #{{}, {}}") => "return 2",
+    length_of_table_with_function("return #{function() end}") => "return 1",
+    length_of_table_in_addition("return #{1, 2} + 1") => "return 3",
+);
+
+test_rule_without_effects!(
+    ComputeExpression::default(),
+    length_of_table_with_trailing_call("return #{f()}"),
+    length_of_table_with_value_and_trailing_call("return #{1, f()}"),
+    length_of_table_with_trailing_variable_arguments("return #{1, ...}"),
+    length_of_table_with_nil_hole("return #{1, nil, 3}"),
+    length_of_table_with_trailing_nil("return #{1, 2, nil}"),
+    length_of_table_with_index_entry("return #{1, [2] = 2}"),
+    length_of_table_with_field_entry("return #{1, a = 2}"),
+    length_of_table_with_unknown_value("return #{1, value}"),
+    length_of_variable("return #value"),
+);
+
+test_rule!(
+    compute_select_with_lua54_stdlib,
+    ComputeExpression::default().with_stdlib_version(LuaVersion::Lua54),
+    select_count("return select('#', a, b, c)") => "return 3",
+    select_count_without_values("return select('#')") => "return 0",
+    select_count_as_last_argument("print(select('#', a, b))") => "print(2)",
+    select_value_in_local_assign("local value = select(2, a, b, c)") => "local value = b",
+    select_value_in_assign("value = select(3, a, b, c)") => "value = c",
+    select_value_in_parentheses("return (select(2, a, b, c))") => "return (b)",
+    select_value_not_last_argument("print(select(1, a, b), c)") => "print(a, c)",
+    select_value_after_last_value("local value = select(4, a, b, c)") => "local value = nil",
+    select_value_in_addition("return select(2, 1, 2) + 1") => "return 3",
+    select_count_with_string_call("return select'#'") => "return 0",
+    type_of_string_call("return type'value'") => "return 'string'",
+    type_of_table_call("return type{}") => "return 'table'",
+);
+
+test_rule_without_effects!(
+    ComputeExpression::default().with_stdlib_version(LuaVersion::Lua54),
+    select_values_returned("return select(2, a, b, c)"),
+    select_values_as_last_argument("print(select(2, a, b, c))"),
+    select_values_as_last_table_entry("return {select(2, a, b, c)}"),
+    select_values_assigned_to_more_variables("local a, b = select(2, x, y, z)"),
+    select_values_in_generic_for("for _ in select(2, a, b, c) do end"),
+    select_count_with_call_argument("return select('#', a, f())"),
+    select_count_with_variable_arguments("return select('#', ...)"),
+    select_with_unknown_index("local value = select(n, a, b)"),
+    select_with_zero_index("local value = select(0, a, b)"),
+    select_with_decimal_index("local value = select(1.5, a, b)"),
+    select_with_side_effects("local value = select(1, a, b.c)"),
+    shadowed_select("local function select() end return select('#', a, b)"),
+    type_of_table_call_with_side_effects("return type{f()}"),
+    select_with_table_call("return select{1, 2}"),
+);
+
+test_rule_without_effects!(
+    ComputeExpression::default(),
+    select_count_without_stdlib("return select('#', a, b)"),
+);
+
+test_rule!(
+    compute_expression_with_luau_stdlib,
+    ComputeExpression::default().with_stdlib_version(LuaVersion::Luau),
+    type_of_math_pi_luau("return type(math.pi) == 'number'") => "return true",
+);
+
+fn compute_with_evaluated_functions() -> ComputeExpression {
+    DeterministicFunction::all().iter().fold(
+        ComputeExpression::default().with_stdlib_version(LuaVersion::Lua51),
+        |rule, function| rule.with_evaluated_function(*function),
+    )
+}
+
+test_rule!(
+    compute_evaluated_functions,
+    compute_with_evaluated_functions(),
+    math_floor("return math.floor(2.5)") => "return 2",
+    math_max("local value = math.max(1, 5, 3)") => "local value = 5",
+    math_fmod_in_addition("return math.fmod(-7, 3) + 1") => "return 0",
+    string_rep("return string.rep('-', 3)") => "return '---'",
+    string_format("print(string.format('%03d|%-4s|', 7, 'ab'))") => "print('007|ab  |')",
+    string_len_of_sub("return string.len(string.sub('hello', 2)) > 3") => "return true",
+    string_byte_not_last_value("return string.byte('abc', 1, 3), x") => "return 97, x",
+    string_byte_in_local_assign("local value = string.byte('abc', 2, 3)") => "local value = 98",
+    string_find_not_found("local index = string.find('abc', 'x', 1, true)")
+        => "local index = nil",
+    string_upper_as_last_argument("print(string.upper('abc'))") => "print('ABC')",
+    string_char_of_string_byte("return string.char(string.byte('abc', 1, 3))") => "return 'abc'",
+);
+
+test_rule_without_effects!(
+    compute_with_evaluated_functions(),
+    string_byte_returned("return string.byte('abc', 1, 3)"),
+    string_find_as_last_argument("print(string.find('abc', 'b', 1, true))"),
+    string_byte_without_values_returned("return string.byte('abc', 10)"),
+    math_random("return math.random(1, 1)"),
+    math_floor_of_variable("return math.floor(value)"),
+    math_floor_with_side_effects("return math.floor(value.field)"),
+    math_floor_with_call_argument("return math.floor(f())"),
+    math_floor_with_variable_arguments("return math.floor(...)"),
+    string_find_with_pattern("return (string.find('abc', 'b'))"),
+    string_format_float("return string.format('%g', 0.1)"),
+    string_upper_of_non_ascii("return string.upper('é')"),
+    string_method("return ('abc'):upper()"),
+    shadowed_math_library("local math = { floor = print } return math.floor(2.5)"),
+);
+
+test_rule_without_effects!(
+    ComputeExpression::default().with_stdlib_version(LuaVersion::Lua51),
+    math_floor_not_evaluated_by_default("return math.floor(2.5)"),
+    string_rep_not_evaluated_by_default("return string.rep('-', 3)"),
+);
+
+test_rule_without_effects!(
+    ComputeExpression::default().with_evaluated_function(DeterministicFunction::MathFloor),
+    math_floor_without_stdlib("return math.floor(2.5)"),
+);
+
+test_rule!(
+    compute_only_allowed_functions,
+    ComputeExpression::default()
+        .with_stdlib_version(LuaVersion::Lua54)
+        .with_evaluated_function(DeterministicFunction::MathFloor),
+    allowed_math_floor("return math.floor(2.5), math.ceil(2.5)")
+        => "return 2, math.ceil(2.5)",
+);
+
+test_rule!(
+    compute_expression_keep_shareable_strings,
+    ComputeExpression::default().with_keep_shareable_strings(true),
+    unique_strings("local a = 'hello' .. 'world'") => "local a = 'helloworld'",
+    string_held_by_local(
+        "local PREFIX = 'x' local a = 'x' .. 'y'"
+    ) => "local PREFIX = 'x' local a = 'x' .. 'y'",
+    shareable_string_with_number(
+        "local URL = 'https://example.com/' local a = 'https://example.com/' .. 1"
+    ) => "local URL = 'https://example.com/' local a = 'https://example.com/' .. 1",
+    only_non_shareable_concat_folded(
+        "local PREFIX = 'x' local a = 'x' .. ('y' .. 'z')"
+    ) => "local PREFIX = 'x' local a = 'x' .. ('yz')",
+    arithmetic_still_computed(
+        "local PREFIX = 'x' local a = 'x' .. 'y' local b = 1 + 2"
+    ) => "local PREFIX = 'x' local a = 'x' .. 'y' local b = 3",
+);
+
+const SHARED_PREFIX_CODE: &str = r#"local users = "https://api.example.com/v1/" .. "users"
+local posts = "https://api.example.com/v1/" .. "posts"
+local tags = "https://api.example.com/v1/" .. "tags"
+local likes = "https://api.example.com/v1/" .. "likes"
+return { users, posts, tags, likes }
+"#;
+
+/// Applies the rule and returns the estimated size of the generated code once the shareable
+/// strings are stored in locals.
+fn size_after_sharing_strings(rule: ComputeExpression) -> usize {
+    let mut block = Parser::default().parse(SHARED_PREFIX_CODE).unwrap();
+
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("src/test.lua", &resources, SHARED_PREFIX_CODE).build();
+
+    rule.process(&mut block, &context)
+        .expect("rule should succeed");
+
+    let mut generator = DenseLuaGenerator::new(usize::MAX);
+    generator.write_block(&block);
+    let size = generator.into_string().len();
+
+    size - StringDecisionContext::from_block(&mut block).estimated_total_savings()
+}
+
+#[test]
+fn keep_shareable_strings_produces_smaller_output_once_strings_are_shared() {
+    let naive_size = size_after_sharing_strings(ComputeExpression::default());
+    let coordinated_size =
+        size_after_sharing_strings(ComputeExpression::default().with_keep_shareable_strings(true));
+
+    assert!(
+        coordinated_size < naive_size,
+        "expected {} to be smaller than {}",
+        coordinated_size,
+        naive_size
+    );
+}
+
+#[test]
+fn deserialize_with_keep_shareable_strings() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'compute_expression',
+        keep_shareable_strings: true,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_with_evaluate_functions() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'compute_expression',
+        stdlib_version: 'lua51',
+        evaluate_functions: ['math.floor', 'string.format'],
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_with_function_that_cannot_be_evaluated() {
+    let result = json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'compute_expression',
+        evaluate_functions: ['tostring'],
+    }"#,
+    );
+
+    assert!(result.is_err());
+}
+
 #[test]
 fn deserialize_from_object_notation() {
     json5::from_str::<Box<dyn Rule>>(
         r#"{
         rule: 'compute_expression',
+        stdlib_version: 'lua54',
     }"#,
     )
     .unwrap();
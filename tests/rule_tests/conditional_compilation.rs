@@ -0,0 +1,106 @@
+use darklua_core::rules::{ConditionalCompilation, Rule};
+
+use super::process_rules;
+
+test_rule!(
+    conditional_compilation,
+    ConditionalCompilation::default()
+        .with_flag("Config.DEBUG", false)
+        .with_flag("FAST_PATH", true)
+        .with_root("Config"),
+    remove_debug_branch_and_unused_locals(
+        "local Config = require('./Config')\nlocal prefix = '[debug]'\nlocal function run() end\nif Config.DEBUG then print(prefix, run) end\nrun()"
+    ) => "local function run() end run()",
+    keep_else_branch_of_debug_flag(
+        "if Config.DEBUG then log('debug') else log('release') end"
+    ) => "do log('release') end",
+    remove_elseif_branch(
+        "if mode == 'a' then a() elseif Config.DEBUG then b() end"
+    ) => "if mode == 'a' then a() end",
+    select_implementation(
+        "local function fastImpl() end\nlocal function slowImpl() end\nlocal impl = FAST_PATH and fastImpl or slowImpl\nreturn impl"
+    ) => "local function fastImpl() end local impl = fastImpl return impl",
+    select_implementation_with_if_expression(
+        "local impl = if Config.DEBUG then checkedImpl else impl\nreturn impl"
+    ) => "local impl = impl return impl",
+    fold_comparison(
+        "if FAST_PATH == false then slow() end"
+    ) => "",
+);
+
+test_rule_without_effects!(
+    ConditionalCompilation::default()
+        .with_flag("Config.DEBUG", false)
+        .with_flag("FAST_PATH", true)
+        .with_root("Config"),
+    shadowed_global_root("local FAST_PATH = false if FAST_PATH then run() end"),
+    shadowed_module_root("local Config = { DEBUG = true } if Config.DEBUG then run() end"),
+    shadowed_root_parameter("local function f(Config) if Config.DEBUG then run() end end"),
+    root_without_roots_property_in_function(
+        "local function f() local FAST_PATH = getFlag() return FAST_PATH end"
+    ),
+    unrelated_field("if Config.VERBOSE then run() end"),
+);
+
+fn process_code(code: &str) -> Result<String, Vec<String>> {
+    let rule: Box<dyn Rule> = Box::new(
+        ConditionalCompilation::default()
+            .with_flag("Config.DEBUG", false)
+            .with_flag("FAST_PATH", true),
+    );
+
+    process_rules([rule], None, code)
+}
+
+#[test]
+fn assign_to_flag_errors() {
+    let errors = process_code("if Config.DEBUG then run() end\nConfig.DEBUG = true").unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert!(
+        errors[0].contains("cannot assign to compile-time constant `Config.DEBUG`"),
+        "unexpected error: {}",
+        errors[0]
+    );
+    assert!(
+        errors[0].contains("block.statements[1]"),
+        "missing location: {}",
+        errors[0]
+    );
+}
+
+#[test]
+fn assign_to_global_flag_errors() {
+    let errors = process_code("FAST_PATH = false").unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("cannot assign to compile-time constant `FAST_PATH`"));
+}
+
+#[test]
+fn define_function_at_flag_errors() {
+    let errors = process_code("function Config.DEBUG() end").unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("cannot assign to compile-time constant `Config.DEBUG`"));
+}
+
+#[test]
+fn assign_to_shadowed_flag_is_allowed() {
+    assert_eq!(
+        process_code("local FAST_PATH = true FAST_PATH = false return FAST_PATH").unwrap(),
+        "local FAST_PATH = true FAST_PATH = false return FAST_PATH"
+    );
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'conditional_compilation',
+        flags: { 'Config.DEBUG': false, FAST_PATH: true },
+        roots: ['Config'],
+    }"#,
+    )
+    .unwrap();
+}
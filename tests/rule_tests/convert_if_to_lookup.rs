@@ -0,0 +1,76 @@
+use darklua_core::rules::{ConvertIfToLookup, Rule};
+
+test_rule!(
+    convert_if_to_lookup,
+    ConvertIfToLookup::default().with_min_branches(3),
+    dispatch_calls("if cmd == 'a' then fa() elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end")
+        => "local __DARKLUA_LOOKUP = { a = function() fa() end, b = function() fb() end, c = function() fc() end } do local __DARKLUA_BRANCH = __DARKLUA_LOOKUP[cmd] if __DARKLUA_BRANCH then __DARKLUA_BRANCH() end end",
+    dispatch_with_reversed_operands("if 'a' == cmd then fa() elseif cmd == 'b' then fb() elseif (1 == cmd) then fc() end")
+        => "local __DARKLUA_LOOKUP = { a = function() fa() end, b = function() fb() end, [1] = function() fc() end } do local __DARKLUA_BRANCH = __DARKLUA_LOOKUP[cmd] if __DARKLUA_BRANCH then __DARKLUA_BRANCH() end end",
+    dispatch_with_else("if cmd == 'a' then fa() elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() else return fallback() end")
+        => "local __DARKLUA_LOOKUP = { a = function() fa() end, b = function() fb() end, c = function() fc() end } do local __DARKLUA_BRANCH = __DARKLUA_LOOKUP[cmd] if __DARKLUA_BRANCH then __DARKLUA_BRANCH() else return fallback() end end",
+    dispatch_returning_in_all_branches("local function run(cmd) if cmd == 1 then return a() elseif cmd == 2 then local v = b() return v, v elseif cmd == 3 then if c then return end return 0 end return nil end")
+        => "local __DARKLUA_LOOKUP = { [1] = function() return a() end, [2] = function() local v = b() return v, v end, [3] = function() if c then return end return 0 end } local function run(cmd) do local __DARKLUA_BRANCH = __DARKLUA_LOOKUP[cmd] if __DARKLUA_BRANCH then return __DARKLUA_BRANCH() end end return nil end",
+    dispatch_capturing_locals("local count = 0 if cmd == 'a' then count = count + 1 elseif cmd == 'b' then count = count - 1 elseif cmd == 'c' then count = 0 end")
+        => "local count = 0 do local __DARKLUA_BRANCH = ({ a = function() count = count + 1 end, b = function() count = count - 1 end, c = function() count = 0 end })[cmd] if __DARKLUA_BRANCH then __DARKLUA_BRANCH() end end",
+    dispatch_with_locals_declared_in_branches("local function run(cmd) if cmd == 'a' then local v = fa() print(v) elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end end")
+        => "local __DARKLUA_LOOKUP = { a = function() local v = fa() print(v) end, b = function() fb() end, c = function() fc() end } local function run(cmd) do local __DARKLUA_BRANCH = __DARKLUA_LOOKUP[cmd] if __DARKLUA_BRANCH then __DARKLUA_BRANCH() end end end",
+    dispatch_with_break_in_nested_loop("while true do if cmd == 'a' then for i = 1, 3 do break end elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end end")
+        => "local __DARKLUA_LOOKUP = { a = function() for i = 1, 3 do break end end, b = function() fb() end, c = function() fc() end } while true do do local __DARKLUA_BRANCH = __DARKLUA_LOOKUP[cmd] if __DARKLUA_BRANCH then __DARKLUA_BRANCH() end end end",
+    dispatch_with_nested_function_return("if cmd == 'a' then f(function() return 1 end) elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end")
+        => "local __DARKLUA_LOOKUP = { a = function() f(function() return 1 end) end, b = function() fb() end, c = function() fc() end } do local __DARKLUA_BRANCH = __DARKLUA_LOOKUP[cmd] if __DARKLUA_BRANCH then __DARKLUA_BRANCH() end end",
+    dispatch_with_shadowed_identifier("local __DARKLUA_BRANCH = 1 if cmd == 'a' then fa() elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end")
+        => "local __DARKLUA_LOOKUP = { a = function() fa() end, b = function() fb() end, c = function() fc() end } local __DARKLUA_BRANCH = 1 do local __DARKLUA_BRANCH0 = __DARKLUA_LOOKUP[cmd] if __DARKLUA_BRANCH0 then __DARKLUA_BRANCH0() end end",
+    lookup_assignment("if cmd == 'a' then x = 1 elseif cmd == 'b' then x = 2 elseif cmd == 'c' then x = 'three' else x = nil end")
+        => "local __DARKLUA_LOOKUP = { a = 1, b = 2, c = 'three' } x = __DARKLUA_LOOKUP[cmd] or nil",
+    two_lookup_assignments("if cmd == 'a' then x = 1 elseif cmd == 'b' then x = 2 elseif cmd == 'c' then x = 3 else x = 0 end if key == 1 then y = true elseif key == 2 then y = 'b' elseif key == 3 then y = 3 else y = false end")
+        => "local __DARKLUA_LOOKUP = { a = 1, b = 2, c = 3 } local __DARKLUA_LOOKUP0 = { [1] = true, [2] = 'b', [3] = 3 } x = __DARKLUA_LOOKUP[cmd] or 0 y = __DARKLUA_LOOKUP0[key] or false",
+    assignment_of_false_uses_dispatch("if cmd == 'a' then x = 1 elseif cmd == 'b' then x = false elseif cmd == 'c' then x = 3 else x = 0 end")
+        => "local __DARKLUA_LOOKUP = { a = function() x = 1 end, b = function() x = false end, c = function() x = 3 end } do local __DARKLUA_BRANCH = __DARKLUA_LOOKUP[cmd] if __DARKLUA_BRANCH then __DARKLUA_BRANCH() else x = 0 end end",
+    assignment_without_else_uses_dispatch("if cmd == 'a' then x = 1 elseif cmd == 'b' then x = 2 elseif cmd == 'c' then x = 3 end")
+        => "local __DARKLUA_LOOKUP = { a = function() x = 1 end, b = function() x = 2 end, c = function() x = 3 end } do local __DARKLUA_BRANCH = __DARKLUA_LOOKUP[cmd] if __DARKLUA_BRANCH then __DARKLUA_BRANCH() end end",
+    nested_dispatch("if cmd == 'a' then if sub == 1 then x() elseif sub == 2 then y() elseif sub == 3 then z() end elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end")
+        => "local __DARKLUA_LOOKUP = { [1] = function() x() end, [2] = function() y() end, [3] = function() z() end } local __DARKLUA_LOOKUP_1 = { a = function() do local __DARKLUA_BRANCH = __DARKLUA_LOOKUP[sub] if __DARKLUA_BRANCH then __DARKLUA_BRANCH() end end end, b = function() fb() end, c = function() fc() end } do local __DARKLUA_BRANCH = __DARKLUA_LOOKUP_1[cmd] if __DARKLUA_BRANCH then __DARKLUA_BRANCH() end end",
+);
+
+test_rule_without_effects!(
+    ConvertIfToLookup::default().with_min_branches(3),
+    not_enough_branches("if cmd == 'a' then fa() elseif cmd == 'b' then fb() end"),
+    different_identifiers("if cmd == 'a' then fa() elseif other == 'b' then fb() elseif cmd == 'c' then fc() end"),
+    non_literal_comparison("if cmd == 'a' then fa() elseif cmd == b then fb() elseif cmd == 'c' then fc() end"),
+    not_equal_comparison("if cmd == 'a' then fa() elseif cmd ~= 'b' then fb() elseif cmd == 'c' then fc() end"),
+    duplicated_key("if cmd == 'a' then fa() elseif cmd == 'b' then fb() elseif cmd == 'a' then fc() end"),
+    duplicated_number_key("if cmd == 1 then fa() elseif cmd == 2 then fb() elseif cmd == 1.0 then fc() end"),
+    field_comparison("if a.cmd == 'a' then fa() elseif a.cmd == 'b' then fb() elseif a.cmd == 'c' then fc() end"),
+    break_in_branch("while true do if cmd == 'a' then break elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end end"),
+    continue_in_branch("while true do if cmd == 'a' then fa() elseif cmd == 'b' then fb() elseif cmd == 'c' then if x then continue end end end"),
+    return_in_some_branches("local function f() if cmd == 'a' then return 1 elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end end"),
+    conditional_return_in_branch("local function f() if cmd == 'a' then if x then return end fa() elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end end"),
+    capturing_locals_in_loop("local count = 0 for _, cmd in ipairs(commands) do if cmd == 'a' then count = count + 1 elseif cmd == 'b' then count = count - 1 elseif cmd == 'c' then count = 0 end end"),
+    capturing_locals_in_while_loop("local count = 0 while cmd do if cmd == 'a' then count = count + 1 elseif cmd == 'b' then count = count - 1 elseif cmd == 'c' then count = 0 end cmd = next() end"),
+    capturing_parameter_in_function("local function run(cmd, value) if cmd == 'a' then fa(value) elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end end"),
+    capturing_self_in_method("function Object:run(cmd) if cmd == 'a' then self:fa() elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end end"),
+    capturing_outer_locals_in_function_expression("local count = 0 return function(cmd) if cmd == 'a' then count = count + 1 elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end end"),
+    variable_arguments_in_branch("local function f(...) if cmd == 'a' then fa(...) elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() end end"),
+);
+
+test_rule_without_effects!(
+    ConvertIfToLookup::default(),
+    default_min_branches("if cmd == 'a' then fa() elseif cmd == 'b' then fb() elseif cmd == 'c' then fc() elseif cmd == 'd' then fd() end"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'convert_if_to_lookup',
+        min_branches: 3,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'convert_if_to_lookup'").unwrap();
+}
@@ -1,5 +1,26 @@
+use darklua_core::process::EnvironmentTarget;
 use darklua_core::rules::{ConvertIndexToField, Rule};
 
+use super::process_rules;
+
+fn process_code(rule: ConvertIndexToField, target: EnvironmentTarget, code: &str) -> String {
+    process_rules([Box::new(rule) as Box<dyn Rule>], Some(target), code).unwrap()
+}
+
+macro_rules! test_targets {
+    ($rule:expr, $($name:ident ($target:ident, $input:literal) => $output:literal),* $(,)?) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!(
+                    process_code($rule, EnvironmentTarget::$target, $input),
+                    $output
+                );
+            }
+        )*
+    };
+}
+
 test_rule!(
     convert_index_to_field,
     ConvertIndexToField::default(),
@@ -10,6 +31,8 @@ test_rule!(
     multiple_assign_to_a_valid_identifier("var[\"field\"], var['key'], var.prop = call()") => "var.field, var.key, var.prop = call()",
     call_function("object['process'](true)") => "object.process(true)",
     call_method("object['sub']:method(...)") => "object.sub:method(...)",
+    key_starts_with_underscore("return var['_private']") => "return var._private",
+    assign_to_key_starts_with_underscore("var['_G'] = {}") => "var._G = {}",
     // table entries
     table_key_is_valid_identifier("return { [\"a\"] = true }") => "return { a = true }",
     table_key_is_valid_identifier_with_number("return { [\"key1\"] = true }") => "return { key1 = true }",
@@ -27,6 +50,25 @@ test_rule_without_effects!(
     key_starts_with_dollar_sign("return var['$$ok']"),
     key_has_dollar_sign("return var['field$end']"),
     key_is_do_keyword("return var['do']"),
+    key_is_and_keyword("return var['and']"),
+    key_is_or_keyword("return var['or']"),
+    key_is_not_keyword("return var['not']"),
+    key_is_if_keyword("return var['if']"),
+    key_is_then_keyword("return var['then']"),
+    key_is_end_keyword("return var['end']"),
+    key_is_for_keyword("return var['for']"),
+    key_is_while_keyword("return var['while']"),
+    key_is_repeat_keyword("return var['repeat']"),
+    key_is_until_keyword("return var['until']"),
+    key_is_return_keyword("return var['return']"),
+    key_is_break_keyword("return var['break']"),
+    key_is_local_keyword("return var['local']"),
+    key_is_function_keyword("return var['function']"),
+    key_is_nil_keyword("return var['nil']"),
+    key_is_true_keyword("return var['true']"),
+    key_is_false_keyword("return var['false']"),
+    assign_to_keyword_key("var['local'] = true"),
+    call_method_on_keyword_key("object['end']:method()"),
     key_starts_with_number("return var['1field']"),
     call_function("object['function'](true)"),
     key_is_a_table("return var[{}]"),
@@ -36,6 +78,41 @@ test_rule_without_effects!(
     table_key_with_interogation_point("return { [\"key?\"] = true }"),
     table_key_is_repeat_keyword("return { ['repeat'] = true }"),
     function_table_args_key_is_while_keyword("return call { ['while'] = true }"),
+    // without a target, the key must be valid for every target
+    key_is_goto_keyword("return var['goto']"),
+    table_key_is_goto_keyword("return { ['goto'] = true }"),
+    key_is_unicode("return var['café']"),
+    key_has_nul_byte("return var['a\\0b']"),
+    key_is_nul_byte("return var['\\0']"),
+    key_has_invalid_byte("return var['a\\255']"),
+);
+
+test_targets!(
+    ConvertIndexToField::default(),
+    lua51_converts_goto_key(Lua51, "return var['goto']") => "return var.goto",
+    lua51_converts_goto_table_key(Lua51, "return { ['goto'] = true }") => "return { goto=true }",
+    lua53_keeps_goto_key(Lua53, "return var['goto']") => "return var['goto']",
+    lua54_keeps_goto_key(Lua54, "return var['goto']") => "return var['goto']",
+    lua54_keeps_goto_table_key(Lua54, "return { ['goto'] = true }") => "return { ['goto'] = true }",
+    luau_converts_goto_key(Luau, "return var['goto']") => "return var.goto",
+    roblox_converts_goto_key(Roblox, "return var['goto']") => "return var.goto",
+    luau_converts_continue_key(Luau, "return var['continue']") => "return var.continue",
+    luau_converts_type_key(Luau, "return var['type']") => "return var.type",
+    luau_converts_export_key(Luau, "return var['export']") => "return var.export",
+    lua54_converts_continue_key(Lua54, "return var['continue']") => "return var.continue",
+    luau_keeps_end_key(Luau, "return var['end']") => "return var['end']",
+    lua51_keeps_unicode_key(Lua51, "return var['café']") => "return var['café']",
+    luau_keeps_unicode_key(Luau, "return var['café']") => "return var['café']",
+    luau_keeps_nul_byte_key(Luau, "return var['a\\0b']") => "return var['a\\0b']",
+);
+
+test_targets!(
+    ConvertIndexToField::default().with_conservative_ascii(true),
+    conservative_lua51_keeps_goto_key(Lua51, "return var['goto']") => "return var['goto']",
+    conservative_luau_keeps_goto_key(Luau, "return var['goto']") => "return var['goto']",
+    conservative_luau_converts_type_key(Luau, "return var['type']") => "return var.type",
+    conservative_luau_keeps_unicode_key(Luau, "return var['café']") => "return var['café']",
+    conservative_lua51_converts_identifier(Lua51, "return var['field']") => "return var.field",
 );
 
 #[test]
@@ -43,6 +120,7 @@ fn deserialize_from_object_notation() {
     json5::from_str::<Box<dyn Rule>>(
         r#"{
         rule: 'convert_index_to_field',
+        conservative_ascii: true,
     }"#,
     )
     .unwrap();
@@ -0,0 +1,60 @@
+use darklua_core::rules::{ConvertNumericForToWhile, Rule};
+
+test_rule!(
+    convert_numeric_for_to_while,
+    ConvertNumericForToWhile::default(),
+    empty_loop("for i = 1, 10 do end")
+        => "do local __DARKLUA_FOR_INDEX = 1 while __DARKLUA_FOR_INDEX <= 10 do local i = __DARKLUA_FOR_INDEX __DARKLUA_FOR_INDEX = __DARKLUA_FOR_INDEX + 1 end end",
+    loop_with_call("for i = 1, 10 do print(i) end")
+        => "do local __DARKLUA_FOR_INDEX = 1 while __DARKLUA_FOR_INDEX <= 10 do local i = __DARKLUA_FOR_INDEX print(i) __DARKLUA_FOR_INDEX = __DARKLUA_FOR_INDEX + 1 end end",
+    loop_with_negative_step("for i = 10, 1, -2 do print(i) end")
+        => "do local __DARKLUA_FOR_INDEX = 10 while __DARKLUA_FOR_INDEX >= 1 do local i = __DARKLUA_FOR_INDEX print(i) __DARKLUA_FOR_INDEX = __DARKLUA_FOR_INDEX + -2 end end",
+    loop_with_variable_limit("for i = 1, #list do print(list[i]) end")
+        => "do local __DARKLUA_FOR_INDEX, __DARKLUA_FOR_LIMIT = 1, #list while __DARKLUA_FOR_INDEX <= __DARKLUA_FOR_LIMIT do local i = __DARKLUA_FOR_INDEX print(list[i]) __DARKLUA_FOR_INDEX = __DARKLUA_FOR_INDEX + 1 end end",
+    loop_with_variable_step("for i = a, b, step do print(i) end")
+        => "do local __DARKLUA_FOR_INDEX, __DARKLUA_FOR_LIMIT, __DARKLUA_FOR_STEP = a, b, step while __DARKLUA_FOR_STEP > 0 and __DARKLUA_FOR_INDEX <= __DARKLUA_FOR_LIMIT or __DARKLUA_FOR_STEP <= 0 and __DARKLUA_FOR_INDEX >= __DARKLUA_FOR_LIMIT do local i = __DARKLUA_FOR_INDEX print(i) __DARKLUA_FOR_INDEX = __DARKLUA_FOR_INDEX + __DARKLUA_FOR_STEP end end",
+    loop_with_break("for i = 1, 10 do if i > n then break end print(i) end")
+        => "do local __DARKLUA_FOR_INDEX = 1 while __DARKLUA_FOR_INDEX <= 10 do local i = __DARKLUA_FOR_INDEX if i > n then break end print(i) __DARKLUA_FOR_INDEX = __DARKLUA_FOR_INDEX + 1 end end",
+    loop_ending_with_break("for i = 1, 10 do print(i) break end")
+        => "do local __DARKLUA_FOR_INDEX = 1 while __DARKLUA_FOR_INDEX <= 10 do local i = __DARKLUA_FOR_INDEX print(i) break end end",
+    loop_ending_with_return("for i = 1, 10 do return i end")
+        => "do local __DARKLUA_FOR_INDEX = 1 while __DARKLUA_FOR_INDEX <= 10 do local i = __DARKLUA_FOR_INDEX return i end end",
+    nested_loops("for i = 1, 2 do for j = 1, 3 do end end")
+        => "do local __DARKLUA_FOR_INDEX = 1 while __DARKLUA_FOR_INDEX <= 2 do local i = __DARKLUA_FOR_INDEX do local __DARKLUA_FOR_INDEX0 = 1 while __DARKLUA_FOR_INDEX0 <= 3 do local j = __DARKLUA_FOR_INDEX0 __DARKLUA_FOR_INDEX0 = __DARKLUA_FOR_INDEX0 + 1 end end __DARKLUA_FOR_INDEX = __DARKLUA_FOR_INDEX + 1 end end",
+    loop_with_continue_in_nested_loop("for i = 1, 10 do while true do continue end end")
+        => "do local __DARKLUA_FOR_INDEX = 1 while __DARKLUA_FOR_INDEX <= 10 do local i = __DARKLUA_FOR_INDEX while true do continue end __DARKLUA_FOR_INDEX = __DARKLUA_FOR_INDEX + 1 end end",
+);
+
+test_rule_without_effects!(
+    ConvertNumericForToWhile::default(),
+    loop_with_continue("for i = 1, 10 do if i % 2 == 0 then continue end print(i) end"),
+);
+
+test_rule!(
+    convert_numeric_for_to_while_without_preserve_break,
+    ConvertNumericForToWhile::default().with_preserve_break(false),
+    loop_without_break("for i = 1, 10 do end")
+        => "do local __DARKLUA_FOR_INDEX = 1 while __DARKLUA_FOR_INDEX <= 10 do local i = __DARKLUA_FOR_INDEX __DARKLUA_FOR_INDEX = __DARKLUA_FOR_INDEX + 1 end end",
+);
+
+test_rule_without_effects!(
+    ConvertNumericForToWhile::default().with_preserve_break(false),
+    loop_with_break_not_preserved("for i = 1, 10 do if i > n then break end print(i) end"),
+    loop_ending_with_break_not_preserved("for i = 1, 10 do break end"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'convert_numeric_for_to_while',
+        preserve_break: false,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'convert_numeric_for_to_while'").unwrap();
+}
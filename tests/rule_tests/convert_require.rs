@@ -176,6 +176,34 @@ fn convert_sibling_module_from_init_module() {
     );
 }
 
+#[test]
+fn convert_sibling_module_required_with_string_call() {
+    let resources = memory_resources!(
+        "src/init.lua" => "local value = require'./value.lua'",
+        "src/value.lua" => "return nil",
+        ".darklua.json" => CONVERT_PATH_TO_ROBLOX_DEFAULT_CONFIG,
+    );
+    expect_file_process(
+        &resources,
+        "src/init.lua",
+        "local value = require(script:FindFirstChild('value'))",
+    );
+}
+
+#[test]
+fn keep_require_with_table_call() {
+    let resources = memory_resources!(
+        "src/init.lua" => "local value = require{'./value.lua'}",
+        "src/value.lua" => "return nil",
+        ".darklua.json" => CONVERT_PATH_TO_ROBLOX_DEFAULT_CONFIG,
+    );
+    expect_file_process(
+        &resources,
+        "src/init.lua",
+        "local value = require{'./value.lua'}",
+    );
+}
+
 #[test]
 fn convert_sibling_init_module_from_init_module() {
     let resources = memory_resources!(
@@ -421,3 +449,194 @@ mod sourcemap {
         );
     }
 }
+
+mod require_styles {
+    use darklua_core::{generator::LuaGenerator, rules::ContextBuilder, Parser};
+
+    use super::*;
+
+    const SHARED_STRING_STYLE: &str = "{ style: 'string', prefix: 'Shared/' }";
+    const SHARED_ROBLOX_STYLE: &str =
+        r#"{ style: 'roblox', root: 'game:GetService("ReplicatedStorage").Shared' }"#;
+    const RELATIVE_PATH_STYLE: &str = "{ style: 'relative-path' }";
+    const SCRIPT_ROBLOX_STYLE: &str = "{ style: 'roblox' }";
+
+    fn style_rule(current: &str, target: &str, module_map: &str) -> Box<dyn Rule> {
+        json5::from_str::<Box<dyn Rule>>(&format!(
+            "{{ rule: 'convert_require', current: {}, target: {}, module_map: {} }}",
+            current, target, module_map
+        ))
+        .unwrap()
+    }
+
+    test_rule!(
+        convert_string_to_roblox,
+        json5::from_str::<Box<dyn Rule>>(
+            r#"{
+                rule: 'convert_require',
+                current: { style: 'string', prefix: 'Shared/' },
+                target: { style: 'roblox', root: 'game:GetService("ReplicatedStorage").Shared' },
+            }"#
+        ).unwrap(),
+        module("local Config = require('Shared/Config')")
+            => "local Config = require(game:GetService('ReplicatedStorage').Shared.Config)",
+        nested_module("local Signal = require('Shared/Util/Signal')")
+            => "local Signal = require(game:GetService('ReplicatedStorage').Shared.Util.Signal)",
+        module_name_with_spaces("local Module = require('Shared/a module')")
+            => "local Module = require(game:GetService('ReplicatedStorage').Shared['a module'])",
+        module_name_is_keyword("local Module = require('Shared/end')")
+            => "local Module = require(game:GetService('ReplicatedStorage').Shared['end'])",
+        require_in_function("local function load() return require('Shared/Config') end")
+            => "local function load() return require(game:GetService('ReplicatedStorage').Shared.Config) end",
+        dynamic_argument_is_unchanged("local Module = require('Shared/' .. name)")
+            => "local Module = require('Shared/' .. name)",
+        interpolated_argument_is_unchanged("local Module = require(`Shared/{name}`)")
+            => "local Module = require(`Shared/{name}`)",
+        argument_without_prefix_is_unchanged("local Module = require('Client/Controller')")
+            => "local Module = require('Client/Controller')",
+        instance_argument_is_unchanged("local Module = require(script.Parent.Module)")
+            => "local Module = require(script.Parent.Module)",
+    );
+
+    test_rule!(
+        convert_roblox_to_string,
+        json5::from_str::<Box<dyn Rule>>(
+            r#"{
+                rule: 'convert_require',
+                current: { style: 'roblox', root: 'game:GetService("ReplicatedStorage").Shared' },
+                target: { style: 'string', prefix: 'Shared/' },
+            }"#
+        ).unwrap(),
+        module("local Config = require(game:GetService('ReplicatedStorage').Shared.Config)")
+            => "local Config = require('Shared/Config')",
+        nested_module("local Signal = require(game:GetService('ReplicatedStorage').Shared.Util.Signal)")
+            => "local Signal = require('Shared/Util/Signal')",
+        module_with_index("local Module = require(game:GetService('ReplicatedStorage').Shared['a module'])")
+            => "local Module = require('Shared/a module')",
+        module_with_find_first_child("local Module = require(game:GetService('ReplicatedStorage').Shared:FindFirstChild('Util'):WaitForChild('Signal'))")
+            => "local Module = require('Shared/Util/Signal')",
+        dynamic_index_is_unchanged("local Module = require(game:GetService('ReplicatedStorage').Shared[name])")
+            => "local Module = require(game:GetService('ReplicatedStorage').Shared[name])",
+        other_service_is_unchanged("local Module = require(game:GetService('ServerStorage').Shared.Config)")
+            => "local Module = require(game:GetService('ServerStorage').Shared.Config)",
+        string_argument_is_unchanged("local Module = require('Shared/Config')")
+            => "local Module = require('Shared/Config')",
+    );
+
+    test_rule!(
+        convert_relative_path_to_roblox,
+        style_rule(RELATIVE_PATH_STYLE, SCRIPT_ROBLOX_STYLE, "{}"),
+        sibling_module("local Module = require('./Module')")
+            => "local Module = require(script.Parent.Module)",
+        nested_sibling_module("local Module = require('./folder/Module')")
+            => "local Module = require(script.Parent.folder.Module)",
+        parent_module("local Module = require('../Module')")
+            => "local Module = require(script.Parent.Parent.Module)",
+        absolute_path_is_unchanged("local Module = require('Shared/Module')")
+            => "local Module = require('Shared/Module')",
+    );
+
+    test_rule!(
+        convert_roblox_to_relative_path,
+        style_rule(SCRIPT_ROBLOX_STYLE, RELATIVE_PATH_STYLE, "{}"),
+        sibling_module("local Module = require(script.Parent.Module)")
+            => "local Module = require('./Module')",
+        parent_module("local Module = require(script.Parent.Parent.Module)")
+            => "local Module = require('../Module')",
+        root_module_is_unchanged("local Module = require(game.ReplicatedStorage.Module)")
+            => "local Module = require(game.ReplicatedStorage.Module)",
+    );
+
+    test_rule!(
+        convert_string_with_custom_separator,
+        json5::from_str::<Box<dyn Rule>>(
+            r#"{
+                rule: 'convert_require',
+                current: { style: 'string', prefix: 'Shared.', separator: '.' },
+                target: { style: 'string', prefix: '@shared/' },
+            }"#
+        ).unwrap(),
+        nested_module("local Signal = require('Shared.Util.Signal')")
+            => "local Signal = require('@shared/Util/Signal')",
+        slash_separated_argument_is_unchanged("local Signal = require('Shared/Util/Signal')")
+            => "local Signal = require('Shared/Util/Signal')",
+    );
+
+    test_rule!(
+        convert_string_to_roblox_with_module_map,
+        style_rule(
+            SHARED_STRING_STYLE,
+            SHARED_ROBLOX_STYLE,
+            r#"{ 'Shared/legacy/OldUtil': 'game:GetService("ReplicatedStorage").Common.Util' }"#,
+        ),
+        mapped_module("local Util = require('Shared/legacy/OldUtil')")
+            => "local Util = require(game:GetService('ReplicatedStorage').Common.Util)",
+        other_module("local Config = require('Shared/Config')")
+            => "local Config = require(game:GetService('ReplicatedStorage').Shared.Config)",
+    );
+
+    fn convert(code: &str, rule: &dyn Rule) -> String {
+        let mut block = Parser::default()
+            .parse(code)
+            .unwrap_or_else(|error| panic!("could not parse content: {:?}\n{}", error, code));
+
+        let resources = Resources::from_memory();
+        let context = ContextBuilder::new("src/module.lua", &resources, code).build();
+        rule.process(&mut block, &context)
+            .expect("rule should succeed");
+
+        let mut generator = darklua_core::generator::DenseLuaGenerator::default();
+        generator.write_block(&block);
+        generator.into_string()
+    }
+
+    fn assert_round_trip(current: &str, target: &str, module_map: &str, reversed_map: &str) {
+        let forward = style_rule(current, target, module_map);
+        let backward = style_rule(target, current, reversed_map);
+
+        for (file_name, code) in [
+            (
+                "relative_modules.lua",
+                include_str!("../test_cases/require_styles/relative_modules.lua"),
+            ),
+            (
+                "shared_modules.lua",
+                include_str!("../test_cases/require_styles/shared_modules.lua"),
+            ),
+            (
+                "special_names.lua",
+                include_str!("../test_cases/require_styles/special_names.lua"),
+            ),
+        ] {
+            let mut generator = darklua_core::generator::DenseLuaGenerator::default();
+            generator.write_block(&Parser::default().parse(code).unwrap());
+            let original = generator.into_string();
+
+            let converted = convert(code, &*forward);
+            let round_trip = convert(&converted, &*backward);
+
+            pretty_assertions::assert_eq!(
+                original,
+                round_trip,
+                "\n{} was converted to:\n{}",
+                file_name,
+                converted
+            );
+        }
+    }
+
+    #[test]
+    fn round_trip_string_to_roblox() {
+        assert_round_trip(
+            SHARED_STRING_STYLE,
+            SHARED_ROBLOX_STYLE,
+            r#"{ 'Shared/legacy/OldUtil': 'game:GetService("ReplicatedStorage").Common.Util' }"#,
+            r#"{ 'game:GetService("ReplicatedStorage").Common.Util': 'Shared/legacy/OldUtil' }"#,
+        );
+    }
+
+    #[test]
+    fn round_trip_relative_path_to_roblox() {
+        assert_round_trip(RELATIVE_PATH_STYLE, SCRIPT_ROBLOX_STYLE, "{}", "{}");
+    }
+}
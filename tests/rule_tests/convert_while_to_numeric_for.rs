@@ -0,0 +1,64 @@
+use darklua_core::rules::{ConvertWhileToNumericFor, Rule};
+
+test_rule!(
+    convert_while_to_numeric_for,
+    ConvertWhileToNumericFor::default(),
+    empty_loop("local i = 1 while i <= 10 do i = i + 1 end") => "for i = 1, 10 do end",
+    loop_with_call("local i = 1 while i <= 10 do print(i) i = i + 1 end")
+        => "for i = 1, 10 do print(i) end",
+    loop_with_compound_increment("local i = 1 while i <= 10 do print(i) i += 1 end")
+        => "for i = 1, 10 do print(i) end",
+    loop_with_step("local i = 0 while i <= 10 do print(i) i = i + 2 end")
+        => "for i = 0, 10, 2 do print(i) end",
+    loop_with_decrement("local i = 10 while i >= 1 do print(i) i = i - 1 end")
+        => "for i = 10, 1, -1 do print(i) end",
+    loop_with_break("local i = 1 while i <= 10 do if i > n then break end i = i + 1 end")
+        => "for i = 1, 10 do if i > n then break end end",
+    loop_with_statement_after("local i = 1 while i <= 10 do i = i + 1 end print('done')")
+        => "for i = 1, 10 do end print('done')",
+    loop_with_nested_loop("local i = 1 while i <= 3 do local j = 1 while j <= 3 do print(i, j) j = j + 1 end i = i + 1 end")
+        => "for i = 1, 3 do for j = 1, 3 do print(i, j) end end",
+);
+
+test_rule_without_effects!(
+    ConvertWhileToNumericFor::default(),
+    loop_without_declaration("while i <= 10 do i = i + 1 end"),
+    loop_with_statement_before("local i = 1 print(i) while i <= 10 do i = i + 1 end"),
+    loop_with_strict_comparison("local i = 1 while i < 10 do i = i + 1 end"),
+    loop_with_variable_limit("local i = 1 while i <= n do i = i + 1 end"),
+    loop_with_variable_step("local i = 1 while i <= 10 do i = i + step end"),
+    loop_with_wrong_step_direction("local i = 1 while i <= 10 do i = i - 1 end"),
+    loop_with_increment_not_last("local i = 1 while i <= 10 do i = i + 1 print(i) end"),
+    loop_ending_with_break("local i = 1 while i <= 10 do i = i + 1 break end"),
+    loop_with_continue("local i = 1 while i <= 10 do i = i + 1 if i > 5 then continue end end"),
+    loop_variable_used_after("local i = 1 while i <= 10 do i = i + 1 end return i"),
+    loop_variable_assigned("local i = 1 while i <= 10 do if a then i = 5 end i = i + 1 end"),
+    loop_variable_captured(
+        "local i = 1 while i <= 10 do callbacks[i] = function() return i end i = i + 1 end"
+    ),
+    loop_variable_shadowed("local i = 1 while i <= 10 do local i = 0 i = i + 1 end"),
+    loop_with_multiple_declarations("local i, j = 1, 2 while i <= 10 do i = i + 1 end"),
+);
+
+test_rule_without_effects!(
+    ConvertWhileToNumericFor::default().with_preserve_break(false),
+    loop_with_break_not_preserved(
+        "local i = 1 while i <= 10 do if i > n then break end i = i + 1 end"
+    ),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'convert_while_to_numeric_for',
+        preserve_break: false,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'convert_while_to_numeric_for'").unwrap();
+}
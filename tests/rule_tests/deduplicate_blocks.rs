@@ -0,0 +1,56 @@
+use darklua_core::rules::{DeduplicateBlocks, Rule};
+
+test_rule!(
+    deduplicate_blocks,
+    DeduplicateBlocks::default(),
+    sequences_with_different_local_names(
+        "local function f(a) local x = a + 1 print('the value of x is', x) print('twice the value of x is', x * 2) print('half of the value of x is', x / 2) end local function g(b) local y = b + 1 print('the value of x is', y) print('twice the value of x is', y * 2) print('half of the value of x is', y / 2) end"
+    ) => "local function __DARKLUA_BLOCK(a) local x = a + 1 print('the value of x is', x) print('twice the value of x is', x * 2) print('half of the value of x is', x / 2) end local function f(a) __DARKLUA_BLOCK(a) end local function g(b) __DARKLUA_BLOCK(b) end",
+    sequences_in_the_same_block(
+        "print('the first message of the sequence') print('the second message of the sequence') print('the third message of the sequence') wait(1) print('the first message of the sequence') print('the second message of the sequence') print('the third message of the sequence')"
+    ) => "local function __DARKLUA_BLOCK() print('the first message of the sequence') print('the second message of the sequence') print('the third message of the sequence') end __DARKLUA_BLOCK() wait(1) __DARKLUA_BLOCK()",
+    sequences_with_shadowed_function_name(
+        "local __DARKLUA_BLOCK = 1 print('the first message of the sequence') print('the second message of the sequence') print('the third message of the sequence') wait(1) print('the first message of the sequence') print('the second message of the sequence') print('the third message of the sequence')"
+    ) => "local function __DARKLUA_BLOCK0() print('the first message of the sequence') print('the second message of the sequence') print('the third message of the sequence') end local __DARKLUA_BLOCK = 1 __DARKLUA_BLOCK0() wait(1) __DARKLUA_BLOCK0()",
+);
+
+test_rule_without_effects!(
+    DeduplicateBlocks::default(),
+    sequences_containing_a_return(
+        "local function f(a) print('the value of a is', a) if a > 10 then return end print('twice the value of a is', a * 2) print('half of the value of a is', a / 2) end local function g(b) print('the value of a is', b) if b > 10 then return end print('twice the value of a is', b * 2) print('half of the value of a is', b / 2) end"
+    ),
+    sequences_containing_a_break(
+        "while true do print('the first message of the sequence') if done() then break end print('the third message of the sequence') end while true do print('the first message of the sequence') if done() then break end print('the third message of the sequence') end"
+    ),
+    sequences_smaller_than_a_function(
+        "local function f() a() b() c() end local function g() a() b() c() end"
+    ),
+    sequences_declaring_a_local_used_after(
+        "local function f() local x = compute('some long argument value') print('the value of x is', x) print('twice the value of x is', x * 2) return x end local function g() local y = compute('some long argument value') print('the value of x is', y) print('twice the value of x is', y * 2) return y end"
+    ),
+    sequences_assigning_a_captured_local(
+        "local count = 0 local function f() count = count + 1 print('the value of count is', count) print('twice the value of count is', count * 2) end local function g() count = count + 1 print('the value of count is', count) print('twice the value of count is', count * 2) end"
+    ),
+    sequences_using_variable_arguments(
+        "local function f(...) print('the first message of the sequence', ...) print('the second message of the sequence') print('the third message of the sequence') end local function g(...) print('the first message of the sequence', ...) print('the second message of the sequence') print('the third message of the sequence') end"
+    ),
+    sequences_shorter_than_min_statements(
+        "print('the first message of the sequence') print('the second message of the sequence') wait(1) print('the first message of the sequence') print('the second message of the sequence')"
+    ),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'deduplicate_blocks',
+        min_statements: 4,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'deduplicate_blocks'").unwrap();
+}
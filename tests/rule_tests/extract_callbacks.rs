@@ -0,0 +1,103 @@
+use darklua_core::{
+    generator::{DenseLuaGenerator, LuaGenerator},
+    rules::{ContextBuilder, ExtractCallbacks, Rule},
+    Parser, Resources,
+};
+
+test_rule!(
+    extract_callbacks,
+    ExtractCallbacks::default().with_min_statements(1),
+    method_callback(
+        "part.Touched:Connect(function(hit) print(hit) destroy(hit) end)"
+    ) => "local onConnect_1 = function(hit) print(hit) destroy(hit) end part.Touched:Connect(onConnect_1)",
+    field_callback(
+        "task.spawn(function() wait(1) print('done') end)"
+    ) => "local onSpawn_1 = function() wait(1) print('done') end task.spawn(onSpawn_1)",
+    identifier_callback(
+        "spawn(function() wait(1) print('done') end)"
+    ) => "local onSpawn_1 = function() wait(1) print('done') end spawn(onSpawn_1)",
+    callback_of_called_value(
+        "get()(function() wait(1) print('done') end)"
+    ) => "local callback_1 = function() wait(1) print('done') end get()(callback_1)",
+    callback_in_local_assignment(
+        "local connection = signal:Connect(function() wait(1) print('done') end)"
+    ) => "local onConnect_1 = function() wait(1) print('done') end local connection = signal:Connect(onConnect_1)",
+    callback_in_return_statement(
+        "return signal:Connect(function() wait(1) print('done') end)"
+    ) => "local onConnect_1 = function() wait(1) print('done') end return signal:Connect(onConnect_1)",
+    two_callbacks_in_the_same_block(
+        "a:Connect(function() wait(1) print('a') end) b:Connect(function() wait(1) print('b') end)"
+    ) => "local onConnect_1 = function() wait(1) print('a') end a:Connect(onConnect_1) local onConnect_2 = function() wait(1) print('b') end b:Connect(onConnect_2)",
+    callback_name_already_used(
+        "local onConnect_1 = 1 signal:Connect(function() wait(onConnect_1) print('done') end)"
+    ) => "local onConnect_1 = 1 local onConnect_2 = function() wait(onConnect_1) print('done') end signal:Connect(onConnect_2)",
+    callback_name_used_as_global_later(
+        "signal:Connect(function() wait(1) print('done') end) print(onConnect_1)"
+    ) => "local onConnect_2 = function() wait(1) print('done') end signal:Connect(onConnect_2) print(onConnect_1)",
+    // the function is declared right before the statement, in the same scope, so it
+    // captures the same locals
+    callback_capturing_locals(
+        "local count = 0 do local step = 2 signal:Connect(function() count = count + step print(count) end) end"
+    ) => "local count = 0 do local step = 2 local onConnect_1 = function() count = count + step print(count) end signal:Connect(onConnect_1) end",
+    // creating a function has no side effects, so it can be created before the sibling
+    // arguments are evaluated
+    callback_after_arguments_with_side_effects(
+        "call(next(), function() wait(1) print('done') end, next())"
+    ) => "local onCall_1 = function() wait(1) print('done') end call(next(), onCall_1, next())",
+    callback_in_nested_call(
+        "print(signal:Connect(function() wait(1) print('done') end))"
+    ) => "local onConnect_1 = function() wait(1) print('done') end print(signal:Connect(onConnect_1))",
+);
+
+test_rule_without_effects!(
+    ExtractCallbacks::default().with_min_statements(1),
+    small_callback("signal:Connect(function() print('done') end)"),
+    callback_in_while_condition("while check(function() wait(1) return true end) do end"),
+    callback_in_repeat_condition(
+        "repeat local value = read() until check(function() print(value) return true end)"
+    ),
+    callback_in_table("register({ run = function() wait(1) print('done') end })"),
+);
+
+test_rule_without_effects!(
+    ExtractCallbacks::default(),
+    callback_with_default_min_statements(
+        "signal:Connect(function() a() b() c() d() e() f() g() h() end)"
+    ),
+);
+
+#[test]
+fn snapshot_nested_callbacks() {
+    let code = "part.Touched:Connect(function(hit) print(hit) hit.Changed:Connect(function(property) print(property) print(hit[property]) end) end)";
+
+    let mut block = Parser::default().parse(code).unwrap();
+
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("src/test.lua", &resources, code).build();
+
+    ExtractCallbacks::default()
+        .with_min_statements(1)
+        .process(&mut block, &context)
+        .expect("rule should succeed");
+
+    let mut generator = DenseLuaGenerator::new(usize::MAX);
+    generator.write_block(&block);
+
+    insta::assert_snapshot!("extract_nested_callbacks", generator.into_string());
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'extract_callbacks',
+        min_statements: 4,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'extract_callbacks'").unwrap();
+}
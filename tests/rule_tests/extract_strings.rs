@@ -0,0 +1,42 @@
+use darklua_core::rules::{ExtractStrings, Rule, StringTableFormat};
+
+test_rule_without_effects!(
+    ExtractStrings::default(),
+    keep_string_literals("local greeting = 'hello' return greeting .. ', world'"),
+    keep_long_string("return [[hello]]"),
+    keep_code_without_strings("return 1 + 2"),
+);
+
+test_rule_without_effects!(
+    ExtractStrings::default()
+        .with_min_length(3)
+        .with_format(StringTableFormat::Csv),
+    keep_code_with_csv_format("print('a', 'abc')"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'extract_strings',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_object_notation_with_properties() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'extract_strings',
+        min_length: 3,
+        format: 'csv',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'extract_strings'").unwrap();
+}
@@ -0,0 +1,127 @@
+use darklua_core::rules::{FactorCommonBranchCode, Rule};
+
+test_rule!(
+    factor_common_branch_code,
+    FactorCommonBranchCode::default(),
+    common_call_suffix(
+        "if c then a() log() else b() log() end"
+    ) => "if c then a() else b() end log()",
+    suffix_of_multiple_statements_with_elseif(
+        "if c then a() x = 1 log() elseif d then b() x = 1 log() else x = 1 log() end"
+    ) => "if c then a() elseif d then b() else end x = 1 log()",
+    suffix_ignores_comments_and_spaces(
+        "if c then a() log( 'done' ) -- done\nelse b() --[[ log ]] log('done') end"
+    ) => "if c then a() else b() end log('done')",
+    longest_common_suffix_only(
+        "if c then x = 1 log() else x = 2 log() end"
+    ) => "if c then x = 1 else x = 2 end log()",
+    nested_if_statements(
+        "if c then if d then a() log() else b() log() end end"
+    ) => "if c then if d then a() else b() end log() end",
+    suffix_after_local_not_used_by_suffix(
+        "if c then local x = 1 a(x) log() else local y = 2 b(y) log() end"
+    ) => "if c then local x = 1 a(x) else local y = 2 b(y) end log()",
+    suffix_in_function(
+        "local function f(c) if c then a() return_value = 1 else b() return_value = 1 end end"
+    ) => "local function f(c) if c then a() else b() end return_value = 1 end",
+);
+
+test_rule!(
+    factor_common_branch_code_with_leading,
+    FactorCommonBranchCode::default().with_leading(),
+    prefix_with_local_condition(
+        "local flag = f() if flag then log() a() else log() b() end"
+    ) => "local flag = f() log() if flag then a() else b() end",
+    prefix_without_calls(
+        "if flag then x = 1 a() else x = 1 b() end"
+    ) => "x = 1 if flag then a() else b() end",
+    prefix_and_suffix(
+        "local flag = f() if flag then start() a() stop() else start() b() stop() end"
+    ) => "local flag = f() start() if flag then a() else b() end stop()",
+    prefix_with_parameter_condition(
+        "local function f(flag) if not flag then log() a() else log() b() end end"
+    ) => "local function f(flag) log() if not flag then a() else b() end end",
+);
+
+test_rule!(
+    factor_common_branch_code_with_scope_widening,
+    FactorCommonBranchCode::default().allow_scope_widening(),
+    suffix_with_local(
+        "if c then a() local x = 1 print(x) else b() local x = 1 print(x) end"
+    ) => "if c then a() else b() end local x = 1 print(x)",
+    suffix_with_local_function(
+        "if c then a() local function f() end else b() local function f() end end"
+    ) => "if c then a() else b() end local function f() end",
+);
+
+test_rule_without_effects!(
+    FactorCommonBranchCode::default(),
+    missing_else("if c then a() log() end"),
+    missing_else_with_elseif("if c then a() log() elseif d then b() log() end"),
+    different_suffix("if c then a() log(1) else b() log(2) end"),
+    branch_with_return("if c then a() log() return else b() log() return end"),
+    branch_with_break("while true do if c then a() log() break else b() log() break end end"),
+    suffix_using_branch_local("if c then local x = 1 print(x) else local x = 2 print(x) end"),
+    suffix_with_local("if c then a() local x = 1 print(x) else b() local x = 1 print(x) end"),
+    prefix_without_leading("if c then log() a() else log() b() end"),
+);
+
+test_rule_without_effects!(
+    FactorCommonBranchCode::default().with_leading(),
+    prefix_with_call_condition("if check() then log() a() else log() b() end"),
+    prefix_with_field_condition("local t = f() if t.flag then log() a() else log() b() end"),
+    prefix_calling_function_with_global_condition("if flag then log() a() else log() b() end"),
+    prefix_assigning_condition(
+        "local flag = f() if flag then flag = false a() else flag = false b() end"
+    ),
+    prefix_calling_function_that_assigns_condition(
+        "local flag = f() local function reset() flag = false end if flag then reset() a() else reset() b() end"
+    ),
+    prefix_with_local_without_scope_widening(
+        "local flag = f() if flag then local x = 1 a(x) else local x = 1 b(x) end"
+    ),
+);
+
+test_rule_without_effects!(
+    FactorCommonBranchCode::default().allow_scope_widening(),
+    suffix_with_local_used_after_if("if c then a() local x = 1 else b() local x = 1 end print(x)"),
+    suffix_with_local_used_by_last_statement(
+        "local function f() if c then a() local x = 1 else b() local x = 1 end return x end"
+    ),
+);
+
+test_rule_without_effects!(
+    FactorCommonBranchCode::default()
+        .with_leading()
+        .allow_scope_widening(),
+    prefix_with_local_shadowing_condition(
+        "local x = f() if x then local x = 1 a(x) else local x = 1 b(x) end"
+    ),
+);
+
+test_rule!(
+    factor_common_branch_code_with_leading_and_scope_widening,
+    FactorCommonBranchCode::default()
+        .with_leading()
+        .allow_scope_widening(),
+    prefix_with_local(
+        "local flag = f() if flag then local x = 1 a(x) else local x = 1 b(x) end"
+    ) => "local flag = f() local x = 1 if flag then a(x) else b(x) end",
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'factor_common_branch_code',
+        leading: true,
+        allow_scope_widening: true,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'factor_common_branch_code'").unwrap();
+}
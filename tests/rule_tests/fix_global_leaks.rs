@@ -0,0 +1,70 @@
+use darklua_core::rules::{FixGlobalLeaks, GlobalLeakMode, Rule};
+
+test_rule_without_effects!(
+    FixGlobalLeaks::default(),
+    typo_of_local(
+        "local playerScore = 0\nlocal function add(x)\n  playerscore = playerScore + x\nend"
+    ),
+    global_only_used_in_one_function(
+        "local function sum(list)\n  total = 0\n  for _, v in ipairs(list) do total = total + v end\n  return total\nend"
+    ),
+);
+
+test_rule!(
+    fix_global_leaks_fix_mode,
+    FixGlobalLeaks::default().with_mode(GlobalLeakMode::Fix),
+    declare_global_only_used_in_one_function(
+        "local function sum(list)\n  total = 0\n  for _, v in ipairs(list) do total = total + v end\n  return total\nend"
+    ) => "local function sum(list)\n  local total\n  total = 0\n  for _, v in ipairs(list) do total = total + v end\n  return total\nend",
+    declare_globals_in_function_statement(
+        "function module.reset()\n  count = 0\n  index = 1\n  return count, index\nend"
+    ) => "function module.reset()\n  local count, index\n  count = 0\n  index = 1\n  return count, index\nend",
+    declare_global_in_function_expression(
+        "return function()\n  result = {}\n  return result\nend"
+    ) => "return function()\n  local result\n  result = {}\n  return result\nend",
+    declare_global_in_nested_function_only(
+        "local function outer()\n  return function()\n    temp = 1\n    return temp\n  end\nend"
+    ) => "local function outer()\n  return function()\n    local temp\n    temp = 1\n    return temp\n  end\nend",
+);
+
+test_rule_without_effects!(
+    FixGlobalLeaks::default().with_mode(GlobalLeakMode::Fix),
+    keep_typo_of_local(
+        "local playerScore = 0\nlocal function add(x)\n  playerscore = playerScore + x\nend"
+    ),
+    keep_global_read_before_assignment(
+        "local function get()\n  if not cache then cache = {} end\n  return cache\nend"
+    ),
+    keep_global_read_in_another_function(
+        "local function set()\n  value = 1\nend\nlocal function get()\n  return value\nend"
+    ),
+    keep_global_read_at_top_level("local function set()\n  value = 1\nend\nset()\nprint(value)"),
+    keep_global_assigned_at_top_level("value = 1\nprint(value)"),
+    keep_intentional_global_function("function helper()\n  return 1\nend"),
+);
+
+test_rule_without_effects!(
+    FixGlobalLeaks::default()
+        .with_mode(GlobalLeakMode::Fix)
+        .with_known_global("total"),
+    keep_known_global(
+        "local function sum(list)\n  total = 0\n  for _, v in ipairs(list) do total = total + v end\n  return total\nend"
+    ),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'fix_global_leaks',
+        mode: 'fix',
+        known_globals: ['Config'],
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'fix_global_leaks'").unwrap();
+}
@@ -0,0 +1,147 @@
+use darklua_core::rules::{FlattenClosures, Rule};
+
+use super::process_rules;
+
+fn process_code(code: &str) -> String {
+    let rule: Box<dyn Rule> = Box::<FlattenClosures>::default();
+
+    process_rules([rule], None, code).unwrap()
+}
+
+macro_rules! test_flatten {
+    ($($name:ident ($input:literal) => $output:literal),* $(,)?) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!(process_code($input), $output);
+            }
+        )*
+    };
+}
+
+macro_rules! test_no_flatten {
+    ($($name:ident ($input:literal)),* $(,)?) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!(process_code($input), $input);
+            }
+        )*
+    };
+}
+
+test_flatten!(
+    two_call_sites(
+        "--!darklua flatten-next\n\
+        local function add(a) return function(b) return a + b end end \
+        print(add(1)(2), add(3)(4))"
+    ) => "\nlocal function add(a, b) return a + b end \
+        print(add(1, 2), add(3, 4))",
+    captured_local(
+        "--!darklua flatten-next\n\
+        local function scale(a) local factor = a * 2 return function(b) return factor * b end end \
+        print(scale(1)(2)) print(scale(3)(4))"
+    ) => "\nlocal function scale(a, b) local factor = a * 2 return factor * b end \
+        print(scale(1, 2)) print(scale(3, 4))",
+    three_levels(
+        "--!darklua flatten-next\n\
+        local function f(a) return function(b) return function(c) return a + b + c end end end \
+        print(f(1)(2)(3))"
+    ) => "\nlocal function f(a, b, c) return a + b + c end \
+        print(f(1, 2, 3))",
+    missing_first_argument(
+        "--!darklua flatten-next\n\
+        local function f(a, b) return function(c) return c end end \
+        print(f(1)(2))"
+    ) => "\nlocal function f(a, b, c) return c end \
+        print(f(1, nil, 2))",
+    string_and_table_arguments(
+        "--!darklua flatten-next\n\
+        local function f(a) return function(b) return a, b end end \
+        print(f 'x' {})"
+    ) => "\nlocal function f(a, b) return a, b end \
+        print(f ('x', {}))",
+    variadic_returned_function(
+        "--!darklua flatten-next\n\
+        local function f(a) return function(...) return a, ... end end \
+        print(f(1)(2, 3))"
+    ) => "\nlocal function f(a, ...) return a, ... end \
+        print(f(1, 2, 3))",
+);
+
+test_no_flatten!(
+    without_directive("local function f(a) return function(b) return a + b end end print(f(1)(2))"),
+    stored_intermediate_function(
+        "--!darklua flatten-next\n\
+        local function f(a) return function(b) return a + b end end \
+        local g = f(1) print(f(1)(2), g(3))"
+    ),
+    function_passed_as_value(
+        "--!darklua flatten-next\n\
+        local function f(a) return function(b) return a + b end end \
+        call(f)"
+    ),
+    second_arguments_with_side_effects(
+        "--!darklua flatten-next\n\
+        local function f(a) return function(b) return a + b end end \
+        print(f(1)(call()))"
+    ),
+    variadic_function(
+        "--!darklua flatten-next\n\
+        local function f(...) return function(b) return b end end \
+        print(f(1)(2))"
+    ),
+    early_return(
+        "--!darklua flatten-next\n\
+        local function f(a) if a then return end return function(b) return b end end \
+        print(f(1)(2))"
+    ),
+    parameter_name_used_by_function(
+        "--!darklua flatten-next\n\
+        local function f(a) local b = a return function(b) return b end end \
+        print(f(1)(2))"
+    ),
+    too_many_first_arguments(
+        "--!darklua flatten-next\n\
+        local function f(a) return function(b) return a + b end end \
+        print(f(1, 2)(3))"
+    ),
+    does_not_return_a_function(
+        "--!darklua flatten-next\n\
+        local function f(a) return a end \
+        print(f(1)(2))"
+    ),
+    recursive_function(
+        "--!darklua flatten-next\n\
+        local function f(a) return function(b) return f(a)(b) end end \
+        print(f(1)(2))"
+    ),
+);
+
+#[test]
+fn shadowed_function_is_not_a_call_site() {
+    let code = "--!darklua flatten-next\n\
+        local function f(a) return function(b) return a + b end end \
+        do local f = print f(1) end print(f(1)(2))";
+
+    assert_eq!(
+        process_code(code),
+        "\nlocal function f(a, b) return a + b end \
+        do local f = print f(1) end print(f(1, 2))"
+    );
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'flatten_closures',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'flatten_closures'").unwrap();
+}
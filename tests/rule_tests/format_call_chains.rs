@@ -0,0 +1,107 @@
+use darklua_core::{
+    generator::{DenseLuaGenerator, LuaGenerator},
+    rules::{FormatCallChains, Rule},
+    Parser,
+};
+
+test_rule_snapshot!(
+    format_call_chains,
+    FormatCallChains::default(),
+    five_links_chain(
+        "local result = promise:andThen(onSuccess):catch(onError):finally(cleanup):await():expect(\"done\")"
+    ),
+    indented_chain_in_function(
+        "local function run()\n    return fetch(url):andThen(parse):andThen(store)\nend\n"
+    ),
+);
+
+test_rule_with_tokens!(
+    format_call_chains,
+    FormatCallChains::default(),
+    comment_on_third_link(
+        "promise:andThen(onSuccess):catch(onError):finally(cleanup) -- always runs\n:await()"
+    ) => "promise\n    :andThen(onSuccess)\n    :catch(onError)\n    :finally(cleanup) -- always runs\n    :await()",
+    comment_before_link(
+        "promise:andThen(a)\n-- handle errors\n:catch(b):finally(c)"
+    ) => "promise\n    :andThen(a)\n    -- handle errors\n    :catch(b)\n    :finally(c)",
+    chain_with_fields(
+        "game:GetService('Players').LocalPlayer:WaitForChild('PlayerGui'):FindFirstChild('Menu')"
+    ) => "game\n    :GetService('Players').LocalPlayer\n    :WaitForChild('PlayerGui')\n    :FindFirstChild('Menu')",
+    already_formatted_chain(
+        "promise\n    :andThen(a)\n    :catch(b)\n    :finally(c)"
+    ) => "promise\n    :andThen(a)\n    :catch(b)\n    :finally(c)",
+    chain_with_fewer_links(
+        "promise:andThen(a):catch(b)"
+    ) => "promise:andThen(a):catch(b)",
+);
+
+test_rule_with_tokens!(
+    format_call_chains_with_min_links,
+    FormatCallChains::default().with_min_links(2),
+    chain_with_two_links(
+        "promise:andThen(a):catch(b)"
+    ) => "promise\n    :andThen(a)\n    :catch(b)",
+);
+
+fn assert_no_ambiguous_call(code: &str) {
+    let lines: Vec<_> = code.lines().collect();
+
+    for window in lines.windows(2) {
+        let previous = window[0].trim_end();
+        let next = window[1].trim_start();
+
+        let ends_with_prefix = previous
+            .chars()
+            .last()
+            .filter(|character| {
+                character.is_alphanumeric() || matches!(character, '_' | ')' | ']' | '"' | '\'')
+            })
+            .is_some();
+
+        assert!(
+            !(ends_with_prefix && next.starts_with('(')),
+            "generated code has an ambiguous call between `{}` and `{}`:\n{}",
+            previous,
+            next,
+            code
+        );
+    }
+}
+
+#[test]
+fn dense_round_trip_of_multiline_chain_with_parenthesized_argument() {
+    let code = "local result = fetch((url))\n    :andThen((parse))\n    :catch(report)\n";
+
+    let parser = Parser::default();
+    let expected_block = parser.parse(code).expect("code should parse");
+
+    for column_span in 1..=40 {
+        let mut generator = DenseLuaGenerator::new(column_span);
+        generator.write_block(&expected_block);
+        let output = generator.into_string();
+
+        assert_no_ambiguous_call(&output);
+
+        let block = parser
+            .parse(&output)
+            .unwrap_or_else(|error| panic!("unable to parse `{}`: {:?}", output, error));
+
+        pretty_assertions::assert_eq!(block, expected_block, "output:\n{}", output);
+    }
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'format_call_chains',
+        min_links: 4,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'format_call_chains'").unwrap();
+}
@@ -0,0 +1,115 @@
+use darklua_core::process::{Environment, EnvironmentTarget};
+use darklua_core::rules::{FreezeConstantTables, Rule};
+
+use super::process_rules;
+
+test_rule!(
+    freeze_constant_tables,
+    FreezeConstantTables::default(),
+    environment = Environment::builtin(EnvironmentTarget::Luau),
+    flat_table("local t = { a = 1, b = 'x' } print(t.a, t.b)")
+        => "local t = table.freeze({ a = 1, b = 'x' }) print(t.a, t.b)",
+    nested_table_frozen_at_both_levels("local t = { a = { b = 1 } } print(t.a.b)")
+        => "local t = table.freeze({ a = table.freeze({ b = 1 }) }) print(t.a.b)",
+    array_passed_to_ipairs("local t = { 1, 2, 3 } for _, v in ipairs(t) do print(v) end")
+        => "local t = table.freeze({ 1, 2, 3 }) for _, v in ipairs(t) do print(v) end",
+    length_of_table("local t = { 1, 2 } print(#t)")
+        => "local t = table.freeze({ 1, 2 }) print(#t)",
+    already_frozen_nested_table("local t = { a = table.freeze({ 1 }) } print(t.a[1])")
+        => "local t = table.freeze({ a = table.freeze({ 1 }) }) print(t.a[1])",
+    method_on_string_field("local t = { name = 'abc' } print(t.name:upper())")
+        => "local t = table.freeze({ name = 'abc' }) print(t.name:upper())",
+    unused_table("local t = {}") => "local t = table.freeze({})",
+    shadowed_variable_can_be_mutated("local t = { 1 } do local t = {} t[1] = 2 end print(t[1])")
+        => "local t = table.freeze({ 1 }) do local t = {} t[1] = 2 end print(t[1])",
+);
+
+test_rule_without_effects!(
+    FreezeConstantTables::default(),
+    environment = Environment::builtin(EnvironmentTarget::Luau),
+    already_frozen("local t = table.freeze({ a = 1 }) print(t.a)"),
+    field_assignment("local t = { a = 1 } t.a = 2"),
+    nested_field_assignment("local t = { a = { b = 1 } } t.a.b = 2"),
+    index_assignment_in_function("local t = { 1 } local function f(i) t[i] = 0 end"),
+    passed_to_print("local t = { 1, 2 } print(t)"),
+    passed_to_unknown_function("local t = { 1, 2 } table.insert(t, 3)"),
+    nested_table_escapes("local t = { a = {} } local a = t.a a.x = 1"),
+    nested_table_passed_to_pairs("local t = { a = {} } for _, v in pairs(t) do v.x = 1 end"),
+    returned_table("local t = { a = 1 } return t"),
+    returned_nested_table("local t = { a = { 1 } } return t.a"),
+    method_call_on_table("local t = { a = 1 } t:method()"),
+    function_statement_on_table("local t = {} function t.f() end"),
+    table_with_function("local t = { f = function() end } print(t.f)"),
+    table_with_variable("local t = { a = x } print(t.a)"),
+    table_with_variable_key("local t = { [x] = 1 } print(t.a)"),
+    table_returned_by_or("local t = { 1 } local u = t or {} u[1] = 2"),
+    shadowed_allowed_function("local t = { 1 } local function ipairs(t) t[1] = 2 end ipairs(t)"),
+);
+
+test_rule!(
+    freeze_constant_tables_with_allowed_function,
+    FreezeConstantTables::default().with_allowed_function("print"),
+    environment = Environment::builtin(EnvironmentTarget::Luau),
+    passed_to_allowed_print("local t = { 1, 2 } print(t)")
+        => "local t = table.freeze({ 1, 2 }) print(t)",
+);
+
+test_rule!(
+    freeze_constant_tables_with_freeze_exports,
+    FreezeConstantTables::default().with_freeze_exports(true),
+    environment = Environment::builtin(EnvironmentTarget::Luau),
+    returned_table_with_freeze_exports("local t = { a = { 1 } } return t")
+        => "local t = table.freeze({ a = table.freeze({ 1 }) }) return t",
+);
+
+test_rule!(
+    freeze_constant_tables_for_roblox,
+    FreezeConstantTables::default(),
+    environment = Environment::builtin(EnvironmentTarget::Roblox),
+    roblox_target_freezes_tables("local t = { 1 } print(t[1])")
+        => "local t = table.freeze({ 1 }) print(t[1])",
+);
+
+test_rule_without_effects!(
+    FreezeConstantTables::default(),
+    environment = Environment::builtin(EnvironmentTarget::Lua51),
+    lua51_target_is_ignored("local t = { 1 } print(t[1])"),
+);
+
+#[test]
+fn lua51_target_errors_with_strict_target() {
+    let rule: Box<dyn Rule> = Box::new(FreezeConstantTables::default().with_strict_target(true));
+
+    let errors = process_rules(
+        [rule],
+        Some(EnvironmentTarget::Lua51),
+        "local t = { 1 } print(t[1])",
+    )
+    .unwrap_err();
+
+    assert!(
+        errors
+            .iter()
+            .any(|error| error.contains("`table.freeze` is not available for the `lua51` target")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'freeze_constant_tables',
+        strict_target: true,
+        freeze_exports: true,
+        allowed_functions: ['print', 'table.find'],
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'freeze_constant_tables'").unwrap();
+}
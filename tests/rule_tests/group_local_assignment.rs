@@ -7,13 +7,25 @@ test_rule!(
     two_locals("local foo = 1 local bar = 2") => "local foo, bar = 1, 2",
     three_locals("local foo = 1 local bar = 2 local baz = 3") => "local foo, bar, baz = 1, 2, 3",
     local_with_no_value_and_local_with_value("local a local b = 7") => "local a, b = nil, 7",
-    local_with_no_values_are_set_to_nil("local a local b = true local c") => "local a, b, c = nil, true, nil"
+    local_with_no_values_are_set_to_nil("local a local b = true local c") => "local a, b, c = nil, true, nil",
+    redeclared_local_not_used_after("local a = 1 local a = 2") => "local a, a = 1, 2",
+    redeclared_local_in_nested_block_is_not_the_same("local a = 1 local b = 2 do local a = 3 print(a) end")
+        => "local a, b = 1, 2 do local a = 3 print(a) end",
+    duplicated_local_in_first_statement("local a, a = 1, 2 local b = 3 print(a, b)")
+        => "local a, a, b = 1, 2, 3 print(a, b)",
+    first_local_reads_variable_declared_by_next("local a = b local b = 1") => "local a, b = b, 1",
 );
 
 test_rule_without_effects!(
     GroupLocalAssignment::default(),
     two_local_using_the_other("local foo = 1 local bar = foo"),
-    multiple_return_values("local a, b = call() local c = 0")
+    swap_pattern("local x = y local y = x"),
+    next_local_reads_previous_variable_in_function("local a = 1 local f = function() return a end"),
+    multiple_return_values("local a, b = call() local c = 0"),
+    extra_values_in_first_statement("local a = 1, call() local b = 2"),
+    redeclared_local_used_after("local a = 1 local a = 2 print(a)"),
+    redeclared_local_used_in_last_statement("local a = 1 local b, a = 2, 3 return a"),
+    redeclared_local_used_in_function("local a = 1 local a = 2 local function f() return a end"),
 );
 
 #[test]
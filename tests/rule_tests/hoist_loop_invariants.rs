@@ -0,0 +1,138 @@
+use darklua_core::rules::{HoistLoopInvariants, Rule};
+
+test_rule!(
+    hoist_loop_invariants,
+    HoistLoopInvariants::default().assume_no_metatables(),
+    field_and_multiplication(
+        "for i = 1, 10 do t[i] = base.scale * factor end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = base.scale * factor for i = 1, 10 do t[i] = __DARKLUA_LOOP_INVARIANT end",
+    field_chain_used_twice(
+        "for i = 1, 10 do list[i] = config.render.scale + config.render.scale * i end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = config.render.scale for i = 1, 10 do list[i] = __DARKLUA_LOOP_INVARIANT + __DARKLUA_LOOP_INVARIANT * i end",
+    field_chain_prefix_of_index(
+        "for i = 1, 10 do a[i] = data.points.x[i] b[i] = data.points.x[i + 1] end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = data.points.x for i = 1, 10 do a[i] = __DARKLUA_LOOP_INVARIANT[i] b[i] = __DARKLUA_LOOP_INVARIANT[i + 1] end",
+    numeric_for_with_step(
+        "for i = 10, 1, -1 do t[i] = base.scale * factor end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = base.scale * factor for i = 10, 1, -1 do t[i] = __DARKLUA_LOOP_INVARIANT end",
+    while_true_loop(
+        "while true do t[i] = base.scale * factor i = i + 1 end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = base.scale * factor while true do t[i] = __DARKLUA_LOOP_INVARIANT i = i + 1 end",
+    repeat_block(
+        "repeat t[i] = base.scale * factor i = i + 1 until i > 10"
+    ) => "local __DARKLUA_LOOP_INVARIANT = base.scale * factor repeat t[i] = __DARKLUA_LOOP_INVARIANT i = i + 1 until i > 10",
+    while_condition(
+        "while i < n * 2 do i = i + 1 end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = n * 2 while i < __DARKLUA_LOOP_INVARIANT do i = i + 1 end",
+    repeat_condition(
+        "local limit = get() repeat count = count + 1 until count > limit * 2"
+    ) => "local limit = get() local __DARKLUA_LOOP_INVARIANT = limit * 2 repeat count = count + 1 until count > __DARKLUA_LOOP_INVARIANT",
+    // the code of the loop calls a function, but the locals it reads are not assigned by
+    // any function
+    local_values_in_loop_with_call(
+        "local scale, factor = get() for i = 1, 10 do update() t[i] = scale * factor end"
+    ) => "local scale, factor = get() local __DARKLUA_LOOP_INVARIANT = scale * factor for i = 1, 10 do update() t[i] = __DARKLUA_LOOP_INVARIANT end",
+    // the whole expression is evaluated at each iteration, even if its right side is not
+    nil_guard_with_and(
+        "for i = 1, 10 do t[i] = opts and opts.scale * factor end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = opts and opts.scale * factor for i = 1, 10 do t[i] = __DARKLUA_LOOP_INVARIANT end",
+    // expressions that cannot raise an error are moved even if they may not be evaluated
+    expression_without_error_in_condition(
+        "for i = 1, n do if flag then t[i] = mode == expected end end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = mode == expected for i = 1, n do if flag then t[i] = __DARKLUA_LOOP_INVARIANT end end",
+    // the guard cannot raise an error, but the guarded expression stays in the loop
+    guarded_expression_in_loop_that_may_not_run(
+        "for i = 1, n do if base ~= nil then t[i] = base.scale * factor end end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = base ~= nil for i = 1, n do if __DARKLUA_LOOP_INVARIANT then t[i] = base.scale * factor end end",
+    expression_before_break(
+        "for i = 1, 10 do t[i] = base.scale * factor if done then break end end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = base.scale * factor for i = 1, 10 do t[i] = __DARKLUA_LOOP_INVARIANT if done then break end end",
+    nested_loops_hoist_to_the_right_level(
+        "for i = 1, 10 do for j = 1, 10 do grid[i][j] = base.scale * i + offset.x * 2 end end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = offset.x * 2 for i = 1, 10 do local __DARKLUA_LOOP_INVARIANT0 = base.scale * i + __DARKLUA_LOOP_INVARIANT for j = 1, 10 do grid[i][j] = __DARKLUA_LOOP_INVARIANT0 end end",
+    two_loops_in_the_same_block(
+        "for i = 1, 10 do a[i] = base.scale * factor end for i = 1, 10 do b[i] = base.scale * factor end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = base.scale * factor for i = 1, 10 do a[i] = __DARKLUA_LOOP_INVARIANT end local __DARKLUA_LOOP_INVARIANT0 = base.scale * factor for i = 1, 10 do b[i] = __DARKLUA_LOOP_INVARIANT0 end",
+);
+
+test_rule!(
+    hoist_loop_invariants_with_min_occurrences,
+    HoistLoopInvariants::default()
+        .assume_no_metatables()
+        .with_min_occurrences(1)
+        .with_min_size(10),
+    single_field_read(
+        "for i = 1, 10 do t[i] = base.scale end"
+    ) => "local __DARKLUA_LOOP_INVARIANT = base.scale for i = 1, 10 do t[i] = __DARKLUA_LOOP_INVARIANT end",
+);
+
+test_rule_without_effects!(
+    HoistLoopInvariants::default().assume_no_metatables(),
+    expression_with_loop_variable("for i = 1, 10 do t[i] = i * scale end"),
+    expression_with_generic_for_variable(
+        "for _, value in ipairs(list) do t[value] = value * scale end"
+    ),
+    expression_with_variable_assigned_in_loop(
+        "for i = 1, 10 do scale = scale + 1 t[i] = scale * factor end"
+    ),
+    expression_with_local_declared_in_loop(
+        "for i = 1, 10 do local scale = get(i) t[i] = scale * factor end"
+    ),
+    field_of_table_modified_in_loop("for i = 1, 10 do base.scale = i t[i] = base.scale * factor end"),
+    field_read_in_loop_with_call("for i = 1, 10 do update() t[i] = base.scale * factor end"),
+    global_in_loop_with_call("for i = 1, 10 do update() t[i] = scale * factor end"),
+    local_assigned_by_function(
+        "local scale = 1 local function update() scale = scale + 1 end for i = 1, 10 do update() t[i] = scale * factor end"
+    ),
+    expression_in_condition(
+        "for i = 1, 10 do if i > 5 then t[i] = base.scale * factor end end"
+    ),
+    nil_guard_with_if(
+        "for i = 1, 10 do if config then t[i] = config.scale * factor end end"
+    ),
+    nil_guard_with_and_in_loop_that_may_not_run(
+        "for i = 1, n do t[i] = opts and opts.scale * factor end"
+    ),
+    expression_after_break(
+        "for i = 1, 10 do if done then break end t[i] = base.scale * factor end"
+    ),
+    loop_without_iterations("for i = 1, 0 do t[i] = base.scale * factor end"),
+    loop_with_unknown_bounds("for i = 1, n do t[i] = base.scale * factor end"),
+    while_loop_that_may_not_run("while i < n do t[i] = base.scale * factor i = i + 1 end"),
+    generic_for_with_standard_iterator(
+        "for _, value in ipairs(list) do sum[value] = offset.x * 2 end"
+    ),
+    generic_for_with_unknown_iterator(
+        "for _, value in iterate(list) do t[value] = base.scale * factor end"
+    ),
+    expression_in_function(
+        "for i = 1, 10 do callbacks[i] = function() return base.scale * factor end end"
+    ),
+    table_constructor("for i = 1, 10 do t[i] = { base.scale, factor } end"),
+    call_result("for i = 1, 10 do t[i] = get(base.scale) end"),
+    numeric_for_bounds("for i = base.first * 2, base.last * 2 do print(i) end"),
+);
+
+test_rule_without_effects!(
+    HoistLoopInvariants::default(),
+    field_read_with_metatables("for i = 1, 10 do t[i] = base.scale * factor end"),
+    binary_expression_with_metatables("local a, b = get() for i = 1, 10 do t[i] = a * b end"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'hoist_loop_invariants',
+        assume_no_metatables: true,
+        min_occurrences: 3,
+        min_size: 5,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'hoist_loop_invariants'").unwrap();
+}
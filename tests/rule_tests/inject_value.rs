@@ -1,3 +1,4 @@
+use darklua_core::process::LuaType;
 use darklua_core::rules::{InjectGlobalValue, Rule};
 
 test_rule!(
@@ -52,6 +53,28 @@ test_rule_without_effects!(
     does_not_inline_if_global_table_is_redefined("local _G return _G.foo"),
 );
 
+test_rule!(
+    inject_global_nil_with_env_scope,
+    InjectGlobalValue::nil("foo"),
+    inject_outside_env_parameter_scope("local function f(_ENV) return foo end return foo")
+        => "local function f(_ENV) return foo end return nil",
+    inject_after_env_local_scope("do local _ENV = {} end return _G.foo") => "do local _ENV = {} end return nil",
+);
+
+test_rule_without_effects!(
+    InjectGlobalValue::nil("foo"),
+    does_not_inline_in_env_local_scope("local _ENV = {} return foo"),
+    does_not_inline_in_env_parameter_scope("local function f(_ENV) return foo end"),
+    does_not_inline_global_table_in_env_scope("local _ENV = {} return _G.foo"),
+    does_not_inline_prefix_in_env_scope("local _ENV = {} return foo.bar"),
+);
+
+test_rule!(
+    inject_global_nil_assume_no_env_manipulation,
+    InjectGlobalValue::nil("foo").with_assume_no_env_manipulation(true),
+    inject_in_env_local_scope("local _ENV = {} return foo") => "local _ENV = {} return nil",
+);
+
 #[test]
 fn deserialize_from_object_notation_without_value() {
     json5::from_str::<Box<dyn Rule>>(
@@ -183,6 +206,26 @@ test_rule!(
     inject_negative_integer("return _G.num") => "return 1E49",
 );
 
+test_rule!(
+    inject_global_with_expected_type,
+    InjectGlobalValue::number("FLAG", 1.0).with_expected_type(LuaType::String),
+    inject_number_compared_with_other_type("if type(FLAG) == 'string' then return FLAG end")
+        => "if type(1) == 'string' then return 1 end",
+);
+
+#[test]
+fn deserialize_from_object_notation_with_expected_type() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'inject_global_value',
+        identifier: 'FLAG',
+        value: 'on',
+        expected_type: 'string',
+    }"#,
+    )
+    .unwrap();
+}
+
 #[test]
 fn deserialize_number_value_too_large() {
     let err = json5::from_str::<Box<dyn Rule>>(
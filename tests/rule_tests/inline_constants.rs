@@ -0,0 +1,77 @@
+use darklua_core::rules::{InlineConstants, Rule};
+
+test_rule!(
+    inline_constants,
+    InlineConstants::default(),
+    inline_number("local a = 1 return a") => "return 1",
+    inline_string("local name = 'darklua' print(name)") => "print('darklua')",
+    inline_true("local enabled = true if enabled then print('on') end")
+        => "if true then print('on') end",
+    inline_false("local enabled = false return enabled") => "return false",
+    inline_nil("local value = nil return value") => "return nil",
+    inline_multiple_reads("local a = 2 return a + a") => "return 2 + 2",
+    inline_in_nested_block("local a = 1 do print(a) end") => "do print(1) end",
+    inline_in_repeat_condition("repeat local done = true until done") => "repeat until true",
+    inline_as_prefix("local name = 'abc' return name:upper()") => "return ('abc'):upper()",
+    inline_as_field_prefix("local name = 'abc' return name.len") => "return ('abc').len",
+    inline_in_interpolated_string("local a = 1 return `{a}`") => "return `{1}`",
+    inline_multiple_variables("local a, b = 1, 2 return a + b") => "return 1 + 2",
+    inline_chained_constants("local a = 1 local b = a return b") => "return 1",
+    inline_unused_constant("local a = 1") => "",
+    inline_only_after_declaration("local a = a return a") => "local a = a return a",
+    inline_shadowed_after_redeclaration("local a = 1 local a = 2 return a") => "return 2",
+    keep_reassigned_variable("local a = 1 a = 2 return a") => "local a = 1 a = 2 return a",
+    keep_compound_assigned_variable("local a = 1 a += 2 return a") => "local a = 1 a += 2 return a",
+    keep_variable_reassigned_by_function("local a = 1 function a() end return a")
+        => "local a = 1 function a() end return a",
+    keep_variable_captured_by_function("local a = 1 local function f() return a end return f")
+        => "local a = 1 local function f() return a end return f",
+    keep_variable_captured_by_function_expression("local a = 1 return function() return a end")
+        => "local a = 1 return function() return a end",
+    keep_non_literal_value_and_inline_literal("local a, b = f(), 1 return a, b")
+        => "local a, b = f(), 1 return a, 1",
+    keep_declaration_with_extra_value("local a = 1, f() return a") => "local a = 1, f() return 1",
+    inline_when_shadowed_in_function_parameter(
+        "local a = 1 local function f(a) return a end return f, a"
+    ) => "local function f(a) return a end return f, 1",
+    inline_when_shadowed_in_nested_block("local a = 1 do local a = f() print(a) end return a")
+        => "do local a = f() print(a) end return 1",
+    keep_reassigned_variable_in_nested_function(
+        "local a = 1 local function f() a = 2 end return a"
+    ) => "local a = 1 local function f() a = 2 end return a",
+);
+
+test_rule!(
+    inline_constants_not_conservative,
+    InlineConstants::default().with_conservative(false),
+    inline_variable_captured_by_function("local a = 1 local function f() return a end return f")
+        => "local function f() return 1 end return f",
+    keep_reassigned_variable_in_nested_function(
+        "local a = 1 local function f() a = 2 end return a"
+    ) => "local a = 1 local function f() a = 2 end return a",
+);
+
+test_rule_without_effects!(
+    InlineConstants::default(),
+    file_using_getfenv("local a = 1 getfenv().value = a return a"),
+    non_literal_value("local a = f() return a"),
+    table_value("local a = {} return a"),
+    declaration_without_value("local a return a"),
+    global_assignment("a = 1 return a"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'inline_constants',
+        conservative: false,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'inline_constants'").unwrap();
+}
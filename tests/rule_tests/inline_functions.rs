@@ -0,0 +1,153 @@
+use darklua_core::rules::{InlineFunctions, Rule};
+
+test_rule!(
+    inline_functions,
+    InlineFunctions::default(),
+    inline_identity("local function id(x) return x end return id(1)") => "return 1",
+    inline_binary_expression("local function add(a, b) return a + b end return add(1, 2)")
+        => "return 1 + 2",
+    inline_with_precedence("local function add(a, b) return a + b end return add(1, 2) * 3")
+        => "return (1 + 2) * 3",
+    inline_argument_with_precedence("local function double(a) return a * 2 end return double(1 + 2)")
+        => "return (1 + 2) * 2",
+    inline_in_unary_expression("local function add(a, b) return a + b end return -add(a, b)")
+        => "return -(a + b)",
+    inline_as_prefix("local function get(t) return t.value end return get(obj).name")
+        => "return obj.value.name",
+    inline_binary_as_prefix("local function concat(a, b) return a .. b end return concat(a, b):upper()")
+        => "return (a .. b):upper()",
+    inline_empty_function("local function noop() end return noop()") => "return nil",
+    inline_empty_return("local function noop() return end return noop(1)") => "return nil",
+    inline_missing_argument("local function id(x) return x end return id()") => "return nil",
+    inline_extra_pure_argument("local function id(x) return x end return id(1, 2)") => "return 1",
+    inline_string_argument("local function id(x) return x end return id 'a'") => "return 'a'",
+    inline_table_argument("local function get(t) return t.value end return get { value = 1 }")
+        => "return ({ value = 1 }).value",
+    inline_duplicated_identifier_argument(
+        "local function square(n) return n * n end return square(value)"
+    ) => "return value * value",
+    inline_duplicated_literal_argument("local function square(n) return n * n end return square(3)")
+        => "return 3 * 3",
+    inline_call_argument_used_once("local function wrap(n) return { n } end return wrap(f())")
+        => "return { (f()) }",
+    inline_multiple_values_argument("local function id(x) return x end return id(f())")
+        => "return (f())",
+    inline_multiple_call_sites(
+        "local function inc(n) return n + 1 end local a = inc(1) local b = inc(a) return a, b"
+    ) => "local a = 1 + 1 local b = a + 1 return a, b",
+    inline_nested_calls("local function inc(n) return n + 1 end return inc(inc(1))")
+        => "return (1 + 1) + 1",
+    inline_chained_functions(
+        "local function g(x) return x + 1 end local function f(y) return g(y) * 2 end return f(3)"
+    ) => "return (3 + 1) * 2",
+    inline_in_nested_function(
+        "local function inc(n) return n + 1 end return function(v) return inc(v) end"
+    ) => "return function(v) return v + 1 end",
+    inline_with_free_variable("local offset = 1 local function inc(n) return n + offset end return inc(2)")
+        => "local offset = 1 return 2 + offset",
+    inline_with_parameter_named_like_argument(
+        "local function sub(a, b) return a - b end return sub(b, a)"
+    ) => "return b - a",
+    inline_and_keep_function_used_as_value(
+        "local function inc(n) return n + 1 end return inc(1), inc"
+    ) => "local function inc(n) return n + 1 end return 1 + 1, inc",
+    inline_and_keep_function_called_as_statement(
+        "local function inc(n) return n + 1 end inc(1) return inc(2)"
+    ) => "local function inc(n) return n + 1 end inc(1) return 2 + 1",
+    inline_in_repeat_condition("repeat local function done() return true end until done()")
+        => "repeat until true",
+    keep_recursive_function("local function f(n) return f(n) end return f(1)")
+        => "local function f(n) return f(n) end return f(1)",
+    keep_variadic_function("local function f(...) return ... end return f(1)")
+        => "local function f(...) return ... end return f(1)",
+    keep_function_with_statements("local function f(n) print(n) return n end return f(1)")
+        => "local function f(n) print(n) return n end return f(1)",
+    keep_function_with_multiple_returns("local function f(n) return n, n end return f(1)")
+        => "local function f(n) return n, n end return f(1)",
+    keep_function_returning_function("local function f(n) return function() return n end end return f(a)")
+        => "local function f(n) return function() return n end end return f(a)",
+    keep_reassigned_function("local function f(n) return n end f = print return f(1)")
+        => "local function f(n) return n end f = print return f(1)",
+    keep_function_with_reassigned_free_variable(
+        "local value = 1 local function f() return value end value = 2 return f()"
+    ) => "local value = 1 local function f() return value end value = 2 return f()",
+    keep_call_with_shadowed_free_variable(
+        "local function f() return value end local value = 2 return f()"
+    ) => "local function f() return value end local value = 2 return f()",
+    inline_shadowing_function(
+        "local function f() return 1 end do local function f() return g() end return f() end"
+    ) => "local function f() return 1 end do return g() end",
+    keep_shadowed_function_call(
+        "local function f() return 1 end do local function f(...) return ... end return f() end"
+    ) => "local function f() return 1 end do local function f(...) return ... end return f() end",
+    keep_call_with_effectful_argument_and_arithmetic_body(
+        "local function inc(n) return n + 1 end return inc(f())"
+    ) => "local function inc(n) return n + 1 end return inc(f())",
+    keep_call_with_effectful_argument_used_twice(
+        "local function square(n) return n * n end return square(f())"
+    ) => "local function square(n) return n * n end return square(f())",
+    keep_call_with_effectful_argument_unused(
+        "local function one(n) return 1 end return one(f())"
+    ) => "local function one(n) return 1 end return one(f())",
+    keep_call_with_table_argument_used_twice(
+        "local function same(t) return t == t end return same({})"
+    ) => "local function same(t) return t == t end return same({})",
+    keep_call_with_multiple_effectful_arguments(
+        "local function sub(a, b) return b - a end return sub(f(), g())"
+    ) => "local function sub(a, b) return b - a end return sub(f(), g())",
+    keep_call_with_effectful_argument_and_effectful_body(
+        "local function add(a) return g() + a end return add(f())"
+    ) => "local function add(a) return g() + a end return add(f())",
+    keep_call_with_effectful_argument_and_free_variable(
+        "local function add(a) return a + value end return add(f())"
+    ) => "local function add(a) return a + value end return add(f())",
+    keep_call_with_effectful_extra_argument(
+        "local function id(x) return x end return id(1, f())"
+    ) => "local function id(x) return x end return id(1, f())",
+    keep_call_with_multiple_values_for_missing_parameters(
+        "local function add(a, b) return a + b end return add(f())"
+    ) => "local function add(a, b) return a + b end return add(f())",
+    keep_method_call("local function f(n) return n end return obj:f(1)")
+        => "local function f(n) return n end return obj:f(1)",
+);
+
+test_rule!(
+    inline_functions_with_small_body,
+    InlineFunctions::default().with_max_body_size(2),
+    inline_small_body("local function id(x) return x end return id(1)") => "return 1",
+    keep_large_body("local function add(a, b) return a + b + 1 end return add(1, 2)")
+        => "local function add(a, b) return a + b + 1 end return add(1, 2)",
+);
+
+test_rule!(
+    inline_functions_with_one_call_site,
+    InlineFunctions::default().with_max_call_sites(1),
+    inline_single_call_site("local function id(x) return x end return id(1)") => "return 1",
+    keep_function_with_too_many_call_sites("local function id(x) return x end return id(1), id(2)")
+        => "local function id(x) return x end return id(1), id(2)",
+);
+
+test_rule_without_effects!(
+    InlineFunctions::default(),
+    file_using_setfenv("local function f(n) return n end setfenv(1, {}) return f(1)"),
+    function_without_calls("local function f(n) return n end"),
+    global_function("function f(n) return n end return f(1)"),
+    local_function_assignment("local f = function(n) return n end return f(1)"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'inline_functions',
+        max_body_size: 8,
+        max_call_sites: 2,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'inline_functions'").unwrap();
+}
@@ -0,0 +1,80 @@
+use darklua_core::rules::{DuplicateLocalSeverity, LintDuplicateLocals, Rule};
+
+use super::process_rules;
+
+test_rule_without_effects!(
+    LintDuplicateLocals::default(),
+    duplicate_in_same_statement("local a, a = 1, 2 return a"),
+    redeclared_local("local a = 1 local a = 2 return a"),
+    distinct_locals("local a, b = 1, 2 return a, b"),
+);
+
+fn process_with_error_severity(code: &str) -> Vec<String> {
+    let rule: Box<dyn Rule> =
+        Box::new(LintDuplicateLocals::default().with_severity(DuplicateLocalSeverity::Error));
+
+    process_rules([rule], None, code).err().unwrap_or_default()
+}
+
+#[test]
+fn error_severity_reports_duplicate_in_same_statement() {
+    pretty_assertions::assert_eq!(
+        process_with_error_severity("local a, b, a = 1, 2, 3\nreturn a, b"),
+        vec![
+            "error processing `src/test.lua` (lint_duplicate_locals [#0]): local `a` (line 1) \
+            is declared more than once in the same statement: only the last one is visible"
+                .to_owned()
+        ]
+    );
+}
+
+#[test]
+fn error_severity_reports_redeclared_local() {
+    pretty_assertions::assert_eq!(
+        process_with_error_severity("local value = 1\nprint(value)\nlocal value = 2\nreturn value"),
+        vec![
+            "error processing `src/test.lua` (lint_duplicate_locals [#0]): local `value` \
+            (line 3) shadows a local declared in the same block (line 1)"
+                .to_owned()
+        ]
+    );
+}
+
+#[test]
+fn error_severity_reports_every_duplicate() {
+    pretty_assertions::assert_eq!(
+        process_with_error_severity(
+            "local a, a = 1, 2\nlocal function f()\n  local b = 1\n  local b = 2\nend"
+        ),
+        vec![
+            "error processing `src/test.lua` (lint_duplicate_locals [#0]):\nlocal `a` (line 1) \
+            is declared more than once in the same statement: only the last one is visible\n\
+            local `b` (line 4) shadows a local declared in the same block (line 3)"
+                .to_owned()
+        ]
+    );
+}
+
+#[test]
+fn error_severity_without_duplicates_succeeds() {
+    pretty_assertions::assert_eq!(
+        process_with_error_severity("local a = 1 do local a = 2 end return a"),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'lint_duplicate_locals',
+        severity: 'error',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'lint_duplicate_locals'").unwrap();
+}
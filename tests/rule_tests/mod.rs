@@ -1,7 +1,9 @@
 pub(crate) use crate::utils::memory_resources;
+pub(crate) use crate::utils::process_rules;
 
 macro_rules! test_rule_with_generator {
     (
+        environment = $environment:expr,
         $rule:expr,
         $resources:expr,
         $generator:expr,
@@ -37,6 +39,7 @@ macro_rules! test_rule_with_generator {
 
             let context =
                 darklua_core::rules::ContextBuilder::new($test_file_name, &resources, $input)
+                    .with_environment($environment)
                     .build();
 
             $rule
@@ -61,6 +64,30 @@ macro_rules! test_rule_with_generator {
             }
         }
     };
+    (
+        $rule:expr,
+        $resources:expr,
+        $generator:expr,
+        $parser:expr,
+        $compare_with_tokens:expr,
+        $test_file_name:literal,
+        $name:ident,
+        $input:literal,
+        $output:literal
+    ) => {
+        test_rule_with_generator!(
+            environment = Default::default(),
+            $rule,
+            $resources,
+            $generator,
+            $parser,
+            $compare_with_tokens,
+            $test_file_name,
+            $name,
+            $input,
+            $output
+        );
+    };
     ($rule:expr, $resources:expr, $generator:expr, $test_file_name:literal, $name:ident, $input:literal, $output:literal) => {
         test_rule_with_generator!(
             $rule,
@@ -208,6 +235,7 @@ macro_rules! test_rule {
         $rule:expr,
         resources = $resources:expr,
         test_file_name = $test_file_name:literal,
+        environment = $environment:expr,
         $($name:ident ($input:literal) => $output:literal),* $(,)?
     ) => {
         paste::paste! {
@@ -217,9 +245,12 @@ macro_rules! test_rule {
 
         $(
             test_rule_with_generator!(
+                environment = $environment,
                 $rule,
                 $resources,
                 |_| darklua_core::generator::ReadableLuaGenerator::default(),
+                darklua_core::Parser::default(),
+                false,
                 $test_file_name,
                 $name,
                 $input,
@@ -234,9 +265,12 @@ macro_rules! test_rule {
 
         $(
             test_rule_with_generator!(
+                environment = $environment,
                 $rule,
                 $resources,
                 |_| darklua_core::generator::DenseLuaGenerator::default(),
+                darklua_core::Parser::default(),
+                false,
                 $test_file_name,
                 $name,
                 $input,
@@ -251,6 +285,7 @@ macro_rules! test_rule {
 
         $(
             test_rule_with_generator!(
+                environment = $environment,
                 $rule,
                 $resources,
                 |input| darklua_core::generator::TokenBasedLuaGenerator::new(input),
@@ -268,6 +303,39 @@ macro_rules! test_rule {
 
     };
 
+    (
+        $rule_name:ident,
+        $rule:expr,
+        resources = $resources:expr,
+        test_file_name = $test_file_name:literal,
+        $($name:ident ($input:literal) => $output:literal),* $(,)?
+    ) => {
+        test_rule!(
+            $rule_name,
+            $rule,
+            resources = $resources,
+            test_file_name = $test_file_name,
+            environment = Default::default(),
+            $( $name ($input) => $output, )*
+        );
+    };
+
+    (
+        $rule_name:ident,
+        $rule:expr,
+        environment = $environment:expr,
+        $($name:ident ($input:literal) => $output:literal),* $(,)?
+    ) => {
+        test_rule!(
+            $rule_name,
+            $rule,
+            resources = darklua_core::Resources::from_memory(),
+            test_file_name = "src/test.lua",
+            environment = $environment,
+            $( $name ($input) => $output, )*
+        );
+    };
+
     (
         $rule_name:ident,
         $rule:expr,
@@ -311,6 +379,13 @@ macro_rules! test_rule {
 
 macro_rules! test_rule_without_effects {
     ($rule:expr, $($name:ident ($input:literal)),* $(,)?) => {
+        test_rule_without_effects!(
+            $rule,
+            environment = Default::default(),
+            $( $name ($input), )*
+        );
+    };
+    ($rule:expr, environment = $environment:expr, $($name:ident ($input:literal)),* $(,)?) => {
         $(
             #[test]
             fn $name() {
@@ -322,7 +397,9 @@ macro_rules! test_rule_without_effects {
                 let mut block = $crate::utils::parse_input($input);
                 let expect_block = block.clone();
                 let resources = darklua_core::Resources::from_memory();
-                let context = darklua_core::rules::ContextBuilder::new(".", &resources, $input).build();
+                let context = darklua_core::rules::ContextBuilder::new(".", &resources, $input)
+                    .with_environment($environment)
+                    .build();
 
                 $rule.process(&mut block, &context)
                     .expect("rule should succeed");
@@ -415,19 +492,72 @@ macro_rules! test_rule_snapshot {
     };
 }
 
+/// Applies a rule that only modifies trivia with the token based generator, then verifies
+/// that the generated code changed while its syntax tree (without the tokens) did not.
+#[allow(dead_code)]
+fn assert_only_trivia_changed(rule: &dyn darklua_core::rules::Rule, input: &str) {
+    use darklua_core::generator::{LuaGenerator, TokenBasedLuaGenerator};
+
+    let mut block = darklua_core::Parser::default()
+        .preserve_tokens()
+        .parse(input)
+        .expect("unable to parse input");
+
+    let resources = darklua_core::Resources::from_memory();
+    let context =
+        darklua_core::rules::ContextBuilder::new("src/test.lua", &resources, input).build();
+
+    rule.process(&mut block, &context)
+        .expect("rule should succeed");
+
+    let mut generator = TokenBasedLuaGenerator::new(input);
+    generator.write_block(&block);
+    let lua_code = generator.into_string();
+
+    assert_ne!(input, lua_code, "expected trivia to change");
+    pretty_assertions::assert_eq!(
+        crate::utils::parse_input(input),
+        crate::utils::parse_input(&lua_code),
+        "\nexpected only trivia to change, but received:\n{}",
+        lua_code
+    );
+}
+
 mod append_text_comment;
+mod check_assignment_arity;
 mod compute_expression;
+mod conditional_compilation;
+mod convert_if_to_lookup;
 mod convert_index_to_field;
+mod convert_numeric_for_to_while;
 mod convert_require;
+mod convert_while_to_numeric_for;
+mod deduplicate_blocks;
+mod extract_callbacks;
+mod extract_strings;
+mod factor_common_branch_code;
 mod filter_early_return;
+mod fix_global_leaks;
+mod flatten_closures;
+mod format_call_chains;
+mod freeze_constant_tables;
 mod group_local_assignment;
+mod hoist_loop_invariants;
 mod inject_value;
+mod inline_constants;
+mod inline_functions;
+mod lint_duplicate_locals;
 mod no_local_function;
+mod normalize_comment_style;
+mod normalize_semicolons;
+mod normalize_string_escapes;
+mod obfuscate_identifiers;
 mod remove_assertions;
 mod remove_call_parens;
 mod remove_comments;
 mod remove_compound_assignment;
 mod remove_continue;
+mod remove_dead_stores;
 mod remove_debug_profiling;
 mod remove_empty_do;
 mod remove_floor_division;
@@ -435,8 +565,19 @@ mod remove_if_expression;
 mod remove_interpolated_string;
 mod remove_method_definition;
 mod remove_nil_declaration;
+mod remove_trailing_nil_values;
 mod remove_types;
 mod remove_unused_if_branch;
 mod remove_unused_variable;
 mod remove_unused_while;
 mod rename_variables;
+mod rewrite;
+mod rewrite_env_access;
+mod simplify_boolean_comparisons;
+mod simplify_parameter_defaults;
+mod sort_local_functions;
+mod sort_table_entries;
+mod transliterate_identifiers;
+mod trim_trailing_whitespace;
+mod validate_limits;
+mod wrap_module_return;
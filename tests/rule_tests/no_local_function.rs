@@ -16,6 +16,30 @@ test_rule_without_effects!(
     two_local_using_the_other("local function foo() foo() end")
 );
 
+#[test]
+fn keeps_function_attributes() {
+    use darklua_core::{
+        generator::{DenseLuaGenerator, LuaGenerator},
+        rules::ContextBuilder,
+        Parser, Resources,
+    };
+
+    let code = "@native local function foo() end";
+    let mut block = Parser::default().parse(code).unwrap();
+
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new(".", &resources, code).build();
+
+    ConvertLocalFunctionToAssign::default()
+        .process(&mut block, &context)
+        .expect("rule should succeed");
+
+    let mut generator = DenseLuaGenerator::default();
+    generator.write_block(&block);
+
+    pretty_assertions::assert_eq!(generator.into_string(), "local foo=@native function()end");
+}
+
 #[test]
 fn deserialize_from_object_notation() {
     json5::from_str::<Box<dyn Rule>>(
@@ -0,0 +1,53 @@
+use darklua_core::rules::{NormalizeCommentStyle, Rule};
+
+use super::assert_only_trivia_changed;
+
+test_rule_with_tokens!(
+    normalize_comment_style,
+    NormalizeCommentStyle::default(),
+    convert_single_line_block_comment("local a = 1 --[[ note ]]\nreturn a") => "local a = 1 -- note\nreturn a",
+    convert_block_comment_above_statement("--[[ note ]]\nreturn a") => "-- note\nreturn a",
+    convert_block_comment_with_equal_signs("return a --[=[ a ]] b ]=]\n") => "return a -- a ]] b\n",
+    convert_empty_block_comment("return a --[[]]\n") => "return a --\n",
+    keep_block_comment_followed_by_code("local a = --[[ note ]] 1\nreturn a") => "local a = --[[ note ]] 1\nreturn a",
+    keep_multiline_block_comment("--[[ a\nb ]]\nreturn a") => "--[[ a\nb ]]\nreturn a",
+    keep_block_comment_starting_with_long_bracket("--[[[[ a ]]\nreturn a") => "--[[[[ a ]]\nreturn a",
+    trim_line_comment("local a = 1 -- note  \nreturn a") => "local a = 1 -- note\nreturn a",
+    trim_converted_comment("--[[ note   ]]\nreturn a") => "-- note\nreturn a",
+    trim_lines_of_multiline_block_comment("--[[ a  \nb\t\n]]\nreturn a") => "--[[ a\nb\n]]\nreturn a",
+    keep_line_comment("-- note\nreturn a") => "-- note\nreturn a",
+);
+
+#[test]
+fn only_trivia_changed_when_converting_comments() {
+    assert_only_trivia_changed(
+        &NormalizeCommentStyle::default(),
+        "--[[ header ]]\nlocal a = 1 --[[ one ]]\nlocal function f() -- two  \n\treturn a --[==[ three ]==]\nend\nreturn f()",
+    );
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'normalize_comment_style',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'normalize_comment_style'").unwrap();
+}
+
+#[test]
+fn serialize_round_trip() {
+    let rule: Box<dyn Rule> = json5::from_str("'normalize_comment_style'").unwrap();
+
+    let serialized = serde_json::to_string(&rule).unwrap();
+    let deserialized: Box<dyn Rule> = json5::from_str(&serialized).unwrap();
+
+    pretty_assertions::assert_eq!(deserialized.get_name(), "normalize_comment_style");
+    pretty_assertions::assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+}
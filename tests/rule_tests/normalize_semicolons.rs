@@ -0,0 +1,82 @@
+use darklua_core::rules::{NormalizeSemicolons, Rule, SemicolonStyle};
+
+test_rule_with_tokens!(
+    normalize_semicolons_never,
+    NormalizeSemicolons::default(),
+    remove_semicolon_after_call("print('a');") => "print('a')",
+    remove_semicolon_after_local_assign("local a = 1; local b = 2;") => "local a = 1 local b = 2",
+    remove_semicolon_after_return("return a;") => "return a",
+    remove_semicolon_in_nested_block("do print(a); end") => "do print(a) end",
+    keep_comment_attached_to_semicolon("local a = 1; -- comment\nreturn a") => "local a = 1 -- comment\nreturn a",
+    keep_semicolon_before_parenthese("local a = b; (f)()") => "local a = b; (f)()",
+);
+
+test_rule_with_tokens!(
+    normalize_semicolons_always,
+    NormalizeSemicolons::default().with_style(SemicolonStyle::Always),
+    add_semicolon_after_call("print('a')") => "print('a');",
+    add_semicolon_after_each_statement("local a = 1\nlocal b = 2") => "local a = 1;\nlocal b = 2;",
+    add_semicolon_after_return("return a") => "return a;",
+    add_semicolon_after_break("while true do break end") => "while true do break; end;",
+    keep_existing_semicolon("print('a');") => "print('a');",
+    add_semicolon_in_function("local function foo() print(a) end") => "local function foo() print(a); end;",
+);
+
+test_rule_with_tokens!(
+    normalize_semicolons_only_after_function_calls,
+    NormalizeSemicolons::default().with_style(SemicolonStyle::OnlyAfterFunctionCalls),
+    add_semicolon_after_call("print('a')") => "print('a');",
+    add_semicolon_after_method_call("object:method()") => "object:method();",
+    remove_semicolon_after_local_assign("local a = 1; print(a)") => "local a = 1 print(a);",
+    remove_semicolon_after_return("print(a) return a;") => "print(a); return a",
+);
+
+test_rule!(
+    normalize_semicolons_always_without_tokens,
+    NormalizeSemicolons::default().with_style(SemicolonStyle::Always),
+    add_semicolon_after_call("print('a')") => "print('a');",
+    add_semicolon_after_return("local a = 1 return a") => "local a = 1; return a;",
+);
+
+#[test]
+fn remove_spaces_keeps_semicolons() {
+    use darklua_core::{
+        generator::{LuaGenerator, TokenBasedLuaGenerator},
+        rules::{ContextBuilder, RemoveSpaces},
+        Parser, Resources,
+    };
+
+    let code = "local a = b ; (f)() ; print(a)";
+    let mut block = Parser::default().preserve_tokens().parse(code).unwrap();
+
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new(".", &resources, code).build();
+
+    NormalizeSemicolons::default()
+        .process(&mut block, &context)
+        .expect("rule should succeed");
+    RemoveSpaces::default()
+        .process(&mut block, &context)
+        .expect("rule should succeed");
+
+    let mut generator = TokenBasedLuaGenerator::new(code);
+    generator.write_block(&block);
+
+    pretty_assertions::assert_eq!(generator.into_string(), "local a=b;(f)()print(a)");
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'normalize_semicolons',
+        style: 'always',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'normalize_semicolons'").unwrap();
+}
@@ -0,0 +1,25 @@
+use darklua_core::rules::{NormalizeStringEscapes, Rule};
+
+test_rule!(
+    normalize_string_escapes,
+    NormalizeStringEscapes::default(),
+    hex_escape(r"return '\x41\x1b'") => r"return 'A\27'",
+    unicode_escape(r"return '\u{e9}'") => r"return '\195\169'",
+    skip_whitespace(r"return 'a\z   b'") => "return 'ab'",
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'normalize_string_escapes',
+        prefer_hex: true,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'normalize_string_escapes'").unwrap();
+}
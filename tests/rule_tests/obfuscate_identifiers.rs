@@ -0,0 +1,65 @@
+use darklua_core::rules::{ObfuscateIdentifiers, Rule};
+
+fn five_mappings() -> ObfuscateIdentifiers {
+    ObfuscateIdentifiers::default()
+        .with_mapping("count", "c")
+        .with_mapping("value", "v")
+        .with_mapping("compute", "f")
+        .with_mapping("index", "i")
+        .with_mapping("Module", "M")
+}
+
+test_rule!(
+    obfuscate_identifiers,
+    five_mappings(),
+    local_variable("local count = 0 return count") => "local c = 0 return c",
+    local_function("local function compute() end compute()") => "local function f() end f()",
+    function_parameters("local function compute(value, index) return value[index] end")
+        => "local function f(v, i) return v[i] end",
+    numeric_for("for index = 1, 10 do print(index) end") => "for i = 1, 10 do print(i) end",
+    generic_for("for index, value in pairs(t) do print(index, value) end")
+        => "for i, v in pairs(t) do print(i, v) end",
+    all_five_mappings(
+        "local Module = {} local count = 0 function Module.compute(value) for index = 1, value do count = count + index end return count end return Module"
+    ) => "local M = {} local c = 0 function M.compute(v) for i = 1, v do c = c + i end return c end return M",
+    keep_unmapped_local("local other = count return other") => "local other = count return other",
+    keep_global_variable("count = 1 return count") => "count = 1 return count",
+    keep_table_fields("local t = { count = 1 } return t.count") => "local t = { count = 1 } return t.count",
+    local_value_referencing_shadowed_global("local count = count return count")
+        => "local c = count return c",
+    nested_scopes("local count = 1 do local count = 2 print(count) end return count")
+        => "local c = 1 do local c = 2 print(c) end return c",
+);
+
+test_rule!(
+    obfuscate_identifiers_with_globals,
+    five_mappings().with_include_globals(true),
+    rename_global_variable("count = 1 return count") => "c = 1 return c",
+    rename_global_function("function compute() end compute()") => "function f() end f()",
+    rename_global_argument("print(value)") => "print(v)",
+);
+
+test_rule_without_effects!(
+    ObfuscateIdentifiers::default(),
+    without_mappings("local count = 0 return count"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'obfuscate_identifiers',
+        mappings: {
+            count: 'c',
+            value: 'v',
+        },
+        include_globals: true,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'obfuscate_identifiers'").unwrap();
+}
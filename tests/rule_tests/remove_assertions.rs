@@ -38,6 +38,24 @@ test_rule_without_effects!(
     assert_with_method_call("assert:oops(condition)"),
 );
 
+test_rule_without_effects!(
+    RemoveAssertions::default(),
+    assert_in_env_local_scope("local _ENV = {} assert(condition)"),
+    assert_in_env_parameter_scope("local function f(_ENV) assert(condition) end"),
+);
+
+test_rule!(
+    remove_assertions_assume_no_env_manipulation,
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'remove_assertions',
+        assume_no_env_manipulation: true,
+    }"#,
+    )
+    .unwrap(),
+    remove_in_env_local_scope("local _ENV = {} assert(condition)") => "local _ENV = {} do end",
+);
+
 #[test]
 fn deserialize_from_object_notation() {
     json5::from_str::<Box<dyn Rule>>(
@@ -1,4 +1,9 @@
-use darklua_core::rules::{RemoveFunctionCallParens, Rule};
+use darklua_core::{
+    generator::{LuaGenerator, TokenBasedLuaGenerator},
+    nodes::{Expression, Statement},
+    rules::{ContextBuilder, RemoveFunctionCallParens, Rewrite, Rule},
+    Parser, Resources,
+};
 
 test_rule!(
     remove_function_call_parens,
@@ -9,6 +14,13 @@ test_rule!(
     call_expression_with_empty_table("return foo({})") => "return foo{}"
 );
 
+test_rule_with_tokens!(
+    remove_function_call_parens_with_tokens,
+    RemoveFunctionCallParens::default(),
+    keep_comment_after_string("foo('bar') -- comment\nfoo('baz')") => "foo'bar' -- comment\nfoo'baz'",
+    keep_comment_after_table("foo({}) --[[ comment ]] return") => "foo{} --[[ comment ]] return",
+);
+
 test_rule_without_effects!(
     RemoveFunctionCallParens::default(),
     two_strings("foo('bar', 'baz')"),
@@ -16,6 +28,81 @@ test_rule_without_effects!(
     variable_parameter("foo(bar)")
 );
 
+fn remove_parens_then_add_argument(code: &str, add_argument: impl Fn(&mut Statement)) -> String {
+    let mut block = Parser::default().preserve_tokens().parse(code).unwrap();
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("test.lua", &resources, code).build();
+
+    RemoveFunctionCallParens::default()
+        .process(&mut block, &context)
+        .unwrap();
+
+    block.iter_mut_statements().for_each(add_argument);
+
+    let mut generator = TokenBasedLuaGenerator::new(code);
+    generator.write_block(&block);
+    let generated = generator.into_string();
+
+    Parser::default()
+        .parse(&generated)
+        .unwrap_or_else(|error| panic!("unable to parse `{}`: {}", generated, error));
+
+    generated
+}
+
+fn push_true_argument(statement: &mut Statement) {
+    if let Statement::Call(call) = statement {
+        let arguments = call
+            .get_arguments()
+            .clone()
+            .with_argument(Expression::from(true));
+        call.set_arguments(arguments);
+    }
+}
+
+#[test]
+fn add_argument_to_string_call_after_removing_parens() {
+    pretty_assertions::assert_eq!(
+        remove_parens_then_add_argument("foo('bar')\nfoo('baz')\n", push_true_argument),
+        "foo('bar',true)\nfoo('baz',true)\n"
+    );
+}
+
+#[test]
+fn add_argument_to_table_call_after_removing_parens() {
+    pretty_assertions::assert_eq!(
+        remove_parens_then_add_argument("foo({ 1 }) -- comment\nfoo({})\n", push_true_argument),
+        "foo({ 1 },true) -- comment\nfoo({},true)\n"
+    );
+}
+
+#[test]
+fn rewrite_adding_argument_after_removing_parens() {
+    let code = "foo('bar')\nfoo({})\n";
+    let mut block = Parser::default().preserve_tokens().parse(code).unwrap();
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("test.lua", &resources, code).build();
+
+    RemoveFunctionCallParens::default()
+        .process(&mut block, &context)
+        .unwrap();
+    Rewrite::default()
+        .with_rewrite("foo($x)", "foo($x, true)")
+        .process(&mut block, &context)
+        .unwrap();
+
+    let mut generator = TokenBasedLuaGenerator::new(code);
+    generator.write_block(&block);
+    let generated = generator.into_string();
+
+    pretty_assertions::assert_eq!(
+        Parser::default().parse(&generated).unwrap(),
+        Parser::default()
+            .parse("foo('bar', true) foo({}, true)")
+            .unwrap()
+    );
+}
+
 #[test]
 fn deserialize_from_object_notation() {
     json5::from_str::<Box<dyn Rule>>(
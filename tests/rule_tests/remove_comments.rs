@@ -44,6 +44,12 @@ test_remove_comments_rule!(
     empty_do("do end -- comment") => "do end ",
     before_empty_do("-- comment\ndo end") => "\ndo end",
     comment_after_semicolon("print('hello');-- bye") => "print('hello');",
+    comment_in_generic_parameters("local function identity<T --[[ type ]]>(value: T): T return value end")
+        => "local function identity<T >(value: T): T return value end",
+    comments_in_type_declaration("export type Result<T --[[ ok ]], E> = { ok: T --[[ value ]] } | { err: E }")
+        => "export type Result<T , E> = { ok: T  } | { err: E }",
+    comments_in_type_annotations("local value: { --[[ values ]] number } = {}")
+        => "local value: {  number } = {}",
 );
 
 test_remove_comments_rule!(
@@ -0,0 +1,95 @@
+use darklua_core::rules::{RemoveDeadStores, Rule};
+
+test_rule!(
+    remove_dead_stores,
+    RemoveDeadStores::default(),
+    remove_overwritten_initializer("local a = 1 a = 2 return a") => "local a a = 2 return a",
+    remove_overwritten_assignment("local a a = 1 a = 2 return a") => "local a a = 2 return a",
+    remove_assignment_at_end_of_block("local a = 1 print(a) a = 2") => "local a = 1 print(a)",
+    remove_assignment_before_return_without_read(
+        "local a = 1 print(a) a = 2 return true"
+    ) => "local a = 1 print(a) return true",
+    keep_call_of_removed_initializer(
+        "local a = f() a = 1 return a"
+    ) => "f() local a a = 1 return a",
+    keep_call_of_removed_assignment(
+        "local a = 1 print(a) a = f() a = 2 return a"
+    ) => "local a = 1 print(a) f() a = 2 return a",
+    remove_chained_dead_stores(
+        "local a = 1 a = not a a = 3 return a"
+    ) => "local a a = 3 return a",
+    remove_initializer_shadowed_by_local(
+        "local a = 1 local a = 2 return a"
+    ) => "local a local a = 2 return a",
+    remove_initializer_when_nested_block_reads_shadowing_local(
+        "local a = 1 do local a = 2 print(a) end a = 3 return a"
+    ) => "local a do local a = 2 print(a) end a = 3 return a",
+    remove_initializer_when_function_parameter_shadows_variable(
+        "local a = 1 local function f(a) return a end a = 2 return f(a)"
+    ) => "local a local function f(a) return a end a = 2 return f(a)",
+    remove_trailing_initializer_of_multiple_locals(
+        "local a, b = 1, 2 b = 3 return a, b"
+    ) => "local a, b = 1 b = 3 return a, b",
+    remove_trailing_slot_of_multiple_assignment(
+        "local a, b a, b = 1, 2 b = 3 return a, b"
+    ) => "local a, b a = 1 b = 3 return a, b",
+    remove_middle_slot_of_multiple_assignment(
+        "local a, b, c a, b, c = 1, 2, 3 b = 4 return a, b, c"
+    ) => "local a, b, c a, c = 1, 3 b = 4 return a, b, c",
+    remove_all_slots_of_multiple_assignment_and_keep_call(
+        "local a, b a, b = f() a, b = 1, 2 return a, b"
+    ) => "local a, b f() a, b = 1, 2 return a, b",
+    remove_dead_store_in_loop_body_declaring_variable(
+        "while c() do local a = f() a = 1 print(a) end"
+    ) => "while c() do f() local a a = 1 print(a) end",
+    remove_dead_slot_of_swap(
+        "local x, y = 1, 2 x, y = y, x return x"
+    ) => "local x, y = 1, 2 x = y return x",
+    remove_dead_store_before_break(
+        "while c() do local a = 1 if d() then break end a = 2 print(a) end"
+    ) => "while c() do local a if d() then break end a = 2 print(a) end",
+);
+
+test_rule_without_effects!(
+    RemoveDeadStores::default(),
+    file_using_setfenv("local a = 1 a = 2 setfenv(1, {}) return a"),
+    keep_global_assignments("a = 1 a = 2"),
+    keep_swap("local x, y = 1, 2 x, y = y, x return x, y"),
+    keep_store_read_in_one_branch("local a = 1 if c then print(a) else a = 2 end a = 3 return a"),
+    keep_store_read_in_else_branch("local a = 1 if c then a = 2 else print(a) end a = 3 return a"),
+    keep_store_read_on_next_loop_iteration("local a = 0 while c() do print(a) a = a + 1 end"),
+    keep_store_when_loop_may_not_run("local a = 0 for i = 1, n do a = i end return a"),
+    keep_store_read_by_nested_return("local a = 1 if c then return a end a = 2 return a"),
+    keep_store_captured_by_local_function(
+        "local a = 1 local function f() return a end a = 2 f() a = 3 return a"
+    ),
+    keep_store_captured_by_function_expression(
+        "local a = 1 local get = function() return a end a = 2 return get()"
+    ),
+    keep_store_captured_by_function_statement(
+        "local a = 1 function get() return a end a = 2 return get()"
+    ),
+    keep_store_read_by_compound_assignment("local a = 1 a += 1 return a"),
+    keep_store_read_in_field_assignment("local a = {} a.x = 1 a = {} return a"),
+    keep_store_read_in_index_of_assignment("local a, t = 1, {} t[a], a = 1, 2 return a, t"),
+    keep_store_read_in_repeat_condition("repeat local a = 1 a = f() until a"),
+    keep_store_of_variable_assigned_twice("local a a, a = 1, 2 a = 3 return a"),
+    keep_non_trailing_initializer_of_multiple_locals("local a, b = 1, 2 a = 3 return a, b"),
+    keep_multiple_values_assigned_from_call("local a, b a, b = f() b = 1 return a, b"),
+    keep_environment_variable("local _ENV = {} _ENV = {} return x"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'remove_dead_stores',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'remove_dead_stores'").unwrap();
+}
@@ -27,6 +27,7 @@ test_rule!(
 test_rule_without_effects!(
     RemoveDebugProfiling::default(),
     debug_library_identifier_used("local debug = nil debug.profilebegin('label')"),
+    debug_in_env_local_scope("local _ENV = {} debug.profilebegin('label')"),
 );
 
 #[test]
@@ -7,7 +7,29 @@ test_rule!(
     empty_do_statement_in_numeric_for("for i=a, b do do end end") => "for i=a, b do end",
     empty_do_statements_in_local_function("local function foo() do end do do end end end")
         => "local function foo() end",
-    empty_do_statement_in_generic_for("for k,v in pairs({}) do do end end") => "for k,v in pairs({}) do end"
+    empty_do_statement_in_generic_for("for k,v in pairs({}) do do end end") => "for k,v in pairs({}) do end",
+    nested_empty_do_statements("do do end end") => "",
+    deeply_nested_empty_do_statements("do do do end end do end end return") => "return",
+    nested_empty_do_statements_in_if("if a then do do end end end") => "if a then end",
+    empty_do_statement_with_comment("do -- note\nend\nprint(1)") => "print(1)",
+    nested_empty_do_statement_with_comment("do do --[[ note ]] end end return 1") => "return 1",
+);
+
+test_rule_with_tokens!(
+    remove_empty_do_preserve_comments,
+    RemoveEmptyDo::default(),
+    comment_moved_to_next_statement("do -- note\nend\nprint(1)") => "-- note\n\nprint(1)",
+    comments_moved_before_next_statement_comments("do end -- a\n-- b\nprint(1)")
+        => "-- a\n-- b\nprint(1)",
+    comment_moved_to_end_of_block("print(1)\ndo -- note\nend") => "print(1)\n-- note\n",
+    nested_comment_moved_to_return("do\n    do -- inner\n    end\nend\nreturn 1")
+        => "-- inner\n\n\n\nreturn 1",
+);
+
+test_rule_with_tokens!(
+    remove_empty_do_without_preserve_comments,
+    RemoveEmptyDo::default().with_preserve_comments(false),
+    comment_removed("do -- note\nend\nprint(1)") => "\n\nprint(1)",
 );
 
 #[test]
@@ -20,6 +42,17 @@ fn deserialize_from_object_notation() {
     .unwrap();
 }
 
+#[test]
+fn deserialize_with_preserve_comments() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'remove_empty_do',
+        preserve_comments: false,
+    }"#,
+    )
+    .unwrap();
+}
+
 #[test]
 fn deserialize_from_string() {
     json5::from_str::<Box<dyn Rule>>("'remove_empty_do'").unwrap();
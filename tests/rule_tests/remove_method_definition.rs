@@ -1,4 +1,13 @@
-use darklua_core::rules::{RemoveMethodDefinition, Rule};
+use darklua_core::{
+    generator::{LuaGenerator, ReadableLuaGenerator},
+    rules::{
+        ContextBuilder, ObfuscateIdentifiers, RemoveMethodDefinition, RenameVariables, Rule,
+        TransliterateIdentifiers, TransliterationStrategy,
+    },
+    Parser, Resources,
+};
+
+use super::process_rules;
 
 test_rule!(
     remove_method_definition,
@@ -8,14 +17,131 @@ test_rule!(
     name_with_field_and_method("function foo.bar:baz() end") => "function foo.bar.baz(self) end",
     with_arguments("function foo:bar(a, b, c) end") => "function foo.bar(self, a, b, c) end",
     variadic_function("function foo:bar(...) end") => "function foo.bar(self, ...) end",
-    variadic_with_arguments("function foo:bar(a, b, c, ...) end") => "function foo.bar(self, a, b, c, ...) end"
+    variadic_with_arguments("function foo:bar(a, b, c, ...) end") => "function foo.bar(self, a, b, c, ...) end",
+    with_attribute("@native function foo:bar() end") => "@native function foo.bar(self) end",
+    keep_method_calls_to_converted_method(
+        "local foo = {} function foo:bar(n) return self.n + n end return foo:bar(1)"
+    ) => "local foo = {} function foo.bar(self, n) return self.n + n end return foo:bar(1)",
+    nested_self_parameter(
+        "function foo:bar() return function(self) return self end end"
+    ) => "function foo.bar(self) return function(self) return self end end",
+    nested_method_definition(
+        "function foo:bar() local helper = {} function helper:run() return self end return helper end"
+    ) => "function foo.bar(self) local helper = {} function helper.run(self) return self end return helper end"
+);
+
+test_rule!(
+    remove_method_definition_without_preserving_method_calls,
+    RemoveMethodDefinition::default().with_preserve_method_calls(false),
+    convert_method_call_on_local(
+        "local foo = {} function foo:bar(n) end foo:bar(1)"
+    ) => "local foo = {} function foo.bar(self, n) end foo.bar(foo, 1)",
+    convert_method_call_without_arguments(
+        "local foo = {} function foo:bar() end return foo:bar()"
+    ) => "local foo = {} function foo.bar(self) end return foo.bar(foo)",
+    convert_method_call_with_table_argument(
+        "local foo = {} function foo:bar(t) end foo:bar { 1 }"
+    ) => "local foo = {} function foo.bar(self, t) end foo.bar(foo, { 1 })",
+    convert_method_call_with_string_argument(
+        "local foo = {} function foo:bar(s) end foo:bar 'abc'"
+    ) => "local foo = {} function foo.bar(self, s) end foo.bar(foo, 'abc')",
+    convert_method_call_inside_method(
+        "local foo = {} function foo:a() end function foo:b() foo:a() end"
+    ) => "local foo = {} function foo.a(self) end function foo.b(self) foo.a(foo) end"
+);
+
+test_rule!(
+    remove_method_definition_keep_other_method_calls,
+    RemoveMethodDefinition::default().with_preserve_method_calls(false),
+    keep_method_call_on_global(
+        "function foo:bar() end foo:bar()"
+    ) => "function foo.bar(self) end foo:bar()",
+    keep_method_call_to_other_method(
+        "local foo = {} function foo:bar() end foo:baz()"
+    ) => "local foo = {} function foo.bar(self) end foo:baz()",
+    keep_method_call_on_field(
+        "local foo = { bar = {} } function foo.bar:baz() end foo.bar:baz()"
+    ) => "local foo = { bar = {} } function foo.bar.baz(self) end foo.bar:baz()",
+    keep_method_call_on_other_table(
+        "local foo, other = {}, {} function foo:bar() end other:bar()"
+    ) => "local foo, other = {}, {} function foo.bar(self) end other:bar()"
 );
 
+fn process_with_rename(code: &str) -> String {
+    let remove_method: Box<dyn Rule> = Box::<RemoveMethodDefinition>::default();
+    let rename: Box<dyn Rule> = Box::new(RenameVariables::new(Vec::new()));
+
+    process_rules([remove_method, rename], None, code).unwrap()
+}
+
+#[test]
+fn rename_variables_renames_converted_self_parameter() {
+    pretty_assertions::assert_eq!(
+        process_with_rename(
+            "local Counter = {} function Counter:add(n) self.value = self.value + n end return Counter"
+        ),
+        "local a = {} function a.add(b,c) b.value = b.value + c end return a"
+    );
+}
+
+#[test]
+fn transliterate_identifiers_does_not_generate_converted_self() {
+    let code = "function Counter:add(sélf) return self.value + sélf end";
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("test.lua", &resources, code).build();
+    let mut block = Parser::default()
+        .allow_utf8_identifiers()
+        .parse(code)
+        .unwrap();
+
+    RemoveMethodDefinition::default()
+        .process(&mut block, &context)
+        .unwrap();
+    TransliterateIdentifiers::default()
+        .with_strategy(TransliterationStrategy::Ascii)
+        .process(&mut block, &context)
+        .unwrap();
+
+    let mut generator = ReadableLuaGenerator::default();
+    generator.write_block(&block);
+
+    pretty_assertions::assert_eq!(
+        Parser::default().parse(&generator.into_string()).unwrap(),
+        Parser::default()
+            .parse("function Counter.add(self, self_2) return self.value + self_2 end")
+            .unwrap()
+    );
+}
+
+#[test]
+fn obfuscate_identifiers_does_not_rename_to_converted_self() {
+    let remove_method: Box<dyn Rule> = Box::<RemoveMethodDefinition>::default();
+    let obfuscate: Box<dyn Rule> =
+        Box::new(ObfuscateIdentifiers::default().with_mapping("other", "self"));
+
+    let errors = process_rules(
+        [remove_method, obfuscate],
+        None,
+        "local Counter = {} function Counter:add(other) return self.value + other end",
+    )
+    .unwrap_err();
+
+    assert!(
+        errors
+            .iter()
+            .any(|error| error
+                .contains("unable to rename `other` to `self` because `self` is reserved")),
+        "unexpected errors: {:?}",
+        errors
+    );
+}
+
 #[test]
 fn deserialize_from_object_notation() {
     json5::from_str::<Box<dyn Rule>>(
         r#"{
         rule: 'remove_method_definition',
+        preserve_method_calls: false,
     }"#,
     )
     .unwrap();
@@ -20,6 +20,24 @@ test_rule!(
     assign_to_nil_and_call_and_false("local a, b, c = nil, call(), false") => "local b, c, a = call(), false",
     // the rule may trim unnecessary expressions in declarations
     assign_to_call_and_true_and_variable("local a = call(), true, var") => "local a = call()",
+    assign_variable_to_true_and_nil("a, b = true, nil") => "a, b = true",
+    assign_variables_to_nil_and_nil("a, b = nil, nil") => "a, b = nil",
+    assign_variable_to_true_and_extra_nil("a = true, nil") => "a = true",
+    assign_field_to_true_and_nil("object.a, b = true, nil") => "object.a, b = true",
+    assign_variables_to_call_and_nil("a, b = call(), nil") => "a, b = (call())",
+    assign_variables_to_variadic_args_and_nil("a, b, c = ..., nil, nil") => "a, b, c = (...)",
+    assign_variable_to_call_and_extra_nil("a = call(), nil") => "a = call()",
+    table_with_nil_field("return { a = nil }") => "return {}",
+    table_with_nil_field_and_other_field("return { a = 1, b = nil }") => "return { a = 1 }",
+    table_with_nil_string_index("return { ['a'] = nil, b = true }") => "return { b = true }",
+    table_with_nil_boolean_index("return { [true] = nil }") => "return {}",
+    table_with_nil_field_before_same_field("return { a = nil, a = true }") => "return { a = true }",
+    table_with_nil_field_and_array_values("return { 1, 2, a = nil }") => "return { 1, 2 }",
+    table_with_nil_field_and_trailing_separator("return { a = 1, b = nil, }") => "return { a = 1, }",
+    table_with_nil_first_field("return { b = nil, a = 1 }") => "return { a = 1 }",
+    table_with_nested_nil_field("return { a = { b = nil } }") => "return { a = {} }",
+    table_with_nil_field_before_call_value("return { a = nil, f() }") => "return { f() }",
+    table_with_parenthesized_call_value_and_nil_field("return { (f()), a = nil }") => "return { (f()) }",
 );
 
 test_rule_without_effects!(
@@ -27,6 +45,21 @@ test_rule_without_effects!(
     assign_to_true("local a = true"),
     assign_to_nil_and_extra_call("local a = nil, call()"),
     assign_to_nil_and_extract_varargs("local a, b, c = nil, ..."),
+    assign_variable_to_nil("a = nil"),
+    assign_variables_to_nil_and_true("a, b = nil, true"),
+    assign_variables_to_true_nil_and_false("a, b, c = true, nil, false"),
+    table_with_nil_array_value("return { nil }"),
+    table_with_nil_array_value_before_value("return { 1, nil, 3 }"),
+    table_with_nil_numeric_index_and_array_values("return { 1, [2] = nil }"),
+    table_with_nil_unknown_index("return { [key] = nil }"),
+    table_with_nil_index_with_side_effects("return { [call()] = nil }"),
+    table_with_nil_index_as_nil("return { [nil] = nil }"),
+    table_with_nil_field_after_same_field("return { a = true, a = nil }"),
+    table_with_nil_field_after_same_string_index("return { ['a'] = true, a = nil }"),
+    table_with_nil_field_and_unknown_index("return { [key] = true, a = nil }"),
+    table_with_call_value_and_nil_field("local t = { f(), a = nil }"),
+    table_with_variadic_value_and_nil_index("local t = { ..., [k] = nil }"),
+    table_with_call_value_and_nil_fields("local t = { g(), a = nil, b = nil }"),
 );
 
 #[test]
@@ -0,0 +1,55 @@
+use darklua_core::rules::{RemoveTrailingNilValues, Rule};
+
+test_rule!(
+    remove_trailing_nil_values,
+    RemoveTrailingNilValues::default(),
+    single_nil("local a = nil") => "local a",
+    all_nil("local a, b, c = nil, nil, nil") => "local a, b, c",
+    trailing_nil("local a, b, c = 1, nil") => "local a, b, c = 1",
+    multiple_trailing_nil("local a, b, c = true, nil, nil") => "local a, b, c = true",
+    extra_trailing_nil("local a = 1, nil") => "local a = 1",
+    nil_in_middle_and_trailing("local a, b, c = 1, nil, 3, nil") => "local a, b, c = 1, nil, 3",
+    call_before_trailing_nil("local a, b = call(), nil") => "local a, b = (call())",
+    variadic_before_trailing_nil("local a, b = ..., nil") => "local a, b = (...)",
+    extra_nil_after_call("local a = call(), nil") => "local a = call()",
+    multiple_nil_after_call("local a, b, c = call(), nil, nil") => "local a, b, c = (call())",
+    call_in_middle_before_trailing_nil("local a, b, c = 1, call(), nil") => "local a, b, c = 1, (call())",
+    method_call_before_trailing_nil("local a, b = object:method(), nil") => "local a, b = (object:method())",
+    extra_nil_after_variadic("local a = ..., nil") => "local a = ...",
+    nested_function("local function f() local a = nil return a end") => "local function f() local a return a end",
+);
+
+test_rule_with_tokens!(
+    remove_trailing_nil_values_tokens,
+    RemoveTrailingNilValues::default(),
+    remove_equal_sign("local a = nil") => "local a ",
+    remove_commas("local a, b, c = 1, nil, nil") => "local a, b, c = 1",
+    keep_comment_before_statement("-- comment\nlocal a, b = true, nil") => "-- comment\nlocal a, b = true",
+);
+
+test_rule_without_effects!(
+    RemoveTrailingNilValues::default(),
+    nil_in_middle("local a, b, c = 1, nil, 3"),
+    nil_at_start("local a, b = nil, true"),
+    nil_before_call("local a, b = nil, call()"),
+    nil_before_variadic("local a, b, c = nil, ..."),
+    call_without_nil("local a, b = call()"),
+    no_values("local a, b"),
+    assignment_to_nil("a, b = true, nil"),
+    return_nil("return nil"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'remove_trailing_nil_values',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'remove_trailing_nil_values'").unwrap();
+}
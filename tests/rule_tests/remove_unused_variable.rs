@@ -83,6 +83,7 @@ test_rule!(
 
 test_rule_without_effects!(
     RemoveUnusedVariable::default(),
+    file_using_setfenv("local unused = true setfenv(1, {})"),
     keep_returning_local_function("local function foo() end return foo"),
     keep_used_local_function("local function foo() end foo()"),
     keep_not_initialized_variable("local foo return foo"),
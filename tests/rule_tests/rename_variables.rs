@@ -47,6 +47,8 @@ test_rule!(
     reexported_type_field("local types = require('./types') export type Oof = types.Oof") => "local a = require('./types') export type Oof = a.Oof",
     type_variable_type_field("local React = require('@pkg/@jsdotlua/react') type Props = { children: React.ReactNode }")
         => "local a = require('@pkg/@jsdotlua/react') type Props = { children: a.ReactNode }",
+    generic_type_parameter_is_not_renamed("local T = 1 local function identity<T>(value: T): T return value end return identity(T)")
+        => "local a = 1 local function b<T>(c: T): T return c end return b(a)",
 );
 
 test_rule!(
@@ -91,17 +93,93 @@ test_rule!(
     function_expression_parameters_reference("return function(foo, bar) return foo + bar end")
         => "return function(a, b) return a + b end",
     recycle_previous_identifiers("do local foo end local foo") => "do local a end local a",
+    duplicated_local_names("local foo, foo = 1, 2 return foo") => "local a, b = 1, 2 return b",
+    duplicated_parameter_names("local function fn(foo, foo) return foo end")
+        => "local function fn(a, b) return b end",
+    redeclared_local_name("local foo = 1 local foo = foo + 1 return foo")
+        => "local a = 1 local b = a + 1 return b",
     reexported_type_field("local types = require('./types') export type Oof = types.Oof") => "local a = require('./types') export type Oof = a.Oof",
     type_variable_type_field("local React = require('@pkg/@jsdotlua/react') type Props = { children: React.ReactNode }")
         => "local a = require('@pkg/@jsdotlua/react') type Props = { children: a.ReactNode }",
 );
 
+test_rule!(
+    rename_variables_with_env,
+    RenameVariables::default(),
+    keep_env_parameter("local function f(_ENV, value) return print(value) end")
+        => "local function f(_ENV, a) return print(a) end",
+    keep_env_local("local _ENV = {} local value = 1 return value") => "local _ENV = {} local a = 1 return a",
+);
+
 test_rule_without_effects!(
     RenameVariables::default(),
+    file_using_setfenv("local value = 1 setfenv(1, {}) return value"),
+    file_using_loadstring_with_variable("local value = ... return loadstring(value)()"),
     local_function_name("local function foo() end"),
     does_not_rename_functions("local function foo() end return foo()"),
 );
 
+test_rule!(
+    rename_variables_keep_exported,
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'rename_variables',
+        include_functions: true,
+        keep_exported: true,
+    }"#,
+    ).unwrap(),
+    table_constructor_exports(
+        "local function format(value) return tostring(value) end \
+        local function parse(text) return tonumber(text) end \
+        local defaultValue = 0 \
+        local cache = {} \
+        return { format = format, parse = parse, default = defaultValue }"
+    ) => "local function format(a) return tostring(a) end \
+        local function parse(a) return tonumber(a) end \
+        local defaultValue = 0 \
+        local a = {} \
+        return { format = format, parse = parse, default = defaultValue }",
+    assigned_field_exports(
+        "local Module = {} \
+        local function helper(value) return value end \
+        local function create(value) return helper(value) end \
+        Module.create = create \
+        return Module"
+    ) => "local Module = {} \
+        local function a(b) return b end \
+        local function create(b) return a(b) end \
+        Module.create = create \
+        return Module",
+    returned_function(
+        "local count = 0 \
+        local function increment(amount) count = count + amount return count end \
+        return increment"
+    ) => "local a = 0 \
+        local function increment(b) a = a + b return a end \
+        return increment",
+    exported_names_are_not_generated(
+        "local a = 1 local function get() local value = 2 return a + value end return { a = a }"
+    ) => "local a = 1 local function b() local c = 2 return a + c end return { a = a }",
+    nested_local_with_exported_name_is_renamed(
+        "local value = 1 do local value = 2 print(value) end return value"
+    ) => "local value = 1 do local a = 2 print(a) end return value",
+    without_return_statement("local value = 1 print(value)") => "local a = 1 print(a)",
+);
+
+test_rule!(
+    rename_variables_keep_exported_fields_matching,
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'rename_variables',
+        keep_exported: true,
+        keep_exported_fields_matching: ['public'],
+    }"#,
+    ).unwrap(),
+    only_matching_fields(
+        "local first, second = 1, 2 return { publicValue = first, private = second }"
+    ) => "local first, a = 1, 2 return { publicValue = first, private = a }",
+);
+
 #[test]
 fn deserialize_with_special_empty_globals() {
     json5::from_str::<Box<dyn Rule>>(
@@ -0,0 +1,102 @@
+use darklua_core::rules::{Rewrite, Rule};
+
+fn api_rewrites() -> Rewrite {
+    Rewrite::default()
+        .with_rewrite("old_api.foo($x)", "new_api.foo($x, true)")
+        .with_rewrite("Vector3.new(0, 0, 0)", "Vector3.zero")
+}
+
+test_rule!(
+    rewrite,
+    api_rewrites(),
+    call_statement("old_api.foo(value)") => "new_api.foo(value, true)",
+    call_expression("local a = old_api.foo(1 + 2)") => "local a = new_api.foo(1 + 2, true)",
+    constant_expression("local v = Vector3.new(0, 0, 0)") => "local v = Vector3.zero",
+    constant_with_different_number_format("local v = Vector3.new(0, 0.0, 0x0)")
+        => "local v = Vector3.zero",
+    constant_as_prefix("return Vector3.new(0, 0, 0).X") => "return Vector3.zero.X",
+    nested_in_binary_expression("return 1 + old_api.foo(a) * 2")
+        => "return 1 + new_api.foo(a, true) * 2",
+    nested_in_bound_expression("return old_api.foo(Vector3.new(0, 0, 0))")
+        => "return new_api.foo(Vector3.zero, true)",
+    nested_in_same_pattern("old_api.foo(old_api.foo(a))")
+        => "new_api.foo(new_api.foo(a, true), true)",
+    nested_in_function("local function f() return old_api.foo(a) end")
+        => "local function f() return new_api.foo(a, true) end",
+);
+
+test_rule_without_effects!(
+    api_rewrites(),
+    different_argument_count("old_api.foo(a, b)"),
+    different_function("old_api.bar(a)"),
+    different_constant("local v = Vector3.new(0, 1, 0)"),
+    method_call("old_api:foo(a)"),
+);
+
+test_rule!(
+    rewrite_with_duplicated_metavariable,
+    Rewrite::default().with_rewrite("square($x)", "$x * $x"),
+    duplicate_identifier("return square(a)") => "return a * a",
+    duplicate_number("return square(1.5)") => "return 1.5 * 1.5",
+);
+
+test_rule_without_effects!(
+    Rewrite::default().with_rewrite("square($x)", "$x * $x"),
+    refuse_to_duplicate_call("return square(f())"),
+    refuse_to_duplicate_call_in_binary_expression("return square(a + f())"),
+    refuse_to_duplicate_field_access("return square(a.b)"),
+);
+
+test_rule!(
+    rewrite_with_binary_expression_replacement,
+    Rewrite::default().with_rewrite("double($x)", "$x * 2"),
+    bound_identifier("return double(a)") => "return a * 2",
+    bound_binary_expression("return double(a + b)") => "return (a + b) * 2",
+);
+
+test_rule_without_effects!(
+    Rewrite::default().with_rewrite("ignore($x)", "nil"),
+    refuse_to_remove_call("return ignore(f())"),
+);
+
+test_rule!(
+    rewrite_with_variadic_metavariable,
+    Rewrite::default().with_rewrite("print($args...)", "log('info', $args...)"),
+    no_arguments("print()") => "log('info')",
+    one_argument("print(a)") => "log('info', a)",
+    multiple_arguments("print(a, b, c)") => "log('info', a, b, c)",
+    string_argument("print 'hello'") => "log('info', 'hello')",
+);
+
+test_rule!(
+    rewrite_with_metavariable_prefix,
+    Rewrite::default().with_rewrite("$object:Destroy()", "destroy($object)"),
+    identifier_prefix("part:Destroy()") => "destroy(part)",
+    field_prefix("self.part:Destroy()") => "destroy(self.part)",
+);
+
+test_rule!(
+    rewrite_with_repeated_find_metavariable,
+    Rewrite::default().with_rewrite("$x == $x", "true"),
+    same_identifiers("return a == a") => "return true",
+    different_identifiers("return a == b") => "return a == b",
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'rewrite',
+        rewrites: [
+            { find: 'old_api.foo($x)', replace: 'new_api.foo($x, true)' },
+            { find: 'Vector3.new(0, 0, 0)', replace: 'Vector3.zero' },
+        ],
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'rewrite'").unwrap();
+}
@@ -0,0 +1,71 @@
+use darklua_core::rules::{RewriteEnvAccess, Rule};
+
+test_rule!(
+    rewrite_env_access,
+    RewriteEnvAccess::default(),
+    global_table_field("return _G.print") => "return print",
+    global_table_index("return _G['value']") => "return value",
+    call_global_table_field("_G.print('hello')") => "print('hello')",
+    call_method_on_global_table_field("_G.object:method()") => "object:method()",
+    assign_global_table_field("_G.value = true") => "value = true",
+    assign_global_table_index("_G[\"value\"] = true") => "value = true",
+    getfenv_field("return getfenv().value") => "return value",
+    call_getfenv_field("getfenv().print('hello')") => "print('hello')",
+    local_assignment_with_same_name("local value = _G.value return value")
+        => "local value = value return value",
+    nested_global_table_field("return _G.Instance.new") => "return Instance.new",
+);
+
+test_rule!(
+    rewrite_env_access_without_global_table,
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'rewrite_env_access',
+        global_table: false,
+    }"#,
+    ).unwrap(),
+    getfenv_field("return getfenv().value") => "return value",
+);
+
+test_rule_without_effects!(
+    RewriteEnvAccess::default(),
+    global_table_assigned_to_variable("local env = _G return env.value"),
+    local_global_table("local _G = {} return _G.value"),
+    global_table_reassigned("_G = {} return _G.value"),
+    global_table_field_shadowed_by_local("local value = 1 return _G.value"),
+    global_table_index_with_variable("local key = 'value' return _G[key]"),
+    global_table_index_with_invalid_identifier("return _G['not valid']"),
+    global_table_index_with_keyword("return _G['end']"),
+    global_table_in_dynamic_environment("setfenv(1, {}) return _G.value"),
+    getfenv_with_level("return getfenv(2).value"),
+    getfenv_with_string_argument("return getfenv'value'.value"),
+    getfenv_with_table_argument("return getfenv{}.value"),
+    local_getfenv("local getfenv = function() return {} end return getfenv().value"),
+    global_table_field_shadowed_in_function("return function(value) return _G.value end"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'rewrite_env_access',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'rewrite_env_access'").unwrap();
+}
+
+#[test]
+fn deserialize_with_global_table() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'rewrite_env_access',
+        global_table: false,
+    }"#,
+    )
+    .unwrap();
+}
@@ -0,0 +1,117 @@
+use darklua_core::rules::{BooleanComparisonsMode, Rule, SimplifyBooleanComparisons};
+
+test_rule!(
+    simplify_boolean_comparisons,
+    SimplifyBooleanComparisons::default(),
+    true_equals_false("return true == false") => "return false",
+    true_not_equals_false("return true ~= false") => "return true",
+    false_equals_false("return false == false") => "return true",
+    negated_equal_true("return not (x == true)") => "return x ~= true",
+    negated_not_equal_false("return not (false ~= x)") => "return false == x",
+    negated_equal_in_condition("if not (x == true) then end") => "if x ~= true then end",
+    negated_equal_in_comparison("return y == not (x == true)") => "return y == (x ~= true)",
+);
+
+test_rule_without_effects!(
+    SimplifyBooleanComparisons::default(),
+    // `x == true` is false when `x` is a truthy value that is not `true`, like `1`
+    equal_true_is_false_for_truthy_non_true_values("if x == true then end"),
+    equal_false_is_false_for_nil("if x == false then end"),
+    not_equal_true_in_condition("while x ~= true do end"),
+    negated_comparison_without_literal("return not (x == y)"),
+    annotated_parameter_in_exact_mode("local function f(x: boolean) if x == true then end end"),
+);
+
+test_rule!(
+    simplify_boolean_comparisons_assume_boolean_annotations,
+    SimplifyBooleanComparisons::default().with_mode(BooleanComparisonsMode::AssumeBoolean),
+    annotated_parameter_equal_true("local function f(x: boolean) if x == true then end end")
+        => "local function f(x: boolean) if x then end end",
+    annotated_parameter_equal_false("local function f(x: boolean) if x == false then end end")
+        => "local function f(x: boolean) if not x then end end",
+    annotated_parameter_not_equal_true("local function f(x: boolean) while x ~= true do end end")
+        => "local function f(x: boolean) while not x do end end",
+    annotated_parameter_not_equal_false("return function(x: boolean) repeat until x ~= false end")
+        => "return function(x: boolean) repeat until x end",
+    literal_on_the_left("local function f(x: boolean) if true == x then end end")
+        => "local function f(x: boolean) if x then end end",
+    annotated_local("local done: boolean = check() if done == true then end")
+        => "local done: boolean = check() if done then end",
+    true_singleton_type("local function f(x: true) if x == true then end end")
+        => "local function f(x: true) if x then end end",
+    inside_and_condition("local function f(x: boolean) if ready and x == false then end end")
+        => "local function f(x: boolean) if ready and not x then end end",
+    inside_not_condition("local function f(x: boolean) if not (x == true) then end end")
+        => "local function f(x: boolean) if not x then end end",
+    inside_not_condition_with_negation("local function f(x: boolean) if not (x == false) then end end")
+        => "local function f(x: boolean) if not (not x) then end end",
+    parenthese_condition("local function f(x: boolean) if (x == true) then end end")
+        => "local function f(x: boolean) if x then end end",
+    elseif_condition("local function f(x: boolean) if a then elseif x == true then end end")
+        => "local function f(x: boolean) if a then elseif x then end end",
+    if_expression_condition("local function f(x: boolean) return if x == true then 1 else 2 end")
+        => "local function f(x: boolean) return if x then 1 else 2 end",
+    method_parameter("function M:set(x: boolean) if x == false then end end")
+        => "function M:set(x: boolean) if not x then end end",
+);
+
+test_rule_without_effects!(
+    SimplifyBooleanComparisons::default().with_mode(BooleanComparisonsMode::AssumeBoolean),
+    return_value_is_not_a_condition("local function f(x: boolean) return x == false end"),
+    assignment_value_is_not_a_condition("local function f(x: boolean) y = x == true end"),
+    call_argument_is_not_a_condition("local function f(x: boolean) print(x == true) end"),
+    optional_boolean_can_be_nil("local function f(x: boolean?) if x == false then end end"),
+    not_annotated_parameter("local function f(x) if x == true then end end"),
+    number_annotation("local function f(x: number) if x == true then end end"),
+    shadowed_by_untyped_parameter(
+        "local function f(x: boolean) return function(x) if x == true then end end end"
+    ),
+    shadowed_by_untyped_local("local function f(x: boolean) local x = 1 if x == true then end end"),
+    shadowed_by_loop_variable(
+        "local function f(x: boolean) for x = 1, 2 do if x == true then end end end"
+    ),
+    shadowed_in_nested_function_value(
+        "local done: boolean = (function(done) if done == true then end end)()"
+    ),
+    shadowed_by_repeat_block_local(
+        "local function f(x: boolean) repeat local x = 1 until x == true end"
+    ),
+    outside_of_annotated_scope("do local x: boolean = true end if x == true then end"),
+);
+
+test_rule!(
+    simplify_boolean_comparisons_boolean_variables,
+    SimplifyBooleanComparisons::default()
+        .with_mode(BooleanComparisonsMode::AssumeBoolean)
+        .with_boolean_variable("done"),
+    listed_global("while done ~= false do end") => "while done do end",
+    listed_local("local done = check() if done == true then end")
+        => "local done = check() if done then end",
+    listed_in_repeat_block("repeat local done = step() until done == true")
+        => "repeat local done = step() until done",
+);
+
+test_rule_without_effects!(
+    SimplifyBooleanComparisons::default()
+        .with_mode(BooleanComparisonsMode::AssumeBoolean)
+        .with_boolean_variable("done"),
+    unlisted_variable("if ready == true then end"),
+    listed_variable_in_return("return done == false"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'simplify_boolean_comparisons',
+        mode: 'assume_boolean',
+        boolean_variables: ['done', 'enabled'],
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'simplify_boolean_comparisons'").unwrap();
+}
@@ -0,0 +1,152 @@
+use darklua_core::process::EnvironmentTarget;
+use darklua_core::rules::{ParameterDefaultsMode, Rule, SimplifyParameterDefaults};
+
+use super::process_rules;
+
+test_rule!(
+    simplify_parameter_defaults,
+    SimplifyParameterDefaults::default(),
+    falsy_check("local function f(x) if not x then x = {} end return x end")
+        => "local function f(x) x = x or {} return x end",
+    nil_check_with_false_default("local function f(x) if x == nil then x = false end return x end")
+        => "local function f(x) x = x or false return x end",
+    nil_check_with_nil_first("local function f(x) if nil == x then x = false end return x end")
+        => "local function f(x) x = x or false return x end",
+    multiple_parameters(
+        "function M.new(a, b) if not a then a = 1 end if not b then b = 2 end return a + b end"
+    ) => "function M.new(a, b) a = a or 1 b = b or 2 return a + b end",
+    function_expression("return function(options) if not options then options = {} end end")
+        => "return function(options) options = options or {} end",
+);
+
+test_rule_without_effects!(
+    SimplifyParameterDefaults::default(),
+    nil_check_can_receive_false("local function f(x) if x == nil then x = 1 end return x end"),
+    nil_check_with_nil_default("local function f(x) if x == nil then x = nil end return x end"),
+    not_a_parameter("local function f() if not x then x = 1 end return x end"),
+    local_variable_at_top("local x if not x then x = 1 end"),
+    after_another_statement("local function f(x) print(x) if not x then x = 1 end end"),
+    with_else_branch("local function f(x) if not x then x = 1 else x = 2 end end"),
+    different_variable("local function f(x, y) if not x then y = 1 end end"),
+);
+
+test_rule!(
+    simplify_parameter_defaults_assume_no_false,
+    SimplifyParameterDefaults::default().with_assume_no_false(true),
+    nil_check("local function f(x) if x == nil then x = 1 end return x end")
+        => "local function f(x) x = x or 1 return x end",
+);
+
+test_rule!(
+    simplify_parameter_defaults_anywhere,
+    SimplifyParameterDefaults::default().with_anywhere(true),
+    local_variable("local x = get() if not x then x = 1 end")
+        => "local x = get() x = x or 1",
+    parameter_after_statement("local function f(x) print(x) if not x then x = 1 end end")
+        => "local function f(x) print(x) x = x or 1 end",
+);
+
+test_rule_without_effects!(
+    SimplifyParameterDefaults::default().with_anywhere(true),
+    global_variable("if not x then x = 1 end"),
+);
+
+test_rule!(
+    expand_parameter_defaults,
+    SimplifyParameterDefaults::default().with_mode(ParameterDefaultsMode::Expand),
+    or_assignment("local function f(x) x = x or 1 return x end")
+        => "local function f(x) if not x then x = 1 end return x end",
+    or_assignment_with_false_default("local function f(x) x = x or false return x end")
+        => "local function f(x) if x == nil then x = false end return x end",
+);
+
+test_rule!(
+    expand_parameter_defaults_assume_no_false,
+    SimplifyParameterDefaults::default()
+        .with_mode(ParameterDefaultsMode::Expand)
+        .with_assume_no_false(true),
+    or_assignment("local function f(x) x = x or 1 return x end")
+        => "local function f(x) if x == nil then x = 1 end return x end",
+);
+
+fn process_code(rule: SimplifyParameterDefaults, target: EnvironmentTarget, code: &str) -> String {
+    process_rules([Box::new(rule) as Box<dyn Rule>], Some(target), code).unwrap()
+}
+
+#[test]
+fn nil_check_uses_if_expression_for_luau_target() {
+    assert_eq!(
+        process_code(
+            SimplifyParameterDefaults::default(),
+            EnvironmentTarget::Luau,
+            "local function f(x) if x == nil then x = 1 end return x end"
+        ),
+        "local function f(x) x=if x==nil then 1 else x return x end"
+    );
+}
+
+#[test]
+fn nil_check_is_kept_for_lua51_target() {
+    assert_eq!(
+        process_code(
+            SimplifyParameterDefaults::default(),
+            EnvironmentTarget::Lua51,
+            "local function f(x) if x == nil then x = 1 end return x end"
+        ),
+        "local function f(x) if x == nil then x = 1 end return x end"
+    );
+}
+
+#[test]
+fn falsy_check_uses_or_for_luau_target() {
+    assert_eq!(
+        process_code(
+            SimplifyParameterDefaults::default(),
+            EnvironmentTarget::Luau,
+            "local function f(x) if not x then x = 1 end return x end"
+        ),
+        "local function f(x) x=x or 1 return x end"
+    );
+}
+
+#[test]
+fn expand_then_simplify_round_trip() {
+    let code = "local function f(x, y) x = x or 1 y = y or false return x, y end";
+
+    let expanded = process_code(
+        SimplifyParameterDefaults::default().with_mode(ParameterDefaultsMode::Expand),
+        EnvironmentTarget::Lua51,
+        code,
+    );
+    assert_eq!(
+        expanded,
+        "local function f(x, y) if not x then x=1 end if y==nil then y=false end return x, y end"
+    );
+
+    assert_eq!(
+        process_code(
+            SimplifyParameterDefaults::default(),
+            EnvironmentTarget::Lua51,
+            &expanded
+        ),
+        "local function f(x, y) x=x or 1 y=y or false return x, y end"
+    );
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'simplify_parameter_defaults',
+        mode: 'expand',
+        assume_no_false: true,
+        anywhere: true,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'simplify_parameter_defaults'").unwrap();
+}
@@ -0,0 +1,73 @@
+use darklua_core::rules::{Rule, SortLocalFunctions};
+
+test_rule!(
+    sort_local_functions,
+    SortLocalFunctions::default(),
+    // the output of `globals_to_locals` for
+    // `function main() helper() end main() function helper() print('help') end`
+    converted_globals_called_before_definition(
+        "local function main() helper() end main() local function helper() print('help') end"
+    ) => "local function helper() print('help') end local function main() helper() end main()",
+    call_before_definition("print(f()) local function f() return 1 end")
+        => "local function f() return 1 end print(f())",
+    function_referenced_before_definition("local callbacks = { f } local function f() end")
+        => "local function f() end local callbacks = { f }",
+    other_statements_keep_their_order(
+        "local a = 1 print(f()) local b = 2 local function f() return 3 end"
+    ) => "local a = 1 local function f() return 3 end print(f()) local b = 2",
+    function_called_by_used_function(
+        "local function a() return b() end local function b() return 1 end return a()"
+    ) => "local function b() return 1 end local function a() return b() end return a()",
+    function_called_through_another_function(
+        "print(a()) local function a() return b() end local function b() return 1 end"
+    ) => "local function b() return 1 end local function a() return b() end print(a())",
+    function_using_local_declared_before(
+        "local prefix = 'x' print(f()) local function f() return prefix end"
+    ) => "local prefix = 'x' local function f() return prefix end print(f())",
+    mutual_recursion_called_before_definition(
+        "print(isEven(4)) \
+        local function isEven(n) if n == 0 then return true end return isOdd(n - 1) end \
+        local function isOdd(n) if n == 0 then return false end return isEven(n - 1) end"
+    ) => "local function isEven(n) if n == 0 then return true end return isOdd(n - 1) end \
+        local function isOdd(n) if n == 0 then return false end return isEven(n - 1) end \
+        print(isEven(4))",
+);
+
+test_rule_without_effects!(
+    SortLocalFunctions::default(),
+    already_sorted("local function f() return 1 end print(f())"),
+    unused_functions("local function b() end local function a() end"),
+    mutual_recursion(
+        "local function isEven(n) if n == 0 then return true end return isOdd(n - 1) end \
+        local function isOdd(n) if n == 0 then return false end return isEven(n - 1) end \
+        print(isEven(4))"
+    ),
+    recursive_function("local function f(n) if n > 0 then return f(n - 1) end end f(2)"),
+    function_hiding_local_declared_after(
+        "local function f() return value end local value = 1 print(f())"
+    ),
+    function_depending_on_local_declared_after_use(
+        "print(f()) local prefix = 'x' local function f() return prefix end"
+    ),
+    function_used_by_its_dependency("local a = f() local function f() return a end"),
+    reassigned_function("print(f()) local function f() end f = nil"),
+    redefined_function("print(f()) local function f() end function f() end"),
+    redeclared_function("print(f()) local function f() end local f = 1"),
+    function_shadowed_in_statement("do local function f() end f() end local function f() end"),
+    nested_block("do print(f()) local function f() end end"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'sort_local_functions',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'sort_local_functions'").unwrap();
+}
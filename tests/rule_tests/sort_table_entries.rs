@@ -0,0 +1,96 @@
+use darklua_core::rules::{Rule, SortTableEntries, TableEntriesOrder};
+
+test_rule!(
+    sort_table_entries,
+    SortTableEntries::default(),
+    sort_fields("return { b = 1, a = 2 }") => "return { a = 2, b = 1 }",
+    sort_string_index("return { ['b'] = 1, ['a'] = 2 }") => "return { ['a'] = 2, ['b'] = 1 }",
+    sort_fields_and_string_index("return { c = 1, ['a b'] = 2, b = 3 }")
+        => "return { ['a b'] = 2, b = 3, c = 1 }",
+    sort_number_index_before_strings("return { a = 1, [2] = 2, [1] = 3 }")
+        => "return { [1] = 3, [2] = 2, a = 1 }",
+    sort_fields_with_pure_values("return { c = {}, b = function() end, a = x }")
+        => "return { a = x, b = function() end, c = {} }",
+    sort_fields_around_array_values("return { b = 1, a = 2, 'v', d = 3, c = 4 }")
+        => "return { a = 2, b = 1, 'v', c = 4, d = 3 }",
+    sort_nested_tables("return { b = { d = 1, c = 2 }, a = 0 }")
+        => "return { a = 0, b = { c = 2, d = 1 } }",
+    sort_call_argument("f({ b = 1, a = 2 })") => "f({ a = 2, b = 1 })",
+    keep_duplicated_keys("return { b = 1, a = 2, b = 3 }") => "return { b = 1, a = 2, b = 3 }",
+    keep_duplicated_field_and_string_index("return { b = 1, a = 2, ['b'] = 3 }")
+        => "return { b = 1, a = 2, ['b'] = 3 }",
+    keep_duplicated_number_keys("return { [2] = 1, [1] = 2, [2.0] = 3 }")
+        => "return { [2] = 1, [1] = 2, [2.0] = 3 }",
+    keep_fields_with_call_value("return { b = f(), a = 1 }") => "return { b = f(), a = 1 }",
+    keep_fields_with_index_value("return { b = t.x, a = 1 }") => "return { b = t.x, a = 1 }",
+    keep_computed_key("return { b = 1, [k] = 2, a = 3 }") => "return { b = 1, [k] = 2, a = 3 }",
+);
+
+test_rule_with_tokens!(
+    sort_table_entries_with_tokens,
+    SortTableEntries::default(),
+    move_separators_with_entries("return { b = 1, [\"1\"] = 2, [1] = 3 }")
+        => "return { [1] = 3, [\"1\"] = 2, b = 1 }",
+    keep_trailing_separator("return { b = 1, a = 2, }") => "return { a = 2, b = 1, }",
+    move_comments_with_entries("return { b = 1, --[[b]] a = 2, --[[a]] }")
+        => "return { a = 2, --[[a]] b = 1, --[[b]] }",
+    keep_comment_before_closing_brace("return { b = 1, a = 2 --[[end]] }")
+        => "return { a = 2, b = 1 --[[end]] }",
+    sort_entries_before_array_value("return { b = 1, a = 2, 'v' }")
+        => "return { a = 2, b = 1, 'v' }",
+);
+
+test_rule!(
+    sort_table_entries_by_length,
+    SortTableEntries::default().with_order(TableEntriesOrder::Length),
+    sort_by_key_length("return { ccc = 1, a = 2, bb = 3, b = 4 }")
+        => "return { a = 2, b = 4, bb = 3, ccc = 1 }",
+);
+
+test_rule!(
+    sort_table_entries_by_frequency,
+    SortTableEntries::default().with_order(TableEntriesOrder::Frequency),
+    group_values_by_shape("return { e = 'x', d = 1, c = 'y', b = 2, a = 'z' }")
+        => "return { a = 'z', c = 'y', e = 'x', b = 2, d = 1 }",
+);
+
+test_rule!(
+    sort_table_entries_skip_call_arguments,
+    SortTableEntries::default().with_skip_call_arguments(true),
+    sort_table_outside_call("local t = { b = 1, a = 2 }") => "local t = { a = 2, b = 1 }",
+    sort_table_nested_in_call_argument("f({ t = { b = 1, a = 2 } })")
+        => "f({ t = { a = 2, b = 1 } })",
+    sort_table_in_called_prefix("({ b = 1, a = 2 }).f({ d = 1, c = 2 })")
+        => "({ a = 2, b = 1 }).f({ d = 1, c = 2 })",
+);
+
+test_rule_without_effects!(
+    SortTableEntries::default().with_skip_call_arguments(true),
+    call_argument_in_parentheses("f({ b = 1, a = 2 })"),
+    call_argument_table("f { b = 1, a = 2 }"),
+    method_call_argument("obj:f(1, { b = 1, a = 2 })"),
+);
+
+test_rule_without_effects!(
+    SortTableEntries::default(),
+    already_sorted("return { a = 1, b = 2 }"),
+    array_values("return { 'b', 'a' }"),
+    effectful_value_between_fields("return { b = g(), a = 2 }"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'sort_table_entries',
+        order: 'frequency',
+        skip_call_arguments: true,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'sort_table_entries'").unwrap();
+}
@@ -0,0 +1,204 @@
+use darklua_core::{
+    generator::{LuaGenerator, ReadableLuaGenerator},
+    rules::{ContextBuilder, Rule, TransliterateIdentifiers, TransliterationStrategy},
+    Parser, Resources,
+};
+
+const FIXTURE: &str = r#"
+local café = { größe = 1 }
+function café:méthode(naïve)
+    return self.größe + naïve
+end
+total = café:méthode(ёмкость)
+return café
+"#;
+
+fn parse(code: &str) -> darklua_core::nodes::Block {
+    Parser::default()
+        .allow_utf8_identifiers()
+        .parse(code)
+        .unwrap_or_else(|error| panic!("could not parse content: {}\n{}", error, code))
+}
+
+fn transliterate(rule: &TransliterateIdentifiers, code: &str) -> String {
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("test.lua", &resources, code).build();
+
+    let mut block = parse(code);
+    rule.process(&mut block, &context)
+        .expect("rule should succeed");
+
+    let mut generator = ReadableLuaGenerator::default();
+    generator.write_block(&block);
+    generator.into_string()
+}
+
+fn assert_transliteration(rule: TransliterateIdentifiers, expected: &str) {
+    let output = transliterate(&rule, FIXTURE);
+
+    pretty_assertions::assert_eq!(
+        parse(&output),
+        parse(expected),
+        "\nexpected code:\n{}\nbut received:\n{}",
+        expected,
+        output
+    );
+}
+
+#[test]
+fn hash_strategy_without_fields() {
+    assert_transliteration(
+        TransliterateIdentifiers::default(),
+        r#"
+local _u_4c83db = { größe = 1 }
+function _u_4c83db:méthode(_u_ccccbc)
+    return self.größe + _u_ccccbc
+end
+total = _u_4c83db:méthode(_u_90a450)
+return _u_4c83db
+"#,
+    );
+}
+
+#[test]
+fn hash_strategy_with_fields() {
+    assert_transliteration(
+        TransliterateIdentifiers::default().with_include_fields(true),
+        r#"
+local _u_4c83db = { _u_a6d7bb = 1 }
+function _u_4c83db:_u_e285ca(_u_ccccbc)
+    return self._u_a6d7bb + _u_ccccbc
+end
+total = _u_4c83db:_u_e285ca(_u_90a450)
+return _u_4c83db
+"#,
+    );
+}
+
+#[test]
+fn ascii_strategy_without_fields() {
+    assert_transliteration(
+        TransliterateIdentifiers::default().with_strategy(TransliterationStrategy::Ascii),
+        r#"
+local cafe = { größe = 1 }
+function cafe:méthode(naive)
+    return self.größe + naive
+end
+total = cafe:méthode(yomkost)
+return cafe
+"#,
+    );
+}
+
+#[test]
+fn ascii_strategy_with_fields() {
+    assert_transliteration(
+        TransliterateIdentifiers::default()
+            .with_strategy(TransliterationStrategy::Ascii)
+            .with_include_fields(true),
+        r#"
+local cafe = { grosse = 1 }
+function cafe:methode(naive)
+    return self.grosse + naive
+end
+total = cafe:methode(yomkost)
+return cafe
+"#,
+    );
+}
+
+#[test]
+fn ascii_strategy_resolves_collisions_with_suffix() {
+    let rule = TransliterateIdentifiers::default().with_strategy(TransliterationStrategy::Ascii);
+
+    pretty_assertions::assert_eq!(
+        parse(&transliterate(&rule, "local cafe, café, cafè = 1, 2, 3")),
+        parse("local cafe, cafe_3, cafe_2 = 1, 2, 3"),
+    );
+}
+
+#[test]
+fn ascii_strategy_avoids_reserved_identifiers() {
+    let rule = TransliterateIdentifiers::default().with_strategy(TransliterationStrategy::Ascii);
+    let code = "local café = 1";
+    let resources = Resources::from_memory();
+    let context = ContextBuilder::new("test.lua", &resources, code)
+        .with_reserved_identifiers(vec!["cafe".to_owned()])
+        .build();
+
+    let mut block = parse(code);
+    rule.process(&mut block, &context)
+        .expect("rule should succeed");
+
+    pretty_assertions::assert_eq!(block, parse("local cafe_2 = 1"));
+}
+
+#[test]
+fn same_name_is_mapped_consistently_across_scopes() {
+    let rule = TransliterateIdentifiers::default().with_strategy(TransliterationStrategy::Ascii);
+
+    pretty_assertions::assert_eq!(
+        parse(&transliterate(
+            &rule,
+            "local é = 1 do local é = é + 1 print(é) end return é"
+        )),
+        parse("local e = 1 do local e = e + 1 print(e) end return e"),
+    );
+}
+
+#[test]
+fn transliteration_is_deterministic() {
+    for rule in [
+        TransliterateIdentifiers::default().with_include_fields(true),
+        TransliterateIdentifiers::default()
+            .with_strategy(TransliterationStrategy::Ascii)
+            .with_include_fields(true),
+    ] {
+        let first = transliterate(&rule, FIXTURE);
+
+        for _ in 0..5 {
+            pretty_assertions::assert_eq!(transliterate(&rule, FIXTURE), first);
+        }
+    }
+}
+
+#[test]
+fn mapping_does_not_depend_on_the_order_of_the_code() {
+    let rule = TransliterateIdentifiers::default().with_strategy(TransliterationStrategy::Ascii);
+
+    pretty_assertions::assert_eq!(
+        parse(&transliterate(&rule, "local cafè = 1 local café = 2")),
+        parse("local cafe = 1 local cafe_2 = 2"),
+    );
+    pretty_assertions::assert_eq!(
+        parse(&transliterate(&rule, "local café = 2 local cafè = 1")),
+        parse("local cafe_2 = 2 local cafe = 1"),
+    );
+}
+
+#[test]
+fn keep_ascii_code() {
+    let code = "local cafe = { size = 1 } return cafe.size";
+
+    pretty_assertions::assert_eq!(
+        parse(&transliterate(&TransliterateIdentifiers::default(), code)),
+        parse(code),
+    );
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'transliterate_identifiers',
+        strategy: 'ascii',
+        include_fields: true,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'transliterate_identifiers'").unwrap();
+}
@@ -0,0 +1,50 @@
+use darklua_core::rules::{Rule, TrimTrailingWhitespace};
+
+use super::assert_only_trivia_changed;
+
+test_rule_with_tokens!(
+    trim_trailing_whitespace,
+    TrimTrailingWhitespace::default(),
+    trim_spaces_at_end_of_line("local a = 1   \nreturn a") => "local a = 1\nreturn a",
+    trim_tabs_at_end_of_line("local a = 1\t\t\nreturn a") => "local a = 1\nreturn a",
+    trim_spaces_of_empty_lines("local a = 1\n    \n\nreturn a") => "local a = 1\n\n\nreturn a",
+    trim_spaces_before_carriage_return("local a = 1  \r\nreturn a") => "local a = 1\r\nreturn a",
+    keep_indentation("do\n    local a = 1  \n    print(a)\nend") => "do\n    local a = 1\n    print(a)\nend",
+    trim_spaces_after_comment("local a = 1 -- note\n   \nreturn a") => "local a = 1 -- note\n\nreturn a",
+    keep_spaces_between_tokens("local a   =   1") => "local a   =   1",
+    keep_spaces_at_end_of_file("return a   ") => "return a   ",
+);
+
+#[test]
+fn only_trivia_changed_when_trimming_whitespace() {
+    assert_only_trivia_changed(
+        &TrimTrailingWhitespace::default(),
+        "local a = 1  \nlocal function f()\t\n    return a   \nend  \n\nreturn f()  \n",
+    );
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'trim_trailing_whitespace',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'trim_trailing_whitespace'").unwrap();
+}
+
+#[test]
+fn serialize_round_trip() {
+    let rule: Box<dyn Rule> = json5::from_str("'trim_trailing_whitespace'").unwrap();
+
+    let serialized = serde_json::to_string(&rule).unwrap();
+    let deserialized: Box<dyn Rule> = json5::from_str(&serialized).unwrap();
+
+    pretty_assertions::assert_eq!(deserialized.get_name(), "trim_trailing_whitespace");
+    pretty_assertions::assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+}
@@ -0,0 +1,70 @@
+use darklua_core::rules::{LimitSeverity, Rule, ValidateLimits};
+use darklua_core::{process, Configuration, Options, Resources};
+
+use super::memory_resources;
+
+test_rule_without_effects!(
+    ValidateLimits::default(),
+    empty_file(""),
+    module_with_functions(
+        "local module = {} local count = 0 function module.increment(amount) count += amount return count end return module"
+    ),
+    nested_function_captures("local a, b = 1, 2 return function() return function() return a + b end end")
+);
+
+test_rule_without_effects!(
+    ValidateLimits::default().with_max_locals(1),
+    too_many_locals_does_not_change_code("local a, b = 1, 2 return a + b")
+);
+
+#[test]
+fn error_severity_fails_before_generating_code() {
+    let resources = memory_resources!(
+        "src/test.lua" => "local a = 1\nlocal function get()\n    return a\nend\nreturn get",
+    );
+
+    let rule: Box<dyn Rule> = Box::new(
+        ValidateLimits::default()
+            .with_max_upvalues(0)
+            .with_severity(LimitSeverity::Error),
+    );
+
+    let errors = process(
+        &resources,
+        Options::new("src")
+            .with_output("out")
+            .with_configuration(Configuration::empty().with_rule(rule)),
+    )
+    .unwrap()
+    .result()
+    .unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    pretty_assertions::assert_eq!(
+        errors[0].to_string(),
+        "error processing `src/test.lua` (validate_limits [#0]): local function `get` (line 2) \
+        captures 1 upvalues (budget is 0): pass values as parameters or group captured variables \
+        into a table"
+    );
+    assert!(!resources.exists("out/test.lua").unwrap());
+}
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'validate_limits',
+        lua_version: 'luau',
+        max_locals: 100,
+        max_upvalues: 30,
+        max_constants: 1000,
+        severity: 'error',
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string() {
+    json5::from_str::<Box<dyn Rule>>("'validate_limits'").unwrap();
+}
@@ -0,0 +1,53 @@
+use darklua_core::rules::{Rule, WrapModuleReturn};
+
+test_rule!(
+    wrap_module_return,
+    WrapModuleReturn::new("__instrument"),
+    test_file_name = "src/module/path.lua",
+    return_identifier("local M = {} return M") => "local M = {} return __instrument('src/module/path', M)",
+    return_table("return { value = true }") => "return __instrument('src/module/path', { value = true })",
+    return_parenthesized_call("return (setmetatable({}, mt))") => "return __instrument('src/module/path', (setmetatable({}, mt)))",
+    return_string("return 'hello'") => "return __instrument('src/module/path', 'hello')",
+);
+
+test_rule!(
+    wrap_module_return_with_field_wrapper,
+    WrapModuleReturn::new("Instrument.wrap").with_key("custom"),
+    return_identifier("local M = {} return M") => "local M = {} return Instrument.wrap('custom', M)",
+);
+
+test_rule!(
+    wrap_module_return_only_tables,
+    WrapModuleReturn::new("__instrument").with_only_tables(true),
+    return_identifier("local M = {} return M") => "local M = {} return __instrument('src/test', M)",
+    return_string("return 'hello'") => "return 'hello'",
+    return_nil("return nil") => "return nil",
+);
+
+test_rule_without_effects!(
+    WrapModuleReturn::new("__instrument"),
+    no_return("local M = {}"),
+    empty_return("local M = {} return"),
+    multiple_values("local a, b = {}, {} return a, b"),
+    return_call("return setmetatable({}, mt)"),
+    return_variable_arguments("return ..."),
+    return_in_nested_block("do return {} end"),
+);
+
+#[test]
+fn deserialize_from_object_notation() {
+    json5::from_str::<Box<dyn Rule>>(
+        r#"{
+        rule: 'wrap_module_return',
+        wrapper: 'Instrument.wrap',
+        key_from: 'path',
+        only_tables: true,
+    }"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_from_string_notation_should_error() {
+    assert!(json5::from_str::<Box<dyn Rule>>("'wrap_module_return'").is_err());
+}
@@ -0,0 +1,15 @@
+-- A module embedded in a Rust binary at compile time.
+local Module = {}
+
+local DEFAULT_GREETING = "hello" -- used when no greeting is given
+
+--[[
+    Returns a greeting for the given name.
+]]
+function Module.greet(name, greeting)
+    local message = (greeting or DEFAULT_GREETING) .. ", " .. name
+
+    return message
+end
+
+return Module
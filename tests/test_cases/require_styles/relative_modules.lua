@@ -0,0 +1,8 @@
+local Sibling = require("./Sibling")
+local Nested = require("./folder/Nested")
+local Parent = require("../Parent")
+local Far = require("../../../shared/Far")
+
+local Absolute = require("Shared/Util")
+
+return { Sibling, Nested, Parent, Far, Absolute }
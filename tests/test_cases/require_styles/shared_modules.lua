@@ -0,0 +1,16 @@
+local Signal = require("Shared/Util/Signal")
+local Promise = require("Shared/Packages/Promise")
+local Config = require("Shared/Config")
+local Legacy = require("Shared/legacy/OldUtil")
+
+-- not following the convention: kept as is
+local Client = require("Client/Controller")
+local Instance = require(game:GetService("ServerStorage").Module)
+
+-- dynamic arguments are left unchanged
+local Dynamic = require("Shared/" .. name)
+
+return function()
+    local Lazy = require("Shared/Util/Lazy")
+    return Signal, Promise, Config, Legacy, Client, Instance, Dynamic, Lazy
+end
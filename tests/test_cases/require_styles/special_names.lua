@@ -0,0 +1,5 @@
+local Module = require("Shared/a module/with spaces")
+local Keyword = require("Shared/while/end")
+local Digits = require("Shared/2d/Vector")
+
+return { Module, Keyword, Digits }
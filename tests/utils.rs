@@ -4,7 +4,9 @@ use std::time::{Duration, Instant};
 
 use anstyle::{AnsiColor, Style};
 use darklua_core::nodes::Block;
-use darklua_core::{Parser, ParserError, Resources};
+use darklua_core::process::EnvironmentTarget;
+use darklua_core::rules::Rule;
+use darklua_core::{Configuration, Options, Parser, ParserError, Resources};
 use log::Level;
 
 #[allow(dead_code)]
@@ -104,3 +106,34 @@ macro_rules! memory_resources {
 
 #[allow(unused_imports)]
 pub(crate) use memory_resources;
+
+/// Processes `src/test.lua` with the given rules (and target), then returns the generated code,
+/// or the errors of the process.
+#[allow(dead_code)]
+pub fn process_rules(
+    rules: impl IntoIterator<Item = Box<dyn Rule>>,
+    target: Option<EnvironmentTarget>,
+    code: &str,
+) -> Result<String, Vec<String>> {
+    let resources = memory_resources!(
+        "src/test.lua" => code,
+    );
+
+    let mut configuration = rules
+        .into_iter()
+        .fold(Configuration::empty(), Configuration::with_rule);
+    if let Some(target) = target {
+        configuration = configuration.with_target(target);
+    }
+
+    darklua_core::process(
+        &resources,
+        Options::new("src")
+            .with_output("out")
+            .with_configuration(configuration),
+    )
+    .unwrap()
+    .result()
+    .map(|()| resources.get("out/test.lua").unwrap())
+    .map_err(|errors| errors.iter().map(ToString::to_string).collect())
+}